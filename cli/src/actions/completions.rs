@@ -0,0 +1,36 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+use std::io;
+
+use clap::{App, Shell};
+
+use crate::error::CliError;
+
+/// Name of the installed binary, used as the command completions are generated for
+const BIN_NAME: &str = "grid";
+
+/// Write the completion script for the given shell to stdout
+pub fn generate_completions(app: &mut App, shell: &str) -> Result<(), CliError> {
+    let shell = shell
+        .parse::<Shell>()
+        .map_err(|err| CliError::UserError(format!("Invalid shell: {}", err)))?;
+
+    app.gen_completions_to(BIN_NAME, shell, &mut io::stdout());
+
+    Ok(())
+}
@@ -19,6 +19,8 @@ mod sqlite;
 use diesel::{connection::Connection as _, pg::PgConnection};
 use std::str::FromStr;
 
+#[cfg(feature = "mfg-batch-audit-log")]
+use grid_sdk::mfg_batch::store::{DieselMfgBatchStore, MfgBatchStore};
 #[cfg(feature = "postgres")]
 use grid_sdk::migrations::run_postgres_migrations;
 
@@ -52,6 +54,54 @@ pub fn run_migrations(database_url: &str) -> Result<(), CliError> {
     Ok(())
 }
 
+/// Checks the manufactured batch audit log against the database, printing each discrepancy
+/// found. Returns an error if the log does not verify.
+#[cfg(feature = "mfg-batch-audit-log")]
+pub fn verify_audit_log(database_url: &str) -> Result<(), CliError> {
+    use diesel::r2d2::{ConnectionManager, Pool};
+
+    let discrepancies =
+        match ConnectionUri::from_str(database_url).map_err(CliError::ActionError)? {
+            #[cfg(feature = "postgres")]
+            ConnectionUri::Postgres(database_url) => {
+                let pool = Pool::builder()
+                    .max_size(1)
+                    .build(ConnectionManager::<PgConnection>::new(&database_url))
+                    .map_err(|_| {
+                        CliError::ActionError("Failed to build connection pool".to_string())
+                    })?;
+                DieselMfgBatchStore::new(pool).verify_mfg_batch_audit_log()
+            }
+            #[cfg(feature = "sqlite")]
+            ConnectionUri::Sqlite(connection_string) => {
+                let pool = Pool::builder()
+                    .max_size(1)
+                    .build(ConnectionManager::<diesel::sqlite::SqliteConnection>::new(
+                        &connection_string,
+                    ))
+                    .map_err(|_| {
+                        CliError::ActionError("Failed to build connection pool".to_string())
+                    })?;
+                DieselMfgBatchStore::new(pool).verify_mfg_batch_audit_log()
+            }
+        }
+        .map_err(|err| CliError::ActionError(format!("Unable to verify audit log: {}", err)))?;
+
+    if discrepancies.is_empty() {
+        info!("Audit log verified; no discrepancies found");
+        return Ok(());
+    }
+
+    for discrepancy in &discrepancies {
+        println!("{}", discrepancy);
+    }
+
+    Err(CliError::ActionError(format!(
+        "Audit log verification failed with {} discrepancies",
+        discrepancies.len()
+    )))
+}
+
 /// The possible connection types and identifiers passed to the migrate command
 pub enum ConnectionUri {
     #[cfg(feature = "postgres")]
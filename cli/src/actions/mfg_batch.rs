@@ -0,0 +1,576 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::prelude::*,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use grid_sdk::{
+    client::mfg_batch::{MfgBatch, MfgBatchClient, MfgBatchPropertyValue},
+    client::schema::{DataType, PropertyDefinition, SchemaClient},
+    mfg_batch::addressing::GRID_MFG_BATCH_NAMESPACE,
+    pike::addressing::GRID_PIKE_NAMESPACE,
+    protocol::{
+        mfg_batch::{
+            payload::{
+                Action, MfgBatchCreateAction, MfgBatchCreateActionBuilder, MfgBatchDeleteAction,
+                MfgBatchPayloadBuilder, MfgBatchUpdateAction, MfgBatchUpdateActionBuilder,
+            },
+            state::MfgBatchNamespace,
+        },
+        schema::state::{LatLongBuilder, PropertyValue, PropertyValueBuilder},
+    },
+    protos::IntoProto,
+    schema::addressing::GRID_SCHEMA_NAMESPACE,
+};
+
+use cylinder::Signer;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::output::{print_formattable, print_formattable_list, TableDisplay};
+use crate::error::CliError;
+use crate::transaction::mfg_batch_batch_builder;
+
+/// The name of the schema GS1 manufactured batch properties are validated against
+pub const GS1_MFG_BATCH_SCHEMA: &str = "gs1_mfg_batch";
+/// The name of the schema internal manufactured batch properties are read from
+pub const INTERNAL_MFG_BATCH_SCHEMA: &str = "internal_mfg_batch";
+/// The name of the schema lot manufactured batch properties are read from
+pub const LOT_MFG_BATCH_SCHEMA: &str = "lot_mfg_batch";
+
+/// Returns the name of the schema that defines the properties of batches in a namespace
+pub fn mfg_batch_schema_name(namespace: &MfgBatchNamespace) -> &'static str {
+    match namespace {
+        MfgBatchNamespace::Gs1 => GS1_MFG_BATCH_SCHEMA,
+        MfgBatchNamespace::Internal => INTERNAL_MFG_BATCH_SCHEMA,
+        MfgBatchNamespace::Lot => LOT_MFG_BATCH_SCHEMA,
+    }
+}
+
+pub fn do_create_mfg_batches(
+    client: Box<dyn MfgBatchClient>,
+    signer: Box<dyn Signer>,
+    wait: u64,
+    actions: Vec<MfgBatchCreateAction>,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    submit_payloads(
+        &*client,
+        signer,
+        wait,
+        actions.into_iter().map(Action::MfgBatchCreate).collect(),
+        service_id,
+    )
+}
+
+pub fn do_update_mfg_batches(
+    client: Box<dyn MfgBatchClient>,
+    signer: Box<dyn Signer>,
+    wait: u64,
+    actions: Vec<MfgBatchUpdateAction>,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    submit_payloads(
+        &*client,
+        signer,
+        wait,
+        actions.into_iter().map(Action::MfgBatchUpdate).collect(),
+        service_id,
+    )
+}
+
+pub fn do_delete_mfg_batch(
+    client: Box<dyn MfgBatchClient>,
+    signer: Box<dyn Signer>,
+    wait: u64,
+    action: MfgBatchDeleteAction,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    submit_payloads(
+        &*client,
+        signer,
+        wait,
+        vec![Action::MfgBatchDelete(action)],
+        service_id,
+    )
+}
+
+pub fn do_list_mfg_batches(
+    client: Box<dyn MfgBatchClient>,
+    service_id: Option<&str>,
+    format: Option<&str>,
+) -> Result<(), CliError> {
+    let mfg_batches = client.list_mfg_batches(service_id)?;
+    let mut mfg_batches: Box<dyn Iterator<Item = Result<MfgBatchCli, CliError>>> = Box::new(
+        mfg_batches
+            .into_iter()
+            .map(|mfg_batch| Ok(MfgBatchCli::from(&mfg_batch))),
+    );
+    print_formattable_list(&mut *mfg_batches, format)
+}
+
+pub fn do_show_mfg_batch(
+    client: Box<dyn MfgBatchClient>,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+    format: Option<&str>,
+) -> Result<(), CliError> {
+    let mfg_batch = MfgBatchCli::from(&client.get_mfg_batch(mfg_batch_id.into(), service_id)?);
+    match format {
+        // A single batch is printed as a one-row table when columns are asked for
+        Some("csv") | Some("table") => {
+            let mut rows = std::iter::once(Ok::<_, CliError>(mfg_batch));
+            print_formattable_list(&mut rows, format)
+        }
+        _ => print_formattable(mfg_batch, format),
+    }
+}
+
+/// Signs each action as a transaction and submits them together in one batch list
+pub fn submit_payloads(
+    client: &dyn MfgBatchClient,
+    signer: Box<dyn Signer>,
+    wait: u64,
+    actions: Vec<Action>,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    let mut builder = mfg_batch_batch_builder(signer);
+
+    for action in actions {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+
+        let mut payload_builder = MfgBatchPayloadBuilder::new()
+            .with_action(action)
+            .with_timestamp(timestamp);
+        if let Some(service_id) = service_id {
+            payload_builder = payload_builder.with_service_id(service_id.to_string());
+        }
+        let action = payload_builder
+            .build()
+            .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+
+        builder.add_transaction(
+            &action.into_proto()?,
+            &[
+                GRID_PIKE_NAMESPACE.to_string(),
+                GRID_SCHEMA_NAMESPACE.to_string(),
+                GRID_MFG_BATCH_NAMESPACE.to_string(),
+            ],
+            &[GRID_MFG_BATCH_NAMESPACE.to_string()],
+        )?;
+    }
+
+    let batches = builder.create_batch_list();
+
+    client.post_batches(wait, &batches, service_id)?;
+    Ok(())
+}
+
+pub fn create_mfg_batch_payloads_from_file(
+    paths: Vec<&str>,
+    client: Box<dyn SchemaClient>,
+    service_id: Option<&str>,
+) -> Result<Vec<MfgBatchCreateAction>, CliError> {
+    let mut payloads = Vec::new();
+
+    for path in paths {
+        let file = std::fs::File::open(path)?;
+        let ymls: Vec<MfgBatchCreateYaml> = serde_yaml::from_reader(&file)?;
+
+        for yml in ymls {
+            let schema = client.get_schema(yml.namespace.schema_name(), service_id)?;
+            payloads.push(yml.into_payload(schema.properties)?);
+        }
+    }
+
+    Ok(payloads)
+}
+
+/// Reads one JSON encoded create action from each of the given paths
+pub fn create_mfg_batch_payloads_from_json(
+    paths: Vec<&str>,
+) -> Result<Vec<MfgBatchCreateAction>, CliError> {
+    let mut payloads = Vec::new();
+
+    for path in paths {
+        let mut json = String::new();
+        File::open(path)?.read_to_string(&mut json)?;
+        payloads.push(MfgBatchCreateAction::from_json(&json)?);
+    }
+
+    Ok(payloads)
+}
+
+pub fn update_mfg_batch_payloads_from_file(
+    paths: Vec<&str>,
+    client: Box<dyn SchemaClient>,
+    service_id: Option<&str>,
+) -> Result<Vec<MfgBatchUpdateAction>, CliError> {
+    let mut payloads = Vec::new();
+
+    for path in paths {
+        let file = std::fs::File::open(path)?;
+        let ymls: Vec<MfgBatchUpdateYaml> = serde_yaml::from_reader(&file)?;
+
+        for yml in ymls {
+            let schema = client.get_schema(yml.namespace.schema_name(), service_id)?;
+            payloads.push(yml.into_payload(schema.properties)?);
+        }
+    }
+
+    Ok(payloads)
+}
+
+pub fn yaml_to_property_values(
+    properties: &HashMap<String, serde_yaml::Value>,
+    definitions: Vec<PropertyDefinition>,
+) -> Result<Vec<PropertyValue>, CliError> {
+    let mut property_values = Vec::new();
+
+    for def in definitions {
+        let value = if let Some(value) = properties.get(&def.name) {
+            value
+        } else if !def.required {
+            continue;
+        } else {
+            return Err(CliError::PayloadError(format!(
+                "Field {} not found",
+                def.name
+            )));
+        };
+
+        match def.data_type {
+            DataType::Bytes => {
+                let mut f = File::open(&serde_yaml::from_value::<String>(value.clone())?)?;
+                let mut buffer = Vec::new();
+                f.read_to_end(&mut buffer)?;
+
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name)
+                    .with_data_type(def.data_type.into())
+                    .with_bytes_value(buffer)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+
+                property_values.push(property_value);
+            }
+            DataType::Boolean => {
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name.clone())
+                    .with_data_type(def.data_type.into())
+                    .with_boolean_value(serde_yaml::from_value(value.clone())?)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+                property_values.push(property_value);
+            }
+            DataType::Number => {
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name.clone())
+                    .with_data_type(def.data_type.into())
+                    .with_number_value(serde_yaml::from_value(value.clone())?)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+                property_values.push(property_value);
+            }
+            DataType::String => {
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name.clone())
+                    .with_data_type(def.data_type.into())
+                    .with_string_value(serde_yaml::from_value(value.clone())?)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+                property_values.push(property_value);
+            }
+            DataType::Enum => {
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name.clone())
+                    .with_data_type(def.data_type.into())
+                    .with_enum_value(serde_yaml::from_value(value.clone())?)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+                property_values.push(property_value);
+            }
+            DataType::Struct => {
+                let properties: HashMap<String, serde_yaml::Value> =
+                    serde_yaml::from_value(value.clone())?;
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name.clone())
+                    .with_data_type(def.data_type.into())
+                    .with_struct_values(yaml_to_property_values(
+                        &properties,
+                        def.struct_properties,
+                    )?)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+                property_values.push(property_value);
+            }
+            DataType::LatLong => {
+                let lat_long = serde_yaml::from_value::<String>(value.clone())?
+                    .split(',')
+                    .map(|x| {
+                        x.parse::<i64>()
+                            .map_err(|err| CliError::PayloadError(format!("{}", err)))
+                    })
+                    .collect::<Result<Vec<i64>, CliError>>()?;
+
+                if lat_long.len() != 2 {
+                    return Err(CliError::PayloadError(format!(
+                        "{:?} is not a valid latitude longitude",
+                        lat_long
+                    )));
+                }
+
+                let lat_long = LatLongBuilder::new()
+                    .with_lat_long(lat_long[0], lat_long[1])
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+
+                let property_value = PropertyValueBuilder::new()
+                    .with_name(def.name)
+                    .with_data_type(def.data_type.into())
+                    .with_lat_long_value(lat_long)
+                    .build()
+                    .map_err(|err| CliError::PayloadError(format!("{}", err)))?;
+
+                property_values.push(property_value);
+            }
+        }
+    }
+
+    Ok(property_values)
+}
+
+#[derive(Debug, Serialize)]
+struct MfgBatchCli {
+    mfg_batch_id: String,
+    mfg_batch_namespace: String,
+    owner: String,
+    parent_batches: Vec<String>,
+    properties: Vec<MfgBatchPropertyCli>,
+    service_id: Option<String>,
+}
+
+impl From<&MfgBatch> for MfgBatchCli {
+    fn from(d: &MfgBatch) -> Self {
+        Self {
+            mfg_batch_id: d.mfg_batch_id.to_string(),
+            mfg_batch_namespace: d.mfg_batch_namespace.to_string(),
+            owner: d.owner.to_string(),
+            parent_batches: d.parent_batches.clone(),
+            properties: d.properties.iter().map(MfgBatchPropertyCli::from).collect(),
+            service_id: d.service_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MfgBatchPropertyCli {
+    name: String,
+    data_type: String,
+    value: Option<MfgBatchPropertyValueCli>,
+}
+
+/// The value of a property, serialized as the plain value of its data type
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum MfgBatchPropertyValueCli {
+    Bytes(Vec<u8>),
+    Boolean(bool),
+    Number(i64),
+    String(String),
+    Enum(i32),
+    Struct(Vec<String>),
+    LatLong { latitude: i64, longitude: i64 },
+}
+
+impl From<&MfgBatchPropertyValue> for MfgBatchPropertyCli {
+    fn from(d: &MfgBatchPropertyValue) -> Self {
+        let value = match d.data_type {
+            DataType::Bytes => d.bytes_value.clone().map(MfgBatchPropertyValueCli::Bytes),
+            DataType::Boolean => d.boolean_value.map(MfgBatchPropertyValueCli::Boolean),
+            DataType::Number => d.number_value.map(MfgBatchPropertyValueCli::Number),
+            DataType::String => d.string_value.clone().map(MfgBatchPropertyValueCli::String),
+            DataType::Enum => d.enum_value.map(MfgBatchPropertyValueCli::Enum),
+            DataType::Struct => d
+                .struct_values
+                .clone()
+                .map(MfgBatchPropertyValueCli::Struct),
+            DataType::LatLong => {
+                d.lat_long_value
+                    .as_ref()
+                    .map(|lat_long| MfgBatchPropertyValueCli::LatLong {
+                        latitude: lat_long.latitude,
+                        longitude: lat_long.longitude,
+                    })
+            }
+        };
+
+        Self {
+            name: d.name.to_string(),
+            data_type: format!("{:?}", d.data_type),
+            value,
+        }
+    }
+}
+
+impl std::fmt::Display for MfgBatchCli {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Manufactured Batch ID: {}\nNamespace: {}\nOwner: {}",
+            self.mfg_batch_id, self.mfg_batch_namespace, self.owner,
+        )?;
+        if !self.parent_batches.is_empty() {
+            write!(f, "\nParent Batches: {}", self.parent_batches.join(", "))?;
+        }
+        write!(f, "\nProperties")?;
+        for property in &self.properties {
+            write!(f, "\n{}", property)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for MfgBatchPropertyCli {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(MfgBatchPropertyValueCli::Bytes(value)) => write!(f, "{}: {:?}", self.name, value),
+            Some(MfgBatchPropertyValueCli::Boolean(value)) => {
+                write!(f, "{}: {:?}", self.name, value)
+            }
+            Some(MfgBatchPropertyValueCli::Number(value)) => {
+                write!(f, "{}: {:?}", self.name, value)
+            }
+            Some(MfgBatchPropertyValueCli::String(value)) => {
+                write!(f, "{}: {:?}", self.name, value)
+            }
+            Some(MfgBatchPropertyValueCli::Enum(value)) => write!(f, "{}: {:?}", self.name, value),
+            Some(MfgBatchPropertyValueCli::Struct(value)) => {
+                write!(f, "{}: {:?}", self.name, value)
+            }
+            Some(MfgBatchPropertyValueCli::LatLong {
+                latitude,
+                longitude,
+            }) => write!(f, "{}: {}, {}", self.name, latitude, longitude),
+            None => write!(f, "{}:", self.name),
+        }
+    }
+}
+
+impl TableDisplay for MfgBatchCli {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAMESPACE", "OWNER"]
+    }
+
+    fn details(&self) -> Vec<String> {
+        vec![
+            self.mfg_batch_id.to_string(),
+            self.mfg_batch_namespace.to_string(),
+            self.owner.to_string(),
+        ]
+    }
+
+    fn widths() -> Vec<usize> {
+        vec![25, 9, 5]
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MfgBatchCreateYaml {
+    mfg_batch_id: String,
+    owner: String,
+    namespace: Namespace,
+    properties: HashMap<String, serde_yaml::Value>,
+}
+
+impl MfgBatchCreateYaml {
+    pub fn into_payload(
+        self,
+        definitions: Vec<PropertyDefinition>,
+    ) -> Result<MfgBatchCreateAction, CliError> {
+        let property_values = yaml_to_property_values(&self.properties, definitions)?;
+        MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id(self.mfg_batch_id)
+            .with_owner(self.owner)
+            .with_mfg_batch_namespace(self.namespace.into())
+            .with_properties(property_values)
+            .build()
+            .map_err(|err| CliError::PayloadError(format!("{}", err)))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MfgBatchUpdateYaml {
+    mfg_batch_id: String,
+    namespace: Namespace,
+    properties: HashMap<String, serde_yaml::Value>,
+    #[serde(default)]
+    expected_version: String,
+    #[serde(default)]
+    validate_changed_properties_only: bool,
+}
+
+impl MfgBatchUpdateYaml {
+    pub fn into_payload(
+        self,
+        definitions: Vec<PropertyDefinition>,
+    ) -> Result<MfgBatchUpdateAction, CliError> {
+        let property_values = yaml_to_property_values(&self.properties, definitions)?;
+        MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(self.mfg_batch_id)
+            .with_mfg_batch_namespace(self.namespace.into())
+            .with_properties(property_values)
+            .with_expected_version(self.expected_version)
+            .with_validate_changed_properties_only(self.validate_changed_properties_only)
+            .build()
+            .map_err(|err| CliError::PayloadError(format!("{}", err)))
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub enum Namespace {
+    #[serde(rename = "GS1")]
+    Gs1,
+    #[serde(rename = "INTERNAL")]
+    Internal,
+    #[serde(rename = "LOT")]
+    Lot,
+}
+
+impl Namespace {
+    fn schema_name(&self) -> String {
+        match self {
+            Namespace::Gs1 => GS1_MFG_BATCH_SCHEMA.to_string(),
+            Namespace::Internal => INTERNAL_MFG_BATCH_SCHEMA.to_string(),
+            Namespace::Lot => LOT_MFG_BATCH_SCHEMA.to_string(),
+        }
+    }
+}
+
+impl From<Namespace> for MfgBatchNamespace {
+    fn from(namespace: Namespace) -> Self {
+        match namespace {
+            Namespace::Gs1 => MfgBatchNamespace::Gs1,
+            Namespace::Internal => MfgBatchNamespace::Internal,
+            Namespace::Lot => MfgBatchNamespace::Lot,
+        }
+    }
+}
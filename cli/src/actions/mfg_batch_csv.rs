@@ -0,0 +1,468 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bulk import and export of manufactured batches as CSV
+//!
+//! A mapping file names the columns that hold each batch's ID, owner and schema properties.
+//! For example:
+//!
+//! ```yaml
+//! namespace: GS1
+//! mfg_batch_id: Batch
+//! owner: Organization
+//! gtin: GTIN
+//! properties:
+//!   gtin: GTIN
+//!   lot_code: Lot Number
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+
+use grid_sdk::{
+    client::mfg_batch::{MfgBatch, MfgBatchClient, MfgBatchPropertyValue},
+    client::schema::{DataType, PropertyDefinition, SchemaClient},
+    mfg_batch::addressing::MfgBatchIdentifier,
+    protocol::mfg_batch::{
+        payload::{Action, MfgBatchCreateAction, MfgBatchCreateActionBuilder},
+        state::MfgBatchNamespace,
+    },
+};
+
+use cylinder::Signer;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::mfg_batch::{
+    mfg_batch_schema_name, submit_payloads, yaml_to_property_values, Namespace,
+};
+use crate::error::CliError;
+
+/// The number of create actions submitted together when no batch size is given
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 100;
+
+/// Describes which CSV columns hold the fields of a manufactured batch
+#[derive(Deserialize, Debug)]
+pub struct CsvMapping {
+    /// The namespace of every batch in the file
+    namespace: Namespace,
+    /// The column holding the batch ID
+    mfg_batch_id: String,
+    /// The column holding the Pike organization ID of the batch's owner
+    #[serde(default)]
+    owner: Option<String>,
+    /// The column holding a GTIN that is validated before the batch is submitted
+    #[serde(default)]
+    gtin: Option<String>,
+    /// Maps schema property names to the columns holding their values
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+}
+
+impl CsvMapping {
+    pub fn from_file(path: &str) -> Result<Self, CliError> {
+        Ok(serde_yaml::from_reader(File::open(path)?)?)
+    }
+
+    fn namespace(&self) -> MfgBatchNamespace {
+        self.namespace.clone().into()
+    }
+
+    /// Lists the columns written on export, in order, without duplicates
+    fn columns(&self) -> Vec<&str> {
+        let mut columns = vec![self.mfg_batch_id.as_str()];
+        columns.extend(self.owner.as_deref());
+        for column in self.properties.values() {
+            if !columns.contains(&column.as_str()) {
+                columns.push(column);
+            }
+        }
+        columns
+    }
+}
+
+/// The outcome of importing one row, as written to the results report
+#[derive(Serialize, Debug, PartialEq)]
+struct ImportResult {
+    line: u64,
+    mfg_batch_id: String,
+    status: &'static str,
+    message: String,
+}
+
+const STATUS_INVALID: &str = "invalid";
+const STATUS_FAILED: &str = "failed";
+const STATUS_SUBMITTED: &str = "submitted";
+const STATUS_COMMITTED: &str = "committed";
+
+/// Creates a manufactured batch for each row of a CSV file and writes the outcome of every row
+/// to a report.
+///
+/// Rows that fail local validation are reported and skipped. The remaining rows are submitted
+/// in groups of `batch_size` create actions; if a group is rejected, each of its rows is
+/// reported as failed and the import moves on to the next group.
+///
+/// Returns an error if any row was not imported.
+#[allow(clippy::too_many_arguments)]
+pub fn do_import_mfg_batches(
+    client: Box<dyn MfgBatchClient>,
+    schema_client: Box<dyn SchemaClient>,
+    signer: Box<dyn Signer>,
+    wait: u64,
+    path: &str,
+    mapping: &CsvMapping,
+    default_owner: Option<&str>,
+    batch_size: usize,
+    report_path: &str,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    let schema_name = mfg_batch_schema_name(&mapping.namespace());
+    let definitions = schema_client
+        .get_schema(schema_name.to_string(), service_id)?
+        .properties;
+    for property in mapping.properties.keys() {
+        if !definitions.iter().any(|def| &def.name == property) {
+            return Err(CliError::UserError(format!(
+                "Property {} is not defined in schema {}",
+                property, schema_name
+            )));
+        }
+    }
+
+    let mut reader = csv::Reader::from_path(path)?;
+    let columns = ColumnIndex::new(reader.headers()?, mapping)?;
+
+    let mut results = Vec::new();
+    let mut pending = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map(|pos| pos.line()).unwrap_or_default();
+        let mfg_batch_id = columns.mfg_batch_id(&record).to_string();
+
+        match columns.to_action(&record, mapping, &definitions, default_owner) {
+            Ok(action) => pending.push((line, action)),
+            Err(err) => results.push(ImportResult {
+                line,
+                mfg_batch_id,
+                status: STATUS_INVALID,
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    for chunk in pending.chunks(batch_size.max(1)) {
+        let actions = chunk
+            .iter()
+            .map(|(_, action)| Action::MfgBatchCreate(action.clone()))
+            .collect();
+
+        let (status, message) =
+            match submit_payloads(&*client, signer.clone(), wait, actions, service_id) {
+                Ok(()) if wait > 0 => (STATUS_COMMITTED, String::new()),
+                Ok(()) => (STATUS_SUBMITTED, String::new()),
+                Err(err) => (STATUS_FAILED, err.to_string()),
+            };
+        info!("{} manufactured batches {}", chunk.len(), status);
+
+        results.extend(chunk.iter().map(|(line, action)| ImportResult {
+            line: *line,
+            mfg_batch_id: action.mfg_batch_id().to_string(),
+            status,
+            message: message.clone(),
+        }));
+    }
+
+    results.sort_by_key(|result| result.line);
+    let mut writer = csv::Writer::from_path(report_path)?;
+    for result in &results {
+        writer.serialize(result)?;
+    }
+    writer.flush()?;
+
+    let rejected = results
+        .iter()
+        .filter(|result| result.status == STATUS_INVALID || result.status == STATUS_FAILED)
+        .count();
+    if rejected > 0 {
+        return Err(CliError::UserError(format!(
+            "{} of {} manufactured batches were not imported; see {}",
+            rejected,
+            results.len(),
+            report_path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Writes every manufactured batch in the mapping's namespace to a CSV file
+pub fn do_export_mfg_batches(
+    client: Box<dyn MfgBatchClient>,
+    path: &str,
+    mapping: &CsvMapping,
+    service_id: Option<&str>,
+) -> Result<(), CliError> {
+    let namespace = match mapping.namespace() {
+        MfgBatchNamespace::Gs1 => "GS1",
+        MfgBatchNamespace::Internal => "INTERNAL",
+        MfgBatchNamespace::Lot => "LOT",
+    };
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(mapping.columns())?;
+
+    let mut count = 0;
+    for mfg_batch in client.list_mfg_batches(service_id)? {
+        if !mfg_batch
+            .mfg_batch_namespace
+            .eq_ignore_ascii_case(namespace)
+        {
+            continue;
+        }
+        writer.write_record(export_record(&mfg_batch, mapping)?)?;
+        count += 1;
+    }
+    writer.flush()?;
+
+    info!("Exported {} manufactured batches to {}", count, path);
+    Ok(())
+}
+
+/// Locates the mapped columns in the header row of an import file
+struct ColumnIndex {
+    mfg_batch_id: usize,
+    owner: Option<usize>,
+    gtin: Option<usize>,
+    properties: Vec<(String, usize)>,
+}
+
+impl ColumnIndex {
+    fn new(headers: &csv::StringRecord, mapping: &CsvMapping) -> Result<Self, CliError> {
+        let position = |column: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim() == column)
+                .ok_or_else(|| CliError::UserError(format!("Column {} not found", column)))
+        };
+
+        Ok(ColumnIndex {
+            mfg_batch_id: position(&mapping.mfg_batch_id)?,
+            owner: mapping.owner.as_deref().map(position).transpose()?,
+            gtin: mapping.gtin.as_deref().map(position).transpose()?,
+            properties: mapping
+                .properties
+                .iter()
+                .map(|(property, column)| Ok((property.clone(), position(column)?)))
+                .collect::<Result<_, CliError>>()?,
+        })
+    }
+
+    fn mfg_batch_id<'a>(&self, record: &'a csv::StringRecord) -> &'a str {
+        cell(record, self.mfg_batch_id)
+    }
+
+    fn to_action(
+        &self,
+        record: &csv::StringRecord,
+        mapping: &CsvMapping,
+        definitions: &[PropertyDefinition],
+        default_owner: Option<&str>,
+    ) -> Result<MfgBatchCreateAction, CliError> {
+        let mfg_batch_id = self.mfg_batch_id(record);
+        if mfg_batch_id.is_empty() {
+            return Err(CliError::PayloadError(
+                "Manufactured batch ID is empty".to_string(),
+            ));
+        }
+
+        if let Some(gtin) = self.gtin.map(|index| cell(record, index)) {
+            validate_gtin(gtin)?;
+        } else if mapping.namespace() == MfgBatchNamespace::Gs1
+            && is_gtin(MfgBatchIdentifier::from_id(mfg_batch_id))
+        {
+            validate_gtin(mfg_batch_id)?;
+        }
+
+        let owner = self
+            .owner
+            .map(|index| cell(record, index))
+            .filter(|owner| !owner.is_empty())
+            .or(default_owner)
+            .ok_or_else(|| CliError::PayloadError("Owner is empty".to_string()))?;
+
+        let mut values = HashMap::new();
+        for (property, index) in &self.properties {
+            let value = cell(record, *index);
+            if value.is_empty() {
+                continue;
+            }
+            if let Some(def) = definitions.iter().find(|def| &def.name == property) {
+                values.insert(property.clone(), cell_to_yaml(value, def)?);
+            }
+        }
+
+        MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
+            .with_owner(owner.to_string())
+            .with_mfg_batch_namespace(mapping.namespace())
+            .with_properties(yaml_to_property_values(&values, definitions.to_vec())?)
+            .build()
+            .map_err(|err| CliError::PayloadError(format!("{}", err)))
+    }
+}
+
+fn cell(record: &csv::StringRecord, index: usize) -> &str {
+    record.get(index).unwrap_or_default().trim()
+}
+
+/// Converts a cell to the YAML value a property of its type is read from
+fn cell_to_yaml(value: &str, def: &PropertyDefinition) -> Result<serde_yaml::Value, CliError> {
+    match def.data_type {
+        DataType::Boolean | DataType::Number | DataType::Enum => Ok(serde_yaml::from_str(value)?),
+        DataType::Struct => Err(CliError::PayloadError(format!(
+            "Struct property {} cannot be read from a CSV column",
+            def.name
+        ))),
+        DataType::Bytes | DataType::String | DataType::LatLong => {
+            Ok(serde_yaml::Value::String(value.to_string()))
+        }
+    }
+}
+
+fn is_gtin(identifier: MfgBatchIdentifier) -> bool {
+    matches!(
+        identifier,
+        MfgBatchIdentifier::Gtin8
+            | MfgBatchIdentifier::Gtin12
+            | MfgBatchIdentifier::Gtin13
+            | MfgBatchIdentifier::Gtin14
+    )
+}
+
+/// Checks that a GTIN has a valid length and GS1 check digit
+fn validate_gtin(gtin: &str) -> Result<(), CliError> {
+    if !is_gtin(MfgBatchIdentifier::from_id(gtin)) {
+        return Err(CliError::PayloadError(format!(
+            "{} is not an 8, 12, 13 or 14 digit GTIN",
+            gtin
+        )));
+    }
+
+    let digits = gtin
+        .bytes()
+        .map(|digit| u32::from(digit - b'0'))
+        .collect::<Vec<_>>();
+    let (check_digit, body) = digits.split_last().expect("GTINs are not empty");
+
+    // Digits are weighted 3 and 1 alternately, starting from the one next to the check digit
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { *digit })
+        .sum();
+
+    if (10 - sum % 10) % 10 != *check_digit {
+        return Err(CliError::PayloadError(format!(
+            "GTIN {} has an invalid check digit",
+            gtin
+        )));
+    }
+
+    Ok(())
+}
+
+fn export_record(mfg_batch: &MfgBatch, mapping: &CsvMapping) -> Result<Vec<String>, CliError> {
+    let mut record = Vec::new();
+    for column in mapping.columns() {
+        if column == mapping.mfg_batch_id {
+            record.push(mfg_batch.mfg_batch_id.clone());
+        } else if mapping.owner.as_deref() == Some(column) {
+            record.push(mfg_batch.owner.clone());
+        } else {
+            let value = mapping
+                .properties
+                .iter()
+                .filter(|(_, mapped)| mapped.as_str() == column)
+                .find_map(|(property, _)| {
+                    mfg_batch
+                        .properties
+                        .iter()
+                        .find(|value| &value.name == property)
+                })
+                .map(export_value)
+                .transpose()?;
+            record.push(value.unwrap_or_default());
+        }
+    }
+    Ok(record)
+}
+
+/// Formats a property value the way the import reads it back
+fn export_value(value: &MfgBatchPropertyValue) -> Result<String, CliError> {
+    let exported = match value.data_type {
+        DataType::Boolean => value.boolean_value.map(|b| b.to_string()),
+        DataType::Number => value.number_value.map(|n| n.to_string()),
+        DataType::String => value.string_value.clone(),
+        DataType::Enum => value.enum_value.map(|e| e.to_string()),
+        DataType::LatLong => value
+            .lat_long_value
+            .as_ref()
+            .map(|lat_long| format!("{},{}", lat_long.latitude, lat_long.longitude)),
+        DataType::Bytes | DataType::Struct => {
+            return Err(CliError::UserError(format!(
+                "Property {} of type {:?} cannot be written to a CSV column",
+                value.name, value.data_type
+            )))
+        }
+    };
+    Ok(exported.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that GTINs with a correct check digit are accepted and all others rejected
+    #[test]
+    fn test_validate_gtin() {
+        assert!(validate_gtin("00614141999996").is_ok());
+        assert!(validate_gtin("614141999996").is_ok());
+        assert!(validate_gtin("96385074").is_ok());
+        assert!(validate_gtin("4006381333931").is_ok());
+
+        assert!(validate_gtin("00614141999995").is_err());
+        assert!(validate_gtin("0061414199999").is_err());
+        assert!(validate_gtin("0061414199999a").is_err());
+        assert!(validate_gtin("").is_err());
+    }
+
+    /// Verifies that the export columns start with the ID and owner and list each property
+    /// column once
+    #[test]
+    fn test_mapping_columns() {
+        let mapping: CsvMapping = serde_yaml::from_str(
+            "namespace: GS1\n\
+             mfg_batch_id: Batch\n\
+             owner: Org\n\
+             gtin: GTIN\n\
+             properties:\n  \
+               lot_code: Lot\n  \
+               gtin: GTIN\n  \
+               item: GTIN\n",
+        )
+        .expect("Unable to parse mapping");
+
+        assert_eq!(mapping.columns(), vec!["Batch", "Org", "GTIN", "Lot"]);
+    }
+}
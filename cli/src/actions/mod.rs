@@ -34,13 +34,20 @@ use super::error::CliError;
 
 #[cfg(feature = "pike")]
 pub mod agent;
+pub mod completions;
 #[cfg(feature = "database")]
 pub mod database;
 pub mod keygen;
 #[cfg(feature = "location")]
 pub mod location;
+#[cfg(feature = "mfg-batch")]
+pub mod mfg_batch;
+#[cfg(feature = "mfg-batch-csv")]
+pub mod mfg_batch_csv;
 #[cfg(feature = "pike")]
 pub mod organization;
+#[cfg(any(feature = "mfg-batch", feature = "purchase-order"))]
+pub mod output;
 #[cfg(feature = "product")]
 pub mod product;
 #[cfg(any(feature = "purchase-order"))]
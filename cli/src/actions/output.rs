@@ -0,0 +1,149 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+//! Output helpers shared by the list and show commands.
+//!
+//! List commands accept a `--format` argument; `json` and `yaml` serialize each
+//! item, `csv` prints the table columns separated by commas, and `table` (or
+//! `human`) prints the aligned columns defined by [`TableDisplay`].
+
+use serde::Serialize;
+
+use crate::error::CliError;
+
+/// Output formats accepted by list commands
+pub const LIST_FORMATS: &[&str] = &["human", "table", "csv", "json", "yaml"];
+
+/// Describes how a value is rendered as a row of a table or CSV output
+pub trait TableDisplay {
+    fn header() -> Vec<&'static str>;
+    fn details(&self) -> Vec<String>;
+    fn widths() -> Vec<usize>;
+}
+
+pub fn print_formattable<T: std::fmt::Display + Serialize>(
+    object: T,
+    format: Option<&str>,
+) -> Result<(), CliError> {
+    match format {
+        Some("json") => {
+            let formatted = serde_json::to_string(&object).map_err(|err| {
+                CliError::ActionError(format!("Error formatting as JSON: {}", err))
+            })?;
+            println!("{}", formatted);
+        }
+        Some("yaml") => {
+            let formatted = serde_yaml::to_string(&object).map_err(|err| {
+                CliError::ActionError(format!("Error formatting as YAML: {}", err))
+            })?;
+            println!("{}", formatted);
+        }
+        _ => println!("{}", object),
+    }
+    Ok(())
+}
+
+pub fn print_formattable_list<T: TableDisplay + std::fmt::Display + Serialize>(
+    data: &mut dyn Iterator<Item = Result<T, CliError>>,
+    format: Option<&str>,
+) -> Result<(), CliError> {
+    match format {
+        Some("json") => {
+            for row in &mut *data {
+                match row {
+                    Ok(object) => {
+                        print_formattable(object, format)?;
+                    }
+                    Err(err) => {
+                        println!("{}", err);
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        Some("yaml") => {
+            for row in &mut *data {
+                match row {
+                    Ok(object) => {
+                        print_formattable(object, format)?;
+                    }
+                    Err(err) => {
+                        println!("{}", err);
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        Some("csv") => {
+            println!("{}", str_join(T::header(), ","));
+            for row in &mut *data {
+                match row {
+                    Ok(object) => {
+                        println!("{}", str_join(object.details(), ","))
+                    }
+                    Err(err) => {
+                        println!("{}", err);
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        _ => {
+            print_table(data)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_table<T: TableDisplay>(
+    data: &mut dyn Iterator<Item = Result<T, CliError>>,
+) -> Result<(), CliError> {
+    // print header row
+    let mut header_row = "".to_owned();
+    for i in 0..T::header().len() {
+        header_row += &format!("{:width$} ", T::header()[i], width = T::widths()[i]);
+    }
+    println!("{}", header_row);
+
+    // print each row
+    for row in &mut *data {
+        match row {
+            Ok(res) => {
+                let mut print_row = "".to_owned();
+                for i in 0..T::header().len() {
+                    print_row += &format!("{:width$} ", res.details()[i], width = T::widths()[i]);
+                }
+                println!("{}", print_row);
+            }
+            Err(err) => {
+                println!("{}", err);
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn str_join<T: ToString>(array: Vec<T>, delimiter: &str) -> String {
+    array
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(delimiter)
+}
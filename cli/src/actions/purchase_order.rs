@@ -39,6 +39,7 @@ use sawtooth_sdk::messages::batch::BatchList;
 use serde::Serialize;
 
 use crate::actions;
+use crate::actions::output::{print_formattable, print_formattable_list, TableDisplay};
 use crate::error::CliError;
 use crate::transaction::purchase_order_batch_builder;
 
@@ -608,12 +609,6 @@ impl std::fmt::Display for PurchaseOrderRevisionCli {
     }
 }
 
-trait TableDisplay {
-    fn header() -> Vec<&'static str>;
-    fn details(&self) -> Vec<String>;
-    fn widths() -> Vec<usize>;
-}
-
 impl TableDisplay for PurchaseOrderCli {
     fn header() -> Vec<&'static str> {
         vec![
@@ -685,119 +680,6 @@ impl TableDisplay for PurchaseOrderRevisionCli {
     }
 }
 
-fn print_formattable<T: std::fmt::Display + Serialize>(
-    object: T,
-    format: Option<&str>,
-) -> Result<(), CliError> {
-    match format {
-        Some("json") => {
-            let formatted = serde_json::to_string(&object).map_err(|err| {
-                CliError::ActionError(format!("Error formatting as JSON: {}", err))
-            })?;
-            println!("{}", formatted);
-        }
-        Some("yaml") => {
-            let formatted = serde_yaml::to_string(&object).map_err(|err| {
-                CliError::ActionError(format!("Error formatting as YAML: {}", err))
-            })?;
-            println!("{}", formatted);
-        }
-        _ => println!("{}", object),
-    }
-    Ok(())
-}
-
-fn print_formattable_list<T: TableDisplay + std::fmt::Display + Serialize>(
-    data: &mut dyn Iterator<Item = Result<T, CliError>>,
-    format: Option<&str>,
-) -> Result<(), CliError> {
-    match format {
-        Some("json") => {
-            for row in &mut *data {
-                match row {
-                    Ok(object) => {
-                        print_formattable(object, format)?;
-                    }
-                    Err(err) => {
-                        println!("{}", err);
-                        return Err(err);
-                    }
-                }
-            }
-        }
-        Some("yaml") => {
-            for row in &mut *data {
-                match row {
-                    Ok(object) => {
-                        print_formattable(object, format)?;
-                    }
-                    Err(err) => {
-                        println!("{}", err);
-                        return Err(err);
-                    }
-                }
-            }
-        }
-        Some("csv") => {
-            println!("{}", str_join(T::header(), ","));
-            for row in &mut *data {
-                match row {
-                    Ok(object) => {
-                        println!("{}", str_join(object.details(), ","))
-                    }
-                    Err(err) => {
-                        println!("{}", err);
-                        return Err(err);
-                    }
-                }
-            }
-        }
-        _ => {
-            print_table(data)?;
-        }
-    }
-
-    Ok(())
-}
-
-fn print_table<T: TableDisplay>(
-    data: &mut dyn Iterator<Item = Result<T, CliError>>,
-) -> Result<(), CliError> {
-    // print header row
-    let mut header_row = "".to_owned();
-    for i in 0..T::header().len() {
-        header_row += &format!("{:width$} ", T::header()[i], width = T::widths()[i]);
-    }
-    println!("{}", header_row);
-
-    // print each row
-    for row in &mut *data {
-        match row {
-            Ok(res) => {
-                let mut print_row = "".to_owned();
-                for i in 0..T::header().len() {
-                    print_row += &format!("{:width$} ", res.details()[i], width = T::widths()[i]);
-                }
-                println!("{}", print_row);
-            }
-            Err(err) => {
-                println!("{}", err);
-                return Err(err);
-            }
-        }
-    }
-
-    Ok(())
-}
-
-fn str_join<T: ToString>(array: Vec<T>, delimiter: &str) -> String {
-    array
-        .iter()
-        .map(|x| x.to_string())
-        .collect::<Vec<String>>()
-        .join(delimiter)
-}
-
 #[cfg(test)]
 pub mod tests {
     use super::*;
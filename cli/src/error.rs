@@ -212,6 +212,13 @@ impl From<serde_yaml::Error> for CliError {
     }
 }
 
+#[cfg(feature = "mfg-batch-csv")]
+impl From<csv::Error> for CliError {
+    fn from(err: csv::Error) -> Self {
+        CliError::UserError(err.to_string())
+    }
+}
+
 impl From<protobuf::ProtobufError> for CliError {
     fn from(err: protobuf::ProtobufError) -> Self {
         CliError::ProtobufError(err)
@@ -38,6 +38,11 @@ const GRID_LOCATION_FAMILY_NAME: &str = "grid_location";
 #[cfg(feature = "location")]
 const GRID_LOCATION_FAMILY_VERSION: &str = "2";
 
+#[cfg(feature = "mfg-batch")]
+const GRID_MFG_BATCH_FAMILY_NAME: &str = "grid_mfg_batch";
+#[cfg(feature = "mfg-batch")]
+const GRID_MFG_BATCH_FAMILY_VERSION: &str = "1";
+
 #[cfg(feature = "pike")]
 const GRID_PIKE_FAMILY_NAME: &str = "grid_pike";
 #[cfg(feature = "pike")]
@@ -101,6 +106,15 @@ pub fn location_batch_builder(signer: Box<dyn Signer>) -> BatchBuilder {
     )
 }
 
+#[cfg(feature = "mfg-batch")]
+pub fn mfg_batch_batch_builder(signer: Box<dyn Signer>) -> BatchBuilder {
+    BatchBuilder::new(
+        GRID_MFG_BATCH_FAMILY_NAME,
+        GRID_MFG_BATCH_FAMILY_VERSION,
+        signer,
+    )
+}
+
 #[derive(Clone)]
 pub struct BatchBuilder {
     family_name: String,
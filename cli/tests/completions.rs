@@ -0,0 +1,46 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+*/
+
+extern crate assert_cmd;
+
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+/// Verifies `grid completions` generates a script for every supported shell.
+#[test]
+fn test_completions_for_each_shell() {
+    for shell in &["bash", "elvish", "fish", "powershell", "zsh"] {
+        let output = Command::cargo_bin("grid")
+            .expect("Unable to find grid binary")
+            .args(["completions", shell])
+            .output()
+            .expect("Unable to run grid completions");
+
+        assert!(output.status.success(), "failed for {}", shell);
+        assert!(!output.stdout.is_empty(), "no output for {}", shell);
+    }
+}
+
+/// Verifies `grid completions` rejects an unknown shell.
+#[test]
+fn test_completions_unknown_shell() {
+    Command::cargo_bin("grid")
+        .expect("Unable to find grid binary")
+        .args(["completions", "tcsh"])
+        .assert()
+        .failure();
+}
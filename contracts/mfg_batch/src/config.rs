@@ -0,0 +1,674 @@
+// Copyright 2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Settings for the transaction processor.
+//!
+//! Each setting is read from, in increasing order of precedence: the built in default, a TOML
+//! config file, a `GRID_MFG_BATCH_TP_*` environment variable, and the command line. The config
+//! file is the one named by `--config` or `GRID_MFG_BATCH_TP_CONFIG`, otherwise
+//! `/etc/grid/mfg-batch-tp.toml` if it exists. For example:
+//!
+//! ```toml
+//! connect = "tcp://validator:4004"
+//! log_level = "info"
+//! log_format = "json"
+//! log_filter = "handler=debug,validation=trace"
+//! strict_payloads = true
+//! decision_traces = true
+//! masked_properties = ["price", "supplier"]
+//! max_properties = 256
+//! max_struct_depth = 8
+//! max_bytes_value_size = 65536
+//! family_name = "acme_mfg_batch"
+//! service_id = "01234-ABCDE::gr00"
+//!
+//! [metrics]
+//! enabled = true
+//! bind = "0.0.0.0:9615"
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use grid_sdk::mfg_batch::addressing::{
+    AddressingError, MfgBatchAddresser, MfgBatchAddresserBuilder, GRID_MFG_BATCH_FAMILY_NAME,
+};
+use grid_sdk::mfg_batch::validation::validate_service_id;
+use log::LogLevelFilter;
+use serde::Deserialize;
+
+use crate::logging::LogFilter;
+use crate::payload::PayloadLimits;
+
+pub const DEFAULT_CONFIG_FILE: &str = "/etc/grid/mfg-batch-tp.toml";
+pub const DEFAULT_ENDPOINT: &str = "tcp://localhost:4004";
+pub const DEFAULT_METRICS_BIND: &str = "127.0.0.1:9615";
+
+const ENV_PREFIX: &str = "GRID_MFG_BATCH_TP_";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read
+    Io(PathBuf, io::Error),
+    /// The config file is not valid TOML or has unknown keys
+    Parse(PathBuf, toml::de::Error),
+    /// A setting has a value that cannot be used
+    InvalidValue { setting: String, value: String },
+}
+
+impl Error for ConfigError {}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => {
+                write!(f, "unable to read config file {}: {}", path.display(), err)
+            }
+            ConfigError::Parse(path, err) => {
+                write!(f, "invalid config file {}: {}", path.display(), err)
+            }
+            ConfigError::InvalidValue { setting, value } => {
+                write!(f, "invalid value for {}: {:?}", setting, value)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// The human readable `level | module:line | message` lines
+    Text,
+    /// One JSON object per record, for log collectors
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ProcessorConfig {
+    /// The validator endpoint to connect to
+    pub endpoint: String,
+    pub log_level: LogLevelFilter,
+    pub log_format: LogFormat,
+    /// The most verbose level written for each of the processor's modules
+    pub log_filter: LogFilter,
+    /// Whether to serve transaction counts in the Prometheus text format
+    pub metrics_enabled: bool,
+    /// The address the metrics endpoint listens on
+    pub metrics_bind: String,
+    /// Whether to reject payloads containing fields the contract does not know about
+    pub strict_payloads: bool,
+    /// Whether to record the outcome of each validation step of a transaction
+    pub decision_traces: bool,
+    /// The properties whose values are masked in logs
+    pub masked_properties: Vec<String>,
+    /// Bounds on the properties a payload may carry
+    pub payload_limits: PayloadLimits,
+    /// The transaction family the processor handles
+    pub family_name: String,
+    /// The namespace the family's state is stored under; derived from the family name if not
+    /// given
+    pub namespace: Option<String>,
+    /// The Splinter service, as `<circuit_id>::<service_id>`, the processor applies transactions
+    /// for; transactions submitted to another service are rejected
+    pub service_id: Option<String>,
+}
+
+impl Default for ProcessorConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            log_level: LogLevelFilter::Warn,
+            log_format: LogFormat::Text,
+            log_filter: LogFilter::default(),
+            metrics_enabled: false,
+            metrics_bind: DEFAULT_METRICS_BIND.to_string(),
+            strict_payloads: false,
+            decision_traces: false,
+            masked_properties: vec![],
+            payload_limits: PayloadLimits::default(),
+            family_name: GRID_MFG_BATCH_FAMILY_NAME.to_string(),
+            namespace: None,
+            service_id: None,
+        }
+    }
+}
+
+/// The settings given on the command line
+#[derive(Default)]
+pub struct CliArgs {
+    pub config_file: Option<PathBuf>,
+    pub connect: Option<String>,
+    pub log_level: Option<String>,
+    /// The number of `-v` flags; any overrides `log_level`
+    pub verbose: u64,
+    pub log_format: Option<String>,
+    pub log_filter: Option<String>,
+    pub metrics: bool,
+    pub metrics_bind: Option<String>,
+    pub strict_payloads: bool,
+    pub decision_traces: bool,
+    /// The comma separated properties given by `--masked-properties`
+    pub masked_properties: Option<String>,
+    pub max_properties: Option<String>,
+    pub max_struct_depth: Option<String>,
+    pub max_bytes_value_size: Option<String>,
+    pub family_name: Option<String>,
+    pub namespace: Option<String>,
+    pub service_id: Option<String>,
+}
+
+/// The layout of the TOML config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    connect: Option<String>,
+    log_level: Option<String>,
+    log_format: Option<String>,
+    log_filter: Option<String>,
+    strict_payloads: Option<bool>,
+    decision_traces: Option<bool>,
+    masked_properties: Option<Vec<String>>,
+    max_properties: Option<usize>,
+    max_struct_depth: Option<usize>,
+    max_bytes_value_size: Option<usize>,
+    family_name: Option<String>,
+    namespace: Option<String>,
+    service_id: Option<String>,
+    #[serde(default)]
+    metrics: MetricsSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MetricsSection {
+    enabled: Option<bool>,
+    bind: Option<String>,
+}
+
+/// The settings given by one source, each overriding the sources before it
+#[derive(Default)]
+struct Layer {
+    connect: Option<String>,
+    log_level: Option<String>,
+    log_format: Option<String>,
+    /// Comma separated `target=level` directives
+    log_filter: Option<String>,
+    metrics_enabled: Option<String>,
+    metrics_bind: Option<String>,
+    strict_payloads: Option<String>,
+    decision_traces: Option<String>,
+    /// A comma separated list of property names
+    masked_properties: Option<String>,
+    max_properties: Option<String>,
+    max_struct_depth: Option<String>,
+    max_bytes_value_size: Option<String>,
+    family_name: Option<String>,
+    namespace: Option<String>,
+    service_id: Option<String>,
+}
+
+impl ProcessorConfig {
+    /// Loads the settings from the config file, the process environment, and the command line
+    pub fn load(args: CliArgs) -> Result<Self, ConfigError> {
+        Self::load_from(
+            args,
+            |name| std::env::var(name).ok(),
+            Path::new(DEFAULT_CONFIG_FILE),
+        )
+    }
+
+    fn load_from<E>(args: CliArgs, env: E, default_file: &Path) -> Result<Self, ConfigError>
+    where
+        E: Fn(&str) -> Option<String>,
+    {
+        let mut config = Self::default();
+
+        let env_var = |setting: &str| format!("{}{}", ENV_PREFIX, setting.to_ascii_uppercase());
+
+        let path = args
+            .config_file
+            .clone()
+            .or_else(|| env(&env_var("config")).map(PathBuf::from))
+            .or_else(|| Some(default_file.to_path_buf()).filter(|path| path.exists()));
+        if let Some(path) = path {
+            let contents =
+                fs::read_to_string(&path).map_err(|err| ConfigError::Io(path.clone(), err))?;
+            let file: ConfigFile =
+                toml::from_str(&contents).map_err(|err| ConfigError::Parse(path.clone(), err))?;
+            let layer = Layer {
+                connect: file.connect,
+                log_level: file.log_level,
+                log_format: file.log_format,
+                log_filter: file.log_filter,
+                metrics_enabled: file.metrics.enabled.map(|enabled| enabled.to_string()),
+                metrics_bind: file.metrics.bind,
+                strict_payloads: file.strict_payloads.map(|strict| strict.to_string()),
+                decision_traces: file.decision_traces.map(|traces| traces.to_string()),
+                masked_properties: file.masked_properties.map(|names| names.join(",")),
+                max_properties: file.max_properties.map(|max| max.to_string()),
+                max_struct_depth: file.max_struct_depth.map(|max| max.to_string()),
+                max_bytes_value_size: file.max_bytes_value_size.map(|max| max.to_string()),
+                family_name: file.family_name,
+                namespace: file.namespace,
+                service_id: file.service_id,
+            };
+            config.apply(layer, |setting| {
+                format!("{} in {}", setting, path.display())
+            })?;
+        }
+
+        let layer = Layer {
+            connect: env(&env_var("connect")),
+            log_level: env(&env_var("log_level")),
+            log_format: env(&env_var("log_format")),
+            log_filter: env(&env_var("log_filter")),
+            metrics_enabled: env(&env_var("metrics_enabled")),
+            metrics_bind: env(&env_var("metrics_bind")),
+            strict_payloads: env(&env_var("strict_payloads")),
+            decision_traces: env(&env_var("decision_traces")),
+            masked_properties: env(&env_var("masked_properties")),
+            max_properties: env(&env_var("max_properties")),
+            max_struct_depth: env(&env_var("max_struct_depth")),
+            max_bytes_value_size: env(&env_var("max_bytes_value_size")),
+            family_name: env(&env_var("family_name")),
+            namespace: env(&env_var("namespace")),
+            service_id: env(&env_var("service_id")),
+        };
+        config.apply(layer, env_var)?;
+
+        let layer = Layer {
+            connect: args.connect,
+            log_level: args.log_level,
+            log_format: args.log_format,
+            log_filter: args.log_filter,
+            metrics_enabled: if args.metrics {
+                Some(true.to_string())
+            } else {
+                None
+            },
+            metrics_bind: args.metrics_bind,
+            strict_payloads: if args.strict_payloads {
+                Some(true.to_string())
+            } else {
+                None
+            },
+            decision_traces: if args.decision_traces {
+                Some(true.to_string())
+            } else {
+                None
+            },
+            masked_properties: args.masked_properties,
+            max_properties: args.max_properties,
+            max_struct_depth: args.max_struct_depth,
+            max_bytes_value_size: args.max_bytes_value_size,
+            family_name: args.family_name,
+            namespace: args.namespace,
+            service_id: args.service_id,
+        };
+        config.apply(layer, |setting| format!("--{}", setting.replace('_', "-")))?;
+        match args.verbose {
+            0 => (),
+            1 => config.log_level = LogLevelFilter::Info,
+            2 => config.log_level = LogLevelFilter::Debug,
+            _ => config.log_level = LogLevelFilter::Trace,
+        }
+
+        Ok(config)
+    }
+
+    /// Builds the addresser of the configured namespace, or of the family name if no namespace
+    /// is configured
+    pub fn addresser(&self) -> Result<MfgBatchAddresser, AddressingError> {
+        let builder = MfgBatchAddresserBuilder::new().with_family_name(self.family_name.clone());
+        match &self.namespace {
+            Some(namespace) => builder.with_namespace(namespace.clone()),
+            None => builder,
+        }
+        .build()
+    }
+
+    /// Overrides the settings a layer gives; `name` spells a setting as that source does
+    fn apply<N>(&mut self, layer: Layer, name: N) -> Result<(), ConfigError>
+    where
+        N: Fn(&str) -> String,
+    {
+        let invalid = |setting: &str, value: String| ConfigError::InvalidValue {
+            setting: name(setting),
+            value,
+        };
+
+        if let Some(connect) = layer.connect {
+            self.endpoint = connect;
+        }
+        if let Some(level) = layer.log_level {
+            self.log_level = level
+                .parse()
+                .map_err(|_| invalid("log_level", level.clone()))?;
+        }
+        if let Some(format) = layer.log_format {
+            self.log_format = format
+                .parse()
+                .map_err(|_| invalid("log_format", format.clone()))?;
+        }
+        if let Some(filter) = layer.log_filter {
+            self.log_filter = filter
+                .parse()
+                .map_err(|_| invalid("log_filter", filter.clone()))?;
+        }
+        if let Some(enabled) = layer.metrics_enabled {
+            self.metrics_enabled =
+                parse_bool(&enabled).ok_or_else(|| invalid("metrics_enabled", enabled.clone()))?;
+        }
+        if let Some(bind) = layer.metrics_bind {
+            self.metrics_bind = bind;
+        }
+        if let Some(strict) = layer.strict_payloads {
+            self.strict_payloads =
+                parse_bool(&strict).ok_or_else(|| invalid("strict_payloads", strict.clone()))?;
+        }
+        if let Some(traces) = layer.decision_traces {
+            self.decision_traces =
+                parse_bool(&traces).ok_or_else(|| invalid("decision_traces", traces.clone()))?;
+        }
+        if let Some(names) = layer.masked_properties {
+            self.masked_properties = names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(max) = layer.max_properties {
+            self.payload_limits.max_properties =
+                parse_limit(&max).ok_or_else(|| invalid("max_properties", max.clone()))?;
+        }
+        if let Some(max) = layer.max_struct_depth {
+            self.payload_limits.max_struct_depth =
+                parse_limit(&max).ok_or_else(|| invalid("max_struct_depth", max.clone()))?;
+        }
+        if let Some(max) = layer.max_bytes_value_size {
+            self.payload_limits.max_bytes_value_size =
+                parse_limit(&max).ok_or_else(|| invalid("max_bytes_value_size", max.clone()))?;
+        }
+        if let Some(family_name) = layer.family_name {
+            if family_name.trim().is_empty() {
+                return Err(invalid("family_name", family_name));
+            }
+            self.family_name = family_name;
+        }
+        if let Some(namespace) = layer.namespace {
+            MfgBatchAddresserBuilder::new()
+                .with_namespace(namespace.clone())
+                .build()
+                .map_err(|_| invalid("namespace", namespace.clone()))?;
+            self.namespace = Some(namespace);
+        }
+        if let Some(service_id) = layer.service_id {
+            if service_id.is_empty() || validate_service_id(&service_id).is_err() {
+                return Err(invalid("service_id", service_id));
+            }
+            self.service_id = Some(service_id);
+        }
+        Ok(())
+    }
+}
+
+/// Parses a payload limit, which must allow at least one of what it bounds
+fn parse_limit(value: &str) -> Option<usize> {
+    value.trim().parse().ok().filter(|limit| *limit > 0)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    const CONFIG_FILE: &str = r#"
+connect = "tcp://file:4004"
+log_level = "info"
+log_format = "json"
+log_filter = "handler=debug"
+strict_payloads = true
+decision_traces = true
+masked_properties = ["price", "supplier"]
+max_properties = 64
+max_struct_depth = 4
+family_name = "acme_mfg_batch"
+
+[metrics]
+enabled = true
+bind = "0.0.0.0:9615"
+"#;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn config_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().expect("Failed to create config file");
+        file.write_all(contents.as_bytes())
+            .expect("Failed to write config file");
+        file
+    }
+
+    /// Verifies the defaults are used when no source gives a setting, and that a missing default
+    /// config file is not an error
+    #[test]
+    fn test_defaults() {
+        let config = ProcessorConfig::load_from(
+            CliArgs::default(),
+            env_of(&[]),
+            Path::new("/nonexistent/mfg-batch-tp.toml"),
+        )
+        .expect("Failed to load config");
+
+        assert_eq!(config, ProcessorConfig::default());
+    }
+
+    /// Verifies the config file overrides the defaults, environment variables override the file,
+    /// and the command line overrides both
+    #[test]
+    fn test_layering() {
+        let file = config_file(CONFIG_FILE);
+        let env = env_of(&[
+            ("GRID_MFG_BATCH_TP_CONFIG", file.path().to_str().unwrap()),
+            ("GRID_MFG_BATCH_TP_CONNECT", "tcp://env:4004"),
+            ("GRID_MFG_BATCH_TP_LOG_FORMAT", "text"),
+            ("GRID_MFG_BATCH_TP_LOG_FILTER", "handler=trace,payload=off"),
+            (
+                "GRID_MFG_BATCH_TP_MASKED_PROPERTIES",
+                "price, supplier, lot_cost",
+            ),
+            ("GRID_MFG_BATCH_TP_MAX_STRUCT_DEPTH", "6"),
+            ("GRID_MFG_BATCH_TP_NAMESPACE", "abc123"),
+        ]);
+
+        let config = ProcessorConfig::load_from(CliArgs::default(), &env, Path::new(""))
+            .expect("Failed to load config");
+        assert_eq!(
+            config,
+            ProcessorConfig {
+                endpoint: "tcp://env:4004".to_string(),
+                log_level: LogLevelFilter::Info,
+                log_format: LogFormat::Text,
+                log_filter: "handler=trace,payload=off".parse().unwrap(),
+                metrics_enabled: true,
+                metrics_bind: "0.0.0.0:9615".to_string(),
+                strict_payloads: true,
+                decision_traces: true,
+                masked_properties: vec![
+                    "price".to_string(),
+                    "supplier".to_string(),
+                    "lot_cost".to_string()
+                ],
+                payload_limits: PayloadLimits {
+                    max_properties: 64,
+                    max_struct_depth: 6,
+                    ..Default::default()
+                },
+                family_name: "acme_mfg_batch".to_string(),
+                namespace: Some("abc123".to_string()),
+                service_id: None,
+            }
+        );
+
+        let args = CliArgs {
+            connect: Some("tcp://cli:4004".to_string()),
+            verbose: 2,
+            metrics_bind: Some("127.0.0.1:9000".to_string()),
+            masked_properties: Some("price,".to_string()),
+            max_properties: Some("32".to_string()),
+            ..Default::default()
+        };
+        let config =
+            ProcessorConfig::load_from(args, &env, Path::new("")).expect("Failed to load config");
+        assert_eq!(config.endpoint, "tcp://cli:4004");
+        assert_eq!(config.log_level, LogLevelFilter::Debug);
+        assert_eq!(config.metrics_bind, "127.0.0.1:9000");
+        assert_eq!(config.masked_properties, vec!["price".to_string()]);
+        assert_eq!(config.payload_limits.max_properties, 32);
+        assert_eq!(
+            config
+                .addresser()
+                .expect("Failed to build addresser")
+                .namespace(),
+            "abc123"
+        );
+    }
+
+    /// Verifies the namespace is derived from the family name unless one is given
+    #[test]
+    fn test_addresser() {
+        let config = ProcessorConfig::default();
+        assert_eq!(
+            config.addresser().expect("Failed to build addresser"),
+            MfgBatchAddresser::default()
+        );
+
+        let args = CliArgs {
+            family_name: Some("acme_mfg_batch".to_string()),
+            ..Default::default()
+        };
+        let config = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect("Failed to load config");
+        assert_eq!(
+            config
+                .addresser()
+                .expect("Failed to build addresser")
+                .namespace(),
+            grid_sdk::mfg_batch::addressing::compute_family_namespace("acme_mfg_batch")
+        );
+    }
+
+    /// Verifies invalid values and unknown config file keys are rejected, naming the setting as
+    /// its source spells it
+    #[test]
+    fn test_invalid() {
+        let err = ProcessorConfig::load_from(
+            CliArgs::default(),
+            env_of(&[("GRID_MFG_BATCH_TP_METRICS_ENABLED", "maybe")]),
+            Path::new(""),
+        )
+        .expect_err("Loaded an invalid config");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for GRID_MFG_BATCH_TP_METRICS_ENABLED: \"maybe\""
+        );
+
+        let args = CliArgs {
+            log_format: Some("xml".to_string()),
+            ..Default::default()
+        };
+        let err = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect_err("Loaded an invalid config");
+        assert_eq!(err.to_string(), "invalid value for --log-format: \"xml\"");
+
+        let args = CliArgs {
+            log_filter: Some("handler".to_string()),
+            ..Default::default()
+        };
+        let err = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect_err("Loaded an invalid config");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for --log-filter: \"handler\""
+        );
+
+        let args = CliArgs {
+            max_bytes_value_size: Some("0".to_string()),
+            ..Default::default()
+        };
+        let err = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect_err("Loaded an invalid config");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for --max-bytes-value-size: \"0\""
+        );
+
+        let args = CliArgs {
+            namespace: Some("11BB0E".to_string()),
+            ..Default::default()
+        };
+        let err = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect_err("Loaded an invalid config");
+        assert_eq!(err.to_string(), "invalid value for --namespace: \"11BB0E\"");
+
+        let args = CliArgs {
+            service_id: Some("gr00".to_string()),
+            ..Default::default()
+        };
+        let err = ProcessorConfig::load_from(args, env_of(&[]), Path::new(""))
+            .expect_err("Loaded an invalid config");
+        assert_eq!(err.to_string(), "invalid value for --service-id: \"gr00\"");
+
+        let file = config_file("endpoint = \"tcp://file:4004\"\n");
+        let args = CliArgs {
+            config_file: Some(file.path().to_path_buf()),
+            ..Default::default()
+        };
+        match ProcessorConfig::load_from(args, env_of(&[]), Path::new("")) {
+            Err(ConfigError::Parse(..)) => (),
+            res => panic!("Expected ConfigError::Parse, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors the contract rejects transactions with.
+//!
+//! A rejection message starts with a code naming the kind of failure in square brackets, followed
+//! by a description for people, for example:
+//!
+//! ```text
+//! [permission] The signer "02a1..." does not have the "mfg_batch::can-update-mfg-batch" ...
+//! [validation:quantity] Quantity may not be negative: -1
+//! [not_found] No mfg_batch exists: 688955434684
+//! ```
+//!
+//! Validation failures name the field at fault after a colon; properties are named
+//! `properties.<name>`. Clients should match on the code rather than the description, which may
+//! change between releases.
+
+use std::error::Error;
+use std::fmt;
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use sabre_sdk::ApplyError;
+    } else {
+        use sawtooth_sdk::processor::handler::ApplyError;
+    }
+}
+
+use grid_sdk::mfg_batch::validation::Violation;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MfgBatchError {
+    /// The payload cannot be decoded, is missing fields, or exceeds the payload limits
+    InvalidPayload(String),
+    /// The signer does not have the permission the action requires
+    Permission(String),
+    /// A field of the action has a value the contract does not accept
+    Validation { field: String, message: String },
+    /// A record the action refers to does not exist
+    NotFound(String),
+    /// A record the action would add has already been recorded
+    AlreadyExists(String),
+    /// The mfg_batch cannot be changed as it is now: it has been archived, recalled, or changed
+    /// since the version the action was built against
+    Conflict(String),
+    /// The schema of the mfg_batch's namespace has not been defined
+    SchemaMissing(String),
+    /// The contract failed to apply a valid action; the transaction is not rejected
+    Internal(String),
+}
+
+impl MfgBatchError {
+    pub fn validation(field: &str, message: String) -> Self {
+        MfgBatchError::Validation {
+            field: field.to_string(),
+            message,
+        }
+    }
+
+    /// Returns the code that starts the error's message
+    pub fn code(&self) -> String {
+        match self {
+            MfgBatchError::InvalidPayload(_) => "payload".to_string(),
+            MfgBatchError::Permission(_) => "permission".to_string(),
+            MfgBatchError::Validation { field, .. } => format!("validation:{}", field),
+            MfgBatchError::NotFound(_) => "not_found".to_string(),
+            MfgBatchError::AlreadyExists(_) => "already_exists".to_string(),
+            MfgBatchError::Conflict(_) => "conflict".to_string(),
+            MfgBatchError::SchemaMissing(_) => "schema_missing".to_string(),
+            MfgBatchError::Internal(_) => "internal".to_string(),
+        }
+    }
+
+    /// Returns the description of the error, without its code
+    pub fn message(&self) -> &str {
+        match self {
+            MfgBatchError::InvalidPayload(message)
+            | MfgBatchError::Permission(message)
+            | MfgBatchError::Validation { message, .. }
+            | MfgBatchError::NotFound(message)
+            | MfgBatchError::AlreadyExists(message)
+            | MfgBatchError::Conflict(message)
+            | MfgBatchError::SchemaMissing(message)
+            | MfgBatchError::Internal(message) => message,
+        }
+    }
+}
+
+impl Error for MfgBatchError {}
+
+impl fmt::Display for MfgBatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.message())
+    }
+}
+
+// The rules shared with clients through the SDK report the field they were checking
+impl From<Violation> for MfgBatchError {
+    fn from(violation: Violation) -> Self {
+        MfgBatchError::Validation {
+            field: violation.field,
+            message: violation.message,
+        }
+    }
+}
+
+impl From<MfgBatchError> for ApplyError {
+    fn from(err: MfgBatchError) -> Self {
+        match err {
+            MfgBatchError::Internal(_) => ApplyError::InternalError(err.to_string()),
+            err => ApplyError::InvalidTransaction(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies each error's message starts with its code, and that only internal errors are
+    /// reported as internal errors rather than invalid transactions
+    #[test]
+    fn test_into_apply_error() {
+        let err = MfgBatchError::validation("quantity", "Quantity may not be negative".into());
+        assert_eq!(err.code(), "validation:quantity");
+        match ApplyError::from(err) {
+            ApplyError::InvalidTransaction(message) => assert_eq!(
+                message,
+                "[validation:quantity] Quantity may not be negative"
+            ),
+            err => panic!("Expected InvalidTransaction, got {:?}", err),
+        }
+
+        match ApplyError::from(MfgBatchError::Permission("Not permitted".into())) {
+            ApplyError::InvalidTransaction(message) => {
+                assert_eq!(message, "[permission] Not permitted")
+            }
+            err => panic!("Expected InvalidTransaction, got {:?}", err),
+        }
+
+        match ApplyError::from(MfgBatchError::Internal("Cannot encode".into())) {
+            ApplyError::InternalError(message) => assert_eq!(message, "[internal] Cannot encode"),
+            err => panic!("Expected InternalError, got {:?}", err),
+        }
+    }
+}
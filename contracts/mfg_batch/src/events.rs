@@ -0,0 +1,59 @@
+// Copyright (c) 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The events the handler adds as it changes mfg_batches, so subscribers can follow the changes
+//! without diffing raw state.
+//!
+//! Each event carries the mfg_batch's ID, owner and namespace as attributes, and the serialized
+//! mfg_batch as its data. A deleted mfg_batch's event carries the mfg_batch as it was before it
+//! was deleted. When the transaction was submitted to a Splinter service, the event also carries
+//! the service as a `service_id` attribute, so subscribers to several circuits can tell which
+//! service's state changed. When an agent of another organization created the mfg_batch for its
+//! owner, the event carries the owner's role that delegated the permission as a `delegated_role`
+//! attribute.
+
+use grid_sdk::protocol::mfg_batch::state::MfgBatchNamespace;
+
+/// A change made to a mfg_batch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MfgBatchEvent {
+    Created,
+    /// The mfg_batch's properties, parents, test results or attestations changed
+    Updated,
+    Deleted,
+    /// The mfg_batch was archived or recalled. The event carries whether the mfg_batch is
+    /// archived as an `archived` attribute and, once it is recalled, the recall as a
+    /// `recall_id` attribute.
+    StatusChanged,
+}
+
+impl MfgBatchEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            MfgBatchEvent::Created => "grid/mfg_batch/created",
+            MfgBatchEvent::Updated => "grid/mfg_batch/updated",
+            MfgBatchEvent::Deleted => "grid/mfg_batch/deleted",
+            MfgBatchEvent::StatusChanged => "grid/mfg_batch/status_changed",
+        }
+    }
+}
+
+/// The name a namespace is given in event attributes, as it is in the REST API
+pub fn namespace_name(namespace: &MfgBatchNamespace) -> &'static str {
+    match namespace {
+        MfgBatchNamespace::Gs1 => "GS1",
+        MfgBatchNamespace::Internal => "INTERNAL",
+        MfgBatchNamespace::Lot => "LOT",
+    }
+}
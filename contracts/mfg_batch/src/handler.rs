@@ -13,7 +13,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-
 // MFG_BTCH
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
@@ -31,21 +30,37 @@ cfg_if! {
 }
 
 use grid_sdk::{
+    log_masking::LogMask,
+    mfg_batch::addressing::{MfgBatchAddresser, MfgBatchIdentifier, GRID_MFG_BATCH_FAMILY_NAME},
     pike::permissions::PermissionChecker,
-    mfg_batch::addressing::GRID_NAMESPACE,
     protocol::mfg_batch::{
         payload::{
-            Action, MfgBatchCreateAction, MfgBatchDeleteAction, MfgBatchPayload, MfgBatchUpdateAction,
+            Action, MfgBatchAddAttestationAction, MfgBatchAddParentsAction,
+            MfgBatchAddTestResultAction, MfgBatchAllocateAction, MfgBatchAnchorAction,
+            MfgBatchCreateAction, MfgBatchDeleteAction, MfgBatchPayload, MfgBatchRecallAction,
+            MfgBatchReleaseAction, MfgBatchUpdateAction,
+        },
+        state::{
+            Allocation, AllocationBuilder, MfgBatchAnchorBuilder, MfgBatchBuilder,
+            MfgBatchNamespace, RecallBuilder,
         },
-        state::{MfgBatchBuilder, MfgBatchNamespace},
     },
-    protos::FromBytes,
+    protos::{FromBytes, FromBytesStrict},
 };
 
-use crate::payload::validate_payload;
-use crate::permissions::{permission_to_perm_string, Permission};
+use crate::error::MfgBatchError;
+use crate::events::MfgBatchEvent;
+use crate::payload::{validate_payload, PayloadLimits};
+use crate::permissions::{find_delegating_role, permission_to_perm_string, Permission};
 use crate::state::MfgBatchState;
-use crate::validation::validate_gtin;
+use crate::trace::DecisionTrace;
+use crate::validation::{
+    validate_allocated_quantity, validate_allocation, validate_anchor, validate_attachments,
+    validate_attestation, validate_dates, validate_expected_version, validate_gs1_company_prefix,
+    validate_manufacture_location, validate_namespaced_mfg_batch_id, validate_no_genealogy_cycle,
+    validate_not_archived, validate_property_languages, validate_property_value, validate_quantity,
+    validate_recall, validate_release, validate_service_id, validate_test_result,
+};
 
 #[cfg(target_arch = "wasm32")]
 // Sabre apply must return a bool
@@ -57,7 +72,11 @@ fn apply(
     match handler.apply(request, context) {
         Ok(_) => Ok(true),
         Err(err) => {
-            info!("{} received {}", handler.family_name(), err);
+            log_info!(
+                "Rejected transaction",
+                family = handler.family_name(),
+                error = err
+            );
             Err(err)
         }
     }
@@ -73,92 +92,173 @@ pub unsafe fn entrypoint(payload: WasmPtr, signer: WasmPtr, signature: WasmPtr)
 pub struct MfgBatchTransactionHandler {
     family_name: String,
     family_versions: Vec<String>,
-    namespaces: Vec<String>,
+    /// Computes the addresses of mfg_batches under the family's namespace
+    addresser: MfgBatchAddresser,
+    /// Whether payloads containing unknown proto fields are rejected
+    strict_payloads: bool,
+    /// Whether the outcome of each validation step is recorded
+    decision_traces: bool,
+    /// The properties whose values are masked when payloads are logged
+    log_mask: LogMask,
+    /// Bounds on the properties a payload may carry
+    payload_limits: PayloadLimits,
+    /// The Splinter service the handler applies transactions for, if scoped to one
+    service_id: Option<String>,
 }
 
 impl MfgBatchTransactionHandler {
     pub fn new() -> MfgBatchTransactionHandler {
         MfgBatchTransactionHandler {
-            family_name: "grid_mfg_batch".to_string(),
+            family_name: GRID_MFG_BATCH_FAMILY_NAME.to_string(),
             family_versions: vec!["1".to_string()],
-            namespaces: vec![GRID_NAMESPACE.to_string()],
+            addresser: MfgBatchAddresser::default(),
+            strict_payloads: false,
+            decision_traces: false,
+            log_mask: LogMask::default(),
+            payload_limits: PayloadLimits::default(),
+            service_id: None,
         }
     }
 
+    /// Registers the handler under another family name, so more than one mfg_batch family can
+    /// run on a network
+    pub fn with_family_name(mut self, family_name: String) -> Self {
+        self.family_name = family_name;
+        self
+    }
+
+    /// Reads and writes state under the addresser's namespace, which each family on a network
+    /// must have to itself
+    pub fn with_addresser(mut self, addresser: MfgBatchAddresser) -> Self {
+        self.addresser = addresser;
+        self
+    }
+
+    /// Rejects payloads containing fields the contract does not know about, rather than
+    /// ignoring them
+    pub fn with_strict_payloads(mut self, strict_payloads: bool) -> Self {
+        self.strict_payloads = strict_payloads;
+        self
+    }
+
+    /// Records the outcome of each validation step in the receipt of an applied transaction, or
+    /// the message of a rejected one
+    pub fn with_decision_traces(mut self, decision_traces: bool) -> Self {
+        self.decision_traces = decision_traces;
+        self
+    }
+
+    /// Masks the values of the given properties when payloads are logged
+    pub fn with_log_mask(mut self, log_mask: LogMask) -> Self {
+        self.log_mask = log_mask;
+        self
+    }
+
+    /// Rejects payloads whose properties exceed the given limits
+    pub fn with_payload_limits(mut self, payload_limits: PayloadLimits) -> Self {
+        self.payload_limits = payload_limits;
+        self
+    }
+
+    /// Scopes the handler to a Splinter service, given as `<circuit_id>::<service_id>`.
+    ///
+    /// Each service on a circuit keeps its own state, so the same address holds different
+    /// mfg_batches for different services. A scoped handler rejects transactions that name
+    /// another service, rather than applying them to the state of the service it serves, and
+    /// records its service in the events it adds. An unscoped handler, such as one run under
+    /// Sabre, records the service a transaction names, if any.
+    pub fn with_service_id(mut self, service_id: String) -> Self {
+        self.service_id = Some(service_id);
+        self
+    }
+
     fn create_mfg_batch(
         &self,
         payload: &MfgBatchCreateAction,
         state: &mut MfgBatchState,
         signer: &str,
         perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
     ) -> Result<(), ApplyError> {
         let mfg_batch_id = payload.mfg_batch_id();
         let owner = payload.owner();
         let mfg_batch_namespace = payload.mfg_batch_namespace();
-        let properties = payload.properties();
-
-        // Check signing agent's permission
-        check_permission(
-            perm_checker,
-            signer,
-            &permission_to_perm_string(Permission::CanCreateMfgBatch),
-            owner,
+        let mut properties = payload.properties().to_vec();
+
+        // Check signing agent's permission, which an agent of another organization may hold
+        // through a role the owner delegates to it
+        let delegated_role = trace.step(
+            "permission",
+            check_delegated_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanCreateMfgBatch),
+                owner,
+            ),
         )?;
+        state.set_delegated_role(delegated_role);
 
         // Check if mfg_batch exists in state
-        if state.get_mfg_batch(mfg_batch_id)?.is_some() {
-            return Err(ApplyError::InvalidTransaction(format!(
-                "Product already exists: {}",
-                mfg_batch_id,
-            )));
+        if state
+            .get_mfg_batch(mfg_batch_namespace, mfg_batch_id)?
+            .is_some()
+        {
+            return trace.reject(
+                "unique",
+                MfgBatchError::AlreadyExists(format!("Product already exists: {}", mfg_batch_id)),
+            );
         }
+        trace.pass("unique");
 
-        // Check if the mfg_batch namespace is a GS1 mfg_batch
-        if mfg_batch_namespace != &MfgBatchNamespace::Gs1 {
-            return Err(ApplyError::InvalidTransaction(
-                "Invalid mfg_batch namespace enum for mfg_batch".to_string(),
-            ));
-        }
+        // Check if mfg_batch mfg_batch_id is a valid identifier
+        let identifier = trace.step(
+            "mfg_batch_id",
+            validate_namespaced_mfg_batch_id(mfg_batch_namespace, mfg_batch_id),
+        )?;
 
-        // Check if mfg_batch mfg_batch_id is a valid gtin
-        if let Err(e) = validate_gtin(mfg_batch_id) {
-            return Err(ApplyError::InvalidTransaction(e.to_string()));
-        }
+        trace.step(
+            "quantity",
+            validate_quantity(
+                payload.quantity(),
+                payload.uom(),
+                payload.expected_quantity(),
+            ),
+        )?;
+        trace.step(
+            "dates",
+            validate_dates(payload.production_date(), payload.expiration_date()),
+        )?;
+        trace.step(
+            "manufacture_location",
+            check_manufacture_location(state, payload.manufacture_location()),
+        )?;
+        trace.step("attachments", validate_attachments(payload.attachments()))?;
 
         // Check that the organization ID exists in state
         let org = match state.get_organization(payload.owner())? {
             Some(org) => org,
             None => {
-                return Err(ApplyError::InvalidTransaction(format!(
-                    "The Agent's organization does not exist: {}",
-                    signer,
-                )));
+                return trace.reject(
+                    "owner",
+                    MfgBatchError::NotFound(format!(
+                        "The Agent's organization does not exist: {}",
+                        signer,
+                    )),
+                );
             }
         };
+        trace.pass("owner");
 
         /* Check if the agents organization contain GS1 Company Prefix key in its alternate IDs
-        (gs1_company_prefix), and the prefix must match the company prefix in the mfg_batch_id */
-        if payload.mfg_batch_namespace() == &MfgBatchNamespace::Gs1 {
-            let metadata = org.alternate_ids().to_vec();
-            let gs1_company_prefix = match metadata
-                .iter()
-                .find(|p| p.id_type() == "gs1_company_prefix")
-            {
-                Some(gs1_company_prefix) => gs1_company_prefix,
-                None => {
-                    return Err(ApplyError::InvalidTransaction(format!(
-                        "The agents organization does not have the gs1_company_prefix prefix: {:?}",
-                        org.alternate_ids()
-                    )));
-                }
-            };
-            // If the gtin identifer does not contain the organizations gs1 prefix
-            if !mfg_batch_id.contains(gs1_company_prefix.id()) {
-                return Err(ApplyError::InvalidTransaction(format!(
-                    "The agents organization does not own the GS1 company prefix in the GTIN mfg_batch_id: {:?}",
-                    org.alternate_ids()
-                )));
-            }
+        (gs1_company_prefix), and the prefix must match the company prefix in the mfg_batch_id.
+        Company-internal identifiers are not GS1 keys, so they carry no company prefix */
+        if payload.mfg_batch_namespace() == &MfgBatchNamespace::Gs1
+            && identifier != MfgBatchIdentifier::CompanyInternal
+        {
+            trace.step(
+                "gs1_company_prefix",
+                validate_gs1_company_prefix(mfg_batch_id, &org),
+            )?;
         }
 
         if payload.mfg_batch_namespace() == &MfgBatchNamespace::Gs1 {
@@ -166,52 +266,96 @@ impl MfgBatchTransactionHandler {
             let schema = if let Some(schema) = state.get_schema("gs1_mfg_batch")? {
                 schema
             } else {
-                return Err(ApplyError::InvalidTransaction(
-                    "gs1_mfg_batch schema has not been defined".into(),
-                ));
+                return trace.reject(
+                    "schema",
+                    MfgBatchError::SchemaMissing(
+                        "gs1_mfg_batch schema has not been defined".into(),
+                    ),
+                );
             };
+            trace.pass("schema");
 
-            // Check if properties in mfg_batch are all a part of the gs1 schema
+            // Check if properties in mfg_batch are all a part of the gs1 schema and are valid
+            // values for their definitions
             for property in payload.properties() {
-                if schema
-                    .properties()
-                    .iter()
-                    .all(|p| p.name() != property.name())
-                {
-                    return Err(ApplyError::InvalidTransaction(format!(
-                        "{} is not a property that is defined by the gs1 schema",
-                        property.name()
-                    )));
+                let step = format!("property {}", property.name());
+                let definition = trace.step(
+                    &step,
+                    schema
+                        .properties()
+                        .iter()
+                        .find(|p| p.name() == property.name())
+                        .ok_or_else(|| {
+                            MfgBatchError::validation(
+                                &format!("properties.{}", property.name()),
+                                format!(
+                                    "{} is not a property that is defined by the gs1 schema",
+                                    property.name()
+                                ),
+                            )
+                        }),
+                )?;
+
+                trace.step(&step, validate_property_value(property, definition))?;
+            }
+
+            // Populate the properties left out of the payload that the schema gives a default,
+            // so payload producers only need to send overrides
+            for definition in schema.properties() {
+                if let Some(default_value) = definition.default_value() {
+                    if !properties.iter().any(|p| p.name() == definition.name()) {
+                        trace.step(
+                            &format!("default {}", definition.name()),
+                            validate_property_value(default_value, definition),
+                        )?;
+                        properties.push(default_value.clone());
+                    }
                 }
             }
 
             // Check if property has all required fields
             for property in schema.properties().iter().filter(|p| *p.required()) {
-                if !payload
-                    .properties()
+                if !properties
                     .iter()
                     .any(|p| p.name() == property.name() && p.data_type() == property.data_type())
                 {
-                    return Err(ApplyError::InvalidTransaction(format!(
-                        "Missing required field '{}' of type '{:?}'",
-                        property.name(),
-                        property.data_type()
-                    )));
+                    return trace.reject(
+                        "required_properties",
+                        MfgBatchError::validation(
+                            &format!("properties.{}", property.name()),
+                            format!(
+                                "Missing required field '{}' of type '{:?}'",
+                                property.name(),
+                                property.data_type()
+                            ),
+                        ),
+                    );
                 }
             }
+            trace.pass("required_properties");
         }
 
+        trace.step("languages", validate_property_languages(&properties))?;
+
         let new_mfg_batch = MfgBatchBuilder::new()
             .with_mfg_batch_id(mfg_batch_id.to_string())
             .with_owner(owner.to_string())
             .with_mfg_batch_namespace(mfg_batch_namespace.clone())
-            .with_properties(properties.to_vec())
+            .with_properties(properties)
+            .with_quantity(payload.quantity())
+            .with_uom(payload.uom().to_string())
+            .with_expected_quantity(payload.expected_quantity())
+            .with_production_date(payload.production_date())
+            .with_expiration_date(payload.expiration_date())
+            .with_manufacture_location(payload.manufacture_location().to_string())
+            .with_attachments(payload.attachments().to_vec())
             .build()
             .map_err(|err| {
-                ApplyError::InvalidTransaction(format!("Cannot build mfg_batch: {}", err))
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
             })?;
 
-        state.set_mfg_batch(mfg_batch_id, new_mfg_batch)?;
+        state.set_mfg_batch(mfg_batch_id, new_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Created, &new_mfg_batch)?;
 
         Ok(())
     }
@@ -222,93 +366,208 @@ impl MfgBatchTransactionHandler {
         state: &mut MfgBatchState,
         signer: &str,
         perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
     ) -> Result<(), ApplyError> {
         let mfg_batch_id = payload.mfg_batch_id();
         let mfg_batch_namespace = payload.mfg_batch_namespace();
         let properties = payload.properties();
 
-        // Check if the mfg_batch namespace is a GS1 mfg_batch
-        if mfg_batch_namespace != &MfgBatchNamespace::Gs1 {
-            return Err(ApplyError::InvalidTransaction(
-                "Invalid mfg_batch namespace enum for mfg_batch".to_string(),
-            ));
-        }
-
         // Check if mfg_batch exists
-        let mfg_batch = match state.get_mfg_batch(mfg_batch_id) {
-            Ok(Some(mfg_batch)) => Ok(mfg_batch),
-            Ok(None) => Err(ApplyError::InvalidTransaction(format!(
-                "No mfg_batch exists: {}",
-                mfg_batch_id
-            ))),
-            Err(err) => Err(err),
-        }?;
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
         // Check signing agent's permission
-        check_permission(
-            perm_checker,
-            signer,
-            &permission_to_perm_string(Permission::CanUpdateMfgBatch),
-            mfg_batch.owner(),
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanUpdateMfgBatch),
+                mfg_batch.owner(),
+            ),
         )?;
 
-        // Check if mfg_batch mfg_batch_id is a valid gtin
-        if let Err(e) = validate_gtin(mfg_batch_id) {
-            return Err(ApplyError::InvalidTransaction(e.to_string()));
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+
+        trace.step(
+            "version",
+            validate_expected_version(&mfg_batch, payload.expected_version()),
+        )?;
+
+        // Check if mfg_batch mfg_batch_id is a valid identifier
+        if let Err(e) = validate_namespaced_mfg_batch_id(mfg_batch_namespace, mfg_batch_id) {
+            return trace.reject("mfg_batch_id", e);
         }
+        trace.pass("mfg_batch_id");
 
         if payload.mfg_batch_namespace() == &MfgBatchNamespace::Gs1 {
             // Check if gs1 schema exists
             let schema = if let Some(schema) = state.get_schema("gs1_mfg_batch")? {
                 schema
             } else {
-                return Err(ApplyError::InvalidTransaction(
-                    "gs1_mfg_batch schema has not been defined".into(),
-                ));
+                return trace.reject(
+                    "schema",
+                    MfgBatchError::SchemaMissing(
+                        "gs1_mfg_batch schema has not been defined".into(),
+                    ),
+                );
             };
+            trace.pass("schema");
 
-            // Check if properties in mfg_batch are all a part of the gs1 schema
-            for property in payload.properties() {
-                if schema
-                    .properties()
-                    .iter()
-                    .all(|p| p.name() != property.name())
-                {
-                    return Err(ApplyError::InvalidTransaction(format!(
-                        "{} is not a property that is defined by the gs1 schema",
-                        property.name()
-                    )));
-                }
+            // Properties the mfg_batch already has are not revalidated when the update asks for
+            // it, so that a schema amended since they were set does not reject them
+            let changed_only = payload.validate_changed_properties_only();
+
+            // Check if properties in mfg_batch are all a part of the gs1 schema and are valid
+            // values for their definitions
+            for property in payload
+                .properties()
+                .iter()
+                .filter(|property| !(changed_only && mfg_batch.properties().contains(property)))
+            {
+                let step = format!("property {}", property.name());
+                let definition = trace.step(
+                    &step,
+                    schema
+                        .properties()
+                        .iter()
+                        .find(|p| p.name() == property.name())
+                        .ok_or_else(|| {
+                            MfgBatchError::validation(
+                                &format!("properties.{}", property.name()),
+                                format!(
+                                    "{} is not a property that is defined by the gs1 schema",
+                                    property.name()
+                                ),
+                            )
+                        }),
+                )?;
+
+                trace.step(&step, validate_property_value(property, definition))?;
             }
 
-            // Check if property has all required fields
+            // Check if property has all required fields; when only changes are validated, a
+            // required property the mfg_batch did not have may stay missing
             for property in schema.properties().iter().filter(|p| *p.required()) {
                 if !payload
                     .properties()
                     .iter()
                     .any(|p| p.name() == property.name() && p.data_type() == property.data_type())
+                    && (!changed_only
+                        || mfg_batch
+                            .properties()
+                            .iter()
+                            .any(|p| p.name() == property.name()))
                 {
-                    return Err(ApplyError::InvalidTransaction(format!(
-                        "Missing required field '{}' of type '{:?}'",
-                        property.name(),
-                        property.data_type()
-                    )));
+                    return trace.reject(
+                        "required_properties",
+                        MfgBatchError::validation(
+                            &format!("properties.{}", property.name()),
+                            format!(
+                                "Missing required field '{}' of type '{:?}'",
+                                property.name(),
+                                property.data_type()
+                            ),
+                        ),
+                    );
                 }
             }
+            trace.pass("required_properties");
         }
 
-        // Handle updating the mfg_batch
-        let updated_mfg_batch = MfgBatchBuilder::new()
-            .with_mfg_batch_id(mfg_batch_id.to_string())
-            .with_owner(mfg_batch.owner().to_string())
-            .with_mfg_batch_namespace(mfg_batch_namespace.clone())
+        trace.step("languages", validate_property_languages(properties))?;
+
+        trace.step(
+            "quantity",
+            validate_quantity(
+                payload.quantity(),
+                payload.uom(),
+                payload.expected_quantity(),
+            ),
+        )?;
+
+        // Quantities are only replaced if the update gives a unit of measure for them
+        let (quantity, uom, expected_quantity) = if payload.uom().is_empty() {
+            (
+                mfg_batch.quantity(),
+                mfg_batch.uom(),
+                mfg_batch.expected_quantity(),
+            )
+        } else {
+            (
+                payload.quantity(),
+                payload.uom(),
+                payload.expected_quantity(),
+            )
+        };
+
+        // Dates are only replaced if the update sets them, and must still be valid together
+        let production_date = match payload.production_date() {
+            0 => mfg_batch.production_date(),
+            date => date,
+        };
+        let expiration_date = match payload.expiration_date() {
+            0 => mfg_batch.expiration_date(),
+            date => date,
+        };
+        trace.step("dates", validate_dates(production_date, expiration_date))?;
+
+        // The location is only replaced if the update sets it
+        let manufacture_location = if payload.manufacture_location().is_empty() {
+            mfg_batch.manufacture_location()
+        } else {
+            trace.step(
+                "manufacture_location",
+                check_manufacture_location(state, payload.manufacture_location()),
+            )?;
+            payload.manufacture_location()
+        };
+
+        // As are the attachments
+        let attachments = if payload.attachments().is_empty() {
+            mfg_batch.attachments()
+        } else {
+            trace.step("attachments", validate_attachments(payload.attachments()))?;
+            payload.attachments()
+        };
+
+        trace.step(
+            "allocations",
+            validate_allocated_quantity(&mfg_batch, quantity, uom),
+        )?;
+
+        // The update is built from the stored mfg_batch, so that what an update cannot set, such
+        // as its parents, test results, attestations, allocations and recall, is kept. A
+        // recalled batch may still be updated, such as to correct its quantity after returns,
+        // and stays recalled.
+        let updated_mfg_batch = mfg_batch
+            .clone()
+            .into_builder()
             .with_properties(properties.to_vec())
+            .with_quantity(quantity)
+            .with_uom(uom.to_string())
+            .with_expected_quantity(expected_quantity)
+            .with_production_date(production_date)
+            .with_expiration_date(expiration_date)
+            .with_manufacture_location(manufacture_location.to_string())
+            .with_attachments(attachments.to_vec())
             .build()
             .map_err(|err| {
-                ApplyError::InvalidTransaction(format!("Cannot build mfg_batch: {}", err))
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
             })?;
 
-        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch)?;
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
 
         Ok(())
     }
@@ -319,338 +578,822 @@ impl MfgBatchTransactionHandler {
         state: &mut MfgBatchState,
         signer: &str,
         perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
     ) -> Result<(), ApplyError> {
         let mfg_batch_id = payload.mfg_batch_id();
         let mfg_batch_namespace = payload.mfg_batch_namespace();
 
-        // Check if the mfg_batch namespace is a GS1 mfg_batch
-        if mfg_batch_namespace != &MfgBatchNamespace::Gs1 {
-            return Err(ApplyError::InvalidTransaction(
-                "Invalid mfg_batch namespace enum for mfg_batch".to_string(),
-            ));
-        }
-
         // Check if mfg_batch exists in state
-        let mfg_batch = match state.get_mfg_batch(mfg_batch_id) {
-            Ok(Some(mfg_batch)) => Ok(mfg_batch),
-            Ok(None) => Err(ApplyError::InvalidTransaction(format!(
-                "No mfg_batch exists: {}",
-                mfg_batch_id
-            ))),
-            Err(err) => Err(err),
-        }?;
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
         // Check signing agent's permission
-        check_permission(
-            perm_checker,
-            signer,
-            &permission_to_perm_string(Permission::CanDeleteMfgBatch),
-            mfg_batch.owner(),
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanDeleteMfgBatch),
+                mfg_batch.owner(),
+            ),
         )?;
 
-        // Check if mfg_batch mfg_batch_id is a valid gtin
-        if let Err(e) = validate_gtin(mfg_batch_id) {
-            return Err(ApplyError::InvalidTransaction(e.to_string()));
+        // Check if mfg_batch mfg_batch_id is a valid identifier
+        if let Err(e) = validate_namespaced_mfg_batch_id(mfg_batch_namespace, mfg_batch_id) {
+            return trace.reject("mfg_batch_id", e);
         }
+        trace.pass("mfg_batch_id");
 
-        // Delete the mfg_batch
-        state.remove_mfg_batch(mfg_batch_id)?;
-        Ok(())
-    }
-}
+        // Archiving keeps the mfg_batch, and its history, in state
+        if payload.archive() {
+            trace.step("not_archived", validate_not_archived(&mfg_batch))?;
 
-impl TransactionHandler for MfgBatchTransactionHandler {
-    fn family_name(&self) -> String {
-        self.family_name.clone()
-    }
+            let archived_mfg_batch = mfg_batch
+                .into_builder()
+                .with_archived(true)
+                .build()
+                .map_err(|err| {
+                    MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+                })?;
 
-    fn family_versions(&self) -> Vec<String> {
-        self.family_versions.clone()
-    }
+            state.set_mfg_batch(mfg_batch_id, archived_mfg_batch.clone())?;
+            state.add_mfg_batch_event(MfgBatchEvent::StatusChanged, &archived_mfg_batch)?;
+            return Ok(());
+        }
 
-    fn namespaces(&self) -> Vec<String> {
-        self.namespaces.clone()
+        // Delete the mfg_batch
+        state.remove_mfg_batch(mfg_batch_namespace, mfg_batch_id)?;
+        state.add_mfg_batch_event(MfgBatchEvent::Deleted, &mfg_batch)?;
+        Ok(())
     }
 
-    fn apply(
+    fn add_mfg_batch_parents(
         &self,
-        request: &TpProcessRequest,
-        context: &mut dyn TransactionContext,
+        payload: &MfgBatchAddParentsAction,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
     ) -> Result<(), ApplyError> {
-        let payload = MfgBatchPayload::from_bytes(request.get_payload()).map_err(|err| {
-            ApplyError::InvalidTransaction(format!("Cannot build manufacturig batch payload: {}", err))
-        })?;
+        let mfg_batch_id = payload.mfg_batch_id();
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
 
-        validate_payload(&payload)?;
+        // Check if mfg_batch exists in state
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
-        info!(
-            "Grid Manufactured Batch Payload {:?} {}",
-            payload.action(),
-            payload.timestamp(),
-        );
+        // Check signing agent's permission
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanUpdateMfgBatch),
+                mfg_batch.owner(),
+            ),
+        )?;
 
-        let signer = request.get_header().get_signer_public_key();
-        let mut state = MfgBatchState::new(context);
-        let perm_checker = PermissionChecker::new(context);
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
 
-        match payload.action() {
-            Action::MfgBatchCreate(create_mfg_batch_payload) => {
-                self.create_mfg_batch(create_mfg_batch_payload, &mut state, signer, &perm_checker)?
-            }
-            Action::MfgBatchUpdate(update_mfg_batch_payload) => {
-                self.update_mfg_batch(update_mfg_batch_payload, &mut state, signer, &perm_checker)?
+        // Only parents which are not already recorded are added
+        let mut parent_batches = mfg_batch.parent_batches().to_vec();
+        let mut new_parents = Vec::new();
+        for parent in payload.parent_batches() {
+            if parent_batches.contains(parent) || new_parents.contains(parent) {
+                continue;
             }
-            Action::MfgBatchDelete(delete_mfg_batch_payload) => {
-                self.delete_mfg_batch(delete_mfg_batch_payload, &mut state, signer, &perm_checker)?
+
+            // Check if the parent mfg_batch exists in state
+            let step = format!("parent {}", parent);
+            if state.get_mfg_batch(mfg_batch_namespace, parent)?.is_none() {
+                return trace.reject(
+                    &step,
+                    MfgBatchError::NotFound(format!("Parent mfg_batch does not exist: {}", parent)),
+                );
             }
+            trace.pass(&step);
+
+            new_parents.push(parent.to_string());
         }
-        Ok(())
-    }
-}
 
-fn check_permission(
-    perm_checker: &PermissionChecker,
-    signer: &str,
-    permission: &str,
-    record_owner: &str,
-) -> Result<(), ApplyError> {
-    match perm_checker.has_permission(signer, permission, record_owner) {
-        Ok(true) => Ok(()),
-        Ok(false) => Err(ApplyError::InvalidTransaction(format!(
-            "The signer \"{}\" does not have the \"{}\" permission for org \"{}\"",
-            signer, permission, record_owner
-        ))),
-        Err(e) => Err(ApplyError::InvalidTransaction(format!(
-            "Permission check failed: {}",
-            e
-        ))),
-    }
-}
+        // Check the new links would not make the batch its own ancestor
+        trace.step(
+            "genealogy",
+            validate_no_genealogy_cycle(mfg_batch_id, &new_parents, |ancestor| {
+                Ok(state
+                    .get_mfg_batch(mfg_batch_namespace, ancestor)?
+                    .map(|ancestor| ancestor.parent_batches().to_vec())
+                    .unwrap_or_default())
+            }),
+        )?;
 
-/*
+        parent_batches.extend(new_parents);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let updated_mfg_batch = mfg_batch
+            .into_builder()
+            .with_parent_batches(parent_batches)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+            })?;
 
-    use std::cell::RefCell;
-    use std::collections::HashMap;
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
 
-    use grid_sdk::{
-        pike::addressing::{
-            compute_agent_address, compute_organization_address, compute_role_address,
-        },
-        mfg_batch::addressing::compute_gs1_mfg_batch_address,
-        protocol::{
-            pike::state::{
-                AgentBuilder, AgentListBuilder, AlternateIdBuilder, OrganizationBuilder,
-                OrganizationListBuilder, RoleBuilder, RoleListBuilder,
-            },
-            mfg_batch::{
-                payload::{
-                    MfgBatchCreateAction, MfgBatchCreateActionBuilder, MfgBatchDeleteAction,
-                    MfgBatchDeleteActionBuilder, MfgBatchUpdateAction, MfgBatchUpdateActionBuilder,
-                },
-                state::{Product, MfgBatchBuilder, ProductListBuilder, MfgBatchNamespace},
-            },
-            schema::state::{
-                DataType, PropertyDefinitionBuilder, PropertyValue, PropertyValueBuilder,
-                SchemaBuilder, SchemaListBuilder,
-            },
-        },
-        protos::IntoBytes,
-        schema::addressing::compute_schema_address,
-    };
+        Ok(())
+    }
 
-    use sawtooth_sdk::processor::handler::{ContextError, TransactionContext};
+    fn add_mfg_batch_test_result(
+        &self,
+        payload: &MfgBatchAddTestResultAction,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        let mfg_batch_id = payload.mfg_batch_id();
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
 
-    const AGENT_ORG_ID: &str = "test_org";
-    const PUBLIC_KEY: &str = "test_public_key";
-    const ROLE_NAME: &str = "mfg_batch_roles";
-    const MFG_BATCH_ID: &str = "688955434684";
-    const PRODUCT_2_ID: &str = "9781981855728";
+        // Check if mfg_batch exists in state
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
-    #[derive(Default, Debug)]
-    /// A MockTransactionContext that can be used to test MfgBatchState
-    struct MockTransactionContext {
-        state: RefCell<HashMap<String, Vec<u8>>>,
-    }
+        // Check signing agent's permission
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanUpdateMfgBatch),
+                mfg_batch.owner(),
+            ),
+        )?;
 
-    impl TransactionContext for MockTransactionContext {
-        fn get_state_entries(
-            &self,
-            addresses: &[String],
-        ) -> Result<Vec<(String, Vec<u8>)>, ContextError> {
-            let mut results = Vec::new();
-            for addr in addresses {
-                let data = match self.state.borrow().get(addr) {
-                    Some(data) => data.clone(),
-                    None => Vec::new(),
-                };
-                results.push((addr.to_string(), data));
-            }
-            Ok(results)
-        }
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+        trace.step("test_result", validate_test_result(payload.test_result()))?;
 
-        fn set_state_entries(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), ContextError> {
-            for (addr, data) in entries {
-                self.state.borrow_mut().insert(addr, data);
-            }
-            Ok(())
-        }
+        // Results are only ever appended, so the batch keeps the full record of its testing
+        let mut test_results = mfg_batch.test_results().to_vec();
+        test_results.push(payload.test_result().clone());
 
-        /// this is not needed for these tests
-        fn delete_state_entries(&self, _addresses: &[String]) -> Result<Vec<String>, ContextError> {
-            unimplemented!()
-        }
+        let updated_mfg_batch = mfg_batch
+            .into_builder()
+            .with_test_results(test_results)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+            })?;
 
-        /// this is not needed for these tests
-        fn add_receipt_data(&self, _data: &[u8]) -> Result<(), ContextError> {
-            unimplemented!()
-        }
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
 
-        /// this is not needed for these tests
-        fn add_event(
-            &self,
-            _event_type: String,
-            _attributes: Vec<(String, String)>,
-            _data: &[u8],
-        ) -> Result<(), ContextError> {
-            unimplemented!()
-        }
+        Ok(())
     }
 
-    impl MockTransactionContext {
-        fn add_agent(&self, public_key: &str) {
-            let builder = AgentBuilder::new();
-            let agent = builder
-                .with_org_id(AGENT_ORG_ID.to_string())
-                .with_public_key(public_key.to_string())
-                .with_active(true)
-                .with_roles(vec![ROLE_NAME.to_string()])
-                .build()
-                .unwrap();
+    fn add_mfg_batch_attestation(
+        &self,
+        payload: &MfgBatchAddAttestationAction,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        let mfg_batch_id = payload.mfg_batch_id();
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
 
-            let builder = AgentListBuilder::new();
-            let agent_list = builder.with_agents(vec![agent.clone()]).build().unwrap();
-            let agent_bytes = agent_list.into_bytes().unwrap();
-            let agent_address = compute_agent_address(public_key);
-            self.set_state_entry(agent_address, agent_bytes).unwrap();
-        }
+        // Check if mfg_batch exists in state
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
-        fn add_agent_without_roles(&self, public_key: &str) {
-            let builder = AgentBuilder::new();
-            let agent = builder
-                .with_org_id(AGENT_ORG_ID.to_string())
-                .with_public_key(public_key.to_string())
-                .with_active(true)
-                .with_roles([].to_vec())
-                .build()
-                .unwrap();
+        // The attestation is signed by an outside party, but is recorded by the batch's owner
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanUpdateMfgBatch),
+                mfg_batch.owner(),
+            ),
+        )?;
 
-            let builder = AgentListBuilder::new();
-            let agent_list = builder.with_agents(vec![agent.clone()]).build().unwrap();
-            let agent_bytes = agent_list.into_bytes().unwrap();
-            let agent_address = compute_agent_address(public_key);
-            self.set_state_entry(agent_address, agent_bytes).unwrap();
-        }
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+        trace.step(
+            "attestation",
+            validate_attestation(&mfg_batch, payload.attestation()),
+        )?;
 
-        fn add_role(&self) {
-            let builder = RoleBuilder::new();
-            let role = builder
-                .with_org_id(AGENT_ORG_ID.to_string())
-                .with_name(ROLE_NAME.to_string())
-                .with_description("role description".to_string())
-                .with_permissions(vec![
-                    "mfg_batch::can-create-mfg_batch".to_string(),
-                    "mfg_batch::can-update-mfg_batch".to_string(),
-                    "mfg_batch::can-delete-mfg_batch".to_string(),
-                ])
-                .build()
-                .unwrap();
+        let mut attestations = mfg_batch.attestations().to_vec();
+        attestations.push(payload.attestation().clone());
 
-            let builder = RoleListBuilder::new();
-            let role_list = builder.with_roles(vec![role.clone()]).build().unwrap();
-            let role_bytes = role_list.into_bytes().unwrap();
-            let role_address = compute_role_address(ROLE_NAME, AGENT_ORG_ID);
-            self.set_state_entry(role_address, role_bytes).unwrap();
-        }
+        let updated_mfg_batch = mfg_batch
+            .into_builder()
+            .with_attestations(attestations)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+            })?;
 
-        fn add_org(&self, org_id: &str) {
-            // Products can only be created when there is a gs1 prefix
-            // within the mfg_batch organization's metadata
-            let alternate_id = AlternateIdBuilder::new()
-                .with_id_type("gs1_company_prefix".to_string())
-                .with_id("6889".to_string())
-                .build()
-                .unwrap();
-            let builder = OrganizationBuilder::new();
-            let org = builder
-                .with_org_id(org_id.to_string())
-                .with_name("test_org_name".to_string())
-                .with_locations(vec!["test".to_string()])
-                .with_alternate_ids(vec![alternate_id.clone()])
-                .build()
-                .unwrap();
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
 
-            let builder = OrganizationListBuilder::new();
-            let org_list = builder
-                .with_organizations(vec![org.clone()])
-                .build()
-                .unwrap();
-            let org_bytes = org_list.into_bytes().unwrap();
-            let org_address = compute_organization_address(org_id);
-            self.set_state_entry(org_address, org_bytes).unwrap();
-        }
+        Ok(())
+    }
 
-        fn add_org_without_gs1_prefix(&self, org_id: &str) {
-            let builder = OrganizationBuilder::new();
-            let org = builder
-                .with_org_id(org_id.to_string())
-                .with_name("test_org_name".to_string())
-                .with_locations(vec!["test".to_string()])
-                .build()
-                .unwrap();
+    fn allocate_mfg_batch(
+        &self,
+        payload: &MfgBatchAllocateAction,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        let mfg_batch_id = payload.mfg_batch_id();
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
 
-            let builder = OrganizationListBuilder::new();
-            let org_list = builder
-                .with_organizations(vec![org.clone()])
-                .build()
-                .unwrap();
-            let org_bytes = org_list.into_bytes().unwrap();
-            let org_address = compute_organization_address(org_id);
-            self.set_state_entry(org_address, org_bytes).unwrap();
-        }
+        // Check if mfg_batch exists in state
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
 
-        fn add_mfg_batch(&self, prod_id: &str) {
-            let mfg_batch_list = ProductListBuilder::new()
-                .with_mfg_batchs(vec![make_mfg_batch()])
-                .build()
-                .unwrap();
-            let mfg_batch_bytes = mfg_batch_list.into_bytes().unwrap();
-            let mfg_batch_address = compute_gs1_mfg_batch_address(prod_id);
-            self.set_state_entry(mfg_batch_address, mfg_batch_bytes)
-                .unwrap();
-        }
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanAllocateMfgBatch),
+                mfg_batch.owner(),
+            ),
+        )?;
 
-        fn add_mfg_batchs(&self, mfg_batch_ids: &[&str]) {
-            let mfg_batch_list = ProductListBuilder::new()
-                .with_mfg_batchs(make_mfg_batchs(mfg_batch_ids))
-                .build()
-                .unwrap();
-            let mfg_batch_list_bytes = mfg_batch_list.into_bytes().unwrap();
-            let mfg_batch_list_bytes_copy = mfg_batch_list_bytes.clone();
-            let mfg_batch_1_address = compute_gs1_mfg_batch_address(PRODUCT_ID);
-            let mfg_batch_2_address = compute_gs1_mfg_batch_address(PRODUCT_2_ID);
-            self.set_state_entries(vec![
-                (mfg_batch_1_address, mfg_batch_list_bytes),
-                (mfg_batch_2_address, mfg_batch_list_bytes_copy),
-            ])
-            .unwrap();
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+        trace.step(
+            "allocation",
+            validate_allocation(&mfg_batch, payload.order_id(), payload.quantity()),
+        )?;
+
+        // An order's allocations of a batch are kept together, in the order first allocated
+        let mut allocations = mfg_batch.allocations().to_vec();
+        match allocations
+            .iter_mut()
+            .find(|allocation| allocation.order_id() == payload.order_id())
+        {
+            Some(allocation) => {
+                let quantity = allocation.quantity() + payload.quantity();
+                *allocation = build_allocation(payload.order_id(), quantity)?;
+            }
+            None => allocations.push(build_allocation(payload.order_id(), payload.quantity())?),
         }
 
-        fn add_gs1_schema(&self) {
-            let properties = vec![
+        let updated_mfg_batch = mfg_batch
+            .into_builder()
+            .with_allocations(allocations)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+            })?;
+
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
+
+        Ok(())
+    }
+
+    fn release_mfg_batch(
+        &self,
+        payload: &MfgBatchReleaseAction,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        let mfg_batch_id = payload.mfg_batch_id();
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
+
+        // Check if mfg_batch exists in state
+        let mfg_batch = trace.step(
+            "exists",
+            match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                Ok(None) => Err(MfgBatchError::NotFound(format!(
+                    "No mfg_batch exists: {}",
+                    mfg_batch_id
+                ))
+                .into()),
+                Err(err) => Err(err),
+            },
+        )?;
+
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanAllocateMfgBatch),
+                mfg_batch.owner(),
+            ),
+        )?;
+
+        trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+        let allocated = trace.step(
+            "release",
+            validate_release(&mfg_batch, payload.order_id(), payload.quantity()),
+        )?;
+
+        // Releasing all that an order has reserved drops its allocation
+        let remaining = match payload.quantity() {
+            0 => 0,
+            quantity => allocated - quantity,
+        };
+        let allocations = mfg_batch
+            .allocations()
+            .iter()
+            .filter_map(|allocation| {
+                if allocation.order_id() != payload.order_id() {
+                    Some(Ok(allocation.clone()))
+                } else if remaining > 0 {
+                    Some(build_allocation(payload.order_id(), remaining))
+                } else {
+                    None
+                }
+            })
+            .collect::<Result<Vec<_>, MfgBatchError>>()?;
+
+        let updated_mfg_batch = mfg_batch
+            .into_builder()
+            .with_allocations(allocations)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+            })?;
+
+        state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+        state.add_mfg_batch_event(MfgBatchEvent::Updated, &updated_mfg_batch)?;
+
+        Ok(())
+    }
+
+    fn recall_mfg_batches(
+        &self,
+        payload: &MfgBatchRecallAction,
+        timestamp: u64,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        trace.step("recall", validate_recall(payload))?;
+
+        let mfg_batch_namespace = payload.mfg_batch_namespace();
+        let recall = RecallBuilder::new()
+            .with_recall_id(payload.recall_id().to_string())
+            .with_reason_code(payload.reason_code().to_string())
+            .with_timestamp(timestamp)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build recall: {}", err))
+            })?;
+
+        for mfg_batch_id in payload.mfg_batch_ids() {
+            // Check if mfg_batch exists in state
+            let mfg_batch = trace.step(
+                "exists",
+                match state.get_mfg_batch(mfg_batch_namespace, mfg_batch_id) {
+                    Ok(Some(mfg_batch)) => Ok(mfg_batch),
+                    Ok(None) => Err(MfgBatchError::NotFound(format!(
+                        "No mfg_batch exists: {}",
+                        mfg_batch_id
+                    ))
+                    .into()),
+                    Err(err) => Err(err),
+                },
+            )?;
+
+            trace.step(
+                "permission",
+                check_permission(
+                    perm_checker,
+                    signer,
+                    &permission_to_perm_string(Permission::CanRecallMfgBatch),
+                    mfg_batch.owner(),
+                ),
+            )?;
+
+            trace.step("not_archived", validate_not_archived(&mfg_batch))?;
+
+            // A recall is kept as first recorded, so a batch cannot be recalled twice
+            trace.step(
+                "not_recalled",
+                match mfg_batch.recall() {
+                    Some(existing) => Err(MfgBatchError::Conflict(format!(
+                        "Mfg_batch {} has already been recalled by recall {}",
+                        mfg_batch_id,
+                        existing.recall_id()
+                    ))),
+                    None => Ok(()),
+                },
+            )?;
+
+            let updated_mfg_batch = mfg_batch
+                .into_builder()
+                .with_recall(recall.clone())
+                .build()
+                .map_err(|err| {
+                    MfgBatchError::InvalidPayload(format!("Cannot build mfg_batch: {}", err))
+                })?;
+
+            state.set_mfg_batch(mfg_batch_id, updated_mfg_batch.clone())?;
+            state.add_mfg_batch_event(MfgBatchEvent::StatusChanged, &updated_mfg_batch)?;
+        }
+
+        Ok(())
+    }
+
+    fn anchor_mfg_batches(
+        &self,
+        payload: &MfgBatchAnchorAction,
+        timestamp: u64,
+        state: &mut MfgBatchState,
+        signer: &str,
+        perm_checker: &PermissionChecker,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        trace.step("anchor", validate_anchor(payload))?;
+
+        // Check signing agent's permission
+        trace.step(
+            "permission",
+            check_permission(
+                perm_checker,
+                signer,
+                &permission_to_perm_string(Permission::CanAnchorMfgBatches),
+                payload.owner(),
+            ),
+        )?;
+
+        // An anchor attests to the records at a commit, so it may not be replaced later
+        trace.step(
+            "not_anchored",
+            match state.get_mfg_batch_anchor(payload.owner(), payload.commit_num()) {
+                Ok(Some(_)) => Err(MfgBatchError::AlreadyExists(format!(
+                    "Organization {} has already anchored commit {}",
+                    payload.owner(),
+                    payload.commit_num()
+                ))
+                .into()),
+                Ok(None) => Ok(()),
+                Err(err) => Err(err),
+            },
+        )?;
+
+        let anchor = MfgBatchAnchorBuilder::new()
+            .with_owner(payload.owner().to_string())
+            .with_commit_num(payload.commit_num())
+            .with_merkle_root(payload.merkle_root().to_string())
+            .with_row_count(payload.row_count())
+            .with_timestamp(timestamp)
+            .build()
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!("Cannot build anchor: {}", err))
+            })?;
+
+        state.add_mfg_batch_anchor(anchor)?;
+
+        Ok(())
+    }
+
+    /// Decodes and applies a transaction, recording each validation step in the trace
+    fn execute(
+        &self,
+        request: &TpProcessRequest,
+        context: &dyn TransactionContext,
+        trace: &DecisionTrace,
+    ) -> Result<(), ApplyError> {
+        let payload = trace.step(
+            "decode",
+            if self.strict_payloads {
+                MfgBatchPayload::from_bytes_strict(request.get_payload())
+            } else {
+                MfgBatchPayload::from_bytes(request.get_payload())
+            }
+            .map_err(|err| {
+                MfgBatchError::InvalidPayload(format!(
+                    "Cannot build manufacturig batch payload: {}",
+                    err
+                ))
+            }),
+        )?;
+
+        trace.step("payload", validate_payload(&payload, &self.payload_limits))?;
+
+        log_info!(
+            format!(
+                "Grid Manufactured Batch Payload {:?}",
+                payload.action().masked(&self.log_mask)
+            ),
+            timestamp = payload.timestamp(),
+        );
+
+        let service_id = trace.step(
+            "service_id",
+            validate_service_id(payload.service_id(), self.service_id.as_deref()),
+        )?;
+
+        let signer = request.get_header().get_signer_public_key();
+        let mut state = MfgBatchState::new(context)
+            .with_addresser(self.addresser.clone())
+            .with_service_id(service_id);
+        let perm_checker = PermissionChecker::new(context);
+
+        match payload.action() {
+            Action::MfgBatchCreate(create_mfg_batch_payload) => self.create_mfg_batch(
+                create_mfg_batch_payload,
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+            Action::MfgBatchUpdate(update_mfg_batch_payload) => self.update_mfg_batch(
+                update_mfg_batch_payload,
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+            Action::MfgBatchDelete(delete_mfg_batch_payload) => self.delete_mfg_batch(
+                delete_mfg_batch_payload,
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+            Action::MfgBatchAddParents(add_parents_payload) => self.add_mfg_batch_parents(
+                add_parents_payload,
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+            Action::MfgBatchAddTestResult(add_test_result_payload) => self
+                .add_mfg_batch_test_result(
+                    add_test_result_payload,
+                    &mut state,
+                    signer,
+                    &perm_checker,
+                    trace,
+                )?,
+            Action::MfgBatchAnchor(anchor_payload) => self.anchor_mfg_batches(
+                anchor_payload,
+                *payload.timestamp(),
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+            Action::MfgBatchAddAttestation(add_attestation_payload) => self
+                .add_mfg_batch_attestation(
+                    add_attestation_payload,
+                    &mut state,
+                    signer,
+                    &perm_checker,
+                    trace,
+                )?,
+            Action::MfgBatchAllocate(allocate_payload) => {
+                self.allocate_mfg_batch(allocate_payload, &mut state, signer, &perm_checker, trace)?
+            }
+            Action::MfgBatchRelease(release_payload) => {
+                self.release_mfg_batch(release_payload, &mut state, signer, &perm_checker, trace)?
+            }
+            Action::MfgBatchRecall(recall_payload) => self.recall_mfg_batches(
+                recall_payload,
+                *payload.timestamp(),
+                &mut state,
+                signer,
+                &perm_checker,
+                trace,
+            )?,
+        }
+        Ok(())
+    }
+}
+
+impl TransactionHandler for MfgBatchTransactionHandler {
+    fn family_name(&self) -> String {
+        self.family_name.clone()
+    }
+
+    fn family_versions(&self) -> Vec<String> {
+        self.family_versions.clone()
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        vec![self.addresser.namespace().to_string()]
+    }
+
+    fn apply(
+        &self,
+        request: &TpProcessRequest,
+        context: &mut dyn TransactionContext,
+    ) -> Result<(), ApplyError> {
+        let trace = DecisionTrace::new(self.decision_traces);
+        let result = self.execute(request, context, &trace);
+        if !self.decision_traces {
+            return result;
+        }
+
+        match result {
+            #[cfg(not(target_arch = "wasm32"))]
+            Ok(()) => context.add_receipt_data(&trace.to_bytes()).map_err(|err| {
+                ApplyError::InternalError(format!("Unable to add decision trace: {}", err))
+            }),
+            Err(ApplyError::InvalidTransaction(msg)) => Err(ApplyError::InvalidTransaction(
+                format!("{} [trace: {}]", msg, trace.summary()),
+            )),
+            result => result,
+        }
+    }
+}
+
+fn build_allocation(order_id: &str, quantity: i64) -> Result<Allocation, MfgBatchError> {
+    AllocationBuilder::new()
+        .with_order_id(order_id.to_string())
+        .with_quantity(quantity)
+        .build()
+        .map_err(|err| MfgBatchError::InvalidPayload(format!("Cannot build allocation: {}", err)))
+}
+
+/// Checks that a manufacture location, if set, is a valid GLN of a location in the Grid Location
+/// namespace
+fn check_manufacture_location(state: &MfgBatchState, gln: &str) -> Result<(), ApplyError> {
+    validate_manufacture_location(gln)?;
+
+    if !gln.is_empty() && state.get_location(gln)?.is_none() {
+        return Err(MfgBatchError::NotFound(format!(
+            "Manufacture location does not exist: {}",
+            gln
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+fn check_permission(
+    perm_checker: &PermissionChecker,
+    signer: &str,
+    permission: &str,
+    record_owner: &str,
+) -> Result<(), MfgBatchError> {
+    match perm_checker.has_permission(signer, permission, record_owner) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(MfgBatchError::Permission(format!(
+            "The signer \"{}\" does not have the \"{}\" permission for org \"{}\"",
+            signer, permission, record_owner
+        ))),
+        Err(e) => Err(MfgBatchError::Permission(format!(
+            "Permission check failed: {}",
+            e
+        ))),
+    }
+}
+
+/// Checks the signer's permission for the record owner, falling back to a role of the owner that
+/// delegates the permission to the signer's organization. Returns the delegating role, if the
+/// permission was delegated.
+fn check_delegated_permission(
+    perm_checker: &PermissionChecker,
+    signer: &str,
+    permission: &str,
+    record_owner: &str,
+) -> Result<Option<String>, MfgBatchError> {
+    let err = match check_permission(perm_checker, signer, permission, record_owner) {
+        Ok(()) => return Ok(None),
+        Err(err) => err,
+    };
+
+    match find_delegating_role(perm_checker, signer, permission, record_owner) {
+        Ok(Some(role)) => Ok(Some(role)),
+        Ok(None) => Err(err),
+        Err(e) => Err(MfgBatchError::Permission(format!(
+            "Permission check failed: {}",
+            e
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context, PrivateKey};
+    use grid_sdk::{
+        protocol::{
+            mfg_batch::{
+                payload::{
+                    MfgBatchAddAttestationActionBuilder, MfgBatchAddTestResultActionBuilder,
+                    MfgBatchAllocateActionBuilder, MfgBatchAnchorActionBuilder,
+                    MfgBatchCreateActionBuilder, MfgBatchDeleteActionBuilder,
+                    MfgBatchPayloadBuilder, MfgBatchRecallActionBuilder,
+                    MfgBatchReleaseActionBuilder, MfgBatchUpdateActionBuilder,
+                },
+                state::{
+                    Attachment, AttachmentBuilder, AttestationBuilder, MfgBatch, TestResultBuilder,
+                },
+            },
+            pike::state::RoleBuilder,
+            schema::state::{
+                DataType, PropertyDefinitionBuilder, PropertyValue, PropertyValueBuilder,
+            },
+        },
+        protos::IntoBytes,
+        testing::{
+            agent, location, organization, property_definition, role, schema,
+            MockTransactionContext,
+        },
+    };
+    use sawtooth_sdk::messages::transaction::TransactionHeader;
+
+    const AGENT_ORG_ID: &str = "test_org";
+    const PUBLIC_KEY: &str = "test_public_key";
+    const ROLE_NAME: &str = "mfg_batch_roles";
+    const CO_MANUFACTURER_ORG_ID: &str = "co_org";
+    const CO_MANUFACTURER_PUBLIC_KEY: &str = "co_public_key";
+    const MFG_BATCH_ID: &str = "688955434684";
+    const GLN: &str = "0614141000005";
+
+    /// Returns a context with an agent allowed to create, update, delete, anchor, allocate and
+    /// recall the organization's mfg_batches, and the GS1 mfg_batch schema
+    fn make_context() -> MockTransactionContext {
+        let context = MockTransactionContext::new();
+        context.add_organization(organization(
+            AGENT_ORG_ID,
+            &[("gs1_company_prefix", "6889")],
+        ));
+        context.add_role(role(
+            AGENT_ORG_ID,
+            ROLE_NAME,
+            &[
+                "mfg_batch::can-create-mfg-batch",
+                "mfg_batch::can-update-mfg-batch",
+                "mfg_batch::can-delete-mfg-batch",
+                "mfg_batch::can-anchor-mfg-batches",
+                "mfg_batch::can-allocate-mfg-batch",
+                "mfg_batch::can-recall-mfg-batch",
+            ],
+        ));
+        context.add_agent(agent(AGENT_ORG_ID, PUBLIC_KEY, &[ROLE_NAME]));
+        context.add_schema(schema(
+            "gs1_mfg_batch",
+            AGENT_ORG_ID,
+            vec![
                 PropertyDefinitionBuilder::new()
                     .with_name("counter".into())
                     .with_data_type(DataType::Number)
@@ -658,438 +1401,1349 @@ mod tests {
                     .with_required(true)
                     .build()
                     .unwrap(),
+                property_definition("description", DataType::String, true),
+            ],
+        ));
+        context
+    }
+
+    fn create_mfg_batch(context: &MockTransactionContext) -> Result<(), ApplyError> {
+        let mut state = MfgBatchState::new(context);
+        let perm_checker = PermissionChecker::new(context);
+
+        MfgBatchTransactionHandler::new().create_mfg_batch(
+            &make_mfg_batch_create_action(),
+            &mut state,
+            PUBLIC_KEY,
+            &perm_checker,
+            &DecisionTrace::default(),
+        )
+    }
+
+    #[test]
+    /// Test that if MfgBatchCreateAction is valid an OK is returned and a new mfg_batch is
+    /// added to state
+    fn test_create_mfg_batch_handler_valid() {
+        let context = make_context();
+
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+
+        let state = MfgBatchState::new(&context);
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch, make_mfg_batch(make_properties()));
+    }
+
+    #[test]
+    /// Test that MfgBatchCreateAction is invalid if the signer's role lacks the permission
+    fn test_create_mfg_batch_without_permission() {
+        let context = make_context();
+        context.add_agent(agent(AGENT_ORG_ID, PUBLIC_KEY, &[]));
+
+        match create_mfg_batch(&context) {
+            Ok(()) => panic!("Agent has no roles, InvalidTransaction should be returned"),
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with("[permission] "));
+                assert!(err.contains("does not have the \"mfg_batch::can-create-mfg-batch\""));
+            }
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
+        }
+    }
+
+    /// Adds a co-manufacturer whose agent creates mfg_batches for its own organization, with a
+    /// role that also inherits the given roles, and returns the agent's public key
+    fn add_co_manufacturer(context: &MockTransactionContext, inherit_from: &[&str]) -> String {
+        context.add_organization(organization(CO_MANUFACTURER_ORG_ID, &[]));
+        context.add_role(
+            RoleBuilder::new()
+                .with_org_id(CO_MANUFACTURER_ORG_ID.to_string())
+                .with_name("co_manufacturing".to_string())
+                .with_description("co_manufacturing description".to_string())
+                .with_permissions(vec!["mfg_batch::can-create-mfg-batch".to_string()])
+                .with_inherit_from(inherit_from.iter().map(ToString::to_string).collect())
+                .build()
+                .expect("Failed to build role"),
+        );
+        context.add_agent(agent(
+            CO_MANUFACTURER_ORG_ID,
+            CO_MANUFACTURER_PUBLIC_KEY,
+            &["co_manufacturing"],
+        ));
+        CO_MANUFACTURER_PUBLIC_KEY.to_string()
+    }
+
+    /// Adds a role of the brand granting the create permission to the allowed organizations
+    fn add_delegate_role(context: &MockTransactionContext, allowed_organizations: &[&str]) {
+        context.add_role(
+            RoleBuilder::new()
+                .with_org_id(AGENT_ORG_ID.to_string())
+                .with_name("co_manufacturer".to_string())
+                .with_description("co_manufacturer description".to_string())
+                .with_permissions(vec!["mfg_batch::can-create-mfg-batch".to_string()])
+                .with_allowed_organizations(
+                    allowed_organizations
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                )
+                .build()
+                .expect("Failed to build role"),
+        );
+    }
+
+    fn create_mfg_batch_as(
+        context: &MockTransactionContext,
+        signer: &str,
+    ) -> Result<(), ApplyError> {
+        let mut state = MfgBatchState::new(context);
+        let perm_checker = PermissionChecker::new(context);
+
+        MfgBatchTransactionHandler::new().create_mfg_batch(
+            &make_mfg_batch_create_action(),
+            &mut state,
+            signer,
+            &perm_checker,
+            &DecisionTrace::default(),
+        )
+    }
+
+    #[test]
+    /// Test that a co-manufacturer's agent can create a mfg_batch owned by the brand through a
+    /// role it inherits from the brand, and that the delegating role is recorded in the event
+    fn test_create_mfg_batch_delegated_by_inherited_role() {
+        let context = make_context();
+        add_delegate_role(&context, &[CO_MANUFACTURER_ORG_ID]);
+        let signer = add_co_manufacturer(&context, &["test_org.co_manufacturer"]);
+
+        create_mfg_batch_as(&context, &signer).expect("Failed to create mfg_batch");
+
+        let state = MfgBatchState::new(&context);
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.owner(), AGENT_ORG_ID);
+        assert!(context.events()[0].attributes.contains(&(
+            "delegated_role".to_string(),
+            "test_org.co_manufacturer".to_string()
+        )));
+    }
+
+    #[test]
+    /// Test that a co-manufacturer's agent can create a mfg_batch owned by the brand through a
+    /// role of the brand assigned to it directly
+    fn test_create_mfg_batch_delegated_by_assigned_role() {
+        let context = make_context();
+        add_delegate_role(&context, &[CO_MANUFACTURER_ORG_ID]);
+        add_co_manufacturer(&context, &[]);
+        context.add_agent(agent(
+            CO_MANUFACTURER_ORG_ID,
+            CO_MANUFACTURER_PUBLIC_KEY,
+            &["test_org.co_manufacturer"],
+        ));
+
+        create_mfg_batch_as(&context, CO_MANUFACTURER_PUBLIC_KEY)
+            .expect("Failed to create mfg_batch");
+    }
+
+    #[test]
+    /// Test that a co-manufacturer's agent cannot create a mfg_batch owned by the brand through
+    /// a role the brand does not allow its organization to use
+    fn test_create_mfg_batch_not_delegated() {
+        let context = make_context();
+        add_delegate_role(&context, &["other_org"]);
+        let signer = add_co_manufacturer(&context, &["test_org.co_manufacturer"]);
+
+        match create_mfg_batch_as(&context, &signer) {
+            Ok(()) => panic!("Role is not delegated, InvalidTransaction should be returned"),
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with("[permission] "));
+                assert!(err.contains("for org \"test_org\""));
+            }
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
+        }
+        assert!(context.events().is_empty());
+    }
+
+    #[test]
+    /// Test that MfgBatchCreateAction is invalid if the organization has no GS1 company prefix
+    fn test_create_mfg_batch_org_without_gs1_prefix() {
+        let context = make_context();
+        context.add_organization(organization(AGENT_ORG_ID, &[]));
+
+        match create_mfg_batch(&context) {
+            Ok(()) => panic!("Organization has no prefix, InvalidTransaction should be returned"),
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with("[validation:owner] "));
+                assert!(err.contains("does not have the gs1_company_prefix prefix"));
+            }
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
+        }
+    }
+
+    #[test]
+    /// Test that a required schema property left out of the payload is populated from its
+    /// default, while a supplied value overrides the default
+    fn test_create_mfg_batch_schema_defaults() {
+        let context = make_context();
+        let default_counter = PropertyValueBuilder::new()
+            .with_name("counter".into())
+            .with_data_type(DataType::Number)
+            .with_number_value(1)
+            .build()
+            .unwrap();
+        context.add_schema(schema(
+            "gs1_mfg_batch",
+            AGENT_ORG_ID,
+            vec![
+                PropertyDefinitionBuilder::new()
+                    .with_name("counter".into())
+                    .with_data_type(DataType::Number)
+                    .with_number_exponent(1)
+                    .with_required(true)
+                    .with_default_value(default_counter.clone())
+                    .build()
+                    .unwrap(),
                 PropertyDefinitionBuilder::new()
                     .with_name("description".into())
                     .with_data_type(DataType::String)
                     .with_required(true)
+                    .with_default_value(
+                        PropertyValueBuilder::new()
+                            .with_name("description".into())
+                            .with_data_type(DataType::String)
+                            .with_string_value("No description".into())
+                            .build()
+                            .unwrap(),
+                    )
                     .build()
                     .unwrap(),
-            ];
+            ],
+        ));
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+
+        let description = make_properties().remove(0);
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_owner(AGENT_ORG_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(vec![description.clone()])
+            .build()
+            .expect("Failed to build MfgBatchCreateAction");
+        MfgBatchTransactionHandler::new()
+            .create_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to create mfg_batch");
 
-            let schema = SchemaBuilder::new()
-                .with_name("gs1_mfg_batch".into())
-                .with_description("GS1 mfg_batch".into())
-                .with_owner(AGENT_ORG_ID.to_string())
-                .with_properties(properties)
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.properties(), &[description, default_counter][..]);
+    }
+
+    #[test]
+    /// Test that MfgBatchCreateAction is invalid if the mfg_batch already exists
+    fn test_create_mfg_batch_already_exists() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+
+        match create_mfg_batch(&context) {
+            Ok(()) => panic!("Mfg_batch exists, InvalidTransaction should be returned"),
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert_eq!(
+                    err,
+                    format!("[already_exists] Product already exists: {}", MFG_BATCH_ID)
+                );
+            }
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
+        }
+    }
+
+    #[test]
+    /// Test that with decision traces enabled, the outcome of each step is added to the receipt
+    /// of an applied transaction and appended to the message of a rejected one
+    fn test_decision_traces() {
+        let mut context = make_context();
+        let handler = MfgBatchTransactionHandler::new().with_decision_traces(true);
+
+        let mut header = TransactionHeader::new();
+        header.set_signer_public_key(PUBLIC_KEY.to_string());
+        let mut request = TpProcessRequest::new();
+        request.set_header(header);
+        request.set_payload(
+            MfgBatchPayloadBuilder::new()
+                .with_action(Action::MfgBatchCreate(make_mfg_batch_create_action()))
+                .with_timestamp(1)
                 .build()
-                .unwrap();
+                .expect("Failed to build MfgBatchPayload")
+                .into_bytes()
+                .expect("Failed to serialize MfgBatchPayload"),
+        );
 
-            let schema_list = SchemaListBuilder::new()
-                .with_schemas(vec![schema])
+        handler
+            .apply(&request, &mut context)
+            .expect("Failed to apply transaction");
+        let receipt = String::from_utf8(context.receipts().remove(0)).expect("Invalid trace");
+        assert!(receipt
+            .starts_with("decode: ok\npayload: ok\nservice_id: ok\npermission: ok\nunique: ok\n"));
+        assert!(receipt.ends_with("\nrequired_properties: ok\nlanguages: ok"));
+
+        match handler.apply(&request, &mut context) {
+            Ok(()) => panic!("Mfg_batch exists, InvalidTransaction should be returned"),
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.ends_with(
+                    "[trace: decode: ok; payload: ok; service_id: ok; permission: ok; unique: rejected]"
+                ));
+            }
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
+        }
+    }
+
+    #[test]
+    /// Test that if MfgBatchUpdateAction is valid the mfg_batch's properties are replaced
+    fn test_update_mfg_batch_handler_valid() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        MfgBatchTransactionHandler::new()
+            .update_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch, make_mfg_batch(make_updated_properties()));
+    }
+
+    #[test]
+    /// Test that an update naming a version of the mfg_batch is only applied while that version
+    /// is current, so that a second update built against the same version is rejected
+    fn test_update_mfg_batch_expected_version() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let version = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found")
+            .version()
+            .expect("Failed to get version");
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .with_expected_version(version.clone())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_properties())
+            .with_expected_version(version.clone())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        match handler.update_mfg_batch(
+            &action,
+            &mut state,
+            PUBLIC_KEY,
+            &perm_checker,
+            &DecisionTrace::default(),
+        ) {
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with(&format!(
+                    "[conflict] Mfg_batch {} has changed since version {}",
+                    MFG_BATCH_ID, version
+                )));
+            }
+            res => panic!("Expected InvalidTransaction, got {:?}", res),
+        }
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch, make_mfg_batch(make_updated_properties()));
+    }
+
+    #[test]
+    /// Test that an update validating only changed properties keeps properties the schema no
+    /// longer defines and does not require properties added to the schema since, while changed
+    /// properties are still validated
+    fn test_update_mfg_batch_changed_properties_only() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        context.add_schema(schema(
+            "gs1_mfg_batch",
+            AGENT_ORG_ID,
+            vec![
+                PropertyDefinitionBuilder::new()
+                    .with_name("counter".into())
+                    .with_data_type(DataType::Number)
+                    .with_number_exponent(1)
+                    .with_required(true)
+                    .build()
+                    .unwrap(),
+                property_definition("grade", DataType::String, true),
+            ],
+        ));
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let mut properties = make_properties();
+        properties[1] = make_updated_properties().remove(1);
+        let update = |properties: Vec<PropertyValue>, changed_only: bool| {
+            MfgBatchUpdateActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_properties(properties)
+                .with_validate_changed_properties_only(changed_only)
                 .build()
-                .unwrap();
+                .expect("Failed to build MfgBatchUpdateAction")
+        };
 
-            self.set_state_entries(vec![(
-                compute_schema_address("gs1_mfg_batch"),
-                schema_list.into_bytes().unwrap(),
-            )])
-            .unwrap();
+        match handler.update_mfg_batch(
+            &update(properties.clone(), false),
+            &mut state,
+            PUBLIC_KEY,
+            &perm_checker,
+            &DecisionTrace::default(),
+        ) {
+            Err(ApplyError::InvalidTransaction(err)) => assert_eq!(
+                err,
+                "[validation:properties.description] description is not a property that is \
+                 defined by the gs1 schema"
+            ),
+            res => panic!("Expected InvalidTransaction, got {:?}", res),
+        }
+
+        handler
+            .update_mfg_batch(
+                &update(properties.clone(), true),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch, make_mfg_batch(properties));
+
+        match handler.update_mfg_batch(
+            &update(make_updated_properties(), true),
+            &mut state,
+            PUBLIC_KEY,
+            &perm_checker,
+            &DecisionTrace::default(),
+        ) {
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with("[validation:properties.description] "))
+            }
+            res => panic!("Expected InvalidTransaction, got {:?}", res),
         }
     }
 
     #[test]
-    /// Test that if ProductCreationAction is valid an OK is returned and a new Product is added to state
-    fn test_create_mfg_batch_handler_valid() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_role();
-        transaction_context.add_gs1_schema();
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_create_action = make_mfg_batch_create_action();
-
-        match transaction_handler.create_mfg_batch(
-            &mfg_batch_create_action,
+    /// Test that a manufacture location must be a GLN in the Location namespace, and that an
+    /// update without one keeps the batch's location
+    fn test_mfg_batch_manufacture_location() {
+        let context = make_context();
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_owner(AGENT_ORG_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_properties())
+            .with_manufacture_location(GLN.to_string())
+            .build()
+            .expect("Failed to build MfgBatchCreateAction");
+        match handler.create_mfg_batch(
+            &action,
+            &mut state,
+            PUBLIC_KEY,
+            &perm_checker,
+            &DecisionTrace::default(),
+        ) {
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert_eq!(
+                    err,
+                    format!("[not_found] Manufacture location does not exist: {}", GLN)
+                );
+            }
+            res => panic!("Expected InvalidTransaction, got {:?}", res),
+        }
+
+        context.add_location(location(GLN, AGENT_ORG_ID));
+        handler
+            .create_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to create mfg_batch");
+
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.manufacture_location(), GLN);
+
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .with_manufacture_location("0614141000006".to_string())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        assert!(handler
+            .update_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .is_err());
+    }
+
+    #[test]
+    /// Test that attachments must have a SHA-256 digest, and that an update without attachments
+    /// keeps the batch's attachments while one with attachments replaces them
+    fn test_mfg_batch_attachments() {
+        let context = make_context();
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let create = |attachments: Vec<Attachment>| {
+            MfgBatchCreateActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_owner(AGENT_ORG_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_properties(make_properties())
+                .with_attachments(attachments)
+                .build()
+                .expect("Failed to build MfgBatchCreateAction")
+        };
+        let update = |attachments: Vec<Attachment>| {
+            MfgBatchUpdateActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_properties(make_updated_properties())
+                .with_attachments(attachments)
+                .build()
+                .expect("Failed to build MfgBatchUpdateAction")
+        };
+
+        match handler.create_mfg_batch(
+            &create(vec![make_attachment("not-a-digest")]),
             &mut state,
             PUBLIC_KEY,
             &perm_checker,
+            &DecisionTrace::default(),
         ) {
-            Ok(()) => {}
-            Err(ApplyError::InternalError(err)) => {
-                assert_eq!("Failed to check permissions: InvalidPublicKey: The signer is not an Agent: test_public_key", err);
+            Err(ApplyError::InvalidTransaction(err)) => {
+                assert!(err.starts_with("[validation:attachments.digest] "))
             }
-            Err(err) => panic!("Should have gotten internal error but got {}", err),
+            res => panic!("Expected InvalidTransaction, got {:?}", res),
         }
 
+        let certificate = make_attachment(&"a1".repeat(32));
+        handler
+            .create_mfg_batch(
+                &create(vec![certificate.clone()]),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to create mfg_batch");
+
+        handler
+            .update_mfg_batch(
+                &update(vec![]),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
         let mfg_batch = state
-            .get_mfg_batch(PRODUCT_ID)
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
             .expect("Failed to fetch mfg_batch")
             .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attachments(), [certificate]);
 
-        assert_eq!(mfg_batch, make_mfg_batch());
+        let report = make_attachment(&"b2".repeat(32));
+        handler
+            .update_mfg_batch(
+                &update(vec![report.clone()]),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attachments(), [report]);
     }
 
     #[test]
-    /// Test that ProductCreationAction is invalid if the agent's org does not exist.
-    fn test_create_mfg_batch_org_does_not_exist() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_create_action = make_mfg_batch_create_action();
-
-        match transaction_handler.create_mfg_batch(
-            &mfg_batch_create_action,
+    /// Test that if MfgBatchDeleteAction is valid the mfg_batch is removed from state, and that
+    /// deleting it again is invalid
+    fn test_delete_mfg_batch_handler() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+
+        let action = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .build()
+            .expect("Failed to build MfgBatchDeleteAction");
+        let handler = MfgBatchTransactionHandler::new();
+        handler
+            .delete_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to delete mfg_batch");
+        assert!(state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .unwrap()
+            .is_none());
+
+        match handler.delete_mfg_batch(
+            &action,
             &mut state,
             PUBLIC_KEY,
             &perm_checker,
+            &DecisionTrace::default(),
         ) {
-            Ok(()) => panic!(
-                "Agent's organization should not exist, InvalidTransaction should be returned"
-            ),
+            Ok(()) => panic!("Mfg_batch should not exist, InvalidTransaction should be returned"),
             Err(ApplyError::InvalidTransaction(err)) => {
-                assert_eq!("The signer \"test_public_key\" does not have the \"mfg_batch::can-create-mfg_batch\" permission for org \"test_org\"", err);
+                assert_eq!(
+                    err,
+                    format!("[not_found] No mfg_batch exists: {}", MFG_BATCH_ID)
+                );
             }
-            Err(err) => panic!("Should have gotten invalid error but go {}", err),
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
         }
     }
 
     #[test]
-    /// Test that ProductCreationAction is invalid if the agent's org does not contain the gs1 prefix.
-    fn test_create_mfg_batch_org_without_gs1_prefix() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_role();
-        transaction_context.add_gs1_schema();
-        transaction_context.add_org_without_gs1_prefix(AGENT_ORG_ID);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_create_action = make_mfg_batch_create_action();
-
-        match transaction_handler.create_mfg_batch(
-            &mfg_batch_create_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker
-        ) {
-            Ok(()) => panic!("Agent's organization should not have a gs1 prefix key, InvalidTransaction should be returned"),
-            Err(ApplyError::InvalidTransaction(err)) => {
-                assert!(err.contains("The agents organization does not have the gs1_company_prefix prefix: []"));
-            }
-            Err(err) => panic!("Should have gotten invalid error but go {}", err),
+    /// Test that creating, updating, archiving and deleting a mfg_batch each add an event
+    /// carrying the mfg_batch's ID, owner and namespace, and the mfg_batch as it is after the
+    /// change, or as it was before it was deleted
+    fn test_mfg_batch_events() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let update = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &update,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        for archive in &[true, false] {
+            let delete = MfgBatchDeleteActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_archive(*archive)
+                .build()
+                .expect("Failed to build MfgBatchDeleteAction");
+            handler
+                .delete_mfg_batch(
+                    &delete,
+                    &mut state,
+                    PUBLIC_KEY,
+                    &perm_checker,
+                    &DecisionTrace::default(),
+                )
+                .expect("Failed to delete mfg_batch");
         }
+
+        let events = context.events();
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| event.event_type.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "grid/mfg_batch/created",
+                "grid/mfg_batch/updated",
+                "grid/mfg_batch/status_changed",
+                "grid/mfg_batch/deleted",
+            ]
+        );
+        assert_eq!(
+            events[0].attributes,
+            vec![
+                ("mfg_batch_id".to_string(), MFG_BATCH_ID.to_string()),
+                ("owner".to_string(), AGENT_ORG_ID.to_string()),
+                ("mfg_batch_namespace".to_string(), "GS1".to_string()),
+            ]
+        );
+        assert!(events[2]
+            .attributes
+            .contains(&("archived".to_string(), "true".to_string())));
+
+        let updated = MfgBatch::from_bytes(&events[1].data).expect("Failed to read event data");
+        assert_eq!(updated, make_mfg_batch(make_updated_properties()));
+        let deleted = MfgBatch::from_bytes(&events[3].data).expect("Failed to read event data");
+        assert!(deleted.archived());
     }
 
     #[test]
-    /// Test that ProductCreationAction is invalid if the a mfg_batch with the same id
-    /// already exists.
-    fn test_create_mfg_batch_already_exist() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_role();
-        transaction_context.add_mfg_batch(PRODUCT_ID);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_create_action = make_mfg_batch_create_action();
-
-        match transaction_handler.create_mfg_batch(
-            &mfg_batch_create_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker,
-        ) {
-            Ok(()) => panic!("Product should not exist, InvalidTransaction should be returned"),
+    /// Test that a handler scoped to a Splinter service rejects transactions submitted to another
+    /// service, and records its service in the events of the transactions it applies
+    fn test_service_id_scope() {
+        let mut context = make_context();
+        let handler =
+            MfgBatchTransactionHandler::new().with_service_id("01234-ABCDE::gr00".to_string());
+
+        let make_request = |service_id: &str| {
+            let mut header = TransactionHeader::new();
+            header.set_signer_public_key(PUBLIC_KEY.to_string());
+            let mut request = TpProcessRequest::new();
+            request.set_header(header);
+            request.set_payload(
+                MfgBatchPayloadBuilder::new()
+                    .with_action(Action::MfgBatchCreate(make_mfg_batch_create_action()))
+                    .with_timestamp(1)
+                    .with_service_id(service_id.to_string())
+                    .build()
+                    .expect("Failed to build MfgBatchPayload")
+                    .into_bytes()
+                    .expect("Failed to serialize MfgBatchPayload"),
+            );
+            request
+        };
+
+        match handler.apply(&make_request("01234-ABCDE::gr01"), &mut context) {
+            Ok(()) => panic!("Service is not served, InvalidTransaction should be returned"),
             Err(ApplyError::InvalidTransaction(err)) => {
-                assert!(err.contains(&format!("Product already exists: {}", PRODUCT_ID)));
+                assert!(err.starts_with("[validation:service_id]"))
             }
-            Err(err) => panic!("Should have gotten invalid error but go {}", err),
+            Err(err) => panic!("Should have gotten invalid error but got {}", err),
         }
+        assert!(context.events().is_empty());
+
+        handler
+            .apply(&make_request(""), &mut context)
+            .expect("Failed to apply transaction");
+        assert!(context.events()[0]
+            .attributes
+            .contains(&("service_id".to_string(), "01234-ABCDE::gr00".to_string())));
     }
 
     #[test]
-    /// Test that if MfgBatchUpdateAction is valid an OK is returned and a Product is updated in state
-    fn test_update_mfg_batch_handler_valid() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_role();
-        transaction_context.add_gs1_schema();
-        transaction_context.add_mfg_batch(PRODUCT_ID);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_update_action = make_mfg_batch_update_action();
-
-        match transaction_handler.update_mfg_batch(
-            &mfg_batch_update_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker,
-        ) {
-            Ok(()) => {}
-            Err(ApplyError::InternalError(err)) => {
-                assert_eq!("Failed to check permissions: InvalidPublicKey: The signer is not an Agent: test_public_key", err);
-            }
-            Err(err) => panic!("Should have gotten internal error but got {}", err),
-        }
+    /// Test that an internal batch can be created by an organization without a GS1 company
+    /// prefix, under a numeric ID that is not a GTIN, and is kept apart from GS1 batches
+    fn test_create_internal_mfg_batch() {
+        let context = make_context();
+        context.add_organization(organization(AGENT_ORG_ID, &[]));
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id("4711".to_string())
+            .with_owner(AGENT_ORG_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Internal)
+            .with_properties(vec![])
+            .build()
+            .expect("Failed to build MfgBatchCreateAction");
+        MfgBatchTransactionHandler::new()
+            .create_mfg_batch(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to create mfg_batch");
 
         let mfg_batch = state
-            .get_mfg_batch(PRODUCT_ID)
+            .get_mfg_batch(&MfgBatchNamespace::Internal, "4711")
             .expect("Failed to fetch mfg_batch")
             .expect("No mfg_batch found");
-
-        assert_eq!(mfg_batch, make_updated_mfg_batch());
+        assert_eq!(
+            mfg_batch.mfg_batch_namespace(),
+            &MfgBatchNamespace::Internal
+        );
+        assert!(state
+            .get_mfg_batch(&MfgBatchNamespace::Lot, "4711")
+            .unwrap()
+            .is_none());
     }
 
     #[test]
-    /// Test that MfgBatchUpdateAction is invalid if there is no mfg_batch to update
-    fn test_update_mfg_batch_that_does_not_exist() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_update_action = make_mfg_batch_update_action();
-
-        match transaction_handler.update_mfg_batch(
-            &mfg_batch_update_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker,
-        ) {
-            Ok(()) => panic!("Product should not exist, InvalidTransaction should be returned"),
-            Err(ApplyError::InvalidTransaction(err)) => {
-                assert!(err.contains(&format!("No mfg_batch exists: {}", PRODUCT_ID)));
-            }
-            Err(err) => panic!("Should have gotten invalid error but go {}", err),
-        }
+    /// Test that test results are appended to a mfg_batch, kept when it is updated and
+    /// rejected once it is archived
+    fn test_add_mfg_batch_test_result() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let test_result = TestResultBuilder::new()
+            .with_test_name("moisture".into())
+            .with_specification("<= 12.5 %".into())
+            .with_result("11.8 %".into())
+            .with_passed(true)
+            .with_lab("Acme Labs".into())
+            .with_timestamp(1_600_100_000)
+            .build()
+            .expect("Failed to build test result");
+        let action = MfgBatchAddTestResultActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_test_result(test_result.clone())
+            .build()
+            .expect("Failed to build MfgBatchAddTestResultAction");
+        handler
+            .add_mfg_batch_test_result(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to add test result");
+
+        let update = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &update,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.test_results(), &[test_result]);
+
+        let archive = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_archive(true)
+            .build()
+            .expect("Failed to build MfgBatchDeleteAction");
+        handler
+            .delete_mfg_batch(
+                &archive,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to archive mfg_batch");
+        assert!(handler
+            .add_mfg_batch_test_result(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default()
+            )
+            .is_err());
     }
 
     #[test]
-    /// Test that if MfgBatchDeleteAction is valid an OK is returned and a Product is deleted from state
-    fn test_delete_mfg_batch_handler_valid() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_role();
-        transaction_context.add_gs1_schema();
-        transaction_context.add_mfg_batchs(&vec![PRODUCT_ID, PRODUCT_2_ID]);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_delete_action = make_mfg_batch_delete_action(PRODUCT_ID);
-
-        assert!(transaction_handler
-            .delete_mfg_batch(
-                &mfg_batch_delete_action,
+    /// Test that an attestation signed over the stored mfg_batch is appended to it, that it is
+    /// kept when the batch is updated, and that one signed over an earlier version of the batch
+    /// is rejected
+    fn test_add_mfg_batch_attestation() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let signing_context = Secp256k1Context::new();
+        let lab_key = PrivateKey::new_from_hex(
+            "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088",
+        )
+        .expect("Failed to load private key");
+        let lab = signing_context.new_signer(lab_key);
+        let attest = |state: &MfgBatchState| {
+            let mfg_batch = state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+                .expect("Failed to fetch mfg_batch")
+                .expect("No mfg_batch found");
+            let attestation = AttestationBuilder::new()
+                .with_signer_public_key(
+                    lab.public_key().expect("Failed to get public key").as_hex(),
+                )
+                .with_algorithm("secp256k1".into())
+                .with_signature(
+                    lab.sign(&mfg_batch.canonical_bytes().unwrap())
+                        .expect("Failed to sign mfg_batch")
+                        .as_hex(),
+                )
+                .with_purpose("certificate_of_analysis".into())
+                .build()
+                .expect("Failed to build attestation");
+            MfgBatchAddAttestationActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_attestation(attestation)
+                .build()
+                .expect("Failed to build MfgBatchAddAttestationAction")
+        };
+
+        let action = attest(&state);
+        handler
+            .add_mfg_batch_attestation(
+                &action,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to add attestation");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attestations(), &[action.attestation().clone()]);
+
+        let stale = attest(&state);
+        let update = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_updated_properties())
+            .build()
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &update,
                 &mut state,
                 PUBLIC_KEY,
-                &perm_checker
+                &perm_checker,
+                &DecisionTrace::default(),
             )
-            .is_ok());
+            .expect("Failed to update mfg_batch");
 
-        let mfg_batch = state.get_mfg_batch(PRODUCT_ID).expect("No mfg_batch found");
+        // The attestation is kept when the batch is updated, though it no longer matches it
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attestations(), &[action.attestation().clone()]);
 
-        assert_eq!(mfg_batch, None);
+        assert!(handler
+            .add_mfg_batch_attestation(
+                &stale,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default()
+            )
+            .is_err());
     }
 
     #[test]
-    /// Test that if MfgBatchDeleteAction is valid an OK is returned and a
-    /// second mfg_batch is deleted from state
-    fn test_delete_second_mfg_batch_handler_valid() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_role();
-        transaction_context.add_gs1_schema();
-        transaction_context.add_mfg_batchs(&vec![PRODUCT_ID, PRODUCT_2_ID]);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_delete_action = make_mfg_batch_delete_action(PRODUCT_2_ID);
-
-        assert!(transaction_handler
-            .delete_mfg_batch(
-                &mfg_batch_delete_action,
+    /// Test that sales orders may reserve a batch's quantity until none is left, that releases
+    /// return it, and that the batch's quantity cannot be updated below what is reserved
+    fn test_allocate_and_release_mfg_batch() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let update = |quantity: i64| {
+            MfgBatchUpdateActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_properties(make_updated_properties())
+                .with_quantity(quantity)
+                .with_uom("KGM".into())
+                .build()
+                .expect("Failed to build MfgBatchUpdateAction")
+        };
+        handler
+            .update_mfg_batch(
+                &update(100),
                 &mut state,
                 PUBLIC_KEY,
                 &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let allocate = |state: &mut MfgBatchState, order_id: &str, quantity: i64| {
+            let action = MfgBatchAllocateActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_order_id(order_id.to_string())
+                .with_quantity(quantity)
+                .build()
+                .expect("Failed to build MfgBatchAllocateAction");
+            handler.allocate_mfg_batch(
+                &action,
+                state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
             )
-            .is_ok());
+        };
+        let release = |state: &mut MfgBatchState, order_id: &str, quantity: i64| {
+            let action = MfgBatchReleaseActionBuilder::new()
+                .with_mfg_batch_id(MFG_BATCH_ID.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_order_id(order_id.to_string())
+                .with_quantity(quantity)
+                .build()
+                .expect("Failed to build MfgBatchReleaseAction");
+            handler.release_mfg_batch(
+                &action,
+                state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+        };
+        let allocations = |state: &MfgBatchState| {
+            state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+                .expect("Failed to fetch mfg_batch")
+                .expect("No mfg_batch found")
+                .allocations()
+                .iter()
+                .map(|allocation| (allocation.order_id().to_string(), allocation.quantity()))
+                .collect::<Vec<_>>()
+        };
+
+        allocate(&mut state, "SO-1", 60).expect("Failed to allocate");
+        allocate(&mut state, "SO-1", 10).expect("Failed to allocate");
+        assert!(allocate(&mut state, "SO-2", 31).is_err());
+        allocate(&mut state, "SO-2", 30).expect("Failed to allocate");
+        assert_eq!(
+            allocations(&state),
+            vec![("SO-1".to_string(), 70), ("SO-2".to_string(), 30)]
+        );
 
-        let mfg_batch = state.get_mfg_batch(PRODUCT_2_ID).expect("No mfg_batch found");
+        release(&mut state, "SO-1", 20).expect("Failed to release");
+        release(&mut state, "SO-2", 0).expect("Failed to release");
+        assert!(release(&mut state, "SO-2", 1).is_err());
+        assert_eq!(allocations(&state), vec![("SO-1".to_string(), 50)]);
 
-        assert_eq!(mfg_batch, None);
+        assert!(handler
+            .update_mfg_batch(
+                &update(40),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default()
+            )
+            .is_err());
+        handler
+            .update_mfg_batch(
+                &update(50),
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+        assert_eq!(allocations(&state), vec![("SO-1".to_string(), 50)]);
     }
 
     #[test]
-    /// Test that MfgBatchDeleteAction is invalid if the agent does not have can_delete_mfg_batch role
-    fn test_delete_mfg_batch_agent_without_roles() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent_without_roles(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_mfg_batchs(&vec![PRODUCT_ID, PRODUCT_2_ID]);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_delete_action = make_mfg_batch_delete_action(PRODUCT_ID);
-
-        match transaction_handler.delete_mfg_batch(
-            &mfg_batch_delete_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker,
-        ) {
-            Ok(()) => panic!(
-                "Agent should not have can_delete_mfg_batch role, InvalidTransaction should be returned"
-            ),
-            Err(ApplyError::InvalidTransaction(err)) => {
-                assert_eq!(
-                    "The signer \"test_public_key\" does not have the \"mfg_batch::can-delete-mfg_batch\" permission for org \"test_org\"",
-                    err
-                );
-            }
-            Err(err) => panic!("Should have gotten invalid error but got {}", err),
-        }
+    /// Test that a recall flags each batch it lists with the recall and the transaction's
+    /// timestamp, that a batch is only recalled once, and that a recall listing a batch that
+    /// does not exist is rejected
+    fn test_recall_mfg_batches() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let recall = |state: &mut MfgBatchState, mfg_batch_ids: &[&str], recall_id: &str| {
+            let action = MfgBatchRecallActionBuilder::new()
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_mfg_batch_ids(mfg_batch_ids.iter().map(|id| id.to_string()).collect())
+                .with_recall_id(recall_id.to_string())
+                .with_reason_code("CONTAMINATION".into())
+                .build()
+                .expect("Failed to build MfgBatchRecallAction");
+            handler.recall_mfg_batches(
+                &action,
+                1_600_200_000,
+                state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+        };
+
+        assert!(recall(&mut state, &["unknown"], "RC-1").is_err());
+        recall(&mut state, &[MFG_BATCH_ID], "RC-1").expect("Failed to recall mfg_batch");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        let flagged = mfg_batch.recall().expect("Mfg_batch was not recalled");
+        assert_eq!(flagged.recall_id(), "RC-1");
+        assert_eq!(flagged.reason_code(), "CONTAMINATION");
+        assert_eq!(flagged.timestamp(), 1_600_200_000);
+
+        let event = context.events().pop().expect("No event added");
+        assert_eq!(event.event_type, "grid/mfg_batch/status_changed");
+        assert!(event
+            .attributes
+            .contains(&("recall_id".to_string(), "RC-1".to_string())));
+
+        assert!(recall(&mut state, &[MFG_BATCH_ID], "RC-2").is_err());
     }
 
     #[test]
-    /// Test that MfgBatchDeleteAction is invalid when deleting a non existant mfg_batch
-    fn test_delete_mfg_batch_not_exists() {
-        let transaction_context = MockTransactionContext::default();
-        transaction_context.add_agent(PUBLIC_KEY);
-        transaction_context.add_org(AGENT_ORG_ID);
-        transaction_context.add_mfg_batchs(&vec![PRODUCT_ID, PRODUCT_2_ID]);
-        let perm_checker = PermissionChecker::new(&transaction_context);
-        let mut state = MfgBatchState::new(&transaction_context);
-
-        let transaction_handler = MfgBatchTransactionHandler::new();
-        let mfg_batch_delete_action = make_mfg_batch_delete_action("13491387613");
-
-        match transaction_handler.delete_mfg_batch(
-            &mfg_batch_delete_action,
-            &mut state,
-            PUBLIC_KEY,
-            &perm_checker,
-        ) {
-            Ok(()) => panic!("Product should not exist, InvalidTransaction should be returned"),
-            Err(ApplyError::InvalidTransaction(err)) => {
-                assert!(err.contains("No mfg_batch exists: 13491387613"));
-            }
-            Err(err) => panic!("Should have gotten invalid error but go {}", err),
-        }
-    }
+    /// Test that a recalled mfg_batch may be updated, and that the update keeps its recall
+    fn test_update_recalled_mfg_batch() {
+        let context = make_context();
+        create_mfg_batch(&context).expect("Failed to create mfg_batch");
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let recall = MfgBatchRecallActionBuilder::new()
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_mfg_batch_ids(vec![MFG_BATCH_ID.to_string()])
+            .with_recall_id("RC-1".into())
+            .with_reason_code("CONTAMINATION".into())
+            .build()
+            .expect("Failed to build MfgBatchRecallAction");
+        handler
+            .recall_mfg_batches(
+                &recall,
+                1_600_200_000,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to recall mfg_batch");
 
-    fn make_mfg_batch() -> Product {
-        MfgBatchBuilder::new()
-            .with_mfg_batch_id(PRODUCT_ID.to_string())
-            .with_owner(AGENT_ORG_ID.to_string())
+        let update = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_properties(make_properties())
+            .with_properties(make_updated_properties())
             .build()
-            .expect("Failed to build new_mfg_batch")
+            .expect("Failed to build MfgBatchUpdateAction");
+        handler
+            .update_mfg_batch(
+                &update,
+                &mut state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+            .expect("Failed to update mfg_batch");
+
+        let mfg_batch = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .expect("Failed to fetch mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.properties(), make_updated_properties().as_slice());
+        let recalled = mfg_batch
+            .recall()
+            .expect("Recall was cleared by the update");
+        assert_eq!(recalled.recall_id(), "RC-1");
+        assert_eq!(recalled.timestamp(), 1_600_200_000);
     }
 
-    fn make_mfg_batchs(mfg_batch_ids: &[&str]) -> Vec<Product> {
-        vec![
-            MfgBatchBuilder::new()
-                .with_mfg_batch_id(mfg_batch_ids[0].to_string())
-                .with_owner(AGENT_ORG_ID.to_string())
-                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-                .with_properties(make_properties())
-                .build()
-                .expect("Failed to build new_mfg_batch"),
-            MfgBatchBuilder::new()
-                .with_mfg_batch_id(mfg_batch_ids[1].to_string())
-                .with_owner(AGENT_ORG_ID.to_string())
-                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-                .with_properties(make_properties())
+    #[test]
+    /// Test that an anchor is recorded once for the organization's commit, and not for an
+    /// organization the signer has no permission for
+    fn test_anchor_mfg_batches() {
+        let context = make_context();
+        let mut state = MfgBatchState::new(&context);
+        let perm_checker = PermissionChecker::new(&context);
+        let handler = MfgBatchTransactionHandler::new();
+
+        let anchor = |owner: &str| {
+            MfgBatchAnchorActionBuilder::new()
+                .with_owner(owner.to_string())
+                .with_commit_num(42)
+                .with_merkle_root("0f".repeat(32))
+                .with_row_count(3)
                 .build()
-                .expect("Failed to build new_mfg_batch"),
-        ]
+                .expect("Failed to build MfgBatchAnchorAction")
+        };
+        let apply = |state: &mut MfgBatchState, action: &MfgBatchAnchorAction| {
+            handler.anchor_mfg_batches(
+                action,
+                1_600_100_000,
+                state,
+                PUBLIC_KEY,
+                &perm_checker,
+                &DecisionTrace::default(),
+            )
+        };
+
+        apply(&mut state, &anchor(AGENT_ORG_ID)).expect("Failed to anchor mfg_batches");
+
+        let recorded = state
+            .get_mfg_batch_anchor(AGENT_ORG_ID, 42)
+            .expect("Failed to fetch anchor")
+            .expect("No anchor found");
+        assert_eq!(recorded.merkle_root(), "0f".repeat(32));
+        assert_eq!(recorded.row_count(), 3);
+        assert_eq!(recorded.timestamp(), 1_600_100_000);
+
+        assert!(apply(&mut state, &anchor(AGENT_ORG_ID)).is_err());
+        assert!(apply(&mut state, &anchor("other_org")).is_err());
     }
 
-    fn make_updated_mfg_batch() -> Product {
+    fn make_mfg_batch(properties: Vec<PropertyValue>) -> MfgBatch {
         MfgBatchBuilder::new()
-            .with_mfg_batch_id(PRODUCT_ID.to_string())
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
             .with_owner(AGENT_ORG_ID.to_string())
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_properties(make_updated_properties())
+            .with_properties(properties)
             .build()
             .expect("Failed to build new_mfg_batch")
     }
 
     fn make_properties() -> Vec<PropertyValue> {
-        let property_value_description = PropertyValueBuilder::new()
-            .with_name("description".into())
-            .with_data_type(DataType::String)
-            .with_string_value("This is a mfg_batch description".into())
-            .build()
-            .unwrap();
-        let property_value_counter = PropertyValueBuilder::new()
-            .with_name("counter".into())
-            .with_data_type(DataType::Number)
-            .with_number_value(3)
-            .build()
-            .unwrap();
-
-        vec![
-            property_value_description.clone(),
-            property_value_counter.clone(),
-        ]
+        make_property_values("This is a mfg_batch description", 3)
     }
 
     fn make_updated_properties() -> Vec<PropertyValue> {
-        let property_value_description = PropertyValueBuilder::new()
-            .with_name("description".into())
-            .with_data_type(DataType::String)
-            .with_string_value("This is a new mfg_batch description".into())
-            .build()
-            .unwrap();
-        let property_value_counter = PropertyValueBuilder::new()
-            .with_name("counter".into())
-            .with_data_type(DataType::Number)
-            .with_number_value(4)
-            .build()
-            .unwrap();
+        make_property_values("This is a new mfg_batch description", 4)
+    }
 
+    fn make_property_values(description: &str, counter: i64) -> Vec<PropertyValue> {
         vec![
-            property_value_description.clone(),
-            property_value_counter.clone(),
+            PropertyValueBuilder::new()
+                .with_name("description".into())
+                .with_data_type(DataType::String)
+                .with_string_value(description.into())
+                .build()
+                .unwrap(),
+            PropertyValueBuilder::new()
+                .with_name("counter".into())
+                .with_data_type(DataType::Number)
+                .with_number_value(counter)
+                .build()
+                .unwrap(),
         ]
     }
 
+    fn make_attachment(digest: &str) -> Attachment {
+        AttachmentBuilder::new()
+            .with_digest(digest.to_string())
+            .with_mime_type("application/pdf".to_string())
+            .with_size(48_213)
+            .with_uri("https://example.com/certificate.pdf".to_string())
+            .build()
+            .expect("Failed to build Attachment")
+    }
+
     fn make_mfg_batch_create_action() -> MfgBatchCreateAction {
         MfgBatchCreateActionBuilder::new()
-            .with_mfg_batch_id(PRODUCT_ID.to_string())
+            .with_mfg_batch_id(MFG_BATCH_ID.to_string())
             .with_owner(AGENT_ORG_ID.to_string())
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
             .with_properties(make_properties())
             .build()
             .expect("Failed to build MfgBatchCreateAction")
     }
-
-    fn make_mfg_batch_update_action() -> MfgBatchUpdateAction {
-        MfgBatchUpdateActionBuilder::new()
-            .with_mfg_batch_id(PRODUCT_ID.to_string())
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_properties(make_updated_properties())
-            .build()
-            .expect("Failed to build MfgBatchUpdateAction")
-    }
-
-    fn make_mfg_batch_delete_action(mfg_batch_id: &str) -> MfgBatchDeleteAction {
-        MfgBatchDeleteActionBuilder::new()
-            .with_mfg_batch_id(mfg_batch_id.to_string())
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .build()
-            .expect("Failed to build MfgBatchDeleteAction")
-    }
 }
-
-*/
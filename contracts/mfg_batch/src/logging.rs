@@ -0,0 +1,389 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logging that works the same in the native transaction processor and in the contract run by
+//! Sabre.
+//!
+//! Records are written with the `log_error!` through `log_trace!` macros, which take a message
+//! followed by any number of `key = value` fields:
+//!
+//! ```ignore
+//! log_debug!("Applied transaction", action = "update", mfg_batch_id = mfg_batch_id);
+//! ```
+//!
+//! which is written as `Applied transaction action=update mfg_batch_id=688955434684`. Natively,
+//! records go to the `log` crate and so to the console as configured by [`init`]. Under Sabre they
+//! go to the Sabre transaction processor's log, prefixed by their target.
+//!
+//! A record's target is the module that wrote it, without the crate name, for example `handler`
+//! or `validation`. A [`LogFilter`] sets the most verbose level written for each target. Natively
+//! the filter is given by the `log_filter` setting; a contract built for Sabre takes its filter
+//! from the `GRID_MFG_BATCH_LOG_FILTER` environment variable at build time. Sabre still drops
+//! records more verbose than the level its transaction processor runs at.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+cfg_if! {
+    if #[cfg(not(target_arch = "wasm32"))] {
+        use log::{LogLevel, LogLevelFilter};
+        use log4rs::append::console::ConsoleAppender;
+        use log4rs::config::{Appender, Config, Logger, Root};
+        use log4rs::encode::json::JsonEncoder;
+        use log4rs::encode::pattern::PatternEncoder;
+        use log4rs::encode::Encode;
+
+        use crate::config::LogFormat;
+    }
+}
+
+static FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// The importance of a record, from the least to the most verbose
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_log_level(self) -> LogLevel {
+        match self {
+            Level::Error => LogLevel::Error,
+            Level::Warn => LogLevel::Warn,
+            Level::Info => LogLevel::Info,
+            Level::Debug => LogLevel::Debug,
+            Level::Trace => LogLevel::Trace,
+        }
+    }
+
+    /// Returns the level a `log` crate filter allows, or `None` if it is off
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_log_level_filter(filter: LogLevelFilter) -> Option<Level> {
+        filter.to_log_level().map(|level| match level {
+            LogLevel::Error => Level::Error,
+            LogLevel::Warn => Level::Warn,
+            LogLevel::Info => Level::Info,
+            LogLevel::Debug => Level::Debug,
+            LogLevel::Trace => Level::Trace,
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn to_sabre_level(self) -> sabre_sdk::LogLevel {
+        match self {
+            Level::Error => sabre_sdk::LogLevel::Error,
+            Level::Warn => sabre_sdk::LogLevel::Warn,
+            Level::Info => sabre_sdk::LogLevel::Info,
+            Level::Debug => sabre_sdk::LogLevel::Debug,
+            Level::Trace => sabre_sdk::LogLevel::Trace,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parses a level name; `off` is parsed as `None`
+fn parse_level(name: &str) -> Result<Option<Level>, ()> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "off" => Ok(None),
+        "error" => Ok(Some(Level::Error)),
+        "warn" => Ok(Some(Level::Warn)),
+        "info" => Ok(Some(Level::Info)),
+        "debug" => Ok(Some(Level::Debug)),
+        "trace" => Ok(Some(Level::Trace)),
+        _ => Err(()),
+    }
+}
+
+/// The most verbose level written for each target
+///
+/// A filter is parsed from comma separated `target=level` directives, such as
+/// `handler=debug,validation=trace,payload=off`. A directive applies to its target and the
+/// modules within it; the longest matching target wins. Records of targets without a directive
+/// are written up to the filter's default level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    default: Option<Level>,
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl LogFilter {
+    /// Returns a filter without directives that writes records up to the given level, or none
+    /// if it is `None`
+    pub fn new(default: Option<Level>) -> Self {
+        LogFilter {
+            default,
+            targets: vec![],
+        }
+    }
+
+    pub fn with_default(mut self, default: Option<Level>) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// Returns whether a record of the given level and target is written
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        let allowed = self
+            .targets
+            .iter()
+            .filter(|(name, _)| {
+                target == name
+                    || (target.starts_with(name.as_str()) && target[name.len()..].starts_with("::"))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default);
+
+        allowed.map(|allowed| level <= allowed).unwrap_or(false)
+    }
+
+    /// Returns the most verbose level the filter writes for any target
+    pub fn max_level(&self) -> Option<Level> {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |max, level| max.max(level))
+    }
+}
+
+/// Writes every record, leaving the backend to filter them
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter::new(Some(Level::Trace))
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) if !target.trim().is_empty() => (target.trim(), level),
+                _ => return Err(format!("expected target=level, got {:?}", directive)),
+            };
+            let level =
+                parse_level(level).map_err(|_| format!("unknown log level in {:?}", directive))?;
+            filter.targets.retain(|(name, _)| name != target);
+            filter.targets.push((target.to_string(), level));
+        }
+        Ok(filter)
+    }
+}
+
+/// Returns the target of a module path: the path without the crate name
+pub fn target(module_path: &str) -> &str {
+    match module_path.split_once("::") {
+        Some((_, target)) => target,
+        None => module_path,
+    }
+}
+
+fn filter() -> &'static LogFilter {
+    FILTER.get_or_init(|| {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(directives) = option_env!("GRID_MFG_BATCH_LOG_FILTER") {
+            return directives.parse().unwrap_or_default();
+        }
+        LogFilter::default()
+    })
+}
+
+/// Returns whether a record of the given level, written by the given module, passes the filter
+pub fn enabled(level: Level, module_path: &str) -> bool {
+    filter().enabled(level, target(module_path))
+}
+
+/// Formats a message followed by its fields as `key=value` pairs; values that are empty or
+/// contain whitespace, `=`, or `"` are quoted
+pub fn format_record(message: &dyn fmt::Display, fields: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut record = message.to_string();
+    for (key, value) in fields {
+        let value = value.to_string();
+        if value.is_empty()
+            || value
+                .chars()
+                .any(|c| c.is_whitespace() || c == '=' || c == '"')
+        {
+            record.push_str(&format!(" {}={:?}", key, value));
+        } else {
+            record.push_str(&format!(" {}={}", key, value));
+        }
+    }
+    record
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(level: Level, module_path: &str, record: String) {
+    let level = level.to_sabre_level();
+    if sabre_sdk::log_enabled(level) {
+        sabre_sdk::log_message(level, format!("{}: {}", target(module_path), record));
+    }
+}
+
+/// Sets up console logging and the filter records are written through
+///
+/// The console writes the records of other crates up to `level`; this crate's records are
+/// written as `filter` allows. Only the first call has an effect, so a handler built into another
+/// processor does not replace the logging that processor set up.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init(level: LogLevelFilter, format: LogFormat, filter: LogFilter) -> Result<(), String> {
+    let crate_level = filter
+        .max_level()
+        .map(Level::to_log_level)
+        .map(|level| level.to_log_level_filter())
+        .unwrap_or(LogLevelFilter::Off);
+    if FILTER.set(filter).is_err() {
+        return Ok(());
+    }
+
+    // Format and log messages
+    let encoder: Box<dyn Encode> = match format {
+        LogFormat::Text => Box::new(PatternEncoder::new(
+            "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}",
+        )),
+        LogFormat::Json => Box::new(JsonEncoder::new()),
+    };
+    let stdout = ConsoleAppender::builder().encoder(encoder).build();
+
+    let config = Config::builder()
+        .appender(Appender::builder().build("stdout", Box::new(stdout)))
+        .logger(Logger::builder().build(
+            module_path!().split("::").next().unwrap_or_default(),
+            crate_level,
+        ))
+        .build(Root::builder().appender("stdout").build(level))
+        .map_err(|err| format!("invalid logging config: {}", err))?;
+
+    log4rs::init_config(config)
+        .map(|_| ())
+        .map_err(|err| format!("unable to set up logging: {}", err))
+}
+
+macro_rules! log_at {
+    ($level:expr, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+        let level = $level;
+        if $crate::logging::enabled(level, module_path!()) {
+            let record = $crate::logging::format_record(
+                &$message,
+                &[$((stringify!($key), &$value as &dyn ::std::fmt::Display)),*],
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            log!(level.to_log_level(), "{}", record);
+            #[cfg(target_arch = "wasm32")]
+            $crate::logging::write(level, module_path!(), record);
+        }
+    }};
+}
+
+// Not every level is written by both targets
+#[allow(unused_macros)]
+macro_rules! log_error {
+    ($($arg:tt)+) => (log_at!($crate::logging::Level::Error, $($arg)+))
+}
+
+#[allow(unused_macros)]
+macro_rules! log_warn {
+    ($($arg:tt)+) => (log_at!($crate::logging::Level::Warn, $($arg)+))
+}
+
+#[allow(unused_macros)]
+macro_rules! log_info {
+    ($($arg:tt)+) => (log_at!($crate::logging::Level::Info, $($arg)+))
+}
+
+#[allow(unused_macros)]
+macro_rules! log_debug {
+    ($($arg:tt)+) => (log_at!($crate::logging::Level::Debug, $($arg)+))
+}
+
+#[allow(unused_macros)]
+macro_rules! log_trace {
+    ($($arg:tt)+) => (log_at!($crate::logging::Level::Trace, $($arg)+))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies directives apply to their target and the modules within it, the longest matching
+    /// target wins, and other targets use the default level
+    #[test]
+    fn test_filter() {
+        let filter: LogFilter = "handler=debug,handler::create=off,validation=trace"
+            .parse()
+            .expect("Failed to parse filter");
+        let filter = filter.with_default(Some(Level::Warn));
+
+        assert!(filter.enabled(Level::Debug, "handler"));
+        assert!(!filter.enabled(Level::Trace, "handler"));
+        assert!(filter.enabled(Level::Debug, "handler::update"));
+        assert!(!filter.enabled(Level::Error, "handler::create"));
+        assert!(!filter.enabled(Level::Debug, "handlers"));
+        assert!(filter.enabled(Level::Trace, "validation"));
+        assert!(filter.enabled(Level::Warn, "payload"));
+        assert!(!filter.enabled(Level::Info, "payload"));
+        assert_eq!(filter.max_level(), Some(Level::Trace));
+
+        assert!(LogFilter::default().enabled(Level::Trace, "handler"));
+        assert_eq!(LogFilter::new(None).max_level(), None);
+
+        assert!("handler".parse::<LogFilter>().is_err());
+        assert!("handler=loud".parse::<LogFilter>().is_err());
+    }
+
+    /// Verifies fields follow the message as key=value pairs, quoting values that would be
+    /// ambiguous otherwise, and that targets drop the crate name
+    #[test]
+    fn test_format_record() {
+        assert_eq!(
+            format_record(
+                &"Applied transaction",
+                &[
+                    ("action", &"update"),
+                    ("quantity", &12),
+                    ("reason", &"out of spec"),
+                    ("owner", &""),
+                ],
+            ),
+            "Applied transaction action=update quantity=12 reason=\"out of spec\" owner=\"\""
+        );
+
+        assert_eq!(target("grid_mfg_batch_tp::handler"), "handler");
+        assert_eq!(
+            target("grid_mfg_batch_tp::handler::tests"),
+            "handler::tests"
+        );
+        assert_eq!(target("grid_mfg_batch_tp"), "grid_mfg_batch_tp");
+    }
+}
@@ -26,24 +26,37 @@ cfg_if! {
         extern crate clap;
         #[macro_use]
         extern crate log;
+        use std::path::PathBuf;
         use std::process;
-        use log::LogLevelFilter;
-        use log4rs::append::console::ConsoleAppender;
-        use log4rs::config::{Appender, Config, Root};
-        use log4rs::encode::pattern::PatternEncoder;
+        #[cfg(feature = "metrics")]
+        use std::sync::Arc;
         use sawtooth_sdk::processor::TransactionProcessor;
         // Load the MfgBatch transaction handler
         use crate::handler::MfgBatchTransactionHandler;
-    } else {
-        #[macro_use]
-        extern crate sabre_sdk;
+        use crate::config::{CliArgs, ProcessorConfig};
+        use crate::logging::Level;
+        use grid_sdk::log_masking::LogMask;
+        #[cfg(feature = "metrics")]
+        use crate::metrics::{MeteredHandler, Metrics};
     }
 }
 
+#[macro_use]
+mod logging;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod config;
+mod error;
+mod events;
 pub mod handler;
+#[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+mod package;
 mod payload;
 pub mod permissions;
 mod state;
+mod trace;
 mod validation;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -51,50 +64,159 @@ fn main() {
     let matches = clap_app!(intkey =>
         (version: crate_version!())
         (about: "Grid Manufactured Batch Processor (Rust)")
+        (@arg config: -c --config +takes_value
+         "TOML file to read settings from; defaults to /etc/grid/mfg-batch-tp.toml")
         (@arg connect: -C --connect +takes_value
          "connection endpoint for validator")
+        (@arg log_level: --("log-level") +takes_value
+         "log level: off, error, warn, info, debug or trace")
+        (@arg log_format: --("log-format") +takes_value
+         "log format: text or json")
+        (@arg log_filter: --("log-filter") +takes_value
+         "comma separated target=level directives, such as handler=debug")
+        (@arg metrics: --metrics "serve transaction counts for Prometheus")
+        (@arg metrics_bind: --("metrics-bind") +takes_value
+         "address to serve metrics on")
+        (@arg strict_payloads: --("strict-payloads")
+         "reject payloads containing fields the contract does not know about")
+        (@arg decision_traces: --("decision-traces")
+         "record the outcome of each validation step in transaction receipts")
+        (@arg masked_properties: --("masked-properties") +takes_value
+         "comma separated properties whose values are masked in logs")
+        (@arg max_properties: --("max-properties") +takes_value
+         "most property values a payload may carry, struct members included")
+        (@arg max_struct_depth: --("max-struct-depth") +takes_value
+         "deepest struct values in a payload may nest")
+        (@arg max_bytes_value_size: --("max-bytes-value-size") +takes_value
+         "largest a bytes property value may be, in bytes")
+        (@arg family_name: --("family-name") +takes_value
+         "transaction family to handle; defaults to grid_mfg_batch")
+        (@arg namespace: --namespace +takes_value
+         "six hex character namespace to store state under; derived from the family name by default")
+        (@arg service_id: --("service-id") +takes_value
+         "Splinter service to apply transactions for, as <circuit_id>::<service_id>")
         (@arg verbose: -v --verbose +multiple
-         "increase output verbosity"))
+         "increase output verbosity")
+        (@subcommand package =>
+            (about: "Package the WASM contract for deployment with Sabre")
+            (@arg wasm: --wasm +takes_value +required "path to the built contract")
+            (@arg out_dir: -o --("out-dir") +takes_value "directory to write the package to")
+            (@arg contract_version: --("contract-version") +takes_value
+             "contract version to register; defaults to the crate version")
+            (@arg owner: --owner +takes_value +multiple +required
+             "public key of a contract and namespace registry owner")
+            (@arg replaces: --replaces +takes_value
+             "previously deployed contract version to delete")))
     .get_matches();
 
-    let endpoint = matches
-        .value_of("connect")
-        // Attach WASM to Sabre validator 
-        .unwrap_or("tcp://localhost:4004");
+    if let Some(matches) = matches.subcommand_matches("package") {
+        let options = package::PackageOptions {
+            wasm: PathBuf::from(matches.value_of("wasm").unwrap_or_default()),
+            out_dir: PathBuf::from(matches.value_of("out_dir").unwrap_or(".")),
+            version: matches
+                .value_of("contract_version")
+                .unwrap_or(crate_version!())
+                .to_string(),
+            owners: matches
+                .values_of("owner")
+                .map(|owners| owners.map(String::from).collect())
+                .unwrap_or_default(),
+            replaces: matches.value_of("replaces").map(String::from),
+        };
 
-    let console_log_level;
-    match matches.occurrences_of("verbose") {
-        0 => console_log_level = LogLevelFilter::Warn,
-        1 => console_log_level = LogLevelFilter::Info,
-        2 => console_log_level = LogLevelFilter::Debug,
-        _ => console_log_level = LogLevelFilter::Trace,
+        match package::package(&options) {
+            Ok(written) => {
+                for path in written {
+                    println!("{}", path.display());
+                }
+                return;
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
+        }
     }
 
-    // Format and log messages
-    let stdout = ConsoleAppender::builder()
-        // Create a heap reference object
-        .encoder(Box::new(PatternEncoder::new(
-            "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}",
-        )))
-        .build();
-
-    let config = match Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .build(Root::builder().appender("stdout").build(console_log_level))
-    {
-        Ok(x) => x,
-        Err(_) => process::exit(1),
+    let args = CliArgs {
+        config_file: matches.value_of("config").map(PathBuf::from),
+        connect: matches.value_of("connect").map(String::from),
+        log_level: matches.value_of("log_level").map(String::from),
+        verbose: matches.occurrences_of("verbose"),
+        log_format: matches.value_of("log_format").map(String::from),
+        log_filter: matches.value_of("log_filter").map(String::from),
+        metrics: matches.is_present("metrics"),
+        metrics_bind: matches.value_of("metrics_bind").map(String::from),
+        strict_payloads: matches.is_present("strict_payloads"),
+        decision_traces: matches.is_present("decision_traces"),
+        masked_properties: matches.value_of("masked_properties").map(String::from),
+        max_properties: matches.value_of("max_properties").map(String::from),
+        max_struct_depth: matches.value_of("max_struct_depth").map(String::from),
+        max_bytes_value_size: matches.value_of("max_bytes_value_size").map(String::from),
+        family_name: matches.value_of("family_name").map(String::from),
+        namespace: matches.value_of("namespace").map(String::from),
+        service_id: matches.value_of("service_id").map(String::from),
     };
+    let processor_config = match ProcessorConfig::load(args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    };
+    let console_log_level = processor_config.log_level;
 
-    match log4rs::init_config(config) {
-        Ok(_) => (),
-        Err(_) => process::exit(1),
+    let log_filter = processor_config
+        .log_filter
+        .clone()
+        .with_default(Level::from_log_level_filter(console_log_level));
+    if let Err(err) = logging::init(console_log_level, processor_config.log_format, log_filter) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
     }
+    let addresser = match processor_config.addresser() {
+        Ok(addresser) => addresser,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    };
     // Assign the batch handler to the Sabre validator
-    let handler = MfgBatchTransactionHandler::new();
-    let mut processor = TransactionProcessor::new(endpoint);
+    #[cfg(feature = "metrics")]
+    let metrics = Arc::new(Metrics::default());
+    let handler = MfgBatchTransactionHandler::new()
+        .with_family_name(processor_config.family_name.clone())
+        .with_addresser(addresser)
+        .with_strict_payloads(processor_config.strict_payloads)
+        .with_decision_traces(processor_config.decision_traces)
+        .with_log_mask(LogMask::new(&processor_config.masked_properties))
+        .with_payload_limits(processor_config.payload_limits);
+    let handler = match processor_config.service_id.clone() {
+        Some(service_id) => handler.with_service_id(service_id),
+        None => handler,
+    };
+    #[cfg(feature = "metrics")]
+    let handler = MeteredHandler::new(handler, metrics.clone());
+    let mut processor = TransactionProcessor::new(&processor_config.endpoint);
 
-    info!("Console logging level: {}", console_log_level);
+    log_info!("Console logging configured", level = console_log_level);
+
+    #[cfg(feature = "metrics")]
+    if processor_config.metrics_enabled {
+        if let Err(err) = metrics::serve(metrics, &processor_config.metrics_bind) {
+            log_error!(
+                "Unable to serve metrics",
+                bind = processor_config.metrics_bind,
+                error = err
+            );
+            process::exit(1);
+        }
+        log_info!("Serving metrics", bind = processor_config.metrics_bind);
+    }
+    #[cfg(not(feature = "metrics"))]
+    if processor_config.metrics_enabled {
+        log_warn!("Metrics are enabled, but the processor was built without the metrics feature");
+    }
 
     processor.add_handler(&handler);
     processor.start();
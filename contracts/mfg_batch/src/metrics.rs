@@ -0,0 +1,280 @@
+// Copyright 2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counts and latencies of the transactions the processor has handled, served in the Prometheus
+//! text format.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use grid_sdk::protocol::mfg_batch::payload::{Action, MfgBatchPayload};
+use grid_sdk::protos::FromBytes;
+use sawtooth_sdk::messages::processor::TpProcessRequest;
+use sawtooth_sdk::processor::handler::{ApplyError, TransactionContext, TransactionHandler};
+
+/// Upper bounds of the apply latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5];
+
+#[derive(Default)]
+pub struct Metrics {
+    applied: AtomicU64,
+    invalid: AtomicU64,
+    internal_error: AtomicU64,
+    /// Transactions by action and result
+    actions: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+    /// Transactions whose apply latency fell in each bucket; the last counts the rest
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+    fn record(&self, action: &'static str, elapsed: Duration, result: &Result<(), ApplyError>) {
+        let (counter, label) = match result {
+            Ok(()) => (&self.applied, "applied"),
+            Err(ApplyError::InvalidTransaction(_)) => (&self.invalid, "invalid"),
+            Err(ApplyError::InternalError(_)) => (&self.internal_error, "internal_error"),
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        if let Ok(mut actions) = self.actions.lock() {
+            *actions.entry((action, label)).or_insert(0) += 1;
+        }
+
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Renders the counts in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::from(
+            "# HELP grid_mfg_batch_tp_transactions_total Transactions handled, by result\n\
+             # TYPE grid_mfg_batch_tp_transactions_total counter\n",
+        );
+        for (result, counter) in &[
+            ("applied", &self.applied),
+            ("invalid", &self.invalid),
+            ("internal_error", &self.internal_error),
+        ] {
+            out.push_str(&format!(
+                "grid_mfg_batch_tp_transactions_total{{result=\"{}\"}} {}\n",
+                result,
+                counter.load(Ordering::Relaxed)
+            ));
+        }
+
+        out.push_str(
+            "# HELP grid_mfg_batch_tp_actions_total Transactions handled, by action and result\n\
+             # TYPE grid_mfg_batch_tp_actions_total counter\n",
+        );
+        if let Ok(actions) = self.actions.lock() {
+            for ((action, result), count) in actions.iter() {
+                out.push_str(&format!(
+                    "grid_mfg_batch_tp_actions_total{{action=\"{}\",result=\"{}\"}} {}\n",
+                    action, result, count
+                ));
+            }
+        }
+
+        out.push_str(
+            "# HELP grid_mfg_batch_tp_apply_seconds Time taken to apply a transaction\n\
+             # TYPE grid_mfg_batch_tp_apply_seconds histogram\n",
+        );
+        let mut cumulative = 0;
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            out.push_str(&format!(
+                "grid_mfg_batch_tp_apply_seconds_bucket{{le=\"{}\"}} {}\n",
+                bound, cumulative
+            ));
+        }
+        cumulative += self.latency_buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+        out.push_str(&format!(
+            "grid_mfg_batch_tp_apply_seconds_bucket{{le=\"+Inf\"}} {}\n\
+             grid_mfg_batch_tp_apply_seconds_sum {}\n\
+             grid_mfg_batch_tp_apply_seconds_count {}\n",
+            cumulative,
+            self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            cumulative
+        ));
+        out
+    }
+}
+
+/// Serves the metrics to every request on `bind`, from a background thread
+pub fn serve(metrics: Arc<Metrics>, bind: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(bind)?;
+    thread::Builder::new()
+        .name("metrics".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log_warn!("Failed to accept metrics connection", error = err);
+                        continue;
+                    }
+                };
+                // Every path gets the metrics, so the request itself is not needed
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                let body = metrics.render();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: text/plain; version=0.0.4\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                if let Err(err) = stream.write_all(response.as_bytes()) {
+                    log_debug!("Failed to write metrics response", error = err);
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// The label a transaction's action is counted under
+fn action_label(payload: &[u8]) -> &'static str {
+    let payload = match MfgBatchPayload::from_bytes(payload) {
+        Ok(payload) => payload,
+        Err(_) => return "unknown",
+    };
+
+    match payload.action() {
+        Action::MfgBatchCreate(_) => "create",
+        Action::MfgBatchUpdate(_) => "update",
+        Action::MfgBatchDelete(_) => "delete",
+        Action::MfgBatchAddParents(_) => "add_parents",
+        Action::MfgBatchAddTestResult(_) => "add_test_result",
+        Action::MfgBatchAnchor(_) => "anchor",
+        Action::MfgBatchAddAttestation(_) => "add_attestation",
+        Action::MfgBatchAllocate(_) => "allocate",
+        Action::MfgBatchRelease(_) => "release",
+        Action::MfgBatchRecall(_) => "recall",
+    }
+}
+
+/// Wraps a handler, counting the action, result and latency of every transaction it applies
+pub struct MeteredHandler<H> {
+    inner: H,
+    metrics: Arc<Metrics>,
+}
+
+impl<H> MeteredHandler<H> {
+    pub fn new(inner: H, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl<H: TransactionHandler> TransactionHandler for MeteredHandler<H> {
+    fn family_name(&self) -> String {
+        self.inner.family_name()
+    }
+
+    fn family_versions(&self) -> Vec<String> {
+        self.inner.family_versions()
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        self.inner.namespaces()
+    }
+
+    fn apply(
+        &self,
+        request: &TpProcessRequest,
+        context: &mut dyn TransactionContext,
+    ) -> Result<(), ApplyError> {
+        let action = action_label(request.get_payload());
+        let start = Instant::now();
+        let result = self.inner.apply(request, context);
+        self.metrics.record(action, start.elapsed(), &result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid_sdk::protocol::mfg_batch::{
+        payload::{MfgBatchDeleteActionBuilder, MfgBatchPayloadBuilder},
+        state::MfgBatchNamespace,
+    };
+    use grid_sdk::protos::IntoBytes;
+
+    /// Verifies each result is counted under its own label
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record("create", Duration::from_micros(500), &Ok(()));
+        metrics.record("update", Duration::from_millis(20), &Ok(()));
+        metrics.record(
+            "create",
+            Duration::from_secs(1),
+            &Err(ApplyError::InvalidTransaction("bad".into())),
+        );
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("grid_mfg_batch_tp_transactions_total{result=\"applied\"} 2\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_transactions_total{result=\"invalid\"} 1\n"));
+        assert!(rendered
+            .contains("grid_mfg_batch_tp_transactions_total{result=\"internal_error\"} 0\n"));
+
+        assert!(rendered
+            .contains("grid_mfg_batch_tp_actions_total{action=\"create\",result=\"applied\"} 1\n"));
+        assert!(rendered
+            .contains("grid_mfg_batch_tp_actions_total{action=\"create\",result=\"invalid\"} 1\n"));
+        assert!(rendered
+            .contains("grid_mfg_batch_tp_actions_total{action=\"update\",result=\"applied\"} 1\n"));
+
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_bucket{le=\"0.025\"} 2\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_bucket{le=\"0.5\"} 2\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_sum 1.0205\n"));
+        assert!(rendered.contains("grid_mfg_batch_tp_apply_seconds_count 3\n"));
+    }
+
+    /// Verifies transactions are labelled by their action, or as unknown if they cannot be read
+    #[test]
+    fn test_action_label() {
+        assert_eq!(action_label(b"not a payload"), "unknown");
+
+        let delete = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_mfg_batch_id("688955434684".into())
+            .build()
+            .unwrap();
+        let payload = MfgBatchPayloadBuilder::new()
+            .with_action(Action::MfgBatchDelete(delete))
+            .with_timestamp(0)
+            .build()
+            .unwrap()
+            .into_bytes()
+            .unwrap();
+        assert_eq!(action_label(&payload), "delete");
+    }
+}
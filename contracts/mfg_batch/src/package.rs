@@ -0,0 +1,378 @@
+// Copyright 2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Packages the mfg_batch WASM contract for deployment with Sabre.
+//!
+//! From a built `.wasm` this writes the `grid-mfg-batch.yaml` contract definition, the `.scar`
+//! archive, and the Sabre payloads that register the contract and its namespace and upload the
+//! new version. The payloads are not signed; they are submitted, in file name order, by an
+//! operator holding one of the registry owner keys.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use bzip2::{write::BzEncoder, Compression};
+use sabre_sdk::protocol::payload::{
+    Action, CreateContractActionBuilder, CreateContractRegistryActionBuilder,
+    CreateNamespaceRegistryActionBuilder, CreateNamespaceRegistryPermissionActionBuilder,
+    DeleteContractActionBuilder, SabrePayloadBuilder,
+};
+use sabre_sdk::protos::IntoBytes;
+use serde::Serialize;
+
+pub const CONTRACT_NAME: &str = "grid_mfg_batch";
+pub const DEFINITION_FILE_NAME: &str = "grid-mfg-batch.yaml";
+
+/// The mfg_batch namespace prefix, the first 6 characters of the SHA-512 of the contract name
+const MFG_BATCH_NAMESPACE: &str = "11bb0e";
+/// Sabre registries and permissions are kept per 6 character namespace
+const NAMESPACE_LENGTH: usize = 6;
+
+const CONTRACT_INPUTS: &[&str] = &["621dee01", "11bb0e01", "621dee05", "11bb0e02", "621dee04"];
+const CONTRACT_OUTPUTS: &[&str] = &["11bb0e01", "11bb0e02"];
+
+/// The `manifest.yaml` stored in a `.scar` archive
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ScarManifest {
+    pub name: String,
+    pub version: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+impl ScarManifest {
+    pub fn new(version: &str) -> Self {
+        Self {
+            name: CONTRACT_NAME.to_string(),
+            version: version.to_string(),
+            inputs: CONTRACT_INPUTS.iter().map(|s| s.to_string()).collect(),
+            outputs: CONTRACT_OUTPUTS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// The contract definition read by `sabre upload`
+#[derive(Debug, Serialize)]
+struct ContractDefinition<'a> {
+    name: &'a str,
+    version: &'a str,
+    wasm: &'a str,
+    inputs: &'a [String],
+    outputs: &'a [String],
+}
+
+pub struct PackageOptions {
+    /// The built contract
+    pub wasm: PathBuf,
+    /// Where the definition, archive and payloads are written
+    pub out_dir: PathBuf,
+    /// The contract version to register
+    pub version: String,
+    /// Public keys of the contract and namespace registry owners
+    pub owners: Vec<String>,
+    /// A previously deployed version to delete once this one is uploaded
+    pub replaces: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum PackageError {
+    IoError(io::Error),
+    InvalidArgument(String),
+    BuildError(String),
+}
+
+impl Error for PackageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PackageError::IoError(err) => Some(err),
+            PackageError::InvalidArgument(_) => None,
+            PackageError::BuildError(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PackageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackageError::IoError(err) => write!(f, "IoError: {}", err),
+            PackageError::InvalidArgument(msg) => write!(f, "InvalidArgument: {}", msg),
+            PackageError::BuildError(msg) => write!(f, "BuildError: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for PackageError {
+    fn from(err: io::Error) -> Self {
+        PackageError::IoError(err)
+    }
+}
+
+/// Writes the contract definition, `.scar` archive and deployment payloads, returning the paths
+/// of the files written
+pub fn package(options: &PackageOptions) -> Result<Vec<PathBuf>, PackageError> {
+    if options.owners.is_empty() {
+        return Err(PackageError::InvalidArgument(
+            "at least one registry owner is required".to_string(),
+        ));
+    }
+
+    let wasm = fs::read(&options.wasm)?;
+    let wasm_name = options
+        .wasm
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| PackageError::InvalidArgument("wasm path has no file name".to_string()))?;
+    let manifest = ScarManifest::new(&options.version);
+    let payload_dir = options.out_dir.join("payloads");
+
+    fs::create_dir_all(&payload_dir)?;
+
+    let mut written = vec![];
+
+    let definition_path = options.out_dir.join(DEFINITION_FILE_NAME);
+    fs::write(&definition_path, contract_definition(&manifest, wasm_name)?)?;
+    written.push(definition_path);
+
+    let scar_path = options
+        .out_dir
+        .join(format!("{}_{}.scar", CONTRACT_NAME, options.version));
+    write_scar(fs::File::create(&scar_path)?, &manifest, wasm_name, &wasm)?;
+    written.push(scar_path);
+
+    let payloads = deploy_payloads(
+        &manifest,
+        &options.owners,
+        wasm,
+        options.replaces.as_deref(),
+    )?;
+    for (name, bytes) in payloads {
+        let path = payload_dir.join(name);
+        fs::write(&path, bytes)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+fn contract_definition(manifest: &ScarManifest, wasm_name: &str) -> Result<String, PackageError> {
+    serde_yaml::to_string(&ContractDefinition {
+        name: &manifest.name,
+        version: &manifest.version,
+        wasm: wasm_name,
+        inputs: &manifest.inputs,
+        outputs: &manifest.outputs,
+    })
+    .map_err(|err| PackageError::BuildError(err.to_string()))
+}
+
+/// Writes a `.scar` archive, a bzip2 compressed tar of `manifest.yaml` and the contract
+pub fn write_scar<W: Write>(
+    writer: W,
+    manifest: &ScarManifest,
+    wasm_name: &str,
+    wasm: &[u8],
+) -> Result<(), PackageError> {
+    let manifest =
+        serde_yaml::to_string(manifest).map_err(|err| PackageError::BuildError(err.to_string()))?;
+
+    let mut archive = tar::Builder::new(BzEncoder::new(writer, Compression::default()));
+    append_file(&mut archive, "manifest.yaml", manifest.as_bytes())?;
+    append_file(&mut archive, wasm_name, wasm)?;
+    archive.into_inner()?.finish()?;
+
+    Ok(())
+}
+
+fn append_file<W: Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    contents: &[u8],
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, Path::new(path), contents)
+}
+
+/// Builds the Sabre payloads that deploy the contract, named in the order they are submitted.
+///
+/// Creating a registry that already exists is rejected by Sabre, so on an upgrade only the
+/// contract payloads need to be submitted.
+pub fn deploy_payloads(
+    manifest: &ScarManifest,
+    owners: &[String],
+    wasm: Vec<u8>,
+    replaces: Option<&str>,
+) -> Result<Vec<(String, Vec<u8>)>, PackageError> {
+    let mut payloads = vec![];
+
+    payloads.push((
+        "00-create-contract-registry".to_string(),
+        Action::CreateContractRegistry(
+            CreateContractRegistryActionBuilder::new()
+                .with_name(manifest.name.clone())
+                .with_owners(owners.to_vec())
+                .build()
+                .map_err(|err| PackageError::BuildError(err.to_string()))?,
+        ),
+    ));
+
+    payloads.push((
+        format!("01-create-namespace-registry-{}", MFG_BATCH_NAMESPACE),
+        Action::CreateNamespaceRegistry(
+            CreateNamespaceRegistryActionBuilder::new()
+                .with_namespace(MFG_BATCH_NAMESPACE.to_string())
+                .with_owners(owners.to_vec())
+                .build()
+                .map_err(|err| PackageError::BuildError(err.to_string()))?,
+        ),
+    ));
+
+    // (read, write) for each namespace the contract touches
+    let mut permissions = BTreeMap::new();
+    for input in &manifest.inputs {
+        permissions
+            .entry(namespace_of(input))
+            .or_insert((true, false))
+            .0 = true;
+    }
+    for output in &manifest.outputs {
+        permissions
+            .entry(namespace_of(output))
+            .or_insert((false, true))
+            .1 = true;
+    }
+
+    for (namespace, (read, write)) in permissions {
+        payloads.push((
+            format!("02-namespace-permission-{}", namespace),
+            Action::CreateNamespaceRegistryPermission(
+                CreateNamespaceRegistryPermissionActionBuilder::new()
+                    .with_namespace(namespace.to_string())
+                    .with_contract_name(manifest.name.clone())
+                    .with_read(read)
+                    .with_write(write)
+                    .build()
+                    .map_err(|err| PackageError::BuildError(err.to_string()))?,
+            ),
+        ));
+    }
+
+    payloads.push((
+        format!("03-create-contract-{}", manifest.version),
+        Action::CreateContract(
+            CreateContractActionBuilder::new()
+                .with_name(manifest.name.clone())
+                .with_version(manifest.version.clone())
+                .with_inputs(manifest.inputs.clone())
+                .with_outputs(manifest.outputs.clone())
+                .with_contract(wasm)
+                .build()
+                .map_err(|err| PackageError::BuildError(err.to_string()))?,
+        ),
+    ));
+
+    if let Some(replaces) = replaces {
+        payloads.push((
+            format!("04-delete-contract-{}", replaces),
+            Action::DeleteContract(
+                DeleteContractActionBuilder::new()
+                    .with_name(manifest.name.clone())
+                    .with_version(replaces.to_string())
+                    .build()
+                    .map_err(|err| PackageError::BuildError(err.to_string()))?,
+            ),
+        ));
+    }
+
+    payloads
+        .into_iter()
+        .map(|(name, action)| {
+            let bytes = SabrePayloadBuilder::new()
+                .with_action(action)
+                .build()
+                .map_err(|err| PackageError::BuildError(err.to_string()))?
+                .into_bytes()
+                .map_err(|err| PackageError::BuildError(err.to_string()))?;
+            Ok((format!("{}.sabre", name), bytes))
+        })
+        .collect()
+}
+
+fn namespace_of(address_prefix: &str) -> &str {
+    &address_prefix[..NAMESPACE_LENGTH.min(address_prefix.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    use bzip2::read::BzDecoder;
+
+    /// Verify that the archive holds the manifest and the contract
+    #[test]
+    fn test_write_scar() {
+        let manifest = ScarManifest::new("0.1.1");
+        let mut scar = vec![];
+        write_scar(&mut scar, &manifest, "grid-mfg-batch-tp.wasm", b"\0asm")
+            .expect("Failed to write scar");
+
+        let mut archive = tar::Archive::new(BzDecoder::new(scar.as_slice()));
+        let mut entries = BTreeMap::new();
+        for entry in archive.entries().expect("Failed to read scar") {
+            let mut entry = entry.expect("Failed to read entry");
+            let path = entry.path().unwrap().to_str().unwrap().to_string();
+            let mut contents = vec![];
+            entry.read_to_end(&mut contents).unwrap();
+            entries.insert(path, contents);
+        }
+
+        assert_eq!(entries["grid-mfg-batch-tp.wasm"], b"\0asm");
+        let manifest_yaml = String::from_utf8(entries["manifest.yaml"].clone()).unwrap();
+        assert!(manifest_yaml.contains("name: grid_mfg_batch"));
+        assert!(manifest_yaml.contains("version: 0.1.1"));
+        assert!(manifest_yaml.contains("- 11bb0e01"));
+    }
+
+    /// Verify that the registries, one permission per namespace and the contract are created,
+    /// and that the replaced version is deleted last
+    #[test]
+    fn test_deploy_payloads() {
+        let manifest = ScarManifest::new("2");
+        let names = deploy_payloads(&manifest, &["owner".to_string()], vec![0], Some("1"))
+            .expect("Failed to build payloads")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec![
+                "00-create-contract-registry.sabre",
+                "01-create-namespace-registry-11bb0e.sabre",
+                "02-namespace-permission-11bb0e.sabre",
+                "02-namespace-permission-621dee.sabre",
+                "03-create-contract-2.sabre",
+                "04-delete-contract-1.sabre",
+            ]
+        );
+    }
+}
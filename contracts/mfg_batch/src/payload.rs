@@ -13,50 +13,250 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-cfg_if! {
-    if #[cfg(target_arch = "wasm32")] {
-        use sabre_sdk::ApplyError;
-    } else {
-        use sawtooth_sdk::processor::handler::ApplyError;
-    }
+use grid_sdk::protocol::{
+    mfg_batch::payload::{Action, MfgBatchCreateAction, MfgBatchPayload},
+    schema::state::PropertyValue,
+};
+
+use crate::error::MfgBatchError;
+
+/// The most property values, struct members included, a payload may carry by default
+pub const DEFAULT_MAX_PROPERTIES: usize = 256;
+/// The deepest struct values may nest by default, a top-level property being at depth 1
+pub const DEFAULT_MAX_STRUCT_DEPTH: usize = 8;
+/// The largest a bytes value may be by default
+pub const DEFAULT_MAX_BYTES_VALUE_SIZE: usize = 64 * 1024;
+
+/// Bounds on the properties a payload may carry, which keep the state entry written for a
+/// mfg_batch within what the validator will accept
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PayloadLimits {
+    /// The most property values a payload may carry, struct members included
+    pub max_properties: usize,
+    /// The deepest struct values may nest, a top-level property being at depth 1
+    pub max_struct_depth: usize,
+    /// The largest a bytes value may be, in bytes
+    pub max_bytes_value_size: usize,
 }
 
-use grid_sdk::protocol::mfg_batch::payload::{Action, MfgBatchCreateAction, MfgBatchPayload};
+impl Default for PayloadLimits {
+    fn default() -> Self {
+        Self {
+            max_properties: DEFAULT_MAX_PROPERTIES,
+            max_struct_depth: DEFAULT_MAX_STRUCT_DEPTH,
+            max_bytes_value_size: DEFAULT_MAX_BYTES_VALUE_SIZE,
+        }
+    }
+}
 
-pub fn validate_payload(payload: &MfgBatchPayload) -> Result<(), ApplyError> {
+pub fn validate_payload(
+    payload: &MfgBatchPayload,
+    limits: &PayloadLimits,
+) -> Result<(), MfgBatchError> {
     validate_timestamp(*payload.timestamp())?;
     match payload.action() {
-        Action::MfgBatchCreate(action_payload) => validate_mfg_batch_create_action(action_payload),
+        Action::MfgBatchCreate(action_payload) => {
+            validate_mfg_batch_create_action(action_payload)?;
+            validate_properties(action_payload.properties(), limits)
+        }
+        Action::MfgBatchUpdate(action_payload) => {
+            validate_properties(action_payload.properties(), limits)
+        }
         _ => Ok(()),
     }
 }
 
 fn validate_mfg_batch_create_action(
     mfg_batch_create_action: &MfgBatchCreateAction,
-) -> Result<(), ApplyError> {
+) -> Result<(), MfgBatchError> {
     if mfg_batch_create_action.mfg_batch_id() == "" {
-        return Err(ApplyError::InvalidTransaction(String::from(
-            "mfg_batch_id cannot be empty string",
-        )));
+        return Err(MfgBatchError::validation(
+            "mfg_batch_id",
+            String::from("mfg_batch_id cannot be empty string"),
+        ));
     }
     if mfg_batch_create_action.owner() == "" {
-        return Err(ApplyError::InvalidTransaction(String::from(
-            "Owner cannot be empty string",
+        return Err(MfgBatchError::validation(
+            "owner",
+            String::from("Owner cannot be empty string"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_properties(
+    properties: &[PropertyValue],
+    limits: &PayloadLimits,
+) -> Result<(), MfgBatchError> {
+    let mut count = 0;
+    validate_property_values(properties, 1, limits, &mut count)
+}
+
+/// Checks a level of property values, counting them toward the payload's total
+fn validate_property_values(
+    values: &[PropertyValue],
+    depth: usize,
+    limits: &PayloadLimits,
+    count: &mut usize,
+) -> Result<(), MfgBatchError> {
+    if depth > limits.max_struct_depth {
+        return Err(MfgBatchError::InvalidPayload(format!(
+            "Struct values cannot nest more than {} levels deep",
+            limits.max_struct_depth
         )));
     }
+
+    for value in values {
+        *count += 1;
+        if *count > limits.max_properties {
+            return Err(MfgBatchError::InvalidPayload(format!(
+                "Payload cannot have more than {} property values",
+                limits.max_properties
+            )));
+        }
+
+        if value.bytes_value().len() > limits.max_bytes_value_size {
+            return Err(MfgBatchError::InvalidPayload(format!(
+                "Bytes value of property {} is {} bytes, more than the limit of {}",
+                value.name(),
+                value.bytes_value().len(),
+                limits.max_bytes_value_size
+            )));
+        }
+
+        if !value.struct_values().is_empty() {
+            validate_property_values(value.struct_values(), depth + 1, limits, count)?;
+        }
+    }
+
     Ok(())
 }
 
-fn validate_timestamp(timestamp: u64) -> Result<(), ApplyError> {
+fn validate_timestamp(timestamp: u64) -> Result<(), MfgBatchError> {
     match timestamp {
-        0 => Err(ApplyError::InvalidTransaction(String::from(
-            "Timestamp is not set",
-        ))),
+        0 => Err(MfgBatchError::validation(
+            "timestamp",
+            String::from("Timestamp is not set"),
+        )),
         _ => Ok(()),
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid_sdk::protocol::{
+        mfg_batch::{
+            payload::{MfgBatchCreateActionBuilder, MfgBatchPayloadBuilder},
+            state::MfgBatchNamespace,
+        },
+        schema::state::{DataType, PropertyValueBuilder},
+    };
+
+    fn string_value(name: &str) -> PropertyValue {
+        PropertyValueBuilder::new()
+            .with_name(name.into())
+            .with_data_type(DataType::String)
+            .with_string_value("value".into())
+            .build()
+            .expect("Failed to build string PropertyValue")
+    }
+
+    /// Wraps the given value in `levels` struct values
+    fn nested_value(levels: usize) -> PropertyValue {
+        (0..levels).fold(string_value("leaf"), |inner, level| {
+            PropertyValueBuilder::new()
+                .with_name(format!("struct_{}", level))
+                .with_data_type(DataType::Struct)
+                .with_struct_values(vec![inner])
+                .build()
+                .expect("Failed to build struct PropertyValue")
+        })
+    }
+
+    fn create_payload(properties: Vec<PropertyValue>) -> MfgBatchPayload {
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into())
+            .with_owner("my_owner".into())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(properties)
+            .build()
+            .expect("Failed to build MfgBatchCreateAction");
+        MfgBatchPayloadBuilder::new()
+            .with_action(Action::MfgBatchCreate(action))
+            .with_timestamp(2)
+            .build()
+            .expect("Failed to build MfgBatchPayload")
+    }
+
+    fn expect_invalid(payload: &MfgBatchPayload, limits: &PayloadLimits, message: &str) {
+        match validate_payload(payload, limits) {
+            Err(MfgBatchError::InvalidPayload(err)) => assert_eq!(err, message),
+            res => panic!("Expected InvalidPayload, got {:?}", res),
+        }
+    }
+
+    #[test]
+    /// Test that a payload within every limit is valid, and one with a single property value
+    /// more than max_properties, counting struct members, is not
+    fn test_validate_payload_max_properties() {
+        let limits = PayloadLimits {
+            max_properties: 3,
+            ..Default::default()
+        };
+
+        let payload = create_payload(vec![string_value("a"), nested_value(1)]);
+        assert!(validate_payload(&payload, &limits).is_ok());
 
+        let payload = create_payload(vec![string_value("a"), nested_value(2)]);
+        expect_invalid(
+            &payload,
+            &limits,
+            "Payload cannot have more than 3 property values",
+        );
+    }
+
+    #[test]
+    /// Test that struct values may nest as deep as max_struct_depth and no deeper
+    fn test_validate_payload_max_struct_depth() {
+        let limits = PayloadLimits {
+            max_struct_depth: 3,
+            ..Default::default()
+        };
+
+        assert!(validate_payload(&create_payload(vec![nested_value(2)]), &limits).is_ok());
+        expect_invalid(
+            &create_payload(vec![nested_value(3)]),
+            &limits,
+            "Struct values cannot nest more than 3 levels deep",
+        );
+    }
+
+    #[test]
+    /// Test that a bytes value larger than max_bytes_value_size is invalid
+    fn test_validate_payload_max_bytes_value_size() {
+        let limits = PayloadLimits {
+            max_bytes_value_size: 4,
+            ..Default::default()
+        };
+        let bytes_value = |size| {
+            PropertyValueBuilder::new()
+                .with_name("image".into())
+                .with_data_type(DataType::Bytes)
+                .with_bytes_value(vec![0; size])
+                .build()
+                .expect("Failed to build bytes PropertyValue")
+        };
+
+        assert!(validate_payload(&create_payload(vec![bytes_value(4)]), &limits).is_ok());
+        expect_invalid(
+            &create_payload(vec![bytes_value(5)]),
+            &limits,
+            "Bytes value of property image is 5 bytes, more than the limit of 4",
+        );
+    }
+}
 
 /*
 
@@ -139,4 +339,4 @@ mod tests {
         }
     }
 }
-*/
\ No newline at end of file
+*/
@@ -12,10 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
+use grid_sdk::pike::permissions::{error::PermissionCheckerError, PermissionChecker};
+
 pub enum Permission {
     CanCreateMfgBatch,
     CanUpdateMfgBatch,
     CanDeleteMfgBatch,
+    CanAnchorMfgBatches,
+    CanAllocateMfgBatch,
+    CanRecallMfgBatch,
 }
 
 pub fn permission_to_perm_string(permission: Permission) -> String {
@@ -23,7 +30,77 @@ pub fn permission_to_perm_string(permission: Permission) -> String {
         Permission::CanCreateMfgBatch => String::from("mfg_batch::can-create-mfg-batch"),
         Permission::CanUpdateMfgBatch => String::from("mfg_batch::can-update-mfg-batch"),
         Permission::CanDeleteMfgBatch => String::from("mfg_batch::can-delete-mfg-batch"),
+        Permission::CanAnchorMfgBatches => String::from("mfg_batch::can-anchor-mfg-batches"),
+        Permission::CanAllocateMfgBatch => String::from("mfg_batch::can-allocate-mfg-batch"),
+        Permission::CanRecallMfgBatch => String::from("mfg_batch::can-recall-mfg-batch"),
     }
 }
 
+/// Finds the role that delegates a permission over an organization's records to the signer's
+/// organization, if the signer is an agent of another organization.
+///
+/// A role delegates the permission if it belongs to the record owner, grants the permission and
+/// lists the signer's organization in its `allowed_organizations`. It may be one of the signer's
+/// roles, or a role one of them inherits from, however deeply. Pike's own check stops at a role
+/// that grants the permission itself, so it misses a co-manufacturer's role that grants the
+/// permission for its own organization and also inherits the brand's. Returns the delegating
+/// role as `<org_id>.<role_name>`.
+pub fn find_delegating_role(
+    perm_checker: &PermissionChecker,
+    signer: &str,
+    permission: &str,
+    record_owner: &str,
+) -> Result<Option<String>, PermissionCheckerError> {
+    let agent = match perm_checker.get_agent(signer)? {
+        Some(agent) if *agent.active() && agent.org_id() != record_owner => agent,
+        _ => return Ok(None),
+    };
+
+    let mut pending: Vec<(String, Option<String>)> = agent
+        .roles()
+        .iter()
+        .map(|role| qualified_role_name(role, agent.org_id()))
+        .collect();
+    let mut visited = HashSet::new();
+    while let Some((name, org_id)) = pending.pop() {
+        let qualified_name = match &org_id {
+            Some(org_id) => format!("{}.{}", org_id, name),
+            None => name.clone(),
+        };
+        if !visited.insert(qualified_name.clone()) {
+            continue;
+        }
+
+        let role = match perm_checker.get_role(&name, org_id.as_deref()) {
+            Ok(Some(role)) => role,
+            _ => continue,
+        };
+        if role.org_id() == record_owner
+            && role.permissions().iter().any(|p| p == permission)
+            && role
+                .allowed_organizations()
+                .iter()
+                .any(|org| org == agent.org_id())
+        {
+            return Ok(Some(format!("{}.{}", role.org_id(), role.name())));
+        }
+
+        pending.extend(
+            role.inherit_from()
+                .iter()
+                .map(|inherited| qualified_role_name(inherited, role.org_id())),
+        );
+    }
+
+    Ok(None)
+}
 
+/// Splits a role reference into the name and organization `get_role` expects: a reference of the
+/// form `<org_id>.<role_name>` names its organization, otherwise the role is `org_id`'s own
+fn qualified_role_name(role: &str, org_id: &str) -> (String, Option<String>) {
+    if role.contains('.') {
+        (role.to_string(), None)
+    } else {
+        (role.to_string(), Some(org_id.to_string()))
+    }
+}
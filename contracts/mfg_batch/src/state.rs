@@ -23,69 +23,115 @@ cfg_if! {
 }
 
 use grid_sdk::{
+    location::addressing::compute_gs1_location_address,
+    mfg_batch::addressing::MfgBatchAddresser,
     pike::addressing::compute_organization_address,
-    // Calls the grid SDK GS1 validation routine in sdk/src/mfg_batch
-    mfg_batch::addressing::compute_gs1_mfg_batch_address,
     protocol::{
+        location::state::{Location, LocationList},
+        mfg_batch::state::{
+            MfgBatch, MfgBatchAnchor, MfgBatchAnchorList, MfgBatchAnchorListBuilder, MfgBatchList,
+            MfgBatchListBuilder, MfgBatchNamespace,
+        },
         pike::state::{Organization, OrganizationList},
-        mfg_batch::state::{MfgBatch, MfgBatchList, MfgBatchListBuilder},
         schema::state::{Schema, SchemaList},
     },
     protos::{FromBytes, IntoBytes},
     schema::addressing::compute_schema_address,
 };
 
+use crate::events::{namespace_name, MfgBatchEvent};
+
 pub struct MfgBatchState<'a> {
     context: &'a dyn TransactionContext,
+    addresser: MfgBatchAddresser,
+    /// The Splinter service transactions are applied for, recorded in events
+    service_id: Option<String>,
+    delegated_role: Option<String>,
 }
 
 impl<'a> MfgBatchState<'a> {
     pub fn new(context: &'a dyn TransactionContext) -> MfgBatchState {
-        MfgBatchState { context }
+        MfgBatchState {
+            context,
+            addresser: MfgBatchAddresser::default(),
+            service_id: None,
+            delegated_role: None,
+        }
     }
 
-    pub fn get_mfg_batch(&self, mfg_batch_id: &str) -> Result<Option<MfgBatch>, ApplyError> {
-        // Calls the grid SDK GS1 validation routine in sdk/src/mfg_batch
-        let address = compute_gs1_mfg_batch_address(mfg_batch_id); //mfg_batch id = gtin
-        let d = self.context.get_state_entry(&address)?;
-        match d {
-            Some(packed) => {
-                let mfg_batches = match MfgBatchList::from_bytes(packed.as_slice()) {
-                    Ok(mfg_batches) => mfg_batches,
-                    Err(_) => {
-                        return Err(ApplyError::InternalError(String::from(
-                            "Cannot deserialize mfg_batch list",
-                        )));
-                    }
-                };
+    /// Reads and writes mfg_batches and anchors under the addresser's namespace
+    pub fn with_addresser(mut self, addresser: MfgBatchAddresser) -> Self {
+        self.addresser = addresser;
+        self
+    }
 
-                // find the mfg_batch with the correct id
-                Ok(mfg_batches
-                    .mfg_batches()
-                    .iter()
-                    .find(|p| p.mfg_batch_id() == mfg_batch_id)
-                    .cloned())
+    /// Records the Splinter service, as `<circuit_id>::<service_id>`, in the events added
+    pub fn with_service_id(mut self, service_id: Option<String>) -> Self {
+        self.service_id = service_id;
+        self
+    }
+
+    /// Records the role, as `<org_id>.<role_name>`, through which the owner delegated the change
+    /// to the signer's organization in the events added
+    pub fn set_delegated_role(&mut self, delegated_role: Option<String>) {
+        self.delegated_role = delegated_role;
+    }
+
+    /// Computes the address a mfg_batch is written to
+    fn mfg_batch_address(
+        &self,
+        mfg_batch_namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> String {
+        #[cfg(feature = "mfg-batch-addressing-v2")]
+        {
+            self.addresser
+                .compute_mfg_batch_address_v2(mfg_batch_namespace, mfg_batch_id)
+        }
+        #[cfg(not(feature = "mfg-batch-addressing-v2"))]
+        {
+            self.addresser
+                .compute_mfg_batch_address(mfg_batch_namespace, mfg_batch_id)
+        }
+    }
+
+    pub fn get_mfg_batch(
+        &self,
+        mfg_batch_namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> Result<Option<MfgBatch>, ApplyError> {
+        let address = self.mfg_batch_address(mfg_batch_namespace, mfg_batch_id);
+        let mfg_batch = self.find_mfg_batch(&address, mfg_batch_id)?;
+
+        // Entries written before version 2 addressing are still read from their old address
+        #[cfg(feature = "mfg-batch-addressing-v2")]
+        {
+            let v1_address = self
+                .addresser
+                .compute_mfg_batch_address(mfg_batch_namespace, mfg_batch_id);
+            if mfg_batch.is_none() && v1_address != address {
+                return self.find_mfg_batch(&v1_address, mfg_batch_id);
             }
-            None => Ok(None),
         }
+
+        Ok(mfg_batch)
     }
 
     pub fn set_mfg_batch(&self, mfg_batch_id: &str, mfg_batch: MfgBatch) -> Result<(), ApplyError> {
-        // Calls the grid SDK GS1 validation routine in sdk/src/mfg_batch
-        let address = compute_gs1_mfg_batch_address(mfg_batch_id);
-        let d = self.context.get_state_entry(&address)?;
-        let mut mfg_batches = match d {
-            Some(packed) => match MfgBatchList::from_bytes(packed.as_slice()) {
-                Ok(mfg_batch_list) => mfg_batch_list.mfg_batches().to_vec(),
-                Err(err) => {
-                    return Err(ApplyError::InternalError(format!(
-                        "Cannot deserialize mfg_batch list: {:?}",
-                        err
-                    )));
-                }
-            },
-            None => vec![],
-        };
+        let address = self.mfg_batch_address(mfg_batch.mfg_batch_namespace(), mfg_batch_id);
+
+        // Writing a mfg_batch moves it off of its version 1 address, if it has a new one
+        #[cfg(feature = "mfg-batch-addressing-v2")]
+        {
+            let v1_address = self
+                .addresser
+                .compute_mfg_batch_address(mfg_batch.mfg_batch_namespace(), mfg_batch_id);
+            if v1_address != address {
+                self.remove_mfg_batch_at(&v1_address, mfg_batch_id)?;
+            }
+        }
+
+        let mut mfg_batches = self.get_mfg_batches(&address)?;
 
         let mut index = None;
         for (i, mfg_batch) in mfg_batches.iter().enumerate() {
@@ -100,6 +146,122 @@ impl<'a> MfgBatchState<'a> {
         }
         mfg_batches.push(mfg_batch);
         mfg_batches.sort_by_key(|r| r.mfg_batch_id().to_string());
+
+        self.set_mfg_batches(address, mfg_batches)
+    }
+
+    pub fn remove_mfg_batch(
+        &self,
+        mfg_batch_namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> Result<(), ApplyError> {
+        let address = self.mfg_batch_address(mfg_batch_namespace, mfg_batch_id);
+
+        #[cfg(feature = "mfg-batch-addressing-v2")]
+        {
+            let v1_address = self
+                .addresser
+                .compute_mfg_batch_address(mfg_batch_namespace, mfg_batch_id);
+            if v1_address != address {
+                self.remove_mfg_batch_at(&v1_address, mfg_batch_id)?;
+            }
+        }
+
+        self.remove_mfg_batch_at(&address, mfg_batch_id)
+    }
+
+    /// Adds the event of a change to a mfg_batch, with the mfg_batch as it is after the change
+    pub fn add_mfg_batch_event(
+        &self,
+        event: MfgBatchEvent,
+        mfg_batch: &MfgBatch,
+    ) -> Result<(), ApplyError> {
+        let mut attributes = vec![
+            (
+                "mfg_batch_id".to_string(),
+                mfg_batch.mfg_batch_id().to_string(),
+            ),
+            ("owner".to_string(), mfg_batch.owner().to_string()),
+            (
+                "mfg_batch_namespace".to_string(),
+                namespace_name(mfg_batch.mfg_batch_namespace()).to_string(),
+            ),
+        ];
+        if event == MfgBatchEvent::StatusChanged {
+            attributes.push(("archived".to_string(), mfg_batch.archived().to_string()));
+            if let Some(recall) = mfg_batch.recall() {
+                attributes.push(("recall_id".to_string(), recall.recall_id().to_string()));
+            }
+        }
+        if let Some(service_id) = &self.service_id {
+            attributes.push(("service_id".to_string(), service_id.clone()));
+        }
+        if let Some(delegated_role) = &self.delegated_role {
+            attributes.push(("delegated_role".to_string(), delegated_role.clone()));
+        }
+
+        let data = mfg_batch.clone().into_bytes().map_err(|err| {
+            ApplyError::InvalidTransaction(format!("Cannot serialize mfg_batch: {:?}", err))
+        })?;
+
+        self.context
+            .add_event(event.event_type().to_string(), attributes, &data)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))
+    }
+
+    fn find_mfg_batch(
+        &self,
+        address: &str,
+        mfg_batch_id: &str,
+    ) -> Result<Option<MfgBatch>, ApplyError> {
+        // find the mfg_batch with the correct id
+        Ok(self
+            .get_mfg_batches(address)?
+            .into_iter()
+            .find(|p| p.mfg_batch_id() == mfg_batch_id))
+    }
+
+    fn remove_mfg_batch_at(&self, address: &str, mfg_batch_id: &str) -> Result<(), ApplyError> {
+        let mfg_batches = self.get_mfg_batches(address)?;
+        if !mfg_batches.iter().any(|p| p.mfg_batch_id() == mfg_batch_id) {
+            return Ok(());
+        }
+
+        // Filter out the mfg_batch we are deleting
+        let filtered_mfg_batches = mfg_batches
+            .into_iter()
+            .filter(|p| p.mfg_batch_id() != mfg_batch_id)
+            .collect::<Vec<_>>();
+
+        self.set_mfg_batches(address.to_string(), filtered_mfg_batches)
+    }
+
+    fn get_mfg_batches(&self, address: &str) -> Result<Vec<MfgBatch>, ApplyError> {
+        match self.context.get_state_entry(address)? {
+            Some(packed) => match MfgBatchList::from_bytes(packed.as_slice()) {
+                Ok(mfg_batch_list) => Ok(mfg_batch_list.mfg_batches().to_vec()),
+                Err(err) => Err(ApplyError::InternalError(format!(
+                    "Cannot deserialize mfg_batch list: {:?}",
+                    err
+                ))),
+            },
+            None => Ok(vec![]),
+        }
+    }
+
+    fn set_mfg_batches(
+        &self,
+        address: String,
+        mfg_batches: Vec<MfgBatch>,
+    ) -> Result<(), ApplyError> {
+        // If no mfg_batches are left at the address, we can delete the entire state entry
+        if mfg_batches.is_empty() {
+            self.context
+                .delete_state_entries(&[address])
+                .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+            return Ok(());
+        }
+
         let mfg_batch_list = MfgBatchListBuilder::new()
             .with_mfg_batches(mfg_batches)
             .build()
@@ -122,60 +284,57 @@ impl<'a> MfgBatchState<'a> {
         Ok(())
     }
 
-    // Currently mfg_batch_id = gtin
-    pub fn remove_mfg_batch(&self, mfg_batch_id: &str) -> Result<(), ApplyError> {
-        // Calls the grid SDK GS1 validation routine in sdk/src/mfg_batch
-        let address = compute_gs1_mfg_batch_address(mfg_batch_id);
-        let d = self.context.get_state_entry(&address)?;
-        let mfg_batches = match d {
-            Some(packed) => match MfgBatchList::from_bytes(packed.as_slice()) {
-                Ok(mfg_batch_list) => mfg_batch_list.mfg_batches().to_vec(),
-                Err(err) => {
-                    return Err(ApplyError::InternalError(format!(
-                        "Cannot deserialize mfg_batch list: {:?}",
-                        err
-                    )));
-                }
-            },
-            None => vec![],
-        };
-
-        // Filter out the mfg_batch we are deleting
-        let filtered_mfg_batches = mfg_batches
+    pub fn get_mfg_batch_anchor(
+        &self,
+        owner: &str,
+        commit_num: i64,
+    ) -> Result<Option<MfgBatchAnchor>, ApplyError> {
+        let address = self
+            .addresser
+            .compute_mfg_batch_anchor_address(owner, commit_num);
+        Ok(self
+            .get_mfg_batch_anchors(&address)?
             .into_iter()
-            .filter(|p| p.mfg_batch_id() != mfg_batch_id)
-            .collect::<Vec<_>>();
+            .find(|anchor| anchor.owner() == owner && anchor.commit_num() == commit_num))
+    }
 
-        // If the only mfg_batch at the address was the one we are removing, we can delete the entire state entry
-        // Else, we can set the the filtered mfg_batch list at the address
-        if filtered_mfg_batches.is_empty() {
-            self.context
-                .delete_state_entries(&[address])
-                .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
-        } else {
-            let mfg_batch_list = MfgBatchListBuilder::new()
-                .with_mfg_batches(filtered_mfg_batches)
-                .build()
-                .map_err(|err| {
-                    ApplyError::InvalidTransaction(format!("Cannot build mfg_batch list: {:?}", err))
-                })?;
-
-            let serialized = match mfg_batch_list.into_bytes() {
-                Ok(serialized) => serialized,
-                Err(_) => {
-                    return Err(ApplyError::InternalError(String::from(
-                        "Cannot serialize mfg_batch list",
-                    )));
-                }
-            };
-            self.context
-                .set_state_entry(address, serialized)
-                .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
-        }
+    /// Adds an anchor to state; anchors are never changed once recorded
+    pub fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), ApplyError> {
+        let address = self
+            .addresser
+            .compute_mfg_batch_anchor_address(anchor.owner(), anchor.commit_num());
+        let mut anchors = self.get_mfg_batch_anchors(&address)?;
+        anchors.push(anchor);
 
+        let anchor_list = MfgBatchAnchorListBuilder::new()
+            .with_anchors(anchors)
+            .build()
+            .map_err(|err| {
+                ApplyError::InvalidTransaction(format!("Cannot build anchor list: {:?}", err))
+            })?;
+
+        let serialized = anchor_list.into_bytes().map_err(|err| {
+            ApplyError::InvalidTransaction(format!("Cannot serialize anchor list: {:?}", err))
+        })?;
+        self.context
+            .set_state_entry(address, serialized)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
         Ok(())
     }
 
+    fn get_mfg_batch_anchors(&self, address: &str) -> Result<Vec<MfgBatchAnchor>, ApplyError> {
+        match self.context.get_state_entry(address)? {
+            Some(packed) => match MfgBatchAnchorList::from_bytes(packed.as_slice()) {
+                Ok(anchor_list) => Ok(anchor_list.anchors().to_vec()),
+                Err(err) => Err(ApplyError::InternalError(format!(
+                    "Cannot deserialize anchor list: {:?}",
+                    err
+                ))),
+            },
+            None => Ok(vec![]),
+        }
+    }
+
     pub fn get_organization(&self, id: &str) -> Result<Option<Organization>, ApplyError> {
         let address = compute_organization_address(id);
         let d = self.context.get_state_entry(&address)?;
@@ -202,6 +361,32 @@ impl<'a> MfgBatchState<'a> {
         }
     }
 
+    pub fn get_location(&self, gln: &str) -> Result<Option<Location>, ApplyError> {
+        let address = compute_gs1_location_address(gln);
+        let d = self.context.get_state_entry(&address)?;
+        match d {
+            Some(packed) => {
+                let locations = match LocationList::from_bytes(packed.as_slice()) {
+                    Ok(locations) => locations,
+                    Err(err) => {
+                        return Err(ApplyError::InvalidTransaction(format!(
+                            "Cannot deserialize location list: {:?}",
+                            err,
+                        )));
+                    }
+                };
+
+                // find the location with the correct GLN
+                Ok(locations
+                    .locations()
+                    .iter()
+                    .find(|location| location.location_id() == gln)
+                    .cloned())
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn get_schema(&self, name: &str) -> Result<Option<Schema>, ApplyError> {
         let address = compute_schema_address(name);
         let d = self.context.get_state_entry(&address)?;
@@ -230,96 +415,212 @@ impl<'a> MfgBatchState<'a> {
     }
 }
 
-/*
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::cell::RefCell;
-    use std::collections::HashMap;
-
-    use grid_sdk::protocol::mfg_batch::state::{MfgBatchBuilder, MfgBatchNamespace};
+    use grid_sdk::mfg_batch::addressing::{compute_mfg_batch_address, MfgBatchAddresserBuilder};
+    use grid_sdk::protocol::mfg_batch::state::MfgBatchBuilder;
     use grid_sdk::protocol::schema::state::{DataType, PropertyValue, PropertyValueBuilder};
+    use grid_sdk::testing::{organization, property_definition, schema, MockTransactionContext};
 
-    use sawtooth_sdk::processor::handler::{ContextError, TransactionContext};
+    const MFG_BATCH_ID: &str = "688955434684";
+    const MFG_BATCH_2_ID: &str = "9781981855728";
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    const LOT_A_ID: &str = "(01)10012345678902(10)A1";
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    const LOT_B_ID: &str = "(01)10012345678902(10)B2";
 
-    const mfg_batch_ID: &str = "688955434684";
+    #[test]
+    // Test that if a mfg_batch does not exist in state, None is returned
+    fn test_get_mfg_batch_none() {
+        let transaction_context = MockTransactionContext::new();
+        let state = MfgBatchState::new(&transaction_context);
 
-    #[derive(Default, Debug)]
-    /// A MockTransactionContext that can be used to test TrackAndTraceState
-    struct MockTransactionContext {
-        state: RefCell<HashMap<String, Vec<u8>>>,
+        let result = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, "not_a_mfg_batch")
+            .unwrap();
+        assert!(result.is_none())
     }
 
-    impl TransactionContext for MockTransactionContext {
-        fn get_state_entries(
-            &self,
-            addresses: &[String],
-        ) -> Result<Vec<(String, Vec<u8>)>, ContextError> {
-            let mut results = Vec::new();
-            for addr in addresses {
-                let data = match self.state.borrow().get(addr) {
-                    Some(data) => data.clone(),
-                    None => Vec::new(),
-                };
-                results.push((addr.to_string(), data));
-            }
-            Ok(results)
-        }
+    #[test]
+    // Test that a mfg_batch can be added to state
+    fn test_set_mfg_batch() {
+        let transaction_context = MockTransactionContext::new();
+        let state = MfgBatchState::new(&transaction_context);
+
+        assert!(state
+            .set_mfg_batch(MFG_BATCH_ID, make_mfg_batch(MFG_BATCH_ID))
+            .is_ok());
+        let result = state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .unwrap();
+        assert_eq!(result, Some(make_mfg_batch(MFG_BATCH_ID)));
+    }
 
-        fn set_state_entries(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), ContextError> {
-            for (addr, data) in entries {
-                self.state.borrow_mut().insert(addr, data);
-            }
-            Ok(())
-        }
+    #[test]
+    // Test that removing a mfg_batch leaves the others in state
+    fn test_remove_mfg_batch() {
+        let transaction_context = MockTransactionContext::new();
+        let state = MfgBatchState::new(&transaction_context);
 
-        /// this is not needed for these tests
-        fn delete_state_entries(&self, _addresses: &[String]) -> Result<Vec<String>, ContextError> {
-            unimplemented!()
-        }
+        state
+            .set_mfg_batch(MFG_BATCH_ID, make_mfg_batch(MFG_BATCH_ID))
+            .unwrap();
+        state
+            .set_mfg_batch(MFG_BATCH_2_ID, make_mfg_batch(MFG_BATCH_2_ID))
+            .unwrap();
 
-        /// this is not needed for these tests
-        fn add_receipt_data(&self, _data: &[u8]) -> Result<(), ContextError> {
-            unimplemented!()
-        }
+        assert!(state
+            .remove_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .is_ok());
+        assert!(state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .unwrap()
+            .is_none());
+        assert!(transaction_context
+            .state_entry(&compute_mfg_batch_address(
+                &MfgBatchNamespace::Gs1,
+                MFG_BATCH_ID
+            ))
+            .is_none());
+        assert_eq!(
+            state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_2_ID)
+                .unwrap(),
+            Some(make_mfg_batch(MFG_BATCH_2_ID))
+        );
+    }
 
-        /// this is not needed for these tests
-        fn add_event(
-            &self,
-            _event_type: String,
-            _attributes: Vec<(String, String)>,
-            _data: &[u8],
-        ) -> Result<(), ContextError> {
-            unimplemented!()
-        }
+    #[test]
+    // Test that a state with another family's addresser stores mfg_batches under its namespace
+    fn test_custom_namespace() {
+        let transaction_context = MockTransactionContext::new();
+        let addresser = MfgBatchAddresserBuilder::new()
+            .with_namespace("abc123".into())
+            .build()
+            .unwrap();
+        let state = MfgBatchState::new(&transaction_context).with_addresser(addresser.clone());
+
+        state
+            .set_mfg_batch(MFG_BATCH_ID, make_mfg_batch(MFG_BATCH_ID))
+            .unwrap();
+        assert!(transaction_context
+            .state_entry(
+                &addresser.compute_mfg_batch_address(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            )
+            .is_some());
+        assert!(transaction_context
+            .state_entry(&compute_mfg_batch_address(
+                &MfgBatchNamespace::Gs1,
+                MFG_BATCH_ID
+            ))
+            .is_none());
+        assert!(MfgBatchState::new(&transaction_context)
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, MFG_BATCH_ID)
+                .unwrap(),
+            Some(make_mfg_batch(MFG_BATCH_ID))
+        );
     }
 
     #[test]
-    // Test that if a mfg_batch does not exist in state, None is returned
-    fn test_get_mfg_batch_none() {
-        let mut transaction_context = MockTransactionContext::default();
-        let state = MfgBatchState::new(&mut transaction_context);
+    // Test that organizations and schemas are read from the Pike and schema namespaces
+    fn test_get_organization_and_schema() {
+        let transaction_context = MockTransactionContext::new();
+        transaction_context
+            .add_organization(organization("test_org", &[("gs1_company_prefix", "6889")]));
+        transaction_context.add_schema(schema(
+            "gs1_mfg_batch",
+            "test_org",
+            vec![property_definition("description", DataType::String, true)],
+        ));
+        let state = MfgBatchState::new(&transaction_context);
+
+        let org = state.get_organization("test_org").unwrap().unwrap();
+        assert_eq!(org.alternate_ids()[0].id(), "6889");
+        assert!(state.get_organization("other_org").unwrap().is_none());
+
+        let schema = state.get_schema("gs1_mfg_batch").unwrap().unwrap();
+        assert_eq!(schema.properties()[0].name(), "description");
+        assert!(state.get_schema("other_schema").unwrap().is_none());
+    }
 
-        let result = state.get_mfg_batch("not_a_mfg_batch").unwrap();
-        assert!(result.is_none())
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    #[test]
+    // Test that each lot of a product is stored at its own address
+    fn test_set_mfg_batch_lots() {
+        let transaction_context = MockTransactionContext::new();
+        let state = MfgBatchState::new(&transaction_context);
+
+        state
+            .set_mfg_batch(LOT_A_ID, make_mfg_batch(LOT_A_ID))
+            .unwrap();
+        state
+            .set_mfg_batch(LOT_B_ID, make_mfg_batch(LOT_B_ID))
+            .unwrap();
+
+        let lot_a_address = state.mfg_batch_address(&MfgBatchNamespace::Gs1, LOT_A_ID);
+        let lot_b_address = state.mfg_batch_address(&MfgBatchNamespace::Gs1, LOT_B_ID);
+        assert_ne!(lot_a_address, lot_b_address);
+        assert!(transaction_context.state_entry(&lot_a_address).is_some());
+        assert!(transaction_context.state_entry(&lot_b_address).is_some());
+        assert_eq!(
+            state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, LOT_B_ID)
+                .unwrap(),
+            Some(make_mfg_batch(LOT_B_ID))
+        );
     }
 
+    #[cfg(feature = "mfg-batch-addressing-v2")]
     #[test]
-    // Test that a mfg_batch can be added to state
-    fn test_set_mfg_batch() {
-        let mut transaction_context = MockTransactionContext::default();
-        let state = MfgBatchState::new(&mut transaction_context);
+    // Test that a mfg_batch at its version 1 address is still read, and is moved to its
+    // version 2 address when it is next written
+    fn test_mfg_batch_v1_address_compatibility() {
+        let transaction_context = MockTransactionContext::new();
+        let state = MfgBatchState::new(&transaction_context);
+
+        let v1_address = compute_mfg_batch_address(&MfgBatchNamespace::Gs1, LOT_A_ID);
+        let mfg_batch_list = MfgBatchListBuilder::new()
+            .with_mfg_batches(vec![make_mfg_batch(LOT_A_ID)])
+            .build()
+            .unwrap();
+        transaction_context
+            .set_state_entry(v1_address.clone(), mfg_batch_list.into_bytes().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            state
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, LOT_A_ID)
+                .unwrap(),
+            Some(make_mfg_batch(LOT_A_ID))
+        );
 
-        assert!(state.set_mfg_batch(mfg_batch_ID, make_mfg_batch()).is_ok());
-        let result = state.get_mfg_batch(mfg_batch_ID).unwrap();
-        assert_eq!(result, Some(make_mfg_batch()));
+        state
+            .set_mfg_batch(LOT_A_ID, make_mfg_batch(LOT_A_ID))
+            .unwrap();
+        assert!(transaction_context.state_entry(&v1_address).is_none());
+        assert!(transaction_context
+            .state_entry(&state.mfg_batch_address(&MfgBatchNamespace::Gs1, LOT_A_ID))
+            .is_some());
+
+        state
+            .remove_mfg_batch(&MfgBatchNamespace::Gs1, LOT_A_ID)
+            .unwrap();
+        assert!(state
+            .get_mfg_batch(&MfgBatchNamespace::Gs1, LOT_A_ID)
+            .unwrap()
+            .is_none());
     }
 
-    fn make_mfg_batch() -> mfg_batch {
-        mfg_batchBuilder::new()
-            .with_mfg_batch_id(mfg_batch_ID.to_string())
+    fn make_mfg_batch(mfg_batch_id: &str) -> MfgBatch {
+        MfgBatchBuilder::new()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
             .with_owner("some_owner".to_string())
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
             .with_properties(make_properties())
@@ -341,10 +642,6 @@ mod tests {
             .build()
             .unwrap();
 
-        vec![
-            property_value_description.clone(),
-            property_value_price.clone(),
-        ]
+        vec![property_value_description, property_value_price]
     }
 }
-*/
@@ -0,0 +1,154 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decision traces record the outcome of each validation step a transaction goes through.
+//!
+//! When tracing is enabled, the trace of an applied transaction is added to its receipt. Receipts
+//! are not kept for invalid transactions, so the trace of a rejected transaction is appended to
+//! the rejection message reported in the batch status instead.
+
+use std::cell::{Cell, RefCell};
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use sabre_sdk::ApplyError;
+    } else {
+        use sawtooth_sdk::processor::handler::ApplyError;
+    }
+}
+
+/// The most bytes a trace may hold; steps past it are dropped and the trace is marked truncated
+pub const MAX_TRACE_SIZE: usize = 2048;
+
+const TRUNCATED: &str = "...";
+
+/// The outcomes of the validation steps of one transaction, recorded only when enabled
+#[derive(Default)]
+pub struct DecisionTrace {
+    enabled: bool,
+    steps: RefCell<Vec<String>>,
+    size: Cell<usize>,
+    truncated: Cell<bool>,
+}
+
+impl DecisionTrace {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Records the outcome of a step, passing its result through
+    pub fn step<T, E>(&self, step: &str, result: Result<T, E>) -> Result<T, ApplyError>
+    where
+        E: Into<ApplyError>,
+    {
+        let result = result.map_err(Into::into);
+        if self.enabled {
+            let outcome = match result {
+                Ok(_) => "ok",
+                Err(ApplyError::InvalidTransaction(_)) => "rejected",
+                Err(ApplyError::InternalError(_)) => "error",
+            };
+            self.record(format!("{}: {}", step, outcome));
+        }
+        result
+    }
+
+    /// Records that a step passed
+    pub fn pass(&self, step: &str) {
+        let _ = self.step(step, Ok::<_, ApplyError>(()));
+    }
+
+    /// Records that a step rejected the transaction, returning the rejection
+    pub fn reject<T, E>(&self, step: &str, err: E) -> Result<T, ApplyError>
+    where
+        E: Into<ApplyError>,
+    {
+        self.step(step, Err(err))
+    }
+
+    fn record(&self, entry: String) {
+        let size = self.size.get() + entry.len() + 1;
+        if self.truncated.get() || size > MAX_TRACE_SIZE - TRUNCATED.len() {
+            self.truncated.set(true);
+            return;
+        }
+        self.size.set(size);
+        self.steps.borrow_mut().push(entry);
+    }
+
+    /// The recorded steps, one per line, as stored in a transaction receipt
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.lines().join("\n").into_bytes()
+    }
+
+    /// The recorded steps on one line, as appended to a rejection message
+    pub fn summary(&self) -> String {
+        self.lines().join("; ")
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = self.steps.borrow().clone();
+        if self.truncated.get() {
+            lines.push(TRUNCATED.to_string());
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies the outcome of each step is recorded in order, and nothing is recorded when
+    /// tracing is disabled
+    #[test]
+    fn test_steps() {
+        let trace = DecisionTrace::new(true);
+        trace.pass("permission");
+        assert!(trace.step("unique", Ok::<_, ApplyError>("value")).is_ok());
+        assert!(trace
+            .reject::<(), _>("dates", ApplyError::InvalidTransaction("bad".into()))
+            .is_err());
+
+        assert_eq!(
+            trace.summary(),
+            "permission: ok; unique: ok; dates: rejected"
+        );
+        assert_eq!(
+            trace.to_bytes(),
+            b"permission: ok\nunique: ok\ndates: rejected".to_vec()
+        );
+
+        let trace = DecisionTrace::new(false);
+        trace.pass("permission");
+        assert_eq!(trace.summary(), "");
+    }
+
+    /// Verifies a trace stops recording steps once it reaches its maximum size
+    #[test]
+    fn test_truncated() {
+        let trace = DecisionTrace::new(true);
+        for i in 0..MAX_TRACE_SIZE {
+            trace.pass(&format!("property {}", i));
+        }
+
+        let bytes = trace.to_bytes();
+        assert!(bytes.len() <= MAX_TRACE_SIZE);
+        assert!(bytes.ends_with(TRUNCATED.as_bytes()));
+    }
+}
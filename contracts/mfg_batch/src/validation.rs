@@ -20,111 +20,576 @@ cfg_if! {
     }
 }
 
-// Validates the specification for GS1 standard format 
-// No immediate changes required for MVP
-
-/* The purpose of this file is to programmatically express the equation used to validate a GTIN
-It validates gtin format to avoid mistype errors similar to a credit card validation
-Check digit validation: (https://www.gs1.org/services/how-calculate-check-digit-manually) */
-
-// Leaving this as an extensible function, so other validation rules can be implemented by GTIN format
-pub fn validate_gtin(gtin: &str) -> Result<(), ApplyError> {
-    // Check that gtin is numeric only
-    if is_numeric(gtin) {
-        match gtin.chars().count() {
-            // GTIN-8 is an 8-digit number used predominately outside of North America on smaller packaging (not supported)
-            8 => Err(ApplyError::InvalidTransaction(format!(
-                "Invalid GTIN, GTIN-8 is not supported at this time: {}",
-                gtin
-            ))),
-            // GTIN-12 is a 12-digit number used primarily in North America
-            12 => check_digit_validation(gtin),
-            // GTIN-13 (it could also be a GLN or the first 13 digits of a GRAI, GDTI or GCN.) (ex: 9781981855728)
-            13 => check_digit_validation(gtin),
-            // GTIN-14 is a 14-digit number used to identify trade items at various packaging levels
-            14 => check_digit_validation(gtin),
-            // Invalid length
-            _ => Err(ApplyError::InvalidTransaction(format!(
-                "Invalid length for GTIN identifier: {}",
-                gtin
-            ))),
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+use crate::error::MfgBatchError;
+
+use grid_sdk::{
+    mfg_batch::{
+        addressing::MfgBatchIdentifier,
+        validation::{self as rules, MAX_DATE, MAX_STRING_VALUE_LENGTH},
+    },
+    protocol::{
+        mfg_batch::{
+            payload::{MfgBatchAnchorAction, MfgBatchRecallAction},
+            state::{Attachment, Attestation, MfgBatch, MfgBatchNamespace, TestResult},
+        },
+        pike::state::Organization,
+        schema::state::{PropertyDefinition, PropertyValue},
+    },
+};
+
+/// The signature scheme attestations are verified with
+pub const ATTESTATION_ALGORITHM: &str = "secp256k1";
+
+/// Validates a mfg_batch ID within its namespace, returning the kind of identifier it is.
+pub fn validate_namespaced_mfg_batch_id(
+    mfg_batch_namespace: &MfgBatchNamespace,
+    mfg_batch_id: &str,
+) -> Result<MfgBatchIdentifier, MfgBatchError> {
+    rules::validate_namespaced_mfg_batch_id(mfg_batch_namespace, mfg_batch_id)
+        .map_err(MfgBatchError::from)
+}
+
+/// Checks that recording `parent_batches` as parents of `mfg_batch_id` would not make the
+/// batch its own ancestor.
+///
+/// `get_parents` returns the parents currently recorded for a batch; batches which no longer
+/// exist should return an empty list.
+pub fn validate_no_genealogy_cycle<F>(
+    mfg_batch_id: &str,
+    parent_batches: &[String],
+    mut get_parents: F,
+) -> Result<(), ApplyError>
+where
+    F: FnMut(&str) -> Result<Vec<String>, ApplyError>,
+{
+    let mut visited = HashSet::new();
+    let mut to_visit = parent_batches.to_vec();
+
+    while let Some(ancestor) = to_visit.pop() {
+        if ancestor == mfg_batch_id {
+            return Err(MfgBatchError::validation(
+                "parent_batches",
+                format!(
+                    "Adding parents to {} would make it its own ancestor",
+                    mfg_batch_id
+                ),
+            )
+            .into());
         }
-    } else {
-        Err(ApplyError::InvalidTransaction(format!(
-            "Invalid format, GTIN identifiers only contain numbers: {}",
-            gtin
-        )))
-    }
-}
-
-fn check_digit_validation(gtin: &str) -> Result<(), ApplyError> {
-    let mut gtin_vec: Vec<char> = gtin.chars().collect();
-    // Remove the check digit from the gtin_vec and store it for later
-    let check_digit_char = gtin_vec
-        .pop()
-        .expect("No characters found, but string length was > 0");
-    let check_digit = convert_char_to_int(check_digit_char);
-    let mut sum = 0;
-    let mut index = 0;
-
-    if is_even(gtin_vec.len()) {
-        // For gtin-13
-        for digit in &gtin_vec {
-            if is_even(index) {
-                sum += convert_char_to_int(*digit);
-            } else {
-                sum += 3 * convert_char_to_int(*digit);
-            }
-            index += 1;
+
+        if visited.insert(ancestor.clone()) {
+            to_visit.extend(get_parents(&ancestor)?);
         }
-    } else {
-        // For gtin 12, 14
-        for digit in &gtin_vec {
-            if is_even(index) {
-                sum += 3 * convert_char_to_int(*digit);
-            } else {
-                sum += convert_char_to_int(*digit);
-            }
-            index += 1;
+    }
+
+    Ok(())
+}
+
+/// Validates a mfg_batch's quantities.
+pub fn validate_quantity(
+    quantity: i64,
+    uom: &str,
+    expected_quantity: i64,
+) -> Result<(), MfgBatchError> {
+    rules::validate_quantity(quantity, uom, expected_quantity).map_err(MfgBatchError::from)
+}
+
+/// Validates a mfg_batch's production and expiration dates.
+pub fn validate_dates(production_date: u64, expiration_date: u64) -> Result<(), MfgBatchError> {
+    rules::validate_dates(production_date, expiration_date).map_err(MfgBatchError::from)
+}
+
+/// Validates the GLN of the location a mfg_batch was produced at.
+pub fn validate_manufacture_location(gln: &str) -> Result<(), MfgBatchError> {
+    rules::validate_manufacture_location(gln).map_err(MfgBatchError::from)
+}
+
+/// Validates the off-chain documents a mfg_batch references.
+pub fn validate_attachments(attachments: &[Attachment]) -> Result<(), MfgBatchError> {
+    rules::validate_attachments(attachments).map_err(MfgBatchError::from)
+}
+
+/// Validates the Splinter service a transaction names, and that it is the service the handler
+/// is scoped to, if it is scoped to one. Returns the service the transaction is applied for.
+pub fn validate_service_id(
+    service_id: &str,
+    scope: Option<&str>,
+) -> Result<Option<String>, MfgBatchError> {
+    rules::validate_service_id(service_id).map_err(MfgBatchError::from)?;
+
+    match scope {
+        Some(scope) if !service_id.is_empty() && service_id != scope => {
+            Err(MfgBatchError::validation(
+                "service_id",
+                format!(
+                    "Transaction was submitted to service {}, but this contract serves {}",
+                    service_id, scope
+                ),
+            ))
         }
+        Some(scope) => Ok(Some(scope.to_string())),
+        None => Ok(Some(service_id.to_string()).filter(|id| !id.is_empty())),
     }
+}
 
-    let nearest_ten = ceiling_to_nearest_ten(sum as f32);
-    let computed_check_digit = nearest_ten - sum;
+/// Checks that an organization holds the GS1 company prefix of a GS1 keyed mfg_batch ID.
+pub fn validate_gs1_company_prefix(
+    mfg_batch_id: &str,
+    org: &Organization,
+) -> Result<(), MfgBatchError> {
+    rules::validate_gs1_company_prefix(mfg_batch_id, org).map_err(MfgBatchError::from)
+}
 
-    if computed_check_digit == check_digit {
-        Ok(())
-    } else {
-        Err(ApplyError::InvalidTransaction(format!(
-            "Invalid gtin, check digit validation failed: {}",
-            gtin
-        )))
+/// Checks that a mfg_batch has not been archived. Archived batches are kept in state for their
+/// history but can no longer be changed.
+pub fn validate_not_archived(mfg_batch: &MfgBatch) -> Result<(), MfgBatchError> {
+    if mfg_batch.archived() {
+        return Err(MfgBatchError::Conflict(format!(
+            "Mfg_batch {} has been archived and cannot be changed",
+            mfg_batch.mfg_batch_id()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that an update was built against the version of a mfg_batch currently in state, if it
+/// names one, so that concurrent updates cannot overwrite each other's changes unseen.
+pub fn validate_expected_version(
+    mfg_batch: &MfgBatch,
+    expected_version: &str,
+) -> Result<(), MfgBatchError> {
+    if expected_version.is_empty() {
+        return Ok(());
     }
+
+    let version = mfg_batch
+        .version()
+        .map_err(|err| MfgBatchError::Internal(format!("Cannot get mfg_batch version: {}", err)))?;
+    if !version.eq_ignore_ascii_case(expected_version) {
+        return Err(MfgBatchError::Conflict(format!(
+            "Mfg_batch {} has changed since version {}; its current version is {}",
+            mfg_batch.mfg_batch_id(),
+            expected_version,
+            version
+        )));
+    }
+
+    Ok(())
 }
 
-fn is_even(num: usize) -> bool {
-    num % 2 == 0
+/// Validates a quantity to be reserved from a mfg_batch for a sales order.
+///
+/// The order ID is required and may not exceed `MAX_STRING_VALUE_LENGTH`, the quantity must be
+/// positive, and the batch must have a recorded quantity that, once the quantities already
+/// reserved are taken out of it, leaves enough to cover the new allocation.
+pub fn validate_allocation(
+    mfg_batch: &MfgBatch,
+    order_id: &str,
+    quantity: i64,
+) -> Result<(), MfgBatchError> {
+    validate_order_id(order_id)?;
+
+    if quantity <= 0 {
+        return Err(MfgBatchError::validation(
+            "quantity",
+            format!("Allocated quantity must be positive: {}", quantity),
+        ));
+    }
+
+    if mfg_batch.uom().is_empty() {
+        return Err(MfgBatchError::validation(
+            "quantity",
+            format!(
+                "Mfg_batch {} has no recorded quantity to allocate from",
+                mfg_batch.mfg_batch_id()
+            ),
+        ));
+    }
+
+    if quantity > mfg_batch.available_quantity() {
+        return Err(MfgBatchError::validation(
+            "quantity",
+            format!(
+                "Cannot allocate {} {} from mfg_batch {}; only {} {} available",
+                quantity,
+                mfg_batch.uom(),
+                mfg_batch.mfg_batch_id(),
+                mfg_batch.available_quantity(),
+                mfg_batch.uom()
+            ),
+        ));
+    }
+
+    Ok(())
 }
 
-fn is_numeric(s: &str) -> bool {
-    s.parse::<f64>().is_ok()
+/// Validates a quantity to be released from a sales order's allocation of a mfg_batch.
+///
+/// The order must have an allocation of the batch, and the quantity may not be negative nor more
+/// than the order has reserved; a quantity of 0 releases the whole allocation. Returns the
+/// quantity the order has reserved.
+pub fn validate_release(
+    mfg_batch: &MfgBatch,
+    order_id: &str,
+    quantity: i64,
+) -> Result<i64, MfgBatchError> {
+    let allocated = mfg_batch
+        .allocations()
+        .iter()
+        .find(|allocation| allocation.order_id() == order_id)
+        .map(|allocation| allocation.quantity())
+        .ok_or_else(|| {
+            MfgBatchError::NotFound(format!(
+                "Order {} has no allocation of mfg_batch {}",
+                order_id,
+                mfg_batch.mfg_batch_id()
+            ))
+        })?;
+
+    if quantity < 0 || quantity > allocated {
+        return Err(MfgBatchError::validation(
+            "quantity",
+            format!(
+                "Released quantity must be between 0 and the {} allocated to order {}: {}",
+                allocated, order_id, quantity
+            ),
+        ));
+    }
+
+    Ok(allocated)
 }
 
-fn ceiling_to_nearest_ten(num: f32) -> i32 {
-    let c: f32 = num / 10.0;
-    let ceil: f32 = c.ceil();
-    let c_ceil = ceil as i32;
-    10 * c_ceil
+/// Validates a mfg_batch's new quantity against the quantities its sales orders have reserved.
+///
+/// While any quantity is reserved, the batch's unit of measure may not change and its quantity
+/// may not fall below the quantity reserved.
+pub fn validate_allocated_quantity(
+    mfg_batch: &MfgBatch,
+    quantity: i64,
+    uom: &str,
+) -> Result<(), MfgBatchError> {
+    if mfg_batch.allocations().is_empty() {
+        return Ok(());
+    }
+
+    if uom != mfg_batch.uom() {
+        return Err(MfgBatchError::validation(
+            "uom",
+            format!(
+                "The unit of measure of mfg_batch {} cannot change while it is allocated",
+                mfg_batch.mfg_batch_id()
+            ),
+        ));
+    }
+
+    if quantity < mfg_batch.allocated_quantity() {
+        return Err(MfgBatchError::validation(
+            "quantity",
+            format!(
+                "Quantity {} of mfg_batch {} is less than the {} allocated",
+                quantity,
+                mfg_batch.mfg_batch_id(),
+                mfg_batch.allocated_quantity()
+            ),
+        ));
+    }
+
+    Ok(())
 }
 
-fn convert_char_to_int(c: char) -> i32 {
-    char::to_digit(c, 10).expect("No conversion for char to i32") as i32
+fn validate_order_id(order_id: &str) -> Result<(), MfgBatchError> {
+    if order_id.is_empty() {
+        return Err(MfgBatchError::validation(
+            "order_id",
+            "An allocation requires an order ID".to_string(),
+        ));
+    }
+
+    if order_id.chars().count() > MAX_STRING_VALUE_LENGTH {
+        return Err(MfgBatchError::validation(
+            "order_id",
+            format!(
+                "Order ID may not be longer than {} characters",
+                MAX_STRING_VALUE_LENGTH
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a quality control test result before it is recorded against a mfg_batch.
+///
+/// The test name and result are required, no text field may exceed `MAX_STRING_VALUE_LENGTH`,
+/// and the test must have been performed at a recorded time no later than `MAX_DATE`.
+pub fn validate_test_result(test_result: &TestResult) -> Result<(), MfgBatchError> {
+    for (field, value) in &[
+        ("test_result.test_name", test_result.test_name()),
+        ("test_result.result", test_result.result()),
+    ] {
+        if value.is_empty() {
+            return Err(MfgBatchError::validation(
+                field,
+                "A test result requires a test name and a result".to_string(),
+            ));
+        }
+    }
+
+    for (field, value) in &[
+        ("test_name", test_result.test_name()),
+        ("specification", test_result.specification()),
+        ("result", test_result.result()),
+        ("lab", test_result.lab()),
+    ] {
+        if value.chars().count() > MAX_STRING_VALUE_LENGTH {
+            return Err(MfgBatchError::validation(
+                &format!("test_result.{}", field),
+                format!(
+                    "Test result {} may not be longer than {} characters",
+                    field, MAX_STRING_VALUE_LENGTH
+                ),
+            ));
+        }
+    }
+
+    if test_result.timestamp() == 0 || test_result.timestamp() > MAX_DATE {
+        return Err(MfgBatchError::validation(
+            "test_result.timestamp",
+            format!(
+                "Test result timestamp must be between 1 and {}: {}",
+                MAX_DATE,
+                test_result.timestamp()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates an anchor before it is recorded.
+///
+/// The anchor must name its owner, be taken at a commit number that is not negative and carry a
+/// Merkle root that is a lowercase hex encoded SHA-256 hash.
+pub fn validate_anchor(anchor: &MfgBatchAnchorAction) -> Result<(), MfgBatchError> {
+    if anchor.owner().is_empty() {
+        return Err(MfgBatchError::validation(
+            "owner",
+            "An anchor requires an owner".to_string(),
+        ));
+    }
+
+    if anchor.commit_num() < 0 {
+        return Err(MfgBatchError::validation(
+            "commit_num",
+            format!(
+                "Anchor commit number may not be negative: {}",
+                anchor.commit_num()
+            ),
+        ));
+    }
+
+    let merkle_root = anchor.merkle_root();
+    if merkle_root.len() != 64
+        || !merkle_root
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        return Err(MfgBatchError::validation(
+            "merkle_root",
+            format!(
+                "Anchor Merkle root must be 64 lowercase hex characters: {}",
+                merkle_root
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a recall before the batches it lists are flagged.
+///
+/// The recall must have an ID and a reason code, neither longer than `MAX_STRING_VALUE_LENGTH`
+/// characters, and may list each batch only once.
+pub fn validate_recall(recall: &MfgBatchRecallAction) -> Result<(), MfgBatchError> {
+    for (field, value) in &[
+        ("recall_id", recall.recall_id()),
+        ("reason_code", recall.reason_code()),
+    ] {
+        if value.is_empty() {
+            return Err(MfgBatchError::validation(
+                field,
+                "A recall requires a recall ID and a reason code".to_string(),
+            ));
+        }
+
+        if value.chars().count() > MAX_STRING_VALUE_LENGTH {
+            return Err(MfgBatchError::validation(
+                field,
+                format!(
+                    "Recall ID and reason code may not be longer than {} characters",
+                    MAX_STRING_VALUE_LENGTH
+                ),
+            ));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for mfg_batch_id in recall.mfg_batch_ids() {
+        if !seen.insert(mfg_batch_id) {
+            return Err(MfgBatchError::validation(
+                "mfg_batch_ids",
+                format!(
+                    "Mfg_batch {} is listed more than once in recall {}",
+                    mfg_batch_id,
+                    recall.recall_id()
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates an attestation before it is recorded against a mfg_batch.
+///
+/// The attestation must name its signer and purpose, use `ATTESTATION_ALGORITHM`, not repeat a
+/// signature already recorded, and carry a valid signature by its signer over the canonical
+/// bytes of the batch as it is now.
+pub fn validate_attestation(
+    mfg_batch: &MfgBatch,
+    attestation: &Attestation,
+) -> Result<(), MfgBatchError> {
+    for (field, value) in &[
+        (
+            "attestation.signer_public_key",
+            attestation.signer_public_key(),
+        ),
+        ("attestation.signature", attestation.signature()),
+        ("attestation.purpose", attestation.purpose()),
+    ] {
+        if value.is_empty() {
+            return Err(MfgBatchError::validation(
+                field,
+                "An attestation requires a signer public key, a signature and a purpose"
+                    .to_string(),
+            ));
+        }
+    }
+
+    if attestation.purpose().chars().count() > MAX_STRING_VALUE_LENGTH {
+        return Err(MfgBatchError::validation(
+            "attestation.purpose",
+            format!(
+                "Attestation purpose may not be longer than {} characters",
+                MAX_STRING_VALUE_LENGTH
+            ),
+        ));
+    }
+
+    if attestation.algorithm() != ATTESTATION_ALGORITHM {
+        return Err(MfgBatchError::validation(
+            "attestation.algorithm",
+            format!(
+                "Unsupported attestation algorithm {:?}; expected {:?}",
+                attestation.algorithm(),
+                ATTESTATION_ALGORITHM
+            ),
+        ));
+    }
+
+    if mfg_batch.attestations().iter().any(|recorded| {
+        recorded.signer_public_key() == attestation.signer_public_key()
+            && recorded.signature() == attestation.signature()
+    }) {
+        return Err(MfgBatchError::AlreadyExists(format!(
+            "Attestation by {} has already been recorded against {}",
+            attestation.signer_public_key(),
+            mfg_batch.mfg_batch_id()
+        )));
+    }
+
+    let message = mfg_batch.canonical_bytes().map_err(|err| {
+        MfgBatchError::Internal(format!("Cannot encode mfg_batch for verification: {}", err))
+    })?;
+
+    verify_attestation_signature(attestation, &message)
+}
+
+/// Verifies a secp256k1 signature over the SHA-256 digest of the message, as Sawtooth signers
+/// make them. The verifier is pure Rust, so signatures are checked the same way in Sabre.
+fn verify_attestation_signature(
+    attestation: &Attestation,
+    message: &[u8],
+) -> Result<(), MfgBatchError> {
+    let public_key = hex::decode(attestation.signer_public_key())
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_sec1_bytes(&bytes).ok())
+        .ok_or_else(|| {
+            MfgBatchError::validation(
+                "attestation.signer_public_key",
+                format!(
+                    "Invalid attestation signer public key: {}",
+                    attestation.signer_public_key()
+                ),
+            )
+        })?;
+    let signature = hex::decode(attestation.signature())
+        .ok()
+        .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| {
+            MfgBatchError::validation(
+                "attestation.signature",
+                "Invalid attestation signature: expected 64 hex-encoded bytes".to_string(),
+            )
+        })?;
+
+    public_key.verify(message, &signature).map_err(|_| {
+        MfgBatchError::validation(
+            "attestation.signature",
+            format!(
+                "Attestation signature by {} does not match the mfg_batch",
+                attestation.signer_public_key()
+            ),
+        )
+    })
+}
+
+/// Checks that a property value is well-formed for the schema property that defines it.
+pub fn validate_property_value(
+    value: &PropertyValue,
+    definition: &PropertyDefinition,
+) -> Result<(), MfgBatchError> {
+    rules::validate_property_value(value, definition).map_err(MfgBatchError::from)
+}
+
+/// Checks the language tags of a mfg_batch's properties.
+pub fn validate_property_languages(properties: &[PropertyValue]) -> Result<(), MfgBatchError> {
+    rules::validate_property_languages(properties).map_err(MfgBatchError::from)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use grid_sdk::mfg_batch::validation::{
+        validate_gtin, validate_mfg_batch_id, MAX_INTERNAL_ID_LENGTH,
+    };
+
+    use cylinder::{secp256k1::Secp256k1Context, Context, PrivateKey};
+    use grid_sdk::protocol::mfg_batch::{
+        payload::{MfgBatchAnchorActionBuilder, MfgBatchRecallActionBuilder},
+        state::{AllocationBuilder, AttestationBuilder, MfgBatchBuilder, TestResultBuilder},
+    };
+    use grid_sdk::protocol::schema::state::{
+        DataType, PropertyDefinitionBuilder, PropertyValueBuilder,
+    };
+
     #[test]
     // This tests that the check-digit validation of the valid gtin-12: "688955434684" is true
     fn valid_gtin_12() {
@@ -136,7 +601,7 @@ mod tests {
     fn invalid_gtin_12() {
         assert_eq!(
             validate_gtin("688955434584").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid gtin, check digit validation failed: 688955434584"
+            "Invalid gtin, check digit validation failed: 688955434584"
         );
     }
 
@@ -151,7 +616,7 @@ mod tests {
     fn invalid_gtin_13() {
         assert_eq!(
             validate_gtin("9781981855738").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid gtin, check digit validation failed: 9781981855738"
+            "Invalid gtin, check digit validation failed: 9781981855738"
         );
     }
 
@@ -166,7 +631,7 @@ mod tests {
     fn invalid_gtin_14() {
         assert_eq!(
             validate_gtin("10012345678912").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid gtin, check digit validation failed: 10012345678912"
+            "Invalid gtin, check digit validation failed: 10012345678912"
         );
     }
 
@@ -178,7 +643,7 @@ mod tests {
                 .err()
                 .unwrap()
                 .to_string(),
-            "InvalidTransaction: Invalid length for GTIN identifier: 10012345678923423423423412"
+            "Invalid length for GTIN identifier: 10012345678923423423423412"
         );
     }
 
@@ -187,7 +652,7 @@ mod tests {
     fn invalid_gtin_length_short() {
         assert_eq!(
             validate_gtin("123").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid length for GTIN identifier: 123"
+            "Invalid length for GTIN identifier: 123"
         );
     }
 
@@ -196,17 +661,528 @@ mod tests {
     fn invalid_gtin_format() {
         assert_eq!(
             validate_gtin("1012938473jer").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid format, GTIN identifiers only contain numbers: 1012938473jer"
+            "Invalid format, GTIN identifiers only contain numbers: 1012938473jer"
         );
     }
 
     #[test]
-    // This tests that a valid gtin-8 returns an error (not supported)
+    // This tests that the check-digit validation of the valid gtin-8: "40170725" is true
     fn valid_gtin_8() {
-        assert!(validate_gtin("40170725").is_err());
+        assert!(validate_gtin("40170725").is_ok());
+    }
+
+    #[test]
+    // This tests that the check-digit validation of the valid gtin-8: "40170735" is false
+    fn invalid_gtin_8() {
+        assert_eq!(
+            validate_gtin("40170735").err().unwrap().to_string(),
+            "Invalid gtin, check digit validation failed: 40170735"
+        );
+    }
+
+    #[test]
+    // This tests that SSCCs are validated by their check digit
+    fn mfg_batch_id_sscc() {
         assert_eq!(
-            validate_gtin("40170725").err().unwrap().to_string(),
-            "InvalidTransaction: Invalid GTIN, GTIN-8 is not supported at this time: 40170725"
+            validate_mfg_batch_id("106141411234567897").unwrap(),
+            MfgBatchIdentifier::Sscc
         );
+        assert!(validate_mfg_batch_id("106141411234567898").is_err());
+    }
+
+    #[test]
+    // This tests that company-internal identifiers are accepted unless they look like a GTIN
+    fn mfg_batch_id_company_internal() {
+        assert_eq!(
+            validate_mfg_batch_id("LOT-2021-0042").unwrap(),
+            MfgBatchIdentifier::CompanyInternal
+        );
+        assert!(validate_mfg_batch_id("123").is_err());
+        assert!(validate_mfg_batch_id("LOT 2021").is_err());
+        assert!(validate_mfg_batch_id(&"L".repeat(MAX_INTERNAL_ID_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    // This tests that only GS1 batch IDs are checked as GS1 keys
+    fn namespaced_mfg_batch_id() {
+        assert!(validate_namespaced_mfg_batch_id(&MfgBatchNamespace::Gs1, "123").is_err());
+        assert_eq!(
+            validate_namespaced_mfg_batch_id(&MfgBatchNamespace::Internal, "123").unwrap(),
+            MfgBatchIdentifier::CompanyInternal
+        );
+        assert_eq!(
+            validate_namespaced_mfg_batch_id(&MfgBatchNamespace::Lot, "688955434685").unwrap(),
+            MfgBatchIdentifier::CompanyInternal
+        );
+        assert!(validate_namespaced_mfg_batch_id(&MfgBatchNamespace::Lot, "LOT 2021").is_err());
+    }
+
+    #[test]
+    // This tests that quantities need a known unit of measure and may not be negative
+    fn quantity_validation() {
+        assert!(validate_quantity(0, "", 0).is_ok());
+        assert!(validate_quantity(950, "KGM", 1000).is_ok());
+        assert!(validate_quantity(950, "", 0).is_err());
+        assert!(validate_quantity(950, "kg", 1000).is_err());
+        assert!(validate_quantity(-1, "KGM", 1000).is_err());
+        assert!(validate_quantity(950, "KGM", -1).is_err());
+    }
+
+    #[test]
+    // This tests that a batch expires after it was produced and neither date is too far out
+    fn date_validation() {
+        assert!(validate_dates(0, 0).is_ok());
+        assert!(validate_dates(1_600_000_000, 0).is_ok());
+        assert!(validate_dates(0, 1_631_536_000).is_ok());
+        assert!(validate_dates(1_600_000_000, 1_631_536_000).is_ok());
+        assert!(validate_dates(1_600_000_000, 1_600_000_000).is_err());
+        assert!(validate_dates(1_631_536_000, 1_600_000_000).is_err());
+        assert!(validate_dates(1_600_000_000, MAX_DATE + 1).is_err());
+        assert!(validate_dates(MAX_DATE + 1, 0).is_err());
+    }
+
+    #[test]
+    // This tests that a manufacture location is either unset or a GLN with a valid check digit
+    fn manufacture_location_validation() {
+        assert!(validate_manufacture_location("").is_ok());
+        assert!(validate_manufacture_location("0614141000005").is_ok());
+        assert!(validate_manufacture_location("0614141000006").is_err());
+        assert!(validate_manufacture_location("061414100000").is_err());
+        assert!(validate_manufacture_location("06141410000a5").is_err());
+    }
+
+    #[test]
+    // This tests that archived batches are rejected and others are not
+    fn archived_validation() {
+        let mfg_batch = MfgBatchBuilder::new()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("test_org".into())
+            .with_properties(vec![])
+            .build()
+            .expect("Failed to build mfg_batch");
+
+        assert!(validate_not_archived(&mfg_batch).is_ok());
+
+        let archived = mfg_batch
+            .into_builder()
+            .with_archived(true)
+            .build()
+            .expect("Failed to build mfg_batch");
+
+        assert!(validate_not_archived(&archived).is_err());
+    }
+
+    #[test]
+    // This tests that allocations need an order and a positive quantity, and together may not
+    // exceed the quantity produced; and that only what an order has reserved may be released
+    fn allocation_validation() {
+        let allocation = |order_id: &str, quantity: i64| {
+            AllocationBuilder::new()
+                .with_order_id(order_id.into())
+                .with_quantity(quantity)
+                .build()
+                .expect("Failed to build allocation")
+        };
+        let mfg_batch = MfgBatchBuilder::new()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("test_org".into())
+            .with_properties(vec![])
+            .with_quantity(100)
+            .with_uom("KGM".into())
+            .with_allocations(vec![allocation("SO-1", 60)])
+            .build()
+            .expect("Failed to build mfg_batch");
+
+        assert!(validate_allocation(&mfg_batch, "SO-2", 40).is_ok());
+        assert!(validate_allocation(&mfg_batch, "SO-1", 40).is_ok());
+        assert!(validate_allocation(&mfg_batch, "SO-2", 41).is_err());
+        assert!(validate_allocation(&mfg_batch, "SO-2", 0).is_err());
+        assert!(validate_allocation(&mfg_batch, "", 10).is_err());
+
+        let unquantified = MfgBatchBuilder::new()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("test_org".into())
+            .with_properties(vec![])
+            .build()
+            .expect("Failed to build mfg_batch");
+        assert!(validate_allocation(&unquantified, "SO-2", 1).is_err());
+
+        assert_eq!(validate_release(&mfg_batch, "SO-1", 0).unwrap(), 60);
+        assert!(validate_release(&mfg_batch, "SO-1", 60).is_ok());
+        assert!(validate_release(&mfg_batch, "SO-1", 61).is_err());
+        assert!(validate_release(&mfg_batch, "SO-1", -1).is_err());
+        assert!(validate_release(&mfg_batch, "SO-2", 10).is_err());
+
+        assert!(validate_allocated_quantity(&mfg_batch, 60, "KGM").is_ok());
+        assert!(validate_allocated_quantity(&mfg_batch, 59, "KGM").is_err());
+        assert!(validate_allocated_quantity(&mfg_batch, 100, "LTR").is_err());
+        assert!(validate_allocated_quantity(&unquantified, 0, "").is_ok());
+    }
+
+    #[test]
+    // This tests that a test result needs a name, a result and a time it was performed
+    fn test_result_validation() {
+        let test_result = TestResultBuilder::new()
+            .with_test_name("moisture".into())
+            .with_specification("<= 12.5 %".into())
+            .with_result("11.8 %".into())
+            .with_passed(true)
+            .with_lab("Acme Labs".into())
+            .with_timestamp(1_600_100_000)
+            .build()
+            .expect("Failed to build test result");
+        assert!(validate_test_result(&test_result).is_ok());
+
+        let mut builder = test_result.clone().into_builder();
+        builder.result = Some(String::new());
+        assert!(validate_test_result(&builder.build().unwrap()).is_err());
+
+        let mut builder = test_result.clone().into_builder();
+        builder.lab = Some("L".repeat(MAX_STRING_VALUE_LENGTH + 1));
+        assert!(validate_test_result(&builder.build().unwrap()).is_err());
+
+        let mut builder = test_result.into_builder();
+        builder.timestamp = Some(0);
+        assert!(validate_test_result(&builder.build().unwrap()).is_err());
+    }
+
+    #[test]
+    // This tests that an anchor needs an owner, a commit number and a SHA-256 Merkle root
+    fn anchor_validation() {
+        let anchor = |owner: &str, commit_num: i64, merkle_root: &str| {
+            MfgBatchAnchorActionBuilder::new()
+                .with_owner(owner.into())
+                .with_commit_num(commit_num)
+                .with_merkle_root(merkle_root.into())
+                .with_row_count(3)
+                .build()
+                .expect("Failed to build anchor")
+        };
+
+        assert!(validate_anchor(&anchor("test_org", 0, &"0f".repeat(32))).is_ok());
+        assert!(validate_anchor(&anchor("", 0, &"0f".repeat(32))).is_err());
+        assert!(validate_anchor(&anchor("test_org", -1, &"0f".repeat(32))).is_err());
+        assert!(validate_anchor(&anchor("test_org", 0, &"0F".repeat(32))).is_err());
+        assert!(validate_anchor(&anchor("test_org", 0, &"0f".repeat(31))).is_err());
+    }
+
+    #[test]
+    // This tests that a recall needs an ID and a reason code and lists each batch once
+    fn recall_validation() {
+        let recall = |mfg_batch_ids: &[&str], recall_id: &str, reason_code: &str| {
+            MfgBatchRecallActionBuilder::new()
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_mfg_batch_ids(mfg_batch_ids.iter().map(|id| id.to_string()).collect())
+                .with_recall_id(recall_id.into())
+                .with_reason_code(reason_code.into())
+                .build()
+                .expect("Failed to build recall")
+        };
+
+        assert!(validate_recall(&recall(&["flour", "dough"], "RC-1", "CONTAMINATION")).is_ok());
+        assert!(validate_recall(&recall(&["flour"], "", "CONTAMINATION")).is_err());
+        assert!(validate_recall(&recall(&["flour"], "RC-1", "")).is_err());
+        assert!(validate_recall(&recall(&["flour"], &"x".repeat(1025), "CONTAMINATION")).is_err());
+        assert!(validate_recall(&recall(&["flour"], &"é".repeat(1024), "CONTAMINATION")).is_ok());
+        assert!(validate_recall(&recall(&["flour", "flour"], "RC-1", "CONTAMINATION")).is_err());
+    }
+
+    #[test]
+    // This tests that an attestation is only accepted with a secp256k1 signature by its signer
+    // over the batch as it is, and only once
+    fn attestation_validation() {
+        let context = Secp256k1Context::new();
+        let private_key = PrivateKey::new_from_hex(
+            "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088",
+        )
+        .expect("Failed to load private key");
+        let public_key = context
+            .get_public_key(&private_key)
+            .expect("Failed to get public key")
+            .as_hex();
+
+        let mfg_batch = MfgBatchBuilder::new()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("test_org".into())
+            .with_properties(vec![])
+            .build()
+            .expect("Failed to build mfg_batch");
+        let signature = context
+            .new_signer(private_key)
+            .sign(&mfg_batch.canonical_bytes().unwrap())
+            .expect("Failed to sign mfg_batch")
+            .as_hex();
+        let attestation = AttestationBuilder::new()
+            .with_signer_public_key(public_key)
+            .with_algorithm(ATTESTATION_ALGORITHM.into())
+            .with_signature(signature)
+            .with_purpose("certificate_of_analysis".into())
+            .build()
+            .expect("Failed to build attestation");
+        assert!(validate_attestation(&mfg_batch, &attestation).is_ok());
+
+        let mut builder = attestation.clone().into_builder();
+        builder.algorithm = Some("ed25519".into());
+        assert!(validate_attestation(&mfg_batch, &builder.build().unwrap()).is_err());
+
+        let mut builder = attestation.clone().into_builder();
+        builder.purpose = Some(String::new());
+        assert!(validate_attestation(&mfg_batch, &builder.build().unwrap()).is_err());
+
+        let mut builder = attestation.clone().into_builder();
+        builder.signer_public_key = Some("not a key".into());
+        assert!(validate_attestation(&mfg_batch, &builder.build().unwrap()).is_err());
+
+        let changed = mfg_batch
+            .clone()
+            .into_builder()
+            .with_quantity(10)
+            .with_uom("KGM".into())
+            .build()
+            .expect("Failed to build mfg_batch");
+        assert_eq!(
+            validate_attestation(&changed, &attestation).err().unwrap(),
+            MfgBatchError::validation(
+                "attestation.signature",
+                format!(
+                    "Attestation signature by {} does not match the mfg_batch",
+                    attestation.signer_public_key()
+                )
+            )
+        );
+
+        let attested = mfg_batch
+            .into_builder()
+            .with_attestations(vec![attestation.clone()])
+            .build()
+            .expect("Failed to build mfg_batch");
+        assert!(validate_attestation(&attested, &attestation).is_err());
+    }
+
+    // Parents recorded for each batch in the genealogy tests below
+    fn genealogy(batch: &str) -> Result<Vec<String>, ApplyError> {
+        Ok(match batch {
+            "finished" => vec!["mixed".to_string()],
+            "mixed" => vec!["flour".to_string(), "sugar".to_string()],
+            _ => vec![],
+        })
+    }
+
+    #[test]
+    // This tests that parents unrelated to the batch are accepted
+    fn genealogy_without_cycle() {
+        assert!(validate_no_genealogy_cycle("finished", &["sugar".to_string()], genealogy).is_ok());
+    }
+
+    #[test]
+    // This tests that a batch cannot be its own parent
+    fn genealogy_self_parent() {
+        assert!(validate_no_genealogy_cycle("flour", &["flour".to_string()], genealogy).is_err());
+    }
+
+    #[test]
+    // This tests that a batch cannot become a parent of one of its descendants
+    fn genealogy_indirect_cycle() {
+        assert_eq!(
+            validate_no_genealogy_cycle("flour", &["finished".to_string()], genealogy)
+                .err()
+                .unwrap()
+                .to_string(),
+            "InvalidTransaction: [validation:parent_batches] Adding parents to flour would make it \
+             its own ancestor"
+        );
+    }
+
+    fn definition(name: &str, data_type: DataType) -> PropertyDefinitionBuilder {
+        PropertyDefinitionBuilder::new()
+            .with_name(name.into())
+            .with_data_type(data_type)
+    }
+
+    fn value(name: &str, data_type: DataType) -> PropertyValueBuilder {
+        PropertyValueBuilder::new()
+            .with_name(name.into())
+            .with_data_type(data_type)
+    }
+
+    fn localized(name: &str, language: &str) -> PropertyValue {
+        value(name, DataType::String)
+            .with_string_value("description".into())
+            .with_language(language.into())
+            .build()
+            .expect("Failed to build property value")
+    }
+
+    #[test]
+    // This tests that only string properties may be localized, once per well-formed language
+    fn property_languages() {
+        let unlocalized = value("description", DataType::String)
+            .with_string_value("Flour".into())
+            .build()
+            .unwrap();
+        assert!(validate_property_languages(&[
+            unlocalized.clone(),
+            localized("description", "fr"),
+            localized("description", "fr-CA"),
+            localized("name", "fr"),
+        ])
+        .is_ok());
+
+        assert!(validate_property_languages(&[
+            localized("description", "fr"),
+            localized("description", "FR"),
+        ])
+        .is_err());
+        assert!(validate_property_languages(&[localized("description", "f")]).is_err());
+        assert!(validate_property_languages(&[localized("description", "fr_CA")]).is_err());
+        assert!(validate_property_languages(&[localized("description", "12-CA")]).is_err());
+
+        let number = value("weight", DataType::Number)
+            .with_number_value(5)
+            .with_language("fr".into())
+            .build()
+            .unwrap();
+        assert!(validate_property_languages(&[number]).is_err());
+
+        let with_localized_field = value("label", DataType::Struct)
+            .with_struct_values(vec![localized("description", "fr")])
+            .build()
+            .unwrap();
+        assert!(validate_property_languages(&[with_localized_field]).is_err());
+    }
+
+    #[test]
+    // This tests that values must have the data type of their definition
+    fn property_value_wrong_type() {
+        let definition = definition("weight", DataType::Number)
+            .with_number_exponent(0)
+            .build()
+            .unwrap();
+        let value = value("weight", DataType::String)
+            .with_string_value("heavy".into())
+            .build()
+            .unwrap();
+
+        assert!(validate_property_value(&value, &definition).is_err());
+    }
+
+    #[test]
+    // This tests that number values must fit in range once scaled by the exponent
+    fn property_value_number_exponent() {
+        let definition = definition("weight", DataType::Number)
+            .with_number_exponent(3)
+            .build()
+            .unwrap();
+        let small = value("weight", DataType::Number)
+            .with_number_value(42)
+            .build()
+            .unwrap();
+        let large = value("weight", DataType::Number)
+            .with_number_value(i64::MAX / 10)
+            .build()
+            .unwrap();
+
+        assert!(validate_property_value(&small, &definition).is_ok());
+        assert!(validate_property_value(&large, &definition).is_err());
+    }
+
+    #[test]
+    // This tests that enum values must index one of the definition's options
+    fn property_value_enum_bounds() {
+        let definition = definition("grade", DataType::Enum)
+            .with_enum_options(vec!["A".into(), "B".into()])
+            .build()
+            .unwrap();
+        let valid = value("grade", DataType::Enum)
+            .with_enum_value(1)
+            .build()
+            .unwrap();
+        let invalid = value("grade", DataType::Enum)
+            .with_enum_value(2)
+            .build()
+            .unwrap();
+
+        assert!(validate_property_value(&valid, &definition).is_ok());
+        assert_eq!(
+            validate_property_value(&invalid, &definition)
+                .err()
+                .unwrap(),
+            MfgBatchError::validation(
+                "properties.grade",
+                "Property 'grade' enum value 2 is not one of its 2 options".into()
+            )
+        );
+    }
+
+    #[test]
+    // This tests that string values may not exceed the maximum length
+    fn property_value_string_length() {
+        let definition = definition("lot", DataType::String).build().unwrap();
+        let value = value("lot", DataType::String)
+            .with_string_value("x".repeat(MAX_STRING_VALUE_LENGTH + 1))
+            .build()
+            .unwrap();
+
+        assert!(validate_property_value(&value, &definition).is_err());
+    }
+
+    #[test]
+    // This tests that struct values are checked against the definition's struct properties
+    fn property_value_struct_shape() {
+        let definition = definition("origin", DataType::Struct)
+            .with_struct_properties(vec![
+                definition("country", DataType::String)
+                    .with_required(true)
+                    .build()
+                    .unwrap(),
+                definition("grade", DataType::Enum)
+                    .with_enum_options(vec!["A".into()])
+                    .build()
+                    .unwrap(),
+            ])
+            .build()
+            .unwrap();
+        let country = value("country", DataType::String)
+            .with_string_value("US".into())
+            .build()
+            .unwrap();
+        let grade = value("grade", DataType::Enum)
+            .with_enum_value(0)
+            .build()
+            .unwrap();
+        let bad_grade = value("grade", DataType::Enum)
+            .with_enum_value(3)
+            .build()
+            .unwrap();
+        let unknown = value("region", DataType::String)
+            .with_string_value("Midwest".into())
+            .build()
+            .unwrap();
+
+        let valid = value("origin", DataType::Struct)
+            .with_struct_values(vec![country.clone()])
+            .build()
+            .unwrap();
+        let missing_required = value("origin", DataType::Struct)
+            .with_struct_values(vec![grade])
+            .build()
+            .unwrap();
+        let invalid_nested = value("origin", DataType::Struct)
+            .with_struct_values(vec![country.clone(), bad_grade])
+            .build()
+            .unwrap();
+        let unknown_field = value("origin", DataType::Struct)
+            .with_struct_values(vec![country, unknown])
+            .build()
+            .unwrap();
+
+        assert!(validate_property_value(&valid, &definition).is_ok());
+        assert!(validate_property_value(&missing_required, &definition).is_err());
+        assert!(validate_property_value(&invalid_nested, &definition).is_err());
+        assert!(validate_property_value(&unknown_field, &definition).is_err());
     }
 }
@@ -0,0 +1,227 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Administers the API keys clients present to the REST API and gRPC server. A key's secret is
+//! only shown when it is created; the database keeps a hash of it.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::api_keys::{
+    store::{ApiKey, ApiKeyStore},
+    ApiKeyToken, Scope,
+};
+use grid_sdk::store::{create_store_factory, ConnectionUri};
+use rand::RngCore;
+
+use crate::error::DaemonError;
+
+const KEY_ID_LENGTH: usize = 8;
+const SECRET_LENGTH: usize = 32;
+
+/// Runs the `api-key` subcommand against the database at `database_url`
+pub fn run_api_key_command(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let connection_uri = database_url
+        .parse::<ConnectionUri>()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store_factory = create_store_factory(&connection_uri)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store = store_factory.get_api_key_store();
+
+    match matches.subcommand() {
+        ("create", Some(m)) => {
+            let scopes = m
+                .values_of("scope")
+                .map(|values| values.map(str::parse).collect::<Result<Vec<Scope>, _>>())
+                .transpose()
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?
+                .unwrap_or_default();
+            create_api_key(&*store, m.value_of("org").unwrap_or_default(), scopes, out)
+        }
+        ("revoke", Some(m)) => revoke_api_key(&*store, m.value_of("key_id").unwrap_or_default()),
+        ("list", Some(m)) => list_api_keys(&*store, m.value_of("org"), out),
+        ("usage", Some(m)) => {
+            let limit =
+                value_t!(m, "limit", i64).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            list_api_key_usage(&*store, m.value_of("key_id"), limit, out)
+        }
+        _ => Err(DaemonError::with_message(
+            "An api-key subcommand is required",
+        )),
+    }
+}
+
+/// Issues a new key to `org_id` and writes its id and token. The token is the only time the
+/// secret is available.
+pub fn create_api_key(
+    store: &dyn ApiKeyStore,
+    org_id: &str,
+    scopes: Vec<Scope>,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    if scopes.is_empty() {
+        return Err(DaemonError::with_message(
+            "An API key needs at least one scope",
+        ));
+    }
+
+    let token = ApiKeyToken::new(random_hex(KEY_ID_LENGTH), random_hex(SECRET_LENGTH));
+    store
+        .add_api_key(ApiKey {
+            key_id: token.key_id.clone(),
+            org_id: org_id.to_string(),
+            secret_hash: token.secret_hash(),
+            scopes,
+            created_at: now(),
+            revoked_at: None,
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    writeln!(out, "{}\t{}", token.key_id, token)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Revokes a key; requests presenting it are refused from then on
+pub fn revoke_api_key(store: &dyn ApiKeyStore, key_id: &str) -> Result<(), DaemonError> {
+    store
+        .revoke_api_key(key_id, now())
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Writes the keys issued to `org_id`, or every key, one per line
+pub fn list_api_keys(
+    store: &dyn ApiKeyStore,
+    org_id: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let api_keys = store
+        .list_api_keys(org_id)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for api_key in api_keys {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}",
+            api_key.key_id,
+            api_key.org_id,
+            api_key
+                .scopes
+                .iter()
+                .map(Scope::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            api_key.created_at,
+            api_key
+                .revoked_at
+                .map(|revoked_at| revoked_at.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the most recent uses of the key `key_id`, or of every key, one per line
+pub fn list_api_key_usage(
+    store: &dyn ApiKeyStore,
+    key_id: Option<&str>,
+    limit: i64,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let usage = store
+        .list_api_key_usage(key_id, 0, limit)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for usage in usage {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            usage.used_at,
+            usage.key_id,
+            usage.scope,
+            usage.method,
+            usage.path,
+            if usage.allowed { "allowed" } else { "refused" },
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn random_hex(length: usize) -> String {
+    let mut bytes = vec![0; length];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use grid_sdk::api_keys::store::DieselApiKeyStore;
+    use grid_sdk::migrations::run_sqlite_migrations;
+
+    /// Verify that a created key is listed with its scopes, that the token written can be
+    /// parsed and matches the stored hash, and that revoking it is listed
+    #[test]
+    fn test_create_list_revoke() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        let store = DieselApiKeyStore::new(pool);
+
+        let mut out = vec![];
+        create_api_key(&store, "org", vec![Scope::MfgBatchRead], &mut out)
+            .expect("Failed to create key");
+        let out = String::from_utf8(out).expect("Output is not UTF-8");
+        let (key_id, token) = out.trim().split_once('\t').expect("Malformed output");
+        let token = token.parse::<ApiKeyToken>().expect("Malformed token");
+        assert_eq!(token.key_id, key_id);
+
+        let api_key = store
+            .get_api_key(key_id)
+            .expect("Failed to get key")
+            .expect("Key not found");
+        assert_eq!(api_key.secret_hash, token.secret_hash());
+
+        assert!(create_api_key(&store, "org", vec![], &mut vec![]).is_err());
+
+        revoke_api_key(&store, key_id).expect("Failed to revoke key");
+        let mut out = vec![];
+        list_api_keys(&store, Some("org"), &mut out).expect("Failed to list keys");
+        let out = String::from_utf8(out).expect("Output is not UTF-8");
+        let fields = out.trim().split('\t').collect::<Vec<_>>();
+        assert_eq!(&fields[..3], &[key_id, "org", "mfg_batch:read"]);
+        assert_ne!(fields[4], "-");
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Removes the API usage the retention policy no longer keeps.
+//!
+//! Each hour, the retention service removes the daily usage rollups and the per-request
+//! key-usage log entries older than the configured number of days, so the tables the API usage
+//! analytics are kept in do not grow without bound.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use grid_sdk::api_keys::analytics::UsageRetentionPolicy;
+use grid_sdk::store::TransactionalStoreFactory;
+
+use crate::config::GridConfig;
+use crate::error::DaemonError;
+
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct UsageRetentionShutdownHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl UsageRetentionShutdownHandle {
+    pub fn shutdown(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Starts the service if the configuration limits how long API usage is kept
+pub fn run_from_config(
+    config: &GridConfig,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+) -> Result<Option<(UsageRetentionShutdownHandle, thread::JoinHandle<()>)>, DaemonError> {
+    let mut policy = UsageRetentionPolicy::new();
+    if let Some(days) = config.api_usage_retention_days() {
+        policy = policy.with_rollup_days(days);
+    }
+    if let Some(days) = config.api_key_usage_retention_days() {
+        policy = policy.with_usage_log_days(days);
+    }
+
+    if policy.is_unlimited() {
+        return Ok(None);
+    }
+
+    run(policy, store_factory).map(Some)
+}
+
+pub fn run(
+    policy: UsageRetentionPolicy,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+) -> Result<(UsageRetentionShutdownHandle, thread::JoinHandle<()>), DaemonError> {
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let join_handle = thread::Builder::new()
+        .name("UsageRetention".into())
+        .spawn(move || {
+            while thread_running.load(Ordering::SeqCst) {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs() as i64)
+                    .unwrap_or_default();
+                match policy.apply(&*store_factory.get_api_key_store(), now) {
+                    Ok((0, 0)) => (),
+                    Ok((usage_removed, rollups_removed)) => info!(
+                        "Removed {} API key uses and {} API usage rollups past retention",
+                        usage_removed, rollups_removed
+                    ),
+                    Err(err) => error!("Unable to remove API usage past retention: {}", err),
+                }
+
+                let mut waited = Duration::from_secs(0);
+                while waited < RETENTION_INTERVAL && thread_running.load(Ordering::SeqCst) {
+                    thread::sleep(SHUTDOWN_CHECK_INTERVAL);
+                    waited += SHUTDOWN_CHECK_INTERVAL;
+                }
+            }
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok((UsageRetentionShutdownHandle { running }, join_handle))
+}
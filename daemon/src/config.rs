@@ -26,6 +26,58 @@ pub struct GridConfig {
     admin_key_dir: String,
     #[cfg(feature = "integration")]
     key_file_name: String,
+    #[cfg(feature = "grpc")]
+    grpc_endpoint: Option<String>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_pseudonym_key_file: Option<String>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_internal_properties: Vec<String>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_admin_token_file: Option<String>,
+    #[cfg(feature = "api-keys")]
+    require_api_keys: bool,
+    #[cfg(feature = "data-mapping")]
+    mapping_dir: Option<String>,
+    #[cfg(feature = "mfg-batch-certificates")]
+    certificate_template_dir: Option<String>,
+    #[cfg(feature = "mfg-batch-history")]
+    mfg_batch_max_response_size: Option<usize>,
+    #[cfg(feature = "mfg-batch-sharding")]
+    mfg_batch_shard_urls: Vec<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_from: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_csv_mapping: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_edi_mapping: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_gdsn_owner: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_service_id: Option<String>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_interval: Option<u64>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_owner: Option<String>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_service_id: Option<String>,
+    #[cfg(feature = "sla-timers")]
+    sla_interval: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_proposal_threshold: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_hold_threshold: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_service_id: Option<String>,
+    #[cfg(feature = "log-masking")]
+    log_mask_properties: Vec<String>,
+    #[cfg(feature = "mfg-batch-retry")]
+    mfg_batch_store_max_attempts: Option<u32>,
+    #[cfg(feature = "mfg-batch-retry")]
+    mfg_batch_store_retry_backoff: Option<u64>,
+    #[cfg(feature = "api-usage-analytics")]
+    api_usage_retention_days: Option<u32>,
+    #[cfg(feature = "api-usage-analytics")]
+    api_key_usage_retention_days: Option<u32>,
 }
 
 impl GridConfig {
@@ -56,6 +108,136 @@ impl GridConfig {
     pub fn key_file_name(&self) -> &str {
         &self.key_file_name
     }
+
+    #[cfg(feature = "grpc")]
+    pub fn grpc_endpoint(&self) -> Option<&str> {
+        self.grpc_endpoint.as_deref()
+    }
+
+    #[cfg(feature = "grpc-pseudonyms")]
+    pub fn grpc_pseudonym_key_file(&self) -> Option<&str> {
+        self.grpc_pseudonym_key_file.as_deref()
+    }
+
+    #[cfg(feature = "grpc-pseudonyms")]
+    pub fn grpc_internal_properties(&self) -> &[String] {
+        &self.grpc_internal_properties
+    }
+
+    #[cfg(feature = "grpc-pseudonyms")]
+    pub fn grpc_admin_token_file(&self) -> Option<&str> {
+        self.grpc_admin_token_file.as_deref()
+    }
+
+    #[cfg(feature = "api-keys")]
+    pub fn require_api_keys(&self) -> bool {
+        self.require_api_keys
+    }
+
+    #[cfg(feature = "data-mapping")]
+    pub fn mapping_dir(&self) -> Option<&str> {
+        self.mapping_dir.as_deref()
+    }
+
+    #[cfg(feature = "mfg-batch-certificates")]
+    pub fn certificate_template_dir(&self) -> Option<&str> {
+        self.certificate_template_dir.as_deref()
+    }
+
+    #[cfg(feature = "mfg-batch-history")]
+    pub fn mfg_batch_max_response_size(&self) -> Option<usize> {
+        self.mfg_batch_max_response_size
+    }
+
+    #[cfg(feature = "mfg-batch-sharding")]
+    pub fn mfg_batch_shard_urls(&self) -> &[String] {
+        &self.mfg_batch_shard_urls
+    }
+
+    #[cfg(feature = "ingestion")]
+    pub fn ingest_from(&self) -> Option<&str> {
+        self.ingest_from.as_deref()
+    }
+
+    #[cfg(feature = "ingestion")]
+    pub fn ingest_csv_mapping(&self) -> Option<&str> {
+        self.ingest_csv_mapping.as_deref()
+    }
+
+    #[cfg(feature = "ingestion")]
+    pub fn ingest_edi_mapping(&self) -> Option<&str> {
+        self.ingest_edi_mapping.as_deref()
+    }
+
+    #[cfg(feature = "ingestion")]
+    pub fn ingest_gdsn_owner(&self) -> Option<&str> {
+        self.ingest_gdsn_owner.as_deref()
+    }
+
+    #[cfg(feature = "ingestion")]
+    pub fn ingest_service_id(&self) -> Option<&str> {
+        self.ingest_service_id.as_deref()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    pub fn anchor_interval(&self) -> Option<u64> {
+        self.anchor_interval
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    pub fn anchor_owner(&self) -> Option<&str> {
+        self.anchor_owner.as_deref()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    pub fn anchor_service_id(&self) -> Option<&str> {
+        self.anchor_service_id.as_deref()
+    }
+
+    #[cfg(feature = "sla-timers")]
+    pub fn sla_interval(&self) -> Option<u64> {
+        self.sla_interval
+    }
+
+    #[cfg(feature = "sla-timers")]
+    pub fn sla_proposal_threshold(&self) -> Option<u64> {
+        self.sla_proposal_threshold
+    }
+
+    #[cfg(feature = "sla-timers")]
+    pub fn sla_hold_threshold(&self) -> Option<u64> {
+        self.sla_hold_threshold
+    }
+
+    #[cfg(feature = "sla-timers")]
+    pub fn sla_service_id(&self) -> Option<&str> {
+        self.sla_service_id.as_deref()
+    }
+
+    #[cfg(feature = "log-masking")]
+    pub fn log_mask_properties(&self) -> &[String] {
+        &self.log_mask_properties
+    }
+
+    #[cfg(feature = "mfg-batch-retry")]
+    pub fn mfg_batch_store_max_attempts(&self) -> Option<u32> {
+        self.mfg_batch_store_max_attempts
+    }
+
+    #[cfg(feature = "mfg-batch-retry")]
+    pub fn mfg_batch_store_retry_backoff(&self) -> Option<u64> {
+        self.mfg_batch_store_retry_backoff
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    pub fn api_usage_retention_days(&self) -> Option<u32> {
+        self.api_usage_retention_days
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    pub fn api_key_usage_retention_days(&self) -> Option<u32> {
+        self.api_key_usage_retention_days
+    }
 }
 
 pub struct GridConfigBuilder {
@@ -66,6 +248,58 @@ pub struct GridConfigBuilder {
     admin_key_dir: Option<String>,
     #[cfg(feature = "integration")]
     key_file_name: Option<String>,
+    #[cfg(feature = "grpc")]
+    grpc_endpoint: Option<String>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_pseudonym_key_file: Option<String>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_internal_properties: Option<Vec<String>>,
+    #[cfg(feature = "grpc-pseudonyms")]
+    grpc_admin_token_file: Option<String>,
+    #[cfg(feature = "api-keys")]
+    require_api_keys: bool,
+    #[cfg(feature = "data-mapping")]
+    mapping_dir: Option<String>,
+    #[cfg(feature = "mfg-batch-certificates")]
+    certificate_template_dir: Option<String>,
+    #[cfg(feature = "mfg-batch-history")]
+    mfg_batch_max_response_size: Option<usize>,
+    #[cfg(feature = "mfg-batch-sharding")]
+    mfg_batch_shard_urls: Option<Vec<String>>,
+    #[cfg(feature = "ingestion")]
+    ingest_from: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_csv_mapping: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_edi_mapping: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_gdsn_owner: Option<String>,
+    #[cfg(feature = "ingestion")]
+    ingest_service_id: Option<String>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_interval: Option<u64>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_owner: Option<String>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    anchor_service_id: Option<String>,
+    #[cfg(feature = "sla-timers")]
+    sla_interval: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_proposal_threshold: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_hold_threshold: Option<u64>,
+    #[cfg(feature = "sla-timers")]
+    sla_service_id: Option<String>,
+    #[cfg(feature = "log-masking")]
+    log_mask_properties: Option<Vec<String>>,
+    #[cfg(feature = "mfg-batch-retry")]
+    mfg_batch_store_max_attempts: Option<u32>,
+    #[cfg(feature = "mfg-batch-retry")]
+    mfg_batch_store_retry_backoff: Option<u64>,
+    #[cfg(feature = "api-usage-analytics")]
+    api_usage_retention_days: Option<u32>,
+    #[cfg(feature = "api-usage-analytics")]
+    api_key_usage_retention_days: Option<u32>,
 }
 
 impl Default for GridConfigBuilder {
@@ -78,6 +312,58 @@ impl Default for GridConfigBuilder {
             admin_key_dir: Some("/etc/grid/keys".to_owned()),
             #[cfg(feature = "integration")]
             key_file_name: Some("root".to_string()),
+            #[cfg(feature = "grpc")]
+            grpc_endpoint: None,
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_pseudonym_key_file: None,
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_internal_properties: Some(Vec::new()),
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_admin_token_file: None,
+            #[cfg(feature = "api-keys")]
+            require_api_keys: false,
+            #[cfg(feature = "data-mapping")]
+            mapping_dir: None,
+            #[cfg(feature = "mfg-batch-certificates")]
+            certificate_template_dir: None,
+            #[cfg(feature = "mfg-batch-history")]
+            mfg_batch_max_response_size: None,
+            #[cfg(feature = "mfg-batch-sharding")]
+            mfg_batch_shard_urls: Some(Vec::new()),
+            #[cfg(feature = "ingestion")]
+            ingest_from: None,
+            #[cfg(feature = "ingestion")]
+            ingest_csv_mapping: None,
+            #[cfg(feature = "ingestion")]
+            ingest_edi_mapping: None,
+            #[cfg(feature = "ingestion")]
+            ingest_gdsn_owner: None,
+            #[cfg(feature = "ingestion")]
+            ingest_service_id: None,
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_interval: None,
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_owner: None,
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_service_id: None,
+            #[cfg(feature = "sla-timers")]
+            sla_interval: None,
+            #[cfg(feature = "sla-timers")]
+            sla_proposal_threshold: None,
+            #[cfg(feature = "sla-timers")]
+            sla_hold_threshold: None,
+            #[cfg(feature = "sla-timers")]
+            sla_service_id: None,
+            #[cfg(feature = "log-masking")]
+            log_mask_properties: Some(Vec::new()),
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_max_attempts: None,
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_retry_backoff: None,
+            #[cfg(feature = "api-usage-analytics")]
+            api_usage_retention_days: None,
+            #[cfg(feature = "api-usage-analytics")]
+            api_key_usage_retention_days: None,
         }
     }
 }
@@ -112,6 +398,150 @@ impl GridConfigBuilder {
                 .value_of("key")
                 .map(ToOwned::to_owned)
                 .or_else(|| self.key_file_name.take()),
+
+            #[cfg(feature = "grpc")]
+            grpc_endpoint: matches
+                .value_of("grpc_bind")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.grpc_endpoint.take()),
+
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_pseudonym_key_file: matches
+                .value_of("grpc_pseudonym_key_file")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.grpc_pseudonym_key_file.take()),
+
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_internal_properties: matches
+                .values_of("grpc_internal_property")
+                .map(|values| values.map(ToOwned::to_owned).collect())
+                .or_else(|| self.grpc_internal_properties.take()),
+
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_admin_token_file: matches
+                .value_of("grpc_admin_token_file")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.grpc_admin_token_file.take()),
+
+            #[cfg(feature = "api-keys")]
+            require_api_keys: matches.is_present("require_api_keys") || self.require_api_keys,
+
+            #[cfg(feature = "data-mapping")]
+            mapping_dir: matches
+                .value_of("mapping_dir")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.mapping_dir.take()),
+
+            #[cfg(feature = "mfg-batch-certificates")]
+            certificate_template_dir: matches
+                .value_of("certificate_template_dir")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.certificate_template_dir.take()),
+
+            #[cfg(feature = "mfg-batch-history")]
+            mfg_batch_max_response_size: matches
+                .value_of("mfg_batch_max_response_size")
+                .and_then(|size| size.parse().ok())
+                .or_else(|| self.mfg_batch_max_response_size.take()),
+
+            #[cfg(feature = "mfg-batch-sharding")]
+            mfg_batch_shard_urls: matches
+                .values_of("mfg_batch_shard_url")
+                .map(|values| values.map(ToOwned::to_owned).collect())
+                .or_else(|| self.mfg_batch_shard_urls.take()),
+
+            #[cfg(feature = "ingestion")]
+            ingest_from: matches
+                .value_of("ingest_from")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.ingest_from.take()),
+
+            #[cfg(feature = "ingestion")]
+            ingest_csv_mapping: matches
+                .value_of("ingest_csv_mapping")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.ingest_csv_mapping.take()),
+
+            #[cfg(feature = "ingestion")]
+            ingest_edi_mapping: matches
+                .value_of("ingest_edi_mapping")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.ingest_edi_mapping.take()),
+
+            #[cfg(feature = "ingestion")]
+            ingest_gdsn_owner: matches
+                .value_of("ingest_gdsn_owner")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.ingest_gdsn_owner.take()),
+
+            #[cfg(feature = "ingestion")]
+            ingest_service_id: matches
+                .value_of("ingest_service_id")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.ingest_service_id.take()),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_interval: matches
+                .value_of("anchor_interval")
+                .and_then(|interval| interval.parse().ok())
+                .or_else(|| self.anchor_interval.take()),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_owner: matches
+                .value_of("anchor_owner")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.anchor_owner.take()),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_service_id: matches
+                .value_of("anchor_service_id")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.anchor_service_id.take()),
+            #[cfg(feature = "sla-timers")]
+            sla_interval: matches
+                .value_of("sla_interval")
+                .and_then(|interval| interval.parse().ok())
+                .or_else(|| self.sla_interval.take()),
+            #[cfg(feature = "sla-timers")]
+            sla_proposal_threshold: matches
+                .value_of("sla_proposal_threshold")
+                .and_then(|threshold| threshold.parse().ok())
+                .or_else(|| self.sla_proposal_threshold.take()),
+            #[cfg(feature = "sla-timers")]
+            sla_hold_threshold: matches
+                .value_of("sla_hold_threshold")
+                .and_then(|threshold| threshold.parse().ok())
+                .or_else(|| self.sla_hold_threshold.take()),
+            #[cfg(feature = "sla-timers")]
+            sla_service_id: matches
+                .value_of("sla_service_id")
+                .map(ToOwned::to_owned)
+                .or_else(|| self.sla_service_id.take()),
+
+            #[cfg(feature = "log-masking")]
+            log_mask_properties: matches
+                .values_of("log_mask_property")
+                .map(|values| values.map(ToOwned::to_owned).collect())
+                .or_else(|| self.log_mask_properties.take()),
+
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_max_attempts: matches
+                .value_of("mfg_batch_store_max_attempts")
+                .and_then(|attempts| attempts.parse().ok())
+                .or_else(|| self.mfg_batch_store_max_attempts.take()),
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_retry_backoff: matches
+                .value_of("mfg_batch_store_retry_backoff")
+                .and_then(|backoff| backoff.parse().ok())
+                .or_else(|| self.mfg_batch_store_retry_backoff.take()),
+
+            #[cfg(feature = "api-usage-analytics")]
+            api_usage_retention_days: matches
+                .value_of("api_usage_retention_days")
+                .and_then(|days| days.parse().ok())
+                .or_else(|| self.api_usage_retention_days.take()),
+            #[cfg(feature = "api-usage-analytics")]
+            api_key_usage_retention_days: matches
+                .value_of("api_key_usage_retention_days")
+                .and_then(|days| days.parse().ok())
+                .or_else(|| self.api_key_usage_retention_days.take()),
         }
     }
 
@@ -139,6 +569,64 @@ impl GridConfigBuilder {
                 .key_file_name
                 .take()
                 .ok_or_else(|| ConfigurationError::MissingValue("key_file_name".to_owned()))?,
+            #[cfg(feature = "grpc")]
+            grpc_endpoint: self.grpc_endpoint.take(),
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_pseudonym_key_file: self.grpc_pseudonym_key_file.take(),
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_internal_properties: self.grpc_internal_properties.take().ok_or_else(|| {
+                ConfigurationError::MissingValue("grpc_internal_properties".to_owned())
+            })?,
+            #[cfg(feature = "grpc-pseudonyms")]
+            grpc_admin_token_file: self.grpc_admin_token_file.take(),
+            #[cfg(feature = "api-keys")]
+            require_api_keys: self.require_api_keys,
+            #[cfg(feature = "data-mapping")]
+            mapping_dir: self.mapping_dir.take(),
+            #[cfg(feature = "mfg-batch-certificates")]
+            certificate_template_dir: self.certificate_template_dir.take(),
+            #[cfg(feature = "mfg-batch-history")]
+            mfg_batch_max_response_size: self.mfg_batch_max_response_size.take(),
+            #[cfg(feature = "mfg-batch-sharding")]
+            mfg_batch_shard_urls: self.mfg_batch_shard_urls.take().ok_or_else(|| {
+                ConfigurationError::MissingValue("mfg_batch_shard_urls".to_owned())
+            })?,
+            #[cfg(feature = "ingestion")]
+            ingest_from: self.ingest_from.take(),
+            #[cfg(feature = "ingestion")]
+            ingest_csv_mapping: self.ingest_csv_mapping.take(),
+            #[cfg(feature = "ingestion")]
+            ingest_edi_mapping: self.ingest_edi_mapping.take(),
+            #[cfg(feature = "ingestion")]
+            ingest_gdsn_owner: self.ingest_gdsn_owner.take(),
+            #[cfg(feature = "ingestion")]
+            ingest_service_id: self.ingest_service_id.take(),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_interval: self.anchor_interval.take(),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_owner: self.anchor_owner.take(),
+            #[cfg(feature = "mfg-batch-anchors")]
+            anchor_service_id: self.anchor_service_id.take(),
+            #[cfg(feature = "sla-timers")]
+            sla_interval: self.sla_interval.take(),
+            #[cfg(feature = "sla-timers")]
+            sla_proposal_threshold: self.sla_proposal_threshold.take(),
+            #[cfg(feature = "sla-timers")]
+            sla_hold_threshold: self.sla_hold_threshold.take(),
+            #[cfg(feature = "sla-timers")]
+            sla_service_id: self.sla_service_id.take(),
+            #[cfg(feature = "log-masking")]
+            log_mask_properties: self.log_mask_properties.take().ok_or_else(|| {
+                ConfigurationError::MissingValue("log_mask_properties".to_owned())
+            })?,
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_max_attempts: self.mfg_batch_store_max_attempts.take(),
+            #[cfg(feature = "mfg-batch-retry")]
+            mfg_batch_store_retry_backoff: self.mfg_batch_store_retry_backoff.take(),
+            #[cfg(feature = "api-usage-analytics")]
+            api_usage_retention_days: self.api_usage_retention_days.take(),
+            #[cfg(feature = "api-usage-analytics")]
+            api_key_usage_retention_days: self.api_key_usage_retention_days.take(),
         })
     }
 }
@@ -16,15 +16,39 @@
  */
 
 pub mod error;
+#[cfg(feature = "reindex")]
+pub mod reindex;
 
 use std::ops::Deref;
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+use std::sync::Arc;
+#[cfg(feature = "mfg-batch-retry")]
+use std::time::Duration;
 
 use diesel::{
     pg::PgConnection,
     r2d2::{ConnectionManager, Pool, PooledConnection},
 };
+#[cfg(all(
+    feature = "log-masking",
+    any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch")
+))]
+use grid_sdk::log_masking::LogMask;
+#[cfg(feature = "mfg-batch-sharding")]
+use grid_sdk::mfg_batch::store::ShardedMfgBatchStore;
+#[cfg(feature = "mfg-batch-retry")]
+use grid_sdk::mfg_batch::store::{RetryPolicy, RetryingMfgBatchStore};
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+use grid_sdk::{
+    mfg_batch::store::{DieselMfgBatchStore, MfgBatchStore},
+    store::ConnectionUri,
+};
 
 pub use super::database::error::{ConnectionError, DatabaseError};
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+use crate::config::GridConfig;
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+use crate::error::DaemonError;
 
 pub struct Connection(PooledConnection<ConnectionManager<PgConnection>>);
 
@@ -60,3 +84,130 @@ impl<C: diesel::Connection> Clone for ConnectionPool<C> {
         }
     }
 }
+
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+pub type SharedMfgBatchStore = Arc<dyn MfgBatchStore + Send + Sync>;
+
+/// Creates the mfg_batch store the daemon's services share, partitioned by owner across the
+/// configured shards if any are given, and otherwise held in the daemon's database. Operations
+/// failing because the database is temporarily unavailable are retried as configured.
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+pub fn create_shared_mfg_batch_store(
+    config: &GridConfig,
+) -> Result<SharedMfgBatchStore, DaemonError> {
+    let store = open_shared_mfg_batch_store(config)?;
+
+    #[cfg(feature = "mfg-batch-retry")]
+    let store: SharedMfgBatchStore =
+        Arc::new(RetryingMfgBatchStore::new(store).with_policy(mfg_batch_retry_policy(config)));
+
+    Ok(store)
+}
+
+/// Returns the policy shared mfg_batch store operations are retried with, using the default
+/// for any setting not configured
+#[cfg(feature = "mfg-batch-retry")]
+fn mfg_batch_retry_policy(config: &GridConfig) -> RetryPolicy {
+    let default = RetryPolicy::default();
+
+    RetryPolicy::new(
+        config
+            .mfg_batch_store_max_attempts()
+            .unwrap_or_else(|| default.max_attempts()),
+        config
+            .mfg_batch_store_retry_backoff()
+            .map(Duration::from_millis)
+            .unwrap_or_else(|| default.base_backoff()),
+        default.max_backoff(),
+    )
+}
+
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+fn open_shared_mfg_batch_store(config: &GridConfig) -> Result<SharedMfgBatchStore, DaemonError> {
+    #[cfg(feature = "mfg-batch-sharding")]
+    {
+        if !config.mfg_batch_shard_urls().is_empty() {
+            return create_sharded_mfg_batch_store(
+                config.mfg_batch_shard_urls(),
+                #[cfg(feature = "log-masking")]
+                &LogMask::new(config.log_mask_properties()),
+            );
+        }
+    }
+
+    open_mfg_batch_store(
+        config.database_url(),
+        #[cfg(feature = "log-masking")]
+        &LogMask::new(config.log_mask_properties()),
+    )
+}
+
+/// Creates an mfg_batch store partitioned by owner across Postgres databases, with a connection
+/// pool for each
+#[cfg(feature = "mfg-batch-sharding")]
+fn create_sharded_mfg_batch_store(
+    shard_urls: &[String],
+    #[cfg(feature = "log-masking")] log_mask: &LogMask,
+) -> Result<SharedMfgBatchStore, DaemonError> {
+    let shards = shard_urls
+        .iter()
+        .map(|shard_url| {
+            let connection_pool: ConnectionPool<PgConnection> = ConnectionPool::new(shard_url)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let store = DieselMfgBatchStore::new(connection_pool.pool);
+            #[cfg(feature = "log-masking")]
+            let store = store.with_log_mask(log_mask.clone());
+            Ok(store)
+        })
+        .collect::<Result<Vec<_>, DaemonError>>()?;
+
+    Ok(Arc::new(
+        ShardedMfgBatchStore::new(shards)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+    ))
+}
+
+/// Creates an mfg_batch store with its own connection pool, which the gRPC server, the EDI
+/// watcher and the mfg_batch endpoints share to read mfg_batches outside of the REST API's store
+/// factory
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+pub fn create_mfg_batch_store(database_url: &str) -> Result<SharedMfgBatchStore, DaemonError> {
+    open_mfg_batch_store(
+        database_url,
+        #[cfg(feature = "log-masking")]
+        &LogMask::default(),
+    )
+}
+
+#[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+fn open_mfg_batch_store(
+    database_url: &str,
+    #[cfg(feature = "log-masking")] log_mask: &LogMask,
+) -> Result<SharedMfgBatchStore, DaemonError> {
+    let connection_uri = database_url
+        .parse()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    match connection_uri {
+        #[cfg(feature = "database-postgres")]
+        ConnectionUri::Postgres(_) => {
+            let connection_pool: ConnectionPool<diesel::pg::PgConnection> =
+                ConnectionPool::new(database_url)
+                    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let store = DieselMfgBatchStore::new(connection_pool.pool);
+            #[cfg(feature = "log-masking")]
+            let store = store.with_log_mask(log_mask.clone());
+            Ok(Arc::new(store))
+        }
+        #[cfg(feature = "database-sqlite")]
+        ConnectionUri::Sqlite(_) => {
+            let connection_pool: ConnectionPool<diesel::sqlite::SqliteConnection> =
+                ConnectionPool::new(database_url)
+                    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let store = DieselMfgBatchStore::new(connection_pool.pool);
+            #[cfg(feature = "log-masking")]
+            let store = store.with_log_mask(log_mask.clone());
+            Ok(Arc::new(store))
+        }
+    }
+}
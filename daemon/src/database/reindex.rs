@@ -0,0 +1,303 @@
+/*
+ * Copyright 2021 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+//! Builds the mfg_batch indexes on a running install. On PostgreSQL each index is built with
+//! `CREATE INDEX CONCURRENTLY`, so the daemon can keep writing while it runs, and the build's
+//! progress is polled and logged. SQLite cannot build indexes concurrently, so they are created
+//! directly.
+
+use std::thread;
+use std::time::Duration;
+
+use diesel::prelude::*;
+use grid_sdk::store::ConnectionUri;
+
+use crate::error::DaemonError;
+
+use super::DatabaseError;
+
+/// How often a running PostgreSQL index build's progress is logged
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct IndexDefinition {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub columns: &'static str,
+}
+
+impl IndexDefinition {
+    fn create_sql(&self, concurrently: bool) -> String {
+        format!(
+            "CREATE INDEX {}IF NOT EXISTS {} ON {} ({})",
+            if concurrently { "CONCURRENTLY " } else { "" },
+            self.name,
+            self.table,
+            self.columns
+        )
+    }
+}
+
+/// The indexes the mfg_batch store's queries rely on
+pub const MFG_BATCH_INDEXES: &[IndexDefinition] = &[
+    IndexDefinition {
+        name: "mfg_batch_mfg_batch_id_idx",
+        table: "mfg_batch",
+        columns: "mfg_batch_id, end_commit_num",
+    },
+    IndexDefinition {
+        name: "mfg_batch_owner_idx",
+        table: "mfg_batch",
+        columns: "owner",
+    },
+    IndexDefinition {
+        name: "mfg_batch_expiration_date_idx",
+        table: "mfg_batch",
+        columns: "expiration_date",
+    },
+    IndexDefinition {
+        name: "mfg_batch_manufacture_location_idx",
+        table: "mfg_batch",
+        columns: "manufacture_location",
+    },
+    IndexDefinition {
+        name: "mfg_batch_property_value_mfg_batch_id_idx",
+        table: "mfg_batch_property_value",
+        columns: "mfg_batch_id, end_commit_num",
+    },
+    IndexDefinition {
+        name: "mfg_batch_property_value_parent_property_idx",
+        table: "mfg_batch_property_value",
+        columns: "mfg_batch_id, parent_property",
+    },
+    IndexDefinition {
+        name: "mfg_batch_property_value_string_idx",
+        table: "mfg_batch_property_value",
+        columns: "property_name, string_value",
+    },
+    IndexDefinition {
+        name: "mfg_batch_property_value_number_idx",
+        table: "mfg_batch_property_value",
+        columns: "property_name, number_value",
+    },
+    IndexDefinition {
+        name: "mfg_batch_property_value_boolean_idx",
+        table: "mfg_batch_property_value",
+        columns: "property_name, boolean_value",
+    },
+    IndexDefinition {
+        name: "mfg_batch_parent_mfg_batch_id_idx",
+        table: "mfg_batch_parent",
+        columns: "mfg_batch_id, end_commit_num",
+    },
+];
+
+/// Creates any missing index in `indexes`, one at a time, waiting `throttle` between builds
+pub fn reindex(
+    database_url: &str,
+    indexes: &[IndexDefinition],
+    throttle: Duration,
+) -> Result<(), DaemonError> {
+    let connection_uri = database_url
+        .parse()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    match connection_uri {
+        #[cfg(feature = "database-postgres")]
+        ConnectionUri::Postgres(_) => pg::reindex(database_url, indexes, throttle),
+        #[cfg(feature = "database-sqlite")]
+        ConnectionUri::Sqlite(_) => sqlite::reindex(database_url, indexes, throttle),
+    }
+    .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+#[cfg(feature = "database-postgres")]
+mod pg {
+    use super::*;
+
+    use diesel::pg::PgConnection;
+    use diesel::sql_types::{BigInt, Bool, Text};
+    use diesel::{sql_query, QueryableByName};
+
+    #[derive(QueryableByName)]
+    struct IndexValidity {
+        #[sql_type = "Bool"]
+        valid: bool,
+    }
+
+    #[derive(QueryableByName)]
+    struct BuildProgress {
+        #[sql_type = "Text"]
+        phase: String,
+        #[sql_type = "BigInt"]
+        blocks_done: i64,
+        #[sql_type = "BigInt"]
+        blocks_total: i64,
+    }
+
+    pub fn reindex(
+        database_url: &str,
+        indexes: &[IndexDefinition],
+        throttle: Duration,
+    ) -> Result<(), DatabaseError> {
+        let conn = PgConnection::establish(database_url)?;
+
+        for (i, index) in indexes.iter().enumerate() {
+            match index_validity(&conn, index.name)? {
+                Some(true) => {
+                    info!("Index {} already exists", index.name);
+                    continue;
+                }
+                Some(false) => {
+                    // An interrupted concurrent build leaves an invalid index behind, which
+                    // IF NOT EXISTS would otherwise keep
+                    warn!("Dropping invalid index {} before rebuilding it", index.name);
+                    conn.execute(&format!("DROP INDEX CONCURRENTLY IF EXISTS {}", index.name))?;
+                }
+                None => (),
+            }
+
+            if i > 0 {
+                thread::sleep(throttle);
+            }
+
+            info!("Building index {} on {}", index.name, index.table);
+
+            // The build blocks its connection, so it runs on its own while this one polls
+            let build_conn = PgConnection::establish(database_url)?;
+            let build_sql = index.create_sql(true);
+            let build = thread::Builder::new()
+                .name(format!("reindex-{}", index.name))
+                .spawn(move || build_conn.execute(&build_sql))
+                .map_err(|err| DatabaseError::QueryError(Box::new(err)))?;
+
+            while !build.is_finished() {
+                thread::sleep(PROGRESS_POLL_INTERVAL);
+
+                if let Some(progress) = build_progress(&conn, index.table)? {
+                    info!(
+                        "Index {}: {} ({}/{} blocks)",
+                        index.name, progress.phase, progress.blocks_done, progress.blocks_total
+                    );
+                }
+            }
+
+            build.join().map_err(|_| {
+                DatabaseError::QueryError(format!("Index build for {} panicked", index.name).into())
+            })??;
+
+            info!("Built index {}", index.name);
+        }
+
+        Ok(())
+    }
+
+    fn index_validity(conn: &PgConnection, name: &str) -> Result<Option<bool>, DatabaseError> {
+        Ok(sql_query(
+            "SELECT i.indisvalid AS valid FROM pg_index i \
+             JOIN pg_class c ON c.oid = i.indexrelid WHERE c.relname = $1",
+        )
+        .bind::<Text, _>(name)
+        .load::<IndexValidity>(conn)?
+        .pop()
+        .map(|index| index.valid))
+    }
+
+    fn build_progress(
+        conn: &PgConnection,
+        table: &str,
+    ) -> Result<Option<BuildProgress>, DatabaseError> {
+        Ok(sql_query(
+            "SELECT phase, blocks_done, blocks_total FROM pg_stat_progress_create_index \
+             WHERE relid = $1::regclass",
+        )
+        .bind::<Text, _>(table)
+        .load::<BuildProgress>(conn)?
+        .pop())
+    }
+}
+
+#[cfg(feature = "database-sqlite")]
+mod sqlite {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+
+    pub fn reindex(
+        database_url: &str,
+        indexes: &[IndexDefinition],
+        throttle: Duration,
+    ) -> Result<(), DatabaseError> {
+        let conn = SqliteConnection::establish(database_url)?;
+
+        for (i, index) in indexes.iter().enumerate() {
+            if i > 0 {
+                thread::sleep(throttle);
+            }
+
+            info!("Building index {} on {}", index.name, index.table);
+            conn.execute(&index.create_sql(false))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+    use diesel::sql_types::Text;
+    use diesel::sqlite::SqliteConnection;
+    use diesel::{sql_query, QueryableByName};
+
+    #[derive(QueryableByName)]
+    struct IndexName {
+        #[sql_type = "Text"]
+        name: String,
+    }
+
+    /// Verify that reindexing SQLite creates each index and can be run again
+    #[test]
+    fn test_sqlite_reindex() {
+        let path = std::env::temp_dir().join(format!("grid-reindex-{}.db", std::process::id()));
+        let database_url = path.to_str().expect("Invalid temp path").to_string();
+
+        SqliteConnection::establish(&database_url)
+            .expect("Failed to connect")
+            .batch_execute("CREATE TABLE mfg_batch (owner TEXT, expiration_date BIGINT);")
+            .expect("Failed to create table");
+
+        let indexes = &MFG_BATCH_INDEXES[1..3];
+        reindex(&database_url, indexes, Duration::from_millis(0)).expect("Failed to reindex");
+        reindex(&database_url, indexes, Duration::from_millis(0)).expect("Failed to reindex");
+
+        let names = sql_query("SELECT name FROM sqlite_master WHERE type = 'index' ORDER BY name")
+            .load::<IndexName>(&SqliteConnection::establish(&database_url).unwrap())
+            .expect("Failed to list indexes")
+            .into_iter()
+            .map(|index| index.name)
+            .collect::<Vec<_>>();
+
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            names,
+            vec!["mfg_batch_expiration_date_idx", "mfg_batch_owner_idx"]
+        );
+    }
+}
@@ -0,0 +1,517 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+//! Test-only fault injection for the event pipeline.
+//!
+//! `ChaosEventConnection` wraps an event connection and randomly delays, duplicates and
+//! reorders the commit events it receives. Events are never dropped. The soak run feeds a
+//! generated chain with forks through it into the database event handler, and after every
+//! delivered event checks the commit store against a reference in-memory model of how the
+//! handler should treat duplicate, forked and new commits.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use diesel::{
+    r2d2::{ConnectionManager, Pool},
+    sqlite::SqliteConnection,
+};
+use grid_sdk::{
+    commits::{CommitStore, DieselCommitStore},
+    migrations::run_sqlite_migrations,
+    store::sqlite::SqliteStoreFactory,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::error::DaemonError;
+
+use super::{
+    db_handler::DatabaseEventHandler, CommitEvent, EventConnection, EventConnectionUnsubscriber,
+    EventHandler, EventIoError,
+};
+
+/// How often and how badly the chaos connection disturbs the events it passes on
+#[derive(Clone, Debug)]
+pub struct ChaosSettings {
+    /// Seeds the random choices, so that a failing run can be reproduced
+    pub seed: u64,
+    /// Chance that an event is delayed before it is delivered
+    pub delay_rate: f64,
+    /// Longest delay before an event is delivered
+    pub max_delay: Duration,
+    /// Chance that an event is delivered again later
+    pub duplicate_rate: f64,
+    /// Number of received events an event is picked from; 1 keeps the received order
+    pub reorder_window: usize,
+}
+
+impl Default for ChaosSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            delay_rate: 0.1,
+            max_delay: Duration::from_millis(5),
+            duplicate_rate: 0.2,
+            reorder_window: 4,
+        }
+    }
+}
+
+struct ChaosState {
+    rng: StdRng,
+    pending: Vec<CommitEvent>,
+    closed: Option<EventIoError>,
+}
+
+/// An event connection that randomly delays, duplicates and reorders the events of another.
+///
+/// Events are picked at random from a window of received events, so `recv` blocks until the
+/// window is full or the wrapped connection is closed.
+pub struct ChaosEventConnection<Conn: EventConnection> {
+    inner: Conn,
+    settings: ChaosSettings,
+    state: RefCell<ChaosState>,
+}
+
+impl<Conn: EventConnection> ChaosEventConnection<Conn> {
+    pub fn new(inner: Conn, settings: ChaosSettings) -> Self {
+        let state = RefCell::new(ChaosState {
+            rng: StdRng::seed_from_u64(settings.seed),
+            pending: Vec::new(),
+            closed: None,
+        });
+
+        Self {
+            inner,
+            settings,
+            state,
+        }
+    }
+}
+
+impl<Conn: EventConnection> EventConnection for ChaosEventConnection<Conn> {
+    type Unsubscriber = Conn::Unsubscriber;
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn recv(&self) -> Result<CommitEvent, EventIoError> {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+
+        while state.pending.len() < self.settings.reorder_window.max(1) && state.closed.is_none() {
+            match self.inner.recv() {
+                Ok(event) => state.pending.push(event),
+                Err(EventIoError::InvalidMessage(msg)) => {
+                    return Err(EventIoError::InvalidMessage(msg))
+                }
+                Err(err) => state.closed = Some(err),
+            }
+        }
+
+        if state.pending.is_empty() {
+            return Err(state.closed.take().unwrap_or_else(|| {
+                EventIoError::ConnectionError("Connection has been closed".into())
+            }));
+        }
+
+        let idx = state.rng.gen_range(0..state.pending.len());
+        let event = if state
+            .rng
+            .gen_bool(self.settings.duplicate_rate.clamp(0.0, 1.0))
+        {
+            state.pending[idx].clone()
+        } else {
+            state.pending.remove(idx)
+        };
+
+        if state.rng.gen_bool(self.settings.delay_rate.clamp(0.0, 1.0)) {
+            let max_delay = self.settings.max_delay.as_millis() as u64;
+            thread::sleep(Duration::from_millis(state.rng.gen_range(0..=max_delay)));
+        }
+
+        Ok(event)
+    }
+
+    fn subscribe(
+        &mut self,
+        namespaces: &[&str],
+        last_commit_id: Option<&str>,
+    ) -> Result<Self::Unsubscriber, EventIoError> {
+        self.inner.subscribe(namespaces, last_commit_id)
+    }
+
+    fn close(self) -> Result<(), EventIoError> {
+        self.inner.close()
+    }
+}
+
+/// Delivers a fixed list of events, then reports that the connection was closed
+struct GeneratedEventConnection {
+    events: RefCell<std::vec::IntoIter<CommitEvent>>,
+}
+
+struct NoopUnsubscriber;
+
+impl EventConnectionUnsubscriber for NoopUnsubscriber {
+    fn unsubscribe(self) -> Result<(), EventIoError> {
+        Ok(())
+    }
+}
+
+impl EventConnection for GeneratedEventConnection {
+    type Unsubscriber = NoopUnsubscriber;
+
+    fn name(&self) -> &str {
+        "generated"
+    }
+
+    fn recv(&self) -> Result<CommitEvent, EventIoError> {
+        self.events
+            .borrow_mut()
+            .next()
+            .ok_or_else(|| EventIoError::ConnectionError("All events have been sent".into()))
+    }
+
+    fn subscribe(
+        &mut self,
+        _namespaces: &[&str],
+        _last_commit_id: Option<&str>,
+    ) -> Result<Self::Unsubscriber, EventIoError> {
+        Ok(NoopUnsubscriber)
+    }
+
+    fn close(self) -> Result<(), EventIoError> {
+        Ok(())
+    }
+}
+
+/// Generates a chain of `commits` events that now and then switches to a new fork a few
+/// heights back, as a node does when it resolves a fork
+fn generate_events(rng: &mut StdRng, commits: usize, fork_rate: f64) -> Vec<CommitEvent> {
+    let mut events = Vec::with_capacity(commits);
+    let mut height = 0u64;
+    let mut fork = 0;
+
+    while events.len() < commits {
+        if height > 1 && rng.gen_bool(fork_rate) {
+            fork += 1;
+            height -= rng.gen_range(1..=height.min(3));
+        }
+
+        events.push(CommitEvent {
+            service_id: None,
+            id: format!("fork-{}-commit-{}", fork, height),
+            height: Some(height),
+            state_changes: Vec::new(),
+        });
+        height += 1;
+    }
+
+    events
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    New,
+    Duplicate,
+    Fork,
+}
+
+/// What the commit store should hold after each event, following the handler's rules: a
+/// commit already stored at its height is ignored, a different commit at a stored height
+/// replaces that commit and every later one, and any other commit is added.
+#[derive(Default)]
+struct ReferenceModel {
+    chain: BTreeMap<i64, String>,
+}
+
+impl ReferenceModel {
+    fn apply(&mut self, event: &CommitEvent) -> Outcome {
+        let height = match event.height {
+            Some(height) => height as i64,
+            None => self.next_commit_num(),
+        };
+
+        match self.chain.get(&height) {
+            Some(id) if id == &event.id => Outcome::Duplicate,
+            Some(_) => {
+                self.chain.split_off(&height);
+                self.chain.insert(height, event.id.clone());
+                Outcome::Fork
+            }
+            None => {
+                self.chain.insert(height, event.id.clone());
+                Outcome::New
+            }
+        }
+    }
+
+    fn next_commit_num(&self) -> i64 {
+        self.chain.keys().last().map(|num| num + 1).unwrap_or(0)
+    }
+
+    /// Returns a description of the first difference between the store and the model
+    fn check(&self, store: &dyn CommitStore) -> Result<Option<String>, DaemonError> {
+        let to_daemon_error = |err| DaemonError::from_source(Box::new(err));
+
+        let current = store.get_current_commit_id().map_err(to_daemon_error)?;
+        let expected = self.chain.values().last().cloned();
+        if current != expected {
+            return Ok(Some(format!(
+                "current commit is {:?}, expected {:?}",
+                current, expected
+            )));
+        }
+
+        let next = store.get_next_commit_num().map_err(to_daemon_error)?;
+        if next != self.next_commit_num() {
+            return Ok(Some(format!(
+                "next commit number is {}, expected {}",
+                next,
+                self.next_commit_num()
+            )));
+        }
+
+        for commit_num in 0..self.next_commit_num() {
+            let stored = store
+                .get_commit_by_commit_num(commit_num)
+                .map_err(to_daemon_error)?
+                .map(|commit| commit.commit_id);
+            let expected = self.chain.get(&commit_num);
+            if stored.as_ref() != expected {
+                return Ok(Some(format!(
+                    "commit at height {} is {:?}, expected {:?}",
+                    commit_num, stored, expected
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The totals of a soak run
+#[derive(Debug, Default)]
+pub struct SoakReport {
+    pub generated: usize,
+    pub delivered: usize,
+    pub duplicates: usize,
+    pub forks: usize,
+    pub final_height: Option<i64>,
+}
+
+/// Sends `commits` generated commit events through a chaos connection into the database
+/// event handler, backed by an in-memory SQLite store, and checks the store against the
+/// reference model after every delivered event.
+///
+/// Returns an error naming the seed and the event if an invariant is broken, or if an event
+/// that was generated was never delivered.
+pub fn run_soak<W: Write>(
+    settings: ChaosSettings,
+    commits: usize,
+    fork_rate: f64,
+    out: &mut W,
+) -> Result<SoakReport, DaemonError> {
+    let seed = settings.seed;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let events = generate_events(&mut rng, commits, fork_rate.clamp(0.0, 1.0));
+    let mut undelivered = events
+        .iter()
+        .map(|event| event.id.clone())
+        .collect::<HashSet<_>>();
+
+    let pool = Pool::builder()
+        .max_size(1)
+        .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    run_sqlite_migrations(
+        &*pool
+            .get()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let handler = DatabaseEventHandler::new(Box::new(SqliteStoreFactory::new(pool.clone())));
+    let commit_store = DieselCommitStore::new(pool);
+
+    let mut report = SoakReport {
+        generated: events.len(),
+        ..SoakReport::default()
+    };
+    let connection = ChaosEventConnection::new(
+        GeneratedEventConnection {
+            events: RefCell::new(events.into_iter()),
+        },
+        settings,
+    );
+    let mut model = ReferenceModel::default();
+
+    loop {
+        let event = match connection.recv() {
+            Ok(event) => event,
+            Err(EventIoError::InvalidMessage(msg)) => {
+                warn!("{}; ignoring...", msg);
+                continue;
+            }
+            Err(_) => break,
+        };
+
+        handler.handle_event(&event).map_err(|err| {
+            DaemonError::with_message(&format!(
+                "Seed {}: failed to handle {}: {}",
+                seed, event, err
+            ))
+        })?;
+
+        report.delivered += 1;
+        undelivered.remove(&event.id);
+        match model.apply(&event) {
+            Outcome::Duplicate => report.duplicates += 1,
+            Outcome::Fork => report.forks += 1,
+            Outcome::New => (),
+        }
+
+        if let Some(violation) = model.check(&commit_store)? {
+            return Err(DaemonError::with_message(&format!(
+                "Seed {}: after {}, {}",
+                seed, event, violation
+            )));
+        }
+    }
+
+    if !undelivered.is_empty() {
+        return Err(DaemonError::with_message(&format!(
+            "Seed {}: {} generated event(s) were never delivered",
+            seed,
+            undelivered.len()
+        )));
+    }
+
+    report.final_height = model.chain.keys().last().copied();
+    writeln!(
+        out,
+        "seed {}: {} event(s) generated, {} delivered, {} duplicate(s), {} fork(s), \
+         final height {:?}",
+        seed,
+        report.generated,
+        report.delivered,
+        report.duplicates,
+        report.forks,
+        report.final_height
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(count: usize) -> Vec<CommitEvent> {
+        (0..count as u64)
+            .map(|height| CommitEvent {
+                service_id: None,
+                id: format!("commit-{}", height),
+                height: Some(height),
+                state_changes: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Verify that the chaos connection delivers every event at least once before reporting
+    /// that the connection was closed, and that it reorders and duplicates them
+    #[test]
+    fn test_chaos_connection_delivers_every_event() {
+        let connection = ChaosEventConnection::new(
+            GeneratedEventConnection {
+                events: RefCell::new(events(50).into_iter()),
+            },
+            ChaosSettings {
+                seed: 7,
+                delay_rate: 0.0,
+                ..ChaosSettings::default()
+            },
+        );
+
+        let mut delivered = Vec::new();
+        while let Ok(event) = connection.recv() {
+            delivered.push(event.id);
+        }
+
+        let unique = delivered.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 50);
+        assert!(delivered.len() > 50);
+        assert_ne!(
+            delivered[..10],
+            events(10).into_iter().map(|e| e.id).collect::<Vec<_>>()[..]
+        );
+    }
+
+    /// Verify that the reference model ignores duplicates and replaces forked commits
+    #[test]
+    fn test_reference_model() {
+        let event = |id: &str, height| CommitEvent {
+            service_id: None,
+            id: id.to_string(),
+            height: Some(height),
+            state_changes: Vec::new(),
+        };
+
+        let mut model = ReferenceModel::default();
+        assert_eq!(model.apply(&event("a0", 0)), Outcome::New);
+        assert_eq!(model.apply(&event("a1", 1)), Outcome::New);
+        assert_eq!(model.apply(&event("a2", 2)), Outcome::New);
+        assert_eq!(model.apply(&event("a1", 1)), Outcome::Duplicate);
+        assert_eq!(model.apply(&event("b1", 1)), Outcome::Fork);
+
+        assert_eq!(
+            model.chain.values().cloned().collect::<Vec<_>>(),
+            vec!["a0".to_string(), "b1".to_string()]
+        );
+        assert_eq!(model.next_commit_num(), 2);
+    }
+
+    /// Verify that the database event handler keeps to the reference model under a soak run
+    /// with several seeds
+    #[test]
+    fn test_soak() {
+        for seed in 0..3 {
+            let mut out = Vec::new();
+            let report = run_soak(
+                ChaosSettings {
+                    seed,
+                    max_delay: Duration::from_millis(1),
+                    ..ChaosSettings::default()
+                },
+                100,
+                0.1,
+                &mut out,
+            )
+            .expect("Soak run failed");
+
+            assert_eq!(report.generated, 100);
+            assert!(report.delivered >= 100);
+            assert!(report.duplicates > 0);
+            assert!(report.forks > 0);
+        }
+    }
+}
@@ -18,6 +18,7 @@
 #[cfg(any(feature = "location", feature = "product"))]
 use grid_sdk::protocol::schema::state::PropertyValue;
 #[cfg(any(
+    feature = "mfg-batch-anchors",
     feature = "pike",
     feature = "schema",
     feature = "product",
@@ -37,6 +38,13 @@ use grid_sdk::{
     protocol::location::state::LocationList,
 };
 
+#[cfg(feature = "webhooks")]
+use grid_sdk::mfg_batch::addressing::GRID_MFG_BATCH_NAMESPACE;
+#[cfg(feature = "mfg-batch-anchors")]
+use grid_sdk::{
+    mfg_batch::{addressing::GRID_MFG_BATCH_ANCHOR_NAMESPACE, store::MfgBatchAnchor},
+    protocol::mfg_batch::state::MfgBatchAnchorList,
+};
 #[cfg(feature = "pike")]
 use grid_sdk::{
     pike::{
@@ -111,6 +119,12 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::i64;
 
+#[cfg(feature = "log-masking")]
+use grid_sdk::log_masking::LogMask;
+
+#[cfg(feature = "mfg-batch-anchors")]
+use crate::database::SharedMfgBatchStore;
+
 use super::{CommitEvent, EventError, EventHandler, StateChange, IGNORED_NAMESPACES};
 
 #[cfg(any(
@@ -123,19 +137,52 @@ pub const MAX_COMMIT_NUM: i64 = i64::MAX;
 
 pub struct DatabaseEventHandler {
     store_factory: Box<dyn TransactionalStoreFactory>,
+    #[cfg(feature = "mfg-batch-anchors")]
+    mfg_batch_store: Option<SharedMfgBatchStore>,
+    #[cfg(feature = "log-masking")]
+    log_mask: LogMask,
 }
 
 impl Clone for DatabaseEventHandler {
     fn clone(&self) -> Self {
         let store_factory = self.store_factory.clone_box();
 
-        Self { store_factory }
+        Self {
+            store_factory,
+            #[cfg(feature = "mfg-batch-anchors")]
+            mfg_batch_store: self.mfg_batch_store.clone(),
+            #[cfg(feature = "log-masking")]
+            log_mask: self.log_mask.clone(),
+        }
     }
 }
 
 impl DatabaseEventHandler {
     pub fn new(store_factory: Box<dyn TransactionalStoreFactory>) -> Self {
-        Self { store_factory }
+        Self {
+            store_factory,
+            #[cfg(feature = "mfg-batch-anchors")]
+            mfg_batch_store: None,
+            #[cfg(feature = "log-masking")]
+            log_mask: LogMask::default(),
+        }
+    }
+
+    /// Records the mfg_batch anchors committed to state in the given store, so they can be
+    /// verified against the records it holds. The store is outside of the handler's
+    /// transaction, but recording an anchor again is a no-op, so a retried commit is harmless.
+    #[cfg(feature = "mfg-batch-anchors")]
+    pub fn with_mfg_batch_store(mut self, mfg_batch_store: SharedMfgBatchStore) -> Self {
+        self.mfg_batch_store = Some(mfg_batch_store);
+        self
+    }
+
+    /// Keeps the operations performed for a commit out of the trace log if any property values
+    /// are masked, since the operations hold the values in full
+    #[cfg(feature = "log-masking")]
+    pub fn with_log_mask(mut self, log_mask: LogMask) -> Self {
+        self.log_mask = log_mask;
+        self
     }
 }
 
@@ -163,6 +210,13 @@ impl EventHandler for DatabaseEventHandler {
                 commit.service_id.as_ref(),
             )?;
 
+            #[cfg(feature = "log-masking")]
+            if self.log_mask.is_empty() {
+                trace!("The following operations will be performed: {:#?}", db_ops);
+            } else {
+                trace!("{} operations will be performed", db_ops.len());
+            }
+            #[cfg(not(feature = "log-masking"))]
             trace!("The following operations will be performed: {:#?}", db_ops);
             match txn
                 .get_grid_commit_store()
@@ -286,6 +340,19 @@ impl EventHandler for DatabaseEventHandler {
                             txn.get_grid_purchase_order_store().add_purchase_order(po)
                         })?;
                     }
+                    #[cfg(feature = "mfg-batch-anchors")]
+                    DbInsertOperation::MfgBatchAnchors(anchors) => match &self.mfg_batch_store {
+                        Some(mfg_batch_store) => {
+                            debug!("Inserting {} mfg_batch anchor(s)", anchors.len());
+                            anchors.into_iter().try_for_each(|anchor| {
+                                mfg_batch_store.add_mfg_batch_anchor(anchor)
+                            })?;
+                        }
+                        None => debug!(
+                            "No mfg_batch store to record {} anchor(s) in",
+                            anchors.len()
+                        ),
+                    },
                 };
             }
             Ok(true) as Result<_, EventError>
@@ -306,7 +373,7 @@ impl EventHandler for DatabaseEventHandler {
     }
 
     fn cloned_box(&self) -> Box<dyn EventHandler> {
-        Box::new(Self::new(self.store_factory.clone_box()))
+        Box::new(self.clone())
     }
 }
 
@@ -763,6 +830,31 @@ fn state_change_to_db_operation(
                     Ok(None)
                 }
             },
+            // mfg_batch state is only subscribed to so webhooks can be notified of it
+            #[cfg(feature = "webhooks")]
+            GRID_MFG_BATCH_NAMESPACE => Ok(None),
+            #[cfg(feature = "mfg-batch-anchors")]
+            GRID_MFG_BATCH_ANCHOR_NAMESPACE => {
+                let anchors = MfgBatchAnchorList::from_bytes(value)
+                    .map_err(|err| {
+                        EventError(format!("Failed to parse mfg_batch anchor list {}", err))
+                    })?
+                    .anchors()
+                    .iter()
+                    .map(|anchor| MfgBatchAnchor {
+                        owner: anchor.owner().to_string(),
+                        commit_num: anchor.commit_num(),
+                        merkle_root: anchor.merkle_root().to_string(),
+                        row_count: anchor.row_count() as i64,
+                        anchor_address: key.to_string(),
+                        anchored_commit_num: commit_num,
+                        anchored_at: anchor.timestamp() as i64,
+                        service_id: service_id.cloned(),
+                    })
+                    .collect();
+
+                Ok(Some(DbInsertOperation::MfgBatchAnchors(anchors)))
+            }
             _ => {
                 let ignore_state_change = IGNORED_NAMESPACES
                     .iter()
@@ -792,6 +884,8 @@ fn state_change_to_db_operation(
                 key.to_string(),
                 commit_num,
             ))),
+            #[cfg(feature = "webhooks")]
+            GRID_MFG_BATCH_NAMESPACE => Ok(None),
             _ => Err(EventError(format!(
                 "could not handle state change; unexpected delete of key {}",
                 key
@@ -802,6 +896,8 @@ fn state_change_to_db_operation(
 
 #[derive(Debug)]
 enum DbInsertOperation {
+    #[cfg(feature = "mfg-batch-anchors")]
+    MfgBatchAnchors(Vec<MfgBatchAnchor>),
     #[cfg(feature = "pike")]
     Agents(Vec<Agent>),
     #[cfg(feature = "pike")]
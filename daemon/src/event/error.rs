@@ -21,8 +21,12 @@ use std::fmt;
 use grid_sdk::error::InternalError;
 
 use grid_sdk::commits::store::CommitStoreError;
+#[cfg(feature = "event-queue")]
+use grid_sdk::event_queue::store::EventQueueStoreError;
 #[cfg(feature = "location")]
 use grid_sdk::location::store::LocationStoreError;
+#[cfg(feature = "mfg-batch-anchors")]
+use grid_sdk::mfg_batch::store::MfgBatchStoreError;
 #[cfg(feature = "pike")]
 use grid_sdk::pike::store::PikeStoreError;
 #[cfg(feature = "product")]
@@ -33,6 +37,8 @@ use grid_sdk::purchase_order::store::{PurchaseOrderBuilderError, PurchaseOrderSt
 use grid_sdk::schema::store::SchemaStoreError;
 #[cfg(feature = "track-and-trace")]
 use grid_sdk::track_and_trace::store::TrackAndTraceStoreError;
+#[cfg(feature = "webhooks")]
+use grid_sdk::webhooks::store::WebhookStoreError;
 
 #[derive(Debug)]
 pub struct EventProcessorError(pub String);
@@ -68,6 +74,13 @@ impl From<CommitStoreError> for EventError {
     }
 }
 
+#[cfg(feature = "event-queue")]
+impl From<EventQueueStoreError> for EventError {
+    fn from(err: EventQueueStoreError) -> Self {
+        EventError(format!("{}", err))
+    }
+}
+
 #[cfg(feature = "location")]
 impl From<LocationStoreError> for EventError {
     fn from(err: LocationStoreError) -> Self {
@@ -75,6 +88,13 @@ impl From<LocationStoreError> for EventError {
     }
 }
 
+#[cfg(feature = "mfg-batch-anchors")]
+impl From<MfgBatchStoreError> for EventError {
+    fn from(err: MfgBatchStoreError) -> Self {
+        EventError(format!("{}", err))
+    }
+}
+
 #[cfg(feature = "pike")]
 impl From<PikeStoreError> for EventError {
     fn from(err: PikeStoreError) -> Self {
@@ -124,6 +144,13 @@ impl From<TrackAndTraceStoreError> for EventError {
     }
 }
 
+#[cfg(feature = "webhooks")]
+impl From<WebhookStoreError> for EventError {
+    fn from(err: WebhookStoreError) -> Self {
+        EventError(format!("{}", err))
+    }
+}
+
 impl From<diesel::result::Error> for EventError {
     fn from(err: diesel::result::Error) -> Self {
         EventError(format!("{}", err))
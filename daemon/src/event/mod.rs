@@ -15,8 +15,14 @@
  * -----------------------------------------------------------------------------
  */
 
+#[cfg(feature = "event-chaos")]
+pub mod chaos;
 pub mod db_handler;
 mod error;
+#[cfg(feature = "event-queue")]
+pub mod queue;
+#[cfg(feature = "event-replay")]
+pub mod replay;
 
 use std::cell::RefCell;
 use std::thread;
@@ -34,6 +40,10 @@ cfg_if! {
     }
 }
 
+#[cfg(feature = "mfg-batch-anchors")]
+use grid_sdk::mfg_batch::addressing::GRID_MFG_BATCH_ANCHOR_NAMESPACE;
+#[cfg(feature = "webhooks")]
+use grid_sdk::mfg_batch::addressing::GRID_MFG_BATCH_NAMESPACE;
 #[cfg(feature = "track-and-trace")]
 use grid_sdk::track_and_trace::addressing::TRACK_AND_TRACE_NAMESPACE;
 
@@ -48,6 +58,10 @@ const ALL_GRID_NAMESPACES: &[&str] = &[
     GRID_NAMESPACE,
     #[cfg(feature = "track-and-trace")]
     TRACK_AND_TRACE_NAMESPACE,
+    #[cfg(feature = "webhooks")]
+    GRID_MFG_BATCH_NAMESPACE,
+    #[cfg(feature = "mfg-batch-anchors")]
+    GRID_MFG_BATCH_ANCHOR_NAMESPACE,
 ];
 
 const SABRE_NAMESPACE: &str = "00ec";
@@ -0,0 +1,445 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+//! Queues the commit events the database event handler fails to record, so that a commit is
+//! never skipped because the database was unavailable or the event could not be recorded yet.
+//!
+//! The events of a service are recorded in the order they were received: while an event of the
+//! service is queued, its later events are queued behind it. The queue of a service is retried
+//! before each of the service's events is handled, and all of it is retried by the
+//! `reprocess-failed-events` command, which also lists the queue and discards an event that can
+//! never be recorded.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::commits::store::CommitEvent as DbCommitEvent;
+use grid_sdk::event_queue::store::{EventQueueStore, QueuedEvent};
+#[cfg(feature = "log-masking")]
+use grid_sdk::log_masking::LogMask;
+use grid_sdk::store::{create_store_factory, ConnectionUri, TransactionalStoreFactory};
+
+use crate::config::GridConfig;
+use crate::error::DaemonError;
+
+use super::{db_handler::DatabaseEventHandler, CommitEvent, EventError, EventHandler};
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Records commit events with another handler, queueing those it fails to record.
+///
+/// If an event can be neither recorded nor queued, such as while the database is down, handling
+/// it is retried until it can, with the delay between attempts doubling up to a maximum. Events
+/// are not received meanwhile, so none are skipped.
+pub struct QueueingEventHandler {
+    handler: Box<dyn EventHandler>,
+    store_factory: Box<dyn TransactionalStoreFactory>,
+}
+
+impl QueueingEventHandler {
+    /// Wraps `handler`, queueing the events it fails to record in the database of
+    /// `store_factory`
+    pub fn new(
+        handler: Box<dyn EventHandler>,
+        store_factory: Box<dyn TransactionalStoreFactory>,
+    ) -> Self {
+        Self {
+            handler,
+            store_factory,
+        }
+    }
+
+    fn record_or_queue(&self, event: &CommitEvent) -> Result<(), EventError> {
+        let store = self.store_factory.get_event_queue_store();
+
+        let service_id = event.service_id.as_deref();
+        let summary = reprocess_queued_events(&*self.handler, &*store, |queued| {
+            queued.event.service_id.as_deref() == service_id
+        })?;
+        if summary.remaining > 0 {
+            let queue_id = store.add_queued_event(
+                &DbCommitEvent::from(event),
+                &format!(
+                    "Queued behind {} earlier event(s) of the service",
+                    summary.remaining
+                ),
+                now(),
+            )?;
+            warn!(
+                "Queued commit {} as {} behind {} earlier event(s) of the service",
+                event.id, queue_id, summary.remaining
+            );
+            return Ok(());
+        }
+
+        if let Err(err) = self.handler.handle_event(event) {
+            let queue_id =
+                store.add_queued_event(&DbCommitEvent::from(event), &err.to_string(), now())?;
+            error!(
+                "Unable to record commit {}; queued as {} to be retried: {}",
+                event.id, queue_id, err
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl EventHandler for QueueingEventHandler {
+    fn handle_event(&self, event: &CommitEvent) -> Result<(), EventError> {
+        let mut delay = INITIAL_RETRY_DELAY;
+        loop {
+            match self.record_or_queue(event) {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    error!(
+                        "Unable to record or queue commit {}; retrying in {:?}: {}",
+                        event.id, delay, err
+                    );
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    fn cloned_box(&self) -> Box<dyn EventHandler> {
+        Box::new(Self {
+            handler: self.handler.clone(),
+            store_factory: self.store_factory.clone_box(),
+        })
+    }
+}
+
+/// The outcome of retrying queued events
+#[derive(Debug, Default, PartialEq)]
+pub struct ReprocessSummary {
+    /// The events recorded and removed from the queue
+    pub recorded: usize,
+    /// The events still queued
+    pub remaining: usize,
+}
+
+/// Retries the queued events selected by `filter` with `handler`, in the order they were
+/// queued, removing each one that is recorded. Once an event of a service fails again, the
+/// later events of that service are left queued behind it.
+pub fn reprocess_queued_events<F>(
+    handler: &dyn EventHandler,
+    store: &dyn EventQueueStore,
+    filter: F,
+) -> Result<ReprocessSummary, EventError>
+where
+    F: Fn(&QueuedEvent) -> bool,
+{
+    let mut summary = ReprocessSummary::default();
+    let mut blocked_services = HashSet::new();
+
+    for queued in store.list_queued_events()?.into_iter().filter(filter) {
+        let service_id = queued.event.service_id.clone();
+        if blocked_services.contains(&service_id) {
+            summary.remaining += 1;
+            continue;
+        }
+
+        match handler.handle_event(&CommitEvent::from(queued.event)) {
+            Ok(()) => {
+                store.remove_queued_event(queued.queue_id)?;
+                info!("Recorded queued event {}", queued.queue_id);
+                summary.recorded += 1;
+            }
+            Err(err) => {
+                store.record_queued_event_failure(queued.queue_id, &err.to_string(), now())?;
+                blocked_services.insert(service_id);
+                summary.remaining += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Runs the `reprocess-failed-events` subcommand against the configured database: lists the
+/// queued events, discards one, or retries them all with the database event handler and writes
+/// how many were recorded
+pub fn run_reprocess_failed_events(
+    config: &GridConfig,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let connection_uri = config
+        .database_url()
+        .parse::<ConnectionUri>()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store_factory = create_store_factory(&connection_uri)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store = store_factory.get_event_queue_store();
+
+    if matches.is_present("list") {
+        return list_queued_events(&*store, out);
+    }
+
+    if let Some(queue_id) = matches.value_of("discard") {
+        let queue_id = queue_id.parse::<i64>().map_err(|err| {
+            DaemonError::with_message(&format!("Invalid queue ID {}: {}", queue_id, err))
+        })?;
+        store
+            .remove_queued_event(queue_id)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        warn!("Discarded queued event {}", queue_id);
+        return Ok(());
+    }
+
+    let handler = DatabaseEventHandler::new(
+        create_store_factory(&connection_uri)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+    );
+    #[cfg(feature = "mfg-batch-anchors")]
+    let handler =
+        handler.with_mfg_batch_store(crate::database::create_shared_mfg_batch_store(config)?);
+    #[cfg(feature = "log-masking")]
+    let handler = handler.with_log_mask(LogMask::new(config.log_mask_properties()));
+
+    let summary = reprocess_queued_events(&handler, &*store, |_| true)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    writeln!(
+        out,
+        "Recorded {} queued event(s); {} remain queued",
+        summary.recorded, summary.remaining
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Writes the queued events, one per line, in the order they will be retried
+pub fn list_queued_events(
+    store: &dyn EventQueueStore,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let queued_events = store
+        .list_queued_events()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for queued in queued_events {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            queued.queue_id,
+            queued.event.id,
+            queued.event.service_id.as_deref().unwrap_or("-"),
+            queued
+                .event
+                .height
+                .map(|height| height.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            queued.attempts,
+            queued.last_error,
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use grid_sdk::migrations::run_sqlite_migrations;
+    use grid_sdk::store::{sqlite::SqliteStoreFactory, StoreFactory};
+
+    /// Records the ids of the events it handles, failing those it is told to
+    #[derive(Clone, Default)]
+    struct FakeEventHandler {
+        recorded: Arc<Mutex<Vec<String>>>,
+        failing: Arc<Mutex<HashSet<String>>>,
+    }
+
+    impl FakeEventHandler {
+        fn fail(&self, commit_id: &str) {
+            self.failing.lock().unwrap().insert(commit_id.to_string());
+        }
+
+        fn recover(&self) {
+            self.failing.lock().unwrap().clear();
+        }
+
+        fn recorded(&self) -> Vec<String> {
+            self.recorded.lock().unwrap().clone()
+        }
+    }
+
+    impl EventHandler for FakeEventHandler {
+        fn handle_event(&self, event: &CommitEvent) -> Result<(), EventError> {
+            if self.failing.lock().unwrap().contains(&event.id) {
+                return Err(EventError(format!("Unable to record {}", event.id)));
+            }
+            self.recorded.lock().unwrap().push(event.id.clone());
+            Ok(())
+        }
+
+        fn cloned_box(&self) -> Box<dyn EventHandler> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn store_factory() -> SqliteStoreFactory {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        SqliteStoreFactory::new(pool)
+    }
+
+    fn commit_event(id: &str, service_id: &str) -> CommitEvent {
+        CommitEvent {
+            service_id: Some(service_id.to_string()),
+            id: id.to_string(),
+            height: None,
+            state_changes: vec![],
+        }
+    }
+
+    /// Verify that an event that fails to be recorded is queued, that the later events of its
+    /// service are queued behind it while other services' events are recorded, and that the
+    /// queue is recorded in order before the service's next event once the failure clears
+    #[test]
+    fn test_failed_events_are_queued_and_recorded_in_order() {
+        let store_factory = store_factory();
+        let fake = FakeEventHandler::default();
+        let handler = QueueingEventHandler::new(Box::new(fake.clone()), store_factory.clone_box());
+
+        fake.fail("commit-1");
+        handler
+            .handle_event(&commit_event("commit-1", "abcde-01234::gsAA"))
+            .expect("Failed to handle event");
+        handler
+            .handle_event(&commit_event("commit-2", "abcde-01234::gsAA"))
+            .expect("Failed to handle event");
+        handler
+            .handle_event(&commit_event("commit-3", "fghij-56789::gsBB"))
+            .expect("Failed to handle event");
+        assert_eq!(fake.recorded(), vec!["commit-3"]);
+
+        let queued = store_factory
+            .get_event_queue_store()
+            .list_queued_events()
+            .expect("Failed to list queued events");
+        assert_eq!(
+            queued
+                .iter()
+                .map(|queued| (queued.event.id.as_str(), queued.attempts))
+                .collect::<Vec<_>>(),
+            vec![("commit-1", 2), ("commit-2", 1)]
+        );
+        assert_eq!(
+            queued[0].last_error,
+            "Event Error: Unable to record commit-1"
+        );
+
+        fake.recover();
+        handler
+            .handle_event(&commit_event("commit-4", "abcde-01234::gsAA"))
+            .expect("Failed to handle event");
+        assert_eq!(
+            fake.recorded(),
+            vec!["commit-3", "commit-1", "commit-2", "commit-4"]
+        );
+        assert!(store_factory
+            .get_event_queue_store()
+            .list_queued_events()
+            .expect("Failed to list queued events")
+            .is_empty());
+    }
+
+    /// Verify that reprocessing records the queued events of every service up to the first one
+    /// that fails again, and that the queue is listed with the reason each event last failed
+    #[test]
+    fn test_reprocess_and_list_queued_events() {
+        let store_factory = store_factory();
+        let store = store_factory.get_event_queue_store();
+        for (commit_id, service_id) in &[
+            ("commit-1", "abcde-01234::gsAA"),
+            ("commit-2", "abcde-01234::gsAA"),
+            ("commit-3", "fghij-56789::gsBB"),
+        ] {
+            store
+                .add_queued_event(
+                    &DbCommitEvent::from(&commit_event(commit_id, service_id)),
+                    "Database is unavailable",
+                    10,
+                )
+                .expect("Failed to queue event");
+        }
+
+        let fake = FakeEventHandler::default();
+        fake.fail("commit-1");
+        let summary =
+            reprocess_queued_events(&fake, &*store, |_| true).expect("Failed to reprocess");
+        assert_eq!(
+            summary,
+            ReprocessSummary {
+                recorded: 1,
+                remaining: 2
+            }
+        );
+        assert_eq!(fake.recorded(), vec!["commit-3"]);
+
+        let mut out = vec![];
+        list_queued_events(&*store, &mut out).expect("Failed to list queued events");
+        let out = String::from_utf8(out).expect("Output is not UTF-8");
+        let lines = out
+            .lines()
+            .map(|line| line.split('\t').skip(1).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                vec![
+                    "commit-1",
+                    "abcde-01234::gsAA",
+                    "-",
+                    "2",
+                    "Event Error: Unable to record commit-1"
+                ],
+                vec![
+                    "commit-2",
+                    "abcde-01234::gsAA",
+                    "-",
+                    "1",
+                    "Database is unavailable"
+                ],
+            ]
+        );
+    }
+}
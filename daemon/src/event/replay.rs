@@ -0,0 +1,362 @@
+/*
+ * Copyright 2022 Cargill Incorporated
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * -----------------------------------------------------------------------------
+ */
+
+//! Replays recorded commit events through the database event handler against a scratch SQLite
+//! store, reporting the rows each event produced. This is a debugging aid for tracking down why
+//! a record never made it into the off-chain database.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{BigInt, Text},
+    sqlite::SqliteConnection,
+    QueryableByName,
+};
+use grid_sdk::{
+    migrations::run_sqlite_migrations,
+    store::{create_store_factory, ConnectionUri},
+};
+
+use crate::error::DaemonError;
+
+use super::{db_handler::DatabaseEventHandler, CommitEvent, EventHandler, StateChange};
+
+/// A commit event as recorded in a replay file, one JSON object per line
+#[derive(Deserialize)]
+struct RecordedCommitEvent {
+    id: String,
+    #[serde(default)]
+    service_id: Option<String>,
+    #[serde(default)]
+    height: Option<u64>,
+    #[serde(default)]
+    state_changes: Vec<RecordedStateChange>,
+}
+
+/// A recorded state change; `value` is the base64-encoded state entry
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum RecordedStateChange {
+    Set { key: String, value: String },
+    Delete { key: String },
+}
+
+impl RecordedCommitEvent {
+    fn into_commit_event(self) -> Result<CommitEvent, DaemonError> {
+        let id = self.id;
+        let state_changes = self
+            .state_changes
+            .into_iter()
+            .map(|change| match change {
+                RecordedStateChange::Set { key, value } => base64::decode(&value)
+                    .map(|value| StateChange::Set { key, value })
+                    .map_err(|err| {
+                        DaemonError::with_message(&format!(
+                            "Invalid base64 value in commit {}: {}",
+                            id, err
+                        ))
+                    }),
+                RecordedStateChange::Delete { key } => Ok(StateChange::Delete { key }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CommitEvent {
+            service_id: self.service_id,
+            id,
+            height: self.height,
+            state_changes,
+        })
+    }
+}
+
+#[derive(QueryableByName)]
+struct TableName {
+    #[sql_type = "Text"]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+struct TableCounts {
+    total: i64,
+    current: Option<i64>,
+}
+
+/// A temporary SQLite database that is removed when dropped
+struct ScratchDatabase {
+    path: PathBuf,
+    conn: SqliteConnection,
+}
+
+impl ScratchDatabase {
+    fn create() -> Result<Self, DaemonError> {
+        let path = std::env::temp_dir().join(format!("gridd-replay-{}.db", uuid::Uuid::new_v4()));
+        let conn = SqliteConnection::establish(&path.to_string_lossy())
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        run_sqlite_migrations(&conn).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        Ok(Self { path, conn })
+    }
+
+    fn uri(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn snapshot(&self) -> Result<BTreeMap<String, TableCounts>, DaemonError> {
+        let tables = sql_query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' \
+             AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '__diesel_%'",
+        )
+        .load::<TableName>(&self.conn)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        let mut snapshot = BTreeMap::new();
+        for TableName { name } in tables {
+            let total = self.count(&format!("SELECT COUNT(*) AS count FROM \"{}\"", name))?;
+
+            let columns = sql_query(format!("PRAGMA table_info(\"{}\")", name))
+                .load::<TableName>(&self.conn)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let current = if columns.iter().any(|col| col.name == "end_commit_num") {
+                Some(self.count(&format!(
+                    "SELECT COUNT(*) AS count FROM \"{}\" WHERE end_commit_num = {}",
+                    name,
+                    i64::MAX
+                ))?)
+            } else {
+                None
+            };
+
+            snapshot.insert(name, TableCounts { total, current });
+        }
+
+        Ok(snapshot)
+    }
+
+    fn count(&self, query: &str) -> Result<i64, DaemonError> {
+        sql_query(query)
+            .get_result::<RowCount>(&self.conn)
+            .map(|row| row.count)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))
+    }
+}
+
+impl Drop for ScratchDatabase {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!(
+                "Unable to remove scratch database {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Reads the commit events recorded in `events_path` (JSON lines) and applies each in turn to a
+/// fresh SQLite store, writing the per-table row changes to `out`.
+///
+/// If any `key_prefixes` are given, only state changes whose address starts with one of them are
+/// replayed; for example, `11bb0e01` limits the replay to manufactured batches.
+pub fn replay_events<W: Write>(
+    events_path: &str,
+    key_prefixes: &[&str],
+    out: &mut W,
+) -> Result<(), DaemonError> {
+    let events = read_events(events_path)?;
+
+    let scratch = ScratchDatabase::create()?;
+    let store_factory = create_store_factory(&ConnectionUri::Sqlite(scratch.uri()))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let handler = DatabaseEventHandler::new(store_factory);
+
+    let mut before = scratch.snapshot()?;
+    for mut event in events {
+        if !key_prefixes.is_empty() {
+            event.state_changes.retain(|change| {
+                key_prefixes
+                    .iter()
+                    .any(|prefix| change.key_has_prefix(prefix))
+            });
+        }
+
+        writeln!(out, "{}", event).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        if let Err(err) = handler.handle_event(&event) {
+            writeln!(out, "    error: {}", err)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            continue;
+        }
+
+        let after = scratch.snapshot()?;
+        write_diff(out, &before, &after).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        before = after;
+    }
+
+    Ok(())
+}
+
+fn read_events(events_path: &str) -> Result<Vec<CommitEvent>, DaemonError> {
+    let file = File::open(events_path).map_err(|err| {
+        DaemonError::with_message(&format!("Unable to open {}: {}", events_path, err))
+    })?;
+
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(
+                serde_json::from_str::<RecordedCommitEvent>(&line)
+                    .map_err(|err| {
+                        DaemonError::with_message(&format!(
+                            "Invalid commit event on line {}: {}",
+                            idx + 1,
+                            err
+                        ))
+                    })
+                    .and_then(RecordedCommitEvent::into_commit_event),
+            ),
+            Err(err) => Some(Err(DaemonError::from_source(Box::new(err)))),
+        })
+        .collect()
+}
+
+fn write_diff<W: Write>(
+    out: &mut W,
+    before: &BTreeMap<String, TableCounts>,
+    after: &BTreeMap<String, TableCounts>,
+) -> std::io::Result<()> {
+    let mut changed = false;
+    for (table, counts) in after {
+        let previous = before.get(table).copied().unwrap_or_default();
+        if *counts == previous {
+            continue;
+        }
+        changed = true;
+
+        write!(
+            out,
+            "    {}: {:+} row(s)",
+            table,
+            counts.total - previous.total
+        )?;
+        if let Some(current) = counts.current {
+            write!(
+                out,
+                ", {:+} current",
+                current - previous.current.unwrap_or_default()
+            )?;
+        }
+        writeln!(out)?;
+    }
+
+    if !changed {
+        writeln!(out, "    no row changes")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    /// Write a replay file to a temporary location and return its path
+    fn write_events_file(contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("gridd-replay-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut file = File::create(&path).expect("Failed to create events file");
+        file.write_all(contents.as_bytes())
+            .expect("Failed to write events file");
+        path
+    }
+
+    /// Verify that recorded events are parsed and base64 values are decoded
+    #[test]
+    fn test_read_events() {
+        let path = write_events_file(
+            r#"{"id": "commit-1", "height": 1, "state_changes": [{"type": "set", "key": "11bb0e01aa", "value": "AQID"}]}
+
+{"id": "commit-2", "service_id": "abcde-01234::gsAA", "state_changes": [{"type": "delete", "key": "11bb0e01aa"}]}
+"#,
+        );
+
+        let events = read_events(&path.to_string_lossy()).expect("Failed to read events");
+        fs::remove_file(&path).ok();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, "commit-1");
+        assert_eq!(events[0].height, Some(1));
+        assert!(
+            events[0].state_changes
+                == vec![StateChange::Set {
+                    key: "11bb0e01aa".to_string(),
+                    value: vec![1, 2, 3],
+                }]
+        );
+        assert_eq!(events[1].service_id.as_deref(), Some("abcde-01234::gsAA"));
+        assert!(
+            events[1].state_changes
+                == vec![StateChange::Delete {
+                    key: "11bb0e01aa".to_string(),
+                }]
+        );
+    }
+
+    /// Verify that an invalid line is reported with its line number
+    #[test]
+    fn test_read_events_invalid_line() {
+        let path = write_events_file("{\"id\": \"commit-1\"}\nnot json\n");
+
+        let err = read_events(&path.to_string_lossy()).err();
+        fs::remove_file(&path).ok();
+
+        assert!(err
+            .expect("Invalid line was accepted")
+            .to_string()
+            .contains("line 2"));
+    }
+
+    /// Verify that replaying a commit records it in the scratch store and reports the new row
+    #[test]
+    fn test_replay_events_reports_row_diff() {
+        let path =
+            write_events_file("{\"id\": \"commit-1\", \"height\": 0, \"state_changes\": []}\n");
+
+        let mut out = Vec::new();
+        let result = replay_events(&path.to_string_lossy(), &[], &mut out);
+        fs::remove_file(&path).ok();
+        result.expect("Failed to replay events");
+
+        let out = String::from_utf8(out).expect("Output was not UTF-8");
+        assert!(out.contains("commit-1"));
+        assert!(out.contains("commits: +1 row(s)"));
+    }
+}
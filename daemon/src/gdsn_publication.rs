@@ -0,0 +1,319 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Publishes products created from GDSN data to a GDSN data pool as Catalogue Item
+//! Notification (CIN) messages, and records the status the data pool reports for each product.
+//!
+//! Messages are written out for the data pool's connector to send; the daemon does not connect
+//! to data pools itself.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::product::gdsn::publication::{
+    store::{GdsnPublication, GdsnPublicationStore, PublicationStatus},
+    validate_cin, CinMessage, CinTradeItem, DocumentCommand,
+};
+use grid_sdk::product::store::ProductStore;
+use grid_sdk::store::{create_store_factory, ConnectionUri};
+use rand::RngCore;
+
+use crate::error::DaemonError;
+
+const PUBLICATION_ID_LENGTH: usize = 16;
+const ENV_GRID_STATE_DIR: &str = "GRID_STATE_DIR";
+const DEFAULT_GRID_STATE_DIR: &str = "/var/lib/grid";
+
+/// Runs the `gdsn` subcommand against the database at `database_url`
+pub fn run_gdsn_command(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let connection_uri = database_url
+        .parse::<ConnectionUri>()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store_factory = create_store_factory(&connection_uri)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store = store_factory.get_gdsn_publication_store();
+
+    match matches.subcommand() {
+        ("publish", Some(m)) => {
+            let message = CinMessage {
+                instance_identifier: random_hex(PUBLICATION_ID_LENGTH),
+                sender: m.value_of("sender").unwrap_or_default().to_string(),
+                source_data_pool: m.value_of("data_pool").unwrap_or_default().to_string(),
+                data_recipient: m.value_of("recipient").unwrap_or_default().to_string(),
+                command: m
+                    .value_of("command")
+                    .unwrap_or("ADD")
+                    .parse::<DocumentCommand>()
+                    .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+                created_at: now(),
+                trade_items: vec![],
+            };
+            let xml = publish(
+                &*store_factory.get_grid_product_store(),
+                &*store,
+                message,
+                &m.values_of("product_id")
+                    .unwrap_or_default()
+                    .collect::<Vec<_>>(),
+                m.value_of("service_id"),
+                &m.value_of("xsd_dir")
+                    .map(String::from)
+                    .unwrap_or_else(default_schema_dir),
+            )?;
+
+            match m.value_of("output") {
+                Some(path) => fs::write(path, xml),
+                None => writeln!(out, "{}", xml),
+            }
+            .map_err(|err| DaemonError::from_source(Box::new(err)))
+        }
+        ("status", Some(m)) => {
+            let status = m
+                .value_of("status")
+                .unwrap_or_default()
+                .parse::<PublicationStatus>()
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            store
+                .update_publication_status(
+                    m.value_of("publication_id").unwrap_or_default(),
+                    m.value_of("product_id").unwrap_or_default(),
+                    status,
+                    m.value_of("message"),
+                    now(),
+                )
+                .map_err(|err| DaemonError::from_source(Box::new(err)))
+        }
+        ("list", Some(m)) => list_publications(&*store, m.value_of("product_id"), out),
+        _ => Err(DaemonError::with_message("A gdsn subcommand is required")),
+    }
+}
+
+/// Renders the trade items of the given products into `message`, checks it against the
+/// GridCatalogueItemNotification.xsd schema in `schema_dir` and records each product as pending
+/// publication. Returns the rendered message.
+pub fn publish(
+    product_store: &dyn ProductStore,
+    publication_store: &dyn GdsnPublicationStore,
+    mut message: CinMessage,
+    product_ids: &[&str],
+    service_id: Option<&str>,
+    schema_dir: &str,
+) -> Result<String, DaemonError> {
+    for product_id in product_ids {
+        let product = product_store
+            .get_product(product_id, service_id)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?
+            .ok_or_else(|| {
+                DaemonError::with_message(&format!("Product does not exist: {}", product_id))
+            })?;
+        message.trade_items.push(
+            CinTradeItem::from_product(&product)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+        );
+    }
+
+    let xml = message
+        .to_xml()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    validate_cin(&xml, schema_dir).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let publications = message
+        .trade_items
+        .iter()
+        .map(|trade_item| GdsnPublication {
+            publication_id: message.instance_identifier.clone(),
+            product_id: trade_item.gtin.clone(),
+            service_id: service_id.map(String::from),
+            data_recipient: message.data_recipient.clone(),
+            status: PublicationStatus::Pending,
+            status_message: None,
+            created_at: message.created_at,
+            updated_at: message.created_at,
+        })
+        .collect();
+    publication_store
+        .add_publications(publications)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok(xml)
+}
+
+/// Writes the publications, newest first, one product per line
+pub fn list_publications(
+    store: &dyn GdsnPublicationStore,
+    product_id: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let publications = store
+        .list_publications(product_id)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for publication in publications {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            publication.publication_id,
+            publication.product_id,
+            publication.data_recipient,
+            publication.status,
+            publication.updated_at,
+            publication.status_message.as_deref().unwrap_or("-"),
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+/// The directory the daemon's packages install the product XSDs to
+fn default_schema_dir() -> String {
+    let state_dir =
+        env::var(ENV_GRID_STATE_DIR).unwrap_or_else(|_| DEFAULT_GRID_STATE_DIR.to_string());
+    PathBuf::from(state_dir)
+        .join("xsd/product")
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn random_hex(length: usize) -> String {
+    let mut bytes = vec![0; length / 2];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use grid_sdk::migrations::run_sqlite_migrations;
+    use grid_sdk::product::gdsn::publication::store::DieselGdsnPublicationStore;
+    use grid_sdk::product::gdsn::GDSN_3_1_PROPERTY_NAME;
+    use grid_sdk::product::store::{DieselProductStore, ProductBuilder, PropertyValueBuilder};
+
+    const GTIN: &str = "00734730437958";
+
+    fn schema_dir() -> String {
+        let mut schema_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        schema_dir.push("../sdk/src/data_validation/xml/xsd/product");
+        schema_dir.to_string_lossy().into_owned()
+    }
+
+    fn message() -> CinMessage {
+        CinMessage {
+            instance_identifier: "5f0c1a2b3c4d5e6f".to_string(),
+            sender: "0000000000000".to_string(),
+            source_data_pool: "0000000000017".to_string(),
+            data_recipient: "0000000000024".to_string(),
+            command: DocumentCommand::Add,
+            created_at: 10,
+            trade_items: vec![],
+        }
+    }
+
+    /// Verify that a product created from GDSN data is rendered and recorded as pending, that
+    /// its status can be listed once reported, and that unknown products are not published
+    #[test]
+    fn test_publish_and_list() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        let product_store = DieselProductStore::new(pool.clone());
+        let publication_store = DieselGdsnPublicationStore::new(pool);
+
+        let trade_item = format!("<tradeItem><gtin>{}</gtin></tradeItem>", GTIN);
+        product_store
+            .add_product(
+                ProductBuilder::default()
+                    .with_product_id(GTIN.to_string())
+                    .with_product_address("product_address".to_string())
+                    .with_product_namespace("Gs1".to_string())
+                    .with_owner("test_org".to_string())
+                    .with_start_commit_number(1)
+                    .with_end_commit_number(i64::MAX)
+                    .with_properties(vec![PropertyValueBuilder::default()
+                        .with_product_id(GTIN.to_string())
+                        .with_product_address("product_address".to_string())
+                        .with_property_name(GDSN_3_1_PROPERTY_NAME.to_string())
+                        .with_data_type("String".to_string())
+                        .with_string_value(Some(trade_item.clone()))
+                        .with_start_commit_number(1)
+                        .with_end_commit_number(i64::MAX)
+                        .build()
+                        .expect("Failed to build property")])
+                    .build()
+                    .expect("Failed to build product"),
+            )
+            .expect("Failed to add product");
+
+        let xml = publish(
+            &product_store,
+            &publication_store,
+            message(),
+            &[GTIN],
+            None,
+            &schema_dir(),
+        )
+        .expect("Failed to publish");
+        assert!(xml.contains(&trade_item));
+
+        assert!(publish(
+            &product_store,
+            &publication_store,
+            message(),
+            &["10036016500279"],
+            None,
+            &schema_dir(),
+        )
+        .is_err());
+
+        publication_store
+            .update_publication_status(
+                "5f0c1a2b3c4d5e6f",
+                GTIN,
+                PublicationStatus::Synchronised,
+                None,
+                20,
+            )
+            .expect("Failed to update status");
+        let mut out = vec![];
+        list_publications(&publication_store, None, &mut out).expect("Failed to list");
+        assert_eq!(
+            String::from_utf8(out).expect("Output is not UTF-8"),
+            format!(
+                "5f0c1a2b3c4d5e6f\t{}\t0000000000024\tSYNCHRONISED\t20\t-\n",
+                GTIN
+            )
+        );
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use grid_sdk::api_keys::{authorize_request, AuthorizeError, Scope};
+use grid_sdk::store::TransactionalStoreFactory;
+use tonic::{Request, Status};
+
+const MFG_BATCH_SERVICE_PATH: &str = "grid.mfg_batch.MfgBatchService";
+
+/// Rejects requests that do not carry an API key with the mfg_batch:read scope as a bearer
+/// token. Each check on a known key is recorded in the key's usage log.
+// Status is large, but it is what tonic interceptors return
+#[allow(clippy::result_large_err)]
+pub fn check_api_key(
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |request: Request<()>| {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match authorize_request(
+            &*store_factory.get_api_key_store(),
+            token,
+            Scope::MfgBatchRead,
+            "gRPC",
+            MFG_BATCH_SERVICE_PATH,
+        ) {
+            Ok(_) => Ok(request),
+            Err(AuthorizeError::Unauthenticated) => {
+                Err(Status::unauthenticated("A valid API key is required"))
+            }
+            Err(err @ AuthorizeError::MissingScope(_)) => {
+                Err(Status::permission_denied(err.to_string()))
+            }
+            Err(AuthorizeError::StoreError(err)) => Err(Status::internal(err.to_string())),
+        }
+    }
+}
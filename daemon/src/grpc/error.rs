@@ -0,0 +1,55 @@
+// Copyright 2018-2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum GrpcServerError {
+    StartUpError(String),
+    StdError(std::io::Error),
+    Transport(tonic::transport::Error),
+}
+
+impl From<std::io::Error> for GrpcServerError {
+    fn from(err: std::io::Error) -> GrpcServerError {
+        GrpcServerError::StdError(err)
+    }
+}
+
+impl From<tonic::transport::Error> for GrpcServerError {
+    fn from(err: tonic::transport::Error) -> GrpcServerError {
+        GrpcServerError::Transport(err)
+    }
+}
+
+impl Error for GrpcServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GrpcServerError::StartUpError(_) => None,
+            GrpcServerError::StdError(err) => Some(err),
+            GrpcServerError::Transport(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for GrpcServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrpcServerError::StartUpError(e) => write!(f, "Start-up Error: {}", e),
+            GrpcServerError::StdError(e) => write!(f, "Std Error: {}", e),
+            GrpcServerError::Transport(e) => write!(f, "Transport Error: {}", e),
+        }
+    }
+}
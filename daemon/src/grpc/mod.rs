@@ -0,0 +1,133 @@
+// Copyright 2018-2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A gRPC server giving read access to the mfg_batches in the daemon's database
+
+#[cfg(feature = "api-keys")]
+mod api_key;
+pub mod error;
+#[cfg(feature = "grpc-pseudonyms")]
+mod pseudonym;
+mod service;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(feature = "api-keys")]
+use grid_sdk::store::TransactionalStoreFactory;
+use tokio::sync::Notify;
+use tonic::transport::Server;
+
+use crate::database::SharedMfgBatchStore;
+pub use crate::grpc::error::GrpcServerError;
+
+#[cfg(feature = "api-keys")]
+use self::api_key::check_api_key;
+use self::proto::mfg_batch_service_server::MfgBatchServiceServer;
+#[cfg(feature = "grpc-pseudonyms")]
+use self::proto::pseudonym_mapping_service_server::PseudonymMappingServiceServer;
+#[cfg(feature = "grpc-pseudonyms")]
+pub use self::pseudonym::PseudonymSettings;
+#[cfg(feature = "grpc-pseudonyms")]
+use self::pseudonym::{check_admin_token, PseudonymMappingGrpcService};
+use self::service::MfgBatchGrpcService;
+
+mod proto {
+    tonic::include_proto!("grid.mfg_batch");
+}
+
+pub struct GrpcShutdownHandle {
+    notify: Arc<Notify>,
+}
+
+impl GrpcShutdownHandle {
+    pub fn shutdown(&self) {
+        // notify_one keeps the permit if the server is not yet waiting on it
+        self.notify.notify_one();
+    }
+}
+
+pub fn run(
+    bind_url: &str,
+    store: SharedMfgBatchStore,
+    #[cfg(feature = "grpc-pseudonyms")] pseudonym_settings: Option<PseudonymSettings>,
+    #[cfg(feature = "api-keys")] api_key_store_factory: Option<Arc<dyn TransactionalStoreFactory>>,
+) -> Result<
+    (
+        GrpcShutdownHandle,
+        thread::JoinHandle<Result<(), GrpcServerError>>,
+    ),
+    GrpcServerError,
+> {
+    let addr: SocketAddr = bind_url.parse().map_err(|err| {
+        GrpcServerError::StartUpError(format!("Invalid gRPC bind address {}: {}", bind_url, err))
+    })?;
+    let notify = Arc::new(Notify::new());
+    let shutdown = notify.clone();
+
+    #[allow(unused_mut)]
+    let mut mfg_batch_service = MfgBatchGrpcService::new(store.clone());
+
+    // The mapping service is only served when admins have a token to authenticate with
+    #[cfg(feature = "grpc-pseudonyms")]
+    let mapping_service = match pseudonym_settings {
+        Some(settings) => {
+            let pseudonymizer = settings.pseudonymizer.clone();
+            mfg_batch_service = mfg_batch_service.with_transform(Arc::new(move |mfg_batch| {
+                pseudonymizer.pseudonymize(mfg_batch)
+            }));
+
+            settings.admin_token.as_deref().map(|admin_token| {
+                PseudonymMappingServiceServer::with_interceptor(
+                    PseudonymMappingGrpcService::new(store, settings.pseudonymizer.clone()),
+                    check_admin_token(admin_token),
+                )
+            })
+        }
+        None => None,
+    };
+
+    // Reads require an API key when the daemon is given a store to check them against
+    #[cfg(feature = "api-keys")]
+    let router = match api_key_store_factory {
+        Some(store_factory) => {
+            Server::builder().add_service(MfgBatchServiceServer::with_interceptor(
+                mfg_batch_service,
+                check_api_key(store_factory),
+            ))
+        }
+        None => Server::builder().add_service(MfgBatchServiceServer::new(mfg_batch_service)),
+    };
+    #[cfg(not(feature = "api-keys"))]
+    let router = Server::builder().add_service(MfgBatchServiceServer::new(mfg_batch_service));
+    #[cfg(feature = "grpc-pseudonyms")]
+    let router = router.add_optional_service(mapping_service);
+
+    let join_handle = thread::Builder::new()
+        .name("GridGrpc".into())
+        .spawn(move || {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?;
+
+            runtime.block_on(router.serve_with_shutdown(addr, shutdown.notified()))?;
+
+            info!("gRPC server terminating");
+
+            Ok(())
+        })?;
+
+    Ok((GrpcShutdownHandle { notify }, join_handle))
+}
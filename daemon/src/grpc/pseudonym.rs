@@ -0,0 +1,176 @@
+// Copyright 2018-2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+
+use grid_sdk::mfg_batch::store::{ListMfgBatchFilters, Pseudonymizer};
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    Request, Response, Status,
+};
+
+use crate::config::GridConfig;
+use crate::error::DaemonError;
+
+use super::proto::{
+    pseudonym_mapping_service_server::PseudonymMappingService, ResolvePseudonymsRequest,
+    ResolvePseudonymsResponse,
+};
+use super::service::{non_empty, to_status};
+use super::SharedMfgBatchStore;
+
+const RESOLVE_PAGE_SIZE: i64 = 100;
+
+/// How the gRPC server pseudonymizes the mfg_batches it serves
+pub struct PseudonymSettings {
+    pub pseudonymizer: Arc<Pseudonymizer>,
+    /// Token admins present to resolve pseudonyms; the mapping service is not served without one
+    pub admin_token: Option<String>,
+}
+
+impl PseudonymSettings {
+    /// Loads the settings from the files named in the config, if a pseudonym key is configured
+    pub fn from_config(config: &GridConfig) -> Result<Option<Self>, DaemonError> {
+        let key_file = match config.grpc_pseudonym_key_file() {
+            Some(key_file) => key_file,
+            None => return Ok(None),
+        };
+
+        let key = fs::read(key_file).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let admin_token = config
+            .grpc_admin_token_file()
+            .map(fs::read_to_string)
+            .transpose()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?
+            .map(|token| token.trim().to_string());
+
+        Ok(Some(Self {
+            pseudonymizer: Arc::new(Pseudonymizer::new(
+                key,
+                config.grpc_internal_properties().to_vec(),
+            )),
+            admin_token,
+        }))
+    }
+}
+
+/// Rejects requests that do not carry the admin token as a bearer token
+// Status is large, but it is what tonic interceptors return
+#[allow(clippy::result_large_err)]
+pub fn check_admin_token(
+    admin_token: &str,
+) -> impl Fn(Request<()>) -> Result<Request<()>, Status> + Clone {
+    let expected = format!("Bearer {}", admin_token)
+        .parse::<MetadataValue<Ascii>>()
+        .ok();
+
+    move |request: Request<()>| match (&expected, request.metadata().get("authorization")) {
+        (Some(expected), Some(token)) if token == expected => Ok(request),
+        _ => Err(Status::unauthenticated("A valid admin token is required")),
+    }
+}
+
+pub struct PseudonymMappingGrpcService {
+    store: SharedMfgBatchStore,
+    pseudonymizer: Arc<Pseudonymizer>,
+}
+
+impl PseudonymMappingGrpcService {
+    pub fn new(store: SharedMfgBatchStore, pseudonymizer: Arc<Pseudonymizer>) -> Self {
+        Self {
+            store,
+            pseudonymizer,
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+#[tonic::async_trait]
+impl PseudonymMappingService for PseudonymMappingGrpcService {
+    async fn resolve_pseudonyms(
+        &self,
+        request: Request<ResolvePseudonymsRequest>,
+    ) -> Result<Response<ResolvePseudonymsResponse>, Status> {
+        let ResolvePseudonymsRequest {
+            service_id,
+            pseudonyms,
+        } = request.into_inner();
+        let store = self.store.clone();
+        let pseudonymizer = self.pseudonymizer.clone();
+
+        // Pseudonyms are one-way, so they are resolved by pseudonymizing the stored values
+        // until every requested pseudonym has been seen
+        let values = tokio::task::spawn_blocking(move || {
+            let service_id = non_empty(&service_id);
+            let mut unresolved = pseudonyms.into_iter().collect::<HashSet<_>>();
+            let mut values = HashMap::new();
+            let filters = ListMfgBatchFilters::default();
+            let mut offset = 0;
+
+            while !unresolved.is_empty() {
+                let list = store
+                    .list_mfg_batches(service_id, &filters, offset, RESOLVE_PAGE_SIZE)
+                    .map_err(to_status)?;
+                let data = list.data();
+                let page_len = data.len() as i64;
+
+                for mfg_batch in data {
+                    for (pseudonym, value) in pseudonymizer.mapping(&mfg_batch) {
+                        if unresolved.remove(&pseudonym) {
+                            values.insert(pseudonym, value);
+                        }
+                    }
+                }
+
+                offset += page_len;
+                if page_len < RESOLVE_PAGE_SIZE || offset >= list.paging().total {
+                    break;
+                }
+            }
+
+            Ok::<_, Status>(values)
+        })
+        .await
+        .map_err(|err| Status::internal(err.to_string()))??;
+
+        Ok(Response::new(ResolvePseudonymsResponse { values }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that only requests carrying the admin token as a bearer token are let through
+    #[test]
+    fn test_check_admin_token() {
+        let check = check_admin_token("secret");
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        assert!(check(request).is_ok());
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer guess".parse().unwrap());
+        assert!(check(request).is_err());
+
+        assert!(check(Request::new(())).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright 2018-2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+use grid_sdk::mfg_batch::store::ListMfgBatchQualityScoreFilters;
+use grid_sdk::mfg_batch::store::{
+    ListMfgBatchFilters, MfgBatch, MfgBatchList, MfgBatchStoreError, PropertyValue,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use super::proto::{
+    mfg_batch_service_server::MfgBatchService, GetMfgBatchRequest, ListMfgBatchesRequest,
+    ListMfgBatchesResponse, MfgBatchFilters, MfgBatchPropertyValue, MfgBatchQualityScore,
+    MfgBatchRecord, StreamMfgBatchesRequest,
+};
+use super::SharedMfgBatchStore;
+
+const DEFAULT_LIMIT: i64 = 10;
+const STREAM_PAGE_SIZE: i64 = 100;
+
+/// Applied to every mfg_batch before it is served
+pub type MfgBatchTransform = Arc<dyn Fn(MfgBatch) -> MfgBatch + Send + Sync>;
+
+pub struct MfgBatchGrpcService {
+    store: SharedMfgBatchStore,
+    transform: Option<MfgBatchTransform>,
+}
+
+impl MfgBatchGrpcService {
+    pub fn new(store: SharedMfgBatchStore) -> Self {
+        Self {
+            store,
+            transform: None,
+        }
+    }
+
+    #[cfg(feature = "grpc-pseudonyms")]
+    pub fn with_transform(mut self, transform: MfgBatchTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Runs a blocking store call off of the async runtime's worker threads
+    // Status is large, but it is what every tonic handler returns
+    #[allow(clippy::result_large_err)]
+    async fn with_store<F, T>(&self, f: F) -> Result<T, Status>
+    where
+        F: FnOnce(&SharedMfgBatchStore) -> Result<T, MfgBatchStoreError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let store = self.store.clone();
+        // Store errors are not Send, so they are converted before leaving the blocking task
+        tokio::task::spawn_blocking(move || f(&store).map_err(to_status))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+    }
+}
+
+#[tonic::async_trait]
+impl MfgBatchService for MfgBatchGrpcService {
+    async fn get_mfg_batch(
+        &self,
+        request: Request<GetMfgBatchRequest>,
+    ) -> Result<Response<MfgBatchRecord>, Status> {
+        let request = request.into_inner();
+        let mfg_batch_id = request.mfg_batch_id.clone();
+
+        let mfg_batch = self
+            .with_store(move |store| {
+                store.get_mfg_batch(&request.mfg_batch_id, non_empty(&request.service_id))
+            })
+            .await?
+            .ok_or_else(|| Status::not_found(format!("Mfg batch not found: {}", mfg_batch_id)))?;
+
+        Ok(Response::new(to_record(&self.transform, mfg_batch)))
+    }
+
+    async fn list_mfg_batches(
+        &self,
+        request: Request<ListMfgBatchesRequest>,
+    ) -> Result<Response<ListMfgBatchesResponse>, Status> {
+        let ListMfgBatchesRequest {
+            service_id,
+            filters,
+            offset,
+            limit,
+        } = request.into_inner();
+        let filters = to_store_filters(filters);
+        let limit = if limit > 0 { limit } else { DEFAULT_LIMIT };
+
+        let (list, mut quality_scores) = self
+            .with_store(move |store| {
+                let list =
+                    store.list_mfg_batches(non_empty(&service_id), &filters, offset, limit)?;
+                let quality_scores = get_quality_scores(store, &list, non_empty(&service_id))?;
+                Ok((list, quality_scores))
+            })
+            .await?;
+
+        let paging = list.paging();
+        Ok(Response::new(ListMfgBatchesResponse {
+            offset: paging.offset,
+            limit: paging.limit,
+            total: paging.total,
+            data: list
+                .data()
+                .into_iter()
+                .map(|mfg_batch| MfgBatchRecord {
+                    quality_score: quality_scores.remove(mfg_batch.mfg_batch_id()),
+                    ..to_record(&self.transform, mfg_batch)
+                })
+                .collect(),
+        }))
+    }
+
+    type StreamMfgBatchesStream = ReceiverStream<Result<MfgBatchRecord, Status>>;
+
+    async fn stream_mfg_batches(
+        &self,
+        request: Request<StreamMfgBatchesRequest>,
+    ) -> Result<Response<Self::StreamMfgBatchesStream>, Status> {
+        let StreamMfgBatchesRequest {
+            service_id,
+            filters,
+        } = request.into_inner();
+        let filters = to_store_filters(filters);
+        let store = self.store.clone();
+        let transform = self.transform.clone();
+        let (tx, rx) = mpsc::channel(STREAM_PAGE_SIZE as usize);
+
+        tokio::task::spawn_blocking(move || {
+            let mut offset = 0;
+            loop {
+                let list = match store.list_mfg_batches(
+                    non_empty(&service_id),
+                    &filters,
+                    offset,
+                    STREAM_PAGE_SIZE,
+                ) {
+                    Ok(list) => list,
+                    Err(err) => {
+                        let _ = tx.blocking_send(Err(to_status(err)));
+                        return;
+                    }
+                };
+
+                let data = list.data();
+                let page_len = data.len() as i64;
+                for mfg_batch in data {
+                    if tx
+                        .blocking_send(Ok(to_record(&transform, mfg_batch)))
+                        .is_err()
+                    {
+                        // The client has gone away
+                        return;
+                    }
+                }
+
+                offset += page_len;
+                if page_len < STREAM_PAGE_SIZE || offset >= list.paging().total {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+fn to_record(transform: &Option<MfgBatchTransform>, mfg_batch: MfgBatch) -> MfgBatchRecord {
+    match transform {
+        Some(transform) => MfgBatchRecord::from(transform(mfg_batch)),
+        None => MfgBatchRecord::from(mfg_batch),
+    }
+}
+
+/// Gets the quality scores of a page of mfg_batches, by mfg_batch ID
+#[cfg(feature = "mfg-batch-quality-scores")]
+fn get_quality_scores(
+    store: &SharedMfgBatchStore,
+    list: &MfgBatchList,
+    service_id: Option<&str>,
+) -> Result<HashMap<String, MfgBatchQualityScore>, MfgBatchStoreError> {
+    let filters = ListMfgBatchQualityScoreFilters {
+        mfg_batch_ids: Some(
+            list.data()
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string())
+                .collect(),
+        ),
+        max_score: None,
+    };
+
+    Ok(store
+        .list_mfg_batch_quality_scores(service_id, &filters, 0, list.data().len() as i64)?
+        .into_iter()
+        .map(|score| {
+            (
+                score.mfg_batch_id,
+                MfgBatchQualityScore {
+                    score: score.score,
+                    failed_checks: score.failed_checks,
+                    scored_at: score.scored_at,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Mfg_batches are not scored without quality scores
+#[cfg(not(feature = "mfg-batch-quality-scores"))]
+fn get_quality_scores(
+    _store: &SharedMfgBatchStore,
+    _list: &MfgBatchList,
+    _service_id: Option<&str>,
+) -> Result<HashMap<String, MfgBatchQualityScore>, MfgBatchStoreError> {
+    Ok(HashMap::new())
+}
+
+pub(super) fn non_empty(value: &str) -> Option<&str> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn to_store_filters(filters: Option<MfgBatchFilters>) -> ListMfgBatchFilters {
+    let filters = filters.unwrap_or_default();
+    ListMfgBatchFilters {
+        owner: non_empty(&filters.owner).map(ToOwned::to_owned),
+        mfg_batch_namespace: non_empty(&filters.mfg_batch_namespace).map(ToOwned::to_owned),
+        expiring_before: Some(filters.expiring_before).filter(|time| *time != 0),
+        expiring_after: Some(filters.expiring_after).filter(|time| *time != 0),
+        archived: if filters.include_archived {
+            None
+        } else {
+            Some(false)
+        },
+        manufacture_location: non_empty(&filters.manufacture_location).map(ToOwned::to_owned),
+        #[cfg(feature = "mfg-batch-visibility")]
+        visibility: None,
+    }
+}
+
+pub(super) fn to_status(err: MfgBatchStoreError) -> Status {
+    match err {
+        MfgBatchStoreError::NotFoundError(msg) => Status::not_found(msg),
+        MfgBatchStoreError::InvalidArgumentError(err) => Status::invalid_argument(err.to_string()),
+        MfgBatchStoreError::ResourceTemporarilyUnavailableError(err) => {
+            Status::unavailable(err.to_string())
+        }
+        err => {
+            error!("{}", err);
+            Status::internal("An internal error occurred")
+        }
+    }
+}
+
+impl From<MfgBatch> for MfgBatchRecord {
+    fn from(mfg_batch: MfgBatch) -> Self {
+        Self {
+            mfg_batch_id: mfg_batch.mfg_batch_id().to_string(),
+            mfg_batch_address: mfg_batch.mfg_batch_address().to_string(),
+            mfg_batch_namespace: mfg_batch.mfg_batch_namespace().to_string(),
+            owner: mfg_batch.owner().to_string(),
+            start_commit_num: *mfg_batch.start_commit_num(),
+            end_commit_num: *mfg_batch.end_commit_num(),
+            service_id: mfg_batch.service_id().unwrap_or_default().to_string(),
+            last_updated: mfg_batch.last_updated().copied().unwrap_or_default(),
+            properties: mfg_batch
+                .properties()
+                .into_iter()
+                .map(MfgBatchPropertyValue::from)
+                .collect(),
+            parent_batches: mfg_batch.parent_batches().to_vec(),
+            quantity: mfg_batch.quantity().unwrap_or_default(),
+            uom: mfg_batch.uom().unwrap_or_default().to_string(),
+            expected_quantity: mfg_batch.expected_quantity().unwrap_or_default(),
+            production_date: mfg_batch.production_date().unwrap_or_default(),
+            expiration_date: mfg_batch.expiration_date().unwrap_or_default(),
+            archived: mfg_batch.archived(),
+            quality_score: None,
+            manufacture_location: mfg_batch
+                .manufacture_location()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+impl From<PropertyValue> for MfgBatchPropertyValue {
+    fn from(property: PropertyValue) -> Self {
+        let lat_long = property.lat_long_value();
+        Self {
+            property_name: property.property_name().to_string(),
+            data_type: property.data_type().to_string(),
+            bytes_value: property.bytes_value().unwrap_or_default(),
+            boolean_value: property.boolean_value().unwrap_or_default(),
+            number_value: property.number_value().unwrap_or_default(),
+            string_value: property.string_value().unwrap_or_default().to_string(),
+            enum_value: property.enum_value().unwrap_or_default(),
+            struct_values: property
+                .struct_values()
+                .into_iter()
+                .map(MfgBatchPropertyValue::from)
+                .collect(),
+            latitude: lat_long.as_ref().map(|l| l.latitude).unwrap_or_default(),
+            longitude: lat_long.as_ref().map(|l| l.longitude).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that empty and zero request fields are not used as filters
+    #[test]
+    fn test_to_store_filters() {
+        let filters = to_store_filters(Some(MfgBatchFilters {
+            owner: "org-1".to_string(),
+            expiring_before: 1_631_536_000,
+            manufacture_location: "0614141000005".to_string(),
+            ..MfgBatchFilters::default()
+        }));
+
+        assert_eq!(filters.owner.as_deref(), Some("org-1"));
+        assert_eq!(filters.mfg_batch_namespace, None);
+        assert_eq!(filters.expiring_before, Some(1_631_536_000));
+        assert_eq!(filters.expiring_after, None);
+        assert_eq!(filters.archived, Some(false));
+        assert_eq!(
+            filters.manufacture_location.as_deref(),
+            Some("0614141000005")
+        );
+
+        let filters = to_store_filters(None);
+        assert!(filters.owner.is_none() && filters.expiring_before.is_none());
+        assert!(filters.manufacture_location.is_none());
+
+        let filters = to_store_filters(Some(MfgBatchFilters {
+            include_archived: true,
+            ..MfgBatchFilters::default()
+        }));
+        assert_eq!(filters.archived, None);
+    }
+}
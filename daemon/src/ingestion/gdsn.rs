@@ -0,0 +1,90 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Builds the product transactions for the trade items in a GDSN 3.1 file. A trade item whose
+//! GTIN is already a product updates it; any other creates a product owned by the configured
+//! organization.
+
+use grid_sdk::pike::addressing::GRID_PIKE_NAMESPACE;
+use grid_sdk::product::{addressing::GRID_PRODUCT_NAMESPACE, gdsn::get_trade_items_from_xml_str};
+use grid_sdk::protocol::product::payload::{Action, ProductPayload, ProductPayloadBuilder};
+use grid_sdk::rest_api::resources::submit::v1::{Batch, Payload, SubmitBatchRequest, Transaction};
+use grid_sdk::schema::addressing::GRID_SCHEMA_NAMESPACE;
+use grid_sdk::store::StoreFactory;
+
+use crate::error::DaemonError;
+
+const PRODUCT_FAMILY_NAME: &str = "grid_product";
+const PRODUCT_FAMILY_VERSION: &str = "2";
+
+pub fn build_submit_request(
+    xml: &[u8],
+    owner: &str,
+    store_factory: &dyn StoreFactory,
+    service_id: Option<String>,
+    timestamp: u64,
+) -> Result<SubmitBatchRequest, DaemonError> {
+    let xml = std::str::from_utf8(xml).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let trade_items =
+        get_trade_items_from_xml_str(xml).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    if trade_items.is_empty() {
+        return Err(DaemonError::with_message("GDSN file holds no trade items"));
+    }
+
+    let product_store = store_factory.get_grid_product_store();
+    let mut transactions = Vec::with_capacity(trade_items.len());
+    for trade_item in trade_items {
+        let exists = product_store
+            .get_product(&trade_item.gtin, service_id.as_deref())
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?
+            .is_some();
+        let action = if exists {
+            trade_item.into_update_payload().map(Action::ProductUpdate)
+        } else {
+            trade_item
+                .into_create_payload(owner)
+                .map(Action::ProductCreate)
+        }
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let payload = ProductPayloadBuilder::new()
+            .with_action(action)
+            .with_timestamp(timestamp)
+            .build()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        transactions.push(product_transaction(payload));
+    }
+
+    Ok(SubmitBatchRequest {
+        batches: vec![Batch {
+            trace: false,
+            service_id,
+            transactions,
+        }],
+    })
+}
+
+fn product_transaction(payload: ProductPayload) -> Transaction {
+    Transaction {
+        family_name: PRODUCT_FAMILY_NAME.to_string(),
+        version: PRODUCT_FAMILY_VERSION.to_string(),
+        dependencies: vec![],
+        inputs: vec![
+            GRID_PIKE_NAMESPACE.to_string(),
+            GRID_SCHEMA_NAMESPACE.to_string(),
+            GRID_PRODUCT_NAMESPACE.to_string(),
+        ],
+        outputs: vec![GRID_PRODUCT_NAMESPACE.to_string()],
+        payload: Payload::MappedProduct(payload),
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ingests the CSV, GDSN and EDI files dropped into a directory or SFTP location.
+//!
+//! Files are picked up in name order, and their kind is told from their extension: CSV files
+//! are applied row by row to the CSV data mapping, GDSN XML files are submitted as products, and
+//! EDI ship notices are applied to the EDI data mapping, as if they were posted to
+//! `/integrations/{mapping}/submit`. Each file is moved to the `processed` folder once its batch
+//! is stored to be sent to the DLT, or to the `failed` folder if it cannot be read, imported or
+//! submitted, and what became of it is recorded in the ingestion log. Hidden files and files
+//! ending in `.tmp` are left alone, so a file can be written under such a name and renamed once
+//! complete.
+
+mod gdsn;
+mod source;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use grid_sdk::data_mapping::{DataMapping, PayloadFormat};
+use grid_sdk::ingestion::{store::IngestionLogEntry, FileKind, IngestionStatus};
+use grid_sdk::mfg_batch::store::MfgBatchStore;
+use grid_sdk::rest_api::actix_web_3::DataMappingState;
+use grid_sdk::rest_api::resources::{
+    data_mapping::v1::build_submit_request,
+    submit::v1::{submit_batches, SubmitBatchRequest},
+};
+use grid_sdk::store::TransactionalStoreFactory;
+use uuid::Uuid;
+
+use crate::config::GridConfig;
+use crate::database::SharedMfgBatchStore;
+use crate::error::DaemonError;
+
+use self::source::IngestionSource;
+
+const PROCESSED_FOLDER: &str = "processed";
+const FAILED_FOLDER: &str = "failed";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct IngestionShutdownHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl IngestionShutdownHandle {
+    pub fn shutdown(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// What the service needs to import and submit files; a kind of file without its mapping or
+/// owner fails
+pub struct IngestionSettings {
+    pub source: Box<dyn IngestionSource>,
+    pub csv_mapping: Option<DataMapping>,
+    pub edi_mapping: Option<DataMapping>,
+    /// The organization that owns the products created from GDSN files
+    pub gdsn_owner: Option<String>,
+    pub service_id: Option<String>,
+    pub key_file_name: String,
+}
+
+/// Starts the service if the configuration names a location to ingest from, applying the
+/// mappings it names from those the REST API serves
+pub fn run_from_config(
+    config: &GridConfig,
+    data_mapping_state: &DataMappingState,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+) -> Result<Option<(IngestionShutdownHandle, thread::JoinHandle<()>)>, DaemonError> {
+    let location = match config.ingest_from() {
+        Some(location) => location,
+        None => return Ok(None),
+    };
+    if config.ingest_csv_mapping().is_none()
+        && config.ingest_edi_mapping().is_none()
+        && config.ingest_gdsn_owner().is_none()
+    {
+        return Err(DaemonError::with_message(
+            "--ingest-from requires --ingest-csv-mapping, --ingest-edi-mapping or \
+            --ingest-gdsn-owner",
+        ));
+    }
+
+    let mapping = |name: Option<&str>, format: PayloadFormat| {
+        name.map(|name| {
+            let mapping = data_mapping_state.mappings.get(name).ok_or_else(|| {
+                DaemonError::with_message(&format!(
+                    "Ingestion mapping {} is not defined in the mapping directory",
+                    name
+                ))
+            })?;
+            if mapping.format != format {
+                return Err(DaemonError::with_message(&format!(
+                    "Ingestion mapping {} is not a {:?} mapping",
+                    name, format
+                )));
+            }
+            Ok(mapping.clone())
+        })
+        .transpose()
+    };
+    let csv_mapping = mapping(config.ingest_csv_mapping(), PayloadFormat::Csv)?;
+    let edi_mapping = mapping(config.ingest_edi_mapping(), PayloadFormat::Edi)?;
+
+    let mfg_batch_store = data_mapping_state
+        .mfg_batch_store
+        .clone()
+        .ok_or_else(|| DaemonError::with_message("Ingestion requires an mfg_batch store"))?;
+
+    run(
+        IngestionSettings {
+            source: source::open(location, &[PROCESSED_FOLDER, FAILED_FOLDER])?,
+            csv_mapping,
+            edi_mapping,
+            gdsn_owner: config.ingest_gdsn_owner().map(ToOwned::to_owned),
+            service_id: config.ingest_service_id().map(ToOwned::to_owned),
+            key_file_name: config.key_file_name().to_string(),
+        },
+        store_factory,
+        mfg_batch_store,
+    )
+    .map(Some)
+}
+
+pub fn run(
+    settings: IngestionSettings,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    mfg_batch_store: SharedMfgBatchStore,
+) -> Result<(IngestionShutdownHandle, thread::JoinHandle<()>), DaemonError> {
+    settings.source.prepare()?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let join_handle = thread::Builder::new()
+        .name("Ingestion".into())
+        .spawn(move || {
+            info!("Ingesting files from {}", settings.source.describe());
+            while thread_running.load(Ordering::SeqCst) {
+                match settings.source.pending_files() {
+                    Ok(files) => {
+                        for file in files {
+                            if !thread_running.load(Ordering::SeqCst) {
+                                break;
+                            }
+                            process(&file, &settings, &*store_factory, &mfg_batch_store);
+                        }
+                    }
+                    Err(err) => error!(
+                        "Unable to list files in {}: {}",
+                        settings.source.describe(),
+                        err
+                    ),
+                }
+
+                let mut waited = Duration::from_secs(0);
+                while waited < POLL_INTERVAL && thread_running.load(Ordering::SeqCst) {
+                    thread::sleep(SHUTDOWN_CHECK_INTERVAL);
+                    waited += SHUTDOWN_CHECK_INTERVAL;
+                }
+            }
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok((IngestionShutdownHandle { running }, join_handle))
+}
+
+fn process(
+    name: &str,
+    settings: &IngestionSettings,
+    store_factory: &dyn TransactionalStoreFactory,
+    mfg_batch_store: &SharedMfgBatchStore,
+) {
+    let started_at = now();
+    let kind = FileKind::from_file_name(name);
+    let correlation_id = Uuid::new_v4().to_string();
+    let result = kind
+        .ok_or_else(|| DaemonError::with_message("File is not a CSV, GDSN or EDI file"))
+        .and_then(|kind| {
+            let contents = settings.source.read(name)?;
+            import(kind, &contents, settings, store_factory, mfg_batch_store)
+        })
+        .and_then(|request| {
+            let transactions = request
+                .batches
+                .iter()
+                .map(|batch| batch.transactions.len())
+                .sum::<usize>();
+            submit_batches(
+                &settings.key_file_name,
+                store_factory.get_batch_store(),
+                request,
+                &correlation_id,
+            )
+            .map_err(|err| DaemonError::with_message(&err.to_string()))?;
+            Ok(transactions)
+        });
+
+    let (status, message, folder) = match &result {
+        Ok(transactions) => {
+            info!(
+                "Submitted {} transactions from {} with correlation ID {}",
+                transactions, name, correlation_id
+            );
+            (
+                IngestionStatus::Processed,
+                format!("Submitted {} transactions", transactions),
+                PROCESSED_FOLDER,
+            )
+        }
+        Err(err) => {
+            error!("Unable to ingest {}: {}", name, err);
+            (IngestionStatus::Failed, err.to_string(), FAILED_FOLDER)
+        }
+    };
+
+    if let Err(err) = store_factory
+        .get_ingestion_log_store()
+        .add_ingestion_log_entry(IngestionLogEntry {
+            file_name: name.to_string(),
+            source: settings.source.describe(),
+            kind,
+            status,
+            message: Some(message),
+            correlation_id: result.as_ref().ok().map(|_| correlation_id.clone()),
+            started_at,
+            finished_at: now(),
+        })
+    {
+        error!("Unable to record the ingestion of {}: {}", name, err);
+    }
+
+    if let Err(err) = settings.source.move_to(name, folder) {
+        error!("Unable to move {} to {}: {}", name, folder, err);
+    }
+}
+
+/// Runs the file through the importer for its kind
+fn import(
+    kind: FileKind,
+    contents: &[u8],
+    settings: &IngestionSettings,
+    store_factory: &dyn TransactionalStoreFactory,
+    mfg_batch_store: &SharedMfgBatchStore,
+) -> Result<SubmitBatchRequest, DaemonError> {
+    let missing = |argument: &str| {
+        DaemonError::with_message(&format!(
+            "{} files are not ingested without {}",
+            kind, argument
+        ))
+    };
+
+    let apply_mapping = |mapping: Option<&DataMapping>, argument: &str| {
+        build_submit_request(
+            mapping.ok_or_else(|| missing(argument))?,
+            contents,
+            store_factory,
+            Some(&**mfg_batch_store as &dyn MfgBatchStore),
+            settings.service_id.clone(),
+        )
+        .map_err(|err| DaemonError::with_message(&err.to_string()))
+    };
+
+    match kind {
+        FileKind::Csv => apply_mapping(settings.csv_mapping.as_ref(), "--ingest-csv-mapping"),
+        FileKind::Edi => apply_mapping(settings.edi_mapping.as_ref(), "--ingest-edi-mapping"),
+        FileKind::Gdsn => gdsn::build_submit_request(
+            contents,
+            settings
+                .gdsn_owner
+                .as_deref()
+                .ok_or_else(|| missing("--ingest-gdsn-owner"))?,
+            store_factory,
+            settings.service_id.clone(),
+            now() as u64,
+        ),
+    }
+}
+
+/// Returns the time in seconds since the epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
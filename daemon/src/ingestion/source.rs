@@ -0,0 +1,209 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Where ingested files are found: a local directory, or a directory on an SFTP server reached
+//! with the system's `sftp` client.
+
+use std::fs;
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+use crate::error::DaemonError;
+use crate::sftp::{quote, SftpLocation};
+
+/// A place files are picked up from and then moved out of the way
+pub trait IngestionSource: Send {
+    /// Describes the source in log messages and the ingestion log
+    fn describe(&self) -> String;
+
+    /// Creates the folders files are moved into, if they do not exist
+    fn prepare(&self) -> Result<(), DaemonError>;
+
+    /// Lists the names of the files waiting, in name order. Hidden files, files ending in
+    /// `.tmp` and the folders files are moved into are left out.
+    fn pending_files(&self) -> Result<Vec<String>, DaemonError>;
+
+    /// Returns the contents of a waiting file
+    fn read(&self, name: &str) -> Result<Vec<u8>, DaemonError>;
+
+    /// Moves a file into one of the folders prepared for it
+    fn move_to(&self, name: &str, folder: &str) -> Result<(), DaemonError>;
+}
+
+/// Opens the source a `--ingest-from` value names, which is either a directory or an
+/// `sftp://` URL
+pub fn open(
+    location: &str,
+    folders: &[&'static str],
+) -> Result<Box<dyn IngestionSource>, DaemonError> {
+    if location.starts_with("sftp://") {
+        Ok(Box::new(SftpSource::from_url(location, folders)?))
+    } else {
+        Ok(Box::new(DirectorySource {
+            dir: PathBuf::from(location),
+            folders: folders.to_vec(),
+        }))
+    }
+}
+
+fn is_pending(name: &str, folders: &[&str]) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.ends_with(".tmp")
+        && !folders.contains(&name)
+}
+
+pub struct DirectorySource {
+    dir: PathBuf,
+    folders: Vec<&'static str>,
+}
+
+impl IngestionSource for DirectorySource {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn prepare(&self) -> Result<(), DaemonError> {
+        for folder in &self.folders {
+            fs::create_dir_all(self.dir.join(folder))
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        }
+        Ok(())
+    }
+
+    fn pending_files(&self) -> Result<Vec<String>, DaemonError> {
+        let mut files = Vec::new();
+        for entry in
+            fs::read_dir(&self.dir).map_err(|err| DaemonError::from_source(Box::new(err)))?
+        {
+            let path = entry
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?
+                .path();
+            if !path.is_file() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                if is_pending(name, &self.folders) {
+                    files.push(name.to_string());
+                }
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, DaemonError> {
+        fs::read(self.dir.join(name)).map_err(|err| DaemonError::from_source(Box::new(err)))
+    }
+
+    fn move_to(&self, name: &str, folder: &str) -> Result<(), DaemonError> {
+        fs::rename(self.dir.join(name), self.dir.join(folder).join(name))
+            .map_err(|err| DaemonError::from_source(Box::new(err)))
+    }
+}
+
+pub struct SftpSource {
+    sftp: SftpLocation,
+    folders: Vec<&'static str>,
+}
+
+impl SftpSource {
+    /// Reads a `sftp://[user@]host[:port]/path` URL
+    pub fn from_url(location: &str, folders: &[&'static str]) -> Result<Self, DaemonError> {
+        Ok(SftpSource {
+            sftp: SftpLocation::from_url(location)?,
+            folders: folders.to_vec(),
+        })
+    }
+}
+
+impl IngestionSource for SftpSource {
+    fn describe(&self) -> String {
+        self.sftp.url().to_string()
+    }
+
+    fn prepare(&self) -> Result<(), DaemonError> {
+        // A leading `-` lets the batch go on if the folder exists
+        let commands = self
+            .folders
+            .iter()
+            .map(|folder| format!("-mkdir {}\n", quote(&self.sftp.remote_path(folder))))
+            .collect::<String>();
+        self.sftp.run(&commands).map(|_| ())
+    }
+
+    fn pending_files(&self) -> Result<Vec<String>, DaemonError> {
+        let mut files = self
+            .sftp
+            .list()?
+            .into_iter()
+            .filter(|name| is_pending(name, &self.folders))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        Ok(files)
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, DaemonError> {
+        let local = std::env::temp_dir().join(format!("gridd-ingest-{}", Uuid::new_v4()));
+        let local_path = local
+            .to_str()
+            .ok_or_else(|| DaemonError::with_message("Temporary directory is not valid UTF-8"))?;
+        let result = self
+            .sftp
+            .run(&format!(
+                "get {} {}\n",
+                quote(&self.sftp.remote_path(name)),
+                quote(local_path)
+            ))
+            .and_then(|_| fs::read(&local).map_err(|err| DaemonError::from_source(Box::new(err))));
+        if local.exists() {
+            if let Err(err) = fs::remove_file(&local) {
+                warn!("Unable to remove {}: {}", local.display(), err);
+            }
+        }
+
+        result
+    }
+
+    fn move_to(&self, name: &str, folder: &str) -> Result<(), DaemonError> {
+        self.sftp
+            .run(&format!(
+                "rename {} {}\n",
+                quote(&self.sftp.remote_path(name)),
+                quote(&self.sftp.remote_path(&format!("{}/{}", folder, name)))
+            ))
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the folders and hidden or partial files are not taken as pending
+    #[test]
+    fn test_is_pending() {
+        let folders = ["processed", "failed"];
+
+        assert!(is_pending("b.csv", &folders));
+        assert!(is_pending("a.edi", &folders));
+        assert!(!is_pending("processed", &folders));
+        assert!(!is_pending(".partial", &folders));
+        assert!(!is_pending("c.xml.tmp", &folders));
+        assert!(!is_pending("", &folders));
+    }
+}
@@ -32,6 +32,10 @@ extern crate serde_json;
 #[macro_use]
 extern crate serde;
 
+#[cfg(feature = "api-keys")]
+mod api_keys;
+#[cfg(feature = "api-usage-analytics")]
+mod api_usage;
 mod config;
 #[cfg(feature = "database")]
 mod database;
@@ -39,12 +43,38 @@ mod error;
 #[cfg(feature = "event")]
 #[macro_use]
 mod event;
+#[cfg(feature = "gdsn-publication")]
+mod gdsn_publication;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "ingestion")]
+mod ingestion;
+#[cfg(feature = "mfg-batch-address-distribution")]
+mod mfg_batch_address_distribution;
+#[cfg(feature = "mfg-batch-anchors")]
+mod mfg_batch_anchors;
+#[cfg(feature = "mfg-batch-duplicates")]
+mod mfg_batch_duplicates;
+#[cfg(feature = "mfg-batch-export")]
+mod mfg_batch_export;
+#[cfg(feature = "mfg-batch-merge")]
+mod mfg_batch_merge;
+#[cfg(feature = "mfg-batch-projections")]
+mod mfg_batch_projections;
+#[cfg(feature = "mfg-batch-rebuild")]
+mod mfg_batch_rebuild;
 #[cfg(feature = "rest-api")]
 mod rest_api;
 #[cfg(feature = "sawtooth-support")]
 mod sawtooth;
+#[cfg(any(feature = "ingestion", feature = "mfg-batch-export"))]
+mod sftp;
+#[cfg(feature = "sla-timers")]
+mod sla;
 #[cfg(feature = "splinter-support")]
 mod splinter;
+#[cfg(feature = "webhooks")]
+mod webhooks;
 
 use flexi_logger::{LogSpecBuilder, Logger};
 
@@ -85,6 +115,936 @@ fn run() -> Result<(), DaemonError> {
         );
     }
 
+    #[cfg(feature = "data-mapping")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("mapping_dir")
+                .long("mapping-dir")
+                .takes_value(true)
+                .help(
+                    "Directory of YAML data mappings served at /integrations/{mapping}/submit; \
+                    none are served if omitted",
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-certificates")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("certificate_template_dir")
+                .long("certificate-template-dir")
+                .takes_value(true)
+                .help(
+                    "Directory of the YAML templates mfg_batch certificates are printed from \
+                    (default: $GRID_STATE_DIR/certificate_templates)",
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-history")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("mfg_batch_max_response_size")
+                .long("mfg-batch-max-response-size")
+                .takes_value(true)
+                .validator(|size| {
+                    size.parse::<usize>()
+                        .map(|_| ())
+                        .map_err(|_| format!("{} is not a size in bytes", size))
+                })
+                .help(
+                    "Estimated size, in bytes, mfg_batch history and property responses are \
+                    limited to before the rest is left to a continuation (default: 1048576)",
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-sharding")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("mfg_batch_shard_url")
+                .long("mfg-batch-shard-url")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "URL of a Postgres database holding a shard of the mfg_batches, partitioned \
+                    by owner; give every shard, in the same order on every start",
+                ),
+        );
+    }
+
+    #[cfg(feature = "ingestion")]
+    {
+        use clap::Arg;
+        app = app
+            .arg(
+                Arg::with_name("ingest_from")
+                    .long("ingest-from")
+                    .takes_value(true)
+                    .help(
+                        "Directory or sftp://[user@]host[:port]/path URL watched for CSV, GDSN \
+                        and EDI files to ingest",
+                    ),
+            )
+            .arg(
+                Arg::with_name("ingest_csv_mapping")
+                    .long("ingest-csv-mapping")
+                    .takes_value(true)
+                    .requires("ingest_from")
+                    .help("Name of the data mapping applied to ingested CSV files"),
+            )
+            .arg(
+                Arg::with_name("ingest_edi_mapping")
+                    .long("ingest-edi-mapping")
+                    .takes_value(true)
+                    .requires("ingest_from")
+                    .help("Name of the data mapping applied to ingested EDI ship notices"),
+            )
+            .arg(
+                Arg::with_name("ingest_gdsn_owner")
+                    .long("ingest-gdsn-owner")
+                    .takes_value(true)
+                    .requires("ingest_from")
+                    .help("Organization ID that owns the products in ingested GDSN files"),
+            )
+            .arg(
+                Arg::with_name("ingest_service_id")
+                    .long("ingest-service-id")
+                    .takes_value(true)
+                    .requires("ingest_from")
+                    .help("Service ID ingested files are submitted to"),
+            );
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    {
+        use clap::Arg;
+        app = app
+            .arg(
+                Arg::with_name("anchor_interval")
+                    .long("anchor-interval")
+                    .takes_value(true)
+                    .requires("anchor_owner")
+                    .validator(|interval| {
+                        interval
+                            .parse::<u64>()
+                            .map(|_| ())
+                            .map_err(|_| format!("{} is not a number of seconds", interval))
+                    })
+                    .help(
+                        "Seconds between anchors of the mfg_batch records on chain; records are \
+                        not anchored if omitted",
+                    ),
+            )
+            .arg(
+                Arg::with_name("anchor_owner")
+                    .long("anchor-owner")
+                    .takes_value(true)
+                    .requires("anchor_interval")
+                    .help("Organization ID the mfg_batch anchors are submitted on behalf of"),
+            )
+            .arg(
+                Arg::with_name("anchor_service_id")
+                    .long("anchor-service-id")
+                    .takes_value(true)
+                    .requires("anchor_interval")
+                    .help("Service ID whose mfg_batch records are anchored"),
+            );
+    }
+
+    #[cfg(feature = "sla-timers")]
+    {
+        use clap::Arg;
+        let seconds = |value: String| {
+            value
+                .parse::<u64>()
+                .map(|_| ())
+                .map_err(|_| format!("{} is not a number of seconds", value))
+        };
+        app = app
+            .arg(
+                Arg::with_name("sla_interval")
+                    .long("sla-interval")
+                    .takes_value(true)
+                    .validator(seconds)
+                    .help(
+                        "Seconds between checks of the pending proposals and holds for ones past \
+                        their thresholds; nothing is escalated if omitted",
+                    ),
+            )
+            .arg(
+                Arg::with_name("sla_proposal_threshold")
+                    .long("sla-proposal-threshold")
+                    .takes_value(true)
+                    .validator(seconds)
+                    .help("Seconds a transfer proposal may stay open before it is escalated"),
+            )
+            .arg(
+                Arg::with_name("sla_hold_threshold")
+                    .long("sla-hold-threshold")
+                    .takes_value(true)
+                    .validator(seconds)
+                    .help("Seconds a batch may be held by a recall before it is escalated"),
+            )
+            .arg(
+                Arg::with_name("sla_service_id")
+                    .long("sla-service-id")
+                    .takes_value(true)
+                    .requires("sla_interval")
+                    .help("Service ID whose pending proposals and holds are escalated"),
+            );
+    }
+
+    #[cfg(feature = "log-masking")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("log_mask_property")
+                .long("log-mask-property")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Name of a property whose values are masked in logs"),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-retry")]
+    {
+        use clap::Arg;
+        app = app
+            .arg(
+                Arg::with_name("mfg_batch_store_max_attempts")
+                    .long("mfg-batch-store-max-attempts")
+                    .takes_value(true)
+                    .validator(|attempts| {
+                        attempts
+                            .parse::<u32>()
+                            .map(|_| ())
+                            .map_err(|_| format!("{} is not a number of attempts", attempts))
+                    })
+                    .help(
+                        "Number of times an mfg_batch store operation is tried when the database \
+                        is temporarily unavailable; 1 disables retries",
+                    ),
+            )
+            .arg(
+                Arg::with_name("mfg_batch_store_retry_backoff")
+                    .long("mfg-batch-store-retry-backoff")
+                    .takes_value(true)
+                    .validator(|backoff| {
+                        backoff
+                            .parse::<u64>()
+                            .map(|_| ())
+                            .map_err(|_| format!("{} is not a number of milliseconds", backoff))
+                    })
+                    .help(
+                        "Milliseconds the first retry of an mfg_batch store operation is \
+                        jittered within; later retries back off exponentially",
+                    ),
+            );
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    {
+        use clap::Arg;
+        app = app
+            .arg(
+                Arg::with_name("api_usage_retention_days")
+                    .long("api-usage-retention-days")
+                    .takes_value(true)
+                    .validator(|days| {
+                        days.parse::<u32>()
+                            .map(|_| ())
+                            .map_err(|_| format!("{} is not a number of days", days))
+                    })
+                    .help(
+                        "Days the daily rollups of API usage are kept for, counting the current \
+                        day; rollups are kept indefinitely if omitted",
+                    ),
+            )
+            .arg(
+                Arg::with_name("api_key_usage_retention_days")
+                    .long("api-key-usage-retention-days")
+                    .takes_value(true)
+                    .validator(|days| {
+                        days.parse::<u32>()
+                            .map(|_| ())
+                            .map_err(|_| format!("{} is not a number of days", days))
+                    })
+                    .help(
+                        "Days each use of an API key is logged for; the log is kept indefinitely \
+                        if omitted",
+                    ),
+            );
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        use clap::Arg;
+        app = app.arg(
+            Arg::with_name("grpc_bind")
+                .long("grpc-bind")
+                .takes_value(true)
+                .help("Endpoint for the mfg_batch gRPC service; not started if omitted"),
+        );
+    }
+
+    #[cfg(feature = "grpc-pseudonyms")]
+    {
+        use clap::Arg;
+        app = app
+            .arg(
+                Arg::with_name("grpc_pseudonym_key_file")
+                    .long("grpc-pseudonym-key-file")
+                    .takes_value(true)
+                    .help(
+                        "File containing the key used to pseudonymize owners and internal \
+                        properties served over gRPC; served as stored if omitted",
+                    ),
+            )
+            .arg(
+                Arg::with_name("grpc_internal_property")
+                    .long("grpc-internal-property")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Name of a property whose values are pseudonymized over gRPC"),
+            )
+            .arg(
+                Arg::with_name("grpc_admin_token_file")
+                    .long("grpc-admin-token-file")
+                    .takes_value(true)
+                    .help(
+                        "File containing the token admins use to resolve pseudonyms; the \
+                        mapping service is not served if omitted",
+                    ),
+            );
+    }
+
+    #[cfg(feature = "api-keys")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app
+            .arg(
+                Arg::with_name("require_api_keys")
+                    .long("require-api-keys")
+                    .help(
+                        "Require an API key with the needed scope to submit mfg_batches over \
+                        REST or read them over gRPC",
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("api-key")
+                    .about("Manage the API keys clients use, then exit")
+                    .subcommand(
+                        SubCommand::with_name("create")
+                            .about(
+                                "Create an API key and print its token, which is not shown again",
+                            )
+                            .arg(
+                                Arg::with_name("org")
+                                    .long("org")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .help("Organization the key is issued to"),
+                            )
+                            .arg(
+                                Arg::with_name("scope")
+                                    .long("scope")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .multiple(true)
+                                    .number_of_values(1)
+                                    .possible_values(&[
+                                        "mfg_batch:read",
+                                        "mfg_batch:submit",
+                                        "reports:read",
+                                        "certificate_templates:write",
+                                    ])
+                                    .help("Scope granted to the key"),
+                            ),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("revoke")
+                            .about("Revoke an API key")
+                            .arg(
+                                Arg::with_name("key_id")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .help("ID of the key to revoke"),
+                            ),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("list").about("List API keys").arg(
+                            Arg::with_name("org")
+                                .long("org")
+                                .takes_value(true)
+                                .help("Only list the keys issued to this organization"),
+                        ),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("usage")
+                            .about("List the most recent requests that presented an API key")
+                            .arg(
+                                Arg::with_name("key_id")
+                                    .long("key-id")
+                                    .takes_value(true)
+                                    .help("Only list requests that presented this key"),
+                            )
+                            .arg(
+                                Arg::with_name("limit")
+                                    .long("limit")
+                                    .takes_value(true)
+                                    .default_value("100")
+                                    .help("Number of requests to list"),
+                            ),
+                    ),
+            );
+    }
+
+    #[cfg(feature = "webhooks")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("webhook")
+                .about("Manage the URLs notified of mfg_batch changes, then exit")
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about(
+                            "Add a webhook and print the secret its notifications are signed \
+                            with",
+                        )
+                        .arg(
+                            Arg::with_name("url")
+                                .takes_value(true)
+                                .required(true)
+                                .help("URL notifications are posted to"),
+                        )
+                        .arg(
+                            Arg::with_name("service_id")
+                                .long("service-id")
+                                .takes_value(true)
+                                .help(
+                                    "Only notify the webhook of changes committed by this service",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Remove a webhook")
+                        .arg(
+                            Arg::with_name("webhook_id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("ID of the webhook to remove"),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").about("List webhooks")),
+        );
+    }
+
+    #[cfg(feature = "gdsn-publication")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("gdsn")
+                .about("Publish products to a GDSN data pool, then exit")
+                .subcommand(
+                    SubCommand::with_name("publish")
+                        .about(
+                            "Render products created from GDSN data into a Catalogue Item \
+                            Notification and record them as pending publication",
+                        )
+                        .arg(
+                            Arg::with_name("product_id")
+                                .takes_value(true)
+                                .multiple(true)
+                                .required(true)
+                                .help("GTINs of the products to publish"),
+                        )
+                        .arg(
+                            Arg::with_name("sender")
+                                .long("sender")
+                                .takes_value(true)
+                                .required(true)
+                                .help("GLN of the information provider publishing the products"),
+                        )
+                        .arg(
+                            Arg::with_name("data_pool")
+                                .long("data-pool")
+                                .takes_value(true)
+                                .required(true)
+                                .help("GLN of the source data pool the message is sent to"),
+                        )
+                        .arg(
+                            Arg::with_name("recipient")
+                                .long("recipient")
+                                .takes_value(true)
+                                .required(true)
+                                .help("GLN of the data recipient the products are published to"),
+                        )
+                        .arg(
+                            Arg::with_name("command")
+                                .long("command")
+                                .takes_value(true)
+                                .possible_values(&["ADD", "CHANGE_BY_REFRESH", "CORRECT", "DELETE"])
+                                .default_value("ADD")
+                                .help("Document command the data pool is sent"),
+                        )
+                        .arg(
+                            Arg::with_name("service_id")
+                                .long("service-id")
+                                .takes_value(true)
+                                .help("The service the products were committed by"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the message to, instead of stdout"),
+                        )
+                        .arg(
+                            Arg::with_name("xsd_dir")
+                                .long("xsd-dir")
+                                .takes_value(true)
+                                .help(
+                                    "Directory containing GridCatalogueItemNotification.xsd \
+                                    (default: $GRID_STATE_DIR/xsd/product)",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("Record the status a data pool reported for a published product")
+                        .arg(
+                            Arg::with_name("publication_id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Instance identifier of the published message"),
+                        )
+                        .arg(
+                            Arg::with_name("product_id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("GTIN of the published product"),
+                        )
+                        .arg(
+                            Arg::with_name("status")
+                                .takes_value(true)
+                                .required(true)
+                                .possible_values(&[
+                                    "RECEIVED",
+                                    "REVIEW",
+                                    "SYNCHRONISED",
+                                    "ACCEPTED",
+                                    "REJECTED",
+                                ])
+                                .help("Status reported by the data pool"),
+                        )
+                        .arg(
+                            Arg::with_name("message")
+                                .long("message")
+                                .takes_value(true)
+                                .help("Reason the data pool gave for the status"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List publications, newest first")
+                        .arg(
+                            Arg::with_name("product_id")
+                                .long("product-id")
+                                .takes_value(true)
+                                .help("Only list publications of this product"),
+                        ),
+                ),
+        );
+    }
+
+    #[cfg(feature = "event-chaos")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("chaos-soak")
+                .about(
+                    "Send generated commit events, randomly delayed, duplicated and reordered, \
+                    through the event handler and check the store after each one",
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .help("Seed for the random choices; a random seed is used if omitted"),
+                )
+                .arg(
+                    Arg::with_name("commits")
+                        .long("commits")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Number of commit events to generate"),
+                )
+                .arg(
+                    Arg::with_name("fork_rate")
+                        .long("fork-rate")
+                        .takes_value(true)
+                        .default_value("0.05")
+                        .help("Chance that a generated commit starts a new fork"),
+                )
+                .arg(
+                    Arg::with_name("max_delay")
+                        .long("max-delay")
+                        .takes_value(true)
+                        .default_value("5")
+                        .help("Longest delay before an event is delivered, in milliseconds"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "event-replay")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("replay")
+                .about(
+                    "Replay recorded commit events against a scratch SQLite store and print \
+                    the resulting row changes",
+                )
+                .arg(
+                    Arg::with_name("events_file")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File containing one JSON commit event per line"),
+                )
+                .arg(
+                    Arg::with_name("key_prefix")
+                        .long("key-prefix")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only replay state changes whose address has this prefix"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "event-queue")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("reprocess-failed-events")
+                .about(
+                    "Retry recording the commit events queued after failing to be recorded, \
+                    in the order they were received, then exit",
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .conflicts_with("discard")
+                        .help("List the queued events instead of retrying them"),
+                )
+                .arg(
+                    Arg::with_name("discard")
+                        .long("discard")
+                        .takes_value(true)
+                        .value_name("QUEUE_ID")
+                        .help("Remove an event that can never be recorded from the queue"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "reindex")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("reindex")
+                .about(
+                    "Build missing mfg_batch indexes, concurrently with writes on PostgreSQL, \
+                    then exit",
+                )
+                .arg(
+                    Arg::with_name("throttle")
+                        .long("throttle")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Milliseconds to wait between index builds"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("detect-duplicates")
+                .about(
+                    "Flag likely duplicate mfg_batches, replacing the duplicate review queue, \
+                    then exit",
+                )
+                .arg(
+                    Arg::with_name("rules")
+                        .long("rules")
+                        .takes_value(true)
+                        .help("YAML file of matching rules; the built in rules are used if absent"),
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help("Only check the mfg_batches of this service"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("verify-anchors")
+                .about(
+                    "Recompute the Merkle root of the mfg_batch records at each anchored commit \
+                    and compare it with the root anchored on chain, then exit",
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help("Only verify the anchors of this service"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("merge-mfg-batches")
+                .about(
+                    "Merge the off-chain records of a duplicate mfg_batch into another, so that \
+                    lookups by either id resolve to it, then exit",
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The id of the duplicate mfg_batch"),
+                )
+                .arg(
+                    Arg::with_name("into")
+                        .long("into")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The id of the mfg_batch to merge it into"),
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help("The service both mfg_batches belong to"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-address-distribution")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("address-distribution")
+                .about(
+                    "Report how mfg_batches spread across state addresses under the current \
+                    addressing scheme, then exit",
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .takes_value(true)
+                        .help("File of mfg_batch IDs, one per batch; the stored batches if absent"),
+                )
+                .arg(
+                    Arg::with_name("namespace")
+                        .long("namespace")
+                        .takes_value(true)
+                        .possible_values(&["gs1", "internal", "lot"])
+                        .default_value("gs1")
+                        .help("Namespace of the mfg_batch IDs in the file"),
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help("Only count the stored mfg_batches of this service"),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("Number of the largest addresses to list"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-export")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("export-mfg-batches")
+                .about(
+                    "Export a snapshot of the stored mfg_batches to a directory, SFTP server or \
+                    S3 bucket, then exit",
+                )
+                .arg(
+                    Arg::with_name("job")
+                        .long("job")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "Name of the export, used for the exported file; running a failed \
+                            job again resumes its upload",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "Directory, sftp://[user@]host[:port]/path URL or \
+                            s3://bucket[/prefix] URL the export is written to",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["csv", "epcis"])
+                        .default_value("csv")
+                        .help("Format of the export"),
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help("Only export the mfg_batches of this service"),
+                )
+                .arg(
+                    Arg::with_name("staging_dir")
+                        .long("staging-dir")
+                        .takes_value(true)
+                        .help(
+                            "Directory exports are kept in until uploaded; a gridd-export \
+                            directory in the system's temporary directory if absent",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("s3_endpoint")
+                        .long("s3-endpoint")
+                        .takes_value(true)
+                        .help("Endpoint of an S3-compatible service to use in place of AWS"),
+                )
+                .arg(
+                    Arg::with_name("s3_region")
+                        .long("s3-region")
+                        .takes_value(true)
+                        .help("Region of the S3 bucket; AWS_REGION or us-east-1 if absent"),
+                )
+                .arg(
+                    Arg::with_name("webhook_url")
+                        .long("webhook-url")
+                        .takes_value(true)
+                        .requires("webhook_secret")
+                        .help("URL notified once the export is complete"),
+                )
+                .arg(
+                    Arg::with_name("webhook_secret")
+                        .long("webhook-secret")
+                        .takes_value(true)
+                        .requires("webhook_url")
+                        .help("Secret the completion notification is signed with"),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-rebuild")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("rebuild-mfg-batch-db")
+                .about(
+                    "Rebuild the mfg_batch store from the mfg_batch records in chain state, \
+                    then exit",
+                )
+                .arg(
+                    Arg::with_name("state_url")
+                        .long("state-url")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "URL of the Sawtooth REST API, or of splinterd if a service ID is \
+                            given, to read state from",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("service_id")
+                        .long("service-id")
+                        .takes_value(true)
+                        .help(
+                            "The {circuit_id}::{service_id} of the scabbard service to read \
+                            state from on Splinter",
+                        ),
+                ),
+        );
+    }
+
+    #[cfg(feature = "mfg-batch-projections")]
+    {
+        use clap::{Arg, SubCommand};
+        app = app.subcommand(
+            SubCommand::with_name("projections")
+                .about("Maintain the read-model projections of the mfg_batch change feed")
+                .subcommand(
+                    SubCommand::with_name("status").about(
+                        "List each projection with its checkpoint and the number of changes \
+                        it has yet to apply, then exit",
+                    ),
+                )
+                .subcommand(
+                    SubCommand::with_name("run")
+                        .about("Apply new changes to the projections as they are recorded")
+                        .arg(
+                            Arg::with_name("interval")
+                                .long("interval")
+                                .takes_value(true)
+                                .default_value("5")
+                                .help("Seconds to wait between checks for new changes"),
+                        )
+                        .arg(
+                            Arg::with_name("once")
+                                .long("once")
+                                .help("Apply the changes recorded so far, then exit"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("rebuild")
+                        .about(
+                            "Empty a projection and apply the whole change feed to it again, \
+                            then exit",
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Name of the projection to rebuild"),
+                        ),
+                ),
+        );
+    }
+
     let matches = app.get_matches();
 
     let log_level = match matches.occurrences_of("verbose") {
@@ -102,10 +1062,176 @@ fn run() -> Result<(), DaemonError> {
         .start()
         .map_err(|err| DaemonError::from_source(Box::new(err)))?;
 
+    #[cfg(feature = "event-chaos")]
+    {
+        if let ("chaos-soak", Some(m)) = matches.subcommand() {
+            let seed = match m.value_of("seed") {
+                Some(_) => value_t!(m, "seed", u64)
+                    .map_err(|err| DaemonError::from_source(Box::new(err)))?,
+                None => rand::random(),
+            };
+            let commits = value_t!(m, "commits", usize)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let fork_rate = value_t!(m, "fork_rate", f64)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let max_delay = value_t!(m, "max_delay", u64)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+            return event::chaos::run_soak(
+                event::chaos::ChaosSettings {
+                    seed,
+                    max_delay: std::time::Duration::from_millis(max_delay),
+                    ..Default::default()
+                },
+                commits,
+                fork_rate,
+                &mut std::io::stdout(),
+            )
+            .map(|_| ());
+        }
+    }
+
+    #[cfg(feature = "event-replay")]
+    {
+        if let ("replay", Some(m)) = matches.subcommand() {
+            let key_prefixes = m
+                .values_of("key_prefix")
+                .map(|values| values.collect::<Vec<_>>())
+                .unwrap_or_default();
+            return event::replay::replay_events(
+                m.value_of("events_file").unwrap_or_default(),
+                &key_prefixes,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
     let config = GridConfigBuilder::default()
         .with_cli_args(&matches)
         .build()?;
 
+    #[cfg(feature = "event-queue")]
+    {
+        if let ("reprocess-failed-events", Some(m)) = matches.subcommand() {
+            return event::queue::run_reprocess_failed_events(&config, m, &mut std::io::stdout());
+        }
+    }
+
+    #[cfg(feature = "reindex")]
+    {
+        if let ("reindex", Some(m)) = matches.subcommand() {
+            let throttle = value_t!(m, "throttle", u64)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            return database::reindex::reindex(
+                config.database_url(),
+                database::reindex::MFG_BATCH_INDEXES,
+                std::time::Duration::from_millis(throttle),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    {
+        if let ("detect-duplicates", Some(m)) = matches.subcommand() {
+            return mfg_batch_duplicates::run_detect_duplicates(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-address-distribution")]
+    {
+        if let ("address-distribution", Some(m)) = matches.subcommand() {
+            return mfg_batch_address_distribution::run_address_distribution(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-export")]
+    {
+        if let ("export-mfg-batches", Some(m)) = matches.subcommand() {
+            return mfg_batch_export::run_export_mfg_batches(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    {
+        if let ("verify-anchors", Some(m)) = matches.subcommand() {
+            return mfg_batch_anchors::run_verify_anchors(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    {
+        if let ("merge-mfg-batches", Some(m)) = matches.subcommand() {
+            return mfg_batch_merge::run_merge_mfg_batches(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-rebuild")]
+    {
+        if let ("rebuild-mfg-batch-db", Some(m)) = matches.subcommand() {
+            return mfg_batch_rebuild::run_rebuild_mfg_batch_db(
+                &config,
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-projections")]
+    {
+        if let ("projections", Some(m)) = matches.subcommand() {
+            return mfg_batch_projections::run_projections_command(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "api-keys")]
+    {
+        if let ("api-key", Some(m)) = matches.subcommand() {
+            return api_keys::run_api_key_command(config.database_url(), m, &mut std::io::stdout());
+        }
+    }
+
+    #[cfg(feature = "gdsn-publication")]
+    {
+        if let ("gdsn", Some(m)) = matches.subcommand() {
+            return gdsn_publication::run_gdsn_command(
+                config.database_url(),
+                m,
+                &mut std::io::stdout(),
+            );
+        }
+    }
+
+    #[cfg(feature = "webhooks")]
+    {
+        if let ("webhook", Some(m)) = matches.subcommand() {
+            return webhooks::run_webhook_command(config.database_url(), m, &mut std::io::stdout());
+        }
+    }
+
     if config.endpoint().starts_with("splinter:") {
         #[cfg(feature = "splinter-support")]
         {
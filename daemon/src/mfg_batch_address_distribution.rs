@@ -0,0 +1,60 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports how mfg_batches spread across state addresses under the current addressing scheme,
+//! either the stored mfg_batches or a file of IDs, such as the GTINs a new customer expects to
+//! record. The worst-case list sizes guide whether address splitting or custom prefixes are
+//! needed.
+
+use std::fs;
+use std::io::Write;
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::address_distribution::{parse_mfg_batch_ids, AddressDistribution};
+use grid_sdk::protocol::mfg_batch::state::MfgBatchNamespace;
+
+use crate::database::create_mfg_batch_store;
+use crate::error::DaemonError;
+
+/// Runs the `address-distribution` subcommand, reading the stored mfg_batches from the database
+/// at `database_url` unless a file of IDs is given, and writing the report
+pub fn run_address_distribution(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let top =
+        value_t!(matches, "top", usize).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let distribution = match matches.value_of("file") {
+        Some(path) => {
+            let ids =
+                fs::read_to_string(path).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            let namespace = match matches.value_of("namespace") {
+                Some("internal") => MfgBatchNamespace::Internal,
+                Some("lot") => MfgBatchNamespace::Lot,
+                _ => MfgBatchNamespace::Gs1,
+            };
+            AddressDistribution::from_ids(&namespace, parse_mfg_batch_ids(&ids))
+        }
+        None => {
+            let store = create_mfg_batch_store(database_url)?;
+            AddressDistribution::from_store(&*store, matches.value_of("service_id"))
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        }
+    };
+
+    write!(out, "{}", distribution.report(top))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
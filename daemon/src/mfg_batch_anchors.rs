@@ -0,0 +1,248 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anchors the off-chain mfg_batch records on chain, and verifies the store against the anchors.
+//!
+//! On each interval, the anchoring service computes the Merkle root of the mfg_batch records
+//! current at the latest commit, and submits it in an anchor transaction on behalf of the
+//! configured organization. The event handler records each anchor in the mfg_batch store once it
+//! is committed, and the `verify-anchors` subcommand recomputes the root at every recorded
+//! anchor's commit, showing whether the store still holds what it held when it was anchored.
+
+use std::io::Write;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::{
+    addressing::GRID_MFG_BATCH_ANCHOR_NAMESPACE,
+    anchors::{compute_merkle_snapshot, verify_mfg_batch_anchors, MerkleSnapshot},
+};
+use grid_sdk::pike::addressing::GRID_PIKE_NAMESPACE;
+use grid_sdk::protocol::mfg_batch::payload::{
+    Action, MfgBatchAnchorActionBuilder, MfgBatchPayloadBuilder,
+};
+use grid_sdk::rest_api::resources::submit::v1::{
+    submit_batches, Batch, Payload, SubmitBatchRequest, Transaction,
+};
+use grid_sdk::store::TransactionalStoreFactory;
+use uuid::Uuid;
+
+use crate::config::GridConfig;
+use crate::database::{create_mfg_batch_store, SharedMfgBatchStore};
+use crate::error::DaemonError;
+
+const MFG_BATCH_FAMILY_NAME: &str = "grid_mfg_batch";
+const MFG_BATCH_FAMILY_VERSION: &str = "1";
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct AnchorShutdownHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl AnchorShutdownHandle {
+    pub fn shutdown(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// What the service needs to anchor the mfg_batch records
+pub struct AnchorSettings {
+    pub interval: Duration,
+    /// The organization the anchors are submitted on behalf of
+    pub owner: String,
+    pub service_id: Option<String>,
+    pub key_file_name: String,
+}
+
+/// Starts the service if the configuration gives an anchoring interval
+pub fn run_from_config(
+    config: &GridConfig,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    mfg_batch_store: SharedMfgBatchStore,
+) -> Result<Option<(AnchorShutdownHandle, thread::JoinHandle<()>)>, DaemonError> {
+    let interval = match config.anchor_interval() {
+        Some(interval) => interval,
+        None => return Ok(None),
+    };
+    let owner = config
+        .anchor_owner()
+        .ok_or_else(|| DaemonError::with_message("--anchor-interval requires --anchor-owner"))?;
+
+    run(
+        AnchorSettings {
+            interval: Duration::from_secs(interval),
+            owner: owner.to_string(),
+            service_id: config.anchor_service_id().map(ToOwned::to_owned),
+            key_file_name: config.key_file_name().to_string(),
+        },
+        store_factory,
+        mfg_batch_store,
+    )
+    .map(Some)
+}
+
+pub fn run(
+    settings: AnchorSettings,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    mfg_batch_store: SharedMfgBatchStore,
+) -> Result<(AnchorShutdownHandle, thread::JoinHandle<()>), DaemonError> {
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let join_handle = thread::Builder::new()
+        .name("Anchoring".into())
+        .spawn(move || {
+            info!(
+                "Anchoring mfg_batch records every {} seconds",
+                settings.interval.as_secs()
+            );
+            let mut last_anchored = None;
+            while thread_running.load(Ordering::SeqCst) {
+                match anchor(&settings, &*store_factory, &mfg_batch_store, last_anchored) {
+                    Ok(Some(commit_num)) => last_anchored = Some(commit_num),
+                    Ok(None) => (),
+                    Err(err) => error!("Unable to anchor mfg_batch records: {}", err),
+                }
+
+                let mut waited = Duration::from_secs(0);
+                while waited < settings.interval && thread_running.load(Ordering::SeqCst) {
+                    thread::sleep(SHUTDOWN_CHECK_INTERVAL);
+                    waited += SHUTDOWN_CHECK_INTERVAL;
+                }
+            }
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok((AnchorShutdownHandle { running }, join_handle))
+}
+
+/// Submits an anchor of the records current at the latest commit, unless there is no commit yet
+/// or the latest commit is the one last anchored, returning the commit anchored
+fn anchor(
+    settings: &AnchorSettings,
+    store_factory: &dyn TransactionalStoreFactory,
+    mfg_batch_store: &SharedMfgBatchStore,
+    last_anchored: Option<i64>,
+) -> Result<Option<i64>, DaemonError> {
+    let commit_num = store_factory
+        .get_grid_commit_store()
+        .get_next_commit_num()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        - 1;
+    if commit_num < 0 || last_anchored == Some(commit_num) {
+        return Ok(None);
+    }
+
+    let snapshot = compute_merkle_snapshot(
+        &**mfg_batch_store,
+        commit_num,
+        settings.service_id.as_deref(),
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let correlation_id = Uuid::new_v4().to_string();
+    submit_batches(
+        &settings.key_file_name,
+        store_factory.get_batch_store(),
+        build_anchor_request(settings, &snapshot, now())?,
+        &correlation_id,
+    )
+    .map_err(|err| DaemonError::with_message(&err.to_string()))?;
+
+    info!(
+        "Submitted anchor {} of {} records at commit {} with correlation ID {}",
+        snapshot.merkle_root, snapshot.row_count, commit_num, correlation_id
+    );
+
+    Ok(Some(commit_num))
+}
+
+fn build_anchor_request(
+    settings: &AnchorSettings,
+    snapshot: &MerkleSnapshot,
+    timestamp: u64,
+) -> Result<SubmitBatchRequest, DaemonError> {
+    let action = MfgBatchAnchorActionBuilder::new()
+        .with_owner(settings.owner.clone())
+        .with_commit_num(snapshot.commit_num)
+        .with_merkle_root(snapshot.merkle_root.clone())
+        .with_row_count(snapshot.row_count as u64)
+        .build()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let payload = MfgBatchPayloadBuilder::new()
+        .with_action(Action::MfgBatchAnchor(action))
+        .with_timestamp(timestamp)
+        .build()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok(SubmitBatchRequest {
+        batches: vec![Batch {
+            trace: false,
+            service_id: settings.service_id.clone(),
+            transactions: vec![Transaction {
+                family_name: MFG_BATCH_FAMILY_NAME.to_string(),
+                version: MFG_BATCH_FAMILY_VERSION.to_string(),
+                dependencies: vec![],
+                inputs: vec![
+                    GRID_PIKE_NAMESPACE.to_string(),
+                    GRID_MFG_BATCH_ANCHOR_NAMESPACE.to_string(),
+                ],
+                outputs: vec![GRID_MFG_BATCH_ANCHOR_NAMESPACE.to_string()],
+                payload: Payload::MfgBatch(payload),
+            }],
+        }],
+    })
+}
+
+/// Runs the `verify-anchors` subcommand against the database at `database_url`, writing the
+/// outcome for each anchor one per line, and failing if any anchor does not match the store
+pub fn run_verify_anchors(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let store = create_mfg_batch_store(database_url)?;
+
+    let verifications = verify_mfg_batch_anchors(&*store, matches.value_of("service_id"))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for verification in &verifications {
+        writeln!(out, "{}", verification).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    let mismatches = verifications
+        .iter()
+        .filter(|verification| !verification.matches())
+        .count();
+    if mismatches > 0 {
+        return Err(DaemonError::with_message(&format!(
+            "{} of {} anchors do not match the store",
+            mismatches,
+            verifications.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the time in seconds since the epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,68 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Flags likely duplicate mfg_batches, replacing the review queue listed by the
+//! `/mfg_batch_duplicate` endpoint. Detection compares every pair of mfg_batches, so it is run
+//! as a job, such as from cron, rather than as mfg_batches are written.
+
+use std::fs;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::duplicates::{detect_mfg_batch_duplicates, DuplicateRules};
+
+use crate::database::create_mfg_batch_store;
+use crate::error::DaemonError;
+
+/// Runs the `detect-duplicates` subcommand against the database at `database_url`, writing the
+/// duplicates found one per line
+pub fn run_detect_duplicates(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let rules = match matches.value_of("rules") {
+        Some(path) => {
+            let yaml =
+                fs::read_to_string(path).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            DuplicateRules::from_yaml(&yaml)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        }
+        None => DuplicateRules::default(),
+    };
+    let store = create_mfg_batch_store(database_url)?;
+
+    let duplicates =
+        detect_mfg_batch_duplicates(&*store, &rules, matches.value_of("service_id"), now())
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for duplicate in duplicates {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}",
+            duplicate.mfg_batch_id, duplicate.duplicate_of, duplicate.rule, duplicate.similarity,
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
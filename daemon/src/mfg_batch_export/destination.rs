@@ -0,0 +1,166 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Where exports are uploaded to: a local directory, a directory on an SFTP server or an S3
+//! bucket. Each destination writes the file under a temporary name, or as an unfinished upload,
+//! and only gives it its name once it is complete, so readers never see part of an export.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::DaemonError;
+use crate::sftp::{quote, SftpLocation};
+
+use super::s3::S3Destination;
+
+/// A place exports are uploaded to
+pub trait ExportDestination {
+    /// Describes the destination in log messages
+    fn describe(&self) -> String;
+
+    /// Uploads the staged file under the given name and returns where it can be found. An
+    /// upload an earlier run of the job left unfinished is picked up where it stopped; any
+    /// record of its progress is kept in the staging directory.
+    fn upload(&self, name: &str, staged: &Path, staging_dir: &Path) -> Result<String, DaemonError>;
+}
+
+/// Settings only S3 destinations use
+pub struct S3Options {
+    /// The endpoint of an S3-compatible service, in place of AWS's endpoint for the region
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+}
+
+/// Opens the destination a `--destination` value names, which is a directory, an `sftp://` URL
+/// or an `s3://bucket/prefix` URL
+pub fn open(
+    location: &str,
+    s3_options: &S3Options,
+) -> Result<Box<dyn ExportDestination>, DaemonError> {
+    if location.starts_with("s3://") {
+        Ok(Box::new(S3Destination::from_url(location, s3_options)?))
+    } else if location.starts_with("sftp://") {
+        Ok(Box::new(SftpDestination {
+            sftp: SftpLocation::from_url(location)?,
+        }))
+    } else {
+        Ok(Box::new(DirectoryDestination {
+            dir: PathBuf::from(location),
+        }))
+    }
+}
+
+pub struct DirectoryDestination {
+    dir: PathBuf,
+}
+
+impl ExportDestination for DirectoryDestination {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn upload(&self, name: &str, staged: &Path, _: &Path) -> Result<String, DaemonError> {
+        fs::create_dir_all(&self.dir).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let partial = self.dir.join(format!("{}.tmp", name));
+        let path = self.dir.join(name);
+        fs::copy(staged, &partial).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        fs::rename(&partial, &path).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        Ok(path.display().to_string())
+    }
+}
+
+pub struct SftpDestination {
+    sftp: SftpLocation,
+}
+
+impl ExportDestination for SftpDestination {
+    fn describe(&self) -> String {
+        self.sftp.url().to_string()
+    }
+
+    fn upload(&self, name: &str, staged: &Path, _: &Path) -> Result<String, DaemonError> {
+        let partial_name = format!("{}.tmp", name);
+        let partial = self.sftp.remote_path(&partial_name);
+        let path = self.sftp.remote_path(name);
+        let staged = staged
+            .to_str()
+            .ok_or_else(|| DaemonError::with_message("Staging directory is not valid UTF-8"))?;
+
+        // `reput` appends to what an earlier run uploaded, but fails if nothing was
+        let put = if self.sftp.list()?.contains(&partial_name) {
+            info!("Resuming upload of {} to {}", name, self.sftp.url());
+            "reput"
+        } else {
+            "put"
+        };
+        // A leading `-` lets the batch go on if there is no earlier export to replace
+        self.sftp.run(&format!(
+            "{} {} {}\n-rm {}\nrename {} {}\n",
+            put,
+            quote(staged),
+            quote(&partial),
+            quote(&path),
+            quote(&partial),
+            quote(&path)
+        ))?;
+
+        Ok(format!(
+            "{}/{}",
+            self.sftp.url().trim_end_matches('/'),
+            name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    /// Verify that a directory destination copies the staged file in under its name, creating
+    /// the directory if needed, and replaces an earlier export of the same name
+    #[test]
+    fn test_directory_destination() {
+        let root = std::env::temp_dir().join(format!("gridd-export-test-{}", Uuid::new_v4()));
+        let staged = root.join("staged.csv");
+        fs::create_dir_all(&root).expect("Failed to create test directory");
+        fs::write(&staged, "mfg_batch_id\nlot-1\n").expect("Failed to stage export");
+
+        let destination = open(
+            root.join("out").to_str().expect("Invalid path"),
+            &S3Options {
+                endpoint: None,
+                region: None,
+            },
+        )
+        .expect("Failed to open destination");
+        destination
+            .upload("nightly.csv", &staged, &root)
+            .expect("Failed to upload export");
+        fs::write(&staged, "mfg_batch_id\nlot-2\n").expect("Failed to stage export");
+        let location = destination
+            .upload("nightly.csv", &staged, &root)
+            .expect("Failed to upload export");
+
+        assert_eq!(
+            fs::read_to_string(&location).expect("Failed to read export"),
+            "mfg_batch_id\nlot-2\n"
+        );
+        assert!(!root.join("out").join("nightly.csv.tmp").exists());
+
+        fs::remove_dir_all(&root).expect("Failed to remove test directory");
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports a snapshot of the stored mfg_batches, as CSV or as an EPCIS document, to a local
+//! directory, a directory on an SFTP server or an S3 bucket, then tells a webhook the export is
+//! complete. Parquet is not offered, as the daemon has no Parquet writer.
+//!
+//! Each export is a named job. The snapshot is first written to a staging directory and stays
+//! there until its upload completes, so running a failed job again under the same name uploads
+//! the same snapshot and picks up where the upload stopped.
+
+mod destination;
+mod s3;
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::epcis::{export_mfg_batches, EpcisOptions};
+use grid_sdk::mfg_batch::store::{ListMfgBatchFilters, MfgBatch, MfgBatchStore};
+use grid_sdk::webhooks::{sign, EVENT_HEADER, SIGNATURE_HEADER};
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::database::create_mfg_batch_store;
+use crate::error::DaemonError;
+
+use self::destination::S3Options;
+
+/// The event a completion webhook is notified of
+const EXPORT_COMPLETED_EVENT: &str = "export.completed";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const PAGE_SIZE: i64 = 1000;
+
+const CSV_HEADER: [&str; 12] = [
+    "mfg_batch_id",
+    "mfg_batch_namespace",
+    "owner",
+    "quantity",
+    "uom",
+    "expected_quantity",
+    "production_date",
+    "expiration_date",
+    "manufacture_location",
+    "parent_batches",
+    "archived",
+    "last_updated",
+];
+
+/// How the snapshot is written
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    /// One row per current mfg_batch
+    Csv,
+    /// The history of every current mfg_batch, as EPCIS events
+    Epcis,
+}
+
+impl ExportFormat {
+    fn from_arg(arg: &str) -> Self {
+        match arg {
+            "epcis" => ExportFormat::Epcis,
+            _ => ExportFormat::Csv,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Epcis => "epcis",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Epcis => "json",
+        }
+    }
+}
+
+/// Runs the `export-mfg-batches` subcommand, reading the mfg_batches from the database at
+/// `database_url`
+pub fn run_export_mfg_batches(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let format = ExportFormat::from_arg(matches.value_of("format").unwrap_or("csv"));
+    let job = matches.value_of("job").unwrap_or_default();
+    let name = format!("{}.{}", job, format.extension());
+    let staging_dir = matches
+        .value_of("staging_dir")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("gridd-export"));
+    fs::create_dir_all(&staging_dir).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let destination = destination::open(
+        matches.value_of("destination").unwrap_or_default(),
+        &S3Options {
+            endpoint: matches.value_of("s3_endpoint").map(String::from),
+            region: matches.value_of("s3_region").map(String::from),
+        },
+    )?;
+
+    let staged = staging_dir.join(&name);
+    if staged.exists() {
+        info!("Resuming export {} to {}", name, destination.describe());
+    } else {
+        let store = create_mfg_batch_store(database_url)?;
+        stage_snapshot(
+            &*store,
+            format,
+            matches.value_of("service_id"),
+            &staged,
+            now(),
+        )?;
+    }
+    let bytes = fs::metadata(&staged)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        .len();
+
+    let location = destination.upload(&name, &staged, &staging_dir)?;
+    if let Err(err) = fs::remove_file(&staged) {
+        warn!("Unable to remove {}: {}", staged.display(), err);
+    }
+
+    if let (Some(url), Some(secret)) = (
+        matches.value_of("webhook_url"),
+        matches.value_of("webhook_secret"),
+    ) {
+        let body = serde_json::to_vec(&serde_json::json!({
+            "event": EXPORT_COMPLETED_EVENT,
+            "job": job,
+            "format": format.as_str(),
+            "location": location,
+            "bytes": bytes,
+            "completed_at": now(),
+        }))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        // The export itself is done, so a webhook that cannot be reached is only reported
+        if let Err(err) = notify(url, secret, body) {
+            warn!(
+                "Unable to notify {} that {} is complete: {}",
+                url, name, err
+            );
+        }
+    }
+
+    writeln!(out, "Exported {} ({} bytes) to {}", name, bytes, location)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Writes the snapshot to `staged`, by way of a temporary file so that a snapshot cut short is
+/// not taken for a complete one
+fn stage_snapshot(
+    store: &dyn MfgBatchStore,
+    format: ExportFormat,
+    service_id: Option<&str>,
+    staged: &Path,
+    exported_at: i64,
+) -> Result<(), DaemonError> {
+    let partial = staged.with_extension("tmp");
+    let file = File::create(&partial).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let mut writer = BufWriter::new(file);
+
+    match format {
+        ExportFormat::Csv => write_csv(store, service_id, &mut writer)?,
+        ExportFormat::Epcis => write_epcis(store, service_id, exported_at, &mut writer)?,
+    }
+
+    writer
+        .flush()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    fs::rename(&partial, staged).map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+fn write_csv(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    writeln!(out, "{}", CSV_HEADER.join(","))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    for mfg_batch in store.iter_mfg_batches(service_id, &ListMfgBatchFilters::default(), PAGE_SIZE)
+    {
+        let mfg_batch = mfg_batch.map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        writeln!(out, "{}", csv_row(&mfg_batch))
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn write_epcis(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    exported_at: i64,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let mut mfg_batch_ids = Vec::new();
+    for mfg_batch in store.iter_mfg_batches(service_id, &ListMfgBatchFilters::default(), PAGE_SIZE)
+    {
+        let mfg_batch = mfg_batch.map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        mfg_batch_ids.push(mfg_batch.mfg_batch_id().to_string());
+    }
+    let mfg_batch_ids = mfg_batch_ids.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let document = export_mfg_batches(
+        store,
+        &mfg_batch_ids,
+        service_id,
+        &EpcisOptions::default(),
+        exported_at,
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    serde_json::to_writer(out, &document).map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+fn csv_row(mfg_batch: &MfgBatch) -> String {
+    let optional = |value: Option<i64>| value.map(|value| value.to_string()).unwrap_or_default();
+
+    [
+        mfg_batch.mfg_batch_id().to_string(),
+        mfg_batch.mfg_batch_namespace().to_string(),
+        mfg_batch.owner().to_string(),
+        optional(mfg_batch.quantity()),
+        mfg_batch.uom().unwrap_or_default().to_string(),
+        optional(mfg_batch.expected_quantity()),
+        optional(mfg_batch.production_date()),
+        optional(mfg_batch.expiration_date()),
+        mfg_batch.manufacture_location().unwrap_or_default().to_string(),
+        mfg_batch.parent_batches().join(";"),
+        mfg_batch.archived().to_string(),
+        optional(mfg_batch.last_updated().copied()),
+    ]
+    .iter()
+    .map(|field| csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Quotes a CSV field if it holds a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Posts a completion notification, signed the way mfg_batch webhook notifications are
+fn notify(url: &str, secret: &str, body: Vec<u8>) -> Result<(), String> {
+    let client = Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let response = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, EXPORT_COMPLETED_EVENT)
+        .header(SIGNATURE_HEADER, sign(secret, &body))
+        .body(body)
+        .send()
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("received status {}", response.status()))
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid_sdk::mfg_batch::store::MfgBatchBuilder;
+
+    /// Verify that a CSV row holds a mfg_batch's fields, with fields holding commas or quotes
+    /// quoted and absent values left empty
+    #[test]
+    fn test_csv_row() {
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id("(01)10012345678902(10)A1".to_string())
+            .with_mfg_batch_address("address".to_string())
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner("Acme, \"Inc\"".to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_parent_batches(vec!["lot-1".to_string(), "lot-2".to_string()])
+            .with_quantity(Some(40))
+            .with_uom(Some("kg".to_string()))
+            .with_last_updated(Some(1_700_000_000))
+            .build()
+            .expect("Failed to build mfg_batch");
+
+        assert_eq!(
+            csv_row(&mfg_batch),
+            "(01)10012345678902(10)A1,GS1,\"Acme, \"\"Inc\"\"\",40,kg,,,,,lot-1;lot-2,false,\
+            1700000000"
+        );
+    }
+}
@@ -0,0 +1,615 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Uploads exports to S3, or an S3-compatible service, as multipart uploads signed with AWS
+//! Signature Version 4. Credentials are read from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+//! and, for temporary credentials, `AWS_SESSION_TOKEN`.
+//!
+//! The upload ID and the parts uploaded so far are recorded next to the staged export, so a run
+//! that fails partway leaves the upload open for the next run of the job to finish.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::Utc;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use reqwest::blocking::Client;
+use reqwest::{Method, StatusCode};
+use url::Url;
+
+use crate::error::DaemonError;
+
+use super::destination::{ExportDestination, S3Options};
+
+/// The size of every part but the last; S3 requires at least 5 MiB
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+const DEFAULT_REGION: &str = "us-east-1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    fn from_env() -> Result<Self, DaemonError> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                DaemonError::with_message(&format!("{} must be set to export to S3", name))
+            })
+        };
+
+        Ok(Credentials {
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// A prefix in an S3 bucket, reached with path-style URLs
+pub struct S3Destination {
+    url: String,
+    bucket: String,
+    prefix: String,
+    endpoint: String,
+    /// The `Host` header requests are signed with
+    host: String,
+    region: String,
+    credentials: Credentials,
+    client: Client,
+}
+
+/// How far an unfinished multipart upload got
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct UploadState {
+    key: String,
+    upload_id: String,
+    /// The number and ETag of each part uploaded, in part number order
+    parts: Vec<(u64, String)>,
+}
+
+impl S3Destination {
+    /// Reads a `s3://bucket[/prefix]` URL
+    pub fn from_url(location: &str, options: &S3Options) -> Result<Self, DaemonError> {
+        let (bucket, prefix) = parse_s3_url(location)?;
+        let region = options
+            .region
+            .clone()
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let endpoint = options
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint_url = Url::parse(&endpoint).map_err(|err| {
+            DaemonError::with_message(&format!("Invalid S3 endpoint {}: {}", endpoint, err))
+        })?;
+        let host = match (endpoint_url.host_str(), endpoint_url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(DaemonError::with_message(&format!(
+                    "S3 endpoint {} has no host",
+                    endpoint
+                )))
+            }
+        };
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        Ok(S3Destination {
+            url: location.to_string(),
+            bucket,
+            prefix,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            host,
+            region,
+            credentials: Credentials::from_env()?,
+            client,
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    /// Sends a signed request for the object with the given key and returns the response's
+    /// status, `ETag` header and body
+    fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<(StatusCode, Option<String>, String), DaemonError> {
+        let uri = format!(
+            "/{}/{}",
+            uri_encode(&self.bucket, false),
+            uri_encode(key, true)
+        );
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = sha256_hex(&body);
+
+        let mut headers = vec![
+            ("host".to_string(), self.host.clone()),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        let request = CanonicalRequest {
+            method: method.as_str().to_string(),
+            uri,
+            query: query
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            headers,
+            payload_hash,
+        };
+        let authorization = request.authorization(&self.credentials, &self.region, &amz_date);
+
+        let mut builder = self
+            .client
+            .request(method, &format!("{}{}", self.endpoint, request.target()))
+            .header("Authorization", authorization)
+            .body(body);
+        for (name, value) in &request.headers {
+            if name != "host" {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+        }
+        let response = builder
+            .send()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let status = response.status();
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|etag| etag.to_str().ok())
+            .map(String::from);
+        let body = response
+            .text()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        Ok((status, etag, body))
+    }
+
+    fn start_upload(&self, key: &str) -> Result<UploadState, DaemonError> {
+        let (status, _, body) = self.send(Method::POST, key, &[("uploads", "")], vec![])?;
+        let upload_id = xml_value(&body, "UploadId")
+            .filter(|_| status.is_success())
+            .ok_or_else(|| {
+                DaemonError::with_message(&format!(
+                    "Unable to start upload of {}: {} {}",
+                    key, status, body
+                ))
+            })?;
+
+        Ok(UploadState {
+            key: key.to_string(),
+            upload_id,
+            parts: vec![],
+        })
+    }
+
+    fn upload_part(
+        &self,
+        state: &UploadState,
+        part_number: u64,
+        data: Vec<u8>,
+        state_path: &Path,
+    ) -> Result<String, DaemonError> {
+        let part = part_number.to_string();
+        let (status, etag, body) = self.send(
+            Method::PUT,
+            &state.key,
+            &[("partNumber", &part), ("uploadId", &state.upload_id)],
+            data,
+        )?;
+        if status == StatusCode::NOT_FOUND {
+            return Err(DaemonError::with_message(&format!(
+                "Upload of {} is no longer open; remove {} to start it over",
+                state.key,
+                state_path.display()
+            )));
+        }
+
+        etag.filter(|_| status.is_success()).ok_or_else(|| {
+            DaemonError::with_message(&format!(
+                "Unable to upload part {} of {}: {} {}",
+                part_number, state.key, status, body
+            ))
+        })
+    }
+
+    fn complete_upload(&self, state: &UploadState) -> Result<(), DaemonError> {
+        let parts = state
+            .parts
+            .iter()
+            .map(|(number, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    number, etag
+                )
+            })
+            .collect::<String>();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+        let (status, _, body) = self.send(
+            Method::POST,
+            &state.key,
+            &[("uploadId", &state.upload_id)],
+            body.into_bytes(),
+        )?;
+
+        // S3 may report a failure to complete in the body of a successful response
+        if status.is_success() && !body.contains("<Error>") {
+            Ok(())
+        } else {
+            Err(DaemonError::with_message(&format!(
+                "Unable to complete upload of {}: {} {}",
+                state.key, status, body
+            )))
+        }
+    }
+}
+
+impl ExportDestination for S3Destination {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn upload(&self, name: &str, staged: &Path, staging_dir: &Path) -> Result<String, DaemonError> {
+        let key = self.key(name);
+        let state_path = staging_dir.join(format!("{}.upload", name));
+        let mut state = match read_state(&state_path)? {
+            Some(state) if state.key == key => {
+                info!(
+                    "Resuming upload of {} after {} part(s)",
+                    key,
+                    state.parts.len()
+                );
+                state
+            }
+            _ => {
+                let state = self.start_upload(&key)?;
+                write_state(&state_path, &state)?;
+                state
+            }
+        };
+
+        let mut file = File::open(staged).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let size = file
+            .metadata()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?
+            .len();
+        // An empty export is still uploaded as one, empty, part
+        let part_count = size.div_ceil(PART_SIZE).max(1);
+
+        for part_number in 1..=part_count {
+            if state.parts.iter().any(|(number, _)| *number == part_number) {
+                continue;
+            }
+            let mut data = Vec::new();
+            file.seek(SeekFrom::Start((part_number - 1) * PART_SIZE))
+                .and_then(|_| (&mut file).take(PART_SIZE).read_to_end(&mut data))
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+            let etag = self.upload_part(&state, part_number, data, &state_path)?;
+            state.parts.push((part_number, etag));
+            state.parts.sort();
+            write_state(&state_path, &state)?;
+        }
+
+        self.complete_upload(&state)?;
+        if let Err(err) = fs::remove_file(&state_path) {
+            warn!("Unable to remove {}: {}", state_path.display(), err);
+        }
+
+        Ok(format!("s3://{}/{}", self.bucket, key))
+    }
+}
+
+/// Reads the bucket and the prefix, without slashes around it, from a `s3://bucket[/prefix]`
+/// URL
+fn parse_s3_url(location: &str) -> Result<(String, String), DaemonError> {
+    let path = location.strip_prefix("s3://").unwrap_or(location);
+    let (bucket, prefix) = match path.find('/') {
+        Some(slash) => (&path[..slash], path[slash + 1..].trim_matches('/')),
+        None => (path, ""),
+    };
+    if bucket.is_empty() {
+        return Err(DaemonError::with_message(&format!(
+            "S3 URL {} has no bucket",
+            location
+        )));
+    }
+
+    Ok((bucket.to_string(), prefix.to_string()))
+}
+
+fn read_state(path: &Path) -> Result<Option<UploadState>, DaemonError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read(path).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    serde_json::from_slice(&contents)
+        .map(Some)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+fn write_state(path: &Path, state: &UploadState) -> Result<(), DaemonError> {
+    let contents =
+        serde_json::to_vec(state).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    fs::write(path, contents).map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// A request in the canonical form AWS Signature Version 4 signs
+struct CanonicalRequest {
+    method: String,
+    /// The URI-encoded path
+    uri: String,
+    query: Vec<(String, String)>,
+    /// Lower-case header names and their values
+    headers: Vec<(String, String)>,
+    payload_hash: String,
+}
+
+impl CanonicalRequest {
+    fn canonical_query(&self) -> String {
+        let mut query = self
+            .query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, false), uri_encode(value, false)))
+            .collect::<Vec<_>>();
+        query.sort();
+        query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    fn sorted_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+            .collect::<Vec<_>>();
+        headers.sort();
+        headers
+    }
+
+    fn signed_headers(&self) -> String {
+        self.sorted_headers()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Returns the path and query the request is sent to
+    fn target(&self) -> String {
+        match self.canonical_query() {
+            query if query.is_empty() => self.uri.clone(),
+            query => format!("{}?{}", self.uri, query),
+        }
+    }
+
+    fn canonical_form(&self) -> String {
+        let headers = self
+            .sorted_headers()
+            .into_iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect::<String>();
+
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            self.method,
+            self.uri,
+            self.canonical_query(),
+            headers,
+            self.signed_headers(),
+            self.payload_hash
+        )
+    }
+
+    /// Returns the `Authorization` header for the request, sent to S3 at the given
+    /// `YYYYMMDDTHHMMSSZ` time
+    fn authorization(&self, credentials: &Credentials, region: &str, amz_date: &str) -> String {
+        self.authorization_for_service(credentials, region, "s3", amz_date)
+    }
+
+    fn authorization_for_service(
+        &self,
+        credentials: &Credentials,
+        region: &str,
+        service: &str,
+        amz_date: &str,
+    ) -> String {
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(self.canonical_form().as_bytes())
+        );
+        let key = signing_key(&credentials.secret_access_key, date, region, service);
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id,
+            scope,
+            self.signed_headers(),
+            to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+        )
+    }
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut sha = Sha256::new();
+    sha.input(data);
+    sha.result_str()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Percent-encodes every byte but the unreserved characters, and `/` if `keep_slash` is set
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b'/' if keep_slash => "/".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Returns the text of the first element with the given tag in an XML response
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{}>", tag))? + start;
+    Some(xml[start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_credentials() -> Credentials {
+        Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    /// Verify that requests are signed as in the `get-vanilla` and `get-vanilla-query-order-key`
+    /// cases of AWS's Signature Version 4 test suite
+    #[test]
+    fn test_authorization() {
+        let mut request = CanonicalRequest {
+            method: "GET".to_string(),
+            uri: "/".to_string(),
+            query: vec![],
+            headers: vec![
+                ("Host".to_string(), "example.amazonaws.com".to_string()),
+                ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+            ],
+            payload_hash: sha256_hex(b""),
+        };
+        assert_eq!(
+            request.authorization_for_service(
+                &example_credentials(),
+                "us-east-1",
+                "service",
+                "20150830T123600Z"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+
+        request.query = vec![
+            ("Param2".to_string(), "value2".to_string()),
+            ("Param1".to_string(), "value1".to_string()),
+        ];
+        assert_eq!(request.target(), "/?Param1=value1&Param2=value2");
+        assert_eq!(
+            request.authorization_for_service(
+                &example_credentials(),
+                "us-east-1",
+                "service",
+                "20150830T123600Z"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    /// Verify that the bucket and prefix are read from an S3 URL and keys are encoded with
+    /// slashes kept
+    #[test]
+    fn test_parse_s3_url() {
+        assert_eq!(
+            parse_s3_url("s3://grid-exports/mfg/daily/").expect("Failed to read URL"),
+            ("grid-exports".to_string(), "mfg/daily".to_string())
+        );
+        assert_eq!(
+            parse_s3_url("s3://grid-exports").expect("Failed to read URL"),
+            ("grid-exports".to_string(), "".to_string())
+        );
+        assert!(parse_s3_url("s3:///mfg").is_err());
+
+        assert_eq!(uri_encode("mfg/nightly 1.csv", true), "mfg/nightly%201.csv");
+        assert_eq!(uri_encode("a/b+c=", false), "a%2Fb%2Bc%3D");
+    }
+
+    /// Verify that the upload ID is read from the response that starts an upload
+    #[test]
+    fn test_xml_value() {
+        let response = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <InitiateMultipartUploadResult>\
+            <Bucket>grid-exports</Bucket><Key>nightly.csv</Key>\
+            <UploadId>VXBsb2FkIElE</UploadId>\
+            </InitiateMultipartUploadResult>";
+
+        assert_eq!(
+            xml_value(response, "UploadId"),
+            Some("VXBsb2FkIElE".to_string())
+        );
+        assert_eq!(xml_value(response, "ETag"), None);
+    }
+}
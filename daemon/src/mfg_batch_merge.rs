@@ -0,0 +1,68 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merges a duplicate mfg_batch into another, such as one flagged by `detect-duplicates`. Only
+//! the records the daemon keeps about the mfg_batches are merged; their on-chain records are left
+//! as they are.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::store::MfgBatchAlias;
+
+use crate::database::create_mfg_batch_store;
+use crate::error::DaemonError;
+
+/// Runs the `merge-mfg-batches` subcommand against the database at `database_url`, writing the
+/// aliases that now resolve to the mfg_batch merged into, one per line
+pub fn run_merge_mfg_batches(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    // Both arguments are required by clap
+    let from = matches.value_of("from").unwrap_or_default();
+    let into = matches.value_of("into").unwrap_or_default();
+    let service_id = matches.value_of("service_id");
+
+    let store = create_mfg_batch_store(database_url)?;
+
+    store
+        .merge_mfg_batches(MfgBatchAlias {
+            alias: from.to_string(),
+            mfg_batch_id: into.to_string(),
+            merged_at: now(),
+            service_id: service_id.map(String::from),
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let aliases = store
+        .list_mfg_batch_aliases(service_id)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for alias in aliases.iter().filter(|alias| alias.mfg_batch_id == into) {
+        writeln!(out, "{}\t{}", alias.alias, alias.mfg_batch_id)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
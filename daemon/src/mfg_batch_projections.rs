@@ -0,0 +1,104 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs the read-model projections the daemon ships with over the mfg_batch change feed:
+//! applying new changes once or on an interval, reporting each projection's checkpoint, and
+//! rebuilding a projection from the start of the feed.
+
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::projections::{
+    ExpiryProjection, Projection, ProjectionConnection, ProjectionRunner,
+};
+use grid_sdk::store::ConnectionUri;
+
+use crate::error::DaemonError;
+
+/// Runs the `projections` subcommand against the database at `database_url`
+pub fn run_projections_command(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let connection_uri = database_url
+        .parse()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    match connection_uri {
+        #[cfg(feature = "database-postgres")]
+        ConnectionUri::Postgres(_) => run::<diesel::pg::PgConnection>(database_url, matches, out),
+        #[cfg(feature = "database-sqlite")]
+        ConnectionUri::Sqlite(_) => {
+            run::<diesel::sqlite::SqliteConnection>(database_url, matches, out)
+        }
+    }
+}
+
+fn run<C>(database_url: &str, matches: &ArgMatches, out: &mut dyn Write) -> Result<(), DaemonError>
+where
+    C: ProjectionConnection,
+    ExpiryProjection: Projection<C>,
+{
+    let conn = C::establish(database_url).map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let runner = ProjectionRunner::<C>::new().with_projection(Box::new(ExpiryProjection));
+
+    match matches.subcommand() {
+        ("status", _) => {
+            let statuses = runner
+                .status(&conn)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            for status in statuses {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    status.name,
+                    status.checkpoint,
+                    status.latest_change_id - status.checkpoint
+                )
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            }
+            Ok(())
+        }
+        ("run", Some(m)) => {
+            let interval = value_t!(m, "interval", u64)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            loop {
+                let applied = runner
+                    .catch_up(&conn)
+                    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+                if applied > 0 {
+                    info!("Applied {} change(s) to projections", applied);
+                }
+                if m.is_present("once") {
+                    return Ok(());
+                }
+                thread::sleep(Duration::from_secs(interval));
+            }
+        }
+        ("rebuild", Some(m)) => {
+            let name = m.value_of("name").unwrap_or_default();
+            let applied = runner
+                .rebuild(&conn, name)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            writeln!(out, "Rebuilt {} from {} change(s)", name, applied)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))
+        }
+        _ => Err(DaemonError::with_message(
+            "A projections subcommand is required",
+        )),
+    }
+}
@@ -0,0 +1,635 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rebuilds the mfg_batch store from the mfg_batch records in chain state, such as after losing
+//! the database or migrating it to a new schema.
+//!
+//! The records under the mfg_batch namespace are read through the Sawtooth REST API, or the state
+//! endpoint of a scabbard service on Splinter, and each mfg_batch is written to the store as
+//! current from the commit the state was read at. On Sawtooth that is the block the REST API read
+//! state at; scabbard does not report it, so the latest commit the daemon has recorded is used.
+//! Stored mfg_batches that are already as new are left as they are, and those whose address no
+//! longer holds any state are deleted, so the command can be run again safely.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use clap::ArgMatches;
+use grid_sdk::mfg_batch::{
+    addressing::GRID_MFG_BATCH_NAMESPACE,
+    store::{
+        LatLongValue, ListMfgBatchFilters, MfgBatch, MfgBatchAttachment, MfgBatchBuilder,
+        MfgBatchStore, PropertyValue, PropertyValueBuilder, TypedValue, UpsertMfgBatchOutcome,
+    },
+    MAX_COMMIT_NUM,
+};
+use grid_sdk::protocol::mfg_batch::state::{
+    MfgBatch as StateMfgBatch, MfgBatchList, MfgBatchNamespace,
+};
+use grid_sdk::protocol::schema::state::{DataType, PropertyValue as StatePropertyValue};
+use grid_sdk::protos::FromBytes;
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+use crate::config::GridConfig;
+use crate::database::create_shared_mfg_batch_store;
+use crate::error::DaemonError;
+
+/// The number of state entries requested per page from the Sawtooth REST API
+const STATE_PAGE_SIZE: usize = 1000;
+/// The number of stored mfg_batches read per query while looking for deleted ones
+const STORE_PAGE_SIZE: i64 = 1000;
+
+/// The mfg_batch state entries, and the commit they were read at
+struct StateSnapshot {
+    commit_num: i64,
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+/// What rebuilding the store changed
+#[derive(Debug, Default, PartialEq)]
+struct RebuildSummary {
+    created: usize,
+    replaced: usize,
+    unchanged: usize,
+    deleted: usize,
+}
+
+/// Runs the `rebuild-mfg-batch-db` subcommand, writing the mfg_batch state read from the REST API
+/// at the `state_url` argument to the configured store, and then how many mfg_batches were added,
+/// replaced, left as they were and deleted
+pub fn run_rebuild_mfg_batch_db(
+    config: &GridConfig,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    // Required by clap
+    let state_url = matches
+        .value_of("state_url")
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let service_id = matches.value_of("service_id");
+
+    let client = Client::new();
+    let snapshot = match service_id {
+        Some(service_id) => read_scabbard_state(config, &client, state_url, service_id)?,
+        None => read_sawtooth_state(&client, state_url)?,
+    };
+    info!(
+        "Read {} mfg_batch state entries at commit {}",
+        snapshot.entries.len(),
+        snapshot.commit_num
+    );
+
+    let store = create_shared_mfg_batch_store(config)?;
+    let summary = rebuild(&*store, &snapshot, service_id)?;
+
+    writeln!(
+        out,
+        "Rebuilt mfg_batches at commit {}: {} added, {} replaced, {} unchanged, {} deleted",
+        snapshot.commit_num, summary.created, summary.replaced, summary.unchanged, summary.deleted
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Writes the mfg_batches in the snapshot to the store, and deletes the stored mfg_batches whose
+/// address is not in it
+fn rebuild(
+    store: &dyn MfgBatchStore,
+    snapshot: &StateSnapshot,
+    service_id: Option<&str>,
+) -> Result<RebuildSummary, DaemonError> {
+    let mut summary = RebuildSummary::default();
+
+    for (address, data) in &snapshot.entries {
+        for mfg_batch in make_mfg_batches(address, data, snapshot.commit_num, service_id)? {
+            let outcome = store
+                .upsert_mfg_batch(mfg_batch)
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            match outcome {
+                UpsertMfgBatchOutcome::Created => summary.created += 1,
+                UpsertMfgBatchOutcome::Replaced { .. } => summary.replaced += 1,
+                UpsertMfgBatchOutcome::Conflict { .. } => summary.unchanged += 1,
+            }
+        }
+    }
+
+    let state_addresses = snapshot
+        .entries
+        .iter()
+        .map(|(address, _)| address.as_str())
+        .collect::<HashSet<_>>();
+    let mut deleted_addresses = HashSet::new();
+    for mfg_batch in
+        store.iter_mfg_batches(service_id, &ListMfgBatchFilters::default(), STORE_PAGE_SIZE)
+    {
+        let mfg_batch = mfg_batch.map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        if !state_addresses.contains(mfg_batch.mfg_batch_address()) {
+            deleted_addresses.insert(mfg_batch.mfg_batch_address().to_string());
+            summary.deleted += 1;
+        }
+    }
+
+    // Every mfg_batch at an address is deleted together
+    for address in deleted_addresses {
+        store
+            .delete_mfg_batch(&address, snapshot.commit_num)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(summary)
+}
+
+/// Reads the mfg_batch state through the Sawtooth REST API, page by page at the head block of the
+/// first page, so that every page reflects the same block
+fn read_sawtooth_state(client: &Client, rest_api_url: &str) -> Result<StateSnapshot, DaemonError> {
+    let mut entries = Vec::new();
+    let mut head: Option<String> = None;
+    let mut start: Option<String> = None;
+
+    loop {
+        let mut query = vec![
+            ("address", GRID_MFG_BATCH_NAMESPACE.to_string()),
+            ("limit", STATE_PAGE_SIZE.to_string()),
+        ];
+        if let Some(head) = &head {
+            query.push(("head", head.clone()));
+        }
+        if let Some(start) = &start {
+            query.push(("start", start.clone()));
+        }
+
+        let page = get_json(client.get(&format!("{}/state", rest_api_url)).query(&query))?;
+        entries.extend(parse_sawtooth_state_entries(&page)?);
+
+        if head.is_none() {
+            head = Some(
+                page["head"]
+                    .as_str()
+                    .ok_or_else(|| invalid_response("state response has no head"))?
+                    .to_string(),
+            );
+        }
+        match page["paging"]["next_position"].as_str() {
+            Some(next_position) => start = Some(next_position.to_string()),
+            None => break,
+        }
+    }
+
+    // The state was read at the head block, so its records are current from that block's number
+    let head = head.unwrap_or_default();
+    let block = get_json(client.get(&format!("{}/blocks/{}", rest_api_url, head)))?;
+
+    Ok(StateSnapshot {
+        commit_num: parse_block_num(&block)?,
+        entries,
+    })
+}
+
+/// Reads the mfg_batch state of a scabbard service, given as `circuit_id::service_id`, through
+/// the splinterd REST API. Scabbard does not say which commit its state reflects, so the records
+/// are taken to be current from the latest commit the daemon has recorded.
+#[cfg(feature = "splinter-support")]
+fn read_scabbard_state(
+    config: &GridConfig,
+    client: &Client,
+    splinterd_url: &str,
+    service_id: &str,
+) -> Result<StateSnapshot, DaemonError> {
+    use std::path::PathBuf;
+
+    use cylinder::{jwt::JsonWebTokenBuilder, load_key, secp256k1::Secp256k1Context, Context};
+    use grid_sdk::store::create_store_factory;
+
+    let (circuit, service) = match service_id.split_once("::") {
+        Some(ids) => ids,
+        None => {
+            return Err(DaemonError::with_message(
+                "Service ID must include {circuit_id}::{service_id}",
+            ))
+        }
+    };
+
+    let gridd_key = load_key("gridd", &[PathBuf::from(config.admin_key_dir())])
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        .ok_or_else(|| DaemonError::with_message("no private key found"))?;
+    let signer = Secp256k1Context::new().new_signer(gridd_key);
+    let jwt = JsonWebTokenBuilder::new()
+        .build(&*signer)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let state = get_json(
+        client
+            .get(&format!(
+                "{}/scabbard/{}/{}/state",
+                splinterd_url, circuit, service
+            ))
+            .query(&[("prefix", GRID_MFG_BATCH_NAMESPACE)])
+            .header("Authorization", format!("Bearer Cylinder:{}", jwt)),
+    )?;
+
+    let connection_uri = config
+        .database_url()
+        .parse()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let next_commit_num = create_store_factory(&connection_uri)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?
+        .get_grid_commit_store()
+        .get_next_commit_num()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok(StateSnapshot {
+        commit_num: (next_commit_num - 1).max(0),
+        entries: parse_scabbard_state_entries(&state)?,
+    })
+}
+
+#[cfg(not(feature = "splinter-support"))]
+fn read_scabbard_state(
+    _config: &GridConfig,
+    _client: &Client,
+    _splinterd_url: &str,
+    _service_id: &str,
+) -> Result<StateSnapshot, DaemonError> {
+    Err(DaemonError::with_message(
+        "A service ID was provided but Splinter support is not enabled for this binary",
+    ))
+}
+
+fn get_json(request: reqwest::blocking::RequestBuilder) -> Result<Value, DaemonError> {
+    let response = request
+        .send()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    if !response.status().is_success() {
+        return Err(DaemonError::with_message(&format!(
+            "State request to {} failed with status {}",
+            response.url(),
+            response.status()
+        )));
+    }
+
+    response
+        .json()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Parses the entries of a Sawtooth REST API state page, whose data is base64 encoded
+fn parse_sawtooth_state_entries(page: &Value) -> Result<Vec<(String, Vec<u8>)>, DaemonError> {
+    page["data"]
+        .as_array()
+        .ok_or_else(|| invalid_response("state response has no data"))?
+        .iter()
+        .map(|entry| {
+            let address = entry["address"]
+                .as_str()
+                .ok_or_else(|| invalid_response("state entry has no address"))?;
+            let data = base64::decode(entry["data"].as_str().unwrap_or_default())
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            Ok((address.to_string(), data))
+        })
+        .collect()
+}
+
+/// Parses the entries returned by a scabbard state endpoint, whose values are arrays of bytes
+#[cfg(any(test, feature = "splinter-support"))]
+fn parse_scabbard_state_entries(state: &Value) -> Result<Vec<(String, Vec<u8>)>, DaemonError> {
+    state
+        .as_array()
+        .ok_or_else(|| invalid_response("state response is not a list"))?
+        .iter()
+        .map(|entry| {
+            let address = entry["address"]
+                .as_str()
+                .ok_or_else(|| invalid_response("state entry has no address"))?;
+            let value = entry["value"]
+                .as_array()
+                .ok_or_else(|| invalid_response("state entry has no value"))?
+                .iter()
+                .map(|byte| byte.as_u64().filter(|byte| *byte <= u8::MAX as u64))
+                .map(|byte| byte.map(|byte| byte as u8))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| invalid_response("state entry value is not a list of bytes"))?;
+            Ok((address.to_string(), value))
+        })
+        .collect()
+}
+
+/// Parses the number of a Sawtooth REST API block, which is given as a string
+fn parse_block_num(block: &Value) -> Result<i64, DaemonError> {
+    let block_num = &block["data"]["header"]["block_num"];
+    block_num
+        .as_str()
+        .and_then(|block_num| block_num.parse().ok())
+        .or_else(|| block_num.as_i64())
+        .ok_or_else(|| invalid_response("block has no block_num"))
+}
+
+fn invalid_response(message: &str) -> DaemonError {
+    DaemonError::with_message(&format!("Invalid state REST API response: {}", message))
+}
+
+/// Converts the mfg_batch list at a state address into the mfg_batches to store, current from
+/// `commit_num`
+fn make_mfg_batches(
+    address: &str,
+    data: &[u8],
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Result<Vec<MfgBatch>, DaemonError> {
+    MfgBatchList::from_bytes(data)
+        .map_err(|err| {
+            DaemonError::with_message(&format!(
+                "Failed to parse mfg_batch list at {}: {}",
+                address, err
+            ))
+        })?
+        .mfg_batches()
+        .iter()
+        .map(|mfg_batch| make_mfg_batch(address, mfg_batch, commit_num, service_id))
+        .collect()
+}
+
+fn make_mfg_batch(
+    address: &str,
+    mfg_batch: &StateMfgBatch,
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Result<MfgBatch, DaemonError> {
+    // Quantities are only recorded along with their unit of measure, and zero dates are unset
+    let has_quantity = !mfg_batch.uom().is_empty();
+    let non_zero = |date: u64| Some(date as i64).filter(|date| *date != 0);
+
+    MfgBatchBuilder::default()
+        .with_mfg_batch_id(mfg_batch.mfg_batch_id().to_string())
+        .with_mfg_batch_address(address.to_string())
+        .with_mfg_batch_namespace(namespace_name(mfg_batch.mfg_batch_namespace()).to_string())
+        .with_owner(mfg_batch.owner().to_string())
+        .with_start_commit_number(commit_num)
+        .with_end_commit_number(MAX_COMMIT_NUM)
+        .with_service_id(service_id.map(String::from))
+        .with_properties(make_property_values(
+            mfg_batch.mfg_batch_id(),
+            address,
+            mfg_batch.properties(),
+            commit_num,
+            service_id,
+        )?)
+        .with_parent_batches(mfg_batch.parent_batches().to_vec())
+        .with_quantity(Some(mfg_batch.quantity()).filter(|_| has_quantity))
+        .with_uom(Some(mfg_batch.uom().to_string()).filter(|_| has_quantity))
+        .with_expected_quantity(
+            Some(mfg_batch.expected_quantity()).filter(|quantity| has_quantity && *quantity != 0),
+        )
+        .with_production_date(non_zero(mfg_batch.production_date()))
+        .with_expiration_date(non_zero(mfg_batch.expiration_date()))
+        .with_manufacture_location(
+            Some(mfg_batch.manufacture_location().to_string())
+                .filter(|location| !location.is_empty()),
+        )
+        .with_attachments(
+            mfg_batch
+                .attachments()
+                .iter()
+                .map(|attachment| MfgBatchAttachment {
+                    digest: attachment.digest().to_string(),
+                    mime_type: attachment.mime_type().to_string(),
+                    size: attachment.size() as i64,
+                    uri: Some(attachment.uri().to_string()).filter(|uri| !uri.is_empty()),
+                })
+                .collect(),
+        )
+        .with_archived(mfg_batch.archived())
+        .build()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+fn make_property_values(
+    mfg_batch_id: &str,
+    address: &str,
+    values: &[StatePropertyValue],
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Result<Vec<PropertyValue>, DaemonError> {
+    values
+        .iter()
+        .map(|value| {
+            let typed_value = match value.data_type() {
+                DataType::Bytes => TypedValue::Bytes(value.bytes_value().to_vec()),
+                DataType::Boolean => TypedValue::Bool(*value.boolean_value()),
+                DataType::Number => TypedValue::Number {
+                    value: *value.number_value(),
+                    exponent: None,
+                },
+                DataType::String => TypedValue::String(value.string_value().to_string()),
+                DataType::Enum => TypedValue::Enum(*value.enum_value() as i32),
+                DataType::Struct => TypedValue::Struct(make_property_values(
+                    mfg_batch_id,
+                    address,
+                    value.struct_values(),
+                    commit_num,
+                    service_id,
+                )?),
+                DataType::LatLong => TypedValue::LatLong(LatLongValue {
+                    latitude: *value.lat_long_value().latitude(),
+                    longitude: *value.lat_long_value().longitude(),
+                }),
+            };
+
+            PropertyValueBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.to_string())
+                .with_mfg_batch_address(address.to_string())
+                .with_property_name(value.name().to_string())
+                .with_typed_value(typed_value)
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .with_service_id(service_id.map(String::from))
+                .with_language(Some(value.language().to_string()).filter(|tag| !tag.is_empty()))
+                .build()
+                .map_err(|err| DaemonError::from_source(Box::new(err)))
+        })
+        .collect()
+}
+
+fn namespace_name(namespace: &MfgBatchNamespace) -> &'static str {
+    match namespace {
+        MfgBatchNamespace::Gs1 => "GS1",
+        MfgBatchNamespace::Internal => "INTERNAL",
+        MfgBatchNamespace::Lot => "LOT",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid_sdk::protocol::mfg_batch::state::{
+        MfgBatchBuilder as StateMfgBatchBuilder, MfgBatchListBuilder,
+    };
+    use grid_sdk::protocol::schema::state::PropertyValueBuilder as StatePropertyValueBuilder;
+    use grid_sdk::protos::IntoBytes;
+
+    /// Verify that a mfg_batch in state is stored with its address, the commit number and service
+    /// it was read at, and unset quantities and dates left empty
+    #[test]
+    fn test_make_mfg_batches() {
+        let lot_code = StatePropertyValueBuilder::new()
+            .with_name("lot_code".to_string())
+            .with_data_type(DataType::String)
+            .with_string_value("LOT-42".to_string())
+            .build()
+            .expect("Failed to build property value");
+        let data = MfgBatchListBuilder::new()
+            .with_mfg_batches(vec![StateMfgBatchBuilder::new()
+                .with_mfg_batch_id("(01)10012345678902(10)A1".to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_owner("acme".to_string())
+                .with_properties(vec![lot_code])
+                .with_parent_batches(vec!["flour".to_string()])
+                .with_production_date(1_600_000_000)
+                .build()
+                .expect("Failed to build mfg_batch")])
+            .build()
+            .expect("Failed to build mfg_batch list")
+            .into_bytes()
+            .expect("Failed to serialize mfg_batch list");
+
+        let mfg_batches = make_mfg_batches("11bb0e01aa", &data, 7, Some("circuit::service"))
+            .expect("Failed to make mfg_batches");
+
+        assert_eq!(mfg_batches.len(), 1);
+        let mfg_batch = &mfg_batches[0];
+        assert_eq!(mfg_batch.mfg_batch_id(), "(01)10012345678902(10)A1");
+        assert_eq!(mfg_batch.mfg_batch_address(), "11bb0e01aa");
+        assert_eq!(mfg_batch.mfg_batch_namespace(), "GS1");
+        assert_eq!(*mfg_batch.start_commit_num(), 7);
+        assert_eq!(*mfg_batch.end_commit_num(), MAX_COMMIT_NUM);
+        assert_eq!(mfg_batch.service_id(), Some("circuit::service"));
+        assert_eq!(mfg_batch.parent_batches(), &["flour".to_string()]);
+        assert_eq!(mfg_batch.quantity(), None);
+        assert_eq!(mfg_batch.uom(), None);
+        assert_eq!(mfg_batch.production_date(), Some(1_600_000_000));
+        assert_eq!(mfg_batch.expiration_date(), None);
+        assert_eq!(mfg_batch.manufacture_location(), None);
+
+        let properties = mfg_batch.properties();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].property_name(), "lot_code");
+        assert_eq!(properties[0].data_type(), "String");
+        assert_eq!(properties[0].string_value(), Some("LOT-42"));
+        assert_eq!(*properties[0].start_commit_num(), 7);
+    }
+
+    /// Verify that rebuilding adds the mfg_batches in state, leaves them as they are when run
+    /// again at the same commit, and at a later commit replaces them and deletes those whose
+    /// address no longer holds state
+    #[cfg(feature = "database-sqlite")]
+    #[test]
+    fn test_rebuild() {
+        use diesel::r2d2::{ConnectionManager, Pool};
+        use diesel::sqlite::SqliteConnection;
+        use grid_sdk::mfg_batch::store::DieselMfgBatchStore;
+        use grid_sdk::migrations::run_sqlite_migrations;
+
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        let store = DieselMfgBatchStore::new(pool);
+
+        let snapshot = |commit_num, entries: &[(&str, &str)]| StateSnapshot {
+            commit_num,
+            entries: entries
+                .iter()
+                .map(|(address, mfg_batch_id)| (address.to_string(), state_data(mfg_batch_id)))
+                .collect(),
+        };
+        let both = snapshot(5, &[("11bb0e01aa", "flour"), ("11bb0e01bb", "sugar")]);
+
+        assert_eq!(
+            rebuild(&store, &both, None).expect("Failed to rebuild"),
+            RebuildSummary {
+                created: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            rebuild(&store, &both, None).expect("Failed to rebuild"),
+            RebuildSummary {
+                unchanged: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            rebuild(&store, &snapshot(9, &[("11bb0e01aa", "flour")]), None)
+                .expect("Failed to rebuild"),
+            RebuildSummary {
+                replaced: 1,
+                deleted: 1,
+                ..Default::default()
+            }
+        );
+
+        let flour = store
+            .get_mfg_batch("flour", None)
+            .expect("Failed to get mfg_batch")
+            .expect("mfg_batch not found");
+        assert_eq!(*flour.start_commit_num(), 9);
+        assert!(store
+            .get_mfg_batch("sugar", None)
+            .expect("Failed to get mfg_batch")
+            .is_none());
+    }
+
+    fn state_data(mfg_batch_id: &str) -> Vec<u8> {
+        MfgBatchListBuilder::new()
+            .with_mfg_batches(vec![StateMfgBatchBuilder::new()
+                .with_mfg_batch_id(mfg_batch_id.to_string())
+                .with_mfg_batch_namespace(MfgBatchNamespace::Lot)
+                .with_owner("acme".to_string())
+                .with_properties(vec![])
+                .build()
+                .expect("Failed to build mfg_batch")])
+            .build()
+            .expect("Failed to build mfg_batch list")
+            .into_bytes()
+            .expect("Failed to serialize mfg_batch list")
+    }
+
+    /// Verify that the state entries and block number of Sawtooth REST API responses, and the
+    /// entries of a scabbard state response, are parsed
+    #[test]
+    fn test_parse_state_responses() {
+        let page = serde_json::json!({
+            "data": [{"address": "11bb0e01aa", "data": base64::encode(b"batch")}],
+            "head": "head-block",
+            "paging": {"start": null, "limit": 1000},
+        });
+        assert_eq!(
+            parse_sawtooth_state_entries(&page).expect("Failed to parse state page"),
+            vec![("11bb0e01aa".to_string(), b"batch".to_vec())]
+        );
+
+        let block = serde_json::json!({"data": {"header": {"block_num": "12"}}});
+        assert_eq!(parse_block_num(&block).expect("Failed to parse block"), 12);
+
+        let state = serde_json::json!([{"address": "11bb0e01bb", "value": [98, 97]}]);
+        assert_eq!(
+            parse_scabbard_state_entries(&state).expect("Failed to parse scabbard state"),
+            vec![("11bb0e01bb".to_string(), b"ba".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A GraphQL endpoint over the stored mfg_batches, so a client can fetch a batch, its properties
+//! and its genealogy, with only the fields it needs, in one request.
+//!
+//! `mfgBatch` fetches one batch and `mfgBatches` a page of them. Each batch resolves its
+//! properties, struct properties included, its parents and all of its ancestors; as `parents`
+//! may be nested, queries are limited to a fixed depth.
+//!
+//! With the `mfg-batch-visibility` feature, a request authorized with an API key only sees the
+//! batches owned by, or shared with, the key's organization; other batches resolve as though
+//! they did not exist.
+
+use std::sync::Arc;
+
+#[cfg(feature = "mfg-batch-visibility")]
+use actix_web::HttpRequest;
+use actix_web::{post, web, HttpResponse};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, InputObject, Object, Schema, SimpleObject,
+};
+use grid_sdk::mfg_batch::store::{
+    LatLongValue, ListMfgBatchFilters, MfgBatch, MfgBatchStore, MfgBatchStoreError, PropertyValue,
+};
+#[cfg(feature = "mfg-batch-visibility")]
+use grid_sdk::{api_keys::store::ApiKey, mfg_batch::store::Visibility};
+
+/// How deeply fields may nest in a query
+const MAX_DEPTH: usize = 16;
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+pub type GraphQlSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+type SharedStore = Arc<dyn MfgBatchStore + Send + Sync>;
+
+/// Creates the schema the endpoint serves, resolving mfg_batches from `store`
+pub fn create_schema(store: SharedStore) -> GraphQlSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(store)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+#[post("/graphql")]
+pub async fn graphql(
+    schema: web::Data<GraphQlSchema>,
+    request: web::Json<async_graphql::Request>,
+    #[cfg(feature = "mfg-batch-visibility")] req: HttpRequest,
+) -> HttpResponse {
+    let request = request.into_inner();
+    #[cfg(feature = "mfg-batch-visibility")]
+    let request = match req.extensions().get::<ApiKey>() {
+        Some(api_key) => request.data(Visibility::org(&api_key.org_id)),
+        None => request,
+    };
+
+    HttpResponse::Ok().json(schema.execute(request).await)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A mfg_batch, by ID
+    async fn mfg_batch(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        service_id: Option<String>,
+    ) -> Result<Option<MfgBatchObject>, Error> {
+        let mfg_batch = get_visible_mfg_batch(ctx, &id, service_id.as_deref())?;
+
+        Ok(mfg_batch.map(|mfg_batch| MfgBatchObject::new(mfg_batch, service_id)))
+    }
+
+    /// A page of the mfg_batches that match the filter, in mfg_batch ID order
+    async fn mfg_batches(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<MfgBatchFilter>,
+        page: Option<PageInput>,
+        service_id: Option<String>,
+    ) -> Result<MfgBatchPage, Error> {
+        let filters = ListMfgBatchFilters {
+            #[cfg(feature = "mfg-batch-visibility")]
+            visibility: ctx.data_opt::<Visibility>().cloned(),
+            ..filter.map(ListMfgBatchFilters::from).unwrap_or_default()
+        };
+        let (offset, limit) = page.unwrap_or_default().bounds();
+        let list = store(ctx)?
+            .list_mfg_batches(service_id.as_deref(), &filters, offset, limit)
+            .map_err(store_error)?;
+
+        Ok(MfgBatchPage {
+            data: list
+                .data()
+                .into_iter()
+                .map(|mfg_batch| MfgBatchObject::new(mfg_batch, service_id.clone()))
+                .collect(),
+            offset: list.paging().offset,
+            limit: list.paging().limit,
+            total: list.paging().total,
+        })
+    }
+}
+
+/// Which mfg_batches `mfgBatches` returns; times are in seconds since the epoch
+#[derive(Default, InputObject)]
+pub struct MfgBatchFilter {
+    owner: Option<String>,
+    namespace: Option<String>,
+    expiring_before: Option<i64>,
+    expiring_after: Option<i64>,
+    archived: Option<bool>,
+    manufacture_location: Option<String>,
+}
+
+impl From<MfgBatchFilter> for ListMfgBatchFilters {
+    fn from(filter: MfgBatchFilter) -> Self {
+        ListMfgBatchFilters {
+            owner: filter.owner,
+            mfg_batch_namespace: filter.namespace,
+            expiring_before: filter.expiring_before,
+            expiring_after: filter.expiring_after,
+            archived: filter.archived,
+            manufacture_location: filter.manufacture_location,
+            #[cfg(feature = "mfg-batch-visibility")]
+            visibility: None,
+        }
+    }
+}
+
+#[derive(Default, InputObject)]
+pub struct PageInput {
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+impl PageInput {
+    /// Returns the offset and limit to list with, keeping the limit within the maximum page size
+    fn bounds(&self) -> (i64, i64) {
+        (
+            self.offset.unwrap_or(0).max(0),
+            self.limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+        )
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct MfgBatchPage {
+    data: Vec<MfgBatchObject>,
+    offset: i64,
+    limit: i64,
+    total: i64,
+}
+
+pub struct MfgBatchObject {
+    mfg_batch: MfgBatch,
+    /// The service the batch was fetched for, which its parents and ancestors are fetched for
+    service_id: Option<String>,
+}
+
+impl MfgBatchObject {
+    fn new(mfg_batch: MfgBatch, service_id: Option<String>) -> Self {
+        Self {
+            mfg_batch,
+            service_id,
+        }
+    }
+
+    fn get_all(
+        &self,
+        ctx: &Context<'_>,
+        mfg_batch_ids: &[String],
+    ) -> Result<Vec<MfgBatchObject>, Error> {
+        let mut mfg_batches = Vec::new();
+        for mfg_batch_id in mfg_batch_ids {
+            // A parent that was never recorded, has since been deleted or is not visible is left
+            // out
+            if let Some(mfg_batch) =
+                get_visible_mfg_batch(ctx, mfg_batch_id, self.service_id.as_deref())?
+            {
+                mfg_batches.push(MfgBatchObject::new(mfg_batch, self.service_id.clone()));
+            }
+        }
+
+        Ok(mfg_batches)
+    }
+}
+
+#[Object(name = "MfgBatch")]
+impl MfgBatchObject {
+    async fn id(&self) -> &str {
+        self.mfg_batch.mfg_batch_id()
+    }
+
+    async fn address(&self) -> &str {
+        self.mfg_batch.mfg_batch_address()
+    }
+
+    async fn namespace(&self) -> &str {
+        self.mfg_batch.mfg_batch_namespace()
+    }
+
+    async fn owner(&self) -> &str {
+        self.mfg_batch.owner()
+    }
+
+    async fn service_id(&self) -> Option<&str> {
+        self.mfg_batch.service_id()
+    }
+
+    async fn quantity(&self) -> Option<i64> {
+        self.mfg_batch.quantity()
+    }
+
+    async fn uom(&self) -> Option<&str> {
+        self.mfg_batch.uom()
+    }
+
+    async fn expected_quantity(&self) -> Option<i64> {
+        self.mfg_batch.expected_quantity()
+    }
+
+    async fn production_date(&self) -> Option<i64> {
+        self.mfg_batch.production_date()
+    }
+
+    async fn expiration_date(&self) -> Option<i64> {
+        self.mfg_batch.expiration_date()
+    }
+
+    /// The GLN of the location the batch was produced at
+    async fn manufacture_location(&self) -> Option<&str> {
+        self.mfg_batch.manufacture_location()
+    }
+
+    async fn archived(&self) -> bool {
+        self.mfg_batch.archived()
+    }
+
+    async fn last_updated(&self) -> Option<i64> {
+        self.mfg_batch.last_updated().copied()
+    }
+
+    /// The batch's properties, or only those named
+    async fn properties(&self, names: Option<Vec<String>>) -> Vec<PropertyObject> {
+        self.mfg_batch
+            .properties()
+            .into_iter()
+            .filter(|property| match &names {
+                Some(names) => names.iter().any(|name| name == property.property_name()),
+                None => true,
+            })
+            .map(PropertyObject)
+            .collect()
+    }
+
+    /// The IDs of the batches this batch was made from
+    async fn parent_ids(&self) -> &[String] {
+        self.mfg_batch.parent_batches()
+    }
+
+    /// The batches this batch was made from
+    async fn parents(&self, ctx: &Context<'_>) -> Result<Vec<MfgBatchObject>, Error> {
+        self.get_all(ctx, self.mfg_batch.parent_batches())
+    }
+
+    /// Every batch this batch descends from, nearest first
+    async fn ancestors(&self, ctx: &Context<'_>) -> Result<Vec<MfgBatchObject>, Error> {
+        let ancestry = store(ctx)?
+            .get_mfg_batch_ancestry(self.mfg_batch.mfg_batch_id(), self.service_id.as_deref())
+            .map_err(store_error)?;
+        self.get_all(ctx, &ancestry)
+    }
+}
+
+pub struct PropertyObject(PropertyValue);
+
+#[Object(name = "Property")]
+impl PropertyObject {
+    async fn name(&self) -> &str {
+        self.0.property_name()
+    }
+
+    async fn data_type(&self) -> &str {
+        self.0.data_type()
+    }
+
+    /// The value of a bytes property, base64-encoded
+    async fn bytes_value(&self) -> Option<String> {
+        self.0.bytes_value().map(base64::encode)
+    }
+
+    async fn boolean_value(&self) -> Option<bool> {
+        self.0.boolean_value()
+    }
+
+    async fn number_value(&self) -> Option<i64> {
+        self.0.number_value()
+    }
+
+    async fn string_value(&self) -> Option<&str> {
+        self.0.string_value()
+    }
+
+    async fn enum_value(&self) -> Option<i32> {
+        self.0.enum_value()
+    }
+
+    /// The fields of a struct property
+    async fn struct_values(&self) -> Vec<PropertyObject> {
+        self.0
+            .struct_values()
+            .into_iter()
+            .map(PropertyObject)
+            .collect()
+    }
+
+    async fn lat_long_value(&self) -> Option<LatLong> {
+        self.0.lat_long_value().map(LatLong::from)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct LatLong {
+    latitude: i64,
+    longitude: i64,
+}
+
+impl From<LatLongValue> for LatLong {
+    fn from(value: LatLongValue) -> Self {
+        LatLong {
+            latitude: value.latitude,
+            longitude: value.longitude,
+        }
+    }
+}
+
+fn store<'a>(ctx: &Context<'a>) -> Result<&'a SharedStore, Error> {
+    ctx.data::<SharedStore>()
+}
+
+/// Gets a mfg_batch, unless the request is restricted to an organization it is not visible to
+fn get_visible_mfg_batch(
+    ctx: &Context<'_>,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+) -> Result<Option<MfgBatch>, Error> {
+    let store = store(ctx)?;
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    if let Some(visibility) = ctx.data_opt::<Visibility>() {
+        if !store
+            .mfg_batch_visible(mfg_batch_id, visibility, service_id)
+            .map_err(store_error)?
+        {
+            return Ok(None);
+        }
+    }
+
+    store
+        .get_mfg_batch(mfg_batch_id, service_id)
+        .map_err(store_error)
+}
+
+/// Reports a store error to the client, leaving out the details of internal errors
+fn store_error(err: MfgBatchStoreError) -> Error {
+    match err {
+        MfgBatchStoreError::InternalError(_)
+        | MfgBatchStoreError::ResourceTemporarilyUnavailableError(_) => {
+            error!("Unable to resolve GraphQL query: {}", err);
+            Error::new("An internal error occurred")
+        }
+        err => Error::new(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that a page defaults to the first 100 mfg_batches and is kept within the maximum
+    /// page size
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(PageInput::default().bounds(), (0, DEFAULT_PAGE_SIZE));
+        assert_eq!(
+            PageInput {
+                offset: Some(200),
+                limit: Some(50),
+            }
+            .bounds(),
+            (200, 50)
+        );
+        assert_eq!(
+            PageInput {
+                offset: Some(-1),
+                limit: Some(100_000),
+            }
+            .bounds(),
+            (0, MAX_PAGE_SIZE)
+        );
+    }
+
+    /// Verify that the schema exposes the mfg_batch queries and genealogy fields
+    #[test]
+    fn test_schema() {
+        let sdl = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .finish()
+            .sdl();
+
+        assert!(sdl.contains("mfgBatch(id: String!, serviceId: String): MfgBatch"));
+        assert!(sdl.contains(
+            "mfgBatches(filter: MfgBatchFilter, page: PageInput, serviceId: String): MfgBatchPage!"
+        ));
+        assert!(sdl.contains("structValues: [Property!]!"));
+        assert!(sdl.contains("ancestors: [MfgBatch!]!"));
+    }
+}
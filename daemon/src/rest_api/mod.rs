@@ -14,19 +14,110 @@
 // limitations under the License.
 
 pub mod error;
-
+#[cfg(feature = "graphql")]
+mod graphql;
+
+#[cfg(feature = "mfg-batch-certificates")]
+use std::env;
+#[cfg(feature = "data-mapping")]
+use std::path::Path;
+#[cfg(feature = "mfg-batch-certificates")]
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::thread;
 
 pub use crate::rest_api::error::RestApiServerError;
 
+#[cfg(feature = "log-masking")]
+use actix_web::dev::Service;
 #[cfg(feature = "integration")]
 use actix_web::web;
 use actix_web::{dev, App, HttpServer, Result};
+#[cfg(feature = "api-keys")]
+use actix_web::{http::Method, middleware::Condition};
 use futures::executor::block_on;
+#[cfg(feature = "log-masking")]
+use futures::FutureExt;
+#[cfg(feature = "api-keys")]
+use grid_sdk::api_keys::Scope;
+#[cfg(feature = "data-mapping")]
+use grid_sdk::data_mapping::{load_data_mappings, DataMappingError};
+#[cfg(feature = "log-masking")]
+use grid_sdk::log_masking::LogMask;
+#[cfg(feature = "mfg-batch-certificates")]
+use grid_sdk::mfg_batch::certificate::CertificateTemplateDirectory;
+#[cfg(feature = "api-keys")]
+use grid_sdk::rest_api::actix_web_3::ApiKeyAuth;
+#[cfg(feature = "data-mapping")]
+use grid_sdk::rest_api::actix_web_3::DataMappingState;
 #[cfg(feature = "integration")]
 use grid_sdk::rest_api::actix_web_3::KeyState;
+#[cfg(feature = "mfg-batch")]
+use grid_sdk::rest_api::actix_web_3::MfgBatchState;
 use grid_sdk::rest_api::actix_web_3::{routes, BackendState, Endpoint, StoreState};
+#[cfg(feature = "sla-timers")]
+use grid_sdk::sla::SlaThresholds;
+
+#[cfg(feature = "mfg-batch")]
+use crate::config::GridConfig;
+#[cfg(feature = "mfg-batch")]
+use crate::database::SharedMfgBatchStore;
+#[cfg(feature = "mfg-batch")]
+use crate::error::DaemonError;
+
+#[cfg(feature = "mfg-batch-certificates")]
+const ENV_GRID_STATE_DIR: &str = "GRID_STATE_DIR";
+#[cfg(feature = "mfg-batch-certificates")]
+const DEFAULT_GRID_STATE_DIR: &str = "/var/lib/grid";
+
+/// Loads the data mappings in `mapping_dir`, or none if no directory is configured
+#[cfg(feature = "data-mapping")]
+pub fn load_data_mapping_state(
+    mapping_dir: Option<&str>,
+) -> Result<DataMappingState, DataMappingError> {
+    match mapping_dir {
+        Some(mapping_dir) => {
+            let mappings = load_data_mappings(Path::new(mapping_dir))?;
+            info!(
+                "Loaded {} data mappings from {}",
+                mappings.len(),
+                mapping_dir
+            );
+            Ok(DataMappingState::new(mappings))
+        }
+        None => Ok(DataMappingState::default()),
+    }
+}
+
+/// Creates the state the mfg_batch endpoints are served from; every actix worker shares `store`
+#[cfg(feature = "mfg-batch")]
+pub fn create_mfg_batch_state(
+    config: &GridConfig,
+    store: SharedMfgBatchStore,
+) -> Result<MfgBatchState, DaemonError> {
+    let state = MfgBatchState::new(store);
+
+    #[cfg(feature = "mfg-batch-certificates")]
+    let state = {
+        let dir = match config.certificate_template_dir() {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(
+                env::var(ENV_GRID_STATE_DIR).unwrap_or_else(|_| DEFAULT_GRID_STATE_DIR.to_string()),
+            )
+            .join("certificate_templates"),
+        };
+        info!("Certificate templates are kept in {}", dir.display());
+        state.with_certificate_templates(CertificateTemplateDirectory::new(dir))
+    };
+
+    #[cfg(feature = "mfg-batch-history")]
+    let state = match config.mfg_batch_max_response_size() {
+        Some(size) => state.with_max_response_size(size),
+        None => state,
+    };
+
+    Ok(state)
+}
 
 pub struct RestApiShutdownHandle {
     server: dev::Server,
@@ -38,12 +129,19 @@ impl RestApiShutdownHandle {
     }
 }
 
+// Most of the arguments are only taken with the features that serve them
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     bind_url: &str,
     store_state: StoreState,
     backend_state: BackendState,
     #[cfg(feature = "integration")] key_state: KeyState,
     endpoint: Endpoint,
+    #[cfg(feature = "api-keys")] require_api_keys: bool,
+    #[cfg(feature = "data-mapping")] data_mapping_state: DataMappingState,
+    #[cfg(feature = "mfg-batch")] mfg_batch_state: MfgBatchState,
+    #[cfg(feature = "log-masking")] log_mask: LogMask,
+    #[cfg(feature = "sla-timers")] sla_thresholds: SlaThresholds,
 ) -> Result<
     (
         RestApiShutdownHandle,
@@ -53,6 +151,8 @@ pub fn run(
 > {
     let bind_url = bind_url.to_owned();
     let (tx, rx) = mpsc::channel();
+    #[cfg(feature = "graphql")]
+    let graphql_schema = graphql::create_schema(mfg_batch_state.store.clone());
 
     let join_handle = thread::Builder::new()
         .name("GridRestApi".into())
@@ -60,9 +160,61 @@ pub fn run(
             let sys = actix::System::new("Grid-Rest-API");
 
             let addr = HttpServer::new(move || {
+                let app = App::new();
+
+                // Submitting batches, and checking on them, needs the mfg_batch:submit scope, and
+                // changing certificate templates the certificate_templates:write scope
+                #[cfg(feature = "api-keys")]
+                let app = {
+                    let auth = ApiKeyAuth::new(store_state.store_factory.clone())
+                        .with_rule(Method::POST, "/batches", Scope::MfgBatchSubmit)
+                        .with_rule(Method::GET, "/batch_statuses", Scope::MfgBatchSubmit)
+                        .with_rule(Method::POST, "/integration/submit", Scope::MfgBatchSubmit)
+                        .with_rule(Method::POST, "/integrations/", Scope::MfgBatchSubmit)
+                        .with_rule(
+                            Method::PUT,
+                            "/certificate_template",
+                            Scope::CertificateTemplatesWrite,
+                        )
+                        .with_rule(
+                            Method::DELETE,
+                            "/certificate_template",
+                            Scope::CertificateTemplatesWrite,
+                        );
+                    // Reading the API usage rollups needs the usage:read scope
+                    #[cfg(feature = "api-usage-analytics")]
+                    let auth = auth.with_rule(Method::GET, "/admin/usage", Scope::UsageRead);
+                    // Explaining store queries needs the query_plan:read scope
+                    #[cfg(feature = "mfg-batch-explain")]
+                    let auth =
+                        auth.with_rule(Method::GET, "/admin/explain", Scope::QueryPlanRead);
+                    // The SLA report needs the reports:read scope
+                    #[cfg(feature = "sla-timers")]
+                    let auth = auth.with_rule(Method::GET, "/admin/sla", Scope::ReportsRead);
+                    app.wrap(Condition::new(require_api_keys, auth))
+                };
+
+                // Each request is logged at debug level, with the values of masked properties in
+                // its query string replaced
+                #[cfg(feature = "log-masking")]
+                let app = {
+                    let log_mask = log_mask.clone();
+                    app.wrap_fn(move |req, srv| {
+                        let method = req.method().clone();
+                        let path = req.path().to_string();
+                        let query = log_mask.mask_query(req.query_string());
+                        srv.call(req).map(move |res| {
+                            if let Ok(res) = &res {
+                                debug!("{} {}?{} {}", method, path, query, res.status());
+                            }
+                            res
+                        })
+                    })
+                };
+
                 #[allow(clippy::let_and_return)]
                 #[allow(unused_mut)]
-                let mut app = App::new()
+                let mut app = app
                     .data(store_state.clone())
                     .data(backend_state.clone())
                     .app_data(endpoint.clone())
@@ -115,6 +267,103 @@ pub fn run(
                         .data(key_state.clone())
                         .service(web::scope("/integration").service(routes::submit));
                 }
+
+                #[cfg(feature = "data-mapping")]
+                {
+                    app = app
+                        .data(data_mapping_state.clone())
+                        .service(routes::submit_mapped);
+                }
+
+                #[cfg(feature = "mfg-batch")]
+                {
+                    app = app
+                        .data(mfg_batch_state.clone())
+                        .service(routes::list_mfg_batch_test_results);
+                }
+
+                #[cfg(feature = "graphql")]
+                {
+                    app = app
+                        .data(graphql_schema.clone())
+                        .service(graphql::graphql);
+                }
+
+                #[cfg(feature = "mfg-batch-annotations")]
+                {
+                    app = app
+                        .service(routes::add_mfg_batch_annotation)
+                        .service(routes::list_mfg_batch_property_annotations);
+                }
+
+                #[cfg(feature = "mfg-batch-certificates")]
+                {
+                    app = app
+                        .service(routes::get_mfg_batch_certificate)
+                        .service(routes::list_certificate_templates)
+                        .service(routes::get_certificate_template)
+                        .service(routes::put_certificate_template)
+                        .service(routes::delete_certificate_template);
+                }
+
+                #[cfg(feature = "mfg-batch-duplicates")]
+                {
+                    app = app.service(routes::list_mfg_batch_duplicates);
+                }
+
+                #[cfg(feature = "mfg-batch-epcis")]
+                {
+                    app = app.service(routes::get_mfg_batch_epcis);
+                }
+
+                #[cfg(feature = "mfg-batch-history")]
+                {
+                    app = app
+                        .service(routes::list_mfg_batch_history)
+                        .service(routes::list_mfg_batch_properties)
+                        .service(routes::get_property_value_history);
+                }
+
+                #[cfg(feature = "mfg-batch-list")]
+                {
+                    app = app.service(routes::list_mfg_batches);
+                }
+
+                #[cfg(feature = "mfg-batch-quality-scores")]
+                {
+                    app = app.service(routes::list_mfg_batch_quality_scores);
+                }
+
+                #[cfg(feature = "mfg-batch-recalls")]
+                {
+                    app = app.service(routes::list_mfg_batch_recalls);
+                }
+
+                #[cfg(feature = "mfg-batch-search")]
+                {
+                    app = app.service(routes::search_mfg_batches);
+                }
+
+                #[cfg(feature = "sla-timers")]
+                {
+                    app = app.data(sla_thresholds).service(routes::get_sla_report);
+                }
+
+                // The store's queries are only explained to holders of a key with the
+                // query_plan:read scope, so they are not served when requests need no keys
+                #[cfg(feature = "mfg-batch-explain")]
+                if require_api_keys {
+                    app = app
+                        .service(routes::explain_list_mfg_batches)
+                        .service(routes::explain_search_mfg_batches);
+                }
+
+                // API usage is only recorded, and its rollups only served, when requests need keys
+                #[cfg(feature = "api-usage-analytics")]
+                if require_api_keys {
+                    app = app.service(routes::list_api_usage);
+                }
+
                 #[cfg(feature = "purchase-order")]
                 {
                     app = app
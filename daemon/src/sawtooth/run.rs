@@ -23,6 +23,8 @@ use std::sync::{
 };
 
 use grid_sdk::backend::SawtoothBackendClient;
+#[cfg(feature = "log-masking")]
+use grid_sdk::log_masking::LogMask;
 #[cfg(feature = "rest-api")]
 use grid_sdk::rest_api::actix_web_3::Endpoint;
 #[cfg(feature = "integration")]
@@ -30,11 +32,25 @@ use grid_sdk::rest_api::actix_web_3::KeyState;
 use grid_sdk::rest_api::actix_web_3::{BackendState, StoreState};
 use grid_sdk::store::{create_store_factory, ConnectionUri};
 
+#[cfg(feature = "api-usage-analytics")]
+use crate::api_usage;
 use crate::config::GridConfig;
 use crate::database::ConnectionPool;
 use crate::error::DaemonError;
-use crate::event::{db_handler::DatabaseEventHandler, EventProcessor};
+#[cfg(feature = "event-queue")]
+use crate::event::queue::QueueingEventHandler;
+use crate::event::{db_handler::DatabaseEventHandler, EventHandler, EventProcessor};
+#[cfg(feature = "grpc")]
+use crate::grpc;
+#[cfg(feature = "ingestion")]
+use crate::ingestion;
+#[cfg(feature = "mfg-batch-anchors")]
+use crate::mfg_batch_anchors;
 use crate::rest_api;
+#[cfg(feature = "sla-timers")]
+use crate::sla;
+#[cfg(feature = "webhooks")]
+use crate::webhooks::WebhookEventHandler;
 
 use super::connection::SawtoothConnection;
 
@@ -57,12 +73,19 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
         "A database backend is required to be active. Supported backends are postgreSQL and SQLite",
     ));
 
+    // One mfg_batch store, and so one connection pool, is shared by the event handler, the EDI
+    // watcher, the mfg_batch endpoints and the gRPC server
+    #[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+    let mfg_batch_store = crate::database::create_shared_mfg_batch_store(&config)?;
+
     #[cfg(any(feature = "database-postgres", feature = "database-sqlite"))]
     let (store_state, evt_processor) = {
         let commit_store = store_factory.get_grid_commit_store();
         let current_commit = commit_store
             .get_current_commit_id()
             .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        #[cfg(feature = "webhooks")]
+        let webhook_handler = WebhookEventHandler::start(store_factory.clone_box())?;
 
         match connection_uri {
             #[cfg(feature = "database-postgres")]
@@ -72,13 +95,23 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
                         .map_err(|err| DaemonError::from_source(Box::new(err)))?;
                 let store_factory = create_store_factory(&connection_uri)
                     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
-                let event_handler = DatabaseEventHandler::new(store_factory);
-                let evt_processor = EventProcessor::start(
-                    sawtooth_connection,
-                    current_commit.as_deref(),
-                    event_handlers![event_handler],
-                )
-                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+                let event_handler = DatabaseEventHandler::new(store_factory.clone_box());
+                #[cfg(feature = "mfg-batch-anchors")]
+                let event_handler = event_handler.with_mfg_batch_store(mfg_batch_store.clone());
+                #[cfg(feature = "log-masking")]
+                let event_handler =
+                    event_handler.with_log_mask(LogMask::new(config.log_mask_properties()));
+                #[cfg(feature = "event-queue")]
+                let event_handler =
+                    QueueingEventHandler::new(Box::new(event_handler), store_factory);
+                #[cfg(feature = "webhooks")]
+                let handlers: Vec<Box<dyn EventHandler>> =
+                    event_handlers![webhook_handler, event_handler];
+                #[cfg(not(feature = "webhooks"))]
+                let handlers: Vec<Box<dyn EventHandler>> = event_handlers![event_handler];
+                let evt_processor =
+                    EventProcessor::start(sawtooth_connection, current_commit.as_deref(), handlers)
+                        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
 
                 (
                     StoreState::with_pg_pool(connection_pool.pool),
@@ -92,13 +125,23 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
                         .map_err(|err| DaemonError::from_source(Box::new(err)))?;
                 let store_factory = create_store_factory(&connection_uri)
                     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
-                let event_handler = DatabaseEventHandler::new(store_factory);
-                let evt_processor = EventProcessor::start(
-                    sawtooth_connection,
-                    current_commit.as_deref(),
-                    event_handlers![event_handler],
-                )
-                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+                let event_handler = DatabaseEventHandler::new(store_factory.clone_box());
+                #[cfg(feature = "mfg-batch-anchors")]
+                let event_handler = event_handler.with_mfg_batch_store(mfg_batch_store.clone());
+                #[cfg(feature = "log-masking")]
+                let event_handler =
+                    event_handler.with_log_mask(LogMask::new(config.log_mask_properties()));
+                #[cfg(feature = "event-queue")]
+                let event_handler =
+                    QueueingEventHandler::new(Box::new(event_handler), store_factory);
+                #[cfg(feature = "webhooks")]
+                let handlers: Vec<Box<dyn EventHandler>> =
+                    event_handlers![webhook_handler, event_handler];
+                #[cfg(not(feature = "webhooks"))]
+                let handlers: Vec<Box<dyn EventHandler>> = event_handlers![event_handler];
+                let evt_processor =
+                    EventProcessor::start(sawtooth_connection, current_commit.as_deref(), handlers)
+                        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
 
                 (
                     StoreState::with_sqlite_pool(connection_pool.pool),
@@ -111,6 +154,56 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
     #[cfg(feature = "integration")]
     let key_state = KeyState::new(config.key_file_name());
 
+    #[cfg(feature = "data-mapping")]
+    let data_mapping_state = rest_api::load_data_mapping_state(config.mapping_dir())
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    #[cfg(feature = "data-mapping-edi")]
+    let data_mapping_state = data_mapping_state.with_mfg_batch_store(mfg_batch_store.clone());
+
+    #[cfg(feature = "ingestion")]
+    let (ingestion_shutdown_handle, ingestion_join_handle) = match ingestion::run_from_config(
+        &config,
+        &data_mapping_state,
+        store_state.store_factory.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    let (anchor_shutdown_handle, anchor_join_handle) = match mfg_batch_anchors::run_from_config(
+        &config,
+        store_state.store_factory.clone(),
+        mfg_batch_store.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "sla-timers")]
+    let (sla_shutdown_handle, sla_join_handle) = match sla::run_from_config(
+        &config,
+        store_state.store_factory.clone(),
+        mfg_batch_store.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "api-usage-analytics")]
+    let (usage_retention_shutdown_handle, usage_retention_join_handle) =
+        match api_usage::run_from_config(&config, store_state.store_factory.clone())? {
+            Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+            None => (None, None),
+        };
+
+    #[cfg(all(feature = "grpc", feature = "api-keys"))]
+    let api_key_store_factory = if config.require_api_keys() {
+        Some(store_state.store_factory.clone())
+    } else {
+        None
+    };
+
     #[cfg(feature = "rest-api")]
     let (rest_api_shutdown_handle, rest_api_join_handle) = rest_api::run(
         config.rest_api_endpoint(),
@@ -119,9 +212,36 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
         #[cfg(feature = "integration")]
         key_state,
         sawtooth_endpoint,
+        #[cfg(feature = "api-keys")]
+        config.require_api_keys(),
+        #[cfg(feature = "data-mapping")]
+        data_mapping_state,
+        #[cfg(feature = "mfg-batch")]
+        rest_api::create_mfg_batch_state(&config, mfg_batch_store.clone())?,
+        #[cfg(feature = "log-masking")]
+        LogMask::new(config.log_mask_properties()),
+        #[cfg(feature = "sla-timers")]
+        sla::thresholds_from_config(&config),
     )
     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
 
+    #[cfg(feature = "grpc")]
+    let (grpc_shutdown_handle, grpc_join_handle) = match config.grpc_endpoint() {
+        Some(grpc_endpoint) => {
+            let (shutdown_handle, join_handle) = grpc::run(
+                grpc_endpoint,
+                mfg_batch_store.clone(),
+                #[cfg(feature = "grpc-pseudonyms")]
+                grpc::PseudonymSettings::from_config(&config)?,
+                #[cfg(feature = "api-keys")]
+                api_key_store_factory,
+            )
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            (Some(shutdown_handle), Some(join_handle))
+        }
+        None => (None, None),
+    };
+
     let (event_processor_shutdown_handle, event_processor_join_handle) =
         evt_processor.take_shutdown_controls();
 
@@ -137,6 +257,31 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
         #[cfg(feature = "rest-api")]
         rest_api_shutdown_handle.shutdown();
 
+        #[cfg(feature = "grpc")]
+        if let Some(grpc_shutdown_handle) = &grpc_shutdown_handle {
+            grpc_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "ingestion")]
+        if let Some(ingestion_shutdown_handle) = &ingestion_shutdown_handle {
+            ingestion_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "mfg-batch-anchors")]
+        if let Some(anchor_shutdown_handle) = &anchor_shutdown_handle {
+            anchor_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "sla-timers")]
+        if let Some(sla_shutdown_handle) = &sla_shutdown_handle {
+            sla_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "api-usage-analytics")]
+        if let Some(usage_retention_shutdown_handle) = &usage_retention_shutdown_handle {
+            usage_retention_shutdown_handle.shutdown();
+        }
+
         if let Err(err) = event_processor_shutdown_handle.shutdown() {
             error!("Unable to gracefully shutdown Event Processor: {}", err);
         }
@@ -149,6 +294,42 @@ pub fn run_sawtooth(config: GridConfig) -> Result<(), DaemonError> {
         .map_err(|_| DaemonError::with_message("Unable to cleanly join the REST API thread"))
         .and_then(|res| res.map_err(|err| DaemonError::from_source(Box::new(err))))?;
 
+    #[cfg(feature = "ingestion")]
+    if let Some(ingestion_join_handle) = ingestion_join_handle {
+        ingestion_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the ingestion thread")
+        })?;
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    if let Some(anchor_join_handle) = anchor_join_handle {
+        anchor_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the anchoring thread")
+        })?;
+    }
+
+    #[cfg(feature = "sla-timers")]
+    if let Some(sla_join_handle) = sla_join_handle {
+        sla_join_handle
+            .join()
+            .map_err(|_| DaemonError::with_message("Unable to cleanly join the SLA thread"))?;
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    if let Some(usage_retention_join_handle) = usage_retention_join_handle {
+        usage_retention_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the usage retention thread")
+        })?;
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_join_handle) = grpc_join_handle {
+        grpc_join_handle
+            .join()
+            .map_err(|_| DaemonError::with_message("Unable to cleanly join the gRPC thread"))
+            .and_then(|res| res.map_err(|err| DaemonError::from_source(Box::new(err))))?;
+    }
+
     event_processor_join_handle
         .join()
         .map_err(|_| DaemonError::with_message("Unable to cleanly join the event processor"))
@@ -0,0 +1,175 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs the system's `sftp` client in batch mode against a directory named by an
+//! `sftp://[user@]host[:port]/path` URL. Batch mode never prompts, so the server must accept the
+//! daemon's SSH key or agent.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use url::Url;
+
+use crate::error::DaemonError;
+
+/// A directory on an SFTP server
+pub struct SftpLocation {
+    url: String,
+    /// `[user@]host`, as the `sftp` client takes it
+    destination: String,
+    port: Option<u16>,
+    dir: String,
+}
+
+impl SftpLocation {
+    /// Reads a `sftp://[user@]host[:port]/path` URL
+    pub fn from_url(location: &str) -> Result<Self, DaemonError> {
+        let url = Url::parse(location).map_err(|err| {
+            DaemonError::with_message(&format!("Invalid SFTP URL {}: {}", location, err))
+        })?;
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| {
+                DaemonError::with_message(&format!("SFTP URL {} has no host", location))
+            })?;
+        let destination = if url.username().is_empty() {
+            host.to_string()
+        } else {
+            format!("{}@{}", url.username(), host)
+        };
+        let dir = match url.path().trim_end_matches('/') {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        };
+
+        Ok(SftpLocation {
+            url: location.to_string(),
+            destination,
+            port: url.port(),
+            dir,
+        })
+    }
+
+    /// Returns the URL the location was read from
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the path on the server of a file in the directory
+    pub fn remote_path(&self, name: &str) -> String {
+        format!("{}/{}", self.dir.trim_end_matches('/'), name)
+    }
+
+    /// Lists the names of the entries in the directory
+    pub fn list(&self) -> Result<Vec<String>, DaemonError> {
+        let listing = self.run(&format!("ls -1 {}\n", quote(&self.dir)))?;
+        Ok(listed_names(&listing))
+    }
+
+    /// Runs `sftp` with the given batch commands and returns what it printed
+    pub fn run(&self, commands: &str) -> Result<String, DaemonError> {
+        let mut command = Command::new("sftp");
+        command.args(["-q", "-o", "BatchMode=yes", "-b", "-"]);
+        if let Some(port) = self.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        let mut child = command
+            .arg(&self.destination)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(commands.as_bytes())
+                .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+        if !output.status.success() {
+            return Err(DaemonError::with_message(&format!(
+                "sftp to {} failed: {}",
+                self.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Quotes an argument to an `sftp` batch command
+pub fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Reads the names out of `ls -1` output, which is echoed after the command and may give each
+/// name with the listed directory in front
+fn listed_names(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("sftp>"))
+        .map(|line| line.rsplit('/').next().unwrap_or(line).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the user, host, port and directory are read from an SFTP URL
+    #[test]
+    fn test_sftp_location_from_url() {
+        let location = SftpLocation::from_url("sftp://grid@erp.example.com:2222/outbound/asn/")
+            .expect("Failed to read URL");
+        assert_eq!(location.destination, "grid@erp.example.com");
+        assert_eq!(location.port, Some(2222));
+        assert_eq!(location.dir, "/outbound/asn");
+        assert_eq!(location.remote_path("a.edi"), "/outbound/asn/a.edi");
+
+        let location =
+            SftpLocation::from_url("sftp://erp.example.com").expect("Failed to read URL");
+        assert_eq!(location.destination, "erp.example.com");
+        assert_eq!(location.port, None);
+        assert_eq!(location.remote_path("a.edi"), "/a.edi");
+
+        assert!(SftpLocation::from_url("sftp:///outbound").is_err());
+    }
+
+    /// Verify that names are read from `ls -1` output, with the echoed command and the
+    /// directory in front of each name left out
+    #[test]
+    fn test_listed_names() {
+        let listing = "sftp> ls -1 \"/outbound\"\n\
+            /outbound/b.csv\n\
+            /outbound/a.edi\n\
+            /outbound/processed\n\
+            \n";
+
+        assert_eq!(listed_names(listing), vec!["b.csv", "a.edi", "processed"]);
+    }
+
+    /// Verify that quotes and backslashes in batch command arguments are escaped
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("my \"dir\""), "\"my \\\"dir\\\"\"");
+        assert_eq!(quote("a\\b"), "\"a\\\\b\"");
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escalates the transfer proposals and recall holds that have been pending past their
+//! thresholds.
+//!
+//! On each interval, the SLA service lists the open proposals and active recalls, and notifies
+//! the webhooks registered for each overdue item's service with an `sla.escalated` event. An item
+//! is escalated once while it stays pending; the items escalated are only kept in memory, so the
+//! items still overdue when the daemon restarts are escalated again.
+
+use std::collections::HashSet;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::Sender,
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use grid_sdk::sla::{list_pending_items, PendingItem, SlaThresholds, ESCALATION_EVENT};
+use grid_sdk::store::TransactionalStoreFactory;
+use serde_json::{json, Value};
+
+use crate::config::GridConfig;
+use crate::database::SharedMfgBatchStore;
+use crate::error::DaemonError;
+use crate::webhooks::{start_delivery_thread, Delivery};
+
+/// How long a transfer proposal may stay open, unless set otherwise: a day
+const DEFAULT_PROPOSAL_THRESHOLD: u64 = 24 * 60 * 60;
+/// How long a batch may be held by a recall, unless set otherwise: a week
+const DEFAULT_HOLD_THRESHOLD: u64 = 7 * 24 * 60 * 60;
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct SlaShutdownHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl SlaShutdownHandle {
+    pub fn shutdown(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// What the service needs to escalate overdue items
+pub struct SlaSettings {
+    pub interval: Duration,
+    pub thresholds: SlaThresholds,
+    pub service_id: Option<String>,
+}
+
+/// Returns the configured thresholds, which the SLA report is measured against too
+pub fn thresholds_from_config(config: &GridConfig) -> SlaThresholds {
+    SlaThresholds {
+        proposal: config
+            .sla_proposal_threshold()
+            .unwrap_or(DEFAULT_PROPOSAL_THRESHOLD) as i64,
+        hold: config
+            .sla_hold_threshold()
+            .unwrap_or(DEFAULT_HOLD_THRESHOLD) as i64,
+    }
+}
+
+/// Starts the service if the configuration gives an SLA interval
+pub fn run_from_config(
+    config: &GridConfig,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    mfg_batch_store: SharedMfgBatchStore,
+) -> Result<Option<(SlaShutdownHandle, thread::JoinHandle<()>)>, DaemonError> {
+    let interval = match config.sla_interval() {
+        Some(interval) => interval,
+        None => return Ok(None),
+    };
+
+    run(
+        SlaSettings {
+            interval: Duration::from_secs(interval),
+            thresholds: thresholds_from_config(config),
+            service_id: config.sla_service_id().map(ToOwned::to_owned),
+        },
+        store_factory,
+        mfg_batch_store,
+    )
+    .map(Some)
+}
+
+pub fn run(
+    settings: SlaSettings,
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    mfg_batch_store: SharedMfgBatchStore,
+) -> Result<(SlaShutdownHandle, thread::JoinHandle<()>), DaemonError> {
+    let sender = start_delivery_thread()?;
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let join_handle = thread::Builder::new()
+        .name("SlaTimers".into())
+        .spawn(move || {
+            info!(
+                "Checking pending proposals and holds every {} seconds",
+                settings.interval.as_secs()
+            );
+            let mut escalations = Escalations::default();
+            while thread_running.load(Ordering::SeqCst) {
+                if let Err(err) = escalate(
+                    &settings,
+                    &*store_factory,
+                    &mfg_batch_store,
+                    &sender,
+                    &mut escalations,
+                ) {
+                    error!("Unable to escalate overdue proposals and holds: {}", err);
+                }
+
+                let mut waited = Duration::from_secs(0);
+                while waited < settings.interval && thread_running.load(Ordering::SeqCst) {
+                    thread::sleep(SHUTDOWN_CHECK_INTERVAL);
+                    waited += SHUTDOWN_CHECK_INTERVAL;
+                }
+            }
+        })
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok((SlaShutdownHandle { running }, join_handle))
+}
+
+/// Notifies the webhooks of each overdue item not escalated yet
+fn escalate(
+    settings: &SlaSettings,
+    store_factory: &dyn TransactionalStoreFactory,
+    mfg_batch_store: &SharedMfgBatchStore,
+    sender: &Sender<Delivery>,
+    escalations: &mut Escalations,
+) -> Result<(), DaemonError> {
+    let items = list_pending_items(
+        &*store_factory.get_grid_track_and_trace_store(),
+        &**mfg_batch_store,
+        settings.service_id.as_deref(),
+        &settings.thresholds,
+        now(),
+    )
+    .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    let due = escalations.due(&items);
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let webhooks = store_factory
+        .get_webhook_store()
+        .list_webhooks()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    for item in due {
+        warn!(
+            "{} {} ({}) has been pending for {} seconds, past its threshold of {} seconds",
+            item.kind, item.subject_id, item.reference, item.pending_for, item.threshold
+        );
+
+        let body = notification(item).to_string().into_bytes();
+        for webhook in webhooks
+            .iter()
+            .filter(|webhook| webhook.matches_service(item.service_id.as_deref()))
+        {
+            sender
+                .send(Delivery {
+                    url: webhook.url.clone(),
+                    secret: webhook.secret.clone(),
+                    event: ESCALATION_EVENT,
+                    body: body.clone(),
+                })
+                .map_err(|_| {
+                    DaemonError::with_message("Unable to queue SLA escalation; channel closed")
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn notification(item: &PendingItem) -> Value {
+    json!({
+        "event": ESCALATION_EVENT,
+        "kind": item.kind.as_str(),
+        "subject_id": item.subject_id,
+        "reference": item.reference,
+        "pending_since": item.pending_since,
+        "pending_for": item.pending_for,
+        "threshold": item.threshold,
+        "service_id": item.service_id,
+    })
+}
+
+/// The keys of the overdue items already escalated
+#[derive(Default)]
+struct Escalations {
+    escalated: HashSet<String>,
+}
+
+impl Escalations {
+    /// Returns the overdue items not escalated yet, noting them as escalated, and forgets the
+    /// items escalated before that are no longer pending
+    fn due<'a>(&mut self, items: &'a [PendingItem]) -> Vec<&'a PendingItem> {
+        let pending = items.iter().map(PendingItem::key).collect::<HashSet<_>>();
+        self.escalated.retain(|key| pending.contains(key));
+
+        items
+            .iter()
+            .filter(|item| item.is_overdue() && self.escalated.insert(item.key()))
+            .collect()
+    }
+}
+
+/// Returns the time in seconds since the epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid_sdk::sla::PendingKind;
+
+    fn item(recall_id: &str, pending_for: i64) -> PendingItem {
+        PendingItem {
+            kind: PendingKind::Hold,
+            subject_id: "lot-1".to_string(),
+            reference: recall_id.to_string(),
+            pending_since: 0,
+            pending_for,
+            threshold: 100,
+            service_id: None,
+        }
+    }
+
+    /// Verify that an overdue item is escalated once while it stays pending, and again if it
+    /// turns up after it was no longer pending
+    #[test]
+    fn test_escalations_due() {
+        let mut escalations = Escalations::default();
+
+        assert!(escalations.due(&[item("recall-1", 50)]).is_empty());
+        assert_eq!(
+            escalations.due(&[item("recall-1", 150), item("recall-2", 50)]),
+            vec![&item("recall-1", 150)]
+        );
+        assert_eq!(
+            escalations.due(&[item("recall-1", 250), item("recall-2", 150)]),
+            vec![&item("recall-2", 150)]
+        );
+        assert!(escalations.due(&[item("recall-2", 250)]).is_empty());
+        assert_eq!(
+            escalations.due(&[item("recall-1", 350), item("recall-2", 350)]),
+            vec![&item("recall-1", 350)]
+        );
+    }
+}
@@ -30,6 +30,8 @@ use grid_sdk::backend::SplinterBackendClient;
 use grid_sdk::commits::store::Commit;
 use grid_sdk::commits::{CommitStore, DieselCommitStore};
 use grid_sdk::error::InvalidStateError;
+#[cfg(feature = "log-masking")]
+use grid_sdk::log_masking::LogMask;
 #[cfg(feature = "rest-api")]
 use grid_sdk::rest_api::actix_web_3::Endpoint;
 #[cfg(feature = "integration")]
@@ -38,11 +40,25 @@ use grid_sdk::rest_api::actix_web_3::{BackendState, StoreState};
 use grid_sdk::store::{create_store_factory, ConnectionUri};
 use splinter::events::Reactor;
 
+#[cfg(feature = "api-usage-analytics")]
+use crate::api_usage;
 use crate::config::GridConfig;
 use crate::database::ConnectionPool;
 use crate::error::DaemonError;
+#[cfg(feature = "event-queue")]
+use crate::event::queue::QueueingEventHandler;
 use crate::event::{db_handler::DatabaseEventHandler, CommitEvent, EventError, EventHandler};
+#[cfg(feature = "grpc")]
+use crate::grpc;
+#[cfg(feature = "ingestion")]
+use crate::ingestion;
+#[cfg(feature = "mfg-batch-anchors")]
+use crate::mfg_batch_anchors;
 use crate::rest_api;
+#[cfg(feature = "sla-timers")]
+use crate::sla;
+#[cfg(feature = "webhooks")]
+use crate::webhooks::WebhookEventHandler;
 
 use super::{
     app_auth_handler, event::processors::EventProcessors, event::ScabbardEventConnectionFactory,
@@ -99,6 +115,11 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
         "A database backend is required to be active. Supported backends are postgreSQL and SQLite",
     ));
 
+    // One mfg_batch store, and so one connection pool, is shared by the event handler, the EDI
+    // watcher, the mfg_batch endpoints and the gRPC server
+    #[cfg(any(feature = "data-mapping-edi", feature = "grpc", feature = "mfg-batch"))]
+    let mfg_batch_store = crate::database::create_shared_mfg_batch_store(&config)?;
+
     #[cfg(any(feature = "database-postgres", feature = "database-sqlite"))]
     let (store_state, db_handler, previous_commits): (_, Box<dyn EventHandler>, Vec<Commit>) = {
         let connection_uri = config
@@ -114,7 +135,15 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
                         .map_err(|err| DaemonError::from_source(Box::new(err)))?;
                 let store_factory = create_store_factory(&connection_uri)
                     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
-                let event_handler = DatabaseEventHandler::new(store_factory);
+                let event_handler = DatabaseEventHandler::new(store_factory.clone_box());
+                #[cfg(feature = "mfg-batch-anchors")]
+                let event_handler = event_handler.with_mfg_batch_store(mfg_batch_store.clone());
+                #[cfg(feature = "log-masking")]
+                let event_handler =
+                    event_handler.with_log_mask(LogMask::new(config.log_mask_properties()));
+                #[cfg(feature = "event-queue")]
+                let event_handler =
+                    QueueingEventHandler::new(Box::new(event_handler), store_factory);
 
                 let commit_store = DieselCommitStore::new(connection_pool.pool.clone());
                 let commits = commit_store
@@ -134,7 +163,15 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
                         .map_err(|err| DaemonError::from_source(Box::new(err)))?;
                 let store_factory = create_store_factory(&connection_uri)
                     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
-                let event_handler = DatabaseEventHandler::new(store_factory);
+                let event_handler = DatabaseEventHandler::new(store_factory.clone_box());
+                #[cfg(feature = "mfg-batch-anchors")]
+                let event_handler = event_handler.with_mfg_batch_store(mfg_batch_store.clone());
+                #[cfg(feature = "log-masking")]
+                let event_handler =
+                    event_handler.with_log_mask(LogMask::new(config.log_mask_properties()));
+                #[cfg(feature = "event-queue")]
+                let event_handler =
+                    QueueingEventHandler::new(Box::new(event_handler), store_factory);
 
                 let commit_store = DieselCommitStore::new(connection_pool.pool.clone());
                 let commits = commit_store
@@ -150,6 +187,17 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
         }
     };
 
+    #[cfg(feature = "webhooks")]
+    let webhook_handler = {
+        let connection_uri = config
+            .database_url()
+            .parse()
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        let store_factory = create_store_factory(&connection_uri)
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+        WebhookEventHandler::start(store_factory)?
+    };
+
     let (event_tx, event_rx) = std::sync::mpsc::channel();
     let chan_event_handler: Box<dyn EventHandler> = Box::new(ChannelEventHandler {
         sender: event_tx.clone(),
@@ -160,6 +208,10 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
         .spawn(move || loop {
             match event_rx.recv() {
                 Ok(EventCmd::Event(evt)) => {
+                    #[cfg(feature = "webhooks")]
+                    if let Err(err) = webhook_handler.handle_event(&evt) {
+                        error!("{}", err);
+                    }
                     if let Err(err) = db_handler.handle_event(&evt) {
                         error!("{}", err.to_string());
                     }
@@ -216,6 +268,56 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
     #[cfg(feature = "integration")]
     let key_state = KeyState::new(config.key_file_name());
 
+    #[cfg(feature = "data-mapping")]
+    let data_mapping_state = rest_api::load_data_mapping_state(config.mapping_dir())
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    #[cfg(feature = "data-mapping-edi")]
+    let data_mapping_state = data_mapping_state.with_mfg_batch_store(mfg_batch_store.clone());
+
+    #[cfg(feature = "ingestion")]
+    let (ingestion_shutdown_handle, ingestion_join_handle) = match ingestion::run_from_config(
+        &config,
+        &data_mapping_state,
+        store_state.store_factory.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    let (anchor_shutdown_handle, anchor_join_handle) = match mfg_batch_anchors::run_from_config(
+        &config,
+        store_state.store_factory.clone(),
+        mfg_batch_store.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "sla-timers")]
+    let (sla_shutdown_handle, sla_join_handle) = match sla::run_from_config(
+        &config,
+        store_state.store_factory.clone(),
+        mfg_batch_store.clone(),
+    )? {
+        Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+        None => (None, None),
+    };
+
+    #[cfg(feature = "api-usage-analytics")]
+    let (usage_retention_shutdown_handle, usage_retention_join_handle) =
+        match api_usage::run_from_config(&config, store_state.store_factory.clone())? {
+            Some((shutdown_handle, join_handle)) => (Some(shutdown_handle), Some(join_handle)),
+            None => (None, None),
+        };
+
+    #[cfg(all(feature = "grpc", feature = "api-keys"))]
+    let api_key_store_factory = if config.require_api_keys() {
+        Some(store_state.store_factory.clone())
+    } else {
+        None
+    };
+
     #[cfg(feature = "rest-api")]
     let (rest_api_shutdown_handle, rest_api_join_handle) = rest_api::run(
         config.rest_api_endpoint(),
@@ -224,9 +326,36 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
         #[cfg(feature = "integration")]
         key_state,
         splinter_endpoint,
+        #[cfg(feature = "api-keys")]
+        config.require_api_keys(),
+        #[cfg(feature = "data-mapping")]
+        data_mapping_state,
+        #[cfg(feature = "mfg-batch")]
+        rest_api::create_mfg_batch_state(&config, mfg_batch_store.clone())?,
+        #[cfg(feature = "log-masking")]
+        LogMask::new(config.log_mask_properties()),
+        #[cfg(feature = "sla-timers")]
+        sla::thresholds_from_config(&config),
     )
     .map_err(|err| DaemonError::from_source(Box::new(err)))?;
 
+    #[cfg(feature = "grpc")]
+    let (grpc_shutdown_handle, grpc_join_handle) = match config.grpc_endpoint() {
+        Some(grpc_endpoint) => {
+            let (shutdown_handle, join_handle) = grpc::run(
+                grpc_endpoint,
+                mfg_batch_store.clone(),
+                #[cfg(feature = "grpc-pseudonyms")]
+                grpc::PseudonymSettings::from_config(&config)?,
+                #[cfg(feature = "api-keys")]
+                api_key_store_factory,
+            )
+            .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+            (Some(shutdown_handle), Some(join_handle))
+        }
+        None => (None, None),
+    };
+
     let reactor_shutdown_signaler = reactor.shutdown_signaler();
 
     let ctrlc_triggered = AtomicBool::new(false);
@@ -247,6 +376,31 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
 
         #[cfg(feature = "rest-api")]
         rest_api_shutdown_handle.shutdown();
+
+        #[cfg(feature = "grpc")]
+        if let Some(grpc_shutdown_handle) = &grpc_shutdown_handle {
+            grpc_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "ingestion")]
+        if let Some(ingestion_shutdown_handle) = &ingestion_shutdown_handle {
+            ingestion_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "mfg-batch-anchors")]
+        if let Some(anchor_shutdown_handle) = &anchor_shutdown_handle {
+            anchor_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "sla-timers")]
+        if let Some(sla_shutdown_handle) = &sla_shutdown_handle {
+            sla_shutdown_handle.shutdown();
+        }
+
+        #[cfg(feature = "api-usage-analytics")]
+        if let Some(usage_retention_shutdown_handle) = &usage_retention_shutdown_handle {
+            usage_retention_shutdown_handle.shutdown();
+        }
         if let Err(err) = event_tx.send(EventCmd::Exit) {
             error!(
                 "Unable to signal shutdown to the DB event handler thread: {}",
@@ -262,6 +416,42 @@ pub fn run_splinter(config: GridConfig) -> Result<(), DaemonError> {
         .map_err(|_| DaemonError::with_message("Unable to cleanly join the REST API thread"))
         .and_then(|res| res.map_err(|err| DaemonError::from_source(Box::new(err))))?;
 
+    #[cfg(feature = "ingestion")]
+    if let Some(ingestion_join_handle) = ingestion_join_handle {
+        ingestion_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the ingestion thread")
+        })?;
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    if let Some(anchor_join_handle) = anchor_join_handle {
+        anchor_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the anchoring thread")
+        })?;
+    }
+
+    #[cfg(feature = "sla-timers")]
+    if let Some(sla_join_handle) = sla_join_handle {
+        sla_join_handle
+            .join()
+            .map_err(|_| DaemonError::with_message("Unable to cleanly join the SLA thread"))?;
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    if let Some(usage_retention_join_handle) = usage_retention_join_handle {
+        usage_retention_join_handle.join().map_err(|_| {
+            DaemonError::with_message("Unable to cleanly join the usage retention thread")
+        })?;
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_join_handle) = grpc_join_handle {
+        grpc_join_handle
+            .join()
+            .map_err(|_| DaemonError::with_message("Unable to cleanly join the gRPC thread"))
+            .and_then(|res| res.map_err(|err| DaemonError::from_source(Box::new(err))))?;
+    }
+
     if db_event_handler_join_handler.join().is_err() {
         error!("Unable to cleanly join the DB event handler thread");
     }
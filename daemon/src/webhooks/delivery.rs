@@ -0,0 +1,169 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Posts notifications to webhooks from a thread of its own, so a slow or unreachable receiver
+//! does not hold up event handling. Failed deliveries are retried with exponential backoff.
+
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use grid_sdk::webhooks::{sign, EVENT_HEADER, SIGNATURE_HEADER};
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::error::DaemonError;
+
+/// How many times a notification is posted before it is dropped
+const MAX_ATTEMPTS: u32 = 6;
+/// The wait before the first retry, doubled for each retry after it
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A notification to post to a webhook
+#[derive(Clone, Debug)]
+pub struct Delivery {
+    pub url: String,
+    pub secret: String,
+    /// The event named in the `X-Grid-Event` header, such as `mfg_batch.created`
+    pub event: &'static str,
+    pub body: Vec<u8>,
+}
+
+struct PendingDelivery {
+    delivery: Delivery,
+    attempts: u32,
+    due: Instant,
+}
+
+/// Starts the delivery thread. It runs until every sender is dropped; retries still pending
+/// then are abandoned.
+pub fn start_delivery_thread() -> Result<Sender<Delivery>, DaemonError> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let (sender, receiver) = channel();
+
+    thread::Builder::new()
+        .name("WebhookDelivery".into())
+        .spawn(move || deliver(&client, receiver))
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    Ok(sender)
+}
+
+fn deliver(client: &Client, receiver: Receiver<Delivery>) {
+    let mut pending: Vec<PendingDelivery> = vec![];
+
+    loop {
+        let received = match pending.iter().map(|retry| retry.due).min() {
+            Some(due) => receiver.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok(delivery) => attempt(client, delivery, 0, &mut pending),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => {
+                if !pending.is_empty() {
+                    warn!(
+                        "Abandoning {} webhook notification(s) awaiting retry",
+                        pending.len()
+                    );
+                }
+                break;
+            }
+        }
+
+        let now = Instant::now();
+        let (due, waiting) = pending.into_iter().partition(|retry| retry.due <= now);
+        pending = waiting;
+        for retry in due {
+            attempt(client, retry.delivery, retry.attempts, &mut pending);
+        }
+    }
+}
+
+/// Posts a notification, queueing a retry if it fails and attempts remain
+fn attempt(
+    client: &Client,
+    delivery: Delivery,
+    previous_attempts: u32,
+    pending: &mut Vec<PendingDelivery>,
+) {
+    let attempts = previous_attempts + 1;
+    let result = client
+        .post(&delivery.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, delivery.event)
+        .header(SIGNATURE_HEADER, sign(&delivery.secret, &delivery.body))
+        .body(delivery.body.clone())
+        .send()
+        .map_err(|err| err.to_string())
+        .and_then(|response| {
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(format!("received status {}", response.status()))
+            }
+        });
+
+    match result {
+        Ok(()) => debug!("Notified {} of {}", delivery.url, delivery.event),
+        Err(err) if attempts < MAX_ATTEMPTS => {
+            let backoff = backoff(attempts);
+            warn!(
+                "Unable to notify {} of {} ({}); retrying in {}s",
+                delivery.url,
+                delivery.event,
+                err,
+                backoff.as_secs()
+            );
+            pending.push(PendingDelivery {
+                delivery,
+                attempts,
+                due: Instant::now() + backoff,
+            });
+        }
+        Err(err) => error!(
+            "Unable to notify {} of {} after {} attempts; giving up: {}",
+            delivery.url, delivery.event, attempts, err
+        ),
+    }
+}
+
+/// Returns the wait before the retry that follows the given number of attempts
+fn backoff(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .checked_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .map(|backoff| backoff.min(MAX_BACKOFF))
+        .unwrap_or(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the wait doubles after each attempt until it reaches the maximum
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(2), Duration::from_secs(4));
+        assert_eq!(backoff(5), Duration::from_secs(32));
+        assert_eq!(backoff(9), MAX_BACKOFF);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::mpsc::Sender;
+
+use grid_sdk::commits::store::CommitEvent as DbCommitEvent;
+use grid_sdk::mfg_batch::addressing::GRID_MFG_BATCH_NAMESPACE;
+use grid_sdk::protocol::mfg_batch::state::{MfgBatch, MfgBatchList};
+use grid_sdk::protos::FromBytes;
+use grid_sdk::store::TransactionalStoreFactory;
+use grid_sdk::webhooks::{
+    store::{Webhook, WebhookStore},
+    MfgBatchEvent,
+};
+use serde_json::{json, Value};
+
+use crate::error::DaemonError;
+use crate::event::{CommitEvent, EventError, EventHandler, StateChange};
+
+use super::delivery::{start_delivery_thread, Delivery};
+
+/// Tells apart the mfg_batches created, updated and deleted by each commit and queues a
+/// notification of each change for every webhook registered for the commit's service.
+///
+/// State only holds the mfg_batches now at an address, so the ids last seen at each address are
+/// kept in the database to compare against. Commits that were already handled, such as the
+/// last one redelivered when the daemon reconnects, are skipped; the handler must therefore run
+/// before the `DatabaseEventHandler` records the commit.
+pub struct WebhookEventHandler {
+    store_factory: Box<dyn TransactionalStoreFactory>,
+    sender: Sender<Delivery>,
+}
+
+impl WebhookEventHandler {
+    /// Creates a handler and starts the thread that delivers its notifications
+    pub fn start(store_factory: Box<dyn TransactionalStoreFactory>) -> Result<Self, DaemonError> {
+        Ok(Self {
+            store_factory,
+            sender: start_delivery_thread()?,
+        })
+    }
+
+    fn is_duplicate(&self, event: &CommitEvent) -> Result<bool, EventError> {
+        let commit_store = self.store_factory.get_grid_commit_store();
+        let commit =
+            match commit_store.create_db_commit_from_commit_event(&DbCommitEvent::from(event))? {
+                Some(commit) => commit,
+                None => return Ok(false),
+            };
+
+        Ok(commit_store
+            .get_commit_by_commit_num(commit.commit_num)?
+            .map(|existing| existing.commit_id == commit.commit_id)
+            .unwrap_or(false))
+    }
+}
+
+impl EventHandler for WebhookEventHandler {
+    fn handle_event(&self, event: &CommitEvent) -> Result<(), EventError> {
+        let state_changes = event
+            .state_changes
+            .iter()
+            .filter(|state_change| state_change.key_has_prefix(GRID_MFG_BATCH_NAMESPACE))
+            .collect::<Vec<_>>();
+        if state_changes.is_empty() || self.is_duplicate(event)? {
+            return Ok(());
+        }
+
+        let txn = self
+            .store_factory
+            .begin_transaction()
+            .map_err(|err| EventError(format!("Unable to start database transaction: {}", err)))?;
+
+        let try_handle_event = || {
+            let store = txn.get_webhook_store();
+            let mut changes = vec![];
+            for state_change in state_changes {
+                changes.extend(diff_state_change(&*store, state_change, event)?);
+            }
+
+            let webhooks = store
+                .list_webhooks()?
+                .into_iter()
+                .filter(|webhook| webhook.matches_service(event.service_id.as_deref()))
+                .collect::<Vec<_>>();
+
+            Ok((changes, webhooks))
+                as Result<(Vec<(MfgBatchEvent, Value)>, Vec<Webhook>), EventError>
+        };
+
+        let (changes, webhooks) = match try_handle_event() {
+            Ok(result) => {
+                txn.commit().map_err(|err| EventError(err.to_string()))?;
+                result
+            }
+            Err(err) => {
+                if let Err(e) = txn.rollback() {
+                    error!("Rollback failed: {}", e);
+                }
+                return Err(err);
+            }
+        };
+
+        for (mfg_batch_event, body) in changes {
+            let body = body.to_string().into_bytes();
+            for webhook in &webhooks {
+                self.sender
+                    .send(Delivery {
+                        url: webhook.url.clone(),
+                        secret: webhook.secret.clone(),
+                        event: mfg_batch_event.as_str(),
+                        body: body.clone(),
+                    })
+                    .map_err(|_| {
+                        EventError("Unable to queue webhook notification; channel closed".into())
+                    })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn cloned_box(&self) -> Box<dyn EventHandler> {
+        Box::new(Self {
+            store_factory: self.store_factory.clone_box(),
+            sender: self.sender.clone(),
+        })
+    }
+}
+
+/// Compares the mfg_batches a state change leaves at its address with those last seen there,
+/// returning the notification body of each change, and records the ones now there
+fn diff_state_change(
+    store: &dyn WebhookStore,
+    state_change: &StateChange,
+    event: &CommitEvent,
+) -> Result<Vec<(MfgBatchEvent, Value)>, EventError> {
+    let service_id = event.service_id.as_deref();
+    let (address, mfg_batches) = match state_change {
+        StateChange::Set { key, value } => (
+            key,
+            MfgBatchList::from_bytes(value)
+                .map_err(|err| EventError(format!("Failed to parse mfg_batch list {}", err)))?
+                .mfg_batches()
+                .to_vec(),
+        ),
+        StateChange::Delete { key } => (key, vec![]),
+    };
+
+    let seen = store.list_seen_mfg_batch_ids(address, service_id)?;
+    let mut changes = vec![];
+    for mfg_batch in &mfg_batches {
+        let mfg_batch_event = if seen.iter().any(|id| id == mfg_batch.mfg_batch_id()) {
+            MfgBatchEvent::Updated
+        } else {
+            MfgBatchEvent::Created
+        };
+        changes.push((
+            mfg_batch_event,
+            notification(
+                mfg_batch_event,
+                mfg_batch.mfg_batch_id(),
+                Some(mfg_batch),
+                event,
+            )?,
+        ));
+    }
+    for mfg_batch_id in &seen {
+        if !mfg_batches
+            .iter()
+            .any(|mfg_batch| mfg_batch.mfg_batch_id() == mfg_batch_id)
+        {
+            changes.push((
+                MfgBatchEvent::Deleted,
+                notification(MfgBatchEvent::Deleted, mfg_batch_id, None, event)?,
+            ));
+        }
+    }
+
+    let mfg_batch_ids = mfg_batches
+        .iter()
+        .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string())
+        .collect::<Vec<_>>();
+    store.set_seen_mfg_batch_ids(address, service_id, &mfg_batch_ids)?;
+
+    Ok(changes)
+}
+
+fn notification(
+    mfg_batch_event: MfgBatchEvent,
+    mfg_batch_id: &str,
+    mfg_batch: Option<&MfgBatch>,
+    event: &CommitEvent,
+) -> Result<Value, EventError> {
+    let mfg_batch = mfg_batch
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|err| EventError(format!("Unable to write mfg_batch as JSON: {}", err)))?;
+
+    Ok(json!({
+        "event": mfg_batch_event.as_str(),
+        "commit_id": event.id,
+        "service_id": event.service_id,
+        "mfg_batch_id": mfg_batch_id,
+        "mfg_batch": mfg_batch,
+    }))
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use grid_sdk::migrations::run_sqlite_migrations;
+    use grid_sdk::protocol::mfg_batch::state::{
+        MfgBatchBuilder, MfgBatchListBuilder, MfgBatchNamespace,
+    };
+    use grid_sdk::protos::IntoBytes;
+    use grid_sdk::webhooks::store::DieselWebhookStore;
+
+    const ADDRESS: &str = "11bb0e0100000000000000000000000000000000000000000000000000000000000000";
+
+    fn set(mfg_batch_ids: &[&str]) -> StateChange {
+        let mfg_batches = mfg_batch_ids
+            .iter()
+            .map(|mfg_batch_id| {
+                MfgBatchBuilder::new()
+                    .with_mfg_batch_id(mfg_batch_id.to_string())
+                    .with_owner("cgl".to_string())
+                    .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                    .with_properties(vec![])
+                    .build()
+                    .expect("Failed to build mfg_batch")
+            })
+            .collect();
+        let value = MfgBatchListBuilder::new()
+            .with_mfg_batches(mfg_batches)
+            .build()
+            .expect("Failed to build mfg_batch list")
+            .into_bytes()
+            .expect("Failed to serialize mfg_batch list");
+
+        StateChange::Set {
+            key: ADDRESS.to_string(),
+            value,
+        }
+    }
+
+    fn events(store: &dyn WebhookStore, state_change: StateChange) -> Vec<(MfgBatchEvent, String)> {
+        let event = CommitEvent {
+            service_id: None,
+            id: "commit".to_string(),
+            height: Some(1),
+            state_changes: vec![state_change.clone()],
+        };
+        diff_state_change(store, &state_change, &event)
+            .expect("Failed to diff state change")
+            .into_iter()
+            .map(|(mfg_batch_event, body)| {
+                (
+                    mfg_batch_event,
+                    body["mfg_batch_id"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Verify that mfg_batches not seen at an address are created, those seen before are
+    /// updated, and those no longer there, or at a deleted address, are deleted
+    #[test]
+    fn test_diff_state_change() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        let store = DieselWebhookStore::new(pool);
+
+        assert_eq!(
+            events(&store, set(&["lot-1"])),
+            vec![(MfgBatchEvent::Created, "lot-1".to_string())]
+        );
+        assert_eq!(
+            events(&store, set(&["lot-1", "lot-2"])),
+            vec![
+                (MfgBatchEvent::Updated, "lot-1".to_string()),
+                (MfgBatchEvent::Created, "lot-2".to_string()),
+            ]
+        );
+        assert_eq!(
+            events(&store, set(&["lot-2"])),
+            vec![
+                (MfgBatchEvent::Updated, "lot-2".to_string()),
+                (MfgBatchEvent::Deleted, "lot-1".to_string()),
+            ]
+        );
+        assert_eq!(
+            events(
+                &store,
+                StateChange::Delete {
+                    key: ADDRESS.to_string()
+                }
+            ),
+            vec![(MfgBatchEvent::Deleted, "lot-2".to_string())]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Notifies webhooks of the mfg_batches created, updated and deleted by each commit, and
+//! administers the webhooks registered with the daemon. The SLA timers post their escalations
+//! with the same retrying delivery.
+
+mod delivery;
+mod handler;
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use grid_sdk::store::{create_store_factory, ConnectionUri};
+use grid_sdk::webhooks::store::{Webhook, WebhookStore};
+use rand::RngCore;
+
+use crate::error::DaemonError;
+
+#[cfg(feature = "sla-timers")]
+pub use delivery::{start_delivery_thread, Delivery};
+pub use handler::WebhookEventHandler;
+
+const WEBHOOK_ID_LENGTH: usize = 8;
+const SECRET_LENGTH: usize = 32;
+
+/// Runs the `webhook` subcommand against the database at `database_url`
+pub fn run_webhook_command(
+    database_url: &str,
+    matches: &ArgMatches,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let connection_uri = database_url
+        .parse::<ConnectionUri>()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store_factory = create_store_factory(&connection_uri)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    let store = store_factory.get_webhook_store();
+
+    match matches.subcommand() {
+        ("add", Some(m)) => add_webhook(
+            &*store,
+            m.value_of("url").unwrap_or_default(),
+            m.value_of("service_id"),
+            out,
+        ),
+        ("remove", Some(m)) => {
+            remove_webhook(&*store, m.value_of("webhook_id").unwrap_or_default())
+        }
+        ("list", Some(_)) => list_webhooks(&*store, out),
+        _ => Err(DaemonError::with_message(
+            "A webhook subcommand is required",
+        )),
+    }
+}
+
+/// Registers `url` as a webhook and writes its id and the secret notifications are signed with
+pub fn add_webhook(
+    store: &dyn WebhookStore,
+    url: &str,
+    service_id: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), DaemonError> {
+    let parsed = url::Url::parse(url).map_err(|err| {
+        DaemonError::with_message(&format!("Invalid webhook URL {}: {}", url, err))
+    })?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(DaemonError::with_message(&format!(
+            "Webhook URLs must be http or https: {}",
+            url
+        )));
+    }
+
+    let webhook = Webhook {
+        webhook_id: random_hex(WEBHOOK_ID_LENGTH),
+        url: url.to_string(),
+        secret: random_hex(SECRET_LENGTH),
+        service_id: service_id.map(String::from),
+        created_at: now(),
+    };
+    store
+        .add_webhook(webhook.clone())
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    writeln!(out, "{}\t{}", webhook.webhook_id, webhook.secret)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Removes a webhook; it is not notified of commits handled from then on
+pub fn remove_webhook(store: &dyn WebhookStore, webhook_id: &str) -> Result<(), DaemonError> {
+    store
+        .remove_webhook(webhook_id)
+        .map_err(|err| DaemonError::from_source(Box::new(err)))
+}
+
+/// Writes the registered webhooks, one per line. Secrets are not written.
+pub fn list_webhooks(store: &dyn WebhookStore, out: &mut dyn Write) -> Result<(), DaemonError> {
+    let webhooks = store
+        .list_webhooks()
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+
+    for webhook in webhooks {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}",
+            webhook.webhook_id,
+            webhook.url,
+            webhook.service_id.as_deref().unwrap_or("-"),
+            webhook.created_at,
+        )
+        .map_err(|err| DaemonError::from_source(Box::new(err)))?;
+    }
+
+    Ok(())
+}
+
+fn random_hex(length: usize) -> String {
+    let mut bytes = vec![0; length];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "database-sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use grid_sdk::migrations::run_sqlite_migrations;
+    use grid_sdk::webhooks::store::DieselWebhookStore;
+
+    /// Verify that an added webhook is listed without its secret, that URLs other than http and
+    /// https are refused, and that a removed webhook is no longer listed
+    #[test]
+    fn test_add_list_remove() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build pool");
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection"))
+            .expect("Failed to run migrations");
+        let store = DieselWebhookStore::new(pool);
+
+        let mut out = vec![];
+        add_webhook(&store, "https://erp.example.com/grid", None, &mut out)
+            .expect("Failed to add webhook");
+        let out = String::from_utf8(out).expect("Output is not UTF-8");
+        let (webhook_id, secret) = out.trim().split_once('\t').expect("Malformed output");
+        assert_eq!(secret.len(), SECRET_LENGTH * 2);
+
+        assert!(add_webhook(&store, "ftp://erp.example.com/grid", None, &mut vec![]).is_err());
+
+        let mut out = vec![];
+        list_webhooks(&store, &mut out).expect("Failed to list webhooks");
+        let out = String::from_utf8(out).expect("Output is not UTF-8");
+        let fields = out.trim().split('\t').collect::<Vec<_>>();
+        assert_eq!(
+            &fields[..3],
+            &[webhook_id, "https://erp.example.com/grid", "-"]
+        );
+        assert!(!out.contains(secret));
+
+        remove_webhook(&store, webhook_id).expect("Failed to remove webhook");
+        let mut out = vec![];
+        list_webhooks(&store, &mut out).expect("Failed to list webhooks");
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Daily rollups of the requests let through with API keys, so that consortium operators can
+//! charge organizations back for their use of the APIs and spot keys used out of the ordinary.
+//!
+//! Each request adds to the rollup of its key, method, endpoint and UTC day: the number of
+//! requests, the rows their responses listed and the submissions they made. The rollups and the
+//! per-request key-usage log are kept for as long as the retention policy says.
+
+use super::store::{ApiKeyStore, ApiKeyStoreError};
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Returns the start of the UTC day the time falls in. Times are seconds since the epoch.
+pub fn day_start(time: i64) -> i64 {
+    time - time.rem_euclid(SECONDS_PER_DAY)
+}
+
+/// A request let through with an API key, as it is added to its rollup
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiUsageEvent {
+    pub key_id: String,
+    /// The organization the key was issued to
+    pub org_id: String,
+    pub method: String,
+    /// The path prefix of the rule the request matched, rather than its path, so that requests
+    /// for different records of the same endpoint are rolled up together
+    pub endpoint: String,
+    /// The number of rows the response listed
+    pub rows_returned: i64,
+    /// Whether the request made a submission
+    pub submission: bool,
+    pub used_at: i64,
+}
+
+/// The use of an endpoint with an API key over one UTC day
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiUsageRollup {
+    /// The start of the day, in seconds since the epoch
+    pub day: i64,
+    pub key_id: String,
+    pub org_id: String,
+    pub method: String,
+    pub endpoint: String,
+    pub requests: i64,
+    pub rows_returned: i64,
+    pub submissions: i64,
+}
+
+/// Narrows the rollups listed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ApiUsageRollupFilters {
+    pub org_id: Option<String>,
+    pub key_id: Option<String>,
+    /// Only list the days starting at or after this time
+    pub since: Option<i64>,
+    /// Only list the days starting before this time
+    pub until: Option<i64>,
+}
+
+/// How many days of API usage are kept. Usage older than a policy's days is removed when it is
+/// applied; usage without a number of days is kept indefinitely.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageRetentionPolicy {
+    usage_log_days: Option<u32>,
+    rollup_days: Option<u32>,
+}
+
+impl UsageRetentionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the per-request key-usage log for this many days
+    pub fn with_usage_log_days(mut self, days: u32) -> Self {
+        self.usage_log_days = Some(days);
+        self
+    }
+
+    /// Keeps the daily rollups for this many days, counting the current day
+    pub fn with_rollup_days(mut self, days: u32) -> Self {
+        self.rollup_days = Some(days);
+        self
+    }
+
+    /// Returns true if the policy keeps all usage
+    pub fn is_unlimited(&self) -> bool {
+        self.usage_log_days.is_none() && self.rollup_days.is_none()
+    }
+
+    /// Removes the usage the policy no longer keeps, returning the number of key-usage log
+    /// entries and of rollups removed
+    ///
+    /// # Arguments
+    ///
+    ///  * `store` - The store the usage is kept in
+    ///  * `now` - The current time, in seconds since the epoch
+    pub fn apply(
+        &self,
+        store: &dyn ApiKeyStore,
+        now: i64,
+    ) -> Result<(usize, usize), ApiKeyStoreError> {
+        let usage_removed = match self.usage_log_cutoff(now) {
+            Some(cutoff) => store.remove_api_key_usage_before(cutoff)?,
+            None => 0,
+        };
+        let rollups_removed = match self.rollup_cutoff(now) {
+            Some(cutoff) => store.remove_api_usage_rollups_before(cutoff)?,
+            None => 0,
+        };
+
+        Ok((usage_removed, rollups_removed))
+    }
+
+    /// Returns the time key-usage log entries are kept from
+    fn usage_log_cutoff(&self, now: i64) -> Option<i64> {
+        self.usage_log_days
+            .map(|days| now - i64::from(days) * SECONDS_PER_DAY)
+    }
+
+    /// Returns the first day rollups are kept for
+    fn rollup_cutoff(&self, now: i64) -> Option<i64> {
+        self.rollup_days
+            .map(|days| day_start(now) - (i64::from(days) - 1) * SECONDS_PER_DAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that times are rounded down to the start of their UTC day
+    #[test]
+    fn test_day_start() {
+        assert_eq!(day_start(0), 0);
+        assert_eq!(day_start(SECONDS_PER_DAY - 1), 0);
+        assert_eq!(day_start(SECONDS_PER_DAY), SECONDS_PER_DAY);
+        assert_eq!(day_start(-1), -SECONDS_PER_DAY);
+    }
+
+    /// Verify that the log is kept for whole days back from now, that rollups are kept for the
+    /// current day and the days before it, and that usage without a number of days is kept
+    #[test]
+    fn test_retention_cutoffs() {
+        let now = 10 * SECONDS_PER_DAY + 3600;
+        let policy = UsageRetentionPolicy::new()
+            .with_usage_log_days(2)
+            .with_rollup_days(3);
+
+        assert_eq!(
+            policy.usage_log_cutoff(now),
+            Some(8 * SECONDS_PER_DAY + 3600)
+        );
+        assert_eq!(policy.rollup_cutoff(now), Some(8 * SECONDS_PER_DAY));
+        assert!(!policy.is_unlimited());
+
+        let policy = UsageRetentionPolicy::new();
+        assert_eq!(policy.usage_log_cutoff(now), None);
+        assert_eq!(policy.rollup_cutoff(now), None);
+        assert!(policy.is_unlimited());
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! API keys let clients of the daemon's APIs authenticate without a Splinter or Sawtooth
+//! identity. A key belongs to an organization and is granted a set of scopes; only a hash of its
+//! secret is stored.
+//!
+//! Clients present a key as a token of the form `<key_id>.<secret>`.
+
+#[cfg(feature = "api-usage-analytics")]
+pub mod analytics;
+pub mod store;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
+use crate::error::InvalidArgumentError;
+
+use self::store::{ApiKey, ApiKeyStore, ApiKeyStoreError, ApiKeyUsage};
+
+/// An operation an API key can be granted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Read mfg_batches
+    MfgBatchRead,
+    /// Submit batches of mfg_batch transactions and check their status
+    MfgBatchSubmit,
+    /// Read reports
+    ReportsRead,
+    /// Store and delete the templates mfg_batch certificates are printed from
+    CertificateTemplatesWrite,
+    /// Read the daily rollups of API usage
+    UsageRead,
+    /// Read the SQL store queries run and the database's plans for them
+    QueryPlanRead,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::MfgBatchRead => "mfg_batch:read",
+            Scope::MfgBatchSubmit => "mfg_batch:submit",
+            Scope::ReportsRead => "reports:read",
+            Scope::CertificateTemplatesWrite => "certificate_templates:write",
+            Scope::UsageRead => "usage:read",
+            Scope::QueryPlanRead => "query_plan:read",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mfg_batch:read" => Ok(Scope::MfgBatchRead),
+            "mfg_batch:submit" => Ok(Scope::MfgBatchSubmit),
+            "reports:read" => Ok(Scope::ReportsRead),
+            "certificate_templates:write" => Ok(Scope::CertificateTemplatesWrite),
+            "usage:read" => Ok(Scope::UsageRead),
+            "query_plan:read" => Ok(Scope::QueryPlanRead),
+            _ => Err(InvalidArgumentError::new(
+                "scope".to_string(),
+                format!("Unknown API key scope: {}", s),
+            )),
+        }
+    }
+}
+
+/// The parts of a token presented by a client
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKeyToken {
+    pub key_id: String,
+    pub secret: String,
+}
+
+impl ApiKeyToken {
+    pub fn new(key_id: String, secret: String) -> Self {
+        Self { key_id, secret }
+    }
+
+    /// Returns the hash of the secret, as it is stored with the key
+    pub fn secret_hash(&self) -> String {
+        hash_secret(&self.secret)
+    }
+}
+
+impl fmt::Display for ApiKeyToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.key_id, self.secret)
+    }
+}
+
+impl FromStr for ApiKeyToken {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('.') {
+            Some((key_id, secret)) if !key_id.is_empty() && !secret.is_empty() => {
+                Ok(ApiKeyToken::new(key_id.to_string(), secret.to_string()))
+            }
+            _ => Err(InvalidArgumentError::new(
+                "token".to_string(),
+                "API key tokens have the form <key_id>.<secret>".to_string(),
+            )),
+        }
+    }
+}
+
+/// Why a request was refused by `authorize_request`
+#[derive(Debug)]
+pub enum AuthorizeError {
+    /// No token was given, or it does not name an unrevoked key with a matching secret
+    Unauthenticated,
+    /// The key does not have the scope
+    MissingScope(Scope),
+    StoreError(ApiKeyStoreError),
+}
+
+impl Error for AuthorizeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AuthorizeError::StoreError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuthorizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthorizeError::Unauthenticated => f.write_str("A valid API key is required"),
+            AuthorizeError::MissingScope(scope) => {
+                write!(f, "The API key does not have the {} scope", scope)
+            }
+            AuthorizeError::StoreError(err) => err.fmt(f),
+        }
+    }
+}
+
+/// Checks that `token` names an unrevoked key with `scope` and returns the key. Each request
+/// naming a stored key is recorded in the key-usage log, whether it is authorized or not.
+///
+/// # Arguments
+///
+///  * `store` - The store the keys and their usage are kept in
+///  * `token` - The token the request presented, if any
+///  * `scope` - The scope the request requires
+///  * `method` - The request's method, recorded in the usage log
+///  * `path` - The request's path, recorded in the usage log
+pub fn authorize_request(
+    store: &dyn ApiKeyStore,
+    token: Option<&str>,
+    scope: Scope,
+    method: &str,
+    path: &str,
+) -> Result<ApiKey, AuthorizeError> {
+    let token = token
+        .and_then(|token| token.parse::<ApiKeyToken>().ok())
+        .ok_or(AuthorizeError::Unauthenticated)?;
+
+    let api_key = store
+        .get_api_key(&token.key_id)
+        .map_err(AuthorizeError::StoreError)?
+        .ok_or(AuthorizeError::Unauthenticated)?;
+
+    let valid = !api_key.is_revoked() && api_key.secret_hash == token.secret_hash();
+    let allowed = valid && api_key.has_scope(scope);
+
+    store
+        .add_api_key_usage(ApiKeyUsage {
+            key_id: api_key.key_id.clone(),
+            scope,
+            method: method.to_string(),
+            path: path.to_string(),
+            allowed,
+            used_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+        })
+        .map_err(AuthorizeError::StoreError)?;
+
+    if !valid {
+        Err(AuthorizeError::Unauthenticated)
+    } else if !allowed {
+        Err(AuthorizeError::MissingScope(scope))
+    } else {
+        Ok(api_key)
+    }
+}
+
+/// Hashes an API key secret with SHA-256
+pub fn hash_secret(secret: &str) -> String {
+    let mut sha = Sha256::new();
+    sha.input_str(secret);
+    sha.result_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that scopes round trip through their string form and unknown scopes are rejected
+    #[test]
+    fn test_scope_from_str() {
+        for scope in &[
+            Scope::MfgBatchRead,
+            Scope::MfgBatchSubmit,
+            Scope::ReportsRead,
+            Scope::CertificateTemplatesWrite,
+            Scope::UsageRead,
+            Scope::QueryPlanRead,
+        ] {
+            assert_eq!(scope.as_str().parse::<Scope>().unwrap(), *scope);
+        }
+
+        assert!("mfg_batch:write".parse::<Scope>().is_err());
+    }
+
+    /// Verify that tokens are split on the first `.` and that both parts are required
+    #[test]
+    fn test_token_from_str() {
+        let token: ApiKeyToken = "abc.def.ghi".parse().unwrap();
+        assert_eq!(token.key_id, "abc");
+        assert_eq!(token.secret, "def.ghi");
+        assert_eq!(token.to_string(), "abc.def.ghi");
+
+        assert!("abc".parse::<ApiKeyToken>().is_err());
+        assert!(".def".parse::<ApiKeyToken>().is_err());
+        assert!("abc.".parse::<ApiKeyToken>().is_err());
+    }
+}
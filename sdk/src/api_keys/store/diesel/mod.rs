@@ -0,0 +1,591 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod models;
+mod operations;
+pub(crate) mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{ApiKey, ApiKeyStore, ApiKeyStoreError, ApiKeyUsage};
+#[cfg(feature = "api-usage-analytics")]
+use crate::api_keys::analytics::{ApiUsageEvent, ApiUsageRollup, ApiUsageRollupFilters};
+use crate::error::ResourceTemporarilyUnavailableError;
+
+use operations::add_api_key::AddApiKeyOperation as _;
+use operations::add_api_key_usage::AddApiKeyUsageOperation as _;
+#[cfg(feature = "api-usage-analytics")]
+use operations::add_api_usage::AddApiUsageOperation as _;
+use operations::get_api_key::GetApiKeyOperation as _;
+use operations::list_api_key_usage::ListApiKeyUsageOperation as _;
+use operations::list_api_keys::ListApiKeysOperation as _;
+#[cfg(feature = "api-usage-analytics")]
+use operations::list_api_usage_rollups::ListApiUsageRollupsOperation as _;
+#[cfg(feature = "api-usage-analytics")]
+use operations::remove_api_usage::RemoveApiUsageOperation as _;
+use operations::revoke_api_key::RevokeApiKeyOperation as _;
+use operations::ApiKeyStoreOperations;
+
+#[derive(Clone)]
+pub struct DieselApiKeyStore<C: diesel::Connection + 'static> {
+    connection_pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection> DieselApiKeyStore<C> {
+    pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselApiKeyStore { connection_pool }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl ApiKeyStore for DieselApiKeyStore<diesel::pg::PgConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_key(api_key)
+    }
+
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_api_key(key_id)
+    }
+
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_keys(org_id)
+    }
+
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .revoke_api_key(key_id, revoked_at)
+    }
+
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_key_usage(usage)
+    }
+
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_key_usage(key_id, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_api_key_usage_before(used_at)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_usage(event)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_usage_rollups(filters, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_api_usage_rollups_before(day)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ApiKeyStore for DieselApiKeyStore<diesel::sqlite::SqliteConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_key(api_key)
+    }
+
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_api_key(key_id)
+    }
+
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_keys(org_id)
+    }
+
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .revoke_api_key(key_id, revoked_at)
+    }
+
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_key_usage(usage)
+    }
+
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_key_usage(key_id, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_api_key_usage_before(used_at)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_api_usage(event)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_api_usage_rollups(filters, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_api_usage_rollups_before(day)
+    }
+}
+
+pub struct DieselConnectionApiKeyStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+}
+
+impl<'a, C> DieselConnectionApiKeyStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionApiKeyStore { connection }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ApiKeyStore for DieselConnectionApiKeyStore<'a, diesel::pg::PgConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_key(api_key)
+    }
+
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).get_api_key(key_id)
+    }
+
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_keys(org_id)
+    }
+
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).revoke_api_key(key_id, revoked_at)
+    }
+
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_key_usage(usage)
+    }
+
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_key_usage(key_id, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).remove_api_key_usage_before(used_at)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_usage(event)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_usage_rollups(filters, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).remove_api_usage_rollups_before(day)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ApiKeyStore for DieselConnectionApiKeyStore<'a, diesel::sqlite::SqliteConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_key(api_key)
+    }
+
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).get_api_key(key_id)
+    }
+
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_keys(org_id)
+    }
+
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).revoke_api_key(key_id, revoked_at)
+    }
+
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_key_usage(usage)
+    }
+
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_key_usage(key_id, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).remove_api_key_usage_before(used_at)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).add_api_usage(event)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).list_api_usage_rollups(filters, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        ApiKeyStoreOperations::new(self.connection).remove_api_usage_rollups_before(day)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+    use diesel::Connection;
+
+    use crate::api_keys::Scope;
+    use crate::migrations::run_sqlite_migrations;
+
+    fn api_key(key_id: &str, org_id: &str, created_at: i64) -> ApiKey {
+        ApiKey {
+            key_id: key_id.to_string(),
+            org_id: org_id.to_string(),
+            secret_hash: format!("hash-{}", key_id),
+            scopes: vec![Scope::MfgBatchRead, Scope::ReportsRead],
+            created_at,
+            revoked_at: None,
+        }
+    }
+
+    fn usage(key_id: &str, allowed: bool, used_at: i64) -> ApiKeyUsage {
+        ApiKeyUsage {
+            key_id: key_id.to_string(),
+            scope: Scope::MfgBatchRead,
+            method: "GET".to_string(),
+            path: "/mfg_batch".to_string(),
+            allowed,
+            used_at,
+        }
+    }
+
+    /// Verify that keys are stored with their scopes, listed by organization and revoked once
+    #[test]
+    fn test_api_key_lifecycle() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionApiKeyStore::new(&conn);
+
+        store.add_api_key(api_key("key-1", "org-1", 10)).unwrap();
+        store.add_api_key(api_key("key-2", "org-2", 20)).unwrap();
+        store.add_api_key(api_key("key-3", "org-1", 30)).unwrap();
+        assert!(matches!(
+            store.add_api_key(api_key("key-1", "org-1", 40)),
+            Err(ApiKeyStoreError::ConstraintViolationError(_))
+        ));
+
+        assert_eq!(
+            store.get_api_key("key-1").unwrap(),
+            Some(api_key("key-1", "org-1", 10))
+        );
+        assert_eq!(store.get_api_key("missing").unwrap(), None);
+
+        let org_keys = store.list_api_keys(Some("org-1")).unwrap();
+        assert_eq!(
+            org_keys
+                .iter()
+                .map(|key| key.key_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["key-1", "key-3"]
+        );
+        assert_eq!(store.list_api_keys(None).unwrap().len(), 3);
+
+        store.revoke_api_key("key-1", 50).unwrap();
+        store.revoke_api_key("key-1", 60).unwrap();
+        assert_eq!(
+            store.get_api_key("key-1").unwrap().unwrap().revoked_at,
+            Some(50)
+        );
+        assert!(matches!(
+            store.revoke_api_key("missing", 50),
+            Err(ApiKeyStoreError::NotFoundError(_))
+        ));
+    }
+
+    /// Verify that uses are listed most recent first and can be filtered by key
+    #[test]
+    fn test_api_key_usage() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionApiKeyStore::new(&conn);
+
+        store.add_api_key(api_key("key-1", "org-1", 10)).unwrap();
+        store.add_api_key(api_key("key-2", "org-1", 10)).unwrap();
+        store.add_api_key_usage(usage("key-1", true, 11)).unwrap();
+        store.add_api_key_usage(usage("key-2", false, 12)).unwrap();
+        store.add_api_key_usage(usage("key-1", false, 13)).unwrap();
+
+        assert_eq!(
+            store.list_api_key_usage(Some("key-1"), 0, 10).unwrap(),
+            vec![usage("key-1", false, 13), usage("key-1", true, 11)]
+        );
+        assert_eq!(
+            store.list_api_key_usage(None, 1, 1).unwrap(),
+            vec![usage("key-2", false, 12)]
+        );
+    }
+
+    /// Verify that events of the same key, method, endpoint and day are rolled up together,
+    /// that rollups are filtered by organization and day, and that the retention policy removes
+    /// the days and key-usage log entries it no longer keeps
+    #[cfg(feature = "api-usage-analytics")]
+    #[test]
+    fn test_api_usage_rollups() {
+        use crate::api_keys::analytics::{
+            ApiUsageRollup, ApiUsageRollupFilters, UsageRetentionPolicy, SECONDS_PER_DAY,
+        };
+
+        fn event(key_id: &str, org_id: &str, rows: i64, used_at: i64) -> ApiUsageEvent {
+            ApiUsageEvent {
+                key_id: key_id.to_string(),
+                org_id: org_id.to_string(),
+                method: "POST".to_string(),
+                endpoint: "/batches".to_string(),
+                rows_returned: rows,
+                submission: rows == 0,
+                used_at,
+            }
+        }
+
+        fn rollup(key_id: &str, org_id: &str, day: i64, counts: (i64, i64, i64)) -> ApiUsageRollup {
+            ApiUsageRollup {
+                day,
+                key_id: key_id.to_string(),
+                org_id: org_id.to_string(),
+                method: "POST".to_string(),
+                endpoint: "/batches".to_string(),
+                requests: counts.0,
+                rows_returned: counts.1,
+                submissions: counts.2,
+            }
+        }
+
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionApiKeyStore::new(&conn);
+
+        let day = SECONDS_PER_DAY;
+        store.add_api_key(api_key("key-1", "org-1", 0)).unwrap();
+        store.add_api_key(api_key("key-2", "org-2", 0)).unwrap();
+        store.add_api_usage(event("key-1", "org-1", 0, 10)).unwrap();
+        store.add_api_usage(event("key-1", "org-1", 4, 20)).unwrap();
+        store
+            .add_api_usage(event("key-1", "org-1", 0, day + 10))
+            .unwrap();
+        store
+            .add_api_usage(event("key-2", "org-2", 2, day + 20))
+            .unwrap();
+        store.add_api_key_usage(usage("key-1", true, 10)).unwrap();
+        store
+            .add_api_key_usage(usage("key-1", true, day + 10))
+            .unwrap();
+
+        assert_eq!(
+            store
+                .list_api_usage_rollups(&ApiUsageRollupFilters::default(), 0, 10)
+                .unwrap(),
+            vec![
+                rollup("key-1", "org-1", day, (1, 0, 1)),
+                rollup("key-2", "org-2", day, (1, 2, 0)),
+                rollup("key-1", "org-1", 0, (2, 4, 1)),
+            ]
+        );
+        assert_eq!(
+            store
+                .list_api_usage_rollups(
+                    &ApiUsageRollupFilters {
+                        org_id: Some("org-1".to_string()),
+                        until: Some(day),
+                        ..Default::default()
+                    },
+                    0,
+                    10
+                )
+                .unwrap(),
+            vec![rollup("key-1", "org-1", 0, (2, 4, 1))]
+        );
+
+        let removed = UsageRetentionPolicy::new()
+            .with_usage_log_days(1)
+            .with_rollup_days(1)
+            .apply(&store, day + 30)
+            .unwrap();
+        assert_eq!(removed, (1, 1));
+        assert_eq!(
+            store
+                .list_api_usage_rollups(&ApiUsageRollupFilters::default(), 0, 10)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(store.list_api_key_usage(None, 0, 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+#[cfg(feature = "api-usage-analytics")]
+use crate::api_keys::analytics::{day_start, ApiUsageEvent, ApiUsageRollup};
+use crate::api_keys::{
+    store::{diesel::schema::*, ApiKey, ApiKeyStoreError, ApiKeyUsage},
+    Scope,
+};
+use crate::error::InternalError;
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "api_keys"]
+pub struct ApiKeyModel {
+    pub key_id: String,
+    pub org_id: String,
+    pub secret_hash: String,
+    /// The key's scopes, separated by spaces
+    pub scopes: String,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "api_key_usage"]
+pub struct NewApiKeyUsageModel {
+    pub key_id: String,
+    pub scope: String,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+    pub used_at: i64,
+}
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct ApiKeyUsageModel {
+    pub id: i64,
+    pub key_id: String,
+    pub scope: String,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+    pub used_at: i64,
+}
+
+fn parse_scope(scope: &str) -> Result<Scope, ApiKeyStoreError> {
+    scope
+        .parse()
+        .map_err(|err| ApiKeyStoreError::InternalError(InternalError::from_source(Box::new(err))))
+}
+
+impl From<ApiKey> for ApiKeyModel {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            key_id: api_key.key_id,
+            org_id: api_key.org_id,
+            secret_hash: api_key.secret_hash,
+            scopes: api_key
+                .scopes
+                .iter()
+                .map(Scope::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+            created_at: api_key.created_at,
+            revoked_at: api_key.revoked_at,
+        }
+    }
+}
+
+impl TryFrom<ApiKeyModel> for ApiKey {
+    type Error = ApiKeyStoreError;
+
+    fn try_from(model: ApiKeyModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scopes: model
+                .scopes
+                .split_whitespace()
+                .map(parse_scope)
+                .collect::<Result<_, _>>()?,
+            key_id: model.key_id,
+            org_id: model.org_id,
+            secret_hash: model.secret_hash,
+            created_at: model.created_at,
+            revoked_at: model.revoked_at,
+        })
+    }
+}
+
+impl From<ApiKeyUsage> for NewApiKeyUsageModel {
+    fn from(usage: ApiKeyUsage) -> Self {
+        Self {
+            key_id: usage.key_id,
+            scope: usage.scope.to_string(),
+            method: usage.method,
+            path: usage.path,
+            allowed: usage.allowed,
+            used_at: usage.used_at,
+        }
+    }
+}
+
+impl TryFrom<ApiKeyUsageModel> for ApiKeyUsage {
+    type Error = ApiKeyStoreError;
+
+    fn try_from(model: ApiKeyUsageModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scope: parse_scope(&model.scope)?,
+            key_id: model.key_id,
+            method: model.method,
+            path: model.path,
+            allowed: model.allowed,
+            used_at: model.used_at,
+        })
+    }
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "api_usage_rollup"]
+pub struct ApiUsageRollupModel {
+    pub day: i64,
+    pub key_id: String,
+    pub org_id: String,
+    pub method: String,
+    pub endpoint: String,
+    pub requests: i64,
+    pub rows_returned: i64,
+    pub submissions: i64,
+}
+
+/// The rollup of a single request, as it is first inserted
+#[cfg(feature = "api-usage-analytics")]
+impl From<ApiUsageEvent> for ApiUsageRollupModel {
+    fn from(event: ApiUsageEvent) -> Self {
+        Self {
+            day: day_start(event.used_at),
+            key_id: event.key_id,
+            org_id: event.org_id,
+            method: event.method,
+            endpoint: event.endpoint,
+            requests: 1,
+            rows_returned: event.rows_returned,
+            submissions: i64::from(event.submission),
+        }
+    }
+}
+
+#[cfg(feature = "api-usage-analytics")]
+impl From<ApiUsageRollupModel> for ApiUsageRollup {
+    fn from(model: ApiUsageRollupModel) -> Self {
+        Self {
+            day: model.day,
+            key_id: model.key_id,
+            org_id: model.org_id,
+            method: model.method,
+            endpoint: model.endpoint,
+            requests: model.requests,
+            rows_returned: model.rows_returned,
+            submissions: model.submissions,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::{models::ApiKeyModel, schema::api_keys},
+    ApiKey, ApiKeyStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::api_keys::store::diesel) trait AddApiKeyOperation {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddApiKeyOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        insert_into(api_keys::table)
+            .values(ApiKeyModel::from(api_key))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(ApiKeyStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddApiKeyOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        insert_into(api_keys::table)
+            .values(ApiKeyModel::from(api_key))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(ApiKeyStoreError::from)
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::{models::NewApiKeyUsageModel, schema::api_key_usage},
+    ApiKeyStoreError, ApiKeyUsage,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::api_keys::store::diesel) trait AddApiKeyUsageOperation {
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddApiKeyUsageOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        insert_into(api_key_usage::table)
+            .values(NewApiKeyUsageModel::from(usage))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(ApiKeyStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddApiKeyUsageOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        insert_into(api_key_usage::table)
+            .values(NewApiKeyUsageModel::from(usage))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(ApiKeyStoreError::from)
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::analytics::ApiUsageEvent;
+use crate::api_keys::store::{
+    diesel::{models::ApiUsageRollupModel, schema::api_usage_rollup},
+    ApiKeyStoreError,
+};
+
+#[cfg(feature = "sqlite")]
+use diesel::dsl::update;
+#[cfg(feature = "postgres")]
+use diesel::pg::upsert::excluded;
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::api_keys::store::diesel) trait AddApiUsageOperation {
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddApiUsageOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        insert_into(api_usage_rollup::table)
+            .values(ApiUsageRollupModel::from(event))
+            .on_conflict((
+                api_usage_rollup::day,
+                api_usage_rollup::key_id,
+                api_usage_rollup::method,
+                api_usage_rollup::endpoint,
+            ))
+            .do_update()
+            .set((
+                api_usage_rollup::requests.eq(api_usage_rollup::requests + 1),
+                api_usage_rollup::rows_returned
+                    .eq(api_usage_rollup::rows_returned + excluded(api_usage_rollup::rows_returned)),
+                api_usage_rollup::submissions
+                    .eq(api_usage_rollup::submissions + excluded(api_usage_rollup::submissions)),
+            ))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(ApiKeyStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddApiUsageOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        let rollup = ApiUsageRollupModel::from(event);
+
+        self.conn.transaction::<_, ApiKeyStoreError, _>(|| {
+            let updated = update(api_usage_rollup::table.find((
+                rollup.day,
+                &rollup.key_id,
+                &rollup.method,
+                &rollup.endpoint,
+            )))
+            .set((
+                api_usage_rollup::requests.eq(api_usage_rollup::requests + 1),
+                api_usage_rollup::rows_returned
+                    .eq(api_usage_rollup::rows_returned + rollup.rows_returned),
+                api_usage_rollup::submissions
+                    .eq(api_usage_rollup::submissions + rollup.submissions),
+            ))
+            .execute(self.conn)?;
+
+            if updated == 0 {
+                insert_into(api_usage_rollup::table)
+                    .values(&rollup)
+                    .execute(self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::{models::ApiKeyModel, schema::api_keys},
+    ApiKey, ApiKeyStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::api_keys::store::diesel) trait GetApiKeyOperation {
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetApiKeyOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        api_keys::table
+            .find(key_id)
+            .first::<ApiKeyModel>(self.conn)
+            .optional()?
+            .map(ApiKey::try_from)
+            .transpose()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetApiKeyOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        api_keys::table
+            .find(key_id)
+            .first::<ApiKeyModel>(self.conn)
+            .optional()?
+            .map(ApiKey::try_from)
+            .transpose()
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::{models::ApiKeyUsageModel, schema::api_key_usage},
+    ApiKeyStoreError, ApiKeyUsage,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::api_keys::store::diesel) trait ListApiKeyUsageOperation {
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListApiKeyUsageOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        let mut query = api_key_usage::table
+            .into_boxed()
+            .order(api_key_usage::id.desc())
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(key_id) = key_id {
+            query = query.filter(api_key_usage::key_id.eq(key_id));
+        }
+
+        query
+            .load::<ApiKeyUsageModel>(self.conn)?
+            .into_iter()
+            .map(ApiKeyUsage::try_from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListApiKeyUsageOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        let mut query = api_key_usage::table
+            .into_boxed()
+            .order(api_key_usage::id.desc())
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(key_id) = key_id {
+            query = query.filter(api_key_usage::key_id.eq(key_id));
+        }
+
+        query
+            .load::<ApiKeyUsageModel>(self.conn)?
+            .into_iter()
+            .map(ApiKeyUsage::try_from)
+            .collect()
+    }
+}
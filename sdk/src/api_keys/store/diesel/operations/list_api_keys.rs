@@ -0,0 +1,66 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::{models::ApiKeyModel, schema::api_keys},
+    ApiKey, ApiKeyStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::api_keys::store::diesel) trait ListApiKeysOperation {
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListApiKeysOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        let mut query = api_keys::table
+            .into_boxed()
+            .order((api_keys::created_at.asc(), api_keys::key_id.asc()));
+
+        if let Some(org_id) = org_id {
+            query = query.filter(api_keys::org_id.eq(org_id));
+        }
+
+        query
+            .load::<ApiKeyModel>(self.conn)?
+            .into_iter()
+            .map(ApiKey::try_from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListApiKeysOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        let mut query = api_keys::table
+            .into_boxed()
+            .order((api_keys::created_at.asc(), api_keys::key_id.asc()));
+
+        if let Some(org_id) = org_id {
+            query = query.filter(api_keys::org_id.eq(org_id));
+        }
+
+        query
+            .load::<ApiKeyModel>(self.conn)?
+            .into_iter()
+            .map(ApiKey::try_from)
+            .collect()
+    }
+}
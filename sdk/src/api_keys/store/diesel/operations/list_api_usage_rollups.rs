@@ -0,0 +1,114 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::analytics::{ApiUsageRollup, ApiUsageRollupFilters};
+use crate::api_keys::store::{
+    diesel::{models::ApiUsageRollupModel, schema::api_usage_rollup},
+    ApiKeyStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::api_keys::store::diesel) trait ListApiUsageRollupsOperation {
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListApiUsageRollupsOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        let mut query = api_usage_rollup::table
+            .into_boxed()
+            .order((
+                api_usage_rollup::day.desc(),
+                api_usage_rollup::key_id.asc(),
+                api_usage_rollup::method.asc(),
+                api_usage_rollup::endpoint.asc(),
+            ))
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(org_id) = &filters.org_id {
+            query = query.filter(api_usage_rollup::org_id.eq(org_id));
+        }
+        if let Some(key_id) = &filters.key_id {
+            query = query.filter(api_usage_rollup::key_id.eq(key_id));
+        }
+        if let Some(since) = filters.since {
+            query = query.filter(api_usage_rollup::day.ge(since));
+        }
+        if let Some(until) = filters.until {
+            query = query.filter(api_usage_rollup::day.lt(until));
+        }
+
+        Ok(query
+            .load::<ApiUsageRollupModel>(self.conn)?
+            .into_iter()
+            .map(ApiUsageRollup::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListApiUsageRollupsOperation
+    for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        let mut query = api_usage_rollup::table
+            .into_boxed()
+            .order((
+                api_usage_rollup::day.desc(),
+                api_usage_rollup::key_id.asc(),
+                api_usage_rollup::method.asc(),
+                api_usage_rollup::endpoint.asc(),
+            ))
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(org_id) = &filters.org_id {
+            query = query.filter(api_usage_rollup::org_id.eq(org_id));
+        }
+        if let Some(key_id) = &filters.key_id {
+            query = query.filter(api_usage_rollup::key_id.eq(key_id));
+        }
+        if let Some(since) = filters.since {
+            query = query.filter(api_usage_rollup::day.ge(since));
+        }
+        if let Some(until) = filters.until {
+            query = query.filter(api_usage_rollup::day.lt(until));
+        }
+
+        Ok(query
+            .load::<ApiUsageRollupModel>(self.conn)?
+            .into_iter()
+            .map(ApiUsageRollup::from)
+            .collect())
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{
+    diesel::schema::{api_key_usage, api_usage_rollup},
+    ApiKeyStoreError,
+};
+
+use diesel::{dsl::delete, prelude::*};
+
+pub(in crate::api_keys::store::diesel) trait RemoveApiUsageOperation {
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError>;
+
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> RemoveApiUsageOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        delete(api_key_usage::table.filter(api_key_usage::used_at.lt(used_at)))
+            .execute(self.conn)
+            .map_err(ApiKeyStoreError::from)
+    }
+
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        delete(api_usage_rollup::table.filter(api_usage_rollup::day.lt(day)))
+            .execute(self.conn)
+            .map_err(ApiKeyStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> RemoveApiUsageOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        delete(api_key_usage::table.filter(api_key_usage::used_at.lt(used_at)))
+            .execute(self.conn)
+            .map_err(ApiKeyStoreError::from)
+    }
+
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        delete(api_usage_rollup::table.filter(api_usage_rollup::day.lt(day)))
+            .execute(self.conn)
+            .map_err(ApiKeyStoreError::from)
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ApiKeyStoreOperations;
+
+use crate::api_keys::store::{diesel::schema::api_keys, ApiKeyStoreError};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::api_keys::store::diesel) trait RevokeApiKeyOperation {
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> RevokeApiKeyOperation for ApiKeyStoreOperations<'a, diesel::pg::PgConnection> {
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        self.conn.transaction::<_, ApiKeyStoreError, _>(|| {
+            let current = api_keys::table
+                .find(key_id)
+                .select(api_keys::revoked_at)
+                .first::<Option<i64>>(self.conn)
+                .optional()?;
+
+            match current {
+                None => Err(ApiKeyStoreError::NotFoundError(key_id.to_string())),
+                Some(Some(_)) => Ok(()),
+                Some(None) => update(api_keys::table.find(key_id))
+                    .set(api_keys::revoked_at.eq(revoked_at))
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(ApiKeyStoreError::from),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> RevokeApiKeyOperation for ApiKeyStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        self.conn.transaction::<_, ApiKeyStoreError, _>(|| {
+            let current = api_keys::table
+                .find(key_id)
+                .select(api_keys::revoked_at)
+                .first::<Option<i64>>(self.conn)
+                .optional()?;
+
+            match current {
+                None => Err(ApiKeyStoreError::NotFoundError(key_id.to_string())),
+                Some(Some(_)) => Ok(()),
+                Some(None) => update(api_keys::table.find(key_id))
+                    .set(api_keys::revoked_at.eq(revoked_at))
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(ApiKeyStoreError::from),
+            }
+        })
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    api_keys (key_id) {
+        key_id -> Text,
+        org_id -> Text,
+        secret_hash -> Text,
+        scopes -> Text,
+        created_at -> Int8,
+        revoked_at -> Nullable<Int8>,
+    }
+}
+
+table! {
+    api_key_usage (id) {
+        id -> Int8,
+        key_id -> Text,
+        scope -> Text,
+        method -> Text,
+        path -> Text,
+        allowed -> Bool,
+        used_at -> Int8,
+    }
+}
+
+table! {
+    api_usage_rollup (day, key_id, method, endpoint) {
+        day -> Int8,
+        key_id -> Text,
+        org_id -> Text,
+        method -> Text,
+        endpoint -> Text,
+        requests -> Int8,
+        rows_returned -> Int8,
+        submissions -> Int8,
+    }
+}
+
+joinable!(api_key_usage -> api_keys (key_id));
+joinable!(api_usage_rollup -> api_keys (key_id));
+
+allow_tables_to_appear_in_same_query!(api_keys, api_key_usage, api_usage_rollup);
@@ -0,0 +1,89 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+use diesel::r2d2::PoolError;
+#[cfg(feature = "diesel")]
+use diesel::result::{DatabaseErrorKind, Error as diesel_error};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "diesel")]
+use crate::error::ConstraintViolationType;
+use crate::error::{ConstraintViolationError, InternalError, ResourceTemporarilyUnavailableError};
+
+/// Represents ApiKeyStore errors
+#[derive(Debug)]
+pub enum ApiKeyStoreError {
+    InternalError(InternalError),
+    ConstraintViolationError(ConstraintViolationError),
+    ResourceTemporarilyUnavailableError(ResourceTemporarilyUnavailableError),
+    NotFoundError(String),
+}
+
+impl Error for ApiKeyStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiKeyStoreError::InternalError(err) => Some(err),
+            ApiKeyStoreError::ConstraintViolationError(err) => Some(err),
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(err) => Some(err),
+            ApiKeyStoreError::NotFoundError(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiKeyStoreError::InternalError(err) => err.fmt(f),
+            ApiKeyStoreError::ConstraintViolationError(err) => err.fmt(f),
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(err) => err.fmt(f),
+            ApiKeyStoreError::NotFoundError(ref s) => write!(f, "API key not found: {}", s),
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<diesel_error> for ApiKeyStoreError {
+    fn from(err: diesel_error) -> ApiKeyStoreError {
+        match err {
+            diesel_error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                ApiKeyStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::Unique,
+                        Box::new(err),
+                    ),
+                )
+            }
+            diesel_error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                ApiKeyStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::ForeignKey,
+                        Box::new(err),
+                    ),
+                )
+            }
+            _ => ApiKeyStoreError::InternalError(InternalError::from_source(Box::new(err))),
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<PoolError> for ApiKeyStoreError {
+    fn from(err: PoolError) -> ApiKeyStoreError {
+        ApiKeyStoreError::ResourceTemporarilyUnavailableError(
+            ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+        )
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+pub(crate) mod diesel;
+mod error;
+
+#[cfg(feature = "api-usage-analytics")]
+use super::analytics::{ApiUsageEvent, ApiUsageRollup, ApiUsageRollupFilters};
+use super::Scope;
+
+#[cfg(feature = "diesel")]
+pub use self::diesel::{DieselApiKeyStore, DieselConnectionApiKeyStore};
+pub use error::ApiKeyStoreError;
+
+/// An API key, as stored. Times are seconds since the epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKey {
+    pub key_id: String,
+    /// The Pike organization the key was issued to
+    pub org_id: String,
+    pub secret_hash: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiKey {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+/// A record of a request that presented an API key. Requests that were refused because the key
+/// was revoked, its secret did not match or it lacked the scope are recorded too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKeyUsage {
+    pub key_id: String,
+    /// The scope the request required
+    pub scope: Scope,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+    pub used_at: i64,
+}
+
+pub trait ApiKeyStore {
+    /// Adds an API key to the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `api_key` - The API key to be added
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError>;
+
+    /// Fetches an API key from the underlying storage, whether or not it is revoked
+    ///
+    /// # Arguments
+    ///
+    ///  * `key_id` - The id of the API key to fetch
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError>;
+
+    /// Lists the API keys in the underlying storage, oldest first
+    ///
+    /// # Arguments
+    ///
+    ///  * `org_id` - Only list the keys issued to this organization
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError>;
+
+    /// Marks an API key as revoked. Revoking a key that is already revoked keeps its original
+    /// revocation time.
+    ///
+    /// # Arguments
+    ///
+    ///  * `key_id` - The id of the API key to revoke
+    ///  * `revoked_at` - The time the key was revoked
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError>;
+
+    /// Records a request that presented an API key
+    ///
+    /// # Arguments
+    ///
+    ///  * `usage` - The request to record
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError>;
+
+    /// Lists the recorded uses of API keys, most recent first
+    ///
+    /// # Arguments
+    ///
+    ///  * `key_id` - Only list the uses of this key
+    ///  * `offset` - The index of the first use to return
+    ///  * `limit` - The number of uses to return
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError>;
+
+    /// Removes the recorded uses of API keys made before a time
+    ///
+    /// # Arguments
+    ///
+    ///  * `used_at` - The time uses are kept from
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError>;
+
+    /// Adds a request to the rollup of its key, method, endpoint and day, creating the rollup
+    /// if it is the day's first such request
+    ///
+    /// # Arguments
+    ///
+    ///  * `event` - The request to add
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError>;
+
+    /// Lists the daily rollups of API usage, most recent day first and then by key, method and
+    /// endpoint
+    ///
+    /// # Arguments
+    ///
+    ///  * `filters` - Filters the listed rollups must match
+    ///  * `offset` - The index of the first rollup to return
+    ///  * `limit` - The number of rollups to return
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError>;
+
+    /// Removes the rollups of the days starting before a time
+    ///
+    /// # Arguments
+    ///
+    ///  * `day` - The start of the first day rollups are kept for
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError>;
+}
+
+impl<AS> ApiKeyStore for Box<AS>
+where
+    AS: ApiKeyStore + ?Sized,
+{
+    fn add_api_key(&self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        (**self).add_api_key(api_key)
+    }
+
+    fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>, ApiKeyStoreError> {
+        (**self).get_api_key(key_id)
+    }
+
+    fn list_api_keys(&self, org_id: Option<&str>) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        (**self).list_api_keys(org_id)
+    }
+
+    fn revoke_api_key(&self, key_id: &str, revoked_at: i64) -> Result<(), ApiKeyStoreError> {
+        (**self).revoke_api_key(key_id, revoked_at)
+    }
+
+    fn add_api_key_usage(&self, usage: ApiKeyUsage) -> Result<(), ApiKeyStoreError> {
+        (**self).add_api_key_usage(usage)
+    }
+
+    fn list_api_key_usage(
+        &self,
+        key_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiKeyUsage>, ApiKeyStoreError> {
+        (**self).list_api_key_usage(key_id, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_key_usage_before(&self, used_at: i64) -> Result<usize, ApiKeyStoreError> {
+        (**self).remove_api_key_usage_before(used_at)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn add_api_usage(&self, event: ApiUsageEvent) -> Result<(), ApiKeyStoreError> {
+        (**self).add_api_usage(event)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn list_api_usage_rollups(
+        &self,
+        filters: &ApiUsageRollupFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageRollup>, ApiKeyStoreError> {
+        (**self).list_api_usage_rollups(filters, offset, limit)
+    }
+
+    #[cfg(feature = "api-usage-analytics")]
+    fn remove_api_usage_rollups_before(&self, day: i64) -> Result<usize, ApiKeyStoreError> {
+        (**self).remove_api_usage_rollups_before(day)
+    }
+}
@@ -57,12 +57,16 @@ pub struct SubmitBatches {
     pub batch_list: BatchList,
     pub response_url: Url,
     pub service_id: Option<String>,
+    /// Passed on to the DLT node as the `X-Correlation-Id` header, where it supports one
+    pub correlation_id: Option<String>,
 }
 
 pub struct BatchStatuses {
     pub batch_ids: Vec<String>,
     pub wait: Option<u32>,
     pub service_id: Option<String>,
+    /// Passed on to the DLT node as the `X-Correlation-Id` header, where it supports one
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,6 +27,8 @@ use super::{
     InvalidTransaction, SubmitBatches,
 };
 
+const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
 macro_rules! try_fut {
     ($try_expr:expr) => {
         match $try_expr {
@@ -92,11 +94,16 @@ impl BackendClient for SplinterBackendClient {
         response_url.set_query(Some(&format!("id={}", batch_query)));
         let link = response_url.to_string();
 
-        reqwest::Client::new()
+        let mut request = reqwest::Client::new()
             .post(&url)
             .header("GridProtocolVersion", "1")
             .header("Content-Type", "octet-stream")
-            .header("Authorization", &self.authorization.to_string())
+            .header("Authorization", &self.authorization.to_string());
+        if let Some(correlation_id) = &msg.correlation_id {
+            request = request.header(CORRELATION_ID_HEADER, correlation_id);
+        }
+
+        request
             .body(batch_list_bytes)
             .send()
             .then(|res| {
@@ -135,10 +142,15 @@ impl BackendClient for SplinterBackendClient {
         url.push_str("ids=");
         url.push_str(&msg.batch_ids.join(","));
 
-        Client::new()
+        let mut request = Client::new()
             .get(&url)
             .header("GridProtocolVersion", "1")
-            .header("Authorization", &self.authorization.to_string())
+            .header("Authorization", &self.authorization.to_string());
+        if let Some(correlation_id) = &msg.correlation_id {
+            request = request.header(CORRELATION_ID_HEADER, correlation_id);
+        }
+
+        request
             .send()
             .then(|res| match res {
                 Ok(res) => future::join(future::ok(res.status()), res.bytes()).boxed(),
@@ -302,6 +314,7 @@ mod tests {
                     batch_ids: vec![TEST_BATCH_ID.to_string()],
                     wait: None,
                     service_id: Some(format!("{TEST_CIRCUIT_ID}::{TEST_SERVICE_ID}")),
+                    correlation_id: None,
                 });
 
         (mock_endpoint, response)
@@ -381,4 +394,32 @@ mod tests {
             while deserializing Splinter batch status error response: bad json\"))"
         );
     }
+    /// Verify that the correlation id the request was made with is passed on to Splinter
+    #[tokio::test]
+    async fn splinter_backend_client_forwards_correlation_id() {
+        let endpoint = mockito::mock(
+            "GET",
+            Matcher::Exact(format!(
+                "/scabbard/{TEST_CIRCUIT_ID}/\
+                {TEST_SERVICE_ID}/batch_statuses?ids={TEST_BATCH_ID}"
+            )),
+        )
+        .match_header(CORRELATION_ID_HEADER, "ticket-42")
+        .with_status(200)
+        .with_body(TEST_SUCCESS_RESPONSE)
+        .create();
+
+        let result =
+            SplinterBackendClient::new(mockito::server_url(), TEST_AUTHORIZATION.to_string())
+                .batch_status(BatchStatuses {
+                    batch_ids: vec![TEST_BATCH_ID.to_string()],
+                    wait: None,
+                    service_id: Some(format!("{TEST_CIRCUIT_ID}::{TEST_SERVICE_ID}")),
+                    correlation_id: Some("ticket-42".to_string()),
+                })
+                .await;
+
+        endpoint.assert();
+        assert!(result.is_ok());
+    }
 }
@@ -150,10 +150,18 @@ impl BatchProcessorBuilder {
                             };
 
                         for batch_submit_info in batches {
+                            let correlation_id = batch_submit_info
+                                .correlation_id
+                                .clone()
+                                .unwrap_or_else(|| "none".to_string());
+
                             let bytes = match hex::parse_hex(&batch_submit_info.serialized_batch) {
                                 Ok(b) => b,
                                 Err(err) => {
-                                    error!("Failed to deserialize batch: {}", err);
+                                    error!(
+                                        "Failed to deserialize batch {} [correlation id {}]: {}",
+                                        batch_submit_info.header_signature, correlation_id, err
+                                    );
                                     if let Err(err) = store.update_submission_error_info(
                                         &batch_submit_info.header_signature,
                                         "Deserialization Error",
@@ -169,7 +177,11 @@ impl BatchProcessorBuilder {
                                 match protobuf::Message::parse_from_bytes(&bytes) {
                                     Ok(batch_list) => batch_list,
                                     Err(err) => {
-                                        error!("Failed to deserialize batch: {}", err);
+                                        error!(
+                                            "Failed to deserialize batch {} \
+                                             [correlation id {}]: {}",
+                                            batch_submit_info.header_signature, correlation_id, err
+                                        );
                                         if let Err(err) = store.update_submission_error_info(
                                             &batch_submit_info.header_signature,
                                             "Deserialization Error",
@@ -184,11 +196,12 @@ impl BatchProcessorBuilder {
                             match submitter.submit_batches(SubmitBatches {
                                 batch_list,
                                 service_id: batch_submit_info.service_id,
+                                correlation_id: batch_submit_info.correlation_id,
                             }) {
                                 Ok(()) => {
                                     info!(
-                                        "Batch submitted successfully {}",
-                                        batch_submit_info.header_signature
+                                        "Batch submitted successfully {} [correlation id {}]",
+                                        batch_submit_info.header_signature, correlation_id
                                     );
                                     if let Err(err) = store.change_batch_to_submitted(
                                         &batch_submit_info.header_signature,
@@ -200,6 +213,10 @@ impl BatchProcessorBuilder {
                                 }
                                 Err(BatchSubmitterError::BadRequestError(ref msg))
                                 | Err(BatchSubmitterError::NotFound(ref msg)) => {
+                                    warn!(
+                                        "Batch {} was rejected [correlation id {}]: {}",
+                                        batch_submit_info.header_signature, correlation_id, msg
+                                    );
                                     if let Err(err) = store.update_submission_error_info(
                                         &batch_submit_info.header_signature,
                                         "Bad Request",
@@ -213,7 +230,11 @@ impl BatchProcessorBuilder {
                                 | Err(BatchSubmitterError::ResourceTemporarilyUnavailableError(
                                     ref msg,
                                 )) => {
-                                    error!("Internal service error: {}", msg);
+                                    error!(
+                                        "Internal service error submitting batch {} \
+                                         [correlation id {}]: {}",
+                                        batch_submit_info.header_signature, correlation_id, msg
+                                    );
                                     if let Err(err) =
                                         store.relinquish_claim(&batch_submit_info.header_signature)
                                     {
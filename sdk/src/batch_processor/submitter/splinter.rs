@@ -22,6 +22,8 @@ use crate::batch_processor::submitter::{
 
 use super::error::BatchSubmitterError;
 
+const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
 #[derive(Clone)]
 pub struct SplinterBatchSubmitter {
     node_url: String,
@@ -55,10 +57,14 @@ impl BatchSubmitter for SplinterBatchSubmitter {
         })?;
 
         let client = reqwest::blocking::Client::new();
-        let res = client
+        let mut request = client
             .post(&url)
             .header("GridProtocolVersion", "1")
-            .header("Content-Type", "octet-stream")
+            .header("Content-Type", "octet-stream");
+        if let Some(correlation_id) = &msg.correlation_id {
+            request = request.header(CORRELATION_ID_HEADER, correlation_id);
+        }
+        let res = request
             .body(batch_list_bytes)
             .send()
             .map_err(|err| BatchSubmitterError::InternalError(format!("{}", err)))?;
@@ -119,7 +125,11 @@ impl BatchSubmitter for SplinterBatchSubmitter {
         url.push_str(&msg.batch_ids.join(","));
 
         let client = reqwest::blocking::Client::new();
-        let res = client.get(&url).send().map_err(|err| {
+        let mut request = client.get(&url);
+        if let Some(correlation_id) = &msg.correlation_id {
+            request = request.header(CORRELATION_ID_HEADER, correlation_id);
+        }
+        let res = request.send().map_err(|err| {
             BatchSubmitterError::InternalError(format!(
                 "Unable to retrieve batch statuses: {}",
                 err
@@ -30,6 +30,7 @@ pub struct BatchModel {
     pub claim_expires: Option<NaiveDateTime>,
     pub created: Option<NaiveDateTime>,
     pub service_id: Option<String>,
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Insertable, Queryable, PartialEq, Debug)]
@@ -69,6 +70,7 @@ impl From<(BatchModel, Vec<TransactionModel>)> for Batch {
             claim_expires: batch_model.claim_expires,
             created: batch_model.created,
             service_id: batch_model.service_id,
+            correlation_id: batch_model.correlation_id,
             transactions: transaction_models
                 .into_iter()
                 .map(Transaction::from)
@@ -92,6 +94,7 @@ impl From<Batch> for (BatchModel, Vec<TransactionModel>) {
             claim_expires: batch.claim_expires,
             created: batch.created,
             service_id: batch.service_id,
+            correlation_id: batch.correlation_id,
         };
 
         let transaction_models = batch
@@ -134,6 +137,7 @@ impl From<BatchModel> for BatchSubmitInfo {
             header_signature: model.header_signature,
             serialized_batch: model.serialized_batch,
             service_id: model.service_id,
+            correlation_id: model.correlation_id,
         }
     }
 }
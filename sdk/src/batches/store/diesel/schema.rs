@@ -26,6 +26,7 @@ table! {
         claim_expires -> Nullable<Timestamp>,
         created -> Nullable<Timestamp>,
         service_id -> Nullable<Text>,
+        correlation_id -> Nullable<Text>,
     }
 }
 
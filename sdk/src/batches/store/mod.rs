@@ -39,6 +39,8 @@ pub struct Batch {
     pub claim_expires: Option<NaiveDateTime>,
     pub created: Option<NaiveDateTime>,
     pub service_id: Option<String>,
+    /// Id the submitter gave the request this batch was submitted in, used to trace it
+    pub correlation_id: Option<String>,
     pub transactions: Vec<Transaction>,
 }
 
@@ -63,6 +65,7 @@ impl Batch {
             claim_expires: None,
             created: None,
             service_id,
+            correlation_id: None,
             transactions: vec![],
         }
     }
@@ -89,6 +92,7 @@ pub struct BatchSubmitInfo {
     pub header_signature: String,
     pub serialized_batch: String,
     pub service_id: Option<String>,
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
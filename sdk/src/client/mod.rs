@@ -18,6 +18,10 @@
 pub mod location;
 #[cfg(feature = "location")]
 pub use location::*;
+#[cfg(feature = "mfg_batch")]
+pub mod mfg_batch;
+#[cfg(feature = "mfg_batch")]
+pub use mfg_batch::*;
 #[cfg(feature = "pike")]
 pub mod pike;
 #[cfg(feature = "pike")]
@@ -61,6 +65,10 @@ pub trait ClientFactory {
     #[cfg(feature = "location")]
     fn get_location_client(&self, url: String) -> Box<dyn location::LocationClient>;
 
+    /// Retrieves a client for listing and showing manufactured batches
+    #[cfg(feature = "mfg_batch")]
+    fn get_mfg_batch_client(&self, url: String) -> Box<dyn mfg_batch::MfgBatchClient>;
+
     /// Retrieves a client for listing and showing pike members
     #[cfg(feature = "pike")]
     fn get_pike_client(&self, url: String) -> Box<dyn pike::PikeClient>;
@@ -0,0 +1,85 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod data;
+
+use crate::client::mfg_batch::{MfgBatch, MfgBatchClient};
+use crate::client::reqwest::{fetch_entities_list, fetch_entity, post_batches};
+use crate::client::Client;
+use crate::error::ClientError;
+
+use sawtooth_sdk::messages::batch::BatchList;
+
+const MFG_BATCH_ROUTE: &str = "mfg_batch";
+
+/// The Reqwest implementation of the MfgBatch client
+pub struct ReqwestMfgBatchClient {
+    url: String,
+}
+
+impl ReqwestMfgBatchClient {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+}
+
+impl Client for ReqwestMfgBatchClient {
+    /// Submits a list of batches
+    ///
+    /// # Arguments
+    ///
+    /// * `wait` - wait time in seconds
+    /// * `batch_list` - The `BatchList` to be submitted
+    /// * `service_id` - optional - the service ID to post batches to if running splinter
+    fn post_batches(
+        &self,
+        wait: u64,
+        batch_list: &BatchList,
+        service_id: Option<&str>,
+    ) -> Result<(), ClientError> {
+        post_batches(&self.url, wait, batch_list, service_id)
+    }
+}
+
+impl MfgBatchClient for ReqwestMfgBatchClient {
+    /// Fetches a manufactured batch by its identifier
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the manufactured batch's identifier
+    /// * `service_id` - optional - the service ID to fetch the batch from
+    fn get_mfg_batch(&self, id: String, service_id: Option<&str>) -> Result<MfgBatch, ClientError> {
+        let dto = fetch_entity::<data::MfgBatch>(
+            &self.url,
+            format!("{}/{}", MFG_BATCH_ROUTE, id),
+            service_id,
+        )?;
+        Ok(MfgBatch::from(&dto))
+    }
+
+    /// Fetches all manufactured batches for a service
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - optional - the service ID to fetch the batches from
+    fn list_mfg_batches(&self, service_id: Option<&str>) -> Result<Vec<MfgBatch>, ClientError> {
+        let dto_vec = fetch_entities_list::<data::MfgBatch>(
+            &self.url,
+            MFG_BATCH_ROUTE.to_string(),
+            service_id,
+            None,
+        )?;
+        Ok(dto_vec.iter().map(MfgBatch::from).collect())
+    }
+}
@@ -0,0 +1,537 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pluggable middleware for the reqwest-backed REST clients.
+//!
+//! A [`MiddlewareClient`] passes every request through an ordered stack of
+//! [`Layer`]s before it reaches the network. Each layer receives the request
+//! and a [`Next`] handle which runs the remainder of the stack, so a layer may
+//! modify the request, short-circuit it, or run the rest of the stack more
+//! than once.
+//!
+//! The following layers are provided:
+//!
+//! * [`RetryLayer`] - retries failed requests with exponential backoff and
+//!   jitter
+//! * [`CircuitBreakerLayer`] - fails fast after repeated failures until a
+//!   cool-down period has elapsed
+//! * [`AuthTokenLayer`] - adds a bearer token and refreshes it when the server
+//!   responds with `401 Unauthorized`
+//! * [`LoggingLayer`] - logs each request and its outcome
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::{Client as BlockingClient, Request, RequestBuilder, Response};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{IntoUrl, Method, StatusCode};
+
+use crate::error::ClientError;
+
+/// A single step in a middleware stack
+pub trait Layer: Send + Sync {
+    /// Handles the request, using `next` to pass it on to the rest of the stack
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError>;
+}
+
+/// The remainder of a middleware stack, ending with the underlying client
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a BlockingClient,
+    layers: &'a [Box<dyn Layer>],
+}
+
+impl<'a> Next<'a> {
+    /// Runs the request through the remaining layers and sends it
+    pub fn run(self, request: Request) -> Result<Response, ClientError> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => layer.handle(
+                request,
+                Next {
+                    client: self.client,
+                    layers: rest,
+                },
+            ),
+            None => self.client.execute(request).map_err(ClientError::from),
+        }
+    }
+}
+
+/// A blocking reqwest client which sends requests through a middleware stack
+pub struct MiddlewareClient {
+    client: BlockingClient,
+    layers: Vec<Box<dyn Layer>>,
+}
+
+impl MiddlewareClient {
+    pub fn new(client: BlockingClient) -> Self {
+        MiddlewareClient {
+            client,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Adds a layer to the stack. Layers run in the order they are added, so
+    /// the first layer added sees the request first.
+    pub fn with_layer<L: Layer + 'static>(mut self, layer: L) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Starts building a request with the given method and URL
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Starts building a `GET` request
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Starts building a `POST` request
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Sends a request through the middleware stack
+    pub fn execute(&self, request: Request) -> Result<Response, ClientError> {
+        Next {
+            client: &self.client,
+            layers: &self.layers,
+        }
+        .run(request)
+    }
+
+    /// Builds the request and sends it through the middleware stack
+    pub fn send(&self, builder: RequestBuilder) -> Result<Response, ClientError> {
+        self.execute(builder.build()?)
+    }
+}
+
+impl Default for MiddlewareClient {
+    fn default() -> Self {
+        MiddlewareClient::new(BlockingClient::new())
+    }
+}
+
+/// Returns true if a response with the given status is worth retrying
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Retries requests which fail to send or receive a retryable status
+/// (`5xx` or `429 Too Many Requests`).
+///
+/// The delay before each retry grows exponentially from `base_delay` and is
+/// capped at `max_delay`; a random amount of that delay is used ("full
+/// jitter") so that many clients retrying at once do not do so in lockstep.
+/// Requests with a streaming body cannot be cloned and are never retried.
+pub struct RetryLayer {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryLayer {
+    pub fn new(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        RetryLayer {
+            max_retries,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// Returns the delay to wait before the given retry attempt, starting at 0
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        let ceiling_millis = ceiling.as_millis() as u64;
+        if ceiling_millis == 0 {
+            return ceiling;
+        }
+
+        Duration::from_millis(jitter_seed() % (ceiling_millis + 1))
+    }
+}
+
+impl Default for RetryLayer {
+    fn default() -> Self {
+        RetryLayer::new(3, Duration::from_millis(100), Duration::from_secs(5))
+    }
+}
+
+impl Layer for RetryLayer {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError> {
+        let mut attempt = 0;
+        let mut request = request;
+
+        loop {
+            let retry_request = if attempt < self.max_retries {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let result = next.run(request);
+
+            let should_retry = match &result {
+                Ok(response) => is_retryable(response.status()),
+                Err(_) => true,
+            };
+
+            match retry_request {
+                Some(retry_request) if should_retry => {
+                    let delay = self.delay(attempt);
+                    debug!(
+                        "Retrying {} {} in {:?} (attempt {} of {})",
+                        retry_request.method(),
+                        retry_request.url(),
+                        delay,
+                        attempt + 1,
+                        self.max_retries
+                    );
+                    thread::sleep(delay);
+                    request = retry_request;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// Returns a pseudo-random value used to spread out retry delays
+fn jitter_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos() as u64)
+        .unwrap_or(0);
+
+    // xorshift the clock's nanoseconds so consecutive calls are not correlated
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    HalfOpen,
+}
+
+/// Stops sending requests after `failure_threshold` consecutive failures.
+///
+/// While the circuit is open, requests fail immediately without reaching the
+/// server. Once `reset_timeout` has elapsed a single trial request is let
+/// through; if it succeeds the circuit closes again, otherwise it stays open
+/// for another `reset_timeout`.
+pub struct CircuitBreakerLayer {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    state: Mutex<CircuitState>,
+}
+
+impl CircuitBreakerLayer {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        CircuitBreakerLayer {
+            failure_threshold,
+            reset_timeout,
+            state: Mutex::new(CircuitState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Returns true if the circuit is currently rejecting requests
+    pub fn is_open(&self) -> bool {
+        match *self.state.lock().unwrap_or_else(|err| err.into_inner()) {
+            CircuitState::Open { since } => since.elapsed() < self.reset_timeout,
+            _ => false,
+        }
+    }
+
+    fn acquire(&self) -> Result<(), ClientError> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        match *state {
+            CircuitState::Closed { .. } => Ok(()),
+            CircuitState::Open { since } if since.elapsed() >= self.reset_timeout => {
+                *state = CircuitState::HalfOpen;
+                Ok(())
+            }
+            CircuitState::Open { .. } | CircuitState::HalfOpen => Err(ClientError::InternalError(
+                "Circuit breaker is open; request not sent".into(),
+            )),
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        *state = match (*state, success) {
+            (_, true) => CircuitState::Closed { failures: 0 },
+            (CircuitState::Closed { failures }, false) if failures + 1 < self.failure_threshold => {
+                CircuitState::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => CircuitState::Open {
+                since: Instant::now(),
+            },
+        };
+    }
+}
+
+impl Layer for CircuitBreakerLayer {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError> {
+        self.acquire()?;
+
+        let result = next.run(request);
+
+        self.record(match &result {
+            Ok(response) => !response.status().is_server_error(),
+            Err(_) => false,
+        });
+
+        result
+    }
+}
+
+/// Supplies the bearer tokens used by [`AuthTokenLayer`]
+pub trait TokenProvider: Send + Sync {
+    /// Returns the current token
+    fn token(&self) -> Result<String, ClientError>;
+
+    /// Obtains a new token after the current one has been rejected
+    fn refresh(&self) -> Result<String, ClientError>;
+}
+
+/// Adds an `Authorization: Bearer` header to every request.
+///
+/// If the server responds with `401 Unauthorized`, the token is refreshed and
+/// the request is sent once more with the new token.
+pub struct AuthTokenLayer {
+    provider: Box<dyn TokenProvider>,
+}
+
+impl AuthTokenLayer {
+    pub fn new(provider: Box<dyn TokenProvider>) -> Self {
+        AuthTokenLayer { provider }
+    }
+}
+
+fn set_bearer_token(request: &mut Request, token: &str) -> Result<(), ClientError> {
+    let value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|err| ClientError::InternalError(format!("Invalid auth token: {}", err)))?;
+    request.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
+
+impl Layer for AuthTokenLayer {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError> {
+        let mut request = request;
+        set_bearer_token(&mut request, &self.provider.token()?)?;
+
+        let retry_request = request.try_clone();
+        let response = next.run(request)?;
+
+        match retry_request {
+            Some(mut retry_request) if response.status() == StatusCode::UNAUTHORIZED => {
+                debug!("Refreshing auth token for {}", retry_request.url());
+                set_bearer_token(&mut retry_request, &self.provider.refresh()?)?;
+                next.run(retry_request)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+/// Logs each request along with its status and how long it took
+#[derive(Default)]
+pub struct LoggingLayer;
+
+impl Layer for LoggingLayer {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError> {
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let start = Instant::now();
+
+        let result = next.run(request);
+
+        match &result {
+            Ok(response) => debug!(
+                "{} {} -> {} ({:?})",
+                method,
+                url,
+                response.status(),
+                start.elapsed()
+            ),
+            Err(err) => debug!(
+                "{} {} -> error: {} ({:?})",
+                method,
+                url,
+                err,
+                start.elapsed()
+            ),
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use mockito::{mock, server_url, Matcher};
+
+    /// Counts the requests that pass through it
+    struct CountingLayer(Arc<AtomicUsize>);
+
+    impl Layer for CountingLayer {
+        fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, ClientError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            next.run(request)
+        }
+    }
+
+    struct StaticTokenProvider {
+        refreshed: AtomicUsize,
+    }
+
+    impl TokenProvider for StaticTokenProvider {
+        fn token(&self) -> Result<String, ClientError> {
+            Ok("stale".into())
+        }
+
+        fn refresh(&self) -> Result<String, ClientError> {
+            self.refreshed.fetch_add(1, Ordering::SeqCst);
+            Ok("fresh".into())
+        }
+    }
+
+    /// Verifies the retry layer re-sends a request until it runs out of
+    /// retries when the server keeps returning an error.
+    #[test]
+    fn test_retry_layer_retries_server_errors() {
+        let _m = mock("GET", "/retry").with_status(503).expect(3).create();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let client = MiddlewareClient::default()
+            .with_layer(RetryLayer::new(
+                2,
+                Duration::from_millis(1),
+                Duration::from_millis(2),
+            ))
+            .with_layer(CountingLayer(count.clone()));
+
+        let response = client
+            .send(client.get(&format!("{}/retry", server_url())))
+            .expect("Request failed");
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+        _m.assert();
+    }
+
+    /// Verifies the retry layer does not retry a successful request.
+    #[test]
+    fn test_retry_layer_passes_success() {
+        let _m = mock("GET", "/ok").with_status(200).expect(1).create();
+
+        let client = MiddlewareClient::default().with_layer(RetryLayer::default());
+
+        let response = client
+            .send(client.get(&format!("{}/ok", server_url())))
+            .expect("Request failed");
+
+        assert!(response.status().is_success());
+        _m.assert();
+    }
+
+    /// Verifies the circuit breaker opens after the failure threshold is
+    /// reached and rejects further requests without sending them.
+    #[test]
+    fn test_circuit_breaker_opens() {
+        let _m = mock("GET", "/breaker").with_status(500).expect(2).create();
+
+        let client = MiddlewareClient::default()
+            .with_layer(CircuitBreakerLayer::new(2, Duration::from_secs(60)));
+        let url = format!("{}/breaker", server_url());
+
+        assert!(client.send(client.get(&url)).is_ok());
+        assert!(client.send(client.get(&url)).is_ok());
+        assert!(client.send(client.get(&url)).is_err());
+        _m.assert();
+    }
+
+    /// Verifies the circuit breaker lets a trial request through once the
+    /// reset timeout has elapsed and closes again when it succeeds.
+    #[test]
+    fn test_circuit_breaker_half_open() {
+        let breaker = CircuitBreakerLayer::new(1, Duration::from_millis(0));
+        breaker.record(false);
+        assert!(breaker.acquire().is_ok());
+        assert!(breaker.acquire().is_err());
+        breaker.record(true);
+        assert!(!breaker.is_open());
+        assert!(breaker.acquire().is_ok());
+    }
+
+    /// Verifies the auth token layer refreshes the token and re-sends the
+    /// request when the server responds with 401.
+    #[test]
+    fn test_auth_token_refresh() {
+        let _stale = mock("GET", "/auth")
+            .match_header("authorization", "Bearer stale")
+            .with_status(401)
+            .expect(1)
+            .create();
+        let _fresh = mock("GET", "/auth")
+            .match_header("authorization", Matcher::Exact("Bearer fresh".into()))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let client = MiddlewareClient::default().with_layer(AuthTokenLayer::new(Box::new(
+            StaticTokenProvider {
+                refreshed: AtomicUsize::new(0),
+            },
+        )));
+
+        let response = client
+            .send(client.get(&format!("{}/auth", server_url())))
+            .expect("Request failed");
+
+        assert!(response.status().is_success());
+        _stale.assert();
+        _fresh.assert();
+    }
+
+    /// Verifies retry delays never exceed the configured maximum.
+    #[test]
+    fn test_retry_delay_is_capped() {
+        let layer = RetryLayer::new(10, Duration::from_millis(10), Duration::from_millis(50));
+        for attempt in 0..10 {
+            assert!(layer.delay(attempt) <= Duration::from_millis(50));
+        }
+    }
+}
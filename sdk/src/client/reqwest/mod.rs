@@ -25,6 +25,12 @@ use std::time::Instant;
 
 #[cfg(feature = "location")]
 mod location;
+#[cfg(feature = "mfg_batch")]
+mod mfg_batch;
+#[cfg(feature = "mfg_batch")]
+pub use mfg_batch::*;
+#[cfg(feature = "client-reqwest-middleware")]
+pub mod middleware;
 #[cfg(feature = "location")]
 pub use location::*;
 #[cfg(feature = "pike")]
@@ -43,6 +49,8 @@ pub use purchase_order::*;
 mod schema;
 #[cfg(feature = "location")]
 use super::location as client_location;
+#[cfg(feature = "mfg_batch")]
+use super::mfg_batch as client_mfg_batch;
 #[cfg(feature = "pike")]
 use super::pike as client_pike;
 #[cfg(feature = "product")]
@@ -79,6 +87,12 @@ impl ClientFactory for ReqwestClientFactory {
         Box::new(ReqwestLocationClient::new(url))
     }
 
+    /// Retrieves a client for listing and showing manufactured batches
+    #[cfg(feature = "mfg_batch")]
+    fn get_mfg_batch_client(&self, url: String) -> Box<dyn client_mfg_batch::MfgBatchClient> {
+        Box::new(ReqwestMfgBatchClient::new(url))
+    }
+
     /// Retrieves a client for listing and showing pike members
     #[cfg(feature = "pike")]
     fn get_pike_client(&self, url: String) -> Box<dyn client_pike::PikeClient> {
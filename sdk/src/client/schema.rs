@@ -27,7 +27,7 @@ pub struct Schema {
 }
 
 /// The client representation of a Grid Schema property definition
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PropertyDefinition {
     pub name: String,
     pub schema_name: String,
@@ -40,7 +40,7 @@ pub struct PropertyDefinition {
 }
 
 /// Possible data types for a schema property
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 pub enum DataType {
     Bytes,
     Boolean,
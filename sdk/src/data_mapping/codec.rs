@@ -0,0 +1,242 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Codecs translate the values of particular properties between the encoding they have in the
+//! documents Grid imports and exports, and the form they are stored in. They let an application
+//! give a property a domain-specific encoding, such as an image sent as a base64 data URI or a
+//! JSON object kept in a string property, without handling it outside of the mapping.
+//!
+//! ```ignore
+//! let codecs = PropertyCodecs::new()
+//!     .with_codec("photo", Base64Codec)
+//!     .with_codec("lab_report", JsonStringCodec);
+//! let mapping = DataMapping::from_yaml(yaml)?.with_codecs(codecs);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::protocol::schema::state::{DataType, PropertyValue};
+
+use super::DataMappingError;
+
+/// Translates the values of a property between their document and stored forms
+pub trait PropertyCodec: Send + Sync {
+    /// Converts a value read from a document into the value that is checked against the
+    /// property's definition and stored
+    fn decode(&self, value: Value) -> Result<Value, String>;
+
+    /// Converts a stored property value into the value written to an exported document
+    fn encode(&self, value: &PropertyValue) -> Result<Value, String>;
+}
+
+/// The codecs of an application's properties, by property name
+#[derive(Clone, Default)]
+pub struct PropertyCodecs {
+    codecs: HashMap<String, Arc<dyn PropertyCodec>>,
+}
+
+impl PropertyCodecs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the codec of a property, replacing any codec it had
+    pub fn with_codec<C: PropertyCodec + 'static>(mut self, property: &str, codec: C) -> Self {
+        self.codecs.insert(property.to_string(), Arc::new(codec));
+        self
+    }
+
+    /// Returns the codec of a property, if it has one
+    pub fn get(&self, property: &str) -> Option<&dyn PropertyCodec> {
+        self.codecs.get(property).map(|codec| codec.as_ref())
+    }
+
+    /// Decodes a value of a property read from a document. Properties without a codec are
+    /// returned as they are.
+    pub fn decode(&self, property: &str, value: Value) -> Result<Value, DataMappingError> {
+        match self.get(property) {
+            Some(codec) => codec
+                .decode(value)
+                .map_err(|message| DataMappingError::invalid(property, message)),
+            None => Ok(value),
+        }
+    }
+
+    /// Encodes a stored property value for an exported document, or returns `None` if the
+    /// property has no codec
+    pub fn encode(&self, value: &PropertyValue) -> Result<Option<Value>, DataMappingError> {
+        self.get(value.name())
+            .map(|codec| {
+                codec
+                    .encode(value)
+                    .map_err(|message| DataMappingError::invalid(value.name(), message))
+            })
+            .transpose()
+    }
+}
+
+impl fmt::Debug for PropertyCodecs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut properties = self.codecs.keys().collect::<Vec<_>>();
+        properties.sort();
+        f.debug_struct("PropertyCodecs")
+            .field("properties", &properties)
+            .finish()
+    }
+}
+
+impl PartialEq for PropertyCodecs {
+    /// Codecs are equal if they register the same codec instances for the same properties
+    fn eq(&self, other: &Self) -> bool {
+        self.codecs.len() == other.codecs.len()
+            && self.codecs.iter().all(|(property, codec)| {
+                other
+                    .codecs
+                    .get(property)
+                    .map(|other| Arc::ptr_eq(codec, other))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Keeps a JSON value in a string property, as its compact serialization
+///
+/// Objects and arrays are serialized when imported; strings are checked to hold JSON. The
+/// stored string is parsed back into JSON when exported.
+pub struct JsonStringCodec;
+
+impl PropertyCodec for JsonStringCodec {
+    fn decode(&self, value: Value) -> Result<Value, String> {
+        match value {
+            Value::String(text) => serde_json::from_str::<Value>(&text)
+                .map(|_| Value::String(text))
+                .map_err(|err| format!("Invalid JSON: {}", err)),
+            value => Ok(Value::String(value.to_string())),
+        }
+    }
+
+    fn encode(&self, value: &PropertyValue) -> Result<Value, String> {
+        string_value(value).and_then(|text| {
+            serde_json::from_str(text).map_err(|err| format!("Invalid JSON: {}", err))
+        })
+    }
+}
+
+/// Keeps binary content, such as an image, in a string property as base64
+///
+/// Imported values may be plain base64 or a `data:` URI; either way the base64 is checked and
+/// stored without the URI prefix. Exported values are the stored base64.
+pub struct Base64Codec;
+
+impl PropertyCodec for Base64Codec {
+    fn decode(&self, value: Value) -> Result<Value, String> {
+        let text = value
+            .as_str()
+            .ok_or_else(|| format!("{} is not a string", value))?;
+        let encoded = match text.strip_prefix("data:") {
+            Some(uri) => match uri.split_once(";base64,") {
+                Some((_, encoded)) => encoded,
+                None => return Err("Data URI is not base64 encoded".to_string()),
+            },
+            None => text,
+        };
+
+        base64::decode(encoded).map_err(|err| format!("Invalid base64: {}", err))?;
+        Ok(Value::String(encoded.to_string()))
+    }
+
+    fn encode(&self, value: &PropertyValue) -> Result<Value, String> {
+        string_value(value).map(|text| Value::String(text.to_string()))
+    }
+}
+
+fn string_value(value: &PropertyValue) -> Result<&str, String> {
+    match value.data_type() {
+        DataType::String => Ok(value.string_value()),
+        data_type => Err(format!(
+            "Properties of type {:?} have no string value",
+            data_type
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::protocol::schema::state::PropertyValueBuilder;
+
+    #[test]
+    // This tests that JSON values are stored as strings and parsed back when exported
+    fn json_string_codec() {
+        let codecs = PropertyCodecs::new().with_codec("report", JsonStringCodec);
+
+        let decoded = codecs
+            .decode("report", json!({"ph": 6.5, "passed": true}))
+            .expect("Failed to decode");
+        assert_eq!(decoded, json!(r#"{"passed":true,"ph":6.5}"#));
+        assert!(codecs.decode("report", json!("{not json")).is_err());
+        assert_eq!(
+            codecs.decode("other", json!({"a": 1})).unwrap(),
+            json!({"a": 1})
+        );
+
+        let value = string_property("report", r#"{"passed":true,"ph":6.5}"#);
+        assert_eq!(
+            codecs.encode(&value).unwrap(),
+            Some(json!({"ph": 6.5, "passed": true}))
+        );
+        assert_eq!(
+            codecs.encode(&string_property("other", "text")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    // This tests that base64 values and data URIs are checked and stored as plain base64
+    fn base64_codec() {
+        let codecs = PropertyCodecs::new().with_codec("photo", Base64Codec);
+
+        assert_eq!(
+            codecs
+                .decode("photo", json!("data:image/png;base64,iVBORw0KGgo="))
+                .unwrap(),
+            json!("iVBORw0KGgo=")
+        );
+        assert_eq!(
+            codecs.decode("photo", json!("iVBORw0KGgo=")).unwrap(),
+            json!("iVBORw0KGgo=")
+        );
+        assert!(codecs.decode("photo", json!("not base64!")).is_err());
+        assert!(codecs
+            .decode("photo", json!("data:text/plain,hello"))
+            .is_err());
+        assert!(codecs.decode("photo", json!(12)).is_err());
+    }
+
+    fn string_property(name: &str, value: &str) -> PropertyValue {
+        PropertyValueBuilder::new()
+            .with_name(name.to_string())
+            .with_data_type(DataType::String)
+            .with_string_value(value.to_string())
+            .build()
+            .expect("Failed to build property value")
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads a CSV payload into one object per row, keyed by the header row's column names, so a
+//! mapping finds a row's values with paths such as `/lot_code`. Fields may be quoted, with `""`
+//! standing for a quote inside a quoted field. Empty fields are left out of the row, so a
+//! mapping's default value applies to them, and blank lines are skipped.
+
+use serde_json::{Map, Value};
+
+use super::DataMappingError;
+
+pub fn to_json_rows(csv: &[u8]) -> Result<Vec<Value>, DataMappingError> {
+    let text = std::str::from_utf8(csv)
+        .map_err(|err| DataMappingError::invalid("payload", format!("Invalid CSV: {}", err)))?;
+    let text = text.trim_start_matches('\u{feff}');
+
+    let mut records = records(text)?.into_iter();
+    let header = match records.next() {
+        Some((_, header)) => header,
+        None => return Ok(vec![]),
+    };
+
+    records
+        .map(|(line, fields)| {
+            if fields.len() != header.len() {
+                return Err(DataMappingError::invalid(
+                    "payload",
+                    format!(
+                        "Invalid CSV: line {} has {} fields, but the header has {}",
+                        line,
+                        fields.len(),
+                        header.len()
+                    ),
+                ));
+            }
+
+            Ok(Value::Object(
+                header
+                    .iter()
+                    .zip(fields)
+                    .filter(|(_, field)| !field.is_empty())
+                    .map(|(name, field)| (name.clone(), Value::String(field)))
+                    .collect::<Map<_, _>>(),
+            ))
+        })
+        .collect()
+}
+
+/// Splits the text into records of fields, each with the line it starts on
+fn records(text: &str) -> Result<Vec<(usize, Vec<String>)>, DataMappingError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => (),
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                if fields.len() > 1 || !fields[0].is_empty() {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                } else {
+                    fields.clear();
+                }
+                line += 1;
+                record_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if quoted {
+        return Err(DataMappingError::invalid(
+            "payload",
+            format!(
+                "Invalid CSV: quoted field starting on line {} is not closed",
+                record_line
+            ),
+        ));
+    }
+    if !fields.is_empty() || !field.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// Verify that rows are keyed by the header, with quoted fields unescaped, empty fields left
+    /// out and blank lines skipped
+    #[test]
+    fn test_to_json_rows() {
+        let csv = "\u{feff}lot,description,quantity\r\n\
+            L-1,\"Flour, 25kg\",10\r\n\
+            \r\n\
+            L-2,\"12\"\" pan\nsecond line\",\n";
+
+        assert_eq!(
+            to_json_rows(csv.as_bytes()).expect("Failed to read CSV"),
+            vec![
+                json!({"lot": "L-1", "description": "Flour, 25kg", "quantity": "10"}),
+                json!({"lot": "L-2", "description": "12\" pan\nsecond line"}),
+            ]
+        );
+    }
+
+    /// Verify that a row with the wrong number of fields is reported by line
+    #[test]
+    fn test_to_json_rows_invalid() {
+        match to_json_rows(b"lot,quantity\nL-1,10\nL-2\n") {
+            Err(DataMappingError::InvalidArgument(err)) => {
+                assert!(err.message().contains("line 3"), "{}", err.message())
+            }
+            other => panic!("Expected an invalid argument error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads EDIFACT DESADV despatch advices.
+//!
+//! The separators are those of the `UNA` service string advice, or the defaults if there is
+//! none, and released characters are read as part of values. Each message (`UNH` to `UNT`) is
+//! read as a shipment. Lots are read from `PIA` (type `NB`) or `GIN+BX`, their quantities from
+//! `QTY+12`, their dates from `DTM`, and the parties from `NAD`.
+
+use super::{date, DataMappingError, EdiDocument, Segment, Shipment};
+
+/// The separators of an interchange
+struct Separators {
+    component: char,
+    element: char,
+    release: char,
+    terminator: char,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Separators {
+            component: ':',
+            element: '+',
+            release: '?',
+            terminator: '\'',
+        }
+    }
+}
+
+pub(super) fn read(text: &str, document: &mut EdiDocument) -> Result<(), DataMappingError> {
+    let (separators, text) = match text.strip_prefix("UNA") {
+        Some(rest) => {
+            let advice: Vec<char> = rest.chars().take(6).collect();
+            if advice.len() < 6 {
+                return Err(DataMappingError::invalid(
+                    "payload",
+                    "UNA segment is truncated".to_string(),
+                ));
+            }
+            let separators = Separators {
+                component: advice[0],
+                element: advice[1],
+                release: advice[3],
+                terminator: advice[5],
+            };
+            let length: usize = advice.iter().map(|c| c.len_utf8()).sum();
+            (separators, &rest[length..])
+        }
+        None => (Separators::default(), text),
+    };
+
+    let mut shipment: Option<Shipment> = None;
+    for segment in segments(text, &separators) {
+        if segment.tag == "UNH" {
+            if let Some(shipment) = shipment.take() {
+                shipment.finish("EDIFACT", document);
+            }
+            if segment.component(2, 1) == "DESADV" {
+                shipment = Some(Shipment::default());
+            }
+            continue;
+        }
+        let current = match shipment.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+
+        match segment.tag.as_str() {
+            "UNT" => {
+                if let Some(shipment) = shipment.take() {
+                    shipment.finish("EDIFACT", document);
+                }
+            }
+            "BGM" => current.set_header("shipment", segment.element(2)),
+            "NAD" => {
+                let role = match segment.element(1) {
+                    "SF" => "ship_from",
+                    "ST" | "DP" => "ship_to",
+                    _ => continue,
+                };
+                current.add_party(role);
+                let id = match segment.component(2, 3) {
+                    "9" => "gln",
+                    _ => "id",
+                };
+                current.set_party(id, segment.element(2));
+                current.set_party("name", &joined(&segment, 4));
+                current.set_party("address", &joined(&segment, 5));
+                current.set_party("city", segment.element(6));
+                current.set_party("state", segment.element(7));
+                current.set_party("postal_code", segment.element(8));
+                current.set_party("country", segment.element(9));
+            }
+            "LIN" => {
+                current.start_line();
+                current.set_line("gtin", segment.element(3));
+            }
+            "PIA" => {
+                for position in 2..=segment.elements.len() {
+                    let field = match segment.component(position, 2) {
+                        "NB" => "batch",
+                        "SRV" | "EN" => "gtin",
+                        "SA" => "vendor_part",
+                        "IN" => "buyer_part",
+                        _ => continue,
+                    };
+                    current.set_line(field, segment.element(position));
+                }
+            }
+            "GIN" if segment.element(1) == "BX" => {
+                current.set_line("batch", segment.element(2));
+            }
+            "QTY" if segment.element(1) == "12" => {
+                current.set_line("quantity", segment.component(1, 2));
+                current.set_line("uom", segment.component(1, 3));
+            }
+            "DTM" => {
+                if let Some(date) = date(segment.component(1, 2)) {
+                    match segment.element(1) {
+                        "11" => current.set_header("ship_date", &date),
+                        "36" => {
+                            current.set_line("expiration_date", &date);
+                        }
+                        "94" => {
+                            current.set_line("production_date", &date);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(shipment) = shipment {
+        shipment.finish("EDIFACT", document);
+    }
+
+    Ok(())
+}
+
+/// Splits an interchange into segments, reading released characters as part of values
+fn segments(text: &str, separators: &Separators) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut elements: Vec<Vec<String>> = vec![vec![String::new()]];
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        let value = elements
+            .last_mut()
+            .and_then(|components| components.last_mut())
+            .expect("There is always a value being read");
+        if c == separators.release {
+            if let Some(released) = chars.next() {
+                value.push(released);
+            }
+        } else if c == separators.component {
+            elements
+                .last_mut()
+                .expect("There is always an element being read")
+                .push(String::new());
+        } else if c == separators.element {
+            elements.push(vec![String::new()]);
+        } else if c == separators.terminator {
+            let mut segment = std::mem::replace(&mut elements, vec![vec![String::new()]]);
+            let tag = segment.remove(0).remove(0).trim().to_string();
+            if !tag.is_empty() {
+                segments.push(Segment {
+                    tag,
+                    elements: segment,
+                });
+            }
+        } else {
+            value.push(c);
+        }
+    }
+
+    segments
+}
+
+/// Joins the components of an element, such as the lines of a name or street
+fn joined(segment: &Segment, position: usize) -> String {
+    segment
+        .elements
+        .get(position - 1)
+        .map(|components| {
+            components
+                .iter()
+                .map(|component| component.trim())
+                .filter(|component| !component.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default()
+}
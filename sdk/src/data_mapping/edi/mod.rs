@@ -0,0 +1,704 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads the lots in EDI advance ship notices, X12 856 and EDIFACT DESADV, into mfg_batch
+//! payloads, and the parties they are shipped between into location payloads.
+//!
+//! Each lot in a ship notice is read into a flat record, which a `DataMapping` with EDI format
+//! is applied to. A record has these fields, each a string, and omits those the notice does not
+//! give:
+//!
+//! * `standard` - `X12` or `EDIFACT`
+//! * `shipment` - The shipment identifier (`BSN02`) or despatch advice number (`BGM02`)
+//! * `ship_date` - As `YYYYMMDD`
+//! * `ship_from` - The GLN, or other identifier, of the party shipping the lot
+//! * `ship_to` - The GLN, or other identifier, of the party receiving the lot
+//! * `gtin` - The GTIN of the item
+//! * `vendor_part` - The supplier's item number
+//! * `buyer_part` - The buyer's item number
+//! * `batch` - The lot or batch number
+//! * `quantity` - The shipped quantity
+//! * `uom` - The unit of the shipped quantity
+//! * `production_date` - As `YYYYMMDD`
+//! * `expiration_date` - As `YYYYMMDD`
+//!
+//! Custody of a lot can be recorded by mapping `ship_to` onto an mfg_batch property.
+//!
+//! The parties are read into records with the fields `role` (`ship_from` or `ship_to`), `gln`
+//! or `id`, `name`, `address`, `city`, `state`, `postal_code`, and `country`. A mapping's
+//! `locations` say which parties, identified by GLN, are made into locations and how their
+//! records are mapped onto the properties of the `gs1_location` schema:
+//!
+//! ```yaml
+//! locations:
+//!   roles: [ship_to]
+//!   properties:
+//!     - property: locationDescription
+//!       path: /name
+//! ```
+//!
+//! Item lines without a lot, which are not lot controlled, are skipped, and a line that only
+//! gives a lot takes its item from the line before it. A lot listed more than once in a
+//! shipment, such as on several pallets, is read into one record with the quantities added.
+
+mod edifact;
+mod x12;
+
+use serde_json::{Map, Value};
+
+use crate::protocol::location::payload::{
+    Action, LocationCreateAction, LocationCreateActionBuilder, LocationNamespace, LocationPayload,
+    LocationPayloadBuilder,
+};
+use crate::protocol::mfg_batch::payload::{MfgBatchCreateAction, MfgBatchPayload};
+use crate::schema::store::PropertyDefinition;
+
+use super::{
+    map_properties, mfg_batch_payload, required_string, validate_properties, DataMapping,
+    DataMappingError, FieldMapping, PayloadFormat, PropertyMapping,
+};
+
+/// Name of the schema that defines the properties of GS1 locations
+pub const GS1_LOCATION_SCHEMA: &str = "gs1_location";
+
+const ROLES: &[&str] = &["ship_from", "ship_to"];
+const ITEM_FIELDS: &[&str] = &["gtin", "vendor_part", "buyer_part"];
+
+/// The mapping used if an integrator does not give one. It maps the lot number to the
+/// mfg_batch ID, the shipped quantity, and the dates; it maps no schema properties and makes
+/// no locations.
+const DEFAULT_MAPPING: &str = r#"
+name: edi_ship_notice
+format: edi
+mfg_batch_id:
+  path: /batch
+owner:
+  value: ""
+quantity:
+  path: /quantity
+uom:
+  path: /uom
+production_date:
+  path: /production_date
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+expiration_date:
+  path: /expiration_date
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+"#;
+
+/// How the parties to a shipment are mapped onto GS1 locations
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LocationMapping {
+    /// The roles of the parties that are made into locations
+    #[serde(default = "default_roles")]
+    pub roles: Vec<String>,
+    /// The owner of the locations; the mapping's mfg_batch owner is used if not given
+    #[serde(default)]
+    pub owner: Option<FieldMapping>,
+    #[serde(default)]
+    pub properties: Vec<PropertyMapping>,
+}
+
+impl LocationMapping {
+    pub(super) fn validate(&self) -> Result<(), DataMappingError> {
+        if let Some(role) = self
+            .roles
+            .iter()
+            .find(|role| !ROLES.contains(&role.as_str()))
+        {
+            return Err(DataMappingError::invalid(
+                "locations.roles",
+                format!("{} is not one of {}", role, ROLES.join(", ")),
+            ));
+        }
+        if let Some(owner) = &self.owner {
+            owner.validate("locations.owner")?;
+        }
+
+        validate_properties(&self.properties)
+    }
+}
+
+fn default_roles() -> Vec<String> {
+    vec!["ship_to".to_string()]
+}
+
+/// The lots and parties read from one or more ship notices
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EdiDocument {
+    pub lots: Vec<Value>,
+    /// The parties, once each, in the order they are first named
+    pub parties: Vec<Value>,
+}
+
+/// Applies a data mapping to the lots and parties in EDI ship notices
+#[derive(Clone, Debug)]
+pub struct EdiAdapter {
+    mapping: DataMapping,
+}
+
+impl EdiAdapter {
+    /// Returns an adapter that applies `mapping` to each lot record. The mapping's paths
+    /// address record fields, such as `/batch`, so its format must be EDI.
+    pub fn new(mapping: DataMapping) -> Result<Self, DataMappingError> {
+        if mapping.format != PayloadFormat::Edi {
+            return Err(DataMappingError::invalid(
+                "format",
+                "EDI mappings apply to lot records, so their format must be edi".to_string(),
+            ));
+        }
+
+        Ok(Self { mapping })
+    }
+
+    /// Returns an adapter with the default mapping, for mfg_batches owned by `owner`
+    pub fn with_default_mapping(owner: &str) -> Result<Self, DataMappingError> {
+        let mut mapping = DataMapping::from_yaml(DEFAULT_MAPPING)?;
+        mapping.owner.value = Some(Value::String(owner.to_string()));
+        Self::new(mapping)
+    }
+
+    /// Builds a create action for each lot in the document
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The ship notices, as read by `read_shipments`
+    /// * `definitions` - The property definitions of the mapping's schema
+    pub fn create_actions(
+        &self,
+        document: &EdiDocument,
+        definitions: &[PropertyDefinition],
+    ) -> Result<Vec<MfgBatchCreateAction>, DataMappingError> {
+        document
+            .lots
+            .iter()
+            .map(|record| self.mapping.apply_document(record, definitions))
+            .collect()
+    }
+
+    /// Builds a payload for each lot in the document: a create action for mfg_batches that do
+    /// not exist yet, and an update action for those that do
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The ship notices, as read by `read_shipments`
+    /// * `definitions` - The property definitions of the mapping's schema
+    /// * `exists` - Whether the mfg_batch with the given ID exists
+    /// * `timestamp` - The timestamp of the payloads
+    pub fn payloads<F>(
+        &self,
+        document: &EdiDocument,
+        definitions: &[PropertyDefinition],
+        exists: F,
+        timestamp: u64,
+    ) -> Result<Vec<MfgBatchPayload>, DataMappingError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.create_actions(document, definitions)?
+            .into_iter()
+            .map(|action| {
+                let exists = exists(action.mfg_batch_id());
+                mfg_batch_payload(action, exists, timestamp)
+            })
+            .collect()
+    }
+
+    /// Builds a create action for each party with a GLN in the roles the mapping's `locations`
+    /// name, or none if the mapping has no `locations`
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The ship notices, as read by `read_shipments`
+    /// * `definitions` - The property definitions of the `gs1_location` schema
+    pub fn location_actions(
+        &self,
+        document: &EdiDocument,
+        definitions: &[PropertyDefinition],
+    ) -> Result<Vec<LocationCreateAction>, DataMappingError> {
+        let locations = match &self.mapping.locations {
+            Some(locations) => locations,
+            None => return Ok(vec![]),
+        };
+
+        document
+            .parties
+            .iter()
+            .filter(|party| {
+                party
+                    .get("role")
+                    .and_then(Value::as_str)
+                    .map(|role| locations.roles.iter().any(|mapped| mapped == role))
+                    .unwrap_or(false)
+            })
+            .filter_map(|party| {
+                party
+                    .get("gln")
+                    .and_then(Value::as_str)
+                    .map(|gln| (gln, party))
+            })
+            .map(|(gln, party)| {
+                let owner = required_string(
+                    locations.owner.as_ref().unwrap_or(&self.mapping.owner),
+                    party,
+                    "locations.owner",
+                )?;
+                let properties = map_properties(
+                    &locations.properties,
+                    party,
+                    definitions,
+                    GS1_LOCATION_SCHEMA,
+                    &self.mapping.codecs,
+                )?;
+
+                Ok(LocationCreateActionBuilder::new()
+                    .with_namespace(LocationNamespace::Gs1)
+                    .with_location_id(gln.to_string())
+                    .with_owner(owner)
+                    .with_properties(properties)
+                    .build()?)
+            })
+            .collect()
+    }
+
+    /// Builds a create payload for each party `location_actions` would make into a location,
+    /// unless that location exists. Existing locations are left as they are, since a ship
+    /// notice is not the source of a location's master data.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The ship notices, as read by `read_shipments`
+    /// * `definitions` - The property definitions of the `gs1_location` schema
+    /// * `exists` - Whether the location with the given GLN exists
+    /// * `timestamp` - The timestamp of the payloads
+    pub fn location_payloads<F>(
+        &self,
+        document: &EdiDocument,
+        definitions: &[PropertyDefinition],
+        exists: F,
+        timestamp: u64,
+    ) -> Result<Vec<LocationPayload>, DataMappingError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.location_actions(document, definitions)?
+            .into_iter()
+            .filter(|action| !exists(action.location_id()))
+            .map(|action| {
+                Ok(LocationPayloadBuilder::new()
+                    .with_action(Action::LocationCreate(action))
+                    .with_timestamp(timestamp)
+                    .build()?)
+            })
+            .collect()
+    }
+}
+
+/// Reads the lots and parties in a file holding one or more X12 interchanges or EDIFACT
+/// interchanges, telling the two apart by their first segment
+pub fn read_shipments(edi: &[u8]) -> Result<EdiDocument, DataMappingError> {
+    let text = std::str::from_utf8(edi)
+        .map_err(|err| DataMappingError::invalid("payload", format!("Invalid UTF-8: {}", err)))?
+        .trim_start_matches('\u{feff}')
+        .trim_start();
+
+    let mut document = EdiDocument::default();
+    if text.starts_with("ISA") {
+        x12::read(text, &mut document)?;
+    } else if text.starts_with("UNA") || text.starts_with("UNB") || text.starts_with("UNH") {
+        edifact::read(text, &mut document)?;
+    } else {
+        return Err(DataMappingError::invalid(
+            "payload",
+            "Document is not an X12 or EDIFACT interchange".to_string(),
+        ));
+    }
+
+    Ok(document)
+}
+
+/// A segment, split into elements and their components
+#[derive(Debug)]
+struct Segment {
+    tag: String,
+    elements: Vec<Vec<String>>,
+}
+
+impl Segment {
+    /// Returns the first component of an element, by position from one as EDI standards
+    /// number them, or an empty string if the segment does not have it
+    fn element(&self, position: usize) -> &str {
+        self.component(position, 1)
+    }
+
+    /// Returns a component of an element, by positions from one
+    fn component(&self, position: usize, component: usize) -> &str {
+        position
+            .checked_sub(1)
+            .and_then(|i| self.elements.get(i))
+            .and_then(|components| components.get(component.checked_sub(1)?))
+            .map(|value| value.trim())
+            .unwrap_or("")
+    }
+}
+
+/// The values read from one ship notice message, which are put into records once the message
+/// ends, since the parties and dates of the shipment may follow its lines
+#[derive(Default)]
+struct Shipment {
+    header: Map<String, Value>,
+    lines: Vec<Map<String, Value>>,
+    parties: Vec<Map<String, Value>>,
+}
+
+impl Shipment {
+    fn set_header(&mut self, key: &str, value: &str) {
+        insert(&mut self.header, key, value);
+    }
+
+    fn start_line(&mut self) {
+        self.lines.push(Map::new());
+    }
+
+    /// Sets a value of the current line, returning false if no line has started
+    fn set_line(&mut self, key: &str, value: &str) -> bool {
+        match self.lines.last_mut() {
+            Some(line) => {
+                insert(line, key, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn add_party(&mut self, role: &str) {
+        let mut party = Map::new();
+        party.insert("role".to_string(), Value::String(role.to_string()));
+        self.parties.push(party);
+    }
+
+    /// Sets a value of the party last added
+    fn set_party(&mut self, key: &str, value: &str) {
+        if let Some(party) = self.parties.last_mut() {
+            insert(party, key, value);
+        }
+    }
+
+    fn finish(self, standard: &str, document: &mut EdiDocument) {
+        let mut header = self.header;
+        header.insert("standard".to_string(), Value::String(standard.to_string()));
+        for party in &self.parties {
+            if let (Some(role), Some(id)) = (
+                party.get("role").and_then(Value::as_str),
+                party.get("gln").or_else(|| party.get("id")),
+            ) {
+                header.entry(role).or_insert_with(|| id.clone());
+            }
+        }
+
+        let mut item = Map::new();
+        let mut lots: Vec<Map<String, Value>> = Vec::new();
+        for mut line in self.lines {
+            if ITEM_FIELDS.iter().any(|field| line.contains_key(*field)) {
+                item = ITEM_FIELDS
+                    .iter()
+                    .filter_map(|field| {
+                        line.get(*field)
+                            .map(|value| (field.to_string(), value.clone()))
+                    })
+                    .collect();
+            } else {
+                line.extend(item.clone());
+            }
+
+            if !line.contains_key("batch") {
+                continue;
+            }
+
+            match lots.iter_mut().find(|lot| {
+                lot.get("batch") == line.get("batch") && lot.get("gtin") == line.get("gtin")
+            }) {
+                Some(lot) => add_quantity(lot, &line),
+                None => lots.push(line),
+            }
+        }
+
+        for lot in lots {
+            let mut record = header.clone();
+            record.extend(lot);
+            document.lots.push(Value::Object(record));
+        }
+
+        for party in self.parties {
+            let is_new = match party.get("gln") {
+                Some(gln) => !document
+                    .parties
+                    .iter()
+                    .any(|other| other.get("gln") == Some(gln)),
+                None => true,
+            };
+            if is_new
+                && ROLES
+                    .iter()
+                    .any(|role| party.get("role") == Some(&Value::from(*role)))
+            {
+                document.parties.push(Value::Object(party));
+            }
+        }
+    }
+}
+
+fn insert(record: &mut Map<String, Value>, key: &str, value: &str) {
+    if !value.is_empty() {
+        record.insert(key.to_string(), Value::String(value.to_string()));
+    }
+}
+
+/// Adds the quantity of a line to that of an earlier line for the same lot, if they are in the
+/// same unit
+fn add_quantity(lot: &mut Map<String, Value>, line: &Map<String, Value>) {
+    let quantity = |record: &Map<String, Value>| {
+        record
+            .get("quantity")
+            .and_then(Value::as_str)
+            .and_then(|quantity| quantity.parse::<f64>().ok())
+    };
+
+    if lot.get("uom") != line.get("uom") {
+        return;
+    }
+    if let (Some(total), Some(more)) = (quantity(lot), quantity(line)) {
+        lot.insert(
+            "quantity".to_string(),
+            Value::String((total + more).to_string()),
+        );
+    }
+}
+
+/// Reads a date written as `CCYYMMDD` or `YYMMDD`, with or without a time after it, into
+/// `YYYYMMDD`, treating dates of zeros as missing
+fn date(value: &str) -> Option<String> {
+    let value = value.trim();
+    if !value.chars().all(|c| c.is_ascii_digit()) || value.chars().all(|c| c == '0') {
+        return None;
+    }
+
+    match value.len() {
+        6 => Some(format!("20{}", value)),
+        len if len >= 8 => Some(value[..8].to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::mfg_batch::payload::Action as MfgBatchAction;
+
+    const ASN_856: &[u8] = include_bytes!("test_files/asn856.edi");
+    const DESADV: &[u8] = include_bytes!("test_files/desadv.edi");
+
+    const MAPPING: &str = r#"
+name: acme_asn
+format: edi
+mfg_batch_id:
+  path: /batch
+owner:
+  value: grid_foods
+quantity:
+  path: /quantity
+uom:
+  path: /uom
+expiration_date:
+  path: /expiration_date
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+properties:
+  - property: gtin
+    path: /gtin
+  - property: custodian
+    path: /ship_to
+locations:
+  properties:
+    - property: locationDescription
+      path: /name
+    - property: city
+      path: /city
+"#;
+
+    fn definition(name: &str) -> PropertyDefinition {
+        PropertyDefinition {
+            start_commit_num: 0,
+            end_commit_num: i64::MAX,
+            name: name.to_string(),
+            schema_name: String::new(),
+            data_type: "String".to_string(),
+            required: false,
+            description: String::new(),
+            number_exponent: 0,
+            enum_options: vec![],
+            struct_properties: vec![],
+            service_id: None,
+        }
+    }
+
+    fn lot<'a>(document: &'a EdiDocument, batch: &str) -> &'a Value {
+        document
+            .lots
+            .iter()
+            .find(|lot| lot["batch"] == batch)
+            .unwrap_or_else(|| panic!("No lot {}", batch))
+    }
+
+    /// Tests that the lots and parties of an X12 856 are read, with the shipment's parties
+    /// and dates on each lot, lots on several lines combined, and lines without a lot skipped
+    #[test]
+    fn test_read_x12_856() {
+        let document = read_shipments(ASN_856).expect("Unable to read 856");
+
+        assert_eq!(document.lots.len(), 2);
+        let first = lot(&document, "L2203A");
+        assert_eq!(first["standard"], "X12");
+        assert_eq!(first["shipment"], "SHP-4471");
+        assert_eq!(first["ship_date"], "20220314");
+        assert_eq!(first["ship_from"], "0614141000012");
+        assert_eq!(first["ship_to"], "0614142000019");
+        assert_eq!(first["gtin"], "00614141000418");
+        assert_eq!(first["vendor_part"], "ACM-418");
+        assert_eq!(first["quantity"], "150");
+        assert_eq!(first["uom"], "CA");
+        assert_eq!(first["production_date"], "20220301");
+        assert_eq!(first["expiration_date"], "20230301");
+        assert_eq!(lot(&document, "L2203B")["expiration_date"], "20230315");
+
+        assert_eq!(document.parties.len(), 2);
+        let ship_to = &document.parties[1];
+        assert_eq!(ship_to["role"], "ship_to");
+        assert_eq!(ship_to["gln"], "0614142000019");
+        assert_eq!(ship_to["name"], "Grid Foods DC");
+        assert_eq!(ship_to["address"], "2 Harbor Way Dock 4");
+        assert_eq!(ship_to["city"], "Duluth");
+        assert_eq!(ship_to["state"], "MN");
+        assert_eq!(ship_to["postal_code"], "55802");
+        assert_eq!(ship_to["country"], "US");
+    }
+
+    /// Tests that the lots and parties of an EDIFACT DESADV are read, from both PIA and GIN
+    /// segments, with released separators kept in values
+    #[test]
+    fn test_read_edifact_desadv() {
+        let document = read_shipments(DESADV).expect("Unable to read DESADV");
+
+        assert_eq!(document.lots.len(), 2);
+        let first = lot(&document, "B2203-17");
+        assert_eq!(first["standard"], "EDIFACT");
+        assert_eq!(first["shipment"], "DES-4471");
+        assert_eq!(first["ship_date"], "20220314");
+        assert_eq!(first["ship_from"], "5412345000013");
+        assert_eq!(first["gtin"], "05412345000419");
+        assert_eq!(first["vendor_part"], "ACM-419");
+        assert_eq!(first["quantity"], "40");
+        assert_eq!(first["uom"], "CT");
+        assert_eq!(first["production_date"], "20220301");
+
+        let second = lot(&document, "B2203-18");
+        assert_eq!(second["gtin"], "05412345000426");
+        assert_eq!(second["quantity"], "25");
+        assert_eq!(second["expiration_date"], "20230315");
+        assert!(second.get("uom").is_none());
+
+        assert_eq!(document.parties[1]["name"], "Grid Foods+ DC");
+        assert_eq!(document.parties[1]["country"], "BE");
+    }
+
+    /// Tests that documents that are not EDI are rejected
+    #[test]
+    fn test_read_invalid() {
+        assert!(matches!(
+            read_shipments(b"<IDOC/>"),
+            Err(DataMappingError::InvalidArgument(_))
+        ));
+    }
+
+    /// Tests that lots are mapped onto create or update payloads depending on whether the
+    /// mfg_batch exists, and the mapped parties onto location payloads unless they exist
+    #[test]
+    fn test_payloads() {
+        let adapter = EdiAdapter::new(DataMapping::from_yaml(MAPPING).expect("Invalid mapping"))
+            .expect("Unable to create adapter");
+        let document = read_shipments(ASN_856).expect("Unable to read 856");
+
+        let payloads = adapter
+            .payloads(
+                &document,
+                &[definition("gtin"), definition("custodian")],
+                |id| id == "L2203B",
+                1,
+            )
+            .expect("Unable to build payloads");
+        assert_eq!(payloads.len(), 2);
+        match payloads[0].action() {
+            MfgBatchAction::MfgBatchCreate(action) => {
+                assert_eq!(action.mfg_batch_id(), "L2203A");
+                assert_eq!(action.owner(), "grid_foods");
+                assert_eq!(action.quantity(), 150);
+                assert_eq!(action.properties()[1].string_value(), "0614142000019");
+            }
+            action => panic!("Expected a create action, got {:?}", action),
+        }
+        assert!(matches!(
+            payloads[1].action(),
+            MfgBatchAction::MfgBatchUpdate(_)
+        ));
+
+        let definitions = [definition("locationDescription"), definition("city")];
+        let locations = adapter
+            .location_payloads(&document, &definitions, |_| false, 1)
+            .expect("Unable to build location payloads");
+        assert_eq!(locations.len(), 1);
+        match locations[0].action() {
+            Action::LocationCreate(action) => {
+                assert_eq!(action.location_id(), "0614142000019");
+                assert_eq!(action.owner(), "grid_foods");
+                assert_eq!(action.properties()[0].string_value(), "Grid Foods DC");
+            }
+            action => panic!("Expected a create action, got {:?}", action),
+        }
+
+        assert!(adapter
+            .location_payloads(&document, &definitions, |_| true, 1)
+            .expect("Unable to build location payloads")
+            .is_empty());
+    }
+
+    /// Tests that mappings whose format is not EDI, or whose locations name an unknown role,
+    /// are rejected
+    #[test]
+    fn test_invalid_mapping() {
+        let json = MAPPING.replace("format: edi", "format: json");
+        assert!(EdiAdapter::new(DataMapping::from_yaml(&json).expect("Invalid mapping")).is_err());
+
+        let role = MAPPING.replace("locations:\n", "locations:\n  roles: [bill_to]\n");
+        assert!(matches!(
+            DataMapping::from_yaml(&role),
+            Err(DataMappingError::InvalidArgument(_))
+        ));
+    }
+}
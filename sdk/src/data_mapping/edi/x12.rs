@@ -0,0 +1,173 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads X12 856 ship notices.
+//!
+//! The separators are those the interchange header declares: the element separator follows
+//! `ISA`, and the component separator and segment terminator end the fixed-width header.
+//! Each transaction set (`ST` to `SE`) is read as a shipment. Lots are read from `LIN`
+//! (qualifier `LT`) or `REF*LT`, their quantities from `SN1`, their dates from `DTM`, and the
+//! parties from the `N1` loops.
+
+use super::{date, DataMappingError, EdiDocument, Segment, Shipment};
+
+/// The length of the ISA segment, up to and including its terminator
+const ISA_LENGTH: usize = 106;
+
+pub(super) fn read(text: &str, document: &mut EdiDocument) -> Result<(), DataMappingError> {
+    let header: Vec<char> = text.chars().take(ISA_LENGTH).collect();
+    if header.len() < ISA_LENGTH {
+        return Err(DataMappingError::invalid(
+            "payload",
+            "ISA segment is truncated".to_string(),
+        ));
+    }
+    let (element, component, terminator) = (header[3], header[104], header[105]);
+
+    let mut shipment: Option<Shipment> = None;
+    let mut in_party = false;
+    for text in text.split(terminator) {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let mut elements = text
+            .split(element)
+            .map(|element| element.split(component).map(str::to_string).collect());
+        let segment = Segment {
+            tag: elements
+                .next()
+                .and_then(|tag: Vec<String>| tag.into_iter().next())
+                .unwrap_or_default(),
+            elements: elements.collect(),
+        };
+
+        if segment.tag == "ST" {
+            if let Some(shipment) = shipment.take() {
+                shipment.finish("X12", document);
+            }
+            if segment.element(1) == "856" {
+                shipment = Some(Shipment::default());
+            }
+            continue;
+        }
+        let current = match shipment.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+
+        match segment.tag.as_str() {
+            "SE" => {
+                if let Some(shipment) = shipment.take() {
+                    shipment.finish("X12", document);
+                }
+            }
+            "BSN" => {
+                current.set_header("shipment", segment.element(2));
+                if let Some(date) = date(segment.element(3)) {
+                    current.header.entry("ship_date").or_insert(date.into());
+                }
+            }
+            "HL" => in_party = false,
+            "LIN" => {
+                in_party = false;
+                current.start_line();
+                read_ids(current, &segment, 2);
+            }
+            "REF" if segment.element(1) == "LT" => {
+                current.set_line("batch", segment.element(2));
+            }
+            "SN1" => {
+                current.set_line("quantity", segment.element(2));
+                current.set_line("uom", segment.element(3));
+            }
+            "DTM" => {
+                if let Some(date) = date(segment.element(2)) {
+                    match segment.element(1) {
+                        "011" => current.set_header("ship_date", &date),
+                        "036" | "208" => {
+                            current.set_line("expiration_date", &date);
+                        }
+                        "094" | "405" => {
+                            current.set_line("production_date", &date);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            "N1" => {
+                in_party = match segment.element(1) {
+                    "SF" => {
+                        current.add_party("ship_from");
+                        true
+                    }
+                    "ST" => {
+                        current.add_party("ship_to");
+                        true
+                    }
+                    _ => false,
+                };
+                if in_party {
+                    current.set_party("name", segment.element(2));
+                    let id = match segment.element(3) {
+                        "UL" => "gln",
+                        _ => "id",
+                    };
+                    current.set_party(id, segment.element(4));
+                }
+            }
+            "N3" if in_party => {
+                let address = [segment.element(1), segment.element(2)]
+                    .iter()
+                    .filter(|line| !line.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                current.set_party("address", &address);
+            }
+            "N4" if in_party => {
+                current.set_party("city", segment.element(1));
+                current.set_party("state", segment.element(2));
+                current.set_party("postal_code", segment.element(3));
+                current.set_party("country", segment.element(4));
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(shipment) = shipment {
+        shipment.finish("X12", document);
+    }
+
+    Ok(())
+}
+
+/// Reads the qualified product and lot IDs of a `LIN` segment, which are given in pairs of
+/// qualifier and ID from `position`
+fn read_ids(shipment: &mut Shipment, segment: &Segment, position: usize) {
+    let mut position = position;
+    while position < segment.elements.len() {
+        let field = match segment.element(position) {
+            "LT" => Some("batch"),
+            "UP" | "UK" | "EN" | "UA" => Some("gtin"),
+            "VP" | "VN" => Some("vendor_part"),
+            "BP" | "IN" => Some("buyer_part"),
+            _ => None,
+        };
+        if let Some(field) = field {
+            shipment.set_line(field, segment.element(position + 1));
+        }
+        position += 2;
+    }
+}
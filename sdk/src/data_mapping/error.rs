@@ -0,0 +1,69 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::error::{InternalError, InvalidArgumentError};
+
+/// An error that can occur while loading a data mapping or applying it to a payload
+#[derive(Debug)]
+pub enum DataMappingError {
+    Internal(InternalError),
+    /// The mapping is malformed, or the payload it was applied to does not satisfy it or the
+    /// schema
+    InvalidArgument(InvalidArgumentError),
+}
+
+impl DataMappingError {
+    pub(super) fn invalid(argument: &str, message: String) -> Self {
+        DataMappingError::InvalidArgument(InvalidArgumentError::new(argument.to_string(), message))
+    }
+}
+
+impl Error for DataMappingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataMappingError::Internal(err) => Some(err),
+            DataMappingError::InvalidArgument(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for DataMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataMappingError::Internal(err) => err.fmt(f),
+            DataMappingError::InvalidArgument(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<std::io::Error> for DataMappingError {
+    fn from(err: std::io::Error) -> Self {
+        DataMappingError::Internal(InternalError::from_source(Box::new(err)))
+    }
+}
+
+impl From<crate::protocol::schema::state::PropertyValueBuildError> for DataMappingError {
+    fn from(err: crate::protocol::schema::state::PropertyValueBuildError) -> Self {
+        DataMappingError::Internal(InternalError::from_source(Box::new(err)))
+    }
+}
+
+impl From<crate::protocol::errors::BuilderError> for DataMappingError {
+    fn from(err: crate::protocol::errors::BuilderError) -> Self {
+        DataMappingError::Internal(InternalError::from_source(Box::new(err)))
+    }
+}
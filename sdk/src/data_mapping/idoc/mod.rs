@@ -0,0 +1,479 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads SAP IDocs carrying batch master data (`BATMAS`) or deliveries (`DELVRY`) into
+//! mfg_batch payloads.
+//!
+//! Each batch found in an IDoc is read into a flat record, which a `DataMapping` with JSON
+//! format is applied to. A record has these fields, each a string, and omits those the IDoc
+//! does not give:
+//!
+//! * `idoc_type` - The basic type, such as `BATMAS03` or `DELVRY07`
+//! * `idoc_number` - The IDoc number (`DOCNUM`)
+//! * `sender` - The sending partner (`SNDPRN`)
+//! * `material` - The material number, without the leading zeros SAP pads it with
+//! * `batch` - The batch number
+//! * `plant` - The plant
+//! * `vendor_batch` - The supplier's batch number
+//! * `production_date` - As `YYYYMMDD`
+//! * `expiration_date` - As `YYYYMMDD`
+//! * `quantity` - The delivered quantity, for deliveries
+//! * `uom` - The unit of the delivered quantity, for deliveries
+//! * `delivery` - The delivery number, without leading zeros, for deliveries
+//!
+//! IDocs vary between releases and systems, so they are read leniently: IDocs may be wrapped
+//! in other elements or sent several to a document; the control record is optional; segment
+//! versions and the BAPI and classic field names (`BATCH` or `CHARG`, `EXPIRYDATE` or `VFDAT`)
+//! are both accepted; fields may be in the segment or its child segments; and zero dates are
+//! treated as missing. Delivery items without a batch, which are not batch managed, are
+//! skipped.
+
+use serde_json::{Map, Value};
+
+use crate::protocol::mfg_batch::payload::{MfgBatchCreateAction, MfgBatchPayload};
+use crate::schema::store::PropertyDefinition;
+
+use super::{mfg_batch_payload, xml, DataMapping, DataMappingError, PayloadFormat};
+
+const BATMAS_SEGMENTS: &[&str] = &["E1BATMAS", "E1BATMAS01", "E1BATMAS02", "E1BATMAS03"];
+const DELIVERY_SEGMENTS: &[&str] = &["E1EDL20"];
+const DELIVERY_ITEM_SEGMENTS: &[&str] = &["E1EDL24"];
+
+/// The record fields and the IDoc fields they are read from, in order of preference
+const FIELDS: &[(&str, &[&str])] = &[
+    ("material", &["MATERIAL_LONG", "MATERIAL", "MATNR"]),
+    ("batch", &["BATCH", "CHARG"]),
+    ("plant", &["PLANT", "WERKS"]),
+    ("vendor_batch", &["VENDRBATCH", "LICHA"]),
+    ("production_date", &["PROD_DATE", "HSDAT"]),
+    ("expiration_date", &["EXPIRYDATE", "VFDAT"]),
+];
+const DELIVERY_FIELDS: &[(&str, &[&str])] = &[
+    ("quantity", &["LFIMG", "LGMNG"]),
+    ("uom", &["VRKME", "MEINS"]),
+];
+
+/// The mapping used if an integrator does not give one. It maps the batch number to the
+/// mfg_batch ID, the delivered quantity, and the dates; it maps no schema properties.
+const DEFAULT_MAPPING: &str = r#"
+name: sap_idoc
+mfg_batch_id:
+  path: /batch
+owner:
+  value: ""
+quantity:
+  path: /quantity
+uom:
+  path: /uom
+production_date:
+  path: /production_date
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+expiration_date:
+  path: /expiration_date
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+"#;
+
+/// The kinds of IDoc that carry batches
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdocKind {
+    BatchMaster,
+    Delivery,
+}
+
+/// Applies a data mapping to the batches in SAP IDocs
+#[derive(Clone, Debug)]
+pub struct IdocAdapter {
+    mapping: DataMapping,
+}
+
+impl IdocAdapter {
+    /// Returns an adapter that applies `mapping` to each batch record. The mapping's paths
+    /// address record fields, such as `/batch`, so its format must be JSON.
+    pub fn new(mapping: DataMapping) -> Result<Self, DataMappingError> {
+        if mapping.format != PayloadFormat::Json {
+            return Err(DataMappingError::invalid(
+                "format",
+                "IDoc mappings apply to batch records, so their format must be json".to_string(),
+            ));
+        }
+
+        Ok(Self { mapping })
+    }
+
+    /// Returns an adapter with the default mapping, for mfg_batches owned by `owner`
+    pub fn with_default_mapping(owner: &str) -> Result<Self, DataMappingError> {
+        let mut mapping = DataMapping::from_yaml(DEFAULT_MAPPING)?;
+        mapping.owner.value = Some(Value::String(owner.to_string()));
+        Self::new(mapping)
+    }
+
+    /// Builds a create action for each batch in the IDocs
+    ///
+    /// # Arguments
+    ///
+    /// * `xml` - A document holding one or more IDocs
+    /// * `definitions` - The property definitions of the mapping's schema
+    pub fn create_actions(
+        &self,
+        xml: &[u8],
+        definitions: &[PropertyDefinition],
+    ) -> Result<Vec<MfgBatchCreateAction>, DataMappingError> {
+        read_batches(xml)?
+            .iter()
+            .map(|record| self.mapping.apply_document(record, definitions))
+            .collect()
+    }
+
+    /// Builds a payload for each batch in the IDocs: a create action for batches that do not
+    /// exist yet, and an update action for those that do
+    ///
+    /// # Arguments
+    ///
+    /// * `xml` - A document holding one or more IDocs
+    /// * `definitions` - The property definitions of the mapping's schema
+    /// * `exists` - Whether the mfg_batch with the given ID exists
+    /// * `timestamp` - The timestamp of the payloads
+    pub fn payloads<F>(
+        &self,
+        xml: &[u8],
+        definitions: &[PropertyDefinition],
+        exists: F,
+        timestamp: u64,
+    ) -> Result<Vec<MfgBatchPayload>, DataMappingError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.create_actions(xml, definitions)?
+            .into_iter()
+            .map(|action| {
+                let exists = exists(action.mfg_batch_id());
+                mfg_batch_payload(action, exists, timestamp)
+            })
+            .collect()
+    }
+}
+
+/// Reads the batches in a document holding one or more IDocs into records
+pub fn read_batches(xml: &[u8]) -> Result<Vec<Value>, DataMappingError> {
+    let document = xml::to_json(xml)?;
+
+    let mut idocs = Vec::new();
+    find_all(&document, &["IDOC"], &mut idocs);
+    if idocs.is_empty() {
+        return Err(DataMappingError::invalid(
+            "payload",
+            "Document holds no IDOC element".to_string(),
+        ));
+    }
+
+    let root_name = match &document {
+        Value::Object(root) => root.keys().next().cloned().unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    let mut records = Vec::new();
+    for idoc in idocs {
+        let mut control = Map::new();
+        let idoc_type = field(idoc, &["EDI_DC40"], &["IDOCTYP"])
+            .or_else(|| Some(root_name.clone()).filter(|name| kind_of(name).is_some()));
+        if let Some(idoc_type) = &idoc_type {
+            control.insert("idoc_type".to_string(), idoc_type.as_str().into());
+        }
+        for (name, source) in [("idoc_number", "DOCNUM"), ("sender", "SNDPRN")] {
+            if let Some(value) = field(idoc, &["EDI_DC40"], &[source]) {
+                control.insert(name.to_string(), value.into());
+            }
+        }
+
+        // Without a control record, the kind is told by the segments present
+        let kind = match idoc_type.as_deref().and_then(kind_of) {
+            Some(kind) => kind,
+            None if has_segment(idoc, DELIVERY_SEGMENTS) => IdocKind::Delivery,
+            None if has_segment(idoc, BATMAS_SEGMENTS) => IdocKind::BatchMaster,
+            None => {
+                return Err(DataMappingError::invalid(
+                    "payload",
+                    "IDoc is neither a batch master nor a delivery".to_string(),
+                ))
+            }
+        };
+
+        match kind {
+            IdocKind::BatchMaster => {
+                let mut segments = Vec::new();
+                find_all(idoc, BATMAS_SEGMENTS, &mut segments);
+                for segment in segments {
+                    let mut record = control.clone();
+                    read_fields(segment, FIELDS, &mut record);
+                    records.push(Value::Object(record));
+                }
+            }
+            IdocKind::Delivery => {
+                let mut deliveries = Vec::new();
+                find_all(idoc, DELIVERY_SEGMENTS, &mut deliveries);
+                for delivery in deliveries {
+                    let mut items = Vec::new();
+                    find_all(delivery, DELIVERY_ITEM_SEGMENTS, &mut items);
+                    for item in items {
+                        let mut record = control.clone();
+                        if let Some(number) = text(delivery.get("VBELN")) {
+                            let number = number.trim_start_matches('0');
+                            record.insert("delivery".to_string(), number.into());
+                        }
+                        read_fields(item, FIELDS, &mut record);
+                        read_fields(item, DELIVERY_FIELDS, &mut record);
+                        if record.contains_key("batch") {
+                            records.push(Value::Object(record));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+fn kind_of(idoc_type: &str) -> Option<IdocKind> {
+    if idoc_type.starts_with("BATMAS") {
+        Some(IdocKind::BatchMaster)
+    } else if idoc_type.starts_with("DELVRY") {
+        Some(IdocKind::Delivery)
+    } else {
+        None
+    }
+}
+
+/// Collects the elements with any of the given names, however deeply they are nested, but
+/// not those nested within a match
+fn find_all<'a>(value: &'a Value, names: &[&str], found: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            for (name, child) in map {
+                if names.contains(&name.as_str()) {
+                    match child {
+                        Value::Array(children) => found.extend(children.iter()),
+                        child => found.push(child),
+                    }
+                } else {
+                    find_all(child, names, found);
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| find_all(value, names, found)),
+        _ => (),
+    }
+}
+
+fn has_segment(idoc: &Value, names: &[&str]) -> bool {
+    let mut found = Vec::new();
+    find_all(idoc, names, &mut found);
+    !found.is_empty()
+}
+
+/// Returns the first value of any of `fields` in a segment with one of `segments`
+fn field(idoc: &Value, segments: &[&str], fields: &[&str]) -> Option<String> {
+    let mut found = Vec::new();
+    find_all(idoc, segments, &mut found);
+    found
+        .into_iter()
+        .find_map(|segment| first_field(segment, fields))
+}
+
+/// Returns the first value of any of `fields`, preferring the segment's own fields to those
+/// of its child segments
+fn first_field(segment: &Value, fields: &[&str]) -> Option<String> {
+    let own = fields.iter().find_map(|field| text(segment.get(field)));
+    own.or_else(|| match segment {
+        Value::Object(map) => map
+            .iter()
+            .filter(|(name, _)| !name.starts_with('@') && name.as_str() != "#text")
+            // BAPI segments ending in X flag which fields changed, rather than giving values
+            .filter(|(name, _)| !(name.starts_with("E1BP") && name.ends_with('X')))
+            .find_map(|(_, child)| match child {
+                Value::Array(children) => children.iter().find_map(|c| first_field(c, fields)),
+                child => first_field(child, fields),
+            }),
+        _ => None,
+    })
+}
+
+fn read_fields(segment: &Value, fields: &[(&str, &[&str])], record: &mut Map<String, Value>) {
+    for (name, sources) in fields {
+        if let Some(value) = first_field(segment, sources) {
+            let value = match *name {
+                "material" => value.trim_start_matches('0').to_string(),
+                // Dates that are not set are sent as zeros
+                "production_date" | "expiration_date" if value.chars().all(|c| c == '0') => {
+                    continue
+                }
+                _ => value,
+            };
+            record.insert(name.to_string(), value.into());
+        }
+    }
+}
+
+/// Returns a field's text, treating blank values as missing
+fn text(value: Option<&Value>) -> Option<String> {
+    let text = match value? {
+        Value::String(text) => text.trim(),
+        Value::Object(map) => map.get("#text")?.as_str()?.trim(),
+        _ => return None,
+    };
+
+    Some(text.to_string()).filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::data_mapping::GS1_MFG_BATCH_SCHEMA;
+    use crate::protocol::mfg_batch::payload::Action;
+
+    fn test_file(name: &str) -> Vec<u8> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("src/data_mapping/idoc/test_files");
+        path.push(name);
+        fs::read(path).expect("Failed to read test file")
+    }
+
+    fn definitions() -> Vec<PropertyDefinition> {
+        vec![PropertyDefinition {
+            start_commit_num: 0,
+            end_commit_num: i64::MAX,
+            name: "material".to_string(),
+            schema_name: GS1_MFG_BATCH_SCHEMA.to_string(),
+            data_type: "String".to_string(),
+            required: true,
+            description: String::new(),
+            number_exponent: 0,
+            enum_options: vec![],
+            struct_properties: vec![],
+            service_id: None,
+        }]
+    }
+
+    fn adapter() -> IdocAdapter {
+        let mapping = DataMapping::from_yaml(&format!(
+            "{}properties:\n  - property: material\n    path: /material\n",
+            DEFAULT_MAPPING.replace("value: \"\"", "value: acme")
+        ))
+        .expect("Failed to parse mapping");
+        IdocAdapter::new(mapping).expect("Failed to create adapter")
+    }
+
+    /// Verify that a BATMAS03 IDoc is read with its BAPI field names
+    #[test]
+    fn test_read_batmas() {
+        let records = read_batches(&test_file("batmas03.xml")).expect("Failed to read IDoc");
+
+        assert_eq!(
+            records,
+            vec![serde_json::json!({
+                "idoc_type": "BATMAS03",
+                "idoc_number": "0000000000482913",
+                "sender": "S4HCLNT100",
+                "material": "10004711",
+                "batch": "B210913A",
+                "plant": "1010",
+                "vendor_batch": "V-7781",
+                "production_date": "20210913",
+                "expiration_date": "20220913",
+            })]
+        );
+    }
+
+    /// Verify that older IDocs, with classic field names, no control record and a wrapper
+    /// element, are read, and that zero dates are treated as missing
+    #[test]
+    fn test_read_batmas_variant() {
+        let records = read_batches(&test_file("batmas_variant.xml")).expect("Failed to read IDocs");
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["batch"], "0000123");
+        assert_eq!(records[0]["material"], "TG-11");
+        assert_eq!(records[0]["production_date"], "20210102");
+        assert!(records[0].get("expiration_date").is_none());
+        assert!(records[0].get("idoc_type").is_none());
+        assert_eq!(records[1]["batch"], "0000124");
+    }
+
+    /// Verify that each batch of a delivery, including batch split items, is read, and that
+    /// items without a batch are skipped
+    #[test]
+    fn test_read_delivery() {
+        let records = read_batches(&test_file("delvry03.xml")).expect("Failed to read IDoc");
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["delivery"], "80001234");
+        assert_eq!(records[0]["batch"], "L0001");
+        assert_eq!(records[0]["quantity"], "40.000");
+        assert_eq!(records[0]["uom"], "KG");
+        assert_eq!(records[0]["expiration_date"], "20230131");
+        assert_eq!(records[1]["batch"], "L0002");
+        assert_eq!(records[1]["material"], "10004711");
+    }
+
+    /// Verify that payloads create batches that do not exist and update those that do
+    #[test]
+    fn test_payloads() {
+        let payloads = adapter()
+            .payloads(
+                &test_file("delvry03.xml"),
+                &definitions(),
+                |id| id == "L0002",
+                1_600_000_000,
+            )
+            .expect("Failed to build payloads");
+
+        match payloads[0].action() {
+            Action::MfgBatchCreate(action) => {
+                assert_eq!(action.mfg_batch_id(), "L0001");
+                assert_eq!(action.owner(), "acme");
+                assert_eq!(action.quantity(), 40);
+                assert_eq!(action.uom(), "KG");
+                assert_eq!(action.expiration_date(), 1_675_123_200);
+                assert_eq!(action.properties()[0].string_value(), "10004711");
+            }
+            action => panic!("Expected a create action but got {:?}", action),
+        }
+        match payloads[1].action() {
+            Action::MfgBatchUpdate(action) => assert_eq!(action.mfg_batch_id(), "L0002"),
+            action => panic!("Expected an update action but got {:?}", action),
+        }
+    }
+
+    /// Verify that documents without IDocs, and mappings for XML, are rejected
+    #[test]
+    fn test_invalid() {
+        assert!(read_batches(b"<ORDERS05><IDOC/></ORDERS05>").is_err());
+        assert!(read_batches(b"<Batch><Number>1</Number></Batch>").is_err());
+
+        let mut mapping =
+            DataMapping::from_yaml(DEFAULT_MAPPING).expect("Failed to parse default mapping");
+        mapping.format = PayloadFormat::Xml;
+        assert!(IdocAdapter::new(mapping).is_err());
+    }
+}
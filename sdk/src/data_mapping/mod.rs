@@ -0,0 +1,899 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maps the payloads an ERP system exports onto mfg_batch create actions, so they can be
+//! submitted without a service to translate them. A mapping is written in YAML and says, for
+//! each field of the action and each schema property, where its value is found in the payload
+//! and how the value is transformed:
+//!
+//! ```yaml
+//! name: acme_erp
+//! format: xml
+//! mfg_batch_id:
+//!   path: /Batch/Number
+//! owner:
+//!   value: acme
+//! production_date:
+//!   path: /Batch/Produced
+//!   transforms:
+//!     - type: parse_date
+//!       format: "%Y%m%d"
+//! properties:
+//!   - property: lot_code
+//!     path: /Batch/Lot
+//!   - property: net_weight
+//!     path: /Batch/Weight/#text
+//!     transforms:
+//!       - type: convert_unit
+//!         from: lb
+//!         to: g
+//! ```
+//!
+//! Paths are slash-separated keys into the payload; see the `xml` module for how XML is read.
+//! A CSV payload holds one mfg_batch per row, and is read as described in the `csv` module.
+//! The mapped properties are checked against the schema of the mapping's namespace, after any
+//! codec registered for them has decoded them; see the `codec` module.
+
+mod codec;
+mod csv;
+#[cfg(feature = "data-mapping-edi")]
+pub mod edi;
+mod error;
+#[cfg(feature = "data-mapping-idoc")]
+pub mod idoc;
+mod transform;
+mod xml;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+#[cfg(any(feature = "data-mapping-edi", feature = "data-mapping-idoc"))]
+use crate::protocol::mfg_batch::payload::{
+    Action, MfgBatchPayload, MfgBatchPayloadBuilder, MfgBatchUpdateActionBuilder,
+};
+use crate::protocol::mfg_batch::{
+    payload::{MfgBatchCreateAction, MfgBatchCreateActionBuilder},
+    state::MfgBatchNamespace,
+};
+use crate::protocol::schema::number::NumberValue;
+use crate::protocol::schema::state::{
+    DataType, LatLongBuilder, PropertyValue, PropertyValueBuilder,
+};
+use crate::schema::store::PropertyDefinition;
+
+pub use codec::{Base64Codec, JsonStringCodec, PropertyCodec, PropertyCodecs};
+pub use error::DataMappingError;
+pub use transform::Transform;
+
+/// Name of the schema that defines the properties of GS1 mfg_batches
+pub const GS1_MFG_BATCH_SCHEMA: &str = "gs1_mfg_batch";
+/// Name of the schema that defines the properties of internal mfg_batches
+pub const INTERNAL_MFG_BATCH_SCHEMA: &str = "internal_mfg_batch";
+/// Name of the schema that defines the properties of lot mfg_batches
+pub const LOT_MFG_BATCH_SCHEMA: &str = "lot_mfg_batch";
+
+/// The encoding of the payloads a mapping is applied to
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadFormat {
+    #[default]
+    Json,
+    Xml,
+    /// A header row of column names, then one row per mfg_batch
+    Csv,
+    /// X12 856 or EDIFACT DESADV ship notices; see the `edi` module
+    #[cfg(feature = "data-mapping-edi")]
+    Edi,
+}
+
+/// Where a value is found in a payload, and how it is transformed
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct FieldMapping {
+    /// The slash-separated path to the value, such as `/Batch/Lot`
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The value used if there is no path or nothing is found at it
+    #[serde(default)]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+}
+
+impl FieldMapping {
+    fn resolve(&self, document: &Value, argument: &str) -> Result<Option<Value>, DataMappingError> {
+        let value = self
+            .path
+            .as_deref()
+            .and_then(|path| lookup(document, path))
+            .filter(|value| !value.is_null())
+            .cloned()
+            .or_else(|| self.value.clone());
+
+        match value {
+            Some(value) => self
+                .transforms
+                .iter()
+                .try_fold(value, |value, transform| transform.apply(value))
+                .map(Some)
+                .map_err(|message| DataMappingError::invalid(argument, message)),
+            None => Ok(None),
+        }
+    }
+
+    fn validate(&self, argument: &str) -> Result<(), DataMappingError> {
+        if self.path.is_none() && self.value.is_none() {
+            return Err(DataMappingError::invalid(
+                argument,
+                "A path or a value is required".to_string(),
+            ));
+        }
+
+        self.transforms
+            .iter()
+            .try_for_each(Transform::validate)
+            .map_err(|message| DataMappingError::invalid(argument, message))
+    }
+}
+
+/// The mapping of a payload value onto a schema property
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PropertyMapping {
+    pub property: String,
+    #[serde(flatten)]
+    pub field: FieldMapping,
+}
+
+/// A mapping of payloads onto mfg_batch create actions
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct DataMapping {
+    pub name: String,
+    #[serde(default)]
+    pub format: PayloadFormat,
+    #[serde(default)]
+    pub namespace: MfgBatchNamespace,
+    pub mfg_batch_id: FieldMapping,
+    pub owner: FieldMapping,
+    #[serde(default)]
+    pub quantity: Option<FieldMapping>,
+    #[serde(default)]
+    pub uom: Option<FieldMapping>,
+    #[serde(default)]
+    pub expected_quantity: Option<FieldMapping>,
+    /// Seconds since the epoch once transformed
+    #[serde(default)]
+    pub production_date: Option<FieldMapping>,
+    /// Seconds since the epoch once transformed
+    #[serde(default)]
+    pub expiration_date: Option<FieldMapping>,
+    #[serde(default)]
+    pub properties: Vec<PropertyMapping>,
+    /// How the parties to EDI shipments are mapped onto locations
+    #[cfg(feature = "data-mapping-edi")]
+    #[serde(default)]
+    pub locations: Option<edi::LocationMapping>,
+    /// Decode the values of properties with application-specific encodings
+    #[serde(skip)]
+    pub codecs: PropertyCodecs,
+}
+
+impl DataMapping {
+    /// Parses and checks a mapping written in YAML
+    pub fn from_yaml(yaml: &str) -> Result<Self, DataMappingError> {
+        let mapping: DataMapping = serde_yaml::from_str(yaml)
+            .map_err(|err| DataMappingError::invalid("mapping", err.to_string()))?;
+
+        mapping.mfg_batch_id.validate("mfg_batch_id")?;
+        mapping.owner.validate("owner")?;
+        for (argument, field) in [
+            ("quantity", &mapping.quantity),
+            ("uom", &mapping.uom),
+            ("expected_quantity", &mapping.expected_quantity),
+            ("production_date", &mapping.production_date),
+            ("expiration_date", &mapping.expiration_date),
+        ] {
+            if let Some(field) = field {
+                field.validate(argument)?;
+            }
+        }
+        validate_properties(&mapping.properties)?;
+        #[cfg(feature = "data-mapping-edi")]
+        if let Some(locations) = &mapping.locations {
+            locations.validate()?;
+        }
+
+        Ok(mapping)
+    }
+
+    /// Sets the codecs that decode mapped property values before they are checked
+    pub fn with_codecs(mut self, codecs: PropertyCodecs) -> Self {
+        self.codecs = codecs;
+        self
+    }
+
+    /// Returns the name of the schema the mapped properties are checked against
+    pub fn schema_name(&self) -> &str {
+        match self.namespace {
+            MfgBatchNamespace::Gs1 => GS1_MFG_BATCH_SCHEMA,
+            MfgBatchNamespace::Internal => INTERNAL_MFG_BATCH_SCHEMA,
+            MfgBatchNamespace::Lot => LOT_MFG_BATCH_SCHEMA,
+        }
+    }
+
+    /// Builds the create action for a payload, checking the mapped properties against the
+    /// schema's property definitions
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload, encoded in the mapping's format
+    /// * `definitions` - The property definitions of the mapping's schema
+    pub fn apply(
+        &self,
+        payload: &[u8],
+        definitions: &[PropertyDefinition],
+    ) -> Result<MfgBatchCreateAction, DataMappingError> {
+        let document = match self.format {
+            PayloadFormat::Json => serde_json::from_slice(payload).map_err(|err| {
+                DataMappingError::invalid("payload", format!("Invalid JSON: {}", err))
+            })?,
+            PayloadFormat::Xml => xml::to_json(payload)?,
+            PayloadFormat::Csv => {
+                return Err(DataMappingError::invalid(
+                    "format",
+                    "CSV payloads hold several rows, so they are read by apply_rows".to_string(),
+                ))
+            }
+            #[cfg(feature = "data-mapping-edi")]
+            PayloadFormat::Edi => {
+                return Err(DataMappingError::invalid(
+                    "format",
+                    "EDI documents hold several lots, so they are read by edi::EdiAdapter"
+                        .to_string(),
+                ))
+            }
+        };
+
+        self.apply_document(&document, definitions)
+    }
+
+    /// Builds a create action for each row of a CSV payload, or the single create action for a
+    /// payload in another format, as `apply` does
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload, encoded in the mapping's format
+    /// * `definitions` - The property definitions of the mapping's schema
+    pub fn apply_rows(
+        &self,
+        payload: &[u8],
+        definitions: &[PropertyDefinition],
+    ) -> Result<Vec<MfgBatchCreateAction>, DataMappingError> {
+        if self.format != PayloadFormat::Csv {
+            return Ok(vec![self.apply(payload, definitions)?]);
+        }
+
+        csv::to_json_rows(payload)?
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                self.apply_document(row, definitions)
+                    .map_err(|err| match err {
+                        DataMappingError::InvalidArgument(err) => DataMappingError::invalid(
+                            &err.argument(),
+                            format!("Row {}: {}", i + 1, err.message()),
+                        ),
+                        err => err,
+                    })
+            })
+            .collect()
+    }
+
+    /// Builds the create action for a payload that has already been read into a document, as
+    /// `apply` does
+    pub fn apply_document(
+        &self,
+        document: &Value,
+        definitions: &[PropertyDefinition],
+    ) -> Result<MfgBatchCreateAction, DataMappingError> {
+        let properties = map_properties(
+            &self.properties,
+            document,
+            definitions,
+            self.schema_name(),
+            &self.codecs,
+        )?;
+
+        let mut builder = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_namespace(self.namespace.clone())
+            .with_mfg_batch_id(required_string(
+                &self.mfg_batch_id,
+                document,
+                "mfg_batch_id",
+            )?)
+            .with_owner(required_string(&self.owner, document, "owner")?)
+            .with_properties(properties);
+
+        if let Some(quantity) = optional_number(&self.quantity, document, "quantity")? {
+            builder = builder.with_quantity(quantity);
+        }
+        if let Some(uom) = optional(&self.uom, document, "uom")? {
+            builder = builder.with_uom(as_string(&uom).ok_or_else(|| {
+                DataMappingError::invalid("uom", format!("{} is not a string", uom))
+            })?);
+        }
+        if let Some(expected_quantity) =
+            optional_number(&self.expected_quantity, document, "expected_quantity")?
+        {
+            builder = builder.with_expected_quantity(expected_quantity);
+        }
+        if let Some(production_date) =
+            optional_timestamp(&self.production_date, document, "production_date")?
+        {
+            builder = builder.with_production_date(production_date);
+        }
+        if let Some(expiration_date) =
+            optional_timestamp(&self.expiration_date, document, "expiration_date")?
+        {
+            builder = builder.with_expiration_date(expiration_date);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Checks each property mapping, and that no property is mapped more than once
+fn validate_properties(properties: &[PropertyMapping]) -> Result<(), DataMappingError> {
+    for (i, property) in properties.iter().enumerate() {
+        property.field.validate(&property.property)?;
+        if properties[..i]
+            .iter()
+            .any(|other| other.property == property.property)
+        {
+            return Err(DataMappingError::invalid(
+                &property.property,
+                "Property is mapped more than once".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves property mappings against a document, decoding the values with `codecs` and
+/// checking them against the property definitions of `schema_name`, and checks that each
+/// required property has one
+fn map_properties(
+    properties: &[PropertyMapping],
+    document: &Value,
+    definitions: &[PropertyDefinition],
+    schema_name: &str,
+    codecs: &PropertyCodecs,
+) -> Result<Vec<PropertyValue>, DataMappingError> {
+    let mut values = Vec::new();
+    for mapping in properties {
+        let definition = definitions
+            .iter()
+            .find(|definition| definition.name == mapping.property)
+            .ok_or_else(|| {
+                DataMappingError::invalid(
+                    &mapping.property,
+                    format!("Property is not defined by schema {}", schema_name),
+                )
+            })?;
+
+        if let Some(value) = mapping.field.resolve(document, &mapping.property)? {
+            let value = codecs.decode(&mapping.property, value)?;
+            values.push(to_property_value(definition, &value)?);
+        }
+    }
+
+    if let Some(definition) = definitions.iter().find(|definition| {
+        definition.required
+            && !values
+                .iter()
+                .any(|value: &PropertyValue| value.name() == definition.name)
+    }) {
+        return Err(DataMappingError::invalid(
+            &definition.name,
+            "Required property has no value".to_string(),
+        ));
+    }
+
+    Ok(values)
+}
+
+/// Builds the payload for a mapped mfg_batch: the create action if the mfg_batch does not
+/// exist yet, or an update action with the same values if it does
+#[cfg(any(feature = "data-mapping-edi", feature = "data-mapping-idoc"))]
+fn mfg_batch_payload(
+    action: MfgBatchCreateAction,
+    exists: bool,
+    timestamp: u64,
+) -> Result<MfgBatchPayload, DataMappingError> {
+    let action = if exists {
+        Action::MfgBatchUpdate(
+            MfgBatchUpdateActionBuilder::new()
+                .with_mfg_batch_namespace(action.mfg_batch_namespace().clone())
+                .with_mfg_batch_id(action.mfg_batch_id().to_string())
+                .with_properties(action.properties().to_vec())
+                .with_quantity(action.quantity())
+                .with_uom(action.uom().to_string())
+                .with_expected_quantity(action.expected_quantity())
+                .with_production_date(action.production_date())
+                .with_expiration_date(action.expiration_date())
+                .with_manufacture_location(action.manufacture_location().to_string())
+                .build()?,
+        )
+    } else {
+        Action::MfgBatchCreate(action)
+    };
+
+    Ok(MfgBatchPayloadBuilder::new()
+        .with_action(action)
+        .with_timestamp(timestamp)
+        .build()?)
+}
+
+/// Loads every mapping in the `.yaml` and `.yml` files in a directory, by name
+pub fn load_data_mappings(dir: &Path) -> Result<HashMap<String, DataMapping>, DataMappingError> {
+    let mut mappings = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => (),
+            _ => continue,
+        }
+
+        let mapping = DataMapping::from_yaml(&fs::read_to_string(&path)?).map_err(|err| {
+            DataMappingError::invalid(&path.display().to_string(), err.to_string())
+        })?;
+        if mappings.contains_key(&mapping.name) {
+            return Err(DataMappingError::invalid(
+                &path.display().to_string(),
+                format!("Mapping {} is defined more than once", mapping.name),
+            ));
+        }
+        mappings.insert(mapping.name.clone(), mapping);
+    }
+
+    Ok(mappings)
+}
+
+/// Follows a slash-separated path into a document. Numeric segments index arrays; other
+/// segments applied to an array look in its first element.
+fn lookup<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(document, |value, segment| match value {
+            Value::Array(values) => match segment.parse::<usize>() {
+                Ok(index) => values.get(index),
+                Err(_) => values.first().and_then(|value| value.get(segment)),
+            },
+            value => value.get(segment),
+        })
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(value) => value.as_f64(),
+        Value::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_boolean(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(value) => Some(*value),
+        Value::Number(value) => match value.as_i64() {
+            Some(1) => Some(true),
+            Some(0) => Some(false),
+            _ => None,
+        },
+        Value::String(value) => match value.trim().to_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" => Some(true),
+            "false" | "no" | "n" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn optional(
+    field: &Option<FieldMapping>,
+    document: &Value,
+    argument: &str,
+) -> Result<Option<Value>, DataMappingError> {
+    match field {
+        Some(field) => field.resolve(document, argument),
+        None => Ok(None),
+    }
+}
+
+fn required_string(
+    field: &FieldMapping,
+    document: &Value,
+    argument: &str,
+) -> Result<String, DataMappingError> {
+    let value = field
+        .resolve(document, argument)?
+        .ok_or_else(|| DataMappingError::invalid(argument, "No value found".to_string()))?;
+
+    as_string(&value)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| DataMappingError::invalid(argument, format!("{} is not a string", value)))
+}
+
+fn optional_number(
+    field: &Option<FieldMapping>,
+    document: &Value,
+    argument: &str,
+) -> Result<Option<i64>, DataMappingError> {
+    optional(field, document, argument)?
+        .map(|value| {
+            as_number(&value)
+                .map(|number| number.round() as i64)
+                .ok_or_else(|| {
+                    DataMappingError::invalid(argument, format!("{} is not a number", value))
+                })
+        })
+        .transpose()
+}
+
+fn optional_timestamp(
+    field: &Option<FieldMapping>,
+    document: &Value,
+    argument: &str,
+) -> Result<Option<u64>, DataMappingError> {
+    optional_number(field, document, argument)?
+        .map(|number| {
+            if number < 0 {
+                Err(DataMappingError::invalid(
+                    argument,
+                    format!("{} is before the epoch", number),
+                ))
+            } else {
+                Ok(number as u64)
+            }
+        })
+        .transpose()
+}
+
+/// Checks a mapped value against its property definition and builds the property value
+fn to_property_value(
+    definition: &PropertyDefinition,
+    value: &Value,
+) -> Result<PropertyValue, DataMappingError> {
+    let expected = |kind: &str| {
+        DataMappingError::invalid(&definition.name, format!("{} is not {}", value, kind))
+    };
+    let builder = PropertyValueBuilder::new().with_name(definition.name.clone());
+
+    let builder = match definition.data_type.as_str() {
+        "String" => builder
+            .with_data_type(DataType::String)
+            .with_string_value(as_string(value).ok_or_else(|| expected("a string"))?),
+        "Number" => {
+            // Numbers are stored as integers scaled by the definition's exponent
+            let number = as_number(value).ok_or_else(|| expected("a number"))?;
+            let number = NumberValue::from_f64(number, definition.number_exponent as i32)
+                .ok_or_else(|| expected("a number in range"))?;
+            builder
+                .with_data_type(DataType::Number)
+                .with_number_value(number.value())
+        }
+        "Boolean" => builder
+            .with_data_type(DataType::Boolean)
+            .with_boolean_value(as_boolean(value).ok_or_else(|| expected("a boolean"))?),
+        "LatLong" => {
+            // Given as "latitude,longitude" in degrees, and stored in millionths of a degree
+            let degrees = as_string(value)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|degrees| degrees.trim().parse::<f64>())
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .and_then(Result::ok)
+                .filter(|degrees| degrees.len() == 2)
+                .ok_or_else(|| expected("a latitude,longitude pair"))?;
+            let lat_long = LatLongBuilder::new()
+                .with_lat_long(
+                    (degrees[0] * 1_000_000f64).round() as i64,
+                    (degrees[1] * 1_000_000f64).round() as i64,
+                )
+                .build()
+                .map_err(|err| DataMappingError::invalid(&definition.name, err.to_string()))?;
+            builder
+                .with_data_type(DataType::LatLong)
+                .with_lat_long_value(lat_long)
+        }
+        "Enum" => {
+            let index = match value {
+                Value::Number(index) => index.as_u64().map(|index| index as usize),
+                value => as_string(value).and_then(|option| {
+                    definition
+                        .enum_options
+                        .iter()
+                        .position(|enum_option| *enum_option == option)
+                }),
+            }
+            .filter(|index| *index < definition.enum_options.len())
+            .ok_or_else(|| expected(&format!("one of {}", definition.enum_options.join(", "))))?;
+            builder
+                .with_data_type(DataType::Enum)
+                .with_enum_value(index as u32)
+        }
+        data_type => {
+            return Err(DataMappingError::invalid(
+                &definition.name,
+                format!("Properties of type {} cannot be mapped", data_type),
+            ))
+        }
+    };
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = r#"
+name: acme_erp
+format: xml
+mfg_batch_id:
+  path: /Batch/Number
+owner:
+  value: acme
+production_date:
+  path: /Batch/Produced
+  transforms:
+    - type: parse_date
+      format: "%Y%m%d"
+properties:
+  - property: lot_code
+    path: /Batch/Lot
+  - property: net_weight
+    path: /Batch/Weight/#text
+    transforms:
+      - type: convert_unit
+        from: lb
+        to: g
+  - property: organic
+    path: /Batch/Organic
+    transforms:
+      - type: lookup
+        values:
+          Y: true
+          N: false
+  - property: grade
+    path: /Batch/Grade
+"#;
+
+    fn definition(name: &str, data_type: &str, required: bool) -> PropertyDefinition {
+        PropertyDefinition {
+            start_commit_num: 0,
+            end_commit_num: i64::MAX,
+            name: name.to_string(),
+            schema_name: GS1_MFG_BATCH_SCHEMA.to_string(),
+            data_type: data_type.to_string(),
+            required,
+            description: String::new(),
+            number_exponent: 0,
+            enum_options: vec![],
+            struct_properties: vec![],
+            service_id: None,
+        }
+    }
+
+    fn definitions() -> Vec<PropertyDefinition> {
+        vec![
+            definition("lot_code", "String", true),
+            PropertyDefinition {
+                number_exponent: -1,
+                ..definition("net_weight", "Number", false)
+            },
+            definition("organic", "Boolean", false),
+            PropertyDefinition {
+                enum_options: vec!["A".to_string(), "B".to_string()],
+                ..definition("grade", "Enum", false)
+            },
+        ]
+    }
+
+    /// Verify that an XML payload is mapped onto a create action, with its values transformed
+    /// and checked against the schema
+    #[test]
+    fn test_apply() {
+        let mapping = DataMapping::from_yaml(MAPPING).expect("Failed to parse mapping");
+        let payload = br#"<Batch>
+            <Number>B-100</Number>
+            <Lot>ABC123</Lot>
+            <Produced>20210913</Produced>
+            <Weight unit="lb">2</Weight>
+            <Organic>Y</Organic>
+            <Grade>B</Grade>
+        </Batch>"#;
+
+        let action = mapping
+            .apply(payload, &definitions())
+            .expect("Failed to apply mapping");
+
+        assert_eq!(action.mfg_batch_id(), "B-100");
+        assert_eq!(action.owner(), "acme");
+        assert_eq!(action.production_date(), 1_631_491_200);
+
+        let properties = action.properties();
+        assert_eq!(properties[0].string_value(), "ABC123");
+        // 907.18474 g, stored with one decimal place
+        assert_eq!(*properties[1].number_value(), 9072);
+        assert!(*properties[2].boolean_value());
+        assert_eq!(*properties[3].enum_value(), 1);
+    }
+
+    /// Verify that a property with a registered codec is decoded before it is checked against
+    /// the schema
+    #[test]
+    fn test_apply_with_codecs() {
+        let mapping = DataMapping::from_yaml(
+            r#"
+name: lab
+mfg_batch_id:
+  path: /id
+owner:
+  value: acme
+properties:
+  - property: lot_code
+    path: /report
+"#,
+        )
+        .expect("Failed to parse mapping")
+        .with_codecs(PropertyCodecs::new().with_codec("lot_code", JsonStringCodec));
+
+        let action = mapping
+            .apply(br#"{"id": "B-1", "report": {"ph": 7}}"#, &definitions())
+            .expect("Failed to apply mapping");
+        assert_eq!(action.properties()[0].string_value(), r#"{"ph":7}"#);
+
+        assert!(mapping
+            .apply(br#"{"id": "B-1", "report": "{ph"}"#, &definitions())
+            .is_err());
+    }
+
+    /// Verify that payloads missing required values, or with values the schema does not
+    /// allow, are rejected
+    #[test]
+    fn test_apply_invalid() {
+        let mapping = DataMapping::from_yaml(MAPPING).expect("Failed to parse mapping");
+
+        // No lot code, which the schema requires
+        assert!(mapping
+            .apply(b"<Batch><Number>B-100</Number></Batch>", &definitions())
+            .is_err());
+        // No mfg_batch ID
+        assert!(mapping
+            .apply(b"<Batch><Lot>ABC123</Lot></Batch>", &definitions())
+            .is_err());
+        // Not an enum option
+        assert!(mapping
+            .apply(
+                b"<Batch><Number>B-100</Number><Lot>ABC123</Lot><Grade>Z</Grade></Batch>",
+                &definitions()
+            )
+            .is_err());
+        // A property the schema does not define
+        assert!(mapping.apply(b"<Batch/>", &definitions()[..1]).is_err());
+    }
+
+    /// Verify that JSON payloads are mapped, including through arrays
+    #[test]
+    fn test_apply_json() {
+        let mapping = DataMapping::from_yaml(
+            r#"
+name: json_erp
+mfg_batch_id:
+  path: /batches/0/id
+owner:
+  path: /plant/org
+quantity:
+  path: /batches/0/qty
+properties:
+  - property: lot_code
+    path: /batches/lot
+"#,
+        )
+        .expect("Failed to parse mapping");
+
+        let action = mapping
+            .apply(
+                br#"{"plant": {"org": "acme"}, "batches": [{"id": "B-1", "lot": "L1", "qty": "12"}]}"#,
+                &definitions()[..1],
+            )
+            .expect("Failed to apply mapping");
+
+        assert_eq!(action.mfg_batch_id(), "B-1");
+        assert_eq!(action.owner(), "acme");
+        assert_eq!(action.quantity(), 12);
+        assert_eq!(action.properties()[0].string_value(), "L1");
+    }
+
+    /// Verify that each row of a CSV payload is mapped, and that a bad row is reported by number
+    #[test]
+    fn test_apply_rows_csv() {
+        let mapping = DataMapping::from_yaml(
+            r#"
+name: csv_erp
+format: csv
+mfg_batch_id:
+  path: /batch
+owner:
+  value: acme
+quantity:
+  path: /qty
+properties:
+  - property: lot_code
+    path: /lot
+"#,
+        )
+        .expect("Failed to parse mapping");
+
+        let actions = mapping
+            .apply_rows(b"batch,lot,qty\nB-1,L1,12\nB-2,L2,\n", &definitions()[..1])
+            .expect("Failed to apply mapping");
+
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].mfg_batch_id(), "B-1");
+        assert_eq!(actions[0].quantity(), 12);
+        assert_eq!(actions[1].properties()[0].string_value(), "L2");
+        assert!(mapping
+            .apply(b"batch,lot\nB-1,L1\n", &definitions()[..1])
+            .is_err());
+
+        match mapping.apply_rows(b"batch,lot\nB-1,L1\nB-2,\n", &definitions()[..1]) {
+            Err(DataMappingError::InvalidArgument(err)) => {
+                assert!(err.message().starts_with("Row 2:"), "{}", err.message())
+            }
+            other => panic!("Expected an invalid argument error, got {:?}", other),
+        }
+    }
+
+    /// Verify that malformed mappings are rejected when they are loaded
+    #[test]
+    fn test_from_yaml_invalid() {
+        // No path or value for the owner
+        assert!(DataMapping::from_yaml("name: a\nmfg_batch_id: {path: /id}\nowner: {}").is_err());
+        // Unknown unit
+        assert!(DataMapping::from_yaml(
+            "name: a\nmfg_batch_id: {path: /id}\nowner: {value: o}\nproperties:\n  - property: w\n    path: /w\n    transforms: [{type: convert_unit, from: lb, to: stone}]"
+        )
+        .is_err());
+        // A property mapped twice
+        assert!(DataMapping::from_yaml(
+            "name: a\nmfg_batch_id: {path: /id}\nowner: {value: o}\nproperties:\n  - {property: w, path: /w}\n  - {property: w, path: /x}"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_json::Value;
+
+use super::{as_number, as_string};
+
+/// Masses in grams, volumes in milliliters and lengths in millimeters
+const UNITS: &[(&str, &str, f64)] = &[
+    ("mg", "mass", 0.001),
+    ("g", "mass", 1.0),
+    ("kg", "mass", 1_000.0),
+    ("t", "mass", 1_000_000.0),
+    ("oz", "mass", 28.349_523_125),
+    ("lb", "mass", 453.592_37),
+    ("ml", "volume", 1.0),
+    ("l", "volume", 1_000.0),
+    ("fl_oz", "volume", 29.573_529_562_5),
+    ("gal", "volume", 3_785.411_784),
+    ("mm", "length", 1.0),
+    ("cm", "length", 10.0),
+    ("m", "length", 1_000.0),
+    ("in", "length", 25.4),
+    ("ft", "length", 304.8),
+];
+
+/// A change made to a value found in a payload before it is checked against the schema.
+/// Transforms are applied in the order they are listed.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Parses a date, or a date and time, written in the given `chrono` format into seconds
+    /// since the epoch, taking it as UTC
+    ParseDate { format: String },
+    /// Converts a number from one unit to another of the same kind, such as `lb` to `kg`
+    ConvertUnit { from: String, to: String },
+    /// Replaces a value with the one it is mapped to; values not in the table are kept
+    Lookup { values: HashMap<String, Value> },
+}
+
+impl Transform {
+    /// Checks that the transform can be applied, such as that its units are known
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Transform::ConvertUnit { from, to } => conversion_factor(from, to).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn apply(&self, value: Value) -> Result<Value, String> {
+        match self {
+            Transform::ParseDate { format } => {
+                let text = as_string(&value).ok_or_else(|| format!("{} is not a date", value))?;
+                NaiveDateTime::parse_from_str(&text, format)
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(&text, format).map(|date| date.and_hms(0, 0, 0))
+                    })
+                    .map(|date_time| Value::from(date_time.timestamp()))
+                    .map_err(|err| format!("Cannot parse {} as {}: {}", text, format, err))
+            }
+            Transform::ConvertUnit { from, to } => {
+                let number =
+                    as_number(&value).ok_or_else(|| format!("{} is not a number", value))?;
+                let converted = number * conversion_factor(from, to)?;
+                serde_json::Number::from_f64(converted)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("{} {} cannot be converted to {}", number, from, to))
+            }
+            Transform::Lookup { values } => Ok(as_string(&value)
+                .and_then(|key| values.get(&key).cloned())
+                .unwrap_or(value)),
+        }
+    }
+}
+
+fn conversion_factor(from: &str, to: &str) -> Result<f64, String> {
+    let unit = |name: &str| {
+        UNITS
+            .iter()
+            .find(|(unit, _, _)| *unit == name)
+            .map(|(_, kind, factor)| (*kind, *factor))
+            .ok_or_else(|| format!("Unknown unit {}", name))
+    };
+    let (from_kind, from_factor) = unit(from)?;
+    let (to_kind, to_factor) = unit(to)?;
+
+    if from_kind != to_kind {
+        return Err(format!(
+            "Cannot convert {} ({}) to {} ({})",
+            from, from_kind, to, to_kind
+        ));
+    }
+
+    Ok(from_factor / to_factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// Verify that dates with and without times are parsed as UTC
+    #[test]
+    fn test_parse_date() {
+        let date = Transform::ParseDate {
+            format: "%Y%m%d".to_string(),
+        };
+        assert_eq!(date.apply(json!("20210913")), Ok(json!(1_631_491_200)));
+        assert!(date.apply(json!("2021-09-13")).is_err());
+
+        let date_time = Transform::ParseDate {
+            format: "%Y-%m-%dT%H:%M:%S".to_string(),
+        };
+        assert_eq!(
+            date_time.apply(json!("2021-09-13T01:00:00")),
+            Ok(json!(1_631_494_800))
+        );
+    }
+
+    /// Verify that numbers, and numeric strings, are converted between units of the same kind
+    #[test]
+    fn test_convert_unit() {
+        let convert = Transform::ConvertUnit {
+            from: "kg".to_string(),
+            to: "g".to_string(),
+        };
+        assert_eq!(convert.apply(json!(1.5)), Ok(json!(1500.0)));
+        assert_eq!(convert.apply(json!("2")), Ok(json!(2000.0)));
+        assert!(convert.apply(json!("heavy")).is_err());
+
+        assert!(Transform::ConvertUnit {
+            from: "kg".to_string(),
+            to: "l".to_string(),
+        }
+        .validate()
+        .is_err());
+        assert!(Transform::ConvertUnit {
+            from: "stone".to_string(),
+            to: "kg".to_string(),
+        }
+        .validate()
+        .is_err());
+    }
+
+    /// Verify that looked up values are replaced and others are kept
+    #[test]
+    fn test_lookup() {
+        let lookup = Transform::Lookup {
+            values: vec![("Y".to_string(), json!(true))].into_iter().collect(),
+        };
+        assert_eq!(lookup.apply(json!("Y")), Ok(json!(true)));
+        assert_eq!(lookup.apply(json!("N")), Ok(json!("N")));
+    }
+}
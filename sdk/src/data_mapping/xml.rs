@@ -0,0 +1,183 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads an XML document into the same tree a JSON payload is parsed into, so mappings find
+//! values in both the same way. An element with neither attributes nor child elements becomes
+//! its text; any other element becomes an object holding its attributes, prefixed with `@`,
+//! its child elements, and its text as `#text`. Repeated child elements become an array.
+//! Namespace prefixes are dropped from names.
+
+use quick_xml::{events::BytesStart, events::Event, Reader};
+use serde_json::{Map, Value};
+
+use super::DataMappingError;
+
+pub fn to_json(xml: &[u8]) -> Result<Value, DataMappingError> {
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    // The open elements' names, attributes and children, and text
+    let mut open: Vec<(String, Map<String, Value>, String)> = Vec::new();
+    let mut root = Map::new();
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                open.push((name(&reader, e)?, attributes(&reader, e)?, String::new()));
+            }
+            Ok(Event::Empty(ref e)) => {
+                let element = element_value(attributes(&reader, e)?, String::new());
+                let parent = open.last_mut().map(|(_, map, _)| map).unwrap_or(&mut root);
+                insert(parent, name(&reader, e)?, element);
+            }
+            Ok(Event::Text(ref e)) => {
+                if let Some((_, _, text)) = open.last_mut() {
+                    text.push_str(&e.unescape_and_decode(&reader).map_err(invalid_xml)?);
+                }
+            }
+            Ok(Event::CData(ref e)) => {
+                if let Some((_, _, text)) = open.last_mut() {
+                    text.push_str(std::str::from_utf8(e).map_err(invalid_xml)?);
+                }
+            }
+            Ok(Event::End(_)) => {
+                let (name, map, text) = open.pop().ok_or_else(|| {
+                    DataMappingError::invalid("payload", "Unexpected closing tag".to_string())
+                })?;
+                let element = element_value(map, text);
+                let parent = open.last_mut().map(|(_, map, _)| map).unwrap_or(&mut root);
+                insert(parent, name, element);
+            }
+            Ok(Event::Eof) => break,
+            Err(err) => return Err(invalid_xml(err)),
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    if root.is_empty() {
+        return Err(DataMappingError::invalid(
+            "payload",
+            "XML document has no root element".to_string(),
+        ));
+    }
+
+    Ok(Value::Object(root))
+}
+
+fn name<B: std::io::BufRead>(
+    reader: &Reader<B>,
+    element: &BytesStart,
+) -> Result<String, DataMappingError> {
+    reader
+        .decode(element.local_name())
+        .map(String::from)
+        .map_err(invalid_xml)
+}
+
+fn attributes<B: std::io::BufRead>(
+    reader: &Reader<B>,
+    element: &BytesStart,
+) -> Result<Map<String, Value>, DataMappingError> {
+    let mut attributes = Map::new();
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(invalid_xml)?;
+        let key = reader.decode(attribute.key).map_err(invalid_xml)?;
+        if key == "xmlns" || key.starts_with("xmlns:") {
+            continue;
+        }
+        let local_key = key.rsplit(':').next().unwrap_or(key);
+
+        attributes.insert(
+            format!("@{}", local_key),
+            Value::String(
+                attribute
+                    .unescape_and_decode_value(reader)
+                    .map_err(invalid_xml)?,
+            ),
+        );
+    }
+
+    Ok(attributes)
+}
+
+fn element_value(mut map: Map<String, Value>, text: String) -> Value {
+    if map.is_empty() {
+        Value::String(text)
+    } else {
+        if !text.is_empty() {
+            map.insert("#text".to_string(), Value::String(text));
+        }
+        Value::Object(map)
+    }
+}
+
+fn insert(parent: &mut Map<String, Value>, name: String, value: Value) {
+    // Element values are never arrays, so an array here holds repeated elements
+    let value = match parent.remove(&name) {
+        None => value,
+        Some(Value::Array(mut values)) => {
+            values.push(value);
+            Value::Array(values)
+        }
+        Some(existing) => Value::Array(vec![existing, value]),
+    };
+    parent.insert(name, value);
+}
+
+fn invalid_xml<E: std::fmt::Display>(err: E) -> DataMappingError {
+    DataMappingError::invalid("payload", format!("Invalid XML: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// Verify that elements, attributes, repeated elements and namespaced names are read into
+    /// the tree
+    #[test]
+    fn test_to_json() {
+        let xml = br#"<?xml version="1.0"?>
+            <erp:Batch xmlns:erp="urn:example">
+                <erp:Number>B-100</erp:Number>
+                <Weight unit="lb">12.5</Weight>
+                <Parent>B-1</Parent>
+                <Parent>B-2</Parent>
+                <Empty/>
+            </erp:Batch>"#;
+
+        assert_eq!(
+            to_json(xml).expect("Failed to read XML"),
+            json!({
+                "Batch": {
+                    "Number": "B-100",
+                    "Weight": {"@unit": "lb", "#text": "12.5"},
+                    "Parent": ["B-1", "B-2"],
+                    "Empty": "",
+                }
+            })
+        );
+    }
+
+    /// Verify that malformed documents are rejected
+    #[test]
+    fn test_to_json_invalid() {
+        assert!(to_json(b"<Batch><Number>1</Batch>").is_err());
+        assert!(to_json(b"").is_err());
+    }
+}
@@ -78,6 +78,29 @@ pub fn validate_gdsn_3_1(
     Ok(())
 }
 
+/// Checks whether an XML file validates against the GridCatalogueItemNotification.xsd XML
+/// schema definition, which describes the envelope of the GDSN 3.1 Catalogue Item Notification
+/// (CIN) messages published to a data pool. The schema is bundled with Grid and imports no other
+/// schemas. An error will be returned if the file fails to validate for any reason.
+///
+/// For more information about CIN messages, view the documentation here:
+///     https://www.gs1.org/docs/gdsn/3.1/gdsn_3_1_operations_manual_i2.pdf
+///
+/// # Arguments
+///
+/// * `data` - A path to an XML file or an XML string to be validated.
+/// * `is_path` - Whether the data provided is a path or a string.
+/// * `schema_dir` - References a path to the directory containing schema files
+///
+pub fn validate_gdsn_cin_3_1(
+    data: &str,
+    is_path: bool,
+    schema_dir: &str,
+) -> Result<(), DataValidationError> {
+    validate_xml(data, is_path, Schema::GdsnCinV3_1, schema_dir)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +206,46 @@ mod tests {
 
         assert_eq!(result.unwrap_err().to_string(), expected_error.to_string());
     }
+
+    // GDSN CIN
+    /// Test a path to a valid GDSN 3.1 CIN message validates successfully
+    #[test]
+    fn test_validate_gdsn_cin_3_1_path() {
+        let mut test_cin_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_cin_path.push("src/data_validation/test_files/gdsn_cin.xml");
+
+        let path_str = test_cin_path.to_str().unwrap();
+        let mut schema_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        schema_dir.push("src/data_validation/xml/xsd/product");
+        let schema_dir = schema_dir
+            .into_os_string()
+            .into_string()
+            .expect("Unable to convert product schema dir to string");
+
+        let result = validate_gdsn_cin_3_1(path_str, true, &schema_dir);
+
+        assert!(result.is_ok());
+    }
+
+    /// Test a path to a GDSN 3.1 CIN message with an invalid data recipient doesn't validate
+    #[test]
+    fn test_validate_gdsn_cin_3_1_path_invalid() {
+        let mut test_cin_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_cin_path.push("src/data_validation/test_files/gdsn_cin_invalid.xml");
+
+        let path_str = test_cin_path.to_str().unwrap();
+        let mut schema_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        schema_dir.push("src/data_validation/xml/xsd/product");
+        let schema_dir = schema_dir
+            .into_os_string()
+            .into_string()
+            .expect("Unable to convert product schema dir to string");
+
+        let result = validate_gdsn_cin_3_1(path_str, true, &schema_dir);
+
+        let expected_error =
+            InvalidArgumentError::new(path_str.to_string(), "file fails to validate".to_string());
+
+        assert_eq!(result.unwrap_err().to_string(), expected_error.to_string());
+    }
 }
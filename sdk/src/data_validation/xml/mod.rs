@@ -25,6 +25,7 @@ use xml_ffi::{XmlDoc, XmlSchema, XmlSchemaParserCtxt};
 pub enum Schema {
     OrderXmlV3_4,
     GdsnXmlV3_1,
+    GdsnCinV3_1,
 }
 
 /// Checks whether an XML file validates against a specified schema. This
@@ -131,6 +132,26 @@ fn load_schema(schema_type: Schema, schema_dir: &str) -> Result<XmlSchema, DataV
                 })?
                 .into_bytes()
         }
+        Schema::GdsnCinV3_1 => {
+            schema_dir_path.push("GridCatalogueItemNotification.xsd");
+            if !schema_dir_path.exists() {
+                return Err(DataValidationError::InvalidArgument(
+                    InvalidArgumentError::new(
+                        "xml validation".to_string(),
+                        format!(
+                            "Cannot validate XML file against XSD file: XSD file {} is \
+                            missing.",
+                            schema_dir_path.to_string_lossy()
+                        ),
+                    ),
+                ));
+            }
+            fs::read_to_string(schema_dir_path.as_path())
+                .map_err(|err| {
+                    DataValidationError::Internal(InternalError::from_source(Box::new(err)))
+                })?
+                .into_bytes()
+        }
     };
 
     let schema_parser_ctxt =
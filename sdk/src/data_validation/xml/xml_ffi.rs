@@ -51,7 +51,7 @@ impl XmlSchema {
                     .map_err(|err| InternalError::from_source(Box::new(err)))?;
                 schema_ptr
             }
-            Schema::GdsnXmlV3_1 => unsafe { xmlSchemaParse(parser.as_ptr()) },
+            Schema::GdsnXmlV3_1 | Schema::GdsnCinV3_1 => unsafe { xmlSchemaParse(parser.as_ptr()) },
         };
         if schema_ptr.is_null() {
             return Err(InternalError::with_message(
@@ -0,0 +1,22 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A persistent queue of the commit events the daemon could not record in its database.
+//!
+//! An event that fails to be recorded is kept, with its state changes, until it is retried
+//! successfully, so the off-chain records never silently diverge from chain state. The events
+//! of each service are retried in the order they were received; a later event of the service is
+//! queued behind the failed one rather than recorded ahead of it.
+
+pub mod store;
@@ -0,0 +1,310 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod models;
+mod operations;
+pub(crate) mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{EventQueueStore, EventQueueStoreError, QueuedEvent};
+use crate::commits::store::CommitEvent;
+use crate::error::ResourceTemporarilyUnavailableError;
+
+use operations::add_queued_event::AddQueuedEventOperation as _;
+use operations::list_queued_events::ListQueuedEventsOperation as _;
+use operations::record_queued_event_failure::RecordQueuedEventFailureOperation as _;
+use operations::remove_queued_event::RemoveQueuedEventOperation as _;
+use operations::EventQueueStoreOperations;
+
+#[derive(Clone)]
+pub struct DieselEventQueueStore<C: diesel::Connection + 'static> {
+    connection_pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection> DieselEventQueueStore<C> {
+    pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselEventQueueStore { connection_pool }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl EventQueueStore for DieselEventQueueStore<diesel::pg::PgConnection> {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_queued_event(event, error, queued_at)
+    }
+
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_queued_events()
+    }
+
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .record_queued_event_failure(queue_id, error, attempted_at)
+    }
+
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_queued_event(queue_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl EventQueueStore for DieselEventQueueStore<diesel::sqlite::SqliteConnection> {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_queued_event(event, error, queued_at)
+    }
+
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_queued_events()
+    }
+
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .record_queued_event_failure(queue_id, error, attempted_at)
+    }
+
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_queued_event(queue_id)
+    }
+}
+
+pub struct DieselConnectionEventQueueStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+}
+
+impl<'a, C> DieselConnectionEventQueueStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionEventQueueStore { connection }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> EventQueueStore for DieselConnectionEventQueueStore<'a, diesel::pg::PgConnection> {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).add_queued_event(event, error, queued_at)
+    }
+
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).list_queued_events()
+    }
+
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).record_queued_event_failure(
+            queue_id,
+            error,
+            attempted_at,
+        )
+    }
+
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).remove_queued_event(queue_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> EventQueueStore for DieselConnectionEventQueueStore<'a, diesel::sqlite::SqliteConnection> {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).add_queued_event(event, error, queued_at)
+    }
+
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).list_queued_events()
+    }
+
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).record_queued_event_failure(
+            queue_id,
+            error,
+            attempted_at,
+        )
+    }
+
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        EventQueueStoreOperations::new(self.connection).remove_queued_event(queue_id)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+    use diesel::Connection;
+
+    use crate::commits::store::StateChange;
+    use crate::migrations::run_sqlite_migrations;
+
+    fn commit_event(id: &str, service_id: Option<&str>) -> CommitEvent {
+        CommitEvent {
+            service_id: service_id.map(String::from),
+            id: id.to_string(),
+            height: Some(7),
+            state_changes: vec![
+                StateChange::Set {
+                    key: "11bb0e01aa".to_string(),
+                    value: vec![1, 2, 3],
+                },
+                StateChange::Delete {
+                    key: "11bb0e01bb".to_string(),
+                },
+            ],
+        }
+    }
+
+    /// Verify that queued events are listed in the order they were queued with their state
+    /// changes, that failures are counted, and that removed events are no longer listed
+    #[test]
+    fn test_add_list_remove_queued_events() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionEventQueueStore::new(&conn);
+
+        let first = store
+            .add_queued_event(&commit_event("commit-1", None), "Database is down", 10)
+            .expect("Unable to queue event");
+        let second = store
+            .add_queued_event(
+                &CommitEvent {
+                    state_changes: vec![],
+                    ..commit_event("commit-2", Some("01234-ABCD::gsAA"))
+                },
+                "Commit 1 is queued",
+                20,
+            )
+            .expect("Unable to queue event");
+        assert!(first < second);
+
+        store
+            .record_queued_event_failure(first, "Invalid agent", 30)
+            .expect("Unable to record failure");
+
+        let queued = store.list_queued_events().expect("Unable to list events");
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].queue_id, first);
+        assert_eq!(queued[0].event.id, "commit-1");
+        assert_eq!(queued[0].event.height, Some(7));
+        assert!(queued[0].event.state_changes == commit_event("commit-1", None).state_changes);
+        assert_eq!(queued[0].attempts, 2);
+        assert_eq!(queued[0].last_error, "Invalid agent");
+        assert_eq!((queued[0].queued_at, queued[0].last_attempted_at), (10, 30));
+        assert_eq!(
+            queued[1].event.service_id.as_deref(),
+            Some("01234-ABCD::gsAA")
+        );
+        assert!(queued[1].event.state_changes.is_empty());
+
+        store
+            .remove_queued_event(first)
+            .expect("Unable to remove event");
+        let queued = store.list_queued_events().expect("Unable to list events");
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].queue_id, second);
+
+        assert!(matches!(
+            store.remove_queued_event(first),
+            Err(EventQueueStoreError::NotFoundError(_))
+        ));
+        assert!(matches!(
+            store.record_queued_event_failure(first, "Invalid agent", 40),
+            Err(EventQueueStoreError::NotFoundError(_))
+        ));
+    }
+}
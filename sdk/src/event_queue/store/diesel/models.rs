@@ -0,0 +1,87 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::commits::store::StateChange;
+use crate::event_queue::store::diesel::schema::*;
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "event_queue"]
+pub struct NewQueuedEventModel {
+    pub commit_id: String,
+    pub service_id: Option<String>,
+    pub height: Option<i64>,
+    pub attempts: i64,
+    pub last_error: String,
+    pub queued_at: i64,
+    pub last_attempted_at: i64,
+}
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct QueuedEventModel {
+    pub queue_id: i64,
+    pub commit_id: String,
+    pub service_id: Option<String>,
+    pub height: Option<i64>,
+    pub attempts: i64,
+    pub last_error: String,
+    pub queued_at: i64,
+    pub last_attempted_at: i64,
+}
+
+/// A state change of a queued event; a `None` value is a delete
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "event_queue_state_change"]
+pub struct NewQueuedStateChangeModel {
+    pub queue_id: i64,
+    pub position: i64,
+    pub key: String,
+    pub value: Option<Vec<u8>>,
+}
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct QueuedStateChangeModel {
+    pub id: i64,
+    pub queue_id: i64,
+    pub position: i64,
+    pub key: String,
+    pub value: Option<Vec<u8>>,
+}
+
+impl NewQueuedStateChangeModel {
+    pub fn new(queue_id: i64, position: usize, state_change: &StateChange) -> Self {
+        let (key, value) = match state_change {
+            StateChange::Set { key, value } => (key.clone(), Some(value.clone())),
+            StateChange::Delete { key } => (key.clone(), None),
+        };
+
+        Self {
+            queue_id,
+            position: position as i64,
+            key,
+            value,
+        }
+    }
+}
+
+impl From<QueuedStateChangeModel> for StateChange {
+    fn from(model: QueuedStateChangeModel) -> Self {
+        match model.value {
+            Some(value) => StateChange::Set {
+                key: model.key,
+                value,
+            },
+            None => StateChange::Delete { key: model.key },
+        }
+    }
+}
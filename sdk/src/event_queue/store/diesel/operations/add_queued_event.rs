@@ -0,0 +1,118 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::EventQueueStoreOperations;
+
+use crate::commits::store::CommitEvent;
+use crate::event_queue::store::{
+    diesel::{
+        models::{NewQueuedEventModel, NewQueuedStateChangeModel},
+        schema::{event_queue, event_queue_state_change},
+    },
+    EventQueueStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::event_queue::store::diesel) trait AddQueuedEventOperation {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddQueuedEventOperation for EventQueueStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        self.conn.transaction::<_, EventQueueStoreError, _>(|| {
+            insert_into(event_queue::table)
+                .values(new_queued_event(event, error, queued_at))
+                .execute(self.conn)?;
+            let queue_id = event_queue::table
+                .select(diesel::dsl::max(event_queue::queue_id))
+                .first::<Option<i64>>(self.conn)?
+                .unwrap_or_default();
+
+            let state_changes = new_state_changes(queue_id, event);
+            if !state_changes.is_empty() {
+                insert_into(event_queue_state_change::table)
+                    .values(state_changes)
+                    .execute(self.conn)?;
+            }
+
+            Ok(queue_id)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddQueuedEventOperation
+    for EventQueueStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        self.conn.transaction::<_, EventQueueStoreError, _>(|| {
+            insert_into(event_queue::table)
+                .values(new_queued_event(event, error, queued_at))
+                .execute(self.conn)?;
+            let queue_id = event_queue::table
+                .select(diesel::dsl::max(event_queue::queue_id))
+                .first::<Option<i64>>(self.conn)?
+                .unwrap_or_default();
+
+            let state_changes = new_state_changes(queue_id, event);
+            if !state_changes.is_empty() {
+                insert_into(event_queue_state_change::table)
+                    .values(state_changes)
+                    .execute(self.conn)?;
+            }
+
+            Ok(queue_id)
+        })
+    }
+}
+
+fn new_queued_event(event: &CommitEvent, error: &str, queued_at: i64) -> NewQueuedEventModel {
+    NewQueuedEventModel {
+        commit_id: event.id.clone(),
+        service_id: event.service_id.clone(),
+        height: event.height.map(|height| height as i64),
+        attempts: 1,
+        last_error: error.to_string(),
+        queued_at,
+        last_attempted_at: queued_at,
+    }
+}
+
+fn new_state_changes(queue_id: i64, event: &CommitEvent) -> Vec<NewQueuedStateChangeModel> {
+    event
+        .state_changes
+        .iter()
+        .enumerate()
+        .map(|(position, state_change)| {
+            NewQueuedStateChangeModel::new(queue_id, position, state_change)
+        })
+        .collect()
+}
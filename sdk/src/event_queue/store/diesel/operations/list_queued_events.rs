@@ -0,0 +1,100 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::EventQueueStoreOperations;
+
+use crate::commits::store::{CommitEvent, StateChange};
+use crate::event_queue::store::{
+    diesel::{
+        models::{QueuedEventModel, QueuedStateChangeModel},
+        schema::{event_queue, event_queue_state_change},
+    },
+    EventQueueStoreError, QueuedEvent,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::event_queue::store::diesel) trait ListQueuedEventsOperation {
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListQueuedEventsOperation for EventQueueStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        let events = event_queue::table
+            .order(event_queue::queue_id.asc())
+            .load::<QueuedEventModel>(self.conn)?;
+        let state_changes = event_queue_state_change::table
+            .order((
+                event_queue_state_change::queue_id.asc(),
+                event_queue_state_change::position.asc(),
+            ))
+            .load::<QueuedStateChangeModel>(self.conn)?;
+
+        Ok(to_queued_events(events, state_changes))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListQueuedEventsOperation
+    for EventQueueStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        let events = event_queue::table
+            .order(event_queue::queue_id.asc())
+            .load::<QueuedEventModel>(self.conn)?;
+        let state_changes = event_queue_state_change::table
+            .order((
+                event_queue_state_change::queue_id.asc(),
+                event_queue_state_change::position.asc(),
+            ))
+            .load::<QueuedStateChangeModel>(self.conn)?;
+
+        Ok(to_queued_events(events, state_changes))
+    }
+}
+
+fn to_queued_events(
+    events: Vec<QueuedEventModel>,
+    state_changes: Vec<QueuedStateChangeModel>,
+) -> Vec<QueuedEvent> {
+    let mut state_changes_by_event: HashMap<i64, Vec<StateChange>> = HashMap::new();
+    for model in state_changes {
+        state_changes_by_event
+            .entry(model.queue_id)
+            .or_default()
+            .push(StateChange::from(model));
+    }
+
+    events
+        .into_iter()
+        .map(|model| QueuedEvent {
+            queue_id: model.queue_id,
+            event: CommitEvent {
+                service_id: model.service_id,
+                id: model.commit_id,
+                height: model.height.map(|height| height as u64),
+                state_changes: state_changes_by_event
+                    .remove(&model.queue_id)
+                    .unwrap_or_default(),
+            },
+            attempts: model.attempts,
+            last_error: model.last_error,
+            queued_at: model.queued_at,
+            last_attempted_at: model.last_attempted_at,
+        })
+        .collect()
+}
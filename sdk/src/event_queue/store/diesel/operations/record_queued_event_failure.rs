@@ -0,0 +1,76 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::EventQueueStoreOperations;
+
+use crate::event_queue::store::{diesel::schema::event_queue, EventQueueStoreError};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::event_queue::store::diesel) trait RecordQueuedEventFailureOperation {
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> RecordQueuedEventFailureOperation
+    for EventQueueStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        match update(event_queue::table.find(queue_id))
+            .set((
+                event_queue::attempts.eq(event_queue::attempts + 1),
+                event_queue::last_error.eq(error),
+                event_queue::last_attempted_at.eq(attempted_at),
+            ))
+            .execute(self.conn)?
+        {
+            0 => Err(EventQueueStoreError::NotFoundError(queue_id.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> RecordQueuedEventFailureOperation
+    for EventQueueStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        match update(event_queue::table.find(queue_id))
+            .set((
+                event_queue::attempts.eq(event_queue::attempts + 1),
+                event_queue::last_error.eq(error),
+                event_queue::last_attempted_at.eq(attempted_at),
+            ))
+            .execute(self.conn)?
+        {
+            0 => Err(EventQueueStoreError::NotFoundError(queue_id.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::EventQueueStoreOperations;
+
+use crate::event_queue::store::{
+    diesel::schema::{event_queue, event_queue_state_change},
+    EventQueueStoreError,
+};
+
+use diesel::{dsl::delete, prelude::*};
+
+pub(in crate::event_queue::store::diesel) trait RemoveQueuedEventOperation {
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> RemoveQueuedEventOperation for EventQueueStoreOperations<'a, diesel::pg::PgConnection> {
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        self.conn.transaction::<_, EventQueueStoreError, _>(|| {
+            delete(
+                event_queue_state_change::table
+                    .filter(event_queue_state_change::queue_id.eq(queue_id)),
+            )
+            .execute(self.conn)?;
+            match delete(event_queue::table.find(queue_id)).execute(self.conn)? {
+                0 => Err(EventQueueStoreError::NotFoundError(queue_id.to_string())),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> RemoveQueuedEventOperation
+    for EventQueueStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        // SQLite only cascades deletes if foreign keys are enabled on the connection
+        self.conn.transaction::<_, EventQueueStoreError, _>(|| {
+            delete(
+                event_queue_state_change::table
+                    .filter(event_queue_state_change::queue_id.eq(queue_id)),
+            )
+            .execute(self.conn)?;
+            match delete(event_queue::table.find(queue_id)).execute(self.conn)? {
+                0 => Err(EventQueueStoreError::NotFoundError(queue_id.to_string())),
+                _ => Ok(()),
+            }
+        })
+    }
+}
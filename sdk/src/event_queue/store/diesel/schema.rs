@@ -0,0 +1,38 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    event_queue (queue_id) {
+        queue_id -> Int8,
+        commit_id -> Text,
+        service_id -> Nullable<Text>,
+        height -> Nullable<Int8>,
+        attempts -> Int8,
+        last_error -> Text,
+        queued_at -> Int8,
+        last_attempted_at -> Int8,
+    }
+}
+
+table! {
+    event_queue_state_change (id) {
+        id -> Int8,
+        queue_id -> Int8,
+        position -> Int8,
+        key -> Text,
+        value -> Nullable<Binary>,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(event_queue, event_queue_state_change);
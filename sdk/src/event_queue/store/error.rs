@@ -0,0 +1,91 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+use diesel::r2d2::PoolError;
+#[cfg(feature = "diesel")]
+use diesel::result::{DatabaseErrorKind, Error as diesel_error};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "diesel")]
+use crate::error::ConstraintViolationType;
+use crate::error::{ConstraintViolationError, InternalError, ResourceTemporarilyUnavailableError};
+
+/// Represents EventQueueStore errors
+#[derive(Debug)]
+pub enum EventQueueStoreError {
+    InternalError(InternalError),
+    ConstraintViolationError(ConstraintViolationError),
+    ResourceTemporarilyUnavailableError(ResourceTemporarilyUnavailableError),
+    NotFoundError(String),
+}
+
+impl Error for EventQueueStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EventQueueStoreError::InternalError(err) => Some(err),
+            EventQueueStoreError::ConstraintViolationError(err) => Some(err),
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(err) => Some(err),
+            EventQueueStoreError::NotFoundError(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for EventQueueStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventQueueStoreError::InternalError(err) => err.fmt(f),
+            EventQueueStoreError::ConstraintViolationError(err) => err.fmt(f),
+            EventQueueStoreError::ResourceTemporarilyUnavailableError(err) => err.fmt(f),
+            EventQueueStoreError::NotFoundError(ref s) => {
+                write!(f, "Queued event not found: {}", s)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<diesel_error> for EventQueueStoreError {
+    fn from(err: diesel_error) -> EventQueueStoreError {
+        match err {
+            diesel_error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                EventQueueStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::Unique,
+                        Box::new(err),
+                    ),
+                )
+            }
+            diesel_error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                EventQueueStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::ForeignKey,
+                        Box::new(err),
+                    ),
+                )
+            }
+            _ => EventQueueStoreError::InternalError(InternalError::from_source(Box::new(err))),
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<PoolError> for EventQueueStoreError {
+    fn from(err: PoolError) -> EventQueueStoreError {
+        EventQueueStoreError::ResourceTemporarilyUnavailableError(
+            ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+        )
+    }
+}
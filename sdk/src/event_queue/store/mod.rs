@@ -0,0 +1,109 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+pub(crate) mod diesel;
+mod error;
+
+use crate::commits::store::CommitEvent;
+
+#[cfg(feature = "diesel")]
+pub use self::diesel::{DieselConnectionEventQueueStore, DieselEventQueueStore};
+pub use error::EventQueueStoreError;
+
+/// A commit event waiting to be recorded. Times are seconds since the epoch.
+#[derive(Clone)]
+pub struct QueuedEvent {
+    /// Orders the queued events by when they were queued
+    pub queue_id: i64,
+    pub event: CommitEvent,
+    /// The number of times recording the event has failed
+    pub attempts: i64,
+    /// The reason recording the event last failed
+    pub last_error: String,
+    pub queued_at: i64,
+    pub last_attempted_at: i64,
+}
+
+pub trait EventQueueStore {
+    /// Adds an event that could not be recorded to the end of the queue, returning its
+    /// queue_id
+    ///
+    /// # Arguments
+    ///
+    ///  * `event` - The event to be queued
+    ///  * `error` - The reason the event could not be recorded
+    ///  * `queued_at` - The time the event was queued
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError>;
+
+    /// Lists the queued events, in the order they were queued
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError>;
+
+    /// Records another failed attempt at recording a queued event
+    ///
+    /// # Arguments
+    ///
+    ///  * `queue_id` - The queue_id of the event
+    ///  * `error` - The reason the event could not be recorded
+    ///  * `attempted_at` - The time of the attempt
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError>;
+
+    /// Removes an event from the queue, once it has been recorded or is to be discarded
+    ///
+    /// # Arguments
+    ///
+    ///  * `queue_id` - The queue_id of the event
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError>;
+}
+
+impl<ES> EventQueueStore for Box<ES>
+where
+    ES: EventQueueStore + ?Sized,
+{
+    fn add_queued_event(
+        &self,
+        event: &CommitEvent,
+        error: &str,
+        queued_at: i64,
+    ) -> Result<i64, EventQueueStoreError> {
+        (**self).add_queued_event(event, error, queued_at)
+    }
+
+    fn list_queued_events(&self) -> Result<Vec<QueuedEvent>, EventQueueStoreError> {
+        (**self).list_queued_events()
+    }
+
+    fn record_queued_event_failure(
+        &self,
+        queue_id: i64,
+        error: &str,
+        attempted_at: i64,
+    ) -> Result<(), EventQueueStoreError> {
+        (**self).record_queued_event_failure(queue_id, error, attempted_at)
+    }
+
+    fn remove_queued_event(&self, queue_id: i64) -> Result<(), EventQueueStoreError> {
+        (**self).remove_queued_event(queue_id)
+    }
+}
@@ -14,7 +14,11 @@
 
 use std::error::Error;
 use std::fmt;
-#[cfg(feature = "batch-store")]
+#[cfg(any(
+    feature = "batch-store",
+    feature = "mfg-batch-change-capture",
+    feature = "webhooks"
+))]
 use std::fmt::Write;
 
 use serde::de;
@@ -25,7 +29,11 @@ use serde::Deserializer;
 /// # Arguments
 ///
 ///  * `bytes`: the byte array to convert
-#[cfg(feature = "batch-store")]
+#[cfg(any(
+    feature = "batch-store",
+    feature = "mfg-batch-change-capture",
+    feature = "webhooks"
+))]
 pub fn to_hex(bytes: &[u8]) -> String {
     let mut buf = String::new();
     for b in bytes {
@@ -0,0 +1,140 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The daemon ingests CSV, GDSN and EDI files dropped into a watched directory or SFTP location.
+//! Each file is run through the importer for its kind, and what became of it is recorded in the
+//! ingestion log.
+
+pub mod store;
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::InvalidArgumentError;
+
+/// The kinds of file that can be ingested
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// Rows of mfg_batches, read through a data mapping with `format: csv`
+    Csv,
+    /// GDSN 3.1 trade items, submitted as products
+    Gdsn,
+    /// X12 856 or EDIFACT DESADV ship notices, read through a data mapping with `format: edi`
+    Edi,
+}
+
+impl FileKind {
+    /// Tells the kind of a file from its extension, ignoring case
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(FileKind::Csv),
+            "xml" => Some(FileKind::Gdsn),
+            "edi" | "x12" | "856" | "edifact" | "desadv" => Some(FileKind::Edi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileKind::Csv => "csv",
+            FileKind::Gdsn => "gdsn",
+            FileKind::Edi => "edi",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FileKind {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(FileKind::Csv),
+            "gdsn" => Ok(FileKind::Gdsn),
+            "edi" => Ok(FileKind::Edi),
+            _ => Err(InvalidArgumentError::new(
+                "kind".to_string(),
+                format!("Unknown ingestion file kind: {}", s),
+            )),
+        }
+    }
+}
+
+/// What became of an ingested file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IngestionStatus {
+    /// The file's transactions were stored to be submitted
+    Processed,
+    /// The file could not be read, imported or submitted
+    Failed,
+}
+
+impl IngestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IngestionStatus::Processed => "processed",
+            IngestionStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for IngestionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IngestionStatus {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "processed" => Ok(IngestionStatus::Processed),
+            "failed" => Ok(IngestionStatus::Failed),
+            _ => Err(InvalidArgumentError::new(
+                "status".to_string(),
+                format!("Unknown ingestion status: {}", s),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that file kinds are told by extension, whatever its case
+    #[test]
+    fn test_file_kind_from_file_name() {
+        assert_eq!(FileKind::from_file_name("batches.csv"), Some(FileKind::Csv));
+        assert_eq!(
+            FileKind::from_file_name("trade_items.XML"),
+            Some(FileKind::Gdsn)
+        );
+        assert_eq!(FileKind::from_file_name("asn.856"), Some(FileKind::Edi));
+        assert_eq!(
+            FileKind::from_file_name("notice.edifact"),
+            Some(FileKind::Edi)
+        );
+        assert_eq!(FileKind::from_file_name("notes.txt"), None);
+        assert_eq!(FileKind::from_file_name("csv"), None);
+    }
+}
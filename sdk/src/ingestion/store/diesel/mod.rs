@@ -0,0 +1,223 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod models;
+mod operations;
+pub(crate) mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{IngestionLogEntry, IngestionLogStore, IngestionLogStoreError};
+use crate::error::ResourceTemporarilyUnavailableError;
+use crate::ingestion::IngestionStatus;
+
+use operations::add_ingestion_log_entry::AddIngestionLogEntryOperation as _;
+use operations::list_ingestion_log_entries::ListIngestionLogEntriesOperation as _;
+use operations::IngestionLogStoreOperations;
+
+#[derive(Clone)]
+pub struct DieselIngestionLogStore<C: diesel::Connection + 'static> {
+    connection_pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection> DieselIngestionLogStore<C> {
+    pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselIngestionLogStore { connection_pool }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl IngestionLogStore for DieselIngestionLogStore<diesel::pg::PgConnection> {
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_ingestion_log_entry(entry)
+    }
+
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_ingestion_log_entries(status, offset, limit)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl IngestionLogStore for DieselIngestionLogStore<diesel::sqlite::SqliteConnection> {
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_ingestion_log_entry(entry)
+    }
+
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_ingestion_log_entries(status, offset, limit)
+    }
+}
+
+pub struct DieselConnectionIngestionLogStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+}
+
+impl<'a, C> DieselConnectionIngestionLogStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionIngestionLogStore { connection }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> IngestionLogStore for DieselConnectionIngestionLogStore<'a, diesel::pg::PgConnection> {
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(self.connection).add_ingestion_log_entry(entry)
+    }
+
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(self.connection)
+            .list_ingestion_log_entries(status, offset, limit)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> IngestionLogStore
+    for DieselConnectionIngestionLogStore<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(self.connection).add_ingestion_log_entry(entry)
+    }
+
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        IngestionLogStoreOperations::new(self.connection)
+            .list_ingestion_log_entries(status, offset, limit)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+    use diesel::Connection;
+
+    use crate::ingestion::FileKind;
+    use crate::migrations::run_sqlite_migrations;
+
+    fn entry(file_name: &str, status: IngestionStatus, finished_at: i64) -> IngestionLogEntry {
+        IngestionLogEntry {
+            file_name: file_name.to_string(),
+            source: "/var/lib/grid/ingest".to_string(),
+            kind: FileKind::from_file_name(file_name),
+            status,
+            message: match status {
+                IngestionStatus::Processed => None,
+                IngestionStatus::Failed => Some("Schema gs1_mfg_batch is not defined".to_string()),
+            },
+            correlation_id: match status {
+                IngestionStatus::Processed => Some(format!("correlation-{}", finished_at)),
+                IngestionStatus::Failed => None,
+            },
+            started_at: finished_at - 1,
+            finished_at,
+        }
+    }
+
+    /// Verify that entries are listed most recent first, can be filtered by status, and keep
+    /// files whose kind is not known
+    #[test]
+    fn test_ingestion_log() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionIngestionLogStore::new(&conn);
+
+        store
+            .add_ingestion_log_entry(entry("batches.csv", IngestionStatus::Processed, 10))
+            .unwrap();
+        store
+            .add_ingestion_log_entry(entry("notes.txt", IngestionStatus::Failed, 20))
+            .unwrap();
+        store
+            .add_ingestion_log_entry(entry("asn.edi", IngestionStatus::Processed, 30))
+            .unwrap();
+
+        assert_eq!(
+            store
+                .list_ingestion_log_entries(Some(IngestionStatus::Processed), 0, 10)
+                .unwrap(),
+            vec![
+                entry("asn.edi", IngestionStatus::Processed, 30),
+                entry("batches.csv", IngestionStatus::Processed, 10)
+            ]
+        );
+
+        let failed = store.list_ingestion_log_entries(None, 1, 1).unwrap();
+        assert_eq!(
+            failed,
+            vec![entry("notes.txt", IngestionStatus::Failed, 20)]
+        );
+        assert_eq!(failed[0].kind, None);
+    }
+}
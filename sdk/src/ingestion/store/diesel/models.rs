@@ -0,0 +1,84 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use crate::error::InternalError;
+use crate::ingestion::store::{diesel::schema::*, IngestionLogEntry, IngestionLogStoreError};
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "ingestion_log"]
+pub struct NewIngestionLogEntryModel {
+    pub file_name: String,
+    pub source: String,
+    pub kind: Option<String>,
+    pub status: String,
+    pub message: Option<String>,
+    pub correlation_id: Option<String>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct IngestionLogEntryModel {
+    pub id: i64,
+    pub file_name: String,
+    pub source: String,
+    pub kind: Option<String>,
+    pub status: String,
+    pub message: Option<String>,
+    pub correlation_id: Option<String>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+impl From<IngestionLogEntry> for NewIngestionLogEntryModel {
+    fn from(entry: IngestionLogEntry) -> Self {
+        Self {
+            file_name: entry.file_name,
+            source: entry.source,
+            kind: entry.kind.map(|kind| kind.to_string()),
+            status: entry.status.to_string(),
+            message: entry.message,
+            correlation_id: entry.correlation_id,
+            started_at: entry.started_at,
+            finished_at: entry.finished_at,
+        }
+    }
+}
+
+impl TryFrom<IngestionLogEntryModel> for IngestionLogEntry {
+    type Error = IngestionLogStoreError;
+
+    fn try_from(model: IngestionLogEntryModel) -> Result<Self, Self::Error> {
+        let to_internal =
+            |err| IngestionLogStoreError::InternalError(InternalError::from_source(Box::new(err)));
+
+        Ok(Self {
+            kind: model
+                .kind
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(to_internal)?,
+            status: model.status.parse().map_err(to_internal)?,
+            file_name: model.file_name,
+            source: model.source,
+            message: model.message,
+            correlation_id: model.correlation_id,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+        })
+    }
+}
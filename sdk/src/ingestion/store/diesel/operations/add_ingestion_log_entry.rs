@@ -0,0 +1,61 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::IngestionLogStoreOperations;
+
+use crate::ingestion::store::{
+    diesel::{models::NewIngestionLogEntryModel, schema::ingestion_log},
+    IngestionLogEntry, IngestionLogStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::ingestion::store::diesel) trait AddIngestionLogEntryOperation {
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddIngestionLogEntryOperation
+    for IngestionLogStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        insert_into(ingestion_log::table)
+            .values(NewIngestionLogEntryModel::from(entry))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(IngestionLogStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddIngestionLogEntryOperation
+    for IngestionLogStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        insert_into(ingestion_log::table)
+            .values(NewIngestionLogEntryModel::from(entry))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(IngestionLogStoreError::from)
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use super::IngestionLogStoreOperations;
+
+use crate::ingestion::{
+    store::{
+        diesel::{models::IngestionLogEntryModel, schema::ingestion_log},
+        IngestionLogEntry, IngestionLogStoreError,
+    },
+    IngestionStatus,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::ingestion::store::diesel) trait ListIngestionLogEntriesOperation {
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListIngestionLogEntriesOperation
+    for IngestionLogStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        let mut query = ingestion_log::table
+            .into_boxed()
+            .order(ingestion_log::id.desc())
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(status) = status {
+            query = query.filter(ingestion_log::status.eq(status.as_str()));
+        }
+
+        query
+            .load::<IngestionLogEntryModel>(self.conn)?
+            .into_iter()
+            .map(IngestionLogEntry::try_from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListIngestionLogEntriesOperation
+    for IngestionLogStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        let mut query = ingestion_log::table
+            .into_boxed()
+            .order(ingestion_log::id.desc())
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(status) = status {
+            query = query.filter(ingestion_log::status.eq(status.as_str()));
+        }
+
+        query
+            .load::<IngestionLogEntryModel>(self.conn)?
+            .into_iter()
+            .map(IngestionLogEntry::try_from)
+            .collect()
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    ingestion_log (id) {
+        id -> Int8,
+        file_name -> Text,
+        source -> Text,
+        kind -> Nullable<Text>,
+        status -> Text,
+        message -> Nullable<Text>,
+        correlation_id -> Nullable<Text>,
+        started_at -> Int8,
+        finished_at -> Int8,
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+use diesel::r2d2::PoolError;
+#[cfg(feature = "diesel")]
+use diesel::result::{DatabaseErrorKind, Error as diesel_error};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "diesel")]
+use crate::error::ConstraintViolationType;
+use crate::error::{ConstraintViolationError, InternalError, ResourceTemporarilyUnavailableError};
+
+/// Represents IngestionLogStore errors
+#[derive(Debug)]
+pub enum IngestionLogStoreError {
+    InternalError(InternalError),
+    ConstraintViolationError(ConstraintViolationError),
+    ResourceTemporarilyUnavailableError(ResourceTemporarilyUnavailableError),
+}
+
+impl Error for IngestionLogStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IngestionLogStoreError::InternalError(err) => Some(err),
+            IngestionLogStoreError::ConstraintViolationError(err) => Some(err),
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for IngestionLogStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IngestionLogStoreError::InternalError(err) => err.fmt(f),
+            IngestionLogStoreError::ConstraintViolationError(err) => err.fmt(f),
+            IngestionLogStoreError::ResourceTemporarilyUnavailableError(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<diesel_error> for IngestionLogStoreError {
+    fn from(err: diesel_error) -> IngestionLogStoreError {
+        match err {
+            diesel_error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                IngestionLogStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::Unique,
+                        Box::new(err),
+                    ),
+                )
+            }
+            diesel_error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                IngestionLogStoreError::ConstraintViolationError(
+                    ConstraintViolationError::from_source_with_violation_type(
+                        ConstraintViolationType::ForeignKey,
+                        Box::new(err),
+                    ),
+                )
+            }
+            _ => IngestionLogStoreError::InternalError(InternalError::from_source(Box::new(err))),
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl From<PoolError> for IngestionLogStoreError {
+    fn from(err: PoolError) -> IngestionLogStoreError {
+        IngestionLogStoreError::ResourceTemporarilyUnavailableError(
+            ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+        )
+    }
+}
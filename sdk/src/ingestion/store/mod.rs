@@ -0,0 +1,87 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+pub(crate) mod diesel;
+mod error;
+
+use super::{FileKind, IngestionStatus};
+
+#[cfg(feature = "diesel")]
+pub use self::diesel::{DieselConnectionIngestionLogStore, DieselIngestionLogStore};
+pub use error::IngestionLogStoreError;
+
+/// A record of what became of an ingested file. Times are seconds since the epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IngestionLogEntry {
+    pub file_name: String,
+    /// The directory or SFTP location the file was found in
+    pub source: String,
+    /// The kind of the file, if its extension is one that is ingested
+    pub kind: Option<FileKind>,
+    pub status: IngestionStatus,
+    /// Why the file failed, or a summary of what was submitted
+    pub message: Option<String>,
+    /// The correlation ID of the submitted batch, if the file was processed
+    pub correlation_id: Option<String>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+pub trait IngestionLogStore {
+    /// Records what became of an ingested file
+    ///
+    /// # Arguments
+    ///
+    ///  * `entry` - The entry to be added
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError>;
+
+    /// Lists the recorded files, most recent first
+    ///
+    /// # Arguments
+    ///
+    ///  * `status` - Only list the files with this status
+    ///  * `offset` - The index of the first entry to return
+    ///  * `limit` - The number of entries to return
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError>;
+}
+
+impl<IS> IngestionLogStore for Box<IS>
+where
+    IS: IngestionLogStore + ?Sized,
+{
+    fn add_ingestion_log_entry(
+        &self,
+        entry: IngestionLogEntry,
+    ) -> Result<(), IngestionLogStoreError> {
+        (**self).add_ingestion_log_entry(entry)
+    }
+
+    fn list_ingestion_log_entries(
+        &self,
+        status: Option<IngestionStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<IngestionLogEntry>, IngestionLogStoreError> {
+        (**self).list_ingestion_log_entries(status, offset, limit)
+    }
+}
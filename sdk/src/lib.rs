@@ -31,6 +31,8 @@ extern crate diesel_migrations;
 #[cfg(feature = "log")]
 extern crate log;
 
+#[cfg(feature = "api-keys")]
+pub mod api_keys;
 #[cfg(feature = "backend")]
 pub mod backend;
 #[cfg(feature = "batch-processor")]
@@ -40,12 +42,20 @@ pub mod batches;
 #[cfg(feature = "client")]
 pub mod client;
 pub mod commits;
+#[cfg(feature = "data-mapping")]
+pub mod data_mapping;
 #[cfg(feature = "data-validation")]
 pub mod data_validation;
 pub mod error;
+#[cfg(feature = "event-queue")]
+pub mod event_queue;
 mod hex;
+#[cfg(feature = "ingestion")]
+pub mod ingestion;
 #[cfg(feature = "location")]
 pub mod location;
+#[cfg(feature = "log-masking")]
+pub mod log_masking;
 pub mod migrations;
 pub mod paging;
 #[cfg(feature = "pike")]
@@ -62,8 +72,14 @@ pub mod purchase_order;
 pub mod rest_api;
 #[cfg(feature = "schema")]
 pub mod schema;
+#[cfg(feature = "sla-timers")]
+pub mod sla;
 pub mod store;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
 #[cfg(feature = "track-and-trace")]
 pub mod track_and_trace;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
 #[cfg(feature = "workflow")]
 pub mod workflow;
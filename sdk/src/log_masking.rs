@@ -0,0 +1,179 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Masking of sensitive property values in logs.
+//!
+//! Property values can hold commercially sensitive data, such as prices or supplier terms, that
+//! should not end up in the logs of the transaction processor or the daemon. A [`LogMask`] is a
+//! deny list of property names; wherever a log line would include the value of a denied
+//! property, [`MASK`] is logged in its place.
+
+use std::collections::HashSet;
+
+/// What the value of a denied property is logged as
+pub const MASK: &str = "****";
+
+/// The names of the properties whose values are masked in logs, compared without regard to case
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogMask {
+    denied: HashSet<String>,
+}
+
+impl LogMask {
+    /// Creates a mask denying the given property names; blank names are ignored
+    pub fn new<I, S>(property_names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        LogMask {
+            denied: property_names
+                .into_iter()
+                .map(|name| name.as_ref().trim().to_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Whether the mask denies no properties, so logs are left as they are
+    pub fn is_empty(&self) -> bool {
+        self.denied.is_empty()
+    }
+
+    /// Whether the value of the named property is masked
+    pub fn is_denied(&self, property_name: &str) -> bool {
+        !self.denied.is_empty() && self.denied.contains(&property_name.to_lowercase())
+    }
+
+    /// Returns the value to log for the named property: the value itself, or [`MASK`] if the
+    /// property is denied
+    pub fn mask_value<'a>(&self, property_name: &str, value: &'a str) -> &'a str {
+        if self.is_denied(property_name) {
+            MASK
+        } else {
+            value
+        }
+    }
+
+    /// Masks the values of the parameters of a URL query string that name a denied property,
+    /// either by the whole parameter name or by its last dot separated part, as in
+    /// `property.price=10`
+    pub fn mask_query(&self, query: &str) -> String {
+        if self.is_empty() {
+            return query.to_string();
+        }
+
+        query
+            .split('&')
+            .map(|parameter| match parameter.split_once('=') {
+                Some((key, _)) if self.is_denied(key.rsplit('.').next().unwrap_or(key)) => {
+                    format!("{}={}", key, MASK)
+                }
+                _ => parameter.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that names are matched without regard to case or surrounding whitespace, and that
+    /// only the values of denied properties are masked
+    #[test]
+    fn test_mask_value() {
+        let mask = LogMask::new(["Price", " supplier ", ""]);
+
+        assert!(!mask.is_empty());
+        assert!(mask.is_denied("price"));
+        assert!(mask.is_denied("SUPPLIER"));
+        assert!(!mask.is_denied("color"));
+        assert!(!mask.is_denied(""));
+        assert_eq!(mask.mask_value("price", "12.50"), MASK);
+        assert_eq!(mask.mask_value("color", "red"), "red");
+
+        assert!(LogMask::default().is_empty());
+        assert_eq!(LogMask::default().mask_value("price", "12.50"), "12.50");
+    }
+
+    /// Verify that the values of query parameters naming a denied property are masked, whether
+    /// named in full or by their last dot separated part, and that other parameters are kept
+    #[test]
+    fn test_mask_query() {
+        let mask = LogMask::new(["price"]);
+
+        assert_eq!(
+            mask.mask_query("service_id=abc&price=10&property.price=12&limit=5&flag"),
+            "service_id=abc&price=****&property.price=****&limit=5&flag"
+        );
+        assert_eq!(mask.mask_query(""), "");
+        assert_eq!(
+            LogMask::default().mask_query("price=10&limit=5"),
+            "price=10&limit=5"
+        );
+    }
+
+    /// Verify that a masked action keeps its allowed property values, including those nested in
+    /// a struct, and logs the mask in place of the denied ones
+    #[cfg(feature = "mfg_batch")]
+    #[test]
+    fn test_masked_action() {
+        use crate::protocol::{
+            mfg_batch::{
+                payload::{Action, MfgBatchCreateActionBuilder},
+                state::MfgBatchNamespace,
+            },
+            schema::state::{DataType, PropertyValueBuilder},
+        };
+
+        let property = |name: &str, value: &str| {
+            PropertyValueBuilder::new()
+                .with_name(name.into())
+                .with_data_type(DataType::String)
+                .with_string_value(value.into())
+                .build()
+                .expect("Failed to build property value")
+        };
+        let terms = PropertyValueBuilder::new()
+            .with_name("terms".into())
+            .with_data_type(DataType::Struct)
+            .with_struct_values(vec![property("incoterm", "FOB"), property("price", "9.75")])
+            .build()
+            .expect("Failed to build property value");
+        let action = Action::MfgBatchCreate(
+            MfgBatchCreateActionBuilder::new()
+                .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+                .with_mfg_batch_id("688955434684".into())
+                .with_owner("org".into())
+                .with_properties(vec![
+                    property("color", "red"),
+                    property("price", "12.50"),
+                    terms,
+                ])
+                .build()
+                .expect("Failed to build action"),
+        );
+
+        let logged = format!("{:?}", action.masked(&LogMask::new(["price"])));
+        assert!(logged.contains("\"red\""));
+        assert!(logged.contains("\"FOB\""));
+        assert!(!logged.contains("12.50"));
+        assert!(!logged.contains("9.75"));
+        assert_eq!(logged.matches(MASK).count(), 2);
+
+        assert_eq!(action.masked(&LogMask::default()), action);
+    }
+}
@@ -0,0 +1,342 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simulates how mfg_batches spread across state addresses under the current addressing scheme.
+//!
+//! Every batch of a GTIN is stored at the GTIN's address, in a single list that is read and
+//! rewritten whenever any batch in it changes. The largest lists are the hot spots that address
+//! splitting or custom address prefixes would need to relieve, so the report leads with them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::protocol::mfg_batch::state::MfgBatchNamespace;
+
+use super::addressing::compute_mfg_batch_address;
+use super::store::{ListMfgBatchFilters, MfgBatchStore, MfgBatchStoreError};
+
+/// The number of mfg_batches read from the store at a time
+const PAGE_SIZE: i64 = 1000;
+
+/// The mfg_batches stored at one state address
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressLoad {
+    pub address: String,
+    /// The first mfg_batch ID seen at the address, such as the GTIN the address is keyed by
+    pub mfg_batch_id: String,
+    /// The number of mfg_batches in the list at the address
+    pub mfg_batches: usize,
+}
+
+/// The number of addresses whose lists hold between `min` and `max` mfg_batches
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListSizeBucket {
+    pub min: usize,
+    pub max: usize,
+    pub addresses: usize,
+}
+
+/// Counts the mfg_batches at each state address
+#[derive(Debug, Default)]
+pub struct AddressDistribution {
+    addresses: HashMap<String, AddressLoad>,
+}
+
+impl AddressDistribution {
+    pub fn new() -> Self {
+        AddressDistribution::default()
+    }
+
+    /// Builds the distribution of the given mfg_batch IDs, one per batch, in a namespace
+    pub fn from_ids<'a, I>(namespace: &MfgBatchNamespace, mfg_batch_ids: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut distribution = AddressDistribution::new();
+        for mfg_batch_id in mfg_batch_ids {
+            distribution.add(namespace, mfg_batch_id);
+        }
+        distribution
+    }
+
+    /// Builds the distribution of the current mfg_batches in the store, computing their
+    /// addresses from their IDs
+    pub fn from_store(
+        store: &dyn MfgBatchStore,
+        service_id: Option<&str>,
+    ) -> Result<Self, MfgBatchStoreError> {
+        let filters = ListMfgBatchFilters::default();
+        let mut distribution = AddressDistribution::new();
+
+        for mfg_batch in store.iter_mfg_batches(service_id, &filters, PAGE_SIZE) {
+            let mfg_batch = mfg_batch?;
+            match namespace_from_store(mfg_batch.mfg_batch_namespace()) {
+                Some(namespace) => distribution.add(&namespace, mfg_batch.mfg_batch_id()),
+                // Mfg_batches of a namespace the addressing scheme does not know of stay where
+                // they were stored
+                None => distribution
+                    .add_at_address(mfg_batch.mfg_batch_address(), mfg_batch.mfg_batch_id()),
+            }
+        }
+
+        Ok(distribution)
+    }
+
+    /// Counts one mfg_batch at the address of its ID in a namespace
+    pub fn add(&mut self, namespace: &MfgBatchNamespace, mfg_batch_id: &str) {
+        let address = compute_mfg_batch_address(namespace, mfg_batch_id);
+        self.add_at_address(&address, mfg_batch_id);
+    }
+
+    fn add_at_address(&mut self, address: &str, mfg_batch_id: &str) {
+        self.addresses
+            .entry(address.to_string())
+            .or_insert_with(|| AddressLoad {
+                address: address.to_string(),
+                mfg_batch_id: mfg_batch_id.to_string(),
+                mfg_batches: 0,
+            })
+            .mfg_batches += 1;
+    }
+
+    /// The number of mfg_batches counted
+    pub fn mfg_batches(&self) -> usize {
+        self.addresses.values().map(|load| load.mfg_batches).sum()
+    }
+
+    /// The number of addresses the mfg_batches are stored at
+    pub fn addresses(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Returns the `count` addresses with the most mfg_batches, most first
+    pub fn largest(&self, count: usize) -> Vec<AddressLoad> {
+        let mut loads = self.addresses.values().cloned().collect::<Vec<_>>();
+        loads.sort_by(|a, b| {
+            b.mfg_batches
+                .cmp(&a.mfg_batches)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        loads.truncate(count);
+        loads
+    }
+
+    /// The list size that `percentile` percent of addresses are at or under, by nearest rank
+    pub fn list_size_at_percentile(&self, percentile: u8) -> usize {
+        let sizes = self.sorted_list_sizes();
+        if sizes.is_empty() {
+            return 0;
+        }
+
+        let rank = (usize::from(percentile.min(100)) * sizes.len() + 99) / 100;
+        sizes[rank.max(1) - 1]
+    }
+
+    /// Groups the addresses by list size, doubling the sizes covered by each bucket
+    pub fn histogram(&self) -> Vec<ListSizeBucket> {
+        let mut buckets: Vec<ListSizeBucket> = Vec::new();
+
+        for size in self.sorted_list_sizes() {
+            match buckets.last_mut() {
+                Some(bucket) if size <= bucket.max => bucket.addresses += 1,
+                _ => {
+                    let min = size.next_power_of_two();
+                    let min = if min == size { min } else { min / 2 };
+                    buckets.push(ListSizeBucket {
+                        min,
+                        max: min * 2 - 1,
+                        addresses: 1,
+                    });
+                }
+            }
+        }
+
+        buckets
+    }
+
+    /// Summarizes the distribution, listing the `top` largest addresses
+    pub fn report(&self, top: usize) -> DistributionReport {
+        let mfg_batches = self.mfg_batches();
+        let addresses = self.addresses();
+
+        DistributionReport {
+            mfg_batches,
+            addresses,
+            mean_list_size: if addresses == 0 {
+                0.0
+            } else {
+                mfg_batches as f64 / addresses as f64
+            },
+            median_list_size: self.list_size_at_percentile(50),
+            p99_list_size: self.list_size_at_percentile(99),
+            max_list_size: self.list_size_at_percentile(100),
+            histogram: self.histogram(),
+            largest: self.largest(top),
+        }
+    }
+
+    fn sorted_list_sizes(&self) -> Vec<usize> {
+        let mut sizes = self
+            .addresses
+            .values()
+            .map(|load| load.mfg_batches)
+            .collect::<Vec<_>>();
+        sizes.sort_unstable();
+        sizes
+    }
+}
+
+/// A summary of an `AddressDistribution`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistributionReport {
+    pub mfg_batches: usize,
+    pub addresses: usize,
+    pub mean_list_size: f64,
+    pub median_list_size: usize,
+    pub p99_list_size: usize,
+    pub max_list_size: usize,
+    pub histogram: Vec<ListSizeBucket>,
+    pub largest: Vec<AddressLoad>,
+}
+
+impl fmt::Display for DistributionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "mfg_batches:\t{}", self.mfg_batches)?;
+        writeln!(f, "addresses:\t{}", self.addresses)?;
+        writeln!(f, "mean list size:\t{:.2}", self.mean_list_size)?;
+        writeln!(f, "median list size:\t{}", self.median_list_size)?;
+        writeln!(f, "p99 list size:\t{}", self.p99_list_size)?;
+        writeln!(f, "max list size:\t{}", self.max_list_size)?;
+
+        writeln!(f, "\nlist size\taddresses")?;
+        for bucket in &self.histogram {
+            if bucket.min == bucket.max {
+                writeln!(f, "{}\t{}", bucket.min, bucket.addresses)?;
+            } else {
+                writeln!(f, "{}-{}\t{}", bucket.min, bucket.max, bucket.addresses)?;
+            }
+        }
+
+        writeln!(f, "\nmfg_batches\tmfg_batch_id\taddress")?;
+        for load in &self.largest {
+            writeln!(
+                f,
+                "{}\t{}\t{}",
+                load.mfg_batches, load.mfg_batch_id, load.address
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads mfg_batch IDs one per line, skipping blank lines and `#` comments
+pub fn parse_mfg_batch_ids(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parses a namespace as it is recorded in the store
+fn namespace_from_store(namespace: &str) -> Option<MfgBatchNamespace> {
+    if namespace.eq_ignore_ascii_case("gs1") {
+        Some(MfgBatchNamespace::Gs1)
+    } else if namespace.eq_ignore_ascii_case("internal") {
+        Some(MfgBatchNamespace::Internal)
+    } else if namespace.eq_ignore_ascii_case("lot") {
+        Some(MfgBatchNamespace::Lot)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDS: &str = "
+        # Three batches of one GTIN, two of another and one SSCC
+        00012345678905
+        012345678905
+        0012345678905
+        00098765432109
+        098765432109
+
+        000123456789012347
+    ";
+
+    #[test]
+    // This tests that the batches of a GTIN are counted at one address, however it is padded
+    fn test_distribution_of_ids() {
+        let distribution =
+            AddressDistribution::from_ids(&MfgBatchNamespace::Gs1, parse_mfg_batch_ids(IDS));
+
+        assert_eq!(distribution.mfg_batches(), 6);
+        assert_eq!(distribution.addresses(), 3);
+
+        let largest = distribution.largest(2);
+        assert_eq!(
+            largest
+                .iter()
+                .map(|load| (load.mfg_batch_id.as_str(), load.mfg_batches))
+                .collect::<Vec<_>>(),
+            vec![("00012345678905", 3), ("00098765432109", 2)]
+        );
+        assert_eq!(
+            largest[0].address,
+            compute_mfg_batch_address(&MfgBatchNamespace::Gs1, "012345678905")
+        );
+    }
+
+    #[test]
+    // This tests the percentiles and histogram of the list sizes
+    fn test_distribution_report() {
+        let report =
+            AddressDistribution::from_ids(&MfgBatchNamespace::Gs1, parse_mfg_batch_ids(IDS))
+                .report(1);
+
+        assert_eq!(report.median_list_size, 2);
+        assert_eq!(report.p99_list_size, 3);
+        assert_eq!(report.max_list_size, 3);
+        assert_eq!(report.mean_list_size, 2.0);
+        assert_eq!(
+            report.histogram,
+            vec![
+                ListSizeBucket {
+                    min: 1,
+                    max: 1,
+                    addresses: 1
+                },
+                ListSizeBucket {
+                    min: 2,
+                    max: 3,
+                    addresses: 2
+                },
+            ]
+        );
+        assert_eq!(report.largest.len(), 1);
+    }
+
+    #[test]
+    // This tests that an empty distribution reports no list sizes rather than failing
+    fn test_empty_distribution_report() {
+        let report = AddressDistribution::new().report(10);
+
+        assert_eq!(report.mfg_batches, 0);
+        assert_eq!(report.max_list_size, 0);
+        assert_eq!(report.mean_list_size, 0.0);
+        assert!(report.histogram.is_empty());
+        assert!(report.largest.is_empty());
+    }
+}
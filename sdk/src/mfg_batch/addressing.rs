@@ -12,6 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::error::Error;
+use std::fmt;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+
+use crate::protocol::mfg_batch::state::MfgBatchNamespace;
 
 /*
 # Adding namespace based on contract seed logic
@@ -21,15 +28,486 @@
 pub const GRID_NAMESPACE: &str = "11bb0e";
 pub const MFG_BATCH_PREFIX: &str = "01";
 pub const GRID_MFG_BATCH_NAMESPACE: &str = "11bb0e01";
+pub const MFG_BATCH_ANCHOR_PREFIX: &str = "02";
+pub const GRID_MFG_BATCH_ANCHOR_NAMESPACE: &str = "11bb0e02";
+
+/// The transaction family the default namespace is derived from
+pub const GRID_MFG_BATCH_FAMILY_NAME: &str = "grid_mfg_batch";
+
+/// Address prefixes of each mfg_batch namespace, following the mfg_batch prefix
+const GS1_NAMESPACE_PREFIX: &str = "01";
+const INTERNAL_NAMESPACE_PREFIX: &str = "02";
+const LOT_NAMESPACE_PREFIX: &str = "03";
+
+/// Address code of a GS1 mfg_batch identified by a GTIN and lot number
+#[cfg(feature = "mfg-batch-addressing-v2")]
+const GTIN_LOT_ADDRESS_CODE: &str = "03";
+
+/// The kind of identifier a mfg_batch is recorded under
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MfgBatchIdentifier {
+    Gtin8,
+    Gtin12,
+    Gtin13,
+    Gtin14,
+    Sscc,
+    CompanyInternal,
+}
+
+impl MfgBatchIdentifier {
+    /// Determines the kind of identifier from the format of a mfg_batch ID. Numeric IDs are
+    /// classified by their length; any other ID is treated as company-internal.
+    pub fn from_id(id: &str) -> Self {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return MfgBatchIdentifier::CompanyInternal;
+        }
+
+        match id.len() {
+            8 => MfgBatchIdentifier::Gtin8,
+            12 => MfgBatchIdentifier::Gtin12,
+            13 => MfgBatchIdentifier::Gtin13,
+            14 => MfgBatchIdentifier::Gtin14,
+            18 => MfgBatchIdentifier::Sscc,
+            _ => MfgBatchIdentifier::CompanyInternal,
+        }
+    }
+
+    /// The code stored in the last two characters of the address.
+    ///
+    /// Every GTIN format shares a code, as shorter GTINs are the same key as the GTIN-14 they
+    /// pad out to.
+    fn address_code(&self) -> &'static str {
+        match self {
+            MfgBatchIdentifier::Gtin8
+            | MfgBatchIdentifier::Gtin12
+            | MfgBatchIdentifier::Gtin13
+            | MfgBatchIdentifier::Gtin14 => "00",
+            MfgBatchIdentifier::Sscc => "01",
+            MfgBatchIdentifier::CompanyInternal => "02",
+        }
+    }
+}
+
+/// Computes the namespace prefix of a transaction family: the first six characters of the
+/// SHA-512 hash of its name
+pub fn compute_family_namespace(family_name: &str) -> String {
+    let mut sha = Sha512::new();
+    sha.input(family_name.as_bytes());
+    sha.result_str()[..6].to_string()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AddressingError {
+    /// The namespace prefix is not six lowercase hex characters
+    InvalidNamespace(String),
+}
+
+impl Error for AddressingError {}
+
+impl fmt::Display for AddressingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressingError::InvalidNamespace(namespace) => write!(
+                f,
+                "namespace must be six lowercase hex characters: {:?}",
+                namespace
+            ),
+        }
+    }
+}
+
+/// Computes mfg_batch addresses under a namespace prefix
+///
+/// Each mfg_batch transaction family on a network needs a namespace of its own. The default
+/// addresser uses the namespace of `grid_mfg_batch`, which the free functions of this module
+/// compute addresses under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MfgBatchAddresser {
+    namespace: String,
+}
+
+impl Default for MfgBatchAddresser {
+    fn default() -> Self {
+        MfgBatchAddresser {
+            namespace: GRID_NAMESPACE.to_string(),
+        }
+    }
+}
+
+impl MfgBatchAddresser {
+    /// The six character prefix of every address
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The prefix of every mfg_batch address
+    pub fn mfg_batch_namespace(&self) -> String {
+        self.namespace.clone() + MFG_BATCH_PREFIX
+    }
+
+    /// The prefix of every mfg_batch anchor address
+    pub fn anchor_namespace(&self) -> String {
+        self.namespace.clone() + MFG_BATCH_ANCHOR_PREFIX
+    }
+
+    /// Computes the address of a GS1 mfg_batch based on its identifier
+    ///
+    /// GTINs and SSCCs are zero-padded into the address, while company-internal identifiers are
+    /// hashed. The identifier type is encoded in the last two characters.
+    pub fn compute_gs1_mfg_batch_address(&self, mfg_batch_id: &str) -> String {
+        let identifier = MfgBatchIdentifier::from_id(mfg_batch_id);
+
+        let key = match identifier {
+            MfgBatchIdentifier::CompanyInternal => {
+                let mut sha = Sha512::new();
+                sha.input(mfg_batch_id.as_bytes());
+                sha.result_str()[..58].to_string()
+            }
+            _ => format!("{:0>58}", mfg_batch_id),
+        };
+
+        // 11bb0e (grid namespace) + 01 (mfg_batch namespace) + 01 (gs1 namespace) + key + type
+        self.mfg_batch_namespace() + GS1_NAMESPACE_PREFIX + &key + identifier.address_code()
+    }
+
+    /// Computes the address of a mfg_batch in the given namespace
+    ///
+    /// Identifiers outside of the GS1 namespace are not GS1 keys, so they are always hashed.
+    pub fn compute_mfg_batch_address(
+        &self,
+        namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> String {
+        let namespace_prefix = match namespace {
+            MfgBatchNamespace::Gs1 => return self.compute_gs1_mfg_batch_address(mfg_batch_id),
+            MfgBatchNamespace::Internal => INTERNAL_NAMESPACE_PREFIX,
+            MfgBatchNamespace::Lot => LOT_NAMESPACE_PREFIX,
+        };
+
+        let mut sha = Sha512::new();
+        sha.input(mfg_batch_id.as_bytes());
+
+        // 11bb0e (grid namespace) + 01 (mfg_batch namespace) + namespace + hashed key
+        self.mfg_batch_namespace() + namespace_prefix + &sha.result_str()[..60]
+    }
+
+    /// Computes the address of an organization's mfg_batch anchor for a commit
+    ///
+    /// The owner and commit number are hashed separately, so each organization's anchors share
+    /// an address prefix.
+    pub fn compute_mfg_batch_anchor_address(&self, owner: &str, commit_num: i64) -> String {
+        let mut owner_sha = Sha512::new();
+        owner_sha.input(owner.as_bytes());
+
+        let mut commit_sha = Sha512::new();
+        commit_sha.input(commit_num.to_string().as_bytes());
+
+        // 11bb0e (grid namespace) + 02 (anchor namespace) + hashed owner + hashed commit number
+        self.anchor_namespace() + &owner_sha.result_str()[..30] + &commit_sha.result_str()[..32]
+    }
+
+    /// Computes the version 2 address of a GS1 mfg_batch identified by a GTIN and lot number
+    ///
+    /// The version 1 address zero-pads the GTIN into the address, so every lot of a product is
+    /// stored in the same state entry. Hashing the GTIN together with the lot gives each lot an
+    /// address of its own.
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    pub fn compute_gs1_mfg_batch_address_v2(&self, gtin: &str, lot: &str) -> String {
+        let mut sha = Sha512::new();
+        sha.input(gtin.as_bytes());
+        sha.input(lot.as_bytes());
+
+        // 11bb0e (grid namespace) + 01 (mfg_batch namespace) + 01 (gs1 namespace) + key + type
+        self.mfg_batch_namespace()
+            + GS1_NAMESPACE_PREFIX
+            + &sha.result_str()[..58]
+            + GTIN_LOT_ADDRESS_CODE
+    }
+
+    /// Computes the version 2 address of a mfg_batch in the given namespace
+    ///
+    /// GS1 mfg_batches identified by a GTIN and lot number are addressed by
+    /// `compute_gs1_mfg_batch_address_v2`; every other mfg_batch keeps its version 1 address.
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    pub fn compute_mfg_batch_address_v2(
+        &self,
+        namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> String {
+        match (namespace, split_gtin_lot(mfg_batch_id)) {
+            (MfgBatchNamespace::Gs1, Some((gtin, lot))) => {
+                self.compute_gs1_mfg_batch_address_v2(gtin, lot)
+            }
+            _ => self.compute_mfg_batch_address(namespace, mfg_batch_id),
+        }
+    }
+}
+
+/// Builds an addresser for the namespace of a transaction family, or an explicit namespace
+#[derive(Clone, Debug, Default)]
+pub struct MfgBatchAddresserBuilder {
+    family_name: Option<String>,
+    namespace: Option<String>,
+}
 
-/// Computes the address of a GS1 product based on its 
-pub fn compute_gs1_mfg_batch_address(gtin: &str) -> String {
-    // 621ddee (grid namespace) + 02 (product namespace) + 01 (gs1 namespace)
-    String::from(GRID_NAMESPACE)
-        + MFG_BATCH_PREFIX
-        + "01"
-        + "00000000000000000000000000000000000000000000"
-        + &format!("{:0>14}", gtin)
-        + "00"
-}
- 
\ No newline at end of file
+impl MfgBatchAddresserBuilder {
+    pub fn new() -> Self {
+        MfgBatchAddresserBuilder::default()
+    }
+
+    /// Derives the namespace from the name of the transaction family
+    pub fn with_family_name(mut self, family_name: String) -> Self {
+        self.family_name = Some(family_name);
+        self
+    }
+
+    /// Sets the namespace, six lowercase hex characters; overrides the family name's namespace
+    pub fn with_namespace(mut self, namespace: String) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Builds the addresser, using the namespace of `grid_mfg_batch` if neither a family name
+    /// nor a namespace was given
+    pub fn build(self) -> Result<MfgBatchAddresser, AddressingError> {
+        let namespace = match (self.namespace, self.family_name) {
+            (Some(namespace), _) => namespace,
+            (None, Some(family_name)) => compute_family_namespace(&family_name),
+            (None, None) => return Ok(MfgBatchAddresser::default()),
+        };
+
+        if namespace.len() != 6
+            || !namespace
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        {
+            return Err(AddressingError::InvalidNamespace(namespace));
+        }
+
+        Ok(MfgBatchAddresser { namespace })
+    }
+}
+
+/// Computes the address of a GS1 mfg_batch based on its identifier, under the grid namespace
+pub fn compute_gs1_mfg_batch_address(mfg_batch_id: &str) -> String {
+    MfgBatchAddresser::default().compute_gs1_mfg_batch_address(mfg_batch_id)
+}
+
+/// Computes the address of a mfg_batch in the given namespace, under the grid namespace
+pub fn compute_mfg_batch_address(namespace: &MfgBatchNamespace, mfg_batch_id: &str) -> String {
+    MfgBatchAddresser::default().compute_mfg_batch_address(namespace, mfg_batch_id)
+}
+
+/// Computes the address of an organization's mfg_batch anchor for a commit, under the grid
+/// namespace
+pub fn compute_mfg_batch_anchor_address(owner: &str, commit_num: i64) -> String {
+    MfgBatchAddresser::default().compute_mfg_batch_anchor_address(owner, commit_num)
+}
+
+/// Splits a GS1 element string of the form `(01)<GTIN-14>(10)<lot>` into its GTIN and lot
+/// number. Returns `None` for any other identifier.
+#[cfg(feature = "mfg-batch-addressing-v2")]
+pub fn split_gtin_lot(mfg_batch_id: &str) -> Option<(&str, &str)> {
+    let rest = mfg_batch_id.strip_prefix("(01)")?;
+    let (gtin, lot) = rest.split_at(rest.find("(10)")?);
+    let lot = &lot[4..];
+
+    // A lot number (AI 10) is at most 20 characters long
+    if MfgBatchIdentifier::from_id(gtin) != MfgBatchIdentifier::Gtin14
+        || lot.is_empty()
+        || lot.len() > 20
+    {
+        return None;
+    }
+
+    Some((gtin, lot))
+}
+
+/// Computes the version 2 address of a GS1 mfg_batch identified by a GTIN and lot number, under
+/// the grid namespace
+#[cfg(feature = "mfg-batch-addressing-v2")]
+pub fn compute_gs1_mfg_batch_address_v2(gtin: &str, lot: &str) -> String {
+    MfgBatchAddresser::default().compute_gs1_mfg_batch_address_v2(gtin, lot)
+}
+
+/// Computes the version 2 address of a mfg_batch in the given namespace, under the grid
+/// namespace
+#[cfg(feature = "mfg-batch-addressing-v2")]
+pub fn compute_mfg_batch_address_v2(namespace: &MfgBatchNamespace, mfg_batch_id: &str) -> String {
+    MfgBatchAddresser::default().compute_mfg_batch_address_v2(namespace, mfg_batch_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // This tests that identifiers are classified by their format
+    fn identifier_from_id() {
+        assert_eq!(
+            MfgBatchIdentifier::from_id("40170725"),
+            MfgBatchIdentifier::Gtin8
+        );
+        assert_eq!(
+            MfgBatchIdentifier::from_id("688955434684"),
+            MfgBatchIdentifier::Gtin12
+        );
+        assert_eq!(
+            MfgBatchIdentifier::from_id("9781981855728"),
+            MfgBatchIdentifier::Gtin13
+        );
+        assert_eq!(
+            MfgBatchIdentifier::from_id("10012345678902"),
+            MfgBatchIdentifier::Gtin14
+        );
+        assert_eq!(
+            MfgBatchIdentifier::from_id("106141411234567897"),
+            MfgBatchIdentifier::Sscc
+        );
+        assert_eq!(
+            MfgBatchIdentifier::from_id("LOT-2021-0042"),
+            MfgBatchIdentifier::CompanyInternal
+        );
+    }
+
+    #[test]
+    // This tests that GTIN addresses are unchanged and that other identifiers encode their type
+    fn mfg_batch_addresses() {
+        assert_eq!(
+            compute_gs1_mfg_batch_address("688955434684"),
+            "11bb0e0101000000000000000000000000000000000000000000000068895543468400"
+        );
+        assert_eq!(
+            compute_gs1_mfg_batch_address("106141411234567897"),
+            "11bb0e0101000000000000000000000000000000000000000010614141123456789701"
+        );
+
+        let internal = compute_gs1_mfg_batch_address("LOT-2021-0042");
+        assert_eq!(internal.len(), 70);
+        assert!(internal.ends_with("02"));
+    }
+
+    #[test]
+    // This tests that each namespace is addressed under its own prefix
+    fn namespaced_mfg_batch_addresses() {
+        assert_eq!(
+            compute_mfg_batch_address(&MfgBatchNamespace::Gs1, "688955434684"),
+            compute_gs1_mfg_batch_address("688955434684")
+        );
+
+        let internal = compute_mfg_batch_address(&MfgBatchNamespace::Internal, "688955434684");
+        assert_eq!(internal.len(), 70);
+        assert!(internal.starts_with("11bb0e0102"));
+
+        let lot = compute_mfg_batch_address(&MfgBatchNamespace::Lot, "688955434684");
+        assert_eq!(lot.len(), 70);
+        assert!(lot.starts_with("11bb0e0103"));
+        assert_eq!(internal[10..], lot[10..]);
+    }
+
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    #[test]
+    // This tests that GTIN and lot element strings are split, and other identifiers are not
+    fn gtin_lot_split() {
+        assert_eq!(
+            split_gtin_lot("(01)10012345678902(10)ABC123"),
+            Some(("10012345678902", "ABC123"))
+        );
+        assert_eq!(split_gtin_lot("(01)688955434684(10)ABC123"), None);
+        assert_eq!(split_gtin_lot("(01)10012345678902(10)"), None);
+        assert_eq!(split_gtin_lot("(01)10012345678902"), None);
+        assert_eq!(split_gtin_lot("10012345678902"), None);
+    }
+
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    #[test]
+    // This tests that each lot of a product has its own version 2 address, and that other
+    // identifiers keep their version 1 address
+    fn mfg_batch_addresses_v2() {
+        let lot_a =
+            compute_mfg_batch_address_v2(&MfgBatchNamespace::Gs1, "(01)10012345678902(10)A");
+        let lot_b =
+            compute_mfg_batch_address_v2(&MfgBatchNamespace::Gs1, "(01)10012345678902(10)B");
+        assert_eq!(lot_a.len(), 70);
+        assert!(lot_a.starts_with("11bb0e0101"));
+        assert!(lot_a.ends_with("03"));
+        assert_ne!(lot_a, lot_b);
+        assert_eq!(
+            lot_a,
+            compute_gs1_mfg_batch_address_v2("10012345678902", "A")
+        );
+
+        assert_eq!(
+            compute_mfg_batch_address_v2(&MfgBatchNamespace::Gs1, "688955434684"),
+            compute_mfg_batch_address(&MfgBatchNamespace::Gs1, "688955434684")
+        );
+        assert_eq!(
+            compute_mfg_batch_address_v2(&MfgBatchNamespace::Lot, "(01)10012345678902(10)A"),
+            compute_mfg_batch_address(&MfgBatchNamespace::Lot, "(01)10012345678902(10)A")
+        );
+    }
+
+    #[test]
+    // This tests that anchors are addressed by owner and commit, under a prefix shared by each
+    // owner's anchors
+    fn mfg_batch_anchor_addresses() {
+        let first = compute_mfg_batch_anchor_address("Target", 1);
+        let second = compute_mfg_batch_anchor_address("Target", 2);
+        let other_owner = compute_mfg_batch_anchor_address("Cargill", 1);
+
+        assert_eq!(first.len(), 70);
+        assert!(first.starts_with(GRID_MFG_BATCH_ANCHOR_NAMESPACE));
+        assert_ne!(first, second);
+        assert_eq!(first[..38], second[..38]);
+        assert_ne!(first[..38], other_owner[..38]);
+    }
+
+    #[test]
+    // This tests that the default namespace is the one derived from the grid_mfg_batch family,
+    // and that another family's addresses differ from it only in their namespace
+    fn custom_namespace_addresses() {
+        assert_eq!(
+            compute_family_namespace(GRID_MFG_BATCH_FAMILY_NAME),
+            GRID_NAMESPACE
+        );
+        assert_eq!(
+            MfgBatchAddresserBuilder::new().build(),
+            Ok(MfgBatchAddresser::default())
+        );
+
+        let addresser = MfgBatchAddresserBuilder::new()
+            .with_family_name("acme_mfg_batch".into())
+            .build()
+            .expect("Failed to build addresser");
+        let namespace = compute_family_namespace("acme_mfg_batch");
+        assert_eq!(addresser.namespace(), namespace);
+        assert_ne!(addresser.namespace(), GRID_NAMESPACE);
+        assert_eq!(addresser.mfg_batch_namespace(), namespace.clone() + "01");
+
+        let address = addresser.compute_mfg_batch_address(&MfgBatchNamespace::Lot, "LOT-1");
+        let default_address = compute_mfg_batch_address(&MfgBatchNamespace::Lot, "LOT-1");
+        assert!(address.starts_with(&addresser.mfg_batch_namespace()));
+        assert_eq!(address[6..], default_address[6..]);
+
+        let anchor = addresser.compute_mfg_batch_anchor_address("Target", 1);
+        assert!(anchor.starts_with(&addresser.anchor_namespace()));
+
+        let addresser = MfgBatchAddresserBuilder::new()
+            .with_family_name("acme_mfg_batch".into())
+            .with_namespace("abc123".into())
+            .build()
+            .expect("Failed to build addresser");
+        assert!(addresser
+            .compute_gs1_mfg_batch_address("688955434684")
+            .starts_with("abc1230101"));
+
+        for namespace in &["abc12", "ABC123", "abc12g", "abc1234"] {
+            assert_eq!(
+                MfgBatchAddresserBuilder::new()
+                    .with_namespace(namespace.to_string())
+                    .build(),
+                Err(AddressingError::InvalidNamespace(namespace.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle roots of the off-chain mfg_batch records, for anchoring on chain.
+//!
+//! The leaves of the tree are the record hashes of the mfg_batches current at a commit, in
+//! ascending order, so the root does not depend on the order rows were written in or on how
+//! the store is sharded. Once a root is anchored on chain, recomputing it from the store shows
+//! whether the store still holds the records it held at that commit.
+
+use crypto::{digest::Digest, sha2::Sha256};
+
+use super::store::{MfgBatchAnchor, MfgBatchStore, MfgBatchStoreError};
+
+/// Computes the Merkle root of the given leaves. Each pair of nodes is hashed together with
+/// SHA-256; a node left without a pair is carried up to the next level unchanged. The root of
+/// no leaves is the SHA-256 hash of no input.
+pub fn merkle_root<S: AsRef<str>>(leaves: &[S]) -> String {
+    if leaves.is_empty() {
+        return Sha256::new().result_str();
+    }
+
+    let mut level: Vec<String> = leaves
+        .iter()
+        .map(|leaf| leaf.as_ref().to_string())
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut sha = Sha256::new();
+                    sha.input_str(left);
+                    sha.input_str(right);
+                    sha.result_str()
+                }
+                [node] => node.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    level.remove(0)
+}
+
+/// The Merkle root of the records current at a commit
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleSnapshot {
+    pub commit_num: i64,
+    pub merkle_root: String,
+    pub row_count: i64,
+}
+
+/// Computes the Merkle root of the mfg_batch records current at a commit
+///
+/// # Arguments
+///
+///  * `store` - The store to read the records from
+///  * `commit_num` - The commit to compute the root at
+///  * `service_id` - The service ID to compute the root for
+pub fn compute_merkle_snapshot<S: MfgBatchStore + ?Sized>(
+    store: &S,
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Result<MerkleSnapshot, MfgBatchStoreError> {
+    let leaves = store.list_mfg_batch_record_hashes(commit_num, service_id)?;
+
+    Ok(MerkleSnapshot {
+        commit_num,
+        merkle_root: merkle_root(&leaves),
+        row_count: leaves.len() as i64,
+    })
+}
+
+/// An anchor, and the Merkle root recomputed from the store at the anchor's commit
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnchorVerification {
+    pub anchor: MfgBatchAnchor,
+    pub recomputed: MerkleSnapshot,
+}
+
+impl AnchorVerification {
+    /// Whether the store held the same records at the anchor's commit as when it was anchored
+    pub fn matches(&self) -> bool {
+        self.anchor.merkle_root == self.recomputed.merkle_root
+            && self.anchor.row_count == self.recomputed.row_count
+    }
+}
+
+impl std::fmt::Display for AnchorVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "commit {} anchored by {} at {}: ",
+            self.anchor.commit_num, self.anchor.owner, self.anchor.anchor_address
+        )?;
+        if self.matches() {
+            write!(f, "match ({} records)", self.anchor.row_count)
+        } else {
+            write!(
+                f,
+                "mismatch (anchored {} over {} records, store has {} over {} records)",
+                self.anchor.merkle_root,
+                self.anchor.row_count,
+                self.recomputed.merkle_root,
+                self.recomputed.row_count
+            )
+        }
+    }
+}
+
+/// Recomputes the Merkle root at each recorded anchor's commit, oldest commit first
+///
+/// # Arguments
+///
+///  * `store` - The store to read the anchors and records from
+///  * `service_id` - The service ID to verify the anchors of
+pub fn verify_mfg_batch_anchors<S: MfgBatchStore + ?Sized>(
+    store: &S,
+    service_id: Option<&str>,
+) -> Result<Vec<AnchorVerification>, MfgBatchStoreError> {
+    store
+        .list_mfg_batch_anchors(service_id)?
+        .into_iter()
+        .map(|anchor| {
+            let recomputed = compute_merkle_snapshot(store, anchor.commit_num, service_id)?;
+            Ok(AnchorVerification { anchor, recomputed })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256(input: &str) -> String {
+        let mut sha = Sha256::new();
+        sha.input_str(input);
+        sha.result_str()
+    }
+
+    /// Verify that pairs are hashed level by level, with an unpaired node carried up, and that
+    /// the roots of no leaves and of one leaf are well defined
+    #[test]
+    fn test_merkle_root() {
+        assert_eq!(
+            merkle_root::<&str>(&[]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(merkle_root(&["a"]), "a");
+        assert_eq!(merkle_root(&["a", "b"]), sha256("ab"));
+        assert_eq!(
+            merkle_root(&["a", "b", "c"]),
+            sha256(&format!("{}c", sha256("ab")))
+        );
+        assert_eq!(
+            merkle_root(&["a", "b", "c", "d"]),
+            sha256(&format!("{}{}", sha256("ab"), sha256("cd")))
+        );
+        assert_ne!(merkle_root(&["a", "b"]), merkle_root(&["b", "a"]));
+    }
+
+    /// Verify that an anchor only matches a recomputed root over the same number of records
+    #[test]
+    fn test_anchor_verification() {
+        let anchor = MfgBatchAnchor {
+            owner: "org".to_string(),
+            commit_num: 3,
+            merkle_root: merkle_root(&["a", "b"]),
+            row_count: 2,
+            anchor_address: "11bb0e02".to_string(),
+            anchored_commit_num: 4,
+            anchored_at: 1_600_100_000,
+            service_id: None,
+        };
+        let verification = |merkle_root: String, row_count: i64| AnchorVerification {
+            anchor: anchor.clone(),
+            recomputed: MerkleSnapshot {
+                commit_num: 3,
+                merkle_root,
+                row_count,
+            },
+        };
+
+        assert!(verification(merkle_root(&["a", "b"]), 2).matches());
+        assert!(!verification(merkle_root(&["a", "c"]), 2).matches());
+        assert!(!verification(merkle_root(&["a", "b"]), 3).matches());
+    }
+
+    /// Verify that the root at a commit covers the records current at that commit, so later
+    /// writes leave earlier anchors verifiable, and that an anchor with a different root is
+    /// reported as a mismatch
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_verify_mfg_batch_anchors() {
+        use crate::mfg_batch::{
+            store::{DieselMfgBatchStore, MfgBatchBuilder},
+            MAX_COMMIT_NUM,
+        };
+
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let add = |mfg_batch_id: &str, commit_num: i64| {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        };
+        let anchor = |snapshot: MerkleSnapshot| MfgBatchAnchor {
+            owner: "org".to_string(),
+            commit_num: snapshot.commit_num,
+            merkle_root: snapshot.merkle_root,
+            row_count: snapshot.row_count,
+            anchor_address: "11bb0e02".to_string(),
+            anchored_commit_num: snapshot.commit_num + 1,
+            anchored_at: 1_600_100_000,
+            service_id: None,
+        };
+
+        add("688955434684", 1);
+        let first = compute_merkle_snapshot(&store, 1, None).expect("Failed to compute root");
+        assert_eq!(first.row_count, 1);
+        store
+            .add_mfg_batch_anchor(anchor(first.clone()))
+            .expect("Failed to add anchor");
+
+        add("9781981855728", 3);
+        let second = compute_merkle_snapshot(&store, 3, None).expect("Failed to compute root");
+        assert_eq!(second.row_count, 2);
+        assert_ne!(second.merkle_root, first.merkle_root);
+        store
+            .add_mfg_batch_anchor(anchor(MerkleSnapshot {
+                merkle_root: first.merkle_root.clone(),
+                ..second
+            }))
+            .expect("Failed to add anchor");
+
+        let verifications = verify_mfg_batch_anchors(&store, None).expect("Failed to verify");
+        assert_eq!(verifications.len(), 2);
+        assert!(verifications[0].matches());
+        assert!(!verifications[1].matches());
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::error::{InternalError, InvalidArgumentError};
+
+/// An error that can occur while loading, storing or rendering a certificate template
+#[derive(Debug)]
+pub enum CertificateError {
+    Internal(InternalError),
+    /// The template is malformed, or uses a placeholder or value that cannot be rendered
+    InvalidArgument(InvalidArgumentError),
+}
+
+impl CertificateError {
+    pub(super) fn invalid(argument: &str, message: String) -> Self {
+        CertificateError::InvalidArgument(InvalidArgumentError::new(argument.to_string(), message))
+    }
+}
+
+impl Error for CertificateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CertificateError::Internal(err) => Some(err),
+            CertificateError::InvalidArgument(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CertificateError::Internal(err) => err.fmt(f),
+            CertificateError::InvalidArgument(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<std::io::Error> for CertificateError {
+    fn from(err: std::io::Error) -> Self {
+        CertificateError::Internal(InternalError::from_source(Box::new(err)))
+    }
+}
+
+impl From<printpdf::Error> for CertificateError {
+    fn from(err: printpdf::Error) -> Self {
+        CertificateError::Internal(InternalError::from_source(Box::new(err)))
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prints certificates, such as certificates of analysis and release certificates, for
+//! mfg_batches. A `CertificateTemplate` says what a certificate shows, and `render_certificate`
+//! fills it in with a mfg_batch's fields, properties and quality test results, and prints it
+//! to a PDF.
+//!
+//! Templates are kept as YAML files in a `CertificateTemplateDirectory`. The `coa` and
+//! `release` templates are built in, and are used unless the directory has its own.
+
+mod error;
+mod render;
+mod template;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+pub use error::CertificateError;
+pub use render::render_certificate;
+pub use template::{
+    validate_name, CertificateTemplate, PageSize, Section, MAX_TEMPLATE_NAME_LENGTH,
+};
+
+const BUILTIN_TEMPLATES: &[&str] = &[
+    include_str!("templates/coa.yaml"),
+    include_str!("templates/release.yaml"),
+];
+
+/// Returns the templates that are available without being stored
+pub fn builtin_templates() -> Vec<CertificateTemplate> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .filter_map(|yaml| CertificateTemplate::from_yaml(yaml).ok())
+        .collect()
+}
+
+/// A directory of certificate templates, one per `NAME.yaml` file
+#[derive(Clone, Debug)]
+pub struct CertificateTemplateDirectory {
+    dir: PathBuf,
+}
+
+impl CertificateTemplateDirectory {
+    /// Creates a template directory at `dir`; the directory is created when the first template
+    /// is stored in it
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Lists the built in templates and those in the directory, by name; a template in the
+    /// directory replaces the built in one of the same name
+    pub fn list(&self) -> Result<Vec<CertificateTemplate>, CertificateError> {
+        let mut templates = builtin_templates()
+            .into_iter()
+            .map(|template| (template.name.clone(), template))
+            .collect::<BTreeMap<_, _>>();
+
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Ok(templates.into_values().collect())
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("yaml") {
+                continue;
+            }
+            match CertificateTemplate::from_yaml(&fs::read_to_string(&path)?) {
+                Ok(template) => {
+                    templates.insert(template.name.clone(), template);
+                }
+                Err(err) => warn!("Skipping certificate template {}: {}", path.display(), err),
+            }
+        }
+
+        Ok(templates.into_values().collect())
+    }
+
+    /// Gets the template with the given name from the directory, or the built in one
+    pub fn get(&self, name: &str) -> Result<Option<CertificateTemplate>, CertificateError> {
+        validate_name(name)?;
+
+        match fs::read_to_string(self.path(name)) {
+            Ok(yaml) => Ok(Some(CertificateTemplate::from_yaml(&yaml)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(builtin_templates()
+                .into_iter()
+                .find(|template| template.name == name)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Stores the template in the directory, replacing any of the same name
+    pub fn put(&self, template: &CertificateTemplate) -> Result<(), CertificateError> {
+        template.validate()?;
+        fs::create_dir_all(&self.dir)?;
+
+        // Write to a temporary file first, so a template is never read half written
+        let path = self.path(&template.name);
+        let temp_path = path.with_extension("yaml.tmp");
+        fs::write(&temp_path, template.to_yaml()?)?;
+        fs::rename(&temp_path, &path)?;
+
+        Ok(())
+    }
+
+    /// Removes the template from the directory, returning whether it was there; a built in
+    /// template of the same name is used again afterwards
+    pub fn delete(&self, name: &str) -> Result<bool, CertificateError> {
+        validate_name(name)?;
+
+        match fs::remove_file(self.path(name)) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.yaml", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    /// Verify that the built in templates are valid
+    #[test]
+    fn test_builtin_templates() {
+        let names = builtin_templates()
+            .into_iter()
+            .map(|template| template.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["coa".to_string(), "release".to_string()]);
+    }
+
+    /// Verify that stored templates are listed with the built in ones, replace those of the
+    /// same name until they are deleted, and that unsafe names are rejected
+    #[test]
+    fn test_template_directory() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let templates = CertificateTemplateDirectory::new(temp_dir.path().join("templates"));
+
+        assert_eq!(templates.list().expect("Failed to list templates").len(), 2);
+
+        let mut coa = templates
+            .get("coa")
+            .expect("Failed to get template")
+            .expect("coa is not built in");
+        coa.title = "Acme CoA".to_string();
+        templates.put(&coa).expect("Failed to put template");
+
+        let mut shipping = coa.clone();
+        shipping.name = "shipping".to_string();
+        templates.put(&shipping).expect("Failed to put template");
+
+        let listed = templates.list().expect("Failed to list templates");
+        assert_eq!(
+            listed
+                .iter()
+                .map(|template| (template.name.as_str(), template.title.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("coa", "Acme CoA"),
+                ("release", "Release Certificate"),
+                ("shipping", "Acme CoA"),
+            ]
+        );
+
+        assert!(templates.delete("coa").expect("Failed to delete template"));
+        assert!(!templates.delete("coa").expect("Failed to delete template"));
+        assert_eq!(
+            templates
+                .get("coa")
+                .expect("Failed to get template")
+                .map(|template| template.title),
+            Some("Certificate of Analysis".to_string())
+        );
+        assert_eq!(
+            templates.get("missing").expect("Failed to get template"),
+            None
+        );
+
+        assert!(matches!(
+            templates.get("../coa"),
+            Err(CertificateError::InvalidArgument(_))
+        ));
+    }
+}
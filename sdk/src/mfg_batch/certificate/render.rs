@@ -0,0 +1,460 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Rect,
+};
+use qrcode::{Color, QrCode};
+
+use super::template::{fill, CertificateTemplate, Section};
+use super::CertificateError;
+use crate::mfg_batch::store::{MfgBatch, MfgBatchTestResult, PropertyValue};
+
+const MARGIN_MM: f32 = 20.0;
+const BODY_SIZE: f32 = 10.0;
+const HEADING_SIZE: f32 = 18.0;
+const SUBHEADING_SIZE: f32 = 12.0;
+/// Millimetres per point
+const PT_MM: f32 = 0.3528;
+/// The average width of a Helvetica character, as a fraction of its size, used to wrap text
+const CHAR_WIDTH: f32 = 0.55;
+/// Lat/long values are stored as millionths of a degree
+const LAT_LONG_SCALE: f64 = 1_000_000.0;
+
+/// Fills in a certificate template for a mfg_batch and its test results, and prints it to a
+/// PDF. `issued_at`, in seconds since the epoch, is printed as `{{issued_date}}`.
+pub fn render_certificate(
+    template: &CertificateTemplate,
+    mfg_batch: &MfgBatch,
+    test_results: &[MfgBatchTestResult],
+    issued_at: i64,
+) -> Result<Vec<u8>, CertificateError> {
+    let values = placeholder_values(mfg_batch, issued_at);
+    let properties = mfg_batch.properties();
+
+    let mut page = Page::new(template)?;
+
+    for section in &template.sections {
+        match section {
+            Section::Heading { text } => {
+                page.line(&fill(text, &values)?, HEADING_SIZE, true);
+                page.space(2.0);
+            }
+            Section::Text { text } => page.paragraph(&fill(text, &values)?, BODY_SIZE),
+            Section::Properties { heading, names } => {
+                page.heading(heading.as_deref(), &values)?;
+                let rows = properties
+                    .iter()
+                    .filter(|property| {
+                        names.is_empty()
+                            || names.iter().any(|name| name == property.property_name())
+                    })
+                    .map(|property| {
+                        vec![
+                            property.property_name().to_string(),
+                            property_text(property),
+                        ]
+                    })
+                    .collect();
+                page.table(&["Property", "Value"], &[0.4, 0.6], rows);
+            }
+            Section::TestResults { heading, summary } => {
+                page.heading(heading.as_deref(), &values)?;
+                if *summary {
+                    let failed = test_results.iter().filter(|result| !result.passed).count();
+                    let text = if test_results.is_empty() {
+                        "No tests have been recorded.".to_string()
+                    } else if failed == 0 {
+                        format!("All {} tests passed.", test_results.len())
+                    } else {
+                        format!("{} of {} tests failed.", failed, test_results.len())
+                    };
+                    page.paragraph(&text, BODY_SIZE);
+                } else {
+                    let rows = test_results
+                        .iter()
+                        .map(|result| {
+                            vec![
+                                result.test_name.clone(),
+                                result.specification.clone(),
+                                result.result.clone(),
+                                if result.passed { "Pass" } else { "Fail" }.to_string(),
+                                result.lab.clone(),
+                                format_date(result.tested_at),
+                            ]
+                        })
+                        .collect();
+                    page.table(
+                        &["Test", "Specification", "Result", "Status", "Lab", "Date"],
+                        &[0.2, 0.2, 0.16, 0.1, 0.18, 0.16],
+                        rows,
+                    );
+                }
+            }
+            Section::Certifications {
+                heading,
+                properties: names,
+            } => {
+                page.heading(heading.as_deref(), &values)?;
+                for name in names {
+                    if let Some(property) = properties
+                        .iter()
+                        .find(|property| property.property_name() == name)
+                    {
+                        page.paragraph(&format!("- {}", property_text(property)), BODY_SIZE);
+                    }
+                }
+            }
+            Section::QrCode { content, size_mm } => {
+                page.qr_code(&fill(content, &values)?, *size_mm)?
+            }
+            Section::Signature { label } => page.signature(&fill(label, &values)?),
+        }
+        page.space(4.0);
+    }
+
+    Ok(page.document.save_to_bytes()?)
+}
+
+/// Returns the values of the placeholders a template can use for the mfg_batch
+fn placeholder_values(mfg_batch: &MfgBatch, issued_at: i64) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    values.insert(
+        "mfg_batch_id".to_string(),
+        mfg_batch.mfg_batch_id().to_string(),
+    );
+    values.insert("owner".to_string(), mfg_batch.owner().to_string());
+    if let Some(quantity) = mfg_batch.quantity() {
+        values.insert("quantity".to_string(), quantity.to_string());
+    }
+    if let Some(expected_quantity) = mfg_batch.expected_quantity() {
+        values.insert(
+            "expected_quantity".to_string(),
+            expected_quantity.to_string(),
+        );
+    }
+    if let Some(uom) = mfg_batch.uom() {
+        values.insert("uom".to_string(), uom.to_string());
+    }
+    if let Some(production_date) = mfg_batch.production_date() {
+        values.insert("production_date".to_string(), format_date(production_date));
+    }
+    if let Some(expiration_date) = mfg_batch.expiration_date() {
+        values.insert("expiration_date".to_string(), format_date(expiration_date));
+    }
+    values.insert("issued_date".to_string(), format_date(issued_at));
+    for property in mfg_batch.properties() {
+        values.insert(
+            format!("property.{}", property.property_name()),
+            property_text(&property),
+        );
+    }
+    values
+}
+
+fn format_date(seconds: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(seconds, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Returns a property's value as it is printed
+fn property_text(property: &PropertyValue) -> String {
+    if let Some(value) = property.string_value() {
+        value.to_string()
+    } else if let Some(value) = property.number_value() {
+        value.to_string()
+    } else if let Some(value) = property.boolean_value() {
+        if value { "Yes" } else { "No" }.to_string()
+    } else if let Some(value) = property.enum_value() {
+        value.to_string()
+    } else if let Some(value) = property.lat_long_value() {
+        format!(
+            "{:.6}, {:.6}",
+            value.latitude as f64 / LAT_LONG_SCALE,
+            value.longitude as f64 / LAT_LONG_SCALE
+        )
+    } else if let Some(value) = property.bytes_value() {
+        format!("{} bytes", value.len())
+    } else {
+        property
+            .struct_values()
+            .iter()
+            .map(|value| format!("{}: {}", value.property_name(), property_text(value)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Prints lines from the top of the page down, starting a new page when one is full
+struct Page {
+    document: PdfDocumentReference,
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    bold_font: IndirectFontRef,
+    width: f32,
+    height: f32,
+    y: f32,
+}
+
+impl Page {
+    fn new(template: &CertificateTemplate) -> Result<Self, CertificateError> {
+        let (width, height) = template.page_size.dimensions();
+        let (document, page, layer) =
+            PdfDocument::new(&template.title, Mm(width), Mm(height), "Certificate");
+        let font = document.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold_font = document.add_builtin_font(BuiltinFont::HelveticaBold)?;
+        let layer = document.get_page(page).get_layer(layer);
+
+        Ok(Self {
+            document,
+            layer,
+            font,
+            bold_font,
+            width,
+            height,
+            y: height - MARGIN_MM,
+        })
+    }
+
+    fn text_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN_MM
+    }
+
+    /// Moves down by `height`, first starting a new page if there is not room for it
+    fn take(&mut self, height: f32) -> f32 {
+        if self.y - height < MARGIN_MM {
+            let (page, layer) =
+                self.document
+                    .add_page(Mm(self.width), Mm(self.height), "Certificate");
+            self.layer = self.document.get_page(page).get_layer(layer);
+            self.y = self.height - MARGIN_MM;
+        }
+        self.y -= height;
+        self.y
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn line(&mut self, text: &str, size: f32, bold: bool) {
+        let y = self.take(size * PT_MM * 1.4);
+        let font = if bold { &self.bold_font } else { &self.font };
+        self.layer.use_text(text, size, Mm(MARGIN_MM), Mm(y), font);
+    }
+
+    fn heading(
+        &mut self,
+        heading: Option<&str>,
+        values: &HashMap<String, String>,
+    ) -> Result<(), CertificateError> {
+        if let Some(heading) = heading {
+            self.line(&fill(heading, values)?, SUBHEADING_SIZE, true);
+            self.space(1.0);
+        }
+        Ok(())
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32) {
+        for line in wrap(text, chars_in(self.text_width(), size)) {
+            self.line(&line, size, false);
+        }
+    }
+
+    /// Prints a table with a bold header row, giving each column its fraction of the width and
+    /// cutting values that do not fit
+    fn table(&mut self, headers: &[&str], widths: &[f32], rows: Vec<Vec<String>>) {
+        let row_height = BODY_SIZE * PT_MM * 1.5;
+        let columns = widths
+            .iter()
+            .scan(MARGIN_MM, |x, width| {
+                let column = (*x, width * self.text_width());
+                *x += column.1;
+                Some(column)
+            })
+            .collect::<Vec<_>>();
+
+        let headers = headers.iter().map(|header| header.to_string()).collect();
+        for (i, row) in std::iter::once(headers).chain(rows).enumerate() {
+            let y = self.take(row_height);
+            let font = if i == 0 { &self.bold_font } else { &self.font };
+            for ((x, width), value) in columns.iter().zip(row) {
+                let value = truncate(&value, chars_in(*width - 1.0, BODY_SIZE));
+                self.layer.use_text(value, BODY_SIZE, Mm(*x), Mm(y), font);
+            }
+            if i == 0 {
+                self.layer.add_rect(Rect::new(
+                    Mm(MARGIN_MM),
+                    Mm(y - 1.2),
+                    Mm(self.width - MARGIN_MM),
+                    Mm(y - 1.0),
+                ));
+            }
+        }
+    }
+
+    /// Draws the QR code's dark modules as squares, with the code's left edge on the margin
+    fn qr_code(&mut self, content: &str, size: f32) -> Result<(), CertificateError> {
+        let code = QrCode::new(content.as_bytes()).map_err(|err| {
+            CertificateError::invalid("content", format!("Cannot encode QR code: {}", err))
+        })?;
+        let modules = code.width();
+        let module_size = size / modules as f32;
+        let bottom = self.take(size);
+
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            if color == Color::Dark {
+                let x = MARGIN_MM + (i % modules) as f32 * module_size;
+                let y = bottom + size - (i / modules + 1) as f32 * module_size;
+                self.layer.add_rect(Rect::new(
+                    Mm(x),
+                    Mm(y),
+                    Mm(x + module_size),
+                    Mm(y + module_size),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn signature(&mut self, label: &str) {
+        self.space(12.0);
+        let y = self.take(0.3);
+        self.layer.add_rect(Rect::new(
+            Mm(MARGIN_MM),
+            Mm(y),
+            Mm(MARGIN_MM + 70.0),
+            Mm(y + 0.3),
+        ));
+        self.line(label, BODY_SIZE, false);
+    }
+}
+
+/// Returns how many characters of text in the given size fit in the width
+fn chars_in(width: f32, size: f32) -> usize {
+    ((width / (size * PT_MM * CHAR_WIDTH)) as usize).max(1)
+}
+
+/// Breaks text into lines of at most `max` characters, between words where it can
+fn wrap(text: &str, max: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+        while line.chars().count() > max {
+            let rest = line.chars().skip(max).collect();
+            lines.push(line.chars().take(max).collect());
+            line = rest;
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut truncated = text.chars().take(max.saturating_sub(3)).collect::<String>();
+        truncated.push_str("...");
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::{MfgBatchBuilder, PropertyValueBuilder};
+
+    /// Verify that long words and lines are wrapped at the given width
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("a quick brown fox", 8),
+            vec![
+                "a quick".to_string(),
+                "brown".to_string(),
+                "fox".to_string()
+            ]
+        );
+        assert_eq!(
+            wrap("abcdefghij", 4),
+            vec!["abcd".to_string(), "efgh".to_string(), "ij".to_string()]
+        );
+        assert_eq!(wrap("", 4), vec!["".to_string()]);
+    }
+
+    /// Verify that the built in templates render a mfg_batch, across several pages if needed
+    #[test]
+    fn test_render_certificate() {
+        let property = PropertyValueBuilder::default()
+            .with_mfg_batch_id("LOT-7".to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_property_name("lot_code".to_string())
+            .with_data_type("STRING".to_string())
+            .with_string_value(Some("A1".to_string()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build property");
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id("LOT-7".to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner("acme".to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(vec![property])
+            .with_production_date(Some(1_646_092_800))
+            .build()
+            .expect("Failed to build mfg_batch");
+        let test_results = (0..80)
+            .map(|i| MfgBatchTestResult {
+                mfg_batch_id: "LOT-7".to_string(),
+                commit_num: i,
+                test_name: format!("test {}", i),
+                specification: "<= 12.5 %".to_string(),
+                result: "11.8 %".to_string(),
+                passed: i % 10 != 0,
+                lab: "Acme Labs".to_string(),
+                tested_at: 1_646_092_800,
+                service_id: None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            placeholder_values(&mfg_batch, 1_646_179_200).get("production_date"),
+            Some(&"2022-03-01".to_string())
+        );
+
+        for template in super::super::builtin_templates() {
+            let pdf = render_certificate(&template, &mfg_batch, &test_results, 1_646_179_200)
+                .expect("Failed to render certificate");
+            assert!(pdf.starts_with(b"%PDF"));
+        }
+    }
+}
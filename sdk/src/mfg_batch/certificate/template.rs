@@ -0,0 +1,325 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::CertificateError;
+
+/// The fields of a mfg_batch that placeholders other than `{{property.NAME}}` can refer to
+const FIELDS: &[&str] = &[
+    "mfg_batch_id",
+    "owner",
+    "quantity",
+    "expected_quantity",
+    "uom",
+    "production_date",
+    "expiration_date",
+    "issued_date",
+];
+
+/// The prefix of placeholders that refer to a property of the mfg_batch
+const PROPERTY_PREFIX: &str = "property.";
+
+/// The smallest and largest QR codes that can be drawn, in millimetres
+const QR_CODE_SIZE_RANGE: (f32, f32) = (10.0, 100.0);
+
+/// The longest template name accepted, in characters
+pub const MAX_TEMPLATE_NAME_LENGTH: usize = 64;
+
+/// The size of the pages a certificate is printed on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    /// Returns the width and height of the page, in millimetres
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+/// A part of a certificate, printed below the one before it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Section {
+    /// A line of large, bold text
+    Heading { text: String },
+    /// A paragraph, wrapped to the width of the page
+    Text { text: String },
+    /// A table of the mfg_batch's properties, or of only those named
+    Properties {
+        #[serde(default)]
+        heading: Option<String>,
+        #[serde(default)]
+        names: Vec<String>,
+    },
+    /// A table of the quality test results recorded against the mfg_batch, or, as a summary,
+    /// a line saying how many of them passed
+    TestResults {
+        #[serde(default)]
+        heading: Option<String>,
+        #[serde(default)]
+        summary: bool,
+    },
+    /// A list of the certifications, such as organic or kosher, held in the named properties;
+    /// properties the mfg_batch does not have are left out
+    Certifications {
+        #[serde(default)]
+        heading: Option<String>,
+        properties: Vec<String>,
+    },
+    /// A QR code encoding the text, such as a link to the mfg_batch
+    QrCode {
+        content: String,
+        #[serde(default = "default_qr_code_size")]
+        size_mm: f32,
+    },
+    /// A line to be signed, with a label beneath it
+    Signature { label: String },
+}
+
+fn default_qr_code_size() -> f32 {
+    30.0
+}
+
+/// A certificate, such as a certificate of analysis or a release certificate, that can be
+/// filled in for any mfg_batch. Templates are written in YAML:
+///
+/// ```yaml
+/// name: coa
+/// title: Certificate of Analysis
+/// page_size: letter
+/// sections:
+///   - type: heading
+///     text: Certificate of Analysis
+///   - type: text
+///     text: "Batch {{mfg_batch_id}}, produced {{production_date}}"
+///   - type: test_results
+///     heading: Analysis
+///   - type: certifications
+///     properties: [organic_certificate, kosher_certificate]
+///   - type: qr_code
+///     content: "https://example.com/batches/{{mfg_batch_id}}"
+/// ```
+///
+/// Text may refer to the fields of the mfg_batch, to `{{issued_date}}`, and to its properties
+/// as `{{property.NAME}}`. Dates are printed as `YYYY-MM-DD`, and missing values as nothing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CertificateTemplate {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub page_size: PageSize,
+    pub sections: Vec<Section>,
+}
+
+impl CertificateTemplate {
+    /// Parses and validates a template written in YAML, or JSON
+    pub fn from_yaml(yaml: &str) -> Result<Self, CertificateError> {
+        let template: CertificateTemplate = serde_yaml::from_str(yaml)
+            .map_err(|err| CertificateError::invalid("template", err.to_string()))?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, CertificateError> {
+        serde_yaml::to_string(self).map_err(|err| {
+            CertificateError::Internal(crate::error::InternalError::from_source(Box::new(err)))
+        })
+    }
+
+    /// Checks the template's name, that it has something to print, and that its placeholders
+    /// and QR codes can be filled in
+    pub fn validate(&self) -> Result<(), CertificateError> {
+        validate_name(&self.name)?;
+
+        if self.sections.is_empty() {
+            return Err(CertificateError::invalid(
+                "sections",
+                format!("Template {} has no sections", self.name),
+            ));
+        }
+
+        for section in &self.sections {
+            match section {
+                Section::Heading { text }
+                | Section::Text { text }
+                | Section::Signature { label: text } => validate_placeholders(text)?,
+                Section::Properties { heading, .. }
+                | Section::TestResults { heading, .. }
+                | Section::Certifications { heading, .. } => {
+                    if let Some(heading) = heading {
+                        validate_placeholders(heading)?;
+                    }
+                }
+                Section::QrCode { content, size_mm } => {
+                    validate_placeholders(content)?;
+                    if *size_mm < QR_CODE_SIZE_RANGE.0 || *size_mm > QR_CODE_SIZE_RANGE.1 {
+                        return Err(CertificateError::invalid(
+                            "size_mm",
+                            format!(
+                                "QR codes must be between {} and {} mm, not {}",
+                                QR_CODE_SIZE_RANGE.0, QR_CODE_SIZE_RANGE.1, size_mm
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that a template name can be used in a URL and as a file name
+pub fn validate_name(name: &str) -> Result<(), CertificateError> {
+    if name.is_empty()
+        || name.len() > MAX_TEMPLATE_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(CertificateError::invalid(
+            "name",
+            format!(
+                "Template names must be 1 to {} letters, digits, '_' or '-', not {:?}",
+                MAX_TEMPLATE_NAME_LENGTH, name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_placeholders(text: &str) -> Result<(), CertificateError> {
+    for placeholder in placeholders(text)? {
+        let is_property =
+            placeholder.starts_with(PROPERTY_PREFIX) && placeholder.len() > PROPERTY_PREFIX.len();
+        if !is_property && !FIELDS.contains(&placeholder) {
+            return Err(CertificateError::invalid(
+                "template",
+                format!("Unknown placeholder {{{{{}}}}}", placeholder),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the names of the `{{...}}` placeholders in the text, in order
+fn placeholders(text: &str) -> Result<Vec<&str>, CertificateError> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or_else(|| {
+            CertificateError::invalid("template", format!("Unclosed placeholder in {:?}", text))
+        })?;
+        names.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    Ok(names)
+}
+
+/// Replaces the placeholders in the text with their values, or with nothing if the mfg_batch
+/// has no value for them
+pub(super) fn fill(
+    text: &str,
+    values: &HashMap<String, String>,
+) -> Result<String, CertificateError> {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    for name in placeholders(text)? {
+        let start = rest.find("{{").unwrap_or(0);
+        let end = start + rest[start..].find("}}").unwrap_or(0) + 2;
+        filled.push_str(&rest[..start]);
+        if let Some(value) = values.get(name) {
+            filled.push_str(value);
+        }
+        rest = &rest[end..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that templates are parsed with their defaults, and that unknown placeholders,
+    /// unsafe names and oversized QR codes are rejected
+    #[test]
+    fn test_template_from_yaml() {
+        let template = CertificateTemplate::from_yaml(
+            "name: coa
+title: Certificate of Analysis
+sections:
+  - type: text
+    text: \"Batch {{mfg_batch_id}} ({{ property.lot_code }})\"
+  - type: qr_code
+    content: \"{{mfg_batch_id}}\"
+",
+        )
+        .expect("Failed to parse template");
+
+        assert_eq!(template.page_size, PageSize::A4);
+        assert_eq!(
+            template.sections[1],
+            Section::QrCode {
+                content: "{{mfg_batch_id}}".to_string(),
+                size_mm: 30.0,
+            }
+        );
+
+        let invalid = [
+            "name: coa\ntitle: t\nsections:\n  - type: text\n    text: \"{{lot}}\"\n",
+            "name: coa\ntitle: t\nsections:\n  - type: text\n    text: \"{{property.}}\"\n",
+            "name: coa\ntitle: t\nsections:\n  - type: text\n    text: \"{{owner\"\n",
+            "name: ../coa\ntitle: t\nsections:\n  - type: text\n    text: t\n",
+            "name: coa\ntitle: t\nsections: []\n",
+            "name: coa\ntitle: t\nsections:\n  - type: qr_code\n    content: x\n    size_mm: 500\n",
+        ];
+        for yaml in invalid.iter() {
+            assert!(
+                matches!(
+                    CertificateTemplate::from_yaml(yaml),
+                    Err(CertificateError::InvalidArgument(_))
+                ),
+                "{} was accepted",
+                yaml
+            );
+        }
+    }
+
+    /// Verify that placeholders are replaced by their values, or by nothing
+    #[test]
+    fn test_fill() {
+        let mut values = HashMap::new();
+        values.insert("mfg_batch_id".to_string(), "LOT-7".to_string());
+        values.insert("property.lot_code".to_string(), "A1".to_string());
+
+        assert_eq!(
+            fill(
+                "Batch {{mfg_batch_id}} ({{ property.lot_code }}), {{uom}}!",
+                &values
+            )
+            .expect("Failed to fill text"),
+            "Batch LOT-7 (A1), !"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signs mfg_batch payloads as Sabre transactions executing the mfg_batch contract.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use cylinder::Signer;
+use protobuf::Message;
+use sabre_sdk::{
+    protocol::payload::ExecuteContractActionBuilder, protos::IntoBytes as SabreIntoBytes,
+};
+use sawtooth_sdk::messages::{batch, transaction};
+
+use crate::error::InternalError;
+use crate::mfg_batch::addressing::MfgBatchAddresser;
+use crate::pike::addressing::GRID_PIKE_NAMESPACE;
+use crate::protocol::mfg_batch::payload::MfgBatchPayload;
+use crate::protos::IntoBytes;
+use crate::schema::addressing::GRID_SCHEMA_NAMESPACE;
+
+use super::MfgBatchClientError;
+
+const SABRE_FAMILY_NAME: &str = "sabre";
+const SABRE_FAMILY_VERSION: &str = "0.5";
+const SABRE_NAMESPACE_REGISTRY_PREFIX: &str = "00ec00";
+const SABRE_CONTRACT_REGISTRY_PREFIX: &str = "00ec01";
+const SABRE_CONTRACT_PREFIX: &str = "00ec02";
+
+/// The contract a batch's transactions execute
+pub struct Contract<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+}
+
+/// Signs each payload as a transaction and returns the ID of the batch holding them, with the
+/// encoded batch list to submit
+pub fn sign_batch_list(
+    signer: &dyn Signer,
+    addresser: &MfgBatchAddresser,
+    contract: &Contract,
+    payloads: Vec<MfgBatchPayload>,
+) -> Result<(String, Vec<u8>), MfgBatchClientError> {
+    let public_key = signer.public_key().map_err(internal_error)?.as_hex();
+
+    // The contract reads the agents and the property schemas along with the mfg_batches
+    let inputs = vec![
+        GRID_PIKE_NAMESPACE.to_string(),
+        GRID_SCHEMA_NAMESPACE.to_string(),
+        addresser.mfg_batch_namespace(),
+    ];
+    let outputs = vec![addresser.mfg_batch_namespace()];
+
+    let transactions = payloads
+        .into_iter()
+        .map(|payload| sign_transaction(signer, &public_key, contract, payload, &inputs, &outputs))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut batch_header = batch::BatchHeader::new();
+    batch_header.set_transaction_ids(protobuf::RepeatedField::from_vec(
+        transactions
+            .iter()
+            .map(|txn| txn.header_signature.clone())
+            .collect(),
+    ));
+    batch_header.set_signer_public_key(public_key);
+    let batch_header_bytes = batch_header.write_to_bytes().map_err(internal_error)?;
+
+    let batch_id = signer
+        .sign(&batch_header_bytes)
+        .map_err(internal_error)?
+        .as_hex();
+
+    let mut batch = batch::Batch::new();
+    batch.set_header(batch_header_bytes);
+    batch.set_header_signature(batch_id.clone());
+    batch.set_transactions(protobuf::RepeatedField::from_vec(transactions));
+
+    let mut batch_list = batch::BatchList::new();
+    batch_list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
+
+    Ok((
+        batch_id,
+        batch_list.write_to_bytes().map_err(internal_error)?,
+    ))
+}
+
+fn sign_transaction(
+    signer: &dyn Signer,
+    public_key: &str,
+    contract: &Contract,
+    payload: MfgBatchPayload,
+    inputs: &[String],
+    outputs: &[String],
+) -> Result<transaction::Transaction, MfgBatchClientError> {
+    let sabre_payload = ExecuteContractActionBuilder::new()
+        .with_name(contract.name.to_string())
+        .with_version(contract.version.to_string())
+        .with_inputs(inputs.to_vec())
+        .with_outputs(outputs.to_vec())
+        .with_payload(payload.into_bytes().map_err(internal_error)?)
+        .into_payload_builder()
+        .map_err(internal_error)?
+        .build()
+        .map_err(internal_error)?
+        .into_bytes()
+        .map_err(internal_error)?;
+
+    let mut txn_header = transaction::TransactionHeader::new();
+    txn_header.set_family_name(SABRE_FAMILY_NAME.into());
+    txn_header.set_family_version(SABRE_FAMILY_VERSION.into());
+    txn_header.set_nonce(create_nonce());
+    txn_header.set_signer_public_key(public_key.to_string());
+    txn_header.set_batcher_public_key(public_key.to_string());
+    txn_header.set_inputs(protobuf::RepeatedField::from_vec(sabre_addresses(
+        contract, inputs,
+    )));
+    txn_header.set_outputs(protobuf::RepeatedField::from_vec(sabre_addresses(
+        contract, outputs,
+    )));
+    txn_header.set_payload_sha512(bytes_to_hex_str(&sha512(&sabre_payload)));
+    let txn_header_bytes = txn_header.write_to_bytes().map_err(internal_error)?;
+
+    let mut txn = transaction::Transaction::new();
+    txn.set_header_signature(
+        signer
+            .sign(&txn_header_bytes)
+            .map_err(internal_error)?
+            .as_hex(),
+    );
+    txn.set_header(txn_header_bytes);
+    txn.set_payload(sabre_payload);
+
+    Ok(txn)
+}
+
+/// Returns the addresses Sabre needs to execute the contract, followed by the contract's own
+fn sabre_addresses(contract: &Contract, addresses: &[String]) -> Vec<String> {
+    let mut sabre_addresses = vec![
+        compute_contract_registry_address(contract.name),
+        compute_contract_address(contract.name, contract.version),
+    ];
+    sabre_addresses.extend(
+        addresses
+            .iter()
+            .map(|address| compute_namespace_registry_address(&address[..6])),
+    );
+    sabre_addresses.extend(addresses.iter().cloned());
+
+    sabre_addresses
+}
+
+/// Creates a nonce appropriate for a TransactionHeader
+fn create_nonce() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}{}", now.as_secs(), now.subsec_nanos())
+}
+
+fn compute_contract_registry_address(name: &str) -> String {
+    String::from(SABRE_CONTRACT_REGISTRY_PREFIX) + &bytes_to_hex_str(&sha512(name.as_bytes()))[..64]
+}
+
+fn compute_contract_address(name: &str, version: &str) -> String {
+    let key = format!("{},{}", name, version);
+    String::from(SABRE_CONTRACT_PREFIX) + &bytes_to_hex_str(&sha512(key.as_bytes()))[..64]
+}
+
+fn compute_namespace_registry_address(namespace: &str) -> String {
+    String::from(SABRE_NAMESPACE_REGISTRY_PREFIX)
+        + &bytes_to_hex_str(&sha512(namespace.as_bytes()))[..64]
+}
+
+fn sha512(bytes: &[u8]) -> [u8; 64] {
+    let mut hash = [0; 64];
+    let mut sha = Sha512::new();
+    sha.input(bytes);
+    sha.result(&mut hash);
+    hash
+}
+
+fn bytes_to_hex_str(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn internal_error<E: std::error::Error + 'static>(err: E) -> MfgBatchClientError {
+    MfgBatchClientError::InternalError(InternalError::from_source(Box::new(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context};
+
+    use crate::protocol::mfg_batch::payload::{
+        Action, MfgBatchDeleteActionBuilder, MfgBatchPayloadBuilder,
+    };
+    use crate::protocol::mfg_batch::state::MfgBatchNamespace;
+
+    /// Verify that a payload is signed into a Sabre transaction of the contract, reading the
+    /// agents, schemas and mfg_batches and writing only mfg_batches
+    #[test]
+    fn test_sign_batch_list() {
+        let context = Secp256k1Context::new();
+        let signer = context.new_signer(context.new_random_private_key());
+        let addresser = MfgBatchAddresser::default();
+
+        let action = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .build()
+            .expect("Unable to build action");
+        let payload = MfgBatchPayloadBuilder::new()
+            .with_action(Action::MfgBatchDelete(action))
+            .with_timestamp(1)
+            .build()
+            .expect("Unable to build payload");
+
+        let contract = Contract {
+            name: "grid_mfg_batch",
+            version: "1",
+        };
+        let (batch_id, bytes) = sign_batch_list(&*signer, &addresser, &contract, vec![payload])
+            .expect("Unable to sign batch");
+
+        let batch_list: batch::BatchList =
+            batch::BatchList::parse_from_bytes(&bytes).expect("Unable to parse batch list");
+        assert_eq!(batch_list.get_batches().len(), 1);
+        let batch = &batch_list.get_batches()[0];
+        assert_eq!(batch.get_header_signature(), batch_id);
+        assert_eq!(batch.get_transactions().len(), 1);
+
+        let header: transaction::TransactionHeader =
+            transaction::TransactionHeader::parse_from_bytes(
+                batch.get_transactions()[0].get_header(),
+            )
+            .expect("Unable to parse header");
+        assert_eq!(header.get_family_name(), SABRE_FAMILY_NAME);
+        assert!(header
+            .get_inputs()
+            .contains(&compute_contract_address("grid_mfg_batch", "1")));
+        assert!(header
+            .get_inputs()
+            .contains(&GRID_PIKE_NAMESPACE.to_string()));
+        assert!(header
+            .get_outputs()
+            .contains(&addresser.mfg_batch_namespace()));
+        assert!(!header
+            .get_outputs()
+            .contains(&GRID_PIKE_NAMESPACE.to_string()));
+    }
+}
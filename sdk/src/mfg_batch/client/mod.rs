@@ -0,0 +1,391 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Clients that submit mfg_batch transactions to, and read mfg_batches from, the ledger directly.
+//!
+//! Unlike the clients of `grid_sdk::client`, which talk to the Grid REST API, these sign each
+//! action as a transaction of the mfg_batch Sabre contract, submit it to a Sawtooth REST API or
+//! a Splinter scabbard service, and wait for the batch to be committed. A rejected transaction is
+//! reported with the contract's message, which starts with the code of the failure, for example
+//! `[validation:quantity]`.
+//!
+//! Mfg_batches are read from the state the contract writes, so they are the records as the
+//! contract stores them rather than the Grid REST API's view of them.
+
+mod batch;
+mod sawtooth;
+mod scabbard;
+
+use std::error::Error;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::{InternalError, InvalidArgumentError};
+use crate::mfg_batch::addressing::{MfgBatchAddresser, GRID_MFG_BATCH_FAMILY_NAME};
+use crate::protocol::mfg_batch::{
+    payload::{
+        Action, MfgBatchCreateAction, MfgBatchDeleteAction, MfgBatchPayloadBuilder,
+        MfgBatchUpdateAction,
+    },
+    state::{MfgBatch, MfgBatchList, MfgBatchNamespace},
+};
+use crate::protos::FromBytes;
+
+pub use sawtooth::SawtoothMfgBatchClient;
+pub use scabbard::ScabbardMfgBatchClient;
+
+/// The version of the mfg_batch contract transactions execute, unless another is given
+pub const DEFAULT_CONTRACT_VERSION: &str = "1";
+
+/// How long to wait between polls of a batch the node reports as not yet committed
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub trait MfgBatchClient {
+    /// Signs each action as a transaction and submits them together in one batch. Waits up to
+    /// `wait` seconds for the batch to be committed, or not at all if `wait` is 0. Returns the
+    /// ID of the batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `actions` - The actions to be submitted, in the order they are applied
+    ///  * `wait` - The number of seconds to wait for the batch to be committed
+    fn submit_actions(
+        &self,
+        actions: Vec<Action>,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError>;
+
+    /// Submits an action creating a mfg_batch. Returns the ID of the batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `action` - The action to be submitted
+    ///  * `wait` - The number of seconds to wait for the batch to be committed
+    fn create_mfg_batch(
+        &self,
+        action: MfgBatchCreateAction,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError> {
+        self.submit_actions(vec![Action::MfgBatchCreate(action)], wait)
+    }
+
+    /// Submits an action updating a mfg_batch. Returns the ID of the batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `action` - The action to be submitted
+    ///  * `wait` - The number of seconds to wait for the batch to be committed
+    fn update_mfg_batch(
+        &self,
+        action: MfgBatchUpdateAction,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError> {
+        self.submit_actions(vec![Action::MfgBatchUpdate(action)], wait)
+    }
+
+    /// Submits an action deleting or archiving a mfg_batch. Returns the ID of the batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `action` - The action to be submitted
+    ///  * `wait` - The number of seconds to wait for the batch to be committed
+    fn delete_mfg_batch(
+        &self,
+        action: MfgBatchDeleteAction,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError> {
+        self.submit_actions(vec![Action::MfgBatchDelete(action)], wait)
+    }
+
+    /// Fetches a mfg_batch from state, if it exists
+    ///
+    /// # Arguments
+    ///
+    ///  * `namespace` - The namespace the mfg_batch is recorded under
+    ///  * `mfg_batch_id` - The ID of the mfg_batch
+    fn get_mfg_batch(
+        &self,
+        namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> Result<Option<MfgBatch>, MfgBatchClientError>;
+
+    /// Fetches every mfg_batch in state, in address order
+    fn list_mfg_batches(&self) -> Result<Vec<MfgBatch>, MfgBatchClientError>;
+}
+
+/// Represents MfgBatchClient errors
+#[derive(Debug)]
+pub enum MfgBatchClientError {
+    /// Returned when the client is given an argument it cannot use
+    InvalidArgumentError(InvalidArgumentError),
+    /// Returned when the client fails to build or sign a batch, or to decode state
+    InternalError(InternalError),
+    /// Returned when a request fails or the node responds with an error
+    RequestError(String),
+    /// Returned when a transaction of the batch is rejected; the message is the contract's
+    InvalidTransaction {
+        transaction_id: String,
+        message: String,
+    },
+    /// Returned when the batch with the given ID is not committed within the wait
+    Pending(String),
+}
+
+impl MfgBatchClientError {
+    /// Returns the code the contract rejected the transaction with, such as `validation:quantity`,
+    /// if the error is a rejected transaction whose message starts with one
+    pub fn rejection_code(&self) -> Option<&str> {
+        match self {
+            MfgBatchClientError::InvalidTransaction { message, .. } => message
+                .strip_prefix('[')
+                .and_then(|message| message.split_once(']'))
+                .map(|(code, _)| code),
+            _ => None,
+        }
+    }
+}
+
+impl Error for MfgBatchClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MfgBatchClientError::InvalidArgumentError(err) => Some(err),
+            MfgBatchClientError::InternalError(err) => Some(err),
+            MfgBatchClientError::RequestError(_) => None,
+            MfgBatchClientError::InvalidTransaction { .. } => None,
+            MfgBatchClientError::Pending(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for MfgBatchClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MfgBatchClientError::InvalidArgumentError(err) => write!(f, "{}", err),
+            MfgBatchClientError::InternalError(err) => write!(f, "{}", err),
+            MfgBatchClientError::RequestError(message) => write!(f, "{}", message),
+            MfgBatchClientError::InvalidTransaction {
+                transaction_id,
+                message,
+            } => write!(f, "transaction {} is invalid: {}", transaction_id, message),
+            MfgBatchClientError::Pending(batch_id) => {
+                write!(f, "batch {} was not committed in time", batch_id)
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for MfgBatchClientError {
+    fn from(err: reqwest::Error) -> Self {
+        MfgBatchClientError::RequestError(format!("Request failed: {}", err))
+    }
+}
+
+/// The status of a submitted batch, as the node reports it
+#[derive(Debug, PartialEq)]
+enum BatchStatus {
+    Committed,
+    /// The rejected transactions' IDs and messages
+    Invalid(Vec<(String, String)>),
+    /// The batch is not committed yet, or the node does not know of it yet
+    Pending,
+}
+
+/// The requests a client makes of the node it submits to
+trait Ledger {
+    /// Submits an encoded batch list
+    fn post_batch_list(&self, batch_list: Vec<u8>) -> Result<(), MfgBatchClientError>;
+
+    /// Fetches the status of a batch, waiting up to `wait` seconds for it to be committed
+    fn batch_status(&self, batch_id: &str, wait: u64) -> Result<BatchStatus, MfgBatchClientError>;
+
+    /// Fetches the entry at an address, if there is one
+    fn get_state(&self, address: &str) -> Result<Option<Vec<u8>>, MfgBatchClientError>;
+
+    /// Fetches every entry under an address prefix, in address order
+    fn list_state(&self, prefix: &str) -> Result<Vec<Vec<u8>>, MfgBatchClientError>;
+}
+
+/// The settings shared by the clients
+struct ClientSettings {
+    signer: Box<dyn cylinder::Signer>,
+    addresser: MfgBatchAddresser,
+    contract_name: String,
+    contract_version: String,
+}
+
+impl ClientSettings {
+    fn new(signer: Box<dyn cylinder::Signer>) -> Self {
+        ClientSettings {
+            signer,
+            addresser: MfgBatchAddresser::default(),
+            contract_name: GRID_MFG_BATCH_FAMILY_NAME.to_string(),
+            contract_version: DEFAULT_CONTRACT_VERSION.to_string(),
+        }
+    }
+}
+
+/// Builds a payload of each action, signs them into a batch, submits it and waits for it
+fn submit_actions(
+    ledger: &dyn Ledger,
+    settings: &ClientSettings,
+    service_id: Option<&str>,
+    actions: Vec<Action>,
+    wait: u64,
+) -> Result<String, MfgBatchClientError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .map_err(|err| {
+            MfgBatchClientError::InternalError(InternalError::from_source(Box::new(err)))
+        })?;
+
+    let payloads = actions
+        .into_iter()
+        .map(|action| {
+            let mut builder = MfgBatchPayloadBuilder::new()
+                .with_action(action)
+                .with_timestamp(timestamp);
+            if let Some(service_id) = service_id {
+                builder = builder.with_service_id(service_id.to_string());
+            }
+            builder.build().map_err(|err| {
+                MfgBatchClientError::InvalidArgumentError(InvalidArgumentError::new(
+                    "actions".to_string(),
+                    err.to_string(),
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (batch_id, batch_list) = batch::sign_batch_list(
+        &*settings.signer,
+        &settings.addresser,
+        &batch::Contract {
+            name: &settings.contract_name,
+            version: &settings.contract_version,
+        },
+        payloads,
+    )?;
+    ledger.post_batch_list(batch_list)?;
+
+    if wait == 0 {
+        return Ok(batch_id);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(wait);
+    loop {
+        let remaining = deadline
+            .saturating_duration_since(Instant::now())
+            .as_secs_f64()
+            .ceil() as u64;
+        match ledger.batch_status(&batch_id, remaining)? {
+            BatchStatus::Committed => return Ok(batch_id),
+            BatchStatus::Invalid(mut transactions) => {
+                let (transaction_id, message) = if transactions.is_empty() {
+                    (String::new(), "batch is invalid".to_string())
+                } else {
+                    transactions.remove(0)
+                };
+                return Err(MfgBatchClientError::InvalidTransaction {
+                    transaction_id,
+                    message,
+                });
+            }
+            BatchStatus::Pending if Instant::now() >= deadline => {
+                return Err(MfgBatchClientError::Pending(batch_id))
+            }
+            BatchStatus::Pending => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Fetches the entry at the mfg_batch's address and finds the mfg_batch in it
+fn get_mfg_batch(
+    ledger: &dyn Ledger,
+    addresser: &MfgBatchAddresser,
+    namespace: &MfgBatchNamespace,
+    mfg_batch_id: &str,
+) -> Result<Option<MfgBatch>, MfgBatchClientError> {
+    #[cfg(not(feature = "mfg-batch-addressing-v2"))]
+    let address = addresser.compute_mfg_batch_address(namespace, mfg_batch_id);
+    #[cfg(feature = "mfg-batch-addressing-v2")]
+    let address = addresser.compute_mfg_batch_address_v2(namespace, mfg_batch_id);
+
+    match ledger.get_state(&address)? {
+        Some(entry) => Ok(decode_entry(&entry)?.into_iter().find(|mfg_batch| {
+            mfg_batch.mfg_batch_id() == mfg_batch_id && mfg_batch.mfg_batch_namespace() == namespace
+        })),
+        None => Ok(None),
+    }
+}
+
+/// Fetches every entry under the mfg_batch namespace and decodes the mfg_batches in them
+fn list_mfg_batches(
+    ledger: &dyn Ledger,
+    addresser: &MfgBatchAddresser,
+) -> Result<Vec<MfgBatch>, MfgBatchClientError> {
+    let mut mfg_batches = Vec::new();
+    for entry in ledger.list_state(&addresser.mfg_batch_namespace())? {
+        mfg_batches.extend(decode_entry(&entry)?);
+    }
+
+    Ok(mfg_batches)
+}
+
+/// Decodes the mfg_batches stored at one address
+fn decode_entry(entry: &[u8]) -> Result<Vec<MfgBatch>, MfgBatchClientError> {
+    MfgBatchList::from_bytes(entry)
+        .map(|list| list.mfg_batches().to_vec())
+        .map_err(|err| {
+            MfgBatchClientError::InternalError(InternalError::from_source(Box::new(err)))
+        })
+}
+
+/// Returns the message of an error response, or its body if it does not have the expected form
+fn error_message(
+    response: reqwest::blocking::Response,
+    read_message: impl Fn(&str) -> Option<String>,
+) -> MfgBatchClientError {
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    let message = read_message(&body).unwrap_or(body);
+
+    MfgBatchClientError::RequestError(format!("Node responded with {}: {}", status, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the code is read from the start of a rejected transaction's message
+    #[test]
+    fn test_rejection_code() {
+        let err = MfgBatchClientError::InvalidTransaction {
+            transaction_id: "abc".to_string(),
+            message: "[validation:quantity] Quantity may not be negative: -1".to_string(),
+        };
+        assert_eq!(err.rejection_code(), Some("validation:quantity"));
+
+        let err = MfgBatchClientError::InvalidTransaction {
+            transaction_id: "abc".to_string(),
+            message: "Quantity may not be negative".to_string(),
+        };
+        assert_eq!(err.rejection_code(), None);
+        assert_eq!(
+            MfgBatchClientError::Pending("abc".to_string()).rejection_code(),
+            None
+        );
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use reqwest::{blocking::Client, StatusCode};
+
+use crate::error::InternalError;
+use crate::mfg_batch::addressing::MfgBatchAddresser;
+use crate::protocol::mfg_batch::{
+    payload::Action,
+    state::{MfgBatch, MfgBatchNamespace},
+};
+
+use super::{
+    error_message, BatchStatus, ClientSettings, Ledger, MfgBatchClient, MfgBatchClientError,
+};
+
+/// Submits mfg_batch transactions to, and reads mfg_batches from, a Sawtooth REST API
+pub struct SawtoothMfgBatchClient {
+    url: String,
+    settings: ClientSettings,
+}
+
+impl SawtoothMfgBatchClient {
+    /// Creates a client of the Sawtooth REST API at `url`, signing with `signer`
+    pub fn new(url: String, signer: Box<dyn cylinder::Signer>) -> Self {
+        SawtoothMfgBatchClient {
+            url: url.trim_end_matches('/').to_string(),
+            settings: ClientSettings::new(signer),
+        }
+    }
+
+    /// Uses the namespace of the given addresser instead of the default one
+    pub fn with_addresser(mut self, addresser: MfgBatchAddresser) -> Self {
+        self.settings.addresser = addresser;
+        self
+    }
+
+    /// Executes the contract registered under the given name instead of the default one
+    pub fn with_contract_name(mut self, contract_name: String) -> Self {
+        self.settings.contract_name = contract_name;
+        self
+    }
+
+    /// Executes the given version of the contract instead of the default one
+    pub fn with_contract_version(mut self, contract_version: String) -> Self {
+        self.settings.contract_version = contract_version;
+        self
+    }
+}
+
+impl MfgBatchClient for SawtoothMfgBatchClient {
+    fn submit_actions(
+        &self,
+        actions: Vec<Action>,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError> {
+        super::submit_actions(self, &self.settings, None, actions, wait)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> Result<Option<MfgBatch>, MfgBatchClientError> {
+        super::get_mfg_batch(self, &self.settings.addresser, namespace, mfg_batch_id)
+    }
+
+    fn list_mfg_batches(&self) -> Result<Vec<MfgBatch>, MfgBatchClientError> {
+        super::list_mfg_batches(self, &self.settings.addresser)
+    }
+}
+
+impl Ledger for SawtoothMfgBatchClient {
+    fn post_batch_list(&self, batch_list: Vec<u8>) -> Result<(), MfgBatchClientError> {
+        let response = Client::new()
+            .post(&format!("{}/batches", self.url))
+            .header("Content-Type", "application/octet-stream")
+            .body(batch_list)
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(error_message(response, read_error));
+        }
+
+        Ok(())
+    }
+
+    fn batch_status(&self, batch_id: &str, wait: u64) -> Result<BatchStatus, MfgBatchClientError> {
+        let response = Client::new()
+            .get(&format!("{}/batch_statuses", self.url))
+            .query(&[("id", batch_id.to_string()), ("wait", wait.to_string())])
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(error_message(response, read_error));
+        }
+
+        // Only the one batch is asked for, so the node answers with only its status
+        let status = response
+            .json::<DataResponse<Vec<BatchStatusResponse>>>()?
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                MfgBatchClientError::RequestError(format!(
+                    "No status was returned for batch {}",
+                    batch_id
+                ))
+            })?;
+
+        Ok(match status.status.as_str() {
+            "COMMITTED" => BatchStatus::Committed,
+            "INVALID" => BatchStatus::Invalid(
+                status
+                    .invalid_transactions
+                    .into_iter()
+                    .map(|txn| (txn.id, txn.message))
+                    .collect(),
+            ),
+            _ => BatchStatus::Pending,
+        })
+    }
+
+    fn get_state(&self, address: &str) -> Result<Option<Vec<u8>>, MfgBatchClientError> {
+        let response = Client::new()
+            .get(&format!("{}/state/{}", self.url, address))
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let entry = response.json::<DataResponse<String>>()?;
+                decode_data(&entry.data).map(Some)
+            }
+            _ => Err(error_message(response, read_error)),
+        }
+    }
+
+    fn list_state(&self, prefix: &str) -> Result<Vec<Vec<u8>>, MfgBatchClientError> {
+        let mut entries = Vec::new();
+        let mut url = format!("{}/state?address={}", self.url, prefix);
+        loop {
+            let response = Client::new().get(&url).send()?;
+            if !response.status().is_success() {
+                return Err(error_message(response, read_error));
+            }
+
+            let page = response.json::<StatePage>()?;
+            for entry in page.data {
+                entries.push(decode_data(&entry.data)?);
+            }
+
+            match page.paging.and_then(|paging| paging.next) {
+                Some(next) => url = next,
+                None => return Ok(entries),
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DataResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct BatchStatusResponse {
+    status: String,
+    #[serde(default)]
+    invalid_transactions: Vec<InvalidTransaction>,
+}
+
+#[derive(Deserialize)]
+struct InvalidTransaction {
+    id: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct StatePage {
+    data: Vec<StateEntry>,
+    paging: Option<Paging>,
+}
+
+#[derive(Deserialize)]
+struct StateEntry {
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct Paging {
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+fn read_error(body: &str) -> Option<String> {
+    serde_json::from_str::<ErrorResponse>(body)
+        .ok()
+        .map(|response| response.error.message)
+}
+
+fn decode_data(data: &str) -> Result<Vec<u8>, MfgBatchClientError> {
+    base64::decode(data).map_err(|err| {
+        MfgBatchClientError::InternalError(InternalError::from_source(Box::new(err)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context};
+    use mockito::{mock, Matcher};
+
+    use crate::protocol::mfg_batch::payload::{MfgBatchDeleteAction, MfgBatchDeleteActionBuilder};
+    use crate::protocol::mfg_batch::state::{MfgBatchBuilder, MfgBatchListBuilder};
+    use crate::protos::IntoBytes;
+
+    fn client() -> SawtoothMfgBatchClient {
+        let context = Secp256k1Context::new();
+        SawtoothMfgBatchClient::new(
+            mockito::server_url(),
+            context.new_signer(context.new_random_private_key()),
+        )
+    }
+
+    fn delete_action() -> MfgBatchDeleteAction {
+        MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .build()
+            .expect("Unable to build action")
+    }
+
+    /// Verify that a rejected batch is reported with the contract's message and the rejected
+    /// transaction's ID
+    #[test]
+    fn test_submit_invalid() {
+        let _batches = mock("POST", "/batches")
+            .match_header("Content-Type", "application/octet-stream")
+            .with_status(202)
+            .with_body(r#"{"link": "/batch_statuses?id=abc"}"#)
+            .create();
+        let _statuses = mock("GET", "/batch_statuses")
+            .match_query(Matcher::UrlEncoded("wait".into(), "5".into()))
+            .with_status(200)
+            .with_body(
+                r#"{"data": [{"id": "abc", "status": "INVALID", "invalid_transactions": [
+                    {"id": "def", "message": "[state:missing] Mfg batch batch-1 does not exist"}
+                ]}]}"#,
+            )
+            .create();
+
+        let err = client()
+            .delete_mfg_batch(delete_action(), 5)
+            .expect_err("The batch should be invalid");
+        match &err {
+            MfgBatchClientError::InvalidTransaction { transaction_id, .. } => {
+                assert_eq!(transaction_id, "def")
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+        assert_eq!(err.rejection_code(), Some("state:missing"));
+    }
+
+    /// Verify that submitting without waiting returns the ID of the signed batch
+    #[test]
+    fn test_submit_without_wait() {
+        let _batches = mock("POST", "/batches")
+            .with_status(202)
+            .with_body("{}")
+            .create();
+        let batch_id = client()
+            .delete_mfg_batch(delete_action(), 0)
+            .expect("Unable to submit batch");
+        assert_eq!(batch_id.len(), 128);
+    }
+
+    /// Verify that a mfg_batch is decoded from the entry at its address, and that a missing
+    /// entry is reported as no mfg_batch
+    #[test]
+    fn test_get_mfg_batch() {
+        let mfg_batch = MfgBatchBuilder::new()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("myorg".to_string())
+            .with_properties(vec![])
+            .build()
+            .expect("Unable to build mfg_batch");
+        let entry = MfgBatchListBuilder::new()
+            .with_mfg_batches(vec![mfg_batch.clone()])
+            .build()
+            .expect("Unable to build mfg_batch list")
+            .into_bytes()
+            .expect("Unable to encode mfg_batch list");
+
+        let client = client();
+        let address = MfgBatchAddresser::default()
+            .compute_mfg_batch_address(&MfgBatchNamespace::Gs1, "batch-1");
+        let _entry = mock("GET", format!("/state/{}", address).as_str())
+            .with_status(200)
+            .with_body(format!(r#"{{"data": "{}"}}"#, base64::encode(&entry)))
+            .create();
+        let missing_address = MfgBatchAddresser::default()
+            .compute_mfg_batch_address(&MfgBatchNamespace::Gs1, "batch-2");
+        let _missing = mock("GET", format!("/state/{}", missing_address).as_str())
+            .with_status(404)
+            .with_body(r#"{"error": {"message": "Not found"}}"#)
+            .create();
+
+        assert_eq!(
+            client
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, "batch-1")
+                .expect("Unable to get mfg_batch"),
+            Some(mfg_batch)
+        );
+        assert_eq!(
+            client
+                .get_mfg_batch(&MfgBatchNamespace::Gs1, "batch-2")
+                .expect("Unable to get mfg_batch"),
+            None
+        );
+    }
+}
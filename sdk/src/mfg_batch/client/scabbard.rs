@@ -0,0 +1,377 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use reqwest::{blocking::Client, StatusCode};
+
+use crate::error::InvalidArgumentError;
+use crate::mfg_batch::addressing::MfgBatchAddresser;
+use crate::mfg_batch::validation::validate_service_id;
+use crate::protocol::mfg_batch::{
+    payload::Action,
+    state::{MfgBatch, MfgBatchNamespace},
+};
+
+use super::{
+    error_message, BatchStatus, ClientSettings, Ledger, MfgBatchClient, MfgBatchClientError,
+};
+
+/// Submits mfg_batch transactions to, and reads mfg_batches from, a Splinter scabbard service
+///
+/// The service ID is recorded in each payload, so the contract keeps the mfg_batches of each
+/// circuit apart.
+pub struct ScabbardMfgBatchClient {
+    url: String,
+    service_id: String,
+    authorization: String,
+    settings: ClientSettings,
+}
+
+impl ScabbardMfgBatchClient {
+    /// Creates a client of the scabbard service `service_id`, of the form
+    /// `<circuit_id>::<service_id>`, on the Splinter node at `url`
+    ///
+    /// # Arguments
+    ///
+    ///  * `url` - The URL of the Splinter node's REST API
+    ///  * `service_id` - The fully qualified ID of the scabbard service
+    ///  * `authorization` - The value of the Authorization header of each request
+    ///  * `signer` - The signer of the transactions and batches
+    pub fn new(
+        url: String,
+        service_id: String,
+        authorization: String,
+        signer: Box<dyn cylinder::Signer>,
+    ) -> Result<Self, MfgBatchClientError> {
+        if service_id.is_empty() {
+            return Err(MfgBatchClientError::InvalidArgumentError(
+                InvalidArgumentError::new("service_id".to_string(), "must be provided".to_string()),
+            ));
+        }
+        validate_service_id(&service_id).map_err(|violation| {
+            MfgBatchClientError::InvalidArgumentError(InvalidArgumentError::new(
+                "service_id".to_string(),
+                violation.to_string(),
+            ))
+        })?;
+
+        Ok(ScabbardMfgBatchClient {
+            url: url.trim_end_matches('/').to_string(),
+            service_id,
+            authorization,
+            settings: ClientSettings::new(signer),
+        })
+    }
+
+    /// Uses the namespace of the given addresser instead of the default one
+    pub fn with_addresser(mut self, addresser: MfgBatchAddresser) -> Self {
+        self.settings.addresser = addresser;
+        self
+    }
+
+    /// Executes the contract registered under the given name instead of the default one
+    pub fn with_contract_name(mut self, contract_name: String) -> Self {
+        self.settings.contract_name = contract_name;
+        self
+    }
+
+    /// Executes the given version of the contract instead of the default one
+    pub fn with_contract_version(mut self, contract_version: String) -> Self {
+        self.settings.contract_version = contract_version;
+        self
+    }
+
+    fn service_url(&self, path: &str) -> String {
+        // The service ID was validated as `<circuit_id>::<service_id>` on creation
+        let (circuit_id, service_id) = self
+            .service_id
+            .split_once("::")
+            .unwrap_or((&self.service_id, ""));
+        format!(
+            "{}/scabbard/{}/{}/{}",
+            self.url, circuit_id, service_id, path
+        )
+    }
+}
+
+impl MfgBatchClient for ScabbardMfgBatchClient {
+    fn submit_actions(
+        &self,
+        actions: Vec<Action>,
+        wait: u64,
+    ) -> Result<String, MfgBatchClientError> {
+        super::submit_actions(self, &self.settings, Some(&self.service_id), actions, wait)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        namespace: &MfgBatchNamespace,
+        mfg_batch_id: &str,
+    ) -> Result<Option<MfgBatch>, MfgBatchClientError> {
+        super::get_mfg_batch(self, &self.settings.addresser, namespace, mfg_batch_id)
+    }
+
+    fn list_mfg_batches(&self) -> Result<Vec<MfgBatch>, MfgBatchClientError> {
+        super::list_mfg_batches(self, &self.settings.addresser)
+    }
+}
+
+impl Ledger for ScabbardMfgBatchClient {
+    fn post_batch_list(&self, batch_list: Vec<u8>) -> Result<(), MfgBatchClientError> {
+        let response = Client::new()
+            .post(&self.service_url("batches"))
+            .header("Content-Type", "application/octet-stream")
+            .header("Authorization", &self.authorization)
+            .body(batch_list)
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(error_message(response, read_error));
+        }
+
+        Ok(())
+    }
+
+    fn batch_status(&self, batch_id: &str, wait: u64) -> Result<BatchStatus, MfgBatchClientError> {
+        let response = Client::new()
+            .get(&self.service_url("batch_statuses"))
+            .query(&[("wait", wait.to_string()), ("ids", batch_id.to_string())])
+            .header("Authorization", &self.authorization)
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(error_message(response, read_error));
+        }
+
+        // Only the one batch is asked for, so the service answers with only its status
+        let status = response
+            .json::<Vec<ScabbardBatchStatus>>()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                MfgBatchClientError::RequestError(format!(
+                    "No status was returned for batch {}",
+                    batch_id
+                ))
+            })?
+            .status;
+
+        // A batch is "Valid" once executed, before it is committed
+        Ok(match status.status_type.as_str() {
+            "Committed" => BatchStatus::Committed,
+            "Invalid" => BatchStatus::Invalid(
+                status
+                    .message
+                    .into_iter()
+                    .map(|message| {
+                        (
+                            message.transaction_id,
+                            message.error_message.unwrap_or_default(),
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => BatchStatus::Pending,
+        })
+    }
+
+    fn get_state(&self, address: &str) -> Result<Option<Vec<u8>>, MfgBatchClientError> {
+        let response = Client::new()
+            .get(&self.service_url(&format!("state/{}", address)))
+            .header("Authorization", &self.authorization)
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.json::<Vec<u8>>()?)),
+            _ => Err(error_message(response, read_error)),
+        }
+    }
+
+    fn list_state(&self, prefix: &str) -> Result<Vec<Vec<u8>>, MfgBatchClientError> {
+        let response = Client::new()
+            .get(&self.service_url("state"))
+            .query(&[("prefix", prefix)])
+            .header("Authorization", &self.authorization)
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(error_message(response, read_error));
+        }
+
+        let mut entries = response.json::<Vec<StateEntry>>()?;
+        entries.sort_by(|a, b| a.address.cmp(&b.address));
+
+        Ok(entries.into_iter().map(|entry| entry.value).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct ScabbardBatchStatus {
+    status: Status,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    #[serde(rename(deserialize = "statusType"))]
+    status_type: String,
+    #[serde(default)]
+    message: Vec<ErrorMessage>,
+}
+
+#[derive(Deserialize)]
+struct ErrorMessage {
+    transaction_id: String,
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StateEntry {
+    address: String,
+    value: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+fn read_error(body: &str) -> Option<String> {
+    serde_json::from_str::<ErrorResponse>(body)
+        .ok()
+        .map(|response| response.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context};
+    use mockito::{mock, Matcher};
+
+    use crate::protocol::mfg_batch::payload::{MfgBatchDeleteAction, MfgBatchDeleteActionBuilder};
+    use crate::protocol::mfg_batch::state::{MfgBatchBuilder, MfgBatchListBuilder};
+    use crate::protos::IntoBytes;
+
+    fn client() -> ScabbardMfgBatchClient {
+        let context = Secp256k1Context::new();
+        ScabbardMfgBatchClient::new(
+            mockito::server_url(),
+            "abcde-01234::gr01".to_string(),
+            "Bearer token".to_string(),
+            context.new_signer(context.new_random_private_key()),
+        )
+        .expect("Unable to create client")
+    }
+
+    fn delete_action() -> MfgBatchDeleteAction {
+        MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .build()
+            .expect("Unable to build action")
+    }
+
+    /// Verify that a service ID which is not fully qualified is rejected
+    #[test]
+    fn test_new_invalid_service_id() {
+        let context = Secp256k1Context::new();
+        let result = ScabbardMfgBatchClient::new(
+            mockito::server_url(),
+            "gr01".to_string(),
+            "Bearer token".to_string(),
+            context.new_signer(context.new_random_private_key()),
+        );
+        assert!(matches!(
+            result,
+            Err(MfgBatchClientError::InvalidArgumentError(_))
+        ));
+    }
+
+    /// Verify that a batch is posted to the service with the authorization, and that an invalid
+    /// batch is reported with the contract's message
+    #[test]
+    fn test_submit_invalid() {
+        let _batches = mock("POST", "/scabbard/abcde-01234/gr01/batches")
+            .match_header("Authorization", "Bearer token")
+            .with_status(202)
+            .create();
+        let _statuses = mock("GET", "/scabbard/abcde-01234/gr01/batch_statuses")
+            .match_header("Authorization", "Bearer token")
+            .match_query(Matcher::UrlEncoded("wait".into(), "5".into()))
+            .with_status(200)
+            .with_body(
+                r#"[{"id": "abc", "status": {"statusType": "Invalid", "message": [{
+                    "transaction_id": "def",
+                    "error_message": "[permission:owner] Agent is not the owner",
+                    "error_data": []
+                }]}}]"#,
+            )
+            .create();
+
+        let err = client()
+            .delete_mfg_batch(delete_action(), 5)
+            .expect_err("The batch should be invalid");
+        assert_eq!(err.rejection_code(), Some("permission:owner"));
+    }
+
+    /// Verify that an error response is reported with the service's message
+    #[test]
+    fn test_submit_error() {
+        let _batches = mock("POST", "/scabbard/abcde-01234/gr01/batches")
+            .with_status(400)
+            .with_body(r#"{"message": "Circuit does not exist"}"#)
+            .create();
+
+        match client().delete_mfg_batch(delete_action(), 0) {
+            Err(MfgBatchClientError::RequestError(message)) => {
+                assert!(message.contains("Circuit does not exist"), "{}", message)
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    /// Verify that the mfg_batches are decoded from every entry under the namespace
+    #[test]
+    fn test_list_mfg_batches() {
+        let mfg_batch = MfgBatchBuilder::new()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("myorg".to_string())
+            .with_properties(vec![])
+            .build()
+            .expect("Unable to build mfg_batch");
+        let entry = MfgBatchListBuilder::new()
+            .with_mfg_batches(vec![mfg_batch.clone()])
+            .build()
+            .expect("Unable to build mfg_batch list")
+            .into_bytes()
+            .expect("Unable to encode mfg_batch list");
+
+        let _state = mock("GET", "/scabbard/abcde-01234/gr01/state")
+            .match_query(Matcher::UrlEncoded(
+                "prefix".into(),
+                MfgBatchAddresser::default().mfg_batch_namespace(),
+            ))
+            .with_status(200)
+            .with_body(format!(r#"[{{"address": "abc", "value": {:?}}}]"#, entry))
+            .create();
+
+        assert_eq!(
+            client()
+                .list_mfg_batches()
+                .expect("Unable to list mfg_batches"),
+            vec![mfg_batch]
+        );
+    }
+}
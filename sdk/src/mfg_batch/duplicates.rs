@@ -0,0 +1,552 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Flags mfg_batches that are likely duplicates of one another, recorded under different IDs,
+//! as a queue for review.
+//!
+//! Pairs of mfg_batches are matched by rules, tried in order, which are read from YAML:
+//!
+//! ```yaml
+//! rules:
+//!   - type: same_gtin_and_lot
+//!     gtin_property: gtin
+//!     lot_property: lot_code
+//!   - type: similar_properties
+//!     name: near_identical
+//!     min_similarity: 90
+//!     min_properties: 3
+//!     ignore: [received_at]
+//! ```
+//!
+//! Each pair found suggests merging one mfg_batch into the other: the one with fewer properties
+//! into the one with more or, if they have as many, the later ID into the earlier.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::error::InvalidArgumentError;
+
+use super::addressing::MfgBatchIdentifier;
+use super::store::{
+    ListMfgBatchFilters, MfgBatch, MfgBatchDuplicate, MfgBatchStore, MfgBatchStoreError,
+    PropertyValue,
+};
+
+/// The number of mfg_batches read per query by a detection run
+const PAGE_SIZE: i64 = 1000;
+
+/// How a pair of mfg_batches is matched as duplicates
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MatchRule {
+    /// Both have the same GTIN and lot. The GTIN is read from `gtin_property` or, for a GS1
+    /// mfg_batch identified by its GTIN, from its ID; lots are compared ignoring case and
+    /// surrounding whitespace.
+    SameGtinAndLot {
+        #[serde(default = "default_gtin_property")]
+        gtin_property: String,
+        #[serde(default = "default_lot_property")]
+        lot_property: String,
+    },
+    /// At least `min_similarity` percent of the properties set on either are set to the same
+    /// value on both, not counting the `ignore`d properties. Mfg_batches with fewer than
+    /// `min_properties` properties are not compared.
+    SimilarProperties {
+        #[serde(default = "default_min_similarity")]
+        min_similarity: u32,
+        #[serde(default = "default_min_properties")]
+        min_properties: usize,
+        #[serde(default)]
+        ignore: Vec<String>,
+    },
+}
+
+impl MatchRule {
+    /// The name the rule is reported by, unless it is given another
+    fn default_name(&self) -> &'static str {
+        match self {
+            MatchRule::SameGtinAndLot { .. } => "same_gtin_and_lot",
+            MatchRule::SimilarProperties { .. } => "similar_properties",
+        }
+    }
+
+    /// Finds the pairs of mfg_batches, by index, matching the rule, with how alike each pair is
+    /// out of 100
+    fn find_pairs(&self, mfg_batches: &[MfgBatch]) -> Vec<((usize, usize), i32)> {
+        match self {
+            MatchRule::SameGtinAndLot {
+                gtin_property,
+                lot_property,
+            } => {
+                let mut groups: BTreeMap<(String, String), Vec<usize>> = BTreeMap::new();
+                for (index, mfg_batch) in mfg_batches.iter().enumerate() {
+                    let gtin = gtin_of(mfg_batch, gtin_property);
+                    let lot = string_property(mfg_batch, lot_property)
+                        .map(|lot| lot.trim().to_lowercase())
+                        .filter(|lot| !lot.is_empty());
+                    if let (Some(gtin), Some(lot)) = (gtin, lot) {
+                        groups.entry((gtin, lot)).or_default().push(index);
+                    }
+                }
+
+                groups
+                    .values()
+                    .flat_map(|indexes| pairs_of(indexes))
+                    .map(|pair| (pair, 100))
+                    .collect()
+            }
+            MatchRule::SimilarProperties {
+                min_similarity,
+                min_properties,
+                ignore,
+            } => {
+                let keys = mfg_batches
+                    .iter()
+                    .map(|mfg_batch| {
+                        mfg_batch
+                            .properties()
+                            .iter()
+                            .filter(|value| {
+                                !ignore.iter().any(|name| name == value.property_name())
+                            })
+                            .map(property_key)
+                            .collect::<HashSet<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                let candidates = (0..keys.len())
+                    .filter(|index| keys[*index].len() >= *min_properties.max(&1))
+                    .collect::<Vec<_>>();
+
+                // Every pair is compared, so this is meant for a periodic job rather than for
+                // each write
+                pairs_of(&candidates)
+                    .into_iter()
+                    .filter_map(|(a, b)| {
+                        let shared = keys[a].intersection(&keys[b]).count();
+                        let total = keys[a].union(&keys[b]).count();
+                        let similarity = (shared * 100 / total) as i32;
+                        if similarity >= *min_similarity as i32 {
+                            Some(((a, b), similarity))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn default_gtin_property() -> String {
+    "gtin".to_string()
+}
+
+fn default_lot_property() -> String {
+    "lot_code".to_string()
+}
+
+fn default_min_similarity() -> u32 {
+    90
+}
+
+fn default_min_properties() -> usize {
+    3
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    rules: Vec<RulesEntry>,
+}
+
+#[derive(Deserialize)]
+struct RulesEntry {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(flatten)]
+    rule: MatchRule,
+}
+
+/// The rules pairs of mfg_batches are matched as duplicates by
+pub struct DuplicateRules {
+    rules: Vec<(String, MatchRule)>,
+}
+
+impl DuplicateRules {
+    /// Creates a set of rules without any rules, which matches nothing
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds a rule, reported by `name`, tried after the rules already added
+    pub fn with_rule(mut self, name: String, rule: MatchRule) -> Self {
+        self.rules.push((name, rule));
+        self
+    }
+
+    /// Reads rules from YAML
+    pub fn from_yaml(yaml: &str) -> Result<Self, InvalidArgumentError> {
+        let file: RulesFile = serde_yaml::from_str(yaml)
+            .map_err(|err| InvalidArgumentError::new("rules".to_string(), err.to_string()))?;
+
+        let mut names = HashSet::new();
+        let mut rules = Self::new();
+        for entry in file.rules {
+            let default_name = entry.rule.default_name();
+            let name = entry.name.unwrap_or_else(|| default_name.to_string());
+            if !names.insert(name.clone()) {
+                return Err(InvalidArgumentError::new(
+                    "name".to_string(),
+                    format!("Rules have more than one rule named {}", name),
+                ));
+            }
+            if let MatchRule::SimilarProperties { min_similarity, .. } = &entry.rule {
+                if *min_similarity > 100 {
+                    return Err(InvalidArgumentError::new(
+                        "min_similarity".to_string(),
+                        format!("Rule {} has a min_similarity over 100", name),
+                    ));
+                }
+            }
+
+            rules = rules.with_rule(name, entry.rule);
+        }
+
+        Ok(rules)
+    }
+
+    /// Finds the pairs of mfg_batches matching any rule. A pair is reported once, by the first
+    /// rule it matches.
+    pub fn find_duplicates(
+        &self,
+        mfg_batches: &[MfgBatch],
+        detected_at: i64,
+    ) -> Vec<MfgBatchDuplicate> {
+        let mut found: HashMap<(usize, usize), (&str, i32)> = HashMap::new();
+        for (name, rule) in &self.rules {
+            for (pair, similarity) in rule.find_pairs(mfg_batches) {
+                found.entry(pair).or_insert((name, similarity));
+            }
+        }
+
+        let mut duplicates = found
+            .into_iter()
+            .map(|((a, b), (rule, similarity))| {
+                let (merged, kept) = merge_order(&mfg_batches[a], &mfg_batches[b]);
+                MfgBatchDuplicate {
+                    mfg_batch_id: merged.mfg_batch_id().to_string(),
+                    duplicate_of: kept.mfg_batch_id().to_string(),
+                    rule: rule.to_string(),
+                    similarity,
+                    detected_at,
+                    service_id: merged.service_id().map(String::from),
+                }
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_by(|a, b| {
+            b.similarity
+                .cmp(&a.similarity)
+                .then_with(|| a.mfg_batch_id.cmp(&b.mfg_batch_id))
+                .then_with(|| a.duplicate_of.cmp(&b.duplicate_of))
+        });
+
+        duplicates
+    }
+}
+
+impl Default for DuplicateRules {
+    /// Matches mfg_batches with the same GTIN and `lot_code`, or with 90% of their properties
+    /// the same
+    fn default() -> Self {
+        vec![
+            MatchRule::SameGtinAndLot {
+                gtin_property: default_gtin_property(),
+                lot_property: default_lot_property(),
+            },
+            MatchRule::SimilarProperties {
+                min_similarity: default_min_similarity(),
+                min_properties: default_min_properties(),
+                ignore: vec![],
+            },
+        ]
+        .into_iter()
+        .fold(Self::new(), |rules, rule| {
+            rules.with_rule(rule.default_name().to_string(), rule)
+        })
+    }
+}
+
+/// Finds the duplicates among the current, unarchived mfg_batches of a service and replaces the
+/// service's review queue with them
+pub fn detect_mfg_batch_duplicates(
+    store: &dyn MfgBatchStore,
+    rules: &DuplicateRules,
+    service_id: Option<&str>,
+    detected_at: i64,
+) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+    let filters = ListMfgBatchFilters {
+        archived: Some(false),
+        ..Default::default()
+    };
+    let mfg_batches = store
+        .iter_mfg_batches(service_id, &filters, PAGE_SIZE)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let duplicates = rules.find_duplicates(&mfg_batches, detected_at);
+    store.replace_mfg_batch_duplicates(service_id, duplicates.clone())?;
+
+    Ok(duplicates)
+}
+
+/// Orders a pair as the mfg_batch to merge and the one to keep
+fn merge_order<'a>(a: &'a MfgBatch, b: &'a MfgBatch) -> (&'a MfgBatch, &'a MfgBatch) {
+    let keep_a = a
+        .properties()
+        .len()
+        .cmp(&b.properties().len())
+        .then_with(|| b.mfg_batch_id().cmp(a.mfg_batch_id()))
+        .is_gt();
+    if keep_a {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+/// Lists every pair of the given indexes
+fn pairs_of(indexes: &[usize]) -> Vec<(usize, usize)> {
+    indexes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| indexes[i + 1..].iter().map(move |b| (*a, *b)))
+        .collect()
+}
+
+fn string_property(mfg_batch: &MfgBatch, property: &str) -> Option<String> {
+    mfg_batch
+        .properties()
+        .iter()
+        .find(|value| value.property_name() == property)
+        .and_then(|value| value.string_value().map(String::from))
+}
+
+/// Reads a mfg_batch's GTIN, as 14 digits
+fn gtin_of(mfg_batch: &MfgBatch, gtin_property: &str) -> Option<String> {
+    let gtin = string_property(mfg_batch, gtin_property).or_else(|| {
+        if !mfg_batch.mfg_batch_namespace().eq_ignore_ascii_case("gs1") {
+            return None;
+        }
+        match MfgBatchIdentifier::from_id(mfg_batch.mfg_batch_id()) {
+            MfgBatchIdentifier::Sscc | MfgBatchIdentifier::CompanyInternal => None,
+            _ => Some(mfg_batch.mfg_batch_id().to_string()),
+        }
+    })?;
+
+    let gtin = gtin.trim();
+    if gtin.is_empty() || gtin.len() > 14 || !gtin.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{:0>14}", gtin))
+}
+
+/// Describes a property and its value, so equal properties have equal keys
+fn property_key(value: &PropertyValue) -> String {
+    let mut struct_values = value
+        .struct_values()
+        .iter()
+        .map(property_key)
+        .collect::<Vec<_>>();
+    struct_values.sort();
+
+    format!(
+        "{}={:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        value.property_name(),
+        value.string_value(),
+        value.number_value(),
+        value.boolean_value(),
+        value.enum_value(),
+        value.bytes_value(),
+        value
+            .lat_long_value()
+            .map(|lat_long| (lat_long.latitude, lat_long.longitude)),
+        struct_values,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::{MfgBatchBuilder, PropertyValueBuilder};
+
+    const NOW: i64 = 1_646_092_800;
+
+    fn property(mfg_batch_id: &str, name: &str, value: &str) -> PropertyValue {
+        PropertyValueBuilder::default()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_property_name(name.to_string())
+            .with_data_type("STRING".to_string())
+            .with_string_value(Some(value.to_string()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build property")
+    }
+
+    fn mfg_batch(id: &str, namespace: &str, properties: &[(&str, &str)]) -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(id.to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_mfg_batch_namespace(namespace.to_string())
+            .with_owner("acme".to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(
+                properties
+                    .iter()
+                    .map(|(name, value)| property(id, name, value))
+                    .collect(),
+            )
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    fn duplicate(
+        mfg_batch_id: &str,
+        duplicate_of: &str,
+        rule: &str,
+        similarity: i32,
+    ) -> MfgBatchDuplicate {
+        MfgBatchDuplicate {
+            mfg_batch_id: mfg_batch_id.to_string(),
+            duplicate_of: duplicate_of.to_string(),
+            rule: rule.to_string(),
+            similarity,
+            detected_at: NOW,
+            service_id: None,
+        }
+    }
+
+    /// Verify that mfg_batches with the same GTIN, read from a property or a GS1 ID, and lot are
+    /// matched, and that the one with fewer properties is suggested to be merged
+    #[test]
+    fn test_same_gtin_and_lot() {
+        let mfg_batches = vec![
+            mfg_batch("00614141999996", "GS1", &[("lot_code", "L1 ")]),
+            mfg_batch(
+                "internal-7",
+                "ACME",
+                &[
+                    ("gtin", "614141999996"),
+                    ("lot_code", "l1"),
+                    ("site", "north"),
+                ],
+            ),
+            mfg_batch(
+                "internal-8",
+                "ACME",
+                &[("gtin", "614141999996"), ("lot_code", "L2")],
+            ),
+            mfg_batch("internal-9", "ACME", &[("lot_code", "L1")]),
+        ];
+
+        assert_eq!(
+            DuplicateRules::default().find_duplicates(&mfg_batches, NOW),
+            vec![duplicate(
+                "00614141999996",
+                "internal-7",
+                "same_gtin_and_lot",
+                100
+            )]
+        );
+    }
+
+    /// Verify that mfg_batches with nearly the same properties are matched, ignoring the given
+    /// properties, that pairs are reported by the first rule they match, and that ties are
+    /// merged into the earlier ID
+    #[test]
+    fn test_similar_properties() {
+        let rules = DuplicateRules::from_yaml(
+            "
+rules:
+  - type: similar_properties
+    name: near_identical
+    min_similarity: 75
+    ignore: [received_at]
+  - type: same_gtin_and_lot
+",
+        )
+        .expect("Failed to read rules");
+
+        let properties = [
+            ("gtin", "614141999996"),
+            ("lot_code", "L1"),
+            ("site", "north"),
+            ("line", "3"),
+        ];
+        let mfg_batches = vec![
+            mfg_batch("B-2", "ACME", &properties),
+            mfg_batch("B-1", "ACME", &properties[..3]),
+            mfg_batch(
+                "B-3",
+                "ACME",
+                &[
+                    ("gtin", "614141999996"),
+                    ("lot_code", "L1"),
+                    ("site", "south"),
+                    ("received_at", "monday"),
+                ],
+            ),
+        ];
+
+        assert_eq!(
+            rules.find_duplicates(&mfg_batches, NOW),
+            vec![
+                duplicate("B-1", "B-3", "same_gtin_and_lot", 100),
+                duplicate("B-3", "B-2", "same_gtin_and_lot", 100),
+                duplicate("B-1", "B-2", "near_identical", 75),
+            ]
+        );
+
+        let mfg_batches = vec![
+            mfg_batch("B-2", "ACME", &properties),
+            mfg_batch("B-1", "ACME", &properties),
+        ];
+        assert_eq!(
+            rules.find_duplicates(&mfg_batches, NOW),
+            vec![duplicate("B-2", "B-1", "near_identical", 100)]
+        );
+    }
+
+    /// Verify that rules with the same name, or a similarity over 100, are rejected
+    #[test]
+    fn test_invalid_rules() {
+        assert!(DuplicateRules::from_yaml(
+            "
+rules:
+  - type: same_gtin_and_lot
+  - type: same_gtin_and_lot
+    lot_property: lot
+"
+        )
+        .is_err());
+        assert!(DuplicateRules::from_yaml(
+            "
+rules:
+  - type: similar_properties
+    min_similarity: 101
+"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::mfg_batch::store::MfgBatchStoreError;
+
+/// An error that can occur while exporting mfg_batches as EPCIS events
+#[derive(Debug)]
+pub enum EpcisError {
+    /// The mfg_batches or their parents could not be read
+    Store(MfgBatchStoreError),
+    /// A mfg_batch to be exported does not exist
+    NotFound(String),
+}
+
+impl Error for EpcisError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EpcisError::Store(err) => Some(err),
+            EpcisError::NotFound(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for EpcisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpcisError::Store(err) => err.fmt(f),
+            EpcisError::NotFound(msg) => write!(f, "Not found: {}", msg),
+        }
+    }
+}
+
+impl From<MfgBatchStoreError> for EpcisError {
+    fn from(err: MfgBatchStoreError) -> Self {
+        EpcisError::Store(err)
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the history of mfg_batches as GS1 EPCIS 2.0 events, in JSON-LD, so traceability
+//! systems can ingest them without knowing Grid's own formats.
+//!
+//! Each version of a mfg_batch is compared with the one before it:
+//!
+//! - The first version is commissioned: an `ObjectEvent` adding the batch, or, if it was made
+//!   from parent batches, a `TransformationEvent` from the parents to the batch.
+//! - Parents added by a later version are recorded in another `TransformationEvent`.
+//! - A change of owner is an `ObjectEvent` observing the batch, with the old and new owners as
+//!   its source and destination owning parties.
+//! - Archiving the batch decommissions it, in an `ObjectEvent` deleting it.
+//!
+//! GS1 batches identified by a GTIN are exported as GS1 Digital Link classes, with their lot
+//! when they have one, and those identified by an SSCC as Digital Link instances. Any other
+//! batch, and every organization, is identified by a `urn:grid:` URN.
+
+mod error;
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde_json::Value;
+
+use crate::mfg_batch::addressing::MfgBatchIdentifier;
+use crate::mfg_batch::store::{MfgBatch, MfgBatchStore};
+
+pub use error::EpcisError;
+
+const EPCIS_CONTEXT: &str = "https://ref.gs1.org/standards/epcis/2.0.0/epcis-context.jsonld";
+const DIGITAL_LINK_BASE: &str = "https://id.gs1.org";
+
+/// The property the lot of a GS1 batch is read from, unless another is given
+pub const DEFAULT_LOT_PROPERTY: &str = "lot_code";
+
+/// How mfg_batches are identified in the events they are exported as
+#[derive(Clone, Debug)]
+pub struct EpcisOptions {
+    /// The property holding the lot of a GS1 batch identified by a GTIN
+    pub lot_property: String,
+}
+
+impl Default for EpcisOptions {
+    fn default() -> Self {
+        Self {
+            lot_property: DEFAULT_LOT_PROPERTY.to_string(),
+        }
+    }
+}
+
+/// How a mfg_batch is named in an event: as a class of objects, whose quantity is counted, or
+/// as a single object
+#[derive(Clone, Debug, PartialEq)]
+pub enum EpcisIdentifier {
+    Class(String),
+    Instance(String),
+}
+
+impl EpcisIdentifier {
+    /// Identifies a version of a mfg_batch
+    pub fn of(mfg_batch: &MfgBatch, options: &EpcisOptions) -> Self {
+        let id = mfg_batch.mfg_batch_id();
+        let namespace = mfg_batch.mfg_batch_namespace().to_lowercase();
+
+        if namespace == "gs1" {
+            match MfgBatchIdentifier::from_id(id) {
+                MfgBatchIdentifier::Sscc => {
+                    return EpcisIdentifier::Instance(format!("{}/00/{}", DIGITAL_LINK_BASE, id))
+                }
+                MfgBatchIdentifier::CompanyInternal => (),
+                _ => {
+                    let mut class = format!("{}/01/{:0>14}", DIGITAL_LINK_BASE, id);
+                    if let Some(lot) = mfg_batch
+                        .properties()
+                        .iter()
+                        .find(|property| property.property_name() == options.lot_property)
+                        .and_then(|property| property.string_value().map(String::from))
+                    {
+                        class.push_str("/10/");
+                        class.push_str(&encode(&lot));
+                    }
+                    return EpcisIdentifier::Class(class);
+                }
+            }
+        }
+
+        EpcisIdentifier::Class(format!(
+            "urn:grid:mfg_batch:{}:{}",
+            encode(&namespace),
+            encode(id)
+        ))
+    }
+
+    /// Returns the identifier as an entry of an event's `epcList`, or of a quantity list, with
+    /// the batch's quantity if it has one
+    fn list_entry(&self, mfg_batch: &MfgBatch) -> (Option<Value>, Option<Value>) {
+        match self {
+            EpcisIdentifier::Instance(epc) => (Some(Value::String(epc.clone())), None),
+            EpcisIdentifier::Class(epc_class) => {
+                let mut quantity = json!({ "epcClass": epc_class });
+                if let Some(amount) = mfg_batch.quantity() {
+                    quantity["quantity"] = json!(amount);
+                    if let Some(uom) = mfg_batch.uom() {
+                        quantity["uom"] = json!(uom);
+                    }
+                }
+                (None, Some(quantity))
+            }
+        }
+    }
+}
+
+/// Converts the history of a mfg_batch, oldest version first, into EPCIS events. `parents`
+/// holds the batches it was made from, by ID; a parent that is missing is identified as if it
+/// were in the same namespace as the batch. Versions without a time of their own are given
+/// the time of the version before them, or `exported_at`, in seconds since the epoch.
+pub fn events_from_history(
+    history: &[MfgBatch],
+    parents: &HashMap<String, MfgBatch>,
+    options: &EpcisOptions,
+    exported_at: i64,
+) -> Vec<Value> {
+    let mut events = Vec::new();
+    let mut previous: Option<&MfgBatch> = None;
+    let mut event_time = exported_at;
+
+    for version in history {
+        if let Some(time) = version.last_updated() {
+            event_time = *time;
+        } else if previous.is_none() {
+            event_time = version.production_date().unwrap_or(exported_at);
+        }
+
+        let identifier = EpcisIdentifier::of(version, options);
+
+        let new_parents = version
+            .parent_batches()
+            .iter()
+            .filter(|parent| {
+                previous
+                    .map(|previous| !previous.parent_batches().contains(parent))
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+
+        if !new_parents.is_empty() {
+            let inputs = new_parents
+                .iter()
+                .map(|parent| match parents.get(*parent) {
+                    Some(parent) => EpcisIdentifier::of(parent, options).list_entry(parent),
+                    None => EpcisIdentifier::Class(format!(
+                        "urn:grid:mfg_batch:{}:{}",
+                        encode(&version.mfg_batch_namespace().to_lowercase()),
+                        encode(parent)
+                    ))
+                    .list_entry(version),
+                })
+                .collect::<Vec<_>>();
+            let mut event = base_event("TransformationEvent", event_time);
+            set_list(
+                &mut event,
+                "inputEPCList",
+                "inputQuantityList",
+                &inputs,
+                false,
+            );
+            set_list(
+                &mut event,
+                "outputEPCList",
+                "outputQuantityList",
+                &[identifier.list_entry(version)],
+                true,
+            );
+            event["bizStep"] = json!("commissioning");
+            event["disposition"] = json!("active");
+            events.push(event);
+        } else if previous.is_none() {
+            events.push(object_event(
+                "ADD",
+                "commissioning",
+                "active",
+                &identifier,
+                version,
+                event_time,
+            ));
+        }
+
+        if let Some(previous) = previous {
+            if previous.owner() != version.owner() {
+                let mut event = base_event("ObjectEvent", event_time);
+                event["action"] = json!("OBSERVE");
+                set_list(
+                    &mut event,
+                    "epcList",
+                    "quantityList",
+                    &[identifier.list_entry(version)],
+                    true,
+                );
+                event["sourceList"] =
+                    json!([{ "type": "owning_party", "source": organization(previous.owner()) }]);
+                event["destinationList"] = json!([{
+                    "type": "owning_party",
+                    "destination": organization(version.owner())
+                }]);
+                events.push(event);
+            }
+        }
+
+        if version.archived() && !previous.map(|p| p.archived()).unwrap_or(false) {
+            events.push(object_event(
+                "DELETE",
+                "decommissioning",
+                "inactive",
+                &identifier,
+                version,
+                event_time,
+            ));
+        }
+
+        previous = Some(version);
+    }
+
+    events
+}
+
+/// Wraps events in an EPCIS document, created at `created_at` seconds since the epoch
+pub fn epcis_document(events: Vec<Value>, created_at: i64) -> Value {
+    json!({
+        "@context": [EPCIS_CONTEXT],
+        "type": "EPCISDocument",
+        "schemaVersion": "2.0",
+        "creationDate": format_time(created_at),
+        "epcisBody": { "eventList": events },
+    })
+}
+
+/// Exports the history of the given mfg_batches as an EPCIS document
+pub fn export_mfg_batches(
+    store: &dyn MfgBatchStore,
+    mfg_batch_ids: &[&str],
+    service_id: Option<&str>,
+    options: &EpcisOptions,
+    exported_at: i64,
+) -> Result<Value, EpcisError> {
+    let mut events = Vec::new();
+
+    for mfg_batch_id in mfg_batch_ids {
+        let history = store.list_mfg_batch_history(mfg_batch_id, service_id)?;
+        if history.is_empty() {
+            return Err(EpcisError::NotFound(format!("Mfg_batch {}", mfg_batch_id)));
+        }
+
+        let mut parents = HashMap::new();
+        for parent_id in history.iter().flat_map(|version| version.parent_batches()) {
+            if !parents.contains_key(parent_id) {
+                if let Some(parent) = store.get_mfg_batch(parent_id, service_id)? {
+                    parents.insert(parent_id.clone(), parent);
+                }
+            }
+        }
+
+        events.extend(events_from_history(
+            &history,
+            &parents,
+            options,
+            exported_at,
+        ));
+    }
+
+    Ok(epcis_document(events, exported_at))
+}
+
+fn base_event(event_type: &str, event_time: i64) -> Value {
+    json!({
+        "type": event_type,
+        "eventTime": format_time(event_time),
+        "eventTimeZoneOffset": "+00:00",
+    })
+}
+
+fn object_event(
+    action: &str,
+    biz_step: &str,
+    disposition: &str,
+    identifier: &EpcisIdentifier,
+    mfg_batch: &MfgBatch,
+    event_time: i64,
+) -> Value {
+    let mut event = base_event("ObjectEvent", event_time);
+    event["action"] = json!(action);
+    set_list(
+        &mut event,
+        "epcList",
+        "quantityList",
+        &[identifier.list_entry(mfg_batch)],
+        true,
+    );
+    event["bizStep"] = json!(biz_step);
+    event["disposition"] = json!(disposition);
+    event
+}
+
+/// Sets an event's EPC and quantity lists from the entries. An object event must have an
+/// `epcList`, even if it is empty; other lists are only set when they have entries.
+fn set_list(
+    event: &mut Value,
+    epc_key: &str,
+    quantity_key: &str,
+    entries: &[(Option<Value>, Option<Value>)],
+    require_epc_list: bool,
+) {
+    let epcs = entries
+        .iter()
+        .filter_map(|(epc, _)| epc.clone())
+        .collect::<Vec<_>>();
+    let quantities = entries
+        .iter()
+        .filter_map(|(_, quantity)| quantity.clone())
+        .collect::<Vec<_>>();
+
+    if !epcs.is_empty() || (require_epc_list && event["type"] == "ObjectEvent") {
+        event[epc_key] = Value::Array(epcs);
+    }
+    if !quantities.is_empty() {
+        event[quantity_key] = Value::Array(quantities);
+    }
+}
+
+fn organization(org_id: &str) -> String {
+    format!("urn:grid:organization:{}", encode(org_id))
+}
+
+fn format_time(seconds: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(seconds, 0)
+        .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0))
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+/// Percent-encodes every character that is not unreserved in a URI, so IDs can be used as a
+/// path segment or URN part
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::{MfgBatchBuilder, PropertyValueBuilder};
+
+    fn mfg_batch(
+        id: &str,
+        namespace: &str,
+        owner: &str,
+        parents: &[&str],
+        archived: bool,
+    ) -> MfgBatch {
+        let lot = PropertyValueBuilder::default()
+            .with_mfg_batch_id(id.to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_property_name(DEFAULT_LOT_PROPERTY.to_string())
+            .with_data_type("STRING".to_string())
+            .with_string_value(Some("L 1".to_string()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build property");
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(id.to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_mfg_batch_namespace(namespace.to_string())
+            .with_owner(owner.to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(vec![lot])
+            .with_parent_batches(parents.iter().map(|p| p.to_string()).collect())
+            .with_quantity(Some(40))
+            .with_uom(Some("KGM".to_string()))
+            .with_production_date(Some(1_646_092_800))
+            .with_archived(archived)
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    /// Verify that GTIN, SSCC and other batches are identified as GS1 Digital Link classes,
+    /// instances and Grid URNs
+    #[test]
+    fn test_identifiers() {
+        let options = EpcisOptions::default();
+        assert_eq!(
+            EpcisIdentifier::of(
+                &mfg_batch("9781981855728", "GS1", "acme", &[], false),
+                &options
+            ),
+            EpcisIdentifier::Class("https://id.gs1.org/01/09781981855728/10/L%201".to_string())
+        );
+        assert_eq!(
+            EpcisIdentifier::of(
+                &mfg_batch("106141411234567897", "GS1", "acme", &[], false),
+                &options
+            ),
+            EpcisIdentifier::Instance("https://id.gs1.org/00/106141411234567897".to_string())
+        );
+        assert_eq!(
+            EpcisIdentifier::of(&mfg_batch("B/7", "INTERNAL", "acme", &[], false), &options),
+            EpcisIdentifier::Class("urn:grid:mfg_batch:internal:B%2F7".to_string())
+        );
+    }
+
+    /// Verify that a batch made from a parent is commissioned by a transformation, and that
+    /// its transfer and archiving are exported in order
+    #[test]
+    fn test_events_from_history() {
+        let parent = mfg_batch("P-1", "LOT", "acme", &[], false);
+        let mut parents = HashMap::new();
+        parents.insert("P-1".to_string(), parent);
+
+        let history = vec![
+            mfg_batch("B-1", "LOT", "acme", &["P-1"], false),
+            mfg_batch("B-1", "LOT", "acme", &["P-1"], false),
+            mfg_batch("B-1", "LOT", "globex", &["P-1"], false),
+            mfg_batch("B-1", "LOT", "globex", &["P-1"], true),
+        ];
+
+        let events =
+            events_from_history(&history, &parents, &EpcisOptions::default(), 1_646_179_200);
+
+        assert_eq!(
+            events,
+            vec![
+                json!({
+                    "type": "TransformationEvent",
+                    "eventTime": "2022-03-01T00:00:00.000Z",
+                    "eventTimeZoneOffset": "+00:00",
+                    "inputQuantityList": [{
+                        "epcClass": "urn:grid:mfg_batch:lot:P-1",
+                        "quantity": 40,
+                        "uom": "KGM"
+                    }],
+                    "outputQuantityList": [{
+                        "epcClass": "urn:grid:mfg_batch:lot:B-1",
+                        "quantity": 40,
+                        "uom": "KGM"
+                    }],
+                    "bizStep": "commissioning",
+                    "disposition": "active"
+                }),
+                json!({
+                    "type": "ObjectEvent",
+                    "eventTime": "2022-03-01T00:00:00.000Z",
+                    "eventTimeZoneOffset": "+00:00",
+                    "action": "OBSERVE",
+                    "epcList": [],
+                    "quantityList": [{
+                        "epcClass": "urn:grid:mfg_batch:lot:B-1",
+                        "quantity": 40,
+                        "uom": "KGM"
+                    }],
+                    "sourceList": [{
+                        "type": "owning_party",
+                        "source": "urn:grid:organization:acme"
+                    }],
+                    "destinationList": [{
+                        "type": "owning_party",
+                        "destination": "urn:grid:organization:globex"
+                    }]
+                }),
+                json!({
+                    "type": "ObjectEvent",
+                    "eventTime": "2022-03-01T00:00:00.000Z",
+                    "eventTimeZoneOffset": "+00:00",
+                    "action": "DELETE",
+                    "epcList": [],
+                    "quantityList": [{
+                        "epcClass": "urn:grid:mfg_batch:lot:B-1",
+                        "quantity": 40,
+                        "uom": "KGM"
+                    }],
+                    "bizStep": "decommissioning",
+                    "disposition": "inactive"
+                }),
+            ]
+        );
+
+        let document = epcis_document(events, 1_646_179_200);
+        assert_eq!(document["creationDate"], "2022-03-02T00:00:00.000Z");
+        assert_eq!(
+            document["epcisBody"]["eventList"][0]["type"],
+            "TransformationEvent"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+
+use crate::mfg_batch::store::MfgBatchStoreError;
+
+/// An error that can occur while exporting mfg_batches
+#[derive(Debug)]
+pub enum ExportError {
+    /// The mfg_batches could not be read
+    Store(MfgBatchStoreError),
+    /// The rows could not be converted into Arrow record batches
+    Arrow(ArrowError),
+    /// A Parquet file could not be written
+    Parquet(ParquetError),
+    /// A partition directory or file could not be created
+    Io(std::io::Error),
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Store(err) => Some(err),
+            ExportError::Arrow(err) => Some(err),
+            ExportError::Parquet(err) => Some(err),
+            ExportError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Store(err) => err.fmt(f),
+            ExportError::Arrow(err) => write!(f, "Unable to build record batch: {}", err),
+            ExportError::Parquet(err) => write!(f, "Unable to write Parquet file: {}", err),
+            ExportError::Io(err) => write!(f, "Unable to write export: {}", err),
+        }
+    }
+}
+
+impl From<MfgBatchStoreError> for ExportError {
+    fn from(err: MfgBatchStoreError) -> Self {
+        ExportError::Store(err)
+    }
+}
+
+impl From<ArrowError> for ExportError {
+    fn from(err: ArrowError) -> Self {
+        ExportError::Arrow(err)
+    }
+}
+
+impl From<ParquetError> for ExportError {
+    fn from(err: ParquetError) -> Self {
+        ExportError::Parquet(err)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
@@ -0,0 +1,656 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the current mfg_batches and their properties as Arrow record batches, or as Parquet
+//! files, so analytics tools such as Spark or DuckDB can load them without querying the store's
+//! tables.
+//!
+//! Mfg_batches are read a page at a time and partitioned by owner and production date. Each
+//! partition's rows are held until `rows_per_batch` mfg_batches have been read for it, then
+//! handed to the sink as two record batches: one of mfg_batches and one of their properties.
+//! Memory use is therefore bounded by the number of partitions times `rows_per_batch`.
+//!
+//! The owner and date are the partition, not columns of the record batches. [`ParquetSink`]
+//! writes them as Hive-style directories, `mfg_batch/owner=<owner>/date=<YYYY-MM-DD>/`, which
+//! Spark and DuckDB read back as columns. A mfg_batch without a production date is written to
+//! `date=__HIVE_DEFAULT_PARTITION__`, which both read as null.
+
+mod error;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Int32Array, Int64Array, ListBuilder, StringArray,
+    StringBuilder, TimestampSecondArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDateTime;
+use parquet::arrow::ArrowWriter;
+
+use crate::mfg_batch::store::{ListMfgBatchFilters, MfgBatch, MfgBatchStore, PropertyValue};
+
+pub use error::ExportError;
+
+/// The partition value of a mfg_batch without a production date
+pub const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// How mfg_batches are read and batched while they are exported
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    /// The number of mfg_batches to read from the store per query
+    pub page_size: i64,
+    /// The number of mfg_batches of a partition to put in one record batch. Up to this many are
+    /// held for each partition until it is flushed, so an export spanning many owners and dates
+    /// holds up to the number of partitions times this many mfg_batches in memory.
+    pub rows_per_batch: usize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            page_size: 1000,
+            rows_per_batch: 10_000,
+        }
+    }
+}
+
+/// The tables an export is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExportTable {
+    /// One row per mfg_batch
+    MfgBatches,
+    /// One row per property value of a mfg_batch; the fields of a struct property are rows of
+    /// their own, named `<property>.<field>`
+    Properties,
+}
+
+impl ExportTable {
+    /// Returns the name of the table's directory
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportTable::MfgBatches => "mfg_batch",
+            ExportTable::Properties => "mfg_batch_property",
+        }
+    }
+
+    /// Returns the schema of the table's record batches
+    pub fn schema(&self) -> SchemaRef {
+        match self {
+            ExportTable::MfgBatches => mfg_batch_schema(),
+            ExportTable::Properties => property_schema(),
+        }
+    }
+}
+
+/// The owner and production date a mfg_batch is partitioned by
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExportPartition {
+    pub owner: String,
+    /// The production date as `YYYY-MM-DD`, or `None` if the mfg_batch has none
+    pub date: Option<String>,
+}
+
+impl ExportPartition {
+    /// Returns the partition a mfg_batch is exported to
+    pub fn of(mfg_batch: &MfgBatch) -> Self {
+        Self {
+            owner: mfg_batch.owner().to_string(),
+            date: mfg_batch.production_date().and_then(|seconds| {
+                NaiveDateTime::from_timestamp_opt(seconds, 0)
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+            }),
+        }
+    }
+
+    /// Returns the partition's Hive-style path, relative to its table's directory
+    pub fn path(&self) -> PathBuf {
+        let date = self.date.as_deref().unwrap_or(DEFAULT_PARTITION);
+        Path::new(&format!("owner={}", escape_path_value(&self.owner)))
+            .join(format!("date={}", escape_path_value(date)))
+    }
+}
+
+/// Receives the record batches of an export
+///
+/// Any `FnMut(ExportTable, &ExportPartition, RecordBatch) -> Result<(), ExportError>` is a sink,
+/// so the record batches can be consumed directly.
+pub trait ExportSink {
+    /// Receives a record batch of a table, holding rows of a single partition
+    fn write(
+        &mut self,
+        table: ExportTable,
+        partition: &ExportPartition,
+        batch: RecordBatch,
+    ) -> Result<(), ExportError>;
+}
+
+impl<F> ExportSink for F
+where
+    F: FnMut(ExportTable, &ExportPartition, RecordBatch) -> Result<(), ExportError>,
+{
+    fn write(
+        &mut self,
+        table: ExportTable,
+        partition: &ExportPartition,
+        batch: RecordBatch,
+    ) -> Result<(), ExportError> {
+        self(table, partition, batch)
+    }
+}
+
+/// Writes each record batch as a Parquet file under a directory, in the Hive-style directory of
+/// its table and partition
+pub struct ParquetSink {
+    dir: PathBuf,
+    parts: HashMap<(ExportTable, ExportPartition), usize>,
+    files: Vec<PathBuf>,
+}
+
+impl ParquetSink {
+    /// Creates a sink writing under `dir`, which is created if it does not exist
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            parts: HashMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Returns the files written so far, in the order they were written
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+}
+
+impl ExportSink for ParquetSink {
+    fn write(
+        &mut self,
+        table: ExportTable,
+        partition: &ExportPartition,
+        batch: RecordBatch,
+    ) -> Result<(), ExportError> {
+        let part = self.parts.entry((table, partition.clone())).or_insert(0);
+        let dir = self.dir.join(table.name()).join(partition.path());
+        let path = dir.join(format!("part-{:05}.parquet", part));
+        *part += 1;
+
+        fs::create_dir_all(&dir)?;
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        self.files.push(path);
+        Ok(())
+    }
+}
+
+/// The number of rows an export wrote to each table
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExportSummary {
+    pub mfg_batches: usize,
+    pub properties: usize,
+}
+
+/// Exports the current mfg_batches matching the filters, and their properties, to a sink
+///
+/// Each partition is flushed on its own once it holds `rows_per_batch` mfg_batches, and the
+/// rest once the store is exhausted, so memory use is bounded by the number of partitions times
+/// `rows_per_batch`.
+///
+/// # Arguments
+///
+///  * `store` - The store the mfg_batches are read from
+///  * `service_id` - The service ID to export the mfg_batches of
+///  * `filters` - Filters the exported mfg_batches must match
+///  * `options` - How the mfg_batches are read and batched
+///  * `sink` - The sink the record batches are written to
+pub fn export_mfg_batches(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    filters: &ListMfgBatchFilters,
+    options: &ExportOptions,
+    sink: &mut dyn ExportSink,
+) -> Result<ExportSummary, ExportError> {
+    let rows_per_batch = options.rows_per_batch.max(1);
+    let mut summary = ExportSummary::default();
+    let mut pending: HashMap<ExportPartition, Vec<MfgBatch>> = HashMap::new();
+
+    for mfg_batch in store.iter_mfg_batches(service_id, filters, options.page_size) {
+        let mfg_batch = mfg_batch?;
+        let partition = ExportPartition::of(&mfg_batch);
+        let rows = pending.entry(partition.clone()).or_default();
+        rows.push(mfg_batch);
+
+        if rows.len() >= rows_per_batch {
+            let rows = pending.remove(&partition).unwrap_or_default();
+            flush(&partition, &rows, sink, &mut summary)?;
+        }
+    }
+
+    // Flush the remaining partitions in a stable order, so repeated exports write the same files
+    let mut remaining: Vec<_> = pending.into_iter().collect();
+    remaining.sort_by(|(a, _), (b, _)| (&a.owner, &a.date).cmp(&(&b.owner, &b.date)));
+    for (partition, rows) in remaining {
+        flush(&partition, &rows, sink, &mut summary)?;
+    }
+
+    Ok(summary)
+}
+
+fn flush(
+    partition: &ExportPartition,
+    rows: &[MfgBatch],
+    sink: &mut dyn ExportSink,
+    summary: &mut ExportSummary,
+) -> Result<(), ExportError> {
+    let mfg_batches = mfg_batches_to_record_batch(rows)?;
+    summary.mfg_batches += mfg_batches.num_rows();
+    sink.write(ExportTable::MfgBatches, partition, mfg_batches)?;
+
+    let properties = properties_to_record_batch(rows)?;
+    if properties.num_rows() > 0 {
+        summary.properties += properties.num_rows();
+        sink.write(ExportTable::Properties, partition, properties)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the schema of the mfg_batch table
+pub fn mfg_batch_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("mfg_batch_id", DataType::Utf8, false),
+        Field::new("mfg_batch_namespace", DataType::Utf8, false),
+        Field::new("mfg_batch_address", DataType::Utf8, false),
+        Field::new("service_id", DataType::Utf8, true),
+        Field::new("quantity", DataType::Int64, true),
+        Field::new("uom", DataType::Utf8, true),
+        Field::new("expected_quantity", DataType::Int64, true),
+        Field::new(
+            "production_date",
+            DataType::Timestamp(TimeUnit::Second, None),
+            true,
+        ),
+        Field::new(
+            "expiration_date",
+            DataType::Timestamp(TimeUnit::Second, None),
+            true,
+        ),
+        Field::new("manufacture_location", DataType::Utf8, true),
+        Field::new(
+            "parent_batches",
+            DataType::List(Box::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+        Field::new("archived", DataType::Boolean, false),
+        Field::new("start_commit_num", DataType::Int64, false),
+        Field::new("last_updated", DataType::Int64, true),
+    ]))
+}
+
+/// Returns the schema of the property table
+pub fn property_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("mfg_batch_id", DataType::Utf8, false),
+        Field::new("mfg_batch_namespace", DataType::Utf8, false),
+        Field::new("property_name", DataType::Utf8, false),
+        Field::new("data_type", DataType::Utf8, false),
+        Field::new("string_value", DataType::Utf8, true),
+        Field::new("number_value", DataType::Int64, true),
+        Field::new("boolean_value", DataType::Boolean, true),
+        Field::new("enum_value", DataType::Int32, true),
+        Field::new("bytes_value", DataType::Binary, true),
+        Field::new("latitude", DataType::Int64, true),
+        Field::new("longitude", DataType::Int64, true),
+        Field::new("language", DataType::Utf8, true),
+    ]))
+}
+
+/// Converts mfg_batches into a record batch of the mfg_batch table
+pub fn mfg_batches_to_record_batch(mfg_batches: &[MfgBatch]) -> Result<RecordBatch, ExportError> {
+    let mut parent_batches = ListBuilder::new(StringBuilder::new());
+    for mfg_batch in mfg_batches {
+        for parent in mfg_batch.parent_batches() {
+            parent_batches.values().append_value(parent);
+        }
+        parent_batches.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            mfg_batches.iter().map(MfgBatch::mfg_batch_id),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            mfg_batches.iter().map(MfgBatch::mfg_batch_namespace),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            mfg_batches.iter().map(MfgBatch::mfg_batch_address),
+        )),
+        Arc::new(StringArray::from_iter(
+            mfg_batches.iter().map(MfgBatch::service_id),
+        )),
+        Arc::new(Int64Array::from_iter(
+            mfg_batches.iter().map(MfgBatch::quantity),
+        )),
+        Arc::new(StringArray::from_iter(
+            mfg_batches.iter().map(MfgBatch::uom),
+        )),
+        Arc::new(Int64Array::from_iter(
+            mfg_batches.iter().map(MfgBatch::expected_quantity),
+        )),
+        Arc::new(TimestampSecondArray::from_iter(
+            mfg_batches.iter().map(MfgBatch::production_date),
+        )),
+        Arc::new(TimestampSecondArray::from_iter(
+            mfg_batches.iter().map(MfgBatch::expiration_date),
+        )),
+        Arc::new(StringArray::from_iter(
+            mfg_batches.iter().map(MfgBatch::manufacture_location),
+        )),
+        Arc::new(parent_batches.finish()),
+        Arc::new(BooleanArray::from_iter(
+            mfg_batches
+                .iter()
+                .map(|mfg_batch| Some(mfg_batch.archived())),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            mfg_batches
+                .iter()
+                .map(|mfg_batch| *mfg_batch.start_commit_num()),
+        )),
+        Arc::new(Int64Array::from_iter(
+            mfg_batches
+                .iter()
+                .map(|mfg_batch| mfg_batch.last_updated().copied()),
+        )),
+    ];
+
+    Ok(RecordBatch::try_new(mfg_batch_schema(), columns)?)
+}
+
+/// Converts the properties of mfg_batches into a record batch of the property table
+pub fn properties_to_record_batch(mfg_batches: &[MfgBatch]) -> Result<RecordBatch, ExportError> {
+    let mut rows = Vec::new();
+    for mfg_batch in mfg_batches {
+        for property in mfg_batch.properties() {
+            flatten_property(
+                mfg_batch,
+                property.property_name().to_string(),
+                property,
+                &mut rows,
+            );
+        }
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.mfg_batch.mfg_batch_id()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.mfg_batch.mfg_batch_namespace()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.name.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.value.data_type()),
+        )),
+        Arc::new(StringArray::from_iter(
+            rows.iter().map(|row| row.value.string_value()),
+        )),
+        Arc::new(Int64Array::from_iter(
+            rows.iter().map(|row| row.value.number_value()),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| row.value.boolean_value()),
+        )),
+        Arc::new(Int32Array::from_iter(
+            rows.iter().map(|row| row.value.enum_value()),
+        )),
+        Arc::new(BinaryArray::from_iter(
+            rows.iter().map(|row| row.value.bytes_value()),
+        )),
+        Arc::new(Int64Array::from_iter(rows.iter().map(|row| {
+            row.value.lat_long_value().map(|lat_long| lat_long.latitude)
+        }))),
+        Arc::new(Int64Array::from_iter(rows.iter().map(|row| {
+            row.value
+                .lat_long_value()
+                .map(|lat_long| lat_long.longitude)
+        }))),
+        Arc::new(StringArray::from_iter(
+            rows.iter().map(|row| row.value.language()),
+        )),
+    ];
+
+    Ok(RecordBatch::try_new(property_schema(), columns)?)
+}
+
+/// A row of the property table
+struct PropertyRow<'a> {
+    mfg_batch: &'a MfgBatch,
+    name: String,
+    value: PropertyValue,
+}
+
+/// Adds a property's row, and the rows of its struct fields, named after the property
+fn flatten_property<'a>(
+    mfg_batch: &'a MfgBatch,
+    name: String,
+    property: PropertyValue,
+    rows: &mut Vec<PropertyRow<'a>>,
+) {
+    for field in property.struct_values() {
+        let field_name = format!("{}.{}", name, field.property_name());
+        flatten_property(mfg_batch, field_name, field, rows);
+    }
+    rows.push(PropertyRow {
+        mfg_batch,
+        name,
+        value: property,
+    });
+}
+
+/// Escapes a partition value the way Hive does, so it is a single path segment
+fn escape_path_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b' ' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tempfile::TempDir;
+
+    use crate::mfg_batch::store::{DieselMfgBatchStore, MfgBatchBuilder, PropertyValueBuilder};
+
+    fn mfg_batch(id: &str, owner: &str, production_date: Option<i64>) -> MfgBatch {
+        let lot = PropertyValueBuilder::default()
+            .with_mfg_batch_id(id.to_string())
+            .with_mfg_batch_address(format!("addr-{}", id))
+            .with_property_name("lot_code".to_string())
+            .with_data_type("STRING".to_string())
+            .with_string_value(Some("L1".to_string()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build property");
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(id.to_string())
+            .with_mfg_batch_address(format!("addr-{}", id))
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner(owner.to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(vec![lot])
+            .with_parent_batches(vec!["parent".to_string()])
+            .with_quantity(Some(40))
+            .with_production_date(production_date)
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    fn store() -> DieselMfgBatchStore<diesel::sqlite::SqliteConnection> {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        store
+            .add_mfg_batches(vec![
+                mfg_batch("00012345600012", "org-a", Some(1_646_092_800)),
+                mfg_batch("00012345600029", "org-a", Some(1_646_092_800)),
+                mfg_batch("00012345600036", "org-a", Some(1_646_179_200)),
+                mfg_batch("00012345600043", "org/b", None),
+            ])
+            .expect("Failed to add mfg_batches");
+        store
+    }
+
+    /// Verify that the record batches are split by owner and production date, with every
+    /// mfg_batch and property in exactly one of them
+    #[test]
+    fn test_export_record_batches() {
+        let store = store();
+        let mut batches = Vec::new();
+        let summary = export_mfg_batches(
+            &store,
+            None,
+            &ListMfgBatchFilters::default(),
+            &ExportOptions::default(),
+            &mut |table, partition: &ExportPartition, batch: RecordBatch| {
+                batches.push((table, partition.clone(), batch.num_rows()));
+                Ok(())
+            },
+        )
+        .expect("Failed to export");
+
+        assert_eq!(
+            summary,
+            ExportSummary {
+                mfg_batches: 4,
+                properties: 4,
+            }
+        );
+        let partition = |owner: &str, date: Option<&str>| ExportPartition {
+            owner: owner.to_string(),
+            date: date.map(String::from),
+        };
+        assert_eq!(
+            batches,
+            vec![
+                (
+                    ExportTable::MfgBatches,
+                    partition("org-a", Some("2022-03-01")),
+                    2
+                ),
+                (
+                    ExportTable::Properties,
+                    partition("org-a", Some("2022-03-01")),
+                    2
+                ),
+                (
+                    ExportTable::MfgBatches,
+                    partition("org-a", Some("2022-03-02")),
+                    1
+                ),
+                (
+                    ExportTable::Properties,
+                    partition("org-a", Some("2022-03-02")),
+                    1
+                ),
+                (ExportTable::MfgBatches, partition("org/b", None), 1),
+                (ExportTable::Properties, partition("org/b", None), 1),
+            ]
+        );
+    }
+
+    /// Verify that a partition is flushed once it holds `rows_per_batch` mfg_batches
+    #[test]
+    fn test_export_rows_per_batch() {
+        let store = store();
+        let mut sizes = Vec::new();
+        export_mfg_batches(
+            &store,
+            None,
+            &ListMfgBatchFilters::default(),
+            &ExportOptions {
+                page_size: 2,
+                rows_per_batch: 1,
+            },
+            &mut |table, _: &ExportPartition, batch: RecordBatch| {
+                if table == ExportTable::MfgBatches {
+                    sizes.push(batch.num_rows());
+                }
+                Ok(())
+            },
+        )
+        .expect("Failed to export");
+
+        assert_eq!(sizes, vec![1, 1, 1, 1]);
+    }
+
+    /// Verify that Parquet files are written to Hive-style partition directories and read back
+    /// with the exported rows
+    #[test]
+    fn test_export_parquet() {
+        let dir = TempDir::new().expect("Failed to create directory");
+        let mut sink = ParquetSink::new(dir.path());
+        export_mfg_batches(
+            &store(),
+            None,
+            &ListMfgBatchFilters::default(),
+            &ExportOptions::default(),
+            &mut sink,
+        )
+        .expect("Failed to export");
+
+        let file = dir
+            .path()
+            .join("mfg_batch/owner=org-a/date=2022-03-01/part-00000.parquet");
+        assert!(sink.files().contains(&file));
+        assert!(sink.files().contains(&dir.path().join(
+            "mfg_batch_property/owner=org%2Fb/date=__HIVE_DEFAULT_PARTITION__/part-00000.parquet"
+        )));
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(
+            File::open(&file).expect("Failed to open file"),
+        )
+        .expect("Failed to read file")
+        .build()
+        .expect("Failed to read file")
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to read record batches");
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].schema(), mfg_batch_schema());
+
+        let ids = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .expect("Unexpected column type");
+        assert_eq!(ids.value(0), "00012345600012");
+        assert_eq!(ids.value(1), "00012345600029");
+    }
+}
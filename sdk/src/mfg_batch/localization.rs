@@ -0,0 +1,245 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Selection of localized property values.
+//!
+//! A string property may be given in several languages, as values that share the property's
+//! name and carry a BCP 47 language tag. Readers are shown one variant of each such property,
+//! the one that best matches the languages they accept.
+
+use super::store::{MfgBatch, PropertyValue};
+
+/// The languages a caller accepts, most preferred first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LanguagePreferences {
+    ranges: Vec<String>,
+}
+
+impl LanguagePreferences {
+    /// Creates preferences from language ranges, such as `fr-CA`, `fr` or `*`, most preferred
+    /// first
+    pub fn new(ranges: Vec<String>) -> Self {
+        Self { ranges }
+    }
+
+    /// Parses the value of an HTTP `Accept-Language` header, such as
+    /// `fr-CA, fr;q=0.9, en;q=0.5`. Ranges are ordered by quality, keeping the header's order
+    /// between ranges of equal quality; ranges with a quality of zero, or that cannot be parsed,
+    /// are left out.
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut weighted: Vec<(String, f32)> = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let range = parts.next().filter(|range| is_language_range(range))?;
+                let quality = match parts.next() {
+                    Some(param) => param
+                        .strip_prefix("q=")
+                        .and_then(|quality| quality.parse::<f32>().ok())
+                        .filter(|quality| (0.0..=1.0).contains(quality))?,
+                    None => 1.0,
+                };
+                if quality > 0.0 {
+                    Some((range.to_string(), quality))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        // A stable sort, so ranges of equal quality keep the order they were given in
+        weighted.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+
+        Self::new(weighted.into_iter().map(|(range, _)| range).collect())
+    }
+
+    /// Returns the language ranges, most preferred first
+    pub fn ranges(&self) -> &[String] {
+        &self.ranges
+    }
+
+    /// Returns the index of the language that best matches the preferences. Each range, in
+    /// order, is matched against the languages exactly, then as a prefix of a more specific
+    /// language, then with its last subtag removed, so `en-GB` matches `en` and `en-US` when
+    /// there is no `en-GB`. Languages that match no range fall back to the unlocalized value,
+    /// if there is one, or else the first.
+    fn best_match(&self, languages: &[Option<&str>]) -> usize {
+        for range in &self.ranges {
+            if range == "*" {
+                break;
+            }
+            let mut range = range.as_str();
+            loop {
+                let found = languages
+                    .iter()
+                    .position(|language| {
+                        matches!(language, Some(language) if language.eq_ignore_ascii_case(range))
+                    })
+                    .or_else(|| {
+                        languages.iter().position(|language| {
+                            matches!(language, Some(language)
+                                if language.len() > range.len()
+                                    && language.as_bytes()[range.len()] == b'-'
+                                    && language[..range.len()].eq_ignore_ascii_case(range))
+                        })
+                    });
+                if let Some(index) = found {
+                    return index;
+                }
+                match range.rfind('-') {
+                    Some(end) => range = &range[..end],
+                    None => break,
+                }
+            }
+        }
+
+        languages
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or_default()
+    }
+}
+
+/// Keeps the one variant of each localized property that best matches the preferences, in
+/// the position of the property's first variant. Properties that are not localized are kept
+/// as they are.
+pub fn localize_properties(
+    properties: Vec<PropertyValue>,
+    preferences: &LanguagePreferences,
+) -> Vec<PropertyValue> {
+    let mut names: Vec<String> = Vec::new();
+    let mut groups: Vec<Vec<PropertyValue>> = Vec::new();
+    for property in properties {
+        match names
+            .iter()
+            .position(|name| name == property.property_name())
+        {
+            Some(index) => groups[index].push(property),
+            None => {
+                names.push(property.property_name().to_string());
+                groups.push(vec![property]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|mut variants| {
+            if variants.iter().all(|variant| variant.language().is_none()) {
+                return variants;
+            }
+            let languages: Vec<Option<&str>> =
+                variants.iter().map(PropertyValue::language).collect();
+            let index = preferences.best_match(&languages);
+            vec![variants.swap_remove(index)]
+        })
+        .collect()
+}
+
+/// Keeps the one variant of each of a mfg_batch's localized properties that best matches the
+/// preferences
+pub fn localize_mfg_batch(mfg_batch: MfgBatch, preferences: &LanguagePreferences) -> MfgBatch {
+    let properties = localize_properties(mfg_batch.properties(), preferences);
+    mfg_batch.with_properties(properties)
+}
+
+/// Whether the range is `*` or made of subtags of one to eight letters or digits, separated
+/// by hyphens
+fn is_language_range(range: &str) -> bool {
+    range == "*"
+        || range.split('-').all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::{store::PropertyValueBuilder, MAX_COMMIT_NUM};
+
+    fn property(name: &str, language: Option<&str>, value: &str) -> PropertyValue {
+        PropertyValueBuilder::default()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_property_name(name.into())
+            .with_data_type("String".into())
+            .with_string_value(Some(value.into()))
+            .with_language(language.map(String::from))
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build property value")
+    }
+
+    fn values(properties: &[PropertyValue]) -> Vec<String> {
+        properties
+            .iter()
+            .map(|property| property.string_value().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    /// Verify that ranges are ordered by quality, keeping the header's order for equal
+    /// qualities, and that unacceptable or malformed ranges are left out
+    #[test]
+    fn test_from_accept_language() {
+        let preferences = LanguagePreferences::from_accept_language(
+            "en;q=0.5, fr-CA, de;q=0, fr;q=0.9, es;q=0.5, bad range, it;q=x, *;q=0.1",
+        );
+
+        assert_eq!(preferences.ranges(), &["fr-CA", "fr", "en", "es", "*"]);
+        assert_eq!(
+            LanguagePreferences::from_accept_language(""),
+            LanguagePreferences::default()
+        );
+    }
+
+    /// Verify that the best matching variant of each localized property is kept, in the
+    /// position of the property's first variant
+    #[test]
+    fn test_localize_properties() {
+        let properties = vec![
+            property("description", None, "Flour"),
+            property("lot", None, "L1"),
+            property("description", Some("fr"), "Farine"),
+            property("description", Some("de-DE"), "Mehl"),
+        ];
+
+        let localized = |header: &str| {
+            values(&localize_properties(
+                properties.clone(),
+                &LanguagePreferences::from_accept_language(header),
+            ))
+        };
+
+        assert_eq!(localized("fr-CA, en;q=0.8"), vec!["Farine", "L1"]);
+        assert_eq!(localized("DE"), vec!["Mehl", "L1"]);
+        assert_eq!(localized("es, *"), vec!["Flour", "L1"]);
+        assert_eq!(localized(""), vec!["Flour", "L1"]);
+    }
+
+    /// Verify that a localized property with no unlocalized value falls back to its first
+    /// variant
+    #[test]
+    fn test_localize_properties_without_fallback() {
+        let properties = vec![
+            property("description", Some("fr"), "Farine"),
+            property("description", Some("en"), "Flour"),
+        ];
+
+        let localized =
+            localize_properties(properties, &LanguagePreferences::new(vec!["es".into()]));
+
+        assert_eq!(values(&localized), vec!["Farine"]);
+    }
+}
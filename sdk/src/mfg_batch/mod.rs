@@ -12,8 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "mfg-batch-address-distribution")]
+pub mod address_distribution;
 pub mod addressing;
+#[cfg(feature = "mfg-batch-anchors")]
+pub mod anchors;
+#[cfg(feature = "mfg-batch-certificates")]
+pub mod certificate;
+#[cfg(feature = "mfg-batch-client")]
+pub mod client;
+#[cfg(feature = "mfg-batch-duplicates")]
+pub mod duplicates;
+#[cfg(feature = "mfg-batch-epcis")]
+pub mod epcis;
+#[cfg(feature = "mfg-batch-export")]
+pub mod export;
+#[cfg(feature = "mfg-batch-localization")]
+pub mod localization;
+#[cfg(feature = "mfg-batch-projections")]
+pub mod projections;
+#[cfg(feature = "mfg-batch-quality-scores")]
+pub mod quality;
+#[cfg(feature = "mfg-batch-recalls")]
+pub mod recall;
 pub mod store;
+pub mod validation;
+
+pub use validation::{validate_create_action, Violation};
 
 pub const MAX_COMMIT_NUM: i64 = i64::MAX;
 //TODO decide what to do with internal validations 
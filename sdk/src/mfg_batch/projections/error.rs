@@ -0,0 +1,49 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+/// An error that can occur while running projections
+#[derive(Debug)]
+pub enum ProjectionError {
+    /// The change feed, a checkpoint or a projection's tables could not be read or written
+    Query(diesel::result::Error),
+    /// No projection with the given name is registered
+    NotFound(String),
+}
+
+impl Error for ProjectionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProjectionError::Query(err) => Some(err),
+            ProjectionError::NotFound(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProjectionError::Query(err) => err.fmt(f),
+            ProjectionError::NotFound(name) => write!(f, "No projection named {}", name),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for ProjectionError {
+    fn from(err: diesel::result::Error) -> Self {
+        ProjectionError::Query(err)
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A projection of when each mfg_batch expires, kept in `mfg_batch_expiry`, for listing the
+//! mfg_batches that expire within a window, such as the next 30 days
+
+use diesel::prelude::*;
+
+use super::schema::mfg_batch_expiry;
+use super::{MfgBatchChange, Projection};
+
+/// The name the projection's checkpoint is kept under
+const EXPIRY_PROJECTION: &str = "mfg_batch_expiry";
+const MFG_BATCH_ENTITY: &str = "mfg_batch";
+const EXPIRATION_DATE_FIELD: &str = "expiration_date";
+
+/// Keeps the expiration date of each current mfg_batch that has one
+pub struct ExpiryProjection;
+
+/// A mfg_batch and when it expires, in seconds since the epoch
+#[derive(Clone, Debug, PartialEq, Queryable)]
+pub struct ExpiringMfgBatch {
+    pub mfg_batch_id: String,
+    pub expiration_date: i64,
+}
+
+/// Returns the expiration date a change sets, if it is a change to a mfg_batch's expiration
+/// date; the inner `None` is a date being removed
+fn expiration_change(change: &MfgBatchChange) -> Option<Option<i64>> {
+    if change.entity != MFG_BATCH_ENTITY || change.field != EXPIRATION_DATE_FIELD {
+        return None;
+    }
+
+    Some(
+        change
+            .new_value
+            .as_deref()
+            .and_then(|value| value.parse().ok()),
+    )
+}
+
+#[cfg(feature = "postgres")]
+impl Projection<PgConnection> for ExpiryProjection {
+    fn name(&self) -> &str {
+        EXPIRY_PROJECTION
+    }
+
+    fn apply(&self, conn: &PgConnection, change: &MfgBatchChange) -> QueryResult<()> {
+        match expiration_change(change) {
+            Some(expiration_date) => pg::set_expiration_date(
+                conn,
+                &change.entity_id,
+                change.service_id.as_deref(),
+                expiration_date,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn reset(&self, conn: &PgConnection) -> QueryResult<()> {
+        diesel::delete(mfg_batch_expiry::table)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Projection<SqliteConnection> for ExpiryProjection {
+    fn name(&self) -> &str {
+        EXPIRY_PROJECTION
+    }
+
+    fn apply(&self, conn: &SqliteConnection, change: &MfgBatchChange) -> QueryResult<()> {
+        match expiration_change(change) {
+            Some(expiration_date) => sqlite::set_expiration_date(
+                conn,
+                &change.entity_id,
+                change.service_id.as_deref(),
+                expiration_date,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn reset(&self, conn: &SqliteConnection) -> QueryResult<()> {
+        diesel::delete(mfg_batch_expiry::table)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub mod pg {
+    use super::*;
+
+    /// Lists the mfg_batches of a service that expire before the given time, soonest first
+    pub fn list_expiring_before(
+        conn: &PgConnection,
+        before: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ExpiringMfgBatch>> {
+        let mut query = mfg_batch_expiry::table
+            .into_boxed()
+            .select((
+                mfg_batch_expiry::mfg_batch_id,
+                mfg_batch_expiry::expiration_date,
+            ))
+            .filter(mfg_batch_expiry::expiration_date.lt(before));
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_expiry::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_expiry::service_id.is_null());
+        }
+
+        query
+            .order((
+                mfg_batch_expiry::expiration_date.asc(),
+                mfg_batch_expiry::mfg_batch_id.asc(),
+            ))
+            .load(conn)
+    }
+
+    pub(super) fn set_expiration_date(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        expiration_date: Option<i64>,
+    ) -> QueryResult<()> {
+        if let Some(service_id) = service_id {
+            diesel::delete(
+                mfg_batch_expiry::table.filter(
+                    mfg_batch_expiry::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_expiry::service_id.eq(service_id)),
+                ),
+            )
+            .execute(conn)?;
+        } else {
+            diesel::delete(
+                mfg_batch_expiry::table.filter(
+                    mfg_batch_expiry::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_expiry::service_id.is_null()),
+                ),
+            )
+            .execute(conn)?;
+        }
+
+        if let Some(expiration_date) = expiration_date {
+            diesel::insert_into(mfg_batch_expiry::table)
+                .values((
+                    mfg_batch_expiry::mfg_batch_id.eq(mfg_batch_id),
+                    mfg_batch_expiry::service_id.eq(service_id),
+                    mfg_batch_expiry::expiration_date.eq(expiration_date),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite {
+    use super::*;
+
+    /// Lists the mfg_batches of a service that expire before the given time, soonest first
+    pub fn list_expiring_before(
+        conn: &SqliteConnection,
+        before: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ExpiringMfgBatch>> {
+        let mut query = mfg_batch_expiry::table
+            .into_boxed()
+            .select((
+                mfg_batch_expiry::mfg_batch_id,
+                mfg_batch_expiry::expiration_date,
+            ))
+            .filter(mfg_batch_expiry::expiration_date.lt(before));
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_expiry::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_expiry::service_id.is_null());
+        }
+
+        query
+            .order((
+                mfg_batch_expiry::expiration_date.asc(),
+                mfg_batch_expiry::mfg_batch_id.asc(),
+            ))
+            .load(conn)
+    }
+
+    pub(super) fn set_expiration_date(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        expiration_date: Option<i64>,
+    ) -> QueryResult<()> {
+        if let Some(service_id) = service_id {
+            diesel::delete(
+                mfg_batch_expiry::table.filter(
+                    mfg_batch_expiry::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_expiry::service_id.eq(service_id)),
+                ),
+            )
+            .execute(conn)?;
+        } else {
+            diesel::delete(
+                mfg_batch_expiry::table.filter(
+                    mfg_batch_expiry::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_expiry::service_id.is_null()),
+                ),
+            )
+            .execute(conn)?;
+        }
+
+        if let Some(expiration_date) = expiration_date {
+            diesel::insert_into(mfg_batch_expiry::table)
+                .values((
+                    mfg_batch_expiry::mfg_batch_id.eq(mfg_batch_id),
+                    mfg_batch_expiry::service_id.eq(service_id),
+                    mfg_batch_expiry::expiration_date.eq(expiration_date),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::projections::tests::{connection, record_change};
+    use crate::mfg_batch::projections::ProjectionRunner;
+
+    /// Verify that expiration dates set, changed and removed are reflected in the projection and
+    /// that changes to other fields are ignored
+    #[test]
+    fn test_expiry_projection() {
+        let conn = connection();
+        let runner = ProjectionRunner::new().with_projection(Box::new(ExpiryProjection));
+
+        record_change(&conn, "lot-1", "expiration_date", None, Some("300"));
+        record_change(&conn, "lot-2", "expiration_date", None, Some("100"));
+        record_change(&conn, "lot-3", "expiration_date", None, Some("200"));
+        record_change(&conn, "lot-3", "production_date", None, Some("50"));
+        record_change(&conn, "lot-1", "expiration_date", Some("300"), Some("150"));
+        record_change(&conn, "lot-2", "expiration_date", Some("100"), None);
+        runner.catch_up(&conn).expect("Failed to catch up");
+
+        assert_eq!(
+            sqlite::list_expiring_before(&conn, 250, None)
+                .expect("Failed to list expiring mfg_batches"),
+            vec![
+                ExpiringMfgBatch {
+                    mfg_batch_id: "lot-1".to_string(),
+                    expiration_date: 150,
+                },
+                ExpiringMfgBatch {
+                    mfg_batch_id: "lot-3".to_string(),
+                    expiration_date: 200,
+                },
+            ]
+        );
+        assert!(sqlite::list_expiring_before(&conn, 250, Some("svc-1"))
+            .expect("Failed to list expiring mfg_batches")
+            .is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Projections keep read tables, such as the mfg_batches expiring soon or the open holds of each
+//! owner, up to date from the mfg_batch change feed, so a new dashboard can be served from a
+//! table of its own without changes to the mfg_batch store.
+//!
+//! A projection is handed each change recorded in `mfg_batch_change`, in the order recorded, and
+//! updates its tables to match. The ID of the last change a projection has applied is its
+//! checkpoint, kept in `mfg_batch_projection_checkpoint` and written in the same transaction as
+//! the projection's tables, so a run that stops partway resumes where it stopped. Rebuilding a
+//! projection empties its tables and applies the whole feed again.
+
+mod error;
+pub mod expiry;
+mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::prelude::*;
+
+use crate::mfg_batch::store::diesel::schema::mfg_batch_change;
+
+use self::schema::mfg_batch_projection_checkpoint;
+
+pub use error::ProjectionError;
+pub use expiry::{ExpiringMfgBatch, ExpiryProjection};
+
+/// How many changes are applied in each transaction, unless set otherwise
+const DEFAULT_BATCH_SIZE: i64 = 500;
+
+/// A change to one field of a mfg_batch, as recorded in the change feed
+#[derive(Clone, Debug, PartialEq, Queryable)]
+pub struct MfgBatchChange {
+    /// The change's position in the feed
+    pub id: i64,
+    pub entity: String,
+    pub entity_id: String,
+    /// The field changed, such as `owner` or `properties.<name>`
+    pub field: String,
+    /// The field's value before the change, or `None` if the field was added
+    pub old_value: Option<String>,
+    /// The field's value after the change, or `None` if the field was removed
+    pub new_value: Option<String>,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+/// Maintains a read table from the change feed
+pub trait Projection<C>: Send + Sync {
+    /// The name the projection's checkpoint is kept under
+    fn name(&self) -> &str;
+
+    /// Updates the projection's tables for a change. Changes the projection has no interest in
+    /// are to be ignored.
+    fn apply(&self, conn: &C, change: &MfgBatchChange) -> QueryResult<()>;
+
+    /// Empties the projection's tables, ahead of applying the whole feed again
+    fn reset(&self, conn: &C) -> QueryResult<()>;
+}
+
+/// A connection the change feed and checkpoints can be read over
+pub trait ProjectionConnection:
+    diesel::Connection<TransactionManager = AnsiTransactionManager>
+{
+    /// Lists up to `limit` changes recorded after the change with the given ID, in order
+    fn changes_after(&self, change_id: i64, limit: i64) -> QueryResult<Vec<MfgBatchChange>>;
+
+    /// Returns the ID of the last change recorded, or 0 if none have been
+    fn latest_change_id(&self) -> QueryResult<i64>;
+
+    /// Returns the ID of the last change a projection applied, or 0 if it has applied none
+    fn checkpoint(&self, projection: &str) -> QueryResult<i64>;
+
+    fn save_checkpoint(&self, projection: &str, change_id: i64) -> QueryResult<()>;
+}
+
+/// How far a projection has got through the change feed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectionStatus {
+    pub name: String,
+    /// The ID of the last change the projection applied
+    pub checkpoint: i64,
+    /// The ID of the last change recorded
+    pub latest_change_id: i64,
+}
+
+/// Runs a set of projections over the change feed
+pub struct ProjectionRunner<C> {
+    projections: Vec<Box<dyn Projection<C>>>,
+    batch_size: i64,
+}
+
+impl<C: ProjectionConnection> Default for ProjectionRunner<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ProjectionConnection> ProjectionRunner<C> {
+    pub fn new() -> Self {
+        Self {
+            projections: vec![],
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Registers a projection
+    pub fn with_projection(mut self, projection: Box<dyn Projection<C>>) -> Self {
+        self.projections.push(projection);
+        self
+    }
+
+    /// Sets how many changes are applied in each transaction
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Returns the names of the registered projections
+    pub fn names(&self) -> Vec<&str> {
+        self.projections
+            .iter()
+            .map(|projection| projection.name())
+            .collect()
+    }
+
+    /// Returns how far each registered projection has got through the change feed
+    pub fn status(&self, conn: &C) -> Result<Vec<ProjectionStatus>, ProjectionError> {
+        let latest_change_id = conn.latest_change_id()?;
+        self.projections
+            .iter()
+            .map(|projection| {
+                Ok(ProjectionStatus {
+                    name: projection.name().to_string(),
+                    checkpoint: conn.checkpoint(projection.name())?,
+                    latest_change_id,
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the changes each projection has not yet applied, returning how many changes were
+    /// applied in all
+    pub fn catch_up(&self, conn: &C) -> Result<usize, ProjectionError> {
+        let mut applied = 0;
+        for projection in &self.projections {
+            applied += self.catch_up_projection(conn, projection.as_ref())?;
+        }
+        Ok(applied)
+    }
+
+    /// Empties a projection's tables and applies the whole change feed to it again, returning
+    /// how many changes were applied
+    pub fn rebuild(&self, conn: &C, name: &str) -> Result<usize, ProjectionError> {
+        let projection = self
+            .projections
+            .iter()
+            .find(|projection| projection.name() == name)
+            .ok_or_else(|| ProjectionError::NotFound(name.to_string()))?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            projection.reset(conn)?;
+            conn.save_checkpoint(name, 0)
+        })?;
+
+        self.catch_up_projection(conn, projection.as_ref())
+    }
+
+    fn catch_up_projection(
+        &self,
+        conn: &C,
+        projection: &dyn Projection<C>,
+    ) -> Result<usize, ProjectionError> {
+        let mut applied = 0;
+        loop {
+            // Each batch and the checkpoint after it are written together, so a failed batch is
+            // applied again in full by the next run
+            let count = conn.transaction::<_, diesel::result::Error, _>(|| {
+                let checkpoint = conn.checkpoint(projection.name())?;
+                let changes = conn.changes_after(checkpoint, self.batch_size)?;
+                for change in &changes {
+                    projection.apply(conn, change)?;
+                }
+                if let Some(last) = changes.last() {
+                    conn.save_checkpoint(projection.name(), last.id)?;
+                }
+                Ok(changes.len())
+            })?;
+
+            applied += count;
+            if (count as i64) < self.batch_size {
+                break;
+            }
+        }
+
+        Ok(applied)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl ProjectionConnection for diesel::pg::PgConnection {
+    fn changes_after(&self, change_id: i64, limit: i64) -> QueryResult<Vec<MfgBatchChange>> {
+        mfg_batch_change::table
+            .filter(mfg_batch_change::id.gt(change_id))
+            .order(mfg_batch_change::id.asc())
+            .limit(limit)
+            .load(self)
+    }
+
+    fn latest_change_id(&self) -> QueryResult<i64> {
+        mfg_batch_change::table
+            .select(diesel::dsl::max(mfg_batch_change::id))
+            .first::<Option<i64>>(self)
+            .map(|id| id.unwrap_or(0))
+    }
+
+    fn checkpoint(&self, projection: &str) -> QueryResult<i64> {
+        mfg_batch_projection_checkpoint::table
+            .filter(mfg_batch_projection_checkpoint::projection_name.eq(projection))
+            .select(mfg_batch_projection_checkpoint::change_id)
+            .first(self)
+            .optional()
+            .map(|change_id| change_id.unwrap_or(0))
+    }
+
+    fn save_checkpoint(&self, projection: &str, change_id: i64) -> QueryResult<()> {
+        diesel::insert_into(mfg_batch_projection_checkpoint::table)
+            .values((
+                mfg_batch_projection_checkpoint::projection_name.eq(projection),
+                mfg_batch_projection_checkpoint::change_id.eq(change_id),
+            ))
+            .on_conflict(mfg_batch_projection_checkpoint::projection_name)
+            .do_update()
+            .set(mfg_batch_projection_checkpoint::change_id.eq(change_id))
+            .execute(self)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ProjectionConnection for diesel::sqlite::SqliteConnection {
+    fn changes_after(&self, change_id: i64, limit: i64) -> QueryResult<Vec<MfgBatchChange>> {
+        mfg_batch_change::table
+            .filter(mfg_batch_change::id.gt(change_id))
+            .order(mfg_batch_change::id.asc())
+            .limit(limit)
+            .load(self)
+    }
+
+    fn latest_change_id(&self) -> QueryResult<i64> {
+        mfg_batch_change::table
+            .select(diesel::dsl::max(mfg_batch_change::id))
+            .first::<Option<i64>>(self)
+            .map(|id| id.unwrap_or(0))
+    }
+
+    fn checkpoint(&self, projection: &str) -> QueryResult<i64> {
+        mfg_batch_projection_checkpoint::table
+            .filter(mfg_batch_projection_checkpoint::projection_name.eq(projection))
+            .select(mfg_batch_projection_checkpoint::change_id)
+            .first(self)
+            .optional()
+            .map(|change_id| change_id.unwrap_or(0))
+    }
+
+    fn save_checkpoint(&self, projection: &str, change_id: i64) -> QueryResult<()> {
+        diesel::replace_into(mfg_batch_projection_checkpoint::table)
+            .values((
+                mfg_batch_projection_checkpoint::projection_name.eq(projection),
+                mfg_batch_projection_checkpoint::change_id.eq(change_id),
+            ))
+            .execute(self)
+            .map(|_| ())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+pub(super) mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+    use diesel::sqlite::SqliteConnection;
+
+    use crate::mfg_batch::store::diesel::models::NewMfgBatchChange;
+
+    /// Creates the change feed, checkpoint and expiry tables in a new in-memory database
+    pub fn connection() -> SqliteConnection {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch_change (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entity TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_projection_checkpoint (
+                projection_name TEXT PRIMARY KEY,
+                change_id BIGINT NOT NULL
+            );
+            CREATE TABLE mfg_batch_expiry (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                service_id TEXT,
+                expiration_date BIGINT NOT NULL
+            );",
+        )
+        .expect("Failed to create tables");
+        conn
+    }
+
+    /// Records a change to a field of a mfg_batch
+    pub fn record_change(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        field: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) {
+        diesel::insert_into(mfg_batch_change::table)
+            .values(NewMfgBatchChange {
+                entity: "mfg_batch".to_string(),
+                entity_id: mfg_batch_id.to_string(),
+                field: field.to_string(),
+                old_value: old_value.map(String::from),
+                new_value: new_value.map(String::from),
+                commit_num: 1,
+                service_id: None,
+            })
+            .execute(conn)
+            .expect("Failed to record change");
+    }
+
+    /// Verify that catching up applies each change once, in batches, keeping a checkpoint per
+    /// projection, and that a rebuild applies the whole feed again
+    #[test]
+    fn test_catch_up_and_rebuild() {
+        let conn = connection();
+        let runner = ProjectionRunner::new()
+            .with_projection(Box::new(ExpiryProjection))
+            .with_batch_size(2);
+
+        record_change(&conn, "lot-1", "owner", None, Some("acme"));
+        record_change(&conn, "lot-1", "expiration_date", None, Some("200"));
+        record_change(&conn, "lot-2", "expiration_date", None, Some("100"));
+        assert_eq!(runner.catch_up(&conn).expect("Failed to catch up"), 3);
+        assert_eq!(runner.catch_up(&conn).expect("Failed to catch up"), 0);
+
+        record_change(&conn, "lot-2", "expiration_date", Some("100"), None);
+        assert_eq!(runner.catch_up(&conn).expect("Failed to catch up"), 1);
+        assert_eq!(
+            runner.status(&conn).expect("Failed to get status"),
+            vec![ProjectionStatus {
+                name: "mfg_batch_expiry".to_string(),
+                checkpoint: 4,
+                latest_change_id: 4,
+            }]
+        );
+
+        assert_eq!(
+            runner
+                .rebuild(&conn, "mfg_batch_expiry")
+                .expect("Failed to rebuild"),
+            4
+        );
+        assert_eq!(
+            expiry::sqlite::list_expiring_before(&conn, 1000, None)
+                .expect("Failed to list expiring mfg_batches"),
+            vec![ExpiringMfgBatch {
+                mfg_batch_id: "lot-1".to_string(),
+                expiration_date: 200,
+            }]
+        );
+
+        assert!(matches!(
+            runner.rebuild(&conn, "open_holds"),
+            Err(ProjectionError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    mfg_batch_projection_checkpoint (projection_name) {
+        projection_name -> Text,
+        change_id -> Int8,
+    }
+}
+
+table! {
+    mfg_batch_expiry (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        service_id -> Nullable<Text>,
+        expiration_date -> Int8,
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scores how completely each mfg_batch's data is filled in, so quality teams can work through
+//! the worst first.
+//!
+//! A mfg_batch is scored against a rubric of weighted checks, out of 100: the weight of the
+//! checks it passes over the weight of every check. The built in checks are read from YAML:
+//!
+//! ```yaml
+//! checks:
+//!   - type: field
+//!     field: expiration_date
+//!     weight: 2
+//!   - type: property
+//!     property: lot_code
+//!   - type: document
+//!     property: specification_sheet
+//!   - type: certification
+//!     name: organic
+//!     property: organic_certified_until
+//!   - type: tests_passed
+//! ```
+//!
+//! Other checks can be added to a rubric by implementing [`QualityCheck`].
+
+use std::collections::HashSet;
+
+use crate::error::InvalidArgumentError;
+
+use super::store::{
+    MfgBatch, MfgBatchQualityScore, MfgBatchStore, MfgBatchStoreError, MfgBatchTestResult,
+};
+
+/// One check of a quality rubric
+pub trait QualityCheck: Send + Sync {
+    /// The name the check is reported by when a mfg_batch fails it
+    fn name(&self) -> &str;
+
+    /// Checks a mfg_batch, given the test results recorded against it and the time it is
+    /// being scored at, in seconds since the epoch
+    fn check(&self, mfg_batch: &MfgBatch, test_results: &[MfgBatchTestResult], now: i64) -> bool;
+}
+
+/// The optional fields of a mfg_batch
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MfgBatchField {
+    Quantity,
+    Uom,
+    ExpectedQuantity,
+    ProductionDate,
+    ExpirationDate,
+}
+
+impl MfgBatchField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MfgBatchField::Quantity => "quantity",
+            MfgBatchField::Uom => "uom",
+            MfgBatchField::ExpectedQuantity => "expected_quantity",
+            MfgBatchField::ProductionDate => "production_date",
+            MfgBatchField::ExpirationDate => "expiration_date",
+        }
+    }
+}
+
+/// The checks a rubric can be written with
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Criterion {
+    /// The optional field is set
+    Field { field: MfgBatchField },
+    /// The mfg_batch has a value for the property
+    Property { property: String },
+    /// A document, such as a specification sheet, is attached in the property, either as a
+    /// link or as its contents
+    Document { property: String },
+    /// The property holds when a certification expires, in seconds since the epoch, and it has
+    /// not yet expired
+    Certification { property: String },
+    /// At least one test result has been recorded and every one passed
+    TestsPassed,
+}
+
+impl Criterion {
+    /// The name the criterion is reported by, unless the rubric gives it another
+    fn default_name(&self) -> String {
+        match self {
+            Criterion::Field { field } => field.as_str().to_string(),
+            Criterion::Property { property } => property.clone(),
+            Criterion::Document { property } => format!("document:{}", property),
+            Criterion::Certification { property } => format!("certification:{}", property),
+            Criterion::TestsPassed => "tests_passed".to_string(),
+        }
+    }
+}
+
+/// A criterion, with the name it is reported by
+pub struct CriterionCheck {
+    name: String,
+    criterion: Criterion,
+}
+
+impl CriterionCheck {
+    pub fn new(criterion: Criterion) -> Self {
+        Self {
+            name: criterion.default_name(),
+            criterion,
+        }
+    }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+}
+
+impl QualityCheck for CriterionCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&self, mfg_batch: &MfgBatch, test_results: &[MfgBatchTestResult], now: i64) -> bool {
+        match &self.criterion {
+            Criterion::Field { field } => match field {
+                MfgBatchField::Quantity => mfg_batch.quantity().is_some(),
+                MfgBatchField::Uom => mfg_batch.uom().map(|uom| !uom.is_empty()).unwrap_or(false),
+                MfgBatchField::ExpectedQuantity => mfg_batch.expected_quantity().is_some(),
+                MfgBatchField::ProductionDate => mfg_batch.production_date().is_some(),
+                MfgBatchField::ExpirationDate => mfg_batch.expiration_date().is_some(),
+            },
+            Criterion::Property { property } => mfg_batch
+                .properties()
+                .iter()
+                .any(|value| value.property_name() == property),
+            Criterion::Document { property } => mfg_batch.properties().iter().any(|value| {
+                value.property_name() == property
+                    && (value.string_value().map(|s| !s.is_empty()).unwrap_or(false)
+                        || value.bytes_value().map(|b| !b.is_empty()).unwrap_or(false))
+            }),
+            Criterion::Certification { property } => mfg_batch.properties().iter().any(|value| {
+                value.property_name() == property
+                    && value
+                        .number_value()
+                        .map(|until| until > now)
+                        .unwrap_or(false)
+            }),
+            Criterion::TestsPassed => {
+                !test_results.is_empty() && test_results.iter().all(|result| result.passed)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RubricFile {
+    checks: Vec<RubricEntry>,
+}
+
+#[derive(Deserialize)]
+struct RubricEntry {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(flatten)]
+    criterion: Criterion,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// The weighted checks mfg_batches are scored against
+pub struct QualityRubric {
+    checks: Vec<(u32, Box<dyn QualityCheck>)>,
+}
+
+impl QualityRubric {
+    /// Creates a rubric without any checks, which every mfg_batch passes
+    pub fn new() -> Self {
+        Self { checks: Vec::new() }
+    }
+
+    /// Adds a check, worth `weight` towards the score
+    pub fn with_check(mut self, weight: u32, check: Box<dyn QualityCheck>) -> Self {
+        self.checks.push((weight, check));
+        self
+    }
+
+    /// Reads a rubric of built in checks from YAML
+    pub fn from_yaml(yaml: &str) -> Result<Self, InvalidArgumentError> {
+        let file: RubricFile = serde_yaml::from_str(yaml)
+            .map_err(|err| InvalidArgumentError::new("rubric".to_string(), err.to_string()))?;
+
+        let mut names = HashSet::new();
+        let mut rubric = Self::new();
+        for entry in file.checks {
+            let mut check = CriterionCheck::new(entry.criterion);
+            if let Some(name) = entry.name {
+                check = check.with_name(name);
+            }
+
+            // Failed checks are stored one per line
+            if check.name.is_empty() || check.name.contains('\n') {
+                return Err(InvalidArgumentError::new(
+                    "name".to_string(),
+                    format!(
+                        "Check name {:?} must be a single, non-empty line",
+                        check.name
+                    ),
+                ));
+            }
+            if !names.insert(check.name.clone()) {
+                return Err(InvalidArgumentError::new(
+                    "name".to_string(),
+                    format!("Rubric has more than one check named {}", check.name),
+                ));
+            }
+
+            rubric = rubric.with_check(entry.weight, Box::new(check));
+        }
+
+        Ok(rubric)
+    }
+
+    /// Scores a mfg_batch, given the test results recorded against it and the time it is being
+    /// scored at, in seconds since the epoch
+    pub fn score(
+        &self,
+        mfg_batch: &MfgBatch,
+        test_results: &[MfgBatchTestResult],
+        now: i64,
+    ) -> MfgBatchQualityScore {
+        let mut total = 0u64;
+        let mut passed = 0u64;
+        let mut failed_checks = Vec::new();
+
+        for (weight, check) in &self.checks {
+            total += u64::from(*weight);
+            if check.check(mfg_batch, test_results, now) {
+                passed += u64::from(*weight);
+            } else {
+                failed_checks.push(check.name().to_string());
+            }
+        }
+
+        // A rubric without checks is passed by every mfg_batch
+        let score = (passed * 100 + total / 2).checked_div(total).unwrap_or(100) as i32;
+
+        MfgBatchQualityScore {
+            mfg_batch_id: mfg_batch.mfg_batch_id().to_string(),
+            commit_num: *mfg_batch.start_commit_num(),
+            score,
+            failed_checks,
+            scored_at: now,
+            service_id: mfg_batch.service_id().map(String::from),
+        }
+    }
+}
+
+impl Default for QualityRubric {
+    /// Checks that the quantity and dates are filled in and that the mfg_batch's tests passed
+    fn default() -> Self {
+        vec![
+            Criterion::Field {
+                field: MfgBatchField::Quantity,
+            },
+            Criterion::Field {
+                field: MfgBatchField::Uom,
+            },
+            Criterion::Field {
+                field: MfgBatchField::ProductionDate,
+            },
+            Criterion::Field {
+                field: MfgBatchField::ExpirationDate,
+            },
+            Criterion::TestsPassed,
+        ]
+        .into_iter()
+        .fold(Self::new(), |rubric, criterion| {
+            rubric.with_check(1, Box::new(CriterionCheck::new(criterion)))
+        })
+    }
+}
+
+/// Scores the current version of a mfg_batch and stores its score. Returns `None` if the
+/// mfg_batch no longer exists.
+pub fn rescore_mfg_batch(
+    store: &dyn MfgBatchStore,
+    rubric: &QualityRubric,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+    now: i64,
+) -> Result<Option<MfgBatchQualityScore>, MfgBatchStoreError> {
+    let mfg_batch = match store.get_mfg_batch(mfg_batch_id, service_id)? {
+        Some(mfg_batch) => mfg_batch,
+        None => return Ok(None),
+    };
+    let test_results = store.list_mfg_batch_test_results(mfg_batch_id, service_id)?;
+
+    let score = rubric.score(&mfg_batch, &test_results, now);
+    store.put_mfg_batch_quality_score(score.clone())?;
+
+    Ok(Some(score))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::{MfgBatchBuilder, PropertyValueBuilder};
+
+    const NOW: i64 = 1_646_092_800;
+
+    fn property(
+        name: &str,
+        string: Option<&str>,
+        number: Option<i64>,
+    ) -> crate::mfg_batch::store::PropertyValue {
+        PropertyValueBuilder::default()
+            .with_mfg_batch_id("B-1".to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_property_name(name.to_string())
+            .with_data_type(if number.is_some() { "NUMBER" } else { "STRING" }.to_string())
+            .with_string_value(string.map(String::from))
+            .with_number_value(number)
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build property")
+    }
+
+    fn mfg_batch() -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id("B-1".to_string())
+            .with_mfg_batch_address("addr".to_string())
+            .with_mfg_batch_namespace("LOT".to_string())
+            .with_owner("acme".to_string())
+            .with_start_commit_number(3)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(vec![
+                property("lot_code", Some("L1"), None),
+                property("specification_sheet", Some(""), None),
+                property("organic_certified_until", None, Some(NOW + 1)),
+                property("kosher_certified_until", None, Some(NOW)),
+            ])
+            .with_quantity(Some(40))
+            .with_production_date(Some(NOW))
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    fn test_result(passed: bool) -> MfgBatchTestResult {
+        MfgBatchTestResult {
+            mfg_batch_id: "B-1".to_string(),
+            commit_num: 3,
+            test_name: "moisture".to_string(),
+            specification: "<= 12.5 %".to_string(),
+            result: "11.8 %".to_string(),
+            passed,
+            lab: "Acme Labs".to_string(),
+            tested_at: NOW,
+            service_id: None,
+        }
+    }
+
+    /// Verify that a rubric read from YAML scores a mfg_batch by the weight of the checks it
+    /// passes, and reports the checks it failed
+    #[test]
+    fn test_score_from_yaml() {
+        let rubric = QualityRubric::from_yaml(
+            "
+checks:
+  - type: field
+    field: quantity
+    weight: 3
+  - type: field
+    field: expiration_date
+    weight: 2
+  - type: property
+    property: lot_code
+  - type: document
+    property: specification_sheet
+  - type: certification
+    name: organic
+    property: organic_certified_until
+  - type: certification
+    property: kosher_certified_until
+  - type: tests_passed
+",
+        )
+        .expect("Failed to read rubric");
+
+        let score = rubric.score(&mfg_batch(), &[test_result(true)], NOW);
+        assert_eq!(score.score, 60);
+        assert_eq!(score.commit_num, 3);
+        assert_eq!(
+            score.failed_checks,
+            vec![
+                "expiration_date".to_string(),
+                "document:specification_sheet".to_string(),
+                "certification:kosher_certified_until".to_string(),
+            ]
+        );
+
+        let score = rubric.score(&mfg_batch(), &[test_result(true), test_result(false)], NOW);
+        assert_eq!(score.score, 50);
+    }
+
+    /// Verify that the default rubric checks the quantity, dates and test results, and that a
+    /// rubric without checks gives full marks
+    #[test]
+    fn test_default_and_empty_rubrics() {
+        let score = QualityRubric::default().score(&mfg_batch(), &[], NOW);
+        assert_eq!(score.score, 40);
+        assert_eq!(
+            score.failed_checks,
+            vec![
+                "uom".to_string(),
+                "expiration_date".to_string(),
+                "tests_passed".to_string()
+            ]
+        );
+
+        assert_eq!(
+            QualityRubric::new().score(&mfg_batch(), &[], NOW).score,
+            100
+        );
+    }
+
+    /// Verify that rubrics with checks of the same name are rejected
+    #[test]
+    fn test_duplicate_check_names() {
+        assert!(QualityRubric::from_yaml(
+            "
+checks:
+  - type: property
+    property: lot_code
+  - type: field
+    field: uom
+    name: lot_code
+"
+        )
+        .is_err());
+    }
+}
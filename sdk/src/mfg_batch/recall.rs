@@ -0,0 +1,239 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recalls of mfg_batches.
+//!
+//! A recall action flags each batch it lists as recalled on chain. The state of a batch does not
+//! link to the batches produced from it, so recalling every descendant of a batch means listing
+//! them in the action; they are resolved here from the genealogy recorded in the store. Once the
+//! action is committed, its recall records are added to the store, where the recall stays
+//! active until it is closed out.
+
+use crate::protocol::errors::BuilderError;
+use crate::protocol::mfg_batch::{
+    payload::{MfgBatchRecallAction, MfgBatchRecallActionBuilder},
+    state::MfgBatchNamespace,
+};
+
+use super::store::{MfgBatchRecall, MfgBatchStore, MfgBatchStoreError};
+
+/// Resolves the mfg_batches a recall covers: the given batches, in the order given, followed
+/// by their descendants if they are to be recalled too. Each batch is listed once.
+///
+/// # Arguments
+///
+///  * `store` - The store to read the genealogy from
+///  * `mfg_batch_ids` - The IDs of the mfg_batches to recall
+///  * `include_descendants` - Whether every batch produced from the given batches is recalled
+///  * `service_id` - The service ID to resolve the batches for
+pub fn resolve_recall_targets<S: MfgBatchStore + ?Sized>(
+    store: &S,
+    mfg_batch_ids: &[String],
+    include_descendants: bool,
+    service_id: Option<&str>,
+) -> Result<Vec<String>, MfgBatchStoreError> {
+    let mut targets: Vec<String> = Vec::new();
+    let mut push = |mfg_batch_id: String| {
+        if !targets.contains(&mfg_batch_id) {
+            targets.push(mfg_batch_id);
+        }
+    };
+
+    for mfg_batch_id in mfg_batch_ids {
+        push(mfg_batch_id.clone());
+    }
+
+    if include_descendants {
+        for mfg_batch_id in mfg_batch_ids {
+            for descendant in store.get_mfg_batch_descendants(mfg_batch_id, service_id)? {
+                push(descendant);
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+/// Builds the action recalling the given mfg_batches and, if asked, their descendants
+///
+/// # Arguments
+///
+///  * `store` - The store to read the genealogy from
+///  * `mfg_batch_namespace` - The namespace of the mfg_batches
+///  * `mfg_batch_ids` - The IDs of the mfg_batches to recall
+///  * `include_descendants` - Whether every batch produced from the given batches is recalled
+///  * `recall_id` - The ID of the recall
+///  * `reason_code` - Why the mfg_batches are recalled
+///  * `service_id` - The service ID to resolve the batches for
+pub fn build_recall_action<S: MfgBatchStore + ?Sized>(
+    store: &S,
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_ids: &[String],
+    include_descendants: bool,
+    recall_id: &str,
+    reason_code: &str,
+    service_id: Option<&str>,
+) -> Result<MfgBatchRecallAction, RecallError> {
+    let targets = resolve_recall_targets(store, mfg_batch_ids, include_descendants, service_id)?;
+
+    Ok(MfgBatchRecallActionBuilder::new()
+        .with_mfg_batch_namespace(mfg_batch_namespace)
+        .with_mfg_batch_ids(targets)
+        .with_recall_id(recall_id.to_string())
+        .with_reason_code(reason_code.to_string())
+        .build()?)
+}
+
+/// The recall records of a committed recall action, one for each mfg_batch it flagged
+///
+/// # Arguments
+///
+///  * `action` - The committed recall action
+///  * `recalled_at` - The timestamp of the transaction the action was committed in
+///  * `commit_num` - The commit the action was committed in
+///  * `service_id` - The service ID the action was committed for
+pub fn recall_records(
+    action: &MfgBatchRecallAction,
+    recalled_at: i64,
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Vec<MfgBatchRecall> {
+    action
+        .mfg_batch_ids()
+        .iter()
+        .map(|mfg_batch_id| MfgBatchRecall {
+            recall_id: action.recall_id().to_string(),
+            mfg_batch_id: mfg_batch_id.clone(),
+            reason_code: action.reason_code().to_string(),
+            recalled_at,
+            closed_at: None,
+            commit_num,
+            service_id: service_id.map(String::from),
+        })
+        .collect()
+}
+
+/// An error that occurred while building a recall action
+#[derive(Debug)]
+pub enum RecallError {
+    /// The descendants of a recalled batch could not be read from the store
+    Store(MfgBatchStoreError),
+    /// The resolved recall does not make a valid action
+    Builder(BuilderError),
+}
+
+impl std::error::Error for RecallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecallError::Store(err) => Some(err),
+            RecallError::Builder(err) => Some(err),
+        }
+    }
+}
+
+impl std::fmt::Display for RecallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RecallError::Store(err) => write!(f, "Unable to resolve recalled batches: {}", err),
+            RecallError::Builder(err) => write!(f, "Unable to build recall action: {}", err),
+        }
+    }
+}
+
+impl From<MfgBatchStoreError> for RecallError {
+    fn from(err: MfgBatchStoreError) -> Self {
+        RecallError::Store(err)
+    }
+}
+
+impl From<BuilderError> for RecallError {
+    fn from(err: BuilderError) -> Self {
+        RecallError::Builder(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the recall records of an action carry the action's recall to each batch it
+    /// lists, active until closed out
+    #[test]
+    fn test_recall_records() {
+        let action = MfgBatchRecallActionBuilder::new()
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_mfg_batch_ids(vec!["flour".into(), "dough".into()])
+            .with_recall_id("RC-2022-014".into())
+            .with_reason_code("CONTAMINATION".into())
+            .build()
+            .expect("Failed to build recall action");
+
+        let records = recall_records(&action, 1_600_200_000, 4, Some("svc"));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].mfg_batch_id, "flour");
+        assert_eq!(records[1].mfg_batch_id, "dough");
+        for record in &records {
+            assert_eq!(record.recall_id, "RC-2022-014");
+            assert_eq!(record.reason_code, "CONTAMINATION");
+            assert_eq!(record.recalled_at, 1_600_200_000);
+            assert_eq!(record.closed_at, None);
+            assert_eq!(record.commit_num, 4);
+            assert_eq!(record.service_id.as_deref(), Some("svc"));
+        }
+    }
+
+    /// Verify that the given batches come first and descendants are only added when asked for,
+    /// each batch once even when it descends from several of the given batches
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_resolve_recall_targets() {
+        use crate::mfg_batch::{
+            store::{DieselMfgBatchStore, MfgBatchBuilder},
+            MAX_COMMIT_NUM,
+        };
+
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let add = |mfg_batch_id: &str, parent_batches: &[&str]| {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_parent_batches(parent_batches.iter().map(|id| id.to_string()).collect())
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        };
+
+        add("flour", &[]);
+        add("sugar", &[]);
+        add("dough", &["flour"]);
+        add("cake", &["flour", "sugar"]);
+
+        let ids = vec!["sugar".to_string(), "flour".to_string()];
+        assert_eq!(
+            resolve_recall_targets(&store, &ids, false, None).expect("Failed to resolve"),
+            vec!["sugar", "flour"]
+        );
+        assert_eq!(
+            resolve_recall_targets(&store, &ids, true, None).expect("Failed to resolve"),
+            vec!["sugar", "flour", "cake", "dough"]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hashing for the mfg_batch audit log.
+//!
+//! Every time a mfg_batch is written, an entry is appended to the audit log holding a hash of the
+//! rows written and a hash chaining it to the entry before it.
+
+use super::models::{
+    NewMfgBatch, NewMfgBatchAuditEntry, NewMfgBatchParent, NewMfgBatchPropertyValue,
+};
+use super::record_hash::{Encoder, HashedRecord};
+
+/// The `previous_hash` of the first entry in the log
+pub(in crate::mfg_batch) const GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Computes the hash of an audit entry, chaining it to the entry before it
+pub(in crate::mfg_batch) fn entry_hash(
+    previous_hash: &str,
+    mfg_batch_id: &str,
+    commit_num: i64,
+    service_id: Option<&str>,
+    record_hash: &str,
+) -> String {
+    let mut encoder = Encoder::default();
+    encoder.write_str(previous_hash);
+    encoder.write_str(mfg_batch_id);
+    encoder.write_i64(commit_num);
+    encoder.write_opt_str(service_id);
+    encoder.write_str(record_hash);
+    encoder.digest()
+}
+
+/// Builds the audit entry for a mfg_batch write, following the entry with `previous_hash`
+pub(in crate::mfg_batch) fn new_audit_entry(
+    mfg_batch: &NewMfgBatch,
+    property_values: &[NewMfgBatchPropertyValue],
+    parents: &[NewMfgBatchParent],
+    previous_hash: String,
+) -> NewMfgBatchAuditEntry {
+    let record_hash = HashedRecord::from_new(mfg_batch, property_values, parents).hash();
+    let entry_hash = entry_hash(
+        &previous_hash,
+        &mfg_batch.mfg_batch_id,
+        mfg_batch.start_commit_num,
+        mfg_batch.service_id.as_deref(),
+        &record_hash,
+    );
+
+    NewMfgBatchAuditEntry {
+        mfg_batch_id: mfg_batch.mfg_batch_id.clone(),
+        commit_num: mfg_batch.start_commit_num,
+        service_id: mfg_batch.service_id.clone(),
+        record_hash,
+        previous_hash,
+        entry_hash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::MAX_COMMIT_NUM;
+
+    fn new_mfg_batch() -> NewMfgBatch {
+        NewMfgBatch {
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            mfg_batch_namespace: "GS1".to_string(),
+            owner: "org-1".to_string(),
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            last_updated: None,
+            quantity: None,
+            uom: None,
+            expected_quantity: None,
+            production_date: None,
+            expiration_date: None,
+            manufacture_location: None,
+            checksum: None,
+            archived: false,
+        }
+    }
+
+    /// Verify that each entry's hash commits to the entry before it
+    #[test]
+    fn test_new_audit_entry_chains_to_previous() {
+        let mfg_batch = new_mfg_batch();
+
+        let first = new_audit_entry(&mfg_batch, &[], &[], GENESIS_HASH.to_string());
+        let second = new_audit_entry(&mfg_batch, &[], &[], first.entry_hash.clone());
+
+        assert_eq!(first.record_hash, second.record_hash);
+        assert_eq!(second.previous_hash, first.entry_hash);
+        assert_ne!(first.entry_hash, second.entry_hash);
+        assert_eq!(
+            second.entry_hash,
+            entry_hash(&first.entry_hash, "batch-1", 1, None, &second.record_hash)
+        );
+    }
+}
@@ -0,0 +1,646 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Column-level change capture for mfg_batches.
+//!
+//! The versioned row model is awkward to consume from reporting tools, so each write also records
+//! one row per changed field in `mfg_batch_change`, holding the field's old and new values as text.
+//! Property values are recorded as `properties.<name>`, or `properties.<struct>.<name>` for
+//! values nested in a struct property.
+
+use std::collections::BTreeMap;
+
+use diesel::{dsl::insert_into, prelude::*};
+
+use crate::hex::to_hex;
+use crate::mfg_batch::MAX_COMMIT_NUM;
+
+use super::models::{
+    MfgBatch, MfgBatchParent, MfgBatchPropertyValue, NewMfgBatch, NewMfgBatchChange,
+    NewMfgBatchParent, NewMfgBatchPropertyValue,
+};
+use super::schema::{mfg_batch, mfg_batch_change, mfg_batch_parent, mfg_batch_property_value};
+
+/// The entity name recorded for mfg_batch changes
+const MFG_BATCH_ENTITY: &str = "mfg_batch";
+
+/// The text value of each field of a mfg_batch, keyed by field name
+type FieldValues = BTreeMap<String, String>;
+
+/// The value columns of a property value row
+struct PropertyFields<'a> {
+    property_name: &'a str,
+    parent_property: Option<&'a str>,
+    bytes_value: Option<&'a [u8]>,
+    boolean_value: Option<bool>,
+    number_value: Option<i64>,
+    string_value: Option<&'a str>,
+    enum_value: Option<i32>,
+    latitude_value: Option<i64>,
+    longitude_value: Option<i64>,
+    language: Option<&'a str>,
+}
+
+impl<'a> PropertyFields<'a> {
+    /// The field name, qualified by the struct property it is nested in, if any, and by the
+    /// language of a localized value, such as `properties.description@fr`
+    fn field_name(&self, mfg_batch_id: &str) -> String {
+        let parent = self.parent_property.map(|parent| {
+            parent
+                .strip_prefix(&format!("{}:", mfg_batch_id))
+                .unwrap_or(parent)
+        });
+
+        let name = match parent {
+            Some(parent) => format!("properties.{}.{}", parent, self.property_name),
+            None => format!("properties.{}", self.property_name),
+        };
+        match self.language {
+            Some(language) => format!("{}@{}", name, language),
+            None => name,
+        }
+    }
+
+    /// The value as text; struct properties carry no value of their own
+    fn value(&self) -> Option<String> {
+        if let Some(bytes) = self.bytes_value {
+            Some(to_hex(bytes))
+        } else if let Some(boolean) = self.boolean_value {
+            Some(boolean.to_string())
+        } else if let Some(number) = self.number_value {
+            Some(number.to_string())
+        } else if let Some(string) = self.string_value {
+            Some(string.to_string())
+        } else if let Some(enum_value) = self.enum_value {
+            Some(enum_value.to_string())
+        } else if let (Some(latitude), Some(longitude)) =
+            (self.latitude_value, self.longitude_value)
+        {
+            Some(format!("{},{}", latitude, longitude))
+        } else {
+            None
+        }
+    }
+}
+
+fn collect_fields<'a>(
+    mfg_batch_id: &str,
+    mfg_batch_address: &str,
+    mfg_batch_namespace: &str,
+    owner: &str,
+    properties: impl Iterator<Item = PropertyFields<'a>>,
+    parents: impl Iterator<Item = &'a str>,
+) -> FieldValues {
+    let mut fields = FieldValues::new();
+    fields.insert("mfg_batch_address".into(), mfg_batch_address.into());
+    fields.insert("mfg_batch_namespace".into(), mfg_batch_namespace.into());
+    fields.insert("owner".into(), owner.into());
+
+    for property in properties {
+        if let Some(value) = property.value() {
+            fields.insert(property.field_name(mfg_batch_id), value);
+        }
+    }
+
+    let mut parents = parents.collect::<Vec<_>>();
+    if !parents.is_empty() {
+        parents.sort_unstable();
+        fields.insert("parent_batches".into(), parents.join(","));
+    }
+
+    fields
+}
+
+/// Adds the quantity columns that are set to `fields`
+fn insert_quantity_fields(
+    fields: &mut FieldValues,
+    quantity: Option<i64>,
+    uom: Option<&str>,
+    expected_quantity: Option<i64>,
+) {
+    if let Some(quantity) = quantity {
+        fields.insert("quantity".into(), quantity.to_string());
+    }
+    if let Some(uom) = uom {
+        fields.insert("uom".into(), uom.into());
+    }
+    if let Some(expected_quantity) = expected_quantity {
+        fields.insert("expected_quantity".into(), expected_quantity.to_string());
+    }
+}
+
+/// Adds the date columns that are set to `fields`
+fn insert_date_fields(
+    fields: &mut FieldValues,
+    production_date: Option<i64>,
+    expiration_date: Option<i64>,
+) {
+    if let Some(production_date) = production_date {
+        fields.insert("production_date".into(), production_date.to_string());
+    }
+    if let Some(expiration_date) = expiration_date {
+        fields.insert("expiration_date".into(), expiration_date.to_string());
+    }
+}
+
+/// Adds the manufacture location to `fields` if it is set
+fn insert_location_field(fields: &mut FieldValues, manufacture_location: Option<&str>) {
+    if let Some(manufacture_location) = manufacture_location {
+        fields.insert("manufacture_location".into(), manufacture_location.into());
+    }
+}
+
+/// Adds the archived flag to `fields` if the mfg_batch has been archived
+fn insert_archived_field(fields: &mut FieldValues, archived: bool) {
+    if archived {
+        fields.insert("archived".into(), archived.to_string());
+    }
+}
+
+fn fields_from_new(
+    mfg_batch: &NewMfgBatch,
+    property_values: &[NewMfgBatchPropertyValue],
+    parents: &[NewMfgBatchParent],
+) -> FieldValues {
+    let mut fields = collect_fields(
+        &mfg_batch.mfg_batch_id,
+        &mfg_batch.mfg_batch_address,
+        &mfg_batch.mfg_batch_namespace,
+        &mfg_batch.owner,
+        property_values.iter().map(|value| PropertyFields {
+            property_name: &value.property_name,
+            parent_property: value.parent_property.as_deref(),
+            bytes_value: value.bytes_value.as_deref(),
+            boolean_value: value.boolean_value,
+            number_value: value.number_value,
+            string_value: value.string_value.as_deref(),
+            enum_value: value.enum_value,
+            latitude_value: value.latitude_value,
+            longitude_value: value.longitude_value,
+            language: value.language.as_deref(),
+        }),
+        parents
+            .iter()
+            .map(|parent| parent.parent_mfg_batch_id.as_str()),
+    );
+    insert_quantity_fields(
+        &mut fields,
+        mfg_batch.quantity,
+        mfg_batch.uom.as_deref(),
+        mfg_batch.expected_quantity,
+    );
+    insert_date_fields(
+        &mut fields,
+        mfg_batch.production_date,
+        mfg_batch.expiration_date,
+    );
+    insert_location_field(&mut fields, mfg_batch.manufacture_location.as_deref());
+    insert_archived_field(&mut fields, mfg_batch.archived);
+    fields
+}
+
+fn fields_from_stored(
+    mfg_batch: &MfgBatch,
+    property_values: &[MfgBatchPropertyValue],
+    parents: &[MfgBatchParent],
+) -> FieldValues {
+    let mut fields = collect_fields(
+        &mfg_batch.mfg_batch_id,
+        &mfg_batch.mfg_batch_address,
+        &mfg_batch.mfg_batch_namespace,
+        &mfg_batch.owner,
+        property_values.iter().map(|value| PropertyFields {
+            property_name: &value.property_name,
+            parent_property: value.parent_property.as_deref(),
+            bytes_value: value.bytes_value.as_deref(),
+            boolean_value: value.boolean_value,
+            number_value: value.number_value,
+            string_value: value.string_value.as_deref(),
+            enum_value: value.enum_value,
+            latitude_value: value.latitude_value,
+            longitude_value: value.longitude_value,
+            language: value.language.as_deref(),
+        }),
+        parents
+            .iter()
+            .map(|parent| parent.parent_mfg_batch_id.as_str()),
+    );
+    insert_quantity_fields(
+        &mut fields,
+        mfg_batch.quantity,
+        mfg_batch.uom.as_deref(),
+        mfg_batch.expected_quantity,
+    );
+    insert_date_fields(
+        &mut fields,
+        mfg_batch.production_date,
+        mfg_batch.expiration_date,
+    );
+    insert_location_field(&mut fields, mfg_batch.manufacture_location.as_deref());
+    insert_archived_field(&mut fields, mfg_batch.archived);
+    fields
+}
+
+/// Compares two versions of a mfg_batch, returning one change per field that was added, removed
+/// or given a different value
+fn diff_fields(
+    mfg_batch_id: &str,
+    old: &FieldValues,
+    new: &FieldValues,
+    commit_num: i64,
+    service_id: Option<&str>,
+) -> Vec<NewMfgBatchChange> {
+    let change =
+        |field: &str, old_value: Option<&String>, new_value: Option<&String>| NewMfgBatchChange {
+            entity: MFG_BATCH_ENTITY.to_string(),
+            entity_id: mfg_batch_id.to_string(),
+            field: field.to_string(),
+            old_value: old_value.cloned(),
+            new_value: new_value.cloned(),
+            commit_num,
+            service_id: service_id.map(String::from),
+        };
+
+    let mut changes = new
+        .iter()
+        .filter(|(field, value)| old.get(*field) != Some(value))
+        .map(|(field, value)| change(field, old.get(field), Some(value)))
+        .collect::<Vec<_>>();
+
+    changes.extend(
+        old.iter()
+            .filter(|(field, _)| !new.contains_key(*field))
+            .map(|(field, value)| change(field, Some(value), None)),
+    );
+
+    changes
+}
+
+#[cfg(feature = "postgres")]
+pub(in crate::mfg_batch) mod pg {
+    use super::*;
+
+    /// Records the fields changed by writing a new version of a mfg_batch. This must run before
+    /// the current version's rows are ended.
+    pub fn record_changes(
+        conn: &PgConnection,
+        mfg_batch: &NewMfgBatch,
+        property_values: &[NewMfgBatchPropertyValue],
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        let mut query = mfg_batch::table.into_boxed().filter(
+            mfg_batch::mfg_batch_id
+                .eq(&mfg_batch.mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        if let Some(service_id) = &mfg_batch.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        let old = match query.first::<MfgBatch>(conn).optional()? {
+            Some(current) => current_fields(conn, &current)?,
+            None => FieldValues::new(),
+        };
+        let new = fields_from_new(mfg_batch, property_values, parents);
+
+        insert_changes(
+            conn,
+            diff_fields(
+                &mfg_batch.mfg_batch_id,
+                &old,
+                &new,
+                mfg_batch.start_commit_num,
+                mfg_batch.service_id.as_deref(),
+            ),
+        )
+    }
+
+    /// Records every field of the current mfg_batch at `address` as removed. This must run before
+    /// the current version's rows are ended.
+    pub fn record_deletion(
+        conn: &PgConnection,
+        address: &str,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let current = match mfg_batch::table
+            .filter(
+                mfg_batch::mfg_batch_address
+                    .eq(address)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            )
+            .first::<MfgBatch>(conn)
+            .optional()?
+        {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+        let old = current_fields(conn, &current)?;
+
+        insert_changes(
+            conn,
+            diff_fields(
+                &current.mfg_batch_id,
+                &old,
+                &FieldValues::new(),
+                current_commit_num,
+                current.service_id.as_deref(),
+            ),
+        )
+    }
+
+    fn current_fields(conn: &PgConnection, current: &MfgBatch) -> QueryResult<FieldValues> {
+        let mut property_query = mfg_batch_property_value::table.into_boxed().filter(
+            mfg_batch_property_value::mfg_batch_id
+                .eq(&current.mfg_batch_id)
+                .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        let mut parent_query = mfg_batch_parent::table.into_boxed().filter(
+            mfg_batch_parent::mfg_batch_id
+                .eq(&current.mfg_batch_id)
+                .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+
+        if let Some(service_id) = &current.service_id {
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        let property_values = property_query.load::<MfgBatchPropertyValue>(conn)?;
+        let parents = parent_query.load::<MfgBatchParent>(conn)?;
+
+        Ok(fields_from_stored(current, &property_values, &parents))
+    }
+
+    fn insert_changes(conn: &PgConnection, changes: Vec<NewMfgBatchChange>) -> QueryResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_change::table)
+            .values(changes)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(in crate::mfg_batch) mod sqlite {
+    use super::*;
+
+    /// Records the fields changed by writing a new version of a mfg_batch. This must run before
+    /// the current version's rows are ended.
+    pub fn record_changes(
+        conn: &SqliteConnection,
+        mfg_batch: &NewMfgBatch,
+        property_values: &[NewMfgBatchPropertyValue],
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        let mut query = mfg_batch::table.into_boxed().filter(
+            mfg_batch::mfg_batch_id
+                .eq(&mfg_batch.mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        if let Some(service_id) = &mfg_batch.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        let old = match query.first::<MfgBatch>(conn).optional()? {
+            Some(current) => current_fields(conn, &current)?,
+            None => FieldValues::new(),
+        };
+        let new = fields_from_new(mfg_batch, property_values, parents);
+
+        insert_changes(
+            conn,
+            diff_fields(
+                &mfg_batch.mfg_batch_id,
+                &old,
+                &new,
+                mfg_batch.start_commit_num,
+                mfg_batch.service_id.as_deref(),
+            ),
+        )
+    }
+
+    /// Records every field of the current mfg_batch at `address` as removed. This must run before
+    /// the current version's rows are ended.
+    pub fn record_deletion(
+        conn: &SqliteConnection,
+        address: &str,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let current = match mfg_batch::table
+            .filter(
+                mfg_batch::mfg_batch_address
+                    .eq(address)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            )
+            .first::<MfgBatch>(conn)
+            .optional()?
+        {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+        let old = current_fields(conn, &current)?;
+
+        insert_changes(
+            conn,
+            diff_fields(
+                &current.mfg_batch_id,
+                &old,
+                &FieldValues::new(),
+                current_commit_num,
+                current.service_id.as_deref(),
+            ),
+        )
+    }
+
+    fn current_fields(conn: &SqliteConnection, current: &MfgBatch) -> QueryResult<FieldValues> {
+        let mut property_query = mfg_batch_property_value::table.into_boxed().filter(
+            mfg_batch_property_value::mfg_batch_id
+                .eq(&current.mfg_batch_id)
+                .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        let mut parent_query = mfg_batch_parent::table.into_boxed().filter(
+            mfg_batch_parent::mfg_batch_id
+                .eq(&current.mfg_batch_id)
+                .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+
+        if let Some(service_id) = &current.service_id {
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        let property_values = property_query.load::<MfgBatchPropertyValue>(conn)?;
+        let parents = parent_query.load::<MfgBatchParent>(conn)?;
+
+        Ok(fields_from_stored(current, &property_values, &parents))
+    }
+
+    fn insert_changes(conn: &SqliteConnection, changes: Vec<NewMfgBatchChange>) -> QueryResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_change::table)
+            .values(changes)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_mfg_batch(owner: &str) -> NewMfgBatch {
+        NewMfgBatch {
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            mfg_batch_namespace: "GS1".to_string(),
+            owner: owner.to_string(),
+            start_commit_num: 2,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            last_updated: None,
+            quantity: None,
+            uom: None,
+            expected_quantity: None,
+            production_date: None,
+            expiration_date: None,
+            manufacture_location: None,
+            checksum: None,
+            archived: false,
+        }
+    }
+
+    fn new_property(
+        name: &str,
+        parent_property: Option<&str>,
+        value: Option<&str>,
+    ) -> NewMfgBatchPropertyValue {
+        NewMfgBatchPropertyValue {
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            property_name: name.to_string(),
+            parent_property: parent_property.map(String::from),
+            data_type: if value.is_some() { "STRING" } else { "STRUCT" }.to_string(),
+            bytes_value: None,
+            boolean_value: None,
+            number_value: None,
+            string_value: value.map(String::from),
+            enum_value: None,
+            latitude_value: None,
+            longitude_value: None,
+            start_commit_num: 2,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            language: None,
+        }
+    }
+
+    fn summarize(changes: &[NewMfgBatchChange]) -> Vec<(&str, Option<&str>, Option<&str>)> {
+        changes
+            .iter()
+            .map(|change| {
+                (
+                    change.field.as_str(),
+                    change.old_value.as_deref(),
+                    change.new_value.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    /// Verify that a new mfg_batch records every field as added, naming nested properties by
+    /// their struct and skipping the struct itself
+    #[test]
+    fn test_diff_new_mfg_batch() {
+        let mfg_batch = new_mfg_batch("org-1");
+        let properties = vec![
+            new_property("lot", None, Some("A1")),
+            new_property("origin", None, None),
+            new_property("country", Some("batch-1:origin"), Some("US")),
+        ];
+        let new = fields_from_new(&mfg_batch, &properties, &[]);
+
+        let changes = diff_fields("batch-1", &FieldValues::new(), &new, 2, None);
+
+        assert_eq!(
+            summarize(&changes),
+            vec![
+                ("mfg_batch_address", None, Some("11bb0e01batch1")),
+                ("mfg_batch_namespace", None, Some("GS1")),
+                ("owner", None, Some("org-1")),
+                ("properties.lot", None, Some("A1")),
+                ("properties.origin.country", None, Some("US")),
+            ]
+        );
+        assert!(changes
+            .iter()
+            .all(|change| change.entity == "mfg_batch" && change.commit_num == 2));
+    }
+
+    /// Verify that only changed, added and removed fields are recorded for an update
+    #[test]
+    fn test_diff_updated_mfg_batch() {
+        let old = fields_from_new(
+            &new_mfg_batch("org-1"),
+            &[
+                new_property("lot", None, Some("A1")),
+                new_property("grade", None, Some("B")),
+            ],
+            &[],
+        );
+        let new = fields_from_new(
+            &new_mfg_batch("org-2"),
+            &[
+                new_property("lot", None, Some("A1")),
+                new_property("expiry", None, Some("2022-01-01")),
+            ],
+            &[NewMfgBatchParent {
+                mfg_batch_id: "batch-1".to_string(),
+                parent_mfg_batch_id: "batch-0".to_string(),
+                start_commit_num: 2,
+                end_commit_num: MAX_COMMIT_NUM,
+                service_id: None,
+            }],
+        );
+
+        let changes = diff_fields("batch-1", &old, &new, 2, None);
+
+        assert_eq!(
+            summarize(&changes),
+            vec![
+                ("owner", Some("org-1"), Some("org-2")),
+                ("parent_batches", None, Some("batch-0")),
+                ("properties.expiry", None, Some("2022-01-01")),
+                ("properties.grade", Some("B"), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Query wrappers that ask the database how it would run a query instead of running it.
+
+use diesel::prelude::*;
+use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
+
+#[cfg(feature = "postgres")]
+pub(in crate::mfg_batch) mod pg {
+    use super::*;
+
+    use diesel::pg::Pg;
+    use diesel::sql_types::Text;
+
+    /// Runs the wrapped query under `EXPLAIN`, returning one row per line of the plan
+    pub struct Explain<T>(pub T);
+
+    impl<T> QueryId for Explain<T> {
+        type QueryId = ();
+
+        const HAS_STATIC_QUERY_ID: bool = false;
+    }
+
+    impl<T> Query for Explain<T> {
+        type SqlType = Text;
+    }
+
+    impl<T: QueryFragment<Pg>> QueryFragment<Pg> for Explain<T> {
+        fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+            out.push_sql("EXPLAIN ");
+            self.0.walk_ast(out.reborrow())
+        }
+    }
+
+    impl<T> RunQueryDsl<PgConnection> for Explain<T> {}
+}
+
+#[cfg(feature = "sqlite")]
+pub(in crate::mfg_batch) mod sqlite {
+    use super::*;
+
+    use diesel::sql_types::{Integer, Text};
+    use diesel::sqlite::Sqlite;
+
+    /// Runs the wrapped query under `EXPLAIN QUERY PLAN`, returning one row per step of the plan
+    /// as `(id, parent, unused, detail)`
+    pub struct Explain<T>(pub T);
+
+    impl<T> QueryId for Explain<T> {
+        type QueryId = ();
+
+        const HAS_STATIC_QUERY_ID: bool = false;
+    }
+
+    impl<T> Query for Explain<T> {
+        type SqlType = (Integer, Integer, Integer, Text);
+    }
+
+    impl<T: QueryFragment<Sqlite>> QueryFragment<Sqlite> for Explain<T> {
+        fn walk_ast(&self, mut out: AstPass<Sqlite>) -> QueryResult<()> {
+            out.push_sql("EXPLAIN QUERY PLAN ");
+            self.0.walk_ast(out.reborrow())
+        }
+    }
+
+    impl<T> RunQueryDsl<SqliteConnection> for Explain<T> {}
+}
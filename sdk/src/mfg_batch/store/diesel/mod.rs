@@ -12,43 +12,372 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "mfg-batch-audit-log")]
+pub(in crate::mfg_batch) mod audit;
+#[cfg(feature = "mfg-batch-change-capture")]
+pub(in crate::mfg_batch) mod change_capture;
+#[cfg(feature = "mfg-batch-explain")]
+pub(in crate::mfg_batch) mod explain;
 pub(in crate::mfg_batch) mod models;
 mod operations;
+#[cfg(any(feature = "mfg-batch-audit-log", feature = "mfg-batch-checksums"))]
+pub(in crate::mfg_batch) mod record_hash;
 pub(in crate) mod schema;
+#[cfg(all(feature = "mfg-batch-text-search", feature = "postgres"))]
+pub(in crate::mfg_batch) mod text_search;
 
+#[cfg(feature = "sqlite")]
+use crate::error::InternalError;
+#[cfg(feature = "log-masking")]
+use crate::log_masking::LogMask;
 use crate::error::ResourceTemporarilyUnavailableError;
+#[cfg(feature = "sqlite")]
+use crate::migrations::run_sqlite_migrations;
 
+#[cfg(feature = "mfg-batch-partitioning")]
+use operations::create_mfg_batch_archive_partition::CreateMfgBatchArchivePartitionOperation;
+#[cfg(feature = "mfg-batch-explain")]
+use operations::explain_list_mfg_batches::ExplainListMfgBatchesOperation;
+#[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+use operations::explain_search_mfg_batches_text::ExplainSearchMfgBatchesTextOperation;
+#[cfg(feature = "mfg-batch-pruning")]
+use operations::prune_mfg_batch_history::PruneMfgBatchHistoryOperation;
+#[cfg(feature = "mfg-batch-row-counts")]
+use operations::{
+    count_mfg_batch_version_rows::CountMfgBatchVersionRowsOperation,
+    get_mfg_batch_properties::GetMfgBatchPropertiesOperation,
+};
+#[cfg(feature = "mfg-batch-audit-log")]
+use operations::verify_mfg_batch_audit_log::VerifyMfgBatchAuditLogOperation;
+#[cfg(feature = "mfg-batch-anchors")]
+use operations::{
+    add_mfg_batch_anchor::AddMfgBatchAnchorOperation,
+    list_mfg_batch_anchors::ListMfgBatchAnchorsOperation,
+    list_mfg_batch_record_hashes::ListMfgBatchRecordHashesOperation,
+};
+#[cfg(feature = "mfg-batch-checksums")]
+use operations::verify_mfg_batch_checksums::VerifyMfgBatchChecksumsOperation;
+use operations::{
+    add_mfg_batch::AddMfgBatchOperation, add_mfg_batches::AddMfgBatchesOperation,
+    count_mfg_batches::CountMfgBatchesOperation, delete_mfg_batch::DeleteMfgBatchOperation,
+    get_mfg_batch::GetMfgBatchOperation, get_mfg_batch_ancestry::GetMfgBatchAncestryOperation,
+    get_mfg_batch_at_commit::GetMfgBatchAtCommitOperation,
+    get_mfg_batch_descendants::GetMfgBatchDescendantsOperation,
+    get_mfg_batches::GetMfgBatchesOperation,
+    get_property_value_history::GetPropertyValueHistoryOperation,
+    list_mfg_batch_history::ListMfgBatchHistoryOperation,
+    list_mfg_batch_owners::ListMfgBatchOwnersOperation, list_mfg_batches::ListMfgBatchsOperation,
+    list_mfg_batches_after::ListMfgBatchesAfterOperation,
+    list_mfg_batches_updated_since::ListMfgBatchesUpdatedSinceOperation,
+    mfg_batch_exists::MfgBatchExistsOperation,
+    search_mfg_batches_by_property::SearchMfgBatchesByPropertyOperation,
+    update_mfg_batch::UpdateMfgBatchOperation, upsert_mfg_batch::UpsertMfgBatchOperation,
+    MfgBatchStoreOperations,
+};
+#[cfg(feature = "mfg-batch-annotations")]
+use operations::{
+    add_mfg_batch_annotation::AddMfgBatchAnnotationOperation,
+    list_mfg_batch_annotations::ListMfgBatchAnnotationsOperation,
+};
+#[cfg(feature = "mfg-batch-recalls")]
+use operations::{
+    add_mfg_batch_recalls::AddMfgBatchRecallsOperation,
+    close_mfg_batch_recall::CloseMfgBatchRecallOperation,
+    list_mfg_batch_recalls::ListMfgBatchRecallsOperation,
+};
+#[cfg(feature = "mfg-batch-text-search")]
+use operations::search_mfg_batches_text::SearchMfgBatchesTextOperation;
+#[cfg(feature = "mfg-batch-test-results")]
+use operations::{
+    add_mfg_batch_test_result::AddMfgBatchTestResultOperation,
+    list_mfg_batch_test_results::ListMfgBatchTestResultsOperation,
+};
+#[cfg(feature = "mfg-batch-allocations")]
+use operations::{
+    get_mfg_batch_availability::GetMfgBatchAvailabilityOperation,
+    list_mfg_batch_allocations::ListMfgBatchAllocationsOperation,
+    put_mfg_batch_allocation::PutMfgBatchAllocationOperation,
+};
+#[cfg(feature = "mfg-batch-merge")]
 use operations::{
-    add_mfg_batch::AddMfgBatchOperation, delete_mfg_batch::DeleteMfgBatchOperation,
-    get_mfg_batch::GetMfgBatchOperation, list_mfg_batches::ListMfgBatchsOperation,
-    update_mfg_batch::UpdateMfgBatchOperation, MfgBatchStoreOperations,
+    list_mfg_batch_aliases::ListMfgBatchAliasesOperation,
+    merge_mfg_batches::MergeMfgBatchesOperation,
+};
+#[cfg(feature = "mfg-batch-duplicates")]
+use operations::{
+    list_mfg_batch_duplicates::ListMfgBatchDuplicatesOperation,
+    replace_mfg_batch_duplicates::ReplaceMfgBatchDuplicatesOperation,
+};
+#[cfg(feature = "mfg-batch-quality-scores")]
+use operations::{
+    list_mfg_batch_quality_scores::ListMfgBatchQualityScoresOperation,
+    put_mfg_batch_quality_score::PutMfgBatchQualityScoreOperation,
+};
+#[cfg(feature = "mfg-batch-visibility")]
+use operations::{
+    list_mfg_batch_shares::ListMfgBatchSharesOperation,
+    mfg_batch_visible::MfgBatchVisibleOperation, share_mfg_batch::ShareMfgBatchOperation,
+    unshare_mfg_batch::UnshareMfgBatchOperation,
 };
 
+#[cfg(feature = "mfg-batch-quality-scores")]
+use std::sync::Arc;
+#[cfg(feature = "mfg-batch-quality-scores")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use diesel::connection::AnsiTransactionManager;
 use diesel::r2d2::{ConnectionManager, Pool};
 
-use super::{MfgBatch, MfgBatchList, MfgBatchStore, MfgBatchStoreError};
+#[cfg(feature = "mfg-batch-quality-scores")]
+use crate::mfg_batch::quality::{rescore_mfg_batch, QualityRubric};
+
+#[cfg(feature = "mfg-batch-audit-log")]
+use super::AuditLogDiscrepancy;
+#[cfg(feature = "mfg-batch-checksums")]
+use super::ChecksumDiscrepancy;
+#[cfg(feature = "mfg-batch-merge")]
+use super::MfgBatchAlias;
+#[cfg(feature = "mfg-batch-anchors")]
+use super::MfgBatchAnchor;
+#[cfg(feature = "mfg-batch-annotations")]
+use super::MfgBatchAnnotation;
+#[cfg(feature = "mfg-batch-duplicates")]
+use super::MfgBatchDuplicate;
+#[cfg(feature = "mfg-batch-test-results")]
+use super::MfgBatchTestResult;
+#[cfg(feature = "mfg-batch-explain")]
+use super::QueryPlan;
+#[cfg(feature = "mfg-batch-visibility")]
+use super::Visibility;
+#[cfg(feature = "mfg-batch-row-counts")]
+use super::MfgBatchVersionRows;
+#[cfg(feature = "mfg-batch-pruning")]
+use super::PrunedMfgBatchHistory;
+use super::{
+    ListMfgBatchFilters, MfgBatch, MfgBatchIter, MfgBatchList, MfgBatchOwner, MfgBatchStore,
+    MfgBatchStoreError,
+    PropertySearchValue, PropertyValue, UpsertMfgBatchOutcome,
+};
+#[cfg(feature = "mfg-batch-keyset-paging")]
+use super::MfgBatchCursor;
+#[cfg(feature = "mfg-batch-quality-scores")]
+use super::{ListMfgBatchQualityScoreFilters, MfgBatchQualityScore};
+#[cfg(feature = "mfg-batch-allocations")]
+use super::{MfgBatchAllocation, MfgBatchAvailability};
+#[cfg(feature = "mfg-batch-recalls")]
+use super::{ListMfgBatchRecallFilters, MfgBatchRecall};
+
+/// The number of mfg_batches written per transaction by `add_mfg_batches`, unless the store is
+/// given another
+pub const DEFAULT_BULK_INSERT_CHUNK_SIZE: usize = 100;
 
-#[derive(Clone)]
 pub struct DieselMfgBatchStore<C: diesel::Connection + 'static> {
     connection_pool: Pool<ConnectionManager<C>>,
+    bulk_insert_chunk_size: usize,
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    quality_rubric: Option<Arc<QualityRubric>>,
+    #[cfg(feature = "log-masking")]
+    log_mask: LogMask,
+}
+
+// Implemented by hand, as deriving would require the connection type itself to be `Clone`;
+// clones share the same connection pool
+impl<C: diesel::Connection> Clone for DieselMfgBatchStore<C> {
+    fn clone(&self) -> Self {
+        DieselMfgBatchStore {
+            connection_pool: self.connection_pool.clone(),
+            bulk_insert_chunk_size: self.bulk_insert_chunk_size,
+            #[cfg(feature = "mfg-batch-quality-scores")]
+            quality_rubric: self.quality_rubric.clone(),
+            #[cfg(feature = "log-masking")]
+            log_mask: self.log_mask.clone(),
+        }
+    }
 }
 
 impl<C: diesel::Connection> DieselMfgBatchStore<C> {
     pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
-        DieselMfgBatchStore { connection_pool }
+        DieselMfgBatchStore {
+            connection_pool,
+            bulk_insert_chunk_size: DEFAULT_BULK_INSERT_CHUNK_SIZE,
+            #[cfg(feature = "mfg-batch-quality-scores")]
+            quality_rubric: None,
+            #[cfg(feature = "log-masking")]
+            log_mask: LogMask::default(),
+        }
+    }
+
+    /// Sets the number of mfg_batches written per transaction by `add_mfg_batches`
+    pub fn with_bulk_insert_chunk_size(mut self, bulk_insert_chunk_size: usize) -> Self {
+        self.bulk_insert_chunk_size = bulk_insert_chunk_size;
+        self
+    }
+
+    /// Sets the rubric mfg_batches are scored against whenever they, or their test results,
+    /// are written through the store
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    pub fn with_quality_rubric(mut self, quality_rubric: Arc<QualityRubric>) -> Self {
+        self.quality_rubric = Some(quality_rubric);
+        self
+    }
+
+    /// Sets the properties whose values are masked when the store logs the queries it runs
+    #[cfg(feature = "log-masking")]
+    pub fn with_log_mask(mut self, log_mask: LogMask) -> Self {
+        self.log_mask = log_mask;
+        self
+    }
+
+    /// Logs a search for the mfg_batches with a property value, masking the value if the
+    /// property is denied
+    #[cfg(feature = "log-masking")]
+    fn log_property_search(&self, property_name: &str, value: &PropertySearchValue) {
+        let value = match value {
+            PropertySearchValue::String(value) => value.clone(),
+            PropertySearchValue::Number(value) => value.to_string(),
+            PropertySearchValue::Boolean(value) => value.to_string(),
+        };
+        debug!(
+            "Searching mfg_batches where {} is {}",
+            property_name,
+            self.log_mask.mask_value(property_name, &value)
+        );
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl DieselMfgBatchStore<diesel::sqlite::SqliteConnection> {
+    /// Creates a store backed by a new in-memory SQLite database, with the Grid migrations
+    /// already run, for tests and demos that have no database of their own
+    pub fn new_in_memory() -> Result<Self, MfgBatchStoreError> {
+        // Each connection to ":memory:" opens a separate database, so the pool is limited to
+        // the one connection the migrations are run on
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<diesel::sqlite::SqliteConnection>::new(
+                ":memory:",
+            ))
+            .map_err(|err| {
+                MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            })?;
+
+        let conn = pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?;
+        run_sqlite_migrations(&conn).map_err(|err| {
+            MfgBatchStoreError::InternalError(InternalError::from_source_with_prefix(
+                Box::new(err),
+                "Unable to run migrations".to_string(),
+            ))
+        })?;
+        drop(conn);
+
+        Ok(Self::new(pool))
+    }
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+impl<C: diesel::Connection> DieselMfgBatchStore<C>
+where
+    Self: MfgBatchStore,
+{
+    /// Scores the current version of each mfg_batch, if the store has a rubric. The changes
+    /// have already been written, so a mfg_batch that cannot be scored is logged rather than
+    /// failing the write.
+    fn rescore<'a, I>(&self, mfg_batches: I)
+    where
+        I: IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    {
+        let rubric = match &self.quality_rubric {
+            Some(rubric) => rubric,
+            None => return,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs() as i64)
+            .unwrap_or_default();
+
+        let mut scored = Vec::new();
+        for (mfg_batch_id, service_id) in mfg_batches {
+            if scored.contains(&(mfg_batch_id, service_id)) {
+                continue;
+            }
+            if let Err(err) = rescore_mfg_batch(self, rubric, mfg_batch_id, service_id, now) {
+                warn!("Unable to score mfg_batch {}: {}", mfg_batch_id, err);
+            }
+            scored.push((mfg_batch_id, service_id));
+        }
     }
 }
 
 #[cfg(feature = "postgres")]
 impl MfgBatchStore for DieselMfgBatchStore<diesel::pg::PgConnection> {
     fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone());
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch(mfg_batch)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+
+        Ok(())
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = mfg_batches
+            .iter()
+            .map(|mfg_batch| (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone()))
+            .collect::<Vec<_>>();
+
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .add_mfg_batch(mfg_batch)
+        .add_mfg_batches(mfg_batches, self.bulk_insert_chunk_size)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(
+            scored
+                .iter()
+                .map(|(mfg_batch_id, service_id)| (mfg_batch_id.as_str(), service_id.as_deref())),
+        );
+
+        Ok(())
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone());
+
+        let outcome =
+            MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            })?)
+            .upsert_mfg_batch(mfg_batch)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        if !matches!(outcome, UpsertMfgBatchOutcome::Conflict { .. }) {
+            self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+        }
+
+        Ok(outcome)
     }
 
     fn get_mfg_batch(
@@ -64,9 +393,23 @@ impl MfgBatchStore for DieselMfgBatchStore<diesel::pg::PgConnection> {
         .get_mfg_batch(mfg_batch_id, service_id)
     }
 
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
     fn list_mfg_batches(
         &self,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> Result<MfgBatchList, MfgBatchStoreError> {
@@ -75,203 +418,2456 @@ impl MfgBatchStore for DieselMfgBatchStore<diesel::pg::PgConnection> {
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .list_mfg_batches(service_id, offset, limit)
+        .list_mfg_batches(service_id, filters, offset, limit)
     }
 
-    fn update_mfg_batch(
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        MfgBatchIter::new(page_size, move |after| {
+            MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            })?)
+            .list_mfg_batches_after(service_id, filters, after, page_size)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
         &self,
-        mfg_batch_id: &str,
         service_id: Option<&str>,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .update_mfg_batch(mfg_batch_id, service_id, current_commit_num)
+        .list_mfg_batches_after_cursor(service_id, filters, cursor, limit)
     }
 
-    fn delete_mfg_batch(
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
         &self,
-        address: &str,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .delete_mfg_batch(address, current_commit_num)
+        .explain_list_mfg_batches(service_id, filters, offset, limit)
     }
-}
 
-#[cfg(feature = "sqlite")]
-impl MfgBatchStore for DieselMfgBatchStore<diesel::sqlite::SqliteConnection> {
-    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .add_mfg_batch(mfg_batch)
+        .list_mfg_batch_owners(service_id, offset, limit)
     }
 
-    fn get_mfg_batch(
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
         &self,
         mfg_batch_id: &str,
         service_id: Option<&str>,
-    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+    ) -> Result<bool, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .get_mfg_batch(mfg_batch_id, service_id)
+        .mfg_batch_exists(mfg_batch_id, service_id)
     }
 
-    fn list_mfg_batches(
+    fn search_mfg_batches_by_property(
         &self,
+        property_name: &str,
+        value: &PropertySearchValue,
         service_id: Option<&str>,
-        offset: i64,
-        limit: i64,
-    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        #[cfg(feature = "log-masking")]
+        self.log_property_search(property_name, value);
+
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .list_mfg_batches(service_id, offset, limit)
+        .search_mfg_batches_by_property(property_name, value, service_id)
     }
 
-    fn update_mfg_batch(
+    fn get_mfg_batch_ancestry(
         &self,
         mfg_batch_id: &str,
         service_id: Option<&str>,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .update_mfg_batch(mfg_batch_id, service_id, current_commit_num)
+        .get_mfg_batch_ancestry(mfg_batch_id, service_id)
     }
 
-    fn delete_mfg_batch(
+    fn get_mfg_batch_descendants(
         &self,
-        address: &str,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
         MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .delete_mfg_batch(address, current_commit_num)
+        .get_mfg_batch_descendants(mfg_batch_id, service_id)
     }
-}
-
-pub struct DieselConnectionMfgBatchStore<'a, C>
-where
-    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
-    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
-{
-    connection: &'a C,
-}
 
-impl<'a, C> DieselConnectionMfgBatchStore<'a, C>
-where
-    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
-    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
-{
-    pub fn new(connection: &'a C) -> Self {
-        DieselConnectionMfgBatchStore { connection }
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)
     }
-}
 
-#[cfg(feature = "postgres")]
-impl<'a> MfgBatchStore for DieselConnectionMfgBatchStore<'a, diesel::pg::PgConnection> {
-    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).add_mfg_batch(mfg_batch)
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_history(mfg_batch_id, service_id)
     }
 
-    fn get_mfg_batch(
+    fn get_property_value_history(
         &self,
         mfg_batch_id: &str,
+        property_name: &str,
         service_id: Option<&str>,
-    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).get_mfg_batch(mfg_batch_id, service_id)
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_property_value_history(mfg_batch_id, property_name, service_id)
     }
 
-    fn list_mfg_batches(
+    fn list_mfg_batches_updated_since(
         &self,
+        since: i64,
         service_id: Option<&str>,
-        offset: i64,
-        limit: i64,
-    ) -> Result<MfgBatchList, MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).list_mfg_batches(service_id, offset, limit)
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batches_updated_since(since, service_id)
     }
 
-    fn update_mfg_batch(
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
         &self,
         mfg_batch_id: &str,
         service_id: Option<&str>,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).update_mfg_batch(
-            mfg_batch_id,
-            service_id,
-            current_commit_num,
-        )
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .count_mfg_batch_version_rows(mfg_batch_id, service_id)
     }
 
-    fn delete_mfg_batch(
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
         &self,
-        address: &str,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).delete_mfg_batch(address, current_commit_num)
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_properties(mfg_batch_id, property_names, service_id)
     }
-}
 
-#[cfg(feature = "sqlite")]
-impl<'a> MfgBatchStore for DieselConnectionMfgBatchStore<'a, diesel::sqlite::SqliteConnection> {
-    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).add_mfg_batch(mfg_batch)
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .mfg_batch_visible(mfg_batch_id, visibility, service_id)
     }
 
-    fn get_mfg_batch(
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
         &self,
         mfg_batch_id: &str,
+        org_id: &str,
         service_id: Option<&str>,
-    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).get_mfg_batch(mfg_batch_id, service_id)
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .share_mfg_batch(mfg_batch_id, org_id, service_id)
     }
 
-    fn list_mfg_batches(
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
         &self,
+        mfg_batch_id: &str,
+        org_id: &str,
         service_id: Option<&str>,
-        offset: i64,
-        limit: i64,
-    ) -> Result<MfgBatchList, MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).list_mfg_batches(service_id, offset, limit)
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .unshare_mfg_batch(mfg_batch_id, org_id, service_id)
     }
 
-    fn update_mfg_batch(
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
         &self,
         mfg_batch_id: &str,
         service_id: Option<&str>,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError> {
-        MfgBatchStoreOperations::new(self.connection).update_mfg_batch(
-            mfg_batch_id,
-            service_id,
-            current_commit_num,
-        )
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_shares(mfg_batch_id, service_id)
     }
 
-    fn delete_mfg_batch(
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (
+            test_result.mfg_batch_id.clone(),
+            test_result.service_id.clone(),
+        );
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_test_result(test_result)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .update_mfg_batch(mfg_batch_id, service_id, current_commit_num)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(mfg_batch_id, service_id)]);
+
+        Ok(())
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .delete_mfg_batch(address, current_commit_num)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl MfgBatchStore for DieselMfgBatchStore<diesel::sqlite::SqliteConnection> {
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone());
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch(mfg_batch)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+
+        Ok(())
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = mfg_batches
+            .iter()
+            .map(|mfg_batch| (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone()))
+            .collect::<Vec<_>>();
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batches(mfg_batches, self.bulk_insert_chunk_size)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(
+            scored
+                .iter()
+                .map(|(mfg_batch_id, service_id)| (mfg_batch_id.as_str(), service_id.as_deref())),
+        );
+
+        Ok(())
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (mfg_batch.mfg_batch_id.clone(), mfg_batch.service_id.clone());
+
+        let outcome =
+            MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            })?)
+            .upsert_mfg_batch(mfg_batch)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        if !matches!(outcome, UpsertMfgBatchOutcome::Conflict { .. }) {
+            self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+        }
+
+        Ok(outcome)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        MfgBatchIter::new(page_size, move |after| {
+            MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            })?)
+            .list_mfg_batches_after(service_id, filters, after, page_size)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batches_after_cursor(service_id, filters, cursor, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_owners(service_id, offset, limit)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .mfg_batch_exists(mfg_batch_id, service_id)
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        #[cfg(feature = "log-masking")]
+        self.log_property_search(property_name, value);
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .search_mfg_batches_by_property(property_name, value, service_id)
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_ancestry(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_descendants(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_history(mfg_batch_id, service_id)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_property_value_history(mfg_batch_id, property_name, service_id)
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batches_updated_since(since, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .count_mfg_batch_version_rows(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_properties(mfg_batch_id, property_names, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .mfg_batch_visible(mfg_batch_id, visibility, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .share_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .unshare_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_shares(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        let scored = (
+            test_result.mfg_batch_id.clone(),
+            test_result.service_id.clone(),
+        );
+
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_test_result(test_result)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(scored.0.as_str(), scored.1.as_deref())]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .update_mfg_batch(mfg_batch_id, service_id, current_commit_num)?;
+
+        #[cfg(feature = "mfg-batch-quality-scores")]
+        self.rescore(vec![(mfg_batch_id, service_id)]);
+
+        Ok(())
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .delete_mfg_batch(address, current_commit_num)
+    }
+}
+
+pub struct DieselConnectionMfgBatchStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+    bulk_insert_chunk_size: usize,
+}
+
+impl<'a, C> DieselConnectionMfgBatchStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionMfgBatchStore {
+            connection,
+            bulk_insert_chunk_size: DEFAULT_BULK_INSERT_CHUNK_SIZE,
+        }
+    }
+
+    /// Sets the number of mfg_batches written per transaction by `add_mfg_batches`
+    pub fn with_bulk_insert_chunk_size(mut self, bulk_insert_chunk_size: usize) -> Self {
+        self.bulk_insert_chunk_size = bulk_insert_chunk_size;
+        self
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> MfgBatchStore for DieselConnectionMfgBatchStore<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch(mfg_batch)
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .add_mfg_batches(mfg_batches, self.bulk_insert_chunk_size)
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).upsert_mfg_batch(mfg_batch)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn iter_mfg_batches<'b>(
+        &'b self,
+        service_id: Option<&'b str>,
+        filters: &'b ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'b> {
+        MfgBatchIter::new(page_size, move |after| {
+            MfgBatchStoreOperations::new(self.connection)
+                .list_mfg_batches_after(service_id, filters, after, page_size)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches_after_cursor(service_id, filters, cursor, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_owners(service_id, offset, limit)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).mfg_batch_exists(mfg_batch_id, service_id)
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).search_mfg_batches_by_property(
+            property_name,
+            value,
+            service_id,
+        )
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_ancestry(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_descendants(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch_at_commit(
+            mfg_batch_id,
+            commit_num,
+            service_id,
+        )
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_history(mfg_batch_id, service_id)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_property_value_history(
+            mfg_batch_id,
+            property_name,
+            service_id,
+        )
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches_updated_since(since, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .count_mfg_batch_version_rows(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch_properties(
+            mfg_batch_id,
+            property_names,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).mfg_batch_visible(
+            mfg_batch_id,
+            visibility,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).share_mfg_batch(
+            mfg_batch_id,
+            org_id,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).unshare_mfg_batch(
+            mfg_batch_id,
+            org_id,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_shares(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_test_result(test_result)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).update_mfg_batch(
+            mfg_batch_id,
+            service_id,
+            current_commit_num,
+        )
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).delete_mfg_batch(address, current_commit_num)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> MfgBatchStore for DieselConnectionMfgBatchStore<'a, diesel::sqlite::SqliteConnection> {
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch(mfg_batch)
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .add_mfg_batches(mfg_batches, self.bulk_insert_chunk_size)
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).upsert_mfg_batch(mfg_batch)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn iter_mfg_batches<'b>(
+        &'b self,
+        service_id: Option<&'b str>,
+        filters: &'b ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'b> {
+        MfgBatchIter::new(page_size, move |after| {
+            MfgBatchStoreOperations::new(self.connection)
+                .list_mfg_batches_after(service_id, filters, after, page_size)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches_after_cursor(service_id, filters, cursor, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_owners(service_id, offset, limit)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).mfg_batch_exists(mfg_batch_id, service_id)
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).search_mfg_batches_by_property(
+            property_name,
+            value,
+            service_id,
+        )
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_ancestry(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_descendants(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch_at_commit(
+            mfg_batch_id,
+            commit_num,
+            service_id,
+        )
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_history(mfg_batch_id, service_id)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_property_value_history(
+            mfg_batch_id,
+            property_name,
+            service_id,
+        )
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batches_updated_since(since, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .count_mfg_batch_version_rows(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).get_mfg_batch_properties(
+            mfg_batch_id,
+            property_names,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).mfg_batch_visible(
+            mfg_batch_id,
+            visibility,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).share_mfg_batch(
+            mfg_batch_id,
+            org_id,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).unshare_mfg_batch(
+            mfg_batch_id,
+            org_id,
+            service_id,
+        )
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_shares(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_test_result(test_result)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection)
+            .explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        MfgBatchStoreOperations::new(self.connection).update_mfg_batch(
+            mfg_batch_id,
+            service_id,
+            current_commit_num,
+        )
+    }
+
+    fn delete_mfg_batch(
         &self,
         address: &str,
         current_commit_num: i64,
@@ -279,3 +2875,844 @@ impl<'a> MfgBatchStore for DieselConnectionMfgBatchStore<'a, diesel::sqlite::Sql
         MfgBatchStoreOperations::new(self.connection).delete_mfg_batch(address, current_commit_num)
     }
 }
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::{
+        store::{MfgBatchAttachment, MfgBatchBuilder, PropertyValueBuilder},
+        MAX_COMMIT_NUM,
+    };
+
+    const MFG_BATCH_ID: &str = "688955434684";
+
+    /// Verify that an in-memory store is ready to use: the migrations have created the mfg_batch
+    /// and mfg_batch_property_value tables, and every write goes to the same database
+    #[test]
+    fn test_new_in_memory() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        let property = PropertyValueBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_property_name("description".into())
+            .with_data_type("String".into())
+            .with_string_value(Some("Flour".into()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build property value");
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .with_owner("org".into())
+            .with_properties(vec![property])
+            .with_quantity(Some(10))
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build mfg_batch");
+        store
+            .add_mfg_batch(mfg_batch)
+            .expect("Failed to add mfg_batch");
+
+        let mfg_batch = store
+            .get_mfg_batch(MFG_BATCH_ID, None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.owner(), "org");
+        assert_eq!(mfg_batch.quantity(), Some(10));
+        assert_eq!(mfg_batch.properties().len(), 1);
+        assert_eq!(mfg_batch.properties()[0].string_value(), Some("Flour"));
+    }
+
+    /// Verify that several mfg_batches are fetched in the order requested, skipping unknown and
+    /// repeated ids, with struct values nested under the value they belong to
+    #[test]
+    fn test_get_mfg_batches() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        let value = |mfg_batch_id: &str, name: &str| {
+            PropertyValueBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("{}-addr", mfg_batch_id))
+                .with_property_name(name.into())
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+        };
+        for (mfg_batch_id, properties) in [
+            (
+                "batch1",
+                vec![value("batch1", "dimensions")
+                    .with_data_type("Struct".into())
+                    .with_struct_values(vec![value("batch1", "width")
+                        .with_data_type("Number".into())
+                        .with_number_value(Some(4))
+                        .build()
+                        .expect("Failed to build property value")])
+                    .build()
+                    .expect("Failed to build property value")],
+            ),
+            (
+                "batch2",
+                vec![value("batch2", "description")
+                    .with_data_type("String".into())
+                    .with_string_value(Some("Flour".into()))
+                    .build()
+                    .expect("Failed to build property value")],
+            ),
+        ] {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("{}-addr", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(properties)
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        }
+
+        let mfg_batches = store
+            .get_mfg_batches(&["batch2", "unknown", "batch1", "batch2"], None)
+            .expect("Failed to get mfg_batches");
+        assert_eq!(
+            mfg_batches
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id())
+                .collect::<Vec<_>>(),
+            vec!["batch2", "batch1"]
+        );
+        assert_eq!(mfg_batches[0].properties()[0].string_value(), Some("Flour"));
+
+        let dimensions = mfg_batches[1].properties();
+        assert_eq!(dimensions.len(), 1);
+        let members = dimensions[0].struct_values();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].property_name(), "width");
+        assert_eq!(members[0].number_value(), Some(4));
+
+        assert!(store
+            .get_mfg_batches(&[], None)
+            .expect("Failed to get mfg_batches")
+            .is_empty());
+    }
+
+    /// Verify that struct values, including those nested in other struct values, are loaded
+    /// under the value they belong to when getting and listing mfg_batches
+    #[test]
+    fn test_nested_struct_values() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        let value = |name: &str| {
+            PropertyValueBuilder::default()
+                .with_mfg_batch_id(MFG_BATCH_ID.into())
+                .with_mfg_batch_address("11bb0e01".into())
+                .with_property_name(name.into())
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+        };
+        let number = |name: &str, number: i64| {
+            value(name)
+                .with_data_type("Number".into())
+                .with_number_value(Some(number))
+                .build()
+                .expect("Failed to build property value")
+        };
+        let packaging = value("packaging")
+            .with_data_type("Struct".into())
+            .with_struct_values(vec![
+                number("units", 12),
+                value("dimensions")
+                    .with_data_type("Struct".into())
+                    .with_struct_values(vec![number("width", 4), number("height", 6)])
+                    .build()
+                    .expect("Failed to build property value"),
+            ])
+            .build()
+            .expect("Failed to build property value");
+        let description = value("description")
+            .with_data_type("String".into())
+            .with_string_value(Some("Flour".into()))
+            .build()
+            .expect("Failed to build property value");
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .with_owner("org".into())
+            .with_properties(vec![description, packaging])
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build mfg_batch");
+        store
+            .add_mfg_batch(mfg_batch)
+            .expect("Failed to add mfg_batch");
+
+        let listed = store
+            .list_mfg_batches(None, &ListMfgBatchFilters::default(), 0, 10)
+            .expect("Failed to list mfg_batches")
+            .data();
+        assert_eq!(listed.len(), 1);
+
+        let fetched = store
+            .get_mfg_batch(MFG_BATCH_ID, None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+
+        for mfg_batch in [&fetched, &listed[0]] {
+            let properties = mfg_batch.properties();
+            assert_eq!(properties.len(), 2);
+            assert_eq!(properties[0].property_name(), "description");
+            assert!(properties[0].struct_values().is_empty());
+
+            let members = properties[1].struct_values();
+            assert_eq!(
+                members
+                    .iter()
+                    .map(|member| member.property_name())
+                    .collect::<Vec<_>>(),
+                vec!["units", "dimensions"]
+            );
+            assert_eq!(members[0].number_value(), Some(12));
+
+            let dimensions = members[1].struct_values();
+            assert_eq!(
+                dimensions
+                    .iter()
+                    .map(|member| (member.property_name(), member.number_value()))
+                    .collect::<Vec<_>>(),
+                vec![("width", Some(4)), ("height", Some(6))]
+            );
+        }
+    }
+
+    /// Verify that the history of a property lists its value in each stored version, oldest
+    /// first, with struct values as they were in that version
+    #[test]
+    fn test_get_property_value_history() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        let value = |name: &str, commit_num: i64| {
+            PropertyValueBuilder::default()
+                .with_mfg_batch_id(MFG_BATCH_ID.into())
+                .with_mfg_batch_address("11bb0e01".into())
+                .with_property_name(name.into())
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+        };
+        let version = |commit_num: i64, qc_status: &str, moisture: i64| {
+            let qc_status = value("qc_status", commit_num)
+                .with_data_type("String".into())
+                .with_string_value(Some(qc_status.into()))
+                .build()
+                .expect("Failed to build property value");
+            let moisture = value("moisture", commit_num)
+                .with_data_type("Number".into())
+                .with_number_value(Some(moisture))
+                .build()
+                .expect("Failed to build property value");
+            let lab_result = value("lab_result", commit_num)
+                .with_data_type("Struct".into())
+                .with_struct_values(vec![moisture])
+                .build()
+                .expect("Failed to build property value");
+            MfgBatchBuilder::default()
+                .with_mfg_batch_id(MFG_BATCH_ID.into())
+                .with_mfg_batch_address("11bb0e01".into())
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![qc_status, lab_result])
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch")
+        };
+
+        store
+            .add_mfg_batch(version(1, "pending", 14))
+            .expect("Failed to add mfg_batch");
+        store
+            .add_mfg_batch(version(3, "released", 11))
+            .expect("Failed to add mfg_batch");
+
+        let history = store
+            .get_property_value_history(MFG_BATCH_ID, "qc_status", None)
+            .expect("Failed to get property history");
+        assert_eq!(
+            history
+                .iter()
+                .map(|value| (*value.start_commit_num(), value.string_value()))
+                .collect::<Vec<_>>(),
+            vec![(1, Some("pending")), (3, Some("released"))]
+        );
+        assert_eq!(*history[0].end_commit_num(), 3);
+
+        let history = store
+            .get_property_value_history(MFG_BATCH_ID, "lab_result", None)
+            .expect("Failed to get property history");
+        assert_eq!(
+            history
+                .iter()
+                .map(|value| value.struct_values()[0].number_value())
+                .collect::<Vec<_>>(),
+            vec![Some(14), Some(11)]
+        );
+
+        assert!(store
+            .get_property_value_history(MFG_BATCH_ID, "moisture", None)
+            .expect("Failed to get property history")
+            .is_empty());
+    }
+
+    /// Verify that a mfg_batch's manufacture location is stored, and that listing and counting
+    /// by it only includes the mfg_batches produced there
+    #[test]
+    fn test_list_mfg_batches_by_manufacture_location() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        for (mfg_batch_id, manufacture_location) in [
+            ("batch1", Some("0614141000005")),
+            ("batch2", Some("0614141000012")),
+            ("batch3", None),
+        ] {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("{}-addr", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_manufacture_location(manufacture_location.map(String::from))
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        }
+
+        let filters = ListMfgBatchFilters {
+            manufacture_location: Some("0614141000005".into()),
+            ..Default::default()
+        };
+        let mfg_batches = store
+            .list_mfg_batches(None, &filters, 0, 10)
+            .expect("Failed to list mfg_batches")
+            .data();
+        assert_eq!(mfg_batches.len(), 1);
+        assert_eq!(mfg_batches[0].mfg_batch_id(), "batch1");
+        assert_eq!(mfg_batches[0].manufacture_location(), Some("0614141000005"));
+        assert_eq!(
+            store
+                .count_mfg_batches(None, &filters)
+                .expect("Failed to count mfg_batches"),
+            1
+        );
+
+        let mfg_batch = store
+            .get_mfg_batch("batch3", None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.manufacture_location(), None);
+    }
+
+    /// Verify that paging through more mfg_batches than fit on a page lists each one exactly
+    /// once, ordered by ID regardless of the order they were added in
+    #[test]
+    fn test_list_mfg_batches_paging() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+
+        let mut mfg_batch_ids = (0..25)
+            .map(|i| format!("batch{:02}", (i * 7) % 25))
+            .collect::<Vec<_>>();
+        let mfg_batches = mfg_batch_ids
+            .iter()
+            .map(|mfg_batch_id| {
+                MfgBatchBuilder::default()
+                    .with_mfg_batch_id(mfg_batch_id.clone())
+                    .with_mfg_batch_address(format!("{}-addr", mfg_batch_id))
+                    .with_mfg_batch_namespace("GS1".into())
+                    .with_owner("org".into())
+                    .with_start_commit_number(1)
+                    .with_end_commit_number(MAX_COMMIT_NUM)
+                    .build()
+                    .expect("Failed to build mfg_batch")
+            })
+            .collect();
+        store
+            .add_mfg_batches(mfg_batches)
+            .expect("Failed to add mfg_batches");
+
+        let filters = ListMfgBatchFilters::default();
+        let mut listed = vec![];
+        let mut offset = 0;
+        loop {
+            let page = store
+                .list_mfg_batches(None, &filters, offset, 10)
+                .expect("Failed to list mfg_batches")
+                .data();
+            if page.is_empty() {
+                break;
+            }
+            assert!(page.len() <= 10);
+            offset += page.len() as i64;
+            listed.extend(
+                page.iter()
+                    .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string()),
+            );
+        }
+
+        mfg_batch_ids.sort();
+        assert_eq!(listed, mfg_batch_ids);
+    }
+
+    /// Verify that descendants are found breadth-first through every generation, each batch
+    /// once even when it is produced from several descendants, and that a batch produced from
+    /// nothing recorded has none
+    #[test]
+    fn test_get_mfg_batch_descendants() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let add = |mfg_batch_id: &str, parent_batches: &[&str]| {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_parent_batches(parent_batches.iter().map(|id| id.to_string()).collect())
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        };
+
+        add("flour", &[]);
+        add("dough", &["flour"]);
+        add("batter", &["flour"]);
+        add("bread", &["dough"]);
+        add("cake", &["batter", "dough"]);
+
+        assert_eq!(
+            store
+                .get_mfg_batch_descendants("flour", None)
+                .expect("Failed to get descendants"),
+            vec!["batter", "dough", "cake", "bread"]
+        );
+        assert_eq!(
+            store
+                .get_mfg_batch_descendants("cake", None)
+                .expect("Failed to get descendants"),
+            Vec::<String>::new()
+        );
+    }
+
+    /// Verify that attachments are stored with the version of the mfg_batch that references them,
+    /// in order, so that a version replacing them leaves the earlier version's attachments intact
+    #[test]
+    fn test_mfg_batch_attachments() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let attachment = |digest: &str, uri: Option<&str>| MfgBatchAttachment {
+            digest: digest.repeat(32),
+            mime_type: "application/pdf".into(),
+            size: 48_213,
+            uri: uri.map(String::from),
+        };
+        let add = |commit_num: i64, attachments: Vec<MfgBatchAttachment>| {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(MFG_BATCH_ID.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", MFG_BATCH_ID))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_attachments(attachments)
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        };
+
+        let first = vec![
+            attachment("b2", Some("https://example.com/coa.pdf")),
+            attachment("a1", None),
+        ];
+        let second = vec![attachment("c3", None)];
+        add(1, first.clone());
+        add(2, second.clone());
+
+        let mfg_batch = store
+            .get_mfg_batch(MFG_BATCH_ID, None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attachments(), second.as_slice());
+
+        let mfg_batch = store
+            .get_mfg_batch_at_commit(MFG_BATCH_ID, 1, None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(mfg_batch.attachments(), first.as_slice());
+
+        let mfg_batches = store
+            .get_mfg_batches(&[MFG_BATCH_ID], None)
+            .expect("Failed to get mfg_batches");
+        assert_eq!(mfg_batches[0].attachments(), second.as_slice());
+
+        let mfg_batches = store
+            .list_mfg_batches(None, &ListMfgBatchFilters::default(), 0, 10)
+            .expect("Failed to list mfg_batches")
+            .data();
+        assert_eq!(mfg_batches[0].attachments(), second.as_slice());
+    }
+
+    /// Verify that pruning deletes only the rows of versions ended before the given commit,
+    /// leaving the current version and later history readable, and that a dry run only counts
+    /// them
+    #[cfg(feature = "mfg-batch-pruning")]
+    #[test]
+    fn test_prune_mfg_batch_history() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        for commit_num in 1..=3 {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(MFG_BATCH_ID.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", MFG_BATCH_ID))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_attachments(vec![MfgBatchAttachment {
+                    digest: format!("{:064x}", commit_num),
+                    mime_type: "application/pdf".into(),
+                    size: 48_213,
+                    uri: None,
+                }])
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        }
+
+        assert!(matches!(
+            store.prune_mfg_batch_history(-1, true),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+
+        let pruned = store
+            .prune_mfg_batch_history(3, true)
+            .expect("Failed to count prunable rows");
+        assert_eq!(
+            pruned,
+            PrunedMfgBatchHistory {
+                mfg_batches: 1,
+                attachments: 1,
+                ..Default::default()
+            }
+        );
+        assert!(store
+            .get_mfg_batch_at_commit(MFG_BATCH_ID, 1, None)
+            .expect("Failed to get mfg_batch")
+            .is_some());
+
+        let pruned = store
+            .prune_mfg_batch_history(3, false)
+            .expect("Failed to prune history");
+        assert_eq!(pruned.total(), 2);
+        assert!(store
+            .get_mfg_batch_at_commit(MFG_BATCH_ID, 1, None)
+            .expect("Failed to get mfg_batch")
+            .is_none());
+        assert!(store
+            .get_mfg_batch_at_commit(MFG_BATCH_ID, 2, None)
+            .expect("Failed to get mfg_batch")
+            .is_some());
+
+        store
+            .prune_mfg_batch_history(MAX_COMMIT_NUM, false)
+            .expect("Failed to prune history");
+        let mfg_batch = store
+            .get_mfg_batch(MFG_BATCH_ID, None)
+            .expect("Failed to get mfg_batch")
+            .expect("No mfg_batch found");
+        assert_eq!(*mfg_batch.start_commit_num(), 3);
+        assert_eq!(mfg_batch.attachments().len(), 1);
+    }
+
+    /// Verify that recalls are only recorded for mfg_batches that exist at the recall's commit,
+    /// that closing a recall closes it for every batch it covers, and that closed recalls are
+    /// only listed when asked for
+    #[cfg(feature = "mfg-batch-recalls")]
+    #[test]
+    fn test_mfg_batch_recalls() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        for mfg_batch_id in &["flour", "dough"] {
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.to_string())
+                .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(vec![])
+                .with_start_commit_number(1)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        }
+
+        let recall = |recall_id: &str, mfg_batch_id: &str, recalled_at: i64| MfgBatchRecall {
+            recall_id: recall_id.into(),
+            mfg_batch_id: mfg_batch_id.into(),
+            reason_code: "CONTAMINATION".into(),
+            recalled_at,
+            closed_at: None,
+            commit_num: 2,
+            service_id: None,
+        };
+
+        assert!(matches!(
+            store.add_mfg_batch_recalls(vec![recall("RC-1", "unknown", 100)]),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+        store
+            .add_mfg_batch_recalls(vec![
+                recall("RC-1", "flour", 100),
+                recall("RC-1", "dough", 100),
+            ])
+            .expect("Failed to add recalls");
+        store
+            .add_mfg_batch_recalls(vec![recall("RC-2", "dough", 200)])
+            .expect("Failed to add recalls");
+
+        let list = |filters: &ListMfgBatchRecallFilters| {
+            store
+                .list_mfg_batch_recalls(None, filters, 0, 100)
+                .expect("Failed to list recalls")
+        };
+
+        assert_eq!(
+            list(&ListMfgBatchRecallFilters::default()),
+            vec![
+                recall("RC-2", "dough", 200),
+                recall("RC-1", "dough", 100),
+                recall("RC-1", "flour", 100),
+            ]
+        );
+        assert_eq!(
+            list(&ListMfgBatchRecallFilters {
+                mfg_batch_id: Some("flour".into()),
+                ..Default::default()
+            }),
+            vec![recall("RC-1", "flour", 100)]
+        );
+
+        store
+            .close_mfg_batch_recall("RC-1", 300, None)
+            .expect("Failed to close recall");
+        assert!(matches!(
+            store.close_mfg_batch_recall("RC-1", 400, None),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+
+        assert_eq!(
+            list(&ListMfgBatchRecallFilters::default()),
+            vec![recall("RC-2", "dough", 200)]
+        );
+        assert_eq!(
+            list(&ListMfgBatchRecallFilters {
+                recall_id: Some("RC-1".into()),
+                include_closed: true,
+                ..Default::default()
+            }),
+            vec![
+                MfgBatchRecall {
+                    closed_at: Some(300),
+                    ..recall("RC-1", "dough", 100)
+                },
+                MfgBatchRecall {
+                    closed_at: Some(300),
+                    ..recall("RC-1", "flour", 100)
+                },
+            ]
+        );
+    }
+
+    /// Verify that a search matches the batches holding every word of the query in their current
+    /// string property values, and that an empty query is rejected
+    #[cfg(feature = "mfg-batch-text-search")]
+    #[test]
+    fn test_search_mfg_batches_text() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let add = |mfg_batch_id: &str, commit_num: i64, values: &[(&str, &str)]| {
+            let properties = values
+                .iter()
+                .map(|(name, value)| {
+                    PropertyValueBuilder::default()
+                        .with_mfg_batch_id(mfg_batch_id.into())
+                        .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                        .with_property_name(name.to_string())
+                        .with_data_type("String".into())
+                        .with_string_value(Some(value.to_string()))
+                        .with_start_commit_number(commit_num)
+                        .with_end_commit_number(MAX_COMMIT_NUM)
+                        .build()
+                        .expect("Failed to build property value")
+                })
+                .collect();
+            let mfg_batch = MfgBatchBuilder::default()
+                .with_mfg_batch_id(mfg_batch_id.into())
+                .with_mfg_batch_address(format!("11bb0e01{}", mfg_batch_id))
+                .with_mfg_batch_namespace("GS1".into())
+                .with_owner("org".into())
+                .with_properties(properties)
+                .with_start_commit_number(commit_num)
+                .with_end_commit_number(MAX_COMMIT_NUM)
+                .build()
+                .expect("Failed to build mfg_batch");
+            store
+                .add_mfg_batch(mfg_batch)
+                .expect("Failed to add mfg_batch");
+        };
+
+        add(
+            "flour",
+            1,
+            &[
+                ("description", "Wholemeal flour"),
+                ("lot_code", "LOT-2021-0042"),
+            ],
+        );
+        add("sugar", 1, &[("description", "Cane sugar")]);
+        add("dough", 1, &[("description", "Bread dough, 50% flour")]);
+        // Only current values are searched
+        add("sugar", 2, &[("description", "Brown sugar")]);
+
+        let search = |query: &str| {
+            store
+                .search_mfg_batches_text(query, None, 0, 100)
+                .expect("Failed to search")
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(search("flour"), vec!["dough", "flour"]);
+        assert_eq!(search("wholemeal LOT-2021-0042"), vec!["flour"]);
+        assert_eq!(search("BROWN sugar"), vec!["sugar"]);
+        assert!(search("cane").is_empty());
+        assert!(search("wholemeal sugar").is_empty());
+        assert_eq!(
+            store
+                .search_mfg_batches_text("flour", None, 1, 100)
+                .expect("Failed to search")
+                .len(),
+            1
+        );
+        assert!(matches!(
+            store.search_mfg_batches_text("  ", None, 0, 100),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+    }
+
+    /// Verify that allocations are set per order and removed at zero, that availability counts
+    /// every order's allocation against the produced quantity, and that an allocation exceeding
+    /// what is available is rejected without changing the existing ones
+    #[cfg(feature = "mfg-batch-allocations")]
+    #[test]
+    fn test_mfg_batch_allocations() {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .with_owner("org".into())
+            .with_properties(vec![])
+            .with_quantity(Some(10))
+            .with_uom(Some("kg".into()))
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build mfg_batch");
+        store
+            .add_mfg_batch(mfg_batch)
+            .expect("Failed to add mfg_batch");
+
+        let allocation = |order_id: &str, quantity: i64| MfgBatchAllocation {
+            mfg_batch_id: MFG_BATCH_ID.into(),
+            order_id: order_id.into(),
+            quantity,
+            commit_num: 2,
+            service_id: None,
+        };
+
+        store
+            .put_mfg_batch_allocation(allocation("order1", 4))
+            .expect("Failed to allocate to order1");
+        store
+            .put_mfg_batch_allocation(allocation("order2", 3))
+            .expect("Failed to allocate to order2");
+        store
+            .put_mfg_batch_allocation(allocation("order1", 6))
+            .expect("Failed to reallocate to order1");
+
+        assert!(matches!(
+            store.put_mfg_batch_allocation(allocation("order3", 2)),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+        assert!(matches!(
+            store.put_mfg_batch_allocation(MfgBatchAllocation {
+                mfg_batch_id: "unknown".into(),
+                ..allocation("order3", 1)
+            }),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+
+        assert_eq!(
+            store
+                .list_mfg_batch_allocations(MFG_BATCH_ID, None)
+                .expect("Failed to list allocations"),
+            vec![allocation("order1", 6), allocation("order2", 3)]
+        );
+        assert_eq!(
+            store
+                .get_mfg_batch_availability(MFG_BATCH_ID, None)
+                .expect("Failed to get availability"),
+            Some(MfgBatchAvailability {
+                mfg_batch_id: MFG_BATCH_ID.into(),
+                quantity: Some(10),
+                uom: Some("kg".into()),
+                allocated_quantity: 9,
+                available_quantity: 1,
+            })
+        );
+
+        store
+            .put_mfg_batch_allocation(allocation("order1", 0))
+            .expect("Failed to release order1");
+        assert_eq!(
+            store
+                .list_mfg_batch_allocations(MFG_BATCH_ID, None)
+                .expect("Failed to list allocations"),
+            vec![allocation("order2", 3)]
+        );
+        assert_eq!(
+            store
+                .get_mfg_batch_availability("unknown", None)
+                .expect("Failed to get availability"),
+            None
+        );
+    }
+}
@@ -12,14 +12,58 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 
+#[cfg(feature = "mfg-batch-merge")]
+use crate::mfg_batch::store::MfgBatchAlias as GridMfgBatchAlias;
+#[cfg(feature = "mfg-batch-allocations")]
+use crate::mfg_batch::store::MfgBatchAllocation as GridMfgBatchAllocation;
+#[cfg(feature = "mfg-batch-annotations")]
+use crate::mfg_batch::store::MfgBatchAnnotation as GridMfgBatchAnnotation;
+#[cfg(feature = "mfg-batch-duplicates")]
+use crate::mfg_batch::store::MfgBatchDuplicate as GridMfgBatchDuplicate;
+#[cfg(feature = "mfg-batch-anchors")]
+use crate::mfg_batch::store::MfgBatchAnchor as GridMfgBatchAnchor;
+#[cfg(feature = "mfg-batch-quality-scores")]
+use crate::mfg_batch::store::MfgBatchQualityScore as GridMfgBatchQualityScore;
+#[cfg(feature = "mfg-batch-recalls")]
+use crate::mfg_batch::store::MfgBatchRecall as GridMfgBatchRecall;
+#[cfg(feature = "mfg-batch-test-results")]
+use crate::mfg_batch::store::MfgBatchTestResult as GridMfgBatchTestResult;
 use crate::mfg_batch::{
-    store::{LatLongValue, MfgBatch as GridMfgBatch, PropertyValue},
+    store::{
+        LatLongValue, MfgBatch as GridMfgBatch, MfgBatchAttachment as GridMfgBatchAttachment,
+        PropertyValue,
+    },
     MAX_COMMIT_NUM,
 };
 
-use super::schema::{mfg_batch, mfg_batch_property_value};
+#[cfg(feature = "mfg-batch-merge")]
+use super::schema::mfg_batch_alias;
+#[cfg(feature = "mfg-batch-allocations")]
+use super::schema::mfg_batch_allocation;
+#[cfg(feature = "mfg-batch-anchors")]
+use super::schema::mfg_batch_anchor;
+#[cfg(feature = "mfg-batch-annotations")]
+use super::schema::mfg_batch_annotation;
+#[cfg(feature = "mfg-batch-audit-log")]
+use super::schema::mfg_batch_audit_log;
+#[cfg(feature = "mfg-batch-change-capture")]
+use super::schema::mfg_batch_change;
+#[cfg(feature = "mfg-batch-duplicates")]
+use super::schema::mfg_batch_duplicate;
+#[cfg(feature = "mfg-batch-quality-scores")]
+use super::schema::mfg_batch_quality_score;
+#[cfg(feature = "mfg-batch-recalls")]
+use super::schema::mfg_batch_recall;
+#[cfg(feature = "mfg-batch-visibility")]
+use super::schema::mfg_batch_shared_with;
+#[cfg(feature = "mfg-batch-test-results")]
+use super::schema::mfg_batch_test_result;
+use super::schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value};
+
+#[cfg(feature = "mfg-batch-checksums")]
+use super::record_hash::HashedRecord;
 
 #[derive(Clone, Insertable, Debug)]
 #[table_name = "mfg_batch"]
@@ -31,6 +75,15 @@ pub struct NewMfgBatch {
     pub start_commit_num: i64,
     pub end_commit_num: i64,
     pub service_id: Option<String>,
+    pub last_updated: Option<NaiveDateTime>,
+    pub quantity: Option<i64>,
+    pub uom: Option<String>,
+    pub expected_quantity: Option<i64>,
+    pub production_date: Option<i64>,
+    pub expiration_date: Option<i64>,
+    pub manufacture_location: Option<String>,
+    pub checksum: Option<String>,
+    pub archived: bool,
 }
 
 #[derive(Queryable, Identifiable, Debug)]
@@ -45,6 +98,14 @@ pub struct MfgBatch {
     pub end_commit_num: i64,
     pub service_id: Option<String>,
     pub last_updated: Option<NaiveDateTime>,
+    pub quantity: Option<i64>,
+    pub uom: Option<String>,
+    pub expected_quantity: Option<i64>,
+    pub production_date: Option<i64>,
+    pub expiration_date: Option<i64>,
+    pub manufacture_location: Option<String>,
+    pub checksum: Option<String>,
+    pub archived: bool,
 }
 
 #[derive(AsChangeset, Clone, Insertable, Debug)]
@@ -65,6 +126,7 @@ pub struct NewMfgBatchPropertyValue {
     pub start_commit_num: i64,
     pub end_commit_num: i64,
     pub service_id: Option<String>,
+    pub language: Option<String>,
 }
 
 #[derive(Queryable, Identifiable, Debug)]
@@ -86,9 +148,317 @@ pub struct MfgBatchPropertyValue {
     pub start_commit_num: i64,
     pub end_commit_num: i64,
     pub service_id: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_parent"]
+pub struct NewMfgBatchParent {
+    pub mfg_batch_id: String,
+    pub parent_mfg_batch_id: String,
+    pub start_commit_num: i64,
+    pub end_commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_parent"]
+pub struct MfgBatchParent {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub parent_mfg_batch_id: String,
+    pub start_commit_num: i64,
+    pub end_commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_attachment"]
+pub struct NewMfgBatchAttachment {
+    pub mfg_batch_id: String,
+    pub digest: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub uri: Option<String>,
+    pub start_commit_num: i64,
+    pub end_commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_attachment"]
+pub struct MfgBatchAttachment {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub digest: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub uri: Option<String>,
+    pub start_commit_num: i64,
+    pub end_commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-audit-log")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_audit_log"]
+pub struct NewMfgBatchAuditEntry {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+    pub record_hash: String,
+    pub previous_hash: String,
+    pub entry_hash: String,
+}
+
+#[cfg(feature = "mfg-batch-audit-log")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_audit_log"]
+pub struct MfgBatchAuditEntry {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+    pub record_hash: String,
+    pub previous_hash: String,
+    pub entry_hash: String,
+}
+
+#[cfg(feature = "mfg-batch-annotations")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_annotation"]
+pub struct NewMfgBatchAnnotation {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub property_name: String,
+    pub author: String,
+    pub comment: String,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-annotations")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_annotation"]
+pub struct MfgBatchAnnotation {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub property_name: String,
+    pub author: String,
+    pub comment: String,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-allocations")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_allocation"]
+pub struct NewMfgBatchAllocation {
+    pub mfg_batch_id: String,
+    pub order_id: String,
+    pub quantity: i64,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-allocations")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_allocation"]
+pub struct MfgBatchAllocation {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub order_id: String,
+    pub quantity: i64,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_recall"]
+pub struct NewMfgBatchRecall {
+    pub recall_id: String,
+    pub mfg_batch_id: String,
+    pub reason_code: String,
+    pub recalled_at: i64,
+    pub closed_at: Option<i64>,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_recall"]
+pub struct MfgBatchRecall {
+    pub id: i64,
+    pub recall_id: String,
+    pub mfg_batch_id: String,
+    pub reason_code: String,
+    pub recalled_at: i64,
+    pub closed_at: Option<i64>,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-test-results")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_test_result"]
+pub struct NewMfgBatchTestResult {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub test_name: String,
+    pub specification: String,
+    pub result: String,
+    pub passed: bool,
+    pub lab: String,
+    pub tested_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-test-results")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_test_result"]
+pub struct MfgBatchTestResult {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub test_name: String,
+    pub specification: String,
+    pub result: String,
+    pub passed: bool,
+    pub lab: String,
+    pub tested_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-anchors")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_anchor"]
+pub struct NewMfgBatchAnchor {
+    pub owner: String,
+    pub commit_num: i64,
+    pub merkle_root: String,
+    pub row_count: i64,
+    pub anchor_address: String,
+    pub anchored_commit_num: i64,
+    pub anchored_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-anchors")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_anchor"]
+pub struct MfgBatchAnchor {
+    pub id: i64,
+    pub owner: String,
+    pub commit_num: i64,
+    pub merkle_root: String,
+    pub row_count: i64,
+    pub anchor_address: String,
+    pub anchored_commit_num: i64,
+    pub anchored_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_quality_score"]
+pub struct NewMfgBatchQualityScore {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub score: i32,
+    pub failed_checks: String,
+    pub scored_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_quality_score"]
+pub struct MfgBatchQualityScore {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub score: i32,
+    pub failed_checks: String,
+    pub scored_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-duplicates")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_duplicate"]
+pub struct NewMfgBatchDuplicate {
+    pub mfg_batch_id: String,
+    pub duplicate_of: String,
+    pub rule: String,
+    pub similarity: i32,
+    pub detected_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-duplicates")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_duplicate"]
+pub struct MfgBatchDuplicate {
+    pub id: i64,
+    pub mfg_batch_id: String,
+    pub duplicate_of: String,
+    pub rule: String,
+    pub similarity: i32,
+    pub detected_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-merge")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_alias"]
+pub struct NewMfgBatchAlias {
+    pub alias: String,
+    pub mfg_batch_id: String,
+    pub merged_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-merge")]
+#[derive(Queryable, Identifiable, Debug)]
+#[table_name = "mfg_batch_alias"]
+pub struct MfgBatchAlias {
+    pub id: i64,
+    pub alias: String,
+    pub mfg_batch_id: String,
+    pub merged_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-visibility")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_shared_with"]
+pub struct NewMfgBatchSharedWith {
+    pub mfg_batch_id: String,
+    pub org_id: String,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-change-capture")]
+#[derive(Clone, Insertable, Debug)]
+#[table_name = "mfg_batch_change"]
+pub struct NewMfgBatchChange {
+    pub entity: String,
+    pub entity_id: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
 }
 
-impl From<GridMfgBatch> for (NewMfgBatch, Vec<NewMfgBatchPropertyValue>) {
+impl From<GridMfgBatch>
+    for (
+        NewMfgBatch,
+        Vec<NewMfgBatchPropertyValue>,
+        Vec<NewMfgBatchParent>,
+    )
+{
     fn from(mfg_batch: GridMfgBatch) -> Self {
         let new_mfg_batch = NewMfgBatch {
             mfg_batch_id: mfg_batch.mfg_batch_id.clone(),
@@ -98,14 +468,59 @@ impl From<GridMfgBatch> for (NewMfgBatch, Vec<NewMfgBatchPropertyValue>) {
             start_commit_num: mfg_batch.start_commit_num,
             end_commit_num: mfg_batch.end_commit_num,
             service_id: mfg_batch.service_id.clone(),
+            last_updated: Some(last_updated_at(mfg_batch.last_updated)),
+            quantity: mfg_batch.quantity,
+            uom: mfg_batch.uom.clone(),
+            expected_quantity: mfg_batch.expected_quantity,
+            production_date: mfg_batch.production_date,
+            expiration_date: mfg_batch.expiration_date,
+            manufacture_location: mfg_batch.manufacture_location.clone(),
+            checksum: None,
+            archived: mfg_batch.archived,
         };
 
-        (new_mfg_batch, make_property_values(None, &mfg_batch.properties))
+        let parents = mfg_batch
+            .parent_batches
+            .iter()
+            .map(|parent| NewMfgBatchParent {
+                mfg_batch_id: mfg_batch.mfg_batch_id.clone(),
+                parent_mfg_batch_id: parent.clone(),
+                start_commit_num: mfg_batch.start_commit_num,
+                end_commit_num: mfg_batch.end_commit_num,
+                service_id: mfg_batch.service_id.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let property_values = make_property_values(None, &mfg_batch.properties);
+
+        #[cfg(feature = "mfg-batch-checksums")]
+        let new_mfg_batch = NewMfgBatch {
+            checksum: Some(
+                HashedRecord::from_new(&new_mfg_batch, &property_values, &parents).hash(),
+            ),
+            ..new_mfg_batch
+        };
+
+        (new_mfg_batch, property_values, parents)
     }
 }
 
-impl From<(MfgBatch, Vec<PropertyValue>)> for GridMfgBatch {
-    fn from((model, properties): (MfgBatch, Vec<PropertyValue>)) -> Self {
+impl
+    From<(
+        MfgBatch,
+        Vec<PropertyValue>,
+        Vec<MfgBatchParent>,
+        Vec<MfgBatchAttachment>,
+    )> for GridMfgBatch
+{
+    fn from(
+        (model, properties, parents, attachments): (
+            MfgBatch,
+            Vec<PropertyValue>,
+            Vec<MfgBatchParent>,
+            Vec<MfgBatchAttachment>,
+        ),
+    ) -> Self {
         Self {
             mfg_batch_id: model.mfg_batch_id,
             mfg_batch_address: model.mfg_batch_address,
@@ -116,10 +531,62 @@ impl From<(MfgBatch, Vec<PropertyValue>)> for GridMfgBatch {
             service_id: model.service_id,
             last_updated: model.last_updated.map(|d| d.timestamp()),
             properties,
+            parent_batches: parents
+                .into_iter()
+                .map(|parent| parent.parent_mfg_batch_id)
+                .collect(),
+            quantity: model.quantity,
+            uom: model.uom,
+            expected_quantity: model.expected_quantity,
+            production_date: model.production_date,
+            expiration_date: model.expiration_date,
+            manufacture_location: model.manufacture_location,
+            attachments: attachments
+                .into_iter()
+                .map(GridMfgBatchAttachment::from)
+                .collect(),
+            archived: model.archived,
+        }
+    }
+}
+
+impl From<MfgBatchAttachment> for GridMfgBatchAttachment {
+    fn from(attachment: MfgBatchAttachment) -> Self {
+        Self {
+            digest: attachment.digest,
+            mime_type: attachment.mime_type,
+            size: attachment.size,
+            uri: attachment.uri,
         }
     }
 }
 
+/// Makes the attachment rows of a mfg_batch, which are versioned with the mfg_batch itself
+pub fn make_attachments(mfg_batch: &GridMfgBatch) -> Vec<NewMfgBatchAttachment> {
+    mfg_batch
+        .attachments
+        .iter()
+        .map(|attachment| NewMfgBatchAttachment {
+            mfg_batch_id: mfg_batch.mfg_batch_id.clone(),
+            digest: attachment.digest.clone(),
+            mime_type: attachment.mime_type.clone(),
+            size: attachment.size,
+            uri: attachment.uri.clone(),
+            start_commit_num: mfg_batch.start_commit_num,
+            end_commit_num: mfg_batch.end_commit_num,
+            service_id: mfg_batch.service_id.clone(),
+        })
+        .collect()
+}
+
+/// The time a write to a mfg_batch is recorded at: the given timestamp, in seconds since the
+/// epoch, such as that of the transaction payload, or the current time if there is none
+pub fn last_updated_at(timestamp: Option<i64>) -> NaiveDateTime {
+    timestamp
+        .and_then(|timestamp| NaiveDateTime::from_timestamp_opt(timestamp, 0))
+        .unwrap_or_else(|| Utc::now().naive_utc())
+}
+
 fn make_property_values(
     parent_property: Option<String>,
     properties: &[PropertyValue],
@@ -143,6 +610,7 @@ fn make_property_values(
             start_commit_num: property.start_commit_num,
             end_commit_num: MAX_COMMIT_NUM,
             service_id: property.service_id.clone(),
+            language: property.language.clone(),
         });
 
         if !property.struct_values.is_empty() {
@@ -183,6 +651,7 @@ impl From<MfgBatchPropertyValue> for PropertyValue {
             start_commit_num: model.start_commit_num,
             end_commit_num: model.end_commit_num,
             service_id: model.service_id,
+            language: model.language,
         }
     }
 }
@@ -211,6 +680,242 @@ impl From<(MfgBatchPropertyValue, Vec<PropertyValue>)> for PropertyValue {
             start_commit_num: model.start_commit_num,
             end_commit_num: model.end_commit_num,
             service_id: model.service_id,
+            language: model.language,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-annotations")]
+impl From<GridMfgBatchAnnotation> for NewMfgBatchAnnotation {
+    fn from(annotation: GridMfgBatchAnnotation) -> Self {
+        Self {
+            mfg_batch_id: annotation.mfg_batch_id,
+            commit_num: annotation.commit_num,
+            property_name: annotation.property_name,
+            author: annotation.author,
+            comment: annotation.comment,
+            service_id: annotation.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-annotations")]
+impl From<MfgBatchAnnotation> for GridMfgBatchAnnotation {
+    fn from(annotation: MfgBatchAnnotation) -> Self {
+        Self {
+            mfg_batch_id: annotation.mfg_batch_id,
+            commit_num: annotation.commit_num,
+            property_name: annotation.property_name,
+            author: annotation.author,
+            comment: annotation.comment,
+            service_id: annotation.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-allocations")]
+impl From<GridMfgBatchAllocation> for NewMfgBatchAllocation {
+    fn from(allocation: GridMfgBatchAllocation) -> Self {
+        Self {
+            mfg_batch_id: allocation.mfg_batch_id,
+            order_id: allocation.order_id,
+            quantity: allocation.quantity,
+            commit_num: allocation.commit_num,
+            service_id: allocation.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-allocations")]
+impl From<MfgBatchAllocation> for GridMfgBatchAllocation {
+    fn from(allocation: MfgBatchAllocation) -> Self {
+        Self {
+            mfg_batch_id: allocation.mfg_batch_id,
+            order_id: allocation.order_id,
+            quantity: allocation.quantity,
+            commit_num: allocation.commit_num,
+            service_id: allocation.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+impl From<GridMfgBatchRecall> for NewMfgBatchRecall {
+    fn from(recall: GridMfgBatchRecall) -> Self {
+        Self {
+            recall_id: recall.recall_id,
+            mfg_batch_id: recall.mfg_batch_id,
+            reason_code: recall.reason_code,
+            recalled_at: recall.recalled_at,
+            closed_at: recall.closed_at,
+            commit_num: recall.commit_num,
+            service_id: recall.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+impl From<MfgBatchRecall> for GridMfgBatchRecall {
+    fn from(recall: MfgBatchRecall) -> Self {
+        Self {
+            recall_id: recall.recall_id,
+            mfg_batch_id: recall.mfg_batch_id,
+            reason_code: recall.reason_code,
+            recalled_at: recall.recalled_at,
+            closed_at: recall.closed_at,
+            commit_num: recall.commit_num,
+            service_id: recall.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-test-results")]
+impl From<GridMfgBatchTestResult> for NewMfgBatchTestResult {
+    fn from(test_result: GridMfgBatchTestResult) -> Self {
+        Self {
+            mfg_batch_id: test_result.mfg_batch_id,
+            commit_num: test_result.commit_num,
+            test_name: test_result.test_name,
+            specification: test_result.specification,
+            result: test_result.result,
+            passed: test_result.passed,
+            lab: test_result.lab,
+            tested_at: test_result.tested_at,
+            service_id: test_result.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-test-results")]
+impl From<MfgBatchTestResult> for GridMfgBatchTestResult {
+    fn from(test_result: MfgBatchTestResult) -> Self {
+        Self {
+            mfg_batch_id: test_result.mfg_batch_id,
+            commit_num: test_result.commit_num,
+            test_name: test_result.test_name,
+            specification: test_result.specification,
+            result: test_result.result,
+            passed: test_result.passed,
+            lab: test_result.lab,
+            tested_at: test_result.tested_at,
+            service_id: test_result.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-anchors")]
+impl From<GridMfgBatchAnchor> for NewMfgBatchAnchor {
+    fn from(anchor: GridMfgBatchAnchor) -> Self {
+        Self {
+            owner: anchor.owner,
+            commit_num: anchor.commit_num,
+            merkle_root: anchor.merkle_root,
+            row_count: anchor.row_count,
+            anchor_address: anchor.anchor_address,
+            anchored_commit_num: anchor.anchored_commit_num,
+            anchored_at: anchor.anchored_at,
+            service_id: anchor.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-anchors")]
+impl From<MfgBatchAnchor> for GridMfgBatchAnchor {
+    fn from(anchor: MfgBatchAnchor) -> Self {
+        Self {
+            owner: anchor.owner,
+            commit_num: anchor.commit_num,
+            merkle_root: anchor.merkle_root,
+            row_count: anchor.row_count,
+            anchor_address: anchor.anchor_address,
+            anchored_commit_num: anchor.anchored_commit_num,
+            anchored_at: anchor.anchored_at,
+            service_id: anchor.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+impl From<GridMfgBatchQualityScore> for NewMfgBatchQualityScore {
+    fn from(score: GridMfgBatchQualityScore) -> Self {
+        Self {
+            mfg_batch_id: score.mfg_batch_id,
+            commit_num: score.commit_num,
+            score: score.score,
+            failed_checks: score.failed_checks.join("\n"),
+            scored_at: score.scored_at,
+            service_id: score.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+impl From<MfgBatchQualityScore> for GridMfgBatchQualityScore {
+    fn from(score: MfgBatchQualityScore) -> Self {
+        Self {
+            mfg_batch_id: score.mfg_batch_id,
+            commit_num: score.commit_num,
+            score: score.score,
+            failed_checks: score
+                .failed_checks
+                .lines()
+                .filter(|check| !check.is_empty())
+                .map(String::from)
+                .collect(),
+            scored_at: score.scored_at,
+            service_id: score.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-duplicates")]
+impl From<GridMfgBatchDuplicate> for NewMfgBatchDuplicate {
+    fn from(duplicate: GridMfgBatchDuplicate) -> Self {
+        Self {
+            mfg_batch_id: duplicate.mfg_batch_id,
+            duplicate_of: duplicate.duplicate_of,
+            rule: duplicate.rule,
+            similarity: duplicate.similarity,
+            detected_at: duplicate.detected_at,
+            service_id: duplicate.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-duplicates")]
+impl From<MfgBatchDuplicate> for GridMfgBatchDuplicate {
+    fn from(duplicate: MfgBatchDuplicate) -> Self {
+        Self {
+            mfg_batch_id: duplicate.mfg_batch_id,
+            duplicate_of: duplicate.duplicate_of,
+            rule: duplicate.rule,
+            similarity: duplicate.similarity,
+            detected_at: duplicate.detected_at,
+            service_id: duplicate.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-merge")]
+impl From<GridMfgBatchAlias> for NewMfgBatchAlias {
+    fn from(alias: GridMfgBatchAlias) -> Self {
+        Self {
+            alias: alias.alias,
+            mfg_batch_id: alias.mfg_batch_id,
+            merged_at: alias.merged_at,
+            service_id: alias.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "mfg-batch-merge")]
+impl From<MfgBatchAlias> for GridMfgBatchAlias {
+    fn from(alias: MfgBatchAlias) -> Self {
+        Self {
+            alias: alias.alias,
+            mfg_batch_id: alias.mfg_batch_id,
+            merged_at: alias.merged_at,
+            service_id: alias.service_id,
         }
     }
 }
@@ -14,11 +14,25 @@
 
 use super::MfgBatchStoreOperations;
 
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::change_capture::pg as pg_change_capture;
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "sqlite"))]
+use crate::mfg_batch::store::diesel::change_capture::sqlite as sqlite_change_capture;
+#[cfg(all(feature = "mfg-batch-text-search", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::text_search;
+#[cfg(feature = "mfg-batch-audit-log")]
+use crate::mfg_batch::store::diesel::{
+    audit::{new_audit_entry, GENESIS_HASH},
+    schema::mfg_batch_audit_log,
+};
 use crate::mfg_batch::{
     store::{
         diesel::{
-            models::{NewMfgBatch, NewMfgBatchPropertyValue},
-            schema::{mfg_batch, mfg_batch_property_value},
+            models::{
+                make_attachments, NewMfgBatch, NewMfgBatchAttachment, NewMfgBatchParent,
+                NewMfgBatchPropertyValue,
+            },
+            schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
         },
         error::MfgBatchStoreError,
         MfgBatch,
@@ -38,11 +52,34 @@ pub(in crate::mfg_batch) trait AddMfgBatchOperation {
 #[cfg(feature = "postgres")]
 impl<'a> AddMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
     fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
-        let (mfg_batch_model, property_models) = mfg_batch.into();
+        let attachment_models = make_attachments(&mfg_batch);
+        let (mfg_batch_model, property_models, parent_models) = mfg_batch.into();
 
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            pg_change_capture::record_changes(
+                &*self.conn,
+                &mfg_batch_model,
+                &property_models,
+                &parent_models,
+            )?;
             pg::insert_mfg_batch(&*self.conn, &mfg_batch_model)?;
             pg::insert_mfg_batch_property_values(&*self.conn, &property_models)?;
+            pg::insert_mfg_batch_parents(&*self.conn, &mfg_batch_model, &parent_models)?;
+            pg::insert_mfg_batch_attachments(&*self.conn, &mfg_batch_model, &attachment_models)?;
+            #[cfg(feature = "mfg-batch-text-search")]
+            text_search::reindex(
+                &*self.conn,
+                &mfg_batch_model.mfg_batch_id,
+                mfg_batch_model.service_id.as_deref(),
+            )?;
+            #[cfg(feature = "mfg-batch-audit-log")]
+            pg::append_audit_entry(
+                &*self.conn,
+                &mfg_batch_model,
+                &property_models,
+                &parent_models,
+            )?;
 
             Ok(())
         })
@@ -52,11 +89,32 @@ impl<'a> AddMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConn
 #[cfg(feature = "sqlite")]
 impl<'a> AddMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
     fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
-        let (mfg_batch_model, property_models) = mfg_batch.into();
+        let attachment_models = make_attachments(&mfg_batch);
+        let (mfg_batch_model, property_models, parent_models) = mfg_batch.into();
 
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            sqlite_change_capture::record_changes(
+                &*self.conn,
+                &mfg_batch_model,
+                &property_models,
+                &parent_models,
+            )?;
             sqlite::insert_mfg_batch(&*self.conn, &mfg_batch_model)?;
             sqlite::insert_mfg_batch_property_values(&*self.conn, &property_models)?;
+            sqlite::insert_mfg_batch_parents(&*self.conn, &mfg_batch_model, &parent_models)?;
+            sqlite::insert_mfg_batch_attachments(
+                &*self.conn,
+                &mfg_batch_model,
+                &attachment_models,
+            )?;
+            #[cfg(feature = "mfg-batch-audit-log")]
+            sqlite::append_audit_entry(
+                &*self.conn,
+                &mfg_batch_model,
+                &property_models,
+                &parent_models,
+            )?;
 
             Ok(())
         })
@@ -64,7 +122,7 @@ impl<'a> AddMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::Sq
 }
 
 #[cfg(feature = "postgres")]
-mod pg {
+pub(super) mod pg {
     use super::*;
 
     pub fn insert_mfg_batch(conn: &PgConnection, mfg_batch: &NewMfgBatch) -> QueryResult<()> {
@@ -99,7 +157,141 @@ mod pg {
             .execute(conn)
             .map(|_| ())
     }
-    fn update_prod_end_commit_num(
+    pub fn insert_mfg_batch_parents(
+        conn: &PgConnection,
+        mfg_batch: &NewMfgBatch,
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        update_parent_end_commit_num(
+            conn,
+            &mfg_batch.mfg_batch_id,
+            mfg_batch.service_id.as_deref(),
+            mfg_batch.start_commit_num,
+        )?;
+
+        if parents.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_parent::table)
+            .values(parents)
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    pub fn insert_mfg_batch_attachments(
+        conn: &PgConnection,
+        mfg_batch: &NewMfgBatch,
+        attachments: &[NewMfgBatchAttachment],
+    ) -> QueryResult<()> {
+        update_attachment_end_commit_num(
+            conn,
+            &mfg_batch.mfg_batch_id,
+            mfg_batch.service_id.as_deref(),
+            mfg_batch.start_commit_num,
+        )?;
+
+        if attachments.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_attachment::table)
+            .values(attachments)
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Appends an entry for the rows just written to the audit log, chained to the latest entry
+    #[cfg(feature = "mfg-batch-audit-log")]
+    pub fn append_audit_entry(
+        conn: &PgConnection,
+        mfg_batch: &NewMfgBatch,
+        property_values: &[NewMfgBatchPropertyValue],
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        let previous_hash = mfg_batch_audit_log::table
+            .select(mfg_batch_audit_log::entry_hash)
+            .order(mfg_batch_audit_log::id.desc())
+            .first::<String>(conn)
+            .optional()?
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        insert_into(mfg_batch_audit_log::table)
+            .values(new_audit_entry(
+                mfg_batch,
+                property_values,
+                parents,
+                previous_hash,
+            ))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    pub fn update_parent_end_commit_num(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch_parent::table);
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch_parent::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch_parent::service_id.eq(service_id)),
+                )
+                .set(mfg_batch_parent::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch_parent::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(mfg_batch_parent::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
+    pub fn update_attachment_end_commit_num(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch_attachment::table);
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch_attachment::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch_attachment::service_id.eq(service_id)),
+                )
+                .set(mfg_batch_attachment::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch_attachment::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(mfg_batch_attachment::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
+    pub fn update_prod_end_commit_num(
         conn: &PgConnection,
         mfg_batch_id: &str,
         service_id: Option<&str>,
@@ -131,7 +323,7 @@ mod pg {
         }
     }
 
-    fn update_prod_property_values(
+    pub fn update_prod_property_values(
         conn: &PgConnection,
         mfg_batch_id: &str,
         service_id: Option<&str>,
@@ -165,7 +357,7 @@ mod pg {
 }
 
 #[cfg(feature = "sqlite")]
-mod sqlite {
+pub(super) mod sqlite {
     use super::*;
 
     pub fn insert_mfg_batch(conn: &SqliteConnection, mfg_batch: &NewMfgBatch) -> QueryResult<()> {
@@ -201,7 +393,141 @@ mod sqlite {
             .map(|_| ())
     }
 
-    fn update_prod_end_commit_num(
+    pub fn insert_mfg_batch_parents(
+        conn: &SqliteConnection,
+        mfg_batch: &NewMfgBatch,
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        update_parent_end_commit_num(
+            conn,
+            &mfg_batch.mfg_batch_id,
+            mfg_batch.service_id.as_deref(),
+            mfg_batch.start_commit_num,
+        )?;
+
+        if parents.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_parent::table)
+            .values(parents)
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    pub fn insert_mfg_batch_attachments(
+        conn: &SqliteConnection,
+        mfg_batch: &NewMfgBatch,
+        attachments: &[NewMfgBatchAttachment],
+    ) -> QueryResult<()> {
+        update_attachment_end_commit_num(
+            conn,
+            &mfg_batch.mfg_batch_id,
+            mfg_batch.service_id.as_deref(),
+            mfg_batch.start_commit_num,
+        )?;
+
+        if attachments.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(mfg_batch_attachment::table)
+            .values(attachments)
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Appends an entry for the rows just written to the audit log, chained to the latest entry
+    #[cfg(feature = "mfg-batch-audit-log")]
+    pub fn append_audit_entry(
+        conn: &SqliteConnection,
+        mfg_batch: &NewMfgBatch,
+        property_values: &[NewMfgBatchPropertyValue],
+        parents: &[NewMfgBatchParent],
+    ) -> QueryResult<()> {
+        let previous_hash = mfg_batch_audit_log::table
+            .select(mfg_batch_audit_log::entry_hash)
+            .order(mfg_batch_audit_log::id.desc())
+            .first::<String>(conn)
+            .optional()?
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        insert_into(mfg_batch_audit_log::table)
+            .values(new_audit_entry(
+                mfg_batch,
+                property_values,
+                parents,
+                previous_hash,
+            ))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    pub fn update_parent_end_commit_num(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch_parent::table);
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch_parent::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch_parent::service_id.eq(service_id)),
+                )
+                .set(mfg_batch_parent::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch_parent::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(mfg_batch_parent::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
+    pub fn update_attachment_end_commit_num(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch_attachment::table);
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch_attachment::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch_attachment::service_id.eq(service_id)),
+                )
+                .set(mfg_batch_attachment::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch_attachment::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(mfg_batch_attachment::end_commit_num.eq(current_commit_num))
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
+    pub fn update_prod_end_commit_num(
         conn: &SqliteConnection,
         mfg_batch_id: &str,
         service_id: Option<&str>,
@@ -233,7 +559,7 @@ mod sqlite {
         }
     }
 
-    fn update_prod_property_values(
+    pub fn update_prod_property_values(
         conn: &SqliteConnection,
         mfg_batch_id: &str,
         service_id: Option<&str>,
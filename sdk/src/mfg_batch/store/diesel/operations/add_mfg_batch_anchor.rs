@@ -0,0 +1,125 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::NewMfgBatchAnchor, schema::mfg_batch_anchor},
+    error::MfgBatchStoreError,
+    MfgBatchAnchor,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::mfg_batch) trait AddMfgBatchAnchorOperation {
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddMfgBatchAnchorOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        let anchor = NewMfgBatchAnchor::from(anchor);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // An anchor is never changed on chain, so one already recorded was read from an
+            // earlier delivery of the same state change
+            if !pg::anchor_exists(&*self.conn, &anchor)? {
+                pg::insert_anchor(&*self.conn, &anchor)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddMfgBatchAnchorOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        let anchor = NewMfgBatchAnchor::from(anchor);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // An anchor is never changed on chain, so one already recorded was read from an
+            // earlier delivery of the same state change
+            if !sqlite::anchor_exists(&*self.conn, &anchor)? {
+                sqlite::insert_anchor(&*self.conn, &anchor)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn anchor_exists(conn: &PgConnection, anchor: &NewMfgBatchAnchor) -> QueryResult<bool> {
+        let mut query = mfg_batch_anchor::table
+            .into_boxed()
+            .select(mfg_batch_anchor::id)
+            .filter(
+                mfg_batch_anchor::owner
+                    .eq(&anchor.owner)
+                    .and(mfg_batch_anchor::commit_num.eq(anchor.commit_num)),
+            );
+
+        if let Some(service_id) = &anchor.service_id {
+            query = query.filter(mfg_batch_anchor::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_anchor::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_anchor(conn: &PgConnection, anchor: &NewMfgBatchAnchor) -> QueryResult<()> {
+        insert_into(mfg_batch_anchor::table)
+            .values(anchor)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn anchor_exists(conn: &SqliteConnection, anchor: &NewMfgBatchAnchor) -> QueryResult<bool> {
+        let mut query = mfg_batch_anchor::table
+            .into_boxed()
+            .select(mfg_batch_anchor::id)
+            .filter(
+                mfg_batch_anchor::owner
+                    .eq(&anchor.owner)
+                    .and(mfg_batch_anchor::commit_num.eq(anchor.commit_num)),
+            );
+
+        if let Some(service_id) = &anchor.service_id {
+            query = query.filter(mfg_batch_anchor::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_anchor::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_anchor(conn: &SqliteConnection, anchor: &NewMfgBatchAnchor) -> QueryResult<()> {
+        insert_into(mfg_batch_anchor::table)
+            .values(anchor)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
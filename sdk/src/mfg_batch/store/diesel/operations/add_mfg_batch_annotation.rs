@@ -0,0 +1,163 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::NewMfgBatchAnnotation,
+        schema::{mfg_batch_annotation, mfg_batch_property_value},
+    },
+    error::MfgBatchStoreError,
+    MfgBatchAnnotation,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::mfg_batch) trait AddMfgBatchAnnotationOperation {
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddMfgBatchAnnotationOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let annotation = NewMfgBatchAnnotation::from(annotation);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !pg::property_exists_at_commit(&*self.conn, &annotation)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Property {} of mfg_batch {} at commit {}",
+                    annotation.property_name, annotation.mfg_batch_id, annotation.commit_num
+                )));
+            }
+
+            pg::insert_annotation(&*self.conn, &annotation)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddMfgBatchAnnotationOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let annotation = NewMfgBatchAnnotation::from(annotation);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !sqlite::property_exists_at_commit(&*self.conn, &annotation)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Property {} of mfg_batch {} at commit {}",
+                    annotation.property_name, annotation.mfg_batch_id, annotation.commit_num
+                )));
+            }
+
+            sqlite::insert_annotation(&*self.conn, &annotation)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Checks that the annotated property had a value in the version of the mfg_batch that was
+    /// current as of the annotation's commit
+    pub fn property_exists_at_commit(
+        conn: &PgConnection,
+        annotation: &NewMfgBatchAnnotation,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::id)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&annotation.mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq(&annotation.property_name))
+                    .and(mfg_batch_property_value::start_commit_num.le(annotation.commit_num))
+                    .and(mfg_batch_property_value::end_commit_num.gt(annotation.commit_num)),
+            );
+
+        if let Some(service_id) = &annotation.service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_annotation(
+        conn: &PgConnection,
+        annotation: &NewMfgBatchAnnotation,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_annotation::table)
+            .values(annotation)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Checks that the annotated property had a value in the version of the mfg_batch that was
+    /// current as of the annotation's commit
+    pub fn property_exists_at_commit(
+        conn: &SqliteConnection,
+        annotation: &NewMfgBatchAnnotation,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::id)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&annotation.mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq(&annotation.property_name))
+                    .and(mfg_batch_property_value::start_commit_num.le(annotation.commit_num))
+                    .and(mfg_batch_property_value::end_commit_num.gt(annotation.commit_num)),
+            );
+
+        if let Some(service_id) = &annotation.service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_annotation(
+        conn: &SqliteConnection,
+        annotation: &NewMfgBatchAnnotation,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_annotation::table)
+            .values(annotation)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::NewMfgBatchRecall,
+        schema::{mfg_batch, mfg_batch_recall},
+    },
+    error::MfgBatchStoreError,
+    MfgBatchRecall,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::mfg_batch) trait AddMfgBatchRecallsOperation {
+    fn add_mfg_batch_recalls(&self, recalls: Vec<MfgBatchRecall>)
+        -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddMfgBatchRecallsOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let recalls = recalls
+            .into_iter()
+            .map(NewMfgBatchRecall::from)
+            .collect::<Vec<_>>();
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            for recall in &recalls {
+                if !pg::mfg_batch_exists_at_commit(&*self.conn, recall)? {
+                    return Err(MfgBatchStoreError::NotFoundError(format!(
+                        "Mfg_batch {} at commit {}",
+                        recall.mfg_batch_id, recall.commit_num
+                    )));
+                }
+            }
+
+            pg::insert_recalls(&*self.conn, &recalls)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddMfgBatchRecallsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let recalls = recalls
+            .into_iter()
+            .map(NewMfgBatchRecall::from)
+            .collect::<Vec<_>>();
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            for recall in &recalls {
+                if !sqlite::mfg_batch_exists_at_commit(&*self.conn, recall)? {
+                    return Err(MfgBatchStoreError::NotFoundError(format!(
+                        "Mfg_batch {} at commit {}",
+                        recall.mfg_batch_id, recall.commit_num
+                    )));
+                }
+            }
+
+            sqlite::insert_recalls(&*self.conn, &recalls)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Checks that a version of the mfg_batch was current as of the recall's commit
+    pub fn mfg_batch_exists_at_commit(
+        conn: &PgConnection,
+        recall: &NewMfgBatchRecall,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(&recall.mfg_batch_id)
+                .and(mfg_batch::start_commit_num.le(recall.commit_num))
+                .and(mfg_batch::end_commit_num.gt(recall.commit_num)),
+        );
+
+        if let Some(service_id) = &recall.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_recalls(conn: &PgConnection, recalls: &[NewMfgBatchRecall]) -> QueryResult<()> {
+        insert_into(mfg_batch_recall::table)
+            .values(recalls)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Checks that a version of the mfg_batch was current as of the recall's commit
+    pub fn mfg_batch_exists_at_commit(
+        conn: &SqliteConnection,
+        recall: &NewMfgBatchRecall,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(&recall.mfg_batch_id)
+                .and(mfg_batch::start_commit_num.le(recall.commit_num))
+                .and(mfg_batch::end_commit_num.gt(recall.commit_num)),
+        );
+
+        if let Some(service_id) = &recall.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_recalls(
+        conn: &SqliteConnection,
+        recalls: &[NewMfgBatchRecall],
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_recall::table)
+            .values(recalls)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::NewMfgBatchTestResult,
+        schema::{mfg_batch, mfg_batch_test_result},
+    },
+    error::MfgBatchStoreError,
+    MfgBatchTestResult,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::mfg_batch) trait AddMfgBatchTestResultOperation {
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddMfgBatchTestResultOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        let test_result = NewMfgBatchTestResult::from(test_result);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !pg::mfg_batch_exists_at_commit(&*self.conn, &test_result)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Mfg_batch {} at commit {}",
+                    test_result.mfg_batch_id, test_result.commit_num
+                )));
+            }
+
+            pg::insert_test_result(&*self.conn, &test_result)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddMfgBatchTestResultOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        let test_result = NewMfgBatchTestResult::from(test_result);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !sqlite::mfg_batch_exists_at_commit(&*self.conn, &test_result)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Mfg_batch {} at commit {}",
+                    test_result.mfg_batch_id, test_result.commit_num
+                )));
+            }
+
+            sqlite::insert_test_result(&*self.conn, &test_result)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Checks that a version of the mfg_batch was current as of the test result's commit
+    pub fn mfg_batch_exists_at_commit(
+        conn: &PgConnection,
+        test_result: &NewMfgBatchTestResult,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(&test_result.mfg_batch_id)
+                .and(mfg_batch::start_commit_num.le(test_result.commit_num))
+                .and(mfg_batch::end_commit_num.gt(test_result.commit_num)),
+        );
+
+        if let Some(service_id) = &test_result.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_test_result(
+        conn: &PgConnection,
+        test_result: &NewMfgBatchTestResult,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_test_result::table)
+            .values(test_result)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Checks that a version of the mfg_batch was current as of the test result's commit
+    pub fn mfg_batch_exists_at_commit(
+        conn: &SqliteConnection,
+        test_result: &NewMfgBatchTestResult,
+    ) -> QueryResult<bool> {
+        let mut query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(&test_result.mfg_batch_id)
+                .and(mfg_batch::start_commit_num.le(test_result.commit_num))
+                .and(mfg_batch::end_commit_num.gt(test_result.commit_num)),
+        );
+
+        if let Some(service_id) = &test_result.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<i64>(conn).optional().map(|id| id.is_some())
+    }
+
+    pub fn insert_test_result(
+        conn: &SqliteConnection,
+        test_result: &NewMfgBatchTestResult,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_test_result::table)
+            .values(test_result)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::add_mfg_batch::pg as pg_add;
+#[cfg(feature = "sqlite")]
+use super::add_mfg_batch::sqlite as sqlite_add;
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::change_capture::pg as pg_change_capture;
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "sqlite"))]
+use crate::mfg_batch::store::diesel::change_capture::sqlite as sqlite_change_capture;
+#[cfg(all(feature = "mfg-batch-text-search", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::text_search;
+use crate::mfg_batch::store::{
+    diesel::{
+        models::{
+            make_attachments, NewMfgBatch, NewMfgBatchAttachment, NewMfgBatchParent,
+            NewMfgBatchPropertyValue,
+        },
+        schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
+    },
+    error::MfgBatchStoreError,
+    MfgBatch,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+/// The rows written for a single mfg_batch
+type MfgBatchModels = (
+    NewMfgBatch,
+    Vec<NewMfgBatchPropertyValue>,
+    Vec<NewMfgBatchParent>,
+    Vec<NewMfgBatchAttachment>,
+);
+
+pub(in crate::mfg_batch) trait AddMfgBatchesOperation {
+    fn add_mfg_batches(
+        &self,
+        mfg_batches: Vec<MfgBatch>,
+        chunk_size: usize,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_mfg_batches(
+        &self,
+        mfg_batches: Vec<MfgBatch>,
+        chunk_size: usize,
+    ) -> Result<(), MfgBatchStoreError> {
+        for chunk in chunk_mfg_batches(mfg_batches, chunk_size) {
+            let models = chunk.into_iter().map(into_models).collect::<Vec<_>>();
+
+            self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+                pg::insert_chunk(&*self.conn, &models)?;
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn add_mfg_batches(
+        &self,
+        mfg_batches: Vec<MfgBatch>,
+        chunk_size: usize,
+    ) -> Result<(), MfgBatchStoreError> {
+        for chunk in chunk_mfg_batches(mfg_batches, chunk_size) {
+            let models = chunk.into_iter().map(into_models).collect::<Vec<_>>();
+
+            self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+                sqlite::insert_chunk(&*self.conn, &models)?;
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits mfg_batches into chunks of at most `chunk_size`, in their original order. A chunk is
+/// also closed early if the next mfg_batch is already in it, since the earlier version has to be
+/// written before the later one can end it.
+fn chunk_mfg_batches(mfg_batches: Vec<MfgBatch>, chunk_size: usize) -> Vec<Vec<MfgBatch>> {
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = Vec::new();
+    let mut chunk: Vec<MfgBatch> = Vec::with_capacity(chunk_size);
+    let mut keys = HashSet::new();
+
+    for mfg_batch in mfg_batches {
+        let key = (
+            mfg_batch.mfg_batch_id().to_string(),
+            mfg_batch.service_id().map(String::from),
+        );
+
+        if chunk.len() == chunk_size || keys.contains(&key) {
+            chunks.push(std::mem::replace(
+                &mut chunk,
+                Vec::with_capacity(chunk_size),
+            ));
+            keys.clear();
+        }
+
+        keys.insert(key);
+        chunk.push(mfg_batch);
+    }
+
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+fn into_models(mfg_batch: MfgBatch) -> MfgBatchModels {
+    let attachments = make_attachments(&mfg_batch);
+    let (mfg_batch, property_values, parents) = mfg_batch.into();
+
+    (mfg_batch, property_values, parents, attachments)
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Ends the current rows of every mfg_batch in the chunk, then inserts the new rows with one
+    /// statement per table
+    pub fn insert_chunk(conn: &PgConnection, models: &[MfgBatchModels]) -> QueryResult<()> {
+        for (mfg_batch, _property_values, _parents, _) in models {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            pg_change_capture::record_changes(conn, mfg_batch, _property_values, _parents)?;
+
+            let service_id = mfg_batch.service_id.as_deref();
+            pg_add::update_prod_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            pg_add::update_prod_property_values(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            pg_add::update_parent_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            pg_add::update_attachment_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+        }
+
+        let mfg_batches = models
+            .iter()
+            .map(|(mfg_batch, _, _, _)| mfg_batch)
+            .collect::<Vec<_>>();
+        let property_values = models
+            .iter()
+            .flat_map(|(_, property_values, _, _)| property_values)
+            .collect::<Vec<_>>();
+        let parents = models
+            .iter()
+            .flat_map(|(_, _, parents, _)| parents)
+            .collect::<Vec<_>>();
+        let attachments = models
+            .iter()
+            .flat_map(|(_, _, _, attachments)| attachments)
+            .collect::<Vec<_>>();
+
+        insert_into(mfg_batch::table)
+            .values(mfg_batches)
+            .execute(conn)?;
+        if !property_values.is_empty() {
+            insert_into(mfg_batch_property_value::table)
+                .values(property_values)
+                .execute(conn)?;
+        }
+        if !parents.is_empty() {
+            insert_into(mfg_batch_parent::table)
+                .values(parents)
+                .execute(conn)?;
+        }
+        if !attachments.is_empty() {
+            insert_into(mfg_batch_attachment::table)
+                .values(attachments)
+                .execute(conn)?;
+        }
+
+        #[cfg(feature = "mfg-batch-text-search")]
+        for (mfg_batch, _, _, _) in models {
+            text_search::reindex(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                mfg_batch.service_id.as_deref(),
+            )?;
+        }
+
+        #[cfg(feature = "mfg-batch-audit-log")]
+        for (mfg_batch, property_values, parents, _) in models {
+            pg_add::append_audit_entry(conn, mfg_batch, property_values, parents)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Ends the current rows of every mfg_batch in the chunk, then inserts the new rows with one
+    /// statement per table
+    pub fn insert_chunk(conn: &SqliteConnection, models: &[MfgBatchModels]) -> QueryResult<()> {
+        for (mfg_batch, _property_values, _parents, _) in models {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            sqlite_change_capture::record_changes(conn, mfg_batch, _property_values, _parents)?;
+
+            let service_id = mfg_batch.service_id.as_deref();
+            sqlite_add::update_prod_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            sqlite_add::update_prod_property_values(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            sqlite_add::update_parent_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+            sqlite_add::update_attachment_end_commit_num(
+                conn,
+                &mfg_batch.mfg_batch_id,
+                service_id,
+                mfg_batch.start_commit_num,
+            )?;
+        }
+
+        let mfg_batches = models
+            .iter()
+            .map(|(mfg_batch, _, _, _)| mfg_batch)
+            .collect::<Vec<_>>();
+        let property_values = models
+            .iter()
+            .flat_map(|(_, property_values, _, _)| property_values)
+            .collect::<Vec<_>>();
+        let parents = models
+            .iter()
+            .flat_map(|(_, _, parents, _)| parents)
+            .collect::<Vec<_>>();
+        let attachments = models
+            .iter()
+            .flat_map(|(_, _, _, attachments)| attachments)
+            .collect::<Vec<_>>();
+
+        insert_into(mfg_batch::table)
+            .values(mfg_batches)
+            .execute(conn)?;
+        if !property_values.is_empty() {
+            insert_into(mfg_batch_property_value::table)
+                .values(property_values)
+                .execute(conn)?;
+        }
+        if !parents.is_empty() {
+            insert_into(mfg_batch_parent::table)
+                .values(parents)
+                .execute(conn)?;
+        }
+        if !attachments.is_empty() {
+            insert_into(mfg_batch_attachment::table)
+                .values(attachments)
+                .execute(conn)?;
+        }
+
+        #[cfg(feature = "mfg-batch-audit-log")]
+        for (mfg_batch, property_values, parents, _) in models {
+            sqlite_add::append_audit_entry(conn, mfg_batch, property_values, parents)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::{store::MfgBatchBuilder, MAX_COMMIT_NUM};
+
+    fn mfg_batch(mfg_batch_id: &str, commit_num: i64) -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
+            .with_mfg_batch_address(format!("address-{}", mfg_batch_id))
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner("org-1".to_string())
+            .with_start_commit_number(commit_num)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Unable to build mfg_batch")
+    }
+
+    fn chunk_ids(chunks: &[Vec<MfgBatch>]) -> Vec<Vec<&str>> {
+        chunks
+            .iter()
+            .map(|chunk| chunk.iter().map(MfgBatch::mfg_batch_id).collect())
+            .collect()
+    }
+
+    /// Verify that mfg_batches are split into chunks of the given size, in order
+    #[test]
+    fn test_chunk_mfg_batches_by_size() {
+        let mfg_batches = vec![
+            mfg_batch("a", 1),
+            mfg_batch("b", 1),
+            mfg_batch("c", 1),
+            mfg_batch("d", 1),
+            mfg_batch("e", 1),
+        ];
+
+        let chunks = chunk_mfg_batches(mfg_batches, 2);
+
+        assert_eq!(
+            chunk_ids(&chunks),
+            vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]]
+        );
+    }
+
+    /// Verify that a second version of a mfg_batch starts a new chunk
+    #[test]
+    fn test_chunk_mfg_batches_repeated_id() {
+        let mfg_batches = vec![mfg_batch("a", 1), mfg_batch("b", 1), mfg_batch("a", 2)];
+
+        let chunks = chunk_mfg_batches(mfg_batches, 10);
+
+        assert_eq!(chunk_ids(&chunks), vec![vec!["a", "b"], vec!["a"]]);
+        assert!(chunk_mfg_batches(vec![], 10).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{diesel::schema::mfg_batch_recall, error::MfgBatchStoreError};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::mfg_batch) trait CloseMfgBatchRecallOperation {
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CloseMfgBatchRecallOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let ids = pg::get_active_recall_ids(&*self.conn, recall_id, service_id)?;
+            if ids.is_empty() {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Active recall {}",
+                    recall_id
+                )));
+            }
+
+            update(mfg_batch_recall::table.filter(mfg_batch_recall::id.eq_any(ids)))
+                .set(mfg_batch_recall::closed_at.eq(closed_at))
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CloseMfgBatchRecallOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let ids = sqlite::get_active_recall_ids(&*self.conn, recall_id, service_id)?;
+            if ids.is_empty() {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Active recall {}",
+                    recall_id
+                )));
+            }
+
+            update(mfg_batch_recall::table.filter(mfg_batch_recall::id.eq_any(ids)))
+                .set(mfg_batch_recall::closed_at.eq(closed_at))
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Fetches the rows of the recall that have not been closed yet
+    pub fn get_active_recall_ids(
+        conn: &PgConnection,
+        recall_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<i64>> {
+        let mut query = mfg_batch_recall::table
+            .into_boxed()
+            .select(mfg_batch_recall::id)
+            .filter(
+                mfg_batch_recall::recall_id
+                    .eq(recall_id)
+                    .and(mfg_batch_recall::closed_at.is_null()),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_recall::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_recall::service_id.is_null());
+        }
+
+        query.load::<i64>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Fetches the rows of the recall that have not been closed yet
+    pub fn get_active_recall_ids(
+        conn: &SqliteConnection,
+        recall_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<i64>> {
+        let mut query = mfg_batch_recall::table
+            .into_boxed()
+            .select(mfg_batch_recall::id)
+            .filter(
+                mfg_batch_recall::recall_id
+                    .eq(recall_id)
+                    .and(mfg_batch_recall::closed_at.is_null()),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_recall::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_recall::service_id.is_null());
+        }
+
+        query.load::<i64>(conn)
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::schema::{mfg_batch, mfg_batch_property_value},
+    error::MfgBatchStoreError,
+    MfgBatchVersionRows,
+};
+use diesel::prelude::*;
+
+/// The commits a mfg_batch version was current between
+type VersionRange = (i64, i64);
+
+/// The property name, parent property and commit range of a property value row
+type PropertyRow = (String, Option<String>, i64, i64);
+
+pub(in crate::mfg_batch) trait CountMfgBatchVersionRowsOperation {
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CountMfgBatchVersionRowsOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let versions = pg::list_version_ranges(&*self.conn, mfg_batch_id, service_id)?;
+            let rows = pg::list_property_rows(&*self.conn, mfg_batch_id, service_id)?;
+
+            Ok(count_version_rows(mfg_batch_id, versions, &rows))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CountMfgBatchVersionRowsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let versions = sqlite::list_version_ranges(&*self.conn, mfg_batch_id, service_id)?;
+            let rows = sqlite::list_property_rows(&*self.conn, mfg_batch_id, service_id)?;
+
+            Ok(count_version_rows(mfg_batch_id, versions, &rows))
+        })
+    }
+}
+
+/// Counts the property value rows current at the start of each version, attributing the rows
+/// of struct values to the top-level property they belong to
+fn count_version_rows(
+    mfg_batch_id: &str,
+    versions: Vec<VersionRange>,
+    rows: &[PropertyRow],
+) -> Vec<MfgBatchVersionRows> {
+    // Struct values name their parent as "<mfg_batch_id>:<property_name>"
+    let parent_prefix = format!("{}:", mfg_batch_id);
+
+    versions
+        .into_iter()
+        .map(|(start_commit_num, end_commit_num)| {
+            let current = rows
+                .iter()
+                .filter(|(_, _, start, end)| *start <= start_commit_num && start_commit_num < *end)
+                .collect::<Vec<_>>();
+
+            let parents = current
+                .iter()
+                .filter_map(|(name, parent, _, _)| {
+                    parent
+                        .as_deref()
+                        .map(|parent| parent.strip_prefix(&parent_prefix).unwrap_or(parent))
+                        .map(|parent| (name.as_str(), parent))
+                })
+                .collect::<HashMap<_, _>>();
+
+            let mut property_rows = BTreeMap::new();
+            for (name, _, _, _) in &current {
+                let mut top_level = name.as_str();
+                // The depth is bounded by the number of rows, should the names form a cycle
+                for _ in 0..current.len() {
+                    match parents.get(top_level) {
+                        Some(parent) => top_level = parent,
+                        None => break,
+                    }
+                }
+                *property_rows.entry(top_level.to_string()).or_insert(0) += 1;
+            }
+
+            MfgBatchVersionRows {
+                start_commit_num,
+                end_commit_num,
+                property_rows: property_rows.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn list_version_ranges(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<VersionRange>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select((mfg_batch::start_commit_num, mfg_batch::end_commit_num))
+            .filter(mfg_batch::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::start_commit_num.asc())
+            .load::<VersionRange>(conn)
+    }
+
+    pub fn list_property_rows(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<PropertyRow>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select((
+                mfg_batch_property_value::property_name,
+                mfg_batch_property_value::parent_property,
+                mfg_batch_property_value::start_commit_num,
+                mfg_batch_property_value::end_commit_num,
+            ))
+            .filter(mfg_batch_property_value::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<PropertyRow>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn list_version_ranges(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<VersionRange>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select((mfg_batch::start_commit_num, mfg_batch::end_commit_num))
+            .filter(mfg_batch::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::start_commit_num.asc())
+            .load::<VersionRange>(conn)
+    }
+
+    pub fn list_property_rows(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<PropertyRow>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select((
+                mfg_batch_property_value::property_name,
+                mfg_batch_property_value::parent_property,
+                mfg_batch_property_value::start_commit_num,
+                mfg_batch_property_value::end_commit_num,
+            ))
+            .filter(mfg_batch_property_value::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<PropertyRow>(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::MAX_COMMIT_NUM;
+
+    fn row(name: &str, parent: Option<&str>, start: i64, end: i64) -> PropertyRow {
+        (name.to_string(), parent.map(String::from), start, end)
+    }
+
+    /// Verify that each version counts the rows current at its start, and that the rows of
+    /// struct values, however deeply nested, count toward their top-level property
+    #[test]
+    fn test_count_version_rows() {
+        let rows = vec![
+            row("color", None, 1, 3),
+            row("color", None, 3, MAX_COMMIT_NUM),
+            row("dimensions", None, 1, MAX_COMMIT_NUM),
+            row("width", Some("batch1:dimensions"), 1, MAX_COMMIT_NUM),
+            row("height", Some("batch1:dimensions"), 1, MAX_COMMIT_NUM),
+            row("unit", Some("batch1:height"), 3, MAX_COMMIT_NUM),
+        ];
+
+        let counts = count_version_rows("batch1", vec![(1, 3), (3, MAX_COMMIT_NUM)], &rows);
+
+        assert_eq!(
+            counts,
+            vec![
+                MfgBatchVersionRows {
+                    start_commit_num: 1,
+                    end_commit_num: 3,
+                    property_rows: vec![("color".to_string(), 1), ("dimensions".to_string(), 3)],
+                },
+                MfgBatchVersionRows {
+                    start_commit_num: 3,
+                    end_commit_num: MAX_COMMIT_NUM,
+                    property_rows: vec![("color".to_string(), 1), ("dimensions".to_string(), 4)],
+                },
+            ]
+        );
+        assert_eq!(counts[1].total_property_rows(), 5);
+    }
+}
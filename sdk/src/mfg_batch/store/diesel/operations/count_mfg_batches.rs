@@ -0,0 +1,87 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::list_mfg_batches::pg as pg_list;
+#[cfg(feature = "sqlite")]
+use super::list_mfg_batches::sqlite as sqlite_list;
+use crate::mfg_batch::store::{error::MfgBatchStoreError, ListMfgBatchFilters};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait CountMfgBatchesOperation {
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CountMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        pg::count_mfg_batches(self.conn, service_id, filters).map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CountMfgBatchesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        sqlite::count_mfg_batches(self.conn, service_id, filters).map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub(super) mod pg {
+    use super::*;
+
+    /// Counts the current mfg_batches the list returns for the same filters
+    pub fn count_mfg_batches(
+        conn: &PgConnection,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> QueryResult<i64> {
+        pg_list::list_query(service_id, filters)
+            .count()
+            .get_result::<i64>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(super) mod sqlite {
+    use super::*;
+
+    /// Counts the current mfg_batches the list returns for the same filters
+    pub fn count_mfg_batches(
+        conn: &SqliteConnection,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> QueryResult<i64> {
+        sqlite_list::list_query(service_id, filters)
+            .count()
+            .get_result::<i64>(conn)
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On Postgres the mfg_batch and mfg_batch_property_value tables are partitioned by
+//! `end_commit_num`. Current rows all have an `end_commit_num` of `MAX_COMMIT_NUM` and live in
+//! their own partition, so current-state queries, which always filter on it, are pruned to that
+//! partition. Rows are moved into an archive partition when a later version ends them; archive
+//! partitions cover a range of commits and are created ahead of time by the store's caller.
+
+use super::MfgBatchStoreOperations;
+
+use crate::error::InvalidArgumentError;
+use crate::mfg_batch::{store::error::MfgBatchStoreError, MAX_COMMIT_NUM};
+
+use diesel::prelude::*;
+
+/// The partitioned tables and the prefix of their archive partitions' names
+#[cfg(feature = "postgres")]
+const PARTITIONED_TABLES: [(&str, &str); 2] = [
+    ("mfg_batch", "mfg_batch_archive"),
+    (
+        "mfg_batch_property_value",
+        "mfg_batch_property_value_archive",
+    ),
+];
+
+pub(in crate::mfg_batch) trait CreateMfgBatchArchivePartitionOperation {
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CreateMfgBatchArchivePartitionOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        check_range(from_commit_num, to_commit_num)?;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            for (table, prefix) in PARTITIONED_TABLES.iter() {
+                diesel::sql_query(format!(
+                    "CREATE TABLE IF NOT EXISTS {prefix}_{from}_{to} PARTITION OF {table} \
+                     FOR VALUES FROM ({from}) TO ({to})",
+                    prefix = prefix,
+                    table = table,
+                    from = from_commit_num,
+                    to = to_commit_num,
+                ))
+                .execute(&*self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// SQLite has no table partitioning, so every row stays in the one table
+#[cfg(feature = "sqlite")]
+impl<'a> CreateMfgBatchArchivePartitionOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        check_range(from_commit_num, to_commit_num)
+    }
+}
+
+/// Archive partitions must cover a non-empty range of commits that stops short of the current
+/// partition
+fn check_range(from_commit_num: i64, to_commit_num: i64) -> Result<(), MfgBatchStoreError> {
+    if from_commit_num < 0 || from_commit_num >= to_commit_num {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "from_commit_num".to_string(),
+                format!(
+                    "must be non-negative and less than to_commit_num ({})",
+                    to_commit_num
+                ),
+            ),
+        ));
+    }
+
+    if to_commit_num == MAX_COMMIT_NUM {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "to_commit_num".to_string(),
+                "must be less than the commit number reserved for current rows".to_string(),
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that only non-empty ranges below the current partition are accepted
+    #[test]
+    fn test_check_range() {
+        assert!(check_range(0, 1000).is_ok());
+        assert!(check_range(1000, 1000).is_err());
+        assert!(check_range(-1, 1000).is_err());
+        assert!(check_range(0, MAX_COMMIT_NUM).is_err());
+    }
+}
@@ -14,9 +14,16 @@
 
 use super::MfgBatchStoreOperations;
 
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::change_capture::pg as pg_change_capture;
+#[cfg(all(feature = "mfg-batch-change-capture", feature = "sqlite"))]
+use crate::mfg_batch::store::diesel::change_capture::sqlite as sqlite_change_capture;
 use crate::mfg_batch::{
     store::{
-        diesel::schema::{mfg_batch, mfg_batch_property_value},
+        diesel::{
+            models::last_updated_at,
+            schema::{mfg_batch, mfg_batch_property_value},
+        },
         error::MfgBatchStoreError,
     },
     MAX_COMMIT_NUM,
@@ -39,6 +46,8 @@ impl<'a> DeleteMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgC
         current_commit_num: i64,
     ) -> Result<(), MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            pg_change_capture::record_deletion(&*self.conn, address, current_commit_num)?;
             pg::delete_mfg_batch(&*self.conn, address, current_commit_num)?;
             pg::delete_mfg_batch_property_values(&*self.conn, address, current_commit_num)?;
 
@@ -55,6 +64,8 @@ impl<'a> DeleteMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite:
         current_commit_num: i64,
     ) -> Result<(), MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            #[cfg(feature = "mfg-batch-change-capture")]
+            sqlite_change_capture::record_deletion(&*self.conn, address, current_commit_num)?;
             sqlite::delete_mfg_batch(&*self.conn, address, current_commit_num)?;
             sqlite::delete_mfg_batch_property_values(&*self.conn, address, current_commit_num)?;
 
@@ -78,7 +89,10 @@ mod pg {
                     .eq(address)
                     .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
             )
-            .set(mfg_batch::end_commit_num.eq(current_commit_num))
+            .set((
+                mfg_batch::end_commit_num.eq(current_commit_num),
+                mfg_batch::last_updated.eq(Some(last_updated_at(None))),
+            ))
             .execute(conn)
             .map(|_| ())
     }
@@ -115,7 +129,10 @@ mod sqlite {
                     .eq(address)
                     .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
             )
-            .set(mfg_batch::end_commit_num.eq(current_commit_num))
+            .set((
+                mfg_batch::end_commit_num.eq(current_commit_num),
+                mfg_batch::last_updated.eq(Some(last_updated_at(None))),
+            ))
             .execute(conn)
             .map(|_| ())
     }
@@ -0,0 +1,135 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::list_mfg_batches::pg as pg_list;
+#[cfg(feature = "sqlite")]
+use super::list_mfg_batches::sqlite as sqlite_list;
+#[cfg(feature = "postgres")]
+use crate::mfg_batch::store::diesel::explain::pg as pg_explain;
+#[cfg(feature = "sqlite")]
+use crate::mfg_batch::store::diesel::explain::sqlite as sqlite_explain;
+use crate::mfg_batch::store::{error::MfgBatchStoreError, ListMfgBatchFilters, QueryPlan};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ExplainListMfgBatchesOperation {
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ExplainListMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        let query = pg_list::list_query(service_id, filters)
+            .limit(limit)
+            .offset(offset);
+        let sql = diesel::debug_query::<diesel::pg::Pg, _>(&query).to_string();
+
+        let plan = pg_explain::Explain(query).load::<String>(self.conn)?;
+
+        Ok(QueryPlan { sql, plan })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ExplainListMfgBatchesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        let query = sqlite_list::list_query(service_id, filters)
+            .limit(limit)
+            .offset(offset);
+        let sql = diesel::debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string();
+
+        let plan = sqlite_explain::Explain(query)
+            .load::<(i32, i32, i32, String)>(self.conn)?
+            .into_iter()
+            .map(|(_, _, _, detail)| detail)
+            .collect();
+
+        Ok(QueryPlan { sql, plan })
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    /// Verify that the SQL has only the filters given and the plan uses the matching index
+    #[test]
+    fn test_explain_list_mfg_batches() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE INDEX mfg_batch_expiration_date_idx ON mfg_batch (expiration_date);",
+        )
+        .expect("Failed to create table");
+
+        let filters = ListMfgBatchFilters {
+            expiring_before: Some(1_631_536_000),
+            ..ListMfgBatchFilters::default()
+        };
+
+        let plan = MfgBatchStoreOperations::new(&conn)
+            .explain_list_mfg_batches(None, &filters, 0, 10)
+            .expect("Failed to explain list");
+
+        assert!(plan.sql.contains("`expiration_date` <"));
+        assert!(!plan.sql.contains("`owner` ="));
+        assert!(plan
+            .plan
+            .iter()
+            .any(|step| step.contains("mfg_batch_expiration_date_idx")));
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::search_mfg_batches_text::check_query;
+#[cfg(feature = "sqlite")]
+use super::search_mfg_batches_text::sqlite as sqlite_search;
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "sqlite")]
+use crate::mfg_batch::store::diesel::explain::sqlite as sqlite_explain;
+#[cfg(feature = "postgres")]
+use crate::mfg_batch::store::diesel::text_search;
+use crate::mfg_batch::store::{error::MfgBatchStoreError, QueryPlan};
+
+#[cfg(feature = "sqlite")]
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ExplainSearchMfgBatchesTextOperation {
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ExplainSearchMfgBatchesTextOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        check_query(query)?;
+
+        Ok(text_search::explain_find_mfg_batch_ids(
+            self.conn, query, service_id, offset, limit,
+        )?)
+    }
+}
+
+/// A search on SQLite runs one query per word and pages the matches in memory, so the plan lists
+/// each word's SQL and plan in turn, and the offset and limit do not appear in it
+#[cfg(feature = "sqlite")]
+impl<'a> ExplainSearchMfgBatchesTextOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        _offset: i64,
+        _limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        check_query(query)?;
+
+        let mut sql = Vec::new();
+        let mut plan = Vec::new();
+        for word in query.split_whitespace() {
+            let word_query = sqlite_search::word_query(word, service_id);
+            sql.push(diesel::debug_query::<diesel::sqlite::Sqlite, _>(&word_query).to_string());
+
+            plan.extend(
+                sqlite_explain::Explain(word_query)
+                    .load::<(i32, i32, i32, String)>(self.conn)?
+                    .into_iter()
+                    .map(|(_, _, _, detail)| detail),
+            );
+        }
+
+        Ok(QueryPlan {
+            sql: sql.join("\n"),
+            plan,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    /// Verify that a search is explained with one query per word and that an empty query is
+    /// rejected
+    #[test]
+    fn test_explain_search_mfg_batches_text() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch_property_value (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                property_name TEXT NOT NULL,
+                parent_property TEXT,
+                data_type TEXT NOT NULL,
+                bytes_value BLOB,
+                boolean_value BOOLEAN,
+                number_value BIGINT,
+                string_value TEXT,
+                enum_value INTEGER,
+                latitude_value BIGINT,
+                longitude_value BIGINT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                language TEXT
+            );
+            CREATE INDEX mfg_batch_property_value_mfg_batch_id_idx
+                ON mfg_batch_property_value (mfg_batch_id);",
+        )
+        .expect("Failed to create table");
+
+        let operations = MfgBatchStoreOperations::new(&conn);
+
+        let plan = operations
+            .explain_search_mfg_batches_text("wheat flour", None, 0, 10)
+            .expect("Failed to explain search");
+
+        let sql = plan.sql.lines().collect::<Vec<_>>();
+        assert_eq!(sql.len(), 2);
+        assert!(sql[0].contains("%wheat%"));
+        assert!(sql[1].contains("%flour%"));
+        assert!(plan.sql.contains("`service_id` IS NULL"));
+        assert!(!plan.plan.is_empty());
+
+        assert!(matches!(
+            operations.explain_search_mfg_batches_text(" ", None, 0, 10),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+    }
+}
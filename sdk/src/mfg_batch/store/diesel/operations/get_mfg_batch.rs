@@ -12,13 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "mfg-batch-merge")]
+use super::resolve_mfg_batch_alias::ResolveMfgBatchAliasOperation;
 use super::MfgBatchStoreOperations;
 
 use crate::mfg_batch::{
     store::{
         diesel::{
-            models::{MfgBatch as ModelMfgBatch, MfgBatchPropertyValue},
-            schema::{mfg_batch, mfg_batch_property_value},
+            models::{
+                MfgBatch as ModelMfgBatch, MfgBatchAttachment, MfgBatchParent,
+                MfgBatchPropertyValue,
+            },
+            schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
         },
         error::MfgBatchStoreError,
         MfgBatch, PropertyValue,
@@ -26,6 +31,7 @@ use crate::mfg_batch::{
     MAX_COMMIT_NUM,
 };
 use diesel::{prelude::*, result::Error::NotFound};
+use std::collections::HashMap;
 
 pub(in crate::mfg_batch) trait GetMfgBatchOperation {
     fn get_mfg_batch(
@@ -43,6 +49,12 @@ impl<'a> GetMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConn
         service_id: Option<&str>,
     ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // Lookups by the id of a merged mfg_batch resolve to the one it was merged into
+            #[cfg(feature = "mfg-batch-merge")]
+            let merged_into = self.resolve_mfg_batch_alias(mfg_batch_id, service_id)?;
+            #[cfg(feature = "mfg-batch-merge")]
+            let mfg_batch_id = merged_into.as_deref().unwrap_or(mfg_batch_id);
+
             let mfg_batch =
                 if let Some(mfg_batch) = pg::get_mfg_batch(&*self.conn, mfg_batch_id, service_id)? {
                     mfg_batch
@@ -54,7 +66,15 @@ impl<'a> GetMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConn
 
             let values = pg::get_property_values(&*self.conn, root_values)?;
 
-            Ok(Some(MfgBatch::from((mfg_batch, values))))
+            let parents = pg::get_parents(&*self.conn, mfg_batch_id, service_id)?;
+            let attachments = pg::get_attachments(&*self.conn, mfg_batch_id, service_id)?;
+
+            Ok(Some(MfgBatch::from((
+                mfg_batch,
+                values,
+                parents,
+                attachments,
+            ))))
         })
     }
 }
@@ -67,6 +87,12 @@ impl<'a> GetMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::Sq
         service_id: Option<&str>,
     ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // Lookups by the id of a merged mfg_batch resolve to the one it was merged into
+            #[cfg(feature = "mfg-batch-merge")]
+            let merged_into = self.resolve_mfg_batch_alias(mfg_batch_id, service_id)?;
+            #[cfg(feature = "mfg-batch-merge")]
+            let mfg_batch_id = merged_into.as_deref().unwrap_or(mfg_batch_id);
+
             let mfg_batch =
                 if let Some(mfg_batch) = sqlite::get_mfg_batch(&*self.conn, mfg_batch_id, service_id)? {
                     mfg_batch
@@ -78,13 +104,76 @@ impl<'a> GetMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::Sq
 
             let values = sqlite::get_property_values(&*self.conn, root_values)?;
 
-            Ok(Some(MfgBatch::from((mfg_batch, values))))
+            let parents = sqlite::get_parents(&*self.conn, mfg_batch_id, service_id)?;
+            let attachments = sqlite::get_attachments(&*self.conn, mfg_batch_id, service_id)?;
+
+            Ok(Some(MfgBatch::from((
+                mfg_batch,
+                values,
+                parents,
+                attachments,
+            ))))
         })
     }
 }
 
+/// Struct members are keyed by their service and the value they belong to, which they name as
+/// "<mfg_batch_id>:<property_name>"
+type MemberValues = HashMap<(Option<String>, String), Vec<MfgBatchPropertyValue>>;
+
+/// Builds the property values from the root values of one or more mfg_batches and the values of
+/// all of their struct members, nesting each member under the value it belongs to
+fn assemble_property_values(
+    root_values: Vec<MfgBatchPropertyValue>,
+    member_values: Vec<MfgBatchPropertyValue>,
+) -> Vec<PropertyValue> {
+    let mut members = MemberValues::new();
+    for value in member_values {
+        if let Some(parent) = value.parent_property.clone() {
+            members
+                .entry((value.service_id.clone(), parent))
+                .or_default()
+                .push(value);
+        }
+    }
+
+    root_values
+        .into_iter()
+        .map(|value| build_value(value, &mut members))
+        .collect()
+}
+
+fn build_value(value: MfgBatchPropertyValue, members: &mut MemberValues) -> PropertyValue {
+    let key = (
+        value.service_id.clone(),
+        format!("{}:{}", value.mfg_batch_id, value.property_name),
+    );
+
+    match members.remove(&key) {
+        Some(children) => {
+            let children = children
+                .into_iter()
+                .map(|child| build_value(child, members))
+                .collect();
+            PropertyValue::from((value, children))
+        }
+        None => PropertyValue::from(value),
+    }
+}
+
+/// Returns the distinct ids of the mfg_batches the values belong to
+fn mfg_batch_ids(values: &[MfgBatchPropertyValue]) -> Vec<&str> {
+    let mut mfg_batch_ids = values
+        .iter()
+        .map(|value| value.mfg_batch_id.as_str())
+        .collect::<Vec<_>>();
+    mfg_batch_ids.sort_unstable();
+    mfg_batch_ids.dedup();
+    mfg_batch_ids
+}
+
 #[cfg(feature = "postgres")]
-mod pg {
+pub(super) mod pg {
     use super::*;
 
     pub fn get_mfg_batch(
@@ -113,6 +202,54 @@ mod pg {
             .or_else(|err| if err == NotFound { Ok(None) } else { Err(err) })
     }
 
+    pub fn get_parents(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+
     pub fn get_root_values(
         conn: &PgConnection,
         mfg_batch_id: &str,
@@ -128,34 +265,41 @@ mod pg {
             .load::<MfgBatchPropertyValue>(conn)
     }
 
+    /// Builds the property values from the root values, loading the struct members of all of
+    /// their mfg_batches in a single query
     pub fn get_property_values(
         conn: &PgConnection,
         root_values: Vec<MfgBatchPropertyValue>,
     ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
-        let mut definitions = Vec::new();
-
-        for root_value in root_values {
-            let children = mfg_batch_property_value::table
-                .select(mfg_batch_property_value::all_columns)
-                .filter(mfg_batch_property_value::parent_property.eq(&root_value.parent_property))
-                .load(conn)?;
-
-            if children.is_empty() {
-                definitions.push(PropertyValue::from(root_value));
-            } else {
-                definitions.push(PropertyValue::from((
-                    root_value,
-                    get_property_values(conn, children)?,
-                )));
-            }
+        let member_values = get_member_values(conn, &root_values)?;
+
+        Ok(assemble_property_values(root_values, member_values))
+    }
+
+    fn get_member_values(
+        conn: &PgConnection,
+        root_values: &[MfgBatchPropertyValue],
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mfg_batch_ids = mfg_batch_ids(root_values);
+        if mfg_batch_ids.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(definitions)
+        mfg_batch_property_value::table
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_property_value::parent_property.is_not_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            )
+            .order(mfg_batch_property_value::id.asc())
+            .load::<MfgBatchPropertyValue>(conn)
     }
 }
 
 #[cfg(feature = "sqlite")]
-mod sqlite {
+pub(super) mod sqlite {
     use super::*;
 
     pub fn get_mfg_batch(
@@ -184,6 +328,54 @@ mod sqlite {
             .or_else(|err| if err == NotFound { Ok(None) } else { Err(err) })
     }
 
+    pub fn get_parents(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+
     pub fn get_root_values(
         conn: &SqliteConnection,
         mfg_batch_id: &str,
@@ -199,28 +391,35 @@ mod sqlite {
             .load::<MfgBatchPropertyValue>(conn)
     }
 
+    /// Builds the property values from the root values, loading the struct members of all of
+    /// their mfg_batches in a single query
     pub fn get_property_values(
         conn: &SqliteConnection,
         root_values: Vec<MfgBatchPropertyValue>,
     ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
-        let mut definitions = Vec::new();
-
-        for root_value in root_values {
-            let children = mfg_batch_property_value::table
-                .select(mfg_batch_property_value::all_columns)
-                .filter(mfg_batch_property_value::parent_property.eq(&root_value.parent_property))
-                .load(conn)?;
-
-            if children.is_empty() {
-                definitions.push(PropertyValue::from(root_value));
-            } else {
-                definitions.push(PropertyValue::from((
-                    root_value,
-                    get_property_values(conn, children)?,
-                )));
-            }
+        let member_values = get_member_values(conn, &root_values)?;
+
+        Ok(assemble_property_values(root_values, member_values))
+    }
+
+    fn get_member_values(
+        conn: &SqliteConnection,
+        root_values: &[MfgBatchPropertyValue],
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mfg_batch_ids = mfg_batch_ids(root_values);
+        if mfg_batch_ids.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(definitions)
+        mfg_batch_property_value::table
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_property_value::parent_property.is_not_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            )
+            .order(mfg_batch_property_value::id.asc())
+            .load::<MfgBatchPropertyValue>(conn)
     }
 }
@@ -0,0 +1,150 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashSet, VecDeque};
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{diesel::schema::mfg_batch_parent, error::MfgBatchStoreError},
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetMfgBatchAncestryOperation {
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetMfgBatchAncestryOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            walk_genealogy(mfg_batch_id, |id| {
+                pg::get_parent_ids(&*self.conn, id, service_id).map_err(MfgBatchStoreError::from)
+            })
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetMfgBatchAncestryOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            walk_genealogy(mfg_batch_id, |id| {
+                sqlite::get_parent_ids(&*self.conn, id, service_id)
+                    .map_err(MfgBatchStoreError::from)
+            })
+        })
+    }
+}
+
+/// Walks the genealogy links breadth-first, in whichever direction `get_linked`
+/// follows them, returning every batch reached once in the order it was
+/// reached. The visited set guards against cycles in stored data.
+pub(super) fn walk_genealogy<F>(
+    mfg_batch_id: &str,
+    mut get_linked: F,
+) -> Result<Vec<String>, MfgBatchStoreError>
+where
+    F: FnMut(&str) -> Result<Vec<String>, MfgBatchStoreError>,
+{
+    let mut reached = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(mfg_batch_id.to_string());
+
+    let mut queue = VecDeque::new();
+    queue.push_back(mfg_batch_id.to_string());
+
+    while let Some(current) = queue.pop_front() {
+        for linked in get_linked(&current)? {
+            if visited.insert(linked.clone()) {
+                reached.push(linked.clone());
+                queue.push_back(linked);
+            }
+        }
+    }
+
+    Ok(reached)
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn get_parent_ids(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<String>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::parent_mfg_batch_id)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<String>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn get_parent_ids(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<String>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::parent_mfg_batch_id)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<String>(conn)
+    }
+}
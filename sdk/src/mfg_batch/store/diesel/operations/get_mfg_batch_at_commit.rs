@@ -0,0 +1,440 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::{
+            MfgBatch as ModelMfgBatch, MfgBatchAttachment, MfgBatchParent, MfgBatchPropertyValue,
+        },
+        schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
+    },
+    error::MfgBatchStoreError,
+    MfgBatch, PropertyValue,
+};
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetMfgBatchAtCommitOperation {
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetMfgBatchAtCommitOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mfg_batch = match pg::get_mfg_batch_at_commit(
+                &*self.conn,
+                mfg_batch_id,
+                commit_num,
+                service_id,
+            )? {
+                Some(mfg_batch) => mfg_batch,
+                None => return Ok(None),
+            };
+
+            let values = pg::get_property_values_at_commit(
+                &*self.conn,
+                mfg_batch_id,
+                commit_num,
+                service_id,
+            )?;
+            let parents =
+                pg::get_parents_at_commit(&*self.conn, mfg_batch_id, commit_num, service_id)?;
+            let attachments =
+                pg::get_attachments_at_commit(&*self.conn, mfg_batch_id, commit_num, service_id)?;
+
+            Ok(Some(MfgBatch::from((
+                mfg_batch,
+                values,
+                parents,
+                attachments,
+            ))))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetMfgBatchAtCommitOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mfg_batch = match sqlite::get_mfg_batch_at_commit(
+                &*self.conn,
+                mfg_batch_id,
+                commit_num,
+                service_id,
+            )? {
+                Some(mfg_batch) => mfg_batch,
+                None => return Ok(None),
+            };
+
+            let values = sqlite::get_property_values_at_commit(
+                &*self.conn,
+                mfg_batch_id,
+                commit_num,
+                service_id,
+            )?;
+            let parents =
+                sqlite::get_parents_at_commit(&*self.conn, mfg_batch_id, commit_num, service_id)?;
+            let attachments = sqlite::get_attachments_at_commit(
+                &*self.conn,
+                mfg_batch_id,
+                commit_num,
+                service_id,
+            )?;
+
+            Ok(Some(MfgBatch::from((
+                mfg_batch,
+                values,
+                parents,
+                attachments,
+            ))))
+        })
+    }
+}
+
+/// Struct property values are stored with their parent's ID and name as `parent_property`
+fn child_key(mfg_batch_id: &str, property_name: &str) -> String {
+    format!("{}:{}", mfg_batch_id, property_name)
+}
+
+// The helpers below select the row versions that were current as of a commit: those that started
+// at or before it and had not yet been ended by it.
+
+#[cfg(feature = "postgres")]
+pub(super) mod pg {
+    use super::*;
+
+    pub fn get_mfg_batch_at_commit(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Option<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch::start_commit_num.le(commit_num))
+                    .and(mfg_batch::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first(conn).optional()
+    }
+
+    pub fn get_parents_at_commit(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.le(commit_num))
+                    .and(mfg_batch_parent::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments_at_commit(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::start_commit_num.le(commit_num))
+                    .and(mfg_batch_attachment::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+
+    pub fn get_property_values_at_commit(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        let root_values = get_values_at_commit(conn, mfg_batch_id, None, commit_num, service_id)?;
+        build_property_values(conn, mfg_batch_id, root_values, commit_num, service_id)
+    }
+
+    pub fn build_property_values(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        values: Vec<MfgBatchPropertyValue>,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        let mut definitions = Vec::new();
+
+        for value in values {
+            let children = get_values_at_commit(
+                conn,
+                mfg_batch_id,
+                Some(&child_key(mfg_batch_id, &value.property_name)),
+                commit_num,
+                service_id,
+            )?;
+
+            if children.is_empty() {
+                definitions.push(PropertyValue::from(value));
+            } else {
+                definitions.push(PropertyValue::from((
+                    value,
+                    build_property_values(conn, mfg_batch_id, children, commit_num, service_id)?,
+                )));
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    fn get_values_at_commit(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        parent_property: Option<&str>,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.le(commit_num))
+                    .and(mfg_batch_property_value::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(parent_property) = parent_property {
+            query = query.filter(mfg_batch_property_value::parent_property.eq(parent_property));
+        } else {
+            query = query.filter(mfg_batch_property_value::parent_property.is_null());
+        }
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<MfgBatchPropertyValue>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(super) mod sqlite {
+    use super::*;
+
+    pub fn get_mfg_batch_at_commit(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Option<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch::start_commit_num.le(commit_num))
+                    .and(mfg_batch::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first(conn).optional()
+    }
+
+    pub fn get_parents_at_commit(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.le(commit_num))
+                    .and(mfg_batch_parent::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments_at_commit(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::start_commit_num.le(commit_num))
+                    .and(mfg_batch_attachment::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+
+    pub fn get_property_values_at_commit(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        let root_values = get_values_at_commit(conn, mfg_batch_id, None, commit_num, service_id)?;
+        build_property_values(conn, mfg_batch_id, root_values, commit_num, service_id)
+    }
+
+    pub fn build_property_values(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        values: Vec<MfgBatchPropertyValue>,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        let mut definitions = Vec::new();
+
+        for value in values {
+            let children = get_values_at_commit(
+                conn,
+                mfg_batch_id,
+                Some(&child_key(mfg_batch_id, &value.property_name)),
+                commit_num,
+                service_id,
+            )?;
+
+            if children.is_empty() {
+                definitions.push(PropertyValue::from(value));
+            } else {
+                definitions.push(PropertyValue::from((
+                    value,
+                    build_property_values(conn, mfg_batch_id, children, commit_num, service_id)?,
+                )));
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    fn get_values_at_commit(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        parent_property: Option<&str>,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.le(commit_num))
+                    .and(mfg_batch_property_value::end_commit_num.gt(commit_num)),
+            );
+
+        if let Some(parent_property) = parent_property {
+            query = query.filter(mfg_batch_property_value::parent_property.eq(parent_property));
+        } else {
+            query = query.filter(mfg_batch_property_value::parent_property.is_null());
+        }
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<MfgBatchPropertyValue>(conn)
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::schema::{mfg_batch, mfg_batch_allocation},
+        error::MfgBatchStoreError,
+        MfgBatchAvailability,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetMfgBatchAvailabilityOperation {
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetMfgBatchAvailabilityOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table
+                .into_boxed()
+                .select((mfg_batch::quantity, mfg_batch::uom))
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+                );
+            let mut allocations = mfg_batch_allocation::table
+                .into_boxed()
+                .select(mfg_batch_allocation::quantity)
+                .filter(mfg_batch_allocation::mfg_batch_id.eq(mfg_batch_id));
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+                allocations = allocations.filter(mfg_batch_allocation::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+                allocations = allocations.filter(mfg_batch_allocation::service_id.is_null());
+            }
+
+            let (quantity, uom) = match query
+                .first::<(Option<i64>, Option<String>)>(self.conn)
+                .optional()?
+            {
+                Some(current) => current,
+                None => return Ok(None),
+            };
+            let allocated = allocations.load::<i64>(self.conn)?;
+
+            Ok(Some(availability(mfg_batch_id, quantity, uom, &allocated)))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetMfgBatchAvailabilityOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table
+                .into_boxed()
+                .select((mfg_batch::quantity, mfg_batch::uom))
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+                );
+            let mut allocations = mfg_batch_allocation::table
+                .into_boxed()
+                .select(mfg_batch_allocation::quantity)
+                .filter(mfg_batch_allocation::mfg_batch_id.eq(mfg_batch_id));
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+                allocations = allocations.filter(mfg_batch_allocation::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+                allocations = allocations.filter(mfg_batch_allocation::service_id.is_null());
+            }
+
+            let (quantity, uom) = match query
+                .first::<(Option<i64>, Option<String>)>(self.conn)
+                .optional()?
+            {
+                Some(current) => current,
+                None => return Ok(None),
+            };
+            let allocated = allocations.load::<i64>(self.conn)?;
+
+            Ok(Some(availability(mfg_batch_id, quantity, uom, &allocated)))
+        })
+    }
+}
+
+fn availability(
+    mfg_batch_id: &str,
+    quantity: Option<i64>,
+    uom: Option<String>,
+    allocations: &[i64],
+) -> MfgBatchAvailability {
+    let allocated_quantity = allocations.iter().sum::<i64>();
+
+    MfgBatchAvailability {
+        mfg_batch_id: mfg_batch_id.to_string(),
+        quantity,
+        uom,
+        allocated_quantity,
+        available_quantity: quantity.unwrap_or(0) - allocated_quantity,
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::get_mfg_batch::pg as pg_get;
+#[cfg(feature = "sqlite")]
+use super::get_mfg_batch::sqlite as sqlite_get;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::{models::MfgBatchPropertyValue, schema::mfg_batch_property_value},
+        error::MfgBatchStoreError,
+        PropertyValue,
+    },
+    MAX_COMMIT_NUM,
+};
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetMfgBatchPropertiesOperation {
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetMfgBatchPropertiesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let root_values =
+                pg::get_root_values(&*self.conn, mfg_batch_id, property_names, service_id)?;
+
+            pg_get::get_property_values(&*self.conn, root_values)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetMfgBatchPropertiesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let root_values =
+                sqlite::get_root_values(&*self.conn, mfg_batch_id, property_names, service_id)?;
+
+            sqlite_get::get_property_values(&*self.conn, root_values)
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Gets the current top-level values of the named properties, in property name order
+    pub fn get_root_values(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq_any(property_names))
+                    .and(mfg_batch_property_value::parent_property.is_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_property_value::property_name.asc())
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Gets the current top-level values of the named properties, in property name order
+    pub fn get_root_values(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq_any(property_names))
+                    .and(mfg_batch_property_value::parent_property.is_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_property_value::property_name.asc())
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "mfg-batch-merge")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_alias;
+use crate::mfg_batch::{
+    store::{
+        diesel::{
+            models::{
+                MfgBatch as ModelMfgBatch, MfgBatchAttachment, MfgBatchParent,
+                MfgBatchPropertyValue,
+            },
+            schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
+        },
+        error::MfgBatchStoreError,
+        MfgBatch, PropertyValue,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetMfgBatchesOperation {
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        if mfg_batch_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // Lookups by the id of a merged mfg_batch resolve to the one it was merged into
+            #[cfg(feature = "mfg-batch-merge")]
+            let aliases = pg::get_aliases(&*self.conn, mfg_batch_ids, service_id)?;
+            #[cfg(not(feature = "mfg-batch-merge"))]
+            let aliases = HashMap::new();
+
+            let ids = resolve_ids(mfg_batch_ids, &aliases);
+
+            let mfg_batches = pg::get_mfg_batches(&*self.conn, &ids, service_id)?;
+            let values = pg::get_property_values(&*self.conn, &ids, service_id)?;
+            let parents = pg::get_parents(&*self.conn, &ids, service_id)?;
+            let attachments = pg::get_attachments(&*self.conn, &ids, service_id)?;
+
+            Ok(assemble(&ids, mfg_batches, values, parents, attachments))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        if mfg_batch_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            // Lookups by the id of a merged mfg_batch resolve to the one it was merged into
+            #[cfg(feature = "mfg-batch-merge")]
+            let aliases = sqlite::get_aliases(&*self.conn, mfg_batch_ids, service_id)?;
+            #[cfg(not(feature = "mfg-batch-merge"))]
+            let aliases = HashMap::new();
+
+            let ids = resolve_ids(mfg_batch_ids, &aliases);
+
+            let mfg_batches = sqlite::get_mfg_batches(&*self.conn, &ids, service_id)?;
+            let values = sqlite::get_property_values(&*self.conn, &ids, service_id)?;
+            let parents = sqlite::get_parents(&*self.conn, &ids, service_id)?;
+            let attachments = sqlite::get_attachments(&*self.conn, &ids, service_id)?;
+
+            Ok(assemble(&ids, mfg_batches, values, parents, attachments))
+        })
+    }
+}
+
+/// Resolves the requested ids through the aliases of merged mfg_batches, keeping the first
+/// occurrence of each resolved id in the order requested
+fn resolve_ids(mfg_batch_ids: &[&str], aliases: &HashMap<String, String>) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::with_capacity(mfg_batch_ids.len());
+
+    for mfg_batch_id in mfg_batch_ids {
+        let id = aliases
+            .get(*mfg_batch_id)
+            .map(String::as_str)
+            .unwrap_or(mfg_batch_id);
+        if !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
+        }
+    }
+
+    ids
+}
+
+/// Builds the mfg_batches from their rows, in the order of `ids`. Struct values name their
+/// parent as "<mfg_batch_id>:<property_name>", which is how members are matched to the value
+/// they belong to.
+pub(super) fn assemble(
+    ids: &[String],
+    mfg_batches: Vec<ModelMfgBatch>,
+    values: Vec<MfgBatchPropertyValue>,
+    parents: Vec<MfgBatchParent>,
+    attachments: Vec<MfgBatchAttachment>,
+) -> Vec<MfgBatch> {
+    let mut roots: HashMap<String, Vec<MfgBatchPropertyValue>> = HashMap::new();
+    let mut members: HashMap<String, Vec<MfgBatchPropertyValue>> = HashMap::new();
+    for value in values {
+        match value.parent_property.clone() {
+            Some(parent) => members.entry(parent).or_default().push(value),
+            None => roots
+                .entry(value.mfg_batch_id.clone())
+                .or_default()
+                .push(value),
+        }
+    }
+
+    let mut batch_parents: HashMap<String, Vec<MfgBatchParent>> = HashMap::new();
+    for parent in parents {
+        batch_parents
+            .entry(parent.mfg_batch_id.clone())
+            .or_default()
+            .push(parent);
+    }
+
+    let mut batch_attachments: HashMap<String, Vec<MfgBatchAttachment>> = HashMap::new();
+    for attachment in attachments {
+        batch_attachments
+            .entry(attachment.mfg_batch_id.clone())
+            .or_default()
+            .push(attachment);
+    }
+
+    let mut models: HashMap<String, ModelMfgBatch> = mfg_batches
+        .into_iter()
+        .map(|mfg_batch| (mfg_batch.mfg_batch_id.clone(), mfg_batch))
+        .collect();
+
+    ids.iter()
+        .filter_map(|id| {
+            let model = models.remove(id)?;
+            let properties = roots
+                .remove(id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| build_value(value, &mut members))
+                .collect();
+            let parents = batch_parents.remove(id).unwrap_or_default();
+            let attachments = batch_attachments.remove(id).unwrap_or_default();
+
+            Some(MfgBatch::from((model, properties, parents, attachments)))
+        })
+        .collect()
+}
+
+fn build_value(
+    value: MfgBatchPropertyValue,
+    members: &mut HashMap<String, Vec<MfgBatchPropertyValue>>,
+) -> PropertyValue {
+    let key = format!("{}:{}", value.mfg_batch_id, value.property_name);
+
+    match members.remove(&key) {
+        Some(children) => {
+            let children = children
+                .into_iter()
+                .map(|child| build_value(child, members))
+                .collect();
+            PropertyValue::from((value, children))
+        }
+        None => PropertyValue::from(value),
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub(super) mod pg {
+    use super::*;
+
+    /// Returns the mfg_batch each of the given ids was merged into, keyed by the merged id
+    #[cfg(feature = "mfg-batch-merge")]
+    pub fn get_aliases(
+        conn: &PgConnection,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> QueryResult<HashMap<String, String>> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select((mfg_batch_alias::alias, mfg_batch_alias::mfg_batch_id))
+            .filter(mfg_batch_alias::alias.eq_any(mfg_batch_ids));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        Ok(query.load::<(String, String)>(conn)?.into_iter().collect())
+    }
+
+    pub fn get_mfg_batches(
+        conn: &PgConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.load::<ModelMfgBatch>(conn)
+    }
+
+    /// Returns the current property values of every given mfg_batch, struct members included
+    pub fn get_property_values(
+        conn: &PgConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_property_value::id.asc())
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+
+    pub fn get_parents(
+        conn: &PgConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_parent::id.asc())
+            .load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &PgConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(super) mod sqlite {
+    use super::*;
+
+    /// Returns the mfg_batch each of the given ids was merged into, keyed by the merged id
+    #[cfg(feature = "mfg-batch-merge")]
+    pub fn get_aliases(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> QueryResult<HashMap<String, String>> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select((mfg_batch_alias::alias, mfg_batch_alias::mfg_batch_id))
+            .filter(mfg_batch_alias::alias.eq_any(mfg_batch_ids));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        Ok(query.load::<(String, String)>(conn)?.into_iter().collect())
+    }
+
+    pub fn get_mfg_batches(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.load::<ModelMfgBatch>(conn)
+    }
+
+    /// Returns the current property values of every given mfg_batch, struct members included
+    pub fn get_property_values(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_property_value::id.asc())
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+
+    pub fn get_parents(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_parent::id.asc())
+            .load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
+    }
+}
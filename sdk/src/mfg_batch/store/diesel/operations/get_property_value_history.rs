@@ -0,0 +1,166 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::get_mfg_batch_at_commit::pg as pg_at_commit;
+#[cfg(feature = "sqlite")]
+use super::get_mfg_batch_at_commit::sqlite as sqlite_at_commit;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatchPropertyValue, schema::mfg_batch_property_value},
+    error::MfgBatchStoreError,
+    PropertyValue,
+};
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait GetPropertyValueHistoryOperation {
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GetPropertyValueHistoryOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            pg::get_root_history(&*self.conn, mfg_batch_id, property_name, service_id)?
+                .into_iter()
+                .map(|value| {
+                    let commit_num = value.start_commit_num;
+                    pg_at_commit::build_property_values(
+                        &*self.conn,
+                        mfg_batch_id,
+                        vec![value],
+                        commit_num,
+                        service_id,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|values| values.into_iter().flatten().collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GetPropertyValueHistoryOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            sqlite::get_root_history(&*self.conn, mfg_batch_id, property_name, service_id)?
+                .into_iter()
+                .map(|value| {
+                    let commit_num = value.start_commit_num;
+                    sqlite_at_commit::build_property_values(
+                        &*self.conn,
+                        mfg_batch_id,
+                        vec![value],
+                        commit_num,
+                        service_id,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|values| values.into_iter().flatten().collect())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Gets every stored top-level value of a property, oldest first
+    pub fn get_root_history(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq(property_name))
+                    .and(mfg_batch_property_value::parent_property.is_null()),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order((
+                mfg_batch_property_value::start_commit_num.asc(),
+                mfg_batch_property_value::id.asc(),
+            ))
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Gets every stored top-level value of a property, oldest first
+    pub fn get_root_history(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchPropertyValue>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::all_columns)
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_property_value::property_name.eq(property_name))
+                    .and(mfg_batch_property_value::parent_property.is_null()),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query
+            .order((
+                mfg_batch_property_value::start_commit_num.asc(),
+                mfg_batch_property_value::id.asc(),
+            ))
+            .load::<MfgBatchPropertyValue>(conn)
+    }
+}
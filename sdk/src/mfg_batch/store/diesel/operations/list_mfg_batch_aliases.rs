@@ -0,0 +1,82 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatchAlias as ModelMfgBatchAlias, schema::mfg_batch_alias},
+    error::MfgBatchStoreError,
+    MfgBatchAlias,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchAliasesOperation {
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchAliasesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::all_columns);
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        Ok(query
+            .order((mfg_batch_alias::merged_at.asc(), mfg_batch_alias::id.asc()))
+            .load::<ModelMfgBatchAlias>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAlias::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchAliasesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::all_columns);
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        Ok(query
+            .order((mfg_batch_alias::merged_at.asc(), mfg_batch_alias::id.asc()))
+            .load::<ModelMfgBatchAlias>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAlias::from)
+            .collect())
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatchAnchor as ModelMfgBatchAnchor, schema::mfg_batch_anchor},
+    error::MfgBatchStoreError,
+    MfgBatchAnchor,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchAnchorsOperation {
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchAnchorsOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        let mut query = mfg_batch_anchor::table
+            .into_boxed()
+            .select(mfg_batch_anchor::all_columns);
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_anchor::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_anchor::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_anchor::commit_num.asc(),
+                mfg_batch_anchor::id.asc(),
+            ))
+            .load::<ModelMfgBatchAnchor>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAnchor::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchAnchorsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        let mut query = mfg_batch_anchor::table
+            .into_boxed()
+            .select(mfg_batch_anchor::all_columns);
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_anchor::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_anchor::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_anchor::commit_num.asc(),
+                mfg_batch_anchor::id.asc(),
+            ))
+            .load::<ModelMfgBatchAnchor>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAnchor::from)
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::diesel::operations::add_mfg_batch_anchor::AddMfgBatchAnchorOperation;
+
+    fn anchor(commit_num: i64, merkle_root: &str) -> MfgBatchAnchor {
+        MfgBatchAnchor {
+            owner: "org".to_string(),
+            commit_num,
+            merkle_root: merkle_root.to_string(),
+            row_count: 2,
+            anchor_address: "11bb0e02".to_string(),
+            anchored_commit_num: commit_num + 1,
+            anchored_at: 1_600_100_000,
+            service_id: None,
+        }
+    }
+
+    /// Verify that anchors are listed oldest commit first, and that an anchor delivered again
+    /// is only recorded once
+    #[test]
+    fn test_add_and_list_mfg_batch_anchors() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch_anchor (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                owner TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                merkle_root TEXT NOT NULL,
+                row_count BIGINT NOT NULL,
+                anchor_address TEXT NOT NULL,
+                anchored_commit_num BIGINT NOT NULL,
+                anchored_at BIGINT NOT NULL,
+                service_id TEXT
+            );",
+        )
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+
+        ops.add_mfg_batch_anchor(anchor(7, "root7"))
+            .expect("Failed to add anchor");
+        ops.add_mfg_batch_anchor(anchor(3, "root3"))
+            .expect("Failed to add anchor");
+        ops.add_mfg_batch_anchor(anchor(7, "root7"))
+            .expect("Failed to add anchor");
+
+        assert_eq!(
+            ops.list_mfg_batch_anchors(None)
+                .expect("Failed to list anchors"),
+            vec![anchor(3, "root3"), anchor(7, "root7")]
+        );
+        assert!(ops
+            .list_mfg_batch_anchors(Some("service"))
+            .expect("Failed to list anchors")
+            .is_empty());
+    }
+}
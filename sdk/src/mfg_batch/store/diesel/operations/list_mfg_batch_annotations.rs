@@ -0,0 +1,95 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatchAnnotation as ModelMfgBatchAnnotation, schema::mfg_batch_annotation},
+    error::MfgBatchStoreError,
+    MfgBatchAnnotation,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchAnnotationsOperation {
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchAnnotationsOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        let mut query = mfg_batch_annotation::table
+            .into_boxed()
+            .select(mfg_batch_annotation::all_columns)
+            .filter(mfg_batch_annotation::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_annotation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_annotation::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_annotation::commit_num.asc(),
+                mfg_batch_annotation::id.asc(),
+            ))
+            .load::<ModelMfgBatchAnnotation>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAnnotation::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchAnnotationsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        let mut query = mfg_batch_annotation::table
+            .into_boxed()
+            .select(mfg_batch_annotation::all_columns)
+            .filter(mfg_batch_annotation::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_annotation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_annotation::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_annotation::commit_num.asc(),
+                mfg_batch_annotation::id.asc(),
+            ))
+            .load::<ModelMfgBatchAnnotation>(self.conn)?
+            .into_iter()
+            .map(MfgBatchAnnotation::from)
+            .collect())
+    }
+}
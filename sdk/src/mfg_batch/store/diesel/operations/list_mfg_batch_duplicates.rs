@@ -0,0 +1,241 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::{
+            models::MfgBatchDuplicate as ModelMfgBatchDuplicate,
+            schema::{mfg_batch, mfg_batch_duplicate},
+        },
+        error::MfgBatchStoreError,
+        MfgBatchDuplicate,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchDuplicatesOperation {
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchDuplicatesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        let mut query = mfg_batch_duplicate::table
+            .into_boxed()
+            .select(mfg_batch_duplicate::all_columns);
+
+        // Mfg_batches deleted since the queue was built no longer need reviewing
+        if let Some(service_id) = service_id {
+            query = query
+                .filter(mfg_batch_duplicate::service_id.eq(service_id))
+                .filter(
+                    mfg_batch_duplicate::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.eq(service_id)),
+                    ),
+                );
+        } else {
+            query = query
+                .filter(mfg_batch_duplicate::service_id.is_null())
+                .filter(
+                    mfg_batch_duplicate::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.is_null()),
+                    ),
+                );
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_duplicate::similarity.desc(),
+                mfg_batch_duplicate::mfg_batch_id.asc(),
+                mfg_batch_duplicate::duplicate_of.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchDuplicate>(self.conn)?
+            .into_iter()
+            .map(MfgBatchDuplicate::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchDuplicatesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        let mut query = mfg_batch_duplicate::table
+            .into_boxed()
+            .select(mfg_batch_duplicate::all_columns);
+
+        // Mfg_batches deleted since the queue was built no longer need reviewing
+        if let Some(service_id) = service_id {
+            query = query
+                .filter(mfg_batch_duplicate::service_id.eq(service_id))
+                .filter(
+                    mfg_batch_duplicate::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.eq(service_id)),
+                    ),
+                );
+        } else {
+            query = query
+                .filter(mfg_batch_duplicate::service_id.is_null())
+                .filter(
+                    mfg_batch_duplicate::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.is_null()),
+                    ),
+                );
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_duplicate::similarity.desc(),
+                mfg_batch_duplicate::mfg_batch_id.asc(),
+                mfg_batch_duplicate::duplicate_of.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchDuplicate>(self.conn)?
+            .into_iter()
+            .map(MfgBatchDuplicate::from)
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::diesel::operations::replace_mfg_batch_duplicates::ReplaceMfgBatchDuplicatesOperation;
+
+    fn duplicate(mfg_batch_id: &str, duplicate_of: &str, similarity: i32) -> MfgBatchDuplicate {
+        MfgBatchDuplicate {
+            mfg_batch_id: mfg_batch_id.to_string(),
+            duplicate_of: duplicate_of.to_string(),
+            rule: "same_gtin_and_lot".to_string(),
+            similarity,
+            detected_at: 1_600_100_000,
+            service_id: None,
+        }
+    }
+
+    /// Verify that replacing the queue removes the duplicates of the previous run, and that the
+    /// duplicates of current mfg_batches are listed most alike first
+    #[test]
+    fn test_replace_and_list_mfg_batch_duplicates() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_duplicate (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                duplicate_of TEXT NOT NULL,
+                rule TEXT NOT NULL,
+                similarity INTEGER NOT NULL,
+                detected_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES
+                ('batch1', 'addr1', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch3', 'addr3', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch4', 'addr4', 'ns', 'org', 2, 5, NULL);",
+        )
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+
+        ops.replace_mfg_batch_duplicates(None, vec![duplicate("batch3", "batch1", 100)])
+            .expect("Failed to replace duplicates");
+        ops.replace_mfg_batch_duplicates(
+            None,
+            vec![
+                duplicate("batch2", "batch1", 90),
+                duplicate("batch3", "batch1", 95),
+                duplicate("batch4", "batch1", 100),
+            ],
+        )
+        .expect("Failed to replace duplicates");
+
+        assert_eq!(
+            ops.list_mfg_batch_duplicates(None, 0, 10)
+                .expect("Failed to list duplicates"),
+            vec![
+                duplicate("batch3", "batch1", 95),
+                duplicate("batch2", "batch1", 90),
+            ]
+        );
+        assert_eq!(
+            ops.list_mfg_batch_duplicates(None, 1, 10)
+                .expect("Failed to list duplicates"),
+            vec![duplicate("batch2", "batch1", 90)]
+        );
+        assert!(ops
+            .list_mfg_batch_duplicates(Some("service"), 0, 10)
+            .expect("Failed to list duplicates")
+            .is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::get_mfg_batch_at_commit::pg as pg_at_commit;
+#[cfg(feature = "sqlite")]
+use super::get_mfg_batch_at_commit::sqlite as sqlite_at_commit;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatch as ModelMfgBatch, schema::mfg_batch},
+    error::MfgBatchStoreError,
+    MfgBatch,
+};
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchHistoryOperation {
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchHistoryOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            pg::list_versions(&*self.conn, mfg_batch_id, service_id)?
+                .into_iter()
+                .map(|version| {
+                    let commit_num = version.start_commit_num;
+                    let values = pg_at_commit::get_property_values_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+                    let parents = pg_at_commit::get_parents_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+                    let attachments = pg_at_commit::get_attachments_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+
+                    Ok(MfgBatch::from((version, values, parents, attachments)))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchHistoryOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            sqlite::list_versions(&*self.conn, mfg_batch_id, service_id)?
+                .into_iter()
+                .map(|version| {
+                    let commit_num = version.start_commit_num;
+                    let values = sqlite_at_commit::get_property_values_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+                    let parents = sqlite_at_commit::get_parents_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+                    let attachments = sqlite_at_commit::get_attachments_at_commit(
+                        &*self.conn,
+                        mfg_batch_id,
+                        commit_num,
+                        service_id,
+                    )?;
+
+                    Ok(MfgBatch::from((version, values, parents, attachments)))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn list_versions(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(mfg_batch::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::start_commit_num.asc())
+            .load::<ModelMfgBatch>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn list_versions(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(mfg_batch::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::start_commit_num.asc())
+            .load::<ModelMfgBatch>(conn)
+    }
+}
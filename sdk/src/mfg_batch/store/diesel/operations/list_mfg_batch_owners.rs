@@ -0,0 +1,228 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use std::collections::HashMap;
+
+use crate::{
+    mfg_batch::{
+        store::{diesel::schema::mfg_batch, error::MfgBatchStoreError, MfgBatchOwner},
+        MAX_COMMIT_NUM,
+    },
+    pike::store::diesel::schema::{pike_organization, pike_organization_location_assoc},
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchOwnersOperation {
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchOwnersOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let owners = pg::list_owners(&*self.conn, service_id, offset, limit)?;
+            let org_ids = owners
+                .iter()
+                .map(|(_, org_id, _)| org_id.to_string())
+                .collect::<Vec<_>>();
+            let locations = pg::list_org_locations(&*self.conn, &org_ids, service_id)?;
+
+            Ok(build_owners(owners, locations))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchOwnersOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let owners = sqlite::list_owners(&*self.conn, service_id, offset, limit)?;
+            let org_ids = owners
+                .iter()
+                .map(|(_, org_id, _)| org_id.to_string())
+                .collect::<Vec<_>>();
+            let locations = sqlite::list_org_locations(&*self.conn, &org_ids, service_id)?;
+
+            Ok(build_owners(owners, locations))
+        })
+    }
+}
+
+/// Pairs each mfg_batch's owner with the locations associated with that organization
+fn build_owners(
+    owners: Vec<(String, String, Option<String>)>,
+    locations: Vec<(String, String)>,
+) -> Vec<MfgBatchOwner> {
+    let mut locations_by_org: HashMap<String, Vec<String>> = HashMap::new();
+    for (org_id, location_id) in locations {
+        locations_by_org
+            .entry(org_id)
+            .or_default()
+            .push(location_id);
+    }
+
+    owners
+        .into_iter()
+        .map(|(mfg_batch_id, org_id, name)| MfgBatchOwner {
+            location_ids: locations_by_org.get(&org_id).cloned().unwrap_or_default(),
+            mfg_batch_id,
+            org_id,
+            name,
+        })
+        .collect()
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn list_owners(
+        conn: &PgConnection,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(String, String, Option<String>)>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .left_join(
+                pike_organization::table.on(mfg_batch::owner
+                    .eq(pike_organization::org_id)
+                    .and(mfg_batch::end_commit_num.eq(pike_organization::end_commit_num))),
+            )
+            .select((
+                mfg_batch::mfg_batch_id,
+                mfg_batch::owner,
+                pike_organization::name.nullable(),
+            ))
+            .limit(limit)
+            .offset(offset)
+            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.load::<(String, String, Option<String>)>(conn)
+    }
+
+    pub fn list_org_locations(
+        conn: &PgConnection,
+        org_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<(String, String)>> {
+        let mut query = pike_organization_location_assoc::table
+            .into_boxed()
+            .select((
+                pike_organization_location_assoc::org_id,
+                pike_organization_location_assoc::location_id,
+            ))
+            .filter(
+                pike_organization_location_assoc::org_id
+                    .eq_any(org_ids)
+                    .and(pike_organization_location_assoc::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(pike_organization_location_assoc::service_id.eq(service_id));
+        } else {
+            query = query.filter(pike_organization_location_assoc::service_id.is_null());
+        }
+
+        query.load::<(String, String)>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn list_owners(
+        conn: &SqliteConnection,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(String, String, Option<String>)>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .left_join(
+                pike_organization::table.on(mfg_batch::owner
+                    .eq(pike_organization::org_id)
+                    .and(mfg_batch::end_commit_num.eq(pike_organization::end_commit_num))),
+            )
+            .select((
+                mfg_batch::mfg_batch_id,
+                mfg_batch::owner,
+                pike_organization::name.nullable(),
+            ))
+            .limit(limit)
+            .offset(offset)
+            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.load::<(String, String, Option<String>)>(conn)
+    }
+
+    pub fn list_org_locations(
+        conn: &SqliteConnection,
+        org_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<(String, String)>> {
+        let mut query = pike_organization_location_assoc::table
+            .into_boxed()
+            .select((
+                pike_organization_location_assoc::org_id,
+                pike_organization_location_assoc::location_id,
+            ))
+            .filter(
+                pike_organization_location_assoc::org_id
+                    .eq_any(org_ids)
+                    .and(pike_organization_location_assoc::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(pike_organization_location_assoc::service_id.eq(service_id));
+        } else {
+            query = query.filter(pike_organization_location_assoc::service_id.is_null());
+        }
+
+        query.load::<(String, String)>(conn)
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::{
+            models::MfgBatchQualityScore as ModelMfgBatchQualityScore,
+            schema::{mfg_batch, mfg_batch_quality_score},
+        },
+        error::MfgBatchStoreError,
+        ListMfgBatchQualityScoreFilters, MfgBatchQualityScore,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchQualityScoresOperation {
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchQualityScoresOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        let mut query = mfg_batch_quality_score::table
+            .into_boxed()
+            .select(mfg_batch_quality_score::all_columns);
+
+        // Scores are kept when a mfg_batch is deleted, so only those of current mfg_batches
+        // are listed
+        if let Some(service_id) = service_id {
+            query = query
+                .filter(mfg_batch_quality_score::service_id.eq(service_id))
+                .filter(
+                    mfg_batch_quality_score::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.eq(service_id)),
+                    ),
+                );
+        } else {
+            query = query
+                .filter(mfg_batch_quality_score::service_id.is_null())
+                .filter(
+                    mfg_batch_quality_score::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.is_null()),
+                    ),
+                );
+        }
+
+        if let Some(mfg_batch_ids) = &filters.mfg_batch_ids {
+            query = query.filter(mfg_batch_quality_score::mfg_batch_id.eq_any(mfg_batch_ids));
+        }
+
+        if let Some(max_score) = filters.max_score {
+            query = query.filter(mfg_batch_quality_score::score.le(max_score));
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_quality_score::score.asc(),
+                mfg_batch_quality_score::mfg_batch_id.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchQualityScore>(self.conn)?
+            .into_iter()
+            .map(MfgBatchQualityScore::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchQualityScoresOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        let mut query = mfg_batch_quality_score::table
+            .into_boxed()
+            .select(mfg_batch_quality_score::all_columns);
+
+        // Scores are kept when a mfg_batch is deleted, so only those of current mfg_batches
+        // are listed
+        if let Some(service_id) = service_id {
+            query = query
+                .filter(mfg_batch_quality_score::service_id.eq(service_id))
+                .filter(
+                    mfg_batch_quality_score::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.eq(service_id)),
+                    ),
+                );
+        } else {
+            query = query
+                .filter(mfg_batch_quality_score::service_id.is_null())
+                .filter(
+                    mfg_batch_quality_score::mfg_batch_id.eq_any(
+                        mfg_batch::table
+                            .select(mfg_batch::mfg_batch_id)
+                            .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                            .filter(mfg_batch::service_id.is_null()),
+                    ),
+                );
+        }
+
+        if let Some(mfg_batch_ids) = &filters.mfg_batch_ids {
+            query = query.filter(mfg_batch_quality_score::mfg_batch_id.eq_any(mfg_batch_ids));
+        }
+
+        if let Some(max_score) = filters.max_score {
+            query = query.filter(mfg_batch_quality_score::score.le(max_score));
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_quality_score::score.asc(),
+                mfg_batch_quality_score::mfg_batch_id.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchQualityScore>(self.conn)?
+            .into_iter()
+            .map(MfgBatchQualityScore::from)
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::diesel::operations::put_mfg_batch_quality_score::PutMfgBatchQualityScoreOperation;
+
+    fn quality_score(
+        mfg_batch_id: &str,
+        score: i32,
+        failed_checks: &[&str],
+    ) -> MfgBatchQualityScore {
+        MfgBatchQualityScore {
+            mfg_batch_id: mfg_batch_id.to_string(),
+            commit_num: 2,
+            score,
+            failed_checks: failed_checks
+                .iter()
+                .map(|check| check.to_string())
+                .collect(),
+            scored_at: 1_600_100_000,
+            service_id: None,
+        }
+    }
+
+    /// Verify that putting a score replaces the mfg_batch's previous one, and that the scores of
+    /// current mfg_batches are listed lowest first
+    #[test]
+    fn test_put_and_list_mfg_batch_quality_scores() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_quality_score (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                score INTEGER NOT NULL,
+                failed_checks TEXT NOT NULL,
+                scored_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES
+                ('batch1', 'addr1', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch3', 'addr3', 'ns', 'org', 2, 5, NULL);",
+        )
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+
+        ops.put_mfg_batch_quality_score(quality_score("batch1", 40, &["uom", "lot_code"]))
+            .expect("Failed to put quality score");
+        ops.put_mfg_batch_quality_score(quality_score("batch2", 75, &["uom"]))
+            .expect("Failed to put quality score");
+        ops.put_mfg_batch_quality_score(quality_score("batch3", 0, &["uom"]))
+            .expect("Failed to put quality score");
+        ops.put_mfg_batch_quality_score(quality_score("batch1", 80, &["lot_code"]))
+            .expect("Failed to put quality score");
+
+        assert_eq!(
+            ops.list_mfg_batch_quality_scores(
+                None,
+                &ListMfgBatchQualityScoreFilters::default(),
+                0,
+                10
+            )
+            .expect("Failed to list quality scores"),
+            vec![
+                quality_score("batch2", 75, &["uom"]),
+                quality_score("batch1", 80, &["lot_code"]),
+            ]
+        );
+        assert_eq!(
+            ops.list_mfg_batch_quality_scores(
+                None,
+                &ListMfgBatchQualityScoreFilters {
+                    mfg_batch_ids: Some(vec!["batch1".to_string()]),
+                    max_score: None,
+                },
+                0,
+                10
+            )
+            .expect("Failed to list quality scores"),
+            vec![quality_score("batch1", 80, &["lot_code"])]
+        );
+        assert_eq!(
+            ops.list_mfg_batch_quality_scores(
+                None,
+                &ListMfgBatchQualityScoreFilters {
+                    mfg_batch_ids: None,
+                    max_score: Some(75),
+                },
+                0,
+                10
+            )
+            .expect("Failed to list quality scores"),
+            vec![quality_score("batch2", 75, &["uom"])]
+        );
+        assert!(ops
+            .list_mfg_batch_quality_scores(
+                Some("service"),
+                &ListMfgBatchQualityScoreFilters::default(),
+                0,
+                10
+            )
+            .expect("Failed to list quality scores")
+            .is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatchRecall as ModelMfgBatchRecall, schema::mfg_batch_recall},
+    error::MfgBatchStoreError,
+    ListMfgBatchRecallFilters, MfgBatchRecall,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchRecallsOperation {
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchRecallsOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        let mut query = mfg_batch_recall::table
+            .into_boxed()
+            .select(mfg_batch_recall::all_columns);
+
+        // Recalls are kept when a mfg_batch is deleted, since the batch may still be in the
+        // supply chain
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_recall::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_recall::service_id.is_null());
+        }
+
+        if let Some(mfg_batch_id) = &filters.mfg_batch_id {
+            query = query.filter(mfg_batch_recall::mfg_batch_id.eq(mfg_batch_id));
+        }
+
+        if let Some(recall_id) = &filters.recall_id {
+            query = query.filter(mfg_batch_recall::recall_id.eq(recall_id));
+        }
+
+        if !filters.include_closed {
+            query = query.filter(mfg_batch_recall::closed_at.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_recall::recalled_at.desc(),
+                mfg_batch_recall::recall_id.asc(),
+                mfg_batch_recall::mfg_batch_id.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchRecall>(self.conn)?
+            .into_iter()
+            .map(MfgBatchRecall::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchRecallsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        let mut query = mfg_batch_recall::table
+            .into_boxed()
+            .select(mfg_batch_recall::all_columns);
+
+        // Recalls are kept when a mfg_batch is deleted, since the batch may still be in the
+        // supply chain
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_recall::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_recall::service_id.is_null());
+        }
+
+        if let Some(mfg_batch_id) = &filters.mfg_batch_id {
+            query = query.filter(mfg_batch_recall::mfg_batch_id.eq(mfg_batch_id));
+        }
+
+        if let Some(recall_id) = &filters.recall_id {
+            query = query.filter(mfg_batch_recall::recall_id.eq(recall_id));
+        }
+
+        if !filters.include_closed {
+            query = query.filter(mfg_batch_recall::closed_at.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_recall::recalled_at.desc(),
+                mfg_batch_recall::recall_id.asc(),
+                mfg_batch_recall::mfg_batch_id.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<ModelMfgBatchRecall>(self.conn)?
+            .into_iter()
+            .map(MfgBatchRecall::from)
+            .collect())
+    }
+}
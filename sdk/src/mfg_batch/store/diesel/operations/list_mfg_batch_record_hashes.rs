@@ -0,0 +1,108 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatch, record_hash::HashedRecord, schema::mfg_batch},
+    error::MfgBatchStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchRecordHashesOperation {
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchRecordHashesOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        use super::verify_mfg_batch_checksums::pg::get_record_rows;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table.into_boxed().filter(
+                mfg_batch::start_commit_num
+                    .le(commit_num)
+                    .and(mfg_batch::end_commit_num.gt(commit_num)),
+            );
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+            }
+
+            let mut hashes = query
+                .load::<MfgBatch>(&*self.conn)?
+                .iter()
+                .map(|mfg_batch| {
+                    let (properties, parents) = get_record_rows(&*self.conn, mfg_batch)?;
+                    Ok(HashedRecord::from_stored(mfg_batch, &properties, &parents).hash())
+                })
+                .collect::<Result<Vec<_>, MfgBatchStoreError>>()?;
+            hashes.sort_unstable();
+
+            Ok(hashes)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchRecordHashesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        use super::verify_mfg_batch_checksums::sqlite::get_record_rows;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table.into_boxed().filter(
+                mfg_batch::start_commit_num
+                    .le(commit_num)
+                    .and(mfg_batch::end_commit_num.gt(commit_num)),
+            );
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+            }
+
+            let mut hashes = query
+                .load::<MfgBatch>(&*self.conn)?
+                .iter()
+                .map(|mfg_batch| {
+                    let (properties, parents) = get_record_rows(&*self.conn, mfg_batch)?;
+                    Ok(HashedRecord::from_stored(mfg_batch, &properties, &parents).hash())
+                })
+                .collect::<Result<Vec<_>, MfgBatchStoreError>>()?;
+            hashes.sort_unstable();
+
+            Ok(hashes)
+        })
+    }
+}
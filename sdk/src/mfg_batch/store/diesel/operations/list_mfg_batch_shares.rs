@@ -0,0 +1,79 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{diesel::schema::mfg_batch_shared_with, error::MfgBatchStoreError};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchSharesOperation {
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchSharesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        let mut query = mfg_batch_shared_with::table
+            .into_boxed()
+            .select(mfg_batch_shared_with::org_id)
+            .filter(mfg_batch_shared_with::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_shared_with::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_shared_with::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_shared_with::org_id.asc())
+            .load::<String>(self.conn)
+            .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchSharesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        let mut query = mfg_batch_shared_with::table
+            .into_boxed()
+            .select(mfg_batch_shared_with::org_id)
+            .filter(mfg_batch_shared_with::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_shared_with::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_shared_with::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_shared_with::org_id.asc())
+            .load::<String>(self.conn)
+            .map_err(MfgBatchStoreError::from)
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::MfgBatchTestResult as ModelMfgBatchTestResult, schema::mfg_batch_test_result,
+    },
+    error::MfgBatchStoreError,
+    MfgBatchTestResult,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchTestResultsOperation {
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchTestResultsOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        let mut query = mfg_batch_test_result::table
+            .into_boxed()
+            .select(mfg_batch_test_result::all_columns)
+            .filter(mfg_batch_test_result::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_test_result::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_test_result::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_test_result::commit_num.asc(),
+                mfg_batch_test_result::id.asc(),
+            ))
+            .load::<ModelMfgBatchTestResult>(self.conn)?
+            .into_iter()
+            .map(MfgBatchTestResult::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchTestResultsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        let mut query = mfg_batch_test_result::table
+            .into_boxed()
+            .select(mfg_batch_test_result::all_columns)
+            .filter(mfg_batch_test_result::mfg_batch_id.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_test_result::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_test_result::service_id.is_null());
+        }
+
+        Ok(query
+            .order((
+                mfg_batch_test_result::commit_num.asc(),
+                mfg_batch_test_result::id.asc(),
+            ))
+            .load::<ModelMfgBatchTestResult>(self.conn)?
+            .into_iter()
+            .map(MfgBatchTestResult::from)
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::diesel::operations::add_mfg_batch_test_result::AddMfgBatchTestResultOperation;
+
+    fn test_result(commit_num: i64, test_name: &str) -> MfgBatchTestResult {
+        MfgBatchTestResult {
+            mfg_batch_id: "batch1".to_string(),
+            commit_num,
+            test_name: test_name.to_string(),
+            specification: "<= 12.5 %".to_string(),
+            result: "11.8 %".to_string(),
+            passed: true,
+            lab: "Acme Labs".to_string(),
+            tested_at: 1_600_100_000,
+            service_id: None,
+        }
+    }
+
+    /// Verify that test results can only be added to a mfg_batch that existed as of their
+    /// commit, and are listed in the order of their commits
+    #[test]
+    fn test_add_and_list_mfg_batch_test_results() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_test_result (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                test_name TEXT NOT NULL,
+                specification TEXT NOT NULL,
+                result TEXT NOT NULL,
+                passed BOOLEAN NOT NULL,
+                lab TEXT NOT NULL,
+                tested_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES ('batch1', 'addr1', 'ns', 'org', 2, 5, NULL);",
+        )
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+
+        ops.add_mfg_batch_test_result(test_result(4, "protein"))
+            .expect("Failed to add test result");
+        ops.add_mfg_batch_test_result(test_result(2, "moisture"))
+            .expect("Failed to add test result");
+        assert!(matches!(
+            ops.add_mfg_batch_test_result(test_result(1, "ash")),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+        assert!(matches!(
+            ops.add_mfg_batch_test_result(test_result(5, "ash")),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+
+        assert_eq!(
+            ops.list_mfg_batch_test_results("batch1", None)
+                .expect("Failed to list test results"),
+            vec![test_result(2, "moisture"), test_result(4, "protein")]
+        );
+        assert!(ops
+            .list_mfg_batch_test_results("batch1", Some("service"))
+            .expect("Failed to list test results")
+            .is_empty());
+    }
+}
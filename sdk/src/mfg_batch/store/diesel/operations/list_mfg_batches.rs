@@ -14,29 +14,44 @@
 
 use super::MfgBatchStoreOperations;
 
+#[cfg(feature = "postgres")]
+use super::count_mfg_batches::pg as pg_count;
+#[cfg(feature = "sqlite")]
+use super::count_mfg_batches::sqlite as sqlite_count;
+use super::get_mfg_batches::assemble;
+#[cfg(feature = "postgres")]
+use super::get_mfg_batches::pg as pg_get;
+#[cfg(feature = "sqlite")]
+use super::get_mfg_batches::sqlite as sqlite_get;
+#[cfg(feature = "mfg-batch-visibility")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_shared_with;
 use crate::{
-    error::InternalError,
-    paging::Paging,
     mfg_batch::{
         store::{
             diesel::{
-                models::{MfgBatch as ModelMfgBatch, MfgBatchPropertyValue},
-                schema::{mfg_batch, mfg_batch_property_value},
+                models::{
+                    MfgBatch as ModelMfgBatch, MfgBatchAttachment, MfgBatchParent,
+                    MfgBatchPropertyValue,
+                },
+                schema::{
+                    mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value,
+                },
             },
             error::MfgBatchStoreError,
-            MfgBatch, MfgBatchList, PropertyValue,
+            ListMfgBatchFilters, MfgBatchList,
         },
         MAX_COMMIT_NUM,
     },
+    paging::Paging,
 };
 
 use diesel::prelude::*;
-use std::convert::TryInto;
 
 pub(in crate::mfg_batch) trait ListMfgBatchsOperation {
     fn list_mfg_batches(
         &self,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> Result<MfgBatchList, MfgBatchStoreError>;
@@ -47,25 +62,26 @@ impl<'a> ListMfgBatchsOperation for MfgBatchStoreOperations<'a, diesel::pg::PgCo
     fn list_mfg_batches(
         &self,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> Result<MfgBatchList, MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
-            let db_mfg_batches = pg::list_mfg_batches(&*self.conn, service_id, offset, limit)?;
-
-            let total = db_mfg_batches.len().try_into().map_err(|err| {
-                MfgBatchStoreError::InternalError(InternalError::from_source(Box::new(err)))
-            })?;
-
-            let mut mfg_batches = Vec::new();
+            let db_mfg_batches =
+                pg::list_mfg_batches(&*self.conn, service_id, filters, offset, limit)?;
 
-            for mfg_batch in db_mfg_batches {
-                let root_values = pg::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+            let total = pg_count::count_mfg_batches(&*self.conn, service_id, filters)?;
 
-                let values = pg::get_property_values(&*self.conn, root_values)?;
+            // The related rows of the whole page are loaded at once, then grouped by mfg_batch
+            let ids = db_mfg_batches
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id.clone())
+                .collect::<Vec<_>>();
+            let values = pg_get::get_property_values(&*self.conn, &ids, service_id)?;
+            let parents = pg_get::get_parents(&*self.conn, &ids, service_id)?;
+            let attachments = pg_get::get_attachments(&*self.conn, &ids, service_id)?;
 
-                mfg_batches.push(MfgBatch::from((mfg_batch, values)));
-            }
+            let mfg_batches = assemble(&ids, db_mfg_batches, values, parents, attachments);
 
             Ok(MfgBatchList::new(
                 mfg_batches,
@@ -80,25 +96,26 @@ impl<'a> ListMfgBatchsOperation for MfgBatchStoreOperations<'a, diesel::sqlite::
     fn list_mfg_batches(
         &self,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> Result<MfgBatchList, MfgBatchStoreError> {
         self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
-            let db_mfg_batches = sqlite::list_mfg_batches(&*self.conn, service_id, offset, limit)?;
+            let db_mfg_batches =
+                sqlite::list_mfg_batches(&*self.conn, service_id, filters, offset, limit)?;
 
-            let total = db_mfg_batches.len().try_into().map_err(|err| {
-                MfgBatchStoreError::InternalError(InternalError::from_source(Box::new(err)))
-            })?;
+            let total = sqlite_count::count_mfg_batches(&*self.conn, service_id, filters)?;
 
-            let mut mfg_batches = Vec::new();
+            // The related rows of the whole page are loaded at once, then grouped by mfg_batch
+            let ids = db_mfg_batches
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id.clone())
+                .collect::<Vec<_>>();
+            let values = sqlite_get::get_property_values(&*self.conn, &ids, service_id)?;
+            let parents = sqlite_get::get_parents(&*self.conn, &ids, service_id)?;
+            let attachments = sqlite_get::get_attachments(&*self.conn, &ids, service_id)?;
 
-            for mfg_batch in db_mfg_batches {
-                let root_values = sqlite::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
-
-                let values = sqlite::get_property_values(&*self.conn, root_values)?;
-
-                mfg_batches.push(MfgBatch::from((mfg_batch, values)));
-            }
+            let mfg_batches = assemble(&ids, db_mfg_batches, values, parents, attachments);
 
             Ok(MfgBatchList::new(
                 mfg_batches,
@@ -109,20 +126,34 @@ impl<'a> ListMfgBatchsOperation for MfgBatchStoreOperations<'a, diesel::sqlite::
 }
 
 #[cfg(feature = "postgres")]
-mod pg {
+pub(super) mod pg {
     use super::*;
 
+    use diesel::pg::Pg;
+
     pub fn list_mfg_batches(
         conn: &PgConnection,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> QueryResult<Vec<ModelMfgBatch>> {
+        list_query(service_id, filters)
+            .order(mfg_batch::mfg_batch_id)
+            .limit(limit)
+            .offset(offset)
+            .load::<ModelMfgBatch>(conn)
+    }
+
+    /// Builds the query for the current mfg_batches matching the filters. Pages of the list,
+    /// its count and its plan are all built from it, so they cannot disagree on the filters.
+    pub fn list_query<'a>(
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+    ) -> mfg_batch::BoxedQuery<'a, Pg> {
         let mut query = mfg_batch::table
             .into_boxed()
             .select(mfg_batch::all_columns)
-            .limit(limit)
-            .offset(offset)
             .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM));
 
         if let Some(service_id) = service_id {
@@ -130,7 +161,100 @@ mod pg {
         } else {
             query = query.filter(mfg_batch::service_id.is_null());
         }
-        query.load::<ModelMfgBatch>(conn)
+
+        if let Some(owner) = &filters.owner {
+            query = query.filter(mfg_batch::owner.eq(owner));
+        }
+
+        if let Some(namespace) = &filters.mfg_batch_namespace {
+            query = query.filter(mfg_batch::mfg_batch_namespace.eq(namespace));
+        }
+
+        if let Some(expiring_before) = filters.expiring_before {
+            query = query.filter(mfg_batch::expiration_date.lt(expiring_before));
+        }
+
+        if let Some(expiring_after) = filters.expiring_after {
+            query = query.filter(mfg_batch::expiration_date.ge(expiring_after));
+        }
+
+        if let Some(archived) = filters.archived {
+            query = query.filter(mfg_batch::archived.eq(archived));
+        }
+
+        if let Some(manufacture_location) = &filters.manufacture_location {
+            query = query.filter(mfg_batch::manufacture_location.eq(manufacture_location));
+        }
+
+        #[cfg(feature = "mfg-batch-visibility")]
+        if let Some(visibility) = &filters.visibility {
+            let shared = mfg_batch_shared_with::table
+                .select(mfg_batch_shared_with::mfg_batch_id)
+                .filter(mfg_batch_shared_with::org_id.eq(&visibility.org_id));
+            let owned = mfg_batch::owner.eq(&visibility.org_id);
+
+            query = if let Some(service_id) = service_id {
+                query.filter(
+                    owned.or(mfg_batch::mfg_batch_id
+                        .eq_any(shared.filter(mfg_batch_shared_with::service_id.eq(service_id)))),
+                )
+            } else {
+                query.filter(
+                    owned.or(mfg_batch::mfg_batch_id
+                        .eq_any(shared.filter(mfg_batch_shared_with::service_id.is_null()))),
+                )
+            };
+        }
+
+        query
+    }
+
+    pub fn get_parents(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
     }
 
     pub fn get_root_values(
@@ -148,47 +272,38 @@ mod pg {
             .load::<MfgBatchPropertyValue>(conn)
     }
 
-    pub fn get_property_values(
-        conn: &PgConnection,
-        root_values: Vec<MfgBatchPropertyValue>,
-    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
-        let mut definitions = Vec::new();
-
-        for root_value in root_values {
-            let children = mfg_batch_property_value::table
-                .select(mfg_batch_property_value::all_columns)
-                .filter(mfg_batch_property_value::parent_property.eq(&root_value.parent_property))
-                .load(conn)?;
-
-            if children.is_empty() {
-                definitions.push(PropertyValue::from(root_value));
-            } else {
-                definitions.push(PropertyValue::from((
-                    root_value,
-                    get_property_values(conn, children)?,
-                )));
-            }
-        }
-
-        Ok(definitions)
-    }
+    pub use super::super::get_mfg_batch::pg::get_property_values;
 }
 
 #[cfg(feature = "sqlite")]
-mod sqlite {
+pub(super) mod sqlite {
     use super::*;
 
+    use diesel::sqlite::Sqlite;
+
     pub fn list_mfg_batches(
         conn: &SqliteConnection,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> QueryResult<Vec<ModelMfgBatch>> {
+        list_query(service_id, filters)
+            .order(mfg_batch::mfg_batch_id)
+            .limit(limit)
+            .offset(offset)
+            .load::<ModelMfgBatch>(conn)
+    }
+
+    /// Builds the query for the current mfg_batches matching the filters. Pages of the list,
+    /// its count and its plan are all built from it, so they cannot disagree on the filters.
+    pub fn list_query<'a>(
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+    ) -> mfg_batch::BoxedQuery<'a, Sqlite> {
         let mut query = mfg_batch::table
             .into_boxed()
             .select(mfg_batch::all_columns)
-            .limit(limit)
-            .offset(offset)
             .filter(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM));
 
         if let Some(service_id) = service_id {
@@ -196,7 +311,100 @@ mod sqlite {
         } else {
             query = query.filter(mfg_batch::service_id.is_null());
         }
-        query.load::<ModelMfgBatch>(conn)
+
+        if let Some(owner) = &filters.owner {
+            query = query.filter(mfg_batch::owner.eq(owner));
+        }
+
+        if let Some(namespace) = &filters.mfg_batch_namespace {
+            query = query.filter(mfg_batch::mfg_batch_namespace.eq(namespace));
+        }
+
+        if let Some(expiring_before) = filters.expiring_before {
+            query = query.filter(mfg_batch::expiration_date.lt(expiring_before));
+        }
+
+        if let Some(expiring_after) = filters.expiring_after {
+            query = query.filter(mfg_batch::expiration_date.ge(expiring_after));
+        }
+
+        if let Some(archived) = filters.archived {
+            query = query.filter(mfg_batch::archived.eq(archived));
+        }
+
+        if let Some(manufacture_location) = &filters.manufacture_location {
+            query = query.filter(mfg_batch::manufacture_location.eq(manufacture_location));
+        }
+
+        #[cfg(feature = "mfg-batch-visibility")]
+        if let Some(visibility) = &filters.visibility {
+            let shared = mfg_batch_shared_with::table
+                .select(mfg_batch_shared_with::mfg_batch_id)
+                .filter(mfg_batch_shared_with::org_id.eq(&visibility.org_id));
+            let owned = mfg_batch::owner.eq(&visibility.org_id);
+
+            query = if let Some(service_id) = service_id {
+                query.filter(
+                    owned.or(mfg_batch::mfg_batch_id
+                        .eq_any(shared.filter(mfg_batch_shared_with::service_id.eq(service_id)))),
+                )
+            } else {
+                query.filter(
+                    owned.or(mfg_batch::mfg_batch_id
+                        .eq_any(shared.filter(mfg_batch_shared_with::service_id.is_null()))),
+                )
+            };
+        }
+
+        query
+    }
+
+    pub fn get_parents(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchParent>> {
+        let mut query = mfg_batch_parent::table
+            .into_boxed()
+            .select(mfg_batch_parent::all_columns)
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_parent::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        query.load::<MfgBatchParent>(conn)
+    }
+
+    pub fn get_attachments(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<MfgBatchAttachment>> {
+        let mut query = mfg_batch_attachment::table
+            .into_boxed()
+            .select(mfg_batch_attachment::all_columns)
+            .filter(
+                mfg_batch_attachment::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch_attachment::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_attachment::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_attachment::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch_attachment::id.asc())
+            .load::<MfgBatchAttachment>(conn)
     }
 
     pub fn get_root_values(
@@ -214,28 +422,5 @@ mod sqlite {
             .load::<MfgBatchPropertyValue>(conn)
     }
 
-    pub fn get_property_values(
-        conn: &SqliteConnection,
-        root_values: Vec<MfgBatchPropertyValue>,
-    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
-        let mut definitions = Vec::new();
-
-        for root_value in root_values {
-            let children = mfg_batch_property_value::table
-                .select(mfg_batch_property_value::all_columns)
-                .filter(mfg_batch_property_value::parent_property.eq(&root_value.parent_property))
-                .load(conn)?;
-
-            if children.is_empty() {
-                definitions.push(PropertyValue::from(root_value));
-            } else {
-                definitions.push(PropertyValue::from((
-                    root_value,
-                    get_property_values(conn, children)?,
-                )));
-            }
-        }
-
-        Ok(definitions)
-    }
+    pub use super::super::get_mfg_batch::sqlite::get_property_values;
 }
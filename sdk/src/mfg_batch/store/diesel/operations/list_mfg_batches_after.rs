@@ -0,0 +1,397 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::list_mfg_batches::pg as pg_list;
+#[cfg(feature = "sqlite")]
+use super::list_mfg_batches::sqlite as sqlite_list;
+#[cfg(feature = "mfg-batch-keyset-paging")]
+use crate::mfg_batch::store::MfgBatchCursor;
+use crate::mfg_batch::store::{
+    diesel::{models::MfgBatch as ModelMfgBatch, schema::mfg_batch},
+    error::MfgBatchStoreError,
+    ListMfgBatchFilters, MfgBatch,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchesAfterOperation {
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
+    /// Lists the mfg_batches after the cursor in (mfg_batch ID, row ID) order, each with the
+    /// cursor of its row, so mfg_batches sharing an ID are not skipped between pages
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after_cursor(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchesAfterOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = pg_list::list_query(service_id, filters)
+                .limit(limit)
+                .order(mfg_batch::mfg_batch_id.asc());
+
+            if let Some(after) = after {
+                query = query.filter(mfg_batch::mfg_batch_id.gt(after));
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values = pg_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = pg_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    pg_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    pg_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                mfg_batches.push(MfgBatch::from((mfg_batch, values, parents, attachments)));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after_cursor(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = pg_list::list_query(service_id, filters)
+                .limit(limit)
+                .order((mfg_batch::mfg_batch_id.asc(), mfg_batch::id.asc()));
+
+            if let Some(cursor) = cursor {
+                query = query.filter(
+                    mfg_batch::mfg_batch_id
+                        .gt(cursor.mfg_batch_id())
+                        .or(mfg_batch::mfg_batch_id
+                            .eq(cursor.mfg_batch_id())
+                            .and(mfg_batch::id.gt(cursor.id()))),
+                );
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values = pg_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = pg_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    pg_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    pg_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                let cursor = MfgBatchCursor::new(mfg_batch.mfg_batch_id.clone(), mfg_batch.id);
+                mfg_batches.push((
+                    cursor,
+                    MfgBatch::from((mfg_batch, values, parents, attachments)),
+                ));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchesAfterOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = sqlite_list::list_query(service_id, filters)
+                .limit(limit)
+                .order(mfg_batch::mfg_batch_id.asc());
+
+            if let Some(after) = after {
+                query = query.filter(mfg_batch::mfg_batch_id.gt(after));
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values =
+                    sqlite_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = sqlite_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    sqlite_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    sqlite_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                mfg_batches.push(MfgBatch::from((mfg_batch, values, parents, attachments)));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after_cursor(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = sqlite_list::list_query(service_id, filters)
+                .limit(limit)
+                .order((mfg_batch::mfg_batch_id.asc(), mfg_batch::id.asc()));
+
+            if let Some(cursor) = cursor {
+                query = query.filter(
+                    mfg_batch::mfg_batch_id
+                        .gt(cursor.mfg_batch_id())
+                        .or(mfg_batch::mfg_batch_id
+                            .eq(cursor.mfg_batch_id())
+                            .and(mfg_batch::id.gt(cursor.id()))),
+                );
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values =
+                    sqlite_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = sqlite_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    sqlite_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    sqlite_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                let cursor = MfgBatchCursor::new(mfg_batch.mfg_batch_id.clone(), mfg_batch.id);
+                mfg_batches.push((
+                    cursor,
+                    MfgBatch::from((mfg_batch, values, parents, attachments)),
+                ));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::{store::MfgBatchIter, MAX_COMMIT_NUM};
+
+    /// Verify that pages continue after the given mfg_batch ID in ID order, and that an iterator
+    /// built on the operation walks every current mfg_batch exactly once
+    #[test]
+    fn test_list_mfg_batches_after() {
+        let conn = connection();
+
+        let filters = ListMfgBatchFilters::default();
+        let ops = MfgBatchStoreOperations::new(&conn);
+        let ids = |mfg_batches: Vec<MfgBatch>| {
+            mfg_batches
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids(ops
+                .list_mfg_batches_after(None, &filters, None, 2)
+                .expect("Failed to list first page")),
+            vec!["batch1", "batch2"]
+        );
+        assert_eq!(
+            ids(ops
+                .list_mfg_batches_after(None, &filters, Some("batch2"), 2)
+                .expect("Failed to list second page")),
+            vec!["batch3", "batch4"]
+        );
+        assert_eq!(
+            ids(ops
+                .list_mfg_batches_after(None, &filters, Some("batch4"), 2)
+                .expect("Failed to list last page")),
+            vec!["batch5"]
+        );
+
+        let walked = MfgBatchIter::new(2, |after| {
+            ops.list_mfg_batches_after(None, &filters, after, 2)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to iterate");
+
+        assert_eq!(
+            ids(walked),
+            vec!["batch1", "batch2", "batch3", "batch4", "batch5"]
+        );
+    }
+
+    /// Verify that cursor pages continue after the cursor's row in (mfg_batch ID, row ID)
+    /// order, so that mfg_batches sharing an ID are neither skipped nor repeated across a page
+    /// boundary, and that each row's cursor round trips through its encoding
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    #[test]
+    fn test_list_mfg_batches_after_cursor() {
+        let conn = connection();
+        conn.batch_execute(&format!(
+            "INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES ('batch2', 'addr2-b', 'ns', 'org', 3, {max}, NULL);",
+            max = MAX_COMMIT_NUM
+        ))
+        .expect("Failed to insert mfg_batch");
+
+        let filters = ListMfgBatchFilters::default();
+        let ops = MfgBatchStoreOperations::new(&conn);
+        let addresses = |page: &[(MfgBatchCursor, MfgBatch)]| {
+            page.iter()
+                .map(|(_, mfg_batch)| mfg_batch.mfg_batch_address().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let first = ops
+            .list_mfg_batches_after_cursor(None, &filters, None, 2)
+            .expect("Failed to list first page");
+        assert_eq!(addresses(&first), vec!["addr1", "addr2"]);
+
+        let cursor = MfgBatchCursor::decode(&first[1].0.encode()).expect("Failed to decode");
+        assert_eq!(cursor, first[1].0);
+
+        let second = ops
+            .list_mfg_batches_after_cursor(None, &filters, Some(&cursor), 2)
+            .expect("Failed to list second page");
+        assert_eq!(addresses(&second), vec!["addr2-b", "addr3"]);
+
+        let last = ops
+            .list_mfg_batches_after_cursor(None, &filters, Some(&second[1].0), 5)
+            .expect("Failed to list last page");
+        assert_eq!(addresses(&last), vec!["addr4", "addr5"]);
+
+        assert!(MfgBatchCursor::decode("not a cursor").is_err());
+    }
+
+    fn connection() -> SqliteConnection {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(&format!(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_property_value (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                property_name TEXT NOT NULL,
+                parent_property TEXT,
+                data_type TEXT NOT NULL,
+                bytes_value BLOB,
+                boolean_value BOOLEAN,
+                number_value BIGINT,
+                string_value TEXT,
+                enum_value INTEGER,
+                latitude_value BIGINT,
+                longitude_value BIGINT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                language TEXT
+            );
+            CREATE TABLE mfg_batch_parent (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                parent_mfg_batch_id TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_attachment (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size BIGINT NOT NULL,
+                uri TEXT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES ('batch3', 'addr3', 'ns', 'org', 1, {max}, NULL),
+                ('batch1', 'addr1', 'ns', 'org', 1, {max}, NULL),
+                ('batch5', 'addr5', 'ns', 'org', 1, {max}, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 1, 2, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 2, {max}, NULL),
+                ('batch4', 'addr4', 'ns', 'org', 1, {max}, NULL),
+                ('batch0', 'addr0', 'ns', 'org', 1, {max}, 'service');",
+            max = MAX_COMMIT_NUM
+        ))
+        .expect("Failed to create tables");
+
+        conn
+    }
+}
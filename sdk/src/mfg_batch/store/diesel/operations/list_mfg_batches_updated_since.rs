@@ -0,0 +1,294 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::list_mfg_batches::pg as pg_list;
+#[cfg(feature = "sqlite")]
+use super::list_mfg_batches::sqlite as sqlite_list;
+use crate::error::InvalidArgumentError;
+use crate::mfg_batch::{
+    store::{
+        diesel::{models::MfgBatch as ModelMfgBatch, schema::mfg_batch},
+        error::MfgBatchStoreError,
+        MfgBatch,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ListMfgBatchesUpdatedSinceOperation {
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListMfgBatchesUpdatedSinceOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let since = to_date_time(since)?;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table
+                .into_boxed()
+                .select(mfg_batch::all_columns)
+                .filter(
+                    mfg_batch::end_commit_num
+                        .eq(MAX_COMMIT_NUM)
+                        .and(mfg_batch::last_updated.ge(since)),
+                )
+                .order((mfg_batch::last_updated.asc(), mfg_batch::mfg_batch_id.asc()));
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values = pg_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = pg_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    pg_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    pg_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                mfg_batches.push(MfgBatch::from((mfg_batch, values, parents, attachments)));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListMfgBatchesUpdatedSinceOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let since = to_date_time(since)?;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut query = mfg_batch::table
+                .into_boxed()
+                .select(mfg_batch::all_columns)
+                .filter(
+                    mfg_batch::end_commit_num
+                        .eq(MAX_COMMIT_NUM)
+                        .and(mfg_batch::last_updated.ge(since)),
+                )
+                .order((mfg_batch::last_updated.asc(), mfg_batch::mfg_batch_id.asc()));
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch::service_id.is_null());
+            }
+
+            let mut mfg_batches = Vec::new();
+
+            for mfg_batch in query.load::<ModelMfgBatch>(&*self.conn)? {
+                let root_values =
+                    sqlite_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+
+                let values = sqlite_list::get_property_values(&*self.conn, root_values)?;
+
+                let parents =
+                    sqlite_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                let attachments =
+                    sqlite_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                mfg_batches.push(MfgBatch::from((mfg_batch, values, parents, attachments)));
+            }
+
+            Ok(mfg_batches)
+        })
+    }
+}
+
+fn to_date_time(since: i64) -> Result<NaiveDateTime, MfgBatchStoreError> {
+    NaiveDateTime::from_timestamp_opt(since, 0).ok_or_else(|| {
+        MfgBatchStoreError::InvalidArgumentError(InvalidArgumentError::new(
+            "since".to_string(),
+            format!("{} is not a valid timestamp", since),
+        ))
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use chrono::Utc;
+
+    use crate::mfg_batch::store::{
+        diesel::{
+            models::{NewMfgBatch, NewMfgBatchParent, NewMfgBatchPropertyValue},
+            operations::add_mfg_batch::sqlite::insert_mfg_batch,
+        },
+        MfgBatchBuilder,
+    };
+
+    type MfgBatchModels = (
+        NewMfgBatch,
+        Vec<NewMfgBatchPropertyValue>,
+        Vec<NewMfgBatchParent>,
+    );
+
+    /// Verify that writes record when the mfg_batch was last updated, and that only the current
+    /// mfg_batches updated at or after a time are listed, oldest update first
+    #[test]
+    fn test_list_mfg_batches_updated_since() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_property_value (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                property_name TEXT NOT NULL,
+                parent_property TEXT,
+                data_type TEXT NOT NULL,
+                bytes_value BLOB,
+                boolean_value BOOLEAN,
+                number_value BIGINT,
+                string_value TEXT,
+                enum_value INTEGER,
+                latitude_value BIGINT,
+                longitude_value BIGINT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                language TEXT
+            );
+            CREATE TABLE mfg_batch_parent (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                parent_mfg_batch_id TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_attachment (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size BIGINT NOT NULL,
+                uri TEXT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );",
+        )
+        .expect("Failed to create tables");
+
+        let add = |mfg_batch: MfgBatch| {
+            let (model, _, _): MfgBatchModels = mfg_batch.into();
+            insert_mfg_batch(&conn, &model).expect("Failed to add mfg_batch")
+        };
+        add(mfg_batch("batch1", 1, Some(2_000)));
+        add(mfg_batch("batch2", 1, Some(1_000)));
+        add(mfg_batch("batch3", 1, Some(1_500)));
+        // The new version of batch3 replaces the old one
+        add(mfg_batch("batch3", 2, Some(3_000)));
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+        let ids = |since| {
+            ops.list_mfg_batches_updated_since(since, None)
+                .expect("Failed to list mfg_batches")
+                .iter()
+                .map(|mfg_batch| {
+                    (
+                        mfg_batch.mfg_batch_id().to_string(),
+                        *mfg_batch.last_updated().expect("No last updated time"),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids(0),
+            vec![
+                ("batch2".to_string(), 1_000),
+                ("batch1".to_string(), 2_000),
+                ("batch3".to_string(), 3_000),
+            ]
+        );
+        assert_eq!(
+            ids(2_000),
+            vec![("batch1".to_string(), 2_000), ("batch3".to_string(), 3_000)]
+        );
+        assert!(ids(3_001).is_empty());
+
+        // Without a timestamp, the write is recorded at the current time
+        let now = Utc::now().timestamp();
+        add(mfg_batch("batch4", 2, None));
+        let updated = ids(now);
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].0, "batch4");
+    }
+
+    fn mfg_batch(mfg_batch_id: &str, commit_num: i64, last_updated: Option<i64>) -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
+            .with_mfg_batch_address(format!("addr-{}", mfg_batch_id))
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner("org".to_string())
+            .with_start_commit_number(commit_num)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .with_last_updated(last_updated)
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+}
@@ -0,0 +1,580 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::mfg_batch_exists::MfgBatchExistsOperation;
+use super::resolve_mfg_batch_alias::ResolveMfgBatchAliasOperation;
+use super::MfgBatchStoreOperations;
+
+use crate::error::InvalidArgumentError;
+#[cfg(feature = "mfg-batch-annotations")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_annotation;
+#[cfg(feature = "mfg-batch-duplicates")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_duplicate;
+#[cfg(feature = "mfg-batch-quality-scores")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_quality_score;
+#[cfg(feature = "mfg-batch-test-results")]
+use crate::mfg_batch::store::diesel::schema::mfg_batch_test_result;
+use crate::mfg_batch::store::{
+    diesel::{models::NewMfgBatchAlias, schema::mfg_batch_alias},
+    error::MfgBatchStoreError,
+    MfgBatchAlias,
+};
+
+use diesel::{
+    dsl::{insert_into, update},
+    prelude::*,
+};
+
+pub(in crate::mfg_batch) trait MergeMfgBatchesOperation {
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> MergeMfgBatchesOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            check_merge(self, &alias)?;
+
+            #[cfg(feature = "mfg-batch-annotations")]
+            pg::move_annotations(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-test-results")]
+            pg::move_test_results(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-quality-scores")]
+            pg::remove_quality_scores(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-duplicates")]
+            pg::remove_duplicates(&*self.conn, &alias)?;
+            pg::move_aliases(&*self.conn, &alias)?;
+
+            insert_into(mfg_batch_alias::table)
+                .values(NewMfgBatchAlias::from(alias))
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> MergeMfgBatchesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            check_merge(self, &alias)?;
+
+            #[cfg(feature = "mfg-batch-annotations")]
+            sqlite::move_annotations(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-test-results")]
+            sqlite::move_test_results(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-quality-scores")]
+            sqlite::remove_quality_scores(&*self.conn, &alias)?;
+            #[cfg(feature = "mfg-batch-duplicates")]
+            sqlite::remove_duplicates(&*self.conn, &alias)?;
+            sqlite::move_aliases(&*self.conn, &alias)?;
+
+            insert_into(mfg_batch_alias::table)
+                .values(NewMfgBatchAlias::from(alias))
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Checks that both mfg_batches are current and that neither has already been merged, which
+/// keeps every alias pointing directly at a mfg_batch rather than at another alias
+fn check_merge<O>(operations: &O, alias: &MfgBatchAlias) -> Result<(), MfgBatchStoreError>
+where
+    O: MfgBatchExistsOperation + ResolveMfgBatchAliasOperation,
+{
+    let service_id = alias.service_id.as_deref();
+
+    if alias.alias == alias.mfg_batch_id {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "alias".to_string(),
+                "a mfg_batch cannot be merged into itself".to_string(),
+            ),
+        ));
+    }
+
+    for mfg_batch_id in &[&alias.alias, &alias.mfg_batch_id] {
+        if let Some(merged_into) = operations.resolve_mfg_batch_alias(mfg_batch_id, service_id)? {
+            return Err(MfgBatchStoreError::InvalidArgumentError(
+                InvalidArgumentError::new(
+                    "alias".to_string(),
+                    format!(
+                        "mfg_batch {} was already merged into {}",
+                        mfg_batch_id, merged_into
+                    ),
+                ),
+            ));
+        }
+
+        if !operations.mfg_batch_exists(mfg_batch_id, service_id)? {
+            return Err(MfgBatchStoreError::NotFoundError(format!(
+                "Mfg_batch {}",
+                mfg_batch_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Re-points the annotations on the merged mfg_batch at the one it was merged into
+    #[cfg(feature = "mfg-batch-annotations")]
+    pub fn move_annotations(conn: &PgConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_annotation::table
+            .into_boxed()
+            .select(mfg_batch_annotation::id)
+            .filter(mfg_batch_annotation::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_annotation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_annotation::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_annotation::table.filter(mfg_batch_annotation::id.eq_any(ids)))
+            .set(mfg_batch_annotation::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Re-points the test results of the merged mfg_batch at the one it was merged into
+    #[cfg(feature = "mfg-batch-test-results")]
+    pub fn move_test_results(conn: &PgConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_test_result::table
+            .into_boxed()
+            .select(mfg_batch_test_result::id)
+            .filter(mfg_batch_test_result::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_test_result::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_test_result::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_test_result::table.filter(mfg_batch_test_result::id.eq_any(ids)))
+            .set(mfg_batch_test_result::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Removes the quality scores of the merged mfg_batch, which scored its on-chain data rather
+    /// than that of the mfg_batch it was merged into
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    pub fn remove_quality_scores(conn: &PgConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_quality_score::table
+            .into_boxed()
+            .select(mfg_batch_quality_score::id)
+            .filter(mfg_batch_quality_score::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_quality_score::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_quality_score::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        diesel::delete(
+            mfg_batch_quality_score::table.filter(mfg_batch_quality_score::id.eq_any(ids)),
+        )
+        .execute(conn)
+        .map(|_| ())
+    }
+
+    /// Removes the merged mfg_batch from the duplicate review queue
+    #[cfg(feature = "mfg-batch-duplicates")]
+    pub fn remove_duplicates(conn: &PgConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_duplicate::table
+            .into_boxed()
+            .select(mfg_batch_duplicate::id)
+            .filter(
+                mfg_batch_duplicate::mfg_batch_id
+                    .eq(&alias.alias)
+                    .or(mfg_batch_duplicate::duplicate_of.eq(&alias.alias)),
+            );
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_duplicate::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_duplicate::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        diesel::delete(mfg_batch_duplicate::table.filter(mfg_batch_duplicate::id.eq_any(ids)))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Re-points the aliases of mfg_batches previously merged into the merged mfg_batch
+    pub fn move_aliases(conn: &PgConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::id)
+            .filter(mfg_batch_alias::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_alias::table.filter(mfg_batch_alias::id.eq_any(ids)))
+            .set(mfg_batch_alias::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Re-points the annotations on the merged mfg_batch at the one it was merged into
+    #[cfg(feature = "mfg-batch-annotations")]
+    pub fn move_annotations(conn: &SqliteConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_annotation::table
+            .into_boxed()
+            .select(mfg_batch_annotation::id)
+            .filter(mfg_batch_annotation::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_annotation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_annotation::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_annotation::table.filter(mfg_batch_annotation::id.eq_any(ids)))
+            .set(mfg_batch_annotation::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Re-points the test results of the merged mfg_batch at the one it was merged into
+    #[cfg(feature = "mfg-batch-test-results")]
+    pub fn move_test_results(conn: &SqliteConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_test_result::table
+            .into_boxed()
+            .select(mfg_batch_test_result::id)
+            .filter(mfg_batch_test_result::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_test_result::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_test_result::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_test_result::table.filter(mfg_batch_test_result::id.eq_any(ids)))
+            .set(mfg_batch_test_result::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Removes the quality scores of the merged mfg_batch, which scored its on-chain data rather
+    /// than that of the mfg_batch it was merged into
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    pub fn remove_quality_scores(
+        conn: &SqliteConnection,
+        alias: &MfgBatchAlias,
+    ) -> QueryResult<()> {
+        let mut query = mfg_batch_quality_score::table
+            .into_boxed()
+            .select(mfg_batch_quality_score::id)
+            .filter(mfg_batch_quality_score::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_quality_score::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_quality_score::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        diesel::delete(
+            mfg_batch_quality_score::table.filter(mfg_batch_quality_score::id.eq_any(ids)),
+        )
+        .execute(conn)
+        .map(|_| ())
+    }
+
+    /// Removes the merged mfg_batch from the duplicate review queue
+    #[cfg(feature = "mfg-batch-duplicates")]
+    pub fn remove_duplicates(conn: &SqliteConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_duplicate::table
+            .into_boxed()
+            .select(mfg_batch_duplicate::id)
+            .filter(
+                mfg_batch_duplicate::mfg_batch_id
+                    .eq(&alias.alias)
+                    .or(mfg_batch_duplicate::duplicate_of.eq(&alias.alias)),
+            );
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_duplicate::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_duplicate::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        diesel::delete(mfg_batch_duplicate::table.filter(mfg_batch_duplicate::id.eq_any(ids)))
+            .execute(conn)
+            .map(|_| ())
+    }
+
+    /// Re-points the aliases of mfg_batches previously merged into the merged mfg_batch
+    pub fn move_aliases(conn: &SqliteConnection, alias: &MfgBatchAlias) -> QueryResult<()> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::id)
+            .filter(mfg_batch_alias::mfg_batch_id.eq(&alias.alias));
+
+        if let Some(service_id) = &alias.service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        let ids = query.load::<i64>(conn)?;
+
+        update(mfg_batch_alias::table.filter(mfg_batch_alias::id.eq_any(ids)))
+            .set(mfg_batch_alias::mfg_batch_id.eq(&alias.mfg_batch_id))
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "sqlite",
+    feature = "mfg-batch-annotations",
+    feature = "mfg-batch-duplicates"
+))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::diesel::operations::{
+        get_mfg_batch::GetMfgBatchOperation, list_mfg_batch_aliases::ListMfgBatchAliasesOperation,
+    };
+
+    fn alias(alias: &str, mfg_batch_id: &str, merged_at: i64) -> MfgBatchAlias {
+        MfgBatchAlias {
+            alias: alias.to_string(),
+            mfg_batch_id: mfg_batch_id.to_string(),
+            merged_at,
+            service_id: None,
+        }
+    }
+
+    /// Verify that merging moves the merged mfg_batch's annotations, drops it from the duplicate
+    /// review queue and resolves lookups by its id, and that an alias follows its mfg_batch when
+    /// that is merged in turn
+    #[test]
+    fn test_merge_mfg_batches() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_property_value (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                property_name TEXT NOT NULL,
+                parent_property TEXT,
+                data_type TEXT NOT NULL,
+                bytes_value BLOB,
+                boolean_value BOOLEAN,
+                number_value BIGINT,
+                string_value TEXT,
+                enum_value INTEGER,
+                latitude_value BIGINT,
+                longitude_value BIGINT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                language TEXT
+            );
+            CREATE TABLE mfg_batch_parent (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                parent_mfg_batch_id TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_attachment (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size BIGINT NOT NULL,
+                uri TEXT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_annotation (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                property_name TEXT NOT NULL,
+                author TEXT NOT NULL,
+                comment TEXT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_test_result (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                test_name TEXT NOT NULL,
+                specification TEXT NOT NULL,
+                result TEXT NOT NULL,
+                passed BOOLEAN NOT NULL,
+                lab TEXT NOT NULL,
+                tested_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_quality_score (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                commit_num BIGINT NOT NULL,
+                score INTEGER NOT NULL,
+                failed_checks TEXT NOT NULL,
+                scored_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_duplicate (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                duplicate_of TEXT NOT NULL,
+                rule TEXT NOT NULL,
+                similarity INTEGER NOT NULL,
+                detected_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_alias (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                alias TEXT NOT NULL,
+                mfg_batch_id TEXT NOT NULL,
+                merged_at BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES
+                ('batch1', 'addr1', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 2, 9223372036854775807, NULL),
+                ('batch3', 'addr3', 'ns', 'org', 2, 9223372036854775807, NULL);
+            INSERT INTO mfg_batch_annotation (mfg_batch_id, commit_num, property_name, author,
+                comment, service_id)
+            VALUES ('batch2', 2, 'lot_code', 'qa', 'Relabelled', NULL);
+            INSERT INTO mfg_batch_duplicate (mfg_batch_id, duplicate_of, rule, similarity,
+                detected_at, service_id)
+            VALUES
+                ('batch2', 'batch1', 'same_gtin_and_lot', 100, 1600100000, NULL),
+                ('batch3', 'batch1', 'similar_properties', 90, 1600100000, NULL);",
+        )
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+
+        ops.merge_mfg_batches(alias("batch2", "batch1", 1_600_200_000))
+            .expect("Failed to merge mfg_batches");
+
+        assert_eq!(
+            mfg_batch_annotation::table
+                .select(mfg_batch_annotation::mfg_batch_id)
+                .load::<String>(&conn)
+                .expect("Failed to load annotations"),
+            vec!["batch1".to_string()]
+        );
+        assert_eq!(
+            mfg_batch_duplicate::table
+                .select(mfg_batch_duplicate::mfg_batch_id)
+                .load::<String>(&conn)
+                .expect("Failed to load duplicates"),
+            vec!["batch3".to_string()]
+        );
+        assert_eq!(
+            ops.get_mfg_batch("batch2", None)
+                .expect("Failed to get mfg_batch")
+                .expect("Mfg_batch not found")
+                .mfg_batch_id(),
+            "batch1"
+        );
+
+        assert!(matches!(
+            ops.merge_mfg_batches(alias("batch1", "batch2", 1_600_300_000)),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+        assert!(matches!(
+            ops.merge_mfg_batches(alias("batch1", "batch1", 1_600_300_000)),
+            Err(MfgBatchStoreError::InvalidArgumentError(_))
+        ));
+        assert!(matches!(
+            ops.merge_mfg_batches(alias("batch4", "batch1", 1_600_300_000)),
+            Err(MfgBatchStoreError::NotFoundError(_))
+        ));
+
+        ops.merge_mfg_batches(alias("batch1", "batch3", 1_600_300_000))
+            .expect("Failed to merge mfg_batches");
+
+        assert_eq!(
+            ops.list_mfg_batch_aliases(None)
+                .expect("Failed to list aliases"),
+            vec![
+                alias("batch2", "batch3", 1_600_200_000),
+                alias("batch1", "batch3", 1_600_300_000),
+            ]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{diesel::schema::mfg_batch, error::MfgBatchStoreError},
+    MAX_COMMIT_NUM,
+};
+
+use diesel::{dsl::exists, prelude::*, select};
+
+pub(in crate::mfg_batch) trait MfgBatchExistsOperation {
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> MfgBatchExistsOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        let query = mfg_batch::table.filter(
+            mfg_batch::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+
+        if let Some(service_id) = service_id {
+            select(exists(query.filter(mfg_batch::service_id.eq(service_id))))
+                .get_result::<bool>(self.conn)
+        } else {
+            select(exists(query.filter(mfg_batch::service_id.is_null())))
+                .get_result::<bool>(self.conn)
+        }
+        .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> MfgBatchExistsOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        let query = mfg_batch::table.filter(
+            mfg_batch::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+
+        if let Some(service_id) = service_id {
+            select(exists(query.filter(mfg_batch::service_id.eq(service_id))))
+                .get_result::<bool>(self.conn)
+        } else {
+            select(exists(query.filter(mfg_batch::service_id.is_null())))
+                .get_result::<bool>(self.conn)
+        }
+        .map_err(MfgBatchStoreError::from)
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::schema::{mfg_batch, mfg_batch_shared_with},
+        error::MfgBatchStoreError,
+        Visibility,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait MfgBatchVisibleOperation {
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> MfgBatchVisibleOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        let query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        let shared = mfg_batch_shared_with::table
+            .select(mfg_batch_shared_with::mfg_batch_id)
+            .filter(mfg_batch_shared_with::org_id.eq(&visibility.org_id));
+        let owned = mfg_batch::owner.eq(&visibility.org_id);
+
+        let query = if let Some(service_id) = service_id {
+            query.filter(mfg_batch::service_id.eq(service_id)).filter(
+                owned.or(mfg_batch::mfg_batch_id
+                    .eq_any(shared.filter(mfg_batch_shared_with::service_id.eq(service_id)))),
+            )
+        } else {
+            query.filter(mfg_batch::service_id.is_null()).filter(
+                owned.or(mfg_batch::mfg_batch_id
+                    .eq_any(shared.filter(mfg_batch_shared_with::service_id.is_null()))),
+            )
+        };
+
+        query
+            .first::<i64>(self.conn)
+            .optional()
+            .map(|id| id.is_some())
+            .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> MfgBatchVisibleOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        let query = mfg_batch::table.into_boxed().select(mfg_batch::id).filter(
+            mfg_batch::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+        );
+        let shared = mfg_batch_shared_with::table
+            .select(mfg_batch_shared_with::mfg_batch_id)
+            .filter(mfg_batch_shared_with::org_id.eq(&visibility.org_id));
+        let owned = mfg_batch::owner.eq(&visibility.org_id);
+
+        let query = if let Some(service_id) = service_id {
+            query.filter(mfg_batch::service_id.eq(service_id)).filter(
+                owned.or(mfg_batch::mfg_batch_id
+                    .eq_any(shared.filter(mfg_batch_shared_with::service_id.eq(service_id)))),
+            )
+        } else {
+            query.filter(mfg_batch::service_id.is_null()).filter(
+                owned.or(mfg_batch::mfg_batch_id
+                    .eq_any(shared.filter(mfg_batch_shared_with::service_id.is_null()))),
+            )
+        };
+
+        query
+            .first::<i64>(self.conn)
+            .optional()
+            .map(|id| id.is_some())
+            .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    use crate::mfg_batch::store::{
+        diesel::operations::{
+            count_mfg_batches::CountMfgBatchesOperation,
+            list_mfg_batch_shares::ListMfgBatchSharesOperation,
+            share_mfg_batch::ShareMfgBatchOperation, unshare_mfg_batch::UnshareMfgBatchOperation,
+        },
+        ListMfgBatchFilters,
+    };
+
+    /// Verify that a mfg_batch is visible to the organization that owns it and to those it is
+    /// shared with, and that restricted counts agree
+    #[test]
+    fn test_mfg_batch_visible() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(&format!(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_shared_with (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                org_id TEXT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES ('batch1', 'addr1', 'ns', 'org1', 1, {max}, NULL),
+                ('batch2', 'addr2', 'ns', 'org2', 1, {max}, NULL),
+                ('batch3', 'addr3', 'ns', 'org2', 1, {max}, 'service');",
+            max = MAX_COMMIT_NUM
+        ))
+        .expect("Failed to create tables");
+
+        let ops = MfgBatchStoreOperations::new(&conn);
+        let org1 = Visibility::org("org1");
+        let filters = ListMfgBatchFilters {
+            visibility: Some(org1.clone()),
+            ..Default::default()
+        };
+
+        assert!(ops.mfg_batch_visible("batch1", &org1, None).unwrap());
+        assert!(!ops.mfg_batch_visible("batch2", &org1, None).unwrap());
+        assert_eq!(ops.count_mfg_batches(None, &filters).unwrap(), 1);
+
+        ops.share_mfg_batch("batch2", "org1", None)
+            .expect("Failed to share batch2");
+        ops.share_mfg_batch("batch2", "org1", None)
+            .expect("Failed to share batch2 again");
+        assert_eq!(
+            ops.list_mfg_batch_shares("batch2", None).unwrap(),
+            vec!["org1"]
+        );
+        assert!(ops.mfg_batch_visible("batch2", &org1, None).unwrap());
+        assert!(!ops
+            .mfg_batch_visible("batch3", &org1, Some("service"))
+            .unwrap());
+        assert_eq!(ops.count_mfg_batches(None, &filters).unwrap(), 2);
+
+        assert!(ops.share_mfg_batch("batch4", "org1", None).is_err());
+
+        ops.unshare_mfg_batch("batch2", "org1", None)
+            .expect("Failed to unshare batch2");
+        assert!(!ops.mfg_batch_visible("batch2", &org1, None).unwrap());
+        assert_eq!(ops.count_mfg_batches(None, &filters).unwrap(), 1);
+    }
+}
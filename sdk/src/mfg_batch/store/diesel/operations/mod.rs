@@ -13,10 +13,90 @@
 // limitations under the License.
 
 pub(super) mod add_mfg_batch;
+#[cfg(feature = "mfg-batch-anchors")]
+pub(super) mod add_mfg_batch_anchor;
+#[cfg(feature = "mfg-batch-annotations")]
+pub(super) mod add_mfg_batch_annotation;
+#[cfg(feature = "mfg-batch-recalls")]
+pub(super) mod add_mfg_batch_recalls;
+#[cfg(feature = "mfg-batch-test-results")]
+pub(super) mod add_mfg_batch_test_result;
+pub(super) mod add_mfg_batches;
+#[cfg(feature = "mfg-batch-recalls")]
+pub(super) mod close_mfg_batch_recall;
+#[cfg(feature = "mfg-batch-row-counts")]
+pub(super) mod count_mfg_batch_version_rows;
+pub(super) mod count_mfg_batches;
+#[cfg(feature = "mfg-batch-partitioning")]
+pub(super) mod create_mfg_batch_archive_partition;
 pub(super) mod delete_mfg_batch;
+#[cfg(feature = "mfg-batch-explain")]
+pub(super) mod explain_list_mfg_batches;
+#[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+pub(super) mod explain_search_mfg_batches_text;
 pub(super) mod get_mfg_batch;
+pub(super) mod get_mfg_batch_ancestry;
+pub(super) mod get_mfg_batch_at_commit;
+#[cfg(feature = "mfg-batch-allocations")]
+pub(super) mod get_mfg_batch_availability;
+pub(super) mod get_mfg_batch_descendants;
+#[cfg(feature = "mfg-batch-row-counts")]
+pub(super) mod get_mfg_batch_properties;
+pub(super) mod get_mfg_batches;
+pub(super) mod get_property_value_history;
+#[cfg(feature = "mfg-batch-merge")]
+pub(super) mod list_mfg_batch_aliases;
+#[cfg(feature = "mfg-batch-allocations")]
+pub(super) mod list_mfg_batch_allocations;
+#[cfg(feature = "mfg-batch-anchors")]
+pub(super) mod list_mfg_batch_anchors;
+#[cfg(feature = "mfg-batch-annotations")]
+pub(super) mod list_mfg_batch_annotations;
+#[cfg(feature = "mfg-batch-duplicates")]
+pub(super) mod list_mfg_batch_duplicates;
+pub(super) mod list_mfg_batch_history;
+#[cfg(feature = "mfg-batch-quality-scores")]
+pub(super) mod list_mfg_batch_quality_scores;
+#[cfg(feature = "mfg-batch-recalls")]
+pub(super) mod list_mfg_batch_recalls;
+#[cfg(feature = "mfg-batch-anchors")]
+pub(super) mod list_mfg_batch_record_hashes;
+#[cfg(feature = "mfg-batch-visibility")]
+pub(super) mod list_mfg_batch_shares;
+#[cfg(feature = "mfg-batch-test-results")]
+pub(super) mod list_mfg_batch_test_results;
+pub(super) mod list_mfg_batch_owners;
 pub(super) mod list_mfg_batches;
+pub(super) mod list_mfg_batches_after;
+pub(super) mod list_mfg_batches_updated_since;
+#[cfg(feature = "mfg-batch-merge")]
+pub(super) mod merge_mfg_batches;
+pub(super) mod mfg_batch_exists;
+#[cfg(feature = "mfg-batch-visibility")]
+pub(super) mod mfg_batch_visible;
+#[cfg(feature = "mfg-batch-pruning")]
+pub(super) mod prune_mfg_batch_history;
+#[cfg(feature = "mfg-batch-allocations")]
+pub(super) mod put_mfg_batch_allocation;
+#[cfg(feature = "mfg-batch-quality-scores")]
+pub(super) mod put_mfg_batch_quality_score;
+#[cfg(feature = "mfg-batch-duplicates")]
+pub(super) mod replace_mfg_batch_duplicates;
+#[cfg(feature = "mfg-batch-merge")]
+pub(super) mod resolve_mfg_batch_alias;
+pub(super) mod search_mfg_batches_by_property;
+#[cfg(feature = "mfg-batch-text-search")]
+pub(super) mod search_mfg_batches_text;
+#[cfg(feature = "mfg-batch-visibility")]
+pub(super) mod share_mfg_batch;
+#[cfg(feature = "mfg-batch-visibility")]
+pub(super) mod unshare_mfg_batch;
 pub(super) mod update_mfg_batch;
+pub(super) mod upsert_mfg_batch;
+#[cfg(feature = "mfg-batch-audit-log")]
+pub(super) mod verify_mfg_batch_audit_log;
+#[cfg(feature = "mfg-batch-checksums")]
+pub(super) mod verify_mfg_batch_checksums;
 
 pub(super) struct MfgBatchStoreOperations<'a, C> {
     conn: &'a C,
@@ -0,0 +1,204 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Every version of a mfg_batch is kept as rows ended by the commit that replaced it, so the
+//! versioned tables grow with every update. Pruning deletes the rows ended before a commit;
+//! current rows end at `MAX_COMMIT_NUM` and are never pruned. The audit log and change log
+//! record commits rather than versions and are left as they are.
+
+use super::MfgBatchStoreOperations;
+
+use crate::error::InvalidArgumentError;
+use crate::mfg_batch::store::{
+    diesel::schema::{mfg_batch, mfg_batch_attachment, mfg_batch_parent, mfg_batch_property_value},
+    error::MfgBatchStoreError,
+    PrunedMfgBatchHistory,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait PruneMfgBatchHistoryOperation {
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> PruneMfgBatchHistoryOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        check_before_commit_num(before_commit_num)?;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if dry_run {
+                pg::count_ended_rows(&*self.conn, before_commit_num)
+            } else {
+                pg::delete_ended_rows(&*self.conn, before_commit_num)
+            }
+            .map_err(MfgBatchStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> PruneMfgBatchHistoryOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        check_before_commit_num(before_commit_num)?;
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if dry_run {
+                sqlite::count_ended_rows(&*self.conn, before_commit_num)
+            } else {
+                sqlite::delete_ended_rows(&*self.conn, before_commit_num)
+            }
+            .map_err(MfgBatchStoreError::from)
+        })
+    }
+}
+
+fn check_before_commit_num(before_commit_num: i64) -> Result<(), MfgBatchStoreError> {
+    if before_commit_num < 0 {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "before_commit_num".to_string(),
+                "must be non-negative".to_string(),
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn count_ended_rows(
+        conn: &PgConnection,
+        before_commit_num: i64,
+    ) -> QueryResult<PrunedMfgBatchHistory> {
+        Ok(PrunedMfgBatchHistory {
+            mfg_batches: mfg_batch::table
+                .filter(mfg_batch::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            property_values: mfg_batch_property_value::table
+                .filter(mfg_batch_property_value::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            parents: mfg_batch_parent::table
+                .filter(mfg_batch_parent::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            attachments: mfg_batch_attachment::table
+                .filter(mfg_batch_attachment::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+        })
+    }
+
+    pub fn delete_ended_rows(
+        conn: &PgConnection,
+        before_commit_num: i64,
+    ) -> QueryResult<PrunedMfgBatchHistory> {
+        Ok(PrunedMfgBatchHistory {
+            mfg_batches: diesel::delete(
+                mfg_batch::table.filter(mfg_batch::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            property_values: diesel::delete(
+                mfg_batch_property_value::table
+                    .filter(mfg_batch_property_value::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            parents: diesel::delete(
+                mfg_batch_parent::table
+                    .filter(mfg_batch_parent::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            attachments: diesel::delete(
+                mfg_batch_attachment::table
+                    .filter(mfg_batch_attachment::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn count_ended_rows(
+        conn: &SqliteConnection,
+        before_commit_num: i64,
+    ) -> QueryResult<PrunedMfgBatchHistory> {
+        Ok(PrunedMfgBatchHistory {
+            mfg_batches: mfg_batch::table
+                .filter(mfg_batch::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            property_values: mfg_batch_property_value::table
+                .filter(mfg_batch_property_value::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            parents: mfg_batch_parent::table
+                .filter(mfg_batch_parent::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+            attachments: mfg_batch_attachment::table
+                .filter(mfg_batch_attachment::end_commit_num.lt(before_commit_num))
+                .count()
+                .get_result(conn)?,
+        })
+    }
+
+    pub fn delete_ended_rows(
+        conn: &SqliteConnection,
+        before_commit_num: i64,
+    ) -> QueryResult<PrunedMfgBatchHistory> {
+        Ok(PrunedMfgBatchHistory {
+            mfg_batches: diesel::delete(
+                mfg_batch::table.filter(mfg_batch::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            property_values: diesel::delete(
+                mfg_batch_property_value::table
+                    .filter(mfg_batch_property_value::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            parents: diesel::delete(
+                mfg_batch_parent::table
+                    .filter(mfg_batch_parent::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+            attachments: diesel::delete(
+                mfg_batch_attachment::table
+                    .filter(mfg_batch_attachment::end_commit_num.lt(before_commit_num)),
+            )
+            .execute(conn)? as i64,
+        })
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::error::InvalidArgumentError;
+use crate::mfg_batch::{
+    store::{
+        diesel::{
+            models::NewMfgBatchAllocation,
+            schema::{mfg_batch, mfg_batch_allocation},
+        },
+        error::MfgBatchStoreError,
+        MfgBatchAllocation,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::mfg_batch) trait PutMfgBatchAllocationOperation {
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> PutMfgBatchAllocationOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let allocation = NewMfgBatchAllocation::from(allocation);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let quantity =
+                pg::get_current_quantity(&*self.conn, &allocation)?.ok_or_else(|| {
+                    MfgBatchStoreError::NotFoundError(format!(
+                        "Mfg_batch {}",
+                        allocation.mfg_batch_id
+                    ))
+                })?;
+            let allocated = pg::get_other_allocations(&*self.conn, &allocation)?;
+
+            check_allocation(&allocation, quantity, &allocated)?;
+
+            pg::delete_allocation(&*self.conn, &allocation)?;
+            if allocation.quantity > 0 {
+                pg::insert_allocation(&*self.conn, &allocation)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> PutMfgBatchAllocationOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let allocation = NewMfgBatchAllocation::from(allocation);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let quantity =
+                sqlite::get_current_quantity(&*self.conn, &allocation)?.ok_or_else(|| {
+                    MfgBatchStoreError::NotFoundError(format!(
+                        "Mfg_batch {}",
+                        allocation.mfg_batch_id
+                    ))
+                })?;
+            let allocated = sqlite::get_other_allocations(&*self.conn, &allocation)?;
+
+            check_allocation(&allocation, quantity, &allocated)?;
+
+            sqlite::delete_allocation(&*self.conn, &allocation)?;
+            if allocation.quantity > 0 {
+                sqlite::insert_allocation(&*self.conn, &allocation)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Checks that the allocation, together with the mfg_batch's allocations to other orders, fits
+/// within the mfg_batch's produced quantity
+fn check_allocation(
+    allocation: &NewMfgBatchAllocation,
+    quantity: Option<i64>,
+    other_allocations: &[i64],
+) -> Result<(), MfgBatchStoreError> {
+    if allocation.quantity < 0 {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "quantity".to_string(),
+                "an allocated quantity cannot be negative".to_string(),
+            ),
+        ));
+    }
+
+    let allocated = other_allocations.iter().sum::<i64>() + allocation.quantity;
+    let quantity = quantity.unwrap_or(0);
+    if allocated > quantity {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "quantity".to_string(),
+                format!(
+                    "allocating {} to order {} would allocate {} of mfg_batch {}, which has \
+                     only {} available",
+                    allocation.quantity,
+                    allocation.order_id,
+                    allocated,
+                    allocation.mfg_batch_id,
+                    quantity
+                ),
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Returns the produced quantity of the current version of the mfg_batch, or `None` if there
+    /// is no current version of it
+    pub fn get_current_quantity(
+        conn: &PgConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<Option<Option<i64>>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::quantity)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(&allocation.mfg_batch_id)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<Option<i64>>(conn).optional()
+    }
+
+    /// Returns the quantities of the mfg_batch allocated to every order but the allocation's
+    pub fn get_other_allocations(
+        conn: &PgConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<Vec<i64>> {
+        let mut query = mfg_batch_allocation::table
+            .into_boxed()
+            .select(mfg_batch_allocation::quantity)
+            .filter(
+                mfg_batch_allocation::mfg_batch_id
+                    .eq(&allocation.mfg_batch_id)
+                    .and(mfg_batch_allocation::order_id.ne(&allocation.order_id)),
+            );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch_allocation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_allocation::service_id.is_null());
+        }
+
+        query.load::<i64>(conn)
+    }
+
+    pub fn delete_allocation(
+        conn: &PgConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<()> {
+        let mut query = delete(mfg_batch_allocation::table).into_boxed().filter(
+            mfg_batch_allocation::mfg_batch_id
+                .eq(&allocation.mfg_batch_id)
+                .and(mfg_batch_allocation::order_id.eq(&allocation.order_id)),
+        );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch_allocation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_allocation::service_id.is_null());
+        }
+
+        query.execute(conn).map(|_| ())
+    }
+
+    pub fn insert_allocation(
+        conn: &PgConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_allocation::table)
+            .values(allocation)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Returns the produced quantity of the current version of the mfg_batch, or `None` if there
+    /// is no current version of it
+    pub fn get_current_quantity(
+        conn: &SqliteConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<Option<Option<i64>>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::quantity)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(&allocation.mfg_batch_id)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first::<Option<i64>>(conn).optional()
+    }
+
+    /// Returns the quantities of the mfg_batch allocated to every order but the allocation's
+    pub fn get_other_allocations(
+        conn: &SqliteConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<Vec<i64>> {
+        let mut query = mfg_batch_allocation::table
+            .into_boxed()
+            .select(mfg_batch_allocation::quantity)
+            .filter(
+                mfg_batch_allocation::mfg_batch_id
+                    .eq(&allocation.mfg_batch_id)
+                    .and(mfg_batch_allocation::order_id.ne(&allocation.order_id)),
+            );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch_allocation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_allocation::service_id.is_null());
+        }
+
+        query.load::<i64>(conn)
+    }
+
+    pub fn delete_allocation(
+        conn: &SqliteConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<()> {
+        let mut query = delete(mfg_batch_allocation::table).into_boxed().filter(
+            mfg_batch_allocation::mfg_batch_id
+                .eq(&allocation.mfg_batch_id)
+                .and(mfg_batch_allocation::order_id.eq(&allocation.order_id)),
+        );
+
+        if let Some(service_id) = &allocation.service_id {
+            query = query.filter(mfg_batch_allocation::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_allocation::service_id.is_null());
+        }
+
+        query.execute(conn).map(|_| ())
+    }
+
+    pub fn insert_allocation(
+        conn: &SqliteConnection,
+        allocation: &NewMfgBatchAllocation,
+    ) -> QueryResult<()> {
+        insert_into(mfg_batch_allocation::table)
+            .values(allocation)
+            .execute(conn)
+            .map(|_| ())
+    }
+}
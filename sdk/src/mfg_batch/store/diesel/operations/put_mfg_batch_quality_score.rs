@@ -0,0 +1,117 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::NewMfgBatchQualityScore, schema::mfg_batch_quality_score},
+    error::MfgBatchStoreError,
+    MfgBatchQualityScore,
+};
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::mfg_batch) trait PutMfgBatchQualityScoreOperation {
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> PutMfgBatchQualityScoreOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        let score = NewMfgBatchQualityScore::from(score);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            pg::delete_quality_score(&*self.conn, &score)?;
+
+            insert_into(mfg_batch_quality_score::table)
+                .values(&score)
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> PutMfgBatchQualityScoreOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        let score = NewMfgBatchQualityScore::from(score);
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            sqlite::delete_quality_score(&*self.conn, &score)?;
+
+            insert_into(mfg_batch_quality_score::table)
+                .values(&score)
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Deletes the score the mfg_batch had before, if any
+    pub fn delete_quality_score(
+        conn: &PgConnection,
+        score: &NewMfgBatchQualityScore,
+    ) -> QueryResult<usize> {
+        let scores = mfg_batch_quality_score::table
+            .filter(mfg_batch_quality_score::mfg_batch_id.eq(&score.mfg_batch_id));
+
+        if let Some(service_id) = &score.service_id {
+            delete(scores.filter(mfg_batch_quality_score::service_id.eq(service_id))).execute(conn)
+        } else {
+            delete(scores.filter(mfg_batch_quality_score::service_id.is_null())).execute(conn)
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Deletes the score the mfg_batch had before, if any
+    pub fn delete_quality_score(
+        conn: &SqliteConnection,
+        score: &NewMfgBatchQualityScore,
+    ) -> QueryResult<usize> {
+        let scores = mfg_batch_quality_score::table
+            .filter(mfg_batch_quality_score::mfg_batch_id.eq(&score.mfg_batch_id));
+
+        if let Some(service_id) = &score.service_id {
+            delete(scores.filter(mfg_batch_quality_score::service_id.eq(service_id))).execute(conn)
+        } else {
+            delete(scores.filter(mfg_batch_quality_score::service_id.is_null())).execute(conn)
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{models::NewMfgBatchDuplicate, schema::mfg_batch_duplicate},
+    error::MfgBatchStoreError,
+    MfgBatchDuplicate,
+};
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::mfg_batch) trait ReplaceMfgBatchDuplicatesOperation {
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ReplaceMfgBatchDuplicatesOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let duplicates = duplicates
+            .into_iter()
+            .map(NewMfgBatchDuplicate::from)
+            .collect::<Vec<_>>();
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if let Some(service_id) = service_id {
+                delete(
+                    mfg_batch_duplicate::table
+                        .filter(mfg_batch_duplicate::service_id.eq(service_id)),
+                )
+                .execute(&*self.conn)?;
+            } else {
+                delete(
+                    mfg_batch_duplicate::table.filter(mfg_batch_duplicate::service_id.is_null()),
+                )
+                .execute(&*self.conn)?;
+            }
+
+            insert_into(mfg_batch_duplicate::table)
+                .values(&duplicates)
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ReplaceMfgBatchDuplicatesOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let duplicates = duplicates
+            .into_iter()
+            .map(NewMfgBatchDuplicate::from)
+            .collect::<Vec<_>>();
+
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if let Some(service_id) = service_id {
+                delete(
+                    mfg_batch_duplicate::table
+                        .filter(mfg_batch_duplicate::service_id.eq(service_id)),
+                )
+                .execute(&*self.conn)?;
+            } else {
+                delete(
+                    mfg_batch_duplicate::table.filter(mfg_batch_duplicate::service_id.is_null()),
+                )
+                .execute(&*self.conn)?;
+            }
+
+            insert_into(mfg_batch_duplicate::table)
+                .values(&duplicates)
+                .execute(&*self.conn)?;
+
+            Ok(())
+        })
+    }
+}
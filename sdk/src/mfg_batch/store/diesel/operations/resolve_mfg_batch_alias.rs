@@ -0,0 +1,79 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{diesel::schema::mfg_batch_alias, error::MfgBatchStoreError};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait ResolveMfgBatchAliasOperation {
+    fn resolve_mfg_batch_alias(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<String>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ResolveMfgBatchAliasOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn resolve_mfg_batch_alias(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<String>, MfgBatchStoreError> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::mfg_batch_id)
+            .filter(mfg_batch_alias::alias.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        query
+            .first::<String>(self.conn)
+            .optional()
+            .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ResolveMfgBatchAliasOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn resolve_mfg_batch_alias(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<String>, MfgBatchStoreError> {
+        let mut query = mfg_batch_alias::table
+            .into_boxed()
+            .select(mfg_batch_alias::mfg_batch_id)
+            .filter(mfg_batch_alias::alias.eq(mfg_batch_id));
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_alias::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_alias::service_id.is_null());
+        }
+
+        query
+            .first::<String>(self.conn)
+            .optional()
+            .map_err(MfgBatchStoreError::from)
+    }
+}
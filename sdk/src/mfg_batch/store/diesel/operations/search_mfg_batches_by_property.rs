@@ -0,0 +1,375 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+#[cfg(feature = "postgres")]
+use super::list_mfg_batches::pg as pg_list;
+#[cfg(feature = "sqlite")]
+use super::list_mfg_batches::sqlite as sqlite_list;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::{
+            models::MfgBatch as ModelMfgBatch,
+            schema::{mfg_batch, mfg_batch_property_value},
+        },
+        error::MfgBatchStoreError,
+        MfgBatch, PropertySearchValue,
+    },
+    MAX_COMMIT_NUM,
+};
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait SearchMfgBatchesByPropertyOperation {
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> SearchMfgBatchesByPropertyOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mfg_batch_ids =
+                pg::find_mfg_batch_ids(&*self.conn, property_name, value, service_id)?;
+
+            pg::get_mfg_batches(&*self.conn, &mfg_batch_ids, service_id)?
+                .into_iter()
+                .map(|mfg_batch| {
+                    let root_values =
+                        pg_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+                    let values = pg_list::get_property_values(&*self.conn, root_values)?;
+                    let parents =
+                        pg_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                    let attachments =
+                        pg_list::get_attachments(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+
+                    Ok(MfgBatch::from((mfg_batch, values, parents, attachments)))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> SearchMfgBatchesByPropertyOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mfg_batch_ids =
+                sqlite::find_mfg_batch_ids(&*self.conn, property_name, value, service_id)?;
+
+            sqlite::get_mfg_batches(&*self.conn, &mfg_batch_ids, service_id)?
+                .into_iter()
+                .map(|mfg_batch| {
+                    let root_values =
+                        sqlite_list::get_root_values(&*self.conn, &mfg_batch.mfg_batch_id)?;
+                    let values = sqlite_list::get_property_values(&*self.conn, root_values)?;
+                    let parents =
+                        sqlite_list::get_parents(&*self.conn, &mfg_batch.mfg_batch_id, service_id)?;
+                    let attachments = sqlite_list::get_attachments(
+                        &*self.conn,
+                        &mfg_batch.mfg_batch_id,
+                        service_id,
+                    )?;
+
+                    Ok(MfgBatch::from((mfg_batch, values, parents, attachments)))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    /// Finds the IDs of the mfg_batches with a current top-level property matching the value,
+    /// using the index on the property name and the value's column
+    pub fn find_mfg_batch_ids(
+        conn: &PgConnection,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<String>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::mfg_batch_id)
+            .distinct()
+            .filter(
+                mfg_batch_property_value::property_name
+                    .eq(property_name)
+                    .and(mfg_batch_property_value::parent_property.is_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        query = match value {
+            PropertySearchValue::String(value) => {
+                query.filter(mfg_batch_property_value::string_value.eq(value))
+            }
+            PropertySearchValue::Number(value) => {
+                query.filter(mfg_batch_property_value::number_value.eq(value))
+            }
+            PropertySearchValue::Boolean(value) => {
+                query.filter(mfg_batch_property_value::boolean_value.eq(value))
+            }
+        };
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<String>(conn)
+    }
+
+    pub fn get_mfg_batches(
+        conn: &PgConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::mfg_batch_id.asc())
+            .load::<ModelMfgBatch>(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    /// Finds the IDs of the mfg_batches with a current top-level property matching the value,
+    /// using the index on the property name and the value's column
+    pub fn find_mfg_batch_ids(
+        conn: &SqliteConnection,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<String>> {
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::mfg_batch_id)
+            .distinct()
+            .filter(
+                mfg_batch_property_value::property_name
+                    .eq(property_name)
+                    .and(mfg_batch_property_value::parent_property.is_null())
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        query = match value {
+            PropertySearchValue::String(value) => {
+                query.filter(mfg_batch_property_value::string_value.eq(value))
+            }
+            PropertySearchValue::Number(value) => {
+                query.filter(mfg_batch_property_value::number_value.eq(value))
+            }
+            PropertySearchValue::Boolean(value) => {
+                query.filter(mfg_batch_property_value::boolean_value.eq(value))
+            }
+        };
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.load::<String>(conn)
+    }
+
+    pub fn get_mfg_batches(
+        conn: &SqliteConnection,
+        mfg_batch_ids: &[String],
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<ModelMfgBatch>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::all_columns)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq_any(mfg_batch_ids)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query
+            .order(mfg_batch::mfg_batch_id.asc())
+            .load::<ModelMfgBatch>(conn)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    /// Verify that only current mfg_batches with a current top-level property matching the name,
+    /// value and service are returned, for each kind of value
+    #[test]
+    fn test_search_mfg_batches_by_property() {
+        let conn = SqliteConnection::establish(":memory:").expect("Failed to connect");
+        conn.batch_execute(&format!(
+            "CREATE TABLE mfg_batch (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                mfg_batch_namespace TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                last_updated TIMESTAMP,
+                quantity BIGINT,
+                uom TEXT,
+                expected_quantity BIGINT,
+                production_date BIGINT,
+                expiration_date BIGINT,
+                manufacture_location TEXT,
+                checksum TEXT,
+                archived BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE TABLE mfg_batch_property_value (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                mfg_batch_address TEXT NOT NULL,
+                property_name TEXT NOT NULL,
+                parent_property TEXT,
+                data_type TEXT NOT NULL,
+                bytes_value BLOB,
+                boolean_value BOOLEAN,
+                number_value BIGINT,
+                string_value TEXT,
+                enum_value INTEGER,
+                latitude_value BIGINT,
+                longitude_value BIGINT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT,
+                language TEXT
+            );
+            CREATE TABLE mfg_batch_parent (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                parent_mfg_batch_id TEXT NOT NULL,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            CREATE TABLE mfg_batch_attachment (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mfg_batch_id TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size BIGINT NOT NULL,
+                uri TEXT,
+                start_commit_num BIGINT NOT NULL,
+                end_commit_num BIGINT NOT NULL,
+                service_id TEXT
+            );
+            INSERT INTO mfg_batch (mfg_batch_id, mfg_batch_address, mfg_batch_namespace, owner,
+                start_commit_num, end_commit_num, service_id)
+            VALUES ('batch1', 'addr1', 'ns', 'org', 1, {max}, NULL),
+                ('batch2', 'addr2', 'ns', 'org', 1, {max}, NULL),
+                ('batch3', 'addr3', 'ns', 'org', 1, {max}, 'service');
+            INSERT INTO mfg_batch_property_value (mfg_batch_id, mfg_batch_address, property_name,
+                data_type, boolean_value, number_value, string_value, start_commit_num,
+                end_commit_num, service_id)
+            VALUES ('batch1', 'addr1', 'lot_code', 'STRING', NULL, NULL, 'ABC123', 1, {max}, NULL),
+                ('batch1', 'addr1', 'line', 'NUMBER', NULL, 7, NULL, 1, {max}, NULL),
+                ('batch1', 'addr1', 'organic', 'BOOLEAN', 1, NULL, NULL, 1, {max}, NULL),
+                ('batch2', 'addr2', 'lot_code', 'STRING', NULL, NULL, 'ABC123', 1, 2, NULL),
+                ('batch2', 'addr2', 'lot_code', 'STRING', NULL, NULL, 'XYZ789', 2, {max}, NULL),
+                ('batch3', 'addr3', 'lot_code', 'STRING', NULL, NULL, 'ABC123', 1, {max},
+                    'service');",
+            max = MAX_COMMIT_NUM
+        ))
+        .expect("Failed to create tables");
+
+        let search = |name: &str, value: PropertySearchValue, service_id: Option<&str>| {
+            MfgBatchStoreOperations::new(&conn)
+                .search_mfg_batches_by_property(name, &value, service_id)
+                .expect("Failed to search")
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            search(
+                "lot_code",
+                PropertySearchValue::String("ABC123".into()),
+                None
+            ),
+            vec!["batch1"]
+        );
+        assert_eq!(
+            search(
+                "lot_code",
+                PropertySearchValue::String("ABC123".into()),
+                Some("service")
+            ),
+            vec!["batch3"]
+        );
+        assert_eq!(
+            search("line", PropertySearchValue::Number(7), None),
+            vec!["batch1"]
+        );
+        assert_eq!(
+            search("organic", PropertySearchValue::Boolean(true), None),
+            vec!["batch1"]
+        );
+        assert!(search("organic", PropertySearchValue::Boolean(false), None).is_empty());
+        assert!(search("line", PropertySearchValue::String("7".into()), None).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::get_mfg_batches::GetMfgBatchesOperation;
+use super::MfgBatchStoreOperations;
+
+use crate::error::InvalidArgumentError;
+#[cfg(feature = "postgres")]
+use crate::mfg_batch::store::diesel::text_search;
+use crate::mfg_batch::store::{error::MfgBatchStoreError, MfgBatch};
+
+#[cfg(feature = "sqlite")]
+use crate::mfg_batch::{store::diesel::schema::mfg_batch_property_value, MAX_COMMIT_NUM};
+#[cfg(feature = "sqlite")]
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait SearchMfgBatchesTextOperation {
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> SearchMfgBatchesTextOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        check_query(query)?;
+
+        let mfg_batch_ids =
+            text_search::find_mfg_batch_ids(self.conn, query, service_id, offset, limit)?;
+        let mfg_batch_ids = mfg_batch_ids.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.get_mfg_batches(&mfg_batch_ids, service_id)
+    }
+}
+
+/// SQLite has no search index; the current string property values are scanned instead, and a
+/// word matches any value containing it. Matches are listed in ID order.
+#[cfg(feature = "sqlite")]
+impl<'a> SearchMfgBatchesTextOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        check_query(query)?;
+
+        let mut mfg_batch_ids: Option<Vec<String>> = None;
+        for word in query.split_whitespace() {
+            let matched = sqlite::find_mfg_batch_ids(self.conn, word, service_id)?;
+            mfg_batch_ids = Some(match mfg_batch_ids {
+                Some(ids) => ids.into_iter().filter(|id| matched.contains(id)).collect(),
+                None => matched,
+            });
+        }
+
+        let mfg_batch_ids = mfg_batch_ids
+            .unwrap_or_default()
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect::<Vec<_>>();
+        let mfg_batch_ids = mfg_batch_ids.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.get_mfg_batches(&mfg_batch_ids, service_id)
+    }
+}
+
+/// A query must have at least one word to match
+pub(super) fn check_query(query: &str) -> Result<(), MfgBatchStoreError> {
+    if query.trim().is_empty() {
+        return Err(MfgBatchStoreError::InvalidArgumentError(
+            InvalidArgumentError::new("query".to_string(), "must not be empty".to_string()),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+pub(super) mod sqlite {
+    use super::*;
+
+    use diesel::{sql_types::Text, sqlite::Sqlite};
+
+    /// Finds the IDs of the current mfg_batches with a string property value containing the
+    /// word, ignoring ASCII case, in ID order
+    pub fn find_mfg_batch_ids(
+        conn: &SqliteConnection,
+        word: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Vec<String>> {
+        word_query(word, service_id).load::<String>(conn)
+    }
+
+    /// Builds the query `find_mfg_batch_ids` runs for the word
+    pub fn word_query<'a>(
+        word: &str,
+        service_id: Option<&'a str>,
+    ) -> mfg_batch_property_value::BoxedQuery<'a, Sqlite, Text> {
+        let pattern = format!(
+            "%{}%",
+            word.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        let mut query = mfg_batch_property_value::table
+            .into_boxed()
+            .select(mfg_batch_property_value::mfg_batch_id)
+            .distinct()
+            .filter(
+                mfg_batch_property_value::string_value
+                    .like(pattern)
+                    .escape('\\')
+                    .and(mfg_batch_property_value::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch_property_value::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch_property_value::service_id.is_null());
+        }
+
+        query.order(mfg_batch_property_value::mfg_batch_id.asc())
+    }
+}
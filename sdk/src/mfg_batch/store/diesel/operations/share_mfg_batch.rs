@@ -0,0 +1,123 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{mfg_batch_exists::MfgBatchExistsOperation, MfgBatchStoreOperations};
+
+use crate::mfg_batch::store::{
+    diesel::{models::NewMfgBatchSharedWith, schema::mfg_batch_shared_with},
+    error::MfgBatchStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::mfg_batch) trait ShareMfgBatchOperation {
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ShareMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !self.mfg_batch_exists(mfg_batch_id, service_id)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Mfg_batch {}",
+                    mfg_batch_id
+                )));
+            }
+
+            let mut query = mfg_batch_shared_with::table
+                .into_boxed()
+                .select(mfg_batch_shared_with::id)
+                .filter(
+                    mfg_batch_shared_with::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_shared_with::org_id.eq(org_id)),
+                );
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch_shared_with::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch_shared_with::service_id.is_null());
+            }
+
+            if query.first::<i64>(self.conn).optional()?.is_none() {
+                insert_into(mfg_batch_shared_with::table)
+                    .values(NewMfgBatchSharedWith {
+                        mfg_batch_id: mfg_batch_id.to_string(),
+                        org_id: org_id.to_string(),
+                        service_id: service_id.map(String::from),
+                    })
+                    .execute(self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ShareMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            if !self.mfg_batch_exists(mfg_batch_id, service_id)? {
+                return Err(MfgBatchStoreError::NotFoundError(format!(
+                    "Mfg_batch {}",
+                    mfg_batch_id
+                )));
+            }
+
+            let mut query = mfg_batch_shared_with::table
+                .into_boxed()
+                .select(mfg_batch_shared_with::id)
+                .filter(
+                    mfg_batch_shared_with::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch_shared_with::org_id.eq(org_id)),
+                );
+
+            if let Some(service_id) = service_id {
+                query = query.filter(mfg_batch_shared_with::service_id.eq(service_id));
+            } else {
+                query = query.filter(mfg_batch_shared_with::service_id.is_null());
+            }
+
+            if query.first::<i64>(self.conn).optional()?.is_none() {
+                insert_into(mfg_batch_shared_with::table)
+                    .values(NewMfgBatchSharedWith {
+                        mfg_batch_id: mfg_batch_id.to_string(),
+                        org_id: org_id.to_string(),
+                        service_id: service_id.map(String::from),
+                    })
+                    .execute(self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
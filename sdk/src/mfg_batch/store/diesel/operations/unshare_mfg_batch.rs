@@ -0,0 +1,80 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{diesel::schema::mfg_batch_shared_with, error::MfgBatchStoreError};
+
+use diesel::{dsl::delete, prelude::*};
+
+pub(in crate::mfg_batch) trait UnshareMfgBatchOperation {
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> UnshareMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shares = mfg_batch_shared_with::table.filter(
+            mfg_batch_shared_with::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch_shared_with::org_id.eq(org_id)),
+        );
+
+        if let Some(service_id) = service_id {
+            delete(shares.filter(mfg_batch_shared_with::service_id.eq(service_id)))
+                .execute(self.conn)
+        } else {
+            delete(shares.filter(mfg_batch_shared_with::service_id.is_null())).execute(self.conn)
+        }
+        .map(|_| ())
+        .map_err(MfgBatchStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> UnshareMfgBatchOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shares = mfg_batch_shared_with::table.filter(
+            mfg_batch_shared_with::mfg_batch_id
+                .eq(mfg_batch_id)
+                .and(mfg_batch_shared_with::org_id.eq(org_id)),
+        );
+
+        if let Some(service_id) = service_id {
+            delete(shares.filter(mfg_batch_shared_with::service_id.eq(service_id)))
+                .execute(self.conn)
+        } else {
+            delete(shares.filter(mfg_batch_shared_with::service_id.is_null())).execute(self.conn)
+        }
+        .map(|_| ())
+        .map_err(MfgBatchStoreError::from)
+    }
+}
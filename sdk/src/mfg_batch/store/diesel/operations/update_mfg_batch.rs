@@ -14,8 +14,16 @@
 
 use super::MfgBatchStoreOperations;
 
+#[cfg(all(feature = "mfg-batch-text-search", feature = "postgres"))]
+use crate::mfg_batch::store::diesel::text_search;
 use crate::mfg_batch::{
-    store::{diesel::schema::mfg_batch_property_value, error::MfgBatchStoreError},
+    store::{
+        diesel::{
+            models::last_updated_at,
+            schema::{mfg_batch, mfg_batch_property_value},
+        },
+        error::MfgBatchStoreError,
+    },
     MAX_COMMIT_NUM,
 };
 use diesel::{dsl::update, prelude::*};
@@ -44,6 +52,9 @@ impl<'a> UpdateMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgC
                 service_id,
                 current_commit_num,
             )?;
+            pg::touch_mfg_batch(&*self.conn, mfg_batch_id, service_id)?;
+            #[cfg(feature = "mfg-batch-text-search")]
+            text_search::reindex(&*self.conn, mfg_batch_id, service_id)?;
 
             Ok(())
         })
@@ -65,6 +76,7 @@ impl<'a> UpdateMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite:
                 service_id,
                 current_commit_num,
             )?;
+            sqlite::touch_mfg_batch(&*self.conn, mfg_batch_id, service_id)?;
 
             Ok(())
         })
@@ -75,6 +87,39 @@ impl<'a> UpdateMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite:
 mod pg {
     use super::*;
 
+    /// Sets the last updated time of the current version of a mfg_batch
+    pub fn touch_mfg_batch(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch::table);
+        let last_updated = mfg_batch::last_updated.eq(Some(last_updated_at(None)));
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch::service_id.eq(service_id)),
+                )
+                .set(last_updated)
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(last_updated)
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
     pub fn update_mfg_batch_property_values(
         conn: &PgConnection,
         mfg_batch_id: &str,
@@ -112,6 +157,39 @@ mod pg {
 mod sqlite {
     use super::*;
 
+    /// Sets the last updated time of the current version of a mfg_batch
+    pub fn touch_mfg_batch(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<()> {
+        let update = update(mfg_batch::table);
+        let last_updated = mfg_batch::last_updated.eq(Some(last_updated_at(None)));
+
+        if let Some(service_id) = service_id {
+            update
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM))
+                        .and(mfg_batch::service_id.eq(service_id)),
+                )
+                .set(last_updated)
+                .execute(conn)
+                .map(|_| ())
+        } else {
+            update
+                .filter(
+                    mfg_batch::mfg_batch_id
+                        .eq(mfg_batch_id)
+                        .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+                )
+                .set(last_updated)
+                .execute(conn)
+                .map(|_| ())
+        }
+    }
+
     pub fn update_mfg_batch_property_values(
         conn: &SqliteConnection,
         mfg_batch_id: &str,
@@ -0,0 +1,220 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::add_mfg_batch::AddMfgBatchOperation;
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::{
+    store::{
+        diesel::schema::mfg_batch, error::MfgBatchStoreError, MfgBatch, UpsertMfgBatchOutcome,
+    },
+    MAX_COMMIT_NUM,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait UpsertMfgBatchOperation {
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> UpsertMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let current = pg::get_current_start_commit_num(
+                &*self.conn,
+                mfg_batch.mfg_batch_id(),
+                mfg_batch.service_id(),
+            )?;
+
+            upsert(self, mfg_batch, current)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> UpsertMfgBatchOperation for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let current = sqlite::get_current_start_commit_num(
+                &*self.conn,
+                mfg_batch.mfg_batch_id(),
+                mfg_batch.service_id(),
+            )?;
+
+            upsert(self, mfg_batch, current)
+        })
+    }
+}
+
+/// Adds the mfg_batch unless the current version is at least as new, given the commit the
+/// current version started at
+fn upsert<O: AddMfgBatchOperation>(
+    operations: &O,
+    mfg_batch: MfgBatch,
+    current_commit_num: Option<i64>,
+) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+    match current_commit_num {
+        Some(current_commit_num) if current_commit_num >= *mfg_batch.start_commit_num() => {
+            Ok(UpsertMfgBatchOutcome::Conflict { current_commit_num })
+        }
+        Some(prior_commit_num) => {
+            operations.add_mfg_batch(mfg_batch)?;
+            Ok(UpsertMfgBatchOutcome::Replaced { prior_commit_num })
+        }
+        None => {
+            operations.add_mfg_batch(mfg_batch)?;
+            Ok(UpsertMfgBatchOutcome::Created)
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn get_current_start_commit_num(
+        conn: &PgConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Option<i64>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::start_commit_num)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first(conn).optional()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn get_current_start_commit_num(
+        conn: &SqliteConnection,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> QueryResult<Option<i64>> {
+        let mut query = mfg_batch::table
+            .into_boxed()
+            .select(mfg_batch::start_commit_num)
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(mfg_batch_id)
+                    .and(mfg_batch::end_commit_num.eq(MAX_COMMIT_NUM)),
+            );
+
+        if let Some(service_id) = service_id {
+            query = query.filter(mfg_batch::service_id.eq(service_id));
+        } else {
+            query = query.filter(mfg_batch::service_id.is_null());
+        }
+
+        query.first(conn).optional()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use crate::mfg_batch::store::MfgBatchBuilder;
+
+    /// Counts the mfg_batches added instead of writing them
+    #[derive(Default)]
+    struct CountingOperations {
+        added: Cell<usize>,
+    }
+
+    impl AddMfgBatchOperation for CountingOperations {
+        fn add_mfg_batch(&self, _mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+            self.added.set(self.added.get() + 1);
+            Ok(())
+        }
+    }
+
+    fn mfg_batch_at(commit_num: i64) -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id("688955434684".into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .with_owner("org".into())
+            .with_start_commit_number(commit_num)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    /// Verify that a mfg_batch with no current version is created
+    #[test]
+    fn test_upsert_created() {
+        let operations = CountingOperations::default();
+
+        assert_eq!(
+            upsert(&operations, mfg_batch_at(5), None).unwrap(),
+            UpsertMfgBatchOutcome::Created
+        );
+        assert_eq!(operations.added.get(), 1);
+    }
+
+    /// Verify that an older current version is replaced
+    #[test]
+    fn test_upsert_replaced() {
+        let operations = CountingOperations::default();
+
+        assert_eq!(
+            upsert(&operations, mfg_batch_at(5), Some(3)).unwrap(),
+            UpsertMfgBatchOutcome::Replaced {
+                prior_commit_num: 3
+            }
+        );
+        assert_eq!(operations.added.get(), 1);
+    }
+
+    /// Verify that a current version at least as new is reported as a conflict and left in place
+    #[test]
+    fn test_upsert_conflict() {
+        let operations = CountingOperations::default();
+
+        assert_eq!(
+            upsert(&operations, mfg_batch_at(5), Some(5)).unwrap(),
+            UpsertMfgBatchOutcome::Conflict {
+                current_commit_num: 5
+            }
+        );
+        assert_eq!(operations.added.get(), 0);
+    }
+}
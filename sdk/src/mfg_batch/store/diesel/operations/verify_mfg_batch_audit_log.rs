@@ -0,0 +1,397 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        audit::{entry_hash, GENESIS_HASH},
+        models::{MfgBatch, MfgBatchAuditEntry, MfgBatchParent, MfgBatchPropertyValue},
+        record_hash::HashedRecord,
+        schema::{mfg_batch, mfg_batch_audit_log, mfg_batch_parent, mfg_batch_property_value},
+    },
+    error::MfgBatchStoreError,
+    AuditLogDiscrepancy, AuditLogDiscrepancyKind,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait VerifyMfgBatchAuditLogOperation {
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> VerifyMfgBatchAuditLogOperation for MfgBatchStoreOperations<'a, diesel::pg::PgConnection> {
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let entries = pg::list_audit_entries(&*self.conn)?;
+            let record_keys = pg::list_record_keys(&*self.conn)?;
+
+            verify_entries(entries, record_keys, |entry| {
+                pg::get_record_hash(&*self.conn, entry).map_err(MfgBatchStoreError::from)
+            })
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> VerifyMfgBatchAuditLogOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let entries = sqlite::list_audit_entries(&*self.conn)?;
+            let record_keys = sqlite::list_record_keys(&*self.conn)?;
+
+            verify_entries(entries, record_keys, |entry| {
+                sqlite::get_record_hash(&*self.conn, entry).map_err(MfgBatchStoreError::from)
+            })
+        })
+    }
+}
+
+/// Identifies the mfg_batch row written at a commit
+type RecordKey = (String, i64, Option<String>);
+
+/// Walks the audit log in insertion order, checking that each entry chains to the one before it,
+/// that its own hash is intact and that the rows it recorded still hash to the recorded value.
+/// Any mfg_batch row in `record_keys` that no entry accounts for is reported as unaudited.
+fn verify_entries<F>(
+    entries: Vec<MfgBatchAuditEntry>,
+    record_keys: Vec<RecordKey>,
+    mut get_record_hash: F,
+) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError>
+where
+    F: FnMut(&MfgBatchAuditEntry) -> Result<Option<String>, MfgBatchStoreError>,
+{
+    let mut discrepancies = Vec::new();
+    let mut audited = HashSet::new();
+    let mut previous_hash = GENESIS_HASH.to_string();
+
+    for entry in entries {
+        let mut report = |kind| {
+            discrepancies.push(AuditLogDiscrepancy {
+                mfg_batch_id: entry.mfg_batch_id.clone(),
+                commit_num: entry.commit_num,
+                service_id: entry.service_id.clone(),
+                kind,
+            })
+        };
+
+        if entry.previous_hash != previous_hash {
+            report(AuditLogDiscrepancyKind::BrokenChain);
+        }
+
+        let expected_hash = entry_hash(
+            &entry.previous_hash,
+            &entry.mfg_batch_id,
+            entry.commit_num,
+            entry.service_id.as_deref(),
+            &entry.record_hash,
+        );
+        if entry.entry_hash != expected_hash {
+            report(AuditLogDiscrepancyKind::EntryModified);
+        }
+
+        match get_record_hash(&entry)? {
+            Some(record_hash) if record_hash != entry.record_hash => {
+                report(AuditLogDiscrepancyKind::RecordModified)
+            }
+            Some(_) => (),
+            None => report(AuditLogDiscrepancyKind::RecordMissing),
+        }
+
+        audited.insert((
+            entry.mfg_batch_id.clone(),
+            entry.commit_num,
+            entry.service_id.clone(),
+        ));
+        previous_hash = entry.entry_hash;
+    }
+
+    discrepancies.extend(
+        record_keys
+            .into_iter()
+            .filter(|key| !audited.contains(key))
+            .map(
+                |(mfg_batch_id, commit_num, service_id)| AuditLogDiscrepancy {
+                    mfg_batch_id,
+                    commit_num,
+                    service_id,
+                    kind: AuditLogDiscrepancyKind::Unaudited,
+                },
+            ),
+    );
+
+    Ok(discrepancies)
+}
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use super::*;
+
+    pub fn list_audit_entries(conn: &PgConnection) -> QueryResult<Vec<MfgBatchAuditEntry>> {
+        mfg_batch_audit_log::table
+            .order(mfg_batch_audit_log::id.asc())
+            .load::<MfgBatchAuditEntry>(conn)
+    }
+
+    pub fn list_record_keys(conn: &PgConnection) -> QueryResult<Vec<RecordKey>> {
+        mfg_batch::table
+            .select((
+                mfg_batch::mfg_batch_id,
+                mfg_batch::start_commit_num,
+                mfg_batch::service_id,
+            ))
+            .load::<RecordKey>(conn)
+    }
+
+    pub fn get_record_hash(
+        conn: &PgConnection,
+        entry: &MfgBatchAuditEntry,
+    ) -> QueryResult<Option<String>> {
+        let mut mfg_batch_query = mfg_batch::table
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+        let mut property_query = mfg_batch_property_value::table
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+        let mut parent_query = mfg_batch_parent::table
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+
+        if let Some(service_id) = &entry.service_id {
+            mfg_batch_query = mfg_batch_query.filter(mfg_batch::service_id.eq(service_id));
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            mfg_batch_query = mfg_batch_query.filter(mfg_batch::service_id.is_null());
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        let mfg_batch = match mfg_batch_query.first::<MfgBatch>(conn).optional()? {
+            Some(mfg_batch) => mfg_batch,
+            None => return Ok(None),
+        };
+        let properties = property_query.load::<MfgBatchPropertyValue>(conn)?;
+        let parents = parent_query.load::<MfgBatchParent>(conn)?;
+
+        Ok(Some(
+            HashedRecord::from_stored(&mfg_batch, &properties, &parents).hash(),
+        ))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+
+    pub fn list_audit_entries(conn: &SqliteConnection) -> QueryResult<Vec<MfgBatchAuditEntry>> {
+        mfg_batch_audit_log::table
+            .order(mfg_batch_audit_log::id.asc())
+            .load::<MfgBatchAuditEntry>(conn)
+    }
+
+    pub fn list_record_keys(conn: &SqliteConnection) -> QueryResult<Vec<RecordKey>> {
+        mfg_batch::table
+            .select((
+                mfg_batch::mfg_batch_id,
+                mfg_batch::start_commit_num,
+                mfg_batch::service_id,
+            ))
+            .load::<RecordKey>(conn)
+    }
+
+    pub fn get_record_hash(
+        conn: &SqliteConnection,
+        entry: &MfgBatchAuditEntry,
+    ) -> QueryResult<Option<String>> {
+        let mut mfg_batch_query = mfg_batch::table
+            .filter(
+                mfg_batch::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+        let mut property_query = mfg_batch_property_value::table
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+        let mut parent_query = mfg_batch_parent::table
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(&entry.mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.eq(entry.commit_num)),
+            )
+            .into_boxed();
+
+        if let Some(service_id) = &entry.service_id {
+            mfg_batch_query = mfg_batch_query.filter(mfg_batch::service_id.eq(service_id));
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            mfg_batch_query = mfg_batch_query.filter(mfg_batch::service_id.is_null());
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        let mfg_batch = match mfg_batch_query.first::<MfgBatch>(conn).optional()? {
+            Some(mfg_batch) => mfg_batch,
+            None => return Ok(None),
+        };
+        let properties = property_query.load::<MfgBatchPropertyValue>(conn)?;
+        let parents = parent_query.load::<MfgBatchParent>(conn)?;
+
+        Ok(Some(
+            HashedRecord::from_stored(&mfg_batch, &properties, &parents).hash(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a chain of entries, one per `(mfg_batch_id, commit_num)`, each with a record hash
+    /// derived from its ID
+    fn build_chain(records: &[(&str, i64)]) -> Vec<MfgBatchAuditEntry> {
+        let mut previous_hash = GENESIS_HASH.to_string();
+        records
+            .iter()
+            .enumerate()
+            .map(|(idx, (mfg_batch_id, commit_num))| {
+                let record_hash = format!("record-{}", mfg_batch_id);
+                let hash = entry_hash(
+                    &previous_hash,
+                    mfg_batch_id,
+                    *commit_num,
+                    None,
+                    &record_hash,
+                );
+                let entry = MfgBatchAuditEntry {
+                    id: idx as i64 + 1,
+                    mfg_batch_id: mfg_batch_id.to_string(),
+                    commit_num: *commit_num,
+                    service_id: None,
+                    record_hash,
+                    previous_hash: previous_hash.clone(),
+                    entry_hash: hash.clone(),
+                };
+                previous_hash = hash;
+                entry
+            })
+            .collect()
+    }
+
+    fn record_keys(records: &[(&str, i64)]) -> Vec<RecordKey> {
+        records
+            .iter()
+            .map(|(mfg_batch_id, commit_num)| (mfg_batch_id.to_string(), *commit_num, None))
+            .collect()
+    }
+
+    fn stored_hash(entry: &MfgBatchAuditEntry) -> Result<Option<String>, MfgBatchStoreError> {
+        Ok(Some(format!("record-{}", entry.mfg_batch_id)))
+    }
+
+    fn kinds(discrepancies: &[AuditLogDiscrepancy]) -> Vec<(&str, AuditLogDiscrepancyKind)> {
+        discrepancies
+            .iter()
+            .map(|d| (d.mfg_batch_id.as_str(), d.kind))
+            .collect()
+    }
+
+    /// Verify that an untouched log and store produce no discrepancies
+    #[test]
+    fn test_verify_intact_log() {
+        let records = [("batch-1", 1), ("batch-2", 2), ("batch-3", 3)];
+
+        let discrepancies =
+            verify_entries(build_chain(&records), record_keys(&records), stored_hash)
+                .expect("Failed to verify");
+
+        assert!(discrepancies.is_empty());
+    }
+
+    /// Verify that edited rows, edited entries and removed entries are each reported
+    #[test]
+    fn test_verify_detects_tampering() {
+        let records = [
+            ("batch-1", 1),
+            ("batch-2", 2),
+            ("batch-3", 3),
+            ("batch-4", 4),
+        ];
+        let mut entries = build_chain(&records);
+
+        // Rewrite an entry's record hash without fixing up its entry hash
+        entries[1].record_hash = "record-forged".to_string();
+        // Drop an entry entirely, breaking the chain for the one after it
+        entries.remove(2);
+
+        let discrepancies = verify_entries(entries, record_keys(&records), |entry| {
+            if entry.mfg_batch_id == "batch-1" {
+                Ok(Some("record-edited".to_string()))
+            } else {
+                stored_hash(entry)
+            }
+        })
+        .expect("Failed to verify");
+
+        assert_eq!(
+            kinds(&discrepancies),
+            vec![
+                ("batch-1", AuditLogDiscrepancyKind::RecordModified),
+                ("batch-2", AuditLogDiscrepancyKind::EntryModified),
+                ("batch-2", AuditLogDiscrepancyKind::RecordModified),
+                ("batch-4", AuditLogDiscrepancyKind::BrokenChain),
+                ("batch-3", AuditLogDiscrepancyKind::Unaudited),
+            ]
+        );
+    }
+
+    /// Verify that an entry whose rows have been deleted is reported
+    #[test]
+    fn test_verify_detects_missing_record() {
+        let records = [("batch-1", 1)];
+
+        let discrepancies =
+            verify_entries(build_chain(&records), vec![], |_| Ok(None)).expect("Failed to verify");
+
+        assert_eq!(
+            kinds(&discrepancies),
+            vec![("batch-1", AuditLogDiscrepancyKind::RecordMissing)]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::MfgBatchStoreOperations;
+
+use crate::mfg_batch::store::{
+    diesel::{
+        models::{MfgBatch, MfgBatchParent, MfgBatchPropertyValue},
+        record_hash::HashedRecord,
+        schema::{mfg_batch, mfg_batch_parent, mfg_batch_property_value},
+    },
+    error::MfgBatchStoreError,
+    ChecksumDiscrepancy, ChecksumDiscrepancyKind,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::mfg_batch) trait VerifyMfgBatchChecksumsOperation {
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> VerifyMfgBatchChecksumsOperation
+    for MfgBatchStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut discrepancies = Vec::new();
+
+            for mfg_batch in pg::list_mfg_batch_rows(&*self.conn)? {
+                let (properties, parents) = pg::get_record_rows(&*self.conn, &mfg_batch)?;
+
+                if let Some(kind) = check_record(&mfg_batch, &properties, &parents) {
+                    discrepancies.push(ChecksumDiscrepancy {
+                        mfg_batch_id: mfg_batch.mfg_batch_id,
+                        commit_num: mfg_batch.start_commit_num,
+                        service_id: mfg_batch.service_id,
+                        kind,
+                    });
+                }
+            }
+
+            Ok(discrepancies)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> VerifyMfgBatchChecksumsOperation
+    for MfgBatchStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        self.conn.transaction::<_, MfgBatchStoreError, _>(|| {
+            let mut discrepancies = Vec::new();
+
+            for mfg_batch in sqlite::list_mfg_batch_rows(&*self.conn)? {
+                let (properties, parents) = sqlite::get_record_rows(&*self.conn, &mfg_batch)?;
+
+                if let Some(kind) = check_record(&mfg_batch, &properties, &parents) {
+                    discrepancies.push(ChecksumDiscrepancy {
+                        mfg_batch_id: mfg_batch.mfg_batch_id,
+                        commit_num: mfg_batch.start_commit_num,
+                        service_id: mfg_batch.service_id,
+                        kind,
+                    });
+                }
+            }
+
+            Ok(discrepancies)
+        })
+    }
+}
+
+/// Compares a mfg_batch row's checksum against the hash of the rows written with it
+fn check_record(
+    mfg_batch: &MfgBatch,
+    properties: &[MfgBatchPropertyValue],
+    parents: &[MfgBatchParent],
+) -> Option<ChecksumDiscrepancyKind> {
+    let checksum = match &mfg_batch.checksum {
+        Some(checksum) => checksum,
+        None => return Some(ChecksumDiscrepancyKind::Missing),
+    };
+
+    if *checksum != HashedRecord::from_stored(mfg_batch, properties, parents).hash() {
+        Some(ChecksumDiscrepancyKind::Mismatch)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub(super) mod pg {
+    use super::*;
+
+    pub fn list_mfg_batch_rows(conn: &PgConnection) -> QueryResult<Vec<MfgBatch>> {
+        mfg_batch::table
+            .order(mfg_batch::id.asc())
+            .load::<MfgBatch>(conn)
+    }
+
+    /// Loads the property and parent rows written in the same commit as a mfg_batch row
+    pub fn get_record_rows(
+        conn: &PgConnection,
+        mfg_batch: &MfgBatch,
+    ) -> QueryResult<(Vec<MfgBatchPropertyValue>, Vec<MfgBatchParent>)> {
+        let mut property_query = mfg_batch_property_value::table
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&mfg_batch.mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.eq(mfg_batch.start_commit_num)),
+            )
+            .into_boxed();
+        let mut parent_query = mfg_batch_parent::table
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(&mfg_batch.mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.eq(mfg_batch.start_commit_num)),
+            )
+            .into_boxed();
+
+        if let Some(service_id) = &mfg_batch.service_id {
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        Ok((
+            property_query.load::<MfgBatchPropertyValue>(conn)?,
+            parent_query.load::<MfgBatchParent>(conn)?,
+        ))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(super) mod sqlite {
+    use super::*;
+
+    pub fn list_mfg_batch_rows(conn: &SqliteConnection) -> QueryResult<Vec<MfgBatch>> {
+        mfg_batch::table
+            .order(mfg_batch::id.asc())
+            .load::<MfgBatch>(conn)
+    }
+
+    /// Loads the property and parent rows written in the same commit as a mfg_batch row
+    pub fn get_record_rows(
+        conn: &SqliteConnection,
+        mfg_batch: &MfgBatch,
+    ) -> QueryResult<(Vec<MfgBatchPropertyValue>, Vec<MfgBatchParent>)> {
+        let mut property_query = mfg_batch_property_value::table
+            .filter(
+                mfg_batch_property_value::mfg_batch_id
+                    .eq(&mfg_batch.mfg_batch_id)
+                    .and(mfg_batch_property_value::start_commit_num.eq(mfg_batch.start_commit_num)),
+            )
+            .into_boxed();
+        let mut parent_query = mfg_batch_parent::table
+            .filter(
+                mfg_batch_parent::mfg_batch_id
+                    .eq(&mfg_batch.mfg_batch_id)
+                    .and(mfg_batch_parent::start_commit_num.eq(mfg_batch.start_commit_num)),
+            )
+            .into_boxed();
+
+        if let Some(service_id) = &mfg_batch.service_id {
+            property_query =
+                property_query.filter(mfg_batch_property_value::service_id.eq(service_id));
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.eq(service_id));
+        } else {
+            property_query = property_query.filter(mfg_batch_property_value::service_id.is_null());
+            parent_query = parent_query.filter(mfg_batch_parent::service_id.is_null());
+        }
+
+        Ok((
+            property_query.load::<MfgBatchPropertyValue>(conn)?,
+            parent_query.load::<MfgBatchParent>(conn)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::MAX_COMMIT_NUM;
+
+    fn stored_mfg_batch(checksum: Option<String>) -> MfgBatch {
+        MfgBatch {
+            id: 1,
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            mfg_batch_namespace: "GS1".to_string(),
+            owner: "org-1".to_string(),
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            last_updated: None,
+            quantity: Some(950),
+            uom: Some("KGM".to_string()),
+            expected_quantity: Some(1000),
+            production_date: None,
+            expiration_date: None,
+            manufacture_location: None,
+            checksum,
+            archived: false,
+        }
+    }
+
+    fn stored_parent(parent: &str) -> MfgBatchParent {
+        MfgBatchParent {
+            id: 1,
+            mfg_batch_id: "batch-1".to_string(),
+            parent_mfg_batch_id: parent.to_string(),
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+        }
+    }
+
+    /// Verify that a row matching its checksum passes, and that modified or partially written
+    /// rows and rows without a checksum are reported
+    #[test]
+    fn test_check_record() {
+        let parents = vec![stored_parent("batch-a"), stored_parent("batch-b")];
+        let checksum = HashedRecord::from_stored(&stored_mfg_batch(None), &[], &parents).hash();
+        let mfg_batch = stored_mfg_batch(Some(checksum));
+
+        assert_eq!(check_record(&mfg_batch, &[], &parents), None);
+
+        // A parent row was never written
+        assert_eq!(
+            check_record(&mfg_batch, &[], &parents[..1]),
+            Some(ChecksumDiscrepancyKind::Mismatch)
+        );
+
+        let mut modified = stored_mfg_batch(mfg_batch.checksum.clone());
+        modified.quantity = Some(9500);
+        assert_eq!(
+            check_record(&modified, &[], &parents),
+            Some(ChecksumDiscrepancyKind::Mismatch)
+        );
+
+        assert_eq!(
+            check_record(&stored_mfg_batch(None), &[], &parents),
+            Some(ChecksumDiscrepancyKind::Missing)
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hashing of the rows written for a mfg_batch.
+//!
+//! A mfg_batch row, along with the property and parent rows written with it, is hashed for the
+//! audit log and for the row checksum. Only columns that never change after insert are hashed;
+//! `end_commit_num` is left out because later commits legitimately close out earlier rows.
+
+use crypto::{digest::Digest, sha2::Sha256};
+
+use super::models::{
+    MfgBatch, MfgBatchParent, MfgBatchPropertyValue, NewMfgBatch, NewMfgBatchParent,
+    NewMfgBatchPropertyValue,
+};
+
+/// The immutable columns of a mfg_batch and the property and parent rows written with it
+pub(in crate::mfg_batch) struct HashedRecord<'a> {
+    mfg_batch_id: &'a str,
+    mfg_batch_address: &'a str,
+    mfg_batch_namespace: &'a str,
+    owner: &'a str,
+    start_commit_num: i64,
+    service_id: Option<&'a str>,
+    quantity: Option<i64>,
+    uom: Option<&'a str>,
+    expected_quantity: Option<i64>,
+    production_date: Option<i64>,
+    expiration_date: Option<i64>,
+    manufacture_location: Option<&'a str>,
+    archived: bool,
+    properties: Vec<HashedProperty<'a>>,
+    parents: Vec<&'a str>,
+}
+
+struct HashedProperty<'a> {
+    mfg_batch_address: &'a str,
+    property_name: &'a str,
+    parent_property: Option<&'a str>,
+    data_type: &'a str,
+    bytes_value: Option<&'a [u8]>,
+    boolean_value: Option<bool>,
+    number_value: Option<i64>,
+    string_value: Option<&'a str>,
+    enum_value: Option<i32>,
+    latitude_value: Option<i64>,
+    longitude_value: Option<i64>,
+    language: Option<&'a str>,
+}
+
+impl<'a> HashedRecord<'a> {
+    pub fn from_new(
+        mfg_batch: &'a NewMfgBatch,
+        property_values: &'a [NewMfgBatchPropertyValue],
+        parents: &'a [NewMfgBatchParent],
+    ) -> Self {
+        Self {
+            mfg_batch_id: &mfg_batch.mfg_batch_id,
+            mfg_batch_address: &mfg_batch.mfg_batch_address,
+            mfg_batch_namespace: &mfg_batch.mfg_batch_namespace,
+            owner: &mfg_batch.owner,
+            start_commit_num: mfg_batch.start_commit_num,
+            service_id: mfg_batch.service_id.as_deref(),
+            quantity: mfg_batch.quantity,
+            uom: mfg_batch.uom.as_deref(),
+            expected_quantity: mfg_batch.expected_quantity,
+            production_date: mfg_batch.production_date,
+            expiration_date: mfg_batch.expiration_date,
+            manufacture_location: mfg_batch.manufacture_location.as_deref(),
+            archived: mfg_batch.archived,
+            properties: property_values
+                .iter()
+                .map(|value| HashedProperty {
+                    mfg_batch_address: &value.mfg_batch_address,
+                    property_name: &value.property_name,
+                    parent_property: value.parent_property.as_deref(),
+                    data_type: &value.data_type,
+                    bytes_value: value.bytes_value.as_deref(),
+                    boolean_value: value.boolean_value,
+                    number_value: value.number_value,
+                    string_value: value.string_value.as_deref(),
+                    enum_value: value.enum_value,
+                    latitude_value: value.latitude_value,
+                    longitude_value: value.longitude_value,
+                    language: value.language.as_deref(),
+                })
+                .collect(),
+            parents: parents
+                .iter()
+                .map(|parent| parent.parent_mfg_batch_id.as_str())
+                .collect(),
+        }
+    }
+
+    pub fn from_stored(
+        mfg_batch: &'a MfgBatch,
+        property_values: &'a [MfgBatchPropertyValue],
+        parents: &'a [MfgBatchParent],
+    ) -> Self {
+        Self {
+            mfg_batch_id: &mfg_batch.mfg_batch_id,
+            mfg_batch_address: &mfg_batch.mfg_batch_address,
+            mfg_batch_namespace: &mfg_batch.mfg_batch_namespace,
+            owner: &mfg_batch.owner,
+            start_commit_num: mfg_batch.start_commit_num,
+            service_id: mfg_batch.service_id.as_deref(),
+            quantity: mfg_batch.quantity,
+            uom: mfg_batch.uom.as_deref(),
+            expected_quantity: mfg_batch.expected_quantity,
+            production_date: mfg_batch.production_date,
+            expiration_date: mfg_batch.expiration_date,
+            manufacture_location: mfg_batch.manufacture_location.as_deref(),
+            archived: mfg_batch.archived,
+            properties: property_values
+                .iter()
+                .map(|value| HashedProperty {
+                    mfg_batch_address: &value.mfg_batch_address,
+                    property_name: &value.property_name,
+                    parent_property: value.parent_property.as_deref(),
+                    data_type: &value.data_type,
+                    bytes_value: value.bytes_value.as_deref(),
+                    boolean_value: value.boolean_value,
+                    number_value: value.number_value,
+                    string_value: value.string_value.as_deref(),
+                    enum_value: value.enum_value,
+                    latitude_value: value.latitude_value,
+                    longitude_value: value.longitude_value,
+                    language: value.language.as_deref(),
+                })
+                .collect(),
+            parents: parents
+                .iter()
+                .map(|parent| parent.parent_mfg_batch_id.as_str())
+                .collect(),
+        }
+    }
+
+    /// Hashes the record. Property and parent rows are hashed in a canonical order, since the
+    /// database does not guarantee the order they are read back in.
+    pub fn hash(&self) -> String {
+        let mut encoder = Encoder::default();
+        encoder.write_str(self.mfg_batch_id);
+        encoder.write_str(self.mfg_batch_address);
+        encoder.write_str(self.mfg_batch_namespace);
+        encoder.write_str(self.owner);
+        encoder.write_i64(self.start_commit_num);
+        encoder.write_opt_str(self.service_id);
+        encoder.write_opt_i64(self.quantity);
+        encoder.write_opt_str(self.uom);
+        encoder.write_opt_i64(self.expected_quantity);
+        encoder.write_opt_i64(self.production_date);
+        encoder.write_opt_i64(self.expiration_date);
+        encoder.write_opt_str(self.manufacture_location);
+        encoder.write_i64(i64::from(self.archived));
+
+        let mut properties = self
+            .properties
+            .iter()
+            .map(HashedProperty::encode)
+            .collect::<Vec<_>>();
+        properties.sort_unstable();
+        encoder.write_u64(properties.len() as u64);
+        properties
+            .iter()
+            .for_each(|property| encoder.write_bytes(property));
+
+        let mut parents = self.parents.clone();
+        parents.sort_unstable();
+        encoder.write_u64(parents.len() as u64);
+        parents.iter().for_each(|parent| encoder.write_str(parent));
+
+        encoder.digest()
+    }
+}
+
+impl<'a> HashedProperty<'a> {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.write_str(self.mfg_batch_address);
+        encoder.write_str(self.property_name);
+        encoder.write_opt_str(self.parent_property);
+        encoder.write_str(self.data_type);
+        encoder.write_opt_bytes(self.bytes_value);
+        encoder.write_opt_i64(self.boolean_value.map(i64::from));
+        encoder.write_opt_i64(self.number_value);
+        encoder.write_opt_str(self.string_value);
+        encoder.write_opt_i64(self.enum_value.map(i64::from));
+        encoder.write_opt_i64(self.latitude_value);
+        encoder.write_opt_i64(self.longitude_value);
+        // Only localized values hash their language, so the hashes of earlier records still
+        // verify
+        if let Some(language) = self.language {
+            encoder.write_str(language);
+        }
+        encoder.0
+    }
+}
+
+/// Length-prefixed encoding of the hashed fields, so that adjacent fields cannot run together
+#[derive(Default)]
+pub(in crate::mfg_batch) struct Encoder(Vec<u8>);
+
+impl Encoder {
+    pub fn write_u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_u64(value.len() as u64);
+        self.0.extend_from_slice(value);
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    pub fn write_opt_bytes(&mut self, value: Option<&[u8]>) {
+        match value {
+            Some(value) => {
+                self.0.push(1);
+                self.write_bytes(value);
+            }
+            None => self.0.push(0),
+        }
+    }
+
+    pub fn write_opt_str(&mut self, value: Option<&str>) {
+        self.write_opt_bytes(value.map(str::as_bytes));
+    }
+
+    pub fn write_opt_i64(&mut self, value: Option<i64>) {
+        match value {
+            Some(value) => {
+                self.0.push(1);
+                self.write_i64(value);
+            }
+            None => self.0.push(0),
+        }
+    }
+
+    pub fn digest(&self) -> String {
+        let mut sha = Sha256::new();
+        sha.input(&self.0);
+        sha.result_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::MAX_COMMIT_NUM;
+
+    fn new_mfg_batch() -> NewMfgBatch {
+        NewMfgBatch {
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            mfg_batch_namespace: "GS1".to_string(),
+            owner: "org-1".to_string(),
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            last_updated: None,
+            quantity: None,
+            uom: None,
+            expected_quantity: None,
+            production_date: None,
+            expiration_date: None,
+            manufacture_location: None,
+            checksum: None,
+            archived: false,
+        }
+    }
+
+    fn new_property(name: &str, value: i64) -> NewMfgBatchPropertyValue {
+        NewMfgBatchPropertyValue {
+            mfg_batch_id: "batch-1".to_string(),
+            mfg_batch_address: "11bb0e01batch1".to_string(),
+            property_name: name.to_string(),
+            parent_property: None,
+            data_type: "NUMBER".to_string(),
+            bytes_value: None,
+            boolean_value: None,
+            number_value: Some(value),
+            string_value: None,
+            enum_value: None,
+            latitude_value: None,
+            longitude_value: None,
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+            language: None,
+        }
+    }
+
+    fn new_parent(parent: &str) -> NewMfgBatchParent {
+        NewMfgBatchParent {
+            mfg_batch_id: "batch-1".to_string(),
+            parent_mfg_batch_id: parent.to_string(),
+            start_commit_num: 1,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+        }
+    }
+
+    /// Verify that the record hash does not depend on the order rows are read back in
+    #[test]
+    fn test_record_hash_is_order_independent() {
+        let mfg_batch = new_mfg_batch();
+        let properties = vec![new_property("width", 1), new_property("length", 2)];
+        let parents = vec![new_parent("batch-a"), new_parent("batch-b")];
+        let reversed_properties = properties.iter().cloned().rev().collect::<Vec<_>>();
+        let reversed_parents = parents.iter().cloned().rev().collect::<Vec<_>>();
+
+        assert_eq!(
+            HashedRecord::from_new(&mfg_batch, &properties, &parents).hash(),
+            HashedRecord::from_new(&mfg_batch, &reversed_properties, &reversed_parents).hash(),
+        );
+    }
+
+    /// Verify that closing out a row does not change its hash, but changing its contents does
+    #[test]
+    fn test_record_hash_covers_immutable_columns() {
+        let mut mfg_batch = new_mfg_batch();
+        let properties = vec![new_property("width", 1)];
+        let original = HashedRecord::from_new(&mfg_batch, &properties, &[]).hash();
+
+        mfg_batch.end_commit_num = 5;
+        assert_eq!(
+            original,
+            HashedRecord::from_new(&mfg_batch, &properties, &[]).hash()
+        );
+
+        mfg_batch.owner = "org-2".to_string();
+        assert_ne!(
+            original,
+            HashedRecord::from_new(&mfg_batch, &properties, &[]).hash()
+        );
+
+        let mfg_batch = new_mfg_batch();
+        let properties = vec![new_property("width", 2)];
+        assert_ne!(
+            original,
+            HashedRecord::from_new(&mfg_batch, &properties, &[]).hash()
+        );
+    }
+}
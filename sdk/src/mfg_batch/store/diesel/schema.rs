@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::pike::store::diesel::schema::pike_organization;
+
 table! {
     mfg_batch_property_value (id) {
         id -> Int8,
@@ -30,6 +32,7 @@ table! {
         start_commit_num -> Int8,
         end_commit_num -> Int8,
         service_id -> Nullable<Text>,
+        language -> Nullable<Text>,
     }
 }
 
@@ -44,5 +47,194 @@ table! {
         end_commit_num -> Int8,
         service_id -> Nullable<Text>,
         last_updated -> Nullable<Timestamp>,
+        quantity -> Nullable<Int8>,
+        uom -> Nullable<Text>,
+        expected_quantity -> Nullable<Int8>,
+        production_date -> Nullable<Int8>,
+        expiration_date -> Nullable<Int8>,
+        manufacture_location -> Nullable<Text>,
+        checksum -> Nullable<Text>,
+        archived -> Bool,
+    }
+}
+
+table! {
+    mfg_batch_parent (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        parent_mfg_batch_id -> Varchar,
+        start_commit_num -> Int8,
+        end_commit_num -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    mfg_batch_attachment (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        digest -> Varchar,
+        mime_type -> Text,
+        size -> Int8,
+        uri -> Nullable<Text>,
+        start_commit_num -> Int8,
+        end_commit_num -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(mfg_batch, pike_organization);
+
+#[cfg(feature = "mfg-batch-audit-log")]
+table! {
+    mfg_batch_audit_log (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        commit_num -> Int8,
+        service_id -> Nullable<Text>,
+        record_hash -> Text,
+        previous_hash -> Text,
+        entry_hash -> Text,
+    }
+}
+
+#[cfg(feature = "mfg-batch-change-capture")]
+table! {
+    mfg_batch_change (id) {
+        id -> Int8,
+        entity -> Text,
+        entity_id -> Varchar,
+        field -> Text,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
+        commit_num -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-annotations")]
+table! {
+    mfg_batch_annotation (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        commit_num -> Int8,
+        property_name -> Text,
+        author -> Text,
+        comment -> Text,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-allocations")]
+table! {
+    mfg_batch_allocation (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        order_id -> Varchar,
+        quantity -> Int8,
+        commit_num -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+table! {
+    mfg_batch_recall (id) {
+        id -> Int8,
+        recall_id -> Varchar,
+        mfg_batch_id -> Varchar,
+        reason_code -> Text,
+        recalled_at -> Int8,
+        closed_at -> Nullable<Int8>,
+        commit_num -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-test-results")]
+table! {
+    mfg_batch_test_result (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        commit_num -> Int8,
+        test_name -> Text,
+        specification -> Text,
+        result -> Text,
+        passed -> Bool,
+        lab -> Text,
+        tested_at -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+// The failed checks are stored one per line
+#[cfg(feature = "mfg-batch-quality-scores")]
+table! {
+    mfg_batch_quality_score (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        commit_num -> Int8,
+        score -> Int4,
+        failed_checks -> Text,
+        scored_at -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+allow_tables_to_appear_in_same_query!(mfg_batch, mfg_batch_quality_score);
+
+#[cfg(feature = "mfg-batch-duplicates")]
+table! {
+    mfg_batch_duplicate (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        duplicate_of -> Varchar,
+        rule -> Text,
+        similarity -> Int4,
+        detected_at -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-duplicates")]
+allow_tables_to_appear_in_same_query!(mfg_batch, mfg_batch_duplicate);
+
+#[cfg(feature = "mfg-batch-visibility")]
+table! {
+    mfg_batch_shared_with (id) {
+        id -> Int8,
+        mfg_batch_id -> Varchar,
+        org_id -> Varchar,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-visibility")]
+allow_tables_to_appear_in_same_query!(mfg_batch, mfg_batch_shared_with);
+
+#[cfg(feature = "mfg-batch-merge")]
+table! {
+    mfg_batch_alias (id) {
+        id -> Int8,
+        alias -> Varchar,
+        mfg_batch_id -> Varchar,
+        merged_at -> Int8,
+        service_id -> Nullable<Text>,
+    }
+}
+
+#[cfg(feature = "mfg-batch-anchors")]
+table! {
+    mfg_batch_anchor (id) {
+        id -> Int8,
+        owner -> Varchar,
+        commit_num -> Int8,
+        merkle_root -> Varchar,
+        row_count -> Int8,
+        anchor_address -> Varchar,
+        anchored_commit_num -> Int8,
+        anchored_at -> Int8,
+        service_id -> Nullable<Text>,
     }
 }
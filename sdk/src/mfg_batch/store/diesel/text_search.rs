@@ -0,0 +1,136 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Full-text search over mfg_batch string property values on Postgres.
+//!
+//! Each mfg_batch has one `tsvector` document in `mfg_batch_text_search`, built from the string
+//! values of its current properties, including those nested in struct properties. The document
+//! is rebuilt whenever the store writes new property values for the batch. Diesel has no
+//! `tsvector` type, so the table is only reached through raw queries.
+//!
+//! Documents and queries use the `simple` configuration, which lower-cases words without
+//! stemming them, so lot codes and other identifiers match as they were written.
+
+use diesel::{
+    pg::{Pg, PgConnection},
+    prelude::*,
+    query_builder::{QueryFragment, QueryId},
+    query_dsl::LoadQuery,
+    sql_query,
+    sql_types::{BigInt, Nullable, Text},
+};
+
+#[cfg(feature = "mfg-batch-explain")]
+use super::explain::pg::Explain;
+#[cfg(feature = "mfg-batch-explain")]
+use crate::mfg_batch::store::QueryPlan;
+use crate::mfg_batch::MAX_COMMIT_NUM;
+
+#[derive(QueryableByName)]
+struct MatchedMfgBatch {
+    #[column_name = "mfg_batch_id"]
+    #[sql_type = "Text"]
+    mfg_batch_id: String,
+}
+
+/// Rebuilds the search document of a mfg_batch from its current string property values. A batch
+/// without any is left without a document.
+pub fn reindex(
+    conn: &PgConnection,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+) -> QueryResult<()> {
+    sql_query(
+        "DELETE FROM mfg_batch_text_search \
+         WHERE mfg_batch_id = $1 AND service_id IS NOT DISTINCT FROM $2",
+    )
+    .bind::<Text, _>(mfg_batch_id)
+    .bind::<Nullable<Text>, _>(service_id)
+    .execute(conn)?;
+
+    sql_query(
+        "INSERT INTO mfg_batch_text_search (mfg_batch_id, service_id, document) \
+         SELECT $1, $2, to_tsvector('simple', string_agg(string_value, ' ')) \
+         FROM mfg_batch_property_value \
+         WHERE mfg_batch_id = $1 AND service_id IS NOT DISTINCT FROM $2 \
+         AND end_commit_num = $3 AND string_value IS NOT NULL \
+         HAVING COUNT(*) > 0",
+    )
+    .bind::<Text, _>(mfg_batch_id)
+    .bind::<Nullable<Text>, _>(service_id)
+    .bind::<BigInt, _>(MAX_COMMIT_NUM)
+    .execute(conn)
+    .map(|_| ())
+}
+
+/// Finds the IDs of the current mfg_batches whose documents match every word of the query, best
+/// match first, then in ID order
+pub fn find_mfg_batch_ids(
+    conn: &PgConnection,
+    query: &str,
+    service_id: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> QueryResult<Vec<String>> {
+    find_query(query, service_id, offset, limit)
+        .load::<MatchedMfgBatch>(conn)
+        .map(|matches| matches.into_iter().map(|m| m.mfg_batch_id).collect())
+}
+
+/// Returns the SQL `find_mfg_batch_ids` runs for the same arguments and the database's plan for
+/// it, without running it
+#[cfg(feature = "mfg-batch-explain")]
+pub fn explain_find_mfg_batch_ids(
+    conn: &PgConnection,
+    query: &str,
+    service_id: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> QueryResult<QueryPlan> {
+    let find_query = find_query(query, service_id, offset, limit);
+    let sql = diesel::debug_query::<Pg, _>(&find_query).to_string();
+
+    let plan = Explain(find_query).load::<String>(conn)?;
+
+    Ok(QueryPlan { sql, plan })
+}
+
+fn find_query<'a>(
+    query: &'a str,
+    service_id: Option<&'a str>,
+    offset: i64,
+    limit: i64,
+) -> impl QueryFragment<Pg> + QueryId + LoadQuery<PgConnection, MatchedMfgBatch> + 'a {
+    // Documents of deleted mfg_batches are kept until the batch is written again, so matches
+    // are checked against the current mfg_batches
+    sql_query(
+        "SELECT search.mfg_batch_id FROM mfg_batch_text_search search \
+         WHERE search.document @@ plainto_tsquery('simple', $1) \
+         AND search.service_id IS NOT DISTINCT FROM $2 \
+         AND EXISTS ( \
+             SELECT 1 FROM mfg_batch \
+             WHERE mfg_batch.mfg_batch_id = search.mfg_batch_id \
+             AND mfg_batch.service_id IS NOT DISTINCT FROM search.service_id \
+             AND mfg_batch.end_commit_num = $3 \
+         ) \
+         ORDER BY ts_rank(search.document, plainto_tsquery('simple', $1)) DESC, \
+             search.mfg_batch_id ASC \
+         OFFSET $4 LIMIT $5",
+    )
+    .bind::<Text, _>(query)
+    .bind::<Nullable<Text>, _>(service_id)
+    .bind::<BigInt, _>(MAX_COMMIT_NUM)
+    .bind::<BigInt, _>(offset)
+    .bind::<BigInt, _>(limit)
+}
@@ -17,7 +17,10 @@ use std::fmt;
 
 #[cfg(feature = "diesel")]
 use crate::error::ConstraintViolationType;
-use crate::error::{ConstraintViolationError, InternalError, ResourceTemporarilyUnavailableError};
+use crate::error::{
+    ConstraintViolationError, InternalError, InvalidArgumentError,
+    ResourceTemporarilyUnavailableError,
+};
 
 /// Represents Store errors
 #[derive(Debug)]
@@ -26,6 +29,7 @@ pub enum MfgBatchStoreError {
     ConstraintViolationError(ConstraintViolationError),
     ResourceTemporarilyUnavailableError(ResourceTemporarilyUnavailableError),
     NotFoundError(String),
+    InvalidArgumentError(InvalidArgumentError),
 }
 
 impl Error for MfgBatchStoreError {
@@ -35,6 +39,7 @@ impl Error for MfgBatchStoreError {
             MfgBatchStoreError::ConstraintViolationError(err) => Some(err),
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(err) => Some(err),
             MfgBatchStoreError::NotFoundError(_) => None,
+            MfgBatchStoreError::InvalidArgumentError(err) => Some(err),
         }
     }
 }
@@ -46,6 +51,7 @@ impl fmt::Display for MfgBatchStoreError {
             MfgBatchStoreError::ConstraintViolationError(err) => err.fmt(f),
             MfgBatchStoreError::ResourceTemporarilyUnavailableError(err) => err.fmt(f),
             MfgBatchStoreError::NotFoundError(ref s) => write!(f, "Element not found: {}", s),
+            MfgBatchStoreError::InvalidArgumentError(err) => err.fmt(f),
         }
     }
 }
@@ -56,11 +62,16 @@ impl From<diesel::result::Error> for MfgBatchStoreError {
         match err {
             diesel::result::Error::DatabaseError(
                 diesel::result::DatabaseErrorKind::UniqueViolation,
-                _,
+                ref info,
             ) => MfgBatchStoreError::ConstraintViolationError(
                 ConstraintViolationError::from_source_with_violation_type(
                     ConstraintViolationType::Unique,
-                    Box::new(err),
+                    Box::new(UniqueViolationDetails {
+                        message: info.message().to_string(),
+                        table: info.table_name().map(String::from),
+                        constraint: info.constraint_name().map(String::from),
+                        details: info.details().map(String::from),
+                    }),
                 ),
             ),
             diesel::result::Error::DatabaseError(
@@ -77,6 +88,35 @@ impl From<diesel::result::Error> for MfgBatchStoreError {
     }
 }
 
+/// The details a database reported for a unique constraint violation, available as the source of
+/// a `MfgBatchStoreError::ConstraintViolationError`
+#[derive(Debug)]
+pub struct UniqueViolationDetails {
+    pub message: String,
+    pub table: Option<String>,
+    pub constraint: Option<String>,
+    pub details: Option<String>,
+}
+
+impl Error for UniqueViolationDetails {}
+
+impl fmt::Display for UniqueViolationDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(constraint) = &self.constraint {
+            write!(f, " (constraint {}", constraint)?;
+            if let Some(table) = &self.table {
+                write!(f, " on {}", table)?;
+            }
+            write!(f, ")")?;
+        }
+        if let Some(details) = &self.details {
+            write!(f, ": {}", details)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(feature = "diesel")]
 impl From<diesel::r2d2::PoolError> for MfgBatchStoreError {
     fn from(err: diesel::r2d2::PoolError) -> MfgBatchStoreError {
@@ -86,19 +126,71 @@ impl From<diesel::r2d2::PoolError> for MfgBatchStoreError {
     }
 }
 
-/// Represents ProductBuilder errors
+/// The fields checked by the mfg_batch and property value builders
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MfgBatchField {
+    MfgBatchId,
+    MfgBatchAddress,
+    MfgBatchNamespace,
+    Owner,
+    StartCommitNum,
+    EndCommitNum,
+    PropertyName,
+    DataType,
+}
+
+impl MfgBatchField {
+    /// Returns the name of the field, as API resources spell it
+    pub fn name(&self) -> &'static str {
+        match self {
+            MfgBatchField::MfgBatchId => "mfg_batch_id",
+            MfgBatchField::MfgBatchAddress => "mfg_batch_address",
+            MfgBatchField::MfgBatchNamespace => "mfg_batch_namespace",
+            MfgBatchField::Owner => "owner",
+            MfgBatchField::StartCommitNum => "start_commit_num",
+            MfgBatchField::EndCommitNum => "end_commit_num",
+            MfgBatchField::PropertyName => "property_name",
+            MfgBatchField::DataType => "data_type",
+        }
+    }
+}
+
+impl fmt::Display for MfgBatchField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Represents MfgBatchBuilder and PropertyValueBuilder errors
 #[derive(Debug)]
 pub enum MfgBatchBuilderError {
     /// Returned when a required field was not set
-    MissingRequiredField(String),
+    MissingField(MfgBatchField),
+    /// Returned when a field was set to a value the builder does not accept
+    InvalidValue {
+        field: MfgBatchField,
+        reason: String,
+    },
     /// Returned when an error occurs building the mfg_batch
     BuildError(Box<dyn Error>),
 }
 
+impl MfgBatchBuilderError {
+    /// Returns the field at fault, if the error is about a single field
+    pub fn field(&self) -> Option<MfgBatchField> {
+        match self {
+            MfgBatchBuilderError::MissingField(field)
+            | MfgBatchBuilderError::InvalidValue { field, .. } => Some(*field),
+            MfgBatchBuilderError::BuildError(_) => None,
+        }
+    }
+}
+
 impl Error for MfgBatchBuilderError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            MfgBatchBuilderError::MissingRequiredField(_) => None,
+            MfgBatchBuilderError::MissingField(_) => None,
+            MfgBatchBuilderError::InvalidValue { .. } => None,
             MfgBatchBuilderError::BuildError(err) => Some(&**err),
         }
     }
@@ -107,9 +199,17 @@ impl Error for MfgBatchBuilderError {
 impl fmt::Display for MfgBatchBuilderError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            MfgBatchBuilderError::MissingRequiredField(ref s) => {
-                write!(f, "failed to build mfg_batch: {}", s)
+            MfgBatchBuilderError::MissingField(ref field) => {
+                write!(f, "failed to build mfg_batch: missing {}", field)
             }
+            MfgBatchBuilderError::InvalidValue {
+                ref field,
+                ref reason,
+            } => write!(
+                f,
+                "failed to build mfg_batch: invalid {}: {}",
+                field, reason
+            ),
             MfgBatchBuilderError::BuildError(ref s) => {
                 write!(f, "failed to build mfg_batch: {}", s)
             }
@@ -15,12 +15,32 @@
 #[cfg(feature = "diesel")]
 pub(in crate) mod diesel;
 pub mod error;
+#[cfg(feature = "mfg-batch-pseudonyms")]
+mod pseudonym;
+#[cfg(feature = "mfg-batch-retry")]
+mod retrying;
+#[cfg(feature = "mfg-batch-sharding")]
+mod sharded;
 
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::error::InvalidArgumentError;
 use crate::paging::Paging;
+use crate::protocol::schema::number::NumberValue;
+use crate::schema::store::PropertyDefinition;
 
 #[cfg(feature = "diesel")]
-pub use self::diesel::{DieselConnectionMfgBatchStore, DieselMfgBatchStore};
-pub use error::{MfgBatchBuilderError, MfgBatchStoreError};
+pub use self::diesel::{
+    DieselConnectionMfgBatchStore, DieselMfgBatchStore, DEFAULT_BULK_INSERT_CHUNK_SIZE,
+};
+pub use error::{MfgBatchBuilderError, MfgBatchField, MfgBatchStoreError, UniqueViolationDetails};
+#[cfg(feature = "mfg-batch-pseudonyms")]
+pub use pseudonym::Pseudonymizer;
+#[cfg(feature = "mfg-batch-retry")]
+pub use retrying::{RetryPolicy, RetryingMfgBatchStore};
+#[cfg(feature = "mfg-batch-sharding")]
+pub use sharded::ShardedMfgBatchStore;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MfgBatch {
@@ -33,6 +53,15 @@ pub struct MfgBatch {
     service_id: Option<String>,
     last_updated: Option<i64>,
     properties: Vec<PropertyValue>,
+    parent_batches: Vec<String>,
+    quantity: Option<i64>,
+    uom: Option<String>,
+    expected_quantity: Option<i64>,
+    production_date: Option<i64>,
+    expiration_date: Option<i64>,
+    manufacture_location: Option<String>,
+    attachments: Vec<MfgBatchAttachment>,
+    archived: bool,
 }
 
 impl MfgBatch {
@@ -80,6 +109,58 @@ impl MfgBatch {
     pub fn properties(&self) -> Vec<PropertyValue> {
         self.properties.to_vec()
     }
+
+    /// Returns the IDs of the batches this mfg_batch was produced from
+    pub fn parent_batches(&self) -> &[String] {
+        &self.parent_batches
+    }
+
+    /// Returns the quantity produced, in units of the mfg_batch's unit of measure
+    pub fn quantity(&self) -> Option<i64> {
+        self.quantity
+    }
+
+    /// Returns the unit of measure code for the mfg_batch's quantities
+    pub fn uom(&self) -> Option<&str> {
+        self.uom.as_deref()
+    }
+
+    /// Returns the quantity the mfg_batch was planned to produce
+    pub fn expected_quantity(&self) -> Option<i64> {
+        self.expected_quantity
+    }
+
+    /// Returns when the mfg_batch was produced, in seconds since the epoch
+    pub fn production_date(&self) -> Option<i64> {
+        self.production_date
+    }
+
+    /// Returns when the mfg_batch expires, in seconds since the epoch
+    pub fn expiration_date(&self) -> Option<i64> {
+        self.expiration_date
+    }
+
+    /// Returns the GLN of the location the mfg_batch was produced at
+    pub fn manufacture_location(&self) -> Option<&str> {
+        self.manufacture_location.as_deref()
+    }
+
+    /// Returns the off-chain documents the mfg_batch references
+    pub fn attachments(&self) -> &[MfgBatchAttachment] {
+        &self.attachments
+    }
+
+    /// Returns whether the mfg_batch has been archived rather than deleted
+    pub fn archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Replaces the properties of the mfg_batch, such as with a localized selection of them
+    #[cfg(feature = "mfg-batch-localization")]
+    pub(in crate::mfg_batch) fn with_properties(mut self, properties: Vec<PropertyValue>) -> Self {
+        self.properties = properties;
+        self
+    }
 }
 
 /// Builder used to create a MfgBatch
@@ -94,6 +175,15 @@ pub struct MfgBatchBuilder {
     service_id: Option<String>,
     last_updated: Option<i64>,
     properties: Vec<PropertyValue>,
+    parent_batches: Vec<String>,
+    quantity: Option<i64>,
+    uom: Option<String>,
+    expected_quantity: Option<i64>,
+    production_date: Option<i64>,
+    expiration_date: Option<i64>,
+    manufacture_location: Option<String>,
+    attachments: Vec<MfgBatchAttachment>,
+    archived: bool,
 }
 
 impl MfgBatchBuilder {
@@ -151,6 +241,60 @@ impl MfgBatchBuilder {
         self
     }
 
+    /// Sets the IDs of the batches this mfg_batch was produced from
+    pub fn with_parent_batches(mut self, parent_batches: Vec<String>) -> Self {
+        self.parent_batches = parent_batches;
+        self
+    }
+
+    /// Sets the quantity produced for this mfg_batch
+    pub fn with_quantity(mut self, quantity: Option<i64>) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    /// Sets the unit of measure code for this mfg_batch's quantities
+    pub fn with_uom(mut self, uom: Option<String>) -> Self {
+        self.uom = uom;
+        self
+    }
+
+    /// Sets the quantity this mfg_batch was planned to produce
+    pub fn with_expected_quantity(mut self, expected_quantity: Option<i64>) -> Self {
+        self.expected_quantity = expected_quantity;
+        self
+    }
+
+    /// Sets when this mfg_batch was produced, in seconds since the epoch
+    pub fn with_production_date(mut self, production_date: Option<i64>) -> Self {
+        self.production_date = production_date;
+        self
+    }
+
+    /// Sets when this mfg_batch expires, in seconds since the epoch
+    pub fn with_expiration_date(mut self, expiration_date: Option<i64>) -> Self {
+        self.expiration_date = expiration_date;
+        self
+    }
+
+    /// Sets the GLN of the location this mfg_batch was produced at
+    pub fn with_manufacture_location(mut self, manufacture_location: Option<String>) -> Self {
+        self.manufacture_location = manufacture_location;
+        self
+    }
+
+    /// Sets the off-chain documents this mfg_batch references
+    pub fn with_attachments(mut self, attachments: Vec<MfgBatchAttachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Sets whether this mfg_batch has been archived
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = archived;
+        self
+    }
+
     pub fn build(self) -> Result<MfgBatch, MfgBatchBuilderError> {
         let MfgBatchBuilder {
             mfg_batch_id,
@@ -162,42 +306,44 @@ impl MfgBatchBuilder {
             service_id,
             last_updated,
             properties,
+            parent_batches,
+            quantity,
+            uom,
+            expected_quantity,
+            production_date,
+            expiration_date,
+            manufacture_location,
+            attachments,
+            archived,
         } = self;
 
         if mfg_batch_id.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_id".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::MfgBatchId,
             ));
         };
 
         if mfg_batch_address.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_address".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::MfgBatchAddress,
             ));
         };
 
         if mfg_batch_namespace.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_namespace".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::MfgBatchNamespace,
             ));
         };
 
         if owner.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing owner".to_string(),
-            ));
+            return Err(MfgBatchBuilderError::MissingField(MfgBatchField::Owner));
         };
 
         if start_commit_num >= end_commit_num {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "start_commit_number must be less than end_commit_num".to_string(),
-            ));
-        };
-
-        if end_commit_num <= start_commit_num {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "end_commit_number must be greater than start_commit_num".to_string(),
-            ));
+            return Err(MfgBatchBuilderError::InvalidValue {
+                field: MfgBatchField::StartCommitNum,
+                reason: format!("must be less than end_commit_num ({})", end_commit_num),
+            });
         };
 
         Ok(MfgBatch {
@@ -210,6 +356,15 @@ impl MfgBatchBuilder {
             service_id,
             last_updated,
             properties,
+            parent_batches,
+            quantity,
+            uom,
+            expected_quantity,
+            production_date,
+            expiration_date,
+            manufacture_location,
+            attachments,
+            archived,
         })
     }
 }
@@ -230,6 +385,8 @@ pub struct PropertyValue {
     start_commit_num: i64,
     end_commit_num: i64,
     service_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
 }
 
 impl PropertyValue {
@@ -268,6 +425,18 @@ impl PropertyValue {
         self.number_value
     }
 
+    /// Returns the number the property value is worth, scaled by the exponent of its
+    /// definition, or `None` if the value is not a number or the exponent is out of range
+    pub fn number(&self, definition: &PropertyDefinition) -> Option<NumberValue> {
+        match DataTypeKind::parse(&self.data_type)? {
+            DataTypeKind::Number => Some(NumberValue::new(
+                self.number_value?,
+                i32::try_from(definition.number_exponent).ok()?,
+            )),
+            _ => None,
+        }
+    }
+
     /// Returns the string_value for the property value
     pub fn string_value(&self) -> Option<&str> {
         self.string_value.as_deref()
@@ -302,6 +471,101 @@ impl PropertyValue {
     pub fn service_id(&self) -> Option<&str> {
         self.service_id.as_deref()
     }
+
+    /// Returns the BCP 47 language tag of the property value, if it is a localized variant
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// Returns the property value's payload as the field its data_type names, or `None` if the
+    /// data_type is not a Grid data type or that field is not populated
+    pub fn typed_value(&self) -> Option<TypedValue> {
+        match DataTypeKind::parse(&self.data_type)? {
+            DataTypeKind::Bytes => self.bytes_value.clone().map(TypedValue::Bytes),
+            DataTypeKind::Boolean => self.boolean_value.map(TypedValue::Bool),
+            DataTypeKind::Number => self.number_value.map(|value| TypedValue::Number {
+                value,
+                exponent: None,
+            }),
+            DataTypeKind::String => self.string_value.clone().map(TypedValue::String),
+            DataTypeKind::Enum => self.enum_value.map(TypedValue::Enum),
+            DataTypeKind::Struct => Some(TypedValue::Struct(self.struct_values.clone())),
+            DataTypeKind::LatLong => self.lat_long_value.clone().map(TypedValue::LatLong),
+        }
+    }
+}
+
+/// The payload of a property value, typed by its data_type
+#[derive(Debug, Clone)]
+pub enum TypedValue {
+    Bytes(Vec<u8>),
+    Bool(bool),
+    /// A fixed-point number, worth `value * 10^exponent`. The exponent is defined by the schema
+    /// rather than stored with the value, so it is `None` when read from a stored value.
+    Number {
+        value: i64,
+        exponent: Option<i32>,
+    },
+    String(String),
+    Enum(i32),
+    Struct(Vec<PropertyValue>),
+    LatLong(LatLongValue),
+}
+
+impl TypedValue {
+    /// Returns the data_type a property value holding this payload is stored with
+    pub fn data_type(&self) -> &'static str {
+        match self {
+            TypedValue::Bytes(_) => "Bytes",
+            TypedValue::Bool(_) => "Boolean",
+            TypedValue::Number { .. } => "Number",
+            TypedValue::String(_) => "String",
+            TypedValue::Enum(_) => "Enum",
+            TypedValue::Struct(_) => "Struct",
+            TypedValue::LatLong(_) => "LatLong",
+        }
+    }
+
+    /// Returns the number a Number payload is worth, or `None` if the payload is not a number
+    /// or its exponent is not known
+    pub fn number(&self) -> Option<NumberValue> {
+        match self {
+            TypedValue::Number {
+                value,
+                exponent: Some(exponent),
+            } => Some(NumberValue::new(*value, *exponent)),
+            _ => None,
+        }
+    }
+}
+
+/// The Grid data types a property value's data_type may name. Data types are stored both as
+/// the `Debug` names of the schema's `DataType` ("LatLong") and upper case ("LAT_LONG"), so
+/// parsing ignores case and underscores.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DataTypeKind {
+    Bytes,
+    Boolean,
+    Number,
+    String,
+    Enum,
+    Struct,
+    LatLong,
+}
+
+impl DataTypeKind {
+    fn parse(data_type: &str) -> Option<Self> {
+        match data_type.replace('_', "").to_ascii_lowercase().as_str() {
+            "bytes" => Some(DataTypeKind::Bytes),
+            "boolean" => Some(DataTypeKind::Boolean),
+            "number" => Some(DataTypeKind::Number),
+            "string" => Some(DataTypeKind::String),
+            "enum" => Some(DataTypeKind::Enum),
+            "struct" => Some(DataTypeKind::Struct),
+            "latlong" => Some(DataTypeKind::LatLong),
+            _ => None,
+        }
+    }
 }
 
 /// Builder used to create a PropertyValue
@@ -321,6 +585,7 @@ pub struct PropertyValueBuilder {
     start_commit_num: i64,
     end_commit_num: i64,
     service_id: Option<String>,
+    language: Option<String>,
 }
 
 impl PropertyValueBuilder {
@@ -408,6 +673,36 @@ impl PropertyValueBuilder {
         self
     }
 
+    /// Sets the BCP 47 language tag of this property value, if it is a localized variant
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Sets the data type and value of this property value from a typed payload, clearing any
+    /// value of another type. A number's exponent is not stored with the value.
+    pub fn with_typed_value(mut self, typed_value: TypedValue) -> Self {
+        self.data_type = typed_value.data_type().to_string();
+        self.bytes_value = None;
+        self.boolean_value = None;
+        self.number_value = None;
+        self.string_value = None;
+        self.enum_value = None;
+        self.struct_values = Vec::new();
+        self.lat_long_value = None;
+
+        match typed_value {
+            TypedValue::Bytes(value) => self.bytes_value = Some(value),
+            TypedValue::Bool(value) => self.boolean_value = Some(value),
+            TypedValue::Number { value, .. } => self.number_value = Some(value),
+            TypedValue::String(value) => self.string_value = Some(value),
+            TypedValue::Enum(value) => self.enum_value = Some(value),
+            TypedValue::Struct(values) => self.struct_values = values,
+            TypedValue::LatLong(value) => self.lat_long_value = Some(value),
+        }
+        self
+    }
+
     pub fn build(self) -> Result<PropertyValue, MfgBatchBuilderError> {
         let PropertyValueBuilder {
             mfg_batch_id,
@@ -424,43 +719,62 @@ impl PropertyValueBuilder {
             start_commit_num,
             end_commit_num,
             service_id,
+            language,
         } = self;
 
         if mfg_batch_id.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_id".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::MfgBatchId,
             ));
         };
 
         if mfg_batch_address.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_address".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::MfgBatchAddress,
             ));
         };
 
         if property_name.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing mfg_batch_name".to_string(),
+            return Err(MfgBatchBuilderError::MissingField(
+                MfgBatchField::PropertyName,
             ));
         };
 
         if data_type.is_empty() {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "Missing data_type".to_string(),
-            ));
+            return Err(MfgBatchBuilderError::MissingField(MfgBatchField::DataType));
         };
 
         if start_commit_num >= end_commit_num {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "start_commit_number must be less than end_commit_num".to_string(),
-            ));
+            return Err(MfgBatchBuilderError::InvalidValue {
+                field: MfgBatchField::StartCommitNum,
+                reason: format!("must be less than end_commit_num ({})", end_commit_num),
+            });
         };
 
-        if end_commit_num <= start_commit_num {
-            return Err(MfgBatchBuilderError::MissingRequiredField(
-                "end_commit_number must be greater than start_commit_num".to_string(),
-            ));
-        };
+        // Values of unrecognized data types are left unchecked
+        if let Some(kind) = DataTypeKind::parse(&data_type) {
+            let populated = [
+                (DataTypeKind::Bytes, bytes_value.is_some()),
+                (DataTypeKind::Boolean, boolean_value.is_some()),
+                (DataTypeKind::Number, number_value.is_some()),
+                (DataTypeKind::String, string_value.is_some()),
+                (DataTypeKind::Enum, enum_value.is_some()),
+                (DataTypeKind::Struct, !struct_values.is_empty()),
+                (DataTypeKind::LatLong, lat_long_value.is_some()),
+            ];
+            if let Some((other, _)) = populated
+                .iter()
+                .find(|(other, is_set)| *is_set && *other != kind)
+            {
+                return Err(MfgBatchBuilderError::InvalidValue {
+                    field: MfgBatchField::DataType,
+                    reason: format!(
+                        "a property value of data type {} cannot hold a {:?} value",
+                        data_type, other
+                    ),
+                });
+            }
+        }
 
         Ok(PropertyValue {
             mfg_batch_id,
@@ -477,6 +791,7 @@ impl PropertyValueBuilder {
             start_commit_num,
             end_commit_num,
             service_id,
+            language,
         })
     }
 }
@@ -503,12 +818,511 @@ impl MfgBatchList {
     }
 }
 
+/// Fetches the mfg_batches listed after the given mfg_batch ID, or the first page if `None`
+type NextMfgBatchPage<'a> =
+    Box<dyn FnMut(Option<&str>) -> Result<Vec<MfgBatch>, MfgBatchStoreError> + 'a>;
+
+/// Walks a set of mfg_batches one page at a time, in mfg_batch ID order
+///
+/// Each page starts after the last mfg_batch ID of the previous page, so only one page is held in
+/// memory at a time. Iteration stops after the first error.
+pub struct MfgBatchIter<'a> {
+    next_page: NextMfgBatchPage<'a>,
+    page_size: i64,
+    page: std::vec::IntoIter<MfgBatch>,
+    last_id: Option<String>,
+    exhausted: bool,
+}
+
+impl<'a> MfgBatchIter<'a> {
+    /// Creates an iterator that loads pages of up to `page_size` mfg_batches with `next_page`
+    ///
+    /// A page with fewer than `page_size` mfg_batches is taken to be the last one.
+    pub fn new<F>(page_size: i64, next_page: F) -> Self
+    where
+        F: FnMut(Option<&str>) -> Result<Vec<MfgBatch>, MfgBatchStoreError> + 'a,
+    {
+        Self {
+            next_page: Box::new(next_page),
+            page_size: page_size.max(1),
+            page: Vec::new().into_iter(),
+            last_id: None,
+            exhausted: false,
+        }
+    }
+}
+
+impl<'a> Iterator for MfgBatchIter<'a> {
+    type Item = Result<MfgBatch, MfgBatchStoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(mfg_batch) = self.page.next() {
+            return Some(Ok(mfg_batch));
+        }
+
+        if self.exhausted {
+            return None;
+        }
+
+        match (self.next_page)(self.last_id.as_deref()) {
+            Ok(page) => {
+                if (page.len() as i64) < self.page_size {
+                    self.exhausted = true;
+                }
+                if let Some(last) = page.last() {
+                    self.last_id = Some(last.mfg_batch_id().to_string());
+                }
+                self.page = page.into_iter();
+                self.page.next().map(Ok)
+            }
+            Err(err) => {
+                self.exhausted = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The position a keyset page of mfg_batches starts after: the mfg_batch ID and row ID of the
+/// last mfg_batch of the page before it
+///
+/// Cursors order by mfg_batch ID, then by row ID, as pages are listed.
+#[cfg(feature = "mfg-batch-keyset-paging")]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MfgBatchCursor {
+    mfg_batch_id: String,
+    id: i64,
+}
+
+#[cfg(feature = "mfg-batch-keyset-paging")]
+impl MfgBatchCursor {
+    pub fn new(mfg_batch_id: String, id: i64) -> Self {
+        Self { mfg_batch_id, id }
+    }
+
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Encodes the cursor as an opaque, URL-safe string
+    pub fn encode(&self) -> String {
+        base64::encode_config(
+            format!("{}:{}", self.id, self.mfg_batch_id),
+            base64::URL_SAFE_NO_PAD,
+        )
+    }
+
+    /// Decodes a cursor encoded with `encode`
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the string is not an encoded cursor
+    pub fn decode(cursor: &str) -> Result<Self, MfgBatchStoreError> {
+        base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|decoded| {
+                let (id, mfg_batch_id) = decoded.split_once(':')?;
+                Some(Self::new(mfg_batch_id.to_string(), id.parse().ok()?))
+            })
+            .ok_or_else(|| {
+                MfgBatchStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                    "cursor".to_string(),
+                    "not a mfg_batch cursor".to_string(),
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListMfgBatchFilters {
+    pub owner: Option<String>,
+    pub mfg_batch_namespace: Option<String>,
+    // Only mfg_batches expiring before this time, in seconds since the epoch
+    pub expiring_before: Option<i64>,
+    // Only mfg_batches expiring at or after this time, in seconds since the epoch
+    pub expiring_after: Option<i64>,
+    // Only mfg_batches that are, or are not, archived
+    pub archived: Option<bool>,
+    // Only mfg_batches produced at the location with this GLN
+    pub manufacture_location: Option<String>,
+    // Only mfg_batches visible to an organization
+    #[cfg(feature = "mfg-batch-visibility")]
+    pub visibility: Option<Visibility>,
+}
+
+/// Restricts reads to the mfg_batches an organization may see: those it owns and those shared
+/// with it
+#[cfg(feature = "mfg-batch-visibility")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Visibility {
+    pub org_id: String,
+}
+
+#[cfg(feature = "mfg-batch-visibility")]
+impl Visibility {
+    pub fn org(org_id: &str) -> Self {
+        Self {
+            org_id: org_id.to_string(),
+        }
+    }
+}
+
+/// The value a property must have for a mfg_batch to match a property search
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertySearchValue {
+    String(String),
+    Number(i64),
+    Boolean(bool),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LatLongValue {
     pub latitude: i64,
     pub longitude: i64,
 }
 
+/// The result of upserting a mfg_batch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpsertMfgBatchOutcome {
+    /// No current version of the mfg_batch existed, so it was added
+    Created,
+    /// The mfg_batch replaced the version that became current at `prior_commit_num`
+    Replaced { prior_commit_num: i64 },
+    /// The current version of the mfg_batch became current at or after the upserted version's
+    /// commit, so nothing was written
+    Conflict { current_commit_num: i64 },
+}
+
+/// The owner of a mfg_batch, with the details of the owning organization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchOwner {
+    mfg_batch_id: String,
+    org_id: String,
+    name: Option<String>,
+    location_ids: Vec<String>,
+}
+
+impl MfgBatchOwner {
+    /// Returns the ID of the mfg_batch
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    /// Returns the ID of the organization that owns the mfg_batch
+    pub fn org_id(&self) -> &str {
+        &self.org_id
+    }
+
+    /// Returns the name of the owning organization, if the organization is known
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the IDs of the locations associated with the owning organization
+    pub fn location_ids(&self) -> &[String] {
+        &self.location_ids
+    }
+}
+
+/// A document kept off-chain that a mfg_batch references by its SHA-256 digest
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAttachment {
+    /// The hex encoded SHA-256 digest of the document
+    pub digest: String,
+    pub mime_type: String,
+    /// The size of the document, in bytes
+    pub size: i64,
+    /// Where the document can be fetched from, if it has been published
+    pub uri: Option<String>,
+}
+
+/// A comment attached to one property of a mfg_batch, as it stood at a given commit
+#[cfg(feature = "mfg-batch-annotations")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAnnotation {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub property_name: String,
+    pub author: String,
+    pub comment: String,
+    pub service_id: Option<String>,
+}
+
+/// A quality control test result recorded against a mfg_batch at a given commit
+#[cfg(feature = "mfg-batch-test-results")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchTestResult {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub test_name: String,
+    pub specification: String,
+    pub result: String,
+    pub passed: bool,
+    pub lab: String,
+    /// When the test was performed, in seconds since the epoch
+    pub tested_at: i64,
+    pub service_id: Option<String>,
+}
+
+/// How completely a mfg_batch's data was filled in when it was last scored, out of 100
+#[cfg(feature = "mfg-batch-quality-scores")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchQualityScore {
+    pub mfg_batch_id: String,
+    /// The commit of the mfg_batch version that was scored
+    pub commit_num: i64,
+    pub score: i32,
+    /// The names of the rubric's checks the mfg_batch failed
+    pub failed_checks: Vec<String>,
+    /// When the mfg_batch was scored, in seconds since the epoch
+    pub scored_at: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-quality-scores")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListMfgBatchQualityScoreFilters {
+    // Only the scores of these mfg_batches
+    pub mfg_batch_ids: Option<Vec<String>>,
+    // Only scores at or below this score
+    pub max_score: Option<i32>,
+}
+
+/// A mfg_batch that is likely a duplicate of another, awaiting review
+#[cfg(feature = "mfg-batch-duplicates")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchDuplicate {
+    pub mfg_batch_id: String,
+    /// The mfg_batch it is suggested to be merged into
+    pub duplicate_of: String,
+    /// The name of the matching rule that flagged the pair
+    pub rule: String,
+    /// How alike the pair is, out of 100
+    pub similarity: i32,
+    /// When the pair was flagged, in seconds since the epoch
+    pub detected_at: i64,
+    pub service_id: Option<String>,
+}
+
+/// A mfg_batch that was merged into another, so that lookups by its id resolve to the other
+#[cfg(feature = "mfg-batch-merge")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAlias {
+    /// The id of the merged mfg_batch
+    pub alias: String,
+    /// The id of the mfg_batch it was merged into
+    pub mfg_batch_id: String,
+    /// When the mfg_batches were merged, in seconds since the epoch
+    pub merged_at: i64,
+    pub service_id: Option<String>,
+}
+
+/// A problem found while verifying the mfg_batch audit log
+#[cfg(feature = "mfg-batch-audit-log")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditLogDiscrepancy {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+    pub kind: AuditLogDiscrepancyKind,
+}
+
+#[cfg(feature = "mfg-batch-audit-log")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuditLogDiscrepancyKind {
+    /// The entry does not follow the one before it, so an entry was removed, inserted or
+    /// reordered
+    BrokenChain,
+    /// The entry's hash no longer matches the entry's contents
+    EntryModified,
+    /// The rows written at this commit no longer match the hash recorded for them
+    RecordModified,
+    /// The rows recorded by this entry no longer exist
+    RecordMissing,
+    /// A mfg_batch row was written without a corresponding audit entry
+    Unaudited,
+}
+
+#[cfg(feature = "mfg-batch-audit-log")]
+impl std::fmt::Display for AuditLogDiscrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let description = match self.kind {
+            AuditLogDiscrepancyKind::BrokenChain => {
+                "audit entry does not follow the previous entry"
+            }
+            AuditLogDiscrepancyKind::EntryModified => "audit entry has been modified",
+            AuditLogDiscrepancyKind::RecordModified => "record has been modified",
+            AuditLogDiscrepancyKind::RecordMissing => "record has been removed",
+            AuditLogDiscrepancyKind::Unaudited => "record has no audit entry",
+        };
+
+        write!(f, "{} at commit {}", self.mfg_batch_id, self.commit_num)?;
+        if let Some(service_id) = &self.service_id {
+            write!(f, " ({})", service_id)?;
+        }
+        write!(f, ": {}", description)
+    }
+}
+
+/// The SQL a store query runs and the database's plan for running it
+#[cfg(feature = "mfg-batch-explain")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub sql: String,
+    /// The backend's EXPLAIN output, one line per entry
+    pub plan: Vec<String>,
+}
+
+/// A mfg_batch row whose checksum could not be verified
+#[cfg(feature = "mfg-batch-checksums")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChecksumDiscrepancy {
+    pub mfg_batch_id: String,
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+    pub kind: ChecksumDiscrepancyKind,
+}
+
+#[cfg(feature = "mfg-batch-checksums")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChecksumDiscrepancyKind {
+    /// The rows written at this commit no longer match the checksum, so they were modified or
+    /// only partially written
+    Mismatch,
+    /// The row has no checksum, such as a row written before checksums were enabled
+    Missing,
+}
+
+#[cfg(feature = "mfg-batch-checksums")]
+impl std::fmt::Display for ChecksumDiscrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let description = match self.kind {
+            ChecksumDiscrepancyKind::Mismatch => "record does not match its checksum",
+            ChecksumDiscrepancyKind::Missing => "record has no checksum",
+        };
+
+        write!(f, "{} at commit {}", self.mfg_batch_id, self.commit_num)?;
+        if let Some(service_id) = &self.service_id {
+            write!(f, " ({})", service_id)?;
+        }
+        write!(f, ": {}", description)
+    }
+}
+
+/// The Merkle root of the mfg_batch records current at a commit, as anchored on chain
+#[cfg(feature = "mfg-batch-anchors")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAnchor {
+    /// The organization that submitted the anchor
+    pub owner: String,
+    /// The commit the Merkle root was computed at
+    pub commit_num: i64,
+    pub merkle_root: String,
+    /// The number of records the Merkle root was computed over
+    pub row_count: i64,
+    /// The state address the anchor is stored at on chain
+    pub anchor_address: String,
+    /// The commit the anchor itself was recorded in
+    pub anchored_commit_num: i64,
+    /// When the anchor was submitted, in seconds since the epoch
+    pub anchored_at: i64,
+    pub service_id: Option<String>,
+}
+
+/// A quantity of a mfg_batch reserved for a sales order
+#[cfg(feature = "mfg-batch-allocations")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAllocation {
+    pub mfg_batch_id: String,
+    pub order_id: String,
+    pub quantity: i64,
+    /// The commit the allocation was last changed in
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+/// How much of a mfg_batch's produced quantity is still free to allocate
+#[cfg(feature = "mfg-batch-allocations")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchAvailability {
+    pub mfg_batch_id: String,
+    /// The produced quantity of the current version of the mfg_batch
+    pub quantity: Option<i64>,
+    pub uom: Option<String>,
+    pub allocated_quantity: i64,
+    pub available_quantity: i64,
+}
+
+/// The recall of a mfg_batch, recorded once the recall action flagging it on-chain is committed
+#[cfg(feature = "mfg-batch-recalls")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfgBatchRecall {
+    pub recall_id: String,
+    pub mfg_batch_id: String,
+    pub reason_code: String,
+    /// When the mfg_batch was recalled, in seconds since the epoch
+    pub recalled_at: i64,
+    /// When the recall was closed out, in seconds since the epoch; `None` while it is active
+    pub closed_at: Option<i64>,
+    /// The commit the recall action was committed in
+    pub commit_num: i64,
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "mfg-batch-recalls")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListMfgBatchRecallFilters {
+    // Only the recalls of this mfg_batch
+    pub mfg_batch_id: Option<String>,
+    // Only the batches covered by this recall
+    pub recall_id: Option<String>,
+    // Whether recalls that have been closed out are listed too
+    pub include_closed: bool,
+}
+
+/// The number of rows a stored version of a mfg_batch is made of, counted without loading them
+#[cfg(feature = "mfg-batch-row-counts")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MfgBatchVersionRows {
+    pub start_commit_num: i64,
+    pub end_commit_num: i64,
+    /// The property value rows of each top-level property, including the rows of its struct
+    /// values, in property name order
+    pub property_rows: Vec<(String, i64)>,
+}
+
+#[cfg(feature = "mfg-batch-row-counts")]
+impl MfgBatchVersionRows {
+    /// Returns the number of property value rows of the version
+    pub fn total_property_rows(&self) -> i64 {
+        self.property_rows.iter().map(|(_, rows)| rows).sum()
+    }
+}
+
+/// The number of rows of each versioned table ended before a commit, that pruning the history
+/// before that commit deletes or, in a dry run, would delete
+#[cfg(feature = "mfg-batch-pruning")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrunedMfgBatchHistory {
+    pub mfg_batches: i64,
+    pub property_values: i64,
+    pub parents: i64,
+    pub attachments: i64,
+}
+
+#[cfg(feature = "mfg-batch-pruning")]
+impl PrunedMfgBatchHistory {
+    /// Returns the number of rows across the versioned tables
+    pub fn total(&self) -> i64 {
+        self.mfg_batches + self.property_values + self.parents + self.attachments
+    }
+}
+
 pub trait MfgBatchStore {
     /// Adds a mfg_batch to the underlying storage
     ///
@@ -517,6 +1331,27 @@ pub trait MfgBatchStore {
     ///  * `mfg_batch` - The mfg_batch to be added
     fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError>;
 
+    /// Adds many mfg_batches to the underlying storage, such as during an initial sync.
+    /// The mfg_batches are written in order, in chunks of the store's bulk insert
+    /// chunk size; each chunk is written in its own transaction.
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batches` - The mfg_batches to be added
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError>;
+
+    /// Adds a mfg_batch to the underlying storage, replacing the current
+    /// version if it is older, and reports which of these happened. Nothing
+    /// is written if the current version is at least as new.
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch` - The mfg_batch to be added or replaced
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError>;
+
     /// Gets a mfg_batch from the underlying storage
     ///
     /// # Arguments
@@ -529,74 +1364,1554 @@ pub trait MfgBatchStore {
         service_id: Option<&str>,
     ) -> Result<Option<MfgBatch>, MfgBatchStoreError>;
 
+    /// Gets several mfg_batches at once, in the order requested. Ids with no current mfg_batch
+    /// are skipped, and each mfg_batch is returned once however many of the ids resolve to it.
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_ids` - The IDs of the mfg_batches to be fetched
+    ///  * `service_id` - The service ID to fetch the mfg_batches for
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
     /// Gets a list of mfg_batches from the underlying storage
     ///
     /// # Arguments
     ///
     ///  * `service_id` - The service ID to fetch the mfg_batch for
+    ///  * `filters` - Filters the listed mfg_batches must match
     ///  * `offset` - The index of the first in storage to retrieve
     ///  * `limit` - The number of items to retrieve from the offset
     fn list_mfg_batches(
         &self,
         service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
         offset: i64,
         limit: i64,
     ) -> Result<MfgBatchList, MfgBatchStoreError>;
 
-    /// Updates a mfg_batch in the underlying storage
+    /// Returns an iterator over every mfg_batch matching the filters, in mfg_batch ID order
+    ///
+    /// Pages are read lazily using the last mfg_batch ID seen rather than an offset, so memory use
+    /// is bounded by `page_size`. The pages are separate reads, not a snapshot; mfg_batches
+    /// changed during the walk may or may not be returned.
     ///
     /// # Arguments
     ///
-    ///  * `mfg_batch` - The updated mfg_batch
-    ///  * `service_id` - The service ID to fetch the mfg_batch for
-    ///  * `current_commit_num` - The current commit height
-    fn update_mfg_batch(
+    ///  * `service_id` - The service ID to fetch the mfg_batches for
+    ///  * `filters` - Filters the listed mfg_batches must match
+    ///  * `page_size` - The number of mfg_batches to read per query
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a>;
+
+    /// Lists a page of the mfg_batches matching the filters in mfg_batch ID order, starting
+    /// after a cursor rather than at an offset, so reading deep pages stays cheap
+    ///
+    /// Each mfg_batch is returned with its cursor; the next page starts after the cursor of the
+    /// last one. A page with fewer than `limit` mfg_batches is the last.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to fetch the mfg_batches for
+    ///  * `filters` - Filters the listed mfg_batches must match
+    ///  * `cursor` - The cursor the page starts after, or `None` for the first page
+    ///  * `limit` - The number of mfg_batches to retrieve
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
         &self,
-        mfg_batch_id: &str,
         service_id: Option<&str>,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError>;
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError>;
 
-    /// Deletes a mfg_batch from the underlying storage
+    /// Returns the SQL `list_mfg_batches` would run for the same arguments and
+    /// the database's plan for it, without running it
     ///
     /// # Arguments
     ///
-    ///  * `address` - The address of the record to be deleted
-    ///  * `current_commit_num` - The current commit height
-    fn delete_mfg_batch(
-        &self,
-        address: &str,
-        current_commit_num: i64,
-    ) -> Result<(), MfgBatchStoreError>;
-}
-
-impl<PS> MfgBatchStore for Box<PS>
-where
-    PS: MfgBatchStore + ?Sized,
-{
-    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
-        (**self).add_mfg_batch(mfg_batch)
-    }
-
-    fn get_mfg_batch(
+    ///  * `service_id` - The service ID to explain the list for
+    ///  * `filters` - Filters the listed mfg_batches must match
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
         &self,
-        mfg_batch_id: &str,
         service_id: Option<&str>,
-    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
-        (**self).get_mfg_batch(mfg_batch_id, service_id)
-    }
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError>;
 
-    fn list_mfg_batches(
+    /// Gets the owner of each mfg_batch in a page of the mfg_batch list, joined
+    /// with the owning organization's name and locations
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to fetch the mfg_batch owners for
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    fn list_mfg_batch_owners(
         &self,
         service_id: Option<&str>,
         offset: i64,
         limit: i64,
-    ) -> Result<MfgBatchList, MfgBatchStoreError> {
-        (**self).list_mfg_batches(service_id, offset, limit)
-    }
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError>;
 
-    fn update_mfg_batch(
-        &self,
+    /// Counts the current mfg_batches in the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to count the mfg_batches for
+    ///  * `filters` - Filters the mfg_batches to be counted must match
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError>;
+
+    /// Checks whether a current mfg_batch exists in the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to check for
+    ///  * `service_id` - The service ID to check the mfg_batch for
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError>;
+
+    /// Gets the current mfg_batches with a top-level property of the given
+    /// name set to the given value, ordered by mfg_batch ID
+    ///
+    /// # Arguments
+    ///
+    ///  * `property_name` - The name of the property to match
+    ///  * `value` - The value the property must have
+    ///  * `service_id` - The service ID to search the mfg_batches for
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
+    /// Fetches the IDs of every ancestor of a mfg_batch, following the
+    /// current parent links breadth-first from the given batch
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to trace
+    ///  * `service_id` - The service ID to trace the mfg_batch for
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+
+    /// Fetches the IDs of every descendant of a mfg_batch, following the
+    /// current parent links breadth-first from the given batch to the batches
+    /// produced from it
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to trace
+    ///  * `service_id` - The service ID to trace the mfg_batch for
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+
+    /// Gets a mfg_batch as it stood at the given commit, rather than its
+    /// current version
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to be fetched
+    ///  * `commit_num` - The commit number to fetch the mfg_batch as of
+    ///  * `service_id` - The service ID to fetch the mfg_batch for
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError>;
+
+    /// Lists every stored version of a mfg_batch, oldest first
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to be fetched
+    ///  * `service_id` - The service ID to fetch the mfg_batch for
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
+    /// Gets every stored value of a top-level property of a mfg_batch, with its struct values
+    /// as they were when it was stored, oldest first
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to get the property of
+    ///  * `property_name` - The name of the property to get the values of
+    ///  * `service_id` - The service ID to get the values for
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError>;
+
+    /// Lists the current mfg_batches last updated at or after a time, oldest update first, so
+    /// that a consumer can sync incrementally from the latest update it has seen
+    ///
+    /// # Arguments
+    ///
+    ///  * `since` - The time to list updates from, in seconds since the epoch
+    ///  * `service_id` - The service ID to list mfg_batches for
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
+    /// Counts the rows each stored version of a mfg_batch is made of, oldest version first,
+    /// so a reader can tell how large the versions are before loading them
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to count the rows of
+    ///  * `service_id` - The service ID to count the rows for
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError>;
+
+    /// Gets the named top-level properties of the current version of a mfg_batch, with their
+    /// struct values, without loading its other properties
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to get the properties of
+    ///  * `property_names` - The names of the properties to get
+    ///  * `service_id` - The service ID to get the properties for
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError>;
+
+    /// Checks whether a current mfg_batch is visible to an organization, as it owns the
+    /// mfg_batch or the mfg_batch is shared with it
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to check
+    ///  * `visibility` - The organization the mfg_batch must be visible to
+    ///  * `service_id` - The service ID to check the mfg_batch for
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError>;
+
+    /// Shares a current mfg_batch with an organization other than its owner, so that reads
+    /// restricted to that organization include it. Sharing it again has no effect.
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to share
+    ///  * `org_id` - The organization to share the mfg_batch with
+    ///  * `service_id` - The service ID to share the mfg_batch for
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Stops sharing a mfg_batch with an organization
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to stop sharing
+    ///  * `org_id` - The organization to stop sharing the mfg_batch with
+    ///  * `service_id` - The service ID to stop sharing the mfg_batch for
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the organizations a mfg_batch is shared with, in order
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to list the shares of
+    ///  * `service_id` - The service ID to list the shares for
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+
+    /// Creates the archive partitions holding mfg_batch rows ended between
+    /// the given commits, if they do not already exist. Partitioning is only
+    /// supported on Postgres; on SQLite this only checks the range.
+    ///
+    /// # Arguments
+    ///
+    ///  * `from_commit_num` - The first ending commit number held by the partitions
+    ///  * `to_commit_num` - The ending commit number the partitions stop before
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Deletes the rows of mfg_batch versions that ended before the given
+    /// commit, so the history before it is no longer kept; current rows are
+    /// never deleted. Versions as of earlier commits can no longer be read
+    /// once pruned. Returns the number of rows deleted from each table, or
+    /// in a dry run the number that would be, without deleting them.
+    ///
+    /// # Arguments
+    ///
+    ///  * `before_commit_num` - The commit number the pruned rows ended before
+    ///  * `dry_run` - Whether to count the rows without deleting them
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError>;
+
+    /// Attaches an annotation to a property of a mfg_batch version. The
+    /// property must have had a value as of the annotation's commit.
+    ///
+    /// # Arguments
+    ///
+    ///  * `annotation` - The annotation to be added
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the annotations on every version of a mfg_batch, ordered by the
+    /// commit they were made against so they can be paired with its history
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to list annotations for
+    ///  * `service_id` - The service ID to list annotations for
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError>;
+
+    /// Records a test result against a mfg_batch. The mfg_batch must have
+    /// existed as of the result's commit.
+    ///
+    /// # Arguments
+    ///
+    ///  * `test_result` - The test result to be added
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the test results recorded against a mfg_batch, in the order
+    /// they were added
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to list test results for
+    ///  * `service_id` - The service ID to list test results for
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError>;
+
+    /// Stores the quality score of a mfg_batch, replacing the one it had
+    ///
+    /// # Arguments
+    ///
+    ///  * `score` - The quality score to be stored
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the quality scores of current mfg_batches, lowest score first, so the mfg_batches
+    /// most in need of cleaning up come first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to list quality scores for
+    ///  * `filters` - Filters the listed quality scores must match
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError>;
+
+    /// Replaces the duplicate review queue of a service with the given duplicates, as found by
+    /// a new detection run
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to replace the review queue of
+    ///  * `duplicates` - The duplicates found
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the duplicate review queue, most alike first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to list duplicates for
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError>;
+
+    /// Merges the off-chain records kept about one mfg_batch into another and records an alias,
+    /// so that lookups by either id resolve to the mfg_batch merged into. Annotations and test
+    /// results are moved, quality scores and duplicate review entries of the merged mfg_batch
+    /// are removed, and the on-chain records of both mfg_batches are left as they are.
+    ///
+    /// # Arguments
+    ///
+    ///  * `alias` - The id of the mfg_batch to merge and the id of the one to merge it into
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the ids of merged mfg_batches and the mfg_batches they were merged into, oldest
+    /// merge first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to list aliases for
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError>;
+
+    /// Checks the audit log's hash chain and compares each entry against the
+    /// rows it recorded, returning every discrepancy found
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError>;
+
+    /// Recomputes the checksum of each mfg_batch row from the rows written with
+    /// it, returning every row that does not match its stored checksum
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError>;
+
+    /// Records an anchor read from chain state
+    ///
+    /// # Arguments
+    ///
+    ///  * `anchor` - The anchor to be added
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the recorded anchors, oldest commit first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to list anchors for
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError>;
+
+    /// Returns the record hash of each mfg_batch current at a commit, in ascending order. These
+    /// are the leaves of the Merkle tree an anchor's root is computed from.
+    ///
+    /// # Arguments
+    ///
+    ///  * `commit_num` - The commit to hash the current records of
+    ///  * `service_id` - The service ID to hash the records of
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError>;
+
+    /// Sets the quantity of a mfg_batch allocated to an order, replacing any earlier allocation
+    /// to it. A quantity of zero removes the allocation. Fails if the mfg_batch's allocations
+    /// would exceed its produced quantity.
+    ///
+    /// # Arguments
+    ///
+    ///  * `allocation` - The allocation to be recorded
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists the orders a mfg_batch is allocated to
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to list allocations for
+    ///  * `service_id` - The service ID to list allocations for
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError>;
+
+    /// Returns the produced, allocated and available quantities of a mfg_batch, or `None` if
+    /// there is no current version of it
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch_id` - The ID of the mfg_batch to fetch availability for
+    ///  * `service_id` - The service ID to fetch availability for
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError>;
+
+    /// Records the mfg_batches a recall action flagged, all or none of them. Fails if any of the
+    /// mfg_batches had no version current as of the recall's commit.
+    ///
+    /// # Arguments
+    ///
+    ///  * `recalls` - The recall of each mfg_batch
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(&self, recalls: Vec<MfgBatchRecall>)
+        -> Result<(), MfgBatchStoreError>;
+
+    /// Closes out a recall once it has been carried through, for every mfg_batch it covers. The
+    /// mfg_batches stay flagged as recalled on-chain.
+    ///
+    /// # Arguments
+    ///
+    ///  * `recall_id` - The ID of the recall to close
+    ///  * `closed_at` - When the recall was closed, in seconds since the epoch
+    ///  * `service_id` - The service ID to close the recall for
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Lists recalled mfg_batches, most recently recalled first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to list recalls for
+    ///  * `filters` - Filters the listed recalls must match
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError>;
+
+    /// Searches the string property values of current mfg_batches for free text, such as words
+    /// of a batch description or a lot code. On Postgres the store keeps a full-text index of
+    /// the values and lists the best matches first; on SQLite the values are scanned and
+    /// matches are listed by mfg_batch ID.
+    ///
+    /// # Arguments
+    ///
+    ///  * `query` - The words every matched mfg_batch must contain
+    ///  * `service_id` - The service ID to search the mfg_batches for
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError>;
+
+    /// Returns the SQL `search_mfg_batches_text` would run to find the matching mfg_batches for
+    /// the same arguments and the database's plan for it, without running it
+    ///
+    /// # Arguments
+    ///
+    ///  * `query` - The words every matched mfg_batch must contain
+    ///  * `service_id` - The service ID to explain the search for
+    ///  * `offset` - The index of the first in storage to retrieve
+    ///  * `limit` - The number of items to retrieve from the offset
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError>;
+
+    /// Updates a mfg_batch in the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `mfg_batch` - The updated mfg_batch
+    ///  * `service_id` - The service ID to fetch the mfg_batch for
+    ///  * `current_commit_num` - The current commit height
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError>;
+
+    /// Deletes a mfg_batch from the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `address` - The address of the record to be deleted
+    ///  * `current_commit_num` - The current commit height
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError>;
+}
+
+impl<PS> MfgBatchStore for Box<PS>
+where
+    PS: MfgBatchStore + ?Sized,
+{
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch(mfg_batch)
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batches(mfg_batches)
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        (**self).upsert_mfg_batch(mfg_batch)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batch(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        (**self).list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        (**self).iter_mfg_batches(service_id, filters, page_size)
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        (**self).list_mfg_batches_after(service_id, filters, cursor, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        (**self).explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_owners(service_id, offset, limit)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        (**self).count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        (**self).mfg_batch_exists(mfg_batch_id, service_id)
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).search_mfg_batches_by_property(property_name, value, service_id)
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_ancestry(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_descendants(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_history(mfg_batch_id, service_id)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        (**self).get_property_value_history(mfg_batch_id, property_name, service_id)
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).list_mfg_batches_updated_since(since, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        (**self).count_mfg_batch_version_rows(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_properties(mfg_batch_id, property_names, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        (**self).mfg_batch_visible(mfg_batch_id, visibility, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).share_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).unshare_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_shares(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        (**self).prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_test_result(test_result)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        (**self).merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        (**self).verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        (**self).verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        (**self).explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).update_mfg_batch(mfg_batch_id, service_id, current_commit_num)
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).delete_mfg_batch(address, current_commit_num)
+    }
+}
+
+impl<PS> MfgBatchStore for Arc<PS>
+where
+    PS: MfgBatchStore + ?Sized,
+{
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch(mfg_batch)
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batches(mfg_batches)
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        (**self).upsert_mfg_batch(mfg_batch)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batch(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batches(mfg_batch_ids, service_id)
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        (**self).list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        (**self).iter_mfg_batches(service_id, filters, page_size)
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        (**self).list_mfg_batches_after(service_id, filters, cursor, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        (**self).explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_owners(service_id, offset, limit)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        (**self).count_mfg_batches(service_id, filters)
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        (**self).mfg_batch_exists(mfg_batch_id, service_id)
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).search_mfg_batches_by_property(property_name, value, service_id)
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_ancestry(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_descendants(mfg_batch_id, service_id)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_history(mfg_batch_id, service_id)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        (**self).get_property_value_history(mfg_batch_id, property_name, service_id)
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).list_mfg_batches_updated_since(since, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        (**self).count_mfg_batch_version_rows(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_properties(mfg_batch_id, property_names, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        (**self).mfg_batch_visible(mfg_batch_id, visibility, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).share_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).unshare_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_shares(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        (**self).prune_mfg_batch_history(before_commit_num, dry_run)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_annotations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_test_result(test_result)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_test_results(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).replace_mfg_batch_duplicates(service_id, duplicates)
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_duplicates(service_id, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        (**self).merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_aliases(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        (**self).verify_mfg_batch_audit_log()
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        (**self).verify_mfg_batch_checksums()
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_anchors(service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_record_hashes(commit_num, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_allocations(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        (**self).get_mfg_batch_availability(mfg_batch_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).add_mfg_batch_recalls(recalls)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        (**self).close_mfg_batch_recall(recall_id, closed_at, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        (**self).list_mfg_batch_recalls(service_id, filters, offset, limit)
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        (**self).search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        (**self).explain_search_mfg_batches_text(query, service_id, offset, limit)
+    }
+
+    fn update_mfg_batch(
+        &self,
         mfg_batch_id: &str,
         service_id: Option<&str>,
         current_commit_num: i64,
@@ -612,3 +2927,158 @@ where
         (**self).delete_mfg_batch(address, current_commit_num)
     }
 }
+
+/// An mfg_batch store that can be cloned behind a trait object, so one store (and its connection
+/// pool) can be handed to several owners, such as actix workers, rather than re-created for each
+pub trait CloneBoxMfgBatchStore: MfgBatchStore + Send + Sync {
+    /// Clones this store into a new boxed trait object; clones share the same connection pool
+    fn clone_boxed(&self) -> Box<dyn CloneBoxMfgBatchStore>;
+
+    /// Moves this store into a boxed trait object
+    fn into_boxed(self) -> Box<dyn CloneBoxMfgBatchStore>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+impl<S> CloneBoxMfgBatchStore for S
+where
+    S: MfgBatchStore + Clone + Send + Sync + 'static,
+{
+    fn clone_boxed(&self) -> Box<dyn CloneBoxMfgBatchStore> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CloneBoxMfgBatchStore> {
+    fn clone(&self) -> Box<dyn CloneBoxMfgBatchStore> {
+        (**self).clone_boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::MAX_COMMIT_NUM;
+
+    fn property_value() -> PropertyValueBuilder {
+        PropertyValueBuilder::default()
+            .with_mfg_batch_id("batch1".into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_property_name("weight".into())
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+    }
+
+    /// Verify that a typed payload sets the matching data type and field, replacing a value of
+    /// another type, and reads back from either spelling of the data type
+    #[test]
+    fn test_typed_value() {
+        let value = property_value()
+            .with_string_value(Some("heavy".into()))
+            .with_typed_value(TypedValue::Number {
+                value: 1250,
+                exponent: Some(-2),
+            })
+            .build()
+            .expect("Failed to build property value");
+
+        assert_eq!(value.data_type(), "Number");
+        assert_eq!(value.number_value(), Some(1250));
+        assert_eq!(value.string_value(), None);
+        assert!(matches!(
+            value.typed_value(),
+            Some(TypedValue::Number {
+                value: 1250,
+                exponent: None
+            })
+        ));
+        assert_eq!(
+            value.number(&PropertyDefinition {
+                start_commit_num: 1,
+                end_commit_num: MAX_COMMIT_NUM,
+                name: "weight".into(),
+                schema_name: "batch".into(),
+                data_type: "Number".into(),
+                required: false,
+                description: "".into(),
+                number_exponent: -2,
+                enum_options: vec![],
+                struct_properties: vec![],
+                service_id: None,
+            }),
+            Some(NumberValue::new(1250, -2))
+        );
+
+        let value = property_value()
+            .with_data_type("LAT_LONG".into())
+            .with_lat_long_value(Some(LatLongValue {
+                latitude: 44_977_753,
+                longitude: -93_265_015,
+            }))
+            .build()
+            .expect("Failed to build property value");
+        assert!(matches!(
+            value.typed_value(),
+            Some(TypedValue::LatLong(LatLongValue {
+                latitude: 44_977_753,
+                longitude: -93_265_015,
+            }))
+        ));
+    }
+
+    /// Verify that the builder names the field it rejects, so callers can report it
+    #[test]
+    fn test_builder_error_field() {
+        let err = MfgBatchBuilder::default()
+            .with_mfg_batch_id("batch1".into())
+            .with_mfg_batch_address("11bb0e01batch1".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .build()
+            .expect_err("Built a mfg_batch without an owner");
+        assert!(matches!(
+            err,
+            MfgBatchBuilderError::MissingField(MfgBatchField::Owner)
+        ));
+        assert_eq!(err.to_string(), "failed to build mfg_batch: missing owner");
+
+        let err = property_value()
+            .with_data_type("Number".into())
+            .with_start_commit_number(5)
+            .with_end_commit_number(5)
+            .build()
+            .expect_err("Built a property value with an empty commit range");
+        assert_eq!(err.field(), Some(MfgBatchField::StartCommitNum));
+        assert_eq!(
+            err.to_string(),
+            "failed to build mfg_batch: invalid start_commit_num: must be less than \
+             end_commit_num (5)"
+        );
+    }
+
+    /// Verify that the builder rejects a value populated in a field other than the one its data
+    /// type names, while values of unrecognized data types are left unchecked
+    #[test]
+    fn test_typed_value_data_type_mismatch() {
+        assert!(matches!(
+            property_value()
+                .with_data_type("Boolean".into())
+                .with_string_value(Some("true".into()))
+                .build(),
+            Err(MfgBatchBuilderError::InvalidValue {
+                field: MfgBatchField::DataType,
+                ..
+            })
+        ));
+
+        let value = property_value()
+            .with_data_type("Custom".into())
+            .with_string_value(Some("true".into()))
+            .build()
+            .expect("Failed to build property value");
+        assert!(value.typed_value().is_none());
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2018-2021 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pseudonymization of mfg_batches served to readers outside the owning organization.
+//!
+//! Owner ids and the values of internal properties are replaced with a keyed hash of the
+//! original. The same value always gets the same pseudonym under the same key, so records can
+//! still be grouped and compared without revealing who owns them.
+
+use std::collections::HashMap;
+
+use crypto::{hmac::Hmac, mac::Mac, sha2::Sha256};
+
+use super::{MfgBatch, PropertyValue};
+
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+    internal_properties: Vec<String>,
+}
+
+impl Pseudonymizer {
+    /// Creates a pseudonymizer
+    ///
+    /// # Arguments
+    ///
+    ///  * `key` - The secret the pseudonyms are keyed with
+    ///  * `internal_properties` - Names of the properties whose values are pseudonymized
+    pub fn new(key: Vec<u8>, internal_properties: Vec<String>) -> Self {
+        Self {
+            key,
+            internal_properties,
+        }
+    }
+
+    /// Returns the pseudonym for a value
+    pub fn pseudonym(&self, value: &str) -> String {
+        let mut mac = Hmac::new(Sha256::new(), &self.key);
+        mac.input(value.as_bytes());
+        mac.result()
+            .code()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Replaces the owner and the values of internal properties with their pseudonyms.
+    ///
+    /// String values are replaced with their pseudonym. Other values of internal properties
+    /// cannot hold a pseudonym, so they are removed.
+    pub fn pseudonymize(&self, mut mfg_batch: MfgBatch) -> MfgBatch {
+        mfg_batch.owner = self.pseudonym(&mfg_batch.owner);
+        mfg_batch.properties = mfg_batch
+            .properties
+            .into_iter()
+            .map(|property| self.pseudonymize_property(property))
+            .collect();
+        mfg_batch
+    }
+
+    fn pseudonymize_property(&self, mut property: PropertyValue) -> PropertyValue {
+        if !self.internal_properties.contains(&property.property_name) {
+            return property;
+        }
+
+        property.string_value = property
+            .string_value
+            .as_deref()
+            .map(|value| self.pseudonym(value));
+        property.bytes_value = None;
+        property.boolean_value = None;
+        property.number_value = None;
+        property.enum_value = None;
+        property.lat_long_value = None;
+        property.struct_values = Vec::new();
+        property
+    }
+
+    /// Returns the values a mfg_batch's pseudonyms were made from, keyed by pseudonym
+    pub fn mapping(&self, mfg_batch: &MfgBatch) -> HashMap<String, String> {
+        let mut mapping = HashMap::new();
+        mapping.insert(self.pseudonym(&mfg_batch.owner), mfg_batch.owner.clone());
+
+        for property in &mfg_batch.properties {
+            if !self.internal_properties.contains(&property.property_name) {
+                continue;
+            }
+
+            if let Some(value) = &property.string_value {
+                mapping.insert(self.pseudonym(value), value.clone());
+            }
+        }
+
+        mapping
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::{MfgBatchBuilder, PropertyValueBuilder};
+
+    fn mfg_batch(owner: &str) -> MfgBatch {
+        let property = |name: &str, value: &str| {
+            PropertyValueBuilder::default()
+                .with_mfg_batch_id("batch-1".to_string())
+                .with_mfg_batch_address("11bb0e01batch1".to_string())
+                .with_property_name(name.to_string())
+                .with_data_type("String".to_string())
+                .with_string_value(Some(value.to_string()))
+                .with_start_commit_number(1)
+                .with_end_commit_number(i64::MAX)
+                .build()
+                .expect("Failed to build property")
+        };
+
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id("batch-1".to_string())
+            .with_mfg_batch_address("11bb0e01batch1".to_string())
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner(owner.to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(vec![
+                property("plant_line", "line-7"),
+                property("color", "red"),
+            ])
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    /// Verify that owners and internal property values are replaced with stable, keyed
+    /// pseudonyms that the mapping resolves, and that other properties are left alone
+    #[test]
+    fn test_pseudonymize() {
+        let pseudonymizer = Pseudonymizer::new(b"key-1".to_vec(), vec!["plant_line".to_string()]);
+
+        let pseudonymized = pseudonymizer.pseudonymize(mfg_batch("org-1"));
+        let owner = pseudonymized.owner().to_string();
+        assert_ne!(owner, "org-1");
+        assert_eq!(
+            owner,
+            pseudonymizer.pseudonymize(mfg_batch("org-1")).owner()
+        );
+        assert_ne!(
+            owner,
+            pseudonymizer.pseudonymize(mfg_batch("org-2")).owner()
+        );
+        assert_ne!(
+            owner,
+            Pseudonymizer::new(b"key-2".to_vec(), vec![])
+                .pseudonymize(mfg_batch("org-1"))
+                .owner()
+        );
+
+        let properties = pseudonymized.properties();
+        let plant_line = properties[0].string_value().unwrap().to_string();
+        assert_ne!(plant_line, "line-7");
+        assert_eq!(properties[1].string_value(), Some("red"));
+
+        let mapping = pseudonymizer.mapping(&mfg_batch("org-1"));
+        assert_eq!(mapping.get(&owner).map(String::as_str), Some("org-1"));
+        assert_eq!(mapping.get(&plant_line).map(String::as_str), Some("line-7"));
+        assert_eq!(mapping.len(), 2);
+    }
+}
@@ -0,0 +1,860 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retrying of mfg_batch store operations that fail for a transient reason.
+//!
+//! A store reports a `ResourceTemporarilyUnavailableError` when it cannot get a connection from
+//! its pool, before the operation has reached the database, so the operation can be run again
+//! as is. Every other error is returned at once.
+
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "mfg-batch-audit-log")]
+use super::AuditLogDiscrepancy;
+#[cfg(feature = "mfg-batch-checksums")]
+use super::ChecksumDiscrepancy;
+#[cfg(feature = "mfg-batch-merge")]
+use super::MfgBatchAlias;
+#[cfg(feature = "mfg-batch-anchors")]
+use super::MfgBatchAnchor;
+#[cfg(feature = "mfg-batch-annotations")]
+use super::MfgBatchAnnotation;
+#[cfg(feature = "mfg-batch-keyset-paging")]
+use super::MfgBatchCursor;
+#[cfg(feature = "mfg-batch-duplicates")]
+use super::MfgBatchDuplicate;
+#[cfg(feature = "mfg-batch-test-results")]
+use super::MfgBatchTestResult;
+#[cfg(feature = "mfg-batch-explain")]
+use super::QueryPlan;
+#[cfg(feature = "mfg-batch-visibility")]
+use super::Visibility;
+use super::{
+    ListMfgBatchFilters, MfgBatch, MfgBatchIter, MfgBatchList, MfgBatchOwner, MfgBatchStore,
+    MfgBatchStoreError, PropertySearchValue, PropertyValue, UpsertMfgBatchOutcome,
+};
+#[cfg(feature = "mfg-batch-quality-scores")]
+use super::{ListMfgBatchQualityScoreFilters, MfgBatchQualityScore};
+#[cfg(feature = "mfg-batch-recalls")]
+use super::{ListMfgBatchRecallFilters, MfgBatchRecall};
+#[cfg(feature = "mfg-batch-allocations")]
+use super::{MfgBatchAllocation, MfgBatchAvailability};
+#[cfg(feature = "mfg-batch-row-counts")]
+use super::MfgBatchVersionRows;
+#[cfg(feature = "mfg-batch-pruning")]
+use super::PrunedMfgBatchHistory;
+
+/// How often and how long apart a failed store operation is tried again
+///
+/// The delay before each retry grows exponentially from `base_backoff` and is capped at
+/// `max_backoff`; a random amount of that delay is used so that several callers failing at once
+/// do not retry in lockstep. A retry duration hint given with the error is waited at least.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a retry policy
+    ///
+    /// # Arguments
+    ///
+    ///  * `max_attempts` - The number of times an operation is tried in all; 1 disables retries
+    ///  * `base_backoff` - The delay the first retry is jittered within
+    ///  * `max_backoff` - The longest delay between two attempts
+    pub fn new(max_attempts: u32, base_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Returns the number of times an operation is tried in all
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay the first retry is jittered within
+    pub fn base_backoff(&self) -> Duration {
+        self.base_backoff
+    }
+
+    /// Returns the longest delay between two attempts
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Returns the delay to wait before the given retry, starting at 0
+    fn backoff(&self, retry: u32, hint: Option<Duration>) -> Duration {
+        let ceiling = self
+            .base_backoff
+            .checked_mul(2u32.saturating_pow(retry))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        let ceiling_millis = ceiling.as_millis() as u64;
+        let delay = if ceiling_millis == 0 {
+            ceiling
+        } else {
+            Duration::from_millis(jitter_seed() % (ceiling_millis + 1))
+        };
+
+        match hint {
+            Some(hint) => delay.max(hint),
+            None => delay,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(3, Duration::from_millis(50), Duration::from_secs(2))
+    }
+}
+
+/// A mfg_batch store that tries operations again when the store it wraps fails transiently
+///
+/// Pages read by `iter_mfg_batches` are loaded by the wrapped store and are not retried.
+#[derive(Clone)]
+pub struct RetryingMfgBatchStore<S> {
+    inner: S,
+    policy: RetryPolicy,
+}
+
+impl<S: MfgBatchStore> RetryingMfgBatchStore<S> {
+    /// Wraps a store, retrying its operations with the default policy
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Sets the policy failed operations are retried with
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn retry<T, F>(&self, operation: &str, f: F) -> Result<T, MfgBatchStoreError>
+    where
+        F: Fn() -> Result<T, MfgBatchStoreError>,
+    {
+        retry(&self.policy, operation, f)
+    }
+}
+
+/// Runs `f` until it succeeds, fails with an error that is not transient or has been tried
+/// as many times as the policy allows
+fn retry<T, F>(policy: &RetryPolicy, operation: &str, f: F) -> Result<T, MfgBatchStoreError>
+where
+    F: Fn() -> Result<T, MfgBatchStoreError>,
+{
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(MfgBatchStoreError::ResourceTemporarilyUnavailableError(err))
+                if attempt < policy.max_attempts =>
+            {
+                let delay = policy.backoff(attempt - 1, err.retry_duration_hint());
+                warn!(
+                    "Retrying {} in {:?} (attempt {} of {}): {}",
+                    operation, delay, attempt, policy.max_attempts, err
+                );
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns a pseudo-random value used to spread out retry delays
+fn jitter_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos() as u64)
+        .unwrap_or(0);
+
+    // xorshift the clock's nanoseconds so consecutive calls are not correlated
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
+
+impl<S: MfgBatchStore> MfgBatchStore for RetryingMfgBatchStore<S> {
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batch", || {
+            self.inner.add_mfg_batch(mfg_batch.clone())
+        })
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batches", || {
+            self.inner.add_mfg_batches(mfg_batches.clone())
+        })
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        self.retry("upsert_mfg_batch", || {
+            self.inner.upsert_mfg_batch(mfg_batch.clone())
+        })
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch", || {
+            self.inner.get_mfg_batch(mfg_batch_id, service_id)
+        })
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.retry("get_mfg_batches", || {
+            self.inner.get_mfg_batches(mfg_batch_ids, service_id)
+        })
+    }
+
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        self.retry("list_mfg_batches", || {
+            self.inner
+                .list_mfg_batches(service_id, filters, offset, limit)
+        })
+    }
+
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        self.inner.iter_mfg_batches(service_id, filters, page_size)
+    }
+
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        self.retry("list_mfg_batches_after", || {
+            self.inner
+                .list_mfg_batches_after(service_id, filters, cursor, limit)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        self.retry("explain_list_mfg_batches", || {
+            self.inner
+                .explain_list_mfg_batches(service_id, filters, offset, limit)
+        })
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_owners", || {
+            self.inner.list_mfg_batch_owners(service_id, offset, limit)
+        })
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        self.retry("count_mfg_batches", || {
+            self.inner.count_mfg_batches(service_id, filters)
+        })
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        self.retry("mfg_batch_exists", || {
+            self.inner.mfg_batch_exists(mfg_batch_id, service_id)
+        })
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.retry("search_mfg_batches_by_property", || {
+            self.inner
+                .search_mfg_batches_by_property(property_name, value, service_id)
+        })
+    }
+
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch_ancestry", || {
+            self.inner.get_mfg_batch_ancestry(mfg_batch_id, service_id)
+        })
+    }
+
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch_descendants", || {
+            self.inner
+                .get_mfg_batch_descendants(mfg_batch_id, service_id)
+        })
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch_at_commit", || {
+            self.inner
+                .get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)
+        })
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_history", || {
+            self.inner.list_mfg_batch_history(mfg_batch_id, service_id)
+        })
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.retry("get_property_value_history", || {
+            self.inner
+                .get_property_value_history(mfg_batch_id, property_name, service_id)
+        })
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.retry("list_mfg_batches_updated_since", || {
+            self.inner.list_mfg_batches_updated_since(since, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        self.retry("count_mfg_batch_version_rows", || {
+            self.inner
+                .count_mfg_batch_version_rows(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch_properties", || {
+            self.inner
+                .get_mfg_batch_properties(mfg_batch_id, property_names, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        self.retry("mfg_batch_visible", || {
+            self.inner
+                .mfg_batch_visible(mfg_batch_id, visibility, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("share_mfg_batch", || {
+            self.inner.share_mfg_batch(mfg_batch_id, org_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("unshare_mfg_batch", || {
+            self.inner
+                .unshare_mfg_batch(mfg_batch_id, org_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_shares", || {
+            self.inner.list_mfg_batch_shares(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("create_mfg_batch_archive_partition", || {
+            self.inner
+                .create_mfg_batch_archive_partition(from_commit_num, to_commit_num)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        self.retry("prune_mfg_batch_history", || {
+            self.inner
+                .prune_mfg_batch_history(before_commit_num, dry_run)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batch_annotation", || {
+            self.inner.add_mfg_batch_annotation(annotation.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_annotations", || {
+            self.inner
+                .list_mfg_batch_annotations(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batch_test_result", || {
+            self.inner.add_mfg_batch_test_result(test_result.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_test_results", || {
+            self.inner
+                .list_mfg_batch_test_results(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("put_mfg_batch_quality_score", || {
+            self.inner.put_mfg_batch_quality_score(score.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_quality_scores", || {
+            self.inner
+                .list_mfg_batch_quality_scores(service_id, filters, offset, limit)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("replace_mfg_batch_duplicates", || {
+            self.inner
+                .replace_mfg_batch_duplicates(service_id, duplicates.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_duplicates", || {
+            self.inner
+                .list_mfg_batch_duplicates(service_id, offset, limit)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        self.retry("merge_mfg_batches", || {
+            self.inner.merge_mfg_batches(alias.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_aliases", || {
+            self.inner.list_mfg_batch_aliases(service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        self.retry("verify_mfg_batch_audit_log", || {
+            self.inner.verify_mfg_batch_audit_log()
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        self.retry("verify_mfg_batch_checksums", || {
+            self.inner.verify_mfg_batch_checksums()
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batch_anchor", || {
+            self.inner.add_mfg_batch_anchor(anchor.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_anchors", || {
+            self.inner.list_mfg_batch_anchors(service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_record_hashes", || {
+            self.inner
+                .list_mfg_batch_record_hashes(commit_num, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("put_mfg_batch_allocation", || {
+            self.inner.put_mfg_batch_allocation(allocation.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_allocations", || {
+            self.inner
+                .list_mfg_batch_allocations(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        self.retry("get_mfg_batch_availability", || {
+            self.inner
+                .get_mfg_batch_availability(mfg_batch_id, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("add_mfg_batch_recalls", || {
+            self.inner.add_mfg_batch_recalls(recalls.clone())
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("close_mfg_batch_recall", || {
+            self.inner
+                .close_mfg_batch_recall(recall_id, closed_at, service_id)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        self.retry("list_mfg_batch_recalls", || {
+            self.inner
+                .list_mfg_batch_recalls(service_id, filters, offset, limit)
+        })
+    }
+
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        self.retry("search_mfg_batches_text", || {
+            self.inner
+                .search_mfg_batches_text(query, service_id, offset, limit)
+        })
+    }
+
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        self.retry("explain_search_mfg_batches_text", || {
+            self.inner
+                .explain_search_mfg_batches_text(query, service_id, offset, limit)
+        })
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("update_mfg_batch", || {
+            self.inner
+                .update_mfg_batch(mfg_batch_id, service_id, current_commit_num)
+        })
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        self.retry("delete_mfg_batch", || {
+            self.inner.delete_mfg_batch(address, current_commit_num)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use crate::error::{InternalError, ResourceTemporarilyUnavailableError};
+
+    fn unavailable() -> MfgBatchStoreError {
+        MfgBatchStoreError::ResourceTemporarilyUnavailableError(
+            ResourceTemporarilyUnavailableError::from_source(Box::new(
+                InternalError::with_message("pool timed out".to_string()),
+            )),
+        )
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            max_attempts,
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+        )
+    }
+
+    /// Verify that transient errors are retried until the operation succeeds
+    #[test]
+    fn test_retry_transient_error() {
+        let attempts = Cell::new(0);
+        let result = retry(&policy(3), "test", || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(unavailable())
+            } else {
+                Ok(attempts.get())
+            }
+        });
+
+        assert_eq!(result.expect("Operation was not retried"), 3);
+    }
+
+    /// Verify that the last transient error is returned once every attempt has been used
+    #[test]
+    fn test_retry_gives_up() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry(&policy(2), "test", || {
+            attempts.set(attempts.get() + 1);
+            Err(unavailable())
+        });
+
+        assert!(matches!(
+            result,
+            Err(MfgBatchStoreError::ResourceTemporarilyUnavailableError(_))
+        ));
+        assert_eq!(attempts.get(), 2);
+    }
+
+    /// Verify that errors which are not transient are returned without retrying
+    #[test]
+    fn test_retry_other_error() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry(&policy(3), "test", || {
+            attempts.set(attempts.get() + 1);
+            Err(MfgBatchStoreError::InternalError(
+                InternalError::with_message("broken".to_string()),
+            ))
+        });
+
+        assert!(matches!(result, Err(MfgBatchStoreError::InternalError(_))));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    /// Verify that the backoff stays within its exponential ceiling, the cap and the hint
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(300));
+
+        assert!(policy.backoff(0, None) <= Duration::from_millis(100));
+        assert!(policy.backoff(1, None) <= Duration::from_millis(200));
+        assert!(policy.backoff(4, None) <= Duration::from_millis(300));
+        assert!(policy.backoff(0, Some(Duration::from_secs(1))) >= Duration::from_secs(1));
+        assert_eq!(
+            RetryPolicy::new(0, Duration::ZERO, Duration::ZERO).max_attempts(),
+            1
+        );
+    }
+}
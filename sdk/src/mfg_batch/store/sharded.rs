@@ -0,0 +1,1208 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partitioning of mfg_batches across several stores by owner.
+//!
+//! Each mfg_batch is written to the shard picked by a hash of its owner's org id, so that every
+//! mfg_batch of an organization lives in the same database. Lists filtered by owner are sent to
+//! that shard alone. Reads by mfg_batch id and lists across owners are sent to every shard and
+//! their results gathered.
+//!
+//! The shard an owner maps to depends on the number of shards, so shards cannot be added,
+//! removed or reordered without moving the mfg_batches they hold.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+
+use crate::error::InvalidArgumentError;
+use crate::paging::Paging;
+
+#[cfg(feature = "mfg-batch-audit-log")]
+use super::AuditLogDiscrepancy;
+#[cfg(feature = "mfg-batch-checksums")]
+use super::ChecksumDiscrepancy;
+#[cfg(feature = "mfg-batch-merge")]
+use super::MfgBatchAlias;
+#[cfg(feature = "mfg-batch-anchors")]
+use super::MfgBatchAnchor;
+#[cfg(feature = "mfg-batch-annotations")]
+use super::MfgBatchAnnotation;
+#[cfg(feature = "mfg-batch-keyset-paging")]
+use super::MfgBatchCursor;
+#[cfg(feature = "mfg-batch-duplicates")]
+use super::MfgBatchDuplicate;
+#[cfg(feature = "mfg-batch-test-results")]
+use super::MfgBatchTestResult;
+#[cfg(feature = "mfg-batch-explain")]
+use super::QueryPlan;
+#[cfg(feature = "mfg-batch-visibility")]
+use super::Visibility;
+use super::{
+    ListMfgBatchFilters, MfgBatch, MfgBatchIter, MfgBatchList, MfgBatchOwner, MfgBatchStore,
+    MfgBatchStoreError, PropertySearchValue, PropertyValue, UpsertMfgBatchOutcome,
+};
+#[cfg(feature = "mfg-batch-quality-scores")]
+use super::{ListMfgBatchQualityScoreFilters, MfgBatchQualityScore};
+#[cfg(feature = "mfg-batch-recalls")]
+use super::{ListMfgBatchRecallFilters, MfgBatchRecall};
+#[cfg(feature = "mfg-batch-allocations")]
+use super::{MfgBatchAllocation, MfgBatchAvailability};
+#[cfg(feature = "mfg-batch-row-counts")]
+use super::MfgBatchVersionRows;
+#[cfg(feature = "mfg-batch-pruning")]
+use super::PrunedMfgBatchHistory;
+
+/// A mfg_batch store that routes each mfg_batch to one of several stores by its owner
+pub struct ShardedMfgBatchStore<S> {
+    shards: Vec<S>,
+}
+
+impl<S: MfgBatchStore> ShardedMfgBatchStore<S> {
+    /// Creates a store that partitions mfg_batches across the given shards
+    ///
+    /// # Arguments
+    ///
+    ///  * `shards` - The stores to partition across, always given in the same order
+    pub fn new(shards: Vec<S>) -> Result<Self, MfgBatchStoreError> {
+        if shards.is_empty() {
+            return Err(MfgBatchStoreError::InvalidArgumentError(
+                InvalidArgumentError::new(
+                    "shards".to_string(),
+                    "at least one shard is required".to_string(),
+                ),
+            ));
+        }
+
+        Ok(Self { shards })
+    }
+
+    fn owner_shard(&self, owner: &str) -> usize {
+        shard_index(owner, self.shards.len())
+    }
+
+    /// Returns the index of the shard holding the current version of a mfg_batch, if any does
+    fn holding_shard(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<usize>, MfgBatchStoreError> {
+        for (index, shard) in self.shards.iter().enumerate() {
+            if shard.mfg_batch_exists(mfg_batch_id, service_id)? {
+                return Ok(Some(index));
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[cfg(any(
+        feature = "mfg-batch-allocations",
+        feature = "mfg-batch-duplicates",
+        feature = "mfg-batch-merge",
+        feature = "mfg-batch-quality-scores",
+        feature = "mfg-batch-visibility"
+    ))]
+    fn require_holding_shard(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<usize, MfgBatchStoreError> {
+        self.holding_shard(mfg_batch_id, service_id)?
+            .ok_or_else(|| MfgBatchStoreError::NotFoundError(format!("Mfg_batch {}", mfg_batch_id)))
+    }
+
+    /// Returns the index of the shard that held a mfg_batch as it stood at the given commit
+    #[cfg(any(
+        feature = "mfg-batch-annotations",
+        feature = "mfg-batch-recalls",
+        feature = "mfg-batch-test-results"
+    ))]
+    fn shard_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<usize, MfgBatchStoreError> {
+        for (index, shard) in self.shards.iter().enumerate() {
+            if shard
+                .get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id)?
+                .is_some()
+            {
+                return Ok(index);
+            }
+        }
+
+        Err(MfgBatchStoreError::NotFoundError(format!(
+            "Mfg_batch {} at commit {}",
+            mfg_batch_id, commit_num
+        )))
+    }
+
+    /// Ends the current version of a mfg_batch on every shard but `shard`. A mfg_batch whose
+    /// owner changed is written to its new owner's shard, and its former version would
+    /// otherwise stay current on the former owner's shard.
+    fn end_on_other_shards(
+        &self,
+        shard: usize,
+        mfg_batch: &MfgBatch,
+    ) -> Result<(), MfgBatchStoreError> {
+        for (index, other) in self.shards.iter().enumerate() {
+            if index != shard
+                && other.mfg_batch_exists(mfg_batch.mfg_batch_id(), mfg_batch.service_id())?
+            {
+                other.delete_mfg_batch(
+                    mfg_batch.mfg_batch_address(),
+                    *mfg_batch.start_commit_num(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a read on every shard and concatenates the results, in shard order
+    fn gather<T, F>(&self, mut read: F) -> Result<Vec<T>, MfgBatchStoreError>
+    where
+        F: FnMut(&S) -> Result<Vec<T>, MfgBatchStoreError>,
+    {
+        let mut gathered = Vec::new();
+        for shard in &self.shards {
+            gathered.extend(read(shard)?);
+        }
+
+        Ok(gathered)
+    }
+
+    /// Runs a read on every shard, returning the first result found
+    fn find<T, F>(&self, mut read: F) -> Result<Option<T>, MfgBatchStoreError>
+    where
+        F: FnMut(&S) -> Result<Option<T>, MfgBatchStoreError>,
+    {
+        for shard in &self.shards {
+            if let Some(found) = read(shard)? {
+                return Ok(Some(found));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<S: MfgBatchStore> MfgBatchStore for ShardedMfgBatchStore<S> {
+    fn add_mfg_batch(&self, mfg_batch: MfgBatch) -> Result<(), MfgBatchStoreError> {
+        let shard = self.owner_shard(mfg_batch.owner());
+        self.shards[shard].add_mfg_batch(mfg_batch.clone())?;
+        self.end_on_other_shards(shard, &mfg_batch)
+    }
+
+    fn add_mfg_batches(&self, mfg_batches: Vec<MfgBatch>) -> Result<(), MfgBatchStoreError> {
+        // The latest version of each mfg_batch decides the shard it stays current on
+        let mut latest: HashMap<(String, Option<String>), (usize, MfgBatch)> = HashMap::new();
+        let mut by_shard: Vec<Vec<MfgBatch>> = self.shards.iter().map(|_| Vec::new()).collect();
+
+        for mfg_batch in mfg_batches {
+            let shard = self.owner_shard(mfg_batch.owner());
+            latest.insert(
+                (
+                    mfg_batch.mfg_batch_id().to_string(),
+                    mfg_batch.service_id().map(String::from),
+                ),
+                (shard, mfg_batch.clone()),
+            );
+            by_shard[shard].push(mfg_batch);
+        }
+
+        for (shard, mfg_batches) in self.shards.iter().zip(by_shard) {
+            if !mfg_batches.is_empty() {
+                shard.add_mfg_batches(mfg_batches)?;
+            }
+        }
+
+        for (shard, mfg_batch) in latest.values() {
+            self.end_on_other_shards(*shard, mfg_batch)?;
+        }
+
+        Ok(())
+    }
+
+    fn upsert_mfg_batch(
+        &self,
+        mfg_batch: MfgBatch,
+    ) -> Result<UpsertMfgBatchOutcome, MfgBatchStoreError> {
+        let shard = self.owner_shard(mfg_batch.owner());
+
+        // A current version on another shard means the owner changed, which the owner's
+        // shard cannot see, so it is compared here
+        for (index, other) in self.shards.iter().enumerate() {
+            if index == shard {
+                continue;
+            }
+
+            if let Some(current) =
+                other.get_mfg_batch(mfg_batch.mfg_batch_id(), mfg_batch.service_id())?
+            {
+                if current.start_commit_num() >= mfg_batch.start_commit_num() {
+                    return Ok(UpsertMfgBatchOutcome::Conflict {
+                        current_commit_num: *current.start_commit_num(),
+                    });
+                }
+
+                self.shards[shard].add_mfg_batch(mfg_batch.clone())?;
+                self.end_on_other_shards(shard, &mfg_batch)?;
+                return Ok(UpsertMfgBatchOutcome::Replaced {
+                    prior_commit_num: *current.start_commit_num(),
+                });
+            }
+        }
+
+        self.shards[shard].upsert_mfg_batch(mfg_batch)
+    }
+
+    fn get_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.find(|shard| shard.get_mfg_batch(mfg_batch_id, service_id))
+    }
+
+    fn get_mfg_batches(
+        &self,
+        mfg_batch_ids: &[&str],
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let mut mfg_batches =
+            self.gather(|shard| shard.get_mfg_batches(mfg_batch_ids, service_id))?;
+        // Mfg_batches fetched by the id of one merged into them sort after the rest
+        mfg_batches.sort_by_key(|mfg_batch| {
+            mfg_batch_ids
+                .iter()
+                .position(|id| *id == mfg_batch.mfg_batch_id())
+                .unwrap_or(mfg_batch_ids.len())
+        });
+
+        Ok(mfg_batches)
+    }
+
+    /// Lists the mfg_batches of the owner's shard when filtered by owner. Otherwise the
+    /// mfg_batches of each shard are counted and the page is taken from the shards in turn.
+    fn list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<MfgBatchList, MfgBatchStoreError> {
+        if let Some(owner) = &filters.owner {
+            return self.shards[self.owner_shard(owner)]
+                .list_mfg_batches(service_id, filters, offset, limit);
+        }
+
+        let counts = self
+            .shards
+            .iter()
+            .map(|shard| shard.count_mfg_batches(service_id, filters))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut data = Vec::new();
+        for (shard, (shard_offset, shard_limit)) in
+            self.shards.iter().zip(page_windows(&counts, offset, limit))
+        {
+            if shard_limit > 0 {
+                data.extend(
+                    shard
+                        .list_mfg_batches(service_id, filters, shard_offset, shard_limit)?
+                        .data(),
+                );
+            }
+        }
+
+        Ok(MfgBatchList::new(
+            data,
+            Paging::new(offset, limit, counts.iter().sum()),
+        ))
+    }
+
+    /// Merges the iterators of every shard, so mfg_batches are still returned in ID order
+    fn iter_mfg_batches<'a>(
+        &'a self,
+        service_id: Option<&'a str>,
+        filters: &'a ListMfgBatchFilters,
+        page_size: i64,
+    ) -> MfgBatchIter<'a> {
+        if let Some(owner) = &filters.owner {
+            return self.shards[self.owner_shard(owner)]
+                .iter_mfg_batches(service_id, filters, page_size);
+        }
+
+        let mut iters: Vec<Peekable<MfgBatchIter<'a>>> = self
+            .shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .iter_mfg_batches(service_id, filters, page_size)
+                    .peekable()
+            })
+            .collect();
+
+        MfgBatchIter::new(page_size, move |_| {
+            let mut page = Vec::new();
+            while (page.len() as i64) < page_size.max(1) {
+                match next_in_id_order(&mut iters) {
+                    Some(Ok(mfg_batch)) => page.push(mfg_batch),
+                    Some(Err(err)) => return Err(err),
+                    None => break,
+                }
+            }
+
+            Ok(page)
+        })
+    }
+
+    /// Reads the next page from every shard and keeps the lowest cursors. Row IDs only
+    /// order mfg_batches sharing an ID within a shard, which is enough for the cursor to be
+    /// unambiguous across the merged page
+    #[cfg(feature = "mfg-batch-keyset-paging")]
+    fn list_mfg_batches_after(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        cursor: Option<&MfgBatchCursor>,
+        limit: i64,
+    ) -> Result<Vec<(MfgBatchCursor, MfgBatch)>, MfgBatchStoreError> {
+        if let Some(owner) = &filters.owner {
+            return self.shards[self.owner_shard(owner)]
+                .list_mfg_batches_after(service_id, filters, cursor, limit);
+        }
+
+        let mut page = Vec::new();
+        for shard in &self.shards {
+            page.extend(shard.list_mfg_batches_after(service_id, filters, cursor, limit)?);
+        }
+        page.sort_by(|(a, _), (b, _)| a.cmp(b));
+        page.truncate(limit.max(0) as usize);
+
+        Ok(page)
+    }
+
+    /// Explains the list on the owner's shard when filtered by owner, and otherwise on the
+    /// first shard, as every shard runs the same query
+    #[cfg(feature = "mfg-batch-explain")]
+    fn explain_list_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        let shard = filters
+            .owner
+            .as_deref()
+            .map(|owner| self.owner_shard(owner))
+            .unwrap_or(0);
+
+        self.shards[shard].explain_list_mfg_batches(service_id, filters, offset, limit)
+    }
+
+    fn list_mfg_batch_owners(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchOwner>, MfgBatchStoreError> {
+        let filters = ListMfgBatchFilters::default();
+        let counts = self
+            .shards
+            .iter()
+            .map(|shard| shard.count_mfg_batches(service_id, &filters))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut owners = Vec::new();
+        for (shard, (shard_offset, shard_limit)) in
+            self.shards.iter().zip(page_windows(&counts, offset, limit))
+        {
+            if shard_limit > 0 {
+                owners.extend(shard.list_mfg_batch_owners(
+                    service_id,
+                    shard_offset,
+                    shard_limit,
+                )?);
+            }
+        }
+
+        Ok(owners)
+    }
+
+    fn count_mfg_batches(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchFilters,
+    ) -> Result<i64, MfgBatchStoreError> {
+        if let Some(owner) = &filters.owner {
+            return self.shards[self.owner_shard(owner)].count_mfg_batches(service_id, filters);
+        }
+
+        self.shards.iter().try_fold(0, |total, shard| {
+            Ok(total + shard.count_mfg_batches(service_id, filters)?)
+        })
+    }
+
+    fn mfg_batch_exists(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        Ok(self.holding_shard(mfg_batch_id, service_id)?.is_some())
+    }
+
+    fn search_mfg_batches_by_property(
+        &self,
+        property_name: &str,
+        value: &PropertySearchValue,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let mut mfg_batches = self.gather(|shard| {
+            shard.search_mfg_batches_by_property(property_name, value, service_id)
+        })?;
+        mfg_batches.sort_by(|a, b| a.mfg_batch_id().cmp(b.mfg_batch_id()));
+
+        Ok(mfg_batches)
+    }
+
+    /// Follows the parent links one mfg_batch at a time, as a mfg_batch's parents may be held
+    /// on other shards than its own
+    fn get_mfg_batch_ancestry(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        let mut ancestry = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        visited.insert(mfg_batch_id.to_string());
+
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(mfg_batch_id.to_string());
+
+        while let Some(current) = queue.pop_front() {
+            let parents = match self.get_mfg_batch(&current, service_id)? {
+                Some(mfg_batch) => mfg_batch.parent_batches().to_vec(),
+                None => continue,
+            };
+
+            for parent in parents {
+                if visited.insert(parent.clone()) {
+                    ancestry.push(parent.clone());
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        Ok(ancestry)
+    }
+
+    /// Asks every shard for the descendants of each mfg_batch found, as a mfg_batch's children
+    /// may be held on other shards than its own
+    fn get_mfg_batch_descendants(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        let mut descendants = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        visited.insert(mfg_batch_id.to_string());
+
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(mfg_batch_id.to_string());
+
+        while let Some(current) = queue.pop_front() {
+            let children =
+                self.gather(|shard| shard.get_mfg_batch_descendants(&current, service_id))?;
+
+            for child in children {
+                if visited.insert(child.clone()) {
+                    descendants.push(child.clone());
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        Ok(descendants)
+    }
+
+    fn get_mfg_batch_at_commit(
+        &self,
+        mfg_batch_id: &str,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatch>, MfgBatchStoreError> {
+        self.find(|shard| shard.get_mfg_batch_at_commit(mfg_batch_id, commit_num, service_id))
+    }
+
+    fn list_mfg_batch_history(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let mut history =
+            self.gather(|shard| shard.list_mfg_batch_history(mfg_batch_id, service_id))?;
+        history.sort_by_key(|mfg_batch| *mfg_batch.start_commit_num());
+
+        Ok(history)
+    }
+
+    fn get_property_value_history(
+        &self,
+        mfg_batch_id: &str,
+        property_name: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        let mut history = self.gather(|shard| {
+            shard.get_property_value_history(mfg_batch_id, property_name, service_id)
+        })?;
+        history.sort_by_key(|value| *value.start_commit_num());
+
+        Ok(history)
+    }
+
+    fn list_mfg_batches_updated_since(
+        &self,
+        since: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let mut mfg_batches =
+            self.gather(|shard| shard.list_mfg_batches_updated_since(since, service_id))?;
+        mfg_batches.sort_by(|a, b| {
+            (a.last_updated(), a.mfg_batch_id()).cmp(&(b.last_updated(), b.mfg_batch_id()))
+        });
+
+        Ok(mfg_batches)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn count_mfg_batch_version_rows(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchVersionRows>, MfgBatchStoreError> {
+        let mut versions =
+            self.gather(|shard| shard.count_mfg_batch_version_rows(mfg_batch_id, service_id))?;
+        versions.sort_by_key(|version| version.start_commit_num);
+
+        Ok(versions)
+    }
+
+    #[cfg(feature = "mfg-batch-row-counts")]
+    fn get_mfg_batch_properties(
+        &self,
+        mfg_batch_id: &str,
+        property_names: &[String],
+        service_id: Option<&str>,
+    ) -> Result<Vec<PropertyValue>, MfgBatchStoreError> {
+        match self.holding_shard(mfg_batch_id, service_id)? {
+            Some(shard) => self.shards[shard].get_mfg_batch_properties(
+                mfg_batch_id,
+                property_names,
+                service_id,
+            ),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn mfg_batch_visible(
+        &self,
+        mfg_batch_id: &str,
+        visibility: &Visibility,
+        service_id: Option<&str>,
+    ) -> Result<bool, MfgBatchStoreError> {
+        match self.holding_shard(mfg_batch_id, service_id)? {
+            Some(shard) => {
+                self.shards[shard].mfg_batch_visible(mfg_batch_id, visibility, service_id)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Shares are kept on the shard holding the mfg_batch
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn share_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shard = self.require_holding_shard(mfg_batch_id, service_id)?;
+        self.shards[shard].share_mfg_batch(mfg_batch_id, org_id, service_id)
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn unshare_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        org_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        for shard in &self.shards {
+            shard.unshare_mfg_batch(mfg_batch_id, org_id, service_id)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mfg-batch-visibility")]
+    fn list_mfg_batch_shares(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        match self.holding_shard(mfg_batch_id, service_id)? {
+            Some(shard) => self.shards[shard].list_mfg_batch_shares(mfg_batch_id, service_id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-partitioning")]
+    fn create_mfg_batch_archive_partition(
+        &self,
+        from_commit_num: i64,
+        to_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        for shard in &self.shards {
+            shard.create_mfg_batch_archive_partition(from_commit_num, to_commit_num)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mfg-batch-pruning")]
+    fn prune_mfg_batch_history(
+        &self,
+        before_commit_num: i64,
+        dry_run: bool,
+    ) -> Result<PrunedMfgBatchHistory, MfgBatchStoreError> {
+        let mut pruned = PrunedMfgBatchHistory::default();
+        for shard in &self.shards {
+            let shard_pruned = shard.prune_mfg_batch_history(before_commit_num, dry_run)?;
+            pruned.mfg_batches += shard_pruned.mfg_batches;
+            pruned.property_values += shard_pruned.property_values;
+            pruned.parents += shard_pruned.parents;
+            pruned.attachments += shard_pruned.attachments;
+        }
+
+        Ok(pruned)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn add_mfg_batch_annotation(
+        &self,
+        annotation: MfgBatchAnnotation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shard = self.shard_at_commit(
+            &annotation.mfg_batch_id,
+            annotation.commit_num,
+            annotation.service_id.as_deref(),
+        )?;
+
+        self.shards[shard].add_mfg_batch_annotation(annotation)
+    }
+
+    #[cfg(feature = "mfg-batch-annotations")]
+    fn list_mfg_batch_annotations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnnotation>, MfgBatchStoreError> {
+        let mut annotations =
+            self.gather(|shard| shard.list_mfg_batch_annotations(mfg_batch_id, service_id))?;
+        annotations.sort_by_key(|annotation| annotation.commit_num);
+
+        Ok(annotations)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn add_mfg_batch_test_result(
+        &self,
+        test_result: MfgBatchTestResult,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shard = self.shard_at_commit(
+            &test_result.mfg_batch_id,
+            test_result.commit_num,
+            test_result.service_id.as_deref(),
+        )?;
+
+        self.shards[shard].add_mfg_batch_test_result(test_result)
+    }
+
+    #[cfg(feature = "mfg-batch-test-results")]
+    fn list_mfg_batch_test_results(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchTestResult>, MfgBatchStoreError> {
+        let mut test_results =
+            self.gather(|shard| shard.list_mfg_batch_test_results(mfg_batch_id, service_id))?;
+        test_results.sort_by_key(|test_result| test_result.commit_num);
+
+        Ok(test_results)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn put_mfg_batch_quality_score(
+        &self,
+        score: MfgBatchQualityScore,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shard = self.require_holding_shard(&score.mfg_batch_id, score.service_id.as_deref())?;
+
+        self.shards[shard].put_mfg_batch_quality_score(score)
+    }
+
+    #[cfg(feature = "mfg-batch-quality-scores")]
+    fn list_mfg_batch_quality_scores(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchQualityScoreFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchQualityScore>, MfgBatchStoreError> {
+        let mut scores = self.gather(|shard| {
+            shard.list_mfg_batch_quality_scores(service_id, filters, 0, offset + limit)
+        })?;
+        scores.sort_by(|a, b| (a.score, &a.mfg_batch_id).cmp(&(b.score, &b.mfg_batch_id)));
+
+        Ok(scores
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    /// Replaces the review queue of every shard with the duplicates of the mfg_batches it holds
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn replace_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        duplicates: Vec<MfgBatchDuplicate>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let mut by_shard: Vec<Vec<MfgBatchDuplicate>> =
+            self.shards.iter().map(|_| Vec::new()).collect();
+        for duplicate in duplicates {
+            let shard = self.require_holding_shard(&duplicate.mfg_batch_id, service_id)?;
+            by_shard[shard].push(duplicate);
+        }
+
+        for (shard, duplicates) in self.shards.iter().zip(by_shard) {
+            shard.replace_mfg_batch_duplicates(service_id, duplicates)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mfg-batch-duplicates")]
+    fn list_mfg_batch_duplicates(
+        &self,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchDuplicate>, MfgBatchStoreError> {
+        let mut duplicates =
+            self.gather(|shard| shard.list_mfg_batch_duplicates(service_id, 0, offset + limit))?;
+        duplicates.sort_by(|a, b| {
+            b.similarity
+                .cmp(&a.similarity)
+                .then_with(|| a.mfg_batch_id.cmp(&b.mfg_batch_id))
+                .then_with(|| a.duplicate_of.cmp(&b.duplicate_of))
+        });
+
+        Ok(duplicates
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    /// Merges mfg_batches held on the same shard. The off-chain records of mfg_batches held on
+    /// different shards cannot be moved in one transaction, so merging them is refused.
+    #[cfg(feature = "mfg-batch-merge")]
+    fn merge_mfg_batches(&self, alias: MfgBatchAlias) -> Result<(), MfgBatchStoreError> {
+        let service_id = alias.service_id.as_deref();
+        let shard = self.require_holding_shard(&alias.mfg_batch_id, service_id)?;
+
+        if let Some(alias_shard) = self.holding_shard(&alias.alias, service_id)? {
+            if alias_shard != shard {
+                return Err(MfgBatchStoreError::InvalidArgumentError(
+                    InvalidArgumentError::new(
+                        "alias".to_string(),
+                        format!(
+                            "mfg_batches {} and {} are held on different shards",
+                            alias.alias, alias.mfg_batch_id
+                        ),
+                    ),
+                ));
+            }
+        }
+
+        self.shards[shard].merge_mfg_batches(alias)
+    }
+
+    #[cfg(feature = "mfg-batch-merge")]
+    fn list_mfg_batch_aliases(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAlias>, MfgBatchStoreError> {
+        let mut aliases = self.gather(|shard| shard.list_mfg_batch_aliases(service_id))?;
+        aliases.sort_by_key(|alias| alias.merged_at);
+
+        Ok(aliases)
+    }
+
+    #[cfg(feature = "mfg-batch-audit-log")]
+    fn verify_mfg_batch_audit_log(&self) -> Result<Vec<AuditLogDiscrepancy>, MfgBatchStoreError> {
+        self.gather(|shard| shard.verify_mfg_batch_audit_log())
+    }
+
+    #[cfg(feature = "mfg-batch-checksums")]
+    fn verify_mfg_batch_checksums(&self) -> Result<Vec<ChecksumDiscrepancy>, MfgBatchStoreError> {
+        self.gather(|shard| shard.verify_mfg_batch_checksums())
+    }
+
+    /// Records an anchor on its owner's shard
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn add_mfg_batch_anchor(&self, anchor: MfgBatchAnchor) -> Result<(), MfgBatchStoreError> {
+        let shard = self.owner_shard(&anchor.owner);
+        self.shards[shard].add_mfg_batch_anchor(anchor)
+    }
+
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_anchors(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAnchor>, MfgBatchStoreError> {
+        let mut anchors = self.gather(|shard| shard.list_mfg_batch_anchors(service_id))?;
+        anchors.sort_by_key(|anchor| anchor.commit_num);
+
+        Ok(anchors)
+    }
+
+    /// Gathers the record hashes of every shard, so the Merkle root covers the whole store
+    #[cfg(feature = "mfg-batch-anchors")]
+    fn list_mfg_batch_record_hashes(
+        &self,
+        commit_num: i64,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, MfgBatchStoreError> {
+        let mut hashes =
+            self.gather(|shard| shard.list_mfg_batch_record_hashes(commit_num, service_id))?;
+        hashes.sort_unstable();
+
+        Ok(hashes)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn put_mfg_batch_allocation(
+        &self,
+        allocation: MfgBatchAllocation,
+    ) -> Result<(), MfgBatchStoreError> {
+        let shard =
+            self.require_holding_shard(&allocation.mfg_batch_id, allocation.service_id.as_deref())?;
+
+        self.shards[shard].put_mfg_batch_allocation(allocation)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn list_mfg_batch_allocations(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<MfgBatchAllocation>, MfgBatchStoreError> {
+        let mut allocations =
+            self.gather(|shard| shard.list_mfg_batch_allocations(mfg_batch_id, service_id))?;
+        allocations.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+
+        Ok(allocations)
+    }
+
+    #[cfg(feature = "mfg-batch-allocations")]
+    fn get_mfg_batch_availability(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+    ) -> Result<Option<MfgBatchAvailability>, MfgBatchStoreError> {
+        match self.holding_shard(mfg_batch_id, service_id)? {
+            Some(shard) => self.shards[shard].get_mfg_batch_availability(mfg_batch_id, service_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes each recall record to the shard that held its mfg_batch at the recall's commit
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn add_mfg_batch_recalls(
+        &self,
+        recalls: Vec<MfgBatchRecall>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let mut by_shard: Vec<Vec<MfgBatchRecall>> =
+            self.shards.iter().map(|_| Vec::new()).collect();
+        for recall in recalls {
+            let shard = self.shard_at_commit(
+                &recall.mfg_batch_id,
+                recall.commit_num,
+                recall.service_id.as_deref(),
+            )?;
+            by_shard[shard].push(recall);
+        }
+
+        for (shard, recalls) in self.shards.iter().zip(by_shard) {
+            if !recalls.is_empty() {
+                shard.add_mfg_batch_recalls(recalls)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closes the recall on every shard holding one of its records
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn close_mfg_batch_recall(
+        &self,
+        recall_id: &str,
+        closed_at: i64,
+        service_id: Option<&str>,
+    ) -> Result<(), MfgBatchStoreError> {
+        let mut closed = false;
+        for shard in &self.shards {
+            match shard.close_mfg_batch_recall(recall_id, closed_at, service_id) {
+                Ok(()) => closed = true,
+                Err(MfgBatchStoreError::NotFoundError(_)) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        if closed {
+            Ok(())
+        } else {
+            Err(MfgBatchStoreError::NotFoundError(format!(
+                "Active recall {}",
+                recall_id
+            )))
+        }
+    }
+
+    #[cfg(feature = "mfg-batch-recalls")]
+    fn list_mfg_batch_recalls(
+        &self,
+        service_id: Option<&str>,
+        filters: &ListMfgBatchRecallFilters,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatchRecall>, MfgBatchStoreError> {
+        let mut recalls = self
+            .gather(|shard| shard.list_mfg_batch_recalls(service_id, filters, 0, offset + limit))?;
+        recalls.sort_by(|a, b| {
+            b.recalled_at
+                .cmp(&a.recalled_at)
+                .then_with(|| a.recall_id.cmp(&b.recall_id))
+                .then_with(|| a.mfg_batch_id.cmp(&b.mfg_batch_id))
+        });
+
+        Ok(recalls
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    /// Relevance is only ranked within a shard, so matches from all shards are listed by
+    /// mfg_batch ID
+    #[cfg(feature = "mfg-batch-text-search")]
+    fn search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<MfgBatch>, MfgBatchStoreError> {
+        let mut mfg_batches =
+            self.gather(|shard| shard.search_mfg_batches_text(query, service_id, 0, i64::MAX))?;
+        mfg_batches.sort_by(|a, b| a.mfg_batch_id().cmp(b.mfg_batch_id()));
+
+        Ok(mfg_batches
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    /// Every shard runs the same search for all of its matches, so it is explained that way on
+    /// the first shard
+    #[cfg(all(feature = "mfg-batch-explain", feature = "mfg-batch-text-search"))]
+    fn explain_search_mfg_batches_text(
+        &self,
+        query: &str,
+        service_id: Option<&str>,
+        _offset: i64,
+        _limit: i64,
+    ) -> Result<QueryPlan, MfgBatchStoreError> {
+        self.shards[0].explain_search_mfg_batches_text(query, service_id, 0, i64::MAX)
+    }
+
+    fn update_mfg_batch(
+        &self,
+        mfg_batch_id: &str,
+        service_id: Option<&str>,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        match self.holding_shard(mfg_batch_id, service_id)? {
+            Some(shard) => {
+                self.shards[shard].update_mfg_batch(mfg_batch_id, service_id, current_commit_num)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn delete_mfg_batch(
+        &self,
+        address: &str,
+        current_commit_num: i64,
+    ) -> Result<(), MfgBatchStoreError> {
+        for shard in &self.shards {
+            shard.delete_mfg_batch(address, current_commit_num)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Picks the shard of an owner with the 64-bit FNV-1a hash of its org id, which unlike the
+/// standard library's hasher is fixed, so owners keep their shard across releases
+fn shard_index(owner: &str, shard_count: usize) -> usize {
+    let hash = owner.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    (hash % shard_count as u64) as usize
+}
+
+/// Splits a page of a list across shards holding `counts` of its items each, in shard order,
+/// returning the offset and limit to read from each shard
+fn page_windows(counts: &[i64], offset: i64, limit: i64) -> Vec<(i64, i64)> {
+    let mut skip = offset.max(0);
+    let mut remaining = limit.max(0);
+
+    counts
+        .iter()
+        .map(|count| {
+            let shard_offset = skip.min(*count);
+            skip -= shard_offset;
+            let shard_limit = remaining.min(count - shard_offset);
+            remaining -= shard_limit;
+            (shard_offset, shard_limit)
+        })
+        .collect()
+}
+
+/// Takes the next item from whichever iterator's next mfg_batch has the lowest ID, taking
+/// errors first so they are not held back
+fn next_in_id_order(
+    iters: &mut [Peekable<MfgBatchIter>],
+) -> Option<Result<MfgBatch, MfgBatchStoreError>> {
+    let index = iters
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, iter)| {
+            iter.peek().map(|next| {
+                (
+                    index,
+                    next.as_ref()
+                        .ok()
+                        .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string()),
+                )
+            })
+        })
+        .min_by(|(_, a), (_, b)| a.cmp(b))
+        .map(|(index, _)| index)?;
+
+    iters[index].next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mfg_batch::store::MfgBatchBuilder;
+
+    fn mfg_batch(mfg_batch_id: &str) -> MfgBatch {
+        MfgBatchBuilder::default()
+            .with_mfg_batch_id(mfg_batch_id.to_string())
+            .with_mfg_batch_address(format!("addr-{}", mfg_batch_id))
+            .with_mfg_batch_namespace("GS1".to_string())
+            .with_owner("org".to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .build()
+            .expect("Failed to build mfg_batch")
+    }
+
+    fn iter_over(ids: &'static [&'static str]) -> Peekable<MfgBatchIter<'static>> {
+        let mut pages = vec![ids.iter().map(|id| mfg_batch(id)).collect::<Vec<_>>()];
+        MfgBatchIter::new(100, move |_| Ok(pages.pop().unwrap_or_default())).peekable()
+    }
+
+    /// Verify that owners are spread across the shards and always map to the same shard
+    #[test]
+    fn test_shard_index() {
+        assert_eq!(shard_index("org-1", 4), shard_index("org-1", 4));
+        assert_eq!(shard_index("anything", 1), 0);
+
+        let mut used = [false; 4];
+        for owner in 0..64 {
+            used[shard_index(&format!("org-{}", owner), 4)] = true;
+        }
+        assert_eq!(used, [true; 4]);
+    }
+
+    /// Verify that a page is taken from the shards in turn, skipping the shards it starts after
+    #[test]
+    fn test_page_windows() {
+        assert_eq!(page_windows(&[3, 5, 2], 0, 4), vec![(0, 3), (0, 1), (0, 0)]);
+        assert_eq!(page_windows(&[3, 5, 2], 4, 4), vec![(3, 0), (1, 4), (0, 0)]);
+        assert_eq!(
+            page_windows(&[3, 5, 2], 7, 10),
+            vec![(3, 0), (4, 1), (0, 2)]
+        );
+        assert_eq!(
+            page_windows(&[3, 5, 2], 20, 10),
+            vec![(3, 0), (5, 0), (2, 0)]
+        );
+    }
+
+    /// Verify that the mfg_batches of several shards are merged in ID order
+    #[test]
+    fn test_next_in_id_order() {
+        let mut iters = vec![
+            iter_over(&["batch1", "batch4"]),
+            iter_over(&[]),
+            iter_over(&["batch2", "batch3", "batch5"]),
+        ];
+
+        let mut ids = Vec::new();
+        while let Some(next) = next_in_id_order(&mut iters) {
+            ids.push(
+                next.expect("Failed to read mfg_batch")
+                    .mfg_batch_id()
+                    .to_string(),
+            );
+        }
+
+        assert_eq!(ids, vec!["batch1", "batch2", "batch3", "batch4", "batch5"]);
+    }
+}
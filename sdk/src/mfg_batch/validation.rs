@@ -0,0 +1,1085 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of mfg_batch actions against the rules the mfg_batch smart contract enforces.
+//!
+//! The contract runs these checks as it applies a transaction and stops at the first that
+//! fails. `validate_create_action` runs them ahead of submitting a transaction and reports every
+//! violation found, so a client can correct an action in one pass. Checks that depend on other
+//! state, such as whether the batch already exists, whether the signer may create it or whether
+//! its manufacture location is recorded, are left to the contract.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::protocol::{
+    mfg_batch::{
+        payload::MfgBatchCreateAction,
+        state::{Attachment, MfgBatchNamespace},
+    },
+    pike::state::Organization,
+    schema::state::{DataType, PropertyDefinition, PropertyValue, Schema},
+};
+
+use super::addressing::MfgBatchIdentifier;
+
+/// The longest string value accepted for a mfg_batch property, in characters
+pub const MAX_STRING_VALUE_LENGTH: usize = 1024;
+
+/// The longest company-internal mfg_batch identifier accepted, in characters
+pub const MAX_INTERNAL_ID_LENGTH: usize = 64;
+
+/// Unit of measure codes accepted for a mfg_batch's quantities (UN/ECE Recommendation 20)
+pub const UOM_CODES: &[&str] = &[
+    "C62", // one
+    "EA",  // each
+    "H87", // piece
+    "MGM", // milligram
+    "GRM", // gram
+    "KGM", // kilogram
+    "TNE", // tonne
+    "ONZ", // ounce
+    "LBR", // pound
+    "MLT", // millilitre
+    "LTR", // litre
+    "MTQ", // cubic metre
+    "GLL", // US gallon
+    "MMT", // millimetre
+    "CMT", // centimetre
+    "MTR", // metre
+    "MTK", // square metre
+];
+
+/// The latest production or expiration date accepted, in seconds since the epoch
+/// (2100-01-01T00:00:00Z)
+pub const MAX_DATE: u64 = 4_102_444_800;
+
+/// The most documents a mfg_batch may reference
+pub const MAX_ATTACHMENTS: usize = 64;
+
+/// The name of the schema GS1 mfg_batches' properties are defined by
+pub const GS1_SCHEMA_NAME: &str = "gs1_mfg_batch";
+
+/// A rule an action breaks
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    /// The field of the action that breaks the rule. Properties are named `properties.<name>`.
+    pub field: String,
+    /// What is wrong with the field, as the contract would report it
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            message,
+        }
+    }
+
+    fn property(name: &str, message: String) -> Self {
+        Self::new(&format!("properties.{}", name), message)
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Runs the checks the contract makes of a create action, returning every violation found. An
+/// action without violations may still be rejected for the state it is applied to.
+///
+/// # Arguments
+///
+///  * `action` - The action to check
+///  * `schema` - The `gs1_mfg_batch` schema, if it has been defined; only GS1 mfg_batches are
+///    checked against it
+///  * `org` - The organization that is to own the mfg_batch, if it exists
+pub fn validate_create_action(
+    action: &MfgBatchCreateAction,
+    schema: Option<&Schema>,
+    org: Option<&Organization>,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let gs1 = action.mfg_batch_namespace() == &MfgBatchNamespace::Gs1;
+
+    let identifier = if action.mfg_batch_id().is_empty() {
+        violations.push(Violation::new(
+            "mfg_batch_id",
+            "mfg_batch_id cannot be empty string".to_string(),
+        ));
+        None
+    } else {
+        validate_namespaced_mfg_batch_id(action.mfg_batch_namespace(), action.mfg_batch_id())
+            .map_err(|violation| violations.push(violation))
+            .ok()
+    };
+
+    if action.owner().is_empty() {
+        violations.push(Violation::new(
+            "owner",
+            "Owner cannot be empty string".to_string(),
+        ));
+    }
+
+    let checks = vec![
+        validate_quantity(action.quantity(), action.uom(), action.expected_quantity()),
+        validate_dates(action.production_date(), action.expiration_date()),
+        validate_manufacture_location(action.manufacture_location()),
+        validate_attachments(action.attachments()),
+    ];
+    violations.extend(checks.into_iter().filter_map(Result::err));
+
+    match org {
+        Some(org) => {
+            // Only GS1 keys carry a company prefix; a malformed ID is reported as such already
+            let keyed = identifier
+                .map(|identifier| identifier != MfgBatchIdentifier::CompanyInternal)
+                .unwrap_or(false);
+            if gs1 && keyed {
+                if let Err(violation) = validate_gs1_company_prefix(action.mfg_batch_id(), org) {
+                    violations.push(violation);
+                }
+            }
+        }
+        None if !action.owner().is_empty() => violations.push(Violation::new(
+            "owner",
+            format!("The organization does not exist: {}", action.owner()),
+        )),
+        None => (),
+    }
+
+    let mut properties = action.properties().to_vec();
+    if gs1 {
+        match schema {
+            Some(schema) => violations.extend(validate_schema_properties(&mut properties, schema)),
+            None => violations.push(Violation::new(
+                "properties",
+                format!("{} schema has not been defined", GS1_SCHEMA_NAME),
+            )),
+        }
+    }
+
+    if let Err(violation) = validate_property_languages(&properties) {
+        violations.push(violation);
+    }
+
+    violations
+}
+
+/// Checks properties against the schema defining them, adding the defaults of the properties
+/// left out, as the contract does before it records them
+fn validate_schema_properties(
+    properties: &mut Vec<PropertyValue>,
+    schema: &Schema,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for property in properties.iter() {
+        match schema
+            .properties()
+            .iter()
+            .find(|p| p.name() == property.name())
+        {
+            Some(definition) => {
+                if let Err(violation) = validate_property_value(property, definition) {
+                    violations.push(violation);
+                }
+            }
+            None => violations.push(Violation::property(
+                property.name(),
+                format!(
+                    "{} is not a property that is defined by the gs1 schema",
+                    property.name()
+                ),
+            )),
+        }
+    }
+
+    for definition in schema.properties() {
+        if let Some(default_value) = definition.default_value() {
+            if !properties.iter().any(|p| p.name() == definition.name()) {
+                if let Err(violation) = validate_property_value(default_value, definition) {
+                    violations.push(violation);
+                }
+                properties.push(default_value.clone());
+            }
+        }
+    }
+
+    for definition in schema.properties().iter().filter(|p| *p.required()) {
+        if !properties
+            .iter()
+            .any(|p| p.name() == definition.name() && p.data_type() == definition.data_type())
+        {
+            violations.push(Violation::property(
+                definition.name(),
+                format!(
+                    "Missing required field '{}' of type '{:?}'",
+                    definition.name(),
+                    definition.data_type()
+                ),
+            ));
+        }
+    }
+
+    violations
+}
+
+// Validates the specification for GS1 standard format
+// No immediate changes required for MVP
+
+/* The purpose of this file is to programmatically express the equation used to validate a GTIN
+It validates gtin format to avoid mistype errors similar to a credit card validation
+Check digit validation: (https://www.gs1.org/services/how-calculate-check-digit-manually) */
+
+/// Validates a mfg_batch ID according to the kind of identifier it is, returning that kind.
+///
+/// GTINs and SSCCs must pass check digit validation. Company-internal identifiers may contain
+/// any printable ASCII characters other than spaces, but may not be purely numeric, so that a
+/// mistyped GTIN is not accepted as an internal identifier.
+pub fn validate_mfg_batch_id(mfg_batch_id: &str) -> Result<MfgBatchIdentifier, Violation> {
+    let identifier = MfgBatchIdentifier::from_id(mfg_batch_id);
+
+    match identifier {
+        MfgBatchIdentifier::Gtin8
+        | MfgBatchIdentifier::Gtin12
+        | MfgBatchIdentifier::Gtin13
+        | MfgBatchIdentifier::Gtin14 => validate_gtin(mfg_batch_id)?,
+        // SSCC is an 18-digit number identifying a logistic unit, with a GS1 check digit
+        MfgBatchIdentifier::Sscc => check_digit_validation(mfg_batch_id)?,
+        MfgBatchIdentifier::CompanyInternal => validate_internal_id(mfg_batch_id)?,
+    }
+
+    Ok(identifier)
+}
+
+/// Validates a mfg_batch ID within its namespace, returning the kind of identifier it is.
+///
+/// Only GS1 batches are identified by GS1 keys. Internal and lot batches are recorded under a
+/// manufacturer's own batch numbers, so their IDs skip GTIN validation and may be numeric.
+pub fn validate_namespaced_mfg_batch_id(
+    mfg_batch_namespace: &MfgBatchNamespace,
+    mfg_batch_id: &str,
+) -> Result<MfgBatchIdentifier, Violation> {
+    match mfg_batch_namespace {
+        MfgBatchNamespace::Gs1 => validate_mfg_batch_id(mfg_batch_id),
+        MfgBatchNamespace::Internal | MfgBatchNamespace::Lot => {
+            validate_internal_id_format(mfg_batch_id)?;
+            Ok(MfgBatchIdentifier::CompanyInternal)
+        }
+    }
+}
+
+fn validate_internal_id(id: &str) -> Result<(), Violation> {
+    if is_numeric(id) {
+        return Err(Violation::new(
+            "mfg_batch_id",
+            format!("Invalid length for GTIN identifier: {}", id),
+        ));
+    }
+
+    validate_internal_id_format(id)
+}
+
+fn validate_internal_id_format(id: &str) -> Result<(), Violation> {
+    if id.is_empty()
+        || id.chars().count() > MAX_INTERNAL_ID_LENGTH
+        || !id.chars().all(|c| c.is_ascii_graphic())
+    {
+        return Err(Violation::new(
+            "mfg_batch_id",
+            format!(
+                "Invalid company-internal identifier, must be 1 to {} printable characters \
+                 without spaces: {}",
+                MAX_INTERNAL_ID_LENGTH, id
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+// Leaving this as an extensible function, so other validation rules can be implemented by GTIN format
+pub fn validate_gtin(gtin: &str) -> Result<(), Violation> {
+    // Check that gtin is numeric only
+    if is_numeric(gtin) {
+        match gtin.chars().count() {
+            // GTIN-8 is an 8-digit number used predominately outside of North America on smaller packaging
+            8 => check_digit_validation(gtin),
+            // GTIN-12 is a 12-digit number used primarily in North America
+            12 => check_digit_validation(gtin),
+            // GTIN-13 (it could also be a GLN or the first 13 digits of a GRAI, GDTI or GCN.) (ex: 9781981855728)
+            13 => check_digit_validation(gtin),
+            // GTIN-14 is a 14-digit number used to identify trade items at various packaging levels
+            14 => check_digit_validation(gtin),
+            // Invalid length
+            _ => Err(Violation::new(
+                "mfg_batch_id",
+                format!("Invalid length for GTIN identifier: {}", gtin),
+            )),
+        }
+    } else {
+        Err(Violation::new(
+            "mfg_batch_id",
+            format!(
+                "Invalid format, GTIN identifiers only contain numbers: {}",
+                gtin
+            ),
+        ))
+    }
+}
+
+/// Checks that an organization holds the GS1 company prefix of a GS1 keyed mfg_batch ID.
+///
+/// The organization must have a `gs1_company_prefix` alternate ID, and the mfg_batch ID must
+/// contain it.
+pub fn validate_gs1_company_prefix(
+    mfg_batch_id: &str,
+    org: &Organization,
+) -> Result<(), Violation> {
+    let gs1_company_prefix = org
+        .alternate_ids()
+        .iter()
+        .find(|p| p.id_type() == "gs1_company_prefix")
+        .ok_or_else(|| {
+            Violation::new(
+                "owner",
+                format!(
+                    "The agents organization does not have the gs1_company_prefix prefix: {:?}",
+                    org.alternate_ids()
+                ),
+            )
+        })?;
+
+    // If the gtin identifer does not contain the organizations gs1 prefix
+    if !mfg_batch_id.contains(gs1_company_prefix.id()) {
+        return Err(Violation::new(
+            "mfg_batch_id",
+            format!(
+                "The agents organization does not own the GS1 company prefix in the GTIN \
+                 mfg_batch_id: {:?}",
+                org.alternate_ids()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a mfg_batch's quantities.
+///
+/// A mfg_batch without a unit of measure has no quantities recorded, so both must be zero.
+/// Otherwise the unit must be one of `UOM_CODES` and neither quantity may be negative.
+pub fn validate_quantity(
+    quantity: i64,
+    uom: &str,
+    expected_quantity: i64,
+) -> Result<(), Violation> {
+    if uom.is_empty() {
+        if quantity != 0 || expected_quantity != 0 {
+            return Err(Violation::new(
+                "uom",
+                "A unit of measure is required when a quantity is given".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+
+    if !UOM_CODES.contains(&uom) {
+        return Err(Violation::new(
+            "uom",
+            format!("Unknown unit of measure: {}", uom),
+        ));
+    }
+
+    if quantity < 0 || expected_quantity < 0 {
+        return Err(Violation::new(
+            "quantity",
+            format!(
+                "Quantities may not be negative: quantity {}, expected quantity {}",
+                quantity, expected_quantity
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a mfg_batch's production and expiration dates.
+///
+/// A date of 0 has not been recorded. Recorded dates may not be later than `MAX_DATE`, and a
+/// batch with both dates must expire after it was produced.
+pub fn validate_dates(production_date: u64, expiration_date: u64) -> Result<(), Violation> {
+    if production_date > MAX_DATE || expiration_date > MAX_DATE {
+        return Err(Violation::new(
+            if production_date > MAX_DATE {
+                "production_date"
+            } else {
+                "expiration_date"
+            },
+            format!(
+                "Dates may not be later than {}: production date {}, expiration date {}",
+                MAX_DATE, production_date, expiration_date
+            ),
+        ));
+    }
+
+    if production_date != 0 && expiration_date != 0 && expiration_date <= production_date {
+        return Err(Violation::new(
+            "expiration_date",
+            format!(
+                "Expiration date {} must be after production date {}",
+                expiration_date, production_date
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates the Splinter service a transaction was submitted to.
+///
+/// An empty service ID means the transaction was not submitted to a circuit. Otherwise it must be
+/// fully qualified as `<circuit_id>::<service_id>`, both made of letters, digits and dashes.
+pub fn validate_service_id(service_id: &str) -> Result<(), Violation> {
+    if service_id.is_empty() {
+        return Ok(());
+    }
+
+    let is_id =
+        |id: &str| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    match service_id.split_once("::") {
+        Some((circuit_id, service)) if is_id(circuit_id) && is_id(service) => Ok(()),
+        _ => Err(Violation::new(
+            "service_id",
+            format!(
+                "Invalid service ID, must be of the form <circuit_id>::<service_id>: {}",
+                service_id
+            ),
+        )),
+    }
+}
+
+/// Validates the GLN of the location a mfg_batch was produced at.
+///
+/// An empty GLN has not been recorded. Otherwise it must be 13 digits with a valid GS1 check
+/// digit.
+pub fn validate_manufacture_location(gln: &str) -> Result<(), Violation> {
+    if gln.is_empty() {
+        return Ok(());
+    }
+
+    if gln.len() != 13 || !gln.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Violation::new(
+            "manufacture_location",
+            format!("Invalid manufacture location, a GLN is 13 digits: {}", gln),
+        ));
+    }
+
+    check_digit_validation(gln).map_err(|_| {
+        Violation::new(
+            "manufacture_location",
+            format!(
+                "Invalid manufacture location, check digit validation failed: {}",
+                gln
+            ),
+        )
+    })
+}
+
+/// Validates the documents a mfg_batch references, which are kept off-chain.
+///
+/// A mfg_batch may reference at most `MAX_ATTACHMENTS` documents, no two with the same digest.
+/// Each digest must be a hex encoded SHA-256 digest in lowercase, each media type of the form
+/// `type/subtype` and each size greater than zero. A URI is not required, but if given must be
+/// an absolute URI without spaces.
+pub fn validate_attachments(attachments: &[Attachment]) -> Result<(), Violation> {
+    if attachments.len() > MAX_ATTACHMENTS {
+        return Err(Violation::new(
+            "attachments",
+            format!(
+                "A mfg_batch may not have more than {} attachments",
+                MAX_ATTACHMENTS
+            ),
+        ));
+    }
+
+    let mut digests = HashSet::new();
+    for attachment in attachments {
+        let digest = attachment.digest();
+        if digest.len() != 64
+            || !digest
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        {
+            return Err(Violation::new(
+                "attachments.digest",
+                format!(
+                    "Invalid attachment digest, must be a hex encoded SHA-256 digest: {}",
+                    digest
+                ),
+            ));
+        }
+        if !digests.insert(digest) {
+            return Err(Violation::new(
+                "attachments.digest",
+                format!("Attachment {} is listed more than once", digest),
+            ));
+        }
+
+        let is_token = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+        };
+        let mime_type = attachment.mime_type();
+        let valid_mime_type = match mime_type.split_once('/') {
+            Some((type_, subtype)) => is_token(type_) && is_token(subtype),
+            None => false,
+        };
+        if !valid_mime_type {
+            return Err(Violation::new(
+                "attachments.mime_type",
+                format!(
+                    "Invalid attachment media type, must be of the form type/subtype: {}",
+                    mime_type
+                ),
+            ));
+        }
+
+        if attachment.size() == 0 {
+            return Err(Violation::new(
+                "attachments.size",
+                format!("Attachment {} may not be empty", digest),
+            ));
+        }
+
+        let uri = attachment.uri();
+        if !uri.is_empty() && !is_absolute_uri(uri) {
+            return Err(Violation::new(
+                "attachments.uri",
+                format!(
+                    "Invalid attachment URI, must be an absolute URI of at most {} characters: \
+                     {}",
+                    MAX_STRING_VALUE_LENGTH, uri
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that a URI starts with a scheme, such as `https:`, and has no spaces
+fn is_absolute_uri(uri: &str) -> bool {
+    if uri.chars().count() > MAX_STRING_VALUE_LENGTH || !uri.chars().all(|c| c.is_ascii_graphic()) {
+        return false;
+    }
+
+    match uri.split_once(':') {
+        Some((scheme, rest)) => {
+            !rest.is_empty()
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        None => false,
+    }
+}
+
+/// Checks that a property value is well-formed for the schema property that defines it.
+///
+/// Number values must stay within range once scaled by the definition's exponent, enum values
+/// must index one of the definition's options, string values may not exceed
+/// `MAX_STRING_VALUE_LENGTH` and struct values must match the definition's struct properties.
+pub fn validate_property_value(
+    value: &PropertyValue,
+    definition: &PropertyDefinition,
+) -> Result<(), Violation> {
+    if value.data_type() != definition.data_type() {
+        return Err(Violation::property(
+            value.name(),
+            format!(
+                "Property '{}' must be of type '{:?}' but was '{:?}'",
+                value.name(),
+                definition.data_type(),
+                value.data_type()
+            ),
+        ));
+    }
+
+    match value.data_type() {
+        DataType::Number => {
+            let exponent = *definition.number_exponent();
+            let in_range = exponent <= 0
+                || 10i64
+                    .checked_pow(exponent as u32)
+                    .and_then(|scale| value.number_value().checked_mul(scale))
+                    .is_some();
+            if !in_range {
+                return Err(Violation::property(
+                    value.name(),
+                    format!(
+                        "Property '{}' value {} is out of range for exponent {}",
+                        value.name(),
+                        value.number_value(),
+                        exponent
+                    ),
+                ));
+            }
+        }
+        DataType::Enum => {
+            if *value.enum_value() as usize >= definition.enum_options().len() {
+                return Err(Violation::property(
+                    value.name(),
+                    format!(
+                        "Property '{}' enum value {} is not one of its {} options",
+                        value.name(),
+                        value.enum_value(),
+                        definition.enum_options().len()
+                    ),
+                ));
+            }
+        }
+        DataType::String => {
+            if value.string_value().chars().count() > MAX_STRING_VALUE_LENGTH {
+                return Err(Violation::property(
+                    value.name(),
+                    format!(
+                        "Property '{}' string value is longer than {} characters",
+                        value.name(),
+                        MAX_STRING_VALUE_LENGTH
+                    ),
+                ));
+            }
+        }
+        DataType::Struct => {
+            validate_struct_values(value.name(), value.struct_values(), definition)?;
+        }
+        DataType::Bytes | DataType::Boolean | DataType::LatLong => (),
+    }
+
+    Ok(())
+}
+
+/// Checks the language tags of a mfg_batch's properties.
+///
+/// Only string properties may be localized, with a well-formed BCP 47 language tag, and no two
+/// values of a property may be in the same language. The fields of a struct value are never
+/// localized; the struct value is localized as a whole, if at all.
+pub fn validate_property_languages(properties: &[PropertyValue]) -> Result<(), Violation> {
+    let mut localized = HashSet::new();
+    for property in properties {
+        validate_struct_languages(property.name(), property.struct_values())?;
+
+        if property.language().is_empty() {
+            continue;
+        }
+        if property.data_type() != &DataType::String {
+            return Err(Violation::property(
+                property.name(),
+                format!(
+                    "Property '{}' is localized but is of type '{:?}'; only strings may be",
+                    property.name(),
+                    property.data_type()
+                ),
+            ));
+        }
+        if !is_language_tag(property.language()) {
+            return Err(Violation::property(
+                property.name(),
+                format!(
+                    "Property '{}' has an invalid language tag: {}",
+                    property.name(),
+                    property.language()
+                ),
+            ));
+        }
+        if !localized.insert((property.name(), property.language().to_ascii_lowercase())) {
+            return Err(Violation::property(
+                property.name(),
+                format!(
+                    "Property '{}' is given more than once in language {}",
+                    property.name(),
+                    property.language()
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_struct_languages(name: &str, struct_values: &[PropertyValue]) -> Result<(), Violation> {
+    for struct_value in struct_values {
+        if !struct_value.language().is_empty() {
+            return Err(Violation::property(
+                name,
+                format!(
+                    "Field '{}' of struct '{}' may not be localized",
+                    struct_value.name(),
+                    name
+                ),
+            ));
+        }
+        validate_struct_languages(struct_value.name(), struct_value.struct_values())?;
+    }
+
+    Ok(())
+}
+
+/// Whether the tag is made of a language subtag of two to eight letters, followed by subtags
+/// of one to eight letters or digits, separated by hyphens
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or_default();
+    (2..=8).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+fn validate_struct_values(
+    name: &str,
+    struct_values: &[PropertyValue],
+    definition: &PropertyDefinition,
+) -> Result<(), Violation> {
+    for struct_value in struct_values {
+        let struct_definition = definition
+            .struct_properties()
+            .iter()
+            .find(|property| property.name() == struct_value.name())
+            .ok_or_else(|| {
+                Violation::property(
+                    name,
+                    format!(
+                        "{} is not a property of struct '{}'",
+                        struct_value.name(),
+                        name
+                    ),
+                )
+            })?;
+
+        validate_property_value(struct_value, struct_definition)?;
+    }
+
+    for required in definition
+        .struct_properties()
+        .iter()
+        .filter(|p| *p.required())
+    {
+        if struct_values.iter().all(|v| v.name() != required.name()) {
+            return Err(Violation::property(
+                name,
+                format!(
+                    "Struct '{}' is missing required field '{}' of type '{:?}'",
+                    name,
+                    required.name(),
+                    required.data_type()
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_digit_validation(gtin: &str) -> Result<(), Violation> {
+    let mut gtin_vec: Vec<char> = gtin.chars().collect();
+    // Remove the check digit from the gtin_vec and store it for later
+    let check_digit_char = gtin_vec
+        .pop()
+        .expect("No characters found, but string length was > 0");
+    let check_digit = convert_char_to_int(check_digit_char);
+    let mut sum = 0;
+    let mut index = 0;
+
+    if is_even(gtin_vec.len()) {
+        // For gtin-13
+        for digit in &gtin_vec {
+            if is_even(index) {
+                sum += convert_char_to_int(*digit);
+            } else {
+                sum += 3 * convert_char_to_int(*digit);
+            }
+            index += 1;
+        }
+    } else {
+        // For gtin 12, 14
+        for digit in &gtin_vec {
+            if is_even(index) {
+                sum += 3 * convert_char_to_int(*digit);
+            } else {
+                sum += convert_char_to_int(*digit);
+            }
+            index += 1;
+        }
+    }
+
+    let nearest_ten = ceiling_to_nearest_ten(sum as f32);
+    let computed_check_digit = nearest_ten - sum;
+
+    if computed_check_digit == check_digit {
+        Ok(())
+    } else {
+        Err(Violation::new(
+            "mfg_batch_id",
+            format!("Invalid gtin, check digit validation failed: {}", gtin),
+        ))
+    }
+}
+
+fn is_even(num: usize) -> bool {
+    num % 2 == 0
+}
+
+fn is_numeric(s: &str) -> bool {
+    s.parse::<f64>().is_ok()
+}
+
+fn ceiling_to_nearest_ten(num: f32) -> i32 {
+    let c: f32 = num / 10.0;
+    let ceil: f32 = c.ceil();
+    let c_ceil = ceil as i32;
+    10 * c_ceil
+}
+
+fn convert_char_to_int(c: char) -> i32 {
+    char::to_digit(c, 10).expect("No conversion for char to i32") as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        mfg_batch::{payload::MfgBatchCreateActionBuilder, state::AttachmentBuilder},
+        pike::state::{AlternateIdBuilder, OrganizationBuilder},
+        schema::state::{PropertyDefinitionBuilder, PropertyValueBuilder, SchemaBuilder},
+    };
+
+    fn schema() -> Schema {
+        let definition = PropertyDefinitionBuilder::new()
+            .with_name("product_name".into())
+            .with_data_type(DataType::String)
+            .with_required(true)
+            .build()
+            .expect("Failed to build property definition");
+        SchemaBuilder::new()
+            .with_name(GS1_SCHEMA_NAME.into())
+            .with_owner("org".into())
+            .with_properties(vec![definition])
+            .build()
+            .expect("Failed to build schema")
+    }
+
+    fn org() -> Organization {
+        let prefix = AlternateIdBuilder::new()
+            .with_id_type("gs1_company_prefix".into())
+            .with_id("6889".into())
+            .build()
+            .expect("Failed to build alternate id");
+        OrganizationBuilder::new()
+            .with_org_id("org".into())
+            .with_name("Org".into())
+            .with_alternate_ids(vec![prefix])
+            .build()
+            .expect("Failed to build organization")
+    }
+
+    fn action(mfg_batch_id: &str, properties: Vec<PropertyValue>) -> MfgBatchCreateActionBuilder {
+        MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_mfg_batch_id(mfg_batch_id.into())
+            .with_owner("org".into())
+            .with_properties(properties)
+    }
+
+    fn product_name() -> PropertyValue {
+        PropertyValueBuilder::new()
+            .with_name("product_name".into())
+            .with_data_type(DataType::String)
+            .with_string_value("Flour".into())
+            .build()
+            .expect("Failed to build property value")
+    }
+
+    /// Verify that an action the contract would accept has no violations
+    #[test]
+    fn test_validate_create_action_valid() {
+        let action = action("688955434684", vec![product_name()])
+            .build()
+            .expect("Failed to build action");
+
+        assert!(validate_create_action(&action, Some(&schema()), Some(&org())).is_empty());
+    }
+
+    /// Verify that every rule an action breaks is reported, each against its field, rather than
+    /// only the first
+    #[test]
+    fn test_validate_create_action_collects_violations() {
+        let action = action("688955434685", vec![])
+            .with_uom("BARREL".into())
+            .with_quantity(10)
+            .with_production_date(1_600_000_000)
+            .with_expiration_date(1_500_000_000)
+            .build()
+            .expect("Failed to build action");
+
+        let violations = validate_create_action(&action, Some(&schema()), Some(&org()));
+        let fields: Vec<&str> = violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "mfg_batch_id",
+                "uom",
+                "expiration_date",
+                "properties.product_name"
+            ]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "Invalid gtin, check digit validation failed: 688955434685"
+        );
+    }
+
+    /// Verify that a service ID is either empty or fully qualified by its circuit
+    #[test]
+    fn test_validate_service_id() {
+        assert!(validate_service_id("").is_ok());
+        assert!(validate_service_id("01234-ABCDE::gr00").is_ok());
+
+        for service_id in &[
+            "gr00",
+            "01234-ABCDE::",
+            "::gr00",
+            "01234-ABCDE::gr00::x",
+            "a b::c",
+        ] {
+            assert_eq!(
+                validate_service_id(service_id)
+                    .expect_err("Accepted an invalid service ID")
+                    .field,
+                "service_id"
+            );
+        }
+    }
+
+    /// Verify that attachments must have a SHA-256 digest, a media type and a size, and may only
+    /// be given an absolute URI
+    #[test]
+    fn test_validate_attachments() {
+        let attachment = |digest: &str, mime_type: &str, size: u64, uri: &str| {
+            AttachmentBuilder::new()
+                .with_digest(digest.into())
+                .with_mime_type(mime_type.into())
+                .with_size(size)
+                .with_uri(uri.into())
+                .build()
+                .expect("Failed to build attachment")
+        };
+        let digest = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let other_digest = "ab".repeat(32);
+
+        assert!(validate_attachments(&[
+            attachment(
+                digest,
+                "application/pdf",
+                48_213,
+                "https://example.com/coa.pdf"
+            ),
+            attachment(&other_digest, "image/png", 1, ""),
+        ])
+        .is_ok());
+
+        let field = |attachments: &[Attachment]| {
+            validate_attachments(attachments)
+                .expect_err("Accepted invalid attachments")
+                .field
+        };
+        assert_eq!(
+            field(&[attachment(&digest.to_uppercase(), "application/pdf", 1, "")]),
+            "attachments.digest"
+        );
+        assert_eq!(
+            field(&[attachment(&digest[..63], "application/pdf", 1, "")]),
+            "attachments.digest"
+        );
+        assert_eq!(
+            field(&[
+                attachment(digest, "application/pdf", 1, ""),
+                attachment(digest, "image/png", 1, ""),
+            ]),
+            "attachments.digest"
+        );
+        assert_eq!(
+            field(&[attachment(digest, "pdf", 1, "")]),
+            "attachments.mime_type"
+        );
+        assert_eq!(
+            field(&[attachment(digest, "application/", 1, "")]),
+            "attachments.mime_type"
+        );
+        assert_eq!(
+            field(&[attachment(digest, "application/pdf", 0, "")]),
+            "attachments.size"
+        );
+        assert_eq!(
+            field(&[attachment(digest, "application/pdf", 1, "coa.pdf")]),
+            "attachments.uri"
+        );
+        assert_eq!(
+            field(&[attachment(
+                digest,
+                "application/pdf",
+                1,
+                "https://example.com/a coa.pdf"
+            )]),
+            "attachments.uri"
+        );
+    }
+
+    /// Verify that the organization and schema are checked for when they are missing, and that
+    /// the organization must own the GS1 company prefix of the ID
+    #[test]
+    fn test_validate_create_action_missing_state() {
+        let action = action("688955434684", vec![product_name()])
+            .build()
+            .expect("Failed to build action");
+
+        let violations = validate_create_action(&action, None, None);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].field, "owner");
+        assert_eq!(violations[1].field, "properties");
+
+        let other = OrganizationBuilder::new()
+            .with_org_id("other".into())
+            .with_name("Other".into())
+            .build()
+            .expect("Failed to build organization");
+        let violations = validate_create_action(&action, Some(&schema()), Some(&other));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0]
+            .message
+            .contains("does not have the gs1_company_prefix prefix"));
+    }
+}
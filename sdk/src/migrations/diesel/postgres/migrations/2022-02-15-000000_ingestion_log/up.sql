@@ -0,0 +1,28 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE ingestion_log (
+    id BIGSERIAL PRIMARY KEY,
+    file_name TEXT NOT NULL,
+    source TEXT NOT NULL,
+    kind TEXT,
+    status TEXT NOT NULL,
+    message TEXT,
+    correlation_id TEXT,
+    started_at BIGINT NOT NULL,
+    finished_at BIGINT NOT NULL
+);
+
+CREATE INDEX ingestion_log_status_idx ON ingestion_log (status);
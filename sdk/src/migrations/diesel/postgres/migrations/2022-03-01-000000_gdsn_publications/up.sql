@@ -0,0 +1,28 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE gdsn_publications (
+    publication_id TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    service_id TEXT,
+    data_recipient TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_message TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (publication_id, product_id)
+);
+
+CREATE INDEX gdsn_publications_product_id_idx ON gdsn_publications (product_id);
@@ -0,0 +1,28 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+DROP TABLE mfg_batch_expiry;
+DROP TABLE mfg_batch_projection_checkpoint;
+DROP TABLE mfg_batch_alias;
+DROP TABLE mfg_batch_shared_with;
+DROP TABLE mfg_batch_duplicate;
+DROP TABLE mfg_batch_quality_score;
+DROP TABLE mfg_batch_test_result;
+DROP TABLE mfg_batch_annotation;
+DROP TABLE mfg_batch_change;
+DROP TABLE mfg_batch_audit_log;
+DROP TABLE mfg_batch_parent;
+DROP TABLE mfg_batch_property_value;
+DROP TABLE mfg_batch;
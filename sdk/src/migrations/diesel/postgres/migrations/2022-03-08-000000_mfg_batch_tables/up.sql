@@ -0,0 +1,193 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- mfg_batch and mfg_batch_property_value are partitioned by end_commit_num. Current rows, which
+-- end at the maximum commit number, are kept in their own partition, so that current-state
+-- queries are pruned to it. Ended rows fall into the history partition, unless the archive
+-- partition covering their commit has been created through the store.
+CREATE TABLE mfg_batch (
+    id BIGSERIAL,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    mfg_batch_address VARCHAR(70) NOT NULL,
+    mfg_batch_namespace TEXT NOT NULL,
+    owner VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    quantity BIGINT,
+    uom TEXT,
+    expected_quantity BIGINT,
+    production_date BIGINT,
+    expiration_date BIGINT,
+    checksum TEXT,
+    archived BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (id, end_commit_num)
+) PARTITION BY RANGE (end_commit_num);
+
+CREATE TABLE mfg_batch_current PARTITION OF mfg_batch
+    FOR VALUES FROM (9223372036854775807) TO (MAXVALUE);
+CREATE TABLE mfg_batch_history PARTITION OF mfg_batch DEFAULT;
+
+CREATE INDEX mfg_batch_mfg_batch_id_idx ON mfg_batch (mfg_batch_id, end_commit_num);
+CREATE INDEX mfg_batch_owner_idx ON mfg_batch (owner);
+CREATE INDEX mfg_batch_last_updated_idx ON mfg_batch (last_updated);
+CREATE INDEX mfg_batch_expiration_date_idx ON mfg_batch (expiration_date);
+
+CREATE TABLE mfg_batch_property_value (
+    id BIGSERIAL,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    mfg_batch_address VARCHAR(70) NOT NULL,
+    property_name TEXT NOT NULL,
+    parent_property TEXT,
+    data_type TEXT NOT NULL,
+    bytes_value BYTEA,
+    boolean_value BOOLEAN,
+    number_value BIGINT,
+    string_value TEXT,
+    enum_value INTEGER,
+    latitude_value BIGINT,
+    longitude_value BIGINT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT,
+    PRIMARY KEY (id, end_commit_num)
+) PARTITION BY RANGE (end_commit_num);
+
+CREATE TABLE mfg_batch_property_value_current PARTITION OF mfg_batch_property_value
+    FOR VALUES FROM (9223372036854775807) TO (MAXVALUE);
+CREATE TABLE mfg_batch_property_value_history PARTITION OF mfg_batch_property_value DEFAULT;
+
+CREATE INDEX mfg_batch_property_value_mfg_batch_id_idx
+    ON mfg_batch_property_value (mfg_batch_id, end_commit_num);
+
+CREATE TABLE mfg_batch_parent (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    parent_mfg_batch_id VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_parent_mfg_batch_id_idx ON mfg_batch_parent (mfg_batch_id);
+CREATE INDEX mfg_batch_parent_parent_mfg_batch_id_idx ON mfg_batch_parent (parent_mfg_batch_id);
+
+CREATE TABLE mfg_batch_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    commit_num BIGINT NOT NULL,
+    service_id TEXT,
+    record_hash TEXT NOT NULL,
+    previous_hash TEXT NOT NULL,
+    entry_hash TEXT NOT NULL
+);
+
+CREATE INDEX mfg_batch_audit_log_mfg_batch_id_idx ON mfg_batch_audit_log (mfg_batch_id);
+
+CREATE TABLE mfg_batch_change (
+    id BIGSERIAL PRIMARY KEY,
+    entity TEXT NOT NULL,
+    entity_id VARCHAR(256) NOT NULL,
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE TABLE mfg_batch_annotation (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    commit_num BIGINT NOT NULL,
+    property_name TEXT NOT NULL,
+    author TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_annotation_mfg_batch_id_idx ON mfg_batch_annotation (mfg_batch_id);
+
+CREATE TABLE mfg_batch_test_result (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    commit_num BIGINT NOT NULL,
+    test_name TEXT NOT NULL,
+    specification TEXT NOT NULL,
+    result TEXT NOT NULL,
+    passed BOOLEAN NOT NULL,
+    lab TEXT NOT NULL,
+    tested_at BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_test_result_mfg_batch_id_idx ON mfg_batch_test_result (mfg_batch_id);
+
+CREATE TABLE mfg_batch_quality_score (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    commit_num BIGINT NOT NULL,
+    score INTEGER NOT NULL,
+    failed_checks TEXT NOT NULL,
+    scored_at BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_quality_score_mfg_batch_id_idx ON mfg_batch_quality_score (mfg_batch_id);
+
+CREATE TABLE mfg_batch_duplicate (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    duplicate_of VARCHAR(256) NOT NULL,
+    rule TEXT NOT NULL,
+    similarity INTEGER NOT NULL,
+    detected_at BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_duplicate_mfg_batch_id_idx ON mfg_batch_duplicate (mfg_batch_id);
+
+CREATE TABLE mfg_batch_shared_with (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_shared_with_mfg_batch_id_idx ON mfg_batch_shared_with (mfg_batch_id, org_id);
+
+CREATE TABLE mfg_batch_alias (
+    id BIGSERIAL PRIMARY KEY,
+    alias VARCHAR(256) NOT NULL,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    merged_at BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_alias_alias_idx ON mfg_batch_alias (alias);
+
+CREATE TABLE mfg_batch_projection_checkpoint (
+    projection_name TEXT PRIMARY KEY,
+    change_id BIGINT NOT NULL
+);
+
+CREATE TABLE mfg_batch_expiry (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    service_id TEXT,
+    expiration_date BIGINT NOT NULL
+);
+
+CREATE INDEX mfg_batch_expiry_expiration_date_idx ON mfg_batch_expiry (expiration_date);
@@ -0,0 +1,30 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Merkle roots of the mfg_batch records anchored on chain. commit_num is the commit the root was
+-- computed at, and anchored_commit_num the commit the anchor itself was recorded in.
+CREATE TABLE mfg_batch_anchor (
+    id BIGSERIAL PRIMARY KEY,
+    owner VARCHAR(256) NOT NULL,
+    commit_num BIGINT NOT NULL,
+    merkle_root VARCHAR(64) NOT NULL,
+    row_count BIGINT NOT NULL,
+    anchor_address VARCHAR(70) NOT NULL,
+    anchored_commit_num BIGINT NOT NULL,
+    anchored_at BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_anchor_commit_num_idx ON mfg_batch_anchor (commit_num);
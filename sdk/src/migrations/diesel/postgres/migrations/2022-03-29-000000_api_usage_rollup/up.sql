@@ -0,0 +1,33 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Requests let through with an API key, rolled up by key, method, endpoint and UTC day. day is
+-- the start of the day in seconds since the epoch.
+CREATE TABLE api_usage_rollup (
+    day BIGINT NOT NULL,
+    key_id TEXT NOT NULL,
+    org_id TEXT NOT NULL,
+    method TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    requests BIGINT NOT NULL,
+    rows_returned BIGINT NOT NULL,
+    submissions BIGINT NOT NULL,
+    PRIMARY KEY (day, key_id, method, endpoint),
+    FOREIGN KEY (key_id) REFERENCES api_keys(key_id) ON DELETE CASCADE
+);
+
+CREATE INDEX api_usage_rollup_org_id_idx ON api_usage_rollup (org_id, day);
+
+CREATE INDEX api_key_usage_used_at_idx ON api_key_usage (used_at);
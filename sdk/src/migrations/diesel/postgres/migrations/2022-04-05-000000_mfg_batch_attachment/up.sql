@@ -0,0 +1,30 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Documents kept off-chain that a mfg_batch references by their SHA-256 digest. Like a
+-- mfg_batch's parents, attachments are versioned by commit and replaced as a set.
+CREATE TABLE mfg_batch_attachment (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    digest VARCHAR(64) NOT NULL,
+    mime_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    uri TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_attachment_mfg_batch_id_idx ON mfg_batch_attachment (mfg_batch_id);
@@ -0,0 +1,26 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- The quantities of each mfg_batch allocated to sales orders
+CREATE TABLE mfg_batch_allocation (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    order_id VARCHAR(256) NOT NULL,
+    quantity BIGINT NOT NULL,
+    commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_allocation_mfg_batch_id_idx ON mfg_batch_allocation (mfg_batch_id);
@@ -0,0 +1,20 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- The GLN of the location a mfg_batch was manufactured at
+ALTER TABLE mfg_batch
+ADD COLUMN manufacture_location TEXT;
+
+CREATE INDEX mfg_batch_manufacture_location_idx ON mfg_batch (manufacture_location);
@@ -0,0 +1,29 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- The mfg_batches flagged by each recall, and when the recall was closed out
+CREATE TABLE mfg_batch_recall (
+    id BIGSERIAL PRIMARY KEY,
+    recall_id VARCHAR(256) NOT NULL,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    reason_code TEXT NOT NULL,
+    recalled_at BIGINT NOT NULL,
+    closed_at BIGINT,
+    commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+
+CREATE INDEX mfg_batch_recall_recall_id_idx ON mfg_batch_recall (recall_id);
+CREATE INDEX mfg_batch_recall_mfg_batch_id_idx ON mfg_batch_recall (mfg_batch_id);
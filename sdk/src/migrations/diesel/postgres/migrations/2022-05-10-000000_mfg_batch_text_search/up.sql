@@ -0,0 +1,34 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Full-text search document over the current string property values of each mfg_batch, kept up
+-- to date by the store when it is built with text search. SQLite has no equivalent and scans
+-- the property values instead.
+CREATE TABLE mfg_batch_text_search (
+    id BIGSERIAL PRIMARY KEY,
+    mfg_batch_id VARCHAR(256) NOT NULL,
+    service_id TEXT,
+    document TSVECTOR NOT NULL
+);
+
+CREATE INDEX mfg_batch_text_search_mfg_batch_id_idx ON mfg_batch_text_search (mfg_batch_id);
+CREATE INDEX mfg_batch_text_search_document_idx ON mfg_batch_text_search USING GIN (document);
+
+-- Build the documents of the mfg_batches already stored, as the store's reindex would
+INSERT INTO mfg_batch_text_search (mfg_batch_id, service_id, document)
+SELECT mfg_batch_id, service_id, to_tsvector('simple', string_agg(string_value, ' '))
+FROM mfg_batch_property_value
+WHERE end_commit_num = 9223372036854775807 AND string_value IS NOT NULL
+GROUP BY mfg_batch_id, service_id;
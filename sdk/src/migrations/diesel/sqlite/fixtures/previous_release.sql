@@ -0,0 +1,522 @@
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE __diesel_schema_migrations (version VARCHAR(50) PRIMARY KEY NOT NULL,run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP);
+INSERT INTO __diesel_schema_migrations VALUES('20200928165102','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20210205160000','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20210324130200','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20210330164015','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20210423151946','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20210722161800','2022-01-17 10:00:00');
+INSERT INTO __diesel_schema_migrations VALUES('20220117100000','2022-01-17 10:00:00');
+CREATE TABLE commits (
+    id INTEGER PRIMARY KEY,
+    commit_id VARCHAR(128),
+    commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+INSERT INTO commits VALUES(1,'commit-1',1,NULL);
+INSERT INTO commits VALUES(2,'commit-2',2,NULL);
+INSERT INTO commits VALUES(3,'commit-3',3,NULL);
+CREATE TABLE chain_record (
+    id INTEGER PRIMARY KEY,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE associated_agent (
+    id INTEGER PRIMARY KEY,
+    record_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    agent_id TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE property (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    property_definition TEXT NOT NULL,
+    current_page INTEGER NOT NULL,
+    wrapped BOOLEAN NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE proposal (
+    id INTEGER PRIMARY KEY,
+    record_id TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    issuing_agent TEXT NOT NULL,
+    receiving_agent TEXT NOT NULL,
+    role TEXT NOT NULL,
+    properties TEXT NOT NULL,
+    status TEXT NOT NULL,
+    terms TEXT NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE record (
+    id INTEGER PRIMARY KEY,
+    record_id TEXT NOT NULL,
+    schema TEXT NOT NULL,
+    final BOOL NOT NULL,
+    owners TEXT NOT NULL,
+    custodians TEXT NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE reported_value (
+    id INTEGER PRIMARY KEY,
+    property_name TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    reporter_index INTEGER NOT NULL,
+    timestamp BIGINT NOT NULL,
+    data_type TEXT NOT NULL,
+    bytes_value BYTEA,
+    boolean_value BOOLEAN,
+    number_value BIGINT,
+    string_value TEXT,
+    enum_value INTEGER,
+    parent_name TEXT,
+    latitude_value BIGINT,
+    longitude_value BIGINT,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE reporter (
+    id INTEGER PRIMARY KEY,
+    property_name TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    authorized BOOLEAN NOT NULL,
+    reporter_index INTEGER NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+CREATE TABLE grid_schema (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO grid_schema VALUES(1,'gs1_product','GS1 product properties','314159',NULL,2,9223372036854775807,'2022-01-17 10:00:00');
+CREATE TABLE grid_property_definition (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    schema_name TEXT NOT NULL,
+    data_type TEXT NOT NULL,
+    required BOOLEAN NOT NULL,
+    description TEXT NOT NULL,
+    number_exponent BIGINT NOT NULL,
+    enum_options TEXT NOT NULL,
+    parent_name TEXT,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+INSERT INTO grid_property_definition VALUES(1,'product_name','gs1_product','String',1,'The product''s name',0,'',NULL,NULL,2,9223372036854775807);
+CREATE TABLE product (
+    id INTEGER PRIMARY KEY,
+    product_id VARCHAR(256) NOT NULL,
+    product_address VARCHAR(70) NOT NULL,
+    product_namespace TEXT NOT NULL,
+    owner VARCHAR(256) NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO product VALUES(1,'762111177704','621dee0201000000000000000000000000000000000000762111177704','GS1','314159',NULL,3,9223372036854775807,'2022-01-17 10:00:00');
+CREATE TABLE product_property_value (
+    id INTEGER PRIMARY KEY,
+    product_id VARCHAR(256) NOT NULL,
+    product_address VARCHAR(70) NOT NULL,
+    property_name TEXT NOT NULL,
+    parent_property TEXT,
+    data_type TEXT NOT NULL,
+    bytes_value BYTEA,
+    number_value BIGINT,
+    boolean_value BOOLEAN,
+    string_value TEXT,
+    enum_value INTEGER,
+    latitude_value BIGINT,
+    longitude_value BIGINT,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+INSERT INTO product_property_value VALUES(1,'762111177704','621dee0201000000000000000000000000000000000000762111177704','product_name',NULL,'String',NULL,NULL,NULL,'Chocolate Bar',NULL,NULL,NULL,NULL,3,9223372036854775807);
+CREATE TABLE location (
+    id INTEGER PRIMARY KEY,
+    location_id VARCHAR(256) NOT NULL,
+    location_address VARCHAR(70) NOT NULL,
+    location_namespace TEXT NOT NULL,
+    owner VARCHAR(256) NOT NULL,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO location VALUES(1,'0653114000000','621dee04010000000000000000000000000000000000000065311400000000','GS1','314159',NULL,3,9223372036854775807,'2022-01-17 10:00:00');
+CREATE TABLE location_attribute (
+    id INTEGER PRIMARY KEY,
+    location_id VARCHAR(256) NOT NULL,
+    location_address VARCHAR(70) NOT NULL,
+    property_name TEXT NOT NULL,
+    parent_property_name TEXT,
+    data_type TEXT NOT NULL,
+    bytes_value BYTEA,
+    boolean_value BOOLEAN,
+    number_value BIGINT,
+    string_value TEXT,
+    enum_value INTEGER,
+    latitude_value BIGINT,
+    longitude_value BIGINT,
+    service_id TEXT,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL
+);
+INSERT INTO location_attribute VALUES(1,'0653114000000','621dee04010000000000000000000000000000000000000065311400000000','locationName',NULL,'String',NULL,NULL,NULL,'Main Warehouse',NULL,NULL,NULL,NULL,3,9223372036854775807);
+CREATE TABLE pike_agent (
+    id INTEGER PRIMARY KEY,
+    state_address VARCHAR(70) NOT NULL,
+    public_key VARCHAR(70) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    active BOOLEAN NOT NULL,
+    metadata BYTEA NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO pike_agent VALUES(1,'621dee0500e34e367f57b2ce46e90899a2f0064c558d05bfbf4e27c9e558ca9d00b999','02a8c2b1f4b1a2c8e2f0e0f6d1a7b0c3e4d5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0','314159',1,X'77617265686f757365207363616e6e6572',1,9223372036854775807,NULL,'2022-01-17 10:00:00');
+CREATE TABLE pike_organization (
+    id INTEGER PRIMARY KEY,
+    state_address VARCHAR(70) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO pike_organization VALUES(1,'621dee0501e6e7fc4c7bd28ffa272b53b028d2e12db105a90b584d5906107b355eea67','314159','Acme Foods',1,9223372036854775807,NULL,'2022-01-17 10:00:00');
+CREATE TABLE pike_agent_role_assoc (
+    id INTEGER PRIMARY KEY,
+    agent_public_key VARCHAR(70) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    role_name VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+INSERT INTO pike_agent_role_assoc VALUES(1,'02a8c2b1f4b1a2c8e2f0e0f6d1a7b0c3e4d5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0','314159','product_owner',1,9223372036854775807,NULL);
+CREATE TABLE pike_role (
+    id INTEGER PRIMARY KEY,
+    state_address VARCHAR(70) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    name VARCHAR NOT NULL,
+    description TEXT NOT NULL,
+    active BOOLEAN NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+, last_updated TIMESTAMP DEFAULT NULL);
+INSERT INTO pike_role VALUES(1,'621dee0502458741bbd60fbddbaa09a4b365fb6b86ae09e2dc8a40b3927037d1b399df','314159','product_owner','Manages the organization''s products',1,1,9223372036854775807,NULL,'2022-01-17 10:00:00');
+CREATE TABLE pike_organization_metadata (
+    id INTEGER PRIMARY KEY,
+    org_id VARCHAR(256) NOT NULL,
+    key VARCHAR NOT NULL,
+    value BYTEA NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE pike_organization_alternate_id (
+    id INTEGER PRIMARY KEY,
+    org_id VARCHAR(256) NOT NULL,
+    alternate_id_type VARCHAR NOT NULL,
+    alternate_id VARCHAR NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE pike_organization_location_assoc (
+    id INTEGER PRIMARY KEY,
+    org_id VARCHAR(256) NOT NULL,
+    location_id VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+INSERT INTO pike_organization_location_assoc VALUES(1,'314159','0653114000000',1,9223372036854775807,NULL);
+CREATE TABLE pike_inherit_from (
+    id INTEGER PRIMARY KEY,
+    role_name VARCHAR(256) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    inherit_from_role_name VARCHAR(256) NOT NULL,
+    inherit_from_org_id VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE pike_permissions (
+    id INTEGER PRIMARY KEY,
+    role_name VARCHAR(256) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+INSERT INTO pike_permissions VALUES(1,'product_owner','314159','can_create_product',1,9223372036854775807,NULL);
+CREATE TABLE pike_allowed_orgs (
+    id INTEGER PRIMARY KEY,
+    role_name VARCHAR(256) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    allowed_org_id VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE pike_role_state_address_assoc (
+    id INTEGER PRIMARY KEY,
+    state_address VARCHAR(70) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    org_id VARCHAR(256) NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+INSERT INTO pike_role_state_address_assoc VALUES(1,'621dee0502458741bbd60fbddbaa09a4b365fb6b86ae09e2dc8a40b3927037d1b399df','product_owner','314159',1,9223372036854775807,NULL);
+CREATE TABLE batches (
+    header_signature TEXT PRIMARY KEY,
+    data_change_id TEXT,
+    signer_public_key TEXT NOT NULL,
+    trace BOOLEAN NOT NULL,
+    serialized_batch TEXT NOT NULL,
+    submitted BOOLEAN NOT NULL,
+    submission_error VARCHAR(16),
+    submission_error_message TEXT,
+    dlt_status VARCHAR(16),
+    claim_expires DATETIME,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP,
+    service_id TEXT
+);
+CREATE TABLE transactions (
+    header_signature TEXT PRIMARY KEY,
+    batch_id TEXT NOT NULL,
+    family_name TEXT NOT NULL,
+    family_version TEXT NOT NULL,
+    signer_public_key TEXT NOT NULL,
+    FOREIGN KEY (batch_id) REFERENCES batches(header_signature) ON DELETE CASCADE
+);
+CREATE TABLE transaction_receipts (
+    id INTEGER PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    result_valid BOOLEAN NOT NULL,
+    error_message TEXT,
+    error_data TEXT,
+    serialized_receipt TEXT NOT NULL,
+    external_status VARCHAR(16),
+    external_error_message TEXT
+);
+CREATE TABLE IF NOT EXISTS "purchase_order"(
+    id INTEGER PRIMARY KEY,
+    purchase_order_uid TEXT NOT NULL,
+    workflow_state TEXT NOT NULL,
+    buyer_org_id VARCHAR(256) NOT NULL,
+    seller_org_id VARCHAR(256) NOT NULL,
+    is_closed BOOLEAN NOT NULL,
+    accepted_version_id TEXT,
+    created_at BIGINT NOT NULL,
+    workflow_id TEXT NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE IF NOT EXISTS "purchase_order_version"(
+    id INTEGER PRIMARY KEY,
+    purchase_order_uid TEXT NOT NULL,
+    version_id TEXT NOT NULL,
+    is_draft BOOLEAN NOT NULL,
+    current_revision_id BIGINT NOT NULL,
+    workflow_state TEXT NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE IF NOT EXISTS "purchase_order_version_revision"(
+    id INTEGER PRIMARY KEY,
+    purchase_order_uid TEXT NOT NULL,
+    version_id TEXT NOT NULL,
+    revision_id BIGINT NOT NULL,
+    order_xml_v3_4 TEXT NOT NULL,
+    submitter TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE TABLE IF NOT EXISTS "purchase_order_alternate_id"(
+    id INTEGER PRIMARY KEY,
+    purchase_order_uid TEXT NOT NULL,
+    alternate_id_type TEXT NOT NULL,
+    alternate_id TEXT NOT NULL,
+    start_commit_num BIGINT NOT NULL,
+    end_commit_num BIGINT NOT NULL,
+    service_id TEXT
+);
+CREATE VIEW reporter_to_agent_metadata
+AS
+  SELECT id,
+         property_name,
+         record_id,
+         public_key,
+         authorized,
+         reporter_index,
+         metadata,
+         service_id,
+         reporter_end_commit_num
+  FROM   (SELECT Row_number()
+                   OVER (
+                     partition BY id
+                     ORDER BY agent_end_commit_num) AS RowNum,
+                 *
+          FROM   (SELECT reporter.id,
+                         reporter.property_name,
+                         reporter.record_id,
+                         reporter.reporter_index,
+                         reporter.authorized,
+                         reporter.public_key,
+                         reporter.end_commit_num AS "reporter_end_commit_num",
+                         pike_agent.end_commit_num    AS "agent_end_commit_num",
+                         pike_agent.metadata,
+                         pike_agent.service_id
+                  FROM   reporter
+                         LEFT JOIN pike_agent
+                                ON reporter.public_key = pike_agent.public_key
+                                   AND reporter.end_commit_num <=
+                                       pike_agent.end_commit_num) AS
+                 join_tables) X
+  WHERE  rownum = 1;
+CREATE VIEW reported_value_reporter_to_agent_metadata
+AS
+  SELECT id,
+         property_name,
+         record_id,
+         reporter_index,
+         timestamp,
+         data_type,
+         bytes_value,
+         boolean_value,
+         number_value,
+         string_value,
+         enum_value,
+         parent_name,
+         latitude_value,
+         longitude_value,
+         public_key,
+         authorized,
+         metadata,
+         reported_value_end_commit_num,
+         reporter_end_commit_num,
+         service_id
+  FROM   (SELECT Row_number()
+                   OVER (
+                     partition BY id
+                     ORDER BY reporter_end_commit_num) AS RowNum,
+                 *
+          FROM   (SELECT reported_value.id,
+                         reported_value.property_name,
+                         reported_value.record_id,
+                         reported_value.reporter_index,
+                         reported_value.timestamp,
+                         reported_value.data_type,
+                         reported_value.bytes_value,
+                         reported_value.boolean_value,
+                         reported_value.number_value,
+                         reported_value.string_value,
+                         reported_value.enum_value,
+                         reported_value.parent_name,
+                         reported_value.latitude_value,
+                         reported_value.longitude_value,
+                         reported_value.end_commit_num AS
+                         "reported_value_end_commit_num",
+                         reporter_to_agent_metadata.reporter_end_commit_num,
+                         reporter_to_agent_metadata.public_key,
+                         reporter_to_agent_metadata.authorized,
+                         reporter_to_agent_metadata.metadata,
+                         reported_value.service_id
+                  FROM   reported_value
+                         LEFT JOIN reporter_to_agent_metadata
+                                ON reported_value.record_id =
+                                   reporter_to_agent_metadata.record_id
+                                   AND reported_value.property_name =
+                                       reporter_to_agent_metadata.property_name
+                                   AND reported_value.reporter_index =
+                                       reporter_to_agent_metadata.reporter_index
+                                   AND reported_value.end_commit_num <=
+  reporter_to_agent_metadata.reporter_end_commit_num) AS
+  join_tables) X
+  WHERE  rownum = 1;
+CREATE TRIGGER set_pike_agent_timestamp
+AFTER INSERT ON pike_agent
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE pike_agent
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+CREATE TRIGGER set_pike_organization_timestamp
+AFTER INSERT ON pike_organization
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE pike_organization
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+CREATE TRIGGER set_pike_role_timestamp
+AFTER INSERT ON pike_role
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE pike_role
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+CREATE TRIGGER set_grid_schema_timestamp
+AFTER INSERT ON grid_schema
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE grid_schema
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+CREATE TRIGGER set_product_timestamp
+AFTER INSERT ON product
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE product
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+CREATE TRIGGER set_location_timestamp
+AFTER INSERT ON location
+FOR EACH ROW
+WHEN NEW.last_updated IS NULL
+BEGIN
+    UPDATE location
+    SET last_updated = CURRENT_TIMESTAMP
+    WHERE rowid = NEW.rowid;
+END;
+COMMIT;
@@ -0,0 +1,35 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE event_queue (
+    queue_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    commit_id TEXT NOT NULL,
+    service_id TEXT,
+    height BIGINT,
+    attempts BIGINT NOT NULL,
+    last_error TEXT NOT NULL,
+    queued_at BIGINT NOT NULL,
+    last_attempted_at BIGINT NOT NULL
+);
+
+CREATE TABLE event_queue_state_change (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    queue_id BIGINT NOT NULL REFERENCES event_queue (queue_id) ON DELETE CASCADE,
+    position BIGINT NOT NULL,
+    key TEXT NOT NULL,
+    value BLOB
+);
+
+CREATE INDEX event_queue_state_change_queue_id_idx ON event_queue_state_change (queue_id);
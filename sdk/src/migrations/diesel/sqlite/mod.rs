@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(all(test, feature = "location", feature = "product"))]
+mod upgrade_tests;
+
 #[cfg(feature = "sqlite")]
 use diesel::sqlite::SqliteConnection;
 
@@ -0,0 +1,316 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Upgrade tests for the SQLite migrations.
+//!
+//! `fixtures/previous_release.sql` is a dump of a database written by the previous release: its
+//! migrations were run, `seed_database` was used to write data through the stores, and the
+//! result was dumped. The tests load that dump, run the current migrations over it and exercise
+//! the stores against the upgraded database, so a migration that breaks existing data fails
+//! here rather than in a customer's deployment.
+//!
+//! To capture a new fixture when cutting a release, run the ignored
+//! `write_previous_release_database` test at the release with `GRID_FIXTURE_DATABASE` set to
+//! the path of a new database file, then dump it with:
+//!
+//! ```text
+//! sqlite3 "$GRID_FIXTURE_DATABASE" .dump \
+//!     | sed -E "s/'[0-9]{4}-[0-9]{2}-[0-9]{2} [0-9]{2}:[0-9]{2}:[0-9]{2}'/'2022-01-17 10:00:00'/g" \
+//!     > fixtures/previous_release.sql
+//! ```
+//!
+//! The `sed` replaces the timestamps written when the database was created, so that the fixture
+//! is the same each time it is captured.
+
+use diesel::{connection::SimpleConnection, sqlite::SqliteConnection, Connection};
+
+use crate::commits::{
+    store::{Commit, CommitStore, DieselConnectionCommitStore},
+    MAX_COMMIT_NUM,
+};
+use crate::location::store::{
+    DieselConnectionLocationStore, Location, LocationAttribute, LocationStore,
+};
+use crate::pike::store::{
+    AgentBuilder, DieselConnectionPikeStore, OrganizationBuilder, PikeStore, RoleBuilder,
+};
+use crate::product::store::{
+    DieselConnectionProductStore, ProductBuilder, ProductStore, PropertyValueBuilder,
+};
+use crate::schema::store::{DieselConnectionSchemaStore, PropertyDefinition, Schema, SchemaStore};
+
+use super::run_migrations;
+
+const PREVIOUS_RELEASE: &str = include_str!("fixtures/previous_release.sql");
+
+const ORG_ID: &str = "314159";
+const AGENT_KEY: &str = "02a8c2b1f4b1a2c8e2f0e0f6d1a7b0c3e4d5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0";
+const ROLE_NAME: &str = "product_owner";
+const SCHEMA_NAME: &str = "gs1_product";
+const PRODUCT_ID: &str = "762111177704";
+const PRODUCT_ADDRESS: &str = "621dee0201000000000000000000000000000000000000762111177704";
+const LOCATION_ID: &str = "0653114000000";
+const LOCATION_ADDRESS: &str = "621dee04010000000000000000000000000000000000000065311400000000";
+
+/// Verify that the data written by the previous release can be read through the stores after
+/// the current migrations have been run
+#[test]
+fn test_upgrade_reads_previous_release_data() -> Result<(), Box<dyn std::error::Error>> {
+    let conn = upgraded_database()?;
+
+    let commit_store = DieselConnectionCommitStore::new(&conn);
+    assert_eq!(
+        commit_store.get_current_commit_id()?,
+        Some("commit-3".to_string())
+    );
+    assert_eq!(commit_store.get_next_commit_num()?, 4);
+    assert_eq!(
+        commit_store
+            .get_commit_by_commit_num(2)?
+            .map(|commit| commit.commit_id),
+        Some("commit-2".to_string())
+    );
+
+    let pike_store = DieselConnectionPikeStore::new(&conn);
+    let org = pike_store
+        .get_organization(ORG_ID, None)?
+        .ok_or("organization missing after upgrade")?;
+    assert_eq!(org.name(), "Acme Foods");
+    assert_eq!(org.locations(), [LOCATION_ID.to_string()]);
+    assert_eq!(pike_store.list_organizations(None, 0, 100)?.data.len(), 1);
+    let agent = pike_store
+        .get_agent(AGENT_KEY, None)?
+        .ok_or("agent missing after upgrade")?;
+    assert_eq!(agent.org_id(), ORG_ID);
+    assert_eq!(agent.metadata(), b"warehouse scanner");
+    assert_eq!(agent.roles(), [ROLE_NAME.to_string()]);
+    assert_eq!(pike_store.list_agents(None, 0, 100)?.data.len(), 1);
+    let role = pike_store
+        .get_role(ROLE_NAME, ORG_ID, None)?
+        .ok_or("role missing after upgrade")?;
+    assert_eq!(role.permissions(), ["can_create_product".to_string()]);
+    assert_eq!(
+        pike_store
+            .list_roles_for_organization(ORG_ID, None, 0, 100)?
+            .data
+            .len(),
+        1
+    );
+
+    let schema_store = DieselConnectionSchemaStore::new(&conn);
+    let schema = schema_store
+        .get_schema(SCHEMA_NAME, None)?
+        .ok_or("schema missing after upgrade")?;
+    assert_eq!(schema.owner, ORG_ID);
+    assert_eq!(schema.properties.len(), 1);
+    assert_eq!(schema_store.list_schemas(None, 0, 100)?.data.len(), 1);
+
+    let product_store = DieselConnectionProductStore::new(&conn);
+    let product = product_store
+        .get_product(PRODUCT_ID, None)?
+        .ok_or("product missing after upgrade")?;
+    assert_eq!(product.owner(), ORG_ID);
+    assert_eq!(
+        product.properties()[0].string_value(),
+        Some("Chocolate Bar")
+    );
+    assert_eq!(product_store.list_products(None, 0, 100)?.data().len(), 1);
+
+    let location_store = DieselConnectionLocationStore::new(&conn);
+    let location = location_store
+        .get_location(LOCATION_ID, None)?
+        .ok_or("location missing after upgrade")?;
+    assert_eq!(location.owner, ORG_ID);
+    assert_eq!(location.attributes.len(), 1);
+    assert_eq!(location_store.list_locations(None, 0, 100)?.data.len(), 1);
+
+    Ok(())
+}
+
+/// Verify that new commits can be written over the previous release's data after the current
+/// migrations have been run
+#[test]
+fn test_upgrade_writes_over_previous_release_data() -> Result<(), Box<dyn std::error::Error>> {
+    let conn = upgraded_database()?;
+
+    DieselConnectionCommitStore::new(&conn).add_commit(Commit {
+        commit_id: "commit-4".to_string(),
+        commit_num: 4,
+        service_id: None,
+    })?;
+
+    let product_store = DieselConnectionProductStore::new(&conn);
+    product_store.add_product(product(4, "Dark Chocolate Bar")?)?;
+    let product = product_store
+        .get_product(PRODUCT_ID, None)?
+        .ok_or("product missing after update")?;
+    assert_eq!(
+        product.properties()[0].string_value(),
+        Some("Dark Chocolate Bar")
+    );
+
+    let location_store = DieselConnectionLocationStore::new(&conn);
+    location_store.delete_location(LOCATION_ADDRESS, 4)?;
+    assert!(location_store.get_location(LOCATION_ID, None)?.is_none());
+
+    Ok(())
+}
+
+/// Writes the fixture's data to the database at `GRID_FIXTURE_DATABASE`; see the module
+/// documentation
+#[test]
+#[ignore]
+fn write_previous_release_database() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::var("GRID_FIXTURE_DATABASE")?;
+    let conn = SqliteConnection::establish(&path)?;
+
+    run_migrations(&conn)?;
+    seed_database(&conn)
+}
+
+/// Loads the previous release's database into memory and runs the current migrations over it
+fn upgraded_database() -> Result<SqliteConnection, Box<dyn std::error::Error>> {
+    let conn = SqliteConnection::establish(":memory:")?;
+    conn.batch_execute(PREVIOUS_RELEASE)?;
+
+    run_migrations(&conn)?;
+
+    Ok(conn)
+}
+
+/// Writes one of each kind of record through the stores, over three commits
+fn seed_database(conn: &SqliteConnection) -> Result<(), Box<dyn std::error::Error>> {
+    let commit_store = DieselConnectionCommitStore::new(conn);
+    for commit_num in 1..=3 {
+        commit_store.add_commit(Commit {
+            commit_id: format!("commit-{}", commit_num),
+            commit_num,
+            service_id: None,
+        })?;
+    }
+
+    let pike_store = DieselConnectionPikeStore::new(conn);
+    pike_store.add_organization(
+        OrganizationBuilder::new()
+            .with_org_id(ORG_ID.to_string())
+            .with_name("Acme Foods".to_string())
+            .with_locations(vec![LOCATION_ID.to_string()])
+            .with_alternate_ids(vec![])
+            .with_metadata(vec![])
+            .with_start_commit_num(1)
+            .with_end_commit_num(MAX_COMMIT_NUM)
+            .build()?,
+    )?;
+    pike_store.add_role(
+        RoleBuilder::new()
+            .with_name(ROLE_NAME.to_string())
+            .with_org_id(ORG_ID.to_string())
+            .with_description("Manages the organization's products".to_string())
+            .with_active(true)
+            .with_permissions(vec!["can_create_product".to_string()])
+            .with_allowed_organizations(vec![])
+            .with_inherit_from(vec![])
+            .with_start_commit_num(1)
+            .with_end_commit_num(MAX_COMMIT_NUM)
+            .build()?,
+    )?;
+    pike_store.add_agent(
+        AgentBuilder::new()
+            .with_public_key(AGENT_KEY.to_string())
+            .with_org_id(ORG_ID.to_string())
+            .with_active(true)
+            .with_metadata(b"warehouse scanner".to_vec())
+            .with_roles(vec![ROLE_NAME.to_string()])
+            .with_start_commit_num(1)
+            .with_end_commit_num(MAX_COMMIT_NUM)
+            .build()?,
+    )?;
+
+    DieselConnectionSchemaStore::new(conn).add_schema(Schema {
+        name: SCHEMA_NAME.to_string(),
+        description: "GS1 product properties".to_string(),
+        owner: ORG_ID.to_string(),
+        properties: vec![PropertyDefinition {
+            start_commit_num: 2,
+            end_commit_num: MAX_COMMIT_NUM,
+            name: "product_name".to_string(),
+            schema_name: SCHEMA_NAME.to_string(),
+            data_type: "String".to_string(),
+            required: true,
+            description: "The product's name".to_string(),
+            number_exponent: 0,
+            enum_options: vec![],
+            struct_properties: vec![],
+            service_id: None,
+        }],
+        service_id: None,
+        start_commit_num: 2,
+        end_commit_num: MAX_COMMIT_NUM,
+        last_updated: None,
+    })?;
+
+    DieselConnectionProductStore::new(conn).add_product(product(3, "Chocolate Bar")?)?;
+
+    DieselConnectionLocationStore::new(conn).add_location(Location {
+        location_id: LOCATION_ID.to_string(),
+        location_address: LOCATION_ADDRESS.to_string(),
+        location_namespace: "GS1".to_string(),
+        owner: ORG_ID.to_string(),
+        attributes: vec![LocationAttribute {
+            location_id: LOCATION_ID.to_string(),
+            location_address: LOCATION_ADDRESS.to_string(),
+            property_name: "locationName".to_string(),
+            data_type: "String".to_string(),
+            bytes_value: None,
+            boolean_value: None,
+            number_value: None,
+            string_value: Some("Main Warehouse".to_string()),
+            enum_value: None,
+            struct_values: None,
+            lat_long_value: None,
+            start_commit_num: 3,
+            end_commit_num: MAX_COMMIT_NUM,
+            service_id: None,
+        }],
+        start_commit_num: 3,
+        end_commit_num: MAX_COMMIT_NUM,
+        service_id: None,
+        last_updated: None,
+    })?;
+
+    Ok(())
+}
+
+fn product(
+    commit_num: i64,
+    product_name: &str,
+) -> Result<crate::product::store::Product, Box<dyn std::error::Error>> {
+    Ok(ProductBuilder::default()
+        .with_product_id(PRODUCT_ID.to_string())
+        .with_product_address(PRODUCT_ADDRESS.to_string())
+        .with_product_namespace("GS1".to_string())
+        .with_owner(ORG_ID.to_string())
+        .with_start_commit_number(commit_num)
+        .with_end_commit_number(MAX_COMMIT_NUM)
+        .with_properties(vec![PropertyValueBuilder::default()
+            .with_product_id(PRODUCT_ID.to_string())
+            .with_product_address(PRODUCT_ADDRESS.to_string())
+            .with_property_name("product_name".to_string())
+            .with_data_type("String".to_string())
+            .with_string_value(Some(product_name.to_string()))
+            .with_start_commit_number(commit_num)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()?])
+        .build()?)
+}
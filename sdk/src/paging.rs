@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp;
+
 #[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct Paging {
     pub offset: i64,
@@ -27,4 +29,85 @@ impl Paging {
             total,
         }
     }
+
+    /// Returns the number of pages of `limit` records needed to hold `total` records
+    pub fn total_pages(&self) -> i64 {
+        if self.limit <= 0 {
+            return 0;
+        }
+
+        (self.total + self.limit - 1) / self.limit
+    }
+
+    /// Returns the offset of the last page of records
+    pub fn last_offset(&self) -> i64 {
+        if self.limit <= 0 {
+            return 0;
+        }
+
+        cmp::max(((self.total - 1) / self.limit) * self.limit, 0)
+    }
+
+    /// Returns the offset of the previous page of records, if there is one
+    pub fn prev_offset(&self) -> Option<i64> {
+        if self.offset == 0 {
+            // There is no previous page if we're at the beginning
+            None
+        } else if self.offset > self.total {
+            // Default to the last page if we've passed the end of the list
+            Some(self.last_offset())
+        } else {
+            // Calculate the previous page normally using increments of the limit
+            Some(cmp::max(self.offset - self.limit, 0))
+        }
+    }
+
+    /// Returns the offset of the next page of records, if there is one
+    pub fn next_offset(&self) -> Option<i64> {
+        let last_offset = self.last_offset();
+
+        if self.offset >= last_offset {
+            // There is no next page if we're on or further than the last page
+            None
+        } else if self.offset + self.limit > last_offset {
+            // Default to the last page if we're about to hit the end of the list
+            Some(last_offset)
+        } else {
+            // Calculate the next page normally using increments of the limit
+            Some(self.offset + self.limit)
+        }
+    }
+
+    /// Fills a URL template's `{offset}` and `{limit}` placeholders for the page at `offset`
+    pub fn page_url(&self, template: &str, offset: i64) -> String {
+        template
+            .replace("{offset}", &offset.to_string())
+            .replace("{limit}", &self.limit.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_pages() {
+        assert_eq!(Paging::new(0, 10, 0).total_pages(), 0);
+        assert_eq!(Paging::new(0, 10, 10).total_pages(), 1);
+        assert_eq!(Paging::new(0, 10, 11).total_pages(), 2);
+        assert_eq!(Paging::new(0, 0, 11).total_pages(), 0);
+    }
+
+    #[test]
+    fn test_page_url() {
+        let paging = Paging::new(20, 10, 80);
+
+        assert_eq!(
+            paging.page_url(
+                "/mfg_batch?offset={offset}&limit={limit}",
+                paging.next_offset().unwrap()
+            ),
+            "/mfg_batch?offset=30&limit=10"
+        );
+    }
 }
@@ -60,6 +60,20 @@ impl From<crate::protocol::schema::state::PropertyValueBuildError> for ProductGd
     }
 }
 
+#[cfg(feature = "product-gdsn-publication")]
+impl From<crate::data_validation::DataValidationError> for ProductGdsnError {
+    fn from(err: crate::data_validation::DataValidationError) -> Self {
+        match err {
+            crate::data_validation::DataValidationError::Internal(err) => {
+                ProductGdsnError::Internal(err)
+            }
+            crate::data_validation::DataValidationError::InvalidArgument(err) => {
+                ProductGdsnError::InvalidArgument(err)
+            }
+        }
+    }
+}
+
 impl From<crate::protocol::errors::BuilderError> for ProductGdsnError {
     fn from(err: crate::protocol::errors::BuilderError) -> Self {
         ProductGdsnError::Internal(InternalError::from_source(Box::new(err)))
@@ -19,6 +19,8 @@
 //! resulting `TradeItem` structs can be converted into Grid Product transaction
 //! payloads.
 mod error;
+#[cfg(feature = "product-gdsn-publication")]
+pub mod publication;
 
 use std::io::{Cursor, Read};
 
@@ -123,14 +125,30 @@ pub fn get_trade_items_from_xml(path: &str) -> Result<Vec<TradeItem>, ProductGds
         ))
     })?;
 
-    let mut grid_trade_items: GridTradeItems = from_str(&xml_str).map_err(|error| {
+    read_trade_items(&xml_str, path)
+}
+
+/// Returns a vector of TradeItem objects from a string of XML containing a gridTradeItems
+/// element, as `get_trade_items_from_xml` does for a file
+///
+/// # Arguments
+///
+/// * `xml` - XML containing GDSN trade item definitions
+///
+pub fn get_trade_items_from_xml_str(xml: &str) -> Result<Vec<TradeItem>, ProductGdsnError> {
+    read_trade_items(xml, "xml")
+}
+
+/// Reads the trade items, naming `path` as the invalid argument in errors
+fn read_trade_items(xml_str: &str, path: &str) -> Result<Vec<TradeItem>, ProductGdsnError> {
+    let mut grid_trade_items: GridTradeItems = from_str(xml_str).map_err(|error| {
         ProductGdsnError::InvalidArgument(InvalidArgumentError::new(
             path.to_string(),
             error.to_string(),
         ))
     })?;
 
-    let mut reader = Reader::from_str(&xml_str);
+    let mut reader = Reader::from_str(xml_str);
     reader.trim_text(true);
     let mut buf = Vec::new();
 
@@ -0,0 +1,389 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Publishes products to a GDSN data pool.
+//!
+//! Products created from GDSN data keep their trade item in the `GDSN_3_1` property. A
+//! `CinMessage` renders those trade items into a GDSN 3.1 Catalogue Item Notification (CIN),
+//! which `validate_cin` checks against the bundled GridCatalogueItemNotification.xsd before it
+//! is sent. The data pool's responses are recorded in a `GdsnPublicationStore`.
+
+pub mod store;
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use quick_xml::escape::escape;
+
+use super::{ProductGdsnError, GDSN_3_1_PROPERTY_NAME};
+use crate::data_validation::validate_gdsn_cin_3_1;
+use crate::error::InvalidArgumentError;
+use crate::product::store::Product;
+
+const CIN_NAMESPACE: &str = "urn:gs1:gdsn:catalogue_item_notification:xsd:3";
+const SBDH_NAMESPACE: &str =
+    "http://www.unece.org/cefact/namespaces/StandardBusinessDocumentHeader";
+
+/// The longest instance identifier accepted, in characters. Each trade item is identified by
+/// the instance identifier and its GTIN, which GS1 limits to 80 characters.
+pub const MAX_INSTANCE_IDENTIFIER_LENGTH: usize = 64;
+
+/// What a data pool is asked to do with the trade items in a CIN message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocumentCommand {
+    Add,
+    ChangeByRefresh,
+    Correct,
+    Delete,
+}
+
+impl DocumentCommand {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentCommand::Add => "ADD",
+            DocumentCommand::ChangeByRefresh => "CHANGE_BY_REFRESH",
+            DocumentCommand::Correct => "CORRECT",
+            DocumentCommand::Delete => "DELETE",
+        }
+    }
+}
+
+impl FromStr for DocumentCommand {
+    type Err = ProductGdsnError;
+
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        match command {
+            "ADD" => Ok(DocumentCommand::Add),
+            "CHANGE_BY_REFRESH" => Ok(DocumentCommand::ChangeByRefresh),
+            "CORRECT" => Ok(DocumentCommand::Correct),
+            "DELETE" => Ok(DocumentCommand::Delete),
+            _ => Err(invalid(
+                "command",
+                format!("Unknown GDSN document command {}", command),
+            )),
+        }
+    }
+}
+
+/// A trade item to publish
+#[derive(Clone, Debug, PartialEq)]
+pub struct CinTradeItem {
+    pub gtin: String,
+    /// The `tradeItem` element
+    pub xml: String,
+}
+
+impl CinTradeItem {
+    /// Reads the trade item a product was created from. Only products created from GDSN data
+    /// have one.
+    pub fn from_product(product: &Product) -> Result<Self, ProductGdsnError> {
+        let xml = product
+            .properties()
+            .into_iter()
+            .find(|property| property.property_name() == GDSN_3_1_PROPERTY_NAME)
+            .and_then(|property| property.string_value().map(String::from))
+            .ok_or_else(|| {
+                invalid(
+                    product.product_id(),
+                    format!(
+                        "Product has no {} property; only products created from GDSN data \
+                         can be published",
+                        GDSN_3_1_PROPERTY_NAME
+                    ),
+                )
+            })?;
+
+        if !xml.trim_start().starts_with("<tradeItem") {
+            return Err(invalid(
+                product.product_id(),
+                format!(
+                    "{} property is not a tradeItem element",
+                    GDSN_3_1_PROPERTY_NAME
+                ),
+            ));
+        }
+
+        Ok(CinTradeItem {
+            gtin: product.product_id().to_string(),
+            xml,
+        })
+    }
+}
+
+/// A CIN message publishing trade items to a data recipient through the sender's source data
+/// pool. Parties are identified by their 13-digit GLNs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CinMessage {
+    /// Identifies the message; the data pool's responses refer back to it
+    pub instance_identifier: String,
+    /// The information provider sending the message
+    pub sender: String,
+    /// The data pool the message is sent to
+    pub source_data_pool: String,
+    /// The party the trade items are published to
+    pub data_recipient: String,
+    pub command: DocumentCommand,
+    /// Seconds since the epoch
+    pub created_at: i64,
+    pub trade_items: Vec<CinTradeItem>,
+}
+
+impl CinMessage {
+    /// Renders the message as XML
+    pub fn to_xml(&self) -> Result<String, ProductGdsnError> {
+        validate_gln("sender", &self.sender)?;
+        validate_gln("source_data_pool", &self.source_data_pool)?;
+        validate_gln("data_recipient", &self.data_recipient)?;
+        if self.instance_identifier.is_empty()
+            || self.instance_identifier.chars().count() > MAX_INSTANCE_IDENTIFIER_LENGTH
+        {
+            return Err(invalid(
+                "instance_identifier",
+                format!(
+                    "Instance identifier must be 1 to {} characters",
+                    MAX_INSTANCE_IDENTIFIER_LENGTH
+                ),
+            ));
+        }
+        if self.trade_items.is_empty() {
+            return Err(invalid(
+                "trade_items",
+                "A CIN message must publish at least one trade item".to_string(),
+            ));
+        }
+
+        let created_at = NaiveDateTime::from_timestamp_opt(self.created_at, 0)
+            .ok_or_else(|| invalid("created_at", format!("Invalid time {}", self.created_at)))?
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let instance_identifier = xml_text(&self.instance_identifier);
+
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <catalogue_item_notification:catalogueItemNotificationMessage \
+             xmlns:catalogue_item_notification=\"{cin}\" xmlns:sh=\"{sbdh}\">\
+             <sh:StandardBusinessDocumentHeader>\
+             <sh:HeaderVersion>1.0</sh:HeaderVersion>\
+             <sh:Sender><sh:Identifier Authority=\"GS1\">{sender}</sh:Identifier></sh:Sender>\
+             <sh:Receiver><sh:Identifier Authority=\"GS1\">{pool}</sh:Identifier></sh:Receiver>\
+             <sh:DocumentIdentification>\
+             <sh:Standard>GS1</sh:Standard>\
+             <sh:TypeVersion>3.1</sh:TypeVersion>\
+             <sh:InstanceIdentifier>{id}</sh:InstanceIdentifier>\
+             <sh:Type>catalogueItemNotification</sh:Type>\
+             <sh:CreationDateAndTime>{created_at}</sh:CreationDateAndTime>\
+             </sh:DocumentIdentification>\
+             </sh:StandardBusinessDocumentHeader>\
+             <transaction>\
+             {transaction_id}\
+             <documentCommand>\
+             <documentCommandHeader type=\"{command}\">{command_id}</documentCommandHeader>",
+            cin = CIN_NAMESPACE,
+            sbdh = SBDH_NAMESPACE,
+            sender = self.sender,
+            pool = self.source_data_pool,
+            id = instance_identifier,
+            created_at = created_at,
+            transaction_id = self.identification("transactionIdentification", &instance_identifier),
+            command = self.command.as_str(),
+            command_id = self.identification("documentCommandIdentification", &instance_identifier),
+        );
+
+        for trade_item in &self.trade_items {
+            let item_id = format!("{}-{}", instance_identifier, xml_text(&trade_item.gtin));
+            xml.push_str(&format!(
+                "<catalogue_item_notification:catalogueItemNotification>\
+                 <creationDateTime>{created_at}</creationDateTime>\
+                 <documentStatusCode>ORIGINAL</documentStatusCode>\
+                 {item_id}\
+                 <isReload>false</isReload>\
+                 <catalogueItem>\
+                 <dataRecipient>{recipient}</dataRecipient>\
+                 <sourceDataPool>{pool}</sourceDataPool>\
+                 {trade_item}\
+                 </catalogueItem>\
+                 </catalogue_item_notification:catalogueItemNotification>",
+                created_at = created_at,
+                item_id = self.identification("catalogueItemNotificationIdentification", &item_id),
+                recipient = self.data_recipient,
+                pool = self.source_data_pool,
+                trade_item = trade_item.xml,
+            ));
+        }
+
+        xml.push_str(
+            "</documentCommand>\
+             </transaction>\
+             </catalogue_item_notification:catalogueItemNotificationMessage>",
+        );
+
+        Ok(xml)
+    }
+
+    /// Renders an identification element owned by the sender
+    fn identification(&self, element: &str, id: &str) -> String {
+        format!(
+            "<{element}><entityIdentification>{id}</entityIdentification>\
+             <contentOwner><gln>{gln}</gln></contentOwner></{element}>",
+            element = element,
+            id = id,
+            gln = self.sender,
+        )
+    }
+}
+
+/// Checks a rendered CIN message against the GridCatalogueItemNotification.xsd schema
+///
+/// # Arguments
+///
+/// * `xml` - The rendered message
+/// * `schema_dir` - The directory containing GridCatalogueItemNotification.xsd
+pub fn validate_cin(xml: &str, schema_dir: &str) -> Result<(), ProductGdsnError> {
+    validate_gdsn_cin_3_1(xml, false, schema_dir).map_err(ProductGdsnError::from)
+}
+
+fn validate_gln(argument: &str, gln: &str) -> Result<(), ProductGdsnError> {
+    if gln.len() != 13 || !gln.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid(
+            argument,
+            format!("GLNs must be 13 digits: {}", gln),
+        ));
+    }
+    Ok(())
+}
+
+fn xml_text(text: &str) -> String {
+    String::from_utf8_lossy(&escape(text.as_bytes())).into_owned()
+}
+
+fn invalid(argument: &str, message: String) -> ProductGdsnError {
+    ProductGdsnError::InvalidArgument(InvalidArgumentError::new(argument.to_string(), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::product::store::{ProductBuilder, PropertyValueBuilder};
+
+    const GTIN: &str = "00734730437958";
+    const TRADE_ITEM: &str =
+        "<tradeItem><gtin>00734730437958</gtin><isTradeItemABaseUnit>true</isTradeItemABaseUnit>\
+         </tradeItem>";
+
+    fn schema_dir() -> String {
+        let mut schema_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        schema_dir.push("src/data_validation/xml/xsd/product");
+        schema_dir
+            .into_os_string()
+            .into_string()
+            .expect("Unable to convert product schema dir to string")
+    }
+
+    fn product(properties: Vec<(&str, &str)>) -> Product {
+        ProductBuilder::default()
+            .with_product_id(GTIN.to_string())
+            .with_product_address("product_address".to_string())
+            .with_product_namespace("Gs1".to_string())
+            .with_owner("test_org".to_string())
+            .with_start_commit_number(1)
+            .with_end_commit_number(i64::MAX)
+            .with_properties(
+                properties
+                    .into_iter()
+                    .map(|(name, value)| {
+                        PropertyValueBuilder::default()
+                            .with_product_id(GTIN.to_string())
+                            .with_product_address("product_address".to_string())
+                            .with_property_name(name.to_string())
+                            .with_data_type("String".to_string())
+                            .with_string_value(Some(value.to_string()))
+                            .with_start_commit_number(1)
+                            .with_end_commit_number(i64::MAX)
+                            .build()
+                            .expect("Failed to build property")
+                    })
+                    .collect(),
+            )
+            .build()
+            .expect("Failed to build product")
+    }
+
+    fn message(trade_items: Vec<CinTradeItem>) -> CinMessage {
+        CinMessage {
+            instance_identifier: "5f0c1a2b3c4d5e6f".to_string(),
+            sender: "0000000000000".to_string(),
+            source_data_pool: "0000000000017".to_string(),
+            data_recipient: "0000000000024".to_string(),
+            command: DocumentCommand::Add,
+            created_at: 1_645_610_400,
+            trade_items,
+        }
+    }
+
+    /// Test that the trade item a product was created from is read, and that products without
+    /// one cannot be published
+    #[test]
+    fn test_trade_item_from_product() {
+        let trade_item = CinTradeItem::from_product(&product(vec![
+            ("description", "Brisket"),
+            (GDSN_3_1_PROPERTY_NAME, TRADE_ITEM),
+        ]))
+        .expect("Failed to read trade item");
+        assert_eq!(
+            trade_item,
+            CinTradeItem {
+                gtin: GTIN.to_string(),
+                xml: TRADE_ITEM.to_string(),
+            }
+        );
+
+        assert!(CinTradeItem::from_product(&product(vec![("description", "Brisket")])).is_err());
+    }
+
+    /// Test that a rendered message validates against the bundled schema and carries the
+    /// command, parties and trade item
+    #[test]
+    fn test_render_cin() {
+        let xml = message(vec![CinTradeItem {
+            gtin: GTIN.to_string(),
+            xml: TRADE_ITEM.to_string(),
+        }])
+        .to_xml()
+        .expect("Failed to render message");
+
+        validate_cin(&xml, &schema_dir()).expect("Rendered message is invalid");
+        assert!(xml.contains("<documentCommandHeader type=\"ADD\">"));
+        assert!(xml.contains("<dataRecipient>0000000000024</dataRecipient>"));
+        assert!(xml.contains("<sh:CreationDateAndTime>2022-02-23T10:00:00Z"));
+        assert!(xml.contains(TRADE_ITEM));
+    }
+
+    /// Test that messages with malformed GLNs or no trade items are not rendered
+    #[test]
+    fn test_render_cin_invalid() {
+        let trade_item = CinTradeItem {
+            gtin: GTIN.to_string(),
+            xml: TRADE_ITEM.to_string(),
+        };
+
+        let mut invalid_recipient = message(vec![trade_item]);
+        invalid_recipient.data_recipient = "RETAILER".to_string();
+        assert!(invalid_recipient.to_xml().is_err());
+
+        assert!(message(vec![]).to_xml().is_err());
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod models;
+mod operations;
+pub(crate) mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{GdsnPublication, GdsnPublicationStore, GdsnPublicationStoreError, PublicationStatus};
+use crate::error::ResourceTemporarilyUnavailableError;
+
+use operations::add_publications::AddPublicationsOperation as _;
+use operations::list_publications::ListPublicationsOperation as _;
+use operations::update_publication_status::UpdatePublicationStatusOperation as _;
+use operations::GdsnPublicationStoreOperations;
+
+#[derive(Clone)]
+pub struct DieselGdsnPublicationStore<C: diesel::Connection + 'static> {
+    connection_pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection> DieselGdsnPublicationStore<C> {
+    pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselGdsnPublicationStore { connection_pool }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl GdsnPublicationStore for DieselGdsnPublicationStore<diesel::pg::PgConnection> {
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_publications(publications)
+    }
+
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .update_publication_status(
+            publication_id,
+            product_id,
+            status,
+            status_message,
+            updated_at,
+        )
+    }
+
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_publications(product_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl GdsnPublicationStore for DieselGdsnPublicationStore<diesel::sqlite::SqliteConnection> {
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_publications(publications)
+    }
+
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .update_publication_status(
+            publication_id,
+            product_id,
+            status,
+            status_message,
+            updated_at,
+        )
+    }
+
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            GdsnPublicationStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_publications(product_id)
+    }
+}
+
+pub struct DieselConnectionGdsnPublicationStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+}
+
+impl<'a, C> DieselConnectionGdsnPublicationStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionGdsnPublicationStore { connection }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> GdsnPublicationStore
+    for DieselConnectionGdsnPublicationStore<'a, diesel::pg::PgConnection>
+{
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).add_publications(publications)
+    }
+
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).update_publication_status(
+            publication_id,
+            product_id,
+            status,
+            status_message,
+            updated_at,
+        )
+    }
+
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).list_publications(product_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> GdsnPublicationStore
+    for DieselConnectionGdsnPublicationStore<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).add_publications(publications)
+    }
+
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).update_publication_status(
+            publication_id,
+            product_id,
+            status,
+            status_message,
+            updated_at,
+        )
+    }
+
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        GdsnPublicationStoreOperations::new(self.connection).list_publications(product_id)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+    use diesel::Connection;
+
+    use crate::migrations::run_sqlite_migrations;
+
+    fn publication(publication_id: &str, product_id: &str, created_at: i64) -> GdsnPublication {
+        GdsnPublication {
+            publication_id: publication_id.to_string(),
+            product_id: product_id.to_string(),
+            service_id: None,
+            data_recipient: "0000000000024".to_string(),
+            status: PublicationStatus::Pending,
+            status_message: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    /// Verify that publications are listed newest first and can be filtered by product, and that
+    /// a product cannot be added to the same publication twice
+    #[test]
+    fn test_add_list_publications() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionGdsnPublicationStore::new(&conn);
+
+        store
+            .add_publications(vec![
+                publication("a", "00734730437958", 10),
+                publication("a", "10036016500279", 10),
+            ])
+            .unwrap();
+        store
+            .add_publications(vec![publication("b", "00734730437958", 20)])
+            .unwrap();
+
+        assert_eq!(
+            store.list_publications(None).unwrap(),
+            vec![
+                publication("b", "00734730437958", 20),
+                publication("a", "00734730437958", 10),
+                publication("a", "10036016500279", 10),
+            ]
+        );
+        assert_eq!(
+            store.list_publications(Some("10036016500279")).unwrap(),
+            vec![publication("a", "10036016500279", 10)]
+        );
+
+        assert!(matches!(
+            store.add_publications(vec![
+                publication("c", "10036016500279", 30),
+                publication("a", "00734730437958", 30),
+            ]),
+            Err(GdsnPublicationStoreError::ConstraintViolationError(_))
+        ));
+        assert!(store
+            .list_publications(None)
+            .unwrap()
+            .iter()
+            .all(|publication| publication.publication_id != "c"));
+    }
+
+    /// Verify that the status reported for a published product is recorded, and that reporting
+    /// one for a product that was not published is an error
+    #[test]
+    fn test_update_publication_status() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionGdsnPublicationStore::new(&conn);
+
+        store
+            .add_publications(vec![publication("a", "00734730437958", 10)])
+            .unwrap();
+        store
+            .update_publication_status(
+                "a",
+                "00734730437958",
+                PublicationStatus::Rejected,
+                Some("Missing net content"),
+                15,
+            )
+            .unwrap();
+
+        assert_eq!(
+            store.list_publications(None).unwrap(),
+            vec![GdsnPublication {
+                status: PublicationStatus::Rejected,
+                status_message: Some("Missing net content".to_string()),
+                updated_at: 15,
+                ..publication("a", "00734730437958", 10)
+            }]
+        );
+        assert!(matches!(
+            store.update_publication_status(
+                "a",
+                "10036016500279",
+                PublicationStatus::Accepted,
+                None,
+                20,
+            ),
+            Err(GdsnPublicationStoreError::NotFoundError(_))
+        ));
+    }
+}
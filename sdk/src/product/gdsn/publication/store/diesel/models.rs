@@ -0,0 +1,67 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use crate::error::InternalError;
+use crate::product::gdsn::publication::store::{
+    diesel::schema::*, GdsnPublication, GdsnPublicationStoreError,
+};
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "gdsn_publications"]
+pub struct GdsnPublicationModel {
+    pub publication_id: String,
+    pub product_id: String,
+    pub service_id: Option<String>,
+    pub data_recipient: String,
+    pub status: String,
+    pub status_message: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<GdsnPublication> for GdsnPublicationModel {
+    fn from(publication: GdsnPublication) -> Self {
+        Self {
+            publication_id: publication.publication_id,
+            product_id: publication.product_id,
+            service_id: publication.service_id,
+            data_recipient: publication.data_recipient,
+            status: publication.status.to_string(),
+            status_message: publication.status_message,
+            created_at: publication.created_at,
+            updated_at: publication.updated_at,
+        }
+    }
+}
+
+impl TryFrom<GdsnPublicationModel> for GdsnPublication {
+    type Error = GdsnPublicationStoreError;
+
+    fn try_from(model: GdsnPublicationModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            publication_id: model.publication_id,
+            product_id: model.product_id,
+            service_id: model.service_id,
+            data_recipient: model.data_recipient,
+            status: model.status.parse().map_err(|err| {
+                GdsnPublicationStoreError::InternalError(InternalError::from_source(Box::new(err)))
+            })?,
+            status_message: model.status_message,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::GdsnPublicationStoreOperations;
+
+use crate::product::gdsn::publication::store::{
+    diesel::{models::GdsnPublicationModel, schema::gdsn_publications},
+    GdsnPublication, GdsnPublicationStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::product::gdsn::publication::store::diesel) trait AddPublicationsOperation {
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddPublicationsOperation for GdsnPublicationStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        let models = publications
+            .into_iter()
+            .map(GdsnPublicationModel::from)
+            .collect::<Vec<_>>();
+
+        insert_into(gdsn_publications::table)
+            .values(models)
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(GdsnPublicationStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddPublicationsOperation
+    for GdsnPublicationStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        let models = publications
+            .into_iter()
+            .map(GdsnPublicationModel::from)
+            .collect::<Vec<_>>();
+
+        // SQLite inserts the rows one at a time, so they are inserted together or not at all
+        self.conn
+            .transaction::<_, GdsnPublicationStoreError, _>(|| {
+                insert_into(gdsn_publications::table)
+                    .values(models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(GdsnPublicationStoreError::from)
+            })
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use super::GdsnPublicationStoreOperations;
+
+use crate::product::gdsn::publication::store::{
+    diesel::{models::GdsnPublicationModel, schema::gdsn_publications},
+    GdsnPublication, GdsnPublicationStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::product::gdsn::publication::store::diesel) trait ListPublicationsOperation {
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListPublicationsOperation
+    for GdsnPublicationStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        let mut query = gdsn_publications::table
+            .order((
+                gdsn_publications::created_at.desc(),
+                gdsn_publications::publication_id.asc(),
+                gdsn_publications::product_id.asc(),
+            ))
+            .into_boxed();
+        if let Some(product_id) = product_id {
+            query = query.filter(gdsn_publications::product_id.eq(product_id));
+        }
+
+        query
+            .load::<GdsnPublicationModel>(self.conn)?
+            .into_iter()
+            .map(GdsnPublication::try_from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListPublicationsOperation
+    for GdsnPublicationStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        let mut query = gdsn_publications::table
+            .order((
+                gdsn_publications::created_at.desc(),
+                gdsn_publications::publication_id.asc(),
+                gdsn_publications::product_id.asc(),
+            ))
+            .into_boxed();
+        if let Some(product_id) = product_id {
+            query = query.filter(gdsn_publications::product_id.eq(product_id));
+        }
+
+        query
+            .load::<GdsnPublicationModel>(self.conn)?
+            .into_iter()
+            .map(GdsnPublication::try_from)
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::GdsnPublicationStoreOperations;
+
+use crate::product::gdsn::publication::store::{
+    diesel::schema::gdsn_publications, GdsnPublicationStoreError, PublicationStatus,
+};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::product::gdsn::publication::store::diesel) trait UpdatePublicationStatusOperation {
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> UpdatePublicationStatusOperation
+    for GdsnPublicationStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        match update(gdsn_publications::table.find((publication_id, product_id)))
+            .set((
+                gdsn_publications::status.eq(status.as_str()),
+                gdsn_publications::status_message.eq(status_message),
+                gdsn_publications::updated_at.eq(updated_at),
+            ))
+            .execute(self.conn)?
+        {
+            0 => Err(GdsnPublicationStoreError::NotFoundError(format!(
+                "{} {}",
+                publication_id, product_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> UpdatePublicationStatusOperation
+    for GdsnPublicationStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        match update(gdsn_publications::table.find((publication_id, product_id)))
+            .set((
+                gdsn_publications::status.eq(status.as_str()),
+                gdsn_publications::status_message.eq(status_message),
+                gdsn_publications::updated_at.eq(updated_at),
+            ))
+            .execute(self.conn)?
+        {
+            0 => Err(GdsnPublicationStoreError::NotFoundError(format!(
+                "{} {}",
+                publication_id, product_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+pub(crate) mod diesel;
+mod error;
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::InvalidArgumentError;
+
+#[cfg(feature = "diesel")]
+pub use self::diesel::{DieselConnectionGdsnPublicationStore, DieselGdsnPublicationStore};
+pub use error::GdsnPublicationStoreError;
+
+/// The state of a trade item published to a data pool. A publication is pending until the data
+/// pool confirms it; the other states are the ones reported by GDSN Catalogue Item
+/// Confirmations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PublicationStatus {
+    Pending,
+    Received,
+    Review,
+    Synchronised,
+    Accepted,
+    Rejected,
+}
+
+impl PublicationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublicationStatus::Pending => "PENDING",
+            PublicationStatus::Received => "RECEIVED",
+            PublicationStatus::Review => "REVIEW",
+            PublicationStatus::Synchronised => "SYNCHRONISED",
+            PublicationStatus::Accepted => "ACCEPTED",
+            PublicationStatus::Rejected => "REJECTED",
+        }
+    }
+}
+
+impl fmt::Display for PublicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PublicationStatus {
+    type Err = InvalidArgumentError;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "PENDING" => Ok(PublicationStatus::Pending),
+            "RECEIVED" => Ok(PublicationStatus::Received),
+            "REVIEW" => Ok(PublicationStatus::Review),
+            "SYNCHRONISED" => Ok(PublicationStatus::Synchronised),
+            "ACCEPTED" => Ok(PublicationStatus::Accepted),
+            "REJECTED" => Ok(PublicationStatus::Rejected),
+            _ => Err(InvalidArgumentError::new(
+                "status".to_string(),
+                format!("Unknown GDSN publication status {}", status),
+            )),
+        }
+    }
+}
+
+/// A product published to a data recipient in a CIN message. Times are seconds since the epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GdsnPublication {
+    /// The instance identifier of the CIN message the product was published in
+    pub publication_id: String,
+    pub product_id: String,
+    pub service_id: Option<String>,
+    /// The GLN of the party the product was published to
+    pub data_recipient: String,
+    pub status: PublicationStatus,
+    /// The reason the data pool gave for the status, if any
+    pub status_message: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+pub trait GdsnPublicationStore {
+    /// Adds the products published in a CIN message to the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `publications` - The publications to be added, one per product
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError>;
+
+    /// Records the status a data pool reported for a published product
+    ///
+    /// # Arguments
+    ///
+    ///  * `publication_id` - The instance identifier of the CIN message
+    ///  * `product_id` - The product the status is reported for
+    ///  * `status` - The reported status
+    ///  * `status_message` - The reason given for the status, if any
+    ///  * `updated_at` - When the status was reported
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError>;
+
+    /// Lists the publications in the underlying storage, newest first
+    ///
+    /// # Arguments
+    ///
+    ///  * `product_id` - Only list the publications of this product
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError>;
+}
+
+impl<GS> GdsnPublicationStore for Box<GS>
+where
+    GS: GdsnPublicationStore + ?Sized,
+{
+    fn add_publications(
+        &self,
+        publications: Vec<GdsnPublication>,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        (**self).add_publications(publications)
+    }
+
+    fn update_publication_status(
+        &self,
+        publication_id: &str,
+        product_id: &str,
+        status: PublicationStatus,
+        status_message: Option<&str>,
+        updated_at: i64,
+    ) -> Result<(), GdsnPublicationStoreError> {
+        (**self).update_publication_status(
+            publication_id,
+            product_id,
+            status,
+            status_message,
+            updated_at,
+        )
+    }
+
+    fn list_publications(
+        &self,
+        product_id: Option<&str>,
+    ) -> Result<Vec<GdsnPublication>, GdsnPublicationStoreError> {
+        (**self).list_publications(product_id)
+    }
+}
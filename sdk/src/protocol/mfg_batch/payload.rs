@@ -21,7 +21,10 @@ use std::error::Error as StdError;
 
 use super::errors::BuilderError;
 
-use crate::protocol::{mfg_batch::state::MfgBatchNamespace, schema::state::PropertyValue};
+use crate::protocol::{
+    mfg_batch::state::{Attachment, Attestation, MfgBatchNamespace, TestResult},
+    schema::state::PropertyValue,
+};
 use crate::protos;
 use crate::protos::{mfg_batch_payload, mfg_batch_payload::MfgBatchPayload_Action};
 use crate::protos::{
@@ -30,17 +33,58 @@ use crate::protos::{
 
 /// The Product payload's action envelope
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "mfg-batch-serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Action {
     MfgBatchCreate(MfgBatchCreateAction),
     MfgBatchUpdate(MfgBatchUpdateAction),
     MfgBatchDelete(MfgBatchDeleteAction),
+    MfgBatchAddParents(MfgBatchAddParentsAction),
+    MfgBatchAddTestResult(MfgBatchAddTestResultAction),
+    MfgBatchAnchor(MfgBatchAnchorAction),
+    MfgBatchAddAttestation(MfgBatchAddAttestationAction),
+    MfgBatchAllocate(MfgBatchAllocateAction),
+    MfgBatchRelease(MfgBatchReleaseAction),
+    MfgBatchRecall(MfgBatchRecallAction),
+}
+
+#[cfg(feature = "log-masking")]
+impl Action {
+    /// Returns a copy of the action to log, with the values of the properties the mask denies
+    /// masked
+    pub fn masked(&self, mask: &crate::log_masking::LogMask) -> Action {
+        let masked = |properties: &[PropertyValue]| {
+            properties
+                .iter()
+                .map(|property| property.masked(mask))
+                .collect()
+        };
+
+        match self {
+            Action::MfgBatchCreate(action) => Action::MfgBatchCreate(MfgBatchCreateAction {
+                properties: masked(&action.properties),
+                ..action.clone()
+            }),
+            Action::MfgBatchUpdate(action) => Action::MfgBatchUpdate(MfgBatchUpdateAction {
+                properties: masked(&action.properties),
+                ..action.clone()
+            }),
+            action => action.clone(),
+        }
+    }
 }
 
 /// Native representation of a Product transaction payload
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct MfgBatchPayload {
     action: Action,
     timestamp: u64,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    service_id: String,
 }
 
 impl MfgBatchPayload {
@@ -50,6 +94,11 @@ impl MfgBatchPayload {
     pub fn timestamp(&self) -> &u64 {
         &self.timestamp
     }
+    /// Returns the Splinter service the transaction was submitted to, as
+    /// `<circuit_id>::<service_id>`, or an empty string if it was not submitted to one
+    pub fn service_id(&self) -> &str {
+        &self.service_id
+    }
 }
 
 impl FromProto<protos::mfg_batch_payload::MfgBatchPayload> for MfgBatchPayload {
@@ -66,6 +115,31 @@ impl FromProto<protos::mfg_batch_payload::MfgBatchPayload> for MfgBatchPayload {
             MfgBatchPayload_Action::MFG_BATCH_DELETE => Action::MfgBatchDelete(
                 MfgBatchDeleteAction::from_proto(payload.get_mfg_batch_delete().clone())?,
             ),
+            MfgBatchPayload_Action::MFG_BATCH_ADD_PARENTS => Action::MfgBatchAddParents(
+                MfgBatchAddParentsAction::from_proto(payload.get_mfg_batch_add_parents().clone())?,
+            ),
+            MfgBatchPayload_Action::MFG_BATCH_ADD_TEST_RESULT => {
+                Action::MfgBatchAddTestResult(MfgBatchAddTestResultAction::from_proto(
+                    payload.get_mfg_batch_add_test_result().clone(),
+                )?)
+            }
+            MfgBatchPayload_Action::MFG_BATCH_ANCHOR => Action::MfgBatchAnchor(
+                MfgBatchAnchorAction::from_proto(payload.get_mfg_batch_anchor().clone())?,
+            ),
+            MfgBatchPayload_Action::MFG_BATCH_ADD_ATTESTATION => {
+                Action::MfgBatchAddAttestation(MfgBatchAddAttestationAction::from_proto(
+                    payload.get_mfg_batch_add_attestation().clone(),
+                )?)
+            }
+            MfgBatchPayload_Action::MFG_BATCH_ALLOCATE => Action::MfgBatchAllocate(
+                MfgBatchAllocateAction::from_proto(payload.get_mfg_batch_allocate().clone())?,
+            ),
+            MfgBatchPayload_Action::MFG_BATCH_RELEASE => Action::MfgBatchRelease(
+                MfgBatchReleaseAction::from_proto(payload.get_mfg_batch_release().clone())?,
+            ),
+            MfgBatchPayload_Action::MFG_BATCH_RECALL => Action::MfgBatchRecall(
+                MfgBatchRecallAction::from_proto(payload.get_mfg_batch_recall().clone())?,
+            ),
             MfgBatchPayload_Action::UNSET_ACTION => {
                 return Err(ProtoConversionError::InvalidTypeError(
                     "Cannot convert MfgBatchPayload_Action with type unset".to_string(),
@@ -75,6 +149,7 @@ impl FromProto<protos::mfg_batch_payload::MfgBatchPayload> for MfgBatchPayload {
         Ok(MfgBatchPayload {
             action,
             timestamp: payload.get_timestamp(),
+            service_id: payload.get_service_id().to_string(),
         })
     }
 }
@@ -84,6 +159,7 @@ impl FromNative<MfgBatchPayload> for protos::mfg_batch_payload::MfgBatchPayload
         let mut proto = mfg_batch_payload::MfgBatchPayload::new();
 
         proto.set_timestamp(*native.timestamp());
+        proto.set_service_id(native.service_id().to_string());
 
         match native.action() {
             Action::MfgBatchCreate(payload) => {
@@ -98,6 +174,34 @@ impl FromNative<MfgBatchPayload> for protos::mfg_batch_payload::MfgBatchPayload
                 proto.set_action(MfgBatchPayload_Action::MFG_BATCH_DELETE);
                 proto.set_mfg_batch_delete(payload.clone().into_proto()?);
             }
+            Action::MfgBatchAddParents(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_ADD_PARENTS);
+                proto.set_mfg_batch_add_parents(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchAddTestResult(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_ADD_TEST_RESULT);
+                proto.set_mfg_batch_add_test_result(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchAnchor(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_ANCHOR);
+                proto.set_mfg_batch_anchor(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchAddAttestation(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_ADD_ATTESTATION);
+                proto.set_mfg_batch_add_attestation(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchAllocate(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_ALLOCATE);
+                proto.set_mfg_batch_allocate(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchRelease(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_RELEASE);
+                proto.set_mfg_batch_release(payload.clone().into_proto()?);
+            }
+            Action::MfgBatchRecall(payload) => {
+                proto.set_action(MfgBatchPayload_Action::MFG_BATCH_RECALL);
+                proto.set_mfg_batch_recall(payload.clone().into_proto()?);
+            }
         }
 
         Ok(proto)
@@ -131,6 +235,29 @@ impl IntoBytes for MfgBatchPayload {
 impl IntoProto<protos::mfg_batch_payload::MfgBatchPayload> for MfgBatchPayload {}
 impl IntoNative<MfgBatchPayload> for protos::mfg_batch_payload::MfgBatchPayload {}
 
+#[cfg(feature = "mfg-batch-serde")]
+impl MfgBatchPayload {
+    /// Parses the payload from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, ProtoConversionError> {
+        serde_json::from_str(json).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to parse MfgBatchPayload from JSON: {}",
+                err
+            ))
+        })
+    }
+
+    /// Returns the JSON representation of the payload
+    pub fn to_json(&self) -> Result<String, ProtoConversionError> {
+        serde_json::to_string(self).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to write MfgBatchPayload as JSON: {}",
+                err
+            ))
+        })
+    }
+}
+
 /// Returned if any required fields in a `MfgBatchPayload` are not present when being
 /// converted from the corresponding builder
 #[derive(Debug)]
@@ -165,6 +292,7 @@ impl std::fmt::Display for MfgBatchPayloadBuildError {
 pub struct MfgBatchPayloadBuilder {
     action: Option<Action>,
     timestamp: Option<u64>,
+    service_id: Option<String>,
 }
 
 impl MfgBatchPayloadBuilder {
@@ -179,6 +307,12 @@ impl MfgBatchPayloadBuilder {
         self.timestamp = Some(value);
         self
     }
+    /// Sets the Splinter service the transaction is submitted to, as
+    /// `<circuit_id>::<service_id>`
+    pub fn with_service_id(mut self, service_id: String) -> Self {
+        self.service_id = Some(service_id);
+        self
+    }
     pub fn build(self) -> Result<MfgBatchPayload, BuilderError> {
         let action = self
             .action
@@ -186,17 +320,34 @@ impl MfgBatchPayloadBuilder {
         let timestamp = self
             .timestamp
             .ok_or_else(|| BuilderError::MissingField("'timestamp' field is required".into()))?;
-        Ok(MfgBatchPayload { action, timestamp })
+        let service_id = self.service_id.unwrap_or_default();
+        Ok(MfgBatchPayload {
+            action,
+            timestamp,
+            service_id,
+        })
     }
 }
 
 /// Native representation of the "create product" action payload
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "mfg-batch-serde",
+    derive(Serialize, Deserialize),
+    serde(default)
+)]
 pub struct MfgBatchCreateAction {
     mfg_batch_namespace: MfgBatchNamespace,
     mfg_batch_id: String,
     owner: String,
     properties: Vec<PropertyValue>,
+    quantity: i64,
+    uom: String,
+    expected_quantity: i64,
+    production_date: u64,
+    expiration_date: u64,
+    manufacture_location: String,
+    attachments: Vec<Attachment>,
 }
 
 impl MfgBatchCreateAction {
@@ -215,6 +366,34 @@ impl MfgBatchCreateAction {
     pub fn properties(&self) -> &[PropertyValue] {
         &self.properties
     }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+
+    pub fn uom(&self) -> &str {
+        &self.uom
+    }
+
+    pub fn expected_quantity(&self) -> i64 {
+        self.expected_quantity
+    }
+
+    pub fn production_date(&self) -> u64 {
+        self.production_date
+    }
+
+    pub fn expiration_date(&self) -> u64 {
+        self.expiration_date
+    }
+
+    pub fn manufacture_location(&self) -> &str {
+        &self.manufacture_location
+    }
+
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
 }
 
 impl FromProto<mfg_batch_payload::MfgBatchCreateAction> for MfgBatchCreateAction {
@@ -231,6 +410,18 @@ impl FromProto<mfg_batch_payload::MfgBatchCreateAction> for MfgBatchCreateAction
                 .into_iter()
                 .map(PropertyValue::from_proto)
                 .collect::<Result<Vec<PropertyValue>, ProtoConversionError>>()?,
+            quantity: proto.get_quantity(),
+            uom: proto.get_uom().to_string(),
+            expected_quantity: proto.get_expected_quantity(),
+            production_date: proto.get_production_date(),
+            expiration_date: proto.get_expiration_date(),
+            manufacture_location: proto.get_manufacture_location().to_string(),
+            attachments: proto
+                .get_attachments()
+                .to_vec()
+                .into_iter()
+                .map(Attachment::from_proto)
+                .collect::<Result<Vec<Attachment>, ProtoConversionError>>()?,
         })
     }
 }
@@ -250,6 +441,21 @@ impl FromNative<MfgBatchCreateAction> for mfg_batch_payload::MfgBatchCreateActio
                 .collect::<Result<Vec<protos::schema_state::PropertyValue>, ProtoConversionError>>(
                 )?,
         ));
+        proto.set_quantity(native.quantity());
+        proto.set_uom(native.uom().to_string());
+        proto.set_expected_quantity(native.expected_quantity());
+        proto.set_production_date(native.production_date());
+        proto.set_expiration_date(native.expiration_date());
+        proto.set_manufacture_location(native.manufacture_location().to_string());
+        proto.set_attachments(RepeatedField::from_vec(
+            native
+                .attachments()
+                .to_vec()
+                .into_iter()
+                .map(Attachment::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::Attachment>, ProtoConversionError>>(
+                )?,
+        ));
         Ok(proto)
     }
 }
@@ -281,6 +487,29 @@ impl IntoBytes for MfgBatchCreateAction {
 impl IntoProto<protos::mfg_batch_payload::MfgBatchCreateAction> for MfgBatchCreateAction {}
 impl IntoNative<MfgBatchCreateAction> for protos::mfg_batch_payload::MfgBatchCreateAction {}
 
+#[cfg(feature = "mfg-batch-serde")]
+impl MfgBatchCreateAction {
+    /// Parses the action from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, ProtoConversionError> {
+        serde_json::from_str(json).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to parse MfgBatchCreateAction from JSON: {}",
+                err
+            ))
+        })
+    }
+
+    /// Returns the JSON representation of the action
+    pub fn to_json(&self) -> Result<String, ProtoConversionError> {
+        serde_json::to_string(self).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to write MfgBatchCreateAction as JSON: {}",
+                err
+            ))
+        })
+    }
+}
+
 /// Builder used to create a "create product" action payload
 #[derive(Default, Debug)]
 pub struct MfgBatchCreateActionBuilder {
@@ -288,6 +517,13 @@ pub struct MfgBatchCreateActionBuilder {
     mfg_batch_id: Option<String>,
     owner: Option<String>,
     properties: Option<Vec<PropertyValue>>,
+    quantity: Option<i64>,
+    uom: Option<String>,
+    expected_quantity: Option<i64>,
+    production_date: Option<u64>,
+    expiration_date: Option<u64>,
+    manufacture_location: Option<String>,
+    attachments: Option<Vec<Attachment>>,
 }
 
 impl MfgBatchCreateActionBuilder {
@@ -310,6 +546,34 @@ impl MfgBatchCreateActionBuilder {
         self.properties = Some(value);
         self
     }
+    pub fn with_quantity(mut self, value: i64) -> Self {
+        self.quantity = Some(value);
+        self
+    }
+    pub fn with_uom(mut self, value: String) -> Self {
+        self.uom = Some(value);
+        self
+    }
+    pub fn with_expected_quantity(mut self, value: i64) -> Self {
+        self.expected_quantity = Some(value);
+        self
+    }
+    pub fn with_production_date(mut self, value: u64) -> Self {
+        self.production_date = Some(value);
+        self
+    }
+    pub fn with_expiration_date(mut self, value: u64) -> Self {
+        self.expiration_date = Some(value);
+        self
+    }
+    pub fn with_manufacture_location(mut self, value: String) -> Self {
+        self.manufacture_location = Some(value);
+        self
+    }
+    pub fn with_attachments(mut self, value: Vec<Attachment>) -> Self {
+        self.attachments = Some(value);
+        self
+    }
     pub fn build(self) -> Result<MfgBatchCreateAction, BuilderError> {
         let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
             BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
@@ -328,16 +592,36 @@ impl MfgBatchCreateActionBuilder {
             mfg_batch_id,
             owner,
             properties,
+            quantity: self.quantity.unwrap_or_default(),
+            uom: self.uom.unwrap_or_default(),
+            expected_quantity: self.expected_quantity.unwrap_or_default(),
+            production_date: self.production_date.unwrap_or_default(),
+            expiration_date: self.expiration_date.unwrap_or_default(),
+            manufacture_location: self.manufacture_location.unwrap_or_default(),
+            attachments: self.attachments.unwrap_or_default(),
         })
     }
 }
 
 /// Native representation of an "update product" action payload
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct MfgBatchUpdateAction {
     mfg_batch_namespace: MfgBatchNamespace,
     mfg_batch_id: String,
     properties: Vec<PropertyValue>,
+    quantity: i64,
+    uom: String,
+    expected_quantity: i64,
+    production_date: u64,
+    expiration_date: u64,
+    manufacture_location: String,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    expected_version: String,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    validate_changed_properties_only: bool,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    attachments: Vec<Attachment>,
 }
 
 impl MfgBatchUpdateAction {
@@ -352,6 +636,51 @@ impl MfgBatchUpdateAction {
     pub fn properties(&self) -> &[PropertyValue] {
         &self.properties
     }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+
+    /// Returns the unit of measure; if empty, the batch's quantities are left unchanged
+    pub fn uom(&self) -> &str {
+        &self.uom
+    }
+
+    pub fn expected_quantity(&self) -> i64 {
+        self.expected_quantity
+    }
+
+    /// Returns the production date; if 0, the batch's production date is left unchanged
+    pub fn production_date(&self) -> u64 {
+        self.production_date
+    }
+
+    /// Returns the expiration date; if 0, the batch's expiration date is left unchanged
+    pub fn expiration_date(&self) -> u64 {
+        self.expiration_date
+    }
+
+    /// Returns the manufacture location; if empty, the batch's location is left unchanged
+    pub fn manufacture_location(&self) -> &str {
+        &self.manufacture_location
+    }
+
+    /// Returns the version of the batch the update was built against, as returned by
+    /// `MfgBatch::version`; if empty, the update applies whatever the batch's current version
+    pub fn expected_version(&self) -> &str {
+        &self.expected_version
+    }
+
+    /// Returns whether only the properties the update adds or changes are validated against the
+    /// schema, rather than every property
+    pub fn validate_changed_properties_only(&self) -> bool {
+        self.validate_changed_properties_only
+    }
+
+    /// Returns the attachments; if empty, the batch's attachments are left unchanged
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
 }
 
 impl FromProto<protos::mfg_batch_payload::MfgBatchUpdateAction> for MfgBatchUpdateAction {
@@ -367,6 +696,20 @@ impl FromProto<protos::mfg_batch_payload::MfgBatchUpdateAction> for MfgBatchUpda
                 .into_iter()
                 .map(PropertyValue::from_proto)
                 .collect::<Result<Vec<PropertyValue>, ProtoConversionError>>()?,
+            quantity: proto.get_quantity(),
+            uom: proto.get_uom().to_string(),
+            expected_quantity: proto.get_expected_quantity(),
+            production_date: proto.get_production_date(),
+            expiration_date: proto.get_expiration_date(),
+            manufacture_location: proto.get_manufacture_location().to_string(),
+            expected_version: proto.get_expected_version().to_string(),
+            validate_changed_properties_only: proto.get_validate_changed_properties_only(),
+            attachments: proto
+                .get_attachments()
+                .to_vec()
+                .into_iter()
+                .map(Attachment::from_proto)
+                .collect::<Result<Vec<Attachment>, ProtoConversionError>>()?,
         })
     }
 }
@@ -385,6 +728,23 @@ impl FromNative<MfgBatchUpdateAction> for protos::mfg_batch_payload::MfgBatchUpd
                 .collect::<Result<Vec<protos::schema_state::PropertyValue>, ProtoConversionError>>(
                 )?,
         ));
+        proto.set_quantity(native.quantity());
+        proto.set_uom(native.uom().to_string());
+        proto.set_expected_quantity(native.expected_quantity());
+        proto.set_production_date(native.production_date());
+        proto.set_expiration_date(native.expiration_date());
+        proto.set_manufacture_location(native.manufacture_location().to_string());
+        proto.set_expected_version(native.expected_version().to_string());
+        proto.set_validate_changed_properties_only(native.validate_changed_properties_only());
+        proto.set_attachments(RepeatedField::from_vec(
+            native
+                .attachments()
+                .to_vec()
+                .into_iter()
+                .map(Attachment::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::Attachment>, ProtoConversionError>>(
+                )?,
+        ));
 
         Ok(proto)
     }
@@ -423,6 +783,15 @@ pub struct MfgBatchUpdateActionBuilder {
     mfg_batch_namespace: Option<MfgBatchNamespace>,
     mfg_batch_id: Option<String>,
     properties: Vec<PropertyValue>,
+    quantity: i64,
+    uom: String,
+    expected_quantity: i64,
+    production_date: u64,
+    expiration_date: u64,
+    manufacture_location: String,
+    expected_version: String,
+    validate_changed_properties_only: bool,
+    attachments: Vec<Attachment>,
 }
 
 impl MfgBatchUpdateActionBuilder {
@@ -445,6 +814,54 @@ impl MfgBatchUpdateActionBuilder {
         self
     }
 
+    pub fn with_quantity(mut self, quantity: i64) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    pub fn with_uom(mut self, uom: String) -> Self {
+        self.uom = uom;
+        self
+    }
+
+    pub fn with_expected_quantity(mut self, expected_quantity: i64) -> Self {
+        self.expected_quantity = expected_quantity;
+        self
+    }
+
+    pub fn with_production_date(mut self, production_date: u64) -> Self {
+        self.production_date = production_date;
+        self
+    }
+
+    pub fn with_expiration_date(mut self, expiration_date: u64) -> Self {
+        self.expiration_date = expiration_date;
+        self
+    }
+
+    pub fn with_manufacture_location(mut self, manufacture_location: String) -> Self {
+        self.manufacture_location = manufacture_location;
+        self
+    }
+
+    pub fn with_expected_version(mut self, expected_version: String) -> Self {
+        self.expected_version = expected_version;
+        self
+    }
+
+    pub fn with_validate_changed_properties_only(
+        mut self,
+        validate_changed_properties_only: bool,
+    ) -> Self {
+        self.validate_changed_properties_only = validate_changed_properties_only;
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
     pub fn build(self) -> Result<MfgBatchUpdateAction, BuilderError> {
         let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
             BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
@@ -468,15 +885,26 @@ impl MfgBatchUpdateActionBuilder {
             mfg_batch_namespace,
             mfg_batch_id,
             properties,
+            quantity: self.quantity,
+            uom: self.uom,
+            expected_quantity: self.expected_quantity,
+            production_date: self.production_date,
+            expiration_date: self.expiration_date,
+            manufacture_location: self.manufacture_location,
+            expected_version: self.expected_version,
+            validate_changed_properties_only: self.validate_changed_properties_only,
+            attachments: self.attachments,
         })
     }
 }
 
 /// Native representation of the "delete product" action payload
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct MfgBatchDeleteAction {
     mfg_batch_namespace: MfgBatchNamespace,
     mfg_batch_id: String,
+    archive: bool,
 }
 
 impl MfgBatchDeleteAction {
@@ -487,6 +915,11 @@ impl MfgBatchDeleteAction {
     pub fn mfg_batch_id(&self) -> &str {
         &self.mfg_batch_id
     }
+
+    /// Returns whether the batch is archived in state instead of removed
+    pub fn archive(&self) -> bool {
+        self.archive
+    }
 }
 
 impl FromProto<protos::mfg_batch_payload::MfgBatchDeleteAction> for MfgBatchDeleteAction {
@@ -496,6 +929,7 @@ impl FromProto<protos::mfg_batch_payload::MfgBatchDeleteAction> for MfgBatchDele
         Ok(MfgBatchDeleteAction {
             mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
             mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            archive: proto.get_archive(),
         })
     }
 }
@@ -505,6 +939,7 @@ impl FromNative<MfgBatchDeleteAction> for protos::mfg_batch_payload::MfgBatchDel
         let mut proto = protos::mfg_batch_payload::MfgBatchDeleteAction::new();
         proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
         proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_archive(native.archive());
         Ok(proto)
     }
 }
@@ -541,6 +976,7 @@ impl IntoNative<MfgBatchDeleteAction> for protos::mfg_batch_payload::MfgBatchDel
 pub struct MfgBatchDeleteActionBuilder {
     mfg_batch_namespace: Option<MfgBatchNamespace>,
     mfg_batch_id: Option<String>,
+    archive: bool,
 }
 
 impl MfgBatchDeleteActionBuilder {
@@ -558,6 +994,11 @@ impl MfgBatchDeleteActionBuilder {
         self
     }
 
+    pub fn with_archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+
     pub fn build(self) -> Result<MfgBatchDeleteAction, BuilderError> {
         let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
             BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
@@ -570,117 +1011,1087 @@ impl MfgBatchDeleteActionBuilder {
         Ok(MfgBatchDeleteAction {
             mfg_batch_namespace,
             mfg_batch_id,
+            archive: self.archive,
         })
     }
 }
-/*
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::protocol::schema::state::{DataType, PropertyValueBuilder};
-    use std::fmt::Debug;
 
-    #[test]
-    /// Validate that a `MfgBatchCreateAction` is built correctly
-    fn test_product_create_builder() {
-        let action = MfgBatchCreateActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_owner("Target".into())
-            .with_properties(make_properties())
-            .build()
-            .unwrap();
+/// Native representation of the "add parents" action payload
+///
+/// Records the batches a manufacturing batch was produced from.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAddParentsAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_id: String,
+    parent_batches: Vec<String>,
+}
 
-        assert_eq!(action.mfg_batch_id(), "688955434684");
-        assert_eq!(action.owner(), "Target");
-        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
-        assert_eq!(action.properties()[0].name(), "description");
-        assert_eq!(*action.properties()[0].data_type(), DataType::String);
-        assert_eq!(
-            action.properties()[0].string_value(),
-            "This is a product description"
-        );
-        assert_eq!(action.properties()[1].name(), "price");
-        assert_eq!(*action.properties()[1].data_type(), DataType::Number);
-        assert_eq!(*action.properties()[1].number_value(), 3);
+impl MfgBatchAddParentsAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
     }
 
-    #[test]
-    /// Validate that a `MfgBatchCreateAction` may be correctly converted into bytes and back
-    /// to its native representation
-    fn test_product_create_into_bytes() {
-        let action = MfgBatchCreateActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_owner("Target".into())
-            .with_properties(make_properties())
-            .build()
-            .unwrap();
-
-        test_from_bytes(action, MfgBatchCreateAction::from_bytes);
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
     }
 
-    #[test]
-    /// Validate that a `MfgBatchUpdateAction` is built correctly
-    fn test_product_update_builder() {
-        let action = MfgBatchUpdateActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_properties(make_properties())
-            .build()
-            .unwrap();
-
-        assert_eq!(action.mfg_batch_id(), "688955434684");
-        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
-        assert_eq!(action.properties()[0].name(), "description");
-        assert_eq!(*action.properties()[0].data_type(), DataType::String);
-        assert_eq!(
-            action.properties()[0].string_value(),
-            "This is a product description"
-        );
-        assert_eq!(action.properties()[1].name(), "price");
-        assert_eq!(*action.properties()[1].data_type(), DataType::Number);
-        assert_eq!(*action.properties()[1].number_value(), 3);
+    pub fn parent_batches(&self) -> &[String] {
+        &self.parent_batches
     }
+}
 
-    #[test]
-    /// Validate that an `MfgBatchUpdateAction` may be correctly converted into bytes and back
-    /// to its native representation
-    fn test_product_update_into_bytes() {
-        let action = MfgBatchUpdateActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .with_properties(make_properties())
-            .build()
-            .unwrap();
-
-        test_from_bytes(action, MfgBatchUpdateAction::from_bytes);
+impl FromProto<protos::mfg_batch_payload::MfgBatchAddParentsAction> for MfgBatchAddParentsAction {
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchAddParentsAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAddParentsAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            parent_batches: proto.get_parent_batches().to_vec(),
+        })
     }
+}
 
-    #[test]
-    /// Validate that an `MfgBatchDeleteAction` may be built correctly
-    fn test_product_delete_builder() {
-        let action = MfgBatchDeleteActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .build()
-            .unwrap();
-
-        assert_eq!(action.mfg_batch_id(), "688955434684");
-        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
+impl FromNative<MfgBatchAddParentsAction> for protos::mfg_batch_payload::MfgBatchAddParentsAction {
+    fn from_native(native: MfgBatchAddParentsAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchAddParentsAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_parent_batches(RepeatedField::from_vec(native.parent_batches().to_vec()));
+        Ok(proto)
     }
+}
 
-    #[test]
-    /// Validate that a `MfgBatchDeleteAction` may be correctly converted into bytes and back
-    /// to its native representation
-    fn test_product_delete_into_bytes() {
-        let action = MfgBatchDeleteActionBuilder::new()
-            .with_mfg_batch_id("688955434684".into()) // GTIN-12
-            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
-            .build()
-            .unwrap();
-
-        test_from_bytes(action, MfgBatchDeleteAction::from_bytes);
+impl FromBytes<MfgBatchAddParentsAction> for MfgBatchAddParentsAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAddParentsAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchAddParentsAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchAddParentsAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAddParentsAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAddParentsAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchAddParentsAction> for MfgBatchAddParentsAction {}
+impl IntoNative<MfgBatchAddParentsAction> for protos::mfg_batch_payload::MfgBatchAddParentsAction {}
+
+/// Builder used to create an "add parents" action
+#[derive(Default, Clone)]
+pub struct MfgBatchAddParentsActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_id: Option<String>,
+    parent_batches: Vec<String>,
+}
+
+impl MfgBatchAddParentsActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchAddParentsActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_id(mut self, mfg_batch_id: String) -> Self {
+        self.mfg_batch_id = Some(mfg_batch_id);
+        self
+    }
+
+    pub fn with_parent_batches(mut self, parent_batches: Vec<String>) -> Self {
+        self.parent_batches = parent_batches;
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAddParentsAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_id' field is required".to_string())
+        })?;
+
+        if self.parent_batches.is_empty() {
+            return Err(BuilderError::MissingField(
+                "'parent_batches' field is required".to_string(),
+            ));
+        }
+
+        Ok(MfgBatchAddParentsAction {
+            mfg_batch_namespace,
+            mfg_batch_id,
+            parent_batches: self.parent_batches,
+        })
+    }
+}
+
+/// Native representation of the "add test result" action payload
+///
+/// Records a quality control result, such as a line of a certificate of analysis, against a
+/// manufacturing batch.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAddTestResultAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_id: String,
+    test_result: TestResult,
+}
+
+impl MfgBatchAddTestResultAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
+    }
+
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    pub fn test_result(&self) -> &TestResult {
+        &self.test_result
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchAddTestResultAction>
+    for MfgBatchAddTestResultAction
+{
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchAddTestResultAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAddTestResultAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            test_result: TestResult::from_proto(proto.get_test_result().clone())?,
+        })
+    }
+}
+
+impl FromNative<MfgBatchAddTestResultAction>
+    for protos::mfg_batch_payload::MfgBatchAddTestResultAction
+{
+    fn from_native(native: MfgBatchAddTestResultAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchAddTestResultAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_test_result(native.test_result.into_proto()?);
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchAddTestResultAction> for MfgBatchAddTestResultAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAddTestResultAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchAddTestResultAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchAddTestResultAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAddTestResultAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAddTestResultAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchAddTestResultAction>
+    for MfgBatchAddTestResultAction
+{
+}
+impl IntoNative<MfgBatchAddTestResultAction>
+    for protos::mfg_batch_payload::MfgBatchAddTestResultAction
+{
+}
+
+/// Builder used to create an "add test result" action
+#[derive(Default, Clone)]
+pub struct MfgBatchAddTestResultActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_id: Option<String>,
+    test_result: Option<TestResult>,
+}
+
+impl MfgBatchAddTestResultActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchAddTestResultActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_id(mut self, mfg_batch_id: String) -> Self {
+        self.mfg_batch_id = Some(mfg_batch_id);
+        self
+    }
+
+    pub fn with_test_result(mut self, test_result: TestResult) -> Self {
+        self.test_result = Some(test_result);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAddTestResultAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_id' field is required".to_string())
+        })?;
+
+        let test_result = self.test_result.ok_or_else(|| {
+            BuilderError::MissingField("'test_result' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchAddTestResultAction {
+            mfg_batch_namespace,
+            mfg_batch_id,
+            test_result,
+        })
+    }
+}
+
+/// Native representation of the "anchor" action payload
+///
+/// Records on chain the Merkle root of an organization's off-chain copy of the mfg_batch records
+/// at a commit, so the copy can later be shown to have matched the chain at that point.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAnchorAction {
+    owner: String,
+    commit_num: i64,
+    merkle_root: String,
+    row_count: u64,
+}
+
+impl MfgBatchAnchorAction {
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn commit_num(&self) -> i64 {
+        self.commit_num
+    }
+
+    pub fn merkle_root(&self) -> &str {
+        &self.merkle_root
+    }
+
+    pub fn row_count(&self) -> u64 {
+        self.row_count
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchAnchorAction> for MfgBatchAnchorAction {
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchAnchorAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAnchorAction {
+            owner: proto.get_owner().to_string(),
+            commit_num: proto.get_commit_num(),
+            merkle_root: proto.get_merkle_root().to_string(),
+            row_count: proto.get_row_count(),
+        })
+    }
+}
+
+impl FromNative<MfgBatchAnchorAction> for protos::mfg_batch_payload::MfgBatchAnchorAction {
+    fn from_native(native: MfgBatchAnchorAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchAnchorAction::new();
+        proto.set_owner(native.owner);
+        proto.set_commit_num(native.commit_num);
+        proto.set_merkle_root(native.merkle_root);
+        proto.set_row_count(native.row_count);
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchAnchorAction> for MfgBatchAnchorAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAnchorAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchAnchorAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchAnchorAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAnchorAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAnchorAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchAnchorAction> for MfgBatchAnchorAction {}
+impl IntoNative<MfgBatchAnchorAction> for protos::mfg_batch_payload::MfgBatchAnchorAction {}
+
+/// Builder used to create an "anchor" action
+#[derive(Default, Clone)]
+pub struct MfgBatchAnchorActionBuilder {
+    owner: Option<String>,
+    commit_num: Option<i64>,
+    merkle_root: Option<String>,
+    row_count: Option<u64>,
+}
+
+impl MfgBatchAnchorActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchAnchorActionBuilder::default()
+    }
+
+    pub fn with_owner(mut self, owner: String) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn with_commit_num(mut self, commit_num: i64) -> Self {
+        self.commit_num = Some(commit_num);
+        self
+    }
+
+    pub fn with_merkle_root(mut self, merkle_root: String) -> Self {
+        self.merkle_root = Some(merkle_root);
+        self
+    }
+
+    pub fn with_row_count(mut self, row_count: u64) -> Self {
+        self.row_count = Some(row_count);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAnchorAction, BuilderError> {
+        let owner = self
+            .owner
+            .ok_or_else(|| BuilderError::MissingField("'owner' field is required".to_string()))?;
+
+        let commit_num = self.commit_num.ok_or_else(|| {
+            BuilderError::MissingField("'commit_num' field is required".to_string())
+        })?;
+
+        let merkle_root = self.merkle_root.ok_or_else(|| {
+            BuilderError::MissingField("'merkle_root' field is required".to_string())
+        })?;
+
+        let row_count = self.row_count.ok_or_else(|| {
+            BuilderError::MissingField("'row_count' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchAnchorAction {
+            owner,
+            commit_num,
+            merkle_root,
+            row_count,
+        })
+    }
+}
+
+/// Native representation of the "add attestation" action payload
+///
+/// Records a lab's, auditor's or other outside party's signed sign-off on a manufacturing batch.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAddAttestationAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_id: String,
+    attestation: Attestation,
+}
+
+impl MfgBatchAddAttestationAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
+    }
+
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    pub fn attestation(&self) -> &Attestation {
+        &self.attestation
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchAddAttestationAction>
+    for MfgBatchAddAttestationAction
+{
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchAddAttestationAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAddAttestationAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            attestation: Attestation::from_proto(proto.get_attestation().clone())?,
+        })
+    }
+}
+
+impl FromNative<MfgBatchAddAttestationAction>
+    for protos::mfg_batch_payload::MfgBatchAddAttestationAction
+{
+    fn from_native(native: MfgBatchAddAttestationAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchAddAttestationAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_attestation(native.attestation.into_proto()?);
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchAddAttestationAction> for MfgBatchAddAttestationAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAddAttestationAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchAddAttestationAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchAddAttestationAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAddAttestationAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAddAttestationAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchAddAttestationAction>
+    for MfgBatchAddAttestationAction
+{
+}
+impl IntoNative<MfgBatchAddAttestationAction>
+    for protos::mfg_batch_payload::MfgBatchAddAttestationAction
+{
+}
+
+/// Builder used to create an "add attestation" action
+#[derive(Default, Clone)]
+pub struct MfgBatchAddAttestationActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_id: Option<String>,
+    attestation: Option<Attestation>,
+}
+
+impl MfgBatchAddAttestationActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchAddAttestationActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_id(mut self, mfg_batch_id: String) -> Self {
+        self.mfg_batch_id = Some(mfg_batch_id);
+        self
+    }
+
+    pub fn with_attestation(mut self, attestation: Attestation) -> Self {
+        self.attestation = Some(attestation);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAddAttestationAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_id' field is required".to_string())
+        })?;
+
+        let attestation = self.attestation.ok_or_else(|| {
+            BuilderError::MissingField("'attestation' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchAddAttestationAction {
+            mfg_batch_namespace,
+            mfg_batch_id,
+            attestation,
+        })
+    }
+}
+/// Native representation of the "allocate" action payload
+///
+/// Reserves a quantity of a manufacturing batch for a sales order, adding to any quantity already
+/// reserved for it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAllocateAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_id: String,
+    order_id: String,
+    quantity: i64,
+}
+
+impl MfgBatchAllocateAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
+    }
+
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchAllocateAction> for MfgBatchAllocateAction {
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchAllocateAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAllocateAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            order_id: proto.get_order_id().to_string(),
+            quantity: proto.get_quantity(),
+        })
+    }
+}
+
+impl FromNative<MfgBatchAllocateAction> for protos::mfg_batch_payload::MfgBatchAllocateAction {
+    fn from_native(native: MfgBatchAllocateAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchAllocateAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_order_id(native.order_id().to_string());
+        proto.set_quantity(native.quantity());
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchAllocateAction> for MfgBatchAllocateAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAllocateAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchAllocateAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchAllocateAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAllocateAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAllocateAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchAllocateAction> for MfgBatchAllocateAction {}
+impl IntoNative<MfgBatchAllocateAction> for protos::mfg_batch_payload::MfgBatchAllocateAction {}
+
+/// Builder used to create a "allocate" action
+#[derive(Default, Clone)]
+pub struct MfgBatchAllocateActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_id: Option<String>,
+    order_id: Option<String>,
+    quantity: Option<i64>,
+}
+
+impl MfgBatchAllocateActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchAllocateActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_id(mut self, mfg_batch_id: String) -> Self {
+        self.mfg_batch_id = Some(mfg_batch_id);
+        self
+    }
+
+    pub fn with_order_id(mut self, order_id: String) -> Self {
+        self.order_id = Some(order_id);
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: i64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAllocateAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_id' field is required".to_string())
+        })?;
+
+        let order_id = self.order_id.ok_or_else(|| {
+            BuilderError::MissingField("'order_id' field is required".to_string())
+        })?;
+
+        let quantity = self.quantity.ok_or_else(|| {
+            BuilderError::MissingField("'quantity' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchAllocateAction {
+            mfg_batch_namespace,
+            mfg_batch_id,
+            order_id,
+            quantity,
+        })
+    }
+}
+
+/// Native representation of the "release" action payload
+///
+/// Returns a quantity reserved for a sales order to a manufacturing batch's available quantity.
+/// A quantity of 0 releases the order's whole allocation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchReleaseAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_id: String,
+    order_id: String,
+    quantity: i64,
+}
+
+impl MfgBatchReleaseAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
+    }
+
+    pub fn mfg_batch_id(&self) -> &str {
+        &self.mfg_batch_id
+    }
+
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchReleaseAction> for MfgBatchReleaseAction {
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchReleaseAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchReleaseAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_id: proto.get_mfg_batch_id().to_string(),
+            order_id: proto.get_order_id().to_string(),
+            quantity: proto.get_quantity(),
+        })
+    }
+}
+
+impl FromNative<MfgBatchReleaseAction> for protos::mfg_batch_payload::MfgBatchReleaseAction {
+    fn from_native(native: MfgBatchReleaseAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchReleaseAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_id(native.mfg_batch_id().to_string());
+        proto.set_order_id(native.order_id().to_string());
+        proto.set_quantity(native.quantity());
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchReleaseAction> for MfgBatchReleaseAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchReleaseAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchReleaseAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchReleaseAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchReleaseAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchReleaseAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchReleaseAction> for MfgBatchReleaseAction {}
+impl IntoNative<MfgBatchReleaseAction> for protos::mfg_batch_payload::MfgBatchReleaseAction {}
+
+/// Builder used to create a "release" action
+#[derive(Default, Clone)]
+pub struct MfgBatchReleaseActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_id: Option<String>,
+    order_id: Option<String>,
+    quantity: Option<i64>,
+}
+
+impl MfgBatchReleaseActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchReleaseActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_id(mut self, mfg_batch_id: String) -> Self {
+        self.mfg_batch_id = Some(mfg_batch_id);
+        self
+    }
+
+    pub fn with_order_id(mut self, order_id: String) -> Self {
+        self.order_id = Some(order_id);
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: i64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchReleaseAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_id' field is required".to_string())
+        })?;
+
+        let order_id = self.order_id.ok_or_else(|| {
+            BuilderError::MissingField("'order_id' field is required".to_string())
+        })?;
+
+        // Without a quantity, the order's whole allocation is released
+        let quantity = self.quantity.unwrap_or_default();
+
+        Ok(MfgBatchReleaseAction {
+            mfg_batch_namespace,
+            mfg_batch_id,
+            order_id,
+            quantity,
+        })
+    }
+}
+
+/// Native representation of the "recall" action payload
+///
+/// Flags manufacturing batches as recalled. The batches' descendants are not found on-chain; to
+/// recall them too, list them alongside the batches they were produced from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchRecallAction {
+    mfg_batch_namespace: MfgBatchNamespace,
+    mfg_batch_ids: Vec<String>,
+    recall_id: String,
+    reason_code: String,
+}
+
+impl MfgBatchRecallAction {
+    pub fn mfg_batch_namespace(&self) -> &MfgBatchNamespace {
+        &self.mfg_batch_namespace
+    }
+
+    pub fn mfg_batch_ids(&self) -> &[String] {
+        &self.mfg_batch_ids
+    }
+
+    pub fn recall_id(&self) -> &str {
+        &self.recall_id
+    }
+
+    pub fn reason_code(&self) -> &str {
+        &self.reason_code
+    }
+}
+
+impl FromProto<protos::mfg_batch_payload::MfgBatchRecallAction> for MfgBatchRecallAction {
+    fn from_proto(
+        proto: protos::mfg_batch_payload::MfgBatchRecallAction,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchRecallAction {
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(proto.get_mfg_batch_namespace())?,
+            mfg_batch_ids: proto.get_mfg_batch_ids().to_vec(),
+            recall_id: proto.get_recall_id().to_string(),
+            reason_code: proto.get_reason_code().to_string(),
+        })
+    }
+}
+
+impl FromNative<MfgBatchRecallAction> for protos::mfg_batch_payload::MfgBatchRecallAction {
+    fn from_native(native: MfgBatchRecallAction) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_payload::MfgBatchRecallAction::new();
+        proto.set_mfg_batch_namespace(native.mfg_batch_namespace().clone().into_proto()?);
+        proto.set_mfg_batch_ids(RepeatedField::from_vec(native.mfg_batch_ids().to_vec()));
+        proto.set_recall_id(native.recall_id().to_string());
+        proto.set_reason_code(native.reason_code().to_string());
+        Ok(proto)
+    }
+}
+
+impl FromBytes<MfgBatchRecallAction> for MfgBatchRecallAction {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchRecallAction, ProtoConversionError> {
+        let proto: protos::mfg_batch_payload::MfgBatchRecallAction =
+            Message::parse_from_bytes(bytes).map_err(|_| {
+                ProtoConversionError::SerializationError(
+                    "Unable to get MfgBatchRecallAction from bytes".to_string(),
+                )
+            })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchRecallAction {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchRecallAction".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_payload::MfgBatchRecallAction> for MfgBatchRecallAction {}
+impl IntoNative<MfgBatchRecallAction> for protos::mfg_batch_payload::MfgBatchRecallAction {}
+
+/// Builder used to create a "recall" action
+#[derive(Default, Clone)]
+pub struct MfgBatchRecallActionBuilder {
+    mfg_batch_namespace: Option<MfgBatchNamespace>,
+    mfg_batch_ids: Vec<String>,
+    recall_id: Option<String>,
+    reason_code: Option<String>,
+}
+
+impl MfgBatchRecallActionBuilder {
+    pub fn new() -> Self {
+        MfgBatchRecallActionBuilder::default()
+    }
+
+    pub fn with_mfg_batch_namespace(mut self, mfg_batch_namespace: MfgBatchNamespace) -> Self {
+        self.mfg_batch_namespace = Some(mfg_batch_namespace);
+        self
+    }
+
+    pub fn with_mfg_batch_ids(mut self, mfg_batch_ids: Vec<String>) -> Self {
+        self.mfg_batch_ids = mfg_batch_ids;
+        self
+    }
+
+    pub fn with_recall_id(mut self, recall_id: String) -> Self {
+        self.recall_id = Some(recall_id);
+        self
+    }
+
+    pub fn with_reason_code(mut self, reason_code: String) -> Self {
+        self.reason_code = Some(reason_code);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchRecallAction, BuilderError> {
+        let mfg_batch_namespace = self.mfg_batch_namespace.ok_or_else(|| {
+            BuilderError::MissingField("'mfg_batch_namespace' field is required".to_string())
+        })?;
+
+        if self.mfg_batch_ids.is_empty() {
+            return Err(BuilderError::MissingField(
+                "'mfg_batch_ids' field is required".to_string(),
+            ));
+        }
+
+        let recall_id = self.recall_id.ok_or_else(|| {
+            BuilderError::MissingField("'recall_id' field is required".to_string())
+        })?;
+
+        let reason_code = self.reason_code.ok_or_else(|| {
+            BuilderError::MissingField("'reason_code' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchRecallAction {
+            mfg_batch_namespace,
+            mfg_batch_ids: self.mfg_batch_ids,
+            recall_id,
+            reason_code,
+        })
+    }
+}
+/*
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::mfg_batch::state::AttachmentBuilder;
+    use crate::protocol::schema::state::{DataType, PropertyValueBuilder};
+    use std::fmt::Debug;
+
+    #[test]
+    /// Validate that a `MfgBatchCreateAction` is built correctly
+    fn test_product_create_builder() {
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("Target".into())
+            .with_properties(make_properties())
+            .build()
+            .unwrap();
+
+        assert_eq!(action.mfg_batch_id(), "688955434684");
+        assert_eq!(action.owner(), "Target");
+        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
+        assert_eq!(action.properties()[0].name(), "description");
+        assert_eq!(*action.properties()[0].data_type(), DataType::String);
+        assert_eq!(
+            action.properties()[0].string_value(),
+            "This is a product description"
+        );
+        assert_eq!(action.properties()[1].name(), "price");
+        assert_eq!(*action.properties()[1].data_type(), DataType::Number);
+        assert_eq!(*action.properties()[1].number_value(), 3);
+    }
+
+    #[test]
+    /// Validate that a `MfgBatchCreateAction` may be correctly converted into bytes and back
+    /// to its native representation
+    fn test_product_create_into_bytes() {
+        let action = MfgBatchCreateActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_owner("Target".into())
+            .with_properties(make_properties())
+            .with_manufacture_location("0614141000005".into())
+            .with_attachments(vec![make_attachment()])
+            .build()
+            .unwrap();
+
+        test_from_bytes(action, MfgBatchCreateAction::from_bytes);
+    }
+
+    #[test]
+    /// Validate that a `MfgBatchUpdateAction` is built correctly
+    fn test_product_update_builder() {
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_properties())
+            .build()
+            .unwrap();
+
+        assert_eq!(action.mfg_batch_id(), "688955434684");
+        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
+        assert_eq!(action.properties()[0].name(), "description");
+        assert_eq!(*action.properties()[0].data_type(), DataType::String);
+        assert_eq!(
+            action.properties()[0].string_value(),
+            "This is a product description"
+        );
+        assert_eq!(action.properties()[1].name(), "price");
+        assert_eq!(*action.properties()[1].data_type(), DataType::Number);
+        assert_eq!(*action.properties()[1].number_value(), 3);
+    }
+
+    #[test]
+    /// Validate that an `MfgBatchUpdateAction` may be correctly converted into bytes and back
+    /// to its native representation
+    fn test_product_update_into_bytes() {
+        let action = MfgBatchUpdateActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_properties(make_properties())
+            .with_manufacture_location("0614141000005".into())
+            .with_expected_version("ab".repeat(32))
+            .with_validate_changed_properties_only(true)
+            .with_attachments(vec![make_attachment()])
+            .build()
+            .unwrap();
+
+        test_from_bytes(action, MfgBatchUpdateAction::from_bytes);
+    }
+
+    #[test]
+    /// Validate that an `MfgBatchDeleteAction` may be built correctly
+    fn test_product_delete_builder() {
+        let action = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .build()
+            .unwrap();
+
+        assert_eq!(action.mfg_batch_id(), "688955434684");
+        assert_eq!(*action.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
+        assert!(!action.archive());
+    }
+
+    #[test]
+    /// Validate that a `MfgBatchDeleteAction` may be correctly converted into bytes and back
+    /// to its native representation
+    fn test_product_delete_into_bytes() {
+        let action = MfgBatchDeleteActionBuilder::new()
+            .with_mfg_batch_id("688955434684".into()) // GTIN-12
+            .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
+            .with_archive(true)
+            .build()
+            .unwrap();
+
+        test_from_bytes(action, MfgBatchDeleteAction::from_bytes);
     }
 
     #[test]
@@ -702,6 +2113,7 @@ mod tests {
 
         assert_eq!(*payload.action(), Action::MfgBatchCreate(action));
         assert_eq!(*payload.timestamp(), 0);
+        assert_eq!(payload.service_id(), "");
     }
 
     #[test]
@@ -719,12 +2131,22 @@ mod tests {
         let payload = MfgBatchPayloadBuilder::new()
             .with_action(Action::MfgBatchCreate(action.clone()))
             .with_timestamp(0)
+            .with_service_id("01234-ABCDE::gr00".into())
             .build()
             .unwrap();
 
         test_from_bytes(payload, MfgBatchPayload::from_bytes);
     }
 
+    fn make_attachment() -> Attachment {
+        AttachmentBuilder::new()
+            .with_digest("ab".repeat(32))
+            .with_mime_type("application/pdf".into())
+            .with_size(48_213)
+            .build()
+            .expect("Failed to build attachment")
+    }
+
     fn make_properties() -> Vec<PropertyValue> {
         let property_value_description = PropertyValueBuilder::new()
             .with_name("description".into())
@@ -14,6 +14,8 @@
 
 //! Protocol structs for MfgBatch state
 
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
 use protobuf::Message;
 use protobuf::RepeatedField;
 
@@ -31,8 +33,16 @@ use crate::protocol::schema::state::PropertyValue;
 ///
 /// The namespace determines the schema used to define a `MfgBatch`'s properties
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub enum MfgBatchNamespace {
+    #[cfg_attr(feature = "mfg-batch-serde", serde(rename = "GS1"))]
     Gs1,
+    /// Batches identified by a manufacturer's own batch numbers rather than GS1 keys
+    #[cfg_attr(feature = "mfg-batch-serde", serde(rename = "INTERNAL"))]
+    Internal,
+    /// Batches identified by a lot number
+    #[cfg_attr(feature = "mfg-batch-serde", serde(rename = "LOT"))]
+    Lot,
 }
 
 impl Default for MfgBatchNamespace {
@@ -47,6 +57,10 @@ impl FromProto<protos::mfg_batch_state::MfgBatch_MfgBatchNamespace> for MfgBatch
     ) -> Result<Self, ProtoConversionError> {
         match mfg_batch_namespace {
             protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::GS1 => Ok(MfgBatchNamespace::Gs1),
+            protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::INTERNAL => {
+                Ok(MfgBatchNamespace::Internal)
+            }
+            protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::LOT => Ok(MfgBatchNamespace::Lot),
             protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::UNSET_TYPE => {
                 Err(ProtoConversionError::InvalidTypeError(
                     "Cannot convert MfgBatch_MfgBatchNamespace with type UNSET_TYPE".to_string(),
@@ -60,6 +74,10 @@ impl FromNative<MfgBatchNamespace> for protos::mfg_batch_state::MfgBatch_MfgBatc
     fn from_native(mfg_batch_namespace: MfgBatchNamespace) -> Result<Self, ProtoConversionError> {
         match mfg_batch_namespace {
             MfgBatchNamespace::Gs1 => Ok(protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::GS1),
+            MfgBatchNamespace::Internal => {
+                Ok(protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::INTERNAL)
+            }
+            MfgBatchNamespace::Lot => Ok(protos::mfg_batch_state::MfgBatch_MfgBatchNamespace::LOT),
         }
     }
 }
@@ -67,15 +85,629 @@ impl FromNative<MfgBatchNamespace> for protos::mfg_batch_state::MfgBatch_MfgBatc
 impl IntoProto<protos::mfg_batch_state::MfgBatch_MfgBatchNamespace> for MfgBatchNamespace {}
 impl IntoNative<MfgBatchNamespace> for protos::mfg_batch_state::MfgBatch_MfgBatchNamespace {}
 
+/// Native representation of a quality control test result recorded against a `MfgBatch`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct TestResult {
+    test_name: String,
+    specification: String,
+    result: String,
+    passed: bool,
+    lab: String,
+    timestamp: u64,
+}
+
+impl TestResult {
+    pub fn test_name(&self) -> &str {
+        &self.test_name
+    }
+
+    pub fn specification(&self) -> &str {
+        &self.specification
+    }
+
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    pub fn passed(&self) -> bool {
+        self.passed
+    }
+
+    pub fn lab(&self) -> &str {
+        &self.lab
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn into_builder(self) -> TestResultBuilder {
+        TestResultBuilder::new()
+            .with_test_name(self.test_name)
+            .with_specification(self.specification)
+            .with_result(self.result)
+            .with_passed(self.passed)
+            .with_lab(self.lab)
+            .with_timestamp(self.timestamp)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::TestResult> for TestResult {
+    fn from_proto(
+        test_result: protos::mfg_batch_state::TestResult,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(TestResult {
+            test_name: test_result.get_test_name().to_string(),
+            specification: test_result.get_specification().to_string(),
+            result: test_result.get_result().to_string(),
+            passed: test_result.get_passed(),
+            lab: test_result.get_lab().to_string(),
+            timestamp: test_result.get_timestamp(),
+        })
+    }
+}
+
+impl FromNative<TestResult> for protos::mfg_batch_state::TestResult {
+    fn from_native(test_result: TestResult) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::TestResult::new();
+        proto.set_test_name(test_result.test_name);
+        proto.set_specification(test_result.specification);
+        proto.set_result(test_result.result);
+        proto.set_passed(test_result.passed);
+        proto.set_lab(test_result.lab);
+        proto.set_timestamp(test_result.timestamp);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::TestResult> for TestResult {}
+impl IntoNative<TestResult> for protos::mfg_batch_state::TestResult {}
+
+/// Builder used to create a `TestResult`
+#[derive(Default, Clone, PartialEq)]
+pub struct TestResultBuilder {
+    pub test_name: Option<String>,
+    pub specification: Option<String>,
+    pub result: Option<String>,
+    pub passed: Option<bool>,
+    pub lab: Option<String>,
+    pub timestamp: Option<u64>,
+}
+
+impl TestResultBuilder {
+    pub fn new() -> Self {
+        TestResultBuilder::default()
+    }
+
+    pub fn with_test_name(mut self, test_name: String) -> Self {
+        self.test_name = Some(test_name);
+        self
+    }
+
+    pub fn with_specification(mut self, specification: String) -> Self {
+        self.specification = Some(specification);
+        self
+    }
+
+    pub fn with_result(mut self, result: String) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    pub fn with_passed(mut self, passed: bool) -> Self {
+        self.passed = Some(passed);
+        self
+    }
+
+    pub fn with_lab(mut self, lab: String) -> Self {
+        self.lab = Some(lab);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn build(self) -> Result<TestResult, MfgBatchBuildError> {
+        let test_name = self.test_name.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'test_name' field is required".to_string())
+        })?;
+
+        let result = self.result.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'result' field is required".to_string())
+        })?;
+
+        let passed = self.passed.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'passed' field is required".to_string())
+        })?;
+
+        let timestamp = self.timestamp.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'timestamp' field is required".to_string())
+        })?;
+
+        // Not every test is run against a written specification or by an outside lab
+        let specification = self.specification.unwrap_or_default();
+        let lab = self.lab.unwrap_or_default();
+
+        Ok(TestResult {
+            test_name,
+            specification,
+            result,
+            passed,
+            lab,
+            timestamp,
+        })
+    }
+}
+
+/// Native representation of a sign-off on a `MfgBatch` by a lab, auditor or other outside party
+///
+/// The signature is over the batch's canonical bytes, as returned by
+/// [`MfgBatch::canonical_bytes`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct Attestation {
+    signer_public_key: String,
+    algorithm: String,
+    signature: String,
+    purpose: String,
+}
+
+impl Attestation {
+    pub fn signer_public_key(&self) -> &str {
+        &self.signer_public_key
+    }
+
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn purpose(&self) -> &str {
+        &self.purpose
+    }
+
+    pub fn into_builder(self) -> AttestationBuilder {
+        AttestationBuilder::new()
+            .with_signer_public_key(self.signer_public_key)
+            .with_algorithm(self.algorithm)
+            .with_signature(self.signature)
+            .with_purpose(self.purpose)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::Attestation> for Attestation {
+    fn from_proto(
+        attestation: protos::mfg_batch_state::Attestation,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(Attestation {
+            signer_public_key: attestation.get_signer_public_key().to_string(),
+            algorithm: attestation.get_algorithm().to_string(),
+            signature: attestation.get_signature().to_string(),
+            purpose: attestation.get_purpose().to_string(),
+        })
+    }
+}
+
+impl FromNative<Attestation> for protos::mfg_batch_state::Attestation {
+    fn from_native(attestation: Attestation) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::Attestation::new();
+        proto.set_signer_public_key(attestation.signer_public_key);
+        proto.set_algorithm(attestation.algorithm);
+        proto.set_signature(attestation.signature);
+        proto.set_purpose(attestation.purpose);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::Attestation> for Attestation {}
+impl IntoNative<Attestation> for protos::mfg_batch_state::Attestation {}
+
+/// Builder used to create an `Attestation`
+#[derive(Default, Clone, PartialEq)]
+pub struct AttestationBuilder {
+    pub signer_public_key: Option<String>,
+    pub algorithm: Option<String>,
+    pub signature: Option<String>,
+    pub purpose: Option<String>,
+}
+
+impl AttestationBuilder {
+    pub fn new() -> Self {
+        AttestationBuilder::default()
+    }
+
+    pub fn with_signer_public_key(mut self, signer_public_key: String) -> Self {
+        self.signer_public_key = Some(signer_public_key);
+        self
+    }
+
+    pub fn with_algorithm(mut self, algorithm: String) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    pub fn with_signature(mut self, signature: String) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    pub fn with_purpose(mut self, purpose: String) -> Self {
+        self.purpose = Some(purpose);
+        self
+    }
+
+    pub fn build(self) -> Result<Attestation, MfgBatchBuildError> {
+        let signer_public_key = self.signer_public_key.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'signer_public_key' field is required".to_string())
+        })?;
+
+        let algorithm = self.algorithm.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'algorithm' field is required".to_string())
+        })?;
+
+        let signature = self.signature.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'signature' field is required".to_string())
+        })?;
+
+        let purpose = self.purpose.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'purpose' field is required".to_string())
+        })?;
+
+        Ok(Attestation {
+            signer_public_key,
+            algorithm,
+            signature,
+            purpose,
+        })
+    }
+}
+
+/// Native representation of a document about a `MfgBatch` kept off-chain, such as a certificate of
+/// analysis, referenced by the digest of its contents
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct Attachment {
+    digest: String,
+    mime_type: String,
+    size: u64,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    uri: String,
+}
+
+impl Attachment {
+    /// Returns the hex encoded SHA-256 digest of the document
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// Returns the size of the document, in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns where the document can be retrieved from, empty if it is shared some other way
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn into_builder(self) -> AttachmentBuilder {
+        AttachmentBuilder::new()
+            .with_digest(self.digest)
+            .with_mime_type(self.mime_type)
+            .with_size(self.size)
+            .with_uri(self.uri)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::Attachment> for Attachment {
+    fn from_proto(
+        attachment: protos::mfg_batch_state::Attachment,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(Attachment {
+            digest: attachment.get_digest().to_string(),
+            mime_type: attachment.get_mime_type().to_string(),
+            size: attachment.get_size(),
+            uri: attachment.get_uri().to_string(),
+        })
+    }
+}
+
+impl FromNative<Attachment> for protos::mfg_batch_state::Attachment {
+    fn from_native(attachment: Attachment) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::Attachment::new();
+        proto.set_digest(attachment.digest);
+        proto.set_mime_type(attachment.mime_type);
+        proto.set_size(attachment.size);
+        proto.set_uri(attachment.uri);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::Attachment> for Attachment {}
+impl IntoNative<Attachment> for protos::mfg_batch_state::Attachment {}
+
+/// Builder used to create an `Attachment`
+#[derive(Default, Clone, PartialEq)]
+pub struct AttachmentBuilder {
+    pub digest: Option<String>,
+    pub mime_type: Option<String>,
+    pub size: Option<u64>,
+    pub uri: Option<String>,
+}
+
+impl AttachmentBuilder {
+    pub fn new() -> Self {
+        AttachmentBuilder::default()
+    }
+
+    pub fn with_digest(mut self, digest: String) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    pub fn with_mime_type(mut self, mime_type: String) -> Self {
+        self.mime_type = Some(mime_type);
+        self
+    }
+
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_uri(mut self, uri: String) -> Self {
+        self.uri = Some(uri);
+        self
+    }
+
+    pub fn build(self) -> Result<Attachment, MfgBatchBuildError> {
+        let digest = self.digest.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'digest' field is required".to_string())
+        })?;
+
+        let mime_type = self.mime_type.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'mime_type' field is required".to_string())
+        })?;
+
+        let size = self.size.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'size' field is required".to_string())
+        })?;
+
+        // The document may be shared without a URI to retrieve it from
+        let uri = self.uri.unwrap_or_default();
+
+        Ok(Attachment {
+            digest,
+            mime_type,
+            size,
+            uri,
+        })
+    }
+}
+
+/// Native representation of a quantity of a `MfgBatch` reserved by a sales order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct Allocation {
+    order_id: String,
+    quantity: i64,
+}
+
+impl Allocation {
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+
+    pub fn into_builder(self) -> AllocationBuilder {
+        AllocationBuilder::new()
+            .with_order_id(self.order_id)
+            .with_quantity(self.quantity)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::Allocation> for Allocation {
+    fn from_proto(
+        allocation: protos::mfg_batch_state::Allocation,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(Allocation {
+            order_id: allocation.get_order_id().to_string(),
+            quantity: allocation.get_quantity(),
+        })
+    }
+}
+
+impl FromNative<Allocation> for protos::mfg_batch_state::Allocation {
+    fn from_native(allocation: Allocation) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::Allocation::new();
+        proto.set_order_id(allocation.order_id);
+        proto.set_quantity(allocation.quantity);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::Allocation> for Allocation {}
+impl IntoNative<Allocation> for protos::mfg_batch_state::Allocation {}
+
+/// Builder used to create an `Allocation`
+#[derive(Default, Clone, PartialEq)]
+pub struct AllocationBuilder {
+    pub order_id: Option<String>,
+    pub quantity: Option<i64>,
+}
+
+impl AllocationBuilder {
+    pub fn new() -> Self {
+        AllocationBuilder::default()
+    }
+
+    pub fn with_order_id(mut self, order_id: String) -> Self {
+        self.order_id = Some(order_id);
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: i64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn build(self) -> Result<Allocation, MfgBatchBuildError> {
+        let order_id = self.order_id.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'order_id' field is required".to_string())
+        })?;
+
+        let quantity = self.quantity.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'quantity' field is required".to_string())
+        })?;
+
+        Ok(Allocation { order_id, quantity })
+    }
+}
+
+/// Native representation of the recall of a `MfgBatch`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct Recall {
+    recall_id: String,
+    reason_code: String,
+    timestamp: u64,
+}
+
+impl Recall {
+    pub fn recall_id(&self) -> &str {
+        &self.recall_id
+    }
+
+    pub fn reason_code(&self) -> &str {
+        &self.reason_code
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn into_builder(self) -> RecallBuilder {
+        RecallBuilder::new()
+            .with_recall_id(self.recall_id)
+            .with_reason_code(self.reason_code)
+            .with_timestamp(self.timestamp)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::Recall> for Recall {
+    fn from_proto(recall: protos::mfg_batch_state::Recall) -> Result<Self, ProtoConversionError> {
+        Ok(Recall {
+            recall_id: recall.get_recall_id().to_string(),
+            reason_code: recall.get_reason_code().to_string(),
+            timestamp: recall.get_timestamp(),
+        })
+    }
+}
+
+impl FromNative<Recall> for protos::mfg_batch_state::Recall {
+    fn from_native(recall: Recall) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::Recall::new();
+        proto.set_recall_id(recall.recall_id);
+        proto.set_reason_code(recall.reason_code);
+        proto.set_timestamp(recall.timestamp);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::Recall> for Recall {}
+impl IntoNative<Recall> for protos::mfg_batch_state::Recall {}
+
+/// Builder used to create a `Recall`
+#[derive(Default, Clone, PartialEq)]
+pub struct RecallBuilder {
+    pub recall_id: Option<String>,
+    pub reason_code: Option<String>,
+    pub timestamp: Option<u64>,
+}
+
+impl RecallBuilder {
+    pub fn new() -> Self {
+        RecallBuilder::default()
+    }
+
+    pub fn with_recall_id(mut self, recall_id: String) -> Self {
+        self.recall_id = Some(recall_id);
+        self
+    }
+
+    pub fn with_reason_code(mut self, reason_code: String) -> Self {
+        self.reason_code = Some(reason_code);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn build(self) -> Result<Recall, MfgBatchBuildError> {
+        let recall_id = self.recall_id.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'recall_id' field is required".to_string())
+        })?;
+
+        let reason_code = self.reason_code.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'reason_code' field is required".to_string())
+        })?;
+
+        let timestamp = self.timestamp.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'timestamp' field is required".to_string())
+        })?;
+
+        Ok(Recall {
+            recall_id,
+            reason_code,
+            timestamp,
+        })
+    }
+}
+
 /// Native representation of `MfgBatch`
 ///
 /// A `MfgBatch` contains a list of properties determined by the `mfg_batch_namespace`.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct MfgBatch {
     mfg_batch_id: String,
     mfg_batch_namespace: MfgBatchNamespace,
     owner: String,
     properties: Vec<PropertyValue>,
+    parent_batches: Vec<String>,
+    quantity: i64,
+    uom: String,
+    expected_quantity: i64,
+    production_date: u64,
+    expiration_date: u64,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    manufacture_location: String,
+    archived: bool,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    test_results: Vec<TestResult>,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    attestations: Vec<Attestation>,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    allocations: Vec<Allocation>,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    recall: Option<Recall>,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    attachments: Vec<Attachment>,
 }
 
 impl MfgBatch {
@@ -95,20 +727,138 @@ impl MfgBatch {
         &self.properties
     }
 
+    pub fn parent_batches(&self) -> &[String] {
+        &self.parent_batches
+    }
+
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+
+    pub fn uom(&self) -> &str {
+        &self.uom
+    }
+
+    pub fn expected_quantity(&self) -> i64 {
+        self.expected_quantity
+    }
+
+    pub fn production_date(&self) -> u64 {
+        self.production_date
+    }
+
+    pub fn expiration_date(&self) -> u64 {
+        self.expiration_date
+    }
+
+    /// Returns the GLN of the location the batch was produced at, empty if not recorded
+    pub fn manufacture_location(&self) -> &str {
+        &self.manufacture_location
+    }
+
+    pub fn archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn test_results(&self) -> &[TestResult] {
+        &self.test_results
+    }
+
+    pub fn attestations(&self) -> &[Attestation] {
+        &self.attestations
+    }
+
+    pub fn allocations(&self) -> &[Allocation] {
+        &self.allocations
+    }
+
+    /// Returns the recall of the batch, or `None` if it has not been recalled
+    pub fn recall(&self) -> Option<&Recall> {
+        self.recall.as_ref()
+    }
+
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// Returns the quantity reserved by sales orders, in units of the batch's uom
+    pub fn allocated_quantity(&self) -> i64 {
+        self.allocations
+            .iter()
+            .map(|allocation| allocation.quantity)
+            .sum()
+    }
+
+    /// Returns the quantity produced that no sales order has reserved yet
+    pub fn available_quantity(&self) -> i64 {
+        self.quantity - self.allocated_quantity()
+    }
+
+    /// Returns the bytes an attestation of the batch is signed over: the batch's protobuf
+    /// encoding with the fields that change after it is attested left out.
+    ///
+    /// * `attestations` - an attestation cannot sign over itself, and each must stay valid as
+    ///   others are added
+    /// * `allocations` - reserving the batch's inventory for sales orders does not change what
+    ///   was produced and attested
+    /// * `recall` - a recall records what became of the batch, and must not invalidate the
+    ///   attestations it is judged against
+    ///
+    /// These fields are still part of the batch's `version`, which hashes its full encoding.
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>, ProtoConversionError> {
+        let mut builder = self
+            .clone()
+            .into_builder()
+            .with_attestations(vec![])
+            .with_allocations(vec![]);
+        builder.recall = None;
+        builder
+            .build()
+            .map_err(|err| ProtoConversionError::SerializationError(err.to_string()))?
+            .into_bytes()
+    }
+
+    /// Returns the version of the batch: the hex encoded SHA-256 hash of its protobuf encoding.
+    /// Any change to the batch gives it a new version, so an update can name the version it was
+    /// built against and be rejected if the batch has changed since.
+    pub fn version(&self) -> Result<String, ProtoConversionError> {
+        let mut sha = Sha256::new();
+        sha.input(&self.clone().into_bytes()?);
+        Ok(sha.result_str())
+    }
+
     pub fn into_builder(self) -> MfgBatchBuilder {
-        MfgBatchBuilder::new()
+        let mut builder = MfgBatchBuilder::new()
             .with_mfg_batch_id(self.mfg_batch_id)
             .with_mfg_batch_namespace(self.mfg_batch_namespace)
             .with_owner(self.owner)
             .with_properties(self.properties)
+            .with_parent_batches(self.parent_batches)
+            .with_quantity(self.quantity)
+            .with_uom(self.uom)
+            .with_expected_quantity(self.expected_quantity)
+            .with_production_date(self.production_date)
+            .with_expiration_date(self.expiration_date)
+            .with_manufacture_location(self.manufacture_location)
+            .with_archived(self.archived)
+            .with_test_results(self.test_results)
+            .with_attestations(self.attestations)
+            .with_allocations(self.allocations)
+            .with_attachments(self.attachments);
+        builder.recall = self.recall;
+        builder
     }
 }
 
 impl FromProto<protos::mfg_batch_state::MfgBatch> for MfgBatch {
-    fn from_proto(mfg_batch: protos::mfg_batch_state::MfgBatch) -> Result<Self, ProtoConversionError> {
+    fn from_proto(
+        mfg_batch: protos::mfg_batch_state::MfgBatch,
+    ) -> Result<Self, ProtoConversionError> {
         Ok(MfgBatch {
             mfg_batch_id: mfg_batch.get_mfg_batch_id().to_string(),
-            mfg_batch_namespace: MfgBatchNamespace::from_proto(mfg_batch.get_mfg_batch_namespace())?,
+            mfg_batch_namespace: MfgBatchNamespace::from_proto(
+                mfg_batch.get_mfg_batch_namespace(),
+            )?,
             owner: mfg_batch.get_owner().to_string(),
             properties: mfg_batch
                 .get_properties()
@@ -116,6 +866,43 @@ impl FromProto<protos::mfg_batch_state::MfgBatch> for MfgBatch {
                 .into_iter()
                 .map(PropertyValue::from_proto)
                 .collect::<Result<Vec<PropertyValue>, ProtoConversionError>>()?,
+            parent_batches: mfg_batch.get_parent_batches().to_vec(),
+            quantity: mfg_batch.get_quantity(),
+            uom: mfg_batch.get_uom().to_string(),
+            expected_quantity: mfg_batch.get_expected_quantity(),
+            production_date: mfg_batch.get_production_date(),
+            expiration_date: mfg_batch.get_expiration_date(),
+            manufacture_location: mfg_batch.get_manufacture_location().to_string(),
+            archived: mfg_batch.get_archived(),
+            test_results: mfg_batch
+                .get_test_results()
+                .to_vec()
+                .into_iter()
+                .map(TestResult::from_proto)
+                .collect::<Result<Vec<TestResult>, ProtoConversionError>>()?,
+            attestations: mfg_batch
+                .get_attestations()
+                .to_vec()
+                .into_iter()
+                .map(Attestation::from_proto)
+                .collect::<Result<Vec<Attestation>, ProtoConversionError>>()?,
+            allocations: mfg_batch
+                .get_allocations()
+                .to_vec()
+                .into_iter()
+                .map(Allocation::from_proto)
+                .collect::<Result<Vec<Allocation>, ProtoConversionError>>()?,
+            recall: if mfg_batch.has_recall() {
+                Some(Recall::from_proto(mfg_batch.get_recall().clone())?)
+            } else {
+                None
+            },
+            attachments: mfg_batch
+                .get_attachments()
+                .to_vec()
+                .into_iter()
+                .map(Attachment::from_proto)
+                .collect::<Result<Vec<Attachment>, ProtoConversionError>>()?,
         })
     }
 }
@@ -134,6 +921,52 @@ impl FromNative<MfgBatch> for protos::mfg_batch_state::MfgBatch {
                 .map(PropertyValue::into_proto)
                 .collect::<Result<Vec<schema_state::PropertyValue>, ProtoConversionError>>()?,
         ));
+        proto.set_parent_batches(RepeatedField::from_vec(mfg_batch.parent_batches().to_vec()));
+        proto.set_quantity(mfg_batch.quantity());
+        proto.set_uom(mfg_batch.uom().to_string());
+        proto.set_expected_quantity(mfg_batch.expected_quantity());
+        proto.set_production_date(mfg_batch.production_date());
+        proto.set_expiration_date(mfg_batch.expiration_date());
+        proto.set_manufacture_location(mfg_batch.manufacture_location().to_string());
+        proto.set_archived(mfg_batch.archived());
+        proto.set_test_results(RepeatedField::from_vec(
+            mfg_batch
+                .test_results()
+                .to_vec()
+                .into_iter()
+                .map(TestResult::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::TestResult>, ProtoConversionError>>(
+                )?,
+        ));
+        proto.set_attestations(RepeatedField::from_vec(
+            mfg_batch
+                .attestations()
+                .to_vec()
+                .into_iter()
+                .map(Attestation::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::Attestation>, ProtoConversionError>>(
+                )?,
+        ));
+        proto.set_allocations(RepeatedField::from_vec(
+            mfg_batch
+                .allocations()
+                .to_vec()
+                .into_iter()
+                .map(Allocation::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::Allocation>, ProtoConversionError>>(
+                )?,
+        ));
+        proto.set_attachments(RepeatedField::from_vec(
+            mfg_batch
+                .attachments
+                .into_iter()
+                .map(Attachment::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::Attachment>, ProtoConversionError>>(
+                )?,
+        ));
+        if let Some(recall) = mfg_batch.recall {
+            proto.set_recall(recall.into_proto()?);
+        }
         Ok(proto)
     }
 }
@@ -154,14 +987,39 @@ impl IntoBytes for MfgBatch {
     fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
         let proto = self.into_proto()?;
         let bytes = proto.write_to_bytes().map_err(|_| {
-            ProtoConversionError::SerializationError("Unable to get bytes from MfgBatch".to_string())
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatch".to_string(),
+            )
         })?;
         Ok(bytes)
     }
-}
+}
+
+impl IntoProto<protos::mfg_batch_state::MfgBatch> for MfgBatch {}
+impl IntoNative<MfgBatch> for protos::mfg_batch_state::MfgBatch {}
+
+#[cfg(feature = "mfg-batch-serde")]
+impl MfgBatch {
+    /// Parses the mfg_batch from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, ProtoConversionError> {
+        serde_json::from_str(json).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to parse MfgBatch from JSON: {}",
+                err
+            ))
+        })
+    }
 
-impl IntoProto<protos::mfg_batch_state::MfgBatch> for MfgBatch {}
-impl IntoNative<MfgBatch> for protos::mfg_batch_state::MfgBatch {}
+    /// Returns the JSON representation of the mfg_batch
+    pub fn to_json(&self) -> Result<String, ProtoConversionError> {
+        serde_json::to_string(self).map_err(|err| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to write MfgBatch as JSON: {}",
+                err
+            ))
+        })
+    }
+}
 
 /// Returned if any required fields in a `MfgBatch` are not present when being
 /// converted from the corresponding builder
@@ -203,6 +1061,19 @@ pub struct MfgBatchBuilder {
     pub mfg_batch_namespace: Option<MfgBatchNamespace>,
     pub owner: Option<String>,
     pub properties: Option<Vec<PropertyValue>>,
+    pub parent_batches: Option<Vec<String>>,
+    pub quantity: Option<i64>,
+    pub uom: Option<String>,
+    pub expected_quantity: Option<i64>,
+    pub production_date: Option<u64>,
+    pub expiration_date: Option<u64>,
+    pub manufacture_location: Option<String>,
+    pub archived: Option<bool>,
+    pub test_results: Option<Vec<TestResult>>,
+    pub attestations: Option<Vec<Attestation>>,
+    pub allocations: Option<Vec<Allocation>>,
+    pub recall: Option<Recall>,
+    pub attachments: Option<Vec<Attachment>>,
 }
 
 impl MfgBatchBuilder {
@@ -230,6 +1101,71 @@ impl MfgBatchBuilder {
         self
     }
 
+    pub fn with_parent_batches(mut self, parent_batches: Vec<String>) -> Self {
+        self.parent_batches = Some(parent_batches);
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: i64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn with_uom(mut self, uom: String) -> Self {
+        self.uom = Some(uom);
+        self
+    }
+
+    pub fn with_expected_quantity(mut self, expected_quantity: i64) -> Self {
+        self.expected_quantity = Some(expected_quantity);
+        self
+    }
+
+    pub fn with_production_date(mut self, production_date: u64) -> Self {
+        self.production_date = Some(production_date);
+        self
+    }
+
+    pub fn with_expiration_date(mut self, expiration_date: u64) -> Self {
+        self.expiration_date = Some(expiration_date);
+        self
+    }
+
+    pub fn with_manufacture_location(mut self, manufacture_location: String) -> Self {
+        self.manufacture_location = Some(manufacture_location);
+        self
+    }
+
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = Some(archived);
+        self
+    }
+
+    pub fn with_test_results(mut self, test_results: Vec<TestResult>) -> Self {
+        self.test_results = Some(test_results);
+        self
+    }
+
+    pub fn with_attestations(mut self, attestations: Vec<Attestation>) -> Self {
+        self.attestations = Some(attestations);
+        self
+    }
+
+    pub fn with_allocations(mut self, allocations: Vec<Allocation>) -> Self {
+        self.allocations = Some(allocations);
+        self
+    }
+
+    pub fn with_recall(mut self, recall: Recall) -> Self {
+        self.recall = Some(recall);
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = Some(attachments);
+        self
+    }
+
     pub fn build(self) -> Result<MfgBatch, MfgBatchBuildError> {
         let mfg_batch_id = self.mfg_batch_id.ok_or_else(|| {
             MfgBatchBuildError::MissingField("'mfg_batch_id' field is required".to_string())
@@ -248,11 +1184,56 @@ impl MfgBatchBuilder {
             MfgBatchBuildError::MissingField("'properties' field is required".to_string())
         })?;
 
+        // A batch without parents is a root of the genealogy
+        let parent_batches = self.parent_batches.unwrap_or_default();
+
+        // Quantities are not required; an empty uom means none have been recorded
+        let quantity = self.quantity.unwrap_or_default();
+        let uom = self.uom.unwrap_or_default();
+        let expected_quantity = self.expected_quantity.unwrap_or_default();
+
+        // Dates are not required; 0 means the date has not been recorded
+        let production_date = self.production_date.unwrap_or_default();
+        let expiration_date = self.expiration_date.unwrap_or_default();
+
+        // Nor is the location; an empty GLN means it has not been recorded
+        let manufacture_location = self.manufacture_location.unwrap_or_default();
+
+        let archived = self.archived.unwrap_or_default();
+
+        // Batches start out with no quality control results
+        let test_results = self.test_results.unwrap_or_default();
+
+        // Nor has anyone signed off on them yet
+        let attestations = self.attestations.unwrap_or_default();
+
+        // Nor has any of them been reserved by a sales order
+        let allocations = self.allocations.unwrap_or_default();
+
+        // Nor recalled
+        let recall = self.recall;
+
+        // Documents about the batch are not required
+        let attachments = self.attachments.unwrap_or_default();
+
         Ok(MfgBatch {
             mfg_batch_id,
             mfg_batch_namespace,
             owner,
             properties,
+            parent_batches,
+            quantity,
+            uom,
+            expected_quantity,
+            production_date,
+            expiration_date,
+            manufacture_location,
+            archived,
+            test_results,
+            attestations,
+            allocations,
+            recall,
+            attachments,
         })
     }
 }
@@ -298,7 +1279,8 @@ impl FromNative<MfgBatchList> for protos::mfg_batch_state::MfgBatchList {
                 .to_vec()
                 .into_iter()
                 .map(MfgBatch::into_proto)
-                .collect::<Result<Vec<protos::mfg_batch_state::MfgBatch>, ProtoConversionError>>()?,
+                .collect::<Result<Vec<protos::mfg_batch_state::MfgBatch>, ProtoConversionError>>(
+                )?,
         ));
 
         Ok(mfg_batch_list_proto)
@@ -307,8 +1289,8 @@ impl FromNative<MfgBatchList> for protos::mfg_batch_state::MfgBatchList {
 
 impl FromBytes<MfgBatchList> for MfgBatchList {
     fn from_bytes(bytes: &[u8]) -> Result<MfgBatchList, ProtoConversionError> {
-        let proto: protos::mfg_batch_state::MfgBatchList =
-            Message::parse_from_bytes(bytes).map_err(|_| {
+        let proto: protos::mfg_batch_state::MfgBatchList = Message::parse_from_bytes(bytes)
+            .map_err(|_| {
                 ProtoConversionError::SerializationError(
                     "Unable to get MfgBatchList from bytes".to_string(),
                 )
@@ -397,6 +1379,254 @@ impl MfgBatchListBuilder {
     }
 }
 
+/// Native representation of an attestation that the off-chain copy of the mfg_batch records
+/// had a given Merkle root at a commit
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
+pub struct MfgBatchAnchor {
+    owner: String,
+    commit_num: i64,
+    merkle_root: String,
+    row_count: u64,
+    timestamp: u64,
+}
+
+impl MfgBatchAnchor {
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn commit_num(&self) -> i64 {
+        self.commit_num
+    }
+
+    pub fn merkle_root(&self) -> &str {
+        &self.merkle_root
+    }
+
+    pub fn row_count(&self) -> u64 {
+        self.row_count
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn into_builder(self) -> MfgBatchAnchorBuilder {
+        MfgBatchAnchorBuilder::new()
+            .with_owner(self.owner)
+            .with_commit_num(self.commit_num)
+            .with_merkle_root(self.merkle_root)
+            .with_row_count(self.row_count)
+            .with_timestamp(self.timestamp)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::MfgBatchAnchor> for MfgBatchAnchor {
+    fn from_proto(
+        anchor: protos::mfg_batch_state::MfgBatchAnchor,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAnchor {
+            owner: anchor.get_owner().to_string(),
+            commit_num: anchor.get_commit_num(),
+            merkle_root: anchor.get_merkle_root().to_string(),
+            row_count: anchor.get_row_count(),
+            timestamp: anchor.get_timestamp(),
+        })
+    }
+}
+
+impl FromNative<MfgBatchAnchor> for protos::mfg_batch_state::MfgBatchAnchor {
+    fn from_native(anchor: MfgBatchAnchor) -> Result<Self, ProtoConversionError> {
+        let mut proto = protos::mfg_batch_state::MfgBatchAnchor::new();
+        proto.set_owner(anchor.owner);
+        proto.set_commit_num(anchor.commit_num);
+        proto.set_merkle_root(anchor.merkle_root);
+        proto.set_row_count(anchor.row_count);
+        proto.set_timestamp(anchor.timestamp);
+        Ok(proto)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::MfgBatchAnchor> for MfgBatchAnchor {}
+impl IntoNative<MfgBatchAnchor> for protos::mfg_batch_state::MfgBatchAnchor {}
+
+/// Builder used to create a `MfgBatchAnchor`
+#[derive(Default, Clone, PartialEq)]
+pub struct MfgBatchAnchorBuilder {
+    pub owner: Option<String>,
+    pub commit_num: Option<i64>,
+    pub merkle_root: Option<String>,
+    pub row_count: Option<u64>,
+    pub timestamp: Option<u64>,
+}
+
+impl MfgBatchAnchorBuilder {
+    pub fn new() -> Self {
+        MfgBatchAnchorBuilder::default()
+    }
+
+    pub fn with_owner(mut self, owner: String) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn with_commit_num(mut self, commit_num: i64) -> Self {
+        self.commit_num = Some(commit_num);
+        self
+    }
+
+    pub fn with_merkle_root(mut self, merkle_root: String) -> Self {
+        self.merkle_root = Some(merkle_root);
+        self
+    }
+
+    pub fn with_row_count(mut self, row_count: u64) -> Self {
+        self.row_count = Some(row_count);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAnchor, MfgBatchBuildError> {
+        let owner = self.owner.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'owner' field is required".to_string())
+        })?;
+
+        let commit_num = self.commit_num.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'commit_num' field is required".to_string())
+        })?;
+
+        let merkle_root = self.merkle_root.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'merkle_root' field is required".to_string())
+        })?;
+
+        let row_count = self.row_count.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'row_count' field is required".to_string())
+        })?;
+
+        let timestamp = self.timestamp.ok_or_else(|| {
+            MfgBatchBuildError::MissingField("'timestamp' field is required".to_string())
+        })?;
+
+        Ok(MfgBatchAnchor {
+            owner,
+            commit_num,
+            merkle_root,
+            row_count,
+            timestamp,
+        })
+    }
+}
+
+/// Native representation of a list of `MfgBatchAnchor`s
+#[derive(Debug, Clone, PartialEq)]
+pub struct MfgBatchAnchorList {
+    anchors: Vec<MfgBatchAnchor>,
+}
+
+impl MfgBatchAnchorList {
+    pub fn anchors(&self) -> &[MfgBatchAnchor] {
+        &self.anchors
+    }
+
+    pub fn into_builder(self) -> MfgBatchAnchorListBuilder {
+        MfgBatchAnchorListBuilder::new().with_anchors(self.anchors)
+    }
+}
+
+impl FromProto<protos::mfg_batch_state::MfgBatchAnchorList> for MfgBatchAnchorList {
+    fn from_proto(
+        anchor_list: protos::mfg_batch_state::MfgBatchAnchorList,
+    ) -> Result<Self, ProtoConversionError> {
+        Ok(MfgBatchAnchorList {
+            anchors: anchor_list
+                .get_entries()
+                .to_vec()
+                .into_iter()
+                .map(MfgBatchAnchor::from_proto)
+                .collect::<Result<Vec<MfgBatchAnchor>, ProtoConversionError>>()?,
+        })
+    }
+}
+
+impl FromNative<MfgBatchAnchorList> for protos::mfg_batch_state::MfgBatchAnchorList {
+    fn from_native(anchor_list: MfgBatchAnchorList) -> Result<Self, ProtoConversionError> {
+        let mut anchor_list_proto = protos::mfg_batch_state::MfgBatchAnchorList::new();
+
+        anchor_list_proto.set_entries(RepeatedField::from_vec(
+            anchor_list
+                .anchors
+                .into_iter()
+                .map(MfgBatchAnchor::into_proto)
+                .collect::<Result<Vec<protos::mfg_batch_state::MfgBatchAnchor>, ProtoConversionError>>()?,
+        ));
+
+        Ok(anchor_list_proto)
+    }
+}
+
+impl FromBytes<MfgBatchAnchorList> for MfgBatchAnchorList {
+    fn from_bytes(bytes: &[u8]) -> Result<MfgBatchAnchorList, ProtoConversionError> {
+        let proto: protos::mfg_batch_state::MfgBatchAnchorList = Message::parse_from_bytes(bytes)
+            .map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get MfgBatchAnchorList from bytes".to_string(),
+            )
+        })?;
+        proto.into_native()
+    }
+}
+
+impl IntoBytes for MfgBatchAnchorList {
+    fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError> {
+        let proto = self.into_proto()?;
+        let bytes = proto.write_to_bytes().map_err(|_| {
+            ProtoConversionError::SerializationError(
+                "Unable to get bytes from MfgBatchAnchorList".to_string(),
+            )
+        })?;
+        Ok(bytes)
+    }
+}
+
+impl IntoProto<protos::mfg_batch_state::MfgBatchAnchorList> for MfgBatchAnchorList {}
+impl IntoNative<MfgBatchAnchorList> for protos::mfg_batch_state::MfgBatchAnchorList {}
+
+/// Builder used to create a `MfgBatchAnchorList`
+#[derive(Default, Clone)]
+pub struct MfgBatchAnchorListBuilder {
+    pub anchors: Option<Vec<MfgBatchAnchor>>,
+}
+
+impl MfgBatchAnchorListBuilder {
+    pub fn new() -> Self {
+        MfgBatchAnchorListBuilder::default()
+    }
+
+    pub fn with_anchors(mut self, anchors: Vec<MfgBatchAnchor>) -> MfgBatchAnchorListBuilder {
+        self.anchors = Some(anchors);
+        self
+    }
+
+    pub fn build(self) -> Result<MfgBatchAnchorList, MfgBatchListBuildError> {
+        let anchors = self.anchors.ok_or_else(|| {
+            MfgBatchListBuildError::MissingField("'anchors' field is required".to_string())
+        })?;
+
+        if anchors.is_empty() {
+            return Err(MfgBatchListBuildError::MissingField(
+                "'anchors' cannot be empty".to_string(),
+            ));
+        }
+
+        Ok(MfgBatchAnchorList { anchors })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -420,6 +1650,12 @@ mod tests {
         assert_eq!(mfg_batch.properties()[1].name(), "price");
         assert_eq!(*mfg_batch.properties()[1].data_type(), DataType::Number);
         assert_eq!(*mfg_batch.properties()[1].number_value(), 3);
+        assert_eq!(mfg_batch.quantity(), 950);
+        assert_eq!(mfg_batch.uom(), "KGM");
+        assert_eq!(mfg_batch.expected_quantity(), 1000);
+        assert_eq!(mfg_batch.production_date(), 1_600_000_000);
+        assert_eq!(mfg_batch.expiration_date(), 1_631_536_000);
+        assert_eq!(mfg_batch.manufacture_location(), "0614141000005");
     }
 
     #[test]
@@ -433,6 +1669,41 @@ mod tests {
         assert_eq!(builder.mfg_batch_namespace, Some(MfgBatchNamespace::Gs1));
         assert_eq!(builder.owner, Some("Target".to_string()));
         assert_eq!(builder.properties, Some(make_properties()));
+        assert_eq!(builder.quantity, Some(950));
+        assert_eq!(builder.uom, Some("KGM".to_string()));
+        assert_eq!(builder.expected_quantity, Some(1000));
+        assert_eq!(builder.production_date, Some(1_600_000_000));
+        assert_eq!(builder.expiration_date, Some(1_631_536_000));
+        assert_eq!(
+            builder.manufacture_location,
+            Some("0614141000005".to_string())
+        );
+        assert_eq!(builder.archived, Some(false));
+        assert_eq!(builder.test_results, Some(vec![]));
+        assert_eq!(builder.attestations, Some(vec![]));
+        assert_eq!(builder.allocations, Some(vec![]));
+        assert_eq!(builder.recall, None);
+        assert_eq!(builder.attachments, Some(vec![]));
+    }
+
+    #[test]
+    /// Validate that a `TestResult` requires its name, result, outcome and timestamp
+    fn test_test_result_builder() {
+        let test_result = make_test_result();
+        assert_eq!(test_result.test_name(), "moisture");
+        assert_eq!(test_result.specification(), "<= 12.5 %");
+        assert_eq!(test_result.result(), "11.8 %");
+        assert!(test_result.passed());
+        assert_eq!(test_result.lab(), "Acme Labs");
+        assert_eq!(test_result.timestamp(), 1_600_100_000);
+
+        assert!(test_result.into_builder().build().is_ok());
+        assert!(TestResultBuilder::new()
+            .with_test_name("moisture".into())
+            .with_result("11.8 %".into())
+            .with_timestamp(1_600_100_000)
+            .build()
+            .is_err());
     }
 
     #[test]
@@ -445,12 +1716,124 @@ mod tests {
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
             .with_owner("Target".into())
             .with_properties(make_properties())
+            .with_parent_batches(vec!["688955434600".into()])
+            .with_quantity(950)
+            .with_uom("KGM".into())
+            .with_expected_quantity(1000)
+            .with_production_date(1_600_000_000)
+            .with_expiration_date(1_631_536_000)
+            .with_manufacture_location("0614141000005".into())
+            .with_archived(true)
+            .with_test_results(vec![make_test_result()])
+            .with_attestations(vec![make_attestation()])
+            .with_allocations(vec![make_allocation("SO-1001", 200)])
+            .with_recall(make_recall())
+            .with_attachments(vec![make_attachment()])
             .build()
             .unwrap();
 
         test_from_bytes(original, MfgBatch::from_bytes);
     }
 
+    #[test]
+    /// Validate that a `MfgBatch` sums the quantities its sales orders have reserved, and that
+    /// the rest of the quantity produced is available
+    fn test_mfg_batch_allocated_quantity() {
+        let mfg_batch = build_mfg_batch();
+        assert_eq!(mfg_batch.allocated_quantity(), 0);
+        assert_eq!(mfg_batch.available_quantity(), 950);
+
+        let allocated = mfg_batch
+            .into_builder()
+            .with_allocations(vec![
+                make_allocation("SO-1001", 200),
+                make_allocation("SO-1002", 300),
+            ])
+            .build()
+            .expect("Failed to build test mfg_batch");
+        assert_eq!(allocated.allocated_quantity(), 500);
+        assert_eq!(allocated.available_quantity(), 450);
+    }
+
+    #[test]
+    /// Validate that the canonical bytes of a `MfgBatch` leave out its attestations, allocations
+    /// and recall, and only them
+    fn test_mfg_batch_canonical_bytes() {
+        let mfg_batch = build_mfg_batch();
+        let canonical_bytes = mfg_batch
+            .canonical_bytes()
+            .expect("Failed to get canonical bytes");
+        assert_eq!(canonical_bytes, mfg_batch.clone().into_bytes().unwrap());
+
+        let attested = mfg_batch
+            .clone()
+            .into_builder()
+            .with_attestations(vec![make_attestation()])
+            .with_allocations(vec![make_allocation("SO-1001", 200)])
+            .with_recall(make_recall())
+            .build()
+            .expect("Failed to build test mfg_batch");
+        assert_eq!(attested.canonical_bytes().unwrap(), canonical_bytes);
+
+        let archived = mfg_batch
+            .into_builder()
+            .with_archived(true)
+            .build()
+            .expect("Failed to build test mfg_batch");
+        assert_ne!(archived.canonical_bytes().unwrap(), canonical_bytes);
+    }
+
+    #[test]
+    /// Validate that the version of a `MfgBatch` is the same for equal batches and changes with
+    /// any field, including those left out of its canonical bytes
+    fn test_mfg_batch_version() {
+        let mfg_batch = build_mfg_batch();
+        let version = mfg_batch.version().expect("Failed to get version");
+        assert_eq!(version.len(), 64);
+        assert_eq!(build_mfg_batch().version().unwrap(), version);
+
+        let allocated = mfg_batch
+            .into_builder()
+            .with_allocations(vec![make_allocation("SO-1001", 200)])
+            .build()
+            .expect("Failed to build test mfg_batch");
+        assert_ne!(allocated.version().unwrap(), version);
+    }
+
+    #[cfg(feature = "mfg-batch-serde")]
+    #[test]
+    /// Validate that a `MfgBatch` may be correctly converted into JSON and back, and that
+    /// properties in JSON only need the value matching their data type
+    fn test_mfg_batch_json() {
+        let original = build_mfg_batch();
+        let json = original.to_json().expect("Failed to write JSON");
+        assert_eq!(MfgBatch::from_json(&json).unwrap(), original);
+
+        let mfg_batch = MfgBatch::from_json(
+            r#"{
+                "mfg_batch_id": "688955434684",
+                "mfg_batch_namespace": "GS1",
+                "owner": "Target",
+                "properties": [
+                    {"name": "price", "data_type": "Number", "number_value": 3}
+                ],
+                "parent_batches": [],
+                "quantity": 950,
+                "uom": "KGM",
+                "expected_quantity": 1000,
+                "production_date": 1600000000,
+                "expiration_date": 1631536000,
+                "archived": false
+            }"#,
+        )
+        .expect("Failed to parse JSON");
+        assert_eq!(*mfg_batch.mfg_batch_namespace(), MfgBatchNamespace::Gs1);
+        assert_eq!(*mfg_batch.properties()[0].number_value(), 3);
+        assert_eq!(mfg_batch.properties()[0].string_value(), "");
+
+        assert!(MfgBatch::from_json("{\"mfg_batch_id\": \"688955434684\"}").is_err());
+    }
+
     #[test]
     /// Validate that a list of mfg_batches, `MfgBatchList`, can be built correctly
     fn test_mfg_batch_list_builder() {
@@ -477,12 +1860,18 @@ mod tests {
             mfg_batch_list.mfg_batches[0].properties()[0].string_value(),
             "This is a mfg_batch description"
         );
-        assert_eq!(mfg_batch_list.mfg_batches[0].properties()[1].name(), "price");
+        assert_eq!(
+            mfg_batch_list.mfg_batches[0].properties()[1].name(),
+            "price"
+        );
         assert_eq!(
             *mfg_batch_list.mfg_batches[0].properties()[1].data_type(),
             DataType::Number
         );
-        assert_eq!(*mfg_batch_list.mfg_batches[0].properties()[1].number_value(), 3);
+        assert_eq!(
+            *mfg_batch_list.mfg_batches[0].properties()[1].number_value(),
+            3
+        );
 
         // Test mfg_batch 2
         assert_eq!(mfg_batch_list.mfg_batches[1].mfg_batch_id(), "688955434685");
@@ -503,12 +1892,18 @@ mod tests {
             mfg_batch_list.mfg_batches[1].properties()[0].string_value(),
             "This is a mfg_batch description"
         );
-        assert_eq!(mfg_batch_list.mfg_batches[1].properties()[1].name(), "price");
+        assert_eq!(
+            mfg_batch_list.mfg_batches[1].properties()[1].name(),
+            "price"
+        );
         assert_eq!(
             *mfg_batch_list.mfg_batches[1].properties()[1].data_type(),
             DataType::Number
         );
-        assert_eq!(*mfg_batch_list.mfg_batches[1].properties()[1].number_value(), 3);
+        assert_eq!(
+            *mfg_batch_list.mfg_batches[1].properties()[1].number_value(),
+            3
+        );
     }
 
     #[test]
@@ -526,7 +1921,10 @@ mod tests {
     /// representation successfully
     fn test_mfg_batch_list_into_bytes() {
         let builder = MfgBatchListBuilder::new();
-        let original = builder.with_mfg_batches(make_mfg_batches()).build().unwrap();
+        let original = builder
+            .with_mfg_batches(make_mfg_batches())
+            .build()
+            .unwrap();
 
         test_from_bytes(original, MfgBatchList::from_bytes);
     }
@@ -537,10 +1935,65 @@ mod tests {
             .with_mfg_batch_namespace(MfgBatchNamespace::Gs1)
             .with_owner("Target".into())
             .with_properties(make_properties())
+            .with_quantity(950)
+            .with_uom("KGM".into())
+            .with_expected_quantity(1000)
+            .with_production_date(1_600_000_000)
+            .with_expiration_date(1_631_536_000)
+            .with_manufacture_location("0614141000005".into())
             .build()
             .expect("Failed to build test mfg_batch")
     }
 
+    fn make_test_result() -> TestResult {
+        TestResultBuilder::new()
+            .with_test_name("moisture".into())
+            .with_specification("<= 12.5 %".into())
+            .with_result("11.8 %".into())
+            .with_passed(true)
+            .with_lab("Acme Labs".into())
+            .with_timestamp(1_600_100_000)
+            .build()
+            .expect("Failed to build test result")
+    }
+
+    fn make_allocation(order_id: &str, quantity: i64) -> Allocation {
+        AllocationBuilder::new()
+            .with_order_id(order_id.into())
+            .with_quantity(quantity)
+            .build()
+            .expect("Failed to build allocation")
+    }
+
+    fn make_recall() -> Recall {
+        RecallBuilder::new()
+            .with_recall_id("RC-2022-014".into())
+            .with_reason_code("CONTAMINATION".into())
+            .with_timestamp(1_600_200_000)
+            .build()
+            .expect("Failed to build recall")
+    }
+
+    fn make_attestation() -> Attestation {
+        AttestationBuilder::new()
+            .with_signer_public_key("02a1b2c3".into())
+            .with_algorithm("secp256k1".into())
+            .with_signature("d4e5f6".into())
+            .with_purpose("certificate_of_analysis".into())
+            .build()
+            .expect("Failed to build attestation")
+    }
+
+    fn make_attachment() -> Attachment {
+        AttachmentBuilder::new()
+            .with_digest("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".into())
+            .with_mime_type("application/pdf".into())
+            .with_size(48_213)
+            .with_uri("https://docs.example.com/coa/688955434684.pdf".into())
+            .build()
+            .expect("Failed to build attachment")
+    }
+
     fn make_properties() -> Vec<PropertyValue> {
         let property_value_description = PropertyValueBuilder::new()
             .with_name("description".into())
@@ -561,6 +2014,38 @@ mod tests {
         ]
     }
 
+    #[test]
+    /// Validate that a `MfgBatchAnchorList` may be correctly converted into bytes and then back
+    /// to its native representation
+    fn test_mfg_batch_anchor_list_into_bytes() {
+        let anchor = MfgBatchAnchorBuilder::new()
+            .with_owner("Target".into())
+            .with_commit_num(42)
+            .with_merkle_root("ab".repeat(32))
+            .with_row_count(3)
+            .with_timestamp(1_600_100_000)
+            .build()
+            .expect("Failed to build anchor");
+
+        assert!(anchor
+            .clone()
+            .into_builder()
+            .with_owner("Cargill".into())
+            .build()
+            .is_ok());
+        assert!(MfgBatchAnchorBuilder::new()
+            .with_owner("Target".into())
+            .with_commit_num(42)
+            .build()
+            .is_err());
+
+        let anchor_list = MfgBatchAnchorListBuilder::new()
+            .with_anchors(vec![anchor])
+            .build()
+            .expect("Failed to build anchor list");
+        test_from_bytes(anchor_list, MfgBatchAnchorList::from_bytes);
+    }
+
     fn build_mfg_batch_list() -> MfgBatchList {
         MfgBatchListBuilder::new()
             .with_mfg_batches(make_mfg_batches())
@@ -598,5 +2083,3 @@ mod tests {
         assert_eq!(under_test, created_from_bytes);
     }
 }
-
-
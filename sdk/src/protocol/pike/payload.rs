@@ -1812,6 +1812,7 @@ mod tests {
     use super::*;
 
     use crate::protocol::pike::state::KeyValueEntryBuilder;
+    use crate::protos::FromBytesStrict;
 
     #[test]
     /// Validate that a `CreateAgentAction` is built correctly
@@ -2144,4 +2145,48 @@ mod tests {
         let payload = PikePayload::from_bytes(&bytes).unwrap();
         assert_eq!(payload, original);
     }
+
+    #[test]
+    /// Validate that strict decoding accepts a `PikePayload` with only known fields, and rejects
+    /// one with an unknown field in the payload or in a nested message
+    fn check_pike_payload_bytes_strict() {
+        let key_value = KeyValueEntryBuilder::new()
+            .with_key("Key".to_string())
+            .with_value("Value".to_string())
+            .build()
+            .unwrap();
+
+        let action = CreateAgentActionBuilder::new()
+            .with_org_id("organization".to_string())
+            .with_public_key("public_key".to_string())
+            .with_active(true)
+            .with_roles(vec!["Role".to_string()])
+            .with_metadata(vec![key_value])
+            .build()
+            .unwrap();
+
+        let original = PikePayloadBuilder::new()
+            .with_action(Action::CreateAgent(action))
+            .with_timestamp(0)
+            .build()
+            .unwrap();
+
+        let bytes = original.clone().into_bytes().unwrap();
+        let payload = PikePayload::from_bytes_strict(&bytes).unwrap();
+        assert_eq!(payload, original);
+
+        let mut proto: protos::pike_payload::PikePayload = original.clone().into_proto().unwrap();
+        proto.mut_unknown_fields().add_varint(99, 1);
+        let bytes = proto.write_to_bytes().unwrap();
+        assert!(PikePayload::from_bytes(&bytes).is_ok());
+        assert!(PikePayload::from_bytes_strict(&bytes).is_err());
+
+        let mut proto: protos::pike_payload::PikePayload = original.into_proto().unwrap();
+        proto.mut_create_agent().mut_metadata()[0]
+            .mut_unknown_fields()
+            .add_varint(99, 1);
+        let bytes = proto.write_to_bytes().unwrap();
+        assert!(PikePayload::from_bytes(&bytes).is_ok());
+        assert!(PikePayload::from_bytes_strict(&bytes).is_err());
+    }
 }
@@ -16,5 +16,6 @@
 //!
 //! These structs are used to represent Schema transaction payloads and state.
 
+pub mod number;
 pub mod payload;
 pub mod state;
@@ -0,0 +1,207 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Number property values are stored as integers, scaled by the exponent of the property's
+//! definition: a value of 12345 defined with an exponent of -2 is worth 123.45. `NumberValue`
+//! pairs the stored integer with its exponent, so the real number can be read, compared and
+//! written without repeating the exponent math.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A fixed-point number, worth `value * 10^exponent`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NumberValue {
+    value: i64,
+    exponent: i32,
+}
+
+impl NumberValue {
+    /// Creates the number a value stored with the given exponent is worth
+    pub fn new(value: i64, exponent: i32) -> Self {
+        Self { value, exponent }
+    }
+
+    /// Converts a real number into the value stored for it with the given exponent, rounding to
+    /// the nearest value the exponent can represent. Returns `None` if the number is not finite
+    /// or its stored value does not fit in an `i64`.
+    pub fn from_f64(number: f64, exponent: i32) -> Option<Self> {
+        let value = (number * 10f64.powi(-exponent)).round();
+        if !value.is_finite() || value < i64::MIN as f64 || value >= i64::MAX as f64 {
+            return None;
+        }
+
+        Some(Self::new(value as i64, exponent))
+    }
+
+    /// Returns the value as it is stored
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// Returns the exponent the value is scaled by
+    pub fn exponent(&self) -> i32 {
+        self.exponent
+    }
+
+    /// Returns the real number, `value * 10^exponent`. Numbers with more than 15 significant
+    /// digits lose precision; use `to_string` for an exact representation.
+    pub fn scaled(&self) -> f64 {
+        self.value as f64 * 10f64.powi(self.exponent)
+    }
+
+    /// Returns the same number stored with another exponent, or `None` if the exponent cannot
+    /// represent it exactly or its stored value does not fit in an `i64`
+    pub fn rescale(&self, exponent: i32) -> Option<Self> {
+        let value = match exponent.cmp(&self.exponent) {
+            Ordering::Equal => self.value,
+            Ordering::Less => self
+                .value
+                .checked_mul(pow10(self.exponent.checked_sub(exponent)?)?)?,
+            Ordering::Greater => {
+                let scale = pow10(exponent.checked_sub(self.exponent)?)?;
+                if self.value % scale != 0 {
+                    return None;
+                }
+                self.value / scale
+            }
+        };
+
+        Some(Self::new(value, exponent))
+    }
+}
+
+impl PartialOrd for NumberValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NumberValue {
+    /// Compares the real numbers, whatever their exponents
+    fn cmp(&self, other: &Self) -> Ordering {
+        let exponent = self.exponent.min(other.exponent);
+        match (self.rescale(exponent), other.rescale(exponent)) {
+            (Some(a), Some(b)) => a
+                .value
+                .cmp(&b.value)
+                .then(self.exponent.cmp(&other.exponent)),
+            // Rescaling only fails here if a value overflows, in which case it is the larger in
+            // magnitude
+            _ => self
+                .scaled()
+                .partial_cmp(&other.scaled())
+                .unwrap_or(Ordering::Equal)
+                .then(self.exponent.cmp(&other.exponent)),
+        }
+    }
+}
+
+impl fmt::Display for NumberValue {
+    /// Writes the real number in decimal, exactly, such as `123.45` or `-0.07`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.value < 0 { "-" } else { "" };
+        let digits = self.value.unsigned_abs().to_string();
+
+        if self.exponent >= 0 {
+            if self.value == 0 {
+                return write!(f, "0");
+            }
+            return write!(
+                f,
+                "{}{}{}",
+                sign,
+                digits,
+                "0".repeat(self.exponent as usize)
+            );
+        }
+
+        let places = self.exponent.unsigned_abs() as usize;
+        let digits = format!("{:0>width$}", digits, width = places + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - places);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Returns 10 to the power of a non-negative exponent, if it fits in an `i64`
+fn pow10(exponent: i32) -> Option<i64> {
+    u32::try_from(exponent)
+        .ok()
+        .and_then(|exponent| 10i64.checked_pow(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the real number is read from the stored value and exponent
+    #[test]
+    fn test_scaled() {
+        assert_eq!(NumberValue::new(12345, -2).scaled(), 123.45);
+        assert_eq!(NumberValue::new(12, 3).scaled(), 12000.0);
+        assert_eq!(NumberValue::new(-7, 0).scaled(), -7.0);
+    }
+
+    /// Verify that a real number is rounded to the nearest value the exponent can represent
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(
+            NumberValue::from_f64(123.456, -2),
+            Some(NumberValue::new(12346, -2))
+        );
+        assert_eq!(
+            NumberValue::from_f64(12500.0, 3),
+            Some(NumberValue::new(13, 3))
+        );
+        assert_eq!(NumberValue::from_f64(f64::NAN, 0), None);
+        assert_eq!(NumberValue::from_f64(1e30, 0), None);
+    }
+
+    /// Verify that rescaling keeps the number, and fails when it cannot be represented exactly
+    /// or overflows
+    #[test]
+    fn test_rescale() {
+        let number = NumberValue::new(12300, -2);
+        assert_eq!(number.rescale(-4), Some(NumberValue::new(1230000, -4)));
+        assert_eq!(number.rescale(0), Some(NumberValue::new(123, 0)));
+        assert_eq!(number.rescale(1), None);
+        assert_eq!(NumberValue::new(i64::MAX, 0).rescale(-1), None);
+    }
+
+    /// Verify that numbers are compared by their real values across exponents
+    #[test]
+    fn test_ordering() {
+        assert!(NumberValue::new(150, -2) < NumberValue::new(2, 0));
+        assert!(NumberValue::new(-1, 3) < NumberValue::new(-999, 0));
+        assert_eq!(
+            NumberValue::new(100, -2).cmp(&NumberValue::new(1, 0)),
+            Ordering::Less
+        );
+    }
+
+    /// Verify that numbers are written exactly in decimal
+    #[test]
+    fn test_display() {
+        assert_eq!(NumberValue::new(12345, -2).to_string(), "123.45");
+        assert_eq!(NumberValue::new(-7, -2).to_string(), "-0.07");
+        assert_eq!(NumberValue::new(12, 3).to_string(), "12000");
+        assert_eq!(NumberValue::new(0, 3).to_string(), "0");
+        assert_eq!(NumberValue::new(0, -2).to_string(), "0.00");
+        assert_eq!(
+            NumberValue::new(i64::MIN, -1).to_string(),
+            "-922337203685477580.8"
+        );
+    }
+}
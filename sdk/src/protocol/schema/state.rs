@@ -19,6 +19,7 @@ use protobuf::RepeatedField;
 
 use std::error::Error as StdError;
 
+use super::number::NumberValue;
 use crate::protos;
 use crate::protos::{
     FromBytes, FromNative, FromProto, IntoBytes, IntoNative, IntoProto, ProtoConversionError,
@@ -28,6 +29,7 @@ use crate::protos::{
 ///
 /// `DataType`s are used in a schema to define a data field's type
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub enum DataType {
     Bytes,
     Boolean,
@@ -80,6 +82,7 @@ impl IntoNative<DataType> for protos::schema_state::PropertyDefinition_DataType
 ///
 /// A `LatLong` object is comprised of a latitude, longitude pair represented as signed integers
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct LatLong {
     latitude: i64,
     longitude: i64,
@@ -93,6 +96,15 @@ impl LatLong {
     pub fn longitude(&self) -> &i64 {
         &self.longitude
     }
+
+    /// The value used for properties whose JSON has no `lat_long_value`, as in protobuf
+    #[cfg(feature = "mfg-batch-serde")]
+    fn default_for_serde() -> Self {
+        LatLong {
+            latitude: 0,
+            longitude: 0,
+        }
+    }
 }
 
 impl FromProto<protos::schema_state::LatLong> for LatLong {
@@ -192,6 +204,7 @@ pub struct PropertyDefinition {
     number_exponent: i32,
     enum_options: Vec<String>,
     struct_properties: Vec<PropertyDefinition>,
+    default_value: Option<PropertyValue>,
 }
 
 impl PropertyDefinition {
@@ -222,6 +235,11 @@ impl PropertyDefinition {
     pub fn struct_properties(&self) -> &[PropertyDefinition] {
         &self.struct_properties
     }
+
+    /// The value used when the property is not supplied, if it has one
+    pub fn default_value(&self) -> Option<&PropertyValue> {
+        self.default_value.as_ref()
+    }
 }
 
 impl FromProto<protos::schema_state::PropertyDefinition> for PropertyDefinition {
@@ -241,6 +259,13 @@ impl FromProto<protos::schema_state::PropertyDefinition> for PropertyDefinition
                 .into_iter()
                 .map(PropertyDefinition::from_proto)
                 .collect::<Result<Vec<PropertyDefinition>, ProtoConversionError>>()?,
+            default_value: if property_definition.has_default_value() {
+                Some(PropertyValue::from_proto(
+                    property_definition.get_default_value().clone(),
+                )?)
+            } else {
+                None
+            },
         })
     }
 }
@@ -262,6 +287,9 @@ impl FromNative<PropertyDefinition> for protos::schema_state::PropertyDefinition
             property_definition.struct_properties().to_vec().into_iter()
             .map(PropertyDefinition::into_proto)
             .collect::<Result<Vec<protos::schema_state::PropertyDefinition>, ProtoConversionError>>()?,));
+        if let Some(default_value) = property_definition.default_value {
+            proto_property_definition.set_default_value(default_value.into_proto()?);
+        }
         Ok(proto_property_definition)
     }
 }
@@ -299,6 +327,7 @@ impl IntoNative<PropertyDefinition> for protos::schema_state::PropertyDefinition
 pub enum PropertyDefinitionBuildError {
     MissingField(String),
     EmptyVec(String),
+    InvalidDefault(String),
 }
 
 impl StdError for PropertyDefinitionBuildError {
@@ -306,6 +335,7 @@ impl StdError for PropertyDefinitionBuildError {
         match *self {
             PropertyDefinitionBuildError::MissingField(ref msg) => msg,
             PropertyDefinitionBuildError::EmptyVec(ref msg) => msg,
+            PropertyDefinitionBuildError::InvalidDefault(ref msg) => msg,
         }
     }
 
@@ -313,6 +343,7 @@ impl StdError for PropertyDefinitionBuildError {
         match *self {
             PropertyDefinitionBuildError::MissingField(_) => None,
             PropertyDefinitionBuildError::EmptyVec(_) => None,
+            PropertyDefinitionBuildError::InvalidDefault(_) => None,
         }
     }
 }
@@ -322,6 +353,9 @@ impl std::fmt::Display for PropertyDefinitionBuildError {
         match *self {
             PropertyDefinitionBuildError::MissingField(ref s) => write!(f, "MissingField: {}", s),
             PropertyDefinitionBuildError::EmptyVec(ref s) => write!(f, "EmptyVec: {}", s),
+            PropertyDefinitionBuildError::InvalidDefault(ref s) => {
+                write!(f, "InvalidDefault: {}", s)
+            }
         }
     }
 }
@@ -336,6 +370,7 @@ pub struct PropertyDefinitionBuilder {
     pub number_exponent: Option<i32>,
     pub enum_options: Vec<String>,
     pub struct_properties: Vec<PropertyDefinition>,
+    pub default_value: Option<PropertyValue>,
 }
 
 impl PropertyDefinitionBuilder {
@@ -381,6 +416,12 @@ impl PropertyDefinitionBuilder {
         self
     }
 
+    /// Sets the value used when the property is not supplied
+    pub fn with_default_value(mut self, default_value: PropertyValue) -> PropertyDefinitionBuilder {
+        self.default_value = Some(default_value);
+        self
+    }
+
     pub fn build(self) -> Result<PropertyDefinition, PropertyDefinitionBuildError> {
         let name = self.name.ok_or_else(|| {
             PropertyDefinitionBuildError::MissingField("'name' field is required".to_string())
@@ -433,6 +474,15 @@ impl PropertyDefinitionBuilder {
             }
         };
 
+        if let Some(default_value) = &self.default_value {
+            if default_value.name() != name || default_value.data_type() != &data_type {
+                return Err(PropertyDefinitionBuildError::InvalidDefault(format!(
+                    "'default_value' must be a {:?} value named '{}'",
+                    data_type, name
+                )));
+            }
+        }
+
         Ok(PropertyDefinition {
             name,
             data_type,
@@ -441,6 +491,7 @@ impl PropertyDefinitionBuilder {
             number_exponent,
             enum_options,
             struct_properties,
+            default_value: self.default_value,
         })
     }
 }
@@ -760,16 +811,29 @@ impl SchemaListBuilder {
 ///
 /// This provides the value as defined by the corresponding `PropertyDefinition`
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "mfg-batch-serde", derive(Serialize, Deserialize))]
 pub struct PropertyValue {
     name: String,
     data_type: DataType,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     bytes_value: Vec<u8>,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     boolean_value: bool,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     number_value: i64,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     string_value: String,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     enum_value: u32,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
     struct_values: Vec<PropertyValue>,
+    #[cfg_attr(
+        feature = "mfg-batch-serde",
+        serde(default = "LatLong::default_for_serde")
+    )]
     lat_long_value: LatLong,
+    #[cfg_attr(feature = "mfg-batch-serde", serde(default))]
+    language: String,
 }
 
 impl PropertyValue {
@@ -793,6 +857,18 @@ impl PropertyValue {
         &self.number_value
     }
 
+    /// Returns the number the value is worth, scaled by the exponent of its definition, or
+    /// `None` if the value is not a number
+    pub fn number(&self, definition: &PropertyDefinition) -> Option<NumberValue> {
+        match self.data_type {
+            DataType::Number => Some(NumberValue::new(
+                self.number_value,
+                *definition.number_exponent(),
+            )),
+            _ => None,
+        }
+    }
+
     pub fn string_value(&self) -> &str {
         &self.string_value
     }
@@ -808,6 +884,46 @@ impl PropertyValue {
     pub fn lat_long_value(&self) -> &LatLong {
         &self.lat_long_value
     }
+
+    /// The BCP 47 language tag of a localized value; empty if the value is not localized
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+}
+
+#[cfg(feature = "log-masking")]
+impl PropertyValue {
+    /// Returns a copy of the value to log: a denied property keeps its name, type and language,
+    /// with its value replaced by the mask as a string, and the values nested in an allowed
+    /// struct are masked in turn
+    pub fn masked(&self, mask: &crate::log_masking::LogMask) -> PropertyValue {
+        if mask.is_denied(&self.name) {
+            PropertyValue {
+                name: self.name.clone(),
+                data_type: self.data_type.clone(),
+                bytes_value: vec![],
+                boolean_value: false,
+                number_value: 0,
+                string_value: crate::log_masking::MASK.to_string(),
+                enum_value: 0,
+                struct_values: vec![],
+                lat_long_value: LatLong {
+                    latitude: 0,
+                    longitude: 0,
+                },
+                language: self.language.clone(),
+            }
+        } else {
+            PropertyValue {
+                struct_values: self
+                    .struct_values
+                    .iter()
+                    .map(|value| value.masked(mask))
+                    .collect(),
+                ..self.clone()
+            }
+        }
+    }
 }
 
 impl FromProto<protos::schema_state::PropertyValue> for PropertyValue {
@@ -829,6 +945,7 @@ impl FromProto<protos::schema_state::PropertyValue> for PropertyValue {
                 .map(PropertyValue::from_proto)
                 .collect::<Result<Vec<PropertyValue>, ProtoConversionError>>()?,
             lat_long_value: property_value.get_lat_long_value().clone().into_native()?,
+            language: property_value.get_language().to_string(),
         })
     }
 }
@@ -854,6 +971,7 @@ impl FromNative<PropertyValue> for protos::schema_state::PropertyValue {
         ));
         proto_property_value
             .set_lat_long_value(property_value.lat_long_value().clone().into_proto()?);
+        proto_property_value.set_language(property_value.language().to_string());
         Ok(proto_property_value)
     }
 }
@@ -926,6 +1044,7 @@ pub struct PropertyValueBuilder {
     pub enum_value: Option<u32>,
     pub struct_values: Vec<PropertyValue>,
     pub lat_long_value: Option<LatLong>,
+    pub language: Option<String>,
 }
 
 impl PropertyValueBuilder {
@@ -978,6 +1097,12 @@ impl PropertyValueBuilder {
         self
     }
 
+    /// Marks the value as the variant of a localized property in the given BCP 47 language
+    pub fn with_language(mut self, language: String) -> PropertyValueBuilder {
+        self.language = Some(language);
+        self
+    }
+
     pub fn build(self) -> Result<PropertyValue, PropertyValueBuildError> {
         let name = self.name.ok_or_else(|| {
             PropertyValueBuildError::MissingField("'name' field is required".to_string())
@@ -1086,6 +1211,7 @@ impl PropertyValueBuilder {
             enum_value,
             struct_values,
             lat_long_value,
+            language: self.language.unwrap_or_default(),
         })
     }
 }
@@ -1184,6 +1310,52 @@ mod tests {
         assert_eq!(property_definition, original);
     }
 
+    #[test]
+    /// Validate that a `PropertyDefinition` with a default value may be converted to bytes and
+    /// back to its native representation successfully
+    fn check_property_definition_default_value_bytes() {
+        let default_value = PropertyValueBuilder::new()
+            .with_name("TEST".to_string())
+            .with_data_type(DataType::String)
+            .with_string_value("Default".to_string())
+            .build()
+            .unwrap();
+        let original = PropertyDefinitionBuilder::new()
+            .with_name("TEST".to_string())
+            .with_data_type(DataType::String)
+            .with_default_value(default_value.clone())
+            .build()
+            .unwrap();
+
+        let bytes = original.clone().into_bytes().unwrap();
+
+        let property_definition = PropertyDefinition::from_bytes(&bytes).unwrap();
+        assert_eq!(property_definition, original);
+        assert_eq!(property_definition.default_value(), Some(&default_value));
+    }
+
+    #[test]
+    /// Validate that a `PropertyDefinition` cannot be built with a default value of another
+    /// data type
+    fn check_property_definition_builder_invalid_default_value() {
+        let default_value = PropertyValueBuilder::new()
+            .with_name("TEST".to_string())
+            .with_data_type(DataType::Boolean)
+            .with_boolean_value(true)
+            .build()
+            .unwrap();
+        let result = PropertyDefinitionBuilder::new()
+            .with_name("TEST".to_string())
+            .with_data_type(DataType::String)
+            .with_default_value(default_value)
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(PropertyDefinitionBuildError::InvalidDefault(_))
+        ));
+    }
+
     #[test]
     /// Validate that a `Schema`, containing a `PropertyDefinition` with an `Enum` data type is
     /// built correctly
@@ -14,6 +14,9 @@
 
 use std::error::Error as StdError;
 
+use protobuf::reflect::{ProtobufValue, ReflectFieldRef, ReflectValueRef};
+use protobuf::Message;
+
 #[derive(Debug)]
 pub enum ProtoConversionError {
     SerializationError(String),
@@ -59,6 +62,64 @@ pub trait FromBytes<N>: Sized {
     fn from_bytes(bytes: &[u8]) -> Result<N, ProtoConversionError>;
 }
 
+/// Converts bytes to a native type like `FromBytes`, but rejects bytes containing fields the
+/// proto definition does not declare, in the message or any message nested in it.
+///
+/// Contracts ignore fields they do not know about. Networks that want to be sure clients are not
+/// relying on such fields decode payloads with `from_bytes_strict` instead of `from_bytes`.
+pub trait FromBytesStrict<P: Message>: FromProto<P> {
+    fn from_bytes_strict(bytes: &[u8]) -> Result<Self, ProtoConversionError> {
+        let proto = P::parse_from_bytes(bytes).map_err(|_| {
+            ProtoConversionError::SerializationError(format!(
+                "Unable to get {} from bytes",
+                P::descriptor_static().name()
+            ))
+        })?;
+        reject_unknown_fields(&proto)?;
+        Self::from_proto(proto)
+    }
+}
+
+impl<N: FromProto<P>, P: Message> FromBytesStrict<P> for N {}
+
+/// Returns an error naming the first unknown field found in a message or its nested messages
+fn reject_unknown_fields(message: &dyn Message) -> Result<(), ProtoConversionError> {
+    let descriptor = message.descriptor();
+    if let Some((number, _)) = message.get_unknown_fields().iter().next() {
+        return Err(ProtoConversionError::SerializationError(format!(
+            "{} contains unknown field {}",
+            descriptor.name(),
+            number
+        )));
+    }
+
+    let reject_nested = |value: &dyn ProtobufValue| match value.as_ref() {
+        ReflectValueRef::Message(nested) => reject_unknown_fields(nested),
+        _ => Ok(()),
+    };
+
+    for field in descriptor.fields() {
+        match field.get_reflect(message) {
+            ReflectFieldRef::Optional(Some(ReflectValueRef::Message(nested))) => {
+                reject_unknown_fields(nested)?
+            }
+            ReflectFieldRef::Optional(_) => (),
+            ReflectFieldRef::Repeated(values) => {
+                for value in values.reflect_iter() {
+                    reject_nested(value)?;
+                }
+            }
+            ReflectFieldRef::Map(entries) => {
+                for (_, value) in entries.reflect_iter() {
+                    reject_nested(value)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub trait IntoBytes: Sized {
     fn into_bytes(self) -> Result<Vec<u8>, ProtoConversionError>;
 }
@@ -0,0 +1,407 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Middleware that requires API keys on the routes that need a scope.
+//!
+//! Each request that matches a rule must carry an `Authorization: Bearer <key_id>.<secret>`
+//! header naming an unrevoked key with the rule's scope. Every request that names a stored key
+//! is recorded in the key-usage log, whether it is let through or not. Requests that do not
+//! match a rule are passed through untouched.
+//!
+//! With API usage analytics, each request let through with a key is also added to the daily
+//! usage rollups, along with the rows its response lists when the handler notes them with
+//! [`RowsReturned`].
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+#[cfg(feature = "api-usage-analytics")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header::AUTHORIZATION, Method, StatusCode},
+    Error, HttpMessage, HttpResponse,
+};
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+#[cfg(feature = "api-usage-analytics")]
+use crate::api_keys::analytics::ApiUsageEvent;
+use crate::api_keys::{authorize_request, store::ApiKey, AuthorizeError, Scope};
+use crate::rest_api::resources::error::ErrorResponse;
+use crate::store::TransactionalStoreFactory;
+
+/// Requests with this method and a path starting with this prefix require the scope
+#[derive(Clone, Debug)]
+pub struct ApiKeyRule {
+    pub method: Method,
+    pub path_prefix: String,
+    pub scope: Scope,
+}
+
+/// The number of rows a response lists. Handlers add it to the response's extensions so that
+/// the rows are counted in the API usage rollups.
+#[cfg(feature = "api-usage-analytics")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RowsReturned(pub usize);
+
+/// Enforces API key scopes on the requests matching its rules. The key a request was let
+/// through with is added to the request's extensions.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    store_factory: Arc<dyn TransactionalStoreFactory>,
+    rules: Arc<Vec<ApiKeyRule>>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(store_factory: Arc<dyn TransactionalStoreFactory>) -> Self {
+        Self {
+            store_factory,
+            rules: Arc::new(vec![]),
+        }
+    }
+
+    /// Requires `scope` for requests with `method` whose path starts with `path_prefix`. The
+    /// first matching rule applies.
+    pub fn with_rule(mut self, method: Method, path_prefix: &str, scope: Scope) -> Self {
+        Arc::make_mut(&mut self.rules).push(ApiKeyRule {
+            method,
+            path_prefix: path_prefix.to_string(),
+            scope,
+        });
+        self
+    }
+
+    /// Returns the first rule the request matches
+    fn matching_rule(&self, req: &ServiceRequest) -> Option<&ApiKeyRule> {
+        self.rules.iter().find(|rule| {
+            rule.method == req.method() && req.path().starts_with(rule.path_prefix.as_str())
+        })
+    }
+
+    /// Returns the key the request may be let through with, `None` if it needs no key, or the
+    /// response it is refused with
+    fn authorize(&self, req: &ServiceRequest) -> Result<Option<ApiKey>, HttpResponse> {
+        let rule = match self.matching_rule(req) {
+            Some(rule) => rule,
+            None => return Ok(None),
+        };
+
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        authorize_request(
+            &*self.store_factory.get_api_key_store(),
+            token,
+            rule.scope,
+            req.method().as_str(),
+            req.path(),
+        )
+        .map(Some)
+        .map_err(|err| match err {
+            AuthorizeError::Unauthenticated => error_response(401, &err.to_string()),
+            AuthorizeError::MissingScope(_) => error_response(403, &err.to_string()),
+            AuthorizeError::StoreError(err) => {
+                HttpResponse::build(StatusCode::INTERNAL_SERVER_ERROR)
+                    .json(ErrorResponse::internal_error(Box::new(err)))
+            }
+        })
+    }
+
+    /// Returns the usage event of a request let through with a key, before its response is
+    /// known. The request is rolled up under the path prefix of the rule it matched.
+    #[cfg(feature = "api-usage-analytics")]
+    fn usage_event(&self, req: &ServiceRequest, api_key: &ApiKey) -> Option<ApiUsageEvent> {
+        self.matching_rule(req).map(|rule| ApiUsageEvent {
+            key_id: api_key.key_id.clone(),
+            org_id: api_key.org_id.clone(),
+            method: req.method().to_string(),
+            endpoint: rule.path_prefix.clone(),
+            rows_returned: 0,
+            submission: rule.scope == Scope::MfgBatchSubmit && req.method() == Method::POST,
+            used_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Adds a request to the usage rollups once its response is known. Only successful
+    /// submissions are counted as such. A failure to record the usage is logged rather than
+    /// failing the request.
+    #[cfg(feature = "api-usage-analytics")]
+    fn record_usage<B>(&self, mut event: ApiUsageEvent, res: &ServiceResponse<B>) {
+        event.rows_returned = res
+            .response()
+            .extensions()
+            .get::<RowsReturned>()
+            .map(|RowsReturned(rows)| *rows as i64)
+            .unwrap_or(0);
+        event.submission = event.submission && res.status().is_success();
+
+        if let Err(err) = self.store_factory.get_api_key_store().add_api_usage(event) {
+            error!("Unable to record API usage: {}", err);
+        }
+    }
+}
+
+impl<S, B> Transform<S> for ApiKeyAuth
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ApiKeyAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ApiKeyAuthMiddleware {
+            auth: self.clone(),
+            service,
+        })
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    auth: ApiKeyAuth,
+    service: S,
+}
+
+impl<S, B> Service for ApiKeyAuthMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        match self.auth.authorize(&req) {
+            Ok(Some(api_key)) => {
+                #[cfg(feature = "api-usage-analytics")]
+                let event = self.auth.usage_event(&req, &api_key);
+                req.extensions_mut().insert(api_key);
+                let fut = self.service.call(req);
+
+                #[cfg(feature = "api-usage-analytics")]
+                let fut = {
+                    let auth = self.auth.clone();
+                    fut.map(move |res| {
+                        if let (Some(event), Ok(res)) = (event, &res) {
+                            auth.record_usage(event, res);
+                        }
+                        res
+                    })
+                };
+
+                fut.boxed_local()
+            }
+            Ok(None) => self.service.call(req).boxed_local(),
+            Err(res) => ok(req.into_response(res.into_body())).boxed_local(),
+        }
+    }
+}
+
+fn error_response(status_code: u16, message: &str) -> HttpResponse {
+    HttpResponse::build(
+        StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    )
+    .json(ErrorResponse::new(status_code, message))
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use actix_web::{test, web, App};
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+
+    use crate::api_keys::{hash_secret, store::ApiKeyStore};
+    use crate::migrations::run_sqlite_migrations;
+    use crate::store::{sqlite::SqliteStoreFactory, StoreFactory};
+
+    fn store_factory() -> Arc<dyn TransactionalStoreFactory> {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to build connection pool");
+        run_sqlite_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+
+        let store_factory = SqliteStoreFactory::new(pool);
+        for (key_id, scopes, revoked_at) in &[
+            ("submitter", vec![Scope::MfgBatchSubmit], None),
+            ("reader", vec![Scope::MfgBatchRead], None),
+            ("revoked", vec![Scope::MfgBatchSubmit], Some(1)),
+        ] {
+            store_factory
+                .get_api_key_store()
+                .add_api_key(ApiKey {
+                    key_id: key_id.to_string(),
+                    org_id: "org-1".to_string(),
+                    secret_hash: hash_secret("secret"),
+                    scopes: scopes.clone(),
+                    created_at: 0,
+                    revoked_at: *revoked_at,
+                })
+                .expect("Unable to add API key");
+        }
+
+        Arc::new(store_factory)
+    }
+
+    /// Verify that requests matching a rule need a valid key with the rule's scope, that other
+    /// requests are let through, and that each use of a stored key is recorded
+    #[test]
+    fn test_api_key_auth() {
+        actix_web::rt::System::new("test").block_on(async {
+            let store_factory = store_factory();
+            let mut app = test::init_service(
+                App::new()
+                    .wrap(ApiKeyAuth::new(store_factory.clone()).with_rule(
+                        Method::POST,
+                        "/batches",
+                        Scope::MfgBatchSubmit,
+                    ))
+                    .route("/batches", web::post().to(HttpResponse::Ok))
+                    .route("/agent", web::get().to(HttpResponse::Ok)),
+            )
+            .await;
+
+            for (token, status) in &[
+                (None, StatusCode::UNAUTHORIZED),
+                (Some("submitter"), StatusCode::UNAUTHORIZED),
+                (Some("unknown.secret"), StatusCode::UNAUTHORIZED),
+                (Some("submitter.wrong"), StatusCode::UNAUTHORIZED),
+                (Some("revoked.secret"), StatusCode::UNAUTHORIZED),
+                (Some("reader.secret"), StatusCode::FORBIDDEN),
+                (Some("submitter.secret"), StatusCode::OK),
+            ] {
+                let mut req = test::TestRequest::post().uri("/batches");
+                if let Some(token) = token {
+                    req = req.header(AUTHORIZATION, format!("Bearer {}", token));
+                }
+                let res = test::call_service(&mut app, req.to_request()).await;
+                assert_eq!(res.status(), *status, "token {:?}", token);
+            }
+
+            let res = test::call_service(
+                &mut app,
+                test::TestRequest::get().uri("/agent").to_request(),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+
+            let usage = store_factory
+                .get_api_key_store()
+                .list_api_key_usage(None, 0, 10)
+                .expect("Unable to list usage");
+            assert_eq!(
+                usage
+                    .iter()
+                    .map(|usage| (usage.key_id.as_str(), usage.allowed))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("submitter", true),
+                    ("reader", false),
+                    ("revoked", false),
+                    ("submitter", false),
+                ]
+            );
+        });
+    }
+
+    /// Verify that requests let through with a key are rolled up under the rule they matched,
+    /// with the rows their responses noted and their successful submissions, and that refused
+    /// requests are not
+    #[cfg(feature = "api-usage-analytics")]
+    #[test]
+    fn test_api_usage_rollups() {
+        use crate::api_keys::analytics::ApiUsageRollupFilters;
+
+        async fn list_rows() -> HttpResponse {
+            let mut res = HttpResponse::Ok().finish();
+            res.extensions_mut().insert(RowsReturned(3));
+            res
+        }
+
+        actix_web::rt::System::new("test").block_on(async {
+            let store_factory = store_factory();
+            let mut app = test::init_service(
+                App::new()
+                    .wrap(
+                        ApiKeyAuth::new(store_factory.clone())
+                            .with_rule(Method::POST, "/batches", Scope::MfgBatchSubmit)
+                            .with_rule(Method::GET, "/mfg_batch", Scope::MfgBatchRead),
+                    )
+                    .route("/batches", web::post().to(HttpResponse::Ok))
+                    .route("/mfg_batch/{id}", web::get().to(list_rows)),
+            )
+            .await;
+
+            for (method, uri, token) in &[
+                (Method::POST, "/batches", "submitter.secret"),
+                (Method::POST, "/batches", "submitter.secret"),
+                (Method::POST, "/batches", "reader.secret"),
+                (Method::GET, "/mfg_batch/a", "reader.secret"),
+                (Method::GET, "/mfg_batch/b", "reader.secret"),
+            ] {
+                test::call_service(
+                    &mut app,
+                    test::TestRequest::with_uri(uri)
+                        .method(method.clone())
+                        .header(AUTHORIZATION, format!("Bearer {}", token))
+                        .to_request(),
+                )
+                .await;
+            }
+
+            let rollups = store_factory
+                .get_api_key_store()
+                .list_api_usage_rollups(&ApiUsageRollupFilters::default(), 0, 10)
+                .expect("Unable to list rollups");
+            assert_eq!(
+                rollups
+                    .iter()
+                    .map(|rollup| (
+                        rollup.key_id.as_str(),
+                        rollup.method.as_str(),
+                        rollup.endpoint.as_str(),
+                        rollup.requests,
+                        rollup.rows_returned,
+                        rollup.submissions,
+                    ))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("reader", "GET", "/mfg_batch", 2, 6, 0),
+                    ("submitter", "POST", "/batches", 2, 0, 2),
+                ]
+            );
+        });
+    }
+}
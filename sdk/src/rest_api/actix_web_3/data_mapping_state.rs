@@ -0,0 +1,58 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::data_mapping::{DataMapping, PropertyCodecs};
+#[cfg(feature = "data-mapping-edi")]
+use crate::mfg_batch::store::MfgBatchStore;
+
+/// The data mappings that payloads can be submitted through, by name
+#[derive(Clone, Default)]
+pub struct DataMappingState {
+    pub mappings: Arc<HashMap<String, DataMapping>>,
+    /// Tells whether the lots in EDI ship notices are mfg_batches already
+    #[cfg(feature = "data-mapping-edi")]
+    pub mfg_batch_store: Option<Arc<dyn MfgBatchStore + Send + Sync>>,
+}
+
+impl DataMappingState {
+    pub fn new(mappings: HashMap<String, DataMapping>) -> Self {
+        Self {
+            mappings: Arc::new(mappings),
+            #[cfg(feature = "data-mapping-edi")]
+            mfg_batch_store: None,
+        }
+    }
+
+    /// Sets the codecs every mapping decodes property values with
+    pub fn with_property_codecs(mut self, codecs: PropertyCodecs) -> Self {
+        self.mappings = Arc::new(
+            self.mappings
+                .iter()
+                .map(|(name, mapping)| (name.clone(), mapping.clone().with_codecs(codecs.clone())))
+                .collect(),
+        );
+        self
+    }
+
+    /// Sets the store used to tell whether lots are mfg_batches already; without one, the lots
+    /// in EDI ship notices are always created
+    #[cfg(feature = "data-mapping-edi")]
+    pub fn with_mfg_batch_store(mut self, store: Arc<dyn MfgBatchStore + Send + Sync>) -> Self {
+        self.mfg_batch_store = Some(store);
+        self
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+use crate::mfg_batch::certificate::CertificateTemplateDirectory;
+use crate::mfg_batch::store::MfgBatchStore;
+
+/// The estimated size, in bytes, a mfg_batch history or property response is limited to unless
+/// another limit is set
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// The store the mfg_batch endpoints read from, which is kept apart from the store factory
+#[derive(Clone)]
+pub struct MfgBatchState {
+    pub store: Arc<dyn MfgBatchStore + Send + Sync>,
+    /// Where the templates certificates are printed from are kept
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+    pub certificate_templates: Option<CertificateTemplateDirectory>,
+    /// The estimated size, in bytes, a history or property response is limited to before the
+    /// rest is left to a continuation
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+    pub max_response_size: usize,
+}
+
+impl MfgBatchState {
+    pub fn new(store: Arc<dyn MfgBatchStore + Send + Sync>) -> Self {
+        Self {
+            store,
+            #[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+            certificate_templates: None,
+            #[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        }
+    }
+
+    /// Sets the directory certificate templates are kept in; without one, the certificate and
+    /// certificate template endpoints are unavailable
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+    pub fn with_certificate_templates(mut self, templates: CertificateTemplateDirectory) -> Self {
+        self.certificate_templates = Some(templates);
+        self
+    }
+
+    /// Sets the estimated size, in bytes, history and property responses are limited to
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+}
@@ -12,9 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "api-keys")]
+mod api_key;
 mod backend_state;
+#[cfg(feature = "rest-api-endpoint-data-mapping")]
+mod data_mapping_state;
 mod endpoint;
 mod key_state;
+#[cfg(feature = "rest-api-endpoint-mfg-batch")]
+mod mfg_batch_state;
 mod paging;
 pub mod routes;
 #[cfg(feature = "rest-api-actix-web-3-run")]
@@ -22,9 +28,19 @@ mod run;
 mod service;
 mod store_state;
 
+#[cfg(feature = "api-keys")]
+pub use api_key::{ApiKeyAuth, ApiKeyAuthMiddleware, ApiKeyRule};
+#[cfg(feature = "api-usage-analytics")]
+pub use api_key::RowsReturned;
 pub use backend_state::BackendState;
+#[cfg(feature = "rest-api-endpoint-data-mapping")]
+pub use data_mapping_state::DataMappingState;
 pub use endpoint::{Backend, Endpoint};
 pub use key_state::KeyState;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+pub use mfg_batch_state::DEFAULT_MAX_RESPONSE_SIZE;
+#[cfg(feature = "rest-api-endpoint-mfg-batch")]
+pub use mfg_batch_state::MfgBatchState;
 pub use paging::QueryPaging;
 #[cfg(feature = "rest-api-actix-web-3-run")]
 pub use run::run;
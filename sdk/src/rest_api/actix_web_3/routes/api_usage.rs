@@ -0,0 +1,64 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use actix_web::{get, http::StatusCode, web, HttpResponse};
+
+use crate::api_keys::analytics::ApiUsageRollupFilters;
+use crate::rest_api::{
+    actix_web_3::{QueryPaging, StoreState},
+    resources::api_usage::v1,
+};
+
+#[derive(Deserialize)]
+pub struct ApiUsageQuery {
+    org_id: Option<String>,
+    key_id: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+}
+
+/// Lists the daily rollups of API usage per key, most recent day first, for chargeback and for
+/// spotting keys used out of the ordinary
+#[get("/admin/usage")]
+pub async fn list_api_usage(
+    store_state: web::Data<StoreState>,
+    query: web::Query<ApiUsageQuery>,
+    query_paging: web::Query<QueryPaging>,
+) -> HttpResponse {
+    let ApiUsageQuery {
+        org_id,
+        key_id,
+        since,
+        until,
+    } = query.into_inner();
+    let filters = ApiUsageRollupFilters {
+        org_id,
+        key_id,
+        since,
+        until,
+    };
+
+    match v1::list_api_usage(
+        &*store_state.store_factory.get_api_key_store(),
+        &filters,
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => HttpResponse::build(
+            StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        )
+        .json(err),
+    }
+}
@@ -21,7 +21,7 @@ use crate::rest_api::{
     resources::{batches::v1, error::ErrorResponse},
 };
 
-use super::DEFAULT_GRID_PROTOCOL_VERSION;
+use super::{correlation_id, DEFAULT_GRID_PROTOCOL_VERSION};
 
 #[post("/batches")]
 pub async fn submit_batches(
@@ -56,7 +56,14 @@ pub async fn submit_batches(
                 bytes.extend_from_slice(&item);
             }
 
-            match v1::submit_batches(response_url, state.client.clone(), &*bytes, service_id).await
+            match v1::submit_batches(
+                response_url,
+                state.client.clone(),
+                &*bytes,
+                service_id,
+                correlation_id(&req),
+            )
+            .await
             {
                 Ok(res) => HttpResponse::Ok().json(res),
                 Err(err) => HttpResponse::build(
@@ -100,8 +107,15 @@ pub async fn get_batch_statuses(
                 }
             };
 
-            match v1::get_batch_statuses(response_url, state.client.clone(), id, wait, service_id)
-                .await
+            match v1::get_batch_statuses(
+                response_url,
+                state.client.clone(),
+                id,
+                wait,
+                service_id,
+                correlation_id(&req),
+            )
+            .await
             {
                 Ok(res) => HttpResponse::Ok().json(res),
                 Err(err) => HttpResponse::build(
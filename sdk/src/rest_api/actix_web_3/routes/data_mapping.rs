@@ -0,0 +1,96 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use actix_web::{http::StatusCode, post, web, HttpRequest, HttpResponse};
+use uuid::Uuid;
+
+#[cfg(feature = "data-mapping-edi")]
+use crate::mfg_batch::store::MfgBatchStore;
+use crate::rest_api::actix_web_3::{DataMappingState, KeyState, QueryServiceId, StoreState};
+use crate::rest_api::resources::{
+    data_mapping::v1::build_submit_request,
+    error::ErrorResponse,
+    submit::v1::{submit_batches, SubmitBatchRequest},
+};
+
+use super::{correlation_id, CORRELATION_ID_HEADER};
+
+/// Applies a data mapping to an ERP payload or EDI ship notice and stores the resulting
+/// transactions to be sent to the DLT.
+///
+/// The payload is checked against the mapping's schema before it is submitted, and the
+/// response carries the `X-Correlation-Id` the batch was stored with, as for `/submit`.
+#[post("/integrations/{mapping}/submit")]
+async fn submit_mapped(
+    req: HttpRequest,
+    mapping: web::Path<String>,
+    body: web::Bytes,
+    store_state: web::Data<StoreState>,
+    key_state: web::Data<KeyState>,
+    data_mapping_state: web::Data<DataMappingState>,
+    query: web::Query<QueryServiceId>,
+) -> HttpResponse {
+    let correlation_id = correlation_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let service_id = query.into_inner().service_id;
+
+    match build_request(
+        &mapping,
+        &body,
+        &store_state,
+        &data_mapping_state,
+        service_id,
+    )
+    .and_then(|request| {
+        submit_batches(
+            &key_state.key_file_name,
+            store_state.store_factory.get_batch_store(),
+            request,
+            &correlation_id,
+        )
+    }) {
+        Ok(res) => HttpResponse::Accepted()
+            .header(CORRELATION_ID_HEADER, correlation_id)
+            .json(res),
+        Err(err) => HttpResponse::build(
+            StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        )
+        .header(CORRELATION_ID_HEADER, correlation_id)
+        .json(err),
+    }
+}
+
+fn build_request(
+    name: &str,
+    body: &[u8],
+    store_state: &StoreState,
+    data_mapping_state: &DataMappingState,
+    service_id: Option<String>,
+) -> Result<SubmitBatchRequest, ErrorResponse> {
+    let mapping = data_mapping_state
+        .mappings
+        .get(name)
+        .ok_or_else(|| ErrorResponse::new(404, &format!("Data mapping {} is not defined", name)))?;
+
+    build_submit_request(
+        mapping,
+        body,
+        &*store_state.store_factory,
+        #[cfg(feature = "data-mapping-edi")]
+        data_mapping_state
+            .mfg_batch_store
+            .as_ref()
+            .map(|store| &**store as &dyn MfgBatchStore),
+        service_id,
+    )
+}
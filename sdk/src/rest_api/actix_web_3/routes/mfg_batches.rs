@@ -0,0 +1,1065 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+use actix_web::http::{header::VARY, HeaderValue};
+#[cfg(feature = "rest-api-endpoint-mfg-batch-annotations")]
+use actix_web::post;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+use actix_web::{delete, put};
+use actix_web::{get, http::StatusCode, web, HttpRequest, HttpResponse};
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+use crate::api_keys::store::ApiKey;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+use crate::mfg_batch::certificate::CertificateTemplateDirectory;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+use crate::mfg_batch::localization::{
+    localize_mfg_batch, localize_properties, LanguagePreferences,
+};
+#[cfg(any(
+    feature = "rest-api-endpoint-mfg-batch-explain",
+    feature = "rest-api-endpoint-mfg-batch-list"
+))]
+use crate::mfg_batch::store::ListMfgBatchFilters;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+use crate::mfg_batch::store::Visibility;
+#[cfg(any(
+    feature = "rest-api-endpoint-mfg-batch-duplicates",
+    feature = "rest-api-endpoint-mfg-batch-explain",
+    feature = "rest-api-endpoint-mfg-batch-list",
+    feature = "rest-api-endpoint-mfg-batch-quality-scores",
+    feature = "rest-api-endpoint-mfg-batch-recalls",
+    feature = "rest-api-endpoint-mfg-batch-search"
+))]
+use crate::rest_api::actix_web_3::QueryPaging;
+#[cfg(feature = "api-usage-analytics")]
+use crate::rest_api::actix_web_3::RowsReturned;
+#[cfg(feature = "rest-api-endpoint-mfg-batch-annotations")]
+use crate::rest_api::resources::mfg_batches::v1::NewAnnotationPayload;
+use crate::rest_api::{
+    actix_web_3::{AcceptServiceIdParam, MfgBatchState, QueryServiceId},
+    resources::{error::ErrorResponse, mfg_batches::v1},
+};
+
+/// The template certificates are printed from when the request does not name one
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+const DEFAULT_CERTIFICATE_TEMPLATE: &str = "coa";
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-list")]
+#[derive(Deserialize)]
+pub struct ListMfgBatchesQuery {
+    cursor: Option<String>,
+    limit: Option<u16>,
+    owner: Option<String>,
+    mfg_batch_namespace: Option<String>,
+    manufacture_location: Option<String>,
+    service_id: Option<String>,
+}
+
+/// Lists current mfg_batches in ID order. A full page has a `next` cursor to fetch the
+/// following page with, passed back as `?cursor=`.
+///
+/// When the request's reads are restricted to an organization, only the mfg_batches it may see
+/// are listed.
+#[cfg(feature = "rest-api-endpoint-mfg-batch-list")]
+#[get("/mfg_batch")]
+pub async fn list_mfg_batches(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<ListMfgBatchesQuery>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let ListMfgBatchesQuery {
+        cursor,
+        limit,
+        owner,
+        mfg_batch_namespace,
+        manufacture_location,
+        service_id,
+    } = query.into_inner();
+
+    let filters = ListMfgBatchFilters {
+        owner,
+        mfg_batch_namespace,
+        manufacture_location,
+        #[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+        visibility: request_visibility(&req),
+        ..Default::default()
+    };
+    #[cfg(not(feature = "rest-api-endpoint-mfg-batch-visibility"))]
+    let _ = req;
+
+    let limit = QueryPaging {
+        offset: None,
+        limit,
+    }
+    .limit();
+
+    match v1::list_mfg_batches(
+        &*mfg_batch_state.store,
+        service_id.as_deref(),
+        &filters,
+        cursor.as_deref(),
+        limit,
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+/// Lists the quality test results, such as those on a certificate of analysis, recorded
+/// against a mfg_batch
+#[get("/mfg_batch/{id}/test_result")]
+pub async fn list_mfg_batch_test_results(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    mfg_batch_id: web::Path<String>,
+    query: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let service_id = query.into_inner().service_id;
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match v1::list_mfg_batch_test_results(
+        &*mfg_batch_state.store,
+        mfg_batch_id.into_inner(),
+        service_id.as_deref(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+#[derive(Deserialize)]
+pub struct CursorQuery {
+    cursor: Option<String>,
+    service_id: Option<String>,
+}
+
+/// Lists the versions of a mfg_batch, oldest first. When the history is larger than a response
+/// may be, responds with 206 Partial Content and a `next` cursor to fetch the rest with.
+///
+/// When the request has an `Accept-Language` header, each version holds only the variant of
+/// each localized property that best matches it.
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+#[get("/mfg_batch/{id}/history")]
+pub async fn list_mfg_batch_history(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    mfg_batch_id: web::Path<String>,
+    query: web::Query<CursorQuery>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let CursorQuery { cursor, service_id } = query.into_inner();
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    let result = v1::list_mfg_batch_history(
+        &*mfg_batch_state.store,
+        mfg_batch_id.into_inner(),
+        service_id.as_deref(),
+        cursor.as_deref(),
+        mfg_batch_state.max_response_size,
+    );
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+    let result = result.map(|mut res| {
+        if let Some(languages) = request_languages(&req) {
+            res.data = res
+                .data
+                .into_iter()
+                .map(|mfg_batch| localize_mfg_batch(mfg_batch, &languages))
+                .collect();
+        }
+        res
+    });
+
+    vary_by_language(match result {
+        Ok(res) if res.next.is_some() => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::PartialContent().json(res), rows)
+        }
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    })
+}
+
+/// Lists the current properties of a mfg_batch in name order. When the properties are larger
+/// than a response may be, responds with 206 Partial Content and a `next` cursor to fetch the
+/// rest with.
+///
+/// When the request has an `Accept-Language` header, only the variant of each localized
+/// property that best matches it is listed; the variants of a property share a page.
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+#[get("/mfg_batch/{id}/property")]
+pub async fn list_mfg_batch_properties(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    mfg_batch_id: web::Path<String>,
+    query: web::Query<CursorQuery>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let CursorQuery { cursor, service_id } = query.into_inner();
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    let result = v1::list_mfg_batch_properties(
+        &*mfg_batch_state.store,
+        mfg_batch_id.into_inner(),
+        service_id.as_deref(),
+        cursor.as_deref(),
+        mfg_batch_state.max_response_size,
+    );
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+    let result = result.map(|mut res| {
+        if let Some(languages) = request_languages(&req) {
+            res.data = localize_properties(res.data, &languages);
+        }
+        res
+    });
+
+    vary_by_language(match result {
+        Ok(res) if res.next.is_some() => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::PartialContent().json(res), rows)
+        }
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    })
+}
+
+/// Lists every stored value of a top-level property of a mfg_batch, oldest first, so that
+/// changes to a property such as a QC status can be followed over time
+#[cfg(feature = "rest-api-endpoint-mfg-batch-history")]
+#[get("/mfg_batch/{id}/properties/{name}/history")]
+pub async fn get_property_value_history(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    path: web::Path<(String, String)>,
+    query: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let (mfg_batch_id, property_name) = path.into_inner();
+    let service_id = query.into_inner().service_id;
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match v1::get_property_value_history(
+        &*mfg_batch_state.store,
+        mfg_batch_id,
+        property_name,
+        service_id.as_deref(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+/// Attaches a comment to a property of a mfg_batch as it stood at a commit, so that a deviation
+/// investigation can discuss the exact value it is about. The property must have had a value as
+/// of the commit.
+#[cfg(feature = "rest-api-endpoint-mfg-batch-annotations")]
+#[post("/mfg_batch/{id}/property/{name}/annotations")]
+pub async fn add_mfg_batch_annotation(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<NewAnnotationPayload>,
+    query: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let (mfg_batch_id, property_name) = path.into_inner();
+    let service_id = query.into_inner().service_id;
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match v1::add_mfg_batch_annotation(
+        &*mfg_batch_state.store,
+        mfg_batch_id,
+        property_name,
+        payload.into_inner(),
+        service_id.as_deref(),
+    ) {
+        Ok(res) => HttpResponse::Created().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+/// Lists the annotations on a property of a mfg_batch, in the order of the commits they were
+/// made against
+#[cfg(feature = "rest-api-endpoint-mfg-batch-annotations")]
+#[get("/mfg_batch/{id}/property/{name}/annotations")]
+pub async fn list_mfg_batch_property_annotations(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    path: web::Path<(String, String)>,
+    query: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let (mfg_batch_id, property_name) = path.into_inner();
+    let service_id = query.into_inner().service_id;
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match v1::list_mfg_batch_property_annotations(
+        &*mfg_batch_state.store,
+        mfg_batch_id,
+        property_name,
+        service_id.as_deref(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[derive(Deserialize)]
+pub struct CertificateQuery {
+    template: Option<String>,
+    service_id: Option<String>,
+}
+
+/// Prints a certificate, such as a certificate of analysis, for a mfg_batch from the template
+/// named by the `template` query parameter
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[get("/mfg_batch/{id}/certificate")]
+pub async fn get_mfg_batch_certificate(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    mfg_batch_id: web::Path<String>,
+    query: web::Query<CertificateQuery>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let CertificateQuery {
+        template,
+        service_id,
+    } = query.into_inner();
+    let template = template.unwrap_or_else(|| DEFAULT_CERTIFICATE_TEMPLATE.to_string());
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match certificate_templates(&mfg_batch_state).and_then(|templates| {
+        v1::render_mfg_batch_certificate(
+            &*mfg_batch_state.store,
+            templates,
+            &mfg_batch_id,
+            &template,
+            service_id.as_deref(),
+        )
+    }) {
+        Ok(pdf) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .header(
+                "Content-Disposition",
+                format!(
+                    "inline; filename=\"{}-{}.pdf\"",
+                    file_name_safe(&mfg_batch_id),
+                    template
+                ),
+            )
+            .body(pdf),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-quality-scores")]
+#[derive(Deserialize)]
+pub struct QualityScoreQuery {
+    max_score: Option<i32>,
+}
+
+/// Lists the quality scores of current mfg_batches, lowest first, so quality teams can clean
+/// up the mfg_batches with the least complete data first
+#[cfg(feature = "rest-api-endpoint-mfg-batch-quality-scores")]
+#[get("/mfg_batch_quality_score")]
+pub async fn list_mfg_batch_quality_scores(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<QualityScoreQuery>,
+    query_service_id: web::Query<QueryServiceId>,
+    query_paging: web::Query<QueryPaging>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    if let Err(err) = require_unrestricted(&req) {
+        return error_response(err);
+    }
+
+    match v1::list_mfg_batch_quality_scores(
+        &*mfg_batch_state.store,
+        query.max_score,
+        query_service_id.into_inner().service_id.as_deref(),
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+/// Lists the mfg_batches flagged as likely duplicates by the last detection run, most alike
+/// first, for review
+#[cfg(feature = "rest-api-endpoint-mfg-batch-duplicates")]
+#[get("/mfg_batch_duplicate")]
+pub async fn list_mfg_batch_duplicates(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query_service_id: web::Query<QueryServiceId>,
+    query_paging: web::Query<QueryPaging>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    if let Err(err) = require_unrestricted(&req) {
+        return error_response(err);
+    }
+
+    match v1::list_mfg_batch_duplicates(
+        &*mfg_batch_state.store,
+        query_service_id.into_inner().service_id.as_deref(),
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-recalls")]
+#[derive(Deserialize)]
+pub struct RecallQuery {
+    mfg_batch_id: Option<String>,
+    recall_id: Option<String>,
+}
+
+/// Lists the active recalls, most recent first, optionally only those of one mfg_batch or the
+/// batches covered by one recall
+#[cfg(feature = "rest-api-endpoint-mfg-batch-recalls")]
+#[get("/mfg_batch_recall")]
+pub async fn list_mfg_batch_recalls(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<RecallQuery>,
+    query_service_id: web::Query<QueryServiceId>,
+    query_paging: web::Query<QueryPaging>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    if let Err(err) = require_unrestricted(&req) {
+        return error_response(err);
+    }
+
+    let query = query.into_inner();
+    match v1::list_mfg_batch_recalls(
+        &*mfg_batch_state.store,
+        query.mfg_batch_id,
+        query.recall_id,
+        query_service_id.into_inner().service_id.as_deref(),
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-search")]
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    q: String,
+}
+
+/// Searches the string property values of current mfg_batches, such as batch descriptions and
+/// lot codes, for the words of the `q` query parameter
+#[cfg(feature = "rest-api-endpoint-mfg-batch-search")]
+#[get("/mfg_batch_search")]
+pub async fn search_mfg_batches(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<SearchQuery>,
+    query_service_id: web::Query<QueryServiceId>,
+    query_paging: web::Query<QueryPaging>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    if let Err(err) = require_unrestricted(&req) {
+        return error_response(err);
+    }
+
+    match v1::search_mfg_batches(
+        &*mfg_batch_state.store,
+        &query.q,
+        query_service_id.into_inner().service_id.as_deref(),
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => {
+            let rows = res.data.len();
+            with_rows_returned(HttpResponse::Ok().json(res), rows)
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-explain")]
+#[derive(Deserialize)]
+pub struct ExplainListMfgBatchesQuery {
+    owner: Option<String>,
+    mfg_batch_namespace: Option<String>,
+    manufacture_location: Option<String>,
+    service_id: Option<String>,
+}
+
+/// Shows the SQL an offset-paged list of mfg_batches runs for the filters and page, and the
+/// database's plan for it, for tuning indexes to the filters a deployment uses
+#[cfg(feature = "rest-api-endpoint-mfg-batch-explain")]
+#[get("/admin/explain/mfg_batch")]
+pub async fn explain_list_mfg_batches(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<ExplainListMfgBatchesQuery>,
+    query_paging: web::Query<QueryPaging>,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let ExplainListMfgBatchesQuery {
+        owner,
+        mfg_batch_namespace,
+        manufacture_location,
+        service_id,
+    } = query.into_inner();
+
+    let filters = ListMfgBatchFilters {
+        owner,
+        mfg_batch_namespace,
+        manufacture_location,
+        ..Default::default()
+    };
+
+    match v1::explain_list_mfg_batches(
+        &*mfg_batch_state.store,
+        service_id.as_deref(),
+        &filters,
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-explain")]
+#[derive(Deserialize)]
+pub struct ExplainSearchQuery {
+    q: String,
+}
+
+/// Shows the SQL a search of mfg_batches for the words of the `q` query parameter runs, and the
+/// database's plan for it
+#[cfg(feature = "rest-api-endpoint-mfg-batch-explain")]
+#[get("/admin/explain/mfg_batch_search")]
+pub async fn explain_search_mfg_batches(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    query: web::Query<ExplainSearchQuery>,
+    query_service_id: web::Query<QueryServiceId>,
+    query_paging: web::Query<QueryPaging>,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    match v1::explain_search_mfg_batches(
+        &*mfg_batch_state.store,
+        &query.q,
+        query_service_id.into_inner().service_id.as_deref(),
+        query_paging.offset(),
+        query_paging.limit(),
+    ) {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+/// Exports the history of a mfg_batch as EPCIS 2.0 events, for traceability systems
+#[cfg(feature = "rest-api-endpoint-mfg-batch-epcis")]
+#[get("/mfg_batch/{id}/epcis")]
+pub async fn get_mfg_batch_epcis(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    mfg_batch_id: web::Path<String>,
+    query: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let service_id = query.into_inner().service_id;
+
+    if let Err(err) = require_visible(&mfg_batch_state, &req, &mfg_batch_id, service_id.as_deref())
+    {
+        return error_response(err);
+    }
+
+    match v1::export_mfg_batch_epcis(
+        &*mfg_batch_state.store,
+        &mfg_batch_id,
+        service_id.as_deref(),
+    ) {
+        Ok(document) => HttpResponse::Ok()
+            .content_type("application/ld+json")
+            .json(document),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[get("/certificate_template")]
+pub async fn list_certificate_templates(mfg_batch_state: web::Data<MfgBatchState>) -> HttpResponse {
+    match certificate_templates(&mfg_batch_state).and_then(v1::list_certificate_templates) {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[get("/certificate_template/{name}")]
+pub async fn get_certificate_template(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    name: web::Path<String>,
+) -> HttpResponse {
+    match certificate_templates(&mfg_batch_state)
+        .and_then(|templates| v1::get_certificate_template(templates, &name))
+    {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+/// Stores a certificate template, written in YAML or JSON, replacing any of the same name
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[put("/certificate_template/{name}")]
+pub async fn put_certificate_template(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    name: web::Path<String>,
+    body: String,
+) -> HttpResponse {
+    match certificate_templates(&mfg_batch_state)
+        .and_then(|templates| v1::put_certificate_template(templates, &name, &body))
+    {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+#[delete("/certificate_template/{name}")]
+pub async fn delete_certificate_template(
+    mfg_batch_state: web::Data<MfgBatchState>,
+    name: web::Path<String>,
+) -> HttpResponse {
+    match certificate_templates(&mfg_batch_state)
+        .and_then(|templates| v1::delete_certificate_template(templates, &name))
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+fn certificate_templates(
+    mfg_batch_state: &MfgBatchState,
+) -> Result<&CertificateTemplateDirectory, ErrorResponse> {
+    mfg_batch_state
+        .certificate_templates
+        .as_ref()
+        .ok_or_else(|| ErrorResponse::new(503, "Certificate templates are not configured"))
+}
+
+/// Keeps the characters of a mfg_batch ID that are safe in a quoted file name
+#[cfg(feature = "rest-api-endpoint-mfg-batch-certificates")]
+fn file_name_safe(mfg_batch_id: &str) -> String {
+    mfg_batch_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+        .collect()
+}
+
+/// The organization reads are restricted to: that of the API key the request was authorized
+/// with, if any
+#[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+fn request_visibility(req: &HttpRequest) -> Option<Visibility> {
+    req.extensions()
+        .get::<ApiKey>()
+        .map(|api_key| Visibility::org(&api_key.org_id))
+}
+
+/// Fails as though the mfg_batch did not exist when the request's reads are restricted to an
+/// organization the mfg_batch is neither owned by nor shared with
+#[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+fn require_visible(
+    mfg_batch_state: &MfgBatchState,
+    req: &HttpRequest,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+) -> Result<(), ErrorResponse> {
+    let visibility = match request_visibility(req) {
+        Some(visibility) => visibility,
+        None => return Ok(()),
+    };
+
+    if mfg_batch_state
+        .store
+        .mfg_batch_visible(mfg_batch_id, &visibility, service_id)
+        .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+    {
+        Ok(())
+    } else {
+        Err(ErrorResponse::new(
+            404,
+            &format!("Mfg_batch {} not found", mfg_batch_id),
+        ))
+    }
+}
+
+#[cfg(not(feature = "rest-api-endpoint-mfg-batch-visibility"))]
+fn require_visible(
+    _: &MfgBatchState,
+    _: &HttpRequest,
+    _: &str,
+    _: Option<&str>,
+) -> Result<(), ErrorResponse> {
+    Ok(())
+}
+
+/// The languages the request accepts, from its `Accept-Language` header, if it has one
+#[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+fn request_languages(req: &HttpRequest) -> Option<LanguagePreferences> {
+    req.headers()
+        .get("Accept-Language")
+        .and_then(|value| value.to_str().ok())
+        .map(LanguagePreferences::from_accept_language)
+}
+
+/// Marks a response as depending on the request's `Accept-Language` header, so caches keep a
+/// copy for each language
+#[cfg(feature = "rest-api-endpoint-mfg-batch-localization")]
+fn vary_by_language(mut response: HttpResponse) -> HttpResponse {
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("Accept-Language"));
+    response
+}
+
+#[cfg(all(
+    feature = "rest-api-endpoint-mfg-batch-history",
+    not(feature = "rest-api-endpoint-mfg-batch-localization")
+))]
+fn vary_by_language(response: HttpResponse) -> HttpResponse {
+    response
+}
+
+/// Refuses requests whose reads are restricted to an organization, for lists that span the
+/// mfg_batches of every organization
+#[cfg(any(
+    feature = "rest-api-endpoint-mfg-batch-duplicates",
+    feature = "rest-api-endpoint-mfg-batch-quality-scores",
+    feature = "rest-api-endpoint-mfg-batch-recalls",
+    feature = "rest-api-endpoint-mfg-batch-search",
+    feature = "rest-api-endpoint-sla"
+))]
+pub(super) fn require_unrestricted(req: &HttpRequest) -> Result<(), ErrorResponse> {
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-visibility")]
+    if request_visibility(req).is_some() {
+        return Err(ErrorResponse::new(
+            403,
+            "Not available to requests restricted to an organization",
+        ));
+    }
+    #[cfg(not(feature = "rest-api-endpoint-mfg-batch-visibility"))]
+    let _ = req;
+
+    Ok(())
+}
+
+/// Notes the number of rows a response lists, for the API usage rollups
+#[cfg(feature = "api-usage-analytics")]
+fn with_rows_returned(mut response: HttpResponse, rows: usize) -> HttpResponse {
+    response.extensions_mut().insert(RowsReturned(rows));
+    response
+}
+
+#[cfg(not(feature = "api-usage-analytics"))]
+fn with_rows_returned(response: HttpResponse, _rows: usize) -> HttpResponse {
+    response
+}
+
+fn error_response(err: ErrorResponse) -> HttpResponse {
+    HttpResponse::build(
+        StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    )
+    .json(err)
+}
+
+#[cfg(all(
+    test,
+    any(
+        all(
+            feature = "rest-api-endpoint-mfg-batch-annotations",
+            feature = "rest-api-endpoint-mfg-batch-history"
+        ),
+        feature = "rest-api-endpoint-mfg-batch-explain"
+    ),
+    feature = "sqlite"
+))]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use actix_web::{test, App};
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-annotations")]
+    use serde_json::json;
+    use serde_json::Value;
+
+    use crate::mfg_batch::{
+        store::{
+            DieselMfgBatchStore, MfgBatchBuilder, MfgBatchStore, PropertyValue,
+            PropertyValueBuilder,
+        },
+        MAX_COMMIT_NUM,
+    };
+    use crate::rest_api::actix_web_3::Endpoint;
+
+    const MFG_BATCH_ID: &str = "688955434684";
+
+    fn property(name: &str, value: &str, start: i64, end: i64) -> PropertyValue {
+        PropertyValueBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_property_name(name.into())
+            .with_data_type("String".into())
+            .with_string_value(Some(value.into()))
+            .with_start_commit_number(start)
+            .with_end_commit_number(end)
+            .build()
+            .expect("Failed to build property value")
+    }
+
+    /// Returns the state of a store holding a mfg_batch whose `qc_status` was `PENDING` from
+    /// commit 1 and `FAILED` from commit 2
+    fn mfg_batch_state() -> MfgBatchState {
+        let store = DieselMfgBatchStore::new_in_memory().expect("Failed to create store");
+        let mfg_batch = MfgBatchBuilder::default()
+            .with_mfg_batch_id(MFG_BATCH_ID.into())
+            .with_mfg_batch_address("11bb0e01".into())
+            .with_mfg_batch_namespace("GS1".into())
+            .with_owner("org".into())
+            .with_properties(vec![
+                property("qc_status", "PENDING", 1, 2),
+                property("qc_status", "FAILED", 2, MAX_COMMIT_NUM),
+            ])
+            .with_start_commit_number(1)
+            .with_end_commit_number(MAX_COMMIT_NUM)
+            .build()
+            .expect("Failed to build mfg_batch");
+        store
+            .add_mfg_batch(mfg_batch)
+            .expect("Failed to add mfg_batch");
+
+        MfgBatchState::new(Arc::new(store))
+    }
+
+    /// Verify that annotations are attached to a property's values, listed in commit order and
+    /// returned with the property's history, and that one on a property that had no value at
+    /// the commit, or without a comment, is refused
+    #[cfg(all(
+        feature = "rest-api-endpoint-mfg-batch-annotations",
+        feature = "rest-api-endpoint-mfg-batch-history"
+    ))]
+    #[test]
+    fn test_mfg_batch_annotations() {
+        actix_web::rt::System::new("test").block_on(async {
+            let mut app = test::init_service(
+                App::new()
+                    .app_data(Endpoint::from("sawtooth:tcp://localhost:8008"))
+                    .data(mfg_batch_state())
+                    .service(add_mfg_batch_annotation)
+                    .service(list_mfg_batch_property_annotations)
+                    .service(get_property_value_history),
+            )
+            .await;
+
+            let uri = format!("/mfg_batch/{}/property/qc_status/annotations", MFG_BATCH_ID);
+            for (commit_num, comment) in &[(2, "Retest ordered"), (1, "Sample taken late")] {
+                let res = test::call_service(
+                    &mut app,
+                    test::TestRequest::post()
+                        .uri(&uri)
+                        .set_json(&json!({
+                            "commit_num": commit_num,
+                            "author": "qa@example.com",
+                            "comment": comment,
+                        }))
+                        .to_request(),
+                )
+                .await;
+                assert_eq!(res.status(), StatusCode::CREATED);
+            }
+
+            for (uri, body, status) in &[
+                (
+                    format!("/mfg_batch/{}/property/lot_size/annotations", MFG_BATCH_ID),
+                    json!({ "commit_num": 1, "author": "qa@example.com", "comment": "Wrong" }),
+                    StatusCode::NOT_FOUND,
+                ),
+                (
+                    uri.clone(),
+                    json!({ "commit_num": 1, "author": "qa@example.com", "comment": " " }),
+                    StatusCode::BAD_REQUEST,
+                ),
+            ] {
+                let res = test::call_service(
+                    &mut app,
+                    test::TestRequest::post()
+                        .uri(uri)
+                        .set_json(body)
+                        .to_request(),
+                )
+                .await;
+                assert_eq!(res.status(), *status, "{}: {}", uri, body);
+            }
+
+            let listed: Value =
+                test::read_response_json(&mut app, test::TestRequest::get().uri(&uri).to_request())
+                    .await;
+            assert_eq!(
+                listed["data"]
+                    .as_array()
+                    .expect("No annotations listed")
+                    .iter()
+                    .map(|annotation| annotation["comment"].as_str().unwrap_or_default())
+                    .collect::<Vec<_>>(),
+                vec!["Sample taken late", "Retest ordered"]
+            );
+
+            let history: Value = test::read_response_json(
+                &mut app,
+                test::TestRequest::get()
+                    .uri(&format!(
+                        "/mfg_batch/{}/properties/qc_status/history",
+                        MFG_BATCH_ID
+                    ))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(history["data"].as_array().map(Vec::len), Some(2));
+            assert_eq!(history["annotations"], listed["data"]);
+        });
+    }
+
+    /// Verify that the list and search of mfg_batches are explained with the SQL they run and a
+    /// plan, and that a search without any words is refused
+    #[cfg(feature = "rest-api-endpoint-mfg-batch-explain")]
+    #[test]
+    fn test_explain_mfg_batches() {
+        actix_web::rt::System::new("test").block_on(async {
+            let mut app = test::init_service(
+                App::new()
+                    .app_data(Endpoint::from("sawtooth:tcp://localhost:8008"))
+                    .data(mfg_batch_state())
+                    .service(explain_list_mfg_batches)
+                    .service(explain_search_mfg_batches),
+            )
+            .await;
+
+            let list: Value = test::read_response_json(
+                &mut app,
+                test::TestRequest::get()
+                    .uri("/admin/explain/mfg_batch?owner=org&limit=10")
+                    .to_request(),
+            )
+            .await;
+            assert!(list["sql"]
+                .as_str()
+                .expect("No SQL returned")
+                .contains("`owner` ="));
+            assert!(!list["plan"]
+                .as_array()
+                .expect("No plan returned")
+                .is_empty());
+
+            let search: Value = test::read_response_json(
+                &mut app,
+                test::TestRequest::get()
+                    .uri("/admin/explain/mfg_batch_search?q=failed")
+                    .to_request(),
+            )
+            .await;
+            assert!(search["sql"]
+                .as_str()
+                .expect("No SQL returned")
+                .contains("%failed%"));
+            assert!(!search["plan"]
+                .as_array()
+                .expect("No plan returned")
+                .is_empty());
+
+            let res = test::call_service(
+                &mut app,
+                test::TestRequest::get()
+                    .uri("/admin/explain/mfg_batch_search?q=%20")
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        });
+    }
+}
@@ -14,10 +14,16 @@
 
 #[cfg(feature = "rest-api-endpoint-agent")]
 mod agents;
+#[cfg(feature = "rest-api-endpoint-api-usage")]
+mod api_usage;
 #[cfg(feature = "rest-api-endpoint-batches")]
 mod batches;
+#[cfg(feature = "rest-api-endpoint-data-mapping")]
+mod data_mapping;
 #[cfg(feature = "rest-api-endpoint-location")]
 mod locations;
+#[cfg(feature = "rest-api-endpoint-mfg-batch")]
+mod mfg_batches;
 #[cfg(feature = "rest-api-endpoint-organization")]
 mod organizations;
 #[cfg(feature = "rest-api-endpoint-product")]
@@ -30,15 +36,23 @@ mod records;
 mod roles;
 #[cfg(feature = "rest-api-endpoint-schema")]
 mod schemas;
+#[cfg(feature = "rest-api-endpoint-sla")]
+mod sla;
 #[cfg(feature = "rest-api-endpoint-submit")]
 mod submit;
 
 #[cfg(feature = "rest-api-endpoint-agent")]
 pub use agents::*;
+#[cfg(feature = "rest-api-endpoint-api-usage")]
+pub use api_usage::*;
 #[cfg(feature = "rest-api-endpoint-batches")]
 pub use batches::*;
+#[cfg(feature = "rest-api-endpoint-data-mapping")]
+pub use data_mapping::*;
 #[cfg(feature = "rest-api-endpoint-location")]
 pub use locations::*;
+#[cfg(feature = "rest-api-endpoint-mfg-batch")]
+pub use mfg_batches::*;
 #[cfg(feature = "rest-api-endpoint-organization")]
 pub use organizations::*;
 #[cfg(feature = "rest-api-endpoint-product")]
@@ -51,6 +65,8 @@ pub use records::*;
 pub use roles::*;
 #[cfg(feature = "rest-api-endpoint-schema")]
 pub use schemas::*;
+#[cfg(feature = "rest-api-endpoint-sla")]
+pub use sla::*;
 #[cfg(feature = "rest-api-endpoint-submit")]
 pub use submit::*;
 
@@ -67,3 +83,22 @@ pub use submit::*;
     feature = "rest-api-endpoint-submit",
 ))]
 pub(in crate::rest_api) const DEFAULT_GRID_PROTOCOL_VERSION: &str = "1";
+
+#[cfg(any(
+    feature = "rest-api-endpoint-batches",
+    feature = "rest-api-endpoint-submit",
+))]
+const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+/// Returns the correlation id a client sent to trace its request, if it sent one
+#[cfg(any(
+    feature = "rest-api-endpoint-batches",
+    feature = "rest-api-endpoint-submit",
+))]
+fn correlation_id(req: &actix_web::HttpRequest) -> Option<String> {
+    req.headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+}
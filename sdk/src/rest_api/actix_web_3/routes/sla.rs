@@ -0,0 +1,66 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{get, http::StatusCode, web, HttpRequest, HttpResponse};
+
+use crate::rest_api::{
+    actix_web_3::{AcceptServiceIdParam, MfgBatchState, QueryServiceId, StoreState},
+    resources::sla::v1,
+};
+use crate::sla::SlaThresholds;
+
+use super::mfg_batches::require_unrestricted;
+
+#[derive(Deserialize)]
+pub struct SlaReportQuery {
+    #[serde(default)]
+    overdue: bool,
+}
+
+/// Reports how long each open transfer proposal and each batch held by an active recall has
+/// been pending against its threshold, listing only the overdue ones if `overdue=true`
+#[get("/admin/sla")]
+pub async fn get_sla_report(
+    store_state: web::Data<StoreState>,
+    mfg_batch_state: web::Data<MfgBatchState>,
+    thresholds: web::Data<SlaThresholds>,
+    query: web::Query<SlaReportQuery>,
+    query_service_id: web::Query<QueryServiceId>,
+    req: HttpRequest,
+    _: AcceptServiceIdParam,
+) -> HttpResponse {
+    let result = require_unrestricted(&req).and_then(|_| {
+        v1::get_sla_report(
+            &*store_state.store_factory.get_grid_track_and_trace_store(),
+            &*mfg_batch_state.store,
+            query_service_id.into_inner().service_id.as_deref(),
+            &thresholds,
+            query.overdue,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default(),
+        )
+    });
+
+    match result {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(err) => HttpResponse::build(
+            StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        )
+        .json(err),
+    }
+}
@@ -14,6 +14,7 @@
 
 use actix_web::{dev, http::StatusCode, post, web, FromRequest, HttpRequest, HttpResponse};
 use futures_util::future::{FutureExt, LocalBoxFuture};
+use uuid::Uuid;
 
 use crate::rest_api::actix_web_3::{KeyState, StoreState};
 use crate::rest_api::resources::{
@@ -21,23 +22,32 @@ use crate::rest_api::resources::{
     submit::v1::{submit_batches, SubmitBatchRequest},
 };
 
-use super::DEFAULT_GRID_PROTOCOL_VERSION;
+use super::{correlation_id, CORRELATION_ID_HEADER, DEFAULT_GRID_PROTOCOL_VERSION};
 
+/// Stores the submitted batches to be sent to the DLT.
+///
+/// The batches are stored with the request's `X-Correlation-Id`, or with a new id if the
+/// request did not have one, and the id is returned in the response's `X-Correlation-Id`.
 #[post("/submit")]
 async fn submit(
+    req: HttpRequest,
     store_state: web::Data<StoreState>,
     key_state: web::Data<KeyState>,
     version: ProtocolVersion,
 ) -> HttpResponse {
+    let correlation_id = correlation_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
     let store = store_state.store_factory.get_batch_store();
     match version {
         ProtocolVersion::V1(payload) => {
-            match submit_batches(&key_state.key_file_name, store, payload) {
-                Ok(res) => HttpResponse::Accepted().json(res),
+            match submit_batches(&key_state.key_file_name, store, payload, &correlation_id) {
+                Ok(res) => HttpResponse::Accepted()
+                    .header(CORRELATION_ID_HEADER, correlation_id)
+                    .json(res),
                 Err(err) => HttpResponse::build(
                     StatusCode::from_u16(err.status_code())
                         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
                 )
+                .header(CORRELATION_ID_HEADER, correlation_id)
                 .json(err),
             }
         }
@@ -0,0 +1,54 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+
+use crate::{
+    api_keys::{
+        analytics::ApiUsageRollupFilters,
+        store::{ApiKeyStore, ApiKeyStoreError},
+    },
+    rest_api::resources::error::ErrorResponse,
+};
+
+use super::payloads::{ApiUsageListSlice, ApiUsageRollupSlice};
+
+/// Lists the daily rollups of API usage that match the filters, most recent day first
+pub fn list_api_usage(
+    store: &dyn ApiKeyStore,
+    filters: &ApiUsageRollupFilters,
+    offset: u64,
+    limit: u16,
+) -> Result<ApiUsageListSlice, ErrorResponse> {
+    let rollups = store
+        .list_api_usage_rollups(
+            filters,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map_err(|err| match err {
+            ApiKeyStoreError::InternalError(err) => ErrorResponse::internal_error(Box::new(err)),
+            ApiKeyStoreError::ConstraintViolationError(err) => {
+                ErrorResponse::new(400, &format!("{}", err))
+            }
+            ApiKeyStoreError::ResourceTemporarilyUnavailableError(_) => {
+                ErrorResponse::new(503, "Service Unavailable")
+            }
+            ApiKeyStoreError::NotFoundError(msg) => ErrorResponse::new(404, &msg),
+        })?;
+
+    Ok(ApiUsageListSlice {
+        data: rollups.into_iter().map(ApiUsageRollupSlice::from).collect(),
+    })
+}
@@ -0,0 +1,47 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api_keys::analytics::ApiUsageRollup;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiUsageRollupSlice {
+    pub day: i64,
+    pub key_id: String,
+    pub org_id: String,
+    pub method: String,
+    pub endpoint: String,
+    pub requests: i64,
+    pub rows_returned: i64,
+    pub submissions: i64,
+}
+
+impl From<ApiUsageRollup> for ApiUsageRollupSlice {
+    fn from(rollup: ApiUsageRollup) -> Self {
+        Self {
+            day: rollup.day,
+            key_id: rollup.key_id,
+            org_id: rollup.org_id,
+            method: rollup.method,
+            endpoint: rollup.endpoint,
+            requests: rollup.requests,
+            rows_returned: rollup.rows_returned,
+            submissions: rollup.submissions,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiUsageListSlice {
+    pub data: Vec<ApiUsageRollupSlice>,
+}
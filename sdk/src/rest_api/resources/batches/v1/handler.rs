@@ -29,6 +29,7 @@ pub async fn submit_batches(
     backend_client: Arc<dyn BackendClient>,
     bytes: &[u8],
     service_id: Option<String>,
+    correlation_id: Option<String>,
 ) -> Result<BatchStatusLink, ErrorResponse> {
     let batch_list: BatchList = match protobuf::Message::parse_from_bytes(bytes) {
         Ok(batch_list) => batch_list,
@@ -45,6 +46,7 @@ pub async fn submit_batches(
             batch_list,
             response_url,
             service_id,
+            correlation_id,
         })
         .await
         .map_err(|err| match err {
@@ -64,6 +66,7 @@ pub async fn get_batch_statuses(
     ids: String,
     wait: Option<String>,
     service_id: Option<String>,
+    correlation_id: Option<String>,
 ) -> Result<BatchStatusResponse, ErrorResponse> {
     let batch_ids = ids.split(',').map(ToString::to_string).collect();
 
@@ -105,6 +108,7 @@ pub async fn get_batch_statuses(
             batch_ids,
             wait,
             service_id,
+            correlation_id,
         })
         .await
         .map_err(|err| match err {
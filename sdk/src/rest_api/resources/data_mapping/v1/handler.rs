@@ -0,0 +1,263 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "data-mapping-edi")]
+use std::collections::HashSet;
+
+#[cfg(feature = "data-mapping-edi")]
+use crate::data_mapping::edi::{read_shipments, EdiAdapter, GS1_LOCATION_SCHEMA};
+#[cfg(feature = "data-mapping-edi")]
+use crate::data_mapping::PayloadFormat;
+use crate::data_mapping::{DataMapping, DataMappingError};
+#[cfg(feature = "data-mapping-edi")]
+use crate::location::addressing::GRID_LOCATION_NAMESPACE;
+use crate::mfg_batch::addressing::GRID_MFG_BATCH_NAMESPACE;
+#[cfg(feature = "data-mapping-edi")]
+use crate::mfg_batch::store::MfgBatchStore;
+use crate::pike::addressing::GRID_PIKE_NAMESPACE;
+#[cfg(feature = "data-mapping-edi")]
+use crate::protocol::location::payload::LocationPayload;
+use crate::protocol::mfg_batch::payload::{Action, MfgBatchPayload, MfgBatchPayloadBuilder};
+use crate::rest_api::resources::{
+    error::ErrorResponse,
+    submit::v1::{Batch, Payload, SubmitBatchRequest, Transaction},
+};
+use crate::schema::addressing::GRID_SCHEMA_NAMESPACE;
+#[cfg(feature = "data-mapping-edi")]
+use crate::schema::store::PropertyDefinition;
+use crate::store::StoreFactory;
+
+const MFG_BATCH_FAMILY_NAME: &str = "grid_mfg_batch";
+const MFG_BATCH_FAMILY_VERSION: &str = "1";
+#[cfg(feature = "data-mapping-edi")]
+const LOCATION_FAMILY_NAME: &str = "grid_location";
+#[cfg(feature = "data-mapping-edi")]
+const LOCATION_FAMILY_VERSION: &str = "2";
+
+/// Applies a data mapping to a payload and builds the request that submits the resulting
+/// transactions in one batch
+///
+/// A JSON or XML payload is mapped onto an mfg_batch create action, and a CSV payload onto one
+/// for each row. An EDI ship notice is mapped onto a create or update action for each of its
+/// lots, and a location create action for each mapped party that is not a location yet; the
+/// location actions come first.
+///
+/// # Arguments
+///
+/// * `mapping` - The mapping to apply
+/// * `payload` - The payload, encoded in the mapping's format
+/// * `store_factory` - The stores the mapped schemas and locations are read from
+/// * `mfg_batch_store` - The store used to tell whether mapped mfg_batches exist; without one
+///   they are all created
+/// * `service_id` - The service the batch is submitted to, if any
+pub fn build_submit_request(
+    mapping: &DataMapping,
+    payload: &[u8],
+    store_factory: &dyn StoreFactory,
+    #[cfg(feature = "data-mapping-edi")] mfg_batch_store: Option<&dyn MfgBatchStore>,
+    service_id: Option<String>,
+) -> Result<SubmitBatchRequest, ErrorResponse> {
+    let schema = store_factory
+        .get_grid_schema_store()
+        .get_schema(mapping.schema_name(), service_id.as_deref())
+        .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+        .ok_or_else(|| {
+            ErrorResponse::new(
+                400,
+                &format!("Schema {} is not defined", mapping.schema_name()),
+            )
+        })?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+        .as_secs();
+
+    #[cfg(feature = "data-mapping-edi")]
+    if mapping.format == PayloadFormat::Edi {
+        return build_edi_request(
+            mapping,
+            payload,
+            store_factory,
+            mfg_batch_store,
+            &schema.properties,
+            service_id,
+            timestamp,
+        );
+    }
+
+    let actions = mapping
+        .apply_rows(payload, &schema.properties)
+        .map_err(to_error_response)?;
+    if actions.is_empty() {
+        return Err(ErrorResponse::new(400, "Payload holds no rows"));
+    }
+    let transactions = actions
+        .into_iter()
+        .map(|action| {
+            MfgBatchPayloadBuilder::new()
+                .with_action(Action::MfgBatchCreate(action))
+                .with_timestamp(timestamp)
+                .build()
+                .map(mfg_batch_transaction)
+                .map_err(|err| ErrorResponse::internal_error(Box::new(err)))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(SubmitBatchRequest {
+        batches: vec![Batch {
+            trace: false,
+            service_id,
+            transactions,
+        }],
+    })
+}
+
+#[cfg(feature = "data-mapping-edi")]
+fn build_edi_request(
+    mapping: &DataMapping,
+    payload: &[u8],
+    store_factory: &dyn StoreFactory,
+    mfg_batch_store: Option<&dyn MfgBatchStore>,
+    definitions: &[PropertyDefinition],
+    service_id: Option<String>,
+    timestamp: u64,
+) -> Result<SubmitBatchRequest, ErrorResponse> {
+    let adapter = EdiAdapter::new(mapping.clone()).map_err(to_error_response)?;
+    let document = read_shipments(payload).map_err(to_error_response)?;
+    if document.lots.is_empty() {
+        return Err(ErrorResponse::new(400, "Ship notice holds no lots"));
+    }
+
+    // The stores are read before the actions are built, since `payloads` takes a plain
+    // predicate
+    let mut existing_batches = HashSet::new();
+    if let Some(store) = mfg_batch_store {
+        let actions = adapter
+            .create_actions(&document, definitions)
+            .map_err(to_error_response)?;
+        let mfg_batch_ids = actions
+            .iter()
+            .map(|action| action.mfg_batch_id())
+            .collect::<Vec<_>>();
+
+        existing_batches.extend(
+            store
+                .get_mfg_batches(&mfg_batch_ids, service_id.as_deref())
+                .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+                .iter()
+                .map(|mfg_batch| mfg_batch.mfg_batch_id().to_string()),
+        );
+    }
+    let batch_payloads = adapter
+        .payloads(
+            &document,
+            definitions,
+            |id| existing_batches.contains(id),
+            timestamp,
+        )
+        .map_err(to_error_response)?;
+
+    let location_payloads = match mapping.locations {
+        Some(_) => {
+            let location_schema = store_factory
+                .get_grid_schema_store()
+                .get_schema(GS1_LOCATION_SCHEMA, service_id.as_deref())
+                .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+                .ok_or_else(|| {
+                    ErrorResponse::new(
+                        400,
+                        &format!("Schema {} is not defined", GS1_LOCATION_SCHEMA),
+                    )
+                })?;
+
+            let location_store = store_factory.get_grid_location_store();
+            let mut existing_locations = HashSet::new();
+            for action in adapter
+                .location_actions(&document, &location_schema.properties)
+                .map_err(to_error_response)?
+            {
+                if location_store
+                    .get_location(action.location_id(), service_id.as_deref())
+                    .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?
+                    .is_some()
+                {
+                    existing_locations.insert(action.location_id().to_string());
+                }
+            }
+
+            adapter
+                .location_payloads(
+                    &document,
+                    &location_schema.properties,
+                    |id| existing_locations.contains(id),
+                    timestamp,
+                )
+                .map_err(to_error_response)?
+        }
+        None => vec![],
+    };
+
+    Ok(SubmitBatchRequest {
+        batches: vec![Batch {
+            trace: false,
+            service_id,
+            transactions: location_payloads
+                .into_iter()
+                .map(location_transaction)
+                .chain(batch_payloads.into_iter().map(mfg_batch_transaction))
+                .collect(),
+        }],
+    })
+}
+
+fn mfg_batch_transaction(payload: MfgBatchPayload) -> Transaction {
+    Transaction {
+        family_name: MFG_BATCH_FAMILY_NAME.to_string(),
+        version: MFG_BATCH_FAMILY_VERSION.to_string(),
+        dependencies: vec![],
+        inputs: vec![
+            GRID_PIKE_NAMESPACE.to_string(),
+            GRID_SCHEMA_NAMESPACE.to_string(),
+            GRID_MFG_BATCH_NAMESPACE.to_string(),
+        ],
+        outputs: vec![GRID_MFG_BATCH_NAMESPACE.to_string()],
+        payload: Payload::MfgBatch(payload),
+    }
+}
+
+#[cfg(feature = "data-mapping-edi")]
+fn location_transaction(payload: LocationPayload) -> Transaction {
+    Transaction {
+        family_name: LOCATION_FAMILY_NAME.to_string(),
+        version: LOCATION_FAMILY_VERSION.to_string(),
+        dependencies: vec![],
+        inputs: vec![
+            GRID_PIKE_NAMESPACE.to_string(),
+            GRID_SCHEMA_NAMESPACE.to_string(),
+            GRID_LOCATION_NAMESPACE.to_string(),
+        ],
+        outputs: vec![GRID_LOCATION_NAMESPACE.to_string()],
+        payload: Payload::MappedLocation(payload),
+    }
+}
+
+fn to_error_response(err: DataMappingError) -> ErrorResponse {
+    match err {
+        DataMappingError::InvalidArgument(_) => ErrorResponse::new(400, &err.to_string()),
+        DataMappingError::Internal(err) => ErrorResponse::internal_error(Box::new(err)),
+    }
+}
@@ -0,0 +1,727 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(any(
+    feature = "rest-api-resources-mfg-batch-duplicates",
+    feature = "rest-api-resources-mfg-batch-explain",
+    feature = "rest-api-resources-mfg-batch-quality-scores",
+    feature = "rest-api-resources-mfg-batch-recalls",
+    feature = "rest-api-resources-mfg-batch-search"
+))]
+use std::convert::TryFrom;
+#[cfg(any(
+    feature = "rest-api-resources-mfg-batch-certificates",
+    feature = "rest-api-resources-mfg-batch-epcis"
+))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "rest-api-resources-mfg-batch-epcis")]
+use serde_json::Value;
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+use crate::mfg_batch::certificate::{
+    render_certificate, CertificateError, CertificateTemplate, CertificateTemplateDirectory,
+};
+#[cfg(feature = "rest-api-resources-mfg-batch-epcis")]
+use crate::mfg_batch::epcis::{export_mfg_batches, EpcisError, EpcisOptions};
+#[cfg(any(
+    feature = "rest-api-resources-mfg-batch-explain",
+    feature = "rest-api-resources-mfg-batch-list"
+))]
+use crate::mfg_batch::store::ListMfgBatchFilters;
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+use crate::mfg_batch::store::ListMfgBatchQualityScoreFilters;
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+use crate::mfg_batch::store::ListMfgBatchRecallFilters;
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+use crate::mfg_batch::store::MfgBatchAnnotation;
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+use crate::mfg_batch::store::MfgBatchCursor;
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+use crate::mfg_batch::{store::MfgBatchVersionRows, MAX_COMMIT_NUM};
+use crate::{
+    mfg_batch::store::{MfgBatchStore, MfgBatchStoreError},
+    rest_api::resources::error::ErrorResponse,
+};
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+use super::payloads::CertificateTemplateListSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+use super::payloads::MfgBatchListSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-search")]
+use super::payloads::MfgBatchSearchSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+use super::payloads::QueryPlanSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+use super::payloads::{AnnotationListSlice, AnnotationSlice, NewAnnotationPayload};
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+use super::payloads::{DuplicateListSlice, DuplicateSlice};
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+use super::payloads::{
+    MfgBatchHistorySlice, MfgBatchPropertyHistorySlice, MfgBatchPropertyListSlice,
+};
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+use super::payloads::{QualityScoreListSlice, QualityScoreSlice};
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+use super::payloads::{RecallListSlice, RecallSlice};
+use super::payloads::{TestResultListSlice, TestResultSlice};
+
+/// Lists the quality test results recorded against a mfg_batch, oldest first
+pub fn list_mfg_batch_test_results(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    service_id: Option<&str>,
+) -> Result<TestResultListSlice, ErrorResponse> {
+    let test_results = store
+        .list_mfg_batch_test_results(&mfg_batch_id, service_id)
+        .map_err(|err| store_error(err, &mfg_batch_id))?;
+
+    Ok(TestResultListSlice {
+        data: test_results
+            .into_iter()
+            .map(TestResultSlice::from)
+            .collect(),
+        mfg_batch_id,
+    })
+}
+
+/// Lists current mfg_batches in ID order, `limit` at a time, starting after the mfg_batch the
+/// cursor points at. Unlike offset paging, each page costs the same however deep it is.
+///
+/// When the page is full, `next` is the cursor the next page is fetched with.
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+pub fn list_mfg_batches(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    filters: &ListMfgBatchFilters,
+    cursor: Option<&str>,
+    limit: u16,
+) -> Result<MfgBatchListSlice, ErrorResponse> {
+    let cursor = cursor
+        .map(MfgBatchCursor::decode)
+        .transpose()
+        .map_err(|err| store_error(err, ""))?;
+
+    let page = store
+        .list_mfg_batches_after(service_id, filters, cursor.as_ref(), i64::from(limit))
+        .map_err(|err| store_error(err, ""))?;
+
+    let next = if limit > 0 && page.len() == usize::from(limit) {
+        page.last().map(|(cursor, _)| cursor.encode())
+    } else {
+        None
+    };
+
+    Ok(MfgBatchListSlice {
+        data: page.into_iter().map(|(_, mfg_batch)| mfg_batch).collect(),
+        next,
+    })
+}
+
+/// Lists the quality scores of current mfg_batches, lowest first, as a queue of the
+/// mfg_batches most in need of cleaning up
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+pub fn list_mfg_batch_quality_scores(
+    store: &dyn MfgBatchStore,
+    max_score: Option<i32>,
+    service_id: Option<&str>,
+    offset: u64,
+    limit: u16,
+) -> Result<QualityScoreListSlice, ErrorResponse> {
+    let filters = ListMfgBatchQualityScoreFilters {
+        mfg_batch_ids: None,
+        max_score,
+    };
+    let scores = store
+        .list_mfg_batch_quality_scores(
+            service_id,
+            &filters,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map_err(|err| match err {
+            MfgBatchStoreError::NotFoundError(msg) => ErrorResponse::new(404, &msg),
+            err => store_error(err, ""),
+        })?;
+
+    Ok(QualityScoreListSlice {
+        data: scores.into_iter().map(QualityScoreSlice::from).collect(),
+    })
+}
+
+/// Lists the review queue of likely duplicate mfg_batches, most alike first, each with the
+/// mfg_batch it is suggested to be merged into
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+pub fn list_mfg_batch_duplicates(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    offset: u64,
+    limit: u16,
+) -> Result<DuplicateListSlice, ErrorResponse> {
+    let duplicates = store
+        .list_mfg_batch_duplicates(
+            service_id,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map_err(|err| match err {
+            MfgBatchStoreError::NotFoundError(msg) => ErrorResponse::new(404, &msg),
+            err => store_error(err, ""),
+        })?;
+
+    Ok(DuplicateListSlice {
+        data: duplicates.into_iter().map(DuplicateSlice::from).collect(),
+    })
+}
+
+/// Lists the active recalls, most recent first, with a record for each mfg_batch a recall
+/// covers
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+pub fn list_mfg_batch_recalls(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: Option<String>,
+    recall_id: Option<String>,
+    service_id: Option<&str>,
+    offset: u64,
+    limit: u16,
+) -> Result<RecallListSlice, ErrorResponse> {
+    let filters = ListMfgBatchRecallFilters {
+        mfg_batch_id,
+        recall_id,
+        include_closed: false,
+    };
+    let recalls = store
+        .list_mfg_batch_recalls(
+            service_id,
+            &filters,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map_err(|err| store_error(err, ""))?;
+
+    Ok(RecallListSlice {
+        data: recalls.into_iter().map(RecallSlice::from).collect(),
+    })
+}
+
+/// Searches the string property values of current mfg_batches for free text
+#[cfg(feature = "rest-api-resources-mfg-batch-search")]
+pub fn search_mfg_batches(
+    store: &dyn MfgBatchStore,
+    query: &str,
+    service_id: Option<&str>,
+    offset: u64,
+    limit: u16,
+) -> Result<MfgBatchSearchSlice, ErrorResponse> {
+    let mfg_batches = store
+        .search_mfg_batches_text(
+            query,
+            service_id,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map_err(|err| store_error(err, ""))?;
+
+    Ok(MfgBatchSearchSlice { data: mfg_batches })
+}
+
+/// Returns the SQL an offset-paged list of mfg_batches runs for the filters and page, and the
+/// database's plan for it
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+pub fn explain_list_mfg_batches(
+    store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    filters: &ListMfgBatchFilters,
+    offset: u64,
+    limit: u16,
+) -> Result<QueryPlanSlice, ErrorResponse> {
+    store
+        .explain_list_mfg_batches(
+            service_id,
+            filters,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map(QueryPlanSlice::from)
+        .map_err(|err| store_error(err, ""))
+}
+
+/// Returns the SQL a free text search of mfg_batches runs for the query and page, and the
+/// database's plan for it
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+pub fn explain_search_mfg_batches(
+    store: &dyn MfgBatchStore,
+    query: &str,
+    service_id: Option<&str>,
+    offset: u64,
+    limit: u16,
+) -> Result<QueryPlanSlice, ErrorResponse> {
+    store
+        .explain_search_mfg_batches_text(
+            query,
+            service_id,
+            i64::try_from(offset).unwrap_or(i64::MAX),
+            i64::from(limit),
+        )
+        .map(QueryPlanSlice::from)
+        .map_err(|err| store_error(err, ""))
+}
+
+/// Prints a certificate for a mfg_batch from the named template, as a PDF
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub fn render_mfg_batch_certificate(
+    store: &dyn MfgBatchStore,
+    templates: &CertificateTemplateDirectory,
+    mfg_batch_id: &str,
+    template_name: &str,
+    service_id: Option<&str>,
+) -> Result<Vec<u8>, ErrorResponse> {
+    let template = get_certificate_template(templates, template_name)?;
+
+    let mfg_batch = store
+        .get_mfg_batch(mfg_batch_id, service_id)
+        .map_err(|err| store_error(err, mfg_batch_id))?
+        .ok_or_else(|| ErrorResponse::new(404, &format!("Mfg_batch {} not found", mfg_batch_id)))?;
+    let test_results = store
+        .list_mfg_batch_test_results(mfg_batch_id, service_id)
+        .map_err(|err| store_error(err, mfg_batch_id))?;
+
+    render_certificate(&template, &mfg_batch, &test_results, now()?).map_err(certificate_error)
+}
+
+/// Exports the history of a mfg_batch as an EPCIS 2.0 document, in JSON-LD
+#[cfg(feature = "rest-api-resources-mfg-batch-epcis")]
+pub fn export_mfg_batch_epcis(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+) -> Result<Value, ErrorResponse> {
+    export_mfg_batches(
+        store,
+        &[mfg_batch_id],
+        service_id,
+        &EpcisOptions::default(),
+        now()?,
+    )
+    .map_err(|err| match err {
+        EpcisError::Store(err) => store_error(err, mfg_batch_id),
+        EpcisError::NotFound(_) => {
+            ErrorResponse::new(404, &format!("Mfg_batch {} not found", mfg_batch_id))
+        }
+    })
+}
+
+#[cfg(any(
+    feature = "rest-api-resources-mfg-batch-certificates",
+    feature = "rest-api-resources-mfg-batch-epcis"
+))]
+fn now() -> Result<i64, ErrorResponse> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as i64)
+        .map_err(|err| ErrorResponse::internal_error(Box::new(err)))
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub fn list_certificate_templates(
+    templates: &CertificateTemplateDirectory,
+) -> Result<CertificateTemplateListSlice, ErrorResponse> {
+    Ok(CertificateTemplateListSlice {
+        data: templates.list().map_err(certificate_error)?,
+    })
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub fn get_certificate_template(
+    templates: &CertificateTemplateDirectory,
+    name: &str,
+) -> Result<CertificateTemplate, ErrorResponse> {
+    templates
+        .get(name)
+        .map_err(certificate_error)?
+        .ok_or_else(|| ErrorResponse::new(404, &format!("Certificate template {} not found", name)))
+}
+
+/// Stores a template, written in YAML or JSON, under the name it is given in the path
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub fn put_certificate_template(
+    templates: &CertificateTemplateDirectory,
+    name: &str,
+    body: &str,
+) -> Result<CertificateTemplate, ErrorResponse> {
+    let template = CertificateTemplate::from_yaml(body).map_err(certificate_error)?;
+    if template.name != name {
+        return Err(ErrorResponse::new(
+            400,
+            &format!(
+                "Template is named {}, but was put to {}",
+                template.name, name
+            ),
+        ));
+    }
+
+    templates.put(&template).map_err(certificate_error)?;
+
+    Ok(template)
+}
+
+/// Deletes a stored template; a built in template of the same name is used again afterwards
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub fn delete_certificate_template(
+    templates: &CertificateTemplateDirectory,
+    name: &str,
+) -> Result<(), ErrorResponse> {
+    if templates.delete(name).map_err(certificate_error)? {
+        Ok(())
+    } else {
+        Err(ErrorResponse::new(
+            404,
+            &format!("Certificate template {} not found", name),
+        ))
+    }
+}
+
+/// The estimated size of a serialized mfg_batch version, not counting its properties, in bytes
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+const VERSION_SIZE_ESTIMATE: usize = 512;
+/// The estimated size of a serialized property value row, in bytes
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+const PROPERTY_ROW_SIZE_ESTIMATE: usize = 256;
+
+/// Lists the versions of a mfg_batch, oldest first, in pages whose estimated size is no more
+/// than `max_size` bytes. The size of each version is estimated from the number of rows it is
+/// stored as, before any version is loaded; a page holds at least one version, however large.
+///
+/// When the history does not fit in one page, `next` is the cursor the next page is fetched
+/// with.
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+pub fn list_mfg_batch_history(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    service_id: Option<&str>,
+    cursor: Option<&str>,
+    max_size: usize,
+) -> Result<MfgBatchHistorySlice, ErrorResponse> {
+    let after = cursor
+        .map(|cursor| {
+            decode_cursor(cursor)?
+                .parse::<i64>()
+                .map_err(|_| ErrorResponse::new(400, "Invalid cursor"))
+        })
+        .transpose()?;
+
+    let versions: Vec<MfgBatchVersionRows> = version_rows(store, &mfg_batch_id, service_id)?
+        .into_iter()
+        .filter(|version| after.map_or(true, |after| version.start_commit_num > after))
+        .collect();
+
+    let (count, estimated_size) = fit_page(
+        versions.iter().map(|version| {
+            VERSION_SIZE_ESTIMATE
+                + version.total_property_rows() as usize * PROPERTY_ROW_SIZE_ESTIMATE
+        }),
+        max_size,
+    );
+
+    let mut data = Vec::with_capacity(count);
+    for version in &versions[..count] {
+        if let Some(mfg_batch) = store
+            .get_mfg_batch_at_commit(&mfg_batch_id, version.start_commit_num, service_id)
+            .map_err(|err| store_error(err, &mfg_batch_id))?
+        {
+            data.push(mfg_batch);
+        }
+    }
+
+    let next = if count < versions.len() {
+        Some(encode_cursor(
+            &versions[count - 1].start_commit_num.to_string(),
+        ))
+    } else {
+        None
+    };
+
+    Ok(MfgBatchHistorySlice {
+        mfg_batch_id,
+        data,
+        next,
+        estimated_size,
+    })
+}
+
+/// Lists the top-level properties of the current version of a mfg_batch, with their struct
+/// values, in name order and in pages whose estimated size is no more than `max_size` bytes.
+/// A page holds at least one property, however large.
+///
+/// When the properties do not fit in one page, `next` is the cursor the next page is fetched
+/// with.
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+pub fn list_mfg_batch_properties(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    service_id: Option<&str>,
+    cursor: Option<&str>,
+    max_size: usize,
+) -> Result<MfgBatchPropertyListSlice, ErrorResponse> {
+    let after = cursor.map(decode_cursor).transpose()?;
+
+    let current = version_rows(store, &mfg_batch_id, service_id)?
+        .into_iter()
+        .find(|version| version.end_commit_num == MAX_COMMIT_NUM)
+        .ok_or_else(|| ErrorResponse::new(404, &format!("Mfg_batch {} not found", mfg_batch_id)))?;
+
+    let properties: Vec<(String, i64)> = current
+        .property_rows
+        .into_iter()
+        .filter(|(name, _)| after.as_ref().map_or(true, |after| name > after))
+        .collect();
+
+    let (count, estimated_size) = fit_page(
+        properties
+            .iter()
+            .map(|(_, rows)| *rows as usize * PROPERTY_ROW_SIZE_ESTIMATE),
+        max_size,
+    );
+
+    let names: Vec<String> = properties[..count]
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let data = if names.is_empty() {
+        vec![]
+    } else {
+        store
+            .get_mfg_batch_properties(&mfg_batch_id, &names, service_id)
+            .map_err(|err| store_error(err, &mfg_batch_id))?
+    };
+
+    let next = if count < properties.len() {
+        Some(encode_cursor(&properties[count - 1].0))
+    } else {
+        None
+    };
+
+    Ok(MfgBatchPropertyListSlice {
+        mfg_batch_id,
+        data,
+        next,
+        estimated_size,
+    })
+}
+
+/// Lists every stored value of a top-level property of a mfg_batch, with its struct values,
+/// oldest first; a property that was never stored is not found
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+pub fn get_property_value_history(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    property_name: String,
+    service_id: Option<&str>,
+) -> Result<MfgBatchPropertyHistorySlice, ErrorResponse> {
+    let data = store
+        .get_property_value_history(&mfg_batch_id, &property_name, service_id)
+        .map_err(|err| store_error(err, &mfg_batch_id))?;
+
+    if data.is_empty() {
+        return Err(ErrorResponse::new(
+            404,
+            &format!(
+                "Property {} of mfg_batch {} not found",
+                property_name, mfg_batch_id
+            ),
+        ));
+    }
+
+    Ok(MfgBatchPropertyHistorySlice {
+        #[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+        annotations: property_annotations(store, &mfg_batch_id, &property_name, service_id)?,
+        mfg_batch_id,
+        property_name,
+        data,
+    })
+}
+
+/// Attaches an annotation to a property of a mfg_batch; the property must have had a value as
+/// of the annotation's commit
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+pub fn add_mfg_batch_annotation(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    property_name: String,
+    payload: NewAnnotationPayload,
+    service_id: Option<&str>,
+) -> Result<AnnotationSlice, ErrorResponse> {
+    if payload.author.trim().is_empty() || payload.comment.trim().is_empty() {
+        return Err(ErrorResponse::new(
+            400,
+            "An annotation must have an author and a comment",
+        ));
+    }
+
+    let annotation = MfgBatchAnnotation {
+        mfg_batch_id: mfg_batch_id.clone(),
+        commit_num: payload.commit_num,
+        property_name,
+        author: payload.author,
+        comment: payload.comment,
+        service_id: service_id.map(String::from),
+    };
+    store
+        .add_mfg_batch_annotation(annotation.clone())
+        .map_err(|err| match err {
+            MfgBatchStoreError::NotFoundError(msg) => {
+                ErrorResponse::new(404, &format!("{} not found", msg))
+            }
+            err => store_error(err, &mfg_batch_id),
+        })?;
+
+    Ok(AnnotationSlice::from(annotation))
+}
+
+/// Lists the annotations on a property of a mfg_batch, in the order of the commits they were
+/// made against
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+pub fn list_mfg_batch_property_annotations(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: String,
+    property_name: String,
+    service_id: Option<&str>,
+) -> Result<AnnotationListSlice, ErrorResponse> {
+    Ok(AnnotationListSlice {
+        data: property_annotations(store, &mfg_batch_id, &property_name, service_id)?,
+        mfg_batch_id,
+        property_name,
+    })
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+fn property_annotations(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: &str,
+    property_name: &str,
+    service_id: Option<&str>,
+) -> Result<Vec<AnnotationSlice>, ErrorResponse> {
+    Ok(store
+        .list_mfg_batch_annotations(mfg_batch_id, service_id)
+        .map_err(|err| store_error(err, mfg_batch_id))?
+        .into_iter()
+        .filter(|annotation| annotation.property_name == property_name)
+        .map(AnnotationSlice::from)
+        .collect())
+}
+
+/// Counts the rows of each stored version of a mfg_batch; a mfg_batch with no versions is not
+/// found
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+fn version_rows(
+    store: &dyn MfgBatchStore,
+    mfg_batch_id: &str,
+    service_id: Option<&str>,
+) -> Result<Vec<MfgBatchVersionRows>, ErrorResponse> {
+    let versions = store
+        .count_mfg_batch_version_rows(mfg_batch_id, service_id)
+        .map_err(|err| store_error(err, mfg_batch_id))?;
+
+    if versions.is_empty() {
+        return Err(ErrorResponse::new(
+            404,
+            &format!("Mfg_batch {} not found", mfg_batch_id),
+        ));
+    }
+
+    Ok(versions)
+}
+
+/// Returns how many of the items, given their estimated sizes in order, fit in `max_size`
+/// bytes, and the estimated size of those items. The first item always fits, so that every
+/// page makes progress.
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+fn fit_page<I: IntoIterator<Item = usize>>(sizes: I, max_size: usize) -> (usize, usize) {
+    let mut count = 0;
+    let mut total = 0;
+    for size in sizes {
+        if count > 0 && total + size > max_size {
+            break;
+        }
+        count += 1;
+        total += size;
+    }
+    (count, total)
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+fn encode_cursor(key: &str) -> String {
+    base64::encode_config(key, base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+fn decode_cursor(cursor: &str) -> Result<String, ErrorResponse> {
+    base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| ErrorResponse::new(400, "Invalid cursor"))
+}
+
+fn store_error(err: MfgBatchStoreError, mfg_batch_id: &str) -> ErrorResponse {
+    match err {
+        MfgBatchStoreError::InternalError(err) => ErrorResponse::internal_error(Box::new(err)),
+        MfgBatchStoreError::ConstraintViolationError(err) => {
+            ErrorResponse::new(400, &format!("{}", err))
+        }
+        MfgBatchStoreError::InvalidArgumentError(err) => {
+            ErrorResponse::new(400, &format!("{}", err))
+        }
+        MfgBatchStoreError::ResourceTemporarilyUnavailableError(_) => {
+            ErrorResponse::new(503, "Service Unavailable")
+        }
+        MfgBatchStoreError::NotFoundError(_) => {
+            ErrorResponse::new(404, &format!("Mfg_batch {} not found", mfg_batch_id))
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+fn certificate_error(err: CertificateError) -> ErrorResponse {
+    match err {
+        CertificateError::Internal(err) => ErrorResponse::internal_error(Box::new(err)),
+        CertificateError::InvalidArgument(err) => ErrorResponse::new(400, &format!("{}", err)),
+    }
+}
+
+#[cfg(all(test, feature = "rest-api-resources-mfg-batch-history"))]
+mod tests {
+    use super::*;
+
+    /// Verifies that a page takes the items that fit, always at least one, and stops at the
+    /// first item that does not fit
+    #[test]
+    fn test_fit_page() {
+        assert_eq!(fit_page(vec![], 100), (0, 0));
+        assert_eq!(fit_page(vec![40, 40, 40], 100), (2, 80));
+        assert_eq!(fit_page(vec![40, 60, 10], 100), (2, 100));
+        assert_eq!(fit_page(vec![500, 10], 100), (1, 500));
+        assert_eq!(fit_page(vec![10, 500, 10], 100), (1, 10));
+    }
+
+    /// Verifies that a cursor decodes to the key it was encoded from, and that a malformed
+    /// cursor is rejected
+    #[test]
+    fn test_cursor() {
+        assert_eq!(
+            decode_cursor(&encode_cursor("lot/size")).unwrap(),
+            "lot/size"
+        );
+        assert!(decode_cursor("not a cursor").is_err());
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod handler;
+mod payloads;
+
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+pub use handler::{add_mfg_batch_annotation, list_mfg_batch_property_annotations};
+#[cfg(feature = "rest-api-resources-mfg-batch-epcis")]
+pub use handler::export_mfg_batch_epcis;
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+pub use handler::{explain_list_mfg_batches, explain_search_mfg_batches};
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+pub use handler::list_mfg_batch_duplicates;
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+pub use handler::list_mfg_batch_quality_scores;
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+pub use handler::list_mfg_batch_recalls;
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+pub use handler::{get_property_value_history, list_mfg_batch_history, list_mfg_batch_properties};
+pub use handler::list_mfg_batch_test_results;
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+pub use handler::list_mfg_batches;
+#[cfg(feature = "rest-api-resources-mfg-batch-search")]
+pub use handler::search_mfg_batches;
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub use handler::{
+    delete_certificate_template, get_certificate_template, list_certificate_templates,
+    put_certificate_template, render_mfg_batch_certificate,
+};
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+pub use payloads::{AnnotationListSlice, AnnotationSlice, NewAnnotationPayload};
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+pub use payloads::CertificateTemplateListSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+pub use payloads::MfgBatchListSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-search")]
+pub use payloads::MfgBatchSearchSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+pub use payloads::QueryPlanSlice;
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+pub use payloads::{DuplicateListSlice, DuplicateSlice};
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+pub use payloads::{
+    MfgBatchHistorySlice, MfgBatchPropertyHistorySlice, MfgBatchPropertyListSlice,
+};
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+pub use payloads::{QualityScoreListSlice, QualityScoreSlice};
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+pub use payloads::{RecallListSlice, RecallSlice};
+pub use payloads::{TestResultListSlice, TestResultSlice};
@@ -0,0 +1,281 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+use crate::mfg_batch::certificate::CertificateTemplate;
+#[cfg(any(
+    feature = "rest-api-resources-mfg-batch-history",
+    feature = "rest-api-resources-mfg-batch-list",
+    feature = "rest-api-resources-mfg-batch-search"
+))]
+use crate::mfg_batch::store::MfgBatch;
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+use crate::mfg_batch::store::MfgBatchAnnotation;
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+use crate::mfg_batch::store::MfgBatchDuplicate;
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+use crate::mfg_batch::store::MfgBatchQualityScore;
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+use crate::mfg_batch::store::MfgBatchRecall;
+use crate::mfg_batch::store::MfgBatchTestResult;
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+use crate::mfg_batch::store::PropertyValue;
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+use crate::mfg_batch::store::QueryPlan;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestResultSlice {
+    pub test_name: String,
+    pub specification: String,
+    pub result: String,
+    pub passed: bool,
+    pub lab: String,
+    pub tested_at: i64,
+    pub commit_num: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+impl From<MfgBatchTestResult> for TestResultSlice {
+    fn from(test_result: MfgBatchTestResult) -> Self {
+        Self {
+            test_name: test_result.test_name,
+            specification: test_result.specification,
+            result: test_result.result,
+            passed: test_result.passed,
+            lab: test_result.lab,
+            tested_at: test_result.tested_at,
+            commit_num: test_result.commit_num,
+            service_id: test_result.service_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestResultListSlice {
+    pub mfg_batch_id: String,
+    pub data: Vec<TestResultSlice>,
+}
+
+/// An annotation to attach to a property of a mfg_batch, as it stood at the given commit
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAnnotationPayload {
+    pub commit_num: i64,
+    pub author: String,
+    pub comment: String,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationSlice {
+    pub commit_num: i64,
+    pub author: String,
+    pub comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+impl From<MfgBatchAnnotation> for AnnotationSlice {
+    fn from(annotation: MfgBatchAnnotation) -> Self {
+        Self {
+            commit_num: annotation.commit_num,
+            author: annotation.author,
+            comment: annotation.comment,
+            service_id: annotation.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationListSlice {
+    pub mfg_batch_id: String,
+    pub property_name: String,
+    pub data: Vec<AnnotationSlice>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-certificates")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateTemplateListSlice {
+    pub data: Vec<CertificateTemplate>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QualityScoreSlice {
+    pub mfg_batch_id: String,
+    pub score: i32,
+    pub failed_checks: Vec<String>,
+    pub commit_num: i64,
+    pub scored_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+impl From<MfgBatchQualityScore> for QualityScoreSlice {
+    fn from(score: MfgBatchQualityScore) -> Self {
+        Self {
+            mfg_batch_id: score.mfg_batch_id,
+            score: score.score,
+            failed_checks: score.failed_checks,
+            commit_num: score.commit_num,
+            scored_at: score.scored_at,
+            service_id: score.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-quality-scores")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QualityScoreListSlice {
+    pub data: Vec<QualityScoreSlice>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateSlice {
+    pub mfg_batch_id: String,
+    pub duplicate_of: String,
+    pub rule: String,
+    pub similarity: i32,
+    pub detected_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+impl From<MfgBatchDuplicate> for DuplicateSlice {
+    fn from(duplicate: MfgBatchDuplicate) -> Self {
+        Self {
+            mfg_batch_id: duplicate.mfg_batch_id,
+            duplicate_of: duplicate.duplicate_of,
+            rule: duplicate.rule,
+            similarity: duplicate.similarity,
+            detected_at: duplicate.detected_at,
+            service_id: duplicate.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-duplicates")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateListSlice {
+    pub data: Vec<DuplicateSlice>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecallSlice {
+    pub recall_id: String,
+    pub mfg_batch_id: String,
+    pub reason_code: String,
+    pub recalled_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<i64>,
+    pub commit_num: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+impl From<MfgBatchRecall> for RecallSlice {
+    fn from(recall: MfgBatchRecall) -> Self {
+        Self {
+            recall_id: recall.recall_id,
+            mfg_batch_id: recall.mfg_batch_id,
+            reason_code: recall.reason_code,
+            recalled_at: recall.recalled_at,
+            closed_at: recall.closed_at,
+            commit_num: recall.commit_num,
+            service_id: recall.service_id,
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-recalls")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecallListSlice {
+    pub data: Vec<RecallSlice>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-list")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfgBatchListSlice {
+    pub data: Vec<MfgBatch>,
+    /// The cursor the next page is fetched with, when this page was full
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-search")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfgBatchSearchSlice {
+    pub data: Vec<MfgBatch>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryPlanSlice {
+    pub sql: String,
+    pub plan: Vec<String>,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-explain")]
+impl From<QueryPlan> for QueryPlanSlice {
+    fn from(query_plan: QueryPlan) -> Self {
+        Self {
+            sql: query_plan.sql,
+            plan: query_plan.plan,
+        }
+    }
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfgBatchHistorySlice {
+    pub mfg_batch_id: String,
+    pub data: Vec<MfgBatch>,
+    /// The cursor the next page is fetched with, when the history did not fit in this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// The estimated size of the versions in this page, in bytes
+    pub estimated_size: usize,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfgBatchPropertyListSlice {
+    pub mfg_batch_id: String,
+    pub data: Vec<PropertyValue>,
+    /// The cursor the next page is fetched with, when the properties did not fit in this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// The estimated size of the properties in this page, in bytes
+    pub estimated_size: usize,
+}
+
+#[cfg(feature = "rest-api-resources-mfg-batch-history")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfgBatchPropertyHistorySlice {
+    pub mfg_batch_id: String,
+    pub property_name: String,
+    pub data: Vec<PropertyValue>,
+    /// The annotations on the property's values, in the order of the commits they were made
+    /// against
+    #[cfg(feature = "rest-api-resources-mfg-batch-annotations")]
+    pub annotations: Vec<AnnotationSlice>,
+}
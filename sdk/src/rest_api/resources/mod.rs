@@ -14,11 +14,17 @@
 
 #[cfg(feature = "rest-api-resources-agent")]
 pub mod agents;
+#[cfg(feature = "rest-api-resources-api-usage")]
+pub mod api_usage;
 #[cfg(feature = "rest-api-resources-batches")]
 pub mod batches;
+#[cfg(feature = "rest-api-resources-data-mapping")]
+pub mod data_mapping;
 pub mod error;
 #[cfg(feature = "rest-api-resources-location")]
 pub mod locations;
+#[cfg(feature = "rest-api-resources-mfg-batch")]
+pub mod mfg_batches;
 #[cfg(feature = "rest-api-resources-organization")]
 pub mod organizations;
 pub mod paging;
@@ -30,6 +36,8 @@ pub mod purchase_order;
 pub mod roles;
 #[cfg(feature = "rest-api-resources-schema")]
 pub mod schemas;
+#[cfg(feature = "rest-api-resources-sla")]
+pub mod sla;
 #[cfg(feature = "rest-api-resources-submit")]
 pub mod submit;
 #[cfg(feature = "rest-api-resources-track-and-trace")]
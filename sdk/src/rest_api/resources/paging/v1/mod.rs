@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use crate::paging;
-use std::cmp;
 use url::Url;
 
 /// Paging data for a REST API dataset, intended to be returned with REST response data
@@ -31,6 +30,9 @@ pub struct Paging {
     /// Total number of records
     total: i64,
 
+    /// Total number of pages of `limit` records
+    total_pages: i64,
+
     /// URL for the first page of records
     first: Url,
 
@@ -62,7 +64,7 @@ impl Paging {
                 .append_pair("service_id", service_id);
         }
 
-        let generator = PageUrlGenerator { url: base_url };
+        let generator = PageUrlGenerator::new(&base_url, &paging);
         let offsets = Offsets::new(&paging);
 
         Paging {
@@ -70,6 +72,7 @@ impl Paging {
             offset: paging.offset,
             limit: paging.limit,
             total: paging.total,
+            total_pages: paging.total_pages(),
             first: generator.url_with_offset(offsets.first),
             prev: offsets.prev.map(|v| generator.url_with_offset(v)),
             last: generator.url_with_offset(offsets.last),
@@ -96,48 +99,33 @@ struct Offsets {
 
 impl Offsets {
     fn new(paging: &paging::Paging) -> Self {
-        // The offset for the very last page
-        let last_offset = cmp::max(((paging.total - 1) / paging.limit) * paging.limit, 0);
-
         Offsets {
             first: 0,
-            prev: if paging.offset == 0 {
-                // There is no previous page if we're at the beginning
-                None
-            } else if paging.offset > paging.total {
-                // Default to the last page if we've passed the end of the list
-                Some(last_offset)
-            } else {
-                // Calculate the previous page normally using increments of the limit
-                Some(cmp::max(paging.offset - paging.limit, 0))
-            },
-            last: last_offset,
-            next: if paging.offset >= last_offset {
-                // There is no next page if we're on or further than the last page
-                None
-            } else {
-                Some(if paging.offset + paging.limit > last_offset {
-                    // Default to the last page if we're about to hit the end of the list
-                    last_offset
-                } else {
-                    // Calculate the next page normally using increments of the limit
-                    paging.offset + paging.limit
-                })
-            },
+            prev: paging.prev_offset(),
+            last: paging.last_offset(),
+            next: paging.next_offset(),
         }
     }
 }
 
 /// Utility to generate a URL at a given offset
-struct PageUrlGenerator {
+struct PageUrlGenerator<'a> {
     url: Url,
+    paging: &'a paging::Paging,
 }
 
-impl PageUrlGenerator {
-    fn url_with_offset<T: ToString>(&self, offset: T) -> Url {
+impl<'a> PageUrlGenerator<'a> {
+    fn new(base_url: &Url, paging: &'a paging::Paging) -> Self {
+        PageUrlGenerator {
+            url: base_url.clone(),
+            paging,
+        }
+    }
+
+    fn url_with_offset(&self, offset: i64) -> Url {
         let mut url = self.url.clone();
         url.query_pairs_mut()
-            .append_pair("offset", &offset.to_string());
+            .append_pair("offset", &self.paging.page_url("{offset}", offset));
         url
     }
 }
@@ -266,6 +254,7 @@ mod tests {
                 offset: 20,
                 limit: 10,
                 total: 80,
+                total_pages: 8,
                 first: Url::parse("http://base/?limit=10&service_id=fakeserviceid&offset=0")
                     .unwrap(),
                 prev: Some(
@@ -280,6 +269,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_paging_no_query_string() {
+        let paging = Paging::new(
+            Url::parse("http://base/").unwrap(),
+            paging::Paging {
+                offset: 0,
+                limit: 10,
+                total: 15,
+            },
+            None,
+        );
+
+        assert_eq!(
+            paging.current,
+            Url::parse("http://base/?limit=10&offset=0").unwrap()
+        );
+        assert_eq!(
+            paging.next,
+            Some(Url::parse("http://base/?limit=10&offset=10").unwrap())
+        );
+    }
+
     #[test]
     fn test_paging_query_params() {
         assert_eq!(
@@ -299,6 +310,7 @@ mod tests {
                 offset: 20,
                 limit: 10,
                 total: 80,
+                total_pages: 8,
                 first: Url::parse(
                     "http://base/?unrelated_filter=9&limit=10&service_id=fakeserviceid&offset=0"
                 ).unwrap(),
@@ -0,0 +1,111 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    mfg_batch::store::MfgBatchStore,
+    rest_api::resources::error::ErrorResponse,
+    sla::{list_pending_items, PendingItem, PendingKind, SlaThresholds},
+    track_and_trace::store::TrackAndTraceStore,
+};
+
+use super::payloads::{PendingItemSlice, SlaReportSlice, SlaSummarySlice};
+
+/// Reports the open proposals and active recalls, the longest pending first, with a summary of
+/// each kind. The summary covers every pending item, even when only the overdue are listed.
+pub fn get_sla_report(
+    track_and_trace_store: &dyn TrackAndTraceStore,
+    mfg_batch_store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    thresholds: &SlaThresholds,
+    overdue_only: bool,
+    now: i64,
+) -> Result<SlaReportSlice, ErrorResponse> {
+    let items = list_pending_items(
+        track_and_trace_store,
+        mfg_batch_store,
+        service_id,
+        thresholds,
+        now,
+    )
+    .map_err(|err| ErrorResponse::internal_error(Box::new(err)))?;
+
+    Ok(SlaReportSlice {
+        generated_at: now,
+        summary: summarize(&items, thresholds),
+        data: items
+            .into_iter()
+            .filter(|item| !overdue_only || item.is_overdue())
+            .map(PendingItemSlice::from)
+            .collect(),
+    })
+}
+
+fn summarize(items: &[PendingItem], thresholds: &SlaThresholds) -> Vec<SlaSummarySlice> {
+    [PendingKind::Proposal, PendingKind::Hold]
+        .iter()
+        .map(|kind| {
+            let items = items.iter().filter(|item| item.kind == *kind);
+            SlaSummarySlice {
+                kind: kind.to_string(),
+                threshold: thresholds.for_kind(*kind),
+                pending: items.clone().count(),
+                overdue: items.clone().filter(|item| item.is_overdue()).count(),
+                longest_pending_for: items.map(|item| item.pending_for).max().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that each kind is summarized, including a kind with nothing pending
+    #[test]
+    fn test_summarize() {
+        let thresholds = SlaThresholds {
+            proposal: 100,
+            hold: 1000,
+        };
+        let item = |pending_for| PendingItem {
+            kind: PendingKind::Hold,
+            subject_id: "lot-1".to_string(),
+            reference: "recall-1".to_string(),
+            pending_since: 0,
+            pending_for,
+            threshold: thresholds.hold,
+            service_id: None,
+        };
+
+        assert_eq!(
+            summarize(&[item(2000), item(500)], &thresholds),
+            vec![
+                SlaSummarySlice {
+                    kind: "proposal".to_string(),
+                    threshold: 100,
+                    pending: 0,
+                    overdue: 0,
+                    longest_pending_for: 0,
+                },
+                SlaSummarySlice {
+                    kind: "hold".to_string(),
+                    threshold: 1000,
+                    pending: 2,
+                    overdue: 1,
+                    longest_pending_for: 2000,
+                },
+            ]
+        );
+    }
+}
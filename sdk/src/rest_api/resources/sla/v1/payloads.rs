@@ -0,0 +1,61 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::sla::PendingItem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingItemSlice {
+    pub kind: String,
+    pub subject_id: String,
+    pub reference: String,
+    pub pending_since: i64,
+    pub pending_for: i64,
+    pub threshold: i64,
+    pub overdue: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+}
+
+impl From<PendingItem> for PendingItemSlice {
+    fn from(item: PendingItem) -> Self {
+        Self {
+            kind: item.kind.to_string(),
+            overdue: item.is_overdue(),
+            subject_id: item.subject_id,
+            reference: item.reference,
+            pending_since: item.pending_since,
+            pending_for: item.pending_for,
+            threshold: item.threshold,
+            service_id: item.service_id,
+        }
+    }
+}
+
+/// The pending and overdue items of one kind
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SlaSummarySlice {
+    pub kind: String,
+    pub threshold: i64,
+    pub pending: usize,
+    pub overdue: usize,
+    /// How long the longest pending item has been pending, or 0 if none are
+    pub longest_pending_for: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlaReportSlice {
+    pub generated_at: i64,
+    pub summary: Vec<SlaSummarySlice>,
+    pub data: Vec<PendingItemSlice>,
+}
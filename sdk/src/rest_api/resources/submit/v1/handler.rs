@@ -40,6 +40,7 @@ pub fn submit_batches<'a>(
     key_file_name: &str,
     store: Box<dyn BatchStore + 'a>,
     request: SubmitBatchRequest,
+    correlation_id: &str,
 ) -> Result<SubmitBatchResponse, ErrorResponse> {
     let private_key = load_key(key_file_name, &[PathBuf::from("/etc/grid/keys")])
         .map_err(|err| {
@@ -55,8 +56,9 @@ pub fn submit_batches<'a>(
 
     let mut ids = Vec::new();
 
-    for db_batch in db_batches {
+    for mut db_batch in db_batches {
         let id = db_batch.header_signature.clone();
+        db_batch.correlation_id = Some(correlation_id.to_string());
 
         store.add_batch(db_batch).map_err(|err| match err {
             BatchStoreError::ConstraintViolationError(err) => {
@@ -71,6 +73,7 @@ pub fn submit_batches<'a>(
             }
         })?;
 
+        info!("Stored batch {} [correlation id {}]", id, correlation_id);
         ids.push(id);
     }
 
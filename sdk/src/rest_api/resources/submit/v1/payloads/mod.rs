@@ -58,6 +58,18 @@ pub enum Payload {
     Product(ProductPayload),
     Location(LocationPayload),
     Schema(SchemaPayload),
+    /// A payload built by the daemon, such as from a data mapping, rather than submitted
+    #[cfg(feature = "mfg_batch")]
+    #[serde(skip)]
+    MfgBatch(crate::protocol::mfg_batch::payload::MfgBatchPayload),
+    /// A location payload built by the daemon, such as from a data mapping
+    #[cfg(feature = "location")]
+    #[serde(skip)]
+    MappedLocation(crate::protocol::location::payload::LocationPayload),
+    /// A product payload built by the daemon, such as from an ingested GDSN file
+    #[cfg(feature = "product")]
+    #[serde(skip)]
+    MappedProduct(crate::protocol::product::payload::ProductPayload),
 }
 
 impl IntoBytes for Payload {
@@ -67,6 +79,12 @@ impl IntoBytes for Payload {
             Payload::Product(payload) => payload.into_bytes(),
             Payload::Location(payload) => payload.into_bytes(),
             Payload::Schema(payload) => payload.into_bytes(),
+            #[cfg(feature = "mfg_batch")]
+            Payload::MfgBatch(payload) => payload.into_bytes(),
+            #[cfg(feature = "location")]
+            Payload::MappedLocation(payload) => payload.into_bytes(),
+            #[cfg(feature = "product")]
+            Payload::MappedProduct(payload) => payload.into_bytes(),
         }
     }
 }
@@ -0,0 +1,255 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Service-level timers for the items left waiting on someone: transfer proposals that are still
+//! open, and mfg_batches held by a recall that has not been closed out.
+//!
+//! An item has been pending since the proposal was issued or the batch was recalled. Each kind of
+//! item has a threshold, and an item pending for longer than its threshold is overdue. The
+//! daemon escalates overdue items to the registered webhooks, and serves the pending items as
+//! an SLA report.
+
+use std::fmt;
+
+use crate::error::InternalError;
+use crate::mfg_batch::store::{ListMfgBatchRecallFilters, MfgBatchStore};
+use crate::track_and_trace::store::TrackAndTraceStore;
+
+/// The event webhooks are notified of an overdue item with
+pub const ESCALATION_EVENT: &str = "sla.escalated";
+
+/// The status of a proposal that has been neither accepted, rejected nor canceled
+const OPEN_PROPOSAL_STATUS: &str = "Open";
+/// How many records or recalls are read from the stores at a time
+const PAGE_SIZE: i64 = 500;
+
+/// The kinds of item service-level timers are kept for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingKind {
+    /// A track-and-trace proposal that is still open
+    Proposal,
+    /// A mfg_batch held by an active recall
+    Hold,
+}
+
+impl PendingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PendingKind::Proposal => "proposal",
+            PendingKind::Hold => "hold",
+        }
+    }
+}
+
+impl fmt::Display for PendingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How long each kind of item may be pending before it is overdue, in seconds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlaThresholds {
+    pub proposal: i64,
+    pub hold: i64,
+}
+
+impl SlaThresholds {
+    pub fn for_kind(&self, kind: PendingKind) -> i64 {
+        match kind {
+            PendingKind::Proposal => self.proposal,
+            PendingKind::Hold => self.hold,
+        }
+    }
+}
+
+/// An item with a service-level timer running
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingItem {
+    pub kind: PendingKind,
+    /// The record a proposal is for, or the mfg_batch a recall holds
+    pub subject_id: String,
+    /// The agent a proposal is waiting on, or the ID of the recall holding the mfg_batch
+    pub reference: String,
+    /// When the proposal was issued or the mfg_batch recalled, in seconds since the epoch
+    pub pending_since: i64,
+    /// How long the item has been pending, in seconds
+    pub pending_for: i64,
+    /// How long the item may be pending before it is overdue, in seconds
+    pub threshold: i64,
+    pub service_id: Option<String>,
+}
+
+impl PendingItem {
+    fn new(
+        kind: PendingKind,
+        subject_id: String,
+        reference: String,
+        pending_since: i64,
+        service_id: Option<String>,
+        thresholds: &SlaThresholds,
+        now: i64,
+    ) -> Self {
+        Self {
+            kind,
+            subject_id,
+            reference,
+            pending_since,
+            pending_for: (now - pending_since).max(0),
+            threshold: thresholds.for_kind(kind),
+            service_id,
+        }
+    }
+
+    /// Whether the item has been pending for longer than its threshold
+    pub fn is_overdue(&self) -> bool {
+        self.pending_for > self.threshold
+    }
+
+    /// Identifies the item across runs of the timers. A proposal made again after it was
+    /// answered, or a batch recalled again, is a new item.
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.kind, self.subject_id, self.reference, self.pending_since
+        )
+    }
+}
+
+/// Lists the open proposals and active recalls, the longest pending first
+///
+/// # Arguments
+///
+///  * `track_and_trace_store` - The store to read the proposals from
+///  * `mfg_batch_store` - The store to read the recalls from
+///  * `service_id` - The service ID to list the items of
+///  * `thresholds` - How long each kind of item may be pending
+///  * `now` - The time to measure how long items have been pending to, in seconds since the epoch
+pub fn list_pending_items(
+    track_and_trace_store: &dyn TrackAndTraceStore,
+    mfg_batch_store: &dyn MfgBatchStore,
+    service_id: Option<&str>,
+    thresholds: &SlaThresholds,
+    now: i64,
+) -> Result<Vec<PendingItem>, InternalError> {
+    let mut items = vec![];
+
+    let mut offset = 0;
+    loop {
+        let records = track_and_trace_store
+            .list_records(service_id, offset, PAGE_SIZE)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+        if records.data.is_empty() {
+            break;
+        }
+        offset += records.data.len() as i64;
+
+        let record_ids = records
+            .data
+            .into_iter()
+            .map(|record| record.record_id)
+            .collect::<Vec<_>>();
+        let proposals = track_and_trace_store
+            .list_proposals(&record_ids, service_id)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+        items.extend(
+            proposals
+                .into_iter()
+                .filter(|proposal| proposal.status == OPEN_PROPOSAL_STATUS)
+                .map(|proposal| {
+                    PendingItem::new(
+                        PendingKind::Proposal,
+                        proposal.record_id,
+                        proposal.receiving_agent,
+                        proposal.timestamp,
+                        proposal.service_id,
+                        thresholds,
+                        now,
+                    )
+                }),
+        );
+
+        if offset >= records.paging.total {
+            break;
+        }
+    }
+
+    let filters = ListMfgBatchRecallFilters::default();
+    let mut offset = 0;
+    loop {
+        let recalls = mfg_batch_store
+            .list_mfg_batch_recalls(service_id, &filters, offset, PAGE_SIZE)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+        let page_len = recalls.len() as i64;
+        offset += page_len;
+
+        items.extend(recalls.into_iter().map(|recall| {
+            PendingItem::new(
+                PendingKind::Hold,
+                recall.mfg_batch_id,
+                recall.recall_id,
+                recall.recalled_at,
+                recall.service_id,
+                thresholds,
+                now,
+            )
+        }));
+
+        if page_len < PAGE_SIZE {
+            break;
+        }
+    }
+
+    items.sort_by(|a, b| b.pending_for.cmp(&a.pending_for));
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: SlaThresholds = SlaThresholds {
+        proposal: 3600,
+        hold: 86400,
+    };
+
+    /// Verify that an item is overdue only once it has been pending for longer than the threshold
+    /// of its kind, and that an item dated after `now` has been pending for no time
+    #[test]
+    fn test_pending_item_overdue() {
+        let item = |kind, pending_since| {
+            PendingItem::new(
+                kind,
+                "lot-1".to_string(),
+                "recall-1".to_string(),
+                pending_since,
+                None,
+                &THRESHOLDS,
+                100_000,
+            )
+        };
+
+        assert!(!item(PendingKind::Proposal, 100_000 - 3600).is_overdue());
+        assert!(item(PendingKind::Proposal, 100_000 - 3601).is_overdue());
+        assert!(!item(PendingKind::Hold, 100_000 - 3601).is_overdue());
+        assert!(item(PendingKind::Hold, 100_000 - 86401).is_overdue());
+        assert_eq!(item(PendingKind::Hold, 100_001).pending_for, 0);
+        assert_eq!(
+            item(PendingKind::Hold, 100).key(),
+            "hold:lot-1:recall-1:100"
+        );
+    }
+}
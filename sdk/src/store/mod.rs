@@ -22,14 +22,22 @@ use std::str::FromStr;
 #[cfg(feature = "diesel")]
 use diesel::r2d2::{ConnectionManager, Pool};
 
+#[cfg(feature = "api-keys")]
+use crate::api_keys::store::ApiKeyStore;
 #[cfg(feature = "batch-store")]
 use crate::batches::store::BatchStore;
 use crate::commits::store::CommitStore;
 use crate::error::InternalError;
+#[cfg(feature = "event-queue")]
+use crate::event_queue::store::EventQueueStore;
+#[cfg(feature = "ingestion")]
+use crate::ingestion::store::IngestionLogStore;
 #[cfg(feature = "location")]
 use crate::location::store::LocationStore;
 #[cfg(feature = "pike")]
 use crate::pike::store::PikeStore;
+#[cfg(feature = "product-gdsn-publication")]
+use crate::product::gdsn::publication::store::GdsnPublicationStore;
 #[cfg(feature = "product")]
 use crate::product::store::ProductStore;
 #[cfg(feature = "purchase-order")]
@@ -38,6 +46,8 @@ use crate::purchase_order::store::PurchaseOrderStore;
 use crate::schema::store::SchemaStore;
 #[cfg(feature = "track-and-trace")]
 use crate::track_and_trace::store::TrackAndTraceStore;
+#[cfg(feature = "webhooks")]
+use crate::webhooks::store::WebhookStore;
 
 /// An abstract factory for creating Grid stores backed by the same storage
 pub trait StoreFactory {
@@ -62,6 +72,21 @@ pub trait StoreFactory {
     fn get_batch_store<'a>(&'a self) -> Box<dyn BatchStore + 'a>;
     #[cfg(feature = "purchase-order")]
     fn get_grid_purchase_order_store<'a>(&'a self) -> Box<dyn PurchaseOrderStore + 'a>;
+    /// Get a new `ApiKeyStore`
+    #[cfg(feature = "api-keys")]
+    fn get_api_key_store<'a>(&'a self) -> Box<dyn ApiKeyStore + 'a>;
+    /// Get a new `IngestionLogStore`
+    #[cfg(feature = "ingestion")]
+    fn get_ingestion_log_store<'a>(&'a self) -> Box<dyn IngestionLogStore + 'a>;
+    /// Get a new `WebhookStore`
+    #[cfg(feature = "webhooks")]
+    fn get_webhook_store<'a>(&'a self) -> Box<dyn WebhookStore + 'a>;
+    /// Get a new `GdsnPublicationStore`
+    #[cfg(feature = "product-gdsn-publication")]
+    fn get_gdsn_publication_store<'a>(&'a self) -> Box<dyn GdsnPublicationStore + 'a>;
+    /// Get a new `EventQueueStore`
+    #[cfg(feature = "event-queue")]
+    fn get_event_queue_store<'a>(&'a self) -> Box<dyn EventQueueStore + 'a>;
 }
 
 pub trait TransactionalStoreFactory: StoreFactory + Send + Sync {
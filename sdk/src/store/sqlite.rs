@@ -19,14 +19,28 @@ use diesel::{
     Connection,
 };
 
+#[cfg(feature = "api-keys")]
+use crate::api_keys::store::{ApiKeyStore, DieselApiKeyStore, DieselConnectionApiKeyStore};
 #[cfg(feature = "batch-store")]
 use crate::batches::store::{BatchStore, DieselBatchStore, DieselConnectionBatchStore};
 use crate::commits::store::{CommitStore, DieselCommitStore, DieselConnectionCommitStore};
 use crate::error::InternalError;
+#[cfg(feature = "event-queue")]
+use crate::event_queue::store::{
+    DieselConnectionEventQueueStore, DieselEventQueueStore, EventQueueStore,
+};
+#[cfg(feature = "ingestion")]
+use crate::ingestion::store::{
+    DieselConnectionIngestionLogStore, DieselIngestionLogStore, IngestionLogStore,
+};
 #[cfg(feature = "location")]
 use crate::location::store::{DieselConnectionLocationStore, DieselLocationStore, LocationStore};
 #[cfg(feature = "pike")]
 use crate::pike::store::{DieselConnectionPikeStore, DieselPikeStore, PikeStore};
+#[cfg(feature = "product-gdsn-publication")]
+use crate::product::gdsn::publication::store::{
+    DieselConnectionGdsnPublicationStore, DieselGdsnPublicationStore, GdsnPublicationStore,
+};
 #[cfg(feature = "product")]
 use crate::product::store::{DieselConnectionProductStore, DieselProductStore, ProductStore};
 #[cfg(feature = "purchase-order")]
@@ -39,6 +53,8 @@ use crate::schema::store::{DieselConnectionSchemaStore, DieselSchemaStore, Schem
 use crate::track_and_trace::store::{
     DieselConnectionTrackAndTraceStore, DieselTrackAndTraceStore, TrackAndTraceStore,
 };
+#[cfg(feature = "webhooks")]
+use crate::webhooks::store::{DieselConnectionWebhookStore, DieselWebhookStore, WebhookStore};
 
 use super::{InContextStoreFactory, StoreFactory, TransactionalStoreFactory};
 
@@ -93,6 +109,31 @@ impl StoreFactory for SqliteStoreFactory {
     fn get_grid_purchase_order_store<'a>(&'a self) -> Box<dyn PurchaseOrderStore + 'a> {
         Box::new(DieselPurchaseOrderStore::new(self.pool.clone()))
     }
+
+    #[cfg(feature = "api-keys")]
+    fn get_api_key_store<'a>(&'a self) -> Box<dyn ApiKeyStore + 'a> {
+        Box::new(DieselApiKeyStore::new(self.pool.clone()))
+    }
+
+    #[cfg(feature = "ingestion")]
+    fn get_ingestion_log_store<'a>(&'a self) -> Box<dyn IngestionLogStore + 'a> {
+        Box::new(DieselIngestionLogStore::new(self.pool.clone()))
+    }
+
+    #[cfg(feature = "webhooks")]
+    fn get_webhook_store<'a>(&'a self) -> Box<dyn WebhookStore + 'a> {
+        Box::new(DieselWebhookStore::new(self.pool.clone()))
+    }
+
+    #[cfg(feature = "product-gdsn-publication")]
+    fn get_gdsn_publication_store<'a>(&'a self) -> Box<dyn GdsnPublicationStore + 'a> {
+        Box::new(DieselGdsnPublicationStore::new(self.pool.clone()))
+    }
+
+    #[cfg(feature = "event-queue")]
+    fn get_event_queue_store<'a>(&'a self) -> Box<dyn EventQueueStore + 'a> {
+        Box::new(DieselEventQueueStore::new(self.pool.clone()))
+    }
 }
 
 impl TransactionalStoreFactory for SqliteStoreFactory {
@@ -165,6 +206,31 @@ impl StoreFactory for InContextSqliteStoreFactory {
     fn get_grid_purchase_order_store<'a>(&'a self) -> Box<dyn PurchaseOrderStore + 'a> {
         Box::new(DieselConnectionPurchaseOrderStore::new(&*self.conn))
     }
+
+    #[cfg(feature = "api-keys")]
+    fn get_api_key_store<'a>(&'a self) -> Box<dyn ApiKeyStore + 'a> {
+        Box::new(DieselConnectionApiKeyStore::new(&*self.conn))
+    }
+
+    #[cfg(feature = "ingestion")]
+    fn get_ingestion_log_store<'a>(&'a self) -> Box<dyn IngestionLogStore + 'a> {
+        Box::new(DieselConnectionIngestionLogStore::new(&*self.conn))
+    }
+
+    #[cfg(feature = "webhooks")]
+    fn get_webhook_store<'a>(&'a self) -> Box<dyn WebhookStore + 'a> {
+        Box::new(DieselConnectionWebhookStore::new(&*self.conn))
+    }
+
+    #[cfg(feature = "product-gdsn-publication")]
+    fn get_gdsn_publication_store<'a>(&'a self) -> Box<dyn GdsnPublicationStore + 'a> {
+        Box::new(DieselConnectionGdsnPublicationStore::new(&*self.conn))
+    }
+
+    #[cfg(feature = "event-queue")]
+    fn get_event_queue_store<'a>(&'a self) -> Box<dyn EventQueueStore + 'a> {
+        Box::new(DieselConnectionEventQueueStore::new(&*self.conn))
+    }
 }
 
 impl<'a> InContextStoreFactory<'a> for InContextSqliteStoreFactory {
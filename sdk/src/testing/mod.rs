@@ -0,0 +1,437 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for testing transaction handlers without a validator.
+//!
+//! `MockTransactionContext` keeps state in memory and records the receipts and events a handler
+//! adds. The functions in this module build the Pike, schema and location state handlers
+//! commonly read, which can be added to a context with its `add_*` methods:
+//!
+//! ```
+//! use grid_sdk::testing::{agent, organization, role, MockTransactionContext};
+//!
+//! let context = MockTransactionContext::new();
+//! context.add_organization(organization("acme", &[("gs1_company_prefix", "6889")]));
+//! context.add_role(role("acme", "admin", &["mfg_batch::can-create-mfg-batch"]));
+//! context.add_agent(agent("acme", "agent_public_key", &["admin"]));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use sawtooth_sdk::processor::handler::{ContextError, TransactionContext};
+
+#[cfg(feature = "location")]
+use crate::location::addressing::compute_gs1_location_address;
+use crate::pike::addressing::{
+    compute_agent_address, compute_organization_address, compute_role_address,
+};
+#[cfg(feature = "location")]
+use crate::protocol::location::state::{
+    Location, LocationBuilder, LocationList, LocationListBuilder, LocationNamespace,
+};
+use crate::protocol::pike::state::{
+    Agent, AgentBuilder, AgentList, AgentListBuilder, AlternateIdBuilder, Organization,
+    OrganizationBuilder, OrganizationList, OrganizationListBuilder, Role, RoleBuilder, RoleList,
+    RoleListBuilder,
+};
+use crate::protocol::schema::state::{
+    DataType, PropertyDefinition, PropertyDefinitionBuilder, Schema, SchemaBuilder, SchemaList,
+    SchemaListBuilder,
+};
+use crate::protos::{FromBytes, IntoBytes};
+use crate::schema::addressing::compute_schema_address;
+
+/// An event added by a transaction handler
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockEvent {
+    pub event_type: String,
+    pub attributes: Vec<(String, String)>,
+    pub data: Vec<u8>,
+}
+
+/// A `TransactionContext` that keeps state in memory
+#[derive(Debug, Default)]
+pub struct MockTransactionContext {
+    state: RefCell<HashMap<String, Vec<u8>>>,
+    receipts: RefCell<Vec<Vec<u8>>>,
+    events: RefCell<Vec<MockEvent>>,
+}
+
+impl MockTransactionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bytes set at an address, if any
+    pub fn state_entry(&self, address: &str) -> Option<Vec<u8>> {
+        self.state.borrow().get(address).cloned()
+    }
+
+    /// Returns the receipt data added so far, in order
+    pub fn receipts(&self) -> Vec<Vec<u8>> {
+        self.receipts.borrow().clone()
+    }
+
+    /// Returns the events added so far, in order
+    pub fn events(&self) -> Vec<MockEvent> {
+        self.events.borrow().clone()
+    }
+
+    /// Adds an agent to the agent list at its address
+    ///
+    /// # Panics
+    ///
+    /// If the list already at the address cannot be read, or the new list cannot be built
+    pub fn add_agent(&self, agent: Agent) {
+        let address = compute_agent_address(agent.public_key());
+        let mut agents = self
+            .read_list::<AgentList>(&address)
+            .map(|list| list.agents().to_vec())
+            .unwrap_or_default();
+        agents.retain(|existing| existing.public_key() != agent.public_key());
+        agents.push(agent);
+
+        let list = AgentListBuilder::new()
+            .with_agents(agents)
+            .build()
+            .expect("Failed to build agent list");
+        self.write_list(address, list);
+    }
+
+    /// Adds an organization to the organization list at its address
+    ///
+    /// # Panics
+    ///
+    /// If the list already at the address cannot be read, or the new list cannot be built
+    pub fn add_organization(&self, organization: Organization) {
+        let address = compute_organization_address(organization.org_id());
+        let mut organizations = self
+            .read_list::<OrganizationList>(&address)
+            .map(|list| list.organizations().to_vec())
+            .unwrap_or_default();
+        organizations.retain(|existing| existing.org_id() != organization.org_id());
+        organizations.push(organization);
+
+        let list = OrganizationListBuilder::new()
+            .with_organizations(organizations)
+            .build()
+            .expect("Failed to build organization list");
+        self.write_list(address, list);
+    }
+
+    /// Adds a role to the role list at its address
+    ///
+    /// # Panics
+    ///
+    /// If the list already at the address cannot be read, or the new list cannot be built
+    pub fn add_role(&self, role: Role) {
+        let address = compute_role_address(role.name(), role.org_id());
+        let mut roles = self
+            .read_list::<RoleList>(&address)
+            .map(|list| list.roles().to_vec())
+            .unwrap_or_default();
+        roles.retain(|existing| {
+            existing.name() != role.name() || existing.org_id() != role.org_id()
+        });
+        roles.push(role);
+
+        let list = RoleListBuilder::new()
+            .with_roles(roles)
+            .build()
+            .expect("Failed to build role list");
+        self.write_list(address, list);
+    }
+
+    /// Adds a schema to the schema list at its address
+    ///
+    /// # Panics
+    ///
+    /// If the list already at the address cannot be read, or the new list cannot be built
+    pub fn add_schema(&self, schema: Schema) {
+        let address = compute_schema_address(schema.name());
+        let mut schemas = self
+            .read_list::<SchemaList>(&address)
+            .map(|list| list.schemas().to_vec())
+            .unwrap_or_default();
+        schemas.retain(|existing| existing.name() != schema.name());
+        schemas.push(schema);
+
+        let list = SchemaListBuilder::new()
+            .with_schemas(schemas)
+            .build()
+            .expect("Failed to build schema list");
+        self.write_list(address, list);
+    }
+
+    /// Adds a GS1 location to the location list at its address
+    ///
+    /// # Panics
+    ///
+    /// If the list already at the address cannot be read, or the new list cannot be built
+    #[cfg(feature = "location")]
+    pub fn add_location(&self, location: Location) {
+        let address = compute_gs1_location_address(location.location_id());
+        let mut locations = self
+            .read_list::<LocationList>(&address)
+            .map(|list| list.locations().to_vec())
+            .unwrap_or_default();
+        locations.retain(|existing| existing.location_id() != location.location_id());
+        locations.push(location);
+
+        let list = LocationListBuilder::new()
+            .with_locations(locations)
+            .build()
+            .expect("Failed to build location list");
+        self.write_list(address, list);
+    }
+
+    fn read_list<T: FromBytes<T>>(&self, address: &str) -> Option<T> {
+        self.state_entry(address)
+            .map(|bytes| T::from_bytes(&bytes).expect("Failed to read list in state"))
+    }
+
+    fn write_list<T: IntoBytes>(&self, address: String, list: T) {
+        self.state.borrow_mut().insert(
+            address,
+            list.into_bytes().expect("Failed to serialize list"),
+        );
+    }
+}
+
+impl TransactionContext for MockTransactionContext {
+    fn get_state_entries(
+        &self,
+        addresses: &[String],
+    ) -> Result<Vec<(String, Vec<u8>)>, ContextError> {
+        let state = self.state.borrow();
+        Ok(addresses
+            .iter()
+            .filter_map(|address| {
+                state
+                    .get(address)
+                    .map(|data| (address.to_string(), data.clone()))
+            })
+            .collect())
+    }
+
+    fn set_state_entries(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), ContextError> {
+        self.state.borrow_mut().extend(entries);
+        Ok(())
+    }
+
+    fn delete_state_entries(&self, addresses: &[String]) -> Result<Vec<String>, ContextError> {
+        let mut state = self.state.borrow_mut();
+        Ok(addresses
+            .iter()
+            .filter(|address| state.remove(*address).is_some())
+            .cloned()
+            .collect())
+    }
+
+    fn add_receipt_data(&self, data: &[u8]) -> Result<(), ContextError> {
+        self.receipts.borrow_mut().push(data.to_vec());
+        Ok(())
+    }
+
+    fn add_event(
+        &self,
+        event_type: String,
+        attributes: Vec<(String, String)>,
+        data: &[u8],
+    ) -> Result<(), ContextError> {
+        self.events.borrow_mut().push(MockEvent {
+            event_type,
+            attributes,
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+}
+
+/// Builds an active agent with the given roles
+pub fn agent(org_id: &str, public_key: &str, roles: &[&str]) -> Agent {
+    AgentBuilder::new()
+        .with_org_id(org_id.to_string())
+        .with_public_key(public_key.to_string())
+        .with_active(true)
+        .with_roles(roles.iter().map(ToString::to_string).collect())
+        .build()
+        .expect("Failed to build agent")
+}
+
+/// Builds an organization with the given alternate IDs, as `(id_type, id)` pairs
+pub fn organization(org_id: &str, alternate_ids: &[(&str, &str)]) -> Organization {
+    let alternate_ids = alternate_ids
+        .iter()
+        .map(|(id_type, id)| {
+            AlternateIdBuilder::new()
+                .with_id_type(id_type.to_string())
+                .with_id(id.to_string())
+                .build()
+                .expect("Failed to build alternate ID")
+        })
+        .collect();
+
+    OrganizationBuilder::new()
+        .with_org_id(org_id.to_string())
+        .with_name(format!("{} name", org_id))
+        .with_alternate_ids(alternate_ids)
+        .build()
+        .expect("Failed to build organization")
+}
+
+/// Builds a role with the given permissions, such as `mfg_batch::can-create-mfg-batch`
+pub fn role(org_id: &str, name: &str, permissions: &[&str]) -> Role {
+    RoleBuilder::new()
+        .with_org_id(org_id.to_string())
+        .with_name(name.to_string())
+        .with_description(format!("{} description", name))
+        .with_permissions(permissions.iter().map(ToString::to_string).collect())
+        .build()
+        .expect("Failed to build role")
+}
+
+/// Builds a property definition. Numbers have an exponent of 0; use
+/// `PropertyDefinitionBuilder` directly for other exponents, enum options or struct properties.
+pub fn property_definition(name: &str, data_type: DataType, required: bool) -> PropertyDefinition {
+    let builder = match data_type {
+        DataType::Number => PropertyDefinitionBuilder::new().with_number_exponent(0),
+        _ => PropertyDefinitionBuilder::new(),
+    };
+
+    builder
+        .with_name(name.to_string())
+        .with_data_type(data_type)
+        .with_required(required)
+        .with_description(format!("{} description", name))
+        .build()
+        .expect("Failed to build property definition")
+}
+
+/// Builds a GS1 location, identified by its GLN, without properties
+#[cfg(feature = "location")]
+pub fn location(gln: &str, owner: &str) -> Location {
+    LocationBuilder::new()
+        .with_location_id(gln.to_string())
+        .with_namespace(LocationNamespace::Gs1)
+        .with_owner(owner.to_string())
+        .with_properties(vec![])
+        .build()
+        .expect("Failed to build location")
+}
+
+/// Builds a schema with the given properties
+pub fn schema(name: &str, owner: &str, properties: Vec<PropertyDefinition>) -> Schema {
+    SchemaBuilder::new()
+        .with_name(name.to_string())
+        .with_description(format!("{} description", name))
+        .with_owner(owner.to_string())
+        .with_properties(properties)
+        .build()
+        .expect("Failed to build schema")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pike::permissions::PermissionChecker;
+
+    /// Verify that the Pike state added to a context is what the permission checker reads
+    #[test]
+    fn test_permission_check() {
+        let context = MockTransactionContext::new();
+        context.add_organization(organization("acme", &[]));
+        context.add_role(role("acme", "admin", &["mfg_batch::can-create-mfg-batch"]));
+        context.add_agent(agent("acme", "alpha", &["admin"]));
+        context.add_agent(agent("acme", "beta", &[]));
+
+        let checker = PermissionChecker::new(&context);
+        assert!(checker
+            .has_permission("alpha", "mfg_batch::can-create-mfg-batch", "acme")
+            .expect("Failed to check permission"));
+        assert!(!checker
+            .has_permission("beta", "mfg_batch::can-create-mfg-batch", "acme")
+            .expect("Failed to check permission"));
+        assert!(checker
+            .has_permission("gamma", "mfg_batch::can-create-mfg-batch", "acme")
+            .is_err());
+    }
+
+    /// Verify that state can be read, replaced and deleted, and that receipts and events are
+    /// recorded
+    #[test]
+    fn test_context() {
+        let context = MockTransactionContext::new();
+        context
+            .set_state_entry("address".to_string(), vec![1])
+            .expect("Failed to set state");
+        context
+            .set_state_entry("address".to_string(), vec![2])
+            .expect("Failed to set state");
+        assert_eq!(
+            context
+                .get_state_entry("address")
+                .expect("Failed to get state"),
+            Some(vec![2])
+        );
+        assert_eq!(
+            context
+                .get_state_entry("missing")
+                .expect("Failed to get state"),
+            None
+        );
+
+        assert_eq!(
+            context
+                .delete_state_entries(&["address".to_string(), "missing".to_string()])
+                .expect("Failed to delete state"),
+            vec!["address".to_string()]
+        );
+        assert_eq!(context.state_entry("address"), None);
+
+        context
+            .add_receipt_data(b"receipt")
+            .expect("Failed to add receipt");
+        context
+            .add_event("grid/test".to_string(), vec![], b"event")
+            .expect("Failed to add event");
+        assert_eq!(context.receipts(), vec![b"receipt".to_vec()]);
+        assert_eq!(context.events()[0].event_type, "grid/test");
+    }
+
+    /// Verify that adding a schema with the name of one in state replaces it
+    #[test]
+    fn test_add_schema() {
+        let context = MockTransactionContext::new();
+        context.add_schema(schema(
+            "gs1_mfg_batch",
+            "acme",
+            vec![property_definition("lot", DataType::String, true)],
+        ));
+        context.add_schema(schema(
+            "gs1_mfg_batch",
+            "acme",
+            vec![property_definition("weight", DataType::Number, false)],
+        ));
+
+        let bytes = context
+            .state_entry(&compute_schema_address("gs1_mfg_batch"))
+            .expect("No schema list in state");
+        let list = SchemaList::from_bytes(&bytes).expect("Failed to read schema list");
+        assert_eq!(list.schemas().len(), 1);
+        assert_eq!(list.schemas()[0].properties()[0].name(), "weight");
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Webhooks let systems outside the daemon, such as an ERP, react to mfg_batch changes without
+//! polling. Each webhook is a URL the daemon posts a JSON notification to whenever a commit
+//! creates, updates or deletes a mfg_batch.
+//!
+//! Notifications are signed with the webhook's secret: the `X-Grid-Signature` header carries
+//! `sha256=` followed by the hex HMAC-SHA256 of the request body, so a receiver can check that
+//! a notification came from the daemon.
+
+pub mod store;
+
+use std::fmt;
+
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+
+use crate::hex::to_hex;
+
+/// The header carrying the notification's `MfgBatchEvent`
+pub const EVENT_HEADER: &str = "X-Grid-Event";
+/// The header carrying the signature of the notification's body
+pub const SIGNATURE_HEADER: &str = "X-Grid-Signature";
+
+/// A change to a mfg_batch that webhooks are notified of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MfgBatchEvent {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl MfgBatchEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MfgBatchEvent::Created => "mfg_batch.created",
+            MfgBatchEvent::Updated => "mfg_batch.updated",
+            MfgBatchEvent::Deleted => "mfg_batch.deleted",
+        }
+    }
+}
+
+impl fmt::Display for MfgBatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns the value of the `X-Grid-Signature` header for a notification body
+///
+/// # Arguments
+///
+///  * `secret` - The secret of the webhook the notification is sent to
+///  * `body` - The body of the notification
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut hmac = Hmac::new(Sha256::new(), secret.as_bytes());
+    hmac.input(body);
+    format!("sha256={}", to_hex(hmac.result().code()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the signature is the hex HMAC-SHA256 of the body, keyed by the secret
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod models;
+mod operations;
+pub(crate) mod schema;
+
+use diesel::connection::AnsiTransactionManager;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{Webhook, WebhookStore, WebhookStoreError};
+use crate::error::ResourceTemporarilyUnavailableError;
+
+use operations::add_webhook::AddWebhookOperation as _;
+use operations::list_seen_mfg_batch_ids::ListSeenMfgBatchIdsOperation as _;
+use operations::list_webhooks::ListWebhooksOperation as _;
+use operations::remove_webhook::RemoveWebhookOperation as _;
+use operations::set_seen_mfg_batch_ids::SetSeenMfgBatchIdsOperation as _;
+use operations::WebhookStoreOperations;
+
+#[derive(Clone)]
+pub struct DieselWebhookStore<C: diesel::Connection + 'static> {
+    connection_pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection> DieselWebhookStore<C> {
+    pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselWebhookStore { connection_pool }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl WebhookStore for DieselWebhookStore<diesel::pg::PgConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_webhook(webhook)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_webhooks()
+    }
+
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_webhook(webhook_id)
+    }
+
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_seen_mfg_batch_ids(address, service_id)
+    }
+
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .set_seen_mfg_batch_ids(address, service_id, mfg_batch_ids)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl WebhookStore for DieselWebhookStore<diesel::sqlite::SqliteConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .add_webhook(webhook)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_webhooks()
+    }
+
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .remove_webhook(webhook_id)
+    }
+
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .list_seen_mfg_batch_ids(address, service_id)
+    }
+
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            WebhookStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .set_seen_mfg_batch_ids(address, service_id, mfg_batch_ids)
+    }
+}
+
+pub struct DieselConnectionWebhookStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+}
+
+impl<'a, C> DieselConnectionWebhookStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionWebhookStore { connection }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> WebhookStore for DieselConnectionWebhookStore<'a, diesel::pg::PgConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).add_webhook(webhook)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).list_webhooks()
+    }
+
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).remove_webhook(webhook_id)
+    }
+
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).list_seen_mfg_batch_ids(address, service_id)
+    }
+
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).set_seen_mfg_batch_ids(
+            address,
+            service_id,
+            mfg_batch_ids,
+        )
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> WebhookStore for DieselConnectionWebhookStore<'a, diesel::sqlite::SqliteConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).add_webhook(webhook)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).list_webhooks()
+    }
+
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).remove_webhook(webhook_id)
+    }
+
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).list_seen_mfg_batch_ids(address, service_id)
+    }
+
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        WebhookStoreOperations::new(self.connection).set_seen_mfg_batch_ids(
+            address,
+            service_id,
+            mfg_batch_ids,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+    use diesel::Connection;
+
+    use crate::migrations::run_sqlite_migrations;
+
+    fn webhook(webhook_id: &str, service_id: Option<&str>, created_at: i64) -> Webhook {
+        Webhook {
+            webhook_id: webhook_id.to_string(),
+            url: format!("https://erp.example.com/hooks/{}", webhook_id),
+            secret: "secret".to_string(),
+            service_id: service_id.map(String::from),
+            created_at,
+        }
+    }
+
+    /// Verify that webhooks are listed oldest first, and that removing one that does not exist
+    /// is an error
+    #[test]
+    fn test_add_list_remove_webhooks() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionWebhookStore::new(&conn);
+
+        store.add_webhook(webhook("b", None, 20)).unwrap();
+        store
+            .add_webhook(webhook("a", Some("01234-ABCD"), 10))
+            .unwrap();
+        assert_eq!(
+            store.list_webhooks().unwrap(),
+            vec![webhook("a", Some("01234-ABCD"), 10), webhook("b", None, 20)]
+        );
+
+        store.remove_webhook("a").unwrap();
+        assert_eq!(store.list_webhooks().unwrap(), vec![webhook("b", None, 20)]);
+        assert!(matches!(
+            store.remove_webhook("a"),
+            Err(WebhookStoreError::NotFoundError(_))
+        ));
+    }
+
+    /// Verify that the mfg_batch ids seen at an address are replaced, and are kept apart by
+    /// service
+    #[test]
+    fn test_seen_mfg_batch_ids() {
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to connect");
+        run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        let store = DieselConnectionWebhookStore::new(&conn);
+
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        store
+            .set_seen_mfg_batch_ids("11bb0e01", None, &ids(&["b", "a"]))
+            .unwrap();
+        store
+            .set_seen_mfg_batch_ids("11bb0e01", Some("01234-ABCD"), &ids(&["c"]))
+            .unwrap();
+        assert_eq!(
+            store.list_seen_mfg_batch_ids("11bb0e01", None).unwrap(),
+            ids(&["a", "b"])
+        );
+
+        store
+            .set_seen_mfg_batch_ids("11bb0e01", None, &ids(&["a"]))
+            .unwrap();
+        assert_eq!(
+            store.list_seen_mfg_batch_ids("11bb0e01", None).unwrap(),
+            ids(&["a"])
+        );
+
+        store.set_seen_mfg_batch_ids("11bb0e01", None, &[]).unwrap();
+        assert!(store
+            .list_seen_mfg_batch_ids("11bb0e01", None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store
+                .list_seen_mfg_batch_ids("11bb0e01", Some("01234-ABCD"))
+                .unwrap(),
+            ids(&["c"])
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::webhooks::store::{diesel::schema::*, Webhook};
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "webhooks"]
+pub struct WebhookModel {
+    pub webhook_id: String,
+    pub url: String,
+    pub secret: String,
+    pub service_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "webhook_seen_mfg_batches"]
+pub struct NewSeenMfgBatchModel {
+    pub address: String,
+    pub service_id: Option<String>,
+    pub mfg_batch_id: String,
+}
+
+impl From<Webhook> for WebhookModel {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            webhook_id: webhook.webhook_id,
+            url: webhook.url,
+            secret: webhook.secret,
+            service_id: webhook.service_id,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+impl From<WebhookModel> for Webhook {
+    fn from(model: WebhookModel) -> Self {
+        Self {
+            webhook_id: model.webhook_id,
+            url: model.url,
+            secret: model.secret,
+            service_id: model.service_id,
+            created_at: model.created_at,
+        }
+    }
+}
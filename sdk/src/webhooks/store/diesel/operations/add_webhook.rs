@@ -0,0 +1,48 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WebhookStoreOperations;
+
+use crate::webhooks::store::{
+    diesel::{models::WebhookModel, schema::webhooks},
+    Webhook, WebhookStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::webhooks::store::diesel) trait AddWebhookOperation {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AddWebhookOperation for WebhookStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        insert_into(webhooks::table)
+            .values(WebhookModel::from(webhook))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(WebhookStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AddWebhookOperation for WebhookStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        insert_into(webhooks::table)
+            .values(WebhookModel::from(webhook))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(WebhookStoreError::from)
+    }
+}
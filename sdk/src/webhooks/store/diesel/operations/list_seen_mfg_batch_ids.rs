@@ -0,0 +1,79 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WebhookStoreOperations;
+
+use crate::webhooks::store::{diesel::schema::webhook_seen_mfg_batches, WebhookStoreError};
+
+use diesel::prelude::*;
+
+pub(in crate::webhooks::store::diesel) trait ListSeenMfgBatchIdsOperation {
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListSeenMfgBatchIdsOperation for WebhookStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        let mut query = webhook_seen_mfg_batches::table
+            .into_boxed()
+            .select(webhook_seen_mfg_batches::mfg_batch_id)
+            .filter(webhook_seen_mfg_batches::address.eq(address))
+            .order(webhook_seen_mfg_batches::mfg_batch_id.asc());
+
+        if let Some(service_id) = service_id {
+            query = query.filter(webhook_seen_mfg_batches::service_id.eq(service_id));
+        } else {
+            query = query.filter(webhook_seen_mfg_batches::service_id.is_null());
+        }
+
+        query
+            .load::<String>(self.conn)
+            .map_err(WebhookStoreError::from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListSeenMfgBatchIdsOperation
+    for WebhookStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        let mut query = webhook_seen_mfg_batches::table
+            .into_boxed()
+            .select(webhook_seen_mfg_batches::mfg_batch_id)
+            .filter(webhook_seen_mfg_batches::address.eq(address))
+            .order(webhook_seen_mfg_batches::mfg_batch_id.asc());
+
+        if let Some(service_id) = service_id {
+            query = query.filter(webhook_seen_mfg_batches::service_id.eq(service_id));
+        } else {
+            query = query.filter(webhook_seen_mfg_batches::service_id.is_null());
+        }
+
+        query
+            .load::<String>(self.conn)
+            .map_err(WebhookStoreError::from)
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WebhookStoreOperations;
+
+use crate::webhooks::store::{
+    diesel::{models::WebhookModel, schema::webhooks},
+    Webhook, WebhookStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::webhooks::store::diesel) trait ListWebhooksOperation {
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> ListWebhooksOperation for WebhookStoreOperations<'a, diesel::pg::PgConnection> {
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        Ok(webhooks::table
+            .order((webhooks::created_at.asc(), webhooks::webhook_id.asc()))
+            .load::<WebhookModel>(self.conn)?
+            .into_iter()
+            .map(Webhook::from)
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> ListWebhooksOperation for WebhookStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        Ok(webhooks::table
+            .order((webhooks::created_at.asc(), webhooks::webhook_id.asc()))
+            .load::<WebhookModel>(self.conn)?
+            .into_iter()
+            .map(Webhook::from)
+            .collect())
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WebhookStoreOperations;
+
+use crate::webhooks::store::{diesel::schema::webhooks, WebhookStoreError};
+
+use diesel::{dsl::delete, prelude::*};
+
+pub(in crate::webhooks::store::diesel) trait RemoveWebhookOperation {
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> RemoveWebhookOperation for WebhookStoreOperations<'a, diesel::pg::PgConnection> {
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        match delete(webhooks::table.find(webhook_id)).execute(self.conn)? {
+            0 => Err(WebhookStoreError::NotFoundError(webhook_id.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> RemoveWebhookOperation for WebhookStoreOperations<'a, diesel::sqlite::SqliteConnection> {
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        match delete(webhooks::table.find(webhook_id)).execute(self.conn)? {
+            0 => Err(WebhookStoreError::NotFoundError(webhook_id.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
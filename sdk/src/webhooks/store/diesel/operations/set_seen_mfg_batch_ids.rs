@@ -0,0 +1,118 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::WebhookStoreOperations;
+
+use crate::webhooks::store::{
+    diesel::{models::NewSeenMfgBatchModel, schema::webhook_seen_mfg_batches},
+    WebhookStoreError,
+};
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::webhooks::store::diesel) trait SetSeenMfgBatchIdsOperation {
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> SetSeenMfgBatchIdsOperation for WebhookStoreOperations<'a, diesel::pg::PgConnection> {
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        self.conn.transaction::<_, WebhookStoreError, _>(|| {
+            let seen = webhook_seen_mfg_batches::table
+                .filter(webhook_seen_mfg_batches::address.eq(address));
+            match service_id {
+                Some(service_id) => {
+                    delete(seen.filter(webhook_seen_mfg_batches::service_id.eq(service_id)))
+                        .execute(self.conn)?
+                }
+                None => delete(seen.filter(webhook_seen_mfg_batches::service_id.is_null()))
+                    .execute(self.conn)?,
+            };
+
+            let models = mfg_batch_ids
+                .iter()
+                .map(|mfg_batch_id| NewSeenMfgBatchModel {
+                    address: address.to_string(),
+                    service_id: service_id.map(String::from),
+                    mfg_batch_id: mfg_batch_id.to_string(),
+                })
+                .collect::<Vec<_>>();
+            if models.is_empty() {
+                return Ok(());
+            }
+
+            insert_into(webhook_seen_mfg_batches::table)
+                .values(models)
+                .execute(self.conn)
+                .map(|_| ())
+                .map_err(WebhookStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> SetSeenMfgBatchIdsOperation
+    for WebhookStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        self.conn.transaction::<_, WebhookStoreError, _>(|| {
+            let seen = webhook_seen_mfg_batches::table
+                .filter(webhook_seen_mfg_batches::address.eq(address));
+            match service_id {
+                Some(service_id) => {
+                    delete(seen.filter(webhook_seen_mfg_batches::service_id.eq(service_id)))
+                        .execute(self.conn)?
+                }
+                None => delete(seen.filter(webhook_seen_mfg_batches::service_id.is_null()))
+                    .execute(self.conn)?,
+            };
+
+            let models = mfg_batch_ids
+                .iter()
+                .map(|mfg_batch_id| NewSeenMfgBatchModel {
+                    address: address.to_string(),
+                    service_id: service_id.map(String::from),
+                    mfg_batch_id: mfg_batch_id.to_string(),
+                })
+                .collect::<Vec<_>>();
+            if models.is_empty() {
+                return Ok(());
+            }
+
+            insert_into(webhook_seen_mfg_batches::table)
+                .values(models)
+                .execute(self.conn)
+                .map(|_| ())
+                .map_err(WebhookStoreError::from)
+        })
+    }
+}
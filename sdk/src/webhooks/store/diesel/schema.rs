@@ -0,0 +1,34 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    webhooks (webhook_id) {
+        webhook_id -> Text,
+        url -> Text,
+        secret -> Text,
+        service_id -> Nullable<Text>,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    webhook_seen_mfg_batches (id) {
+        id -> Int8,
+        address -> Text,
+        service_id -> Nullable<Text>,
+        mfg_batch_id -> Text,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(webhooks, webhook_seen_mfg_batches);
@@ -0,0 +1,123 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "diesel")]
+pub(crate) mod diesel;
+mod error;
+
+#[cfg(feature = "diesel")]
+pub use self::diesel::{DieselConnectionWebhookStore, DieselWebhookStore};
+pub use error::WebhookStoreError;
+
+/// A URL notified of mfg_batch changes. Times are seconds since the epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Webhook {
+    pub webhook_id: String,
+    pub url: String,
+    /// The key notifications are signed with
+    pub secret: String,
+    /// Only notify the webhook of changes committed by this service
+    pub service_id: Option<String>,
+    pub created_at: i64,
+}
+
+impl Webhook {
+    /// Returns whether the webhook is notified of changes committed by `service_id`
+    pub fn matches_service(&self, service_id: Option<&str>) -> bool {
+        match self.service_id.as_deref() {
+            Some(webhook_service_id) => Some(webhook_service_id) == service_id,
+            None => true,
+        }
+    }
+}
+
+pub trait WebhookStore {
+    /// Adds a webhook to the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `webhook` - The webhook to be added
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError>;
+
+    /// Lists the webhooks in the underlying storage, oldest first
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError>;
+
+    /// Removes a webhook from the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `webhook_id` - The id of the webhook to remove
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError>;
+
+    /// Lists the ids of the mfg_batches last seen at a state address, which tell the changes in
+    /// a commit apart as creates, updates and deletes
+    ///
+    /// # Arguments
+    ///
+    ///  * `address` - The state address of the mfg_batches
+    ///  * `service_id` - The service the mfg_batches were committed by
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError>;
+
+    /// Replaces the ids of the mfg_batches seen at a state address
+    ///
+    /// # Arguments
+    ///
+    ///  * `address` - The state address of the mfg_batches
+    ///  * `service_id` - The service the mfg_batches were committed by
+    ///  * `mfg_batch_ids` - The ids now at the address, empty if it was deleted
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError>;
+}
+
+impl<WS> WebhookStore for Box<WS>
+where
+    WS: WebhookStore + ?Sized,
+{
+    fn add_webhook(&self, webhook: Webhook) -> Result<(), WebhookStoreError> {
+        (**self).add_webhook(webhook)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>, WebhookStoreError> {
+        (**self).list_webhooks()
+    }
+
+    fn remove_webhook(&self, webhook_id: &str) -> Result<(), WebhookStoreError> {
+        (**self).remove_webhook(webhook_id)
+    }
+
+    fn list_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+    ) -> Result<Vec<String>, WebhookStoreError> {
+        (**self).list_seen_mfg_batch_ids(address, service_id)
+    }
+
+    fn set_seen_mfg_batch_ids(
+        &self,
+        address: &str,
+        service_id: Option<&str>,
+        mfg_batch_ids: &[String],
+    ) -> Result<(), WebhookStoreError> {
+        (**self).set_seen_mfg_batch_ids(address, service_id, mfg_batch_ids)
+    }
+}